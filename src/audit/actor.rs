@@ -0,0 +1,90 @@
+// src/audit/actor.rs
+
+//! Кто выполняет запрос: учётная запись, адрес клиента и протокол. Контекст действует на время
+//! обработки запроса — REST (`middleware::actor_context`), вызова gRPC (`grpc::auth::ActorLayer`)
+//! или LDAP-соединения — и попадает в события аудита, записанные из этой задачи:
+//! `actor_id` — кто, `target_id` — над каким объектом
+
+use std::cell::RefCell;
+use std::future::Future;
+use uuid::Uuid;
+
+use crate::events::AuditEvent;
+use crate::models::{LoginProtocol, User};
+
+tokio::task_local! {
+    static ACTOR: RefCell<ActorContext>;
+}
+
+/// Автор изменений в текущей задаче
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActorContext {
+    /// `None` — запрос ещё не аутентифицирован или анонимный
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub ip_addr: Option<String>,
+    /// User-Agent клиента REST и gRPC — устройство для оценки риска входа
+    pub device: Option<String>,
+    /// Идентификатор запроса REST (`X-Request-Id`), связывает событие с журналом доступа
+    pub request_id: Option<String>,
+    pub protocol: LoginProtocol,
+}
+
+impl ActorContext {
+    pub fn new(protocol: LoginProtocol, ip_addr: Option<String>) -> Self {
+        Self { user_id: None, username: None, ip_addr, device: None, request_id: None, protocol }
+    }
+
+    pub fn with_device(mut self, device: Option<String>) -> Self {
+        self.device = device;
+        self
+    }
+
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    pub fn with_user(mut self, user: &User) -> Self {
+        self.user_id = Some(user.id);
+        self.username = Some(user.username.clone());
+        self
+    }
+
+    /// Выполнить `future` от имени этого автора
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        ACTOR.scope(RefCell::new(self), future).await
+    }
+
+    /// Автор в текущей задаче; `None` вне запроса — CLI, фоновые задачи, репликация
+    pub fn current() -> Option<ActorContext> {
+        ACTOR.try_with(|actor| actor.borrow().clone()).ok()
+    }
+
+    /// Запомнить вошедшего после проверки токена или bind; `None` — анонимный доступ.
+    /// Вне контекста ничего не делает
+    pub fn set_current_user(user: Option<&User>) {
+        let _ = ACTOR.try_with(|actor| {
+            let mut actor = actor.borrow_mut();
+            actor.user_id = user.map(|user| user.id);
+            actor.username = user.map(|user| user.username.clone());
+        });
+    }
+
+    /// Дополнить событие автором; заданные вызывающим поля не меняются
+    pub fn apply(&self, event: &mut AuditEvent) {
+        if event.actor_id.is_none() {
+            event.actor_id = self.user_id;
+        }
+        if event.ip_addr.is_none() {
+            event.ip_addr = self.ip_addr.clone();
+        }
+        if let Some(username) = &self.username {
+            event.metadata.entry("actor".to_string()).or_insert_with(|| username.clone());
+        }
+        if let Some(request_id) = &self.request_id {
+            event.metadata.entry("http_request_id".to_string()).or_insert_with(|| request_id.clone());
+        }
+        event.metadata.entry("protocol".to_string()).or_insert_with(|| self.protocol.as_str().to_string());
+    }
+}
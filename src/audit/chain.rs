@@ -0,0 +1,96 @@
+// src/audit/chain.rs
+
+//! Цепочка хэшей журнала аудита: каждое событие хранит хэш предыдущего,
+//! а каждые `anchor_every` событий последний хэш подписывается ключом сервера (якорь).
+//! Изменение или удаление события рвёт цепочку; откат истории выдают якоря,
+//! копии которых уходят во внешние приёмники (syslog, Kafka).
+
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{self, AuthError};
+use crate::events::{AuditEvent, AuditResult};
+
+/// `prev_hash` первого звена
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// `typ` в заголовке JWT якоря
+const ANCHOR_TYP: &str = "audit-anchor+jwt";
+
+/// Последнее звено цепочки
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditChainHead {
+    pub seq: u64,
+    pub hash: String,
+}
+
+impl Default for AuditChainHead {
+    fn default() -> Self {
+        Self { seq: 0, hash: GENESIS_HASH.to_string() }
+    }
+}
+
+/// Подписанная точка цепочки
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditAnchor {
+    pub seq: u64,
+    pub hash: String,
+    pub created_at: DateTime<Utc>,
+    /// JWT (RS256) с `seq` и `hash`
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AnchorClaims {
+    seq: u64,
+    hash: String,
+    iat: i64,
+}
+
+impl AuditAnchor {
+    pub fn sign(head: &AuditChainHead) -> Result<Self, AuthError> {
+        let created_at = Utc::now();
+        let claims = AnchorClaims { seq: head.seq, hash: head.hash.clone(), iat: created_at.timestamp() };
+        Ok(Self {
+            seq: head.seq,
+            hash: head.hash.clone(),
+            created_at,
+            token: auth::sign_claims(&claims, Some(ANCHOR_TYP))?,
+        })
+    }
+
+    /// Проверить подпись токена и вернуть подписанные `seq` и `hash`
+    pub fn verify_token(token: &str) -> Result<AuditChainHead, AuthError> {
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.validate_exp = false;
+        validation.required_spec_claims.clear();
+        let claims = auth::verify_claims::<AnchorClaims>(token, &validation)?.claims;
+        Ok(AuditChainHead { seq: claims.seq, hash: claims.hash })
+    }
+
+    /// Событие для внешних приёмников: копия якоря вне базы каталога
+    pub fn to_event(&self) -> AuditEvent {
+        let mut event = AuditEvent::new("audit_anchor", AuditResult::Success);
+        event.metadata.insert("seq".to_string(), self.seq.to_string());
+        event.metadata.insert("hash".to_string(), self.hash.clone());
+        event.metadata.insert("token".to_string(), self.token.clone());
+        event
+    }
+}
+
+/// Результат `audit verify`; цепочка цела, если `problems` пуст
+#[derive(Debug, Default, Serialize)]
+pub struct AuditChainReport {
+    pub head_seq: u64,
+    pub verified: u64,
+    pub anchors_verified: usize,
+    pub last_anchor: Option<DateTime<Utc>>,
+    pub problems: Vec<String>,
+}
+
+impl AuditChainReport {
+    pub fn is_intact(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
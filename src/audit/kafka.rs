@@ -0,0 +1,210 @@
+// src/audit/kafka.rs
+
+//! Минимальный продюсер Kafka: Produce v3 с одним RecordBatch v2 в партицию 0.
+//! Брокеры перебираются по порядку, пока один из них не примет запись как лидер.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use super::{to_json, AuditError, AuditSink};
+use crate::config::KafkaConfig;
+use crate::events::AuditEvent;
+
+const API_KEY_PRODUCE: i16 = 0;
+const PRODUCE_VERSION: i16 = 3;
+
+/// acks=1: достаточно подтверждения лидера
+const ACKS_LEADER: i16 = 1;
+const PRODUCE_TIMEOUT_MS: i32 = 5000;
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Ответ больше этого размера считается ошибкой протокола
+const MAX_RESPONSE_SIZE: usize = 1024 * 1024;
+
+pub struct KafkaProducer {
+    config: KafkaConfig,
+    /// Соединение с брокером, принявшим последнюю запись
+    connection: Option<TcpStream>,
+    correlation_id: i32,
+}
+
+impl KafkaProducer {
+    pub fn new(config: KafkaConfig) -> Result<Self, AuditError> {
+        if config.brokers.is_empty() {
+            return Err(AuditError::Config("security.audit.kafka.brokers is empty".to_string()));
+        }
+        Ok(Self { config, connection: None, correlation_id: 0 })
+    }
+
+    /// Отправить запись; при ошибке пробуем следующий брокер
+    pub fn send(&mut self, key: Option<&[u8]>, value: &[u8]) -> Result<(), AuditError> {
+        let batch = encode_record_batch(key, value, chrono::Utc::now().timestamp_millis());
+        let mut last_error = AuditError::Kafka("No brokers available".to_string());
+
+        if let Some(stream) = self.connection.as_mut() {
+            self.correlation_id = self.correlation_id.wrapping_add(1);
+            match produce(stream, &self.config, self.correlation_id, &batch) {
+                Ok(()) => return Ok(()),
+                Err(e) => last_error = e,
+            }
+            self.connection = None;
+        }
+
+        for broker in &self.config.brokers {
+            let mut stream = match connect(broker) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    last_error = e.into();
+                    continue;
+                }
+            };
+            self.correlation_id = self.correlation_id.wrapping_add(1);
+            match produce(&mut stream, &self.config, self.correlation_id, &batch) {
+                Ok(()) => {
+                    self.connection = Some(stream);
+                    return Ok(());
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+}
+
+impl AuditSink for KafkaProducer {
+    fn write(&mut self, event: &AuditEvent) -> Result<(), AuditError> {
+        // Ключ — объект события: изменения одного объекта попадут в одну партицию
+        let key = event.target_id.map(|id| id.to_string());
+        self.send(key.as_deref().map(str::as_bytes), to_json(event)?.as_bytes())
+    }
+}
+
+fn connect(broker: &str) -> std::io::Result<TcpStream> {
+    let stream = TcpStream::connect(broker)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    Ok(stream)
+}
+
+/// Отправить ProduceRequest и проверить код ошибки партиции в ответе
+fn produce(stream: &mut TcpStream, config: &KafkaConfig, correlation_id: i32, batch: &[u8]) -> Result<(), AuditError> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&API_KEY_PRODUCE.to_be_bytes());
+    body.extend_from_slice(&PRODUCE_VERSION.to_be_bytes());
+    body.extend_from_slice(&correlation_id.to_be_bytes());
+    put_string(&mut body, config.client_id.as_deref().unwrap_or("nextdomen"));
+    body.extend_from_slice(&(-1i16).to_be_bytes()); // transactional_id = null
+    body.extend_from_slice(&ACKS_LEADER.to_be_bytes());
+    body.extend_from_slice(&PRODUCE_TIMEOUT_MS.to_be_bytes());
+    body.extend_from_slice(&1i32.to_be_bytes()); // одна тема
+    put_string(&mut body, &config.topic);
+    body.extend_from_slice(&1i32.to_be_bytes()); // одна партиция
+    body.extend_from_slice(&0i32.to_be_bytes());
+    body.extend_from_slice(&(batch.len() as i32).to_be_bytes());
+    body.extend_from_slice(batch);
+
+    stream.write_all(&(body.len() as i32).to_be_bytes())?;
+    stream.write_all(&body)?;
+
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len)?;
+    let len = i32::from_be_bytes(len) as usize;
+    if len > MAX_RESPONSE_SIZE {
+        return Err(AuditError::Kafka(format!("Response too large: {} bytes", len)));
+    }
+    let mut response = vec![0u8; len];
+    stream.read_exact(&mut response)?;
+
+    let error_code = parse_produce_error(&response, correlation_id)
+        .ok_or_else(|| AuditError::Kafka("Malformed ProduceResponse".to_string()))?;
+    match error_code {
+        0 => Ok(()),
+        code => Err(AuditError::Kafka(format!("Broker returned error code {}", code))),
+    }
+}
+
+/// correlation_id, [topic, [partition, error_code, ...]] — нужен только error_code первой партиции
+fn parse_produce_error(response: &[u8], correlation_id: i32) -> Option<i16> {
+    let read_i32 = |at: usize| response.get(at..at + 4).map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    let read_i16 = |at: usize| response.get(at..at + 2).map(|b| i16::from_be_bytes([b[0], b[1]]));
+
+    if read_i32(0)? != correlation_id || read_i32(4)? < 1 {
+        return None;
+    }
+    let topic_len = usize::try_from(read_i16(8)?).ok()?;
+    let partitions_at = 10 + topic_len;
+    if read_i32(partitions_at)? < 1 {
+        return None;
+    }
+    // partition (4 байта), затем error_code
+    read_i16(partitions_at + 8)
+}
+
+fn put_string(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(&(value.len() as i16).to_be_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+/// RecordBatch v2 (magic 2) с одной записью без сжатия и заголовков
+fn encode_record_batch(key: Option<&[u8]>, value: &[u8], timestamp_ms: i64) -> Vec<u8> {
+    let mut record = Vec::new();
+    record.push(0); // attributes
+    put_varint(&mut record, 0); // timestampDelta
+    put_varint(&mut record, 0); // offsetDelta
+    match key {
+        Some(key) => {
+            put_varint(&mut record, key.len() as i64);
+            record.extend_from_slice(key);
+        }
+        None => put_varint(&mut record, -1),
+    }
+    put_varint(&mut record, value.len() as i64);
+    record.extend_from_slice(value);
+    put_varint(&mut record, 0); // headers
+
+    // Часть, покрываемая CRC: от attributes до конца
+    let mut tail = Vec::new();
+    tail.extend_from_slice(&0i16.to_be_bytes()); // attributes
+    tail.extend_from_slice(&0i32.to_be_bytes()); // lastOffsetDelta
+    tail.extend_from_slice(&timestamp_ms.to_be_bytes()); // baseTimestamp
+    tail.extend_from_slice(&timestamp_ms.to_be_bytes()); // maxTimestamp
+    tail.extend_from_slice(&(-1i64).to_be_bytes()); // producerId
+    tail.extend_from_slice(&(-1i16).to_be_bytes()); // producerEpoch
+    tail.extend_from_slice(&(-1i32).to_be_bytes()); // baseSequence
+    tail.extend_from_slice(&1i32.to_be_bytes()); // число записей
+    put_varint(&mut tail, record.len() as i64);
+    tail.extend_from_slice(&record);
+
+    let mut batch = Vec::new();
+    batch.extend_from_slice(&0i64.to_be_bytes()); // baseOffset
+    // batchLength: всё после этого поля — partitionLeaderEpoch, magic, crc и tail
+    batch.extend_from_slice(&((4 + 1 + 4 + tail.len()) as i32).to_be_bytes());
+    batch.extend_from_slice(&(-1i32).to_be_bytes()); // partitionLeaderEpoch
+    batch.push(2); // magic
+    batch.extend_from_slice(&crc32c(&tail).to_be_bytes());
+    batch.extend_from_slice(&tail);
+    batch
+}
+
+/// Varint с zigzag-кодированием, как в записях Kafka
+fn put_varint(out: &mut Vec<u8>, value: i64) {
+    let mut v = ((value << 1) ^ (value >> 63)) as u64;
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+/// CRC-32C (Castagnoli), которым Kafka защищает RecordBatch
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0x82F6_3B78 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
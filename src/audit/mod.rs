@@ -0,0 +1,196 @@
+// src/audit/mod.rs
+
+//! Журнал аудита: события из `EventHub` записываются в приёмник,
+//! выбранный `security.audit.backend` — файл JSON lines, syslog или Kafka.
+
+pub mod actor;
+pub mod chain;
+pub mod kafka;
+pub mod query;
+
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::net::UdpSocket;
+use std::os::unix::net::UnixDatagram;
+use std::thread::JoinHandle;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::config::{AuditConfig, SyslogConfig};
+use crate::events::{AuditEvent, AuditResult};
+use kafka::KafkaProducer;
+
+/// Файл журнала по умолчанию для FILE
+const DEFAULT_AUDIT_FILE: &str = "audit.jsonl";
+
+/// Локальный сокет syslog
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Facility 13 — log audit (RFC 5424)
+const SYSLOG_FACILITY_AUDIT: u8 = 13;
+
+#[derive(Debug)]
+pub enum AuditError {
+    Io(std::io::Error),
+    Serialization(String),
+    Kafka(String),
+    Config(String),
+}
+
+impl From<std::io::Error> for AuditError {
+    fn from(e: std::io::Error) -> Self {
+        AuditError::Io(e)
+    }
+}
+
+impl std::fmt::Display for AuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditError::Io(e) => write!(f, "IO error: {}", e),
+            AuditError::Serialization(e) => write!(f, "Serialization error: {}", e),
+            AuditError::Kafka(e) => write!(f, "Kafka error: {}", e),
+            AuditError::Config(e) => write!(f, "Config error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+/// Приёмник событий аудита; вызывается из отдельного потока, поэтому может блокироваться
+pub trait AuditSink: Send {
+    fn write(&mut self, event: &AuditEvent) -> Result<(), AuditError>;
+}
+
+/// Выбрать приёмник по `backend`
+pub fn build_sink(config: &AuditConfig) -> Result<Box<dyn AuditSink>, AuditError> {
+    match config.backend.to_uppercase().as_str() {
+        "FILE" => Ok(Box::new(FileSink::open(config.file_path.as_deref().unwrap_or(DEFAULT_AUDIT_FILE))?)),
+        "SYSLOG" => Ok(Box::new(SyslogSink::connect(&config.syslog.clone().unwrap_or_default())?)),
+        "KAFKA" => {
+            let kafka = config.kafka.as_ref()
+                .ok_or_else(|| AuditError::Config("security.audit.kafka is required for KAFKA backend".to_string()))?;
+            Ok(Box::new(KafkaProducer::new(kafka.clone())?))
+        }
+        "NONE" => Ok(Box::new(NullSink)),
+        other => Err(AuditError::Config(format!("Unsupported audit backend: {}", other))),
+    }
+}
+
+/// Переносить события из канала в приёмник, пока канал не закроется (сервис каталога удалён)
+pub fn spawn_writer(mut receiver: Receiver<AuditEvent>, mut sink: Box<dyn AuditSink>) -> JoinHandle<()> {
+    std::thread::spawn(move || loop {
+        let event = match receiver.blocking_recv() {
+            Ok(event) => event,
+            // Приёмник не успевает — фиксируем в журнале сам факт потери
+            Err(RecvError::Lagged(missed)) => {
+                let mut event = AuditEvent::new("audit_events_dropped", AuditResult::Failure);
+                event.metadata.insert("missed".to_string(), missed.to_string());
+                event
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if let Err(e) = sink.write(&event) {
+            tracing::error!(action = %event.action, error = %e, "Ошибка записи аудита");
+        }
+    })
+}
+
+fn to_json(event: &AuditEvent) -> Result<String, AuditError> {
+    serde_json::to_string(event).map_err(|e| AuditError::Serialization(e.to_string()))
+}
+
+// === FILE ===
+
+/// Одно событие — одна строка JSON
+pub struct FileSink {
+    file: LineWriter<File>,
+}
+
+impl FileSink {
+    pub fn open(path: &str) -> Result<Self, AuditError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file: LineWriter::new(file) })
+    }
+}
+
+impl AuditSink for FileSink {
+    fn write(&mut self, event: &AuditEvent) -> Result<(), AuditError> {
+        writeln!(self.file, "{}", to_json(event)?)?;
+        Ok(())
+    }
+}
+
+// === SYSLOG ===
+
+enum SyslogTransport {
+    Udp(UdpSocket),
+    Unix(UnixDatagram),
+}
+
+/// RFC 5424; тело сообщения — событие в JSON
+pub struct SyslogSink {
+    transport: SyslogTransport,
+    facility: u8,
+    hostname: String,
+}
+
+impl SyslogSink {
+    pub fn connect(config: &SyslogConfig) -> Result<Self, AuditError> {
+        let transport = match &config.address {
+            Some(address) => {
+                let socket = UdpSocket::bind("0.0.0.0:0")?;
+                socket.connect(address)?;
+                SyslogTransport::Udp(socket)
+            }
+            None => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(SYSLOG_SOCKET)?;
+                SyslogTransport::Unix(socket)
+            }
+        };
+        let hostname = std::fs::read_to_string("/etc/hostname")
+            .map(|h| h.trim().to_string())
+            .ok()
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| "-".to_string());
+        Ok(Self {
+            transport,
+            facility: config.facility.unwrap_or(SYSLOG_FACILITY_AUDIT),
+            hostname,
+        })
+    }
+}
+
+impl AuditSink for SyslogSink {
+    fn write(&mut self, event: &AuditEvent) -> Result<(), AuditError> {
+        // Severity: 6 — informational, 4 — warning для неудачных действий
+        let severity = match event.result {
+            AuditResult::Success => 6,
+            AuditResult::Failure => 4,
+        };
+        let message = format!(
+            "<{}>1 {} {} nextdomen {} {} - {}",
+            self.facility as u16 * 8 + severity,
+            event.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            self.hostname,
+            std::process::id(),
+            event.action,
+            to_json(event)?,
+        );
+        match &self.transport {
+            SyslogTransport::Udp(socket) => socket.send(message.as_bytes())?,
+            SyslogTransport::Unix(socket) => socket.send(message.as_bytes())?,
+        };
+        Ok(())
+    }
+}
+
+// === NONE ===
+
+/// Аудит отключён
+pub struct NullSink;
+
+impl AuditSink for NullSink {
+    fn write(&mut self, _event: &AuditEvent) -> Result<(), AuditError> {
+        Ok(())
+    }
+}
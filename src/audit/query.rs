@@ -0,0 +1,148 @@
+// src/audit/query.rs
+
+//! Поиск по сохранённым событиям аудита: фильтры, разбор границ времени и страницы результата.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::events::{AuditEvent, AuditResult};
+
+pub const DEFAULT_PER_PAGE: usize = 50;
+pub const MAX_PER_PAGE: usize = 500;
+
+/// Условия поиска; все заданные условия должны выполняться одновременно
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub actor: Option<Uuid>,
+    pub target: Option<Uuid>,
+    /// Точное действие или префикс со `*` в конце: `delete_*`
+    pub action: Option<String>,
+    pub result: Option<AuditResult>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Номер страницы, начиная с 1
+    pub page: usize,
+    pub per_page: usize,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        self.actor.is_none_or(|actor| event.actor_id == Some(actor))
+            && self.target.is_none_or(|target| event.target_id == Some(target))
+            && self.action.as_deref().is_none_or(|action| match action.strip_suffix('*') {
+                Some(prefix) => event.action.starts_with(prefix),
+                None => event.action == action,
+            })
+            && self.result.is_none_or(|result| event.result == result)
+            && self.from.is_none_or(|from| event.timestamp >= from)
+            && self.to.is_none_or(|to| event.timestamp <= to)
+    }
+
+    /// Действие без `*` можно искать по индексу действий
+    pub fn exact_action(&self) -> Option<&str> {
+        self.action.as_deref().filter(|action| !action.ends_with('*'))
+    }
+
+    /// Страница, приведённая к допустимым значениям
+    pub fn page_bounds(&self) -> (usize, usize) {
+        let per_page = match self.per_page {
+            0 => DEFAULT_PER_PAGE,
+            n => n.min(MAX_PER_PAGE),
+        };
+        (self.page.max(1), per_page)
+    }
+}
+
+/// Параметры поиска в виде строк — из запроса REST или аргументов CLI
+#[derive(Debug, Default, Deserialize, clap::Args, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditSearchParams {
+    /// Кто выполнил действие: UUID или имя пользователя
+    #[arg(long)]
+    pub actor: Option<String>,
+    /// Над кем выполнено действие: UUID или имя пользователя
+    #[arg(long)]
+    pub target: Option<String>,
+    /// Действие; `*` в конце — префикс: `delete_*`
+    #[arg(long)]
+    pub action: Option<String>,
+    /// success или failure
+    #[arg(long)]
+    pub result: Option<String>,
+    /// Начало интервала: RFC 3339 или YYYY-MM-DD
+    #[arg(long)]
+    pub from: Option<String>,
+    /// Конец интервала: RFC 3339 или YYYY-MM-DD (день включительно)
+    #[arg(long)]
+    pub to: Option<String>,
+    #[arg(long, default_value_t = 1)]
+    #[serde(default)]
+    pub page: usize,
+    #[arg(long, default_value_t = DEFAULT_PER_PAGE)]
+    #[serde(default)]
+    pub per_page: usize,
+}
+
+impl AuditSearchParams {
+    /// Разобрать параметры; имена пользователей разрешаются через каталог
+    pub async fn into_query(self, service: &DirectoryService) -> Result<AuditQuery, DirectoryError> {
+        let non_empty = |value: Option<String>| value.filter(|v| !v.is_empty());
+        let mut query = AuditQuery {
+            action: non_empty(self.action),
+            page: self.page,
+            per_page: self.per_page,
+            ..Default::default()
+        };
+        if let Some(actor) = non_empty(self.actor) {
+            query.actor = Some(service.resolve_user_ref(&actor).await?);
+        }
+        if let Some(target) = non_empty(self.target) {
+            query.target = Some(service.resolve_user_ref(&target).await?);
+        }
+        if let Some(result) = non_empty(self.result) {
+            query.result = Some(parse_result(&result).map_err(DirectoryError::InvalidInput)?);
+        }
+        if let Some(from) = non_empty(self.from) {
+            query.from = Some(parse_time_bound(&from, false).map_err(DirectoryError::InvalidInput)?);
+        }
+        if let Some(to) = non_empty(self.to) {
+            query.to = Some(parse_time_bound(&to, true).map_err(DirectoryError::InvalidInput)?);
+        }
+        Ok(query)
+    }
+}
+
+/// Страница результата: события от новых к старым
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct AuditPage {
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub events: Vec<AuditEvent>,
+}
+
+/// Граница интервала: RFC 3339 или дата `YYYY-MM-DD`.
+/// Дата как верхняя граница (`end_of_day`) включает весь день
+fn parse_time_bound(value: &str, end_of_day: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid time '{}': expected RFC 3339 or YYYY-MM-DD", value))?;
+    let time = match end_of_day {
+        true => date.and_hms_milli_opt(23, 59, 59, 999),
+        false => date.and_hms_opt(0, 0, 0),
+    };
+    Ok(time.expect("valid time of day").and_utc())
+}
+
+/// `success` / `failure`
+fn parse_result(value: &str) -> Result<AuditResult, String> {
+    match value.to_lowercase().as_str() {
+        "success" => Ok(AuditResult::Success),
+        "failure" => Ok(AuditResult::Failure),
+        other => Err(format!("Invalid result '{}': expected success or failure", other)),
+    }
+}
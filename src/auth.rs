@@ -1,18 +1,14 @@
 // src/auth.rs
 
-use jsonwebtoken::{encode, decode, Algorithm, Header, Validation, EncodingKey, DecodingKey};
+use jsonwebtoken::{encode, decode, Algorithm, Header, TokenData, Validation, EncodingKey, DecodingKey};
 use once_cell::sync::Lazy;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::env;
 use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use dotenvy::dotenv;
 
-// Для RSA
-use rsa::pkcs8::DecodePrivateKey;
-use rsa::pkcs8::EncodePrivateKey; // for .to_pkcs8_der()
-use rsa::RsaPrivateKey;
-
 static CONFIG: Lazy<Result<AuthConfig, AuthError>> = Lazy::new(|| {
     dotenv().ok();
     AuthConfig::from_env()
@@ -97,6 +93,19 @@ impl AuthConfig {
     }
 }
 
+/// Время жизни токенов входа в секундах (`security.jwt.token_expiry`)
+static TOKEN_EXPIRY_SECS: AtomicU64 = AtomicU64::new(24 * 3600);
+
+/// Задать время жизни токенов входа из конфигурации
+pub fn set_token_expiry(expiry: std::time::Duration) {
+    TOKEN_EXPIRY_SECS.store(expiry.as_secs(), Ordering::Relaxed);
+}
+
+/// Сколько секунд действует токен, выданный `generate_token`
+pub fn token_expiry_secs() -> u64 {
+    TOKEN_EXPIRY_SECS.load(Ordering::Relaxed)
+}
+
 // === Claims ===
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -104,36 +113,88 @@ pub struct Claims {
     pub sub: String, // user_id
     pub exp: usize,
     pub iat: usize,
+    /// id сессии (`Session`); по нему токен отзывается. У токенов, выданных до учёта сессий, нет
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+impl Claims {
+    /// Сессия токена, если он выдан с ней
+    pub fn session_id(&self) -> Option<uuid::Uuid> {
+        self.jti.as_deref().and_then(|jti| jti.parse().ok())
+    }
 }
 
 // === Функции ===
 
-use chrono;
+/// `typ` токена входа REST и gRPC; у access-токенов OIDC (`at+jwt`) и якорей журнала аудита
+/// он свой, и `validate_token` их не принимает
+pub const SESSION_TOKEN_TYPE: &str = "JWT";
 
-pub fn generate_token(user_id: &str) -> Result<String, AuthError> {
-    let config = CONFIG.as_ref().map_err(|e| e.clone())?;
+/// Токен входа для сессии: пользователь, id сессии и срок берутся из неё
+pub fn generate_token(session: &crate::models::Session) -> Result<String, AuthError> {
+    let claims = Claims {
+        sub: session.user_id.to_string(),
+        exp: session.expires_at.timestamp() as usize,
+        iat: session.issued_at.timestamp() as usize,
+        jti: Some(session.id.to_string()),
+    };
+
+    sign_claims(&claims, Some(SESSION_TOKEN_TYPE))
+}
 
-    let private_key = RsaPrivateKey::from_pkcs8_pem(
-        &String::from_utf8(config.private_key_pem.clone())
-            .map_err(|_| AuthError::InvalidKeyFormat("Private key is not valid UTF-8".into()))?
-    )?;
+/// Подписать произвольные claims ключом RS256; `typ` попадает в заголовок (например `at+jwt`)
+pub fn sign_claims<T: Serialize>(claims: &T, typ: Option<&str>) -> Result<String, AuthError> {
+    let config = CONFIG.as_ref().map_err(|e| e.clone())?;
 
-    let der = private_key.to_pkcs8_der()?;
-    let encoding_key = EncodingKey::from_rsa_der(der.as_bytes());
+    // from_rsa_der ждёт PKCS#1, а ключ хранится в PKCS#8 — PEM-загрузчик понимает оба формата
+    let encoding_key = EncodingKey::from_rsa_pem(&config.private_key_pem)?;
 
-    let header = Header {
+    let mut header = Header {
         alg: Algorithm::RS256,
+        kid: Some(key_id(&config.public_key_pem)),
         ..Header::default()
     };
+    if let Some(typ) = typ {
+        header.typ = Some(typ.to_string());
+    }
 
-    let now = chrono::Utc::now().timestamp() as usize;
-    let claims = Claims {
-        sub: user_id.to_owned(),
-        exp: now + 24 * 3600,
-        iat: now,
-    };
+    encode(&header, claims, &encoding_key).map_err(Into::into)
+}
+
+/// Проверить подпись и стандартные claims по заданным правилам
+pub fn verify_claims<T: DeserializeOwned>(token: &str, validation: &Validation) -> Result<TokenData<T>, AuthError> {
+    let config = CONFIG.as_ref().map_err(|e| e.clone())?;
+    let decoding_key = DecodingKey::from_rsa_pem(&config.public_key_pem)?;
+    decode::<T>(token, &decoding_key, validation).map_err(Into::into)
+}
 
-    encode(&header, &claims, &encoding_key).map_err(Into::into)
+/// Открытый ключ подписи в формате JWK (RFC 7517) для публикации в JWKS
+pub fn public_jwk() -> Result<serde_json::Value, AuthError> {
+    use base64::engine::{general_purpose::URL_SAFE_NO_PAD, Engine};
+    use rsa::pkcs8::DecodePublicKey;
+    use rsa::traits::PublicKeyParts;
+
+    let config = CONFIG.as_ref().map_err(|e| e.clone())?;
+    let pem = String::from_utf8(config.public_key_pem.clone())
+        .map_err(|_| AuthError::InvalidKeyFormat("Public key is not valid UTF-8".into()))?;
+    let public_key = rsa::RsaPublicKey::from_public_key_pem(&pem)
+        .map_err(|e| AuthError::InvalidKeyFormat(e.to_string()))?;
+
+    Ok(serde_json::json!({
+        "kty": "RSA",
+        "use": "sig",
+        "alg": "RS256",
+        "kid": key_id(&config.public_key_pem),
+        "n": URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+        "e": URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+    }))
+}
+
+/// Идентификатор ключа: начало SHA-256 от открытого ключа
+fn key_id(public_key_pem: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(&Sha256::digest(public_key_pem)[..8])
 }
 
 pub fn validate_token(token: &str) -> Result<Claims, AuthError> {
@@ -145,5 +206,8 @@ pub fn validate_token(token: &str) -> Result<Claims, AuthError> {
     validation.validate_exp = true;
 
     let data = decode::<Claims>(token, &decoding_key, &validation)?;
+    if data.header.typ.as_deref() != Some(SESSION_TOKEN_TYPE) {
+        return Err(AuthError::InvalidKeyFormat(format!("Not a session token: typ {:?}", data.header.typ)));
+    }
     Ok(data.claims)
 }
\ No newline at end of file
@@ -0,0 +1,199 @@
+// src/auth_provider.rs
+
+//! Проверка паролей при входе (`security.auth_providers`): локальный хеш, bind к вышестоящему
+//! LDAP / AD или grant `password` провайдера OIDC. Провайдер выбирается по домену пользователя
+//! (суффикс UPN); авторизация — группы, права, блокировки, политика риска — остаётся локальной.
+//! Через `DirectoryService::authenticate_from` провайдер действует для REST, gRPC и LDAP
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use ldap3::{LdapConnAsync, LdapConnSettings};
+
+use crate::config::{AuthProviderConfig, OidcProviderConfig, UpstreamLdapConfig};
+use crate::models::User;
+
+/// Код LDAP invalidCredentials
+const LDAP_INVALID_CREDENTIALS: u32 = 49;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthProviderError {
+    /// Провайдер не ответил или ответил не по протоколу; попытка не считается неудачной
+    Unavailable(String),
+}
+
+impl fmt::Display for AuthProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthProviderError::Unavailable(msg) => write!(f, "Authentication provider is unavailable: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AuthProviderError {}
+
+/// Кто проверяет пароль пользователя
+pub trait AuthProvider: Send + Sync {
+    /// Для аудита и журнала: `local`, `upstream_ldap`, `oidc`
+    fn name(&self) -> &'static str;
+
+    /// Пароль хранится в каталоге: действуют срок его действия и смена через каталог
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    /// Верен ли пароль; `Err` — провайдер недоступен
+    fn verify<'a>(&'a self, user: &'a User, password: &'a str) -> BoxFuture<'a, Result<bool, AuthProviderError>>;
+}
+
+/// Хеш пароля в базе каталога
+pub struct LocalProvider;
+
+impl AuthProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    fn verify<'a>(&'a self, user: &'a User, password: &'a str) -> BoxFuture<'a, Result<bool, AuthProviderError>> {
+        Box::pin(async move { Ok(user.password_hash.verify(password).unwrap_or(false)) })
+    }
+}
+
+/// Простой bind к вышестоящему LDAP / AD от имени пользователя
+pub struct UpstreamLdapProvider {
+    config: UpstreamLdapConfig,
+}
+
+impl UpstreamLdapProvider {
+    pub fn new(config: &UpstreamLdapConfig) -> Self {
+        Self { config: config.clone() }
+    }
+}
+
+impl AuthProvider for UpstreamLdapProvider {
+    fn name(&self) -> &'static str {
+        "upstream_ldap"
+    }
+
+    fn verify<'a>(&'a self, user: &'a User, password: &'a str) -> BoxFuture<'a, Result<bool, AuthProviderError>> {
+        Box::pin(async move {
+            // Bind с пустым паролем — анонимный и «успешный» (RFC 4513, 5.1.2)
+            if password.is_empty() {
+                return Ok(false);
+            }
+            let unavailable = |e: ldap3::LdapError| AuthProviderError::Unavailable(format!("{}: {}", self.config.url, e));
+            let timeout = Duration::from_secs(self.config.timeout_secs);
+            let settings = LdapConnSettings::new()
+                .set_conn_timeout(timeout)
+                .set_starttls(self.config.starttls)
+                .set_no_tls_verify(self.config.tls_skip_verify);
+            let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &self.config.url).await.map_err(unavailable)?;
+            ldap3::drive!(conn);
+
+            let name = login_name(&self.config.bind_name, user);
+            let result = tokio::time::timeout(timeout, ldap.simple_bind(&name, password)).await
+                .map_err(|_| AuthProviderError::Unavailable(format!("{}: bind timed out", self.config.url)))?
+                .map_err(unavailable)?;
+            let _ = ldap.unbind().await;
+            match result.rc {
+                0 => Ok(true),
+                LDAP_INVALID_CREDENTIALS => Ok(false),
+                rc => Err(AuthProviderError::Unavailable(format!("{}: bind returned {} {}", self.config.url, rc, result.text))),
+            }
+        })
+    }
+}
+
+/// Grant `password` (RFC 6749, 4.3) на token endpoint провайдера OIDC: выданный токен
+/// не нужен, важен только ответ
+pub struct OidcProvider {
+    config: OidcProviderConfig,
+    client: reqwest::Client,
+}
+
+impl OidcProvider {
+    pub fn new(config: &OidcProviderConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self { config: config.clone(), client }
+    }
+}
+
+impl AuthProvider for OidcProvider {
+    fn name(&self) -> &'static str {
+        "oidc"
+    }
+
+    fn verify<'a>(&'a self, user: &'a User, password: &'a str) -> BoxFuture<'a, Result<bool, AuthProviderError>> {
+        Box::pin(async move {
+            let login = login_name(&self.config.login, user);
+            let mut form = vec![
+                ("grant_type", "password"),
+                ("username", login.as_str()),
+                ("password", password),
+                ("client_id", self.config.client_id.as_str()),
+                ("scope", self.config.scope.as_str()),
+            ];
+            if let Some(secret) = &self.config.client_secret {
+                form.push(("client_secret", secret.as_str()));
+            }
+            let unavailable = |e: reqwest::Error| AuthProviderError::Unavailable(format!("{}: {}", self.config.token_url, e));
+            let response = self.client.post(&self.config.token_url).form(&form).send().await.map_err(unavailable)?;
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(true);
+            }
+            // Неверный пароль — `invalid_grant` (RFC 6749, 5.2); прочие ошибки — сбой настройки или провайдера
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            match body["error"].as_str() {
+                Some("invalid_grant") => Ok(false),
+                error => Err(AuthProviderError::Unavailable(format!("{} returned {} {}", self.config.token_url, status, error.unwrap_or_default()))),
+            }
+        })
+    }
+}
+
+/// Имя пользователя у провайдера: `{username}` — sAMAccountName, `{upn}` — userPrincipalName
+fn login_name(template: &str, user: &User) -> String {
+    template.replace("{username}", &user.username).replace("{upn}", &user.user_principal_name)
+}
+
+/// Провайдеры доменов из `security.auth_providers`
+#[derive(Clone, Default)]
+pub struct AuthProviders {
+    by_domain: HashMap<String, Arc<dyn AuthProvider>>,
+}
+
+impl AuthProviders {
+    pub fn new(config: &HashMap<String, AuthProviderConfig>) -> Self {
+        let by_domain = config.iter()
+            .map(|(domain, provider)| {
+                let provider: Arc<dyn AuthProvider> = match provider {
+                    AuthProviderConfig::Local => Arc::new(LocalProvider),
+                    AuthProviderConfig::UpstreamLdap(config) => Arc::new(UpstreamLdapProvider::new(config)),
+                    AuthProviderConfig::Oidc(config) => Arc::new(OidcProvider::new(config)),
+                };
+                (domain.to_lowercase(), provider)
+            })
+            .collect();
+        Self { by_domain }
+    }
+
+    /// Провайдер домена пользователя по суффиксу UPN; без записи — локальный
+    pub fn for_user(&self, user: &User) -> Arc<dyn AuthProvider> {
+        user.user_principal_name.rsplit_once('@')
+            .and_then(|(_, domain)| self.by_domain.get(&domain.to_lowercase()))
+            .cloned()
+            .unwrap_or_else(|| Arc::new(LocalProvider))
+    }
+}
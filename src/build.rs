@@ -1,18 +1,36 @@
 // build.rs
 
+//! Код gRPC (`tonic::include_proto!` в src/grpc) из src/proto. protoc берётся из
+//! `protoc-bin-vendored`, если `PROTOC` не задана: сборке не нужен установленный protoc.
+
 use std::env;
-use std::path::PathBuf;
+
+const PROTOS: &[&str] = &[
+    "src/proto/user.proto",
+    "src/proto/organization.proto",
+    "src/proto/audit.proto",
+    "src/proto/auth.proto",
+    "src/proto/group.proto",
+    "src/proto/ou.proto",
+    "src/proto/gpo.proto",
+    "src/proto/service_account.proto",
+    "src/proto/change.proto",
+];
 
 fn main() {
+    let mut includes = vec!["src/proto".to_string()];
+    if env::var_os("PROTOC").is_none() {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc");
+        // Скрипт сборки однопоточный: переменную читает prost-build в этом же процессе
+        unsafe { env::set_var("PROTOC", protoc) };
+        // google/protobuf/*.proto (FieldMask и др.)
+        includes.push(protoc_bin_vendored::include_path().expect("vendored protoc includes").display().to_string());
+    }
+
     tonic_build::configure()
-        .out_dir("src/grpc/gen")
-        .compile(&[
-            "proto/user.proto",
-            "proto/organization.proto",
-            "proto/audit.proto",
-            "proto/auth.proto",
-        ], &["proto"])
+        .build_client(false)
+        .compile(PROTOS, &includes)
         .unwrap_or_else(|e| panic!("Failed to compile protos: {}", e));
 
-    println!("cargo:rerun-if-changed=proto");
-}
\ No newline at end of file
+    println!("cargo:rerun-if-changed=src/proto");
+}
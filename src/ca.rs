@@ -0,0 +1,237 @@
+// src/ca.rs
+
+//! Внутренний CA (секция `ca`): ключ ECDSA P-256, выпуск сертификатов по CSR и CRL.
+//! Кому и что выдать, решает `DirectoryService`: subject и SAN берутся из учётной записи,
+//! из CSR — только открытый ключ
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, CertificateRevocationList, CertificateRevocationListParams,
+    CertificateSigningRequest, CrlDistributionPoint, DistinguishedName, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyIdMethod, KeyPair, KeyUsagePurpose, RevokedCertParams, SanType, SerialNumber, PKCS_ECDSA_P256_SHA256,
+};
+use time::OffsetDateTime;
+
+use crate::models::{CertificateAuthority, CertificateKind, IssuedCertificate, RevocationReason};
+
+/// Длина серийного номера, байт (RFC 5280 разрешает до 20)
+const SERIAL_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaError {
+    /// CSR не разбирается, подпись неверна или в нём неподдерживаемые расширения
+    InvalidCsr(String),
+    /// Ключ или сертификат CA повреждены, ошибка подписи
+    Crypto(String),
+}
+
+impl fmt::Display for CaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaError::InvalidCsr(msg) => write!(f, "Invalid certificate signing request: {}", msg),
+            CaError::Crypto(msg) => write!(f, "Certificate authority error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CaError {}
+
+impl From<rcgen::Error> for CaError {
+    fn from(e: rcgen::Error) -> Self {
+        CaError::Crypto(e.to_string())
+    }
+}
+
+/// Что попадёт в сертификат
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateSubject {
+    pub common_name: String,
+    pub kind: CertificateKind,
+    /// Для пользователя — адреса почты, для компьютера — DNS-имена
+    pub sans: Vec<String>,
+}
+
+/// Подписанный сертификат
+#[derive(Debug, Clone)]
+pub struct SignedCertificate {
+    pub serial: String,
+    pub pem: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+}
+
+/// Новый самоподписанный CA
+pub fn generate(common_name: &str, validity: chrono::Duration) -> Result<CertificateAuthority, CaError> {
+    let now = Utc::now();
+    let mut params = CertificateParams::default();
+    params.alg = &PKCS_ECDSA_P256_SHA256;
+    params.distinguished_name = distinguished_name(common_name);
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign, KeyUsagePurpose::DigitalSignature];
+    params.not_before = offset(now)?;
+    params.not_after = offset(now + validity)?;
+    params.serial_number = Some(random_serial());
+    params.key_identifier_method = KeyIdMethod::Sha256;
+
+    let certificate = Certificate::from_params(params)?;
+    Ok(CertificateAuthority {
+        cert_pem: certificate.serialize_pem()?,
+        key_pem: certificate.serialize_private_key_pem(),
+        created_at: now,
+        crl_number: 0,
+    })
+}
+
+/// Выпустить сертификат по CSR (PEM или DER). Подпись CSR проверяется: ключ принадлежит заявителю
+pub fn sign(
+    ca: &CertificateAuthority,
+    csr: &[u8],
+    subject: &CertificateSubject,
+    validity: chrono::Duration,
+    crl_url: Option<&str>,
+) -> Result<SignedCertificate, CaError> {
+    let mut request = parse_csr(csr)?;
+    let now = Utc::now();
+    let not_after = now + validity;
+    let serial = random_serial();
+
+    let params = &mut request.params;
+    params.distinguished_name = distinguished_name(&subject.common_name);
+    params.subject_alt_names = subject.sans.iter()
+        .map(|san| match subject.kind {
+            CertificateKind::User => SanType::Rfc822Name(san.clone()),
+            CertificateKind::Computer => SanType::DnsName(san.clone()),
+        })
+        .collect();
+    params.is_ca = IsCa::ExplicitNoCa;
+    params.key_usages = vec![KeyUsagePurpose::DigitalSignature, KeyUsagePurpose::KeyEncipherment];
+    params.extended_key_usages = match subject.kind {
+        CertificateKind::User => vec![ExtendedKeyUsagePurpose::ClientAuth, ExtendedKeyUsagePurpose::EmailProtection],
+        CertificateKind::Computer => vec![ExtendedKeyUsagePurpose::ServerAuth, ExtendedKeyUsagePurpose::ClientAuth],
+    };
+    params.crl_distribution_points = crl_url
+        .map(|url| vec![CrlDistributionPoint { uris: vec![url.to_string()] }])
+        .unwrap_or_default();
+    params.use_authority_key_identifier_extension = true;
+    params.not_before = offset(now)?;
+    params.not_after = offset(not_after)?;
+    params.serial_number = Some(serial.clone());
+
+    Ok(SignedCertificate {
+        serial: hex::encode(serial.to_bytes()),
+        pem: request.serialize_pem_with_signer(&signer(ca)?)?,
+        not_before: now,
+        not_after,
+    })
+}
+
+/// Новый ключ ECDSA P-256 и CSR к нему (DER): сертификат для сервера, у которого ключа ещё нет.
+/// Возвращает CSR и закрытый ключ в PKCS#8 PEM
+pub fn generate_csr(common_name: &str) -> Result<(Vec<u8>, String), CaError> {
+    let mut params = CertificateParams::default();
+    params.alg = &PKCS_ECDSA_P256_SHA256;
+    params.distinguished_name = distinguished_name(common_name);
+    let request = Certificate::from_params(params)?;
+    Ok((request.serialize_request_der()?, request.serialize_private_key_pem()))
+}
+
+/// Самоподписанный сертификат сервера для DNS-имён `names` (первое — CN), когда CA не создан.
+/// Возвращает сертификат и закрытый ключ в PEM
+pub fn self_signed(names: &[String], validity: chrono::Duration) -> Result<(String, String), CaError> {
+    let now = Utc::now();
+    let mut params = CertificateParams::new(names.to_vec());
+    params.alg = &PKCS_ECDSA_P256_SHA256;
+    params.distinguished_name = distinguished_name(names.first().map(String::as_str).unwrap_or("localhost"));
+    params.key_usages = vec![KeyUsagePurpose::DigitalSignature, KeyUsagePurpose::KeyEncipherment];
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+    params.not_before = offset(now)?;
+    params.not_after = offset(now + validity)?;
+    params.serial_number = Some(random_serial());
+
+    let certificate = Certificate::from_params(params)?;
+    Ok((certificate.serialize_pem()?, certificate.serialize_private_key_pem()))
+}
+
+/// CRL с номером `ca.crl_number`: отозванные и ещё не истёкшие сертификаты
+pub fn crl(ca: &CertificateAuthority, revoked: &[IssuedCertificate], validity: chrono::Duration) -> Result<Vec<u8>, CaError> {
+    let now = Utc::now();
+    let revoked_certs = revoked.iter()
+        .filter_map(|cert| Some((cert, cert.revoked_at?)))
+        .map(|(cert, revoked_at)| {
+            let serial = hex::decode(&cert.serial).map_err(|e| CaError::Crypto(format!("serial {}: {}", cert.serial, e)))?;
+            Ok(RevokedCertParams {
+                serial_number: SerialNumber::from_slice(&serial),
+                revocation_time: offset(revoked_at)?,
+                reason_code: cert.revocation_reason.map(reason_code),
+                invalidity_date: None,
+            })
+        })
+        .collect::<Result<Vec<_>, CaError>>()?;
+
+    let crl = CertificateRevocationList::from_params(CertificateRevocationListParams {
+        this_update: offset(now)?,
+        next_update: offset(now + validity)?,
+        crl_number: SerialNumber::from(ca.crl_number),
+        issuing_distribution_point: None,
+        revoked_certs,
+        alg: &PKCS_ECDSA_P256_SHA256,
+        key_identifier_method: KeyIdMethod::Sha256,
+    })?;
+    Ok(crl.serialize_der_with_signer(&signer(ca)?)?)
+}
+
+/// Сертификат в PEM → DER
+pub fn pem_to_der(pem: &str) -> Result<Vec<u8>, CaError> {
+    let mut reader = pem.as_bytes();
+    rustls_pemfile::certs(&mut reader)
+        .map_err(|e| CaError::Crypto(e.to_string()))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| CaError::Crypto("no certificate in PEM".to_string()))
+}
+
+fn parse_csr(csr: &[u8]) -> Result<CertificateSigningRequest, CaError> {
+    let parsed = match std::str::from_utf8(csr) {
+        Ok(text) if text.trim_start().starts_with("-----BEGIN") => CertificateSigningRequest::from_pem(text),
+        _ => CertificateSigningRequest::from_der(csr),
+    };
+    parsed.map_err(|e| CaError::InvalidCsr(e.to_string()))
+}
+
+/// Сертификат CA с ключом — для подписи
+fn signer(ca: &CertificateAuthority) -> Result<Certificate, CaError> {
+    let key_pair = KeyPair::from_pem(&ca.key_pem)?;
+    let params = CertificateParams::from_ca_cert_pem(&ca.cert_pem, key_pair)?;
+    Ok(Certificate::from_params(params)?)
+}
+
+fn distinguished_name(common_name: &str) -> DistinguishedName {
+    let mut name = DistinguishedName::new();
+    name.push(DnType::CommonName, common_name);
+    name
+}
+
+/// Случайный положительный серийный номер
+fn random_serial() -> SerialNumber {
+    let mut serial = [0u8; SERIAL_LEN];
+    rand::thread_rng().fill_bytes(&mut serial);
+    serial[0] = (serial[0] & 0x7f) | 0x01;
+    SerialNumber::from_slice(&serial)
+}
+
+fn offset(time: DateTime<Utc>) -> Result<OffsetDateTime, CaError> {
+    OffsetDateTime::from_unix_timestamp(time.timestamp()).map_err(|e| CaError::Crypto(e.to_string()))
+}
+
+fn reason_code(reason: RevocationReason) -> rcgen::RevocationReason {
+    match reason {
+        RevocationReason::Unspecified => rcgen::RevocationReason::Unspecified,
+        RevocationReason::KeyCompromise => rcgen::RevocationReason::KeyCompromise,
+        RevocationReason::AffiliationChanged => rcgen::RevocationReason::AffiliationChanged,
+        RevocationReason::Superseded => rcgen::RevocationReason::Superseded,
+        RevocationReason::CessationOfOperation => rcgen::RevocationReason::CessationOfOperation,
+    }
+}
@@ -0,0 +1,131 @@
+// src/cache.rs
+
+//! Кэш десериализованных объектов каталога по ключу базы (`user:{id}`, `group:{id}`, ...):
+//! горячие пути — поиск LDAP, проверка прав, вход — не разбирают bincode каждый раз.
+//! Вытесняется давно не использованный объект; запись объекта сбрасывает его ключ,
+//! снимок репликации — весь кэш.
+
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+/// Число объектов в кэше по умолчанию (`cache.capacity`)
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Типы объектов, которые кэшируются: префиксы ключей базы
+const CACHED_PREFIXES: &[&str] = &["user:", "group:", "ou:", "contact:"];
+
+/// Счётчики кэша для `/metrics`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub capacity: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    /// Объект и отметка последнего обращения
+    entries: HashMap<String, (u64, Arc<dyn Any + Send + Sync>)>,
+    /// Ключи по отметке обращения: первый — давно не использованный
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Lru {
+    fn touch(&mut self, key: &str) -> Option<Arc<dyn Any + Send + Sync>> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (used, value) = self.entries.get_mut(key)?;
+        let key = self.order.remove(used)?;
+        *used = tick;
+        self.order.insert(tick, key);
+        Some(Arc::clone(value))
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some((used, _)) = self.entries.remove(key) {
+            self.order.remove(&used);
+        }
+    }
+}
+
+/// LRU-кэш объектов; `capacity` 0 — кэш выключен
+pub struct ObjectCache {
+    capacity: usize,
+    lru: Mutex<Lru>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl ObjectCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            lru: Mutex::new(Lru::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Кэшируется ли объект с этим ключом базы
+    pub fn is_cached_key(&self, key: &str) -> bool {
+        self.capacity > 0 && CACHED_PREFIXES.iter().any(|prefix| key.starts_with(prefix))
+    }
+
+    pub fn get<T: Clone + 'static>(&self, key: &str) -> Option<T> {
+        let value = self.lock().touch(key).and_then(|value| value.downcast_ref::<T>().cloned());
+        let counter = if value.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    pub fn insert<T: Clone + Send + Sync + 'static>(&self, key: &str, value: &T) {
+        if !self.is_cached_key(key) {
+            return;
+        }
+        let mut lru = self.lock();
+        lru.remove(key);
+        while lru.entries.len() >= self.capacity {
+            let Some((_, oldest)) = lru.order.pop_first() else { break };
+            lru.entries.remove(&oldest);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        lru.tick += 1;
+        let tick = lru.tick;
+        lru.order.insert(tick, key.to_string());
+        lru.entries.insert(key.to_string(), (tick, Arc::new(value.clone())));
+    }
+
+    /// Сбросить объект после записи или удаления
+    pub fn invalidate(&self, key: &str) {
+        if self.is_cached_key(key) {
+            self.lock().remove(key);
+        }
+    }
+
+    pub fn clear(&self) {
+        *self.lock() = Lru::default();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            capacity: self.capacity,
+            entries: self.lock().entries.len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru> {
+        self.lru.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
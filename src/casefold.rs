@@ -0,0 +1,18 @@
+// src/casefold.rs
+
+//! Ключи индексов поиска: имя, адрес, DNS-имя или DN без учёта регистра и пробелов по краям.
+//! Свёртка не зависит от локали сервера, как сравнение имён в AD: `ẞ` и `ß` сворачиваются
+//! в `ss`, конечная `ς` — в `σ`, остальное — по таблицам Unicode
+
+/// Значение для ключа индекса: `  Alice ` → `alice`, `STRASSE` и `Straße` → `strasse`
+pub fn fold(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.trim().chars() {
+        match c {
+            'ß' | 'ẞ' => out.push_str("ss"),
+            'ς' => out.push('σ'),
+            _ => out.extend(c.to_lowercase()),
+        }
+    }
+    out
+}
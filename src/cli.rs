@@ -1,34 +1,228 @@
 // src/cli.rs
 
 use crate::directory_service::DirectoryService;
-use clap::Parser;
+use crate::models::policy_schema::PolicySchema;
+use crate::web::{GpoResponse, GroupResponse, OuResponse, OuTreeNode, UserResponse};
+use crate::web::contacts::ContactResponse;
+use crate::web::group_requests::JoinRequestResponse;
+use crate::web::password_policies::{PasswordPolicyChanges, PasswordSettingsResponse, ResultantPasswordPolicyResponse};
+use crate::web::service_accounts::ServiceAccountResponse;
+use crate::web::sites::{SiteResponse, SubnetResponse};
+use clap::{CommandFactory, Parser};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{CompletionType, Config, Context, Editor, Helper};
 use std::sync::Arc;
 
-/// Точка входа CLI — сам создаёт service
-pub async fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
-    let config = load_config()?;
-    let key = decode_key(&config.master_key_hex)?;
-    
-    // Создаём сервис внутри CLI
-    let service = Arc::new(DirectoryService::open(&config.db_path, &key)?);
-
-    let cli = Cli::parse();
-
-    match cli.command {
-        Command::User { cmd } => handle_user(cmd, &service).await?,
-        Command::Group { cmd } => handle_group(cmd, &service).await?,
-        Command::Ou { cmd } => handle_ou(cmd, &service).await?,
-        Command::Gpo { cmd } => handle_gpo(cmd, &service).await?,
+mod output;
+mod remote;
+
+pub use output::{OutputArgs, OutputFormat};
+use output::Output;
+
+/// Команды самой оболочки, помимо команд каталога
+const SHELL_COMMANDS: &[&str] = &["help", "exit", "quit"];
+
+/// Подкоманды `user`, первый аргумент которых — имя пользователя
+const USERNAME_ARGS: &[&str] = &["get", "set", "enable", "disable", "unlock", "flags", "move", "delete", "set-password"];
+
+/// Интерактивная оболочка: сервис открыт на всю сессию, команды те же, что у `user`/`group`/`ou`/`gpo`/...;
+/// `output` — формат вывода по умолчанию для всех команд сессии
+pub async fn run_cli(service: Arc<DirectoryService>, output: OutputArgs) -> Result<(), Box<dyn std::error::Error>> {
+    run_shell(Backend::Local(service), output).await
+}
+
+/// Оболочка поверх REST API запущенного сервера (`--server`); база не открывается
+pub async fn run_remote_cli(server: &str, token: Option<String>, output: OutputArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let client = remote::RemoteClient::connect(server, token).await?;
+    eprintln!("Подключено к {}", server);
+    run_shell(Backend::Remote(client), output).await
+}
+
+/// Описание команд оболочки для страниц man: `nextDomen-shell.1`, `nextDomen-shell-user.1`, ...
+pub fn shell_command() -> clap::Command {
+    Cli::command().name("nextDomen-shell").no_binary_name(false)
+}
+
+/// Где выполняются команды
+enum Backend {
+    /// Прямо в базе — для обслуживания без запущенного сервера
+    Local(Arc<DirectoryService>),
+    Remote(remote::RemoteClient),
+}
+
+impl Backend {
+    async fn execute(&self, command: Command, output: Output) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Backend::Local(service) => execute(command, output, service).await,
+            Backend::Remote(client) => client.execute(command, output).await,
+        }
+    }
+
+    async fn usernames(&self) -> Vec<String> {
+        match self {
+            Backend::Local(service) => service.get_all_users().await
+                .map(|users| users.into_iter().map(|user| user.username).collect())
+                .unwrap_or_default(),
+            Backend::Remote(client) => client.usernames().await,
+        }
     }
+}
+
+async fn run_shell(backend: Backend, defaults: OutputArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::builder()
+        .completion_type(CompletionType::List)
+        .auto_add_history(false)
+        .build();
+    let mut editor = Editor::<CliHelper, DefaultHistory>::with_config(config)?;
+    editor.set_helper(Some(CliHelper::new(backend.usernames().await)));
+
+    let history = history_path();
+    if let Some(path) = &history {
+        let _ = editor.load_history(path); // первого запуска истории ещё нет
+    }
+
+    eprintln!("nextDomen CLI — help для списка команд, exit для выхода");
+    loop {
+        let line = match editor.readline("nextdomen> ") {
+            Ok(line) => line,
+            // Ctrl-C сбрасывает строку, Ctrl-D завершает сессию
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+
+        let Some(words) = shlex::split(line) else {
+            eprintln!("❌ Незакрытая кавычка");
+            continue;
+        };
+        if matches!(words.first().map(String::as_str), Some("exit" | "quit")) {
+            break;
+        }
+        // help, --help и ошибки разбора clap возвращает как Err — печатаем и ждём следующую команду
+        let cli = match Cli::try_parse_from(words) {
+            Ok(cli) => cli,
+            Err(e) => {
+                let _ = e.print();
+                continue;
+            }
+        };
 
+        if let Err(e) = backend.execute(cli.command, cli.output.resolve(defaults)).await {
+            eprintln!("❌ {}", e);
+        }
+        // Команда могла создать или удалить пользователя
+        let usernames = backend.usernames().await;
+        if let Some(helper) = editor.helper_mut() {
+            helper.usernames = usernames;
+        }
+    }
+
+    if let Some(path) = &history {
+        let _ = editor.save_history(path);
+    }
     Ok(())
 }
 
+async fn execute(command: Command, output: Output, service: &DirectoryService) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::User { cmd } => handle_user(cmd, output, service).await,
+        Command::Group { cmd } => handle_group(cmd, output, service).await,
+        Command::Ou { cmd } => handle_ou(cmd, output, service).await,
+        Command::Contact { cmd } => handle_contact(cmd, output, service).await,
+        Command::ServiceAccount { cmd } => handle_service_account(cmd, output, service).await,
+        Command::PasswordPolicy { cmd } => handle_password_policy(cmd, output, service).await,
+        Command::Site { cmd } => handle_site(cmd, output, service).await,
+        Command::Subnet { cmd } => handle_subnet(cmd, output, service).await,
+        Command::Gpo { cmd } => handle_gpo(cmd, output, service).await,
+        Command::Domain { cmd } => handle_domain(cmd, service).await,
+        Command::OauthClient { cmd } => handle_oauth_client(cmd, service).await,
+        Command::Ldif { cmd } => handle_ldif(cmd, service).await,
+        Command::Audit { cmd } => handle_audit(cmd, output, service).await,
+    }
+}
+
+fn history_path() -> Option<std::path::PathBuf> {
+    std::env::var_os("HOME").map(|home| std::path::PathBuf::from(home).join(".nextdomen_history"))
+}
+
+// === Автодополнение ===
+
+/// Дополняет команды и подкоманды (из описания clap) и имена пользователей
+struct CliHelper {
+    commands: Vec<(String, Vec<String>)>,
+    usernames: Vec<String>,
+}
+
+impl CliHelper {
+    fn new(usernames: Vec<String>) -> Self {
+        let commands = Cli::command()
+            .get_subcommands()
+            .map(|command| (
+                command.get_name().to_string(),
+                command.get_subcommands().map(|sub| sub.get_name().to_string()).collect(),
+            ))
+            .collect();
+        Self { commands, usernames }
+    }
+}
+
+impl Completer for CliHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let start = line.rfind(' ').map_or(0, |i| i + 1);
+        let word = &line[start..];
+        let before: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let options: Vec<&str> = match before.as_slice() {
+            [] => self.commands.iter()
+                .map(|(name, _)| name.as_str())
+                .chain(SHELL_COMMANDS.iter().copied())
+                .collect(),
+            [command] => self.commands.iter()
+                .find(|(name, _)| name == command)
+                .map(|(_, subs)| subs.iter().map(String::as_str).collect())
+                .unwrap_or_default(),
+            ["user", sub] if USERNAME_ARGS.contains(sub) => self.usernames.iter().map(String::as_str).collect(),
+            _ => Vec::new(),
+        };
+
+        let candidates = options.into_iter()
+            .filter(|option| option.starts_with(word))
+            .map(str::to_string)
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for CliHelper {
+    type Hint = String;
+}
+
+impl Highlighter for CliHelper {}
+
+impl Validator for CliHelper {}
+
+impl Helper for CliHelper {}
+
 // === CLI ===
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(no_binary_name = true, about = "Команды каталога", long_about = None)]
 struct Cli {
+    #[command(flatten)]
+    output: OutputArgs,
     #[command(subcommand)]
     command: Command,
 }
@@ -50,11 +244,56 @@ enum Command {
         #[command(subcommand)]
         cmd: OuCommand,
     },
+    /// Управление контактами и общими почтовыми ящиками
+    Contact {
+        #[command(subcommand)]
+        cmd: ContactCommand,
+    },
+    /// Управление учётными записями служб с автоматической сменой пароля
+    ServiceAccount {
+        #[command(subcommand)]
+        cmd: ServiceAccountCommand,
+    },
+    /// Детальные парольные политики (PSO) для групп и OU
+    PasswordPolicy {
+        #[command(subcommand)]
+        cmd: PasswordPolicyCommand,
+    },
+    /// Сайты: по подсетям клиенты находят ближайший контроллер домена
+    Site {
+        #[command(subcommand)]
+        cmd: SiteCommand,
+    },
+    /// Подсети (CIDR) и их сайты
+    Subnet {
+        #[command(subcommand)]
+        cmd: SubnetCommand,
+    },
     /// Управление групповыми политиками (GPO)
     Gpo {
         #[command(subcommand)]
         cmd: GpoCommand,
     },
+    /// Управление доменами и контроллерами домена
+    Domain {
+        #[command(subcommand)]
+        cmd: DomainCommand,
+    },
+    /// Управление приложениями OAuth2 / OpenID Connect
+    OauthClient {
+        #[command(subcommand)]
+        cmd: OAuthClientCommand,
+    },
+    /// Импорт и экспорт LDIF
+    Ldif {
+        #[command(subcommand)]
+        cmd: LdifCommand,
+    },
+    /// Журнал аудита
+    Audit {
+        #[command(subcommand)]
+        cmd: AuditCommand,
+    },
 }
 
 // === Подкоманды ===
@@ -62,6 +301,7 @@ enum Command {
 #[derive(clap::Subcommand)]
 enum UserCommand {
     Create {
+        #[clap(value_parser = parse_username)]
         username: String,
         #[clap(short, long)]
         email: Option<String>,
@@ -69,8 +309,93 @@ enum UserCommand {
         display_name: Option<String>,
     },
     Get { username: String },
-    List { #[clap(short, long)] json: bool },
+    List,
+    /// Изменить атрибуты пользователя; не указанные не меняются
+    Set {
+        username: String,
+        #[clap(long)]
+        email: Option<String>,
+        #[clap(long)]
+        display_name: Option<String>,
+        /// userPrincipalName, например ivanov@corp.acme.com
+        #[clap(long)]
+        upn: Option<String>,
+        /// OU: DN или UUID
+        #[clap(long)]
+        ou: Option<String>,
+    },
+    Enable { username: String },
+    /// Отключить учётную запись: вход невозможен, данные сохраняются
+    Disable { username: String },
+    /// Снять блокировку после неудачных попыток входа
+    Unlock { username: String },
+    /// История входов: время, протокол, адрес, результат
+    Logins {
+        username: String,
+        /// Не больше записей
+        #[clap(long)]
+        limit: Option<usize>,
+    },
+    /// Флаги userAccountControl; без --set и --clear — показать текущие
+    Flags {
+        username: String,
+        /// Установить флаги: DONT_EXPIRE_PASSWORD, SMARTCARD_REQUIRED, TRUSTED_FOR_DELEGATION, ...
+        #[clap(long, value_delimiter = ',')]
+        set: Vec<String>,
+        /// Снять флаги; LOCKOUT снимает блокировку
+        #[clap(long, value_delimiter = ',')]
+        clear: Vec<String>,
+    },
+    /// Перенести пользователя в OU
+    Move {
+        username: String,
+        /// OU: DN или UUID
+        #[clap(long)]
+        ou: String,
+    },
+    /// Сменить основную группу (primaryGroupID): статическая группа безопасности Global или Universal,
+    /// в которой пользователь уже постоянный участник
+    SetPrimaryGroup {
+        username: String,
+        /// sAMAccountName группы
+        group: String,
+    },
     Delete { username: String },
+    /// Установить пароль (и вывести ключи Kerberos)
+    /// Пароль не принимается аргументом: он был бы виден в списке процессов и истории shell
+    SetPassword {
+        username: String,
+        /// Прочитать пароль из первой строки stdin (для скриптов); без флага пароль запрашивается без эха
+        #[clap(long)]
+        password_stdin: bool,
+        /// Запросить пароль без эха — так и без флага; оставлен для прежних скриптов
+        #[clap(long, hide = true, conflicts_with = "password_stdin")]
+        prompt: bool,
+    },
+    /// Массовый импорт пользователей из CSV (выгрузка отдела кадров)
+    Import {
+        #[clap(long)]
+        csv: std::path::PathBuf,
+        /// YAML-файл соответствия полей столбцам CSV
+        #[clap(long)]
+        mapping: Option<std::path::PathBuf>,
+        /// Только проверить строки, ничего не записывая
+        #[clap(long)]
+        dry_run: bool,
+        /// Сгенерировать пароль, если в строке его нет
+        #[clap(long)]
+        generate_passwords: bool,
+        /// Обновлять существующих пользователей
+        #[clap(long)]
+        update: bool,
+    },
+    /// Выгрузить пользователей в CSV
+    Export {
+        #[clap(long)]
+        csv: std::path::PathBuf,
+        #[clap(long)]
+        mapping: Option<std::path::PathBuf>,
+    },
 }
 
 #[derive(clap::Subcommand)]
@@ -79,19 +404,74 @@ enum GroupCommand {
         name: String,
         #[clap(long)]
         sam_account_name: Option<String>,
+        /// Правило членства (фильтр LDAP) — динамическая группа
+        #[clap(long)]
+        rule: Option<String>,
     },
     Get { sam: String },
+    /// Добавить пользователя (--user-id) или вложить группу (--group)
     AddMember {
         sam: String,
-        #[clap(long)]
-        user_id: uuid::Uuid,
+        #[clap(long, required_unless_present = "group")]
+        user_id: Option<uuid::Uuid>,
+        /// sAMAccountName вложенной группы; допустимость — по областям, как в AD
+        #[clap(long, conflicts_with = "user_id")]
+        group: Option<String>,
+        /// Временное членство: `30m`, `8h`, `7d`; по истечении участник удаляется
+        #[clap(long, value_parser = crate::config::duration::parse)]
+        ttl: Option<std::time::Duration>,
     },
     RemoveMember {
         sam: String,
+        #[clap(long, required_unless_present = "group")]
+        user_id: Option<uuid::Uuid>,
+        #[clap(long, conflicts_with = "user_id")]
+        group: Option<String>,
+    },
+    /// Сменить область группы: между DomainLocal и Global — через Universal, вложенные группы должны её допускать
+    ConvertScope {
+        sam: String,
+        #[clap(value_enum)]
+        scope: crate::models::GroupScope,
+    },
+    List,
+    /// Задать правило членства (фильтр LDAP); без правила группа становится статической
+    SetRule { sam: String, rule: Option<String> },
+    /// Пересчитать участников динамической группы, без имени — всех динамических групп
+    Refresh { sam: Option<String> },
+    /// Задать владельца и срок действия; без параметра значение снимается
+    SetLifecycle {
+        sam: String,
+        /// Имя пользователя-владельца
+        #[clap(long)]
+        owner: Option<String>,
+        /// Когда группа отключается, RFC 3339: `2026-12-31T00:00:00Z`
+        #[clap(long)]
+        expires: Option<chrono::DateTime<chrono::Utc>>,
+    },
+    /// Заявки на вступление в группу
+    Requests { sam: String },
+    /// Одобрить заявку: пользователь добавляется в группу
+    Approve {
+        sam: String,
+        id: uuid::Uuid,
+        #[clap(long)]
+        comment: Option<String>,
+    },
+    /// Отклонить заявку
+    Deny {
+        sam: String,
+        id: uuid::Uuid,
+        #[clap(long)]
+        comment: Option<String>,
+    },
+    /// Отключить группы и удалить участников с истёкшим сроком, не дожидаясь фоновой проверки
+    Expire,
+    /// Выгрузить членство в группах в CSV
+    Export {
         #[clap(long)]
-        user_id: uuid::Uuid,
+        csv: std::path::PathBuf,
     },
-    List { #[clap(short, long)] json: bool },
 }
 
 #[derive(clap::Subcommand)]
@@ -102,6 +482,196 @@ enum OuCommand {
         parent: Option<String>,
     },
     List,
+    /// Иерархия OU
+    Tree,
+    /// Удалить OU (DN или UUID); с вложенными OU — только с --recursive
+    Delete {
+        ou: String,
+        #[clap(long)]
+        recursive: bool,
+        /// Пользователи и контакты OU: refuse — не удалять OU, move-to-parent — перенести в родительскую, delete — удалить
+        #[clap(long, value_enum, default_value_t = crate::models::OuContents::Refuse)]
+        contents: crate::models::OuContents,
+    },
+    /// Переименовать OU; DN вложенных OU пересчитываются
+    Rename { ou: String, new_name: String },
+    /// Перенести OU под другую OU или в корень домена (DN)
+    Move {
+        ou: String,
+        #[clap(long)]
+        parent: String,
+    },
+}
+
+#[derive(clap::Subcommand)]
+enum ContactCommand {
+    Create {
+        name: String,
+        #[clap(long)]
+        email: String,
+        #[clap(long)]
+        display_name: Option<String>,
+        #[clap(long)]
+        company: Option<String>,
+        #[clap(long)]
+        phone: Option<String>,
+        /// DN OU; без него — CN=Users
+        #[clap(long)]
+        ou: Option<String>,
+        /// Общий почтовый ящик вместо внешнего адресата
+        #[clap(long)]
+        shared_mailbox: bool,
+    },
+    /// Контакт по адресу или UUID
+    Get { contact: String },
+    List,
+    /// Изменить контакт (адрес или UUID); не указанные поля не меняются
+    Set {
+        contact: String,
+        #[clap(long)]
+        email: Option<String>,
+        #[clap(long)]
+        display_name: Option<String>,
+        #[clap(long)]
+        company: Option<String>,
+        #[clap(long)]
+        phone: Option<String>,
+        #[clap(long)]
+        ou: Option<String>,
+    },
+    Delete { contact: String },
+}
+
+#[derive(clap::Subcommand)]
+enum ServiceAccountCommand {
+    Create {
+        username: String,
+        /// servicePrincipalName; можно указать несколько раз
+        #[clap(long = "spn")]
+        spns: Vec<String>,
+        /// Кому выдаётся пароль: пользователь, группа или SID; можно указать несколько раз
+        #[clap(long = "allow", required = true)]
+        allow: Vec<String>,
+        /// Интервал смены пароля, дней
+        #[clap(long)]
+        rotation_days: Option<u32>,
+        /// Сколько часов после смены принимается прежний пароль
+        #[clap(long)]
+        overlap_hours: Option<u32>,
+        /// DENY_PASSWORD_LOGON, NOT_DELEGATED; без флагов — только DENY_PASSWORD_LOGON
+        #[clap(long = "flag")]
+        flags: Option<Vec<String>>,
+    },
+    Get { username: String },
+    List,
+    /// Изменить учётную запись службы; не указанные параметры не меняются
+    Set {
+        username: String,
+        #[clap(long = "allow")]
+        allow: Option<Vec<String>>,
+        #[clap(long)]
+        rotation_days: Option<u32>,
+        #[clap(long)]
+        overlap_hours: Option<u32>,
+        #[clap(long = "flag")]
+        flags: Option<Vec<String>>,
+    },
+    /// Сменить пароль вне расписания; прежний принимается до конца окна перекрытия
+    Rotate { username: String },
+}
+
+#[derive(clap::Subcommand)]
+enum PasswordPolicyCommand {
+    Create {
+        name: String,
+        /// При нескольких подходящих политиках действует меньший
+        #[clap(long)]
+        precedence: u32,
+        #[clap(long)]
+        description: Option<String>,
+        /// sAMAccountName группы; можно указать несколько раз
+        #[clap(long = "group")]
+        groups: Vec<String>,
+        /// DN OU (с вложенными); можно указать несколько раз
+        #[clap(long = "ou")]
+        ous: Vec<String>,
+        /// Не указанные параметры — как у политики домена по умолчанию
+        #[command(flatten)]
+        settings: PasswordPolicyChanges,
+    },
+    Get { name: String },
+    List,
+    /// Изменить PSO; не указанные параметры не меняются, `--group` и `--ou` заменяют списки
+    Set {
+        name: String,
+        #[clap(long)]
+        precedence: Option<u32>,
+        #[clap(long)]
+        description: Option<String>,
+        #[clap(long = "group")]
+        groups: Option<Vec<String>>,
+        #[clap(long = "ou")]
+        ous: Option<Vec<String>>,
+        #[command(flatten)]
+        settings: PasswordPolicyChanges,
+    },
+    Delete { name: String },
+    /// Политика, действующая для пользователя
+    Resultant { username: String },
+}
+
+#[derive(clap::Subcommand)]
+enum SiteCommand {
+    Create {
+        name: String,
+        #[clap(long)]
+        description: Option<String>,
+        #[clap(long)]
+        location: Option<String>,
+    },
+    Get { name: String },
+    List,
+    /// Изменить сайт; не указанные параметры не меняются
+    Set {
+        name: String,
+        /// Новое имя
+        #[clap(long)]
+        rename: Option<String>,
+        #[clap(long)]
+        description: Option<String>,
+        #[clap(long)]
+        location: Option<String>,
+    },
+    /// Удалить сайт без подсетей
+    Delete { name: String },
+    /// Сайт клиента по его адресу
+    Lookup { ip: std::net::IpAddr },
+}
+
+#[derive(clap::Subcommand)]
+enum SubnetCommand {
+    Create {
+        /// Подсеть в нотации CIDR: 10.1.0.0/16, 2001:db8::/32
+        subnet: String,
+        #[clap(long)]
+        site: String,
+        #[clap(long)]
+        description: Option<String>,
+        #[clap(long)]
+        location: Option<String>,
+    },
+    List,
+    /// Изменить подсеть; `--site` переносит её в другой сайт
+    Set {
+        subnet: String,
+        #[clap(long)]
+        site: Option<String>,
+        #[clap(long)]
+        description: Option<String>,
+        #[clap(long)]
+        location: Option<String>,
+    },
+    Delete { subnet: String },
 }
 
 #[derive(clap::Subcommand)]
@@ -118,8 +688,16 @@ enum GpoCommand {
         enforced: bool,
         #[clap(long)]
         enabled: bool,
+        /// Security, Registry, Script, Network, Software, FolderRedirection или своё имя
+        #[clap(long = "type")]
+        policy_type: Option<String>,
+        /// Настройка `key=value` по схеме типа (`gpo schemas <тип>`); списки — через `;`
+        #[clap(long = "setting")]
+        settings: Vec<String>,
     },
-    List { #[clap(short, long)] json: bool },
+    List,
+    /// Схемы настроек встроенных типов; с типом — его настройки
+    Schemas { policy_type: Option<String> },
     Link {
         gpo_id: uuid::Uuid,
         ou_id: uuid::Uuid,
@@ -130,44 +708,89 @@ enum GpoCommand {
     },
     SetInheritance {
         ou_id: uuid::Uuid,
+        /// true или false
+        #[clap(action = clap::ArgAction::Set)]
         block: bool,
     },
     SetEnforced {
         ou_id: uuid::Uuid,
+        /// true или false
+        #[clap(action = clap::ArgAction::Set)]
         enforced: bool,
     },
 }
 
-// === Конфигурация ===
+#[derive(clap::Subcommand)]
+enum DomainCommand {
+    Create {
+        /// DNS-имя домена, например corp.acme.com
+        dns_name: String,
+        #[clap(long)]
+        netbios_name: Option<String>,
+    },
+    List,
+    /// Зарегистрировать контроллер домена (публикуется в DNS)
+    RegisterDc {
+        dns_name: String,
+        /// FQDN контроллера, например dc01.corp.acme.com
+        hostname: String,
+        #[clap(long = "ip")]
+        addresses: Vec<std::net::IpAddr>,
+    },
+}
 
-/// Конфигурация из `config.yaml`
-#[derive(serde::Deserialize)]
-struct Config {
-    db_path: String,
-    master_key_hex: String,
+#[derive(clap::Subcommand)]
+enum OAuthClientCommand {
+    /// Зарегистрировать приложение; секрет выводится один раз
+    Register {
+        name: String,
+        #[clap(long = "redirect-uri", required = true)]
+        redirect_uris: Vec<String>,
+        /// Публичный клиент без секрета (SPA, мобильное приложение)
+        #[clap(long)]
+        public: bool,
+    },
+    List,
+    Delete { client_id: String },
 }
 
-fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
-    let file = std::fs::File::open("config.yaml")?;
-    let config: Config = serde_yaml::from_reader(file)?;
-    Ok(config)
+#[derive(clap::Subcommand)]
+enum LdifCommand {
+    /// Импортировать OU, пользователей и группы из LDIF-файла
+    Import {
+        file: std::path::PathBuf,
+        #[clap(long, value_enum, default_value_t = crate::ldif::DuplicatePolicy::Skip)]
+        on_duplicate: crate::ldif::DuplicatePolicy,
+    },
+    /// Выгрузить каталог в LDIF-файл
+    Export { file: std::path::PathBuf },
 }
 
-fn decode_key(hex: &str) -> Result<[u8; 32], hex::FromHexError> {
-    let mut key = [0u8; 32];
-    hex::decode_to_slice(hex, &mut key)?;
-    Ok(key)
+#[derive(clap::Subcommand)]
+enum AuditCommand {
+    /// Найти события: кто, над кем, что и когда сделал
+    Search {
+        #[command(flatten)]
+        params: crate::audit::query::AuditSearchParams,
+    },
+    /// Проверить цепочку хэшей: изменённые, удалённые события и откат истории
+    Verify {
+        /// Якорь (JWT) из внешнего журнала — событие `audit_anchor`
+        #[clap(long)]
+        anchor: Option<String>,
+    },
 }
 
 // === Обработчики ===
 
 async fn handle_user(
     cmd: UserCommand,
+    output: Output,
     service: &DirectoryService,
 ) -> Result<(), Box<dyn std::error::Error>> {
     match cmd {
         UserCommand::Create { username, email, display_name } => {
-            use crate::models::{User, SecurityIdentifier, PasswordHash, PasswordAlgorithm};
+            use crate::models::{User, SecurityIdentifier, PasswordHash, PasswordAlgorithm, UserAccountControl};
             let user = User {
                 id: uuid::Uuid::new_v4(),
                 sid: SecurityIdentifier::new_nt_authority(1001),
@@ -199,83 +822,338 @@ async fn handle_user(
                 script_path: None,
                 meta: std::collections::HashMap::new(),
                 primary_group_id: Some(513),
+                service_principal_names: vec![],
+                kerberos_keys: vec![],
+                user_account_control: UserAccountControl::NORMAL_ACCOUNT,
+                totp_secret: None,
+                totp_pending_secret: None,
             };
             service.create_user(&user).await?;
-            println!("✅ Пользователь создан: {}", user.username);
+            let message = format!("✅ Пользователь создан: {}", user.username);
+            output.saved(&UserResponse::from(user), &message)?;
         }
         UserCommand::Get { username } => {
             if let Some(user) = service.find_user_by_username(&username).await? {
-                println!("{:#?}", user);
+                output.one(&UserResponse::from(user))?;
             } else {
                 eprintln!("❌ Пользователь не найден");
             }
         }
-        UserCommand::List { json } => {
-            let users = service.get_all_users().await?;
-            if json {
-                println!("{}", serde_json::to_string_pretty(&users)?);
-            } else {
-                for user in users {
-                    println!("{} | {}", user.username, user.id);
-                }
+        UserCommand::List => {
+            let users: Vec<UserResponse> = service.get_all_users().await?.into_iter().map(Into::into).collect();
+            output.list(&users)?;
+        }
+        UserCommand::Set { username, email, display_name, upn, ou } => {
+            let Some(mut user) = service.find_user_by_username(&username).await? else {
+                eprintln!("❌ Пользователь не найден");
+                return Ok(());
+            };
+            if let Some(upn) = &upn
+                && !upn.contains('@')
+            {
+                return Err(format!("Invalid UPN '{}': expected name@domain", upn).into());
+            }
+            if email.is_some() || display_name.is_some() || upn.is_some() {
+                user.email = email.or(user.email);
+                user.display_name = display_name.or(user.display_name);
+                user.user_principal_name = upn.unwrap_or(user.user_principal_name);
+                user.updated_at = chrono::Utc::now();
+                service.update_user(&user).await?;
+            }
+            if let Some(ou) = ou {
+                user = service.move_user(user.id, Some(resolve_ou(service, &ou).await?)).await?;
             }
+            let message = format!("✅ Пользователь изменён: {}", username);
+            output.saved(&UserResponse::from(user), &message)?;
+        }
+        UserCommand::Enable { username } => set_enabled(service, output, &username, true).await?,
+        UserCommand::Disable { username } => set_enabled(service, output, &username, false).await?,
+        UserCommand::Logins { username, limit } => {
+            let Some(user) = service.find_user_by_username(&username).await? else {
+                eprintln!("❌ Пользователь не найден");
+                return Ok(());
+            };
+            let mut logins = service.get_login_history(user.id).await?;
+            logins.truncate(limit.unwrap_or(logins.len()));
+            output.list(&logins)?;
+        }
+        UserCommand::Unlock { username } => {
+            let Some(user) = service.find_user_by_username(&username).await? else {
+                eprintln!("❌ Пользователь не найден");
+                return Ok(());
+            };
+            let user = service.unlock_user(user.id).await?;
+            let message = format!("✅ Блокировка снята: {}", username);
+            output.saved(&UserResponse::from(user), &message)?;
+        }
+        UserCommand::Flags { username, set, clear } => {
+            let Some(user) = service.find_user_by_username(&username).await? else {
+                eprintln!("❌ Пользователь не найден");
+                return Ok(());
+            };
+            let user = if set.is_empty() && clear.is_empty() {
+                user
+            } else {
+                use crate::models::UserAccountControl;
+                let set = UserAccountControl::from_flag_names(&set)?;
+                let clear = UserAccountControl::from_flag_names(&clear)?;
+                service.update_user_account_control(user.id, set, clear).await?
+            };
+            let user = UserResponse::from(user);
+            output.saved(&user, &account_flags_message(&user))?;
+        }
+        UserCommand::Move { username, ou } => {
+            let Some(user) = service.find_user_by_username(&username).await? else {
+                eprintln!("❌ Пользователь не найден");
+                return Ok(());
+            };
+            let user = service.move_user(user.id, Some(resolve_ou(service, &ou).await?)).await?;
+            let message = format!("✅ Пользователь {} перенесён в {}", username, ou);
+            output.saved(&UserResponse::from(user), &message)?;
+        }
+        UserCommand::SetPrimaryGroup { username, group } => {
+            let user = service.find_user_by_username(&username).await?
+                .ok_or_else(|| format!("User not found: {}", username))?;
+            let primary = service.find_group_by_sam_account_name(&group).await?
+                .ok_or_else(|| format!("Group not found: {}", group))?;
+            let user = service.set_primary_group(user.id, primary.id).await?;
+            let message = format!("✅ Основная группа {}: {}", username, primary.sam_account_name);
+            output.saved(&UserResponse::from(user), &message)?;
         }
         UserCommand::Delete { username } => {
             if let Some(user) = service.find_user_by_username(&username).await? {
                 service.delete_user(user.id).await?;
-                println!("✅ Пользователь удалён: {}", username);
+                output.done(&format!("✅ Пользователь удалён: {}", username));
             } else {
                 eprintln!("❌ Пользователь не найден");
             }
         }
+        UserCommand::SetPassword { username, password_stdin, .. } => {
+            if let Some(user) = service.find_user_by_username(&username).await? {
+                let password = match password_stdin {
+                    true => crate::init::read_password_stdin()?,
+                    false => crate::init::prompt_new_password(&username)?,
+                };
+                service.set_password(user.id, &password).await?;
+                output.done(&format!("✅ Пароль установлен: {}", username));
+            } else {
+                eprintln!("❌ Пользователь не найден");
+            }
+        }
+        UserCommand::Import { csv, mapping, dry_run, generate_passwords, update } => {
+            use crate::csv_io::{CsvColumnMapping, CsvImportOptions, CsvRowStatus};
+            let mapping = match mapping {
+                Some(path) => CsvColumnMapping::load(path)?,
+                None => CsvColumnMapping::default(),
+            };
+            let options = CsvImportOptions { dry_run, generate_passwords, update_existing: update };
+            let input = std::fs::read_to_string(&csv)?;
+            let report = crate::csv_io::import_users(service, &input, &mapping, options).await?;
+
+            for row in &report.rows {
+                match &row.status {
+                    CsvRowStatus::Created => println!("✅ Строка {}: {} создан", row.line, row.username),
+                    CsvRowStatus::Updated => println!("🔄 Строка {}: {} обновлён", row.line, row.username),
+                    CsvRowStatus::Failed(e) => eprintln!("❌ Строка {}: {} — {}", row.line, row.username, e),
+                }
+                if let Some(password) = &row.generated_password {
+                    println!("   🔑 Пароль: {}", password);
+                }
+            }
+            println!(
+                "{} Создано {}, обновлено {}, ошибок {}",
+                if dry_run { "🔍 Проверка (dry-run):" } else { "✅ Импорт завершён:" },
+                report.count(|s| *s == CsvRowStatus::Created),
+                report.count(|s| *s == CsvRowStatus::Updated),
+                report.count(|s| matches!(s, CsvRowStatus::Failed(_))),
+            );
+        }
+        UserCommand::Export { csv, mapping } => {
+            let mapping = match mapping {
+                Some(path) => crate::csv_io::CsvColumnMapping::load(path)?,
+                None => crate::csv_io::CsvColumnMapping::default(),
+            };
+            std::fs::write(&csv, crate::csv_io::export_users(service, &mapping).await?)?;
+            println!("✅ Пользователи выгружены в {}", csv.display());
+        }
     }
     Ok(())
 }
 
+/// `alice: 0x00010200 NORMAL_ACCOUNT | DONT_EXPIRE_PASSWORD`
+/// Имя новой учётной записи по правилам sAMAccountName: ошибка видна до обращения к каталогу
+fn parse_username(value: &str) -> Result<String, String> {
+    crate::models::validate_username(value).map(|_| value.to_string())
+}
+
+fn account_flags_message(user: &UserResponse) -> String {
+    format!("{}: 0x{:08X} {}", user.username, user.user_account_control, user.account_flags.join(" | "))
+}
+
+async fn set_enabled(service: &DirectoryService, output: Output, username: &str, enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(user) = service.find_user_by_username(username).await? else {
+        eprintln!("❌ Пользователь не найден");
+        return Ok(());
+    };
+    let user = service.set_user_enabled(user.id, enabled).await?;
+    let message = format!("✅ Пользователь {}: {}", if enabled { "включён" } else { "отключён" }, username);
+    output.saved(&UserResponse::from(user), &message)
+}
+
+/// Контакт по адресу или UUID
+async fn resolve_contact(service: &DirectoryService, value: &str) -> Result<crate::models::Contact, Box<dyn std::error::Error>> {
+    let contact = match uuid::Uuid::parse_str(value) {
+        Ok(id) => service.get_contact(id).await?,
+        Err(_) => service.find_contact_by_email(value).await?,
+    };
+    Ok(contact.ok_or_else(|| format!("Contact not found: {}", value))?)
+}
+
+/// Решение по заявке группы из локального CLI
+async fn decide_group_request(
+    service: &DirectoryService,
+    sam: &str,
+    id: uuid::Uuid,
+    approve: bool,
+    comment: Option<String>,
+) -> Result<JoinRequestResponse, Box<dyn std::error::Error>> {
+    let group = service.find_group_by_sam_account_name(sam).await?
+        .ok_or_else(|| format!("Group not found: {}", sam))?;
+    match service.get_group_join_request(id).await? {
+        Some(request) if request.group_id == group.id => {}
+        _ => return Err(format!("Membership request not found: {}", id).into()),
+    }
+    let request = service.decide_group_join_request(id, approve, None, comment).await?;
+    Ok(JoinRequestResponse::new(service, &group, request).await?)
+}
+
+/// OU по DN или UUID
+async fn resolve_ou(service: &DirectoryService, value: &str) -> Result<uuid::Uuid, Box<dyn std::error::Error>> {
+    let ou = match uuid::Uuid::parse_str(value) {
+        Ok(id) => service.get_ou(id).await?,
+        Err(_) => service.find_ou_by_dn(value).await?,
+    };
+    Ok(ou.ok_or_else(|| format!("OU not found: {}", value))?.id)
+}
+
+/// Участник группы: пользователь по UUID или вложенная группа по sAMAccountName
+async fn resolve_member(service: &DirectoryService, user_id: Option<uuid::Uuid>, group: Option<String>) -> Result<uuid::Uuid, Box<dyn std::error::Error>> {
+    match (user_id, group) {
+        (Some(user_id), _) => Ok(user_id),
+        (None, Some(sam)) => Ok(service.find_group_by_sam_account_name(&sam).await?.ok_or_else(|| format!("Group not found: {}", sam))?.id),
+        (None, None) => Err("Specify --user-id or --group".into()),
+    }
+}
+
 async fn handle_group(
     cmd: GroupCommand,
+    output: Output,
     service: &DirectoryService,
 ) -> Result<(), Box<dyn std::error::Error>> {
     match cmd {
-        GroupCommand::Create { name, sam_account_name } => {
+        GroupCommand::Create { name, sam_account_name, rule } => {
             use crate::models::{Group, GroupTypeFlags, GroupScope};
             let sam = sam_account_name.unwrap_or_else(|| name.to_uppercase());
-            let group = Group::new(name, sam, uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+            let mut group = Group::new(name, sam, uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+            group.membership_rule = rule;
             service.create_group(&group).await?;
-            println!("✅ Группа создана: {}", group.sam_account_name);
+            let group = service.get_group(group.id).await?.unwrap_or(group);
+            let message = format!("✅ Группа создана: {}", group.sam_account_name);
+            output.saved(&GroupResponse::from(group), &message)?;
         }
         GroupCommand::Get { sam } => {
             if let Some(group) = service.find_group_by_sam_account_name(&sam).await? {
-                println!("{:#?}", group);
+                output.one(&GroupResponse::from(group))?;
             } else {
                 eprintln!("❌ Группа не найдена");
             }
         }
-        GroupCommand::AddMember { sam, user_id } => {
+        GroupCommand::AddMember { sam, user_id, group: member, ttl } => {
             if let Some(group) = service.find_group_by_sam_account_name(&sam).await? {
-                service.add_member_to_group(group.id, user_id).await?;
-                println!("✅ Участник добавлен в группу");
+                let ttl = ttl.map(chrono::Duration::from_std).transpose().map_err(|_| "Membership TTL is too long")?;
+                service.add_member_to_group(group.id, resolve_member(service, user_id, member).await?, ttl).await?;
+                output.done("✅ Участник добавлен в группу");
             } else {
                 eprintln!("❌ Группа не найдена");
             }
         }
-        GroupCommand::RemoveMember { sam, user_id } => {
+        GroupCommand::RemoveMember { sam, user_id, group: member } => {
             if let Some(group) = service.find_group_by_sam_account_name(&sam).await? {
-                service.remove_member_from_group(group.id, user_id).await?;
-                println!("✅ Участник удалён из группы");
+                service.remove_member_from_group(group.id, resolve_member(service, user_id, member).await?).await?;
+                output.done("✅ Участник удалён из группы");
             } else {
                 eprintln!("❌ Группа не найдена");
             }
         }
-        GroupCommand::List { json } => {
-            let groups = service.get_all_groups().await?;
-            if json {
-                println!("{}", serde_json::to_string_pretty(&groups)?);
-            } else {
-                for group in groups {
-                    println!("{} ({}) — {} участников", group.name, group.sam_account_name, group.members.len());
-                }
+        GroupCommand::ConvertScope { sam, scope } => {
+            let group = service.find_group_by_sam_account_name(&sam).await?
+                .ok_or_else(|| format!("Group not found: {}", sam))?;
+            let group = service.convert_group_scope(group.id, scope).await?;
+            let message = format!("✅ Область группы {}: {:?}", group.sam_account_name, group.scope);
+            output.saved(&GroupResponse::from(group), &message)?;
+        }
+        GroupCommand::List => {
+            let groups: Vec<GroupResponse> = service.get_all_groups().await?.into_iter().map(Into::into).collect();
+            output.list(&groups)?;
+        }
+        GroupCommand::SetRule { sam, rule } => {
+            let group = service.find_group_by_sam_account_name(&sam).await?
+                .ok_or_else(|| format!("Group not found: {}", sam))?;
+            let group = service.set_group_membership_rule(group.id, rule).await?;
+            let message = match &group.membership_rule {
+                Some(rule) => format!("✅ Правило членства {}: {} (участников: {})", group.sam_account_name, rule, group.members.len()),
+                None => format!("✅ Группа {} стала статической", group.sam_account_name),
+            };
+            output.saved(&GroupResponse::from(group), &message)?;
+        }
+        GroupCommand::Refresh { sam: Some(sam) } => {
+            let group = service.find_group_by_sam_account_name(&sam).await?
+                .ok_or_else(|| format!("Group not found: {}", sam))?;
+            let changed = service.refresh_dynamic_group(group.id).await?;
+            output.done(&format!("✅ Участники группы {} {}", sam, if changed { "пересчитаны" } else { "не изменились" }));
+        }
+        GroupCommand::Refresh { sam: None } => {
+            let changed = service.refresh_dynamic_groups().await?;
+            output.done(&format!("✅ Динамических групп с изменившимся составом: {}", changed));
+        }
+        GroupCommand::SetLifecycle { sam, owner, expires } => {
+            let group = service.find_group_by_sam_account_name(&sam).await?
+                .ok_or_else(|| format!("Group not found: {}", sam))?;
+            let owner = match owner {
+                Some(username) => Some(service.find_user_by_username(&username).await?
+                    .ok_or_else(|| format!("User not found: {}", username))?.id),
+                None => None,
+            };
+            let group = service.set_group_lifecycle(group.id, owner, expires).await?;
+            let message = format!("✅ Владелец и срок группы {} сохранены", group.sam_account_name);
+            output.saved(&GroupResponse::from(group), &message)?;
+        }
+        GroupCommand::Requests { sam } => {
+            let group = service.find_group_by_sam_account_name(&sam).await?
+                .ok_or_else(|| format!("Group not found: {}", sam))?;
+            let mut requests = Vec::new();
+            for request in service.get_group_join_requests(group.id).await? {
+                requests.push(JoinRequestResponse::new(service, &group, request).await?);
             }
+            output.list(&requests)?;
+        }
+        GroupCommand::Approve { sam, id, comment } => {
+            let request = decide_group_request(service, &sam, id, true, comment).await?;
+            output.saved(&request, &format!("✅ Заявка одобрена: {} добавлен в {}", request.username, request.group))?;
+        }
+        GroupCommand::Deny { sam, id, comment } => {
+            let request = decide_group_request(service, &sam, id, false, comment).await?;
+            output.saved(&request, &format!("✅ Заявка {} в {} отклонена", request.username, request.group))?;
+        }
+        GroupCommand::Expire => {
+            let expired = service.expire_groups().await?;
+            let memberships = service.expire_memberships().await?;
+            output.done(&format!("✅ Отключено групп с истёкшим сроком: {}, удалено временных участников: {}", expired, memberships));
+        }
+        GroupCommand::Export { csv } => {
+            let mapping = crate::csv_io::CsvColumnMapping::default();
+            std::fs::write(&csv, crate::csv_io::export_group_memberships(service, &mapping).await?)?;
+            println!("✅ Членство в группах выгружено в {}", csv.display());
         }
     }
     Ok(())
@@ -283,27 +1161,502 @@ async fn handle_group(
 
 async fn handle_ou(
     cmd: OuCommand,
+    output: Output,
     service: &DirectoryService,
 ) -> Result<(), Box<dyn std::error::Error>> {
     match cmd {
         OuCommand::Create { name, parent } => {
-            let dn = crate::directory_service::DirectoryService::generate_ou_dn(&name, parent.as_deref());
-            let ou = crate::models::OrganizationalUnit::new(name, dn, None);
+            let dn = crate::directory_service::DirectoryService::generate_ou_dn(&name, parent.as_deref())?;
+            let parent_id = match &parent {
+                Some(parent) => service.find_ou_by_dn(parent).await?.map(|ou| ou.id),
+                None => None,
+            };
+            let ou = crate::models::OrganizationalUnit::new(name, dn, parent_id);
             service.create_ou(&ou).await?;
-            println!("✅ OU создана: DN={}", ou.dn);
+            let message = format!("✅ OU создана: DN={}", ou.dn);
+            output.saved(&OuResponse::from(ou), &message)?;
         }
         OuCommand::List => {
-            let ous = service.get_all_ous().await?;
-            for ou in ous {
-                println!("OU={}, DN={}", ou.name, ou.dn);
+            let ous: Vec<OuResponse> = service.get_all_ous().await?.into_iter().map(Into::into).collect();
+            output.list(&ous)?;
+        }
+        OuCommand::Tree => {
+            output.tree(&OuTreeNode::build(service.get_all_ous().await?))?;
+        }
+        OuCommand::Delete { ou, recursive, contents } => {
+            let deleted = service.delete_ou_tree(resolve_ou(service, &ou).await?, recursive, contents).await?;
+            output.done(&format!("✅ OU удалена: {} (всего OU: {})", ou, deleted));
+        }
+        OuCommand::Rename { ou, new_name } => {
+            let renamed = service.rename_ou(resolve_ou(service, &ou).await?, &new_name).await?;
+            let message = format!("✅ OU переименована: DN={}", renamed.dn);
+            output.saved(&OuResponse::from(renamed), &message)?;
+        }
+        OuCommand::Move { ou, parent } => {
+            let moved = service.move_ou(resolve_ou(service, &ou).await?, &parent).await?;
+            let message = format!("✅ OU перенесена: DN={}", moved.dn);
+            output.saved(&OuResponse::from(moved), &message)?;
+        }
+    }
+    Ok(())
+}
+
+async fn handle_contact(
+    cmd: ContactCommand,
+    output: Output,
+    service: &DirectoryService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::models::{Contact, ContactKind};
+    match cmd {
+        ContactCommand::Create { name, email, display_name, company, phone, ou, shared_mailbox } => {
+            let mut contact = Contact::new(name, email);
+            contact.display_name = display_name;
+            contact.company = company;
+            contact.telephone_number = phone;
+            if let Some(ou) = &ou {
+                contact.organizational_unit = Some(resolve_ou(service, ou).await?);
+            }
+            if shared_mailbox {
+                contact.kind = ContactKind::SharedMailbox;
             }
+            service.create_contact(&contact).await?;
+            let message = format!("✅ Контакт создан: {}", contact.email);
+            output.saved(&ContactResponse::from(contact), &message)?;
+        }
+        ContactCommand::Get { contact } => {
+            output.one(&ContactResponse::from(resolve_contact(service, &contact).await?))?;
+        }
+        ContactCommand::List => {
+            let contacts: Vec<ContactResponse> = service.get_all_contacts().await?.into_iter().map(Into::into).collect();
+            output.list(&contacts)?;
+        }
+        ContactCommand::Set { contact, email, display_name, company, phone, ou } => {
+            let mut contact = resolve_contact(service, &contact).await?;
+            contact.email = email.unwrap_or(contact.email);
+            contact.display_name = display_name.or(contact.display_name);
+            contact.company = company.or(contact.company);
+            contact.telephone_number = phone.or(contact.telephone_number);
+            if let Some(ou) = &ou {
+                contact.organizational_unit = Some(resolve_ou(service, ou).await?);
+            }
+            contact.updated_at = chrono::Utc::now();
+            service.update_contact(&contact).await?;
+            let message = format!("✅ Контакт изменён: {}", contact.email);
+            output.saved(&ContactResponse::from(contact), &message)?;
+        }
+        ContactCommand::Delete { contact } => {
+            let contact = resolve_contact(service, &contact).await?;
+            service.delete_contact(contact.id).await?;
+            output.done(&format!("✅ Контакт удалён: {}", contact.email));
         }
     }
     Ok(())
 }
 
+async fn handle_service_account(
+    cmd: ServiceAccountCommand,
+    output: Output,
+    service: &DirectoryService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::models::{ServiceAccount, ServiceAccountFlags};
+    use crate::web::service_accounts::{find_service_account, resolve_principals};
+    match cmd {
+        ServiceAccountCommand::Create { username, spns, allow, rotation_days, overlap_hours, flags } => {
+            let mut account = ServiceAccount::new(uuid::Uuid::new_v4(), resolve_principals(service, &allow).await?);
+            account.rotation_interval_days = rotation_days.unwrap_or(account.rotation_interval_days);
+            account.overlap_hours = overlap_hours.unwrap_or(account.overlap_hours);
+            if let Some(flags) = &flags {
+                account.flags = ServiceAccountFlags::from_flag_names(flags)?;
+            }
+            let user = service.create_service_account(&username, &spns, &account).await?;
+            let message = format!("✅ Учётная запись службы создана: {}", username);
+            output.saved(&ServiceAccountResponse::new(account, user), &message)?;
+        }
+        ServiceAccountCommand::Get { username } => {
+            let (account, user) = find_service_account(service, &username).await?;
+            output.one(&ServiceAccountResponse::new(account, user))?;
+        }
+        ServiceAccountCommand::List => {
+            let mut accounts = Vec::new();
+            for account in service.get_all_service_accounts().await? {
+                if let Some(user) = service.get_user(account.id).await? {
+                    accounts.push(ServiceAccountResponse::new(account, user));
+                }
+            }
+            output.list(&accounts)?;
+        }
+        ServiceAccountCommand::Set { username, allow, rotation_days, overlap_hours, flags } => {
+            let (mut account, _) = find_service_account(service, &username).await?;
+            if let Some(allow) = &allow {
+                account.allowed_principals = resolve_principals(service, allow).await?;
+            }
+            account.rotation_interval_days = rotation_days.unwrap_or(account.rotation_interval_days);
+            account.overlap_hours = overlap_hours.unwrap_or(account.overlap_hours);
+            if let Some(flags) = &flags {
+                account.flags = ServiceAccountFlags::from_flag_names(flags)?;
+            }
+            service.update_service_account(&account).await?;
+            let (account, user) = find_service_account(service, &username).await?;
+            let message = format!("✅ Учётная запись службы изменена: {}", username);
+            output.saved(&ServiceAccountResponse::new(account, user), &message)?;
+        }
+        ServiceAccountCommand::Rotate { username } => {
+            let (account, user) = find_service_account(service, &username).await?;
+            let account = service.rotate_service_account_password(account.id).await?;
+            let message = format!("✅ Пароль учётной записи службы сменён: {}", username);
+            output.saved(&ServiceAccountResponse::new(account, user), &message)?;
+        }
+    }
+    Ok(())
+}
+
+async fn handle_password_policy(
+    cmd: PasswordPolicyCommand,
+    output: Output,
+    service: &DirectoryService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::models::{PasswordPolicy, PasswordSettingsObject};
+    use crate::web::password_policies::{find_pso, resolve_groups, resolve_ous};
+    match cmd {
+        PasswordPolicyCommand::Create { name, precedence, description, groups, ous, settings } => {
+            let mut policy = PasswordPolicy::default();
+            settings.apply(&mut policy);
+            let mut pso = PasswordSettingsObject::new(name, precedence, policy);
+            pso.description = description;
+            pso.applies_to_groups = resolve_groups(service, &groups).await?;
+            pso.applies_to_ous = resolve_ous(service, &ous).await?;
+            service.save_pso(&pso).await?;
+            let message = format!("✅ Парольная политика создана: {}", pso.name);
+            output.saved(&PasswordSettingsResponse::new(service, pso).await?, &message)?;
+        }
+        PasswordPolicyCommand::Get { name } => {
+            output.one(&PasswordSettingsResponse::new(service, find_pso(service, &name).await?).await?)?;
+        }
+        PasswordPolicyCommand::List => {
+            let mut psos = Vec::new();
+            for pso in service.get_all_psos().await? {
+                psos.push(PasswordSettingsResponse::new(service, pso).await?);
+            }
+            output.list(&psos)?;
+        }
+        PasswordPolicyCommand::Set { name, precedence, description, groups, ous, settings } => {
+            let mut pso = find_pso(service, &name).await?;
+            pso.precedence = precedence.unwrap_or(pso.precedence);
+            if description.is_some() {
+                pso.description = description;
+            }
+            if let Some(groups) = &groups {
+                pso.applies_to_groups = resolve_groups(service, groups).await?;
+            }
+            if let Some(ous) = &ous {
+                pso.applies_to_ous = resolve_ous(service, ous).await?;
+            }
+            settings.apply(&mut pso.policy);
+            service.save_pso(&pso).await?;
+            let message = format!("✅ Парольная политика изменена: {}", name);
+            output.saved(&PasswordSettingsResponse::new(service, find_pso(service, &name).await?).await?, &message)?;
+        }
+        PasswordPolicyCommand::Delete { name } => {
+            let pso = find_pso(service, &name).await?;
+            service.delete_pso(pso.id).await?;
+            output.done(&format!("✅ Парольная политика удалена: {}", name));
+        }
+        PasswordPolicyCommand::Resultant { username } => {
+            let user = service.find_user_by_username(&username).await?
+                .ok_or_else(|| format!("User not found: {}", username))?;
+            let pso = service.resultant_pso(&user).await?;
+            let settings = service.resultant_password_policy(&user).await?;
+            output.one(&ResultantPasswordPolicyResponse { pso: pso.map(|pso| pso.name), settings: settings.into() })?;
+        }
+    }
+    Ok(())
+}
+
+async fn handle_site(
+    cmd: SiteCommand,
+    output: Output,
+    service: &DirectoryService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::models::Site;
+    use crate::web::sites::find_site;
+    match cmd {
+        SiteCommand::Create { name, description, location } => {
+            let mut site = Site::new(name);
+            site.description = description;
+            site.location = location;
+            service.save_site(&site).await?;
+            let message = format!("✅ Сайт создан: {}", site.name);
+            output.saved(&SiteResponse::new(service, site).await?, &message)?;
+        }
+        SiteCommand::Get { name } => {
+            output.one(&SiteResponse::new(service, find_site(service, &name).await?).await?)?;
+        }
+        SiteCommand::List => {
+            let mut sites = Vec::new();
+            for site in service.get_all_sites().await? {
+                sites.push(SiteResponse::new(service, site).await?);
+            }
+            output.list(&sites)?;
+        }
+        SiteCommand::Set { name, rename, description, location } => {
+            let mut site = find_site(service, &name).await?;
+            if let Some(rename) = rename {
+                site.name = rename;
+            }
+            if description.is_some() {
+                site.description = description;
+            }
+            if location.is_some() {
+                site.location = location;
+            }
+            service.save_site(&site).await?;
+            let message = format!("✅ Сайт изменён: {}", site.name);
+            output.saved(&SiteResponse::new(service, site).await?, &message)?;
+        }
+        SiteCommand::Delete { name } => {
+            let site = find_site(service, &name).await?;
+            service.delete_site(site.id).await?;
+            output.done(&format!("✅ Сайт удалён: {}", name));
+        }
+        SiteCommand::Lookup { ip } => {
+            let site = service.find_site_for_ip(ip).await?
+                .ok_or_else(|| format!("No site for address {}", ip))?;
+            output.one(&SiteResponse::new(service, site).await?)?;
+        }
+    }
+    Ok(())
+}
+
+async fn handle_subnet(
+    cmd: SubnetCommand,
+    output: Output,
+    service: &DirectoryService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::models::Subnet;
+    use crate::web::sites::{find_site, find_subnet};
+    match cmd {
+        SubnetCommand::Create { subnet, site, description, location } => {
+            let site = find_site(service, &site).await?;
+            let mut subnet = Subnet::new(&subnet, site.id)?;
+            subnet.description = description;
+            subnet.location = location;
+            service.save_subnet(&subnet).await?;
+            let message = format!("✅ Подсеть создана: {} → {}", subnet.cidr(), site.name);
+            output.saved(&SubnetResponse::new(service, subnet).await?, &message)?;
+        }
+        SubnetCommand::List => {
+            let mut subnets = Vec::new();
+            for subnet in service.get_all_subnets().await? {
+                subnets.push(SubnetResponse::new(service, subnet).await?);
+            }
+            output.list(&subnets)?;
+        }
+        SubnetCommand::Set { subnet, site, description, location } => {
+            let mut subnet = find_subnet(service, &subnet).await?;
+            if let Some(site) = &site {
+                subnet.site_id = find_site(service, site).await?.id;
+            }
+            if description.is_some() {
+                subnet.description = description;
+            }
+            if location.is_some() {
+                subnet.location = location;
+            }
+            service.save_subnet(&subnet).await?;
+            let message = format!("✅ Подсеть изменена: {}", subnet.cidr());
+            output.saved(&SubnetResponse::new(service, subnet).await?, &message)?;
+        }
+        SubnetCommand::Delete { subnet } => {
+            let found = find_subnet(service, &subnet).await?;
+            service.delete_subnet(found.id).await?;
+            output.done(&format!("✅ Подсеть удалена: {}", found.cidr()));
+        }
+    }
+    Ok(())
+}
+
+async fn handle_domain(
+    cmd: DomainCommand,
+    service: &DirectoryService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::models::{Domain, DomainControllerInfo, SecurityIdentifier};
+    match cmd {
+        DomainCommand::Create { dns_name, netbios_name } => {
+            let mut domain = Domain::new(dns_name.clone(), dns_name.clone(), SecurityIdentifier::new_domain());
+            domain.netbios_name = netbios_name.unwrap_or_else(|| {
+                dns_name.split('.').next().unwrap_or_default().to_uppercase()
+            });
+            service.create_domain(&domain).await?;
+            println!("✅ Домен создан: {} ({})", domain.dns_name, domain.dn());
+        }
+        DomainCommand::List => {
+            for domain in service.get_all_domains().await? {
+                println!("{} | {} | {}", domain.dns_name, domain.netbios_name, domain.id);
+                for dc in service.get_domain_controllers(domain.id).await? {
+                    println!("    DC {} {:?} {:?}", dc.hostname, dc.ipv4_addresses, dc.ipv6_addresses);
+                }
+            }
+        }
+        DomainCommand::RegisterDc { dns_name, hostname, addresses } => {
+            let Some(domain) = service.find_domain_by_dns_name(&dns_name).await? else {
+                eprintln!("❌ Домен не найден");
+                return Ok(());
+            };
+            let dc = DomainControllerInfo {
+                id: uuid::Uuid::new_v4(),
+                domain_id: domain.id,
+                hostname,
+                ipv4_addresses: addresses.iter().filter_map(|a| match a {
+                    std::net::IpAddr::V4(v4) => Some(*v4),
+                    _ => None,
+                }).collect(),
+                ipv6_addresses: addresses.iter().filter_map(|a| match a {
+                    std::net::IpAddr::V6(v6) => Some(*v6),
+                    _ => None,
+                }).collect(),
+                registered_at: chrono::Utc::now(),
+            };
+            service.register_domain_controller(&dc).await?;
+            println!("✅ Контроллер домена зарегистрирован: {}", dc.hostname);
+        }
+    }
+    Ok(())
+}
+
+async fn handle_oauth_client(
+    cmd: OAuthClientCommand,
+    service: &DirectoryService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::models::{OAuthClient, PasswordHash};
+    use rand::{rngs::OsRng, RngCore};
+    match cmd {
+        OAuthClientCommand::Register { name, redirect_uris, public } => {
+            let mut client = OAuthClient::new(uuid::Uuid::new_v4().simple().to_string(), name, redirect_uris);
+            let secret = if public {
+                None
+            } else {
+                let mut bytes = [0u8; 32];
+                OsRng.fill_bytes(&mut bytes);
+                let secret = hex::encode(bytes);
+                client.client_secret = Some(PasswordHash::new_bcrypt(&secret)?);
+                Some(secret)
+            };
+            service.create_oauth_client(&client).await?;
+            println!("✅ Клиент зарегистрирован: client_id={}", client.client_id);
+            if let Some(secret) = secret {
+                println!("🔑 client_secret={} (сохраните, повторно не показывается)", secret);
+            }
+        }
+        OAuthClientCommand::List => {
+            for client in service.get_all_oauth_clients().await? {
+                let kind = if client.is_confidential() { "confidential" } else { "public" };
+                println!("{} | {} | {} | {}", client.client_id, client.name, kind, client.redirect_uris.join(", "));
+            }
+        }
+        OAuthClientCommand::Delete { client_id } => {
+            service.delete_oauth_client(&client_id).await?;
+            println!("✅ Клиент удалён: {}", client_id);
+        }
+    }
+    Ok(())
+}
+
+async fn handle_ldif(
+    cmd: LdifCommand,
+    service: &DirectoryService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match cmd {
+        LdifCommand::Import { file, on_duplicate } => {
+            let input = std::fs::read_to_string(&file)?;
+            let report = service.import_ldif(&input, on_duplicate).await?;
+            println!(
+                "✅ Импорт завершён: создано {}, обновлено {}, пропущено {}",
+                report.created, report.updated, report.skipped
+            );
+            for error in &report.errors {
+                eprintln!("⚠️  {}", error);
+            }
+        }
+        LdifCommand::Export { file } => {
+            std::fs::write(&file, service.export_ldif().await?)?;
+            println!("✅ Каталог выгружен в {}", file.display());
+        }
+    }
+    Ok(())
+}
+
+async fn handle_audit(
+    cmd: AuditCommand,
+    output: Output,
+    service: &DirectoryService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match cmd {
+        AuditCommand::Search { params } => {
+            let query = params.into_query(service).await?;
+            let page = service.search_audit(&query).await?;
+            match output.format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&page)?);
+                    return Ok(());
+                }
+                OutputFormat::Yaml => {
+                    print!("{}", serde_yaml::to_string(&page)?);
+                    return Ok(());
+                }
+                OutputFormat::Table => {}
+            }
+            for event in &page.events {
+                let id = |id: Option<uuid::Uuid>| id.map(|id| id.to_string()).unwrap_or_else(|| "-".to_string());
+                let mut metadata: Vec<_> = event.metadata.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                metadata.sort();
+                println!(
+                    "{} | {} | {:?} | actor {} | target {} | {} | {}",
+                    event.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    event.action,
+                    event.result,
+                    id(event.actor_id),
+                    id(event.target_id),
+                    event.ip_addr.as_deref().unwrap_or("-"),
+                    metadata.join(" "),
+                );
+            }
+            println!("📄 Страница {} ({} на странице), всего событий: {}", page.page, page.per_page, page.total);
+        }
+        AuditCommand::Verify { anchor } => {
+            let report = service.verify_audit_chain(anchor.as_deref()).await?;
+            println!(
+                "🔗 Звеньев: {} (проверено {}), якорей подтверждено: {}",
+                report.head_seq, report.verified, report.anchors_verified
+            );
+            if let Some(last_anchor) = report.last_anchor {
+                println!("🔏 Последний якорь: {}", last_anchor.to_rfc3339());
+            }
+            if !report.is_intact() {
+                for problem in &report.problems {
+                    eprintln!("❌ {}", problem);
+                }
+                return Err(format!("Audit chain is broken: {} problem(s)", report.problems.len()).into());
+            }
+            println!("✅ Цепочка аудита не нарушена");
+        }
+    }
+    Ok(())
+}
+
+/// Схемы GPO для `gpo schemas`: список типов или настройки одного типа
+fn print_gpo_schemas(schemas: Vec<PolicySchema>, policy_type: Option<&str>, output: &Output) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(policy_type) = policy_type else {
+        return output.list(&schemas);
+    };
+    let schema = schemas.into_iter()
+        .find(|schema| schema.policy_type.eq_ignore_ascii_case(policy_type))
+        .ok_or_else(|| format!("No settings schema for policy type {}", policy_type))?;
+    output.list(&schema.settings)
+}
+
 async fn handle_gpo(
     cmd: GpoCommand,
+    output: Output,
     service: &DirectoryService,
 ) -> Result<(), Box<dyn std::error::Error>> {
     match cmd {
@@ -314,9 +1667,14 @@ async fn handle_gpo(
             linked_to,
             enforced,
             enabled,
+            policy_type,
+            settings,
         } => {
             use crate::models::policy::{GroupPolicy, PolicyType, PolicyTarget};
 
+            let policy_type = policy_type.as_deref().map(PolicyType::from_name).unwrap_or_default();
+            let settings = crate::models::policy_schema::parse_settings(&policy_type, &settings)?;
+
             let gpo = GroupPolicy {
                 id: uuid::Uuid::new_v4(),
                 name,
@@ -330,45 +1688,38 @@ async fn handle_gpo(
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
                 enabled,
-                policy_type: PolicyType::Custom("Custom".to_string()),
+                policy_type,
                 target: PolicyTarget::All,
-                settings: std::collections::HashMap::new(),
+                settings,
                 wmi_filter: None,
             };
 
             service.create_gpo(&gpo).await?;
-            println!("✅ GPO создана: ID={}", gpo.id);
+            let message = format!("✅ GPO создана: ID={}", gpo.id);
+            output.saved(&GpoResponse::from(gpo), &message)?;
         }
-        GpoCommand::List { json } => {
-            let gpos = service.get_all_gpos().await?;
-            if json {
-                println!("{}", serde_json::to_string_pretty(&gpos)?);
-            } else {
-                for gpo in &gpos {
-                    println!(
-                        "GPO(id={}, name={}, enabled={})",
-                        gpo.id,
-                        gpo.name,
-                        gpo.enabled
-                    );
-                }
-            }
+        GpoCommand::List => {
+            let gpos: Vec<GpoResponse> = service.get_all_gpos().await?.into_iter().map(Into::into).collect();
+            output.list(&gpos)?;
+        }
+        GpoCommand::Schemas { policy_type } => {
+            print_gpo_schemas(crate::models::policy_schema::builtin_schemas(), policy_type.as_deref(), &output)?;
         }
         GpoCommand::Link { gpo_id, ou_id } => {
             service.link_gpo_to_ou(gpo_id, ou_id).await?;
-            println!("✅ GPO привязана к OU");
+            output.done("✅ GPO привязана к OU");
         }
         GpoCommand::Unlink { gpo_id, ou_id } => {
             service.unlink_gpo_from_ou(gpo_id, ou_id).await?;
-            println!("✅ GPO отвязана от OU");
+            output.done("✅ GPO отвязана от OU");
         }
         GpoCommand::SetInheritance { ou_id, block } => {
             service.set_block_inheritance(ou_id, block).await?;
-            println!("✅ Наследование GPO {}: {}", if block { "заблокировано" } else { "разрешено" }, ou_id);
+            output.done(&format!("✅ Наследование GPO {}: {}", if block { "заблокировано" } else { "разрешено" }, ou_id));
         }
         GpoCommand::SetEnforced { ou_id, enforced } => {
             service.set_gpo_enforced(ou_id, enforced).await?;
-            println!("✅ GPO принудительно применяемая: {} для OU {}", enforced, ou_id);
+            output.done(&format!("✅ GPO принудительно применяемая: {} для OU {}", enforced, ou_id));
         }
     }
     Ok(())
@@ -0,0 +1,561 @@
+// src/cli/output.rs
+
+//! Формат вывода команд: таблица для человека, JSON/YAML для скриптов, `--quiet` — только ID.
+//! Схемы JSON и YAML совпадают с ответами REST API (`UserResponse`, `GroupResponse`, ...),
+//! поэтому локальный и удалённый режим выводят одно и то же.
+
+use serde::Serialize;
+
+use crate::jobs::JobStatus;
+use crate::models::{DanglingReference, DirectoryStats, IssuedCertificate, JobRun, LoginRecord, OuUserCount};
+use crate::models::policy_schema::{PolicySchema, SettingDefinition};
+use crate::web::{GpoResponse, GroupResponse, OuResponse, OuTreeNode, UserResponse};
+use crate::web::contacts::ContactResponse;
+use crate::web::group_requests::JoinRequestResponse;
+use crate::web::password_policies::{PasswordSettingsResponse, ResultantPasswordPolicyResponse};
+use crate::web::service_accounts::ServiceAccountResponse;
+use crate::web::sites::{SiteResponse, SubnetResponse};
+
+type CliResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Таблица с выровненными столбцами
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+/// Флаги вывода: у оболочки задают формат по умолчанию, у команды — переопределяют его
+#[derive(Debug, Clone, Copy, Default, clap::Args)]
+pub struct OutputArgs {
+    /// Формат вывода
+    #[arg(short, long, global = true, value_enum)]
+    pub output: Option<OutputFormat>,
+    /// То же, что `--output json`
+    #[arg(short, long, global = true, hide = true)]
+    pub json: bool,
+    /// Выводить только идентификаторы, по одному на строку
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+}
+
+impl OutputArgs {
+    /// Флаги команды поверх флагов оболочки
+    pub fn resolve(self, defaults: OutputArgs) -> Output {
+        let format = |args: OutputArgs| args.output.or(args.json.then_some(OutputFormat::Json));
+        Output {
+            format: format(self).or(format(defaults)).unwrap_or_default(),
+            quiet: self.quiet || defaults.quiet,
+        }
+    }
+}
+
+/// Итоговый формат вывода команды
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Output {
+    pub format: OutputFormat,
+    pub quiet: bool,
+}
+
+/// Объект, который выводится строкой таблицы
+pub trait Tabular: Serialize {
+    const COLUMNS: &'static [&'static str];
+
+    fn id(&self) -> String;
+    fn cells(&self) -> Vec<String>;
+}
+
+impl Output {
+    /// Вывести список объектов
+    pub fn list<T: Tabular>(&self, items: &[T]) -> CliResult<()> {
+        if self.quiet {
+            items.iter().for_each(|item| println!("{}", item.id()));
+            return Ok(());
+        }
+        match self.format {
+            OutputFormat::Table => print_table(T::COLUMNS, items.iter().map(Tabular::cells).collect()),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(items)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(items)?),
+        }
+        Ok(())
+    }
+
+    /// Вывести один объект; таблица — «столбец: значение» построчно
+    pub fn one<T: Tabular>(&self, item: &T) -> CliResult<()> {
+        if self.quiet {
+            println!("{}", item.id());
+            return Ok(());
+        }
+        match self.format {
+            OutputFormat::Table => {
+                let rows = T::COLUMNS.iter().zip(item.cells())
+                    .map(|(column, value)| vec![column.to_string(), value])
+                    .collect();
+                print_table(&[], rows);
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(item)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(item)?),
+        }
+        Ok(())
+    }
+
+    /// Результат создания или изменения: сообщение для человека, объект для скрипта, ID в тихом режиме
+    pub fn saved<T: Tabular>(&self, item: &T, message: &str) -> CliResult<()> {
+        if self.format == OutputFormat::Table && !self.quiet {
+            println!("{}", message);
+            return Ok(());
+        }
+        self.one(item)
+    }
+
+    /// Вывести иерархию OU; таблица — имена с отступом по уровню
+    pub fn tree(&self, nodes: &[OuTreeNode]) -> CliResult<()> {
+        fn walk<'a>(nodes: &'a [OuTreeNode], depth: usize, rows: &mut Vec<(usize, &'a OuResponse)>) {
+            for node in nodes {
+                rows.push((depth, &node.ou));
+                walk(&node.children, depth + 1, rows);
+            }
+        }
+
+        let mut rows = Vec::new();
+        walk(nodes, 0, &mut rows);
+        if self.quiet {
+            rows.iter().for_each(|(_, ou)| println!("{}", ou.id));
+            return Ok(());
+        }
+        match self.format {
+            OutputFormat::Table => {
+                let rows = rows.into_iter()
+                    .map(|(depth, ou)| vec![format!("{}{}", "  ".repeat(depth), ou.name), ou.dn.clone(), ou.id.to_string()])
+                    .collect();
+                print_table(&["NAME", "DN", "ID"], rows);
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(nodes)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(nodes)?),
+        }
+        Ok(())
+    }
+
+    /// Сообщение об успехе; в тихом режиме и для JSON/YAML не выводится
+    pub fn done(&self, message: &str) {
+        if self.format == OutputFormat::Table && !self.quiet {
+            println!("{}", message);
+        }
+    }
+}
+
+/// Столбцы выравниваются по самому длинному значению; пустой `header` — без заголовка
+fn print_table(header: &[&str], rows: Vec<Vec<String>>) {
+    let header: Vec<String> = header.iter().map(|column| column.to_string()).collect();
+    let lines: Vec<&Vec<String>> = std::iter::once(&header).filter(|h| !h.is_empty()).chain(&rows).collect();
+
+    let mut widths: Vec<usize> = Vec::new();
+    for line in &lines {
+        for (i, cell) in line.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(i) {
+                Some(w) => *w = (*w).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    for line in lines {
+        let cells: Vec<String> = line.iter().enumerate()
+            .map(|(i, cell)| format!("{}{}", cell, " ".repeat(widths[i] - cell.chars().count())))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+fn or_dash(value: &Option<String>) -> String {
+    value.clone().unwrap_or_else(|| "-".to_string())
+}
+
+// === Столбцы объектов ===
+
+impl Tabular for UserResponse {
+    const COLUMNS: &'static [&'static str] = &["ID", "USERNAME", "DISPLAY NAME", "EMAIL", "ENABLED"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.username.clone(),
+            or_dash(&self.display_name),
+            or_dash(&self.email),
+            self.enabled.to_string(),
+        ]
+    }
+}
+
+impl Tabular for GroupResponse {
+    const COLUMNS: &'static [&'static str] = &["ID", "NAME", "SAM ACCOUNT NAME", "MEMBERS"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.sam_account_name.clone(),
+            self.members_count.to_string(),
+        ]
+    }
+}
+
+impl Tabular for JoinRequestResponse {
+    const COLUMNS: &'static [&'static str] = &["ID", "USERNAME", "STATUS", "REQUESTED", "JUSTIFICATION"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let status = match self.status {
+            crate::models::JoinRequestStatus::Pending => "pending",
+            crate::models::JoinRequestStatus::Approved => "approved",
+            crate::models::JoinRequestStatus::Denied => "denied",
+        };
+        vec![
+            self.id.to_string(),
+            self.username.clone(),
+            status.to_string(),
+            self.requested_at.format("%Y-%m-%d %H:%M").to_string(),
+            or_dash(&self.justification),
+        ]
+    }
+}
+
+impl Tabular for OuResponse {
+    const COLUMNS: &'static [&'static str] = &["ID", "NAME", "DN"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.id.to_string(), self.name.clone(), self.dn.clone()]
+    }
+}
+
+impl Tabular for ContactResponse {
+    const COLUMNS: &'static [&'static str] = &["ID", "NAME", "EMAIL", "COMPANY", "KIND"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let kind = match self.kind {
+            crate::models::ContactKind::External => "external",
+            crate::models::ContactKind::SharedMailbox => "shared_mailbox",
+        };
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.email.clone(),
+            or_dash(&self.company),
+            kind.to_string(),
+        ]
+    }
+}
+
+impl Tabular for ServiceAccountResponse {
+    const COLUMNS: &'static [&'static str] = &["ID", "USERNAME", "SPNS", "FLAGS", "NEXT ROTATION"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.username.clone(),
+            self.service_principal_names.len().to_string(),
+            self.flags.join(","),
+            self.next_rotation.format("%Y-%m-%d %H:%M").to_string(),
+        ]
+    }
+}
+
+impl Tabular for PasswordSettingsResponse {
+    const COLUMNS: &'static [&'static str] = &["ID", "NAME", "PRECEDENCE", "MIN LENGTH", "HISTORY", "LOCKOUT", "GROUPS", "OUS"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.precedence.to_string(),
+            self.settings.min_length.to_string(),
+            self.settings.history_count.to_string(),
+            lockout(&self.settings),
+            self.groups.join(","),
+            self.ous.join(";"),
+        ]
+    }
+}
+
+impl Tabular for ResultantPasswordPolicyResponse {
+    const COLUMNS: &'static [&'static str] = &["PSO", "MIN LENGTH", "COMPLEXITY", "MAX AGE DAYS", "HISTORY", "LOCKOUT"];
+
+    fn id(&self) -> String {
+        or_dash(&self.pso)
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let settings = &self.settings;
+        let complexity: Vec<&str> = [
+            (settings.require_uppercase, "upper"),
+            (settings.require_lowercase, "lower"),
+            (settings.require_digits, "digits"),
+            (settings.require_special_chars, "special"),
+        ].into_iter().filter(|(required, _)| *required).map(|(_, name)| name).collect();
+        vec![
+            or_dash(&self.pso),
+            settings.min_length.to_string(),
+            if complexity.is_empty() { "-".to_string() } else { complexity.join(",") },
+            settings.max_age_days.to_string(),
+            settings.history_count.to_string(),
+            lockout(settings),
+        ]
+    }
+}
+
+/// «5 / 15m» — попыток до блокировки и её длительность
+fn lockout(settings: &crate::web::password_policies::PasswordPolicyBody) -> String {
+    if settings.lockout_threshold == 0 {
+        "-".to_string()
+    } else {
+        format!("{} / {}m", settings.lockout_threshold, settings.lockout_duration_minutes)
+    }
+}
+
+impl Tabular for SiteResponse {
+    const COLUMNS: &'static [&'static str] = &["ID", "NAME", "LOCATION", "SUBNETS"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            or_dash(&self.location),
+            self.subnets.join(","),
+        ]
+    }
+}
+
+impl Tabular for SubnetResponse {
+    const COLUMNS: &'static [&'static str] = &["ID", "SUBNET", "SITE", "LOCATION"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.subnet.clone(),
+            self.site.clone(),
+            or_dash(&self.location),
+        ]
+    }
+}
+
+impl Tabular for PolicySchema {
+    const COLUMNS: &'static [&'static str] = &["TYPE", "SETTINGS", "DESCRIPTION"];
+
+    fn id(&self) -> String {
+        self.policy_type.clone()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.policy_type.clone(), self.settings.len().to_string(), self.description.clone()]
+    }
+}
+
+impl Tabular for SettingDefinition {
+    const COLUMNS: &'static [&'static str] = &["KEY", "TYPE", "REQUIRED", "DESCRIPTION"];
+
+    fn id(&self) -> String {
+        self.key.clone()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.key.clone(), self.kind.to_string(), self.required.to_string(), self.description.clone()]
+    }
+}
+
+impl Tabular for GpoResponse {
+    const COLUMNS: &'static [&'static str] = &["ID", "NAME", "ENABLED", "ENFORCED", "LINKS"];
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.enabled.to_string(),
+            self.enforced.to_string(),
+            self.linked_to.len().to_string(),
+        ]
+    }
+}
+
+/// Итог выполнения задачи: число обработанных объектов или ошибка
+fn job_result(run: &JobRun) -> String {
+    match (&run.processed, &run.error) {
+        (Some(processed), _) => format!("ok: {}", processed),
+        (None, error) => format!("error: {}", error.as_deref().unwrap_or_default()),
+    }
+}
+
+impl Tabular for JobStatus {
+    const COLUMNS: &'static [&'static str] = &["NAME", "SCHEDULE", "ENABLED", "NEXT RUN", "LAST RUN", "LAST RESULT"];
+
+    fn id(&self) -> String {
+        self.name.clone()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.schedule.clone(),
+            self.enabled.to_string(),
+            or_dash(&self.next_run.map(|next| next.format("%Y-%m-%d %H:%M").to_string())),
+            or_dash(&self.last_run.as_ref().map(|run| run.started_at.format("%Y-%m-%d %H:%M").to_string())),
+            or_dash(&self.last_run.as_ref().map(job_result)),
+        ]
+    }
+}
+
+impl Tabular for JobRun {
+    const COLUMNS: &'static [&'static str] = &["STARTED", "TRIGGER", "DURATION MS", "RESULT"];
+
+    fn id(&self) -> String {
+        self.started_at.to_rfc3339()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            self.trigger.as_str().to_string(),
+            self.duration_ms().to_string(),
+            job_result(self),
+        ]
+    }
+}
+
+impl Tabular for LoginRecord {
+    const COLUMNS: &'static [&'static str] = &["TIME", "PROTOCOL", "ADDRESS", "RESULT"];
+
+    fn id(&self) -> String {
+        self.timestamp.to_rfc3339()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+            self.protocol.as_str().to_string(),
+            or_dash(&self.ip_addr),
+            match &self.reason {
+                _ if self.success => "ok".to_string(),
+                Some(reason) => format!("failed: {}", reason),
+                None => "failed".to_string(),
+            },
+        ]
+    }
+}
+
+impl Tabular for DirectoryStats {
+    const COLUMNS: &'static [&'static str] = &[
+        "USERS", "COMPUTERS", "DISABLED USERS", "LOCKED USERS", "GROUPS", "OUS", "CONTACTS",
+        "GPOS", "ENABLED GPOS", "LINKED GPOS", "DB SIZE", "LAST FLUSH",
+    ];
+
+    fn id(&self) -> String {
+        self.generated_at.to_rfc3339()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let count = |object_type: &str| self.objects.get(object_type).copied().unwrap_or_default().to_string();
+        vec![
+            count("user"),
+            self.computers.to_string(),
+            self.disabled_users.to_string(),
+            self.locked_users.to_string(),
+            count("group"),
+            count("ou"),
+            count("contact"),
+            count("gpo"),
+            self.enabled_gpos.to_string(),
+            self.linked_gpos.to_string(),
+            format!("{} bytes", self.db_size_bytes),
+            or_dash(&self.last_flush.map(|flush| flush.format("%Y-%m-%d %H:%M:%S").to_string())),
+        ]
+    }
+}
+
+impl Tabular for OuUserCount {
+    const COLUMNS: &'static [&'static str] = &["OU", "USERS"];
+
+    fn id(&self) -> String {
+        self.ou_id.map(|id| id.to_string()).unwrap_or_default()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.dn.clone().unwrap_or_else(|| "(no OU)".to_string()), self.users.to_string()]
+    }
+}
+
+impl Tabular for DanglingReference {
+    const COLUMNS: &'static [&'static str] = &["TYPE", "OBJECT", "ATTRIBUTE", "MISSING"];
+
+    fn id(&self) -> String {
+        self.object_id.to_string()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.object_type.clone(), self.object.clone(), self.attribute.clone(), self.target_id.to_string()]
+    }
+}
+
+impl Tabular for IssuedCertificate {
+    const COLUMNS: &'static [&'static str] = &["SERIAL", "SUBJECT", "KIND", "SAN", "NOT AFTER", "STATUS"];
+
+    fn id(&self) -> String {
+        self.serial.clone()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.serial.clone(),
+            self.subject.clone(),
+            self.kind.as_str().to_string(),
+            self.sans.join(", "),
+            self.not_after.format("%Y-%m-%d").to_string(),
+            match (&self.revoked_at, &self.revocation_reason) {
+                (Some(_), Some(reason)) => format!("revoked: {}", reason.as_str()),
+                (Some(_), None) => "revoked".to_string(),
+                (None, _) if self.not_after <= chrono::Utc::now() => "expired".to_string(),
+                (None, _) => "valid".to_string(),
+            },
+        ]
+    }
+}
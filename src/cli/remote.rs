@@ -0,0 +1,577 @@
+// src/cli/remote.rs
+
+//! Удалённый режим CLI (`cli --server <url>`): команды идут в REST API запущенного сервера,
+//! база не открывается. Команды, которых нет в REST API, доступны только локально.
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use super::{Command, ContactCommand, GpoCommand, GroupCommand, OuCommand, Output, PasswordPolicyCommand, ServiceAccountCommand, SiteCommand, SubnetCommand, UserCommand};
+use crate::models::LoginRecord;
+use crate::models::policy::PolicyType;
+use crate::models::policy_schema::{parse_settings, PolicySchema};
+use crate::web::{GpoResponse, GroupResponse, OuResponse, OuTreeNode, UserResponse};
+use crate::web::contacts::ContactResponse;
+use crate::web::group_requests::JoinRequestResponse;
+use crate::web::password_policies::{PasswordSettingsResponse, ResultantPasswordPolicyResponse};
+use crate::web::sites::{SiteResponse, SubnetResponse};
+use crate::web::service_accounts::ServiceAccountResponse;
+
+type CliResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+/// Клиент REST API с токеном администратора
+pub struct RemoteClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl RemoteClient {
+    /// Подключиться с готовым токеном или войти, запросив имя и пароль
+    pub async fn connect(server: &str, token: Option<String>) -> CliResult<Self> {
+        let mut client = Self {
+            http: reqwest::Client::new(),
+            base_url: server.trim_end_matches('/').to_string(),
+            token: token.unwrap_or_default(),
+        };
+        if client.token.is_empty() {
+            client.token = client.login().await?;
+        }
+        Ok(client)
+    }
+
+    async fn login(&self) -> CliResult<String> {
+        let username = prompt("Пользователь: ")?;
+        let password = rpassword::prompt_password("Пароль: ")?;
+
+        let response = self.send(self.http.post(self.url("/api/auth/login"))
+            .json(&json!({ "username": username, "password": password })))
+            .await?;
+        let body: Value = response.json().await?;
+        body["token"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "Login response has no token".into())
+    }
+
+    pub async fn usernames(&self) -> Vec<String> {
+        self.get::<Vec<UserResponse>>("/api/users").await
+            .map(|users| users.into_iter().map(|user| user.username).collect())
+            .unwrap_or_default()
+    }
+
+    pub async fn execute(&self, command: Command, output: Output) -> CliResult<()> {
+        match command {
+            Command::User { cmd } => self.user(cmd, output).await,
+            Command::Group { cmd } => self.group(cmd, output).await,
+            Command::Ou { cmd } => self.ou(cmd, output).await,
+            Command::Contact { cmd } => self.contact(cmd, output).await,
+            Command::ServiceAccount { cmd } => self.service_account(cmd, output).await,
+            Command::PasswordPolicy { cmd } => self.password_policy(cmd, output).await,
+            Command::Site { cmd } => self.site(cmd, output).await,
+            Command::Subnet { cmd } => self.subnet(cmd, output).await,
+            Command::Gpo { cmd } => self.gpo(cmd, output).await,
+            _ => Err(local_only()),
+        }
+    }
+
+    async fn user(&self, cmd: UserCommand, output: Output) -> CliResult<()> {
+        match cmd {
+            UserCommand::Create { username, email, display_name } => {
+                let user: UserResponse = self.post("/api/users", json!({ "username": username, "email": email, "display_name": display_name })).await?;
+                output.saved(&user, &format!("✅ Пользователь создан: {}", username))?;
+            }
+            UserCommand::Get { username } => {
+                let user: UserResponse = self.send(self.http.get(self.user_url(&username)?)).await?.json().await?;
+                output.one(&user)?;
+            }
+            UserCommand::List => {
+                output.list(&self.get::<Vec<UserResponse>>("/api/users").await?)?;
+            }
+            UserCommand::Delete { username } => {
+                self.send(self.http.delete(self.user_url(&username)?)).await?;
+                output.done(&format!("✅ Пользователь удалён: {}", username));
+            }
+            UserCommand::Set { username, email, display_name, upn: None, ou: None } => {
+                let user = self.put_user(&username, json!({ "email": email, "display_name": display_name })).await?;
+                output.saved(&user, &format!("✅ Пользователь изменён: {}", username))?;
+            }
+            UserCommand::Enable { username } => {
+                let user = self.put_user(&username, json!({ "enabled": true })).await?;
+                output.saved(&user, &format!("✅ Пользователь включён: {}", username))?;
+            }
+            UserCommand::Disable { username } => {
+                let user = self.put_user(&username, json!({ "enabled": false })).await?;
+                output.saved(&user, &format!("✅ Пользователь отключён: {}", username))?;
+            }
+            UserCommand::Flags { username, set, clear } => {
+                let user: UserResponse = if set.is_empty() && clear.is_empty() {
+                    self.send(self.http.get(self.user_url(&username)?)).await?.json().await?
+                } else {
+                    let mut url = self.user_url(&username)?;
+                    url.path_segments_mut()
+                        .map_err(|_| format!("Invalid server URL: {}", self.base_url))?
+                        .push("account-control");
+                    self.send(self.http.put(url).json(&json!({ "set": set, "clear": clear }))).await?.json().await?
+                };
+                output.saved(&user, &super::account_flags_message(&user))?;
+            }
+            UserCommand::Logins { username, limit } => {
+                let mut url = self.user_url(&username)?;
+                url.path_segments_mut()
+                    .map_err(|_| format!("Invalid server URL: {}", self.base_url))?
+                    .push("logins");
+                if let Some(limit) = limit {
+                    url.query_pairs_mut().append_pair("limit", &limit.to_string());
+                }
+                let logins: Vec<LoginRecord> = self.send(self.http.get(url)).await?.json().await?;
+                output.list(&logins)?;
+            }
+            UserCommand::Set { .. }
+            | UserCommand::Unlock { .. }
+            | UserCommand::Move { .. }
+            | UserCommand::SetPrimaryGroup { .. }
+            | UserCommand::SetPassword { .. }
+            | UserCommand::Import { .. }
+            | UserCommand::Export { .. } => {
+                return Err(local_only());
+            }
+        }
+        Ok(())
+    }
+
+    async fn group(&self, cmd: GroupCommand, output: Output) -> CliResult<()> {
+        match cmd {
+            GroupCommand::Create { name, sam_account_name, rule } => {
+                let group: GroupResponse = self.post("/api/groups", json!({ "name": name, "sam_account_name": sam_account_name, "membership_rule": rule })).await?;
+                let message = format!("✅ Группа создана: {}", group.sam_account_name);
+                output.saved(&group, &message)?;
+            }
+            GroupCommand::List => {
+                output.list(&self.get::<Vec<GroupResponse>>("/api/groups").await?)?;
+            }
+            GroupCommand::SetRule { sam, rule } => {
+                let url = self.group_url(&sam, &["membership-rule"])?;
+                let group: GroupResponse = self.send(self.http.put(url).json(&json!({ "membership_rule": rule }))).await?.json().await?;
+                let message = match &group.membership_rule {
+                    Some(rule) => format!("✅ Правило членства {}: {} (участников: {})", group.sam_account_name, rule, group.members_count),
+                    None => format!("✅ Группа {} стала статической", group.sam_account_name),
+                };
+                output.saved(&group, &message)?;
+            }
+            GroupCommand::SetLifecycle { sam, owner, expires } => {
+                let url = self.group_url(&sam, &["lifecycle"])?;
+                let group: GroupResponse = self.send(self.http.put(url).json(&json!({ "managed_by": owner, "expires_at": expires }))).await?.json().await?;
+                output.saved(&group, &format!("✅ Владелец и срок группы {} сохранены", group.sam_account_name))?;
+            }
+            GroupCommand::ConvertScope { sam, scope } => {
+                let url = self.group_url(&sam, &[])?;
+                let group: GroupResponse = self.send(self.http.put(url).json(&json!({ "scope": scope }))).await?.json().await?;
+                output.saved(&group, &format!("✅ Область группы {}: {:?}", group.sam_account_name, group.scope))?;
+            }
+            GroupCommand::Requests { sam } => {
+                let url = self.group_url(&sam, &["requests"])?;
+                let requests: Vec<JoinRequestResponse> = self.send(self.http.get(url)).await?.json().await?;
+                output.list(&requests)?;
+            }
+            GroupCommand::Approve { sam, id, comment } => {
+                let url = self.group_url(&sam, &["requests", &id.to_string(), "approve"])?;
+                let request: JoinRequestResponse = self.send(self.http.post(url).json(&json!({ "comment": comment }))).await?.json().await?;
+                output.saved(&request, &format!("✅ Заявка одобрена: {} добавлен в {}", request.username, request.group))?;
+            }
+            GroupCommand::Deny { sam, id, comment } => {
+                let url = self.group_url(&sam, &["requests", &id.to_string(), "deny"])?;
+                let request: JoinRequestResponse = self.send(self.http.post(url).json(&json!({ "comment": comment }))).await?.json().await?;
+                output.saved(&request, &format!("✅ Заявка {} в {} отклонена", request.username, request.group))?;
+            }
+            GroupCommand::Get { .. }
+            | GroupCommand::AddMember { .. }
+            | GroupCommand::RemoveMember { .. }
+            | GroupCommand::Refresh { .. }
+            | GroupCommand::Expire
+            | GroupCommand::Export { .. } => {
+                return Err(local_only());
+            }
+        }
+        Ok(())
+    }
+
+    async fn ou(&self, cmd: OuCommand, output: Output) -> CliResult<()> {
+        match cmd {
+            OuCommand::Create { name, parent } => {
+                let ou: OuResponse = self.post("/api/ous", json!({ "name": name, "parent": parent })).await?;
+                let message = format!("✅ OU создана: DN={}", ou.dn);
+                output.saved(&ou, &message)?;
+            }
+            OuCommand::List => {
+                output.list(&self.get::<Vec<OuResponse>>("/api/ous").await?)?;
+            }
+            OuCommand::Tree => {
+                output.tree(&self.get::<Vec<OuTreeNode>>("/api/ous/tree").await?)?;
+            }
+            OuCommand::Delete { ou, recursive, contents } => {
+                let url = self.ou_url(&ou).await?;
+                self.send(self.http.delete(url).query(&[("recursive", json!(recursive)), ("contents", json!(contents))])).await?;
+                output.done(&format!("✅ OU удалена: {}", ou));
+            }
+            OuCommand::Rename { ou, new_name } => {
+                let url = self.ou_url(&ou).await?;
+                let renamed: OuResponse = self.send(self.http.put(url).json(&json!({ "name": new_name }))).await?.json().await?;
+                let message = format!("✅ OU переименована: DN={}", renamed.dn);
+                output.saved(&renamed, &message)?;
+            }
+            OuCommand::Move { ou, parent } => {
+                let url = self.ou_url(&ou).await?;
+                let moved: OuResponse = self.send(self.http.put(url).json(&json!({ "parent": parent }))).await?.json().await?;
+                let message = format!("✅ OU перенесена: DN={}", moved.dn);
+                output.saved(&moved, &message)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn contact(&self, cmd: ContactCommand, output: Output) -> CliResult<()> {
+        match cmd {
+            ContactCommand::Create { name, email, display_name, company, phone, ou, shared_mailbox } => {
+                let contact: ContactResponse = self.post("/api/contacts", json!({
+                    "name": name,
+                    "email": email,
+                    "display_name": display_name,
+                    "company": company,
+                    "telephone_number": phone,
+                    "ou": ou,
+                    "kind": if shared_mailbox { "shared_mailbox" } else { "external" },
+                })).await?;
+                let message = format!("✅ Контакт создан: {}", contact.email);
+                output.saved(&contact, &message)?;
+            }
+            ContactCommand::Get { contact } => {
+                output.one(&self.find_contact(&contact).await?)?;
+            }
+            ContactCommand::List => {
+                output.list(&self.get::<Vec<ContactResponse>>("/api/contacts").await?)?;
+            }
+            ContactCommand::Set { contact, email, display_name, company, phone, ou } => {
+                let url = self.url(&format!("/api/contacts/{}", self.find_contact(&contact).await?.id));
+                let contact: ContactResponse = self.send(self.http.put(url).json(&json!({
+                    "email": email,
+                    "display_name": display_name,
+                    "company": company,
+                    "telephone_number": phone,
+                    "ou": ou,
+                }))).await?.json().await?;
+                let message = format!("✅ Контакт изменён: {}", contact.email);
+                output.saved(&contact, &message)?;
+            }
+            ContactCommand::Delete { contact } => {
+                let contact = self.find_contact(&contact).await?;
+                self.send(self.http.delete(self.url(&format!("/api/contacts/{}", contact.id)))).await?;
+                output.done(&format!("✅ Контакт удалён: {}", contact.email));
+            }
+        }
+        Ok(())
+    }
+
+    async fn service_account(&self, cmd: ServiceAccountCommand, output: Output) -> CliResult<()> {
+        match cmd {
+            ServiceAccountCommand::Create { username, spns, allow, rotation_days, overlap_hours, flags } => {
+                let account: ServiceAccountResponse = self.post("/api/service-accounts", json!({
+                    "username": username,
+                    "service_principal_names": spns,
+                    "allowed_principals": allow,
+                    "rotation_interval_days": rotation_days,
+                    "overlap_hours": overlap_hours,
+                    "flags": flags,
+                })).await?;
+                output.saved(&account, &format!("✅ Учётная запись службы создана: {}", username))?;
+            }
+            ServiceAccountCommand::Get { username } => {
+                let account: ServiceAccountResponse = self.send(self.http.get(self.service_account_url(&username, None)?)).await?.json().await?;
+                output.one(&account)?;
+            }
+            ServiceAccountCommand::List => {
+                output.list(&self.get::<Vec<ServiceAccountResponse>>("/api/service-accounts").await?)?;
+            }
+            ServiceAccountCommand::Set { username, allow, rotation_days, overlap_hours, flags } => {
+                let account: ServiceAccountResponse = self.send(self.http.put(self.service_account_url(&username, None)?).json(&json!({
+                    "allowed_principals": allow,
+                    "rotation_interval_days": rotation_days,
+                    "overlap_hours": overlap_hours,
+                    "flags": flags,
+                }))).await?.json().await?;
+                output.saved(&account, &format!("✅ Учётная запись службы изменена: {}", username))?;
+            }
+            ServiceAccountCommand::Rotate { username } => {
+                let account: ServiceAccountResponse = self.send(self.http.post(self.service_account_url(&username, Some("rotate"))?)).await?.json().await?;
+                output.saved(&account, &format!("✅ Пароль учётной записи службы сменён: {}", username))?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn password_policy(&self, cmd: PasswordPolicyCommand, output: Output) -> CliResult<()> {
+        match cmd {
+            PasswordPolicyCommand::Create { name, precedence, description, groups, ous, settings } => {
+                let pso: PasswordSettingsResponse = self.post("/api/password-policies", json!({
+                    "name": name,
+                    "precedence": precedence,
+                    "description": description,
+                    "groups": groups,
+                    "ous": ous,
+                    "settings": settings,
+                })).await?;
+                output.saved(&pso, &format!("✅ Парольная политика создана: {}", name))?;
+            }
+            PasswordPolicyCommand::Get { name } => {
+                let pso: PasswordSettingsResponse = self.send(self.http.get(self.password_policy_url(&name)?)).await?.json().await?;
+                output.one(&pso)?;
+            }
+            PasswordPolicyCommand::List => {
+                output.list(&self.get::<Vec<PasswordSettingsResponse>>("/api/password-policies").await?)?;
+            }
+            PasswordPolicyCommand::Set { name, precedence, description, groups, ous, settings } => {
+                let pso: PasswordSettingsResponse = self.send(self.http.put(self.password_policy_url(&name)?).json(&json!({
+                    "precedence": precedence,
+                    "description": description,
+                    "groups": groups,
+                    "ous": ous,
+                    "settings": settings,
+                }))).await?.json().await?;
+                output.saved(&pso, &format!("✅ Парольная политика изменена: {}", name))?;
+            }
+            PasswordPolicyCommand::Delete { name } => {
+                self.send(self.http.delete(self.password_policy_url(&name)?)).await?;
+                output.done(&format!("✅ Парольная политика удалена: {}", name));
+            }
+            PasswordPolicyCommand::Resultant { username } => {
+                let mut url = self.user_url(&username)?;
+                url.path_segments_mut()
+                    .map_err(|_| format!("Invalid server URL: {}", self.base_url))?
+                    .push("password-policy");
+                let policy: ResultantPasswordPolicyResponse = self.send(self.http.get(url)).await?.json().await?;
+                output.one(&policy)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn site(&self, cmd: SiteCommand, output: Output) -> CliResult<()> {
+        match cmd {
+            SiteCommand::Create { name, description, location } => {
+                let site: SiteResponse = self.post("/api/sites", json!({
+                    "name": name,
+                    "description": description,
+                    "location": location,
+                })).await?;
+                output.saved(&site, &format!("✅ Сайт создан: {}", name))?;
+            }
+            SiteCommand::Get { name } => {
+                let site: SiteResponse = self.send(self.http.get(self.named_url("/api/sites", &[&name])?)).await?.json().await?;
+                output.one(&site)?;
+            }
+            SiteCommand::List => {
+                output.list(&self.get::<Vec<SiteResponse>>("/api/sites").await?)?;
+            }
+            SiteCommand::Set { name, rename, description, location } => {
+                let site: SiteResponse = self.send(self.http.put(self.named_url("/api/sites", &[&name])?).json(&json!({
+                    "name": rename,
+                    "description": description,
+                    "location": location,
+                }))).await?.json().await?;
+                output.saved(&site, &format!("✅ Сайт изменён: {}", site.name))?;
+            }
+            SiteCommand::Delete { name } => {
+                self.send(self.http.delete(self.named_url("/api/sites", &[&name])?)).await?;
+                output.done(&format!("✅ Сайт удалён: {}", name));
+            }
+            SiteCommand::Lookup { ip } => {
+                let site: SiteResponse = self.send(self.http.get(self.url("/api/sites/lookup"))
+                    .query(&[("ip", ip.to_string())])).await?.json().await?;
+                output.one(&site)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn subnet(&self, cmd: SubnetCommand, output: Output) -> CliResult<()> {
+        match cmd {
+            SubnetCommand::Create { subnet, site, description, location } => {
+                let created: SubnetResponse = self.post("/api/subnets", json!({
+                    "subnet": subnet,
+                    "site": site,
+                    "description": description,
+                    "location": location,
+                })).await?;
+                output.saved(&created, &format!("✅ Подсеть создана: {} → {}", created.subnet, created.site))?;
+            }
+            SubnetCommand::List => {
+                output.list(&self.get::<Vec<SubnetResponse>>("/api/subnets").await?)?;
+            }
+            SubnetCommand::Set { subnet, site, description, location } => {
+                let updated: SubnetResponse = self.send(self.http.put(self.subnet_url(&subnet)?).json(&json!({
+                    "site": site,
+                    "description": description,
+                    "location": location,
+                }))).await?.json().await?;
+                output.saved(&updated, &format!("✅ Подсеть изменена: {}", updated.subnet))?;
+            }
+            SubnetCommand::Delete { subnet } => {
+                self.send(self.http.delete(self.subnet_url(&subnet)?)).await?;
+                output.done(&format!("✅ Подсеть удалена: {}", subnet));
+            }
+        }
+        Ok(())
+    }
+
+    async fn gpo(&self, cmd: GpoCommand, output: Output) -> CliResult<()> {
+        match cmd {
+            GpoCommand::Create { name, display_name, description, linked_to, enforced, enabled, policy_type, settings } => {
+                // Разбор по схеме типа: числа и списки уходят в JSON с нужным типом
+                let settings = parse_settings(&policy_type.as_deref().map(PolicyType::from_name).unwrap_or_default(), &settings)?;
+                let gpo: GpoResponse = self.post("/api/gpos", json!({
+                    "name": name,
+                    "display_name": display_name,
+                    "description": description,
+                    "linked_to": linked_to,
+                    "enforced": enforced,
+                    "enabled": enabled,
+                    "policy_type": policy_type,
+                    "settings": settings,
+                })).await?;
+                let message = format!("✅ GPO создана: ID={}", gpo.id);
+                output.saved(&gpo, &message)?;
+            }
+            GpoCommand::Schemas { policy_type } => {
+                let schemas: Vec<PolicySchema> = self.get("/api/gpos/schemas").await?;
+                super::print_gpo_schemas(schemas, policy_type.as_deref(), &output)?;
+            }
+            _ => return Err(local_only()),
+        }
+        Ok(())
+    }
+
+    // === HTTP ===
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// `/api/users/{username}` с экранированием имени
+    fn user_url(&self, username: &str) -> CliResult<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&self.url("/api/users"))?;
+        url.path_segments_mut()
+            .map_err(|_| format!("Invalid server URL: {}", self.base_url))?
+            .push(username);
+        Ok(url)
+    }
+
+    /// `/api/service-accounts/{username}[/{action}]` с экранированием имени
+    fn service_account_url(&self, username: &str, action: Option<&str>) -> CliResult<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&self.url("/api/service-accounts"))?;
+        url.path_segments_mut()
+            .map_err(|_| format!("Invalid server URL: {}", self.base_url))?
+            .push(username)
+            .extend(action);
+        Ok(url)
+    }
+
+    /// `/api/password-policies/{name}` с экранированием имени
+    fn password_policy_url(&self, name: &str) -> CliResult<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&self.url("/api/password-policies"))?;
+        url.path_segments_mut()
+            .map_err(|_| format!("Invalid server URL: {}", self.base_url))?
+            .push(name);
+        Ok(url)
+    }
+
+    /// `{path}/{segments...}` с экранированием сегментов
+    fn named_url(&self, path: &str, segments: &[&str]) -> CliResult<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&self.url(path))?;
+        url.path_segments_mut()
+            .map_err(|_| format!("Invalid server URL: {}", self.base_url))?
+            .extend(segments);
+        Ok(url)
+    }
+
+    /// `/api/subnets/{network}/{prefix_len}` для записи CIDR
+    fn subnet_url(&self, cidr: &str) -> CliResult<reqwest::Url> {
+        let (network, prefix_len) = crate::models::site::parse_cidr(cidr)?;
+        self.named_url("/api/subnets", &[&network.to_string(), &prefix_len.to_string()])
+    }
+
+    /// `/api/groups/{sam}/{segments...}` с экранированием имени
+    fn group_url(&self, sam: &str, segments: &[&str]) -> CliResult<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&self.url("/api/groups"))?;
+        url.path_segments_mut()
+            .map_err(|_| format!("Invalid server URL: {}", self.base_url))?
+            .push(sam)
+            .extend(segments);
+        Ok(url)
+    }
+
+    /// `PUT /api/users/{username}`: меняет только переданные поля
+    async fn put_user(&self, username: &str, body: Value) -> CliResult<UserResponse> {
+        Ok(self.send(self.http.put(self.user_url(username)?).json(&body)).await?.json().await?)
+    }
+
+    /// `/api/ous/{id}` для OU по UUID или DN
+    async fn ou_url(&self, ou: &str) -> CliResult<String> {
+        let id = match uuid::Uuid::parse_str(ou) {
+            Ok(id) => id,
+            Err(_) => self.get::<Vec<OuResponse>>("/api/ous").await?
+                .into_iter()
+                .find(|candidate| crate::dn::canonical(&candidate.dn).is_some_and(|dn| crate::dn::canonical(ou) == Some(dn)))
+                .map(|candidate| candidate.id)
+                .ok_or_else(|| format!("OU not found: {}", ou))?,
+        };
+        Ok(self.url(&format!("/api/ous/{}", id)))
+    }
+
+    /// Контакт по UUID или адресу
+    async fn find_contact(&self, contact: &str) -> CliResult<ContactResponse> {
+        if let Ok(id) = uuid::Uuid::parse_str(contact) {
+            return self.get(&format!("/api/contacts/{}", id)).await;
+        }
+        self.get::<Vec<ContactResponse>>("/api/contacts").await?
+            .into_iter()
+            .find(|candidate| candidate.email.eq_ignore_ascii_case(contact) || candidate.other_emails.iter().any(|email| email.eq_ignore_ascii_case(contact)))
+            .ok_or_else(|| format!("Contact not found: {}", contact).into())
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> CliResult<T> {
+        Ok(self.send(self.http.get(self.url(path))).await?.json().await?)
+    }
+
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: Value) -> CliResult<T> {
+        Ok(self.send(self.http.post(self.url(path)).json(&body)).await?.json().await?)
+    }
+
+    /// Отправить запрос с токеном; ответ не 2xx — ошибка с текстом из `{"error": ...}`
+    async fn send(&self, request: reqwest::RequestBuilder) -> CliResult<reqwest::Response> {
+        let request = if self.token.is_empty() { request } else { request.bearer_auth(&self.token) };
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body: Option<Value> = response.json().await.ok();
+        let message = body.as_ref()
+            .and_then(|body| body["error"].as_str())
+            .unwrap_or_else(|| status.canonical_reason().unwrap_or("request failed"));
+        Err(format!("{} (HTTP {})", message, status.as_u16()).into())
+    }
+}
+
+fn local_only() -> Box<dyn std::error::Error> {
+    "Command is not available in remote mode; run the CLI on the server without --server".into()
+}
+
+fn prompt(label: &str) -> std::io::Result<String> {
+    use std::io::Write;
+
+    print!("{}", label);
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
@@ -1,13 +1,36 @@
 // src/config.rs
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::ldif::DuplicatePolicy;
+
+pub mod address;
+pub mod duration;
+pub mod secrets;
+pub mod validate;
+pub use address::ListenAddresses;
+pub use duration::ConfigDuration;
+pub use secrets::SecretsConfig;
+/// Парольная политика домена — та же структура, что и у PSO
+pub use crate::models::PasswordPolicy;
+use crate::models::{ApprovalOperation, RiskAction};
 
 #[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct AppConfig {
     pub db_path: String,
-    pub master_key_hex: String,
+    /// Мастер-ключ базы в hex. Задаётся ровно один источник ключа: это поле (лучше через
+    /// `${ENV_VAR}`), `master_key_file` или `master_key_secret`
+    pub master_key_hex: Option<String>,
+    /// Файл с мастер-ключом в hex (Docker / Kubernetes secret)
+    pub master_key_file: Option<String>,
+    /// Ключ в хранилище секретов из секции `secrets`: `nextdomen/prod#master_key`
+    pub master_key_secret: Option<String>,
+    #[serde(default)]
+    pub secrets: SecretsConfig,
 
     #[serde(default)]
     pub web_server: ServerConfig,
@@ -18,9 +41,29 @@ pub struct AppConfig {
     #[serde(default)]
     pub ldap_server: LdapServerConfig,
 
+    #[serde(default)]
+    pub radius_server: RadiusServerConfig,
+
+    #[serde(default)]
+    pub oidc: OidcConfig,
+
+    /// Внутренний CA: сертификаты пользователей и компьютеров (`nextDomen ca init`)
+    #[serde(default)]
+    pub ca: CaConfig,
+
+    /// Односторонняя синхронизация из внешнего LDAP / AD
+    pub ldap_sync: Option<LdapSyncConfig>,
+
     #[serde(default)]
     pub security: SecurityConfig,
 
+    #[serde(default)]
+    pub groups: GroupsConfig,
+
+    /// Отправка писем пользователям: сброс пароля, приглашения
+    #[serde(default)]
+    pub mail: MailConfig,
+
     #[serde(default)]
     pub logging: LoggingConfig,
 
@@ -29,41 +72,431 @@ pub struct AppConfig {
 
     #[serde(default)]
     pub metrics: MetricsConfig,
+
+    #[serde(default)]
+    pub cache: CacheConfig,
+
+    /// Реплика только для чтения (филиал)
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+
+    /// Расписание фоновых задач процесса `web` по имени задачи (`nextDomen jobs list`)
+    #[serde(default)]
+    pub jobs: HashMap<String, JobConfig>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct ServerConfig {
-    pub address: Option<String>,
+    /// `host:port` или список адресов (IPv4 и IPv6 рядом); для web_server ещё и
+    /// `unix:/run/nextdomen.sock`. `--addr` и `--grpc-addr` команды `web` главнее
+    #[serde(default)]
+    pub address: ListenAddresses,
+    /// Права Unix-сокета `address: unix:...` (только web_server)
+    #[serde(default)]
+    pub unix_socket: UnixSocketConfig,
     #[serde(default)]
     pub enable_tls: bool,
     #[serde(default)]
     pub tls: TlsConfig,
+    /// Предел тела запроса в байтах, иначе 413 (только web_server); 0 — без предела
     #[serde(default = "default_max_request_size")]
     pub max_request_size: u64,
+    /// Swagger UI на `/api/docs/` (только web_server, только для Domain Admins)
+    #[serde(default)]
+    pub swagger_ui: bool,
+    /// Не больше объектов в ответе списков REST (`GET /api/users`, `/api/groups`, `/api/ous`,
+    /// `/api/contacts`), иначе 413; 0 — без предела (только web_server)
+    #[serde(default)]
+    pub size_limit: usize,
+    /// Не дольше секунд на чтение списка, иначе 408; 0 — без предела (только web_server)
+    #[serde(default, deserialize_with = "duration::secs")]
+    pub time_limit_secs: u64,
+    #[serde(default)]
+    pub cors: CorsConfig,
+    /// Обратные прокси перед REST API (только web_server)
+    #[serde(default)]
+    pub proxy: ProxyConfig,
 }
 
+/// Адрес REST API без `--addr` и `web_server.address`
+pub const DEFAULT_WEB_ADDRESS: &str = "127.0.0.1:8080";
+
 fn default_max_request_size() -> u64 {
     10 * 1024 * 1024 // 10 MB
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: ListenAddresses::default(),
+            unix_socket: UnixSocketConfig::default(),
+            enable_tls: false,
+            tls: TlsConfig::default(),
+            max_request_size: default_max_request_size(),
+            swagger_ui: false,
+            size_limit: 0,
+            time_limit_secs: 0,
+            cors: CorsConfig::default(),
+            proxy: ProxyConfig::default(),
+        }
+    }
+}
+
+/// Обратные прокси и балансировщики, которые передают адрес клиента
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyConfig {
+    /// Адреса и сети прокси (`10.0.0.10`, `10.0.0.0/24`, `fd00::/8`): только от них принимаются
+    /// X-Forwarded-For и заголовок PROXY
+    #[serde(default)]
+    pub trusted: Vec<String>,
+    /// Соединения от доверенных прокси начинаются заголовком PROXY protocol v1 или v2
+    /// (HAProxy `send-proxy`, nginx `proxy_protocol on`)
+    #[serde(default)]
+    pub proxy_protocol: bool,
+}
+
+/// Unix-сокет REST API для обратного прокси на том же узле
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct UnixSocketConfig {
+    /// Права в восьмеричной записи: `660` — владелец и группа
+    #[serde(default = "default_unix_socket_mode")]
+    pub mode: String,
+    /// Группа сокета (имя из /etc/group или gid), например группа обратного прокси
+    pub group: Option<String>,
+}
+
+fn default_unix_socket_mode() -> String {
+    "660".to_string()
+}
+
+impl Default for UnixSocketConfig {
+    fn default() -> Self {
+        Self { mode: default_unix_socket_mode(), group: None }
+    }
+}
+
+/// CORS для браузерных клиентов с других источников; пустой список — только тот же источник
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Источники вида `https://admin.corp.example.com`; `*` — любой (без `allow_credentials`)
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Разрешить cookie и заголовок Authorization в запросах с других источников
+    #[serde(default)]
+    pub allow_credentials: bool,
+    /// Сколько секунд браузер может кешировать ответ на preflight
+    #[serde(default = "default_cors_max_age", deserialize_with = "duration::secs")]
+    pub max_age_secs: u64,
+}
+
+fn default_cors_max_age() -> u64 {
+    600
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self { allowed_origins: Vec::new(), allow_credentials: false, max_age_secs: default_cors_max_age() }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct LdapServerConfig {
-    pub address: Option<String>,
+    /// `host:port` или список адресов
+    #[serde(default)]
+    pub address: ListenAddresses,
     #[serde(default)]
     pub enable_tls: bool,
     #[serde(default)]
     pub tls: TlsConfig,
+    /// Поиск без bind (анонимный); записи отдаются только те, что DACL открывает Everyone
     #[serde(default)]
     pub allow_anonymous_bind: bool,
     #[serde(default = "default_base_dn")]
     pub base_dn: String,
+    /// Не больше записей в ответе поиска, даже если клиент просит больше (как MaxPageSize в AD); 0 — без предела
+    #[serde(default = "default_ldap_size_limit")]
+    pub size_limit: usize,
+    /// Не дольше секунд на поиск, даже если клиент разрешает больше (как MaxQueryDuration в AD); 0 — без предела
+    #[serde(default = "default_ldap_time_limit", deserialize_with = "duration::secs")]
+    pub time_limit_secs: u64,
+    /// Сколько операций принимается в одном соединении; дальше — adminLimitExceeded и разрыв; 0 — без предела
+    #[serde(default)]
+    pub max_operations_per_connection: u64,
+    /// Обратные прокси перед LDAP: адрес клиента — из заголовка PROXY
+    #[serde(default)]
+    pub proxy: ProxyConfig,
 }
 
 fn default_base_dn() -> String {
     "DC=corp,DC=acme,DC=com".to_string()
 }
 
+fn default_ldap_size_limit() -> usize { 1000 }
+
+fn default_ldap_time_limit() -> u64 { 120 }
+
+impl Default for LdapServerConfig {
+    fn default() -> Self {
+        Self {
+            address: ListenAddresses::default(),
+            enable_tls: false,
+            tls: TlsConfig::default(),
+            allow_anonymous_bind: false,
+            base_dn: default_base_dn(),
+            size_limit: default_ldap_size_limit(),
+            time_limit_secs: default_ldap_time_limit(),
+            max_operations_per_connection: 0,
+            proxy: ProxyConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RadiusServerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `host:port` или список адресов, по умолчанию 0.0.0.0:1812
+    #[serde(default)]
+    pub address: ListenAddresses,
+    /// NAS (точки доступа, VPN-шлюзы), которым разрешено обращаться к серверу
+    #[serde(default)]
+    pub clients: Vec<RadiusClientConfig>,
+    /// Сертификат сервера для EAP-TTLS
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Атрибуты, возвращаемые участникам групп
+    #[serde(default)]
+    pub group_attributes: Vec<RadiusGroupAttribute>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RadiusClientConfig {
+    /// IP-адрес NAS
+    pub address: String,
+    pub secret: String,
+    pub name: Option<String>,
+}
+
+/// Vendor-Specific атрибут для участников группы (например VLAN или роль на контроллере Wi-Fi)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RadiusGroupAttribute {
+    /// sAMAccountName группы
+    pub group: String,
+    pub vendor_id: u32,
+    pub vendor_type: u8,
+    pub value: String,
+}
+
+/// Провайдер OAuth2 / OpenID Connect поверх REST API
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct OidcConfig {
+    /// Внешний адрес REST API (`iss` в токенах); по умолчанию `http://<адрес --addr>`
+    pub issuer: Option<String>,
+    /// Время жизни access и ID токенов в секундах
+    #[serde(default = "default_oidc_token_lifetime", deserialize_with = "duration::secs")]
+    pub token_lifetime_secs: u64,
+}
+
+fn default_oidc_token_lifetime() -> u64 {
+    3600
+}
+
+impl Default for OidcConfig {
+    fn default() -> Self {
+        Self {
+            issuer: None,
+            token_lifetime_secs: default_oidc_token_lifetime(),
+        }
+    }
+}
+
+/// Реплика только для чтения, как RODC в AD: каталог забирается с записываемого контроллера,
+/// локальные изменения отклоняются со ссылкой на него
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ReplicationConfig {
+    #[serde(default)]
+    pub read_only: bool,
+    /// Записываемый контроллер, например `https://dc01.corp.acme.com:8080`
+    pub upstream: Option<String>,
+    /// API-ключ администратора на `upstream`
+    pub api_key: Option<String>,
+    /// Как часто процесс `web` забирает каталог с `upstream`
+    #[serde(default = "default_pull_interval_secs", deserialize_with = "duration::secs")]
+    pub pull_interval_secs: u64,
+    /// Пользователи и группы, чьи пароли и ключи Kerberos хранит реплика (Password Replication
+    /// Policy); остальным войти через реплику нельзя. Пусто — ничьи
+    #[serde(default)]
+    pub cache_credentials_for: Vec<String>,
+}
+
+fn default_pull_interval_secs() -> u64 { 300 }
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            read_only: false,
+            upstream: None,
+            api_key: None,
+            pull_interval_secs: default_pull_interval_secs(),
+            cache_credentials_for: Vec::new(),
+        }
+    }
+}
+
+/// Группы каталога
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GroupsConfig {
+    /// Период пересчёта снимков участников динамических групп процессом `web`; 0 — не пересчитывать
+    /// (memberOf и tokenGroups вычисляются по правилу при каждом запросе и без этого)
+    #[serde(default, deserialize_with = "duration::secs")]
+    pub dynamic_refresh_secs: u64,
+    /// Как часто процесс `web` отключает группы и удаляет участников с истёкшим сроком
+    #[serde(default = "default_expiration_check_secs", deserialize_with = "duration::secs")]
+    pub expiration_check_secs: u64,
+}
+
+fn default_expiration_check_secs() -> u64 { 3600 }
+
+impl Default for GroupsConfig {
+    fn default() -> Self {
+        Self { dynamic_refresh_secs: 0, expiration_check_secs: default_expiration_check_secs() }
+    }
+}
+
+/// Кэш десериализованных объектов каталога
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Сколько пользователей, групп, OU и контактов держать в памяти; 0 — без кэша
+    #[serde(default = "default_cache_capacity")]
+    pub capacity: usize,
+}
+
+fn default_cache_capacity() -> usize { crate::cache::DEFAULT_CAPACITY }
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { capacity: default_cache_capacity() }
+    }
+}
+
+/// Внутренний CA
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CaConfig {
+    /// CN сертификата CA, создаваемого `ca init`
+    #[serde(default = "default_ca_common_name")]
+    pub common_name: String,
+    /// Срок действия сертификата CA, дней
+    #[serde(default = "default_ca_validity_days")]
+    pub ca_validity_days: u32,
+    /// Срок действия выдаваемых сертификатов, дней
+    #[serde(default = "default_certificate_validity_days")]
+    pub validity_days: u32,
+    /// Через сколько выпускать следующий CRL (nextUpdate)
+    #[serde(default = "default_crl_validity", deserialize_with = "duration::secs")]
+    pub crl_validity_secs: u64,
+    /// Адрес CRL в выдаваемых сертификатах: `https://dc01.corp.acme.com:8080/api/ca/crl`
+    pub crl_url: Option<String>,
+}
+
+fn default_ca_common_name() -> String { "nextDomen CA".to_string() }
+fn default_ca_validity_days() -> u32 { 3650 }
+fn default_certificate_validity_days() -> u32 { 365 }
+fn default_crl_validity() -> u64 { 7 * 24 * 3600 }
+
+impl Default for CaConfig {
+    fn default() -> Self {
+        Self {
+            common_name: default_ca_common_name(),
+            ca_validity_days: default_ca_validity_days(),
+            validity_days: default_certificate_validity_days(),
+            crl_validity_secs: default_crl_validity(),
+            crl_url: None,
+        }
+    }
+}
+
+/// Переопределение встроенной фоновой задачи
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct JobConfig {
+    /// Cron из пяти полей по UTC (`*/15 * * * *`), `@hourly`, `@daily`, ... или `@every 10m`
+    pub schedule: Option<String>,
+    /// Без поля задача включена, если у неё есть расписание
+    pub enabled: Option<bool>,
+}
+
+/// Источник для односторонней синхронизации пользователей, групп и OU
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LdapSyncConfig {
+    /// `ldap://dc01.corp.example.com:389` или `ldaps://...`
+    pub url: String,
+    #[serde(default)]
+    pub starttls: bool,
+    /// Не проверять сертификат источника (только для тестовых стендов)
+    #[serde(default)]
+    pub tls_skip_verify: bool,
+    pub bind_dn: String,
+    pub bind_password: String,
+    pub base_dn: String,
+    #[serde(default = "default_sync_user_filter")]
+    pub user_filter: String,
+    #[serde(default = "default_sync_group_filter")]
+    pub group_filter: String,
+    #[serde(default = "default_sync_ou_filter")]
+    pub ou_filter: String,
+    /// Переименование атрибутов источника: `employeeMail: mail`
+    #[serde(default)]
+    pub attribute_mapping: HashMap<String, String>,
+    /// Интервал инкрементальной синхронизации в секундах
+    #[serde(default = "default_sync_interval", deserialize_with = "duration::secs")]
+    pub interval_secs: u64,
+    /// Что делать, если объект уже есть локально: update — источник главнее
+    #[serde(default = "default_sync_conflict_policy")]
+    pub on_conflict: DuplicatePolicy,
+    #[serde(default = "default_sync_page_size")]
+    pub page_size: i32,
+}
+
+fn default_sync_user_filter() -> String {
+    "(&(|(objectClass=user)(objectClass=inetOrgPerson))(!(objectClass=computer)))".to_string()
+}
+
+fn default_sync_group_filter() -> String {
+    "(|(objectClass=group)(objectClass=groupOfNames)(objectClass=groupOfUniqueNames)(objectClass=posixGroup))".to_string()
+}
+
+fn default_sync_ou_filter() -> String {
+    "(objectClass=organizationalUnit)".to_string()
+}
+
+fn default_sync_interval() -> u64 {
+    300
+}
+
+fn default_sync_conflict_policy() -> DuplicatePolicy {
+    DuplicatePolicy::Update
+}
+
+fn default_sync_page_size() -> i32 {
+    500
+}
+
 #[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct SecurityConfig {
     #[serde(default)]
     pub jwt: JwtConfig,
@@ -71,115 +504,682 @@ pub struct SecurityConfig {
     pub password_policy: PasswordPolicy,
     #[serde(default)]
     pub audit: AuditConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub service_accounts: ServiceAccountConfig,
+    #[serde(default)]
+    pub password_expiry: PasswordExpiryConfig,
+    #[serde(default)]
+    pub self_service: SelfServiceConfig,
+    #[serde(default)]
+    pub password_reset: PasswordResetConfig,
+    #[serde(default)]
+    pub invitations: InvitationConfig,
+    #[serde(default)]
+    pub approvals: ApprovalConfig,
+    #[serde(default)]
+    pub sessions: SessionConfig,
+    #[serde(default)]
+    pub risk: RiskConfig,
+    /// Кто проверяет пароли домена — ключ: DNS-имя домена (суффикс UPN). Домены без записи —
+    /// локальные хеши
+    #[serde(default)]
+    pub auth_providers: HashMap<String, AuthProviderConfig>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+/// Проверка паролей домена; права, группы и блокировки остаются локальными
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthProviderConfig {
+    /// Хеш пароля в базе каталога
+    Local,
+    /// Простой bind к вышестоящему LDAP / AD от имени пользователя
+    UpstreamLdap(UpstreamLdapConfig),
+    /// Вход по паролю (grant `password`) на token endpoint провайдера OIDC
+    Oidc(OidcProviderConfig),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct UpstreamLdapConfig {
+    /// `ldaps://dc01.corp.example.com:636`
+    pub url: String,
+    #[serde(default)]
+    pub starttls: bool,
+    /// Не проверять сертификат (только для тестовых стендов)
+    #[serde(default)]
+    pub tls_skip_verify: bool,
+    /// Имя для bind: `{username}` — sAMAccountName, `{upn}` — userPrincipalName
+    #[serde(default = "default_upstream_bind_name")]
+    pub bind_name: String,
+    #[serde(default = "default_upstream_timeout_secs", deserialize_with = "duration::secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_upstream_bind_name() -> String { "{upn}".to_string() }
+fn default_upstream_timeout_secs() -> u64 { 10 }
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct OidcProviderConfig {
+    /// `https://login.example.com/oauth2/token`
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    #[serde(default = "default_oidc_provider_scope")]
+    pub scope: String,
+    /// Имя пользователя у провайдера: `{username}` — sAMAccountName, `{upn}` — userPrincipalName
+    #[serde(default = "default_upstream_bind_name")]
+    pub login: String,
+    #[serde(default = "default_upstream_timeout_secs", deserialize_with = "duration::secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_oidc_provider_scope() -> String { "openid".to_string() }
+
+/// Признаки подозрительного входа и реакция на каждый: `ignore`, `log`, `step_up`, `block`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RiskConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Вход с другого сайта раньше этого срока после предыдущего — невозможное перемещение
+    #[serde(default = "default_travel_window_secs", deserialize_with = "duration::secs")]
+    pub travel_window_secs: u64,
+    #[serde(default)]
+    pub new_device: RiskAction,
+    #[serde(default)]
+    pub new_location: RiskAction,
+    #[serde(default)]
+    pub impossible_travel: RiskAction,
+}
+
+fn default_travel_window_secs() -> u64 { 3600 }
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            travel_window_secs: default_travel_window_secs(),
+            new_device: RiskAction::default(),
+            new_location: RiskAction::default(),
+            impossible_travel: RiskAction::default(),
+        }
+    }
+}
+
+/// Сессии входа (REST, gRPC): сколько их может быть у пользователя одновременно
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SessionConfig {
+    /// Действующих сессий на пользователя; 0 — без ограничения
+    #[serde(default)]
+    pub max_concurrent: usize,
+    #[serde(default)]
+    pub on_limit: SessionLimitAction,
+}
+
+/// Что делать со входом сверх `max_concurrent`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionLimitAction {
+    /// Отозвать самые старые сессии пользователя
+    #[default]
+    RevokeOldest,
+    /// Отказать во входе, пока старые сессии не истекут или не будут отозваны
+    Reject,
+}
+
+/// Принцип четырёх глаз: перечисленные операции создают заявку, которую одобряет
+/// или отклоняет другой администратор (`/api/approvals`)
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ApprovalConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `delete_user`, `add_privileged_member`, `change_gpo`
+    #[serde(default)]
+    pub operations: Vec<ApprovalOperation>,
+}
+
+impl ApprovalConfig {
+    pub fn requires(&self, operation: ApprovalOperation) -> bool {
+        self.enabled && self.operations.contains(&operation)
+    }
+}
+
+/// Что пользователь меняет у себя сам через `/api/me`, без прав на свой объект в DACL
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SelfServiceConfig {
+    #[serde(default = "default_self_service_enabled")]
+    pub enabled: bool,
+    /// Поля профиля, доступные для изменения; `display_name` по умолчанию не входит — из него
+    /// строится DN пользователя
+    #[serde(default = "default_profile_fields")]
+    pub profile_fields: Vec<ProfileField>,
+    /// Дополнительные атрибуты схемы, доступные для изменения, например `telephoneNumber`
+    #[serde(default)]
+    pub attributes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileField {
+    DisplayName,
+    GivenName,
+    Surname,
+    Email,
+}
+
+fn default_self_service_enabled() -> bool { true }
+fn default_profile_fields() -> Vec<ProfileField> {
+    vec![ProfileField::GivenName, ProfileField::Surname]
+}
+
+impl Default for SelfServiceConfig {
+    fn default() -> Self {
+        Self { enabled: default_self_service_enabled(), profile_fields: default_profile_fields(), attributes: Vec::new() }
+    }
+}
+
+/// Сброс забытого пароля по ссылке из письма (`/api/password-reset/*`); нужна секция `mail`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PasswordResetConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Сколько действует ссылка
+    #[serde(default = "default_reset_token_ttl_secs", deserialize_with = "duration::secs")]
+    pub token_ttl_secs: u64,
+    /// Страница сброса пароля; `{token}` заменяется токеном, например
+    /// `https://portal.corp.acme.com/reset?token={token}`
+    pub reset_url: Option<String>,
+    #[serde(default = "default_reset_subject")]
+    pub subject: String,
+    /// Запросы сброса с одного адреса
+    #[serde(default = "default_reset_per_ip_limit")]
+    pub per_ip: RateLimit,
+    /// Запросы сброса для одной учётной записи, с любых адресов
+    #[serde(default = "default_reset_per_account_limit")]
+    pub per_account: RateLimit,
+}
+
+fn default_reset_token_ttl_secs() -> u64 { 3600 }
+fn default_reset_subject() -> String { "Password reset".to_string() }
+fn default_reset_per_ip_limit() -> RateLimit { RateLimit { burst: 10, per_minute: 2 } }
+fn default_reset_per_account_limit() -> RateLimit { RateLimit { burst: 3, per_minute: 1 } }
+
+impl Default for PasswordResetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token_ttl_secs: default_reset_token_ttl_secs(),
+            reset_url: None,
+            subject: default_reset_subject(),
+            per_ip: default_reset_per_ip_limit(),
+            per_account: default_reset_per_account_limit(),
+        }
+    }
+}
+
+/// Приглашения новых пользователей (`POST /api/users/invite`); нужна секция `mail`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct InvitationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Сколько действует ссылка
+    #[serde(default = "default_invitation_ttl_secs", deserialize_with = "duration::secs")]
+    pub token_ttl_secs: u64,
+    /// Страница принятия приглашения; `{token}` заменяется токеном, например
+    /// `https://portal.corp.acme.com/welcome?token={token}`
+    pub accept_url: Option<String>,
+    #[serde(default = "default_invitation_subject")]
+    pub subject: String,
+}
+
+fn default_invitation_ttl_secs() -> u64 { 7 * 24 * 3600 }
+fn default_invitation_subject() -> String { "Your new account".to_string() }
+
+impl Default for InvitationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token_ttl_secs: default_invitation_ttl_secs(),
+            accept_url: None,
+            subject: default_invitation_subject(),
+        }
+    }
+}
+
+/// Почта для писем пользователям
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MailConfig {
+    /// Сервер отправки; без него письма не отправляются
+    pub smtp: Option<SmtpConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SmtpConfig {
+    pub host: String,
+    /// По умолчанию 587 для STARTTLS, 465 для TLS, 25 без шифрования
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    /// Лучше через `${ENV_VAR}`
+    pub password: Option<String>,
+    /// Адрес отправителя: `nextDomen <noreply@corp.acme.com>`
+    pub from: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls,
+    Tls,
+    /// Без шифрования: только локальный релей
+    None,
+}
+
+/// Предупреждения об истечении паролей, которые рассылает процесс `web`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PasswordExpiryConfig {
+    /// За сколько дней до истечения пароля отправить событие `password_expiry_warning`; 0 — не предупреждать
+    #[serde(default = "default_expiry_warning_days", deserialize_with = "duration::days")]
+    pub warning_days: u32,
+    /// Как часто проверять сроки паролей
+    #[serde(default = "default_expiry_check_secs", deserialize_with = "duration::secs")]
+    pub check_secs: u64,
+}
+
+fn default_expiry_warning_days() -> u32 { 14 }
+fn default_expiry_check_secs() -> u64 { 3600 }
+
+impl Default for PasswordExpiryConfig {
+    fn default() -> Self {
+        Self { warning_days: default_expiry_warning_days(), check_secs: default_expiry_check_secs() }
+    }
+}
+
+/// Смена паролей учётных записей служб процессом `web`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceAccountConfig {
+    /// Как часто проверять, у каких учётных записей подошёл срок смены пароля
+    #[serde(default = "default_rotation_check_secs", deserialize_with = "duration::secs")]
+    pub rotation_check_secs: u64,
+}
+
+fn default_rotation_check_secs() -> u64 { 3600 }
+
+impl Default for ServiceAccountConfig {
+    fn default() -> Self {
+        Self { rotation_check_secs: default_rotation_check_secs() }
+    }
+}
+
+/// Ограничение частоты попыток входа (REST, OIDC, RADIUS)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    #[serde(default = "default_rate_limit_enabled")]
+    pub enabled: bool,
+    /// Попытки с одного адреса
+    #[serde(default = "default_per_ip_limit")]
+    pub per_ip: RateLimit,
+    /// Попытки для одной учётной записи, с любых адресов
+    #[serde(default = "default_per_account_limit")]
+    pub per_account: RateLimit,
+    /// Адреса без ограничения по адресу: NAS RADIUS, обратные прокси
+    #[serde(default)]
+    pub exempt_ips: Vec<String>,
+    /// Блокировать учётную запись, когда исчерпан её лимит
+    #[serde(default = "default_rate_limit_lock_account")]
+    pub lock_account: bool,
+}
+
+/// Корзина токенов: `burst` попыток сразу, затем `per_minute` в минуту
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+fn default_rate_limit_enabled() -> bool { true }
+fn default_per_ip_limit() -> RateLimit { RateLimit { burst: 20, per_minute: 10 } }
+fn default_per_account_limit() -> RateLimit { RateLimit { burst: 10, per_minute: 5 } }
+fn default_rate_limit_lock_account() -> bool { true }
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_rate_limit_enabled(),
+            per_ip: default_per_ip_limit(),
+            per_account: default_per_account_limit(),
+            exempt_ips: Vec::new(),
+            lock_account: default_rate_limit_lock_account(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct JwtConfig {
     #[serde(default = "default_jwt_algorithm")]
     pub algorithm: String,
     pub secret_key: Option<String>,
     pub private_key_path: Option<String>,
     pub public_key_path: Option<String>,
+    /// Время жизни токенов входа (REST и gRPC): `30m`, `24h`, `7d`
     #[serde(default = "default_token_expiry")]
-    pub token_expiry: String,
+    pub token_expiry: ConfigDuration,
 }
 
 fn default_jwt_algorithm() -> String {
     "RS256".to_string()
 }
 
-fn default_token_expiry() -> String {
-    "24h".to_string()
+fn default_token_expiry() -> ConfigDuration {
+    ConfigDuration::from_secs(24 * 3600)
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
-pub struct PasswordPolicy {
-    #[serde(default = "default_min_length")]
-    pub min_length: u8,
-    #[serde(default = "default_require_uppercase")]
-    pub require_uppercase: bool,
-    #[serde(default = "default_require_lowercase")]
-    pub require_lowercase: bool,
-    #[serde(default = "default_require_digits")]
-    pub require_digits: bool,
-    #[serde(default = "default_require_special_chars")]
-    pub require_special_chars: bool,
-    #[serde(default = "default_max_age_days")]
-    pub max_age_days: u32,
-    #[serde(default = "default_history_count")]
-    pub history_count: u8,
-}
-
-fn default_min_length() -> u8 { 8 }
-fn default_require_uppercase() -> bool { true }
-fn default_require_lowercase() -> bool { true }
-fn default_require_digits() -> bool { true }
-fn default_require_special_chars() -> bool { false }
-fn default_max_age_days() -> u32 { 90 }
-fn default_history_count() -> u8 { 5 }
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: default_jwt_algorithm(),
+            secret_key: None,
+            private_key_path: None,
+            public_key_path: None,
+            token_expiry: default_token_expiry(),
+        }
+    }
+}
 
 #[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct TlsConfig {
     pub cert_file: Option<String>,
     pub key_file: Option<String>,
+    /// CA клиентских сертификатов (mTLS); без него сертификаты клиентов не запрашиваются
     pub ca_cert_file: Option<String>,
     #[serde(default)]
     pub client_auth_required: bool,
+    /// SAN клиентского сертификата → имя учётной записи (`spiffe://corp/billing: svc-billing`);
+    /// DNS-имя без записи здесь ищется по SPN `HOST/<имя>`
+    #[serde(default)]
+    pub san_accounts: HashMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+impl TlsConfig {
+    /// Сертификат и ключ сервера `server`; не заданные в конфигурации — в `certs_dir`
+    /// (`web_server.pem`, `web_server-key.pem`), где их создаёт первый запуск
+    pub fn files(&self, server: &str, certs_dir: &Path) -> (PathBuf, PathBuf) {
+        let cert = self.cert_file.as_ref().map(PathBuf::from).unwrap_or_else(|| certs_dir.join(format!("{}.pem", server)));
+        let key = self.key_file.as_ref().map(PathBuf::from).unwrap_or_else(|| certs_dir.join(format!("{}-key.pem", server)));
+        (cert, key)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct LoggingConfig {
     #[serde(default = "default_log_level")]
     pub level: String,
+    /// Уровни отдельных модулей: `nextDomen::kerberos: DEBUG`, `ldap3: WARN`
+    #[serde(default)]
+    pub modules: HashMap<String, String>,
+    /// Каждая запись — объект JSON в строке
     #[serde(default)]
     pub enable_json_output: bool,
+    /// Файл журнала (дописывается) вместо stdout
     pub log_file: Option<String>,
+    /// Экспорт спанов OpenTelemetry по OTLP
     #[serde(default)]
     pub enable_tracing: bool,
+    /// Коллектор OTLP (gRPC)
+    #[serde(default = "default_otlp_endpoint")]
+    pub otlp_endpoint: String,
+    /// Доля трассируемых запросов без traceparent от вызывающей стороны: 0.0–1.0
+    #[serde(default = "default_trace_sampling_ratio")]
+    pub trace_sampling_ratio: f64,
+    /// `service.name` в ресурсе OpenTelemetry
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
 }
 
 fn default_log_level() -> String {
     "INFO".to_string()
 }
 
+fn default_otlp_endpoint() -> String {
+    "http://localhost:4317".to_string()
+}
+
+fn default_trace_sampling_ratio() -> f64 {
+    1.0
+}
+
+fn default_service_name() -> String {
+    "nextdomen".to_string()
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_log_level(),
+            modules: HashMap::new(),
+            enable_json_output: false,
+            log_file: None,
+            enable_tracing: false,
+            otlp_endpoint: default_otlp_endpoint(),
+            trace_sampling_ratio: default_trace_sampling_ratio(),
+            service_name: default_service_name(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct PathsConfig {
     pub keys_dir: Option<String>,
+    /// Сертификаты TLS, созданные при первом запуске; по умолчанию `certs`
     pub certs_dir: Option<String>,
     pub temp_dir: Option<String>,
 }
 
+impl PathsConfig {
+    pub fn certs_dir(&self) -> PathBuf {
+        PathBuf::from(self.certs_dir.as_deref().unwrap_or("certs"))
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct MetricsConfig {
     pub enabled: bool,
     pub prometheus_endpoint: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct AuditConfig {
+    /// FILE (JSON lines), SYSLOG, KAFKA или NONE
     #[serde(default = "default_audit_backend")]
     pub backend: String,
+    /// Файл для FILE; по умолчанию `audit.jsonl`
     pub file_path: Option<String>,
     pub database_url: Option<String>,
     pub kafka: Option<KafkaConfig>,
+    pub syslog: Option<SyslogConfig>,
+    #[serde(default)]
+    pub chain: AuditChainConfig,
+}
+
+/// Цепочка хэшей событий аудита в базе каталога
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AuditChainConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Через сколько событий подписывать якорь ключом сервера (JWT_PRIVATE_KEY_PATH)
+    #[serde(default = "default_anchor_every")]
+    pub anchor_every: u64,
+}
+
+fn default_anchor_every() -> u64 {
+    100
+}
+
+impl Default for AuditChainConfig {
+    fn default() -> Self {
+        Self { enabled: false, anchor_every: default_anchor_every() }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SyslogConfig {
+    /// `host:port` для UDP (RFC 5424); по умолчанию локальный сокет `/dev/log`
+    pub address: Option<String>,
+    /// Facility; по умолчанию 13 (log audit)
+    pub facility: Option<u8>,
 }
 
 fn default_audit_backend() -> String {
     "FILE".to_string()
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_audit_backend(),
+            file_path: None,
+            database_url: None,
+            kafka: None,
+            syslog: None,
+            chain: AuditChainConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct KafkaConfig {
     pub brokers: Vec<String>,
     pub topic: String,
     pub client_id: Option<String>,
 }
 
+/// Префикс переменных окружения: `NEXTDOMEN_DB_PATH`, `NEXTDOMEN_WEB_SERVER__CORS__ALLOWED_ORIGINS`
+const ENV_PREFIX: &str = "NEXTDOMEN_";
+/// Разделитель уровней вложенности в имени переменной (одиночный `_` — часть имени поля)
+const ENV_SEPARATOR: &str = "__";
+/// Путь к файлу конфигурации; сам не является полем
+pub const CONFIG_ENV: &str = "NEXTDOMEN_CONFIG";
+/// Токен администратора для `cli --server`; тоже не поле
+pub const TOKEN_ENV: &str = "NEXTDOMEN_TOKEN";
+/// Файл по умолчанию; если его нет, конфигурация собирается только из окружения
+pub const DEFAULT_CONFIG_PATH: &str = "config.yaml";
+
+/// Метка значения, которое подставляется в текст YAML как есть
+const ENV_PLAIN_MARKER: &str = "__nextdomen_env_plain_";
+
+/// Короткие имена для часто задаваемых полей
+const ENV_ALIASES: &[(&str, &str)] = &[("NEXTDOMEN_MASTER_KEY", "master_key_hex")];
+
 impl AppConfig {
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
         let content = fs::read_to_string(path)?;
-        let config: Self = serde_yaml::from_str(&content)?;
-        Ok(config)
+        let mut value = serde_yaml::from_str(&content)?;
+        secrets::interpolate(&mut value, &|name| std::env::var(name).ok())?;
+        Ok(serde_yaml::from_value(value)?)
+    }
+
+    /// Файл (`--config`, иначе `NEXTDOMEN_CONFIG`, иначе `config.yaml`, если он есть)
+    /// и поверх него переменные `NEXTDOMEN_*`. Явно указанный файл обязан существовать
+    pub fn load_with_env(path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let explicit = path.map(Path::to_path_buf)
+            .or_else(|| std::env::var_os(CONFIG_ENV).map(Into::into));
+        let base = match explicit {
+            Some(path) => {
+                let content = fs::read_to_string(&path)
+                    .map_err(|e| format!("Cannot read config {}: {}", path.display(), e))?;
+                serde_yaml::from_str(&content)?
+            }
+            None => match fs::read_to_string(DEFAULT_CONFIG_PATH) {
+                Ok(content) => serde_yaml::from_str(&content)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_yaml::Value::Mapping(Default::default()),
+                Err(e) => return Err(format!("Cannot read config {}: {}", DEFAULT_CONFIG_PATH, e).into()),
+            },
+        };
+        Self::from_yaml_with_env(base, std::env::vars())
+    }
+
+    /// Подставить `${ENV_VAR}` в строковые значения YAML и наложить переменные окружения.
+    /// Тип значения переменной `NEXTDOMEN_*` берётся из поля конфигурации (со значениями
+    /// по умолчанию): `8080` для строкового поля остаётся строкой, списки задаются через запятую
+    pub fn from_yaml_with_env(
+        mut base: serde_yaml::Value,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let vars: Vec<(String, String)> = vars.into_iter().collect();
+        secrets::interpolate(&mut base, &|name| {
+            vars.iter().find(|(var, _)| var == name).map(|(_, value)| value.clone())
+        })?;
+        let overrides: Vec<(Vec<String>, String)> = vars.into_iter()
+            .filter(|(name, _)| name != CONFIG_ENV && name != TOKEN_ENV)
+            .filter_map(|(name, value)| {
+                let path = match ENV_ALIASES.iter().find(|(alias, _)| *alias == name) {
+                    Some((_, field)) => vec![field.to_string()],
+                    None => name.strip_prefix(ENV_PREFIX)?
+                        .split(ENV_SEPARATOR)
+                        .map(str::to_lowercase)
+                        .collect(),
+                };
+                Some((path, value))
+            })
+            .collect();
+        if overrides.is_empty() {
+            return Ok(serde_yaml::from_value(base)?);
+        }
+        if let Some((path, _)) = overrides.iter().find(|(path, _)| path.iter().any(String::is_empty)) {
+            return Err(format!("Invalid environment override: {}{}", ENV_PREFIX, path.join(ENV_SEPARATOR).to_uppercase()).into());
+        }
+
+        // Обязательные строковые поля могут прийти только из окружения — без них не собрать образец типов
+        for (path, value) in &overrides {
+            if let [field] = path.as_slice()
+                && (field == "db_path" || field == "master_key_hex")
+                && let serde_yaml::Value::Mapping(map) = &mut base
+            {
+                map.insert(field.as_str().into(), value.as_str().into());
+            }
+        }
+
+        // Образец: все поля с типами и значениями по умолчанию
+        let mut typed = serde_yaml::to_value(serde_yaml::from_value::<Self>(base)?)?;
+        let mut plain = Vec::new();
+        for (path, value) in overrides {
+            set_env_value(&mut typed, &path, &value, &mut plain);
+        }
+        if plain.is_empty() {
+            return Ok(serde_yaml::from_value(typed)?);
+        }
+
+        // Простой скаляр в тексте YAML читается и как строка, и как число — в зависимости от поля
+        let mut text = serde_yaml::to_string(&typed)?;
+        for (index, raw) in plain.iter().enumerate().rev() {
+            text = text.replace(&format!("{}{}", ENV_PLAIN_MARKER, index), raw);
+        }
+        Ok(serde_yaml::from_str(&text)?)
     }
 
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
@@ -187,4 +1187,44 @@ impl AppConfig {
         fs::write(path, content)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+/// Записать значение по пути, создавая промежуточные секции
+fn set_env_value(node: &mut serde_yaml::Value, path: &[String], raw: &str, plain: &mut Vec<String>) {
+    use serde_yaml::Value;
+
+    let Some((key, rest)) = path.split_first() else {
+        *node = coerce_env_value(node, raw, plain);
+        return;
+    };
+    if !node.is_mapping() {
+        *node = Value::Mapping(Default::default());
+    }
+    if let Value::Mapping(map) = node {
+        let child = map.entry(key.as_str().into()).or_insert(Value::Null);
+        set_env_value(child, rest, raw, plain);
+    }
+}
+
+/// Значение переменной в типе поля, которое она заменяет. Тип незаданного (`None`) поля
+/// неизвестен: число или флаг для него остаётся простым скаляром YAML (см. `ENV_PLAIN_MARKER`)
+fn coerce_env_value(current: &serde_yaml::Value, raw: &str, plain: &mut Vec<String>) -> serde_yaml::Value {
+    use serde_yaml::Value;
+
+    match current {
+        Value::String(_) => Value::String(raw.to_string()),
+        Value::Sequence(items) => Value::Sequence(raw
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| coerce_env_value(items.first().unwrap_or(&Value::Null), item, plain))
+            .collect()),
+        Value::Null => match serde_yaml::from_str::<Value>(raw) {
+            Ok(Value::Number(_) | Value::Bool(_)) => {
+                plain.push(raw.trim().to_string());
+                Value::String(format!("{}{}", ENV_PLAIN_MARKER, plain.len() - 1))
+            }
+            _ => Value::String(raw.to_string()),
+        },
+        _ => serde_yaml::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+    }
+}
@@ -0,0 +1,91 @@
+// src/config/address.rs
+
+//! Адреса, на которых слушает сервер: `address: 0.0.0.0:8080`, список
+//! `address: ["0.0.0.0:8080", "[::]:8080"]` или строка через запятую (удобно в переменной
+//! окружения `NEXTDOMEN_WEB_SERVER__ADDRESS=0.0.0.0:8080,[::]:8080`). Каждый адрес — отдельный сокет
+
+use std::fmt;
+
+use serde::ser::SerializeSeq;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Адреса одного сервера; пустой список — адрес не задан
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListenAddresses(Vec<String>);
+
+impl ListenAddresses {
+    pub fn new(addresses: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self(addresses.into_iter().map(Into::into).collect())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    pub fn to_vec(&self) -> Vec<String> {
+        self.0.clone()
+    }
+}
+
+impl fmt::Display for ListenAddresses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.join(","))
+    }
+}
+
+impl Serialize for ListenAddresses {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let [address] = self.0.as_slice() {
+            return serializer.serialize_str(address);
+        }
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for address in &self.0 {
+            seq.serialize_element(address)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ListenAddresses {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AddressesVisitor)
+    }
+}
+
+struct AddressesVisitor;
+
+impl<'de> de::Visitor<'de> for AddressesVisitor {
+    type Value = ListenAddresses;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an address like 0.0.0.0:8080 or a list of addresses")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        let addresses: Vec<&str> = value.split(',').map(str::trim).collect();
+        if addresses.iter().any(|address| address.is_empty()) {
+            return Err(E::custom(format!("invalid address list '{}': empty address", value)));
+        }
+        Ok(ListenAddresses::new(addresses))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut addresses = Vec::new();
+        while let Some(address) = seq.next_element::<String>()? {
+            addresses.push(address);
+        }
+        Ok(ListenAddresses(addresses))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(ListenAddresses::default())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(ListenAddresses::default())
+    }
+}
@@ -0,0 +1,177 @@
+// src/config/duration.rs
+
+//! Длительности в конфигурации: `30s`, `10m`, `24h`, `7d` и составные `1h30m`. Поле типа
+//! `ConfigDuration` хранит длительность со строкой единиц; числовые поля с единицей в имени
+//! (`*_secs`, `*_minutes`, `*_days`) через `deserialize_with` принимают и число в этой единице,
+//! и строку с единицами: `token_lifetime_secs: 1h`. Ошибка — при загрузке конфигурации.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Единицы по убыванию: суффикс и число секунд
+const UNITS: &[(&str, u64)] = &[("d", 86_400), ("h", 3600), ("m", 60), ("s", 1)];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DurationError(String);
+
+impl fmt::Display for DurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for DurationError {}
+
+/// Разобрать `30s`, `10m`, `24h`, `7d`, `1h30m`; число без единицы — секунды
+pub fn parse(text: &str) -> Result<Duration, DurationError> {
+    let invalid = || DurationError(format!("invalid duration '{}': expected a number with unit s, m, h or d, like 30m or 1h30m", text));
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(secs) = text.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut secs: u64 = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let value: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let unit = rest[..unit_len].trim();
+        rest = &rest[unit_len..];
+        let (_, scale) = UNITS.iter().find(|(suffix, _)| *suffix == unit).ok_or_else(invalid)?;
+        secs = value.checked_mul(*scale).and_then(|part| secs.checked_add(part)).ok_or_else(invalid)?;
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Запись длительности крупнейшими целыми единицами: `1h30m`, `7d`, `0s`
+pub fn format(duration: Duration) -> String {
+    let mut secs = duration.as_secs();
+    if secs == 0 {
+        return "0s".to_string();
+    }
+    let mut text = String::new();
+    for (suffix, scale) in UNITS {
+        if secs >= *scale {
+            text.push_str(&format!("{}{}", secs / scale, suffix));
+            secs %= scale;
+        }
+    }
+    text
+}
+
+/// Длительность из конфигурации: в YAML — строка с единицами или число секунд
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct ConfigDuration(pub Duration);
+
+impl ConfigDuration {
+    pub fn from_secs(secs: u64) -> Self {
+        Self(Duration::from_secs(secs))
+    }
+
+    pub fn as_duration(&self) -> Duration {
+        self.0
+    }
+
+    pub fn as_secs(&self) -> u64 {
+        self.0.as_secs()
+    }
+}
+
+impl FromStr for ConfigDuration {
+    type Err = DurationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse(text).map(Self)
+    }
+}
+
+impl fmt::Display for ConfigDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format(self.0))
+    }
+}
+
+impl Serialize for ConfigDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_u64(self.as_secs());
+        }
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            return u64::deserialize(deserializer).map(Self::from_secs);
+        }
+        deserializer.deserialize_any(Scaled(1)).map(Self::from_secs)
+    }
+}
+
+/// Длительность в единицах `Scaled.0` секунд: число — уже в этих единицах, строка с единицами
+/// должна делиться на единицу поля нацело
+struct Scaled(u64);
+
+impl de::Visitor<'_> for Scaled {
+    type Value = u64;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a duration like 30m, 24h or 7d")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<u64, E> {
+        Ok(value)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<u64, E> {
+        u64::try_from(value).map_err(|_| E::custom(format!("invalid duration {}: must not be negative", value)))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+        // Число без единицы — в единицах поля, как и в числовом виде
+        if let Ok(value) = value.trim().parse::<u64>() {
+            return Ok(value);
+        }
+        let secs = parse(value).map_err(E::custom)?.as_secs();
+        if secs % self.0 != 0 {
+            return Err(E::custom(format!("invalid duration '{}': must be a whole number of {}", value, format(Duration::from_secs(self.0)))));
+        }
+        Ok(secs / self.0)
+    }
+}
+
+/// Для полей `*_secs`: `#[serde(deserialize_with = "duration::secs")]`. Двоичные форматы
+/// (bincode в базе) хранят только число
+pub fn secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    if !deserializer.is_human_readable() {
+        return u64::deserialize(deserializer);
+    }
+    deserializer.deserialize_any(Scaled(1))
+}
+
+/// Для полей `*_minutes`
+pub fn minutes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    scaled_u32(deserializer, 60)
+}
+
+/// Для полей `*_days`
+pub fn days<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    scaled_u32(deserializer, 86_400)
+}
+
+fn scaled_u32<'de, D: Deserializer<'de>>(deserializer: D, scale: u64) -> Result<u32, D::Error> {
+    if !deserializer.is_human_readable() {
+        return u32::deserialize(deserializer);
+    }
+    let value = deserializer.deserialize_any(Scaled(scale))?;
+    u32::try_from(value).map_err(|_| de::Error::custom(format!("invalid duration {}: too large", value)))
+}
@@ -0,0 +1,222 @@
+// src/config/secrets.rs
+
+//! Секреты вне YAML: подстановка `${ENV_VAR}` в значения, мастер-ключ из файла (`master_key_file`)
+//! или из хранилища секретов (`master_key_secret` + секция `secrets`). Ключ из хранилища
+//! читается при запуске и не записывается на диск
+
+use std::fmt;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use super::AppConfig;
+
+/// Переменная с токеном Vault, если `secrets.vault.token` не задан
+pub const VAULT_TOKEN_ENV: &str = "VAULT_TOKEN";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretError {
+    /// Источник ключа не задан или задано несколько
+    NotConfigured(String),
+    /// Хранилище недоступно или отказало в доступе
+    Unavailable(String),
+    NotFound(String),
+    /// Значение есть, но это не ключ
+    Invalid(String),
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretError::NotConfigured(msg) => write!(f, "Secret is not configured: {}", msg),
+            SecretError::Unavailable(msg) => write!(f, "Secret store is unavailable: {}", msg),
+            SecretError::NotFound(reference) => write!(f, "Secret not found: {}", reference),
+            SecretError::Invalid(msg) => write!(f, "Invalid secret: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+/// Хранилище секретов (Vault, KMS, ...): значение по ссылке вида `путь#поле`
+pub trait SecretProvider: Send + Sync {
+    fn fetch<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String, SecretError>>;
+}
+
+/// Хранилища секретов
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SecretsConfig {
+    pub vault: Option<VaultConfig>,
+}
+
+/// HashiCorp Vault, движок KV версии 2
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct VaultConfig {
+    /// `https://vault.corp.example.com:8200`
+    pub address: String,
+    /// По умолчанию — переменная `VAULT_TOKEN`
+    pub token: Option<String>,
+    /// Точка монтирования KV v2
+    #[serde(default = "default_vault_mount")]
+    pub mount: String,
+    /// Пространство имён Vault Enterprise
+    pub namespace: Option<String>,
+}
+
+fn default_vault_mount() -> String {
+    "secret".to_string()
+}
+
+impl SecretsConfig {
+    /// Настроенное хранилище; `None` — секция пуста
+    pub fn provider(&self) -> Result<Option<Box<dyn SecretProvider>>, SecretError> {
+        match &self.vault {
+            Some(vault) => Ok(Some(Box::new(VaultProvider::new(vault)?))),
+            None => Ok(None),
+        }
+    }
+}
+
+pub struct VaultProvider {
+    client: reqwest::Client,
+    address: String,
+    token: String,
+    mount: String,
+    namespace: Option<String>,
+}
+
+impl VaultProvider {
+    pub fn new(config: &VaultConfig) -> Result<Self, SecretError> {
+        let token = config.token.clone()
+            .or_else(|| std::env::var(VAULT_TOKEN_ENV).ok())
+            .ok_or_else(|| SecretError::NotConfigured(format!("secrets.vault.token or {} is required", VAULT_TOKEN_ENV)))?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| SecretError::Unavailable(e.to_string()))?;
+        Ok(Self {
+            client,
+            address: config.address.trim_end_matches('/').to_string(),
+            token,
+            mount: config.mount.trim_matches('/').to_string(),
+            namespace: config.namespace.clone(),
+        })
+    }
+}
+
+impl SecretProvider for VaultProvider {
+    /// `nextdomen/prod#master_key` — поле `master_key` секрета `nextdomen/prod`
+    fn fetch<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String, SecretError>> {
+        Box::pin(async move {
+            let (path, field) = reference.split_once('#')
+                .ok_or_else(|| SecretError::NotConfigured(format!("'{}': expected path#field", reference)))?;
+            let url = format!("{}/v1/{}/data/{}", self.address, self.mount, path.trim_matches('/'));
+            let mut request = self.client.get(&url).header("X-Vault-Token", &self.token);
+            if let Some(namespace) = &self.namespace {
+                request = request.header("X-Vault-Namespace", namespace);
+            }
+            let response = request.send().await.map_err(|e| SecretError::Unavailable(e.to_string()))?;
+            match response.status() {
+                reqwest::StatusCode::NOT_FOUND => return Err(SecretError::NotFound(reference.to_string())),
+                status if !status.is_success() => return Err(SecretError::Unavailable(format!("{} returned {}", url, status))),
+                _ => {}
+            }
+            let body: serde_json::Value = response.json().await.map_err(|e| SecretError::Unavailable(e.to_string()))?;
+            body["data"]["data"][field].as_str()
+                .map(str::to_string)
+                .ok_or_else(|| SecretError::NotFound(reference.to_string()))
+        })
+    }
+}
+
+impl AppConfig {
+    /// Мастер-ключ базы из единственного заданного источника: `master_key_hex`, `master_key_file`
+    /// или `master_key_secret` в хранилище из секции `secrets`
+    pub async fn master_key(&self) -> Result<[u8; 32], SecretError> {
+        let provider = match &self.master_key_secret {
+            Some(_) => self.secrets.provider()?,
+            None => None,
+        };
+        self.master_key_with(provider.as_deref()).await
+    }
+
+    /// То же с явно переданным хранилищем (например KMS, которого нет в `secrets`)
+    pub async fn master_key_with(&self, provider: Option<&dyn SecretProvider>) -> Result<[u8; 32], SecretError> {
+        let hex_key = match (&self.master_key_hex, &self.master_key_file, &self.master_key_secret) {
+            (Some(hex_key), None, None) => hex_key.clone(),
+            (None, Some(path), None) => std::fs::read_to_string(path)
+                .map_err(|e| SecretError::NotFound(format!("{}: {}", path, e)))?,
+            (None, None, Some(reference)) => {
+                let provider = provider
+                    .ok_or_else(|| SecretError::NotConfigured("master_key_secret needs a secret store in the secrets section".into()))?;
+                provider.fetch(reference).await?
+            }
+            (None, None, None) => {
+                return Err(SecretError::NotConfigured("set one of master_key_hex, master_key_file or master_key_secret".into()));
+            }
+            _ => return Err(SecretError::NotConfigured("only one of master_key_hex, master_key_file or master_key_secret may be set".into())),
+        };
+        decode_master_key(&hex_key)
+    }
+}
+
+/// 32 байта в hex; пробелы и перевод строки в конце файла не мешают
+pub fn decode_master_key(hex_key: &str) -> Result<[u8; 32], SecretError> {
+    let mut key = [0u8; 32];
+    hex::decode_to_slice(hex_key.trim(), &mut key)
+        .map_err(|e| SecretError::Invalid(format!("master key must be 64 hex characters (32 bytes): {}", e)))?;
+    Ok(key)
+}
+
+/// Подставить переменные окружения в строковые значения: `${NAME}`, `${NAME:-по умолчанию}`;
+/// `$${` — сам текст `${`
+pub fn interpolate(value: &mut serde_yaml::Value, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), String> {
+    use serde_yaml::Value;
+
+    match value {
+        Value::String(text) if text.contains("${") => *text = interpolate_str(text, lookup)?,
+        Value::Sequence(items) => {
+            for item in items {
+                interpolate(item, lookup)?;
+            }
+        }
+        Value::Mapping(map) => {
+            for (_, item) in map.iter_mut() {
+                interpolate(item, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn interpolate_str(text: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            result.push_str(&rest[..start - 1]);
+            result.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find('}')
+            .ok_or_else(|| format!("Unterminated ${{...}} in configuration value '{}'", text))?;
+        let expression = &rest[start + 2..start + end];
+        let (name, default) = match expression.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expression, None),
+        };
+        let value = lookup(name)
+            .or_else(|| default.map(str::to_string))
+            .ok_or_else(|| format!("Environment variable {} is not set (referenced as ${{{}}} in the configuration)", name, expression))?;
+        result.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
@@ -0,0 +1,386 @@
+// src/config/validate.rs
+
+//! Проверка конфигурации до открытия базы и сокетов: длина ключей, файлы TLS, пара ключей JWT,
+//! совпадающие адреса серверов. Неизвестные поля отклоняет уже разбор (`deny_unknown_fields`).
+//! Та же проверка — `nextDomen config validate` и запуск серверов
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::Path;
+
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::traits::PublicKeyParts;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use super::secrets::decode_master_key;
+use super::{AppConfig, AuthProviderConfig, TlsConfig, DEFAULT_WEB_ADDRESS};
+use crate::mail::{SmtpSender, TOKEN_PLACEHOLDER};
+use crate::proxy::TrustedProxies;
+use crate::web::listener::UNIX_PREFIX;
+
+/// Не меньше бит в ключе RSA для подписи токенов
+const MIN_RSA_BITS: usize = 2048;
+/// Не короче байт общий секрет HS256 (`security.jwt.secret_key`)
+const MIN_JWT_SECRET_LEN: usize = 32;
+
+/// Проблема в конфигурации: поле и что с ним не так
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Сокет, который откроет процесс: имя для сообщения, адрес и UDP или TCP
+#[derive(Debug, Clone)]
+pub struct Listener {
+    pub name: String,
+    pub address: String,
+    pub udp: bool,
+}
+
+impl Listener {
+    pub fn tcp(name: &str, address: &str) -> Self {
+        Self { name: name.to_string(), address: address.to_string(), udp: false }
+    }
+
+    pub fn udp(name: &str, address: &str) -> Self {
+        Self { name: name.to_string(), address: address.to_string(), udp: true }
+    }
+}
+
+impl AppConfig {
+    /// Адреса REST API: `--addr` (можно несколько), иначе `web_server.address`, иначе 127.0.0.1:8080
+    pub fn web_addresses(&self, addr: &[String]) -> Vec<String> {
+        match (addr.is_empty(), self.web_server.address.is_empty()) {
+            (false, _) => addr.to_vec(),
+            (true, false) => self.web_server.address.to_vec(),
+            (true, true) => vec![DEFAULT_WEB_ADDRESS.to_string()],
+        }
+    }
+
+    /// Сокеты REST API для проверки адресов
+    pub fn web_listeners(&self, addr: &[String]) -> Vec<Listener> {
+        let name = if addr.is_empty() { "web_server.address" } else { "web --addr" };
+        self.web_addresses(addr).iter().map(|address| Listener::tcp(name, address)).collect()
+    }
+
+    /// Все проблемы конфигурации; `listeners` — адреса из командной строки (`web --addr`, ...)
+    pub fn validate(&self, listeners: &[Listener]) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        check_master_key(self, &mut issues);
+        check_jwt(self, &mut issues);
+        check_mail(self, &mut issues);
+        check_auth_providers(self, &mut issues);
+        check_ca(self, &mut issues);
+        check_proxies(self, &mut issues);
+
+        let certs_dir = self.paths.certs_dir();
+        for (field, server) in [("web_server", &self.web_server), ("grpc_server", &self.grpc_server)] {
+            check_tls(field, server.enable_tls, &server.tls, Some(&certs_dir), &mut issues);
+        }
+        check_tls("ldap_server", self.ldap_server.enable_tls, &self.ldap_server.tls, None, &mut issues);
+        let radius_tls = self.radius_server.enabled && self.radius_server.tls.cert_file.is_some();
+        check_tls("radius_server", radius_tls, &self.radius_server.tls, None, &mut issues);
+
+        let mut all = listeners.to_vec();
+        for (field, addresses) in [("grpc_server.address", &self.grpc_server.address), ("ldap_server.address", &self.ldap_server.address)] {
+            all.extend(addresses.iter().map(|address| Listener::tcp(field, address)));
+        }
+        if self.radius_server.enabled {
+            let addresses = match self.radius_server.address.is_empty() {
+                true => vec!["0.0.0.0:1812".to_string()],
+                false => self.radius_server.address.to_vec(),
+            };
+            all.extend(addresses.iter().map(|address| Listener::udp("radius_server.address", address)));
+        }
+        check_listeners(&all, &mut issues);
+
+        issues
+    }
+}
+
+/// Ровно один источник мастер-ключа; ключ в hex или файле — 32 байта. Хранилище секретов
+/// не опрашивается, проверяется только его настройка
+fn check_master_key(config: &AppConfig, issues: &mut Vec<ConfigIssue>) {
+    let hint = "generate one with `nextDomen keys generate`";
+    match (&config.master_key_hex, &config.master_key_file, &config.master_key_secret) {
+        (Some(hex_key), None, None) => {
+            if let Err(e) = decode_master_key(hex_key) {
+                issues.push(ConfigIssue::new("master_key_hex", format!("{}; {}", e, hint)));
+            }
+        }
+        (None, Some(path), None) => {
+            let key = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))
+                .and_then(|hex_key| decode_master_key(&hex_key).map_err(|e| format!("{}: {}", path, e)));
+            if let Err(message) = key {
+                issues.push(ConfigIssue::new("master_key_file", message));
+            }
+        }
+        (None, None, Some(_)) => match config.secrets.provider() {
+            Ok(Some(_)) => {}
+            Ok(None) => issues.push(ConfigIssue::new("master_key_secret", "needs a secret store in the secrets section")),
+            Err(e) => issues.push(ConfigIssue::new("secrets", e.to_string())),
+        },
+        (None, None, None) => issues.push(ConfigIssue::new("master_key_hex", format!("no master key: set master_key_hex, master_key_file or master_key_secret; {}", hint))),
+        _ => issues.push(ConfigIssue::new("master_key_hex", "only one of master_key_hex, master_key_file or master_key_secret may be set")),
+    }
+}
+
+/// Доверенные прокси разбираются; PROXY protocol без них ни к чему не приведёт
+fn check_proxies(config: &AppConfig, issues: &mut Vec<ConfigIssue>) {
+    for (field, proxy) in [("web_server.proxy", &config.web_server.proxy), ("ldap_server.proxy", &config.ldap_server.proxy)] {
+        if let Err(e) = TrustedProxies::new(proxy) {
+            issues.push(ConfigIssue::new(format!("{}.trusted", field), e));
+        }
+        if proxy.proxy_protocol && proxy.trusted.is_empty() {
+            issues.push(ConfigIssue::new(format!("{}.proxy_protocol", field), "needs trusted proxies: PROXY headers are accepted only from them"));
+        }
+    }
+}
+
+/// Ключи из `security.jwt` и переменных JWT_PRIVATE_KEY_PATH / JWT_PUBLIC_KEY_PATH, которыми подписываются токены
+fn check_jwt(config: &AppConfig, issues: &mut Vec<ConfigIssue>) {
+    let jwt = &config.security.jwt;
+    if let Some(secret) = &jwt.secret_key
+        && secret.len() < MIN_JWT_SECRET_LEN
+    {
+        issues.push(ConfigIssue::new("security.jwt.secret_key", format!("must be at least {} bytes, got {}", MIN_JWT_SECRET_LEN, secret.len())));
+    }
+
+    let env = |name: &str| std::env::var(name).ok();
+    let pairs = [
+        ("security.jwt.private_key_path", "security.jwt.public_key_path", jwt.private_key_path.clone(), jwt.public_key_path.clone()),
+        ("JWT_PRIVATE_KEY_PATH", "JWT_PUBLIC_KEY_PATH", env("JWT_PRIVATE_KEY_PATH"), env("JWT_PUBLIC_KEY_PATH")),
+    ];
+    for (private_field, public_field, private_path, public_path) in pairs {
+        match (private_path, public_path) {
+            (Some(private_path), Some(public_path)) => {
+                check_jwt_pair(private_field, &private_path, public_field, &public_path, issues);
+            }
+            (Some(_), None) => issues.push(ConfigIssue::new(public_field, format!("is required together with {}", private_field))),
+            (None, Some(_)) => issues.push(ConfigIssue::new(private_field, format!("is required together with {}", public_field))),
+            (None, None) => {}
+        }
+    }
+}
+
+/// Отправитель писем собирается без подключения к серверу; сброс пароля и приглашения без него не работают
+fn check_mail(config: &AppConfig, issues: &mut Vec<ConfigIssue>) {
+    if let Some(smtp) = &config.mail.smtp
+        && let Err(e) = SmtpSender::new(smtp)
+    {
+        issues.push(ConfigIssue::new("mail.smtp", e.to_string()));
+    }
+
+    let reset = &config.security.password_reset;
+    let invitations = &config.security.invitations;
+    let links = [
+        ("security.password_reset", "reset_url", reset.enabled, &reset.reset_url),
+        ("security.invitations", "accept_url", invitations.enabled, &invitations.accept_url),
+    ];
+    for (section, url_field, enabled, url) in links {
+        if !enabled {
+            continue;
+        }
+        if config.mail.smtp.is_none() {
+            issues.push(ConfigIssue::new(format!("{}.enabled", section), "requires mail.smtp"));
+        }
+        let field = format!("{}.{}", section, url_field);
+        match url {
+            Some(url) if url.contains(TOKEN_PLACEHOLDER) => {}
+            Some(_) => issues.push(ConfigIssue::new(field, format!("must contain {}", TOKEN_PLACEHOLDER))),
+            None => issues.push(ConfigIssue::new(field, "is required when the section is enabled")),
+        }
+    }
+}
+
+/// Адрес провайдера со схемой его протокола; имя пользователя у провайдера — из `{username}` или `{upn}`
+fn check_auth_providers(config: &AppConfig, issues: &mut Vec<ConfigIssue>) {
+    for (domain, provider) in &config.security.auth_providers {
+        let section = format!("security.auth_providers.{}", domain);
+        let (url_field, url, schemes, name_field, name): (_, _, &[&str], _, _) = match provider {
+            AuthProviderConfig::Local => continue,
+            AuthProviderConfig::UpstreamLdap(ldap) => ("url", &ldap.url, &["ldap://", "ldaps://"], "bind_name", &ldap.bind_name),
+            AuthProviderConfig::Oidc(oidc) => ("token_url", &oidc.token_url, &["https://", "http://"], "login", &oidc.login),
+        };
+        if !schemes.iter().any(|scheme| url.starts_with(scheme)) {
+            issues.push(ConfigIssue::new(format!("{}.{}", section, url_field), format!("must start with {}", schemes.join(" or "))));
+        }
+        if !name.contains("{username}") && !name.contains("{upn}") {
+            issues.push(ConfigIssue::new(format!("{}.{}", section, name_field), "must contain {username} or {upn}"));
+        }
+    }
+}
+
+/// Сроки CA не нулевые, выданный сертификат не переживает CA, адрес CRL — HTTP(S)
+fn check_ca(config: &AppConfig, issues: &mut Vec<ConfigIssue>) {
+    let ca = &config.ca;
+    if ca.validity_days == 0 {
+        issues.push(ConfigIssue::new("ca.validity_days", "must be greater than 0"));
+    } else if ca.validity_days > ca.ca_validity_days {
+        issues.push(ConfigIssue::new("ca.validity_days", format!("must not exceed ca.ca_validity_days ({})", ca.ca_validity_days)));
+    }
+    if ca.crl_validity_secs == 0 {
+        issues.push(ConfigIssue::new("ca.crl_validity_secs", "must be greater than 0"));
+    }
+    if let Some(url) = &ca.crl_url
+        && !url.starts_with("https://") && !url.starts_with("http://")
+    {
+        issues.push(ConfigIssue::new("ca.crl_url", "must start with https:// or http://"));
+    }
+}
+
+fn check_jwt_pair(private_field: &str, private_path: &str, public_field: &str, public_path: &str, issues: &mut Vec<ConfigIssue>) {
+    let private_key = match std::fs::read_to_string(private_path) {
+        Ok(pem) => RsaPrivateKey::from_pkcs8_pem(&pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem))
+            .map_err(|e| ConfigIssue::new(private_field, format!("{} is not an RSA private key in PEM: {}", private_path, e))),
+        Err(e) => Err(ConfigIssue::new(private_field, format!("cannot read {}: {}", private_path, e))),
+    };
+    let public_key = match std::fs::read_to_string(public_path) {
+        Ok(pem) => RsaPublicKey::from_public_key_pem(&pem)
+            .map_err(|e| ConfigIssue::new(public_field, format!("{} is not an RSA public key in PEM: {}", public_path, e))),
+        Err(e) => Err(ConfigIssue::new(public_field, format!("cannot read {}: {}", public_path, e))),
+    };
+
+    match (private_key, public_key) {
+        (Ok(private_key), Ok(public_key)) => {
+            if private_key.size() * 8 < MIN_RSA_BITS {
+                issues.push(ConfigIssue::new(private_field, format!("RSA key is {} bits, at least {} required", private_key.size() * 8, MIN_RSA_BITS)));
+            }
+            if RsaPublicKey::from(&private_key) != public_key {
+                issues.push(ConfigIssue::new(public_field, format!("{} does not match the private key {}; regenerate both with `nextDomen keys generate --jwt-dir`", public_path, private_path)));
+            }
+        }
+        (private_key, public_key) => {
+            issues.extend(private_key.err());
+            issues.extend(public_key.err());
+        }
+    }
+}
+
+/// Сертификат и ключ сервера обязательны при `enabled`; заданные файлы должны читаться и разбираться.
+/// С `certs_dir` сервер создаёт их при первом запуске: отсутствие обоих файлов — не ошибка
+fn check_tls(server: &str, enabled: bool, tls: &TlsConfig, certs_dir: Option<&Path>, issues: &mut Vec<ConfigIssue>) {
+    let field = |name: &str| format!("{}.tls.{}", server, name);
+    // Файлы, которые должны читаться; `None` — их ещё нет и их создаст первый запуск
+    let (cert_file, key_file) = match certs_dir {
+        Some(certs_dir) if enabled => {
+            let (cert, key) = tls.files(server, certs_dir);
+            match cert.exists() || key.exists() {
+                true => (Some(cert.display().to_string()), Some(key.display().to_string())),
+                false => (None, None),
+            }
+        }
+        _ => {
+            if enabled {
+                for (name, path) in [("cert_file", &tls.cert_file), ("key_file", &tls.key_file)] {
+                    if path.is_none() {
+                        issues.push(ConfigIssue::new(field(name), "is required when TLS is enabled"));
+                    }
+                }
+            }
+            (tls.cert_file.clone(), tls.key_file.clone())
+        }
+    };
+    if tls.client_auth_required && tls.ca_cert_file.is_none() {
+        issues.push(ConfigIssue::new(field("client_auth_required"), "needs ca_cert_file to verify client certificates"));
+    }
+
+    for (name, path) in [("cert_file", &cert_file), ("ca_cert_file", &tls.ca_cert_file)] {
+        if let Some(path) = path
+            && let Err(message) = read_certificates(path)
+        {
+            issues.push(ConfigIssue::new(field(name), message));
+        }
+    }
+    if let Some(path) = &key_file
+        && let Err(message) = read_private_key(path)
+    {
+        issues.push(ConfigIssue::new(field("key_file"), message));
+    }
+}
+
+fn read_certificates(path: &str) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file)).map_err(|e| format!("cannot parse {}: {}", path, e))?;
+    if certs.is_empty() {
+        return Err(format!("{} contains no PEM certificates", path));
+    }
+    for der in &certs {
+        let (_, cert) = X509Certificate::from_der(der).map_err(|e| format!("{} contains an invalid certificate: {}", path, e))?;
+        if !cert.validity().is_valid() {
+            return Err(format!("certificate {} in {} is expired or not yet valid", cert.subject(), path));
+        }
+    }
+    Ok(())
+}
+
+fn read_private_key(path: &str) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let items = rustls_pemfile::read_all(&mut BufReader::new(file)).map_err(|e| format!("cannot parse {}: {}", path, e))?;
+    let has_key = items.iter().any(|item| matches!(
+        item,
+        rustls_pemfile::Item::PKCS8Key(_) | rustls_pemfile::Item::RSAKey(_) | rustls_pemfile::Item::ECKey(_)
+    ));
+    if !has_key {
+        return Err(format!("{} contains no PEM private key", path));
+    }
+    Ok(())
+}
+
+/// Два сокета на одном порту и протоколе: адреса совпадают или один из них — все интерфейсы
+/// того же семейства. IPv6-сокет не принимает IPv4 (`IPV6_V6ONLY`), поэтому `0.0.0.0:8080`
+/// и `[::]:8080` не конфликтуют.
+/// Для Unix-сокета (`unix:/run/nextdomen.sock`) — каталог существует и путь не занят другим сервером
+fn check_listeners(listeners: &[Listener], issues: &mut Vec<ConfigIssue>) {
+    let mut unix_paths: HashMap<&str, &Listener> = HashMap::new();
+    let mut bound: HashMap<(u16, bool), Vec<(&Listener, SocketAddr)>> = HashMap::new();
+    for listener in listeners {
+        if let Some(path) = listener.address.strip_prefix(UNIX_PREFIX) {
+            let dir = std::path::Path::new(path).parent().filter(|dir| !dir.as_os_str().is_empty());
+            if dir.is_some_and(|dir| !dir.is_dir()) {
+                issues.push(ConfigIssue::new(&listener.name, format!("directory of socket {} does not exist", path)));
+            }
+            if let Some(other) = unix_paths.insert(path, listener) {
+                issues.push(ConfigIssue::new(&listener.name, format!("{} collides with {}", listener.address, other.name)));
+            }
+            continue;
+        }
+        let addr = match listener.address.to_socket_addrs().map(|mut addrs| addrs.next()) {
+            Ok(Some(addr)) => addr,
+            Ok(None) | Err(_) => {
+                issues.push(ConfigIssue::new(&listener.name, format!("'{}' is not a valid host:port address", listener.address)));
+                continue;
+            }
+        };
+        let same_port = bound.entry((addr.port(), listener.udp)).or_default();
+        if let Some((other, _)) = same_port.iter().find(|(_, other)| {
+            other.is_ipv4() == addr.is_ipv4() && (other.ip() == addr.ip() || other.ip().is_unspecified() || addr.ip().is_unspecified())
+        }) {
+            issues.push(ConfigIssue::new(
+                &listener.name,
+                format!("{} collides with {} ({}): both listen on {} port {}", listener.address, other.name, other.address, if listener.udp { "UDP" } else { "TCP" }, addr.port()),
+            ));
+        }
+        same_port.push((listener, addr));
+    }
+}
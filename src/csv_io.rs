@@ -0,0 +1,407 @@
+// src/csv_io.rs
+
+//! Массовый импорт пользователей из CSV (выгрузки отдела кадров) и экспорт
+//! пользователей и членства в группах обратно в CSV.
+
+use rand::{rngs::OsRng, seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::error_code::ErrorCode;
+use crate::models::{PasswordAlgorithm, PasswordHash, SecurityIdentifier, User, UserAccountControl};
+
+/// Длина сгенерированного пароля
+const GENERATED_PASSWORD_LEN: usize = 16;
+
+/// Классы символов сгенерированного пароля (без похожих 0/O, 1/l/I); в пароле есть каждый,
+/// чтобы он прошёл требования сложности политики
+const PASSWORD_CLASSES: [&[u8]; 4] = [b"ABCDEFGHJKLMNPQRSTUVWXYZ", b"abcdefghijkmnopqrstuvwxyz", b"23456789", b"!#%+-=?@"];
+
+/// Соответствие полей пользователя столбцам CSV; не заданные поля ищутся по собственному имени
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CsvColumnMapping {
+    pub username: Option<String>,
+    pub user_principal_name: Option<String>,
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub given_name: Option<String>,
+    pub surname: Option<String>,
+    pub password: Option<String>,
+    pub enabled: Option<String>,
+    /// DN подразделения, например `OU=Sales,DC=corp,DC=acme,DC=com`
+    pub ou: Option<String>,
+    /// sAMAccountName групп через `list_separator`
+    pub groups: Option<String>,
+
+    /// Разделитель столбцов (по умолчанию `,`)
+    pub delimiter: Option<char>,
+    /// Разделитель списка групп внутри ячейки (по умолчанию `;`)
+    pub list_separator: Option<char>,
+    /// Суффикс UPN, если столбца нет (по умолчанию DNS-имя первого домена)
+    pub upn_suffix: Option<String>,
+}
+
+impl CsvColumnMapping {
+    /// Загрузить соответствие из YAML-файла
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&content)?)
+    }
+
+    fn column<'a>(&'a self, field: &'a str) -> &'a str {
+        let mapped = match field {
+            "username" => &self.username,
+            "user_principal_name" => &self.user_principal_name,
+            "email" => &self.email,
+            "display_name" => &self.display_name,
+            "given_name" => &self.given_name,
+            "surname" => &self.surname,
+            "password" => &self.password,
+            "enabled" => &self.enabled,
+            "ou" => &self.ou,
+            "groups" => &self.groups,
+            _ => &None,
+        };
+        mapped.as_deref().unwrap_or(field)
+    }
+
+    fn delimiter(&self) -> u8 {
+        self.delimiter.map(|c| c as u8).unwrap_or(b',')
+    }
+
+    fn list_separator(&self) -> char {
+        self.list_separator.unwrap_or(';')
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvImportOptions {
+    /// Только проверить строки, ничего не записывая
+    pub dry_run: bool,
+    /// Сгенерировать пароль, если в строке его нет
+    pub generate_passwords: bool,
+    /// Обновлять существующих пользователей вместо ошибки
+    pub update_existing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvRowStatus {
+    Created,
+    Updated,
+    Failed(String),
+}
+
+/// Результат обработки одной строки
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvRowResult {
+    /// Номер строки в файле (заголовок — строка 1)
+    pub line: u64,
+    pub username: String,
+    pub status: CsvRowStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_password: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CsvImportReport {
+    pub dry_run: bool,
+    pub rows: Vec<CsvRowResult>,
+}
+
+impl CsvImportReport {
+    pub fn count(&self, status: fn(&CsvRowStatus) -> bool) -> usize {
+        self.rows.iter().filter(|r| status(&r.status)).count()
+    }
+}
+
+/// Разобранная и проверенная строка, готовая к записи
+struct UserRow {
+    username: String,
+    user_principal_name: Option<String>,
+    email: Option<String>,
+    display_name: Option<String>,
+    given_name: Option<String>,
+    surname: Option<String>,
+    password: Option<String>,
+    enabled: Option<bool>,
+    ou: Option<Uuid>,
+    groups: Vec<Uuid>,
+}
+
+/// Импортировать пользователей; ошибка строки не прерывает импорт остальных
+pub async fn import_users(
+    service: &DirectoryService,
+    input: &str,
+    mapping: &CsvColumnMapping,
+    options: CsvImportOptions,
+) -> Result<CsvImportReport, DirectoryError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(mapping.delimiter())
+        .trim(csv::Trim::All)
+        .from_reader(input.as_bytes());
+
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let index: HashMap<&str, usize> = headers.iter().enumerate().map(|(i, h)| (h, i)).collect();
+    let username_column = mapping.column("username");
+    if !index.contains_key(username_column) {
+        return Err(DirectoryError::InvalidInput(format!("Missing column: {}", username_column)));
+    }
+
+    let upn_suffix = match &mapping.upn_suffix {
+        Some(suffix) => suffix.clone(),
+        None => service.get_all_domains().await?
+            .first()
+            .map(|d| d.dns_name.trim_end_matches('.').to_lowercase())
+            .unwrap_or_else(|| "corp.acme.com".to_string()),
+    };
+
+    let mut report = CsvImportReport { dry_run: options.dry_run, rows: Vec::new() };
+    let mut seen = HashSet::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        let cell = |field: &str| {
+            index.get(mapping.column(field))
+                .and_then(|i| record.get(*i))
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let username = cell("username").unwrap_or_default();
+
+        let mut result = CsvRowResult { line, username: username.clone(), status: CsvRowStatus::Created, generated_password: None };
+        if !seen.insert(username.to_lowercase()) {
+            result.status = CsvRowStatus::Failed("Duplicate username in file".to_string());
+            report.rows.push(result);
+            continue;
+        }
+
+        match parse_row(service, mapping, &cell).await {
+            Ok(mut row) => {
+                if row.password.is_none() && options.generate_passwords {
+                    let password = generate_password();
+                    result.generated_password = Some(password.clone());
+                    row.password = Some(password);
+                }
+                match apply_row(service, row, &upn_suffix, options).await {
+                    Ok(status) => result.status = status,
+                    Err(e) => {
+                        result.status = CsvRowStatus::Failed(e.to_string());
+                        result.generated_password = None;
+                    }
+                }
+            }
+            Err(e) => result.status = CsvRowStatus::Failed(e.to_string()),
+        }
+        report.rows.push(result);
+    }
+    Ok(report)
+}
+
+/// Проверить значения строки и разрешить ссылки на OU и группы
+async fn parse_row(
+    service: &DirectoryService,
+    mapping: &CsvColumnMapping,
+    cell: &impl Fn(&str) -> Option<String>,
+) -> Result<UserRow, DirectoryError> {
+    let username = cell("username").unwrap_or_default();
+    if username.is_empty() || username.len() > 64 {
+        return Err(DirectoryError::InvalidInput("Username must be 1-64 characters".to_string()));
+    }
+    let email = cell("email");
+    if email.as_deref().is_some_and(|e| !e.contains('@')) {
+        return Err(DirectoryError::InvalidInput("Invalid email format".to_string()));
+    }
+    let enabled = match cell("enabled") {
+        Some(value) => Some(parse_bool(&value)
+            .ok_or_else(|| DirectoryError::InvalidInput(format!("Invalid enabled value: {}", value)))?),
+        None => None,
+    };
+
+    let ou = match cell("ou") {
+        Some(dn) => Some(service.find_ou_by_dn(&dn).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("OU not found: {}", dn)))?
+            .id),
+        None => None,
+    };
+
+    let mut groups = Vec::new();
+    for sam in cell("groups").unwrap_or_default().split(mapping.list_separator()).map(str::trim).filter(|s| !s.is_empty()) {
+        let group = service.find_group_by_sam_account_name(sam).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", sam)))?;
+        groups.push(group.id);
+    }
+
+    Ok(UserRow {
+        username,
+        user_principal_name: cell("user_principal_name"),
+        email,
+        display_name: cell("display_name"),
+        given_name: cell("given_name"),
+        surname: cell("surname"),
+        password: cell("password"),
+        enabled,
+        ou,
+        groups,
+    })
+}
+
+/// Создать или обновить пользователя; в режиме dry-run только проверить конфликты
+async fn apply_row(
+    service: &DirectoryService,
+    row: UserRow,
+    upn_suffix: &str,
+    options: CsvImportOptions,
+) -> Result<CsvRowStatus, DirectoryError> {
+    let existing = service.find_user_by_username(&row.username).await?;
+    if existing.is_some() && !options.update_existing {
+        return Err(DirectoryError::AlreadyExists(ErrorCode::UserAlreadyExists, format!("User {} already exists", row.username)));
+    }
+    if let Some(email) = &row.email
+        && let Some(owner) = service.find_user_by_email(email).await?
+        && existing.as_ref().is_none_or(|u| u.id != owner.id)
+    {
+        return Err(DirectoryError::AlreadyExists(ErrorCode::EmailAlreadyExists, format!("Email {} already in use", email)));
+    }
+
+    let status = if existing.is_some() { CsvRowStatus::Updated } else { CsvRowStatus::Created };
+    if options.dry_run {
+        return Ok(status);
+    }
+
+    let mut user = match existing {
+        Some(user) => user,
+        None => User {
+            id: Uuid::new_v4(),
+            sid: SecurityIdentifier::new_nt_authority(1001),
+            username: row.username.clone(),
+            user_principal_name: format!("{}@{}", row.username, upn_suffix),
+            email: None,
+            display_name: None,
+            given_name: None,
+            surname: None,
+            password_hash: PasswordHash {
+                hash: "!".to_string(),
+                algorithm: PasswordAlgorithm::Bcrypt,
+                salt: vec![],
+            },
+            password_expires: None,
+            last_password_change: chrono::Utc::now(),
+            lockout_until: None,
+            failed_logins: 0,
+            enabled: true,
+            mfa_enabled: false,
+            mfa_methods: vec![],
+            domains: vec![],
+            groups: vec![],
+            organizational_unit: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            last_login: None,
+            profile_path: None,
+            script_path: None,
+            meta: HashMap::new(),
+            primary_group_id: Some(513),
+            service_principal_names: vec![],
+            kerberos_keys: vec![],
+            user_account_control: UserAccountControl::NORMAL_ACCOUNT,
+            totp_secret: None,
+            totp_pending_secret: None,
+        },
+    };
+
+    user.user_principal_name = row.user_principal_name.unwrap_or(user.user_principal_name);
+    user.email = row.email.or(user.email);
+    user.display_name = row.display_name.or(user.display_name);
+    user.given_name = row.given_name.or(user.given_name);
+    user.surname = row.surname.or(user.surname);
+    user.enabled = row.enabled.unwrap_or(user.enabled);
+    user.organizational_unit = row.ou.or(user.organizational_unit);
+    user.updated_at = chrono::Utc::now();
+    service.create_user(&user).await?;
+
+    if let Some(password) = &row.password {
+        service.set_password(user.id, password).await?;
+    }
+    for group_id in row.groups {
+        service.add_member_to_group(group_id, user.id, None).await?;
+    }
+    Ok(status)
+}
+
+/// Выгрузить пользователей в CSV с теми же столбцами, что понимает импорт
+pub async fn export_users(service: &DirectoryService, mapping: &CsvColumnMapping) -> Result<String, DirectoryError> {
+    let fields = ["username", "user_principal_name", "email", "display_name", "given_name", "surname", "enabled", "ou", "groups"];
+    let mut writer = csv::WriterBuilder::new().delimiter(mapping.delimiter()).from_writer(vec![]);
+    writer.write_record(fields.iter().map(|f| mapping.column(f))).map_err(csv_error)?;
+
+    let separator = mapping.list_separator().to_string();
+    for user in service.get_all_users().await? {
+        let ou = match user.organizational_unit {
+            Some(id) => service.get_ou(id).await?.map(|ou| ou.dn).unwrap_or_default(),
+            None => String::new(),
+        };
+        let mut groups: Vec<String> = service.find_groups_by_member(user.id).await?
+            .into_iter()
+            .map(|g| g.sam_account_name)
+            .collect();
+        groups.sort();
+
+        writer.write_record([
+            user.username.as_str(),
+            &user.user_principal_name,
+            user.email.as_deref().unwrap_or_default(),
+            user.display_name.as_deref().unwrap_or_default(),
+            user.given_name.as_deref().unwrap_or_default(),
+            user.surname.as_deref().unwrap_or_default(),
+            if user.enabled { "true" } else { "false" },
+            &ou,
+            &groups.join(&separator),
+        ]).map_err(csv_error)?;
+    }
+    finish(writer)
+}
+
+/// Выгрузить членство в группах: одна строка на пару группа–участник
+pub async fn export_group_memberships(service: &DirectoryService, mapping: &CsvColumnMapping) -> Result<String, DirectoryError> {
+    let mut writer = csv::WriterBuilder::new().delimiter(mapping.delimiter()).from_writer(vec![]);
+    writer.write_record(["group", "group_name", "username"]).map_err(csv_error)?;
+
+    for group in service.get_all_groups().await? {
+        for member_id in &group.members {
+            if let Some(user) = service.get_user(*member_id).await? {
+                writer.write_record([&group.sam_account_name, &group.name, &user.username]).map_err(csv_error)?;
+            }
+        }
+    }
+    finish(writer)
+}
+
+fn finish(writer: csv::Writer<Vec<u8>>) -> Result<String, DirectoryError> {
+    let bytes = writer.into_inner().map_err(|e| DirectoryError::Serialization(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| DirectoryError::Serialization(e.to_string()))
+}
+
+fn csv_error(e: csv::Error) -> DirectoryError {
+    DirectoryError::InvalidInput(format!("CSV error: {}", e))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "1" | "да" => Some(true),
+        "false" | "no" | "0" | "нет" => Some(false),
+        _ => None,
+    }
+}
+
+fn generate_password() -> String {
+    let alphabet = PASSWORD_CLASSES.concat();
+    let pick = |chars: &[u8]| chars[OsRng.gen_range(0..chars.len())];
+    let mut password: Vec<u8> = PASSWORD_CLASSES.iter().map(|class| pick(class)).collect();
+    password.extend((password.len()..GENERATED_PASSWORD_LEN).map(|_| pick(&alphabet)));
+    password.shuffle(&mut OsRng);
+    String::from_utf8(password).expect("password alphabet is ASCII")
+}
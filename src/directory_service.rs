@@ -1,15 +1,28 @@
 // src/directory_service.rs
 
-use crate::raddb::RadDB;
+use crate::raddb::{Batch, RadDB};
 use crate::models::*;
+use crate::models::ldap_entry::OBJECT_GUID;
+use crate::dn::{self, Dn, Rdn};
+use crate::ldif::{self, DuplicatePolicy, ImportReport, LdifEntry, LdifObjectKind};
+use crate::events::{AuditChainLink, AuditEvent, AuditResult, EventHub};
+use crate::audit::actor::ActorContext;
+use crate::audit::chain::{AuditAnchor, AuditChainHead, AuditChainReport, GENESIS_HASH};
+use crate::audit::query::{AuditPage, AuditQuery};
+use crate::cache::{self, CacheStats, ObjectCache};
+use crate::casefold;
+use crate::error_code::ErrorCode;
+use crate::auth_provider::{AuthProvider, AuthProviders, LocalProvider};
+use crate::kerberos::keytab::KeytabEntry;
+use crate::kerberos::messages::PrincipalName;
+use crate::config::{duration, ApprovalConfig, AuditChainConfig, AuthProviderConfig, CaConfig, CacheConfig, RateLimitConfig, ReplicationConfig, RiskConfig, SessionConfig, SessionLimitAction};
+use crate::ratelimit::{AuthRateLimiter, LimitScope, Limited};
 use bincode;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 use chrono::Utc;
-use std::fs::OpenOptions;
-use std::io::Write;
 
 /// Ошибки каталога
 #[derive(Debug)]
@@ -17,13 +30,62 @@ pub enum DirectoryError {
     DbError(crate::raddb::RadDbError),
     Serialization(String),
     NotFound(String),
-    AlreadyExists(String),
+    /// Объект с тем же именем, адресом или идентификатором уже есть; код уточняет, что именно занято
+    AlreadyExists(ErrorCode, String),
     InvalidInput(String),
+    /// Новый пароль не отвечает действующей парольной политике
+    PasswordPolicy(String),
+    AuthenticationFailed(String),
+    /// Пароль верен, но срок его действия истёк: вход только после смены пароля
+    PasswordExpired(String),
+    /// Слишком много попыток входа; через сколько секунд повторить
+    RateLimited(u64),
+    /// Операция запрещена для системного объекта (`isCriticalSystemObject`)
+    Protected(String),
+    /// DACL объекта не даёт запрошенных прав
+    AccessDenied(String),
+    /// Каталог — реплика только для чтения; изменения принимает записываемый контроллер (URL)
+    ReadOnly(String),
+    /// База открыта только для чтения (`DirectoryService::open_read_only`, `--read-only`)
+    DatabaseReadOnly,
+    /// В выдаче больше объектов, чем разрешено (`SearchLimits::size_limit`)
+    SizeLimitExceeded(usize),
+    /// Выдача не уложилась в `SearchLimits::time_limit`, секунд
+    TimeLimitExceeded(u64),
+    /// У пользователя уже столько действующих сессий, сколько разрешает `security.sessions.max_concurrent`
+    SessionLimitExceeded(usize),
+    /// Пароль верен, но политика риска (`security.risk`) требует второго фактора: вход повторяется
+    /// с кодом одного из перечисленных методов
+    MfaRequired(Vec<MfaMethod>),
 }
 
 impl From<crate::raddb::RadDbError> for DirectoryError {
     fn from(e: crate::raddb::RadDbError) -> Self {
-        DirectoryError::DbError(e)
+        match e {
+            crate::raddb::RadDbError::ReadOnly => DirectoryError::DatabaseReadOnly,
+            e => DirectoryError::DbError(e),
+        }
+    }
+}
+
+impl From<crate::ca::CaError> for DirectoryError {
+    fn from(e: crate::ca::CaError) -> Self {
+        match e {
+            crate::ca::CaError::InvalidCsr(_) => DirectoryError::InvalidInput(e.to_string()),
+            crate::ca::CaError::Crypto(_) => DirectoryError::Serialization(e.to_string()),
+        }
+    }
+}
+
+impl From<crate::dn::DnError> for DirectoryError {
+    fn from(e: crate::dn::DnError) -> Self {
+        DirectoryError::InvalidInput(e.to_string())
+    }
+}
+
+impl From<crate::ldif::LdifError> for DirectoryError {
+    fn from(e: crate::ldif::LdifError) -> Self {
+        DirectoryError::InvalidInput(e.to_string())
     }
 }
 
@@ -45,43 +107,430 @@ impl std::fmt::Display for DirectoryError {
             DirectoryError::DbError(e) => write!(f, "DB error: {}", e),
             DirectoryError::Serialization(e) => write!(f, "Serialization error: {}", e),
             DirectoryError::NotFound(e) => write!(f, "Not found: {}", e),
-            DirectoryError::AlreadyExists(e) => write!(f, "Already exists: {}", e),
+            DirectoryError::AlreadyExists(_, e) => write!(f, "Already exists: {}", e),
             DirectoryError::InvalidInput(e) => write!(f, "Invalid input: {}", e),
+            DirectoryError::PasswordPolicy(e) => write!(f, "Password policy violation: {}", e),
+            DirectoryError::AuthenticationFailed(e) => write!(f, "Authentication failed: {}", e),
+            DirectoryError::PasswordExpired(e) => write!(f, "Password expired, must change: {}", e),
+            DirectoryError::RateLimited(secs) => write!(f, "Too many login attempts, retry in {}s", secs),
+            DirectoryError::Protected(e) => write!(f, "Protected object: {}", e),
+            DirectoryError::AccessDenied(e) => write!(f, "Access denied: {}", e),
+            DirectoryError::ReadOnly(upstream) => write!(f, "Read-only replica: send changes to {}", upstream),
+            DirectoryError::DatabaseReadOnly => write!(f, "Database is opened read-only; changes are refused"),
+            DirectoryError::SizeLimitExceeded(limit) => write!(f, "Result exceeds the size limit of {} entries, narrow the query", limit),
+            DirectoryError::TimeLimitExceeded(secs) => write!(f, "Search exceeded the time limit of {}s", secs),
+            DirectoryError::SessionLimitExceeded(limit) => write!(f, "User already has {} active sessions, the maximum allowed", limit),
+            DirectoryError::MfaRequired(methods) => {
+                let methods: Vec<String> = methods.iter().map(|method| format!("{:?}", method)).collect();
+                write!(f, "Second factor required, repeat the login with a code: {}", methods.join(", "))
+            }
         }
     }
 }
 
 impl std::error::Error for DirectoryError {}
 
+impl DirectoryError {
+    /// Стабильный код ошибки для REST и gRPC
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            DirectoryError::NotFound(_) => ErrorCode::NotFound,
+            DirectoryError::AlreadyExists(code, _) => *code,
+            DirectoryError::InvalidInput(_) | DirectoryError::Serialization(_) => ErrorCode::InvalidInput,
+            DirectoryError::PasswordPolicy(_) => ErrorCode::PasswordPolicyViolation,
+            DirectoryError::AuthenticationFailed(_) => ErrorCode::AuthenticationFailed,
+            DirectoryError::PasswordExpired(_) => ErrorCode::PasswordExpired,
+            DirectoryError::RateLimited(_) => ErrorCode::RateLimited,
+            DirectoryError::Protected(_) => ErrorCode::ProtectedObject,
+            DirectoryError::AccessDenied(_) => ErrorCode::AccessDenied,
+            DirectoryError::ReadOnly(_) => ErrorCode::ReadOnlyReplica,
+            DirectoryError::DatabaseReadOnly => ErrorCode::DatabaseReadOnly,
+            DirectoryError::SizeLimitExceeded(_) => ErrorCode::SizeLimitExceeded,
+            DirectoryError::TimeLimitExceeded(_) => ErrorCode::TimeLimitExceeded,
+            DirectoryError::SessionLimitExceeded(_) => ErrorCode::SessionLimitExceeded,
+            DirectoryError::MfaRequired(_) => ErrorCode::MfaRequired,
+            DirectoryError::DbError(_) => ErrorCode::InternalError,
+        }
+    }
+
+    /// diagnosticMessage ответа на LDAP bind в формате AD: код `data` различает неверный пароль (52e)
+    /// и истёкший пароль (532), по нему клиенты предлагают сменить пароль
+    pub fn ldap_diagnostic(&self) -> Option<String> {
+        let data = match self {
+            DirectoryError::AuthenticationFailed(_) => "52e",
+            DirectoryError::PasswordExpired(_) => "532",
+            _ => return None,
+        };
+        Some(format!("80090308: LdapErr: DSID-0C09044E, comment: AcceptSecurityContext error, data {}, v4563", data))
+    }
+}
+
+/// Принимает ли каталог изменения
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum WriteMode {
+    Writable,
+    /// Реплика только для чтения: URL записываемого контроллера
+    Replica(String),
+    /// База открыта только для чтения
+    ReadOnlyDatabase,
+}
+
+/// Индексы поиска, ключи которых — значение после `casefold::fold` (у `dn_index` — канонический DN)
+const LOOKUP_INDEXES: &[&str] = &[
+    "username_index", "email_index", "spn_index", "sam_account_name_index", "contact_email_index",
+    "dns_domain_index", "organization_name_index", "dn_index",
+];
+
+/// Версия ключей индексов поиска; растёт при каждом изменении их нормализации
+const LOOKUP_INDEX_VERSION: u32 = 1;
+const LOOKUP_INDEX_VERSION_KEY: &str = "lookup_index_version";
+
+/// sAMAccountName группы администраторов домена
+pub const DOMAIN_ADMINS_SAM: &str = "DOMAIN ADMINS";
+
+/// sAMAccountName группы, в которую попадают новые пользователи
+pub const DOMAIN_USERS_SAM: &str = "DOMAIN USERS";
+
+/// Первый RID, выдаваемый новым пользователям и группам (как в AD, после встроенных)
+const FIRST_ALLOCATED_RID: u32 = 1100;
+
+/// Объекты, изменения которых попадают в журнал изменений: префикс ключа и тип объекта
+const CHANGE_TRACKED: &[(&str, &str)] = &[
+    ("user:", "user"),
+    ("group:", "group"),
+    ("ou:", "ou"),
+    ("contact:", "contact"),
+    ("gpo:", "gpo"),
+    ("service_account:", "service_account"),
+    ("site:", "site"),
+    ("subnet:", "subnet"),
+    ("pso:", "pso"),
+];
+
+/// Ответ на любой неудачный вход по паролю; причина — только в аудите
+pub const INVALID_CREDENTIALS: &str = "Invalid username or password";
+
+/// Сколько последних попыток входа хранится в истории пользователя
+const LOGIN_HISTORY_LEN: usize = 100;
+
+/// Сколько последних выполнений каждой фоновой задачи хранится
+const JOB_HISTORY_LEN: usize = 50;
+
+/// Последний выданный USN
+const USN_KEY: &str = "usn_counter";
+
+/// Сколько объектов читается за одну страницу при выдаче списка
+const SCAN_BATCH: usize = 500;
+
+/// Пределы выдачи списка объектов, заданные администратором
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// Не больше объектов в выдаче; 0 — без предела
+    pub size_limit: usize,
+    /// Не дольше на чтение; `None` — без предела
+    pub time_limit: Option<std::time::Duration>,
+}
+
+impl SearchLimits {
+    pub fn new(size_limit: usize, time_limit_secs: u64) -> Self {
+        Self { size_limit, time_limit: (time_limit_secs > 0).then(|| std::time::Duration::from_secs(time_limit_secs)) }
+    }
+}
+
+/// Страница обхода объектов каталога
+#[derive(Debug, Clone)]
+pub struct ScanPage<T> {
+    pub items: Vec<T>,
+    /// Курсор следующей страницы; `None` — обход закончен
+    pub next: Option<usize>,
+}
+
 /// Сервис каталога
 pub struct DirectoryService {
     db: Arc<RwLock<RadDB>>,
-    log_file: std::sync::Mutex<std::fs::File>,
+    /// Поток событий аудита: журнал (`audit::spawn_writer`) и подписчики SSE / WebSocket
+    events: EventHub,
+    /// Цепочка хэшей событий аудита; `None` — выключена
+    audit_chain: Option<AuditChainConfig>,
+    /// Лимиты попыток входа; `None` — выключены. Заменяются при перезагрузке конфигурации
+    rate_limit: std::sync::RwLock<Option<Arc<AuthRateLimiter>>>,
+    /// Парольная политика домена; действует, если пользователю не назначен PSO
+    password_policy: std::sync::RwLock<PasswordPolicy>,
+    /// Операции, которые выполняются только после одобрения другим администратором
+    approvals: std::sync::RwLock<ApprovalConfig>,
+    /// Предел одновременных сессий входа на пользователя
+    sessions: std::sync::RwLock<SessionConfig>,
+    /// Признаки подозрительного входа и реакция на них
+    risk: std::sync::RwLock<RiskConfig>,
+    /// Кто проверяет пароли доменов: локальный хеш, вышестоящий LDAP или OIDC
+    auth_providers: std::sync::RwLock<AuthProviders>,
+    /// Сроки сертификатов внутреннего CA и адрес CRL
+    ca: std::sync::RwLock<CaConfig>,
+    /// Принимает ли каталог изменения
+    write_mode: WriteMode,
+    /// Десериализованные пользователи, группы, OU и контакты
+    cache: ObjectCache,
 }
 
 #[allow(dead_code)]
 impl DirectoryService {
     /// Открыть сервис с путём к базе и мастер-ключом
     pub fn open<P: AsRef<str>>(path: P, key: &[u8; 32]) -> Result<Self, DirectoryError> {
-        let db = RadDB::open(path.as_ref(), key)?;
-        let log_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open("mextdomen.log")
-            .map_err(|e| DirectoryError::InvalidInput(format!("Failed to open log file: {}", e)))?;
-
-        Ok(Self {
+        Ok(Self::with_db(RadDB::open(path.as_ref(), key)?))
+    }
+
+    /// Открыть базу только для чтения, без блокировки (`--read-only`): отчёты и выгрузки рядом
+    /// с работающим сервером, который держит базу на запись; изменения завершаются ошибкой
+    pub fn open_read_only<P: AsRef<str>>(path: P, key: &[u8; 32]) -> Result<Self, DirectoryError> {
+        let mut service = Self::with_db(RadDB::open_read_only(path.as_ref(), key)?);
+        service.write_mode = WriteMode::ReadOnlyDatabase;
+        Ok(service)
+    }
+
+    fn with_db(db: RadDB) -> Self {
+        Self {
             db: Arc::new(RwLock::new(db)),
-            log_file: std::sync::Mutex::new(log_file),
-        })
+            events: EventHub::new(),
+            audit_chain: None,
+            rate_limit: std::sync::RwLock::new(None),
+            password_policy: std::sync::RwLock::new(PasswordPolicy::default()),
+            approvals: std::sync::RwLock::new(ApprovalConfig::default()),
+            sessions: std::sync::RwLock::new(SessionConfig::default()),
+            risk: std::sync::RwLock::new(RiskConfig::default()),
+            auth_providers: std::sync::RwLock::new(AuthProviders::default()),
+            ca: std::sync::RwLock::new(CaConfig::default()),
+            write_mode: WriteMode::Writable,
+            cache: ObjectCache::new(cache::DEFAULT_CAPACITY),
+        }
+    }
+
+    /// Включить цепочку хэшей аудита (`security.audit.chain`)
+    pub fn with_audit_chain(mut self, chain: &AuditChainConfig) -> Self {
+        self.audit_chain = chain.enabled.then(|| chain.clone());
+        self
+    }
+
+    /// Включить лимиты попыток входа (`security.rate_limit`)
+    pub fn with_rate_limit(self, config: &RateLimitConfig) -> Self {
+        self.set_rate_limit(config);
+        self
+    }
+
+    /// Заменить лимиты попыток входа на работающем сервисе; счётчики попыток начинаются заново
+    pub fn set_rate_limit(&self, config: &RateLimitConfig) {
+        *self.rate_limit.write().unwrap() = config.enabled.then(|| Arc::new(AuthRateLimiter::new(config)));
+    }
+
+    /// Задать парольную политику домена (`security.password_policy`)
+    pub fn with_password_policy(self, policy: &PasswordPolicy) -> Self {
+        self.set_password_policy(policy);
+        self
+    }
+
+    /// Заменить парольную политику домена на работающем сервисе
+    pub fn set_password_policy(&self, policy: &PasswordPolicy) {
+        *self.password_policy.write().unwrap() = policy.clone();
+    }
+
+    /// Задать операции, требующие одобрения (`security.approvals`)
+    pub fn with_approvals(self, config: &ApprovalConfig) -> Self {
+        self.set_approvals(config);
+        self
+    }
+
+    /// Заменить список операций, требующих одобрения; созданные заявки остаются в силе
+    pub fn set_approvals(&self, config: &ApprovalConfig) {
+        *self.approvals.write().unwrap() = config.clone();
+    }
+
+    /// Задать предел одновременных сессий (`security.sessions`)
+    pub fn with_sessions(self, config: &SessionConfig) -> Self {
+        self.set_sessions(config);
+        self
+    }
+
+    /// Заменить предел одновременных сессий; уже открытые сессии он не закрывает
+    pub fn set_sessions(&self, config: &SessionConfig) {
+        *self.sessions.write().unwrap() = config.clone();
+    }
+
+    /// Включить оценку риска входа (`security.risk`)
+    pub fn with_risk(self, config: &RiskConfig) -> Self {
+        self.set_risk(config);
+        self
+    }
+
+    /// Заменить политику риска входа на работающем сервисе
+    pub fn set_risk(&self, config: &RiskConfig) {
+        *self.risk.write().unwrap() = config.clone();
+    }
+
+    /// Задать провайдеров проверки паролей по доменам (`security.auth_providers`)
+    pub fn with_auth_providers(self, config: &HashMap<String, AuthProviderConfig>) -> Self {
+        self.set_auth_providers(config);
+        self
+    }
+
+    /// Заменить провайдеров проверки паролей на работающем сервисе
+    pub fn set_auth_providers(&self, config: &HashMap<String, AuthProviderConfig>) {
+        *self.auth_providers.write().unwrap() = AuthProviders::new(config);
+    }
+
+    /// Задать настройки внутреннего CA (`ca`)
+    pub fn with_ca(self, config: &CaConfig) -> Self {
+        self.set_ca(config);
+        self
+    }
+
+    /// Заменить настройки CA; выданные сертификаты и сам CA не меняются
+    pub fn set_ca(&self, config: &CaConfig) {
+        *self.ca.write().unwrap() = config.clone();
+    }
+
+    /// Режим реплики только для чтения (`replication.read_only`): изменения отклоняются
+    /// с `DirectoryError::ReadOnly` и ссылкой на `replication.upstream`
+    pub fn with_replication(mut self, config: &ReplicationConfig) -> Self {
+        if config.read_only && self.write_mode == WriteMode::Writable {
+            self.write_mode = WriteMode::Replica(config.upstream.clone().unwrap_or_default());
+        }
+        self
+    }
+
+    /// Размер кэша объектов (`cache.capacity`); 0 — без кэша
+    pub fn with_cache(mut self, config: &CacheConfig) -> Self {
+        self.cache = ObjectCache::new(config.capacity);
+        self
+    }
+
+    /// Счётчики кэша объектов
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Каталог не принимает изменения: реплика или база, открытая только для чтения
+    pub fn is_read_only(&self) -> bool {
+        self.write_mode != WriteMode::Writable
+    }
+
+    /// Реплика только для чтения (`replication.read_only`)
+    pub fn is_replica(&self) -> bool {
+        matches!(self.write_mode, WriteMode::Replica(_))
+    }
+
+    /// Изменения каталога запрещены на реплике и в базе только для чтения; журнал аудита
+    /// на реплике ведётся
+    fn check_writable(&self) -> Result<(), DirectoryError> {
+        match &self.write_mode {
+            WriteMode::Writable => Ok(()),
+            WriteMode::Replica(upstream) => Err(DirectoryError::ReadOnly(upstream.clone())),
+            WriteMode::ReadOnlyDatabase => Err(DirectoryError::DatabaseReadOnly),
+        }
+    }
+
+    /// База на запись для изменения каталога
+    async fn write_db(&self) -> Result<tokio::sync::RwLockWriteGuard<'_, RadDB>, DirectoryError> {
+        self.check_writable()?;
+        Ok(self.db.write().await)
+    }
+
+    /// Начать пакет изменений: база записывается на диск один раз, в `end_batch`, а не после
+    /// каждого изменения. Для массовых операций; `end_batch` надо вызвать и при ошибке, а если
+    /// вызывающий прервётся раньше, пакет закроется при удалении `Batch`
+    pub async fn begin_batch(&self) -> Batch {
+        self.db.read().await.begin_batch()
+    }
+
+    /// Закрыть пакет изменений и записать базу на диск
+    pub async fn end_batch(&self, batch: Batch) -> Result<(), DirectoryError> {
+        self.db.read().await.end_batch(batch)?;
+        Ok(())
+    }
+
+    /// Перестроить индексы поиска по имени, адресу и DN, если они записаны прежней версией
+    /// (с учётом регистра, `sam_account_name_index` — в верхнем регистре). Ключи, которые после
+    /// свёртки совпали у разных объектов, пишутся в журнал; индекс получает последний. Возвращает
+    /// число записанных ключей, 0 — индексы уже актуальны или база только для чтения
+    pub async fn migrate_lookup_indexes(&self) -> Result<usize, DirectoryError> {
+        if self.check_writable().is_err()
+            || self.load::<u32>(LOOKUP_INDEX_VERSION_KEY).await?.is_some_and(|version| version >= LOOKUP_INDEX_VERSION)
+        {
+            return Ok(0);
+        }
+        let batch = self.begin_batch().await;
+        let result = self.rebuild_lookup_indexes().await;
+        self.end_batch(batch).await?;
+        result
+    }
+
+    async fn rebuild_lookup_indexes(&self) -> Result<usize, DirectoryError> {
+        let mut index: HashMap<String, Uuid> = HashMap::new();
+        let mut add = |key: String, id: Uuid| {
+            if let Some(previous) = index.insert(key.clone(), id)
+                && previous != id
+            {
+                tracing::warn!(%key, %previous, %id, "lookup index key is shared by two objects");
+            }
+        };
+        for user in self.get_all_users().await? {
+            add(lookup_key("username_index", &user.username), user.id);
+            if let Some(email) = &user.email {
+                add(lookup_key("email_index", email), user.id);
+            }
+            for spn in &user.service_principal_names {
+                add(lookup_key("spn_index", spn), user.id);
+            }
+        }
+        for group in self.get_all_groups().await? {
+            add(lookup_key("sam_account_name_index", &group.sam_account_name), group.id);
+        }
+        for contact in self.get_all_contacts().await? {
+            for email in std::iter::once(&contact.email).chain(&contact.other_emails) {
+                add(lookup_key("contact_email_index", email), contact.id);
+            }
+        }
+        for domain in self.get_all_domains().await? {
+            add(lookup_key("dns_domain_index", domain.dns_name.trim_end_matches('.')), domain.id);
+        }
+        for organization in self.list_organizations().await? {
+            add(lookup_key("organization_name_index", &organization.name), organization.id);
+        }
+        for ou in self.get_all_ous().await? {
+            add(dn_index_key(&ou.dn), ou.id);
+        }
+
+        let db = self.write_db().await?;
+        let stale: Vec<String> = db.entries().into_iter()
+            .map(|(key, _)| key)
+            .filter(|key| LOOKUP_INDEXES.iter().any(|prefix| key.starts_with(&format!("{}:", prefix))))
+            .collect();
+        for key in stale {
+            db.remove(&key);
+        }
+        drop(db);
+        let written = index.len();
+        for (key, id) in index {
+            self.store(key, &id).await?;
+        }
+        self.store(LOOKUP_INDEX_VERSION_KEY.to_string(), &LOOKUP_INDEX_VERSION).await?;
+        Ok(written)
+    }
+
+    /// Подписка на события аудита
+    pub fn events(&self) -> &EventHub {
+        &self.events
     }
 
     /// Сохранить объект в базу
     async fn store<T: serde::Serialize>(&self, key: String, value: &T) -> Result<(), DirectoryError> {
+        self.check_writable()?;
         let data = bincode::serialize(value)
             .map_err(|e| DirectoryError::Serialization(e.to_string()))?;
         let db = self.db.write().await;
-        db.set(key, data)?;
+        let change_type = if db.contains_key(&key) { ChangeType::Modify } else { ChangeType::Add };
+        let mut entries = change_entries(&db, &key, change_type)?;
+        self.cache.invalidate(&key);
+        entries.push((key, data));
+        db.set_many(entries)?;
         Ok(())
     }
 
@@ -100,28 +549,135 @@ impl DirectoryService {
         }
     }
 
-    /// Логирование действий в файл
-    async fn log_action(&self, action: &str, details: &str, user_id: Option<Uuid>) -> Result<(), DirectoryError> {
-        let log_entry = format!(
-            "{} | ACTION: {} | DETAILS: {} | USER: {:?}\n",
-            Utc::now().to_rfc3339(),
-            action,
-            details,
-            user_id
-        );
+    /// Загрузить объект через кэш
+    async fn load_cached<T>(&self, key: &str) -> Result<Option<T>, DirectoryError>
+    where
+        T: for<'de> serde::Deserialize<'de> + Clone + Send + Sync + 'static,
+    {
+        let db = self.db.read().await;
+        self.read_cached(&db, key)
+    }
+
+    /// Объект из кэша или из базы; в кэш он попадает под блокировкой чтения,
+    /// поэтому параллельная запись не оставит в нём устаревшую копию
+    fn read_cached<T>(&self, db: &RadDB, key: &str) -> Result<Option<T>, DirectoryError>
+    where
+        T: for<'de> serde::Deserialize<'de> + Clone + Send + Sync + 'static,
+    {
+        let cached = self.cache.is_cached_key(key);
+        if cached && let Some(value) = self.cache.get::<T>(key) {
+            return Ok(Some(value));
+        }
+        let Some(data) = db.get(key) else {
+            return Ok(None);
+        };
+        let value: T = bincode::deserialize(&data[..])
+            .map_err(|e| DirectoryError::Serialization(e.to_string()))?;
+        if cached {
+            self.cache.insert(key, &value);
+        }
+        Ok(Some(value))
+    }
+
+    /// Страница обхода по индексу `all_X_index`: не больше `limit` объектов начиная с позиции `cursor`.
+    /// Объекты страницы читаются под одной блокировкой базы; удалённые между страницами пропускаются
+    async fn scan<T>(&self, index_key: &str, prefix: &str, cursor: usize, limit: usize) -> Result<ScanPage<T>, DirectoryError>
+    where
+        T: for<'de> serde::Deserialize<'de> + Clone + Send + Sync + 'static,
+    {
+        let db = self.db.read().await;
+        let ids: Vec<Uuid> = match db.get(index_key) {
+            Some(data) => bincode::deserialize(&data).map_err(|e| DirectoryError::Serialization(e.to_string()))?,
+            None => Vec::new(),
+        };
+        let start = cursor.min(ids.len());
+        let end = start.saturating_add(limit.max(1)).min(ids.len());
+        let mut items = Vec::with_capacity(end - start);
+        for id in &ids[start..end] {
+            items.extend(self.read_cached(&db, &format!("{}{}", prefix, id))?);
+        }
+        Ok(ScanPage { items, next: (end < ids.len()).then_some(end) })
+    }
+
+    /// Все объекты индекса в пределах `limits`: лишний объект — `SizeLimitExceeded`,
+    /// истёкшее время — `TimeLimitExceeded`; чтение прекращается сразу, не дочитывая индекс
+    async fn collect_within<T>(&self, index_key: &str, prefix: &str, limits: SearchLimits) -> Result<Vec<T>, DirectoryError>
+    where
+        T: for<'de> serde::Deserialize<'de> + Clone + Send + Sync + 'static,
+    {
+        let deadline = limits.time_limit.map(|limit| (std::time::Instant::now() + limit, limit.as_secs()));
+        let mut items = Vec::new();
+        let mut cursor = Some(0);
+        while let Some(position) = cursor {
+            if let Some((deadline, secs)) = deadline && std::time::Instant::now() >= deadline {
+                return Err(DirectoryError::TimeLimitExceeded(secs));
+            }
+            let page = self.scan(index_key, prefix, position, SCAN_BATCH).await?;
+            items.extend(page.items);
+            if limits.size_limit > 0 && items.len() > limits.size_limit {
+                return Err(DirectoryError::SizeLimitExceeded(limits.size_limit));
+            }
+            cursor = page.next;
+        }
+        Ok(items)
+    }
 
-        let mut file = self.log_file.lock().map_err(|_| DirectoryError::InvalidInput("Log file lock poisoned".to_string()))?;
-        file.write_all(log_entry.as_bytes())
-            .map_err(|e| DirectoryError::InvalidInput(e.to_string()))?;
+    /// Записать изменение каталога в аудит: `target_id` — изменённый объект, автор берётся из `ActorContext`
+    pub(crate) async fn log_action(&self, action: &str, details: &str, target_id: Option<Uuid>) -> Result<(), DirectoryError> {
+        let mut event = AuditEvent::new(action, AuditResult::Success);
+        event.target_id = target_id;
+        event.metadata.insert("details".to_string(), details.to_string());
+        self.record(event).await
+    }
+
+    /// Сохранить событие аудита для поиска и передать подписчикам (журнал, SSE);
+    /// для событий с адресом клиента из REST, RADIUS, KDC. Пустые автор и адрес берутся из `ActorContext`
+    pub async fn record(&self, mut event: AuditEvent) -> Result<(), DirectoryError> {
+        if let Some(actor) = ActorContext::current() {
+            actor.apply(&mut event);
+        }
+        // База только для чтения событие не сохраняет, приёмники аудита его получают
+        let anchor = match self.write_mode {
+            WriteMode::ReadOnlyDatabase => None,
+            _ => self.store_audit_event(&mut event).await?,
+        };
+        self.events.emit(event);
+        if let Some(anchor) = anchor {
+            self.events.emit(anchor.to_event());
+        }
         Ok(())
     }
 
     // ================= USERS =================
 
+    #[tracing::instrument(skip_all, fields(username = %user.username))]
     pub async fn create_user(&self, user: &User) -> Result<(), DirectoryError> {
+        validate_username(&user.username).map_err(DirectoryError::InvalidInput)?;
+        let mut user = user.clone();
+        if let Some(sid) = self.allocate_sid(&user.sid).await? {
+            user.sid = sid;
+        }
+        let user = &user;
+        self.validate_attributes(SchemaClass::User, &user.meta).await?;
+        let previous = self.get_user(user.id).await?.map(|previous| previous.meta);
+        self.save_user(user).await?;
+        self.reindex_attributes(user.id, previous.as_ref(), Some(&user.meta)).await?;
+        self.log_action("create_user", &format!("username:{}", user.username), Some(user.id)).await?;
+
+        // Основная группа (по умолчанию Domain Users) всегда содержит пользователя
+        if let Some(rid) = user.primary_group_id
+            && let Some(primary) = self.find_group_by_rid(rid).await?
+        {
+            self.add_member_to_group(primary.id, user.id, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Записать пользователя и индексы без события аудита
+    async fn save_user(&self, user: &User) -> Result<(), DirectoryError> {
         if let Some(existing) = self.find_user_by_username(&user.username).await? {
             if existing.id != user.id {
-                return Err(DirectoryError::AlreadyExists(format!(
+                return Err(DirectoryError::AlreadyExists(ErrorCode::UserAlreadyExists, format!(
                     "User with username {} already exists",
                     user.username
                 )));
@@ -131,7 +687,7 @@ impl DirectoryService {
         if let Some(email) = &user.email {
             if let Some(existing) = self.find_user_by_email(email).await? {
                 if existing.id != user.id {
-                    return Err(DirectoryError::AlreadyExists(format!(
+                    return Err(DirectoryError::AlreadyExists(ErrorCode::EmailAlreadyExists, format!(
                         "User with email {} already exists",
                         email
                     )));
@@ -139,13 +695,24 @@ impl DirectoryService {
             }
         }
 
+        self.check_sid_unique(&user.sid, user.id).await?;
+
+        // Смена email: старый индекс больше не должен находить пользователя
+        let previous = self.get_user(user.id).await?;
+        if let Some(old_email) = previous.as_ref().and_then(|previous| previous.email.as_ref())
+            && user.email.as_ref() != Some(old_email)
+        {
+            self.write_db().await?.remove(&lookup_key("email_index", old_email));
+        }
+
         let key = format!("user:{}", user.id);
         self.store(key, user).await?;
 
-        self.store(format!("username_index:{}", user.username), &user.id).await?;
+        self.store(lookup_key("username_index", &user.username), &user.id).await?;
         if let Some(email) = &user.email {
-            self.store(format!("email_index:{}", email), &user.id).await?;
+            self.store(lookup_key("email_index", email), &user.id).await?;
         }
+        self.index_sid(user.id, previous.as_ref().map(|previous| &previous.sid), Some(&user.sid)).await?;
 
         let all_users: Vec<Uuid> = self.load::<Vec<Uuid>>("all_users_index").await?.unwrap_or_default();
         if !all_users.contains(&user.id) {
@@ -153,19 +720,17 @@ impl DirectoryService {
             updated.push(user.id);
             self.store("all_users_index".to_string(), &updated).await?;
         }
-
-        self.log_action("create_user", &format!("username:{}", user.username), Some(user.id)).await?;
         Ok(())
     }
 
     pub async fn get_user(&self, id: Uuid) -> Result<Option<User>, DirectoryError> {
         let key = format!("user:{}", id);
-        self.load(&key).await
+        self.load_cached(&key).await
     }
 
+    /// Пользователь по имени без учёта регистра
     pub async fn find_user_by_username(&self, username: &str) -> Result<Option<User>, DirectoryError> {
-        let index_key = format!("username_index:{}", username);
-        let user_id: Option<Uuid> = self.load(&index_key).await?;
+        let user_id: Option<Uuid> = self.load(&lookup_key("username_index", username)).await?;
         if let Some(id) = user_id {
             self.get_user(id).await
         } else {
@@ -174,7 +739,7 @@ impl DirectoryService {
     }
 
     pub async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, DirectoryError> {
-        let index_key = format!("email_index:{}", email);
+        let index_key = lookup_key("email_index", email);
         let user_id: Option<Uuid> = self.load(&index_key).await?;
         if let Some(id) = user_id {
             self.get_user(id).await
@@ -183,59 +748,105 @@ impl DirectoryService {
         }
     }
 
-    pub async fn get_all_users(&self) -> Result<Vec<User>, DirectoryError> {
-        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_users_index").await?.unwrap_or_default();
-        let mut users = Vec::new();
-        for id in ids {
-            if let Some(user) = self.get_user(id).await? {
-                users.push(user);
-            }
+    /// Пользователь по objectSid; SID группы даёт `None`
+    pub async fn find_user_by_sid(&self, sid: &SecurityIdentifier) -> Result<Option<User>, DirectoryError> {
+        match self.find_id_by_sid(sid).await? {
+            Some(id) => self.get_user(id).await,
+            None => Ok(None),
         }
-        Ok(users)
     }
 
+    pub async fn get_all_users(&self) -> Result<Vec<User>, DirectoryError> {
+        Ok(self.scan_users(0, usize::MAX).await?.items)
+    }
+
+    /// Все пользователи в пределах `limits` — для выдачи списка клиенту
+    pub async fn list_users_within(&self, limits: SearchLimits) -> Result<Vec<User>, DirectoryError> {
+        self.collect_within("all_users_index", "user:", limits).await
+    }
+
+    /// Пользователи постранично: `cursor` — 0 или `next` предыдущей страницы
+    pub async fn scan_users(&self, cursor: usize, limit: usize) -> Result<ScanPage<User>, DirectoryError> {
+        self.scan("all_users_index", "user:", cursor, limit).await
+    }
+
+    #[tracing::instrument(skip(self))]
     pub async fn delete_user(&self, user_id: Uuid) -> Result<(), DirectoryError> {
         let user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        if user.is_protected() {
+            return Err(DirectoryError::Protected(format!("User {} is a built-in account and cannot be deleted", user.username)));
+        }
 
-        for group in self.find_groups_by_member(user_id).await? {
-            self.remove_member_from_group(group.id, user_id).await?;
+        // Из всех групп, включая основную и снимки динамических
+        let group_ids: HashSet<Uuid> = self.load(&format!("member_index:{}", user_id)).await?.unwrap_or_default();
+        for group_id in group_ids {
+            if let Some(group) = self.get_group(group_id).await? {
+                self.remove_member(group, user_id).await?;
+            }
         }
 
-        let username_index_key = format!("username_index:{}", user.username);
-        let email_index_key = user.email.clone().map(|e| format!("email_index:{}", e));
+        let username_index_key = lookup_key("username_index", &user.username);
+        let email_index_key = user.email.as_deref().map(|e| lookup_key("email_index", e));
+        let reset_user_key = format!("password_reset_user:{}", user_id);
+        let reset_hash: Option<String> = self.load(&reset_user_key).await?;
+        if let Some(invitation) = self.get_invitation(user_id).await? {
+            self.remove_invitation(&invitation).await?;
+        }
 
         let all_users: Vec<Uuid> = self.load::<Vec<Uuid>>("all_users_index").await?.unwrap_or_default();
         let updated_users: Vec<Uuid> = all_users.into_iter().filter(|id| *id != user_id).collect();
         self.store("all_users_index".to_string(), &updated_users).await?;
 
         let key = format!("user:{}", user_id);
-        let db = self.db.write().await;
-        db.remove(&key);
+        let db = self.write_db().await?;
+        remove_object(&db, &self.cache, &key)?;
         db.remove(&username_index_key);
         if let Some(email_key) = email_index_key {
             db.remove(&email_key);
         }
+        for spn in &user.service_principal_names {
+            db.remove(&lookup_key("spn_index", spn));
+        }
+        if let Some(sid_key) = sid_index_key(&user.sid) {
+            db.remove(&sid_key);
+        }
+        db.remove(&format!("security_descriptor:{}", user_id));
+        remove_object(&db, &self.cache, &format!("service_account:{}", user_id))?;
+        db.remove(&format!("password_history:{}", user_id));
+        db.remove(&format!("password_expiry_notice:{}", user_id));
+        db.remove(&format!("user_photo:{}", user_id));
+        if let Some(reset_hash) = reset_hash {
+            db.remove(&format!("password_reset:{}", reset_hash));
+            db.remove(&reset_user_key);
+        }
         drop(db);
+        self.reindex_attributes(user_id, Some(&user.meta), None).await?;
+        let service_accounts: Vec<Uuid> = self.load::<Vec<Uuid>>("all_service_accounts_index").await?.unwrap_or_default();
+        if service_accounts.contains(&user_id) {
+            let updated: Vec<Uuid> = service_accounts.into_iter().filter(|id| *id != user_id).collect();
+            self.store("all_service_accounts_index".to_string(), &updated).await?;
+        }
 
         self.log_action("delete_user", &format!("username:{}", user.username), Some(user_id)).await?;
         Ok(())
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn rename_user(&self, user_id: Uuid, new_username: Option<String>, new_display_name: Option<String>) -> Result<(), DirectoryError> {
         let mut user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
 
         if let Some(username) = new_username {
+            validate_username(&username).map_err(DirectoryError::InvalidInput)?;
             if let Some(existing) = self.find_user_by_username(&username).await? {
                 if existing.id != user_id {
-                    return Err(DirectoryError::AlreadyExists(format!("Username '{}' already taken", username)));
+                    return Err(DirectoryError::AlreadyExists(ErrorCode::UserAlreadyExists, format!("Username '{}' already taken", username)));
                 }
             }
-            let old_key = format!("username_index:{}", user.username);
-            let db = self.db.write().await;
-            db.remove(&old_key);
+            let db = self.write_db().await?;
+            db.remove(&lookup_key("username_index", &user.username));
             drop(db);
 
-            self.store(format!("username_index:{}", username), &user_id).await?;
+            self.store(lookup_key("username_index", &username), &user_id).await?;
             user.username = username;
         }
 
@@ -249,413 +860,5108 @@ impl DirectoryService {
         Ok(())
     }
 
+    #[tracing::instrument(skip_all, fields(username = %user.username))]
     pub async fn update_user(&self, user: &User) -> Result<(), DirectoryError> {
-        self.create_user(user).await
+        self.validate_attributes(SchemaClass::User, &user.meta).await?;
+        let previous = self.get_user(user.id).await?;
+        // Членство смена основной группы не добавляет: в прежней пользователь остаётся обычным участником
+        if let (Some(rid), Some(previous)) = (user.primary_group_id, &previous)
+            && previous.primary_group_id != Some(rid)
+        {
+            self.primary_group_candidate(user, rid).await?;
+        }
+        self.save_user(user).await?;
+        self.reindex_attributes(user.id, previous.as_ref().map(|previous| &previous.meta), Some(&user.meta)).await?;
+        self.log_action("update_user", &format!("username:{}", user.username), Some(user.id)).await?;
+        Ok(())
     }
 
-    // ================= GROUPS =================
+    /// Сделать группу основной группой пользователя (primaryGroupID). Как в AD, пользователь уже
+    /// должен быть её постоянным участником; из прежней основной группы его после этого можно удалить
+    #[tracing::instrument(skip(self))]
+    pub async fn set_primary_group(&self, user_id: Uuid, group_id: Uuid) -> Result<User, DirectoryError> {
+        let mut user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        let group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+        user.primary_group_id = Some(group.get_rid());
+        user.updated_at = Utc::now();
+        self.update_user(&user).await?;
+        Ok(user)
+    }
 
-    pub async fn create_group(&self, group: &Group) -> Result<(), DirectoryError> {
-        if let Some(existing) = self.find_group_by_sam_account_name(&group.sam_account_name).await? {
-            if existing.id != group.id {
-                return Err(DirectoryError::AlreadyExists(format!(
-                    "Group {} already exists",
-                    group.sam_account_name
-                )));
-            }
+    /// Основной группой, как в AD, может быть только статическая группа безопасности с областью
+    /// Global или Universal, в которой пользователь уже состоит постоянно: права на группу
+    /// проверяются при добавлении участника, а смена основной группы их не обходит
+    async fn primary_group_candidate(&self, user: &User, rid: u32) -> Result<Group, DirectoryError> {
+        let group = self.find_group_by_rid(rid).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Primary group with RID {} not found", rid)))?;
+        if !group.is_security_group() || group.scope == GroupScope::DomainLocal || group.is_dynamic() {
+            return Err(DirectoryError::InvalidInput(format!(
+                "Group {} cannot be the primary group of {}: only static Global or Universal security groups can",
+                group.sam_account_name, user.username
+            )));
         }
-
-        let key = format!("group:{}", group.id);
-        self.store(key, group).await?;
-        self.store(format!("sam_account_name_index:{}", group.sam_account_name.to_uppercase()), &group.id).await?;
-
-        for member_id in &group.members {
-            self.add_member_to_index(*member_id, group.id).await?;
+        if !group.members.contains(&user.id) || group.member_ttl(user.id, Utc::now()).is_some() {
+            return Err(DirectoryError::InvalidInput(format!(
+                "Group {} cannot be the primary group of {}: the user must already be a permanent member",
+                group.sam_account_name, user.username
+            )));
         }
+        Ok(group)
+    }
 
-        let all_groups: Vec<Uuid> = self.load::<Vec<Uuid>>("all_groups_index").await?.unwrap_or_default();
-        if !all_groups.contains(&group.id) {
-            let mut updated = all_groups;
-            updated.push(group.id);
-            self.store("all_groups_index".to_string(), &updated).await?;
+    /// Включить или отключить учётную запись; отключённая не проходит аутентификацию
+    #[tracing::instrument(skip(self))]
+    pub async fn set_user_enabled(&self, user_id: Uuid, enabled: bool) -> Result<User, DirectoryError> {
+        let mut user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+
+        if user.enabled != enabled {
+            user.enabled = enabled;
+            user.updated_at = Utc::now();
+            self.save_user(&user).await?;
         }
 
-        self.log_action("create_group", &format!("sam_account_name:{}", group.sam_account_name), None).await?;
-        Ok(())
+        let action = if enabled { "enable_user" } else { "disable_user" };
+        self.log_action(action, &format!("username:{}", user.username), Some(user_id)).await?;
+        Ok(user)
     }
 
-    pub async fn get_group(&self, id: Uuid) -> Result<Option<Group>, DirectoryError> {
-        let key = format!("group:{}", id);
-        self.load(&key).await
-    }
+    /// Снять блокировку после неудачных попыток входа
+    #[tracing::instrument(skip(self))]
+    pub async fn unlock_user(&self, user_id: Uuid) -> Result<User, DirectoryError> {
+        let mut user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
 
-    pub async fn find_group_by_sam_account_name(&self, sam_account_name: &str) -> Result<Option<Group>, DirectoryError> {
-        let key = format!("sam_account_name_index:{}", sam_account_name.to_uppercase());
-        let group_id: Option<Uuid> = self.load(&key).await?;
-        if let Some(id) = group_id {
-            self.get_group(id).await
-        } else {
-            Ok(None)
-        }
+        user.lockout_until = None;
+        user.failed_logins = 0;
+        user.updated_at = Utc::now();
+        self.save_user(&user).await?;
+        self.log_action("unlock_user", &format!("username:{}", user.username), Some(user_id)).await?;
+        Ok(user)
     }
 
-    pub async fn add_member_to_group(&self, group_id: Uuid, user_id: Uuid) -> Result<(), DirectoryError> {
-        let mut group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
-        if !group.members.contains(&user_id) {
-            group.members.push(user_id);
-            self.store(format!("group:{}", group.id), &group).await?;
-            self.add_member_to_index(user_id, group.id).await?;
-            self.log_action("add_member_to_group", &format!("group:{} user:{}", group.sam_account_name, user_id), Some(user_id)).await?;
+    /// Установить и снять флаги userAccountControl; ACCOUNTDISABLE и LOCKOUT меняют
+    /// `enabled` и блокировку так же, как `set_user_enabled` и `unlock_user`
+    #[tracing::instrument(skip(self))]
+    pub async fn update_user_account_control(&self, user_id: Uuid, set: UserAccountControl, clear: UserAccountControl) -> Result<User, DirectoryError> {
+        let mut user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        if set.contains(UserAccountControl::LOCKOUT) {
+            return Err(DirectoryError::InvalidInput("LOCKOUT cannot be set manually".to_string()));
         }
-        Ok(())
+        if !(set & clear).is_empty() {
+            return Err(DirectoryError::InvalidInput(format!("Flags both set and cleared: {}", (set & clear).flag_names().join(", "))));
+        }
+
+        let before = user.account_control();
+        user.set_account_control((before | set) - clear);
+        user.updated_at = Utc::now();
+        self.save_user(&user).await?;
+        self.log_action(
+            "update_user_account_control",
+            &format!("username:{} uac:0x{:08X}->0x{:08X}", user.username, before.bits(), user.account_control().bits()),
+            Some(user_id),
+        ).await?;
+        Ok(user)
     }
 
-    pub async fn remove_member_from_group(&self, group_id: Uuid, user_id: Uuid) -> Result<(), DirectoryError> {
-        let mut group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
-        if group.members.contains(&user_id) {
-            group.members.retain(|id| id != &user_id);
-            self.store(format!("group:{}", group.id), &group).await?;
-            self.remove_member_from_index(user_id, group.id).await?;
-            self.log_action("remove_member_from_group", &format!("group:{} user:{}", group.sam_account_name, user_id), Some(user_id)).await?;
+    /// Перенести пользователя в OU (`None` — убрать из OU); списки `users` обеих OU обновляются
+    #[tracing::instrument(skip(self))]
+    pub async fn move_user(&self, user_id: Uuid, ou_id: Option<Uuid>) -> Result<User, DirectoryError> {
+        let mut user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        let mut target = match ou_id {
+            Some(id) => Some(self.get_ou(id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?),
+            None => None,
+        };
+
+        if let Some(old_id) = user.organizational_unit
+            && Some(old_id) != ou_id
+            && let Some(mut old_ou) = self.get_ou(old_id).await?
+        {
+            old_ou.users.retain(|id| *id != user_id);
+            self.store(format!("ou:{}", old_id), &old_ou).await?;
+        }
+        if let Some(ou) = &mut target
+            && !ou.users.contains(&user_id)
+        {
+            ou.users.push(user_id);
+            self.store(format!("ou:{}", ou.id), ou).await?;
         }
-        Ok(())
-    }
 
-    pub async fn delete_group(&self, group_id: Uuid) -> Result<(), DirectoryError> {
-        let group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
-        let sam_key = format!("sam_account_name_index:{}", group.sam_account_name.to_uppercase());
+        user.organizational_unit = ou_id;
+        user.updated_at = Utc::now();
+        self.save_user(&user).await?;
 
-        let all_groups: Vec<Uuid> = self.load::<Vec<Uuid>>("all_groups_index").await?.unwrap_or_default();
-        let updated_groups: Vec<Uuid> = all_groups.into_iter().filter(|id| *id != group_id).collect();
-        self.store("all_groups_index".to_string(), &updated_groups).await?;
+        let dn = target.map(|ou| ou.dn).unwrap_or_else(|| "-".to_string());
+        self.log_action("move_user", &format!("username:{} ou:{}", user.username, dn), Some(user_id)).await?;
+        Ok(user)
+    }
 
-        for user_id in &group.members {
-            self.remove_member_from_index(*user_id, group.id).await?;
-        }
+    // ================= AUTHENTICATION =================
 
-        let db = self.db.write().await;
-        db.remove(&format!("group:{}", group_id));
-        db.remove(&sam_key);
-        drop(db);
+    /// Проверить имя и пароль; неудачные попытки считаются, после превышения учётная запись блокируется
+    pub async fn authenticate(&self, username: &str, password: &str) -> Result<User, DirectoryError> {
+        self.authenticate_from(username, password, None, LoginProtocol::Other).await
+    }
 
-        self.log_action("delete_group", &format!("group:{}", group.sam_account_name), None).await?;
-        Ok(())
+    /// То же с адресом клиента и протоколом; каждая попытка входа, удачная или нет, попадает
+    /// в аудит и историю входов пользователя. Частота попыток ограничена по адресу и по учётной
+    /// записи (`security.rate_limit`)
+    pub async fn authenticate_from(&self, username: &str, password: &str, source_ip: Option<String>, protocol: LoginProtocol) -> Result<User, DirectoryError> {
+        self.authenticate_with_mfa(username, password, None, source_ip, protocol).await
     }
 
-    pub async fn find_groups_by_member(&self, user_id: Uuid) -> Result<Vec<Group>, DirectoryError> {
-        let group_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&format!("member_index:{}", user_id)).await?.unwrap_or_else(|| HashSet::new());
+    /// То же с кодом второго фактора: его проверяют, только если политика риска требует
+    /// подтвердить вход (`step_up`); без кода такой вход отклоняется с `MfaRequired`
+    pub async fn authenticate_with_mfa(
+        &self,
+        username: &str,
+        password: &str,
+        mfa_code: Option<&str>,
+        source_ip: Option<String>,
+        protocol: LoginProtocol,
+    ) -> Result<User, DirectoryError> {
+        let limit_ip = source_ip.clone();
+        self.authenticate_limited(username, password, mfa_code, source_ip, limit_ip, protocol).await
+    }
+
+    /// Вход через NAS (RADIUS): в аудит попадает адрес NAS, но лимит только по учётной записи —
+    /// за одним NAS много клиентов
+    pub async fn authenticate_for_nas(&self, username: &str, password: &str, nas_ip: Option<String>) -> Result<User, DirectoryError> {
+        self.authenticate_limited(username, password, None, nas_ip, None, LoginProtocol::Radius).await
+    }
+
+    /// `limit_ip` — адрес для лимита по адресу клиента
+    #[tracing::instrument(name = "authenticate", skip(self, password, mfa_code, limit_ip))]
+    async fn authenticate_limited(
+        &self,
+        username: &str,
+        password: &str,
+        mfa_code: Option<&str>,
+        source_ip: Option<String>,
+        limit_ip: Option<String>,
+        protocol: LoginProtocol,
+    ) -> Result<User, DirectoryError> {
+        let limiter = self.rate_limit.read().unwrap().clone();
+        if let Some(limiter) = limiter
+            && let Err(limited) = limiter.check(username, limit_ip.as_deref())
+        {
+            if limited.breach {
+                self.on_rate_limit_breach(username, source_ip, &limited, limiter.lock_account).await?;
+            }
+            return Err(DirectoryError::RateLimited(limited.retry_after.as_secs().max(1)));
+        }
+
+        let mut result = self.verify_credentials(username, password).await;
+
+        // Пароль верен — вход сравнивается с прежними (`security.risk`)
+        let device = ActorContext::current().and_then(|actor| actor.device);
+        if let Ok(user) = &result
+            && let Some(risk) = self.assess_login_risk(user, source_ip.as_deref(), device.as_deref(), protocol).await?
+        {
+            let signals: Vec<&str> = risk.signals.iter().map(RiskSignal::as_str).collect();
+            match risk.action {
+                RiskAction::Block => result = Err(DirectoryError::AccessDenied(format!("Login blocked by risk policy: {}", signals.join(", ")))),
+                RiskAction::StepUp => {
+                    if let Err(e) = confirm_second_factor(user, mfa_code, &signals) {
+                        result = Err(e);
+                    }
+                }
+                RiskAction::Ignore | RiskAction::Log => {}
+            }
+        }
+
+        let mut event = AuditEvent::new("authenticate", if result.is_ok() { AuditResult::Success } else { AuditResult::Failure });
+        event.ip_addr = source_ip.clone();
+        event.metadata.insert("username".to_string(), username.to_string());
+        event.metadata.insert("protocol".to_string(), protocol.as_str().to_string());
+        let reason = match &result {
+            Ok(user) => {
+                event.actor_id = Some(user.id);
+                event.target_id = Some(user.id);
+                None
+            }
+            Err(DirectoryError::AuthenticationFailed(reason)) => Some(reason.clone()),
+            Err(DirectoryError::PasswordExpired(_)) => Some("password_expired".to_string()),
+            Err(DirectoryError::AccessDenied(_)) => Some("risk_blocked".to_string()),
+            Err(DirectoryError::MfaRequired(_)) => Some("mfa_required".to_string()),
+            // Сбой хранилища — не попытка входа
+            Err(_) => return result,
+        };
+        if let Some(reason) = &reason {
+            event.metadata.insert("reason".to_string(), reason.clone());
+        }
+        self.record(event).await?;
+
+        let user_id = match &result {
+            Ok(user) => Some(user.id),
+            Err(_) => self.find_user_by_username(username).await?.map(|user| user.id),
+        };
+        if let Some(user_id) = user_id {
+            let login = LoginRecord { timestamp: Utc::now(), protocol, ip_addr: source_ip, device, success: result.is_ok(), reason };
+            self.save_login_record(user_id, login).await?;
+        }
+        // Настоящая причина отказа (отключена, заблокирована, вход паролем запрещён) — только в
+        // аудите: по ответу нельзя узнать, что учётная запись существует и в каком она состоянии
+        result.map_err(|e| match e {
+            DirectoryError::AuthenticationFailed(_) => DirectoryError::AuthenticationFailed(INVALID_CREDENTIALS.to_string()),
+            other => other,
+        })
+    }
+
+    /// Признаки подозрительного входа по истории удачных входов (`security.risk`): новое устройство,
+    /// новый сайт, вход с другого сайта вскоре после предыдущего. Сайт адреса — по подсетям сайтов.
+    /// Признаки попадают в аудит событием `login_risk`; `None` — признаков нет или оценка выключена.
+    /// RADIUS не оценивается и не учитывается: его адрес — адрес NAS, а не клиента
+    async fn assess_login_risk(&self, user: &User, source_ip: Option<&str>, device: Option<&str>, protocol: LoginProtocol) -> Result<Option<LoginRisk>, DirectoryError> {
+        let config = self.risk.read().unwrap().clone();
+        if !config.enabled || protocol == LoginProtocol::Radius {
+            return Ok(None);
+        }
+        let history: Vec<LoginRecord> = self.get_login_history(user.id).await?.into_iter()
+            .filter(|login| login.success && login.protocol != LoginProtocol::Radius)
+            .collect();
+        // Первый вход сравнивать не с чем
+        let Some(previous) = history.first() else {
+            return Ok(None);
+        };
+
+        let subnets = self.get_all_subnets().await?;
+        let site_of = |ip: Option<&str>| {
+            let addr: std::net::IpAddr = ip?.parse().ok()?;
+            subnets.iter().filter(|subnet| subnet.contains(addr)).max_by_key(|subnet| subnet.prefix_len).map(|subnet| subnet.site_id)
+        };
+        let site = site_of(source_ip);
+        let previous_site = site_of(previous.ip_addr.as_deref());
+
+        let mut signals = Vec::new();
+        // У входов LDAP и у записей до учёта устройств его нет — сравнивать есть с чем, только если история его знает
+        if let Some(device) = device
+            && history.iter().any(|login| login.device.is_some())
+            && !history.iter().any(|login| login.device.as_deref() == Some(device))
+        {
+            signals.push((RiskSignal::NewDevice, config.new_device));
+        }
+        if let Some(site) = site {
+            if !history.iter().any(|login| site_of(login.ip_addr.as_deref()) == Some(site)) {
+                signals.push((RiskSignal::NewLocation, config.new_location));
+            }
+            if previous_site.is_some_and(|previous_site| previous_site != site)
+                && Utc::now() - previous.timestamp < chrono::Duration::seconds(config.travel_window_secs as i64)
+            {
+                signals.push((RiskSignal::ImpossibleTravel, config.impossible_travel));
+            }
+        }
+        signals.retain(|(_, action)| *action != RiskAction::Ignore);
+        let Some(action) = signals.iter().map(|(_, action)| *action).max() else {
+            return Ok(None);
+        };
+
+        let risk = LoginRisk {
+            signals: signals.into_iter().map(|(signal, _)| signal).collect(),
+            action,
+            site: self.site_name(site).await?,
+            previous_site: self.site_name(previous_site).await?,
+            previous_ip: previous.ip_addr.clone(),
+        };
+
+        let mut event = AuditEvent::new("login_risk", if action >= RiskAction::StepUp { AuditResult::Failure } else { AuditResult::Success });
+        event.ip_addr = source_ip.map(str::to_string);
+        event.actor_id = Some(user.id);
+        event.target_id = Some(user.id);
+        event.metadata.insert("username".to_string(), user.username.clone());
+        event.metadata.insert("protocol".to_string(), protocol.as_str().to_string());
+        event.metadata.insert("signals".to_string(), risk.signals.iter().map(RiskSignal::as_str).collect::<Vec<_>>().join(","));
+        event.metadata.insert("action".to_string(), action.as_str().to_string());
+        let details = [("device", device.map(str::to_string)), ("site", risk.site.clone()), ("previous_site", risk.previous_site.clone()), ("previous_ip", risk.previous_ip.clone())];
+        for (key, value) in details {
+            if let Some(value) = value {
+                event.metadata.insert(key.to_string(), value);
+            }
+        }
+        self.record(event).await?;
+        Ok(Some(risk))
+    }
+
+    async fn site_name(&self, id: Option<Uuid>) -> Result<Option<String>, DirectoryError> {
+        Ok(match id {
+            Some(id) => self.get_site(id).await?.map(|site| site.name),
+            None => None,
+        })
+    }
+
+    /// Добавить попытку в историю входов (последние `LOGIN_HISTORY_LEN`); на реплике история,
+    /// как и время входа, не хранится
+    async fn save_login_record(&self, user_id: Uuid, login: LoginRecord) -> Result<(), DirectoryError> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+        let key = format!("login_history:{}", user_id);
+        let mut history: Vec<LoginRecord> = self.load(&key).await?.unwrap_or_default();
+        history.insert(0, login);
+        history.truncate(LOGIN_HISTORY_LEN);
+        self.store(key, &history).await
+    }
+
+    /// История входов пользователя, последние первыми
+    pub async fn get_login_history(&self, user_id: Uuid) -> Result<Vec<LoginRecord>, DirectoryError> {
+        Ok(self.load(&format!("login_history:{}", user_id)).await?.unwrap_or_default())
+    }
+
+    /// Фотография пользователя (thumbnailPhoto / jpegPhoto)
+    pub async fn get_user_photo(&self, user_id: Uuid) -> Result<Option<UserPhoto>, DirectoryError> {
+        self.load(&format!("user_photo:{}", user_id)).await
+    }
+
+    /// Заменить фотографию пользователя; `None` удаляет её
+    #[tracing::instrument(skip(self, photo))]
+    pub async fn set_user_photo(&self, user_id: Uuid, photo: Option<&UserPhoto>) -> Result<(), DirectoryError> {
+        let user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        let key = format!("user_photo:{}", user_id);
+        let details = match photo {
+            Some(photo) => {
+                self.store(key, photo).await?;
+                format!("username:{} content_type:{} size:{}", user.username, photo.content_type, photo.data.len())
+            }
+            None => {
+                self.write_db().await?.remove(&key);
+                format!("username:{} removed", user.username)
+            }
+        };
+        self.log_action("set_user_photo", &details, Some(user_id)).await?;
+        Ok(())
+    }
+
+    /// Первый отказ по лимиту: событие аудита и, для лимита учётной записи, блокировка
+    async fn on_rate_limit_breach(&self, username: &str, source_ip: Option<String>, limited: &Limited, lock_account: bool) -> Result<(), DirectoryError> {
+        tracing::warn!(username, source_ip = source_ip.as_deref(), scope = limited.scope.as_str(), "Превышен лимит попыток входа");
+
+        let mut event = AuditEvent::new("auth_rate_limited", AuditResult::Failure);
+        event.ip_addr = source_ip;
+        event.metadata.insert("username".to_string(), username.to_string());
+        event.metadata.insert("scope".to_string(), limited.scope.as_str().to_string());
+        event.metadata.insert("retry_after".to_string(), limited.retry_after.as_secs().max(1).to_string());
+
+        if limited.scope == LimitScope::Account
+            && lock_account
+            && let Some(mut user) = self.find_user_by_username(username).await?
+        {
+            let policy = self.resultant_password_policy(&user).await?;
+            user.lockout_until = Some(Utc::now() + chrono::Duration::minutes(policy.lockout_duration_minutes.max(1).into()));
+            user.failed_logins = 0;
+            self.save_login_state(&user).await?;
+            event.target_id = Some(user.id);
+            event.metadata.insert("locked_until".to_string(), user.lockout_until.map(|t| t.to_rfc3339()).unwrap_or_default());
+        }
+        self.record(event).await
+    }
+
+    /// Сохранить счётчик неудачных попыток, блокировку и время входа; реплика их не хранит —
+    /// они перезаписались бы при следующей репликации, блокировкой ведает записываемый контроллер
+    async fn save_login_state(&self, user: &User) -> Result<(), DirectoryError> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+        // Как lastLogon и badPwdCount в AD: вход не меняет USN и не попадает в журнал изменений
+        let data = bincode::serialize(user).map_err(|e| DirectoryError::Serialization(e.to_string()))?;
+        let key = format!("user:{}", user.id);
+        let db = self.db.write().await;
+        self.cache.invalidate(&key);
+        db.set(key, data)?;
+        Ok(())
+    }
+
+    /// Неудачная попытка входа, проверенная не паролем (предаутентификация Kerberos): счётчик
+    /// и блокировка по политике паролей — так же, как неверный пароль в `authenticate`
+    pub async fn record_failed_login(&self, user_id: Uuid) -> Result<(), DirectoryError> {
+        match self.get_user(user_id).await? {
+            Some(mut user) => self.count_failed_login(&mut user).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Удачный вход, проверенный не паролем: счётчик неудачных попыток сбрасывается
+    pub async fn record_successful_login(&self, user_id: Uuid) -> Result<(), DirectoryError> {
+        let Some(mut user) = self.get_user(user_id).await? else {
+            return Ok(());
+        };
+        user.failed_logins = 0;
+        user.lockout_until = None;
+        user.last_login = Some(Utc::now());
+        self.save_login_state(&user).await
+    }
+
+    async fn count_failed_login(&self, user: &mut User) -> Result<(), DirectoryError> {
+        user.failed_logins += 1;
+        let policy = self.resultant_password_policy(user).await?;
+        if let Some(duration) = policy.lockout_duration()
+            && user.failed_logins >= policy.lockout_threshold
+        {
+            user.lockout_until = Some(Utc::now() + duration);
+            user.failed_logins = 0;
+        }
+        self.save_login_state(user).await
+    }
+
+    async fn verify_credentials(&self, username: &str, password: &str) -> Result<User, DirectoryError> {
+        let invalid = || DirectoryError::AuthenticationFailed(INVALID_CREDENTIALS.to_string());
+        let mut user = self.find_user_by_username(username).await?.ok_or_else(invalid)?;
+
+        if !user.enabled {
+            return Err(DirectoryError::AuthenticationFailed("Account is disabled".to_string()));
+        }
+        if user.is_locked_out() {
+            return Err(DirectoryError::AuthenticationFailed("Account is locked out".to_string()));
+        }
+
+        // Учётная запись службы: вход по паролю может быть запрещён, а прежний пароль
+        // принимается до конца окна перекрытия после смены
+        let service_account = self.get_service_account(user.id).await?;
+        if let Some(account) = &service_account
+            && account.flags.contains(ServiceAccountFlags::DENY_PASSWORD_LOGON)
+        {
+            return Err(DirectoryError::AuthenticationFailed("Password logon is denied for this service account".to_string()));
+        }
+        let previous_matches = service_account.as_ref()
+            .and_then(|account| account.previous_password_valid(Utc::now()))
+            .is_some_and(|previous| previous == password);
+
+        // Пароль проверяет провайдер домена (`security.auth_providers`); паролями учётных записей
+        // служб управляет сам каталог. Недоступный провайдер — отказ, но не неудачная попытка
+        let provider: Arc<dyn AuthProvider> = match &service_account {
+            Some(_) => Arc::new(LocalProvider),
+            None => self.auth_providers.read().unwrap().for_user(&user),
+        };
+        let verified = previous_matches || provider.verify(&user, password).await.map_err(|e| {
+            tracing::warn!(username = %user.username, provider = provider.name(), error = %e, "Провайдер аутентификации недоступен");
+            DirectoryError::AuthenticationFailed(e.to_string())
+        })?;
+
+        if !verified {
+            self.count_failed_login(&mut user).await?;
+            return Err(invalid());
+        }
+
+        user.failed_logins = 0;
+        user.lockout_until = None;
+        // Верный, но истёкший пароль — не неудачная попытка, но и не вход. Сроком внешнего пароля
+        // управляет его провайдер
+        if provider.is_local() && user.is_password_expired(Utc::now()) {
+            self.save_login_state(&user).await?;
+            return Err(DirectoryError::PasswordExpired(format!("password of {} has expired", user.username)));
+        }
+        user.last_login = Some(Utc::now());
+        self.save_login_state(&user).await?;
+        Ok(user)
+    }
+
+    // ================= INVITATIONS =================
+
+    /// Создать приглашённого пользователя: учётная запись выключена до принятия приглашения,
+    /// пароль задаёт сам приглашённый. Возвращает приглашение и токен для ссылки в письме
+    #[tracing::instrument(skip_all, fields(username = %user.username))]
+    pub async fn invite_user(&self, user: &User, invited_by: Option<Uuid>, ttl: chrono::Duration) -> Result<(Invitation, String), DirectoryError> {
+        if user.email.is_none() {
+            return Err(DirectoryError::InvalidInput("An invited user needs an email".to_string()));
+        }
+        let mut user = user.clone();
+        user.enabled = false;
+        self.create_user(&user).await?;
+        // create_user мог выделить SID: дальше работаем с сохранённой записью
+        let user = self.find_user_by_username(&user.username).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+
+        let (invitation, token) = Invitation::generate(user.id, invited_by, ttl);
+        self.save_invitation(&invitation, None).await?;
+        let mut index: Vec<Uuid> = self.load("all_invitations_index").await?.unwrap_or_default();
+        index.push(user.id);
+        self.store("all_invitations_index".to_string(), &index).await?;
+
+        self.log_action("invite_user", &format!("username:{} expires_at:{}", user.username, invitation.expires_at.to_rfc3339()), Some(user.id)).await?;
+        Ok((invitation, token))
+    }
+
+    /// Приглашение пользователя, пока оно не принято
+    pub async fn get_invitation(&self, user_id: Uuid) -> Result<Option<Invitation>, DirectoryError> {
+        self.load(&format!("invitation:{}", user_id)).await
+    }
+
+    /// Непринятые приглашения, в том числе просроченные
+    pub async fn list_invitations(&self) -> Result<Vec<Invitation>, DirectoryError> {
+        let index: Vec<Uuid> = self.load("all_invitations_index").await?.unwrap_or_default();
+        let mut invitations = Vec::with_capacity(index.len());
+        for user_id in index {
+            if let Some(invitation) = self.get_invitation(user_id).await? {
+                invitations.push(invitation);
+            }
+        }
+        Ok(invitations)
+    }
+
+    /// Новая ссылка с новым сроком; прежняя перестаёт действовать
+    #[tracing::instrument(skip(self))]
+    pub async fn resend_invitation(&self, user_id: Uuid, ttl: chrono::Duration) -> Result<(Invitation, String), DirectoryError> {
+        let mut invitation = self.get_invitation(user_id).await?
+            .ok_or_else(|| DirectoryError::NotFound("User has no pending invitation".to_string()))?;
+        let previous = invitation.token_hash.clone();
+        let token = invitation.renew(ttl);
+        self.save_invitation(&invitation, Some(&previous)).await?;
+        self.log_action("resend_invitation", &format!("expires_at:{}", invitation.expires_at.to_rfc3339()), Some(user_id)).await?;
+        Ok((invitation, token))
+    }
+
+    /// Отозвать приглашение: приглашённый пользователь ещё не входил, поэтому удаляется вместе с ним
+    #[tracing::instrument(skip(self))]
+    pub async fn revoke_invitation(&self, user_id: Uuid) -> Result<(), DirectoryError> {
+        if self.get_invitation(user_id).await?.is_none() {
+            return Err(DirectoryError::NotFound("User has no pending invitation".to_string()));
+        }
+        self.delete_user(user_id).await?;
+        self.log_action("revoke_invitation", "", Some(user_id)).await?;
+        Ok(())
+    }
+
+    /// Принять приглашение по токену из письма: пароль по действующей политике, методы MFA
+    /// (пустой список — без MFA), учётная запись включается. Пароль не по политике токен не гасит
+    #[tracing::instrument(skip_all)]
+    pub async fn accept_invitation(&self, token: &str, password: &str, mfa_methods: Vec<MfaMethod>, source_ip: Option<String>) -> Result<User, DirectoryError> {
+        let user_id: Option<Uuid> = self.load(&format!("invitation_token:{}", crate::models::password_reset::hash_token(token))).await?;
+        let invitation = match user_id {
+            Some(user_id) => self.get_invitation(user_id).await?,
+            None => None,
+        };
+
+        let mut event = AuditEvent::new("accept_invitation", AuditResult::Success);
+        event.ip_addr = source_ip;
+        let invitation = match invitation {
+            Some(invitation) if !invitation.is_expired() => invitation,
+            expired => {
+                event.target_id = expired.map(|invitation| invitation.user_id);
+                event.result = AuditResult::Failure;
+                event.metadata.insert("reason".to_string(), "invalid_token".to_string());
+                self.record(event).await?;
+                return Err(DirectoryError::InvalidInput("Invalid or expired invitation".to_string()));
+            }
+        };
+
+        event.target_id = Some(invitation.user_id);
+        event.actor_id = Some(invitation.user_id);
+        if let Err(e) = self.set_password(invitation.user_id, password).await {
+            event.result = AuditResult::Failure;
+            event.metadata.insert("reason".to_string(), e.to_string());
+            self.record(event).await?;
+            return Err(e);
+        }
+
+        let mut user = self.get_user(invitation.user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        user.enabled = true;
+        user.mfa_enabled = !mfa_methods.is_empty();
+        user.mfa_methods = mfa_methods;
+        user.updated_at = Utc::now();
+        self.update_user(&user).await?;
+        self.remove_invitation(&invitation).await?;
+
+        event.metadata.insert("username".to_string(), user.username.clone());
+        self.record(event).await?;
+        Ok(user)
+    }
+
+    /// Сохранить приглашение и ключ поиска по токену; `previous` — хеш заменённого токена
+    async fn save_invitation(&self, invitation: &Invitation, previous: Option<&str>) -> Result<(), DirectoryError> {
+        if let Some(previous) = previous {
+            self.write_db().await?.remove(&format!("invitation_token:{}", previous));
+        }
+        self.store(format!("invitation:{}", invitation.user_id), invitation).await?;
+        self.store(format!("invitation_token:{}", invitation.token_hash), &invitation.user_id).await
+    }
+
+    async fn remove_invitation(&self, invitation: &Invitation) -> Result<(), DirectoryError> {
+        let index: Vec<Uuid> = self.load("all_invitations_index").await?.unwrap_or_default();
+        let updated: Vec<Uuid> = index.into_iter().filter(|id| *id != invitation.user_id).collect();
+        self.store("all_invitations_index".to_string(), &updated).await?;
+        let db = self.write_db().await?;
+        db.remove(&format!("invitation:{}", invitation.user_id));
+        db.remove(&format!("invitation_token:{}", invitation.token_hash));
+        Ok(())
+    }
+
+    // ================= KERBEROS =================
+
+    /// Установить пароль по действующей политике (`resultant_password_policy`): обновляет хеш,
+    /// выводит новые ключи Kerberos (kvno + 1) и отсчитывает срок действия (`max_age_days`)
+    #[tracing::instrument(skip(self, password))]
+    pub async fn set_password(&self, user_id: Uuid, password: &str) -> Result<(), DirectoryError> {
+        let mut user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        if self.get_service_account(user_id).await?.is_some() {
+            return Err(DirectoryError::InvalidInput(format!(
+                "Password of service account {} is managed by the directory; rotate it instead",
+                user.username
+            )));
+        }
+
+        let policy = self.resultant_password_policy(&user).await?;
+        policy.check(password).map_err(DirectoryError::PasswordPolicy)?;
+        let history_key = format!("password_history:{}", user_id);
+        let mut history: Vec<PasswordHash> = self.load(&history_key).await?.unwrap_or_default();
+        if policy.history_count > 0 {
+            history.insert(0, user.password_hash.clone());
+            history.truncate(usize::from(policy.history_count));
+            if history.iter().any(|previous| previous.verify(password).unwrap_or(false)) {
+                return Err(DirectoryError::PasswordPolicy(format!(
+                    "Password matches one of the last {} passwords", policy.history_count
+                )));
+            }
+        } else {
+            history.clear();
+        }
+
+        let kvno = apply_password(&mut user, password)?;
+        user.password_expires = password_expiry(&user, &policy);
+        self.update_user(&user).await?;
+        self.store(history_key, &history).await?;
+        self.log_action("set_password", &format!("username:{} kvno:{}", user.username, kvno), Some(user_id)).await?;
+        Ok(())
+    }
+
+    /// Смена пароля самим пользователем: текущий пароль проверяется как при входе (лимиты попыток,
+    /// блокировка, история входов); истёкший текущий пароль подходит — его и надо сменить
+    #[tracing::instrument(skip(self, current, new))]
+    pub async fn change_password(&self, user_id: Uuid, current: &str, new: &str, source_ip: Option<String>) -> Result<(), DirectoryError> {
+        self.confirm_password(user_id, current, source_ip).await?;
+        self.set_password(user_id, new).await
+    }
+
+    /// Подтвердить действие вошедшего пользователя текущим паролем: одного токена мало, чтобы
+    /// сменить пароль или ослабить MFA. Проверка — как вход: лимит попыток, аудит, история
+    pub async fn confirm_password(&self, user_id: Uuid, current: &str, source_ip: Option<String>) -> Result<(), DirectoryError> {
+        let user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        match self.authenticate_from(&user.username, current, source_ip, LoginProtocol::Rest).await {
+            Ok(_) | Err(DirectoryError::PasswordExpired(_)) => Ok(()),
+            // Пользователь уже вошёл: неверный текущий пароль — отказ в действии, а не в сессии
+            Err(DirectoryError::AuthenticationFailed(reason)) => Err(DirectoryError::AccessDenied(format!("Current password rejected: {}", reason))),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Запрос сброса забытого пароля по имени или email: новый одноразовый токен заменяет прежний.
+    /// `None` — сбрасывать нечего (нет пользователя, он выключен, без email или это учётная запись
+    /// службы); отказ тоже попадает в аудит, но вызывающему о нём знать не нужно
+    #[tracing::instrument(skip(self))]
+    pub async fn request_password_reset(&self, login: &str, ttl: chrono::Duration, source_ip: Option<String>) -> Result<Option<(User, String)>, DirectoryError> {
+        let user = match self.find_user_by_username(login).await? {
+            Some(user) => Some(user),
+            None => self.find_user_by_email(login).await?,
+        };
+
+        let mut event = AuditEvent::new("password_reset_requested", AuditResult::Success);
+        event.ip_addr = source_ip;
+        event.metadata.insert("login".to_string(), login.to_string());
+        let refused = match &user {
+            None => Some("unknown_user"),
+            Some(user) if !user.enabled => Some("disabled"),
+            Some(user) if user.email.is_none() => Some("no_email"),
+            Some(user) if self.get_service_account(user.id).await?.is_some() => Some("service_account"),
+            Some(_) => None,
+        };
+        let user = match user {
+            Some(user) if refused.is_none() => user,
+            user => {
+                event.result = AuditResult::Failure;
+                event.target_id = user.map(|user| user.id);
+                event.metadata.insert("reason".to_string(), refused.unwrap_or_default().to_string());
+                self.record(event).await?;
+                return Ok(None);
+            }
+        };
+
+        let (reset, token) = PasswordResetToken::generate(user.id, ttl);
+        let user_key = format!("password_reset_user:{}", user.id);
+        if let Some(previous) = self.load::<String>(&user_key).await? {
+            self.write_db().await?.remove(&format!("password_reset:{}", previous));
+        }
+        self.store(format!("password_reset:{}", reset.token_hash), &reset).await?;
+        self.store(user_key, &reset.token_hash).await?;
+
+        event.target_id = Some(user.id);
+        event.metadata.insert("expires_at".to_string(), reset.expires_at.to_rfc3339());
+        self.record(event).await?;
+        Ok(Some((user, token)))
+    }
+
+    /// Сбросить пароль по токену из письма: новый пароль проверяется политикой, как в `set_password`;
+    /// токен гасится только после успешной смены, чтобы пользователь мог подобрать подходящий пароль
+    #[tracing::instrument(skip_all)]
+    pub async fn confirm_password_reset(&self, token: &str, new_password: &str, source_ip: Option<String>) -> Result<User, DirectoryError> {
+        let key = format!("password_reset:{}", crate::models::password_reset::hash_token(token));
+        let reset = self.load::<PasswordResetToken>(&key).await?;
+
+        let mut event = AuditEvent::new("password_reset", AuditResult::Success);
+        event.ip_addr = source_ip;
+        let reset = match reset {
+            Some(reset) if !reset.is_expired() => reset,
+            expired => {
+                if let Some(reset) = expired {
+                    self.remove_password_reset(&reset).await?;
+                    event.target_id = Some(reset.user_id);
+                }
+                event.result = AuditResult::Failure;
+                event.metadata.insert("reason".to_string(), "invalid_token".to_string());
+                self.record(event).await?;
+                return Err(DirectoryError::InvalidInput("Invalid or expired password reset token".to_string()));
+            }
+        };
+
+        event.target_id = Some(reset.user_id);
+        event.actor_id = Some(reset.user_id);
+        // Отключённой учётной записи ссылка больше не нужна; заблокированная ждёт конца блокировки —
+        // ссылка остаётся в силе до своего срока. Ответ тот же, что на неверную ссылку
+        let user = self.get_user(reset.user_id).await?;
+        let refused = match &user {
+            None => Some("unknown_user"),
+            Some(user) if !user.enabled => Some("disabled"),
+            Some(user) if user.is_locked_out() => Some("locked_out"),
+            Some(_) => None,
+        };
+        if let Some(refused) = refused {
+            if refused != "locked_out" {
+                self.remove_password_reset(&reset).await?;
+            }
+            event.result = AuditResult::Failure;
+            event.metadata.insert("reason".to_string(), refused.to_string());
+            self.record(event).await?;
+            return Err(DirectoryError::InvalidInput("Invalid or expired password reset token".to_string()));
+        }
+        if let Err(e) = self.set_password(reset.user_id, new_password).await {
+            event.result = AuditResult::Failure;
+            event.metadata.insert("reason".to_string(), e.to_string());
+            self.record(event).await?;
+            return Err(e);
+        }
+        self.remove_password_reset(&reset).await?;
+        // Кто бы ни вошёл со старым паролем, его сессии больше не действуют
+        let revoked = self.revoke_user_sessions(reset.user_id, "password_reset").await?;
+        event.metadata.insert("revoked_sessions".to_string(), revoked.to_string());
+        self.record(event).await?;
+        self.get_user(reset.user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))
+    }
+
+    async fn remove_password_reset(&self, reset: &PasswordResetToken) -> Result<(), DirectoryError> {
+        let db = self.write_db().await?;
+        db.remove(&format!("password_reset:{}", reset.token_hash));
+        db.remove(&format!("password_reset_user:{}", reset.user_id));
+        Ok(())
+    }
+
+    /// Установить пароль, сгенерированный самим каталогом (например, ключ krbtgt): случайный пароль
+    /// не проверяется на состав и историю, срок действия — по действующей политике
+    #[tracing::instrument(skip(self, password))]
+    pub async fn set_generated_password(&self, user_id: Uuid, password: &str) -> Result<(), DirectoryError> {
+        let mut user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        let policy = self.resultant_password_policy(&user).await?;
+        let kvno = apply_password(&mut user, password)?;
+        user.password_expires = password_expiry(&user, &policy);
+        self.update_user(&user).await?;
+        self.log_action("set_password", &format!("username:{} kvno:{} generated", user.username, kvno), Some(user_id)).await?;
+        Ok(())
+    }
+
+    /// Уведомить о паролях включённых пользователей: событие `password_expiry_warning` за
+    /// `warning_days` дней до срока и `password_expired` по его наступлении (журнал аудита,
+    /// его приёмники и `/api/events/stream`). Каждое событие отправляется один раз на срок пароля.
+    /// Возвращает число отправленных событий
+    pub async fn notify_password_expiry(&self, warning_days: u32) -> Result<usize, DirectoryError> {
+        let now = Utc::now();
+        let mut sent = 0;
+        for user in self.get_all_users().await? {
+            let Some(expires) = user.password_expires else { continue };
+            if !user.enabled || user.user_account_control.contains(UserAccountControl::DONT_EXPIRE_PASSWORD) {
+                continue;
+            }
+            // Последнее уведомление: о каком сроке и было ли это уже `password_expired`
+            let key = format!("password_expiry_notice:{}", user.id);
+            let notice: Option<(chrono::DateTime<Utc>, bool)> = self.load(&key).await?;
+            let expired = expires <= now;
+            let action = if expired {
+                if notice == Some((expires, true)) {
+                    continue;
+                }
+                "password_expired"
+            } else {
+                if warning_days == 0
+                    || expires - now > chrono::Duration::days(warning_days.into())
+                    || notice.is_some_and(|(noticed, _)| noticed == expires)
+                {
+                    continue;
+                }
+                "password_expiry_warning"
+            };
+
+            let mut event = AuditEvent::new(action, AuditResult::Success);
+            event.target_id = Some(user.id);
+            event.metadata.insert("username".to_string(), user.username.clone());
+            event.metadata.insert("expires_at".to_string(), expires.to_rfc3339());
+            if !expired {
+                event.metadata.insert("days_left".to_string(), (expires - now).num_days().to_string());
+            }
+            if let Some(email) = &user.email {
+                event.metadata.insert("email".to_string(), email.clone());
+            }
+            self.record(event).await?;
+            self.store(key, &(expires, expired)).await?;
+            sent += 1;
+        }
+        Ok(sent)
+    }
+
+    /// Зарегистрировать servicePrincipalName (например `HTTP/web.corp.acme.com`)
+    #[tracing::instrument(skip(self))]
+    pub async fn register_spn(&self, user_id: Uuid, spn: &str) -> Result<(), DirectoryError> {
+        if !spn.contains('/') {
+            return Err(DirectoryError::InvalidInput(format!("Invalid SPN '{}': expected service/host", spn)));
+        }
+        if let Some(existing) = self.find_user_by_spn(spn).await? {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::SpnAlreadyRegistered, format!(
+                "SPN {} already registered to {}",
+                spn, existing.username
+            )));
+        }
+
+        let mut user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        user.service_principal_names.push(spn.to_string());
+        user.updated_at = Utc::now();
+        self.update_user(&user).await?;
+        self.store(lookup_key("spn_index", spn), &user_id).await?;
+
+        self.log_action("register_spn", &format!("spn:{}", spn), Some(user_id)).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn remove_spn(&self, user_id: Uuid, spn: &str) -> Result<(), DirectoryError> {
+        let mut user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        let before = user.service_principal_names.len();
+        user.service_principal_names.retain(|s| !s.eq_ignore_ascii_case(spn));
+        if user.service_principal_names.len() == before {
+            return Err(DirectoryError::NotFound(format!("SPN {} not registered", spn)));
+        }
+
+        user.updated_at = Utc::now();
+        self.update_user(&user).await?;
+        let db = self.write_db().await?;
+        db.remove(&lookup_key("spn_index", spn));
+        drop(db);
+
+        self.log_action("remove_spn", &format!("spn:{}", spn), Some(user_id)).await?;
+        Ok(())
+    }
+
+    pub async fn find_user_by_spn(&self, spn: &str) -> Result<Option<User>, DirectoryError> {
+        let index_key = lookup_key("spn_index", spn);
+        let user_id: Option<Uuid> = self.load(&index_key).await?;
+        if let Some(id) = user_id {
+            self.get_user(id).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Записи keytab для принципала службы (`HTTP/web01` — по SPN, без `/` — по имени учётной
+    /// записи; `@REALM` необязателен). Ключи — текущие ключи учётной записи, включая
+    /// предыдущий kvno на время перекрытия ротации
+    #[tracing::instrument(skip(self))]
+    pub async fn export_keytab(&self, principal: &str) -> Result<Vec<KeytabEntry>, DirectoryError> {
+        let (name, realm) = match principal.rsplit_once('@') {
+            Some((name, realm)) => (name, Some(realm.to_uppercase())),
+            None => (principal, None),
+        };
+        let user = if name.contains('/') {
+            self.find_user_by_spn(name).await?
+        } else {
+            self.find_user_by_username(name).await?
+        };
+        let user = user.ok_or_else(|| DirectoryError::NotFound(format!("No account for principal {}", name)))?;
+        if user.kerberos_keys.is_empty() {
+            return Err(DirectoryError::InvalidInput(format!(
+                "Account {} has no Kerberos keys; set or rotate its password first",
+                user.username
+            )));
+        }
+        let realm = realm
+            .or_else(|| crate::kerberos::realm_from_upn(&user.user_principal_name))
+            .ok_or_else(|| DirectoryError::InvalidInput(format!("Cannot determine realm of {}", user.username)))?;
+
+        let principal_name = match name.split_once('/') {
+            Some((service, instance)) => PrincipalName::service(service, instance),
+            None => PrincipalName::principal(name),
+        };
+        let timestamp = Utc::now().timestamp() as u32;
+        let entries: Vec<KeytabEntry> = user.kerberos_keys.iter()
+            .map(|key| KeytabEntry::new(&principal_name, &realm, key, timestamp))
+            .collect();
+
+        let mut event = AuditEvent::new("export_keytab", AuditResult::Success);
+        event.target_id = Some(user.id);
+        event.metadata.insert("principal".to_string(), format!("{}@{}", name, realm));
+        event.metadata.insert("kvno".to_string(), entries.iter().map(|e| e.kvno).max().unwrap_or(0).to_string());
+        self.record(event).await?;
+        Ok(entries)
+    }
+
+    // ================= SERVICE ACCOUNTS =================
+
+    /// Создать учётную запись службы `username` с id `account.id`: пароль генерирует каталог,
+    /// SPN регистрируются сразу. Пароль выдаётся только через `retrieve_managed_password`
+    #[tracing::instrument(skip(self, account), fields(id = %account.id))]
+    pub async fn create_service_account(&self, username: &str, spns: &[String], account: &ServiceAccount) -> Result<User, DirectoryError> {
+        account.validate()?;
+        if account.allowed_principals.is_empty() {
+            return Err(DirectoryError::InvalidInput("Service account needs at least one principal allowed to retrieve its password".to_string()));
+        }
+        let realm = self.get_all_domains().await?
+            .first()
+            .map(|domain| domain.dns_name.clone())
+            .ok_or_else(|| DirectoryError::NotFound("No domain to create the service account in".to_string()))?;
+
+        let mut account_control = UserAccountControl::NORMAL_ACCOUNT | UserAccountControl::DONT_EXPIRE_PASSWORD | UserAccountControl::PASSWD_CANT_CHANGE;
+        if account.flags.contains(ServiceAccountFlags::NOT_DELEGATED) {
+            account_control |= UserAccountControl::NOT_DELEGATED;
+        }
+        let mut user = User {
+            id: account.id,
+            sid: SecurityIdentifier::new_nt_authority(1001),
+            username: username.to_string(),
+            user_principal_name: format!("{}@{}", username, realm),
+            email: None,
+            display_name: None,
+            given_name: None,
+            surname: None,
+            password_hash: PasswordHash {
+                hash: "!".to_string(),
+                algorithm: PasswordAlgorithm::Bcrypt,
+                salt: vec![],
+            },
+            password_expires: None,
+            last_password_change: Utc::now(),
+            lockout_until: None,
+            failed_logins: 0,
+            enabled: true,
+            mfa_enabled: false,
+            mfa_methods: vec![],
+            domains: vec![],
+            groups: vec![],
+            organizational_unit: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_login: None,
+            profile_path: None,
+            script_path: None,
+            meta: HashMap::new(),
+            primary_group_id: Some(well_known::rid::DOMAIN_USERS),
+            service_principal_names: vec![],
+            kerberos_keys: vec![],
+            user_account_control: account_control,
+            totp_secret: None,
+            totp_pending_secret: None,
+        };
+        apply_password(&mut user, &account.current_password)?;
+
+        self.create_user(&user).await?;
+        self.save_service_account(account).await?;
+        for spn in spns {
+            self.register_spn(user.id, spn).await?;
+        }
+
+        self.log_action(
+            "create_service_account",
+            &format!("username:{} principals:{} interval_days:{}", username, account.allowed_principals.len(), account.rotation_interval_days),
+            Some(user.id),
+        ).await?;
+        self.get_user(user.id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))
+    }
+
+    async fn save_service_account(&self, account: &ServiceAccount) -> Result<(), DirectoryError> {
+        self.store(format!("service_account:{}", account.id), account).await?;
+        let all: Vec<Uuid> = self.load::<Vec<Uuid>>("all_service_accounts_index").await?.unwrap_or_default();
+        if !all.contains(&account.id) {
+            let mut updated = all;
+            updated.push(account.id);
+            self.store("all_service_accounts_index".to_string(), &updated).await?;
+        }
+        Ok(())
+    }
+
+    /// Параметры учётной записи службы по id пользователя; `None` — обычный пользователь
+    pub async fn get_service_account(&self, id: Uuid) -> Result<Option<ServiceAccount>, DirectoryError> {
+        self.load(&format!("service_account:{}", id)).await
+    }
+
+    pub async fn get_all_service_accounts(&self) -> Result<Vec<ServiceAccount>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_service_accounts_index").await?.unwrap_or_default();
+        let mut accounts = Vec::new();
+        for id in ids {
+            if let Some(account) = self.get_service_account(id).await? {
+                accounts.push(account);
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// Изменить флаги, участников и расписание; пароль не меняется
+    #[tracing::instrument(skip(self, account), fields(id = %account.id))]
+    pub async fn update_service_account(&self, account: &ServiceAccount) -> Result<(), DirectoryError> {
+        account.validate()?;
+        let existing = self.get_service_account(account.id).await?
+            .ok_or_else(|| DirectoryError::NotFound("Service account not found".to_string()))?;
+        if account.allowed_principals.is_empty() {
+            return Err(DirectoryError::InvalidInput("Service account needs at least one principal allowed to retrieve its password".to_string()));
+        }
+
+        let mut user = self.get_user(account.id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        let mut flags = user.account_control();
+        flags.set(UserAccountControl::NOT_DELEGATED, account.flags.contains(ServiceAccountFlags::NOT_DELEGATED));
+        if flags != user.account_control() {
+            user.set_account_control(flags);
+            user.updated_at = Utc::now();
+            self.save_user(&user).await?;
+        }
+
+        let mut account = account.clone();
+        // Пароли меняет только ротация
+        account.current_password = existing.current_password;
+        account.previous_password = existing.previous_password;
+        account.password_changed_at = existing.password_changed_at;
+        account.updated_at = Utc::now();
+        self.save_service_account(&account).await?;
+
+        self.log_action(
+            "update_service_account",
+            &format!("username:{} flags:{} principals:{} interval_days:{}", user.username, account.flags.flag_names().join("|"), account.allowed_principals.len(), account.rotation_interval_days),
+            Some(account.id),
+        ).await?;
+        Ok(())
+    }
+
+    /// Сменить пароль сейчас: прежний остаётся в силе до конца окна перекрытия,
+    /// ключи Kerberos прежнего kvno — тоже, чтобы службы успели получить новый пароль
+    #[tracing::instrument(skip(self))]
+    pub async fn rotate_service_account_password(&self, id: Uuid) -> Result<ServiceAccount, DirectoryError> {
+        let mut account = self.get_service_account(id).await?
+            .ok_or_else(|| DirectoryError::NotFound("Service account not found".to_string()))?;
+        let mut user = self.get_user(id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+
+        account.rotate();
+        let previous_kvno = user.kerberos_keys.iter().map(|key| key.kvno).max();
+        let previous_keys: Vec<KerberosKey> = user.kerberos_keys.iter()
+            .filter(|key| Some(key.kvno) == previous_kvno)
+            .cloned()
+            .collect();
+        let kvno = apply_password(&mut user, &account.current_password)?;
+        user.kerberos_keys.extend(previous_keys);
+
+        self.save_user(&user).await?;
+        self.save_service_account(&account).await?;
+        self.log_action("rotate_service_account_password", &format!("username:{} kvno:{}", user.username, kvno), Some(id)).await?;
+        Ok(account)
+    }
+
+    /// Сменить пароли, срок которых подошёл, и забыть прежние после окна перекрытия;
+    /// возвращает число сменённых паролей
+    pub async fn rotate_due_service_accounts(&self) -> Result<usize, DirectoryError> {
+        let now = Utc::now();
+        let mut rotated = 0;
+        for mut account in self.get_all_service_accounts().await? {
+            if account.rotation_due(now) {
+                self.rotate_service_account_password(account.id).await?;
+                rotated += 1;
+            } else if account.previous_password.is_some() && account.previous_password_valid(now).is_none() {
+                account.previous_password = None;
+                self.save_service_account(&account).await?;
+                if let Some(mut user) = self.get_user(account.id).await? {
+                    let current = user.kerberos_keys.iter().map(|key| key.kvno).max();
+                    user.kerberos_keys.retain(|key| Some(key.kvno) == current);
+                    self.save_user(&user).await?;
+                }
+            }
+        }
+        Ok(rotated)
+    }
+
+    /// Пароль учётной записи службы для `caller`: только если он сам или одна из его групп
+    /// есть в `allowed_principals`. Каждая попытка, удачная или нет, попадает в аудит
+    #[tracing::instrument(skip_all, fields(caller = %caller.username, id = %id))]
+    pub async fn retrieve_managed_password(&self, caller: &User, id: Uuid) -> Result<ManagedPassword, DirectoryError> {
+        let account = self.get_service_account(id).await?
+            .ok_or_else(|| DirectoryError::NotFound("Service account not found".to_string()))?;
+        let sids = self.principal_sids(caller).await?;
+        let allowed = account.allowed_principals.iter().any(|principal| sids.contains(principal));
+
+        let mut event = AuditEvent::new("retrieve_managed_password", if allowed { AuditResult::Success } else { AuditResult::Failure });
+        event.actor_id = Some(caller.id);
+        event.target_id = Some(id);
+        event.metadata.insert("caller".to_string(), caller.username.clone());
+        self.record(event).await?;
+
+        if !allowed {
+            return Err(DirectoryError::AccessDenied(format!("{} may not retrieve the password of service account {}", caller.username, id)));
+        }
+        Ok(account.managed_password(Utc::now()))
+    }
+
+    // ================= PASSWORD SETTINGS OBJECTS (PSO) =================
+
+    /// Создать или изменить PSO; имя уникально, группы и OU должны существовать
+    #[tracing::instrument(skip(self, pso), fields(name = %pso.name))]
+    pub async fn save_pso(&self, pso: &PasswordSettingsObject) -> Result<(), DirectoryError> {
+        if pso.name.trim().is_empty() {
+            return Err(DirectoryError::InvalidInput("Password settings name cannot be empty".to_string()));
+        }
+        if pso.policy.lockout_threshold > 0 && pso.policy.lockout_duration_minutes == 0 {
+            return Err(DirectoryError::InvalidInput("Lockout duration must be at least one minute".to_string()));
+        }
+        if let Some(existing) = self.find_pso_by_name(&pso.name).await?
+            && existing.id != pso.id
+        {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyExists, format!("Password settings {} already exist", pso.name)));
+        }
+        for group_id in &pso.applies_to_groups {
+            if self.get_group(*group_id).await?.is_none() {
+                return Err(DirectoryError::NotFound(format!("Group not found: {}", group_id)));
+            }
+        }
+        for ou_id in &pso.applies_to_ous {
+            if self.get_ou(*ou_id).await?.is_none() {
+                return Err(DirectoryError::NotFound(format!("OU not found: {}", ou_id)));
+            }
+        }
+
+        let mut pso = pso.clone();
+        pso.updated_at = Utc::now();
+        self.store(format!("pso:{}", pso.id), &pso).await?;
+        let all: Vec<Uuid> = self.load::<Vec<Uuid>>("all_pso_index").await?.unwrap_or_default();
+        if !all.contains(&pso.id) {
+            let mut updated = all;
+            updated.push(pso.id);
+            self.store("all_pso_index".to_string(), &updated).await?;
+        }
+
+        self.log_action("save_pso", &format!("name:{} precedence:{}", pso.name, pso.precedence), Some(pso.id)).await?;
+        Ok(())
+    }
+
+    pub async fn get_pso(&self, id: Uuid) -> Result<Option<PasswordSettingsObject>, DirectoryError> {
+        self.load(&format!("pso:{}", id)).await
+    }
+
+    pub async fn find_pso_by_name(&self, name: &str) -> Result<Option<PasswordSettingsObject>, DirectoryError> {
+        Ok(self.get_all_psos().await?.into_iter().find(|pso| pso.name.eq_ignore_ascii_case(name)))
+    }
+
+    /// Все PSO по возрастанию `precedence`
+    pub async fn get_all_psos(&self) -> Result<Vec<PasswordSettingsObject>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_pso_index").await?.unwrap_or_default();
+        let mut psos = Vec::new();
+        for id in ids {
+            if let Some(pso) = self.get_pso(id).await? {
+                psos.push(pso);
+            }
+        }
+        psos.sort_by_key(|pso| (pso.precedence, pso.id));
+        Ok(psos)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_pso(&self, id: Uuid) -> Result<(), DirectoryError> {
+        let pso = self.get_pso(id).await?.ok_or_else(|| DirectoryError::NotFound(format!("Password settings not found: {}", id)))?;
+        let all: Vec<Uuid> = self.load::<Vec<Uuid>>("all_pso_index").await?.unwrap_or_default();
+        let updated: Vec<Uuid> = all.into_iter().filter(|pso_id| *pso_id != id).collect();
+        self.store("all_pso_index".to_string(), &updated).await?;
+
+        let db = self.write_db().await?;
+        remove_object(&db, &self.cache, &format!("pso:{}", id))?;
+        drop(db);
+
+        self.log_action("delete_pso", &format!("name:{}", pso.name), Some(id)).await?;
+        Ok(())
+    }
+
+    /// msDS-ResultantPSO: PSO с наименьшим `precedence` среди назначенных группам пользователя
+    /// и OU, в которых он находится (с вложенностью); при равенстве — с меньшим id, как в AD
+    pub async fn resultant_pso(&self, user: &User) -> Result<Option<PasswordSettingsObject>, DirectoryError> {
+        let psos = self.get_all_psos().await?;
+        if psos.is_empty() {
+            return Ok(None);
+        }
+
+        let token_groups = self.get_token_groups(user.id).await?;
+        let mut ous = Vec::new();
+        let mut current = user.organizational_unit;
+        while let Some(ou_id) = current {
+            if ous.contains(&ou_id) {
+                break;
+            }
+            ous.push(ou_id);
+            current = self.get_ou(ou_id).await?.and_then(|ou| ou.parent);
+        }
+
+        for pso in psos {
+            if pso.applies_to_ous.iter().any(|ou_id| ous.contains(ou_id)) {
+                return Ok(Some(pso));
+            }
+            for group_id in &pso.applies_to_groups {
+                if let Some(group) = self.get_group(*group_id).await?
+                    && token_groups.contains(&group.sid)
+                {
+                    return Ok(Some(pso));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Парольная политика, действующая для пользователя: его PSO или политика домена
+    pub async fn resultant_password_policy(&self, user: &User) -> Result<PasswordPolicy, DirectoryError> {
+        Ok(self.resultant_pso(user).await?.map(|pso| pso.policy).unwrap_or_else(|| self.password_policy.read().unwrap().clone()))
+    }
+
+    // ================= GROUPS =================
+
+    #[tracing::instrument(skip_all, fields(sam_account_name = %group.sam_account_name))]
+    pub async fn create_group(&self, group: &Group) -> Result<(), DirectoryError> {
+        let mut group = group.clone();
+        if let Some(sid) = self.allocate_sid(&group.sid).await? {
+            group.sid = sid;
+        }
+        let group = &group;
+        if let Some(existing) = self.find_group_by_sam_account_name(&group.sam_account_name).await? {
+            if existing.id != group.id {
+                return Err(DirectoryError::AlreadyExists(ErrorCode::GroupAlreadyExists, format!(
+                    "Group {} already exists",
+                    group.sam_account_name
+                )));
+            }
+        }
+        self.check_sid_unique(&group.sid, group.id).await?;
+        self.validate_attributes(SchemaClass::Group, &group.meta).await?;
+        let previous_group = self.get_group(group.id).await?;
+        let previous = previous_group.as_ref().map(|previous| previous.meta.clone());
+
+        // Динамическая группа: снимок участников сразу вычисляется по правилу
+        let mut group = group.clone();
+        if let Some(filter) = group.membership_filter()? {
+            group.members = self.evaluate_membership_rule(&filter).await?;
+        }
+        let group = &group;
+        for member_id in &group.members {
+            if previous_group.as_ref().is_some_and(|previous| previous.members.contains(member_id))
+                || self.get_user(*member_id).await?.is_some()
+            {
+                continue;
+            }
+            let member = self.get_group(*member_id).await?
+                .ok_or_else(|| DirectoryError::NotFound(format!("Member not found: {} is neither a user nor a group", member_id)))?;
+            self.check_nesting(group, &member).await?;
+        }
+        self.set_dynamic_group_indexed(group.id, group.is_dynamic()).await?;
+
+        let key = format!("group:{}", group.id);
+        self.store(key, group).await?;
+        self.reindex_attributes(group.id, previous.as_ref(), Some(&group.meta)).await?;
+        self.store(lookup_key("sam_account_name_index", &group.sam_account_name), &group.id).await?;
+        self.index_sid(group.id, previous_group.as_ref().map(|previous| &previous.sid), Some(&group.sid)).await?;
+
+        for member_id in &group.members {
+            self.add_member_to_index(*member_id, group.id).await?;
+        }
+        for member_id in previous_group.iter().flat_map(|previous| &previous.members) {
+            if !group.members.contains(member_id) {
+                self.remove_member_from_index(*member_id, group.id).await?;
+            }
+        }
+
+        let all_groups: Vec<Uuid> = self.load::<Vec<Uuid>>("all_groups_index").await?.unwrap_or_default();
+        if !all_groups.contains(&group.id) {
+            let mut updated = all_groups;
+            updated.push(group.id);
+            self.store("all_groups_index".to_string(), &updated).await?;
+        }
+
+        self.log_action("create_group", &format!("sam_account_name:{}", group.sam_account_name), None).await?;
+        Ok(())
+    }
+
+    pub async fn get_group(&self, id: Uuid) -> Result<Option<Group>, DirectoryError> {
+        let key = format!("group:{}", id);
+        self.load_cached(&key).await
+    }
+
+    pub async fn find_group_by_sam_account_name(&self, sam_account_name: &str) -> Result<Option<Group>, DirectoryError> {
+        let key = lookup_key("sam_account_name_index", sam_account_name);
+        let group_id: Option<Uuid> = self.load(&key).await?;
+        if let Some(id) = group_id {
+            self.get_group(id).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Изменить имя, описание, область, тип и атрибуты группы. Участники, правило членства,
+    /// владелец и срок меняются своими методами и берутся из базы
+    #[tracing::instrument(skip_all, fields(sam_account_name = %group.sam_account_name))]
+    pub async fn update_group(&self, group: &Group) -> Result<Group, DirectoryError> {
+        let previous = self.get_group(group.id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+        if group.sam_account_name != previous.sam_account_name {
+            return Err(DirectoryError::InvalidInput("sAMAccountName of a group cannot be changed".to_string()));
+        }
+        if group.name.is_empty() {
+            return Err(DirectoryError::InvalidInput("Group name cannot be empty".to_string()));
+        }
+        check_group_conversion(&previous, group)?;
+        if previous.scope != group.scope {
+            self.check_scope_change(&previous, group.scope).await?;
+        }
+        self.validate_attributes(SchemaClass::Group, &group.meta).await?;
+
+        let updated = Group {
+            members: previous.members.clone(),
+            membership_rule: previous.membership_rule.clone(),
+            managed_by: previous.managed_by,
+            expires_at: previous.expires_at,
+            disabled_at: previous.disabled_at,
+            ..group.clone()
+        };
+        self.store(format!("group:{}", updated.id), &updated).await?;
+        self.reindex_attributes(updated.id, Some(&previous.meta), Some(&updated.meta)).await?;
+
+        let details = format!("group:{} scope:{:?} security:{}", updated.sam_account_name, updated.scope, updated.is_security_group());
+        self.log_action("update_group", &details, Some(updated.id)).await?;
+        Ok(updated)
+    }
+
+    /// Группа по objectSid; SID пользователя даёт `None`
+    pub async fn find_group_by_sid(&self, sid: &SecurityIdentifier) -> Result<Option<Group>, DirectoryError> {
+        match self.find_id_by_sid(sid).await? {
+            Some(id) => self.get_group(id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Добавить участника: пользователя (учётные записи служб и компьютеров — тоже пользователи)
+    /// или группу, если её область допускает вложение и не получается цикл. С `ttl` членство
+    /// временное: по истечении срока участника удаляет `expire_memberships`; повторное добавление
+    /// задаёт новый срок, без `ttl` — делает членство постоянным
+    #[tracing::instrument(skip(self))]
+    pub async fn add_member_to_group(&self, group_id: Uuid, user_id: Uuid, ttl: Option<chrono::Duration>) -> Result<(), DirectoryError> {
+        let mut group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+        reject_dynamic(&group)?;
+        let expires_at = match ttl {
+            Some(ttl) if ttl <= chrono::Duration::zero() => {
+                return Err(DirectoryError::InvalidInput("Membership TTL must be positive".to_string()));
+            }
+            ttl => ttl.map(|ttl| Utc::now() + ttl),
+        };
+        match self.get_user(user_id).await? {
+            Some(user) if expires_at.is_some() && user.primary_group_id == Some(group.get_rid()) => {
+                return Err(DirectoryError::InvalidInput(format!(
+                    "Group {} is the primary group of {}; its membership cannot expire",
+                    group.sam_account_name, user.username
+                )));
+            }
+            Some(_) => {}
+            None => {
+                let member = self.get_group(user_id).await?
+                    .ok_or_else(|| DirectoryError::NotFound(format!("Member not found: {} is neither a user nor a group", user_id)))?;
+                self.check_nesting(&group, &member).await?;
+            }
+        }
+
+        let added = !group.members.contains(&user_id);
+        if !added && group.member_expirations.get(&user_id) == expires_at.as_ref() {
+            return Ok(());
+        }
+        if added {
+            group.members.push(user_id);
+        }
+        let mut details = format!("group:{} user:{}", group.sam_account_name, user_id);
+        match expires_at {
+            Some(expires_at) => {
+                group.member_expirations.insert(user_id, expires_at);
+                details.push_str(&format!(" expires:{}", expires_at.to_rfc3339()));
+            }
+            None => {
+                group.member_expirations.remove(&user_id);
+            }
+        }
+        self.store(format!("group:{}", group.id), &group).await?;
+        if added {
+            self.add_member_to_index(user_id, group.id).await?;
+        }
+        self.log_action("add_member_to_group", &details, Some(user_id)).await?;
+        Ok(())
+    }
+
+    /// Удалить участника; из основной группы пользователя — нельзя, сначала её нужно сменить
+    #[tracing::instrument(skip(self))]
+    pub async fn remove_member_from_group(&self, group_id: Uuid, user_id: Uuid) -> Result<(), DirectoryError> {
+        let group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+        reject_dynamic(&group)?;
+        if let Some(user) = self.get_user(user_id).await?
+            && user.primary_group_id == Some(group.get_rid())
+        {
+            return Err(DirectoryError::InvalidInput(format!(
+                "Group {} is the primary group of {}; set another primary group first",
+                group.sam_account_name, user.username
+            )));
+        }
+        self.remove_member(group, user_id).await
+    }
+
+    async fn remove_member(&self, mut group: Group, user_id: Uuid) -> Result<(), DirectoryError> {
+        if group.members.contains(&user_id) {
+            group.members.retain(|id| id != &user_id);
+            group.member_expirations.remove(&user_id);
+            self.store(format!("group:{}", group.id), &group).await?;
+            self.remove_member_from_index(user_id, group.id).await?;
+            self.log_action("remove_member_from_group", &format!("group:{} user:{}", group.sam_account_name, user_id), Some(user_id)).await?;
+        }
+        Ok(())
+    }
+
+    /// Вложить группу `member` в `group` по правилам AD: Global содержит только Global,
+    /// Universal — Global и Universal, DomainLocal — любые; группа не вкладывается сама в себя
+    /// и в свои же вложенные группы
+    async fn check_nesting(&self, group: &Group, member: &Group) -> Result<(), DirectoryError> {
+        if !group.scope.can_contain(member.scope) {
+            return Err(DirectoryError::InvalidInput(format!(
+                "{:?} group {} cannot contain {:?} group {}",
+                group.scope, group.sam_account_name, member.scope, member.sam_account_name
+            )));
+        }
+        if member.id == group.id || self.parent_groups(group.id).await?.contains(&member.id) {
+            return Err(DirectoryError::InvalidInput(format!(
+                "Group {} already contains {}; nesting them would create a cycle",
+                member.sam_account_name, group.sam_account_name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Смена области допустима, если новая область может содержать вложенные группы,
+    /// а группы, в которые вложена эта, могут содержать её с новой областью
+    async fn check_scope_change(&self, group: &Group, scope: GroupScope) -> Result<(), DirectoryError> {
+        for member_id in &group.members {
+            if let Some(member) = self.get_group(*member_id).await?
+                && !scope.can_contain(member.scope)
+            {
+                return Err(DirectoryError::InvalidInput(format!(
+                    "Group {} cannot be converted to {:?}: it contains {:?} group {}",
+                    group.sam_account_name, scope, member.scope, member.sam_account_name
+                )));
+            }
+        }
+        let parents: HashSet<Uuid> = self.load(&format!("member_index:{}", group.id)).await?.unwrap_or_default();
+        for parent_id in parents {
+            if let Some(parent) = self.get_group(parent_id).await?
+                && !parent.scope.can_contain(scope)
+            {
+                return Err(DirectoryError::InvalidInput(format!(
+                    "Group {} cannot be converted to {:?}: it is a member of {:?} group {}",
+                    group.sam_account_name, scope, parent.scope, parent.sam_account_name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Сменить область группы по правилам AD; ошибка называет группу, которая мешает
+    #[tracing::instrument(skip(self))]
+    pub async fn convert_group_scope(&self, group_id: Uuid, scope: GroupScope) -> Result<Group, DirectoryError> {
+        let mut group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+        group.scope = scope;
+        self.update_group(&group).await
+    }
+
+    /// Группы, в которые объект входит напрямую или через вложенные группы
+    async fn parent_groups(&self, member_id: Uuid) -> Result<HashSet<Uuid>, DirectoryError> {
+        let mut parents = HashSet::new();
+        let mut queue = vec![member_id];
+        while let Some(id) = queue.pop() {
+            let direct: HashSet<Uuid> = self.load(&format!("member_index:{}", id)).await?.unwrap_or_default();
+            queue.extend(direct.into_iter().filter(|parent| parents.insert(*parent)));
+        }
+        Ok(parents)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_group(&self, group_id: Uuid) -> Result<(), DirectoryError> {
+        let group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+        if group.is_protected() {
+            return Err(DirectoryError::Protected(format!("Group {} is a built-in group and cannot be deleted", group.sam_account_name)));
+        }
+        let rid = group.get_rid();
+        if let Some(user) = self.get_all_users().await?.into_iter().find(|user| user.primary_group_id == Some(rid)) {
+            return Err(DirectoryError::InvalidInput(format!(
+                "Group {} is the primary group of {}; set another primary group first",
+                group.sam_account_name, user.username
+            )));
+        }
+        let sam_key = lookup_key("sam_account_name_index", &group.sam_account_name);
+
+        let all_groups: Vec<Uuid> = self.load::<Vec<Uuid>>("all_groups_index").await?.unwrap_or_default();
+        let updated_groups: Vec<Uuid> = all_groups.into_iter().filter(|id| *id != group_id).collect();
+        self.store("all_groups_index".to_string(), &updated_groups).await?;
+
+        for user_id in &group.members {
+            self.remove_member_from_index(*user_id, group.id).await?;
+        }
+        let parents: HashSet<Uuid> = self.load(&format!("member_index:{}", group_id)).await?.unwrap_or_default();
+        for parent_id in parents {
+            self.remove_member_from_group(parent_id, group_id).await?;
+        }
+        self.set_dynamic_group_indexed(group.id, false).await?;
+        let request_ids: Vec<Uuid> = self.load::<Vec<Uuid>>(&format!("group_requests_index:{}", group_id)).await?.unwrap_or_default();
+
+        let db = self.write_db().await?;
+        for request_id in request_ids {
+            db.remove(&format!("group_request:{}", request_id));
+        }
+        db.remove(&format!("group_requests_index:{}", group_id));
+        remove_object(&db, &self.cache, &format!("group:{}", group_id))?;
+        db.remove(&sam_key);
+        if let Some(sid_key) = sid_index_key(&group.sid) {
+            db.remove(&sid_key);
+        }
+        db.remove(&format!("security_descriptor:{}", group_id));
+        drop(db);
+        self.reindex_attributes(group_id, Some(&group.meta), None).await?;
+
+        self.log_action("delete_group", &format!("group:{}", group.sam_account_name), None).await?;
+        Ok(())
+    }
+
+    /// Группы пользователя: статические по индексу членства и динамические, чьё правило
+    /// подходит к пользователю сейчас (снимок `members` для них не используется)
+    pub async fn find_groups_by_member(&self, user_id: Uuid) -> Result<Vec<Group>, DirectoryError> {
+        let mut groups = self.find_static_groups_by_member(user_id).await?;
+
+        let dynamic_ids: Vec<Uuid> = self.load::<Vec<Uuid>>("dynamic_groups_index").await?.unwrap_or_default();
+        if dynamic_ids.is_empty() {
+            return Ok(groups);
+        }
+        let Some(user) = self.get_user(user_id).await? else {
+            return Ok(groups);
+        };
+        let entry = self.membership_rule_entry(&user, &groups).await?;
+        for id in dynamic_ids {
+            let Some(group) = self.get_group(id).await?.filter(|group| !group.is_disabled()) else { continue };
+            match group.membership_filter() {
+                Ok(Some(filter)) if filter.matches(&entry) => groups.push(group),
+                Ok(_) => {}
+                Err(e) => tracing::warn!(group = %group.sam_account_name, error = %e, "Invalid membership rule"),
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Группы с явным списком участников, в которых состоит пользователь
+    async fn find_static_groups_by_member(&self, user_id: Uuid) -> Result<Vec<Group>, DirectoryError> {
+        let group_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&format!("member_index:{}", user_id)).await?.unwrap_or_else(|| HashSet::new());
         let mut groups = Vec::new();
         for id in group_ids {
-            if let Some(group) = self.get_group(id).await? {
+            if let Some(group) = self.get_group(id).await?
+                && !group.is_dynamic()
+                && !group.is_disabled()
+            {
                 groups.push(group);
             }
         }
-        Ok(groups)
+        Ok(groups)
+    }
+
+    /// Входит ли пользователь в группу Domain Admins (права администратора REST API)
+    pub async fn is_domain_admin(&self, user_id: Uuid) -> Result<bool, DirectoryError> {
+        Ok(self.find_groups_by_member(user_id).await?
+            .iter()
+            .any(|g| g.sam_account_name.eq_ignore_ascii_case(DOMAIN_ADMINS_SAM)))
+    }
+
+    pub async fn get_all_groups(&self) -> Result<Vec<Group>, DirectoryError> {
+        Ok(self.scan("all_groups_index", "group:", 0, usize::MAX).await?.items)
+    }
+
+    /// Все группы в пределах `limits`, как `list_users_within`
+    pub async fn list_groups_within(&self, limits: SearchLimits) -> Result<Vec<Group>, DirectoryError> {
+        self.collect_within("all_groups_index", "group:", limits).await
+    }
+
+    async fn add_member_to_index(&self, user_id: Uuid, group_id: Uuid) -> Result<(), DirectoryError> {
+        let key = format!("member_index:{}", user_id);
+        let mut group_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&key).await?.unwrap_or_else(|| HashSet::new());
+        group_ids.insert(group_id);
+        self.store(key, &group_ids).await
+    }
+
+    async fn remove_member_from_index(&self, user_id: Uuid, group_id: Uuid) -> Result<(), DirectoryError> {
+        let key = format!("member_index:{}", user_id);
+        let mut group_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&key).await?.unwrap_or_else(|| HashSet::new());
+        group_ids.remove(&group_id);
+        self.store(key, &group_ids).await
+    }
+
+    // ================= DYNAMIC GROUPS =================
+
+    /// Запись пользователя, к которой применяются правила членства: хранимые атрибуты и memberOf
+    /// статических групп (правило не может ссылаться на другую динамическую группу)
+    async fn membership_rule_entry(&self, user: &User, static_groups: &[Group]) -> Result<LdapAttributes, DirectoryError> {
+        let mut entry = user.stored_attributes();
+        entry.insert_identity(&self.user_dn(user).await?, user.object_classes(), user.id);
+        let mut member_of = Vec::new();
+        for group in static_groups {
+            member_of.push(self.group_dn(group).await?);
+        }
+        if !member_of.is_empty() {
+            entry.insert("memberOf", member_of);
+        }
+        Ok(entry)
+    }
+
+    /// Пользователи, подходящие под правило членства
+    pub async fn evaluate_membership_rule(&self, filter: &LdapFilter) -> Result<Vec<Uuid>, DirectoryError> {
+        let mut members = Vec::new();
+        for user in self.get_all_users().await? {
+            let static_groups = self.find_static_groups_by_member(user.id).await?;
+            if filter.matches(&self.membership_rule_entry(&user, &static_groups).await?) {
+                members.push(user.id);
+            }
+        }
+        Ok(members)
+    }
+
+    /// Задать или снять правило членства; снимок участников пересчитывается сразу.
+    /// Без правила группа становится статической с участниками из последнего снимка
+    #[tracing::instrument(skip(self))]
+    pub async fn set_group_membership_rule(&self, group_id: Uuid, rule: Option<String>) -> Result<Group, DirectoryError> {
+        let mut group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+        let rule = rule.map(|rule| LdapFilter::parse(&rule)).transpose()?;
+        if rule.is_none()
+            && let Some(previous) = group.membership_filter()?
+        {
+            group.members = self.evaluate_membership_rule(&previous).await?;
+        }
+        group.membership_rule = rule.map(|filter| filter.to_string());
+        self.create_group(&group).await?;
+
+        let details = format!("group:{} rule:{}", group.sam_account_name, group.membership_rule.as_deref().unwrap_or("-"));
+        self.log_action("set_group_membership_rule", &details, Some(group_id)).await?;
+        self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))
+    }
+
+    /// Пересчитать снимок участников динамической группы; возвращает, изменился ли он
+    #[tracing::instrument(skip(self))]
+    pub async fn refresh_dynamic_group(&self, group_id: Uuid) -> Result<bool, DirectoryError> {
+        let mut group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+        let Some(filter) = group.membership_filter()? else {
+            return Err(DirectoryError::InvalidInput(format!("Group {} has no membership rule", group.sam_account_name)));
+        };
+        let members = self.evaluate_membership_rule(&filter).await?;
+        let previous: HashSet<Uuid> = group.members.iter().copied().collect();
+        let current: HashSet<Uuid> = members.iter().copied().collect();
+        if previous == current {
+            return Ok(false);
+        }
+
+        for user_id in previous.difference(&current) {
+            self.remove_member_from_index(*user_id, group.id).await?;
+        }
+        for user_id in current.difference(&previous) {
+            self.add_member_to_index(*user_id, group.id).await?;
+        }
+        let details = format!(
+            "group:{} added:{} removed:{}",
+            group.sam_account_name,
+            current.difference(&previous).count(),
+            previous.difference(&current).count(),
+        );
+        group.members = members;
+        self.store(format!("group:{}", group.id), &group).await?;
+
+        self.log_action("refresh_dynamic_group", &details, Some(group_id)).await?;
+        Ok(true)
+    }
+
+    /// Пересчитать снимки всех динамических групп; возвращает число изменившихся
+    pub async fn refresh_dynamic_groups(&self) -> Result<usize, DirectoryError> {
+        let mut changed = 0;
+        for id in self.load::<Vec<Uuid>>("dynamic_groups_index").await?.unwrap_or_default() {
+            if self.refresh_dynamic_group(id).await? {
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    async fn set_dynamic_group_indexed(&self, group_id: Uuid, dynamic: bool) -> Result<(), DirectoryError> {
+        let mut ids: Vec<Uuid> = self.load::<Vec<Uuid>>("dynamic_groups_index").await?.unwrap_or_default();
+        let indexed = ids.contains(&group_id);
+        if dynamic && !indexed {
+            ids.push(group_id);
+        } else if !dynamic && indexed {
+            ids.retain(|id| *id != group_id);
+        } else {
+            return Ok(());
+        }
+        self.store("dynamic_groups_index".to_string(), &ids).await
+    }
+
+    // ================= GROUP OWNERS, EXPIRATION AND JOIN REQUESTS =================
+
+    /// Задать владельца и срок действия группы; новый срок в будущем (или его снятие)
+    /// снова включает группу, отключённую по сроку
+    #[tracing::instrument(skip(self))]
+    pub async fn set_group_lifecycle(&self, group_id: Uuid, managed_by: Option<Uuid>, expires_at: Option<chrono::DateTime<Utc>>) -> Result<Group, DirectoryError> {
+        let mut group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+        if let Some(owner_id) = managed_by
+            && self.get_user(owner_id).await?.is_none()
+        {
+            return Err(DirectoryError::NotFound(format!("Owner not found: {}", owner_id)));
+        }
+        if expires_at.is_some() && group.is_protected() {
+            return Err(DirectoryError::Protected(format!("Group {} is a built-in group and cannot expire", group.sam_account_name)));
+        }
+
+        group.managed_by = managed_by;
+        group.expires_at = expires_at;
+        if expires_at.is_none_or(|expires_at| expires_at > Utc::now()) {
+            group.disabled_at = None;
+        }
+        self.store(format!("group:{}", group.id), &group).await?;
+
+        let details = format!(
+            "group:{} owner:{} expires:{}",
+            group.sam_account_name,
+            managed_by.map(|id| id.to_string()).unwrap_or_else(|| "-".to_string()),
+            expires_at.map(|at| at.to_rfc3339()).unwrap_or_else(|| "-".to_string()),
+        );
+        self.log_action("set_group_lifecycle", &details, Some(group_id)).await?;
+        Ok(group)
+    }
+
+    /// Может ли пользователь решать заявки группы: владелец или право `WRITE_PROPERTY` на группу
+    pub async fn can_manage_group_membership(&self, user: &User, group: &Group) -> Result<bool, DirectoryError> {
+        if group.managed_by == Some(user.id) {
+            return Ok(true);
+        }
+        let sids = self.principal_sids(user).await?;
+        self.check_access(&sids, SecuredObject::Group(group.id), AccessMask::WRITE_PROPERTY).await
+    }
+
+    /// Заявка пользователя на вступление в группу
+    #[tracing::instrument(skip(self, justification))]
+    pub async fn request_group_membership(&self, group_id: Uuid, user_id: Uuid, justification: Option<String>) -> Result<GroupJoinRequest, DirectoryError> {
+        let group = self.get_group(group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+        reject_dynamic(&group)?;
+        if group.is_disabled() {
+            return Err(DirectoryError::InvalidInput(format!("Group {} is disabled", group.sam_account_name)));
+        }
+        let user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+        if group.members.contains(&user_id) {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyMember, format!("{} is already a member of {}", user.username, group.sam_account_name)));
+        }
+        if self.get_group_join_requests(group_id).await?.iter().any(|request| request.user_id == user_id && request.is_pending()) {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::RequestAlreadyPending, format!("{} already has a pending request for {}", user.username, group.sam_account_name)));
+        }
+
+        let request = GroupJoinRequest::new(group_id, user_id, justification);
+        self.store(format!("group_request:{}", request.id), &request).await?;
+        let index_key = format!("group_requests_index:{}", group_id);
+        let mut ids: Vec<Uuid> = self.load::<Vec<Uuid>>(&index_key).await?.unwrap_or_default();
+        ids.push(request.id);
+        self.store(index_key, &ids).await?;
+
+        self.log_action("request_group_membership", &format!("group:{} user:{} request:{}", group.sam_account_name, user.username, request.id), Some(user_id)).await?;
+        Ok(request)
+    }
+
+    pub async fn get_group_join_request(&self, id: Uuid) -> Result<Option<GroupJoinRequest>, DirectoryError> {
+        self.load(&format!("group_request:{}", id)).await
+    }
+
+    /// Заявки группы от старых к новым
+    pub async fn get_group_join_requests(&self, group_id: Uuid) -> Result<Vec<GroupJoinRequest>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>(&format!("group_requests_index:{}", group_id)).await?.unwrap_or_default();
+        let mut requests = Vec::new();
+        for id in ids {
+            if let Some(request) = self.get_group_join_request(id).await? {
+                requests.push(request);
+            }
+        }
+        requests.sort_by_key(|request| request.requested_at);
+        Ok(requests)
+    }
+
+    /// Одобрить или отклонить заявку; при одобрении пользователь добавляется в группу.
+    /// Права решающего проверяет вызывающий (`can_manage_group_membership`); свою заявку не
+    /// решить даже владельцу группы. Вступление в административную группу под
+    /// `security.approvals` так не одобрить — только заявкой на добавление участника
+    #[tracing::instrument(skip(self, comment))]
+    pub async fn decide_group_join_request(&self, request_id: Uuid, approve: bool, decided_by: Option<Uuid>, comment: Option<String>) -> Result<GroupJoinRequest, DirectoryError> {
+        let mut request = self.get_group_join_request(request_id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Membership request not found: {}", request_id)))?;
+        if !request.is_pending() {
+            return Err(DirectoryError::InvalidInput(format!("Membership request {} is already decided", request_id)));
+        }
+        if decided_by == Some(request.user_id) {
+            return Err(DirectoryError::AccessDenied(format!("Membership request {} must be decided by someone other than the requester", request_id)));
+        }
+        if approve {
+            let operation = SensitiveOperation::AddGroupMember { group_id: request.group_id, member_id: request.user_id, ttl_secs: None };
+            if self.requires_approval(&operation).await? {
+                return Err(DirectoryError::AccessDenied(format!(
+                    "Membership request {} is for a privileged group; adding members there requires approval",
+                    request_id
+                )));
+            }
+            self.add_member_to_group(request.group_id, request.user_id, None).await?;
+        }
+
+        request.status = if approve { JoinRequestStatus::Approved } else { JoinRequestStatus::Denied };
+        request.decided_by = decided_by;
+        request.decided_at = Some(Utc::now());
+        request.comment = comment;
+        self.store(format!("group_request:{}", request.id), &request).await?;
+
+        let mut event = AuditEvent::new(if approve { "approve_group_membership" } else { "deny_group_membership" }, AuditResult::Success);
+        event.actor_id = decided_by;
+        event.target_id = Some(request.user_id);
+        event.metadata.insert("group_id".to_string(), request.group_id.to_string());
+        event.metadata.insert("request_id".to_string(), request.id.to_string());
+        self.record(event).await?;
+        Ok(request)
+    }
+
+    /// Отключить группы с истёкшим сроком; владелец узнаёт об этом из события `group_expired`
+    /// (журнал аудита, `/api/events/stream`). Возвращает число отключённых групп
+    pub async fn expire_groups(&self) -> Result<usize, DirectoryError> {
+        let now = Utc::now();
+        let mut expired = 0;
+        for mut group in self.get_all_groups().await? {
+            if !group.is_expired(now) {
+                continue;
+            }
+            group.disabled_at = Some(now);
+            self.store(format!("group:{}", group.id), &group).await?;
+
+            let mut event = AuditEvent::new("group_expired", AuditResult::Success);
+            event.target_id = Some(group.id);
+            event.metadata.insert("group".to_string(), group.sam_account_name.clone());
+            if let Some(expires_at) = group.expires_at {
+                event.metadata.insert("expires_at".to_string(), expires_at.to_rfc3339());
+            }
+            if let Some(owner) = match group.managed_by {
+                Some(owner_id) => self.get_user(owner_id).await?,
+                None => None,
+            } {
+                event.metadata.insert("owner_id".to_string(), owner.id.to_string());
+                event.metadata.insert("owner".to_string(), owner.username);
+                if let Some(email) = owner.email {
+                    event.metadata.insert("owner_email".to_string(), email);
+                }
+            }
+            self.record(event).await?;
+            expired += 1;
+        }
+        Ok(expired)
+    }
+
+    /// Удалить участников с истёкшим сроком временного членства; каждое удаление — событие
+    /// `group_membership_expired` (журнал аудита, `/api/events/stream`). Возвращает число удалённых
+    pub async fn expire_memberships(&self) -> Result<usize, DirectoryError> {
+        let now = Utc::now();
+        let mut expired = 0;
+        for group in self.get_all_groups().await? {
+            for member_id in group.expired_members(now) {
+                // Группа перечитывается: remove_member сохраняет её целиком
+                let Some(current) = self.get_group(group.id).await? else {
+                    break;
+                };
+                self.remove_member(current, member_id).await?;
+
+                let mut event = AuditEvent::new("group_membership_expired", AuditResult::Success);
+                event.target_id = Some(member_id);
+                event.metadata.insert("group_id".to_string(), group.id.to_string());
+                event.metadata.insert("group".to_string(), group.sam_account_name.clone());
+                event.metadata.insert("expires_at".to_string(), group.member_expirations[&member_id].to_rfc3339());
+                self.record(event).await?;
+                expired += 1;
+            }
+        }
+        Ok(expired)
+    }
+
+    // ================= ORGANIZATIONAL UNITS (OU) =================
+
+    #[tracing::instrument(skip_all, fields(dn = %ou.dn))]
+    pub async fn create_ou(&self, ou: &OrganizationalUnit) -> Result<(), DirectoryError> {
+        self.validate_attributes(SchemaClass::OrganizationalUnit, &ou.meta).await?;
+        let previous = self.get_ou(ou.id).await?.map(|previous| previous.meta);
+        self.store(format!("ou:{}", ou.id), ou).await?;
+        self.reindex_attributes(ou.id, previous.as_ref(), Some(&ou.meta)).await?;
+        self.store(dn_index_key(&ou.dn), &ou.id).await?;
+
+        let all_ous: Vec<Uuid> = self.load::<Vec<Uuid>>("all_ous_index").await?.unwrap_or_default();
+        if !all_ous.contains(&ou.id) {
+            let mut updated = all_ous;
+            updated.push(ou.id);
+            self.store("all_ous_index".to_string(), &updated).await?;
+        }
+
+        self.log_action("create_ou", &format!("ou:{}", ou.dn), None).await?;
+        Ok(())
+    }
+
+    pub async fn get_ou(&self, id: Uuid) -> Result<Option<OrganizationalUnit>, DirectoryError> {
+        self.load_cached(&format!("ou:{}", id)).await
+    }
+
+    /// OU по DN без учёта регистра, пробелов и формы экранирования
+    pub async fn find_ou_by_dn(&self, dn: &str) -> Result<Option<OrganizationalUnit>, DirectoryError> {
+        match self.load::<Uuid>(&dn_index_key(dn)).await? {
+            Some(ou_id) => self.get_ou(ou_id).await,
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_all_ous(&self) -> Result<Vec<OrganizationalUnit>, DirectoryError> {
+        Ok(self.scan("all_ous_index", "ou:", 0, usize::MAX).await?.items)
+    }
+
+    /// Все OU в пределах `limits`, как `list_users_within`
+    pub async fn list_ous_within(&self, limits: SearchLimits) -> Result<Vec<OrganizationalUnit>, DirectoryError> {
+        self.collect_within("all_ous_index", "ou:", limits).await
+    }
+
+    /// Удалить одну OU без проверок содержимого: оставшиеся в ней пользователи и контакты
+    /// переводятся в `CN=Users`, чтобы не ссылаться на удалённую OU
+    #[tracing::instrument(skip(self))]
+    async fn delete_ou(&self, ou_id: Uuid) -> Result<(), DirectoryError> {
+        let ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+
+        for mut user in self.get_all_users().await?.into_iter().filter(|user| user.organizational_unit == Some(ou_id)) {
+            user.organizational_unit = None;
+            self.save_user(&user).await?;
+        }
+        for mut contact in self.get_all_contacts().await?.into_iter().filter(|contact| contact.organizational_unit == Some(ou_id)) {
+            contact.organizational_unit = None;
+            self.store(format!("contact:{}", contact.id), &contact).await?;
+        }
+
+        let all_ous: Vec<Uuid> = self.load::<Vec<Uuid>>("all_ous_index").await?.unwrap_or_default();
+        let updated_ous: Vec<Uuid> = all_ous.into_iter().filter(|id| *id != ou_id).collect();
+        self.store("all_ous_index".to_string(), &updated_ous).await?;
+
+        let db = self.write_db().await?;
+        remove_object(&db, &self.cache, &format!("ou:{}", ou_id))?;
+        db.remove(&dn_index_key(&ou.dn));
+        db.remove(&format!("security_descriptor:{}", ou_id));
+        drop(db);
+        self.reindex_attributes(ou_id, Some(&ou.meta), None).await?;
+
+        self.log_action("delete_ou", &format!("ou:{}", ou.dn), None).await?;
+        Ok(())
+    }
+
+    /// Удалить OU; с вложенными OU — только при `recursive`, вместе со всем поддеревом.
+    /// Пользователи и контакты поддерева обрабатываются по `contents`: по умолчанию OU с ними
+    /// не удаляется. Возвращает число удалённых OU
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_ou_tree(&self, ou_id: Uuid, recursive: bool, contents: OuContents) -> Result<usize, DirectoryError> {
+        let ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+        let subtree = self.ou_subtree(&ou).await?;
+        if subtree.len() > 1 && !recursive {
+            return Err(DirectoryError::InvalidInput(format!(
+                "OU {} has {} nested OU(s); delete it recursively",
+                ou.dn,
+                subtree.len() - 1
+            )));
+        }
+
+        let ids: HashSet<Uuid> = subtree.iter().map(|ou| ou.id).collect();
+        let users: Vec<User> = self.get_all_users().await?.into_iter()
+            .filter(|user| user.organizational_unit.is_some_and(|id| ids.contains(&id)))
+            .collect();
+        let contacts: Vec<Contact> = self.get_all_contacts().await?.into_iter()
+            .filter(|contact| contact.organizational_unit.is_some_and(|id| ids.contains(&id)))
+            .collect();
+
+        match contents {
+            OuContents::Refuse => {
+                if !users.is_empty() {
+                    return Err(DirectoryError::InvalidInput(format!(
+                        "OU {} contains {} user(s); move them first or choose what to do with the contents",
+                        ou.dn,
+                        users.len()
+                    )));
+                }
+                if !contacts.is_empty() {
+                    return Err(DirectoryError::InvalidInput(format!(
+                        "OU {} contains {} contact(s); move them first or choose what to do with the contents",
+                        ou.dn,
+                        contacts.len()
+                    )));
+                }
+            }
+            OuContents::MoveToParent => {
+                // Родитель — по DN, как в `move_ou`: у OU из LDIF поле `parent` может быть пустым
+                let parent = match Dn::parse(&ou.dn)?.parent() {
+                    Some(parent_dn) => self.find_ou_by_dn(&parent_dn.to_string()).await?.map(|parent| parent.id),
+                    None => None,
+                };
+                for user in &users {
+                    self.move_user(user.id, parent).await?;
+                }
+                for mut contact in contacts {
+                    contact.organizational_unit = parent;
+                    contact.updated_at = Utc::now();
+                    self.update_contact(&contact).await?;
+                }
+            }
+            OuContents::Delete => {
+                if let Some(user) = users.iter().find(|user| user.is_protected()) {
+                    return Err(DirectoryError::Protected(format!(
+                        "OU {} contains built-in account {} that cannot be deleted",
+                        ou.dn,
+                        user.username
+                    )));
+                }
+                // Удаление пользователя под `security.approvals` идёт только через заявку
+                if let Some(user) = users.first()
+                    && self.requires_approval(&SensitiveOperation::DeleteUser { user_id: user.id }).await?
+                {
+                    return Err(DirectoryError::AccessDenied(format!(
+                        "OU {} contains {} user(s); deleting users requires approval, delete them one by one or move them first",
+                        ou.dn,
+                        users.len()
+                    )));
+                }
+                for user in &users {
+                    self.delete_user(user.id).await?;
+                }
+                for contact in &contacts {
+                    self.delete_contact(contact.id).await?;
+                }
+            }
+        }
+
+        for ou in &subtree {
+            self.delete_ou(ou.id).await?;
+        }
+        Ok(subtree.len())
+    }
+
+    /// Переименовать OU; DN вложенных OU пересчитываются
+    #[tracing::instrument(skip(self))]
+    pub async fn rename_ou(&self, ou_id: Uuid, new_name: &str) -> Result<OrganizationalUnit, DirectoryError> {
+        let mut ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+        let old_dn = ou.dn.clone();
+
+        let parent = Dn::parse(&ou.dn)?.parent().map(|parent| parent.to_string());
+        let new_dn = Self::generate_ou_dn(new_name, parent.as_deref())?;
+        ou.name = new_name.to_string();
+        let ou = self.relocate_ou(ou, new_dn).await?;
+
+        self.log_action("rename_ou", &format!("ou:{} -> {}", old_dn, ou.dn), None).await?;
+        Ok(ou)
+    }
+
+    /// Перенести OU под другой родитель: DN другой OU или корень домена (`DC=corp,DC=acme,DC=com`)
+    #[tracing::instrument(skip(self))]
+    pub async fn move_ou(&self, ou_id: Uuid, parent_dn: &str) -> Result<OrganizationalUnit, DirectoryError> {
+        let mut ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+        let old_dn = ou.dn.clone();
+
+        let parent = self.find_ou_by_dn(parent_dn).await?;
+        let target = Dn::parse(parent_dn)?;
+        if parent.is_none() && !target.rdn().is_some_and(|rdn| rdn.is("DC")) {
+            return Err(DirectoryError::NotFound(format!("Parent not found: {}", parent_dn)));
+        }
+        if target.ends_with(&Dn::parse(&ou.dn)?) {
+            return Err(DirectoryError::InvalidInput("Cannot move an OU into itself or its descendant".to_string()));
+        }
+
+        let new_dn = Self::generate_ou_dn(&ou.name, Some(parent_dn))?;
+        ou.parent = parent.map(|parent| parent.id);
+        let ou = self.relocate_ou(ou, new_dn).await?;
+
+        self.log_action("move_ou", &format!("ou:{} -> {}", old_dn, ou.dn), None).await?;
+        Ok(ou)
+    }
+
+    /// OU и все вложенные в неё (по DN), от самых глубоких к самой OU
+    async fn ou_subtree(&self, ou: &OrganizationalUnit) -> Result<Vec<OrganizationalUnit>, DirectoryError> {
+        let root = Dn::parse(&ou.dn)?;
+        let mut subtree: Vec<(usize, OrganizationalUnit)> = self.get_all_ous().await?.into_iter()
+            .filter_map(|other| {
+                let dn = Dn::parse(&other.dn).ok()?;
+                (other.id == ou.id || dn.is_descendant_of(&root)).then_some((dn.len(), other))
+            })
+            .collect();
+        subtree.sort_by_key(|(depth, _)| std::cmp::Reverse(*depth));
+        Ok(subtree.into_iter().map(|(_, ou)| ou).collect())
+    }
+
+    /// Записать OU с новым DN и пересчитать DN вложенных OU вместе с индексом `dn_index`
+    async fn relocate_ou(&self, mut ou: OrganizationalUnit, new_dn: String) -> Result<OrganizationalUnit, DirectoryError> {
+        if let Some(existing) = self.find_ou_by_dn(&new_dn).await?
+            && existing.id != ou.id
+        {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::OuAlreadyExists, format!("OU {} already exists", new_dn)));
+        }
+
+        let old_dn = ou.dn.clone();
+        let (old_root, new_root) = (Dn::parse(&old_dn)?, Dn::parse(&new_dn)?);
+        let now = Utc::now();
+        let mut changed = Vec::new();
+        for mut child in self.ou_subtree(&ou).await?.into_iter().filter(|child| child.id != ou.id) {
+            let dn = Dn::parse(&child.dn)?.rebase(&old_root, &new_root)
+                .ok_or_else(|| DirectoryError::InvalidInput(format!("OU {} is not under {}", child.dn, old_dn)))?;
+            changed.push((std::mem::replace(&mut child.dn, dn.to_string()), child));
+        }
+        ou.dn = new_dn;
+        changed.push((old_dn, ou.clone()));
+
+        let db = self.write_db().await?;
+        for (old_dn, _) in &changed {
+            db.remove(&dn_index_key(old_dn));
+        }
+        drop(db);
+        for (_, mut ou) in changed {
+            ou.updated_at = now;
+            self.store(format!("ou:{}", ou.id), &ou).await?;
+            self.store(dn_index_key(&ou.dn), &ou.id).await?;
+        }
+        ou.updated_at = now;
+        Ok(ou)
+    }
+
+    // ================= CONTACTS =================
+
+    /// Создать контакт; адрес не должен быть занят другим контактом или пользователем
+    #[tracing::instrument(skip_all, fields(email = %contact.email))]
+    pub async fn create_contact(&self, contact: &Contact) -> Result<(), DirectoryError> {
+        if self.get_contact(contact.id).await?.is_some() {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::ContactAlreadyExists, format!("Contact {} already exists", contact.id)));
+        }
+        self.save_contact(contact).await?;
+        self.log_action("create_contact", &format!("contact:{} email:{}", contact.name, contact.email), Some(contact.id)).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(email = %contact.email))]
+    pub async fn update_contact(&self, contact: &Contact) -> Result<(), DirectoryError> {
+        if self.get_contact(contact.id).await?.is_none() {
+            return Err(DirectoryError::NotFound("Contact not found".to_string()));
+        }
+        self.save_contact(contact).await?;
+        self.log_action("update_contact", &format!("contact:{} email:{}", contact.name, contact.email), Some(contact.id)).await?;
+        Ok(())
+    }
+
+    /// Записать контакт, индексы адресов и атрибутов без события аудита
+    async fn save_contact(&self, contact: &Contact) -> Result<(), DirectoryError> {
+        contact.validate()?;
+        self.validate_attributes(SchemaClass::Contact, &contact.meta).await?;
+        if let Some(ou_id) = contact.organizational_unit
+            && self.get_ou(ou_id).await?.is_none()
+        {
+            return Err(DirectoryError::NotFound("OU not found".to_string()));
+        }
+
+        let emails: Vec<&String> = std::iter::once(&contact.email).chain(&contact.other_emails).collect();
+        for email in &emails {
+            if let Some(existing) = self.find_contact_by_email(email).await?
+                && existing.id != contact.id
+            {
+                return Err(DirectoryError::AlreadyExists(ErrorCode::EmailAlreadyExists, format!("Contact with email {} already exists", email)));
+            }
+            if self.find_user_by_email(email).await?.is_some() {
+                return Err(DirectoryError::AlreadyExists(ErrorCode::EmailAlreadyExists, format!("User with email {} already exists", email)));
+            }
+        }
+
+        let previous = self.get_contact(contact.id).await?;
+        if let Some(previous) = &previous {
+            let db = self.write_db().await?;
+            for email in std::iter::once(&previous.email).chain(&previous.other_emails) {
+                if !emails.contains(&email) {
+                    db.remove(&lookup_key("contact_email_index", email));
+                }
+            }
+            drop(db);
+        }
+
+        self.store(format!("contact:{}", contact.id), contact).await?;
+        for email in &emails {
+            self.store(lookup_key("contact_email_index", email), &contact.id).await?;
+        }
+        self.reindex_attributes(contact.id, previous.as_ref().map(|previous| &previous.meta), Some(&contact.meta)).await?;
+
+        let all_contacts: Vec<Uuid> = self.load::<Vec<Uuid>>("all_contacts_index").await?.unwrap_or_default();
+        if !all_contacts.contains(&contact.id) {
+            let mut updated = all_contacts;
+            updated.push(contact.id);
+            self.store("all_contacts_index".to_string(), &updated).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_contact(&self, id: Uuid) -> Result<Option<Contact>, DirectoryError> {
+        self.load_cached(&format!("contact:{}", id)).await
+    }
+
+    /// Контакт по основному или дополнительному адресу без учёта регистра
+    pub async fn find_contact_by_email(&self, email: &str) -> Result<Option<Contact>, DirectoryError> {
+        match self.load::<Uuid>(&lookup_key("contact_email_index", email)).await? {
+            Some(id) => self.get_contact(id).await,
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_all_contacts(&self) -> Result<Vec<Contact>, DirectoryError> {
+        Ok(self.scan_contacts(0, usize::MAX).await?.items)
+    }
+
+    /// Все контакты в пределах `limits`, как `list_users_within`
+    pub async fn list_contacts_within(&self, limits: SearchLimits) -> Result<Vec<Contact>, DirectoryError> {
+        self.collect_within("all_contacts_index", "contact:", limits).await
+    }
+
+    /// Контакты постранично, как `scan_users`
+    pub async fn scan_contacts(&self, cursor: usize, limit: usize) -> Result<ScanPage<Contact>, DirectoryError> {
+        self.scan("all_contacts_index", "contact:", cursor, limit).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_contact(&self, contact_id: Uuid) -> Result<(), DirectoryError> {
+        let contact = self.get_contact(contact_id).await?.ok_or_else(|| DirectoryError::NotFound("Contact not found".to_string()))?;
+
+        let all_contacts: Vec<Uuid> = self.load::<Vec<Uuid>>("all_contacts_index").await?.unwrap_or_default();
+        let updated: Vec<Uuid> = all_contacts.into_iter().filter(|id| *id != contact_id).collect();
+        self.store("all_contacts_index".to_string(), &updated).await?;
+
+        let db = self.write_db().await?;
+        remove_object(&db, &self.cache, &format!("contact:{}", contact_id))?;
+        for email in std::iter::once(&contact.email).chain(&contact.other_emails) {
+            db.remove(&lookup_key("contact_email_index", email));
+        }
+        db.remove(&format!("security_descriptor:{}", contact_id));
+        drop(db);
+        self.reindex_attributes(contact_id, Some(&contact.meta), None).await?;
+
+        self.log_action("delete_contact", &format!("contact:{} email:{}", contact.name, contact.email), Some(contact_id)).await?;
+        Ok(())
+    }
+
+    /// DN контакта: `CN=<name>` в его OU или в `CN=Users` домена
+    pub async fn contact_dn(&self, contact: &Contact) -> Result<String, DirectoryError> {
+        let container = self.container_dn(contact.organizational_unit).await?;
+        Ok(dn::join(&Rdn::cn(contact.name.as_str()), &container))
+    }
+
+    /// DN пользователя, как в экспорте LDIF: `CN=<displayName или имя>` в его OU или в `CN=Users` домена
+    pub async fn user_dn(&self, user: &User) -> Result<String, DirectoryError> {
+        let container = self.container_dn(user.organizational_unit).await?;
+        let cn = user.display_name.as_deref().unwrap_or(&user.username);
+        Ok(dn::join(&Rdn::cn(cn), &container))
+    }
+
+    /// DN группы, как в экспорте LDIF: `CN=<name>` в `CN=Users` домена
+    pub async fn group_dn(&self, group: &Group) -> Result<String, DirectoryError> {
+        let container = self.container_dn(None).await?;
+        Ok(dn::join(&Rdn::cn(group.name.as_str()), &container))
+    }
+
+    /// DN OU или, без неё, `CN=Users` домена
+    async fn container_dn(&self, ou: Option<Uuid>) -> Result<String, DirectoryError> {
+        if let Some(id) = ou
+            && let Some(ou) = self.get_ou(id).await?
+        {
+            return Ok(ou.dn);
+        }
+        let base_dn = self.get_all_domains().await?
+            .first()
+            .map(Domain::dn)
+            .unwrap_or_else(|| "DC=corp,DC=acme,DC=com".to_string());
+        Ok(dn::join(&Rdn::cn("Users"), &base_dn))
+    }
+
+    /// Объект любого типа по objectGUID: пользователь, группа, OU, контакт, домен или GPO
+    pub async fn find_object_by_guid(&self, id: Uuid) -> Result<Option<DirectoryObject>, DirectoryError> {
+        let ctx = LdapContext::new(self).await?;
+        let object = |object_type: &str, dn: String, name: &str| DirectoryObject {
+            object_guid: id,
+            object_type: object_type.to_string(),
+            dn,
+            name: name.to_string(),
+        };
+        if let Some(user) = self.get_user(id).await? {
+            return Ok(Some(object("user", user.dn(&ctx), &user.username)));
+        }
+        if let Some(group) = self.get_group(id).await? {
+            return Ok(Some(object("group", group.dn(&ctx), &group.sam_account_name)));
+        }
+        if let Some(ou) = self.get_ou(id).await? {
+            return Ok(Some(object("ou", ou.dn.clone(), &ou.name)));
+        }
+        if let Some(contact) = self.get_contact(id).await? {
+            return Ok(Some(object("contact", contact.dn(&ctx), &contact.name)));
+        }
+        if let Some(domain) = self.get_domain(id).await? {
+            return Ok(Some(object("domain", domain.dn(), &domain.dns_name)));
+        }
+        if let Some(gpo) = self.get_gpo(id).await? {
+            // Как в AD: контейнер GPO назван его GUID в фигурных скобках
+            let dn = format!("CN={{{}}},CN=Policies,CN=System,{}", gpo.id.to_string().to_uppercase(), ctx.domain_dn);
+            return Ok(Some(object("gpo", dn, &gpo.name)));
+        }
+        Ok(None)
+    }
+
+    // ================= ATTRIBUTE MODIFY =================
+
+    /// Изменить атрибуты пользователя, группы, OU или контакта по отдельности, как LDAP Modify.
+    /// Изменения применяются по порядку и сохраняются вместе: ошибка в любом отменяет все.
+    /// Встроенные атрибуты проверяет модель, остальные должны быть определены в схеме
+    #[tracing::instrument(skip(self, changes))]
+    pub async fn modify_object(&self, id: Uuid, changes: Vec<AttributeChange>) -> Result<DirectoryObject, DirectoryError> {
+        if changes.is_empty() {
+            return Err(DirectoryError::InvalidInput("No attribute changes".to_string()));
+        }
+
+        let applied = if let Some(mut user) = self.get_user(id).await? {
+            let previous = user.clone();
+            let applied = self.apply_attribute_changes(&mut user, SchemaClass::User, &changes).await?;
+            user.updated_at = Utc::now();
+            self.validate_attributes(SchemaClass::User, &user.meta).await?;
+            self.save_user(&user).await?;
+            // Индекс email save_user обновляет сам, прежнее имя больше не должно находить пользователя
+            if normalize_username(&user.username) != normalize_username(&previous.username) {
+                self.write_db().await?.remove(&lookup_key("username_index", &previous.username));
+            }
+            self.reindex_attributes(id, Some(&previous.meta), Some(&user.meta)).await?;
+            applied
+        } else if let Some(mut group) = self.get_group(id).await? {
+            let previous = group.meta.clone();
+            let applied = self.apply_attribute_changes(&mut group, SchemaClass::Group, &changes).await?;
+            self.validate_attributes(SchemaClass::Group, &group.meta).await?;
+            self.store(format!("group:{}", id), &group).await?;
+            self.reindex_attributes(id, Some(&previous), Some(&group.meta)).await?;
+            applied
+        } else if let Some(mut ou) = self.get_ou(id).await? {
+            let previous = ou.meta.clone();
+            let applied = self.apply_attribute_changes(&mut ou, SchemaClass::OrganizationalUnit, &changes).await?;
+            ou.updated_at = Utc::now();
+            self.validate_attributes(SchemaClass::OrganizationalUnit, &ou.meta).await?;
+            self.store(format!("ou:{}", id), &ou).await?;
+            self.reindex_attributes(id, Some(&previous), Some(&ou.meta)).await?;
+            applied
+        } else if let Some(mut contact) = self.get_contact(id).await? {
+            let applied = self.apply_attribute_changes(&mut contact, SchemaClass::Contact, &changes).await?;
+            contact.updated_at = Utc::now();
+            self.save_contact(&contact).await?;
+            applied
+        } else {
+            return Err(DirectoryError::NotFound(format!("Object not found: {}", id)));
+        };
+
+        let object = self.find_object_by_guid(id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Object not found: {}", id)))?;
+        let mut event = AuditEvent::new("modify_object", AuditResult::Success);
+        event.target_id = Some(id);
+        event.metadata.insert("details".to_string(), format!("{}:{} {}", object.object_type, object.name, applied.join(" ")));
+        event.metadata.insert("attributes".to_string(), applied.join(","));
+        self.record(event).await?;
+        Ok(object)
+    }
+
+    /// Применить изменения к объекту в памяти; возвращает `операция:атрибут` с именами как в схеме
+    async fn apply_attribute_changes<T: ModifiableObject>(&self, object: &mut T, class: SchemaClass, changes: &[AttributeChange]) -> Result<Vec<String>, DirectoryError> {
+        let mut applied = Vec::with_capacity(changes.len());
+        for change in changes {
+            let name = match object.modifiable_attribute(change.attribute()) {
+                Some((name, current)) => {
+                    object.set_attribute(name, change.apply(current)?)?;
+                    name.to_string()
+                }
+                None => {
+                    let definition = self.get_attribute_definition(change.attribute()).await?
+                        .ok_or_else(|| DirectoryError::InvalidInput(format!("Attribute {} cannot be modified", change.attribute())))?;
+                    if !definition.applies_to(class) {
+                        return Err(DirectoryError::InvalidInput(format!("Attribute {} does not apply to {:?}", definition.name, class)));
+                    }
+                    let current = object.meta_mut().get(&definition.name).map(|raw| schema::values(raw)).unwrap_or_default();
+                    let values = change.apply(current)?;
+                    schema::apply_changes(object.meta_mut(), HashMap::from([(definition.name.clone(), values)]));
+                    definition.name
+                }
+            };
+            applied.push(format!("{}:{}", change.operation(), name));
+        }
+        Ok(applied)
+    }
+
+    // ================= APPROVALS =================
+
+    /// Нужна ли операции заявка (`security.approvals`); добавление участника — только
+    /// в административные группы (`well_known::ADMINISTRATOR_GROUPS`)
+    pub async fn requires_approval(&self, operation: &SensitiveOperation) -> Result<bool, DirectoryError> {
+        let config = self.approvals.read().unwrap().clone();
+        Ok(match operation {
+            SensitiveOperation::DeleteUser { .. } => config.requires(ApprovalOperation::DeleteUser),
+            SensitiveOperation::AddGroupMember { group_id, .. } => {
+                config.requires(ApprovalOperation::AddPrivilegedMember)
+                    && self.get_group(*group_id).await?.is_some_and(|group| {
+                        well_known::ADMINISTRATOR_GROUPS.iter().any(|name| name.eq_ignore_ascii_case(&group.sam_account_name))
+                    })
+            }
+            SensitiveOperation::DeleteGpo { .. } | SensitiveOperation::LinkGpo { .. } | SensitiveOperation::UnlinkGpo { .. } => {
+                config.requires(ApprovalOperation::ChangeGpo)
+            }
+        })
+    }
+
+    /// Выполнить чувствительную операцию или, если она требует одобрения, создать заявку.
+    /// `None` — операция выполнена сразу
+    #[tracing::instrument(skip(self))]
+    pub async fn submit_operation(&self, operation: SensitiveOperation, requested_by: Uuid) -> Result<Option<ApprovalRequest>, DirectoryError> {
+        if !self.requires_approval(&operation).await? {
+            self.execute_operation(&operation).await?;
+            return Ok(None);
+        }
+
+        let summary = self.describe_operation(&operation).await?;
+        if self.list_approvals(Some(ApprovalStatus::Pending)).await?.iter().any(|request| request.operation == operation) {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::RequestAlreadyPending, format!("Operation '{}' is already waiting for approval", summary)));
+        }
+
+        let request = ApprovalRequest::new(operation, summary, requested_by);
+        self.store(format!("approval:{}", request.id), &request).await?;
+        let mut ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_approvals_index").await?.unwrap_or_default();
+        ids.push(request.id);
+        self.store("all_approvals_index".to_string(), &ids).await?;
+
+        let mut event = AuditEvent::new("request_approval", AuditResult::Success);
+        event.actor_id = Some(requested_by);
+        event.target_id = Some(request.operation.target_id());
+        event.metadata.insert("request_id".to_string(), request.id.to_string());
+        event.metadata.insert("operation".to_string(), request.summary.clone());
+        self.record(event).await?;
+        Ok(Some(request))
+    }
+
+    /// Описание операции для заявки; заодно проверяет, что её объекты существуют
+    async fn describe_operation(&self, operation: &SensitiveOperation) -> Result<String, DirectoryError> {
+        let gpo_name = |gpo: Option<GroupPolicy>| gpo.map(|gpo| gpo.name).ok_or_else(|| DirectoryError::NotFound("GPO not found".to_string()));
+        let ou_name = |ou: Option<OrganizationalUnit>| ou.map(|ou| ou.name).ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()));
+        Ok(match operation {
+            SensitiveOperation::DeleteUser { user_id } => {
+                let user = self.get_user(*user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+                if user.is_protected() {
+                    return Err(DirectoryError::Protected(format!("User {} is a built-in account and cannot be deleted", user.username)));
+                }
+                format!("delete user {}", user.username)
+            }
+            SensitiveOperation::AddGroupMember { group_id, member_id, ttl_secs } => {
+                let group = self.get_group(*group_id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+                let member = match self.get_user(*member_id).await? {
+                    Some(user) => user.username,
+                    None => self.get_group(*member_id).await?
+                        .map(|member| member.sam_account_name)
+                        .ok_or_else(|| DirectoryError::NotFound(format!("Member not found: {}", member_id)))?,
+                };
+                match ttl_secs {
+                    Some(secs) => format!("add {} to {} for {}", member, group.sam_account_name, duration::format(std::time::Duration::from_secs(*secs))),
+                    None => format!("add {} to {}", member, group.sam_account_name),
+                }
+            }
+            SensitiveOperation::DeleteGpo { gpo_id } => format!("delete GPO {}", gpo_name(self.get_gpo(*gpo_id).await?)?),
+            SensitiveOperation::LinkGpo { gpo_id, ou_id } => {
+                format!("link GPO {} to OU {}", gpo_name(self.get_gpo(*gpo_id).await?)?, ou_name(self.get_ou(*ou_id).await?)?)
+            }
+            SensitiveOperation::UnlinkGpo { gpo_id, ou_id } => {
+                format!("unlink GPO {} from OU {}", gpo_name(self.get_gpo(*gpo_id).await?)?, ou_name(self.get_ou(*ou_id).await?)?)
+            }
+        })
+    }
+
+    async fn execute_operation(&self, operation: &SensitiveOperation) -> Result<(), DirectoryError> {
+        match *operation {
+            SensitiveOperation::DeleteUser { user_id } => self.delete_user(user_id).await,
+            SensitiveOperation::AddGroupMember { group_id, member_id, ttl_secs } => {
+                let ttl = ttl_secs.map(|secs| chrono::Duration::seconds(secs as i64));
+                self.add_member_to_group(group_id, member_id, ttl).await
+            }
+            SensitiveOperation::DeleteGpo { gpo_id } => self.delete_gpo(gpo_id).await,
+            SensitiveOperation::LinkGpo { gpo_id, ou_id } => self.link_gpo_to_ou(gpo_id, ou_id).await,
+            SensitiveOperation::UnlinkGpo { gpo_id, ou_id } => self.unlink_gpo_from_ou(gpo_id, ou_id).await,
+        }
+    }
+
+    pub async fn get_approval(&self, id: Uuid) -> Result<Option<ApprovalRequest>, DirectoryError> {
+        self.load(&format!("approval:{}", id)).await
+    }
+
+    /// Заявки от старых к новым; `status` — только в этом состоянии
+    pub async fn list_approvals(&self, status: Option<ApprovalStatus>) -> Result<Vec<ApprovalRequest>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_approvals_index").await?.unwrap_or_default();
+        let mut requests = Vec::new();
+        for id in ids {
+            if let Some(request) = self.get_approval(id).await?
+                && status.is_none_or(|status| request.status == status)
+            {
+                requests.push(request);
+            }
+        }
+        requests.sort_by_key(|request| request.requested_at);
+        Ok(requests)
+    }
+
+    /// Одобрить или отклонить заявку; решает не тот, кто её создал. Одобренная операция
+    /// выполняется сразу; если не получилось, заявка переходит в `failed` с причиной
+    #[tracing::instrument(skip(self, comment))]
+    pub async fn decide_approval(&self, id: Uuid, approve: bool, decided_by: Uuid, comment: Option<String>) -> Result<ApprovalRequest, DirectoryError> {
+        let mut request = self.get_approval(id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Approval request not found: {}", id)))?;
+        if !request.is_pending() {
+            return Err(DirectoryError::InvalidInput(format!("Approval request {} is already decided", id)));
+        }
+        if request.requested_by == decided_by {
+            return Err(DirectoryError::AccessDenied(format!("Approval request {} must be decided by another administrator", id)));
+        }
+
+        request.status = if !approve {
+            ApprovalStatus::Rejected
+        } else if let Err(e) = self.execute_operation(&request.operation).await {
+            request.error = Some(e.to_string());
+            ApprovalStatus::Failed
+        } else {
+            ApprovalStatus::Approved
+        };
+        request.decided_by = Some(decided_by);
+        request.decided_at = Some(Utc::now());
+        request.comment = comment;
+        self.store(format!("approval:{}", request.id), &request).await?;
+
+        let result = if request.status == ApprovalStatus::Failed { AuditResult::Failure } else { AuditResult::Success };
+        let mut event = AuditEvent::new(if approve { "approve_operation" } else { "reject_operation" }, result);
+        event.actor_id = Some(decided_by);
+        event.target_id = Some(request.operation.target_id());
+        event.metadata.insert("request_id".to_string(), request.id.to_string());
+        event.metadata.insert("requested_by".to_string(), request.requested_by.to_string());
+        event.metadata.insert("operation".to_string(), request.summary.clone());
+        if let Some(error) = &request.error {
+            event.metadata.insert("error".to_string(), error.clone());
+        }
+        self.record(event).await?;
+        Ok(request)
+    }
+
+    // ================= SESSIONS =================
+
+    /// Открыть сессию для токена входа со сроком `security.jwt.token_expiry`. Сверх
+    /// `security.sessions.max_concurrent` самые старые сессии отзываются или вход отклоняется.
+    /// На реплике сессия не сохраняется и предел не действует
+    #[tracing::instrument(skip(self, device))]
+    pub async fn open_session(&self, user_id: Uuid, protocol: LoginProtocol, ip_addr: Option<String>, device: Option<String>) -> Result<Session, DirectoryError> {
+        let ttl = chrono::Duration::seconds(crate::auth::token_expiry_secs() as i64);
+        let session = Session::new(user_id, protocol, ip_addr, device, ttl);
+        if self.is_read_only() {
+            return Ok(session);
+        }
+
+        // Истёкшие сессии больше не нужны ни для предела, ни для списка отзыва
+        let key = format!("user_sessions_index:{}", user_id);
+        let ids: Vec<Uuid> = self.load(&key).await?.unwrap_or_default();
+        let mut kept = Vec::new();
+        let mut active = Vec::new();
+        for id in ids {
+            match self.get_session(id).await? {
+                Some(existing) if existing.expires_at > session.issued_at => {
+                    kept.push(id);
+                    if existing.is_active(session.issued_at) {
+                        active.push(existing);
+                    }
+                }
+                _ => {
+                    self.write_db().await?.remove(&format!("session:{}", id));
+                }
+            }
+        }
+
+        let config = self.sessions.read().unwrap().clone();
+        if config.max_concurrent > 0 && active.len() >= config.max_concurrent {
+            if config.on_limit == SessionLimitAction::Reject {
+                self.store(key, &kept).await?;
+                return Err(DirectoryError::SessionLimitExceeded(config.max_concurrent));
+            }
+            active.sort_by_key(|existing| existing.issued_at);
+            let excess = active.len() + 1 - config.max_concurrent;
+            for existing in active.into_iter().take(excess) {
+                self.revoke(existing, "session_limit").await?;
+            }
+        }
+
+        self.store(format!("session:{}", session.id), &session).await?;
+        kept.push(session.id);
+        self.store(key, &kept).await?;
+        Ok(session)
+    }
+
+    pub async fn get_session(&self, id: Uuid) -> Result<Option<Session>, DirectoryError> {
+        self.load(&format!("session:{}", id)).await
+    }
+
+    /// Неистёкшие сессии пользователя, включая отозванные, новые первыми
+    pub async fn list_user_sessions(&self, user_id: Uuid) -> Result<Vec<Session>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load(&format!("user_sessions_index:{}", user_id)).await?.unwrap_or_default();
+        let now = Utc::now();
+        let mut sessions = Vec::new();
+        for id in ids {
+            if let Some(session) = self.get_session(id).await?
+                && session.expires_at > now
+            {
+                sessions.push(session);
+            }
+        }
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.issued_at));
+        Ok(sessions)
+    }
+
+    /// Отозвать сессию: её токен перестаёт приниматься сразу, а не по истечении срока
+    #[tracing::instrument(skip(self))]
+    pub async fn revoke_session(&self, id: Uuid) -> Result<Session, DirectoryError> {
+        let session = self.get_session(id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Session not found: {}", id)))?;
+        if session.revoked_at.is_some() {
+            return Ok(session);
+        }
+        self.revoke(session, "admin").await
+    }
+
+    /// Отозвать все действующие сессии пользователя; возвращает, сколько отозвано
+    pub async fn revoke_user_sessions(&self, user_id: Uuid, reason: &str) -> Result<usize, DirectoryError> {
+        let now = Utc::now();
+        let mut revoked = 0;
+        for session in self.list_user_sessions(user_id).await? {
+            if session.is_active(now) {
+                self.revoke(session, reason).await?;
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+
+    async fn revoke(&self, mut session: Session, reason: &str) -> Result<Session, DirectoryError> {
+        let now = Utc::now();
+        session.revoked_at = Some(now);
+        self.store(format!("session:{}", session.id), &session).await?;
+
+        // Список отзыва: id сессии → срок её токена; после срока токен отклоняется и так
+        let mut revoked: HashMap<Uuid, chrono::DateTime<Utc>> = self.load("revoked_sessions").await?.unwrap_or_default();
+        revoked.retain(|_, expires_at| *expires_at > now);
+        revoked.insert(session.id, session.expires_at);
+        self.store("revoked_sessions".to_string(), &revoked).await?;
+
+        let mut event = AuditEvent::new("revoke_session", AuditResult::Success);
+        event.target_id = Some(session.user_id);
+        event.metadata.insert("session_id".to_string(), session.id.to_string());
+        event.metadata.insert("protocol".to_string(), session.protocol.as_str().to_string());
+        event.metadata.insert("reason".to_string(), reason.to_string());
+        self.record(event).await?;
+        Ok(session)
+    }
+
+    /// Есть ли сессия в списке отзыва
+    pub async fn is_session_revoked(&self, id: Uuid) -> Result<bool, DirectoryError> {
+        let revoked: HashMap<Uuid, chrono::DateTime<Utc>> = self.load("revoked_sessions").await?.unwrap_or_default();
+        Ok(revoked.contains_key(&id))
+    }
+
+    // ================= SECURITY DESCRIPTORS =================
+
+    /// SID домена с новым RID для объекта с SID-заглушкой (`Group::new`, `User` из REST/CLI);
+    /// `None` — SID уже доменный или встроенный, либо домена ещё нет
+    async fn allocate_sid(&self, sid: &SecurityIdentifier) -> Result<Option<SecurityIdentifier>, DirectoryError> {
+        if sid.is_domain_account() || sid.sub_authorities.first() == Some(&32) {
+            return Ok(None);
+        }
+        let Some(domain) = self.get_all_domains().await?.into_iter().next() else {
+            return Ok(None);
+        };
+        let rid = self.load::<u32>("next_rid").await?.unwrap_or(FIRST_ALLOCATED_RID);
+        self.store("next_rid".to_string(), &(rid + 1)).await?;
+        Ok(Some(domain.sid.with_rid(rid)))
+    }
+
+    /// Пользователь или группа с этим SID по `sid_index`. Базу без индекса (созданную до него)
+    /// индексирует первый поиск; на реплике только для чтения индекс приходит со снимком
+    async fn find_id_by_sid(&self, sid: &SecurityIdentifier) -> Result<Option<Uuid>, DirectoryError> {
+        let Some(key) = sid_index_key(sid) else {
+            return Ok(None);
+        };
+        if !self.is_read_only() && self.load::<bool>("sid_index_built").await?.is_none() {
+            for user in self.get_all_users().await? {
+                self.index_sid(user.id, None, Some(&user.sid)).await?;
+            }
+            for group in self.get_all_groups().await? {
+                self.index_sid(group.id, None, Some(&group.sid)).await?;
+            }
+            self.store("sid_index_built".to_string(), &true).await?;
+        }
+        self.load(&key).await
+    }
+
+    /// SID уже принадлежит другому пользователю или группе
+    async fn check_sid_unique(&self, sid: &SecurityIdentifier, id: Uuid) -> Result<(), DirectoryError> {
+        if let Some(owner) = self.find_id_by_sid(sid).await?
+            && owner != id
+        {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::SidAlreadyAssigned, format!("SID {} is already assigned", sid)));
+        }
+        Ok(())
+    }
+
+    /// Перенести запись `sid_index` с прежнего SID объекта на новый
+    async fn index_sid(&self, id: Uuid, old: Option<&SecurityIdentifier>, new: Option<&SecurityIdentifier>) -> Result<(), DirectoryError> {
+        if old == new && old.is_some() {
+            return Ok(());
+        }
+        if let Some(key) = old.and_then(sid_index_key) {
+            self.write_db().await?.remove(&key);
+        }
+        if let Some(key) = new.and_then(sid_index_key) {
+            self.store(key, &id).await?;
+        }
+        Ok(())
+    }
+
+    /// Явный дескриптор объекта; `None` — объект только наследует права
+    pub async fn get_security_descriptor(&self, object: SecuredObject) -> Result<Option<SecurityDescriptor>, DirectoryError> {
+        self.load(&format!("security_descriptor:{}", object.id())).await
+    }
+
+    /// Заменить явный дескриптор объекта; унаследованные ACE не сохраняются
+    #[tracing::instrument(skip(self, descriptor))]
+    pub async fn set_security_descriptor(&self, object: SecuredObject, descriptor: &SecurityDescriptor) -> Result<(), DirectoryError> {
+        self.secured_object_exists(object).await?;
+        let mut descriptor = descriptor.clone();
+        descriptor.dacl.retain(|ace| !ace.flags.contains(AceFlags::INHERITED));
+
+        self.store(format!("security_descriptor:{}", object.id()), &descriptor).await?;
+        self.log_action(
+            "set_security_descriptor",
+            &format!("{} owner:{} aces:{} protected:{}", object, descriptor.owner, descriptor.dacl.len(), descriptor.protected),
+            None,
+        ).await?;
+        Ok(())
+    }
+
+    /// Действующий дескриптор: явные ACE объекта, затем наследуемые ACE родительских OU
+    /// и корня домена, от ближних к дальним. Наследование обрывается на защищённом DACL
+    pub async fn effective_security_descriptor(&self, object: SecuredObject) -> Result<SecurityDescriptor, DirectoryError> {
+        let mut parent = self.secured_object_exists(object).await?;
+        let root = self.root_security_descriptor().await?;
+        let mut effective = self.get_security_descriptor(object).await?
+            .unwrap_or_else(|| SecurityDescriptor::new(root.owner.clone()));
+        if effective.protected {
+            return Ok(effective);
+        }
+
+        let mut direct = true;
+        loop {
+            let (ancestor, next) = match parent {
+                Some(ou_id) => {
+                    let next = self.get_ou(ou_id).await?.and_then(|ou| ou.parent);
+                    let descriptor = self.get_security_descriptor(SecuredObject::Ou(ou_id)).await?;
+                    (descriptor, next)
+                }
+                None => (Some(root.clone()), None),
+            };
+            if let Some(ancestor) = &ancestor {
+                effective.dacl.extend(ancestor.inheritable_aces(object.is_container(), direct));
+            }
+            if parent.is_none() || ancestor.is_some_and(|ancestor| ancestor.protected) {
+                break;
+            }
+            parent = next;
+            direct = false;
+        }
+        Ok(effective)
+    }
+
+    /// SID, от имени которых действует пользователь: сам пользователь, его группы,
+    /// Everyone и Authenticated Users
+    pub async fn principal_sids(&self, user: &User) -> Result<Vec<SecurityIdentifier>, DirectoryError> {
+        let mut sids = vec![user.sid.clone()];
+        for sid in self.get_token_groups(user.id).await? {
+            if !sids.contains(&sid) {
+                sids.push(sid);
+            }
+        }
+        sids.push(well_known::sids::everyone());
+        sids.push(well_known::sids::authenticated_users());
+        Ok(sids)
+    }
+
+    /// Есть ли у набора SID запрошенные права на объект
+    pub async fn check_access(&self, principal_sids: &[SecurityIdentifier], object: SecuredObject, desired: AccessMask) -> Result<bool, DirectoryError> {
+        Ok(self.effective_security_descriptor(object).await?.check_access(principal_sids, desired))
+    }
+
+    /// Есть ли у набора SID запрошенные права на сам объект домена (корень каталога)
+    pub async fn check_root_access(&self, principal_sids: &[SecurityIdentifier], desired: AccessMask) -> Result<bool, DirectoryError> {
+        Ok(self.root_security_descriptor().await?.check_access(principal_sids, desired))
+    }
+
+    /// Проверить права пользователя на объект; для собственной учётной записи добавляется Principal Self
+    pub async fn authorize(&self, user: &User, object: SecuredObject, desired: AccessMask) -> Result<(), DirectoryError> {
+        let mut sids = self.principal_sids(user).await?;
+        if object == SecuredObject::User(user.id) {
+            sids.push(well_known::sids::principal_self());
+        }
+        if self.check_access(&sids, object, desired).await? {
+            return Ok(());
+        }
+        Err(DirectoryError::AccessDenied(format!(
+            "{} has no {} on {}",
+            user.username,
+            desired.names().join(" | "),
+            object
+        )))
+    }
+
+    /// Право создать объект в OU `parent` (`None` — в корне домена): `CREATE_CHILD` на контейнер
+    pub async fn authorize_create(&self, user: &User, parent: Option<Uuid>) -> Result<(), DirectoryError> {
+        if let Some(ou_id) = parent {
+            return self.authorize(user, SecuredObject::Ou(ou_id), AccessMask::CREATE_CHILD).await;
+        }
+        let sids = self.principal_sids(user).await?;
+        if self.root_security_descriptor().await?.check_access(&sids, AccessMask::CREATE_CHILD) {
+            return Ok(());
+        }
+        Err(DirectoryError::AccessDenied(format!("{} has no CREATE_CHILD on the domain root", user.username)))
+    }
+
+    /// Объекты из `objects`, на которые у пользователя есть `desired`; как `authorize`, без ошибки
+    pub async fn filter_authorized<T>(
+        &self,
+        user: &User,
+        objects: Vec<T>,
+        object: impl Fn(&T) -> SecuredObject,
+        desired: AccessMask,
+    ) -> Result<Vec<T>, DirectoryError> {
+        let sids = self.principal_sids(user).await?;
+        let mut allowed = Vec::with_capacity(objects.len());
+        for item in objects {
+            let secured = object(&item);
+            let granted = if secured == SecuredObject::User(user.id) {
+                let mut own = sids.clone();
+                own.push(well_known::sids::principal_self());
+                self.check_access(&own, secured, desired).await?
+            } else {
+                self.check_access(&sids, secured, desired).await?
+            };
+            if granted {
+                allowed.push(item);
+            }
+        }
+        Ok(allowed)
+    }
+
+    /// Дескриптор корня домена, от которого наследуют все объекты: владелец — Domain Admins,
+    /// полный доступ администраторам, чтение — Authenticated Users
+    async fn root_security_descriptor(&self) -> Result<SecurityDescriptor, DirectoryError> {
+        let inherit = AceFlags::CONTAINER_INHERIT | AceFlags::OBJECT_INHERIT;
+        let mut admins = Vec::new();
+        for sam in [DOMAIN_ADMINS_SAM, "ENTERPRISE ADMINS", "ADMINISTRATORS"] {
+            if let Some(group) = self.find_group_by_sam_account_name(sam).await? {
+                admins.push(group.sid);
+            }
+        }
+
+        let owner = admins.first().cloned().unwrap_or_else(|| SecurityIdentifier::new_builtin(well_known::rid::BUILTIN_ADMINISTRATORS));
+        let mut descriptor = SecurityDescriptor::new(owner);
+        descriptor.dacl = admins.into_iter()
+            .map(|sid| Ace::allow(sid, AccessMask::GENERIC_ALL, inherit))
+            .collect();
+        descriptor.dacl.push(Ace::allow(well_known::sids::authenticated_users(), AccessMask::GENERIC_READ, inherit));
+        Ok(descriptor)
+    }
+
+    /// Ошибка, если объекта нет; для существующего — родительская OU (`None` — корень домена)
+    async fn secured_object_exists(&self, object: SecuredObject) -> Result<Option<Uuid>, DirectoryError> {
+        match object {
+            SecuredObject::User(id) => self.get_user(id).await?
+                .map(|user| user.organizational_unit)
+                .ok_or_else(|| DirectoryError::NotFound("User not found".to_string())),
+            SecuredObject::Group(id) => self.get_group(id).await?
+                .map(|_| None)
+                .ok_or_else(|| DirectoryError::NotFound("Group not found".to_string())),
+            SecuredObject::Ou(id) => self.get_ou(id).await?
+                .map(|ou| ou.parent)
+                .ok_or_else(|| DirectoryError::NotFound("OU not found".to_string())),
+            SecuredObject::Contact(id) => self.get_contact(id).await?
+                .map(|contact| contact.organizational_unit)
+                .ok_or_else(|| DirectoryError::NotFound("Contact not found".to_string())),
+        }
+    }
+
+    // ================= SCHEMA =================
+
+    /// Определить дополнительный атрибут; для индексируемого индекс строится по уже заданным значениям
+    #[tracing::instrument(skip_all, fields(name = %definition.name))]
+    pub async fn define_attribute(&self, definition: &AttributeDefinition) -> Result<(), DirectoryError> {
+        definition.validate()?;
+        if self.get_attribute_definition(&definition.name).await?.is_some() {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyExists, format!("Attribute {} is already defined", definition.name)));
+        }
+        self.store(format!("schema_attribute:{}", definition.name.to_lowercase()), definition).await?;
+
+        let mut names: Vec<String> = self.load("all_schema_attributes_index").await?.unwrap_or_default();
+        names.push(definition.name.to_lowercase());
+        self.store("all_schema_attributes_index".to_string(), &names).await?;
+
+        if definition.indexed {
+            let mut index: HashMap<String, Vec<Uuid>> = HashMap::new();
+            let users = self.get_all_users().await?.into_iter().map(|user| (user.id, user.meta));
+            let groups = self.get_all_groups().await?.into_iter().map(|group| (group.id, group.meta));
+            let ous = self.get_all_ous().await?.into_iter().map(|ou| (ou.id, ou.meta));
+            let contacts = self.get_all_contacts().await?.into_iter().map(|contact| (contact.id, contact.meta));
+            for (id, meta) in users.chain(groups).chain(ous).chain(contacts) {
+                for value in meta.get(&definition.name).map(|raw| schema::values(raw)).unwrap_or_default() {
+                    index.entry(value.to_lowercase()).or_default().push(id);
+                }
+            }
+            self.store(format!("attribute_index:{}", definition.name.to_lowercase()), &index).await?;
+        }
+
+        self.log_action("define_attribute", &format!("attribute:{} syntax:{:?}", definition.name, definition.syntax), None).await?;
+        Ok(())
+    }
+
+    /// Определение атрибута по имени без учёта регистра
+    pub async fn get_attribute_definition(&self, name: &str) -> Result<Option<AttributeDefinition>, DirectoryError> {
+        self.load(&format!("schema_attribute:{}", name.to_lowercase())).await
+    }
+
+    pub async fn get_schema(&self) -> Result<Vec<AttributeDefinition>, DirectoryError> {
+        let names: Vec<String> = self.load("all_schema_attributes_index").await?.unwrap_or_default();
+        let mut schema = Vec::new();
+        for name in names {
+            if let Some(definition) = self.get_attribute_definition(&name).await? {
+                schema.push(definition);
+            }
+        }
+        Ok(schema)
+    }
+
+    /// Удалить определение; значения в объектах остаются, но больше не проверяются
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_attribute_definition(&self, name: &str) -> Result<(), DirectoryError> {
+        let definition = self.get_attribute_definition(name).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Attribute not found: {}", name)))?;
+        let key = definition.name.to_lowercase();
+
+        let names: Vec<String> = self.load("all_schema_attributes_index").await?.unwrap_or_default();
+        let names: Vec<String> = names.into_iter().filter(|other| *other != key).collect();
+        self.store("all_schema_attributes_index".to_string(), &names).await?;
+
+        let db = self.write_db().await?;
+        db.remove(&format!("schema_attribute:{}", key));
+        db.remove(&format!("attribute_index:{}", key));
+        drop(db);
+
+        self.log_action("delete_attribute", &format!("attribute:{}", definition.name), None).await?;
+        Ok(())
+    }
+
+    /// Изменения атрибутов из запроса: имена приводятся к определённым в схеме,
+    /// атрибуты вне схемы или другого класса объектов отклоняются
+    pub async fn schema_attribute_changes(&self, class: SchemaClass, changes: HashMap<String, Vec<String>>) -> Result<HashMap<String, Vec<String>>, DirectoryError> {
+        let mut normalized = HashMap::new();
+        for (name, values) in changes {
+            let definition = self.get_attribute_definition(&name).await?
+                .ok_or_else(|| DirectoryError::InvalidInput(format!("Attribute {} is not defined in the schema", name)))?;
+            if !definition.applies_to(class) {
+                return Err(DirectoryError::InvalidInput(format!("Attribute {} does not apply to {:?}", definition.name, class)));
+            }
+            normalized.insert(definition.name, values);
+        }
+        Ok(normalized)
+    }
+
+    /// Изменить дополнительные атрибуты объекта; пустой список значений удаляет атрибут
+    #[tracing::instrument(skip(self, changes))]
+    pub async fn set_attributes(&self, object: SecuredObject, changes: HashMap<String, Vec<String>>) -> Result<(), DirectoryError> {
+        let changes = self.schema_attribute_changes(object.into(), changes).await?;
+        let names: Vec<String> = changes.keys().cloned().collect();
+        match object {
+            SecuredObject::User(id) => {
+                let mut user = self.get_user(id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+                schema::apply_changes(&mut user.meta, changes);
+                user.updated_at = Utc::now();
+                self.update_user(&user).await?;
+            }
+            SecuredObject::Group(id) => {
+                let mut group = self.get_group(id).await?.ok_or_else(|| DirectoryError::NotFound("Group not found".to_string()))?;
+                schema::apply_changes(&mut group.meta, changes);
+                self.create_group(&group).await?;
+            }
+            SecuredObject::Ou(id) => {
+                let mut ou = self.get_ou(id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+                schema::apply_changes(&mut ou.meta, changes);
+                ou.updated_at = Utc::now();
+                self.create_ou(&ou).await?;
+            }
+            SecuredObject::Contact(id) => {
+                let mut contact = self.get_contact(id).await?.ok_or_else(|| DirectoryError::NotFound("Contact not found".to_string()))?;
+                schema::apply_changes(&mut contact.meta, changes);
+                contact.updated_at = Utc::now();
+                self.save_contact(&contact).await?;
+            }
+        }
+        self.log_action("set_attributes", &format!("{} attributes:{}", object, names.join(",")), Some(object.id())).await?;
+        Ok(())
+    }
+
+    /// Объекты с указанным значением индексируемого атрибута (без учёта регистра)
+    pub async fn find_by_attribute(&self, name: &str, value: &str) -> Result<Vec<Uuid>, DirectoryError> {
+        let definition = self.get_attribute_definition(name).await?
+            .ok_or_else(|| DirectoryError::InvalidInput(format!("Attribute {} is not defined in the schema", name)))?;
+        if !definition.indexed {
+            return Err(DirectoryError::InvalidInput(format!("Attribute {} is not indexed", definition.name)));
+        }
+        let index: HashMap<String, Vec<Uuid>> = self.load(&format!("attribute_index:{}", definition.name.to_lowercase())).await?.unwrap_or_default();
+        Ok(index.get(&value.to_lowercase()).cloned().unwrap_or_default())
+    }
+
+    async fn validate_attributes(&self, class: SchemaClass, meta: &HashMap<String, String>) -> Result<(), DirectoryError> {
+        let schema = self.get_schema().await?;
+        Ok(schema::validate_meta(&schema, class, meta)?)
+    }
+
+    /// Обновить индексы индексируемых атрибутов после записи объекта (`new = None` — объект удалён)
+    async fn reindex_attributes(&self, id: Uuid, old: Option<&HashMap<String, String>>, new: Option<&HashMap<String, String>>) -> Result<(), DirectoryError> {
+        let values = |meta: Option<&HashMap<String, String>>, name: &str| -> HashSet<String> {
+            meta.and_then(|meta| meta.get(name))
+                .map(|raw| schema::values(raw).into_iter().map(|value| value.to_lowercase()).collect())
+                .unwrap_or_default()
+        };
+
+        for definition in self.get_schema().await?.into_iter().filter(|definition| definition.indexed) {
+            let (before, after) = (values(old, &definition.name), values(new, &definition.name));
+            if before == after {
+                continue;
+            }
+            let key = format!("attribute_index:{}", definition.name.to_lowercase());
+            let mut index: HashMap<String, Vec<Uuid>> = self.load(&key).await?.unwrap_or_default();
+            for value in before.difference(&after) {
+                if let Some(ids) = index.get_mut(value) {
+                    ids.retain(|other| *other != id);
+                    if ids.is_empty() {
+                        index.remove(value);
+                    }
+                }
+            }
+            for value in after.difference(&before) {
+                let ids = index.entry(value.clone()).or_default();
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+            self.store(key, &index).await?;
+        }
+        Ok(())
+    }
+
+    /// Атрибуты схемы класса `class` из записи LDIF
+    async fn ldif_attributes(&self, entry: &LdifEntry, class: SchemaClass, meta: &mut HashMap<String, String>) -> Result<(), DirectoryError> {
+        for definition in self.get_schema().await?.into_iter().filter(|definition| definition.applies_to(class)) {
+            let values: Vec<String> = entry.get_all(&definition.name).into_iter().map(str::to_string).collect();
+            if !values.is_empty() {
+                schema::apply_changes(meta, HashMap::from([(definition.name, values)]));
+            }
+        }
+        Ok(())
+    }
+
+    // ================= GPO =================
+
+    #[tracing::instrument(skip_all, fields(gpo_id = %gpo.id))]
+    pub async fn create_gpo(&self, gpo: &GroupPolicy) -> Result<(), DirectoryError> {
+        gpo.validate().map_err(|e| DirectoryError::InvalidInput(e))?;
+        for target_id in &gpo.linked_to {
+            if !self.is_gpo_link_target(*target_id).await? {
+                return Err(DirectoryError::NotFound(format!("GPO link target not found: {} is neither an OU nor a domain", target_id)));
+            }
+        }
+
+        // JSON, а не bincode: тип, цель и значения настроек — помеченные перечисления serde,
+        // которые bincode не читает
+        let data = serde_json::to_string(gpo).map_err(|e| DirectoryError::Serialization(e.to_string()))?;
+        self.store(format!("gpo:{}", gpo.id), &data).await?;
+        for target_id in &gpo.linked_to {
+            let key = format!("gpo_link:{}", target_id);
+            let mut gpo_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&key).await?.unwrap_or_else(|| HashSet::new());
+            gpo_ids.insert(gpo.id);
+            self.store(key, &gpo_ids).await?;
+        }
+
+        let all_gpos: Vec<Uuid> = self.load::<Vec<Uuid>>("all_gpos_index").await?.unwrap_or_default();
+        if !all_gpos.contains(&gpo.id) {
+            let mut updated = all_gpos;
+            updated.push(gpo.id);
+            self.store("all_gpos_index".to_string(), &updated).await?;
+        }
+
+        self.log_action("create_gpo", &format!("gpo:{}", gpo.id), None).await?;
+        Ok(())
+    }
+
+    /// GPO привязывается к OU или домену
+    async fn is_gpo_link_target(&self, id: Uuid) -> Result<bool, DirectoryError> {
+        Ok(self.get_ou(id).await?.is_some() || self.get_domain(id).await?.is_some())
+    }
+
+    pub async fn get_gpo(&self, id: Uuid) -> Result<Option<GroupPolicy>, DirectoryError> {
+        match self.load::<String>(&format!("gpo:{}", id)).await? {
+            Some(data) => serde_json::from_str(&data).map(Some).map_err(|e| DirectoryError::Serialization(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_all_gpos(&self) -> Result<Vec<GroupPolicy>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_gpos_index").await?.unwrap_or_default();
+        let mut gpos = Vec::new();
+        for id in ids {
+            if let Some(gpo) = self.get_gpo(id).await? {
+                gpos.push(gpo);
+            }
+        }
+        Ok(gpos)
+    }
+
+    pub async fn find_gpos_for_ou(&self, ou_id: Uuid) -> Result<Vec<GroupPolicy>, DirectoryError> {
+        let ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&format!("gpo_link:{}", ou_id)).await?.unwrap_or_else(|| HashSet::new());
+        let mut gpos = Vec::new();
+        for id in ids {
+            if let Some(gpo) = self.get_gpo(id).await? {
+                gpos.push(gpo);
+            }
+        }
+        Ok(gpos)
+    }
+
+    pub async fn find_gpos_for_domain(&self, domain_id: Uuid) -> Result<Vec<GroupPolicy>, DirectoryError> {
+        let ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&format!("gpo_link:{}", domain_id)).await?.unwrap_or_else(|| HashSet::new());
+        let mut gpos = Vec::new();
+        for id in ids {
+            if let Some(gpo) = self.get_gpo(id).await? {
+                gpos.push(gpo);
+            }
+        }
+        Ok(gpos)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn link_gpo_to_ou(&self, gpo_id: Uuid, ou_id: Uuid) -> Result<(), DirectoryError> {
+        let _gpo = self.get_gpo(gpo_id).await?.ok_or_else(|| DirectoryError::NotFound("GPO not found".to_string()))?;
+        let mut ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+
+        if !ou.linked_gpos.contains(&gpo_id) {
+            ou.linked_gpos.push(gpo_id);
+            ou.enforced = true;
+            ou.update_gplink();
+            ou.updated_at = Utc::now();
+
+            self.store(format!("ou:{}", ou.id), &ou).await?;
+
+            let index_key = format!("gpo_link:{}", ou_id);
+            let mut gpo_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&index_key).await?.unwrap_or_else(|| HashSet::new());
+            gpo_ids.insert(gpo_id);
+            self.store(index_key, &gpo_ids).await?;
+        }
+
+        self.log_action("link_gpo_to_ou", &format!("gpo:{} ou:{}", gpo_id, ou_id), None).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn unlink_gpo_from_ou(&self, gpo_id: Uuid, ou_id: Uuid) -> Result<(), DirectoryError> {
+        let mut ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+
+        if ou.linked_gpos.contains(&gpo_id) {
+            ou.linked_gpos.retain(|id| id != &gpo_id);
+            ou.update_gplink();
+            ou.updated_at = Utc::now();
+
+            self.store(format!("ou:{}", ou.id), &ou).await?;
+
+            let index_key = format!("gpo_link:{}", ou_id);
+            let mut gpo_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&index_key).await?.unwrap_or_else(|| HashSet::new());
+            gpo_ids.remove(&gpo_id);
+            self.store(index_key, &gpo_ids).await?;
+        }
+
+        self.log_action("unlink_gpo_from_ou", &format!("gpo:{} ou:{}", gpo_id, ou_id), None).await?;
+        Ok(())
+    }
+
+    /// Удалить GPO вместе со всеми её привязками к OU и домену
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_gpo(&self, gpo_id: Uuid) -> Result<(), DirectoryError> {
+        let gpo = self.get_gpo(gpo_id).await?.ok_or_else(|| DirectoryError::NotFound("GPO not found".to_string()))?;
+
+        for ou in self.get_all_ous().await? {
+            if ou.linked_gpos.contains(&gpo_id) {
+                self.unlink_gpo_from_ou(gpo_id, ou.id).await?;
+            }
+        }
+        for target_id in &gpo.linked_to {
+            let key = format!("gpo_link:{}", target_id);
+            let mut gpo_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&key).await?.unwrap_or_default();
+            gpo_ids.remove(&gpo_id);
+            self.store(key, &gpo_ids).await?;
+        }
+
+        let all_gpos: Vec<Uuid> = self.load::<Vec<Uuid>>("all_gpos_index").await?.unwrap_or_default();
+        let updated_gpos: Vec<Uuid> = all_gpos.into_iter().filter(|id| *id != gpo_id).collect();
+        self.store("all_gpos_index".to_string(), &updated_gpos).await?;
+
+        let db = self.write_db().await?;
+        remove_object(&db, &self.cache, &format!("gpo:{}", gpo_id))?;
+        drop(db);
+
+        self.log_action("delete_gpo", &format!("gpo:{}", gpo_id), None).await?;
+        Ok(())
+    }
+
+    pub async fn set_block_inheritance(&self, ou_id: Uuid, block: bool) -> Result<(), DirectoryError> {
+        let mut ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+
+        ou.block_inheritance = block;
+        ou.update_gpoptions();
+        ou.updated_at = Utc::now();
+
+        self.store(format!("ou:{}", ou.id), &ou).await?;
+
+        self.log_action("set_block_inheritance", &format!("ou:{} block:{}", ou_id, block), None).await?;
+        Ok(())
+    }
+
+    pub async fn set_gpo_enforced(&self, ou_id: Uuid, enforced: bool) -> Result<(), DirectoryError> {
+        let mut ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+
+        ou.enforced = enforced;
+        ou.update_gplink();
+        ou.updated_at = Utc::now();
+
+        self.store(format!("ou:{}", ou.id), &ou).await?;
+
+        self.log_action("set_gpo_enforced", &format!("ou:{} enforced:{}", ou_id, enforced), None).await?;
+        Ok(())
+    }
+
+    pub async fn is_gpo_applicable_to(
+        &self,
+        gpo: &GroupPolicy,
+        principal_sid: &SecurityIdentifier,
+    ) -> Result<bool, DirectoryError> {
+        if gpo.security_filtering.is_empty() {
+            return Ok(true);
+        }
+
+        for filter in &gpo.security_filtering {
+            if let SidOrId::Sid(sid) = filter {
+                if sid == principal_sid {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub async fn get_effective_gpos_for_ou(
+        &self,
+        ou_id: Uuid,
+    ) -> Result<Vec<GroupPolicy>, DirectoryError> {
+        let mut all_gpos = Vec::new();
+        let mut visited_ou_ids = HashSet::new();
+        let mut current_ou_id = Some(ou_id);
+
+        while let Some(ou_id) = current_ou_id {
+            if visited_ou_ids.contains(&ou_id) {
+                return Err(DirectoryError::InvalidInput("Circular OU hierarchy detected".to_string()));
+            }
+            visited_ou_ids.insert(ou_id);
+
+            let ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+
+            if !all_gpos.is_empty() && ou.block_inheritance {
+                let gpos = self.find_gpos_for_ou(ou_id).await?;
+                let enforced: Vec<_> = gpos.into_iter().filter(|g| g.enforced).collect();
+                all_gpos.extend(enforced);
+                break;
+            }
+
+            let mut gpos = self.find_gpos_for_ou(ou_id).await?;
+            gpos.sort_by(|a, b| b.enforced.cmp(&a.enforced).then_with(|| a.order.cmp(&b.order)));
+            all_gpos.extend(gpos);
+
+            current_ou_id = ou.parent;
+        }
+
+        let mut seen = HashSet::new();
+        let mut unique = Vec::new();
+        for gpo in all_gpos {
+            if seen.insert(gpo.id) {
+                unique.push(gpo);
+            }
+        }
+
+        Ok(unique)
+    }
+
+    pub async fn get_effective_gpos_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<GroupPolicy>, DirectoryError> {
+        let user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
+
+        let mut all_gpos = Vec::new();
+
+        if let Some(ou_id) = user.organizational_unit {
+            let gpos = self.get_effective_gpos_for_ou(ou_id).await?;
+            all_gpos.extend(gpos);
+        }
+
+        // Фильтрация безопасности GPO — по SID пользователя и его групп, включая динамические
+        let group_sids = self.get_token_groups(user_id).await?;
+
+        if let Some(domain_id) = user.domains.get(0) {
+            let gpos = self.find_gpos_for_domain(*domain_id).await?;
+            all_gpos.extend(gpos);
+        }
+
+        let mut seen = HashSet::new();
+        let mut unique = Vec::new();
+        for gpo in all_gpos {
+            if seen.insert(gpo.id) {
+                unique.push(gpo);
+            }
+        }
+
+        unique.retain(|gpo| gpo.is_applicable_to(&user.sid, &group_sids));
+        unique.sort_by(|a, b| b.enforced.cmp(&a.enforced).then_with(|| a.order.cmp(&b.order)));
+
+        Ok(unique)
+    }
+
+    // ================= POLICY AGENTS =================
+
+    /// Check-in агента политик от имени `caller`: компьютер — сам `caller`, если это учётная
+    /// запись компьютера, иначе `computer` (имя без `$`), на котором вошёл пользователь.
+    /// Сохраняет отчёты агента и возвращает итоговые политики по убыванию приоритета
+    #[tracing::instrument(skip_all, fields(principal = %caller.username))]
+    pub async fn agent_checkin(
+        &self,
+        caller: &User,
+        computer: Option<&str>,
+        reports: Vec<PolicyReport>,
+    ) -> Result<(AgentCheckin, Vec<GroupPolicy>), DirectoryError> {
+        let computer = match computer.map(|name| name.trim_end_matches('$')) {
+            None if is_computer_account(caller) => caller.clone(),
+            Some(name) if is_computer_account(caller) => {
+                if !caller.username.trim_end_matches('$').eq_ignore_ascii_case(name) {
+                    return Err(DirectoryError::InvalidInput(format!("Computer account {} cannot check in for {}", caller.username, name)));
+                }
+                caller.clone()
+            }
+            None => return Err(DirectoryError::InvalidInput("computer is required for a user agent".to_string())),
+            Some(name) => self.find_user_by_username(&format!("{}$", name)).await?
+                .filter(is_computer_account)
+                .ok_or_else(|| DirectoryError::NotFound(format!("Computer not found: {}", name)))?,
+        };
+
+        let gpos = self.get_effective_gpos_for_user(caller.id).await?;
+        let expected = gpos.iter().map(|gpo| ExpectedPolicy { gpo_id: gpo.id, version: gpo.version }).collect();
+        let checkin = AgentCheckin::new(&computer, caller, expected, reports);
+        self.store(format!("agent_checkin:{}:{}", computer.id, caller.id), &checkin).await?;
+        let all: Vec<(Uuid, Uuid)> = self.load::<Vec<(Uuid, Uuid)>>("agent_checkins_index").await?.unwrap_or_default();
+        if !all.contains(&(computer.id, caller.id)) {
+            let mut updated = all;
+            updated.push((computer.id, caller.id));
+            self.store("agent_checkins_index".to_string(), &updated).await?;
+        }
+        Ok((checkin, gpos))
+    }
+
+    /// Последние check-in всех агентов
+    pub async fn get_agent_checkins(&self) -> Result<Vec<AgentCheckin>, DirectoryError> {
+        let ids: Vec<(Uuid, Uuid)> = self.load::<Vec<(Uuid, Uuid)>>("agent_checkins_index").await?.unwrap_or_default();
+        let mut checkins = Vec::new();
+        for (computer_id, principal_id) in ids {
+            if let Some(checkin) = self.load::<AgentCheckin>(&format!("agent_checkin:{}:{}", computer_id, principal_id)).await? {
+                checkins.push(checkin);
+            }
+        }
+        checkins.sort_by(|a, b| a.computer.to_lowercase().cmp(&b.computer.to_lowercase()).then_with(|| a.principal.cmp(&b.principal)));
+        Ok(checkins)
+    }
+
+    // ================= CERTIFICATE AUTHORITY =================
+
+    /// Создать внутренний CA (`ca.common_name`, `ca.ca_validity_days`). С `force` прежний CA
+    /// заменяется: выданные им сертификаты перестанут проверяться
+    #[tracing::instrument(skip(self))]
+    pub async fn init_ca(&self, force: bool) -> Result<CertificateAuthority, DirectoryError> {
+        if !force && self.load::<CertificateAuthority>("ca").await?.is_some() {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyExists, "Certificate authority is already initialized".to_string()));
+        }
+        let config = self.ca.read().unwrap().clone();
+        let ca = crate::ca::generate(&config.common_name, chrono::Duration::days(config.ca_validity_days.into()))?;
+        self.store("ca".to_string(), &ca).await?;
+
+        self.log_action("init_ca", &format!("cn:{}", config.common_name), None).await?;
+        Ok(ca)
+    }
+
+    async fn certificate_authority(&self) -> Result<CertificateAuthority, DirectoryError> {
+        self.load("ca").await?
+            .ok_or_else(|| DirectoryError::NotFound("Certificate authority is not initialized; run `nextDomen ca init`".to_string()))
+    }
+
+    /// Сертификат CA в PEM — для `tls.ca_cert_file` и доверенных корней клиентов
+    pub async fn ca_certificate(&self) -> Result<String, DirectoryError> {
+        Ok(self.certificate_authority().await?.cert_pem)
+    }
+
+    /// Выпустить сертификат учётной записи по CSR (PEM или DER). Из CSR берётся только открытый
+    /// ключ: subject и SAN — из каталога (пользователь — имя и UPN, компьютер — SPN `HOST/...`)
+    #[tracing::instrument(skip(self, subject, csr), fields(subject = %subject.username))]
+    pub async fn issue_certificate(&self, subject: &User, csr: &[u8]) -> Result<IssuedCertificate, DirectoryError> {
+        if !subject.enabled {
+            return Err(DirectoryError::AccessDenied(format!("Account {} is disabled", subject.username)));
+        }
+        let spec = certificate_subject(subject)?;
+        let config = self.ca.read().unwrap().clone();
+        let ca = self.certificate_authority().await?;
+        let signed = crate::ca::sign(&ca, csr, &spec, chrono::Duration::days(config.validity_days.into()), config.crl_url.as_deref())?;
+
+        let certificate = IssuedCertificate {
+            serial: signed.serial,
+            subject_id: subject.id,
+            subject: subject.username.clone(),
+            kind: spec.kind,
+            sans: spec.sans,
+            not_before: signed.not_before,
+            not_after: signed.not_after,
+            issued_by: ActorContext::current().and_then(|actor| actor.user_id),
+            revoked_at: None,
+            revocation_reason: None,
+            pem: signed.pem,
+        };
+        self.store(format!("certificate:{}", certificate.serial), &certificate).await?;
+        let mut index: Vec<String> = self.load("certificates_index").await?.unwrap_or_default();
+        index.push(certificate.serial.clone());
+        self.store("certificates_index".to_string(), &index).await?;
+
+        let mut event = AuditEvent::new("issue_certificate", AuditResult::Success);
+        event.target_id = Some(subject.id);
+        event.metadata.insert("serial".to_string(), certificate.serial.clone());
+        event.metadata.insert("kind".to_string(), certificate.kind.as_str().to_string());
+        event.metadata.insert("not_after".to_string(), certificate.not_after.to_rfc3339());
+        self.record(event).await?;
+        Ok(certificate)
+    }
+
+    /// Автоматическая выдача компьютеру (`POST /api/ca/enroll`): агент входит учётной записью
+    /// компьютера и получает сертификат на неё
+    pub async fn enroll_computer_certificate(&self, caller: &User, csr: &[u8]) -> Result<IssuedCertificate, DirectoryError> {
+        if !is_computer_account(caller) {
+            return Err(DirectoryError::AccessDenied(format!("{} is not a computer account", caller.username)));
+        }
+        self.issue_certificate(caller, csr).await
+    }
+
+    /// Сертификат сервера nextDomen для DNS-имён `names` (первое — CN) с новым ключом — для TLS
+    /// при первом запуске. Учётной записи за ним нет, в список выданных он не попадает.
+    /// `None`, если CA не создан. Возвращает сертификат и закрытый ключ в PEM
+    pub async fn issue_server_certificate(&self, names: &[String]) -> Result<Option<(String, String)>, DirectoryError> {
+        let Some(ca) = self.load::<CertificateAuthority>("ca").await? else {
+            return Ok(None);
+        };
+        let common_name = names.first().cloned().unwrap_or_else(|| "localhost".to_string());
+        let (csr, key_pem) = crate::ca::generate_csr(&common_name)?;
+        let spec = crate::ca::CertificateSubject { common_name, kind: CertificateKind::Computer, sans: names.to_vec() };
+        let config = self.ca.read().unwrap().clone();
+        let signed = crate::ca::sign(&ca, &csr, &spec, chrono::Duration::days(config.validity_days.into()), config.crl_url.as_deref())?;
+
+        self.log_action("issue_server_certificate", &format!("serial:{} sans:{}", signed.serial, names.join(",")), None).await?;
+        Ok(Some((signed.pem, key_pem)))
+    }
+
+    pub async fn get_certificate(&self, serial: &str) -> Result<Option<IssuedCertificate>, DirectoryError> {
+        self.load(&format!("certificate:{}", serial.to_lowercase())).await
+    }
+
+    /// Выданные сертификаты, новые первыми; с `subject_id` — только этой учётной записи
+    pub async fn list_certificates(&self, subject_id: Option<Uuid>) -> Result<Vec<IssuedCertificate>, DirectoryError> {
+        let index: Vec<String> = self.load("certificates_index").await?.unwrap_or_default();
+        let mut certificates = Vec::new();
+        for serial in index {
+            if let Some(certificate) = self.get_certificate(&serial).await?
+                && subject_id.is_none_or(|id| certificate.subject_id == id)
+            {
+                certificates.push(certificate);
+            }
+        }
+        certificates.sort_by_key(|certificate| std::cmp::Reverse(certificate.not_before));
+        Ok(certificates)
+    }
+
+    /// Отозвать сертификат: он попадёт в следующий CRL
+    #[tracing::instrument(skip(self))]
+    pub async fn revoke_certificate(&self, serial: &str, reason: RevocationReason) -> Result<IssuedCertificate, DirectoryError> {
+        let mut certificate = self.get_certificate(serial).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Certificate not found: {}", serial)))?;
+        if certificate.revoked_at.is_some() {
+            return Err(DirectoryError::InvalidInput(format!("Certificate {} is already revoked", certificate.serial)));
+        }
+        certificate.revoked_at = Some(Utc::now());
+        certificate.revocation_reason = Some(reason);
+        self.store(format!("certificate:{}", certificate.serial), &certificate).await?;
+
+        let mut event = AuditEvent::new("revoke_certificate", AuditResult::Success);
+        event.target_id = Some(certificate.subject_id);
+        event.metadata.insert("serial".to_string(), certificate.serial.clone());
+        event.metadata.insert("reason".to_string(), reason.as_str().to_string());
+        self.record(event).await?;
+        Ok(certificate)
+    }
+
+    /// Новый CRL (DER) с отозванными и ещё не истёкшими сертификатами; nextUpdate — через
+    /// `ca.crl_validity_secs`. Номер CRL растёт с каждым выпуском, кроме базы только для чтения
+    pub async fn certificate_revocation_list(&self) -> Result<Vec<u8>, DirectoryError> {
+        let mut ca = self.certificate_authority().await?;
+        ca.crl_number += 1;
+        if !self.is_read_only() {
+            self.store("ca".to_string(), &ca).await?;
+        }
+        let now = Utc::now();
+        let revoked: Vec<IssuedCertificate> = self.list_certificates(None).await?.into_iter()
+            .filter(|certificate| certificate.revoked_at.is_some() && certificate.not_after > now)
+            .collect();
+        let validity = chrono::Duration::seconds(self.ca.read().unwrap().crl_validity_secs as i64);
+        Ok(crate::ca::crl(&ca, &revoked, validity)?)
+    }
+
+    // ================= JOBS =================
+
+    /// Сохранить выполнение фоновой задачи (последние `JOB_HISTORY_LEN`) и передать событие
+    /// `job_run` подписчикам (журнал, SSE); в поиск по аудиту оно не попадает
+    pub async fn record_job_run(&self, run: &JobRun) -> Result<(), DirectoryError> {
+        let key = format!("job_runs:{}", run.job);
+        let mut runs: Vec<JobRun> = self.load(&key).await?.unwrap_or_default();
+        runs.insert(0, run.clone());
+        runs.truncate(JOB_HISTORY_LEN);
+        self.store(key, &runs).await?;
+
+        let mut event = AuditEvent::new("job_run", if run.succeeded() { AuditResult::Success } else { AuditResult::Failure });
+        event.metadata.insert("job".to_string(), run.job.clone());
+        event.metadata.insert("trigger".to_string(), run.trigger.as_str().to_string());
+        event.metadata.insert("duration_ms".to_string(), run.duration_ms().to_string());
+        if let Some(processed) = run.processed {
+            event.metadata.insert("processed".to_string(), processed.to_string());
+        }
+        if let Some(error) = &run.error {
+            event.metadata.insert("error".to_string(), error.clone());
+        }
+        self.events.emit(event);
+        Ok(())
+    }
+
+    /// Выполнения задачи, последние первыми
+    pub async fn get_job_runs(&self, job: &str) -> Result<Vec<JobRun>, DirectoryError> {
+        Ok(self.load(&format!("job_runs:{}", job)).await?.unwrap_or_default())
+    }
+
+    // ================= DOMAINS =================
+
+    #[tracing::instrument(skip_all, fields(dns_name = %domain.dns_name))]
+    pub async fn create_domain(&self, domain: &Domain) -> Result<(), DirectoryError> {
+        let dns_name = domain.dns_name.trim_end_matches('.');
+        if let Some(existing) = self.find_domain_by_dns_name(dns_name).await?
+            && existing.id != domain.id
+        {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyExists, format!("Domain {} already exists", dns_name)));
+        }
+
+        self.store(format!("domain:{}", domain.id), domain).await?;
+        self.store(lookup_key("dns_domain_index", dns_name), &domain.id).await?;
+
+        let mut all_domains: Vec<Uuid> = self.load::<Vec<Uuid>>("all_domains_index").await?.unwrap_or_default();
+        if !all_domains.contains(&domain.id) {
+            all_domains.push(domain.id);
+            self.store("all_domains_index".to_string(), &all_domains).await?;
+        }
+
+        self.log_action("create_domain", &format!("dns_name:{}", dns_name), None).await?;
+        Ok(())
+    }
+
+    pub async fn get_domain(&self, id: Uuid) -> Result<Option<Domain>, DirectoryError> {
+        self.load(&format!("domain:{}", id)).await
+    }
+
+    pub async fn find_domain_by_dns_name(&self, dns_name: &str) -> Result<Option<Domain>, DirectoryError> {
+        let index_key = lookup_key("dns_domain_index", dns_name.trim_end_matches('.'));
+        let domain_id: Option<Uuid> = self.load(&index_key).await?;
+        if let Some(id) = domain_id {
+            self.get_domain(id).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn get_all_domains(&self) -> Result<Vec<Domain>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_domains_index").await?.unwrap_or_default();
+        let mut domains = Vec::new();
+        for id in ids {
+            if let Some(domain) = self.get_domain(id).await? {
+                domains.push(domain);
+            }
+        }
+        Ok(domains)
+    }
+
+    /// Зарегистрировать контроллер домена (его записи появятся в DNS)
+    #[tracing::instrument(skip_all, fields(hostname = %dc.hostname))]
+    pub async fn register_domain_controller(&self, dc: &DomainControllerInfo) -> Result<(), DirectoryError> {
+        let domain = self.get_domain(dc.domain_id).await?
+            .ok_or_else(|| DirectoryError::NotFound("Domain not found".to_string()))?;
+
+        self.store(format!("dc:{}", dc.id), dc).await?;
+        let index_key = format!("domain_dcs_index:{}", dc.domain_id);
+        let mut dcs: Vec<Uuid> = self.load::<Vec<Uuid>>(&index_key).await?.unwrap_or_default();
+        if !dcs.contains(&dc.id) {
+            dcs.push(dc.id);
+            self.store(index_key, &dcs).await?;
+        }
+
+        self.bump_dns_serial(&domain.dns_name).await?;
+        self.log_action("register_domain_controller", &format!("hostname:{}", dc.hostname), None).await?;
+        Ok(())
+    }
+
+    pub async fn get_domain_controllers(&self, domain_id: Uuid) -> Result<Vec<DomainControllerInfo>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>(&format!("domain_dcs_index:{}", domain_id)).await?.unwrap_or_default();
+        let mut dcs = Vec::new();
+        for id in ids {
+            if let Some(dc) = self.load::<DomainControllerInfo>(&format!("dc:{}", id)).await? {
+                dcs.push(dc);
+            }
+        }
+        Ok(dcs)
+    }
+
+    // ================= ORGANIZATIONS =================
+
+    #[tracing::instrument(skip_all, fields(name = %organization.name))]
+    pub async fn create_organization(&self, organization: &Organization) -> Result<(), DirectoryError> {
+        organization.validate().map_err(DirectoryError::InvalidInput)?;
+        let name_key = lookup_key("organization_name_index", &organization.name);
+        if self.load::<Uuid>(&name_key).await?.is_some() {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyExists, format!("Organization {} already exists", organization.name)));
+        }
+
+        self.store(format!("organization:{}", organization.id), organization).await?;
+        self.store(name_key, &organization.id).await?;
+        let mut all: Vec<Uuid> = self.load("all_organizations_index").await?.unwrap_or_default();
+        all.push(organization.id);
+        self.store("all_organizations_index".to_string(), &all).await?;
+
+        self.log_action("create_organization", &format!("name:{}", organization.name), Some(organization.id)).await?;
+        Ok(())
+    }
+
+    pub async fn get_organization(&self, id: Uuid) -> Result<Option<Organization>, DirectoryError> {
+        self.load(&format!("organization:{}", id)).await
+    }
+
+    pub async fn list_organizations(&self) -> Result<Vec<Organization>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load("all_organizations_index").await?.unwrap_or_default();
+        let mut organizations = Vec::new();
+        for id in ids {
+            if let Some(organization) = self.get_organization(id).await? {
+                organizations.push(organization);
+            }
+        }
+        Ok(organizations)
+    }
+
+    /// Сохранить реквизиты и администраторов; переименование переносит индекс имени.
+    /// Домены меняются только через `attach_domain` / `detach_domain`
+    #[tracing::instrument(skip_all, fields(id = %organization.id))]
+    pub async fn update_organization(&self, organization: &Organization) -> Result<Organization, DirectoryError> {
+        organization.validate().map_err(DirectoryError::InvalidInput)?;
+        let existing = self.get_organization(organization.id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Organization not found: {}", organization.id)))?;
+        if casefold::fold(&existing.name) != casefold::fold(&organization.name) {
+            let name_key = lookup_key("organization_name_index", &organization.name);
+            if self.load::<Uuid>(&name_key).await?.is_some() {
+                return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyExists, format!("Organization {} already exists", organization.name)));
+            }
+            self.write_db().await?.remove(&lookup_key("organization_name_index", &existing.name));
+            self.store(name_key, &organization.id).await?;
+        }
+
+        let updated = Organization {
+            domains: existing.domains,
+            default_domain_id: existing.default_domain_id,
+            created_at: existing.created_at,
+            updated_at: Utc::now(),
+            ..organization.clone()
+        };
+        self.store(format!("organization:{}", updated.id), &updated).await?;
+        self.log_action("update_organization", &format!("name:{}", updated.name), Some(updated.id)).await?;
+        Ok(updated)
     }
 
-    pub async fn get_all_groups(&self) -> Result<Vec<Group>, DirectoryError> {
-        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_groups_index").await?.unwrap_or_default();
-        let mut groups = Vec::new();
+    /// Удалить организацию; её домены остаются в каталоге без организации
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_organization(&self, id: Uuid) -> Result<(), DirectoryError> {
+        let organization = self.get_organization(id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Organization not found: {}", id)))?;
+        {
+            let db = self.write_db().await?;
+            remove_object(&db, &self.cache, &format!("organization:{}", id))?;
+            db.remove(&lookup_key("organization_name_index", &organization.name));
+        }
+        let mut all: Vec<Uuid> = self.load("all_organizations_index").await?.unwrap_or_default();
+        all.retain(|other| *other != id);
+        self.store("all_organizations_index".to_string(), &all).await?;
+
+        self.log_action("delete_organization", &format!("name:{}", organization.name), Some(id)).await?;
+        Ok(())
+    }
+
+    /// Присоединить домен; домен другой организации — ошибка. С `make_default` или первым
+    /// доменом он становится доменом по умолчанию
+    #[tracing::instrument(skip(self))]
+    pub async fn attach_domain(&self, id: Uuid, domain_id: Uuid, make_default: bool) -> Result<Organization, DirectoryError> {
+        let mut organization = self.get_organization(id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Organization not found: {}", id)))?;
+        let domain = self.get_domain(domain_id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Domain not found: {}", domain_id)))?;
+        if let Some(owner) = self.list_organizations().await?.into_iter().find(|other| other.id != id && other.domains.contains(&domain_id)) {
+            return Err(DirectoryError::AlreadyExists(
+                ErrorCode::AlreadyExists,
+                format!("Domain {} already belongs to organization {}", domain.dns_name, owner.name),
+            ));
+        }
+
+        if !organization.domains.contains(&domain_id) {
+            organization.domains.push(domain_id);
+        }
+        if make_default || organization.default_domain_id.is_none() {
+            organization.default_domain_id = Some(domain_id);
+        }
+        organization.updated_at = Utc::now();
+        self.store(format!("organization:{}", id), &organization).await?;
+        self.log_action("attach_organization_domain", &format!("domain:{}", domain.dns_name), Some(id)).await?;
+        Ok(organization)
+    }
+
+    /// Отсоединить домен; если он был доменом по умолчанию, им становится первый оставшийся
+    #[tracing::instrument(skip(self))]
+    pub async fn detach_domain(&self, id: Uuid, domain_id: Uuid) -> Result<Organization, DirectoryError> {
+        let mut organization = self.get_organization(id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Organization not found: {}", id)))?;
+        if !organization.domains.contains(&domain_id) {
+            return Err(DirectoryError::InvalidInput(format!("Domain {} does not belong to organization {}", domain_id, organization.name)));
+        }
+        organization.domains.retain(|other| *other != domain_id);
+        if organization.default_domain_id == Some(domain_id) {
+            organization.default_domain_id = organization.domains.first().copied();
+        }
+        organization.updated_at = Utc::now();
+        self.store(format!("organization:{}", id), &organization).await?;
+        self.log_action("detach_organization_domain", &format!("domain:{}", domain_id), Some(id)).await?;
+        Ok(organization)
+    }
+
+    /// Domain Admins управляют всеми организациями, администраторы организации — своей
+    pub async fn is_organization_admin(&self, organization: &Organization, user_id: Uuid) -> Result<bool, DirectoryError> {
+        Ok(organization.admins.contains(&user_id) || self.is_domain_admin(user_id).await?)
+    }
+
+    // ================= SITES AND SUBNETS =================
+
+    /// Создать или изменить сайт; имя уникально
+    #[tracing::instrument(skip(self, site), fields(name = %site.name))]
+    pub async fn save_site(&self, site: &Site) -> Result<(), DirectoryError> {
+        if site.name.trim().is_empty() {
+            return Err(DirectoryError::InvalidInput("Site name cannot be empty".to_string()));
+        }
+        if let Some(existing) = self.find_site_by_name(&site.name).await?
+            && existing.id != site.id
+        {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyExists, format!("Site {} already exists", site.name)));
+        }
+
+        let mut site = site.clone();
+        site.updated_at = Utc::now();
+        self.store(format!("site:{}", site.id), &site).await?;
+        let all: Vec<Uuid> = self.load::<Vec<Uuid>>("all_sites_index").await?.unwrap_or_default();
+        if !all.contains(&site.id) {
+            let mut updated = all;
+            updated.push(site.id);
+            self.store("all_sites_index".to_string(), &updated).await?;
+        }
+
+        self.log_action("save_site", &format!("name:{}", site.name), Some(site.id)).await?;
+        Ok(())
+    }
+
+    pub async fn get_site(&self, id: Uuid) -> Result<Option<Site>, DirectoryError> {
+        self.load(&format!("site:{}", id)).await
+    }
+
+    pub async fn find_site_by_name(&self, name: &str) -> Result<Option<Site>, DirectoryError> {
+        Ok(self.get_all_sites().await?.into_iter().find(|site| site.name.eq_ignore_ascii_case(name)))
+    }
+
+    /// Все сайты по имени
+    pub async fn get_all_sites(&self) -> Result<Vec<Site>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_sites_index").await?.unwrap_or_default();
+        let mut sites = Vec::new();
         for id in ids {
-            if let Some(group) = self.get_group(id).await? {
-                groups.push(group);
+            if let Some(site) = self.get_site(id).await? {
+                sites.push(site);
             }
         }
-        Ok(groups)
+        sites.sort_by_key(|site| site.name.to_lowercase());
+        Ok(sites)
     }
 
-    async fn add_member_to_index(&self, user_id: Uuid, group_id: Uuid) -> Result<(), DirectoryError> {
-        let key = format!("member_index:{}", user_id);
-        let mut group_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&key).await?.unwrap_or_else(|| HashSet::new());
-        group_ids.insert(group_id);
-        self.store(key, &group_ids).await
+    /// Удалить сайт; сначала нужно удалить его подсети или перенести их в другой сайт
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_site(&self, id: Uuid) -> Result<(), DirectoryError> {
+        let site = self.get_site(id).await?.ok_or_else(|| DirectoryError::NotFound(format!("Site not found: {}", id)))?;
+        let subnets = self.get_site_subnets(id).await?;
+        if !subnets.is_empty() {
+            return Err(DirectoryError::InvalidInput(format!(
+                "Site {} still has {} subnet(s); move or delete them first", site.name, subnets.len()
+            )));
+        }
+
+        let all: Vec<Uuid> = self.load::<Vec<Uuid>>("all_sites_index").await?.unwrap_or_default();
+        let updated: Vec<Uuid> = all.into_iter().filter(|site_id| *site_id != id).collect();
+        self.store("all_sites_index".to_string(), &updated).await?;
+
+        let db = self.write_db().await?;
+        remove_object(&db, &self.cache, &format!("site:{}", id))?;
+        drop(db);
+
+        self.log_action("delete_site", &format!("name:{}", site.name), Some(id)).await?;
+        Ok(())
     }
 
-    async fn remove_member_from_index(&self, user_id: Uuid, group_id: Uuid) -> Result<(), DirectoryError> {
-        let key = format!("member_index:{}", user_id);
-        let mut group_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&key).await?.unwrap_or_else(|| HashSet::new());
-        group_ids.remove(&group_id);
-        self.store(key, &group_ids).await
+    /// Создать или изменить подсеть; запись CIDR уникальна, сайт должен существовать
+    #[tracing::instrument(skip(self, subnet), fields(subnet = %subnet.cidr()))]
+    pub async fn save_subnet(&self, subnet: &Subnet) -> Result<(), DirectoryError> {
+        let site = self.get_site(subnet.site_id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Site not found: {}", subnet.site_id)))?;
+        if let Some(existing) = self.find_subnet(&subnet.cidr()).await?
+            && existing.id != subnet.id
+        {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyExists, format!("Subnet {} already exists", subnet.cidr())));
+        }
+
+        let mut subnet = subnet.clone();
+        subnet.updated_at = Utc::now();
+        self.store(format!("subnet:{}", subnet.id), &subnet).await?;
+        let all: Vec<Uuid> = self.load::<Vec<Uuid>>("all_subnets_index").await?.unwrap_or_default();
+        if !all.contains(&subnet.id) {
+            let mut updated = all;
+            updated.push(subnet.id);
+            self.store("all_subnets_index".to_string(), &updated).await?;
+        }
+
+        self.log_action("save_subnet", &format!("subnet:{} site:{}", subnet.cidr(), site.name), Some(subnet.id)).await?;
+        Ok(())
     }
 
-    // ================= ORGANIZATIONAL UNITS (OU) =================
+    pub async fn get_subnet(&self, id: Uuid) -> Result<Option<Subnet>, DirectoryError> {
+        self.load(&format!("subnet:{}", id)).await
+    }
 
-    pub async fn create_ou(&self, ou: &OrganizationalUnit) -> Result<(), DirectoryError> {
-        self.store(format!("ou:{}", ou.id), ou).await?;
-        self.store(format!("dn_index:{}", ou.dn), &ou.id).await?;
+    /// Подсеть по записи CIDR (`10.1.0.0/16`)
+    pub async fn find_subnet(&self, cidr: &str) -> Result<Option<Subnet>, DirectoryError> {
+        let (network, prefix_len) = crate::models::site::parse_cidr(cidr)?;
+        Ok(self.get_all_subnets().await?.into_iter()
+            .find(|subnet| subnet.network == network && subnet.prefix_len == prefix_len))
+    }
 
-        let all_ous: Vec<Uuid> = self.load::<Vec<Uuid>>("all_ous_index").await?.unwrap_or_default();
-        if !all_ous.contains(&ou.id) {
-            let mut updated = all_ous;
-            updated.push(ou.id);
-            self.store("all_ous_index".to_string(), &updated).await?;
+    /// Все подсети: IPv4, затем IPv6, по адресу сети
+    pub async fn get_all_subnets(&self) -> Result<Vec<Subnet>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_subnets_index").await?.unwrap_or_default();
+        let mut subnets = Vec::new();
+        for id in ids {
+            if let Some(subnet) = self.get_subnet(id).await? {
+                subnets.push(subnet);
+            }
         }
+        subnets.sort_by_key(|subnet| (subnet.network, subnet.prefix_len));
+        Ok(subnets)
+    }
 
-        self.log_action("create_ou", &format!("ou:{}", ou.dn), None).await?;
+    pub async fn get_site_subnets(&self, site_id: Uuid) -> Result<Vec<Subnet>, DirectoryError> {
+        Ok(self.get_all_subnets().await?.into_iter().filter(|subnet| subnet.site_id == site_id).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_subnet(&self, id: Uuid) -> Result<(), DirectoryError> {
+        let subnet = self.get_subnet(id).await?.ok_or_else(|| DirectoryError::NotFound(format!("Subnet not found: {}", id)))?;
+        let all: Vec<Uuid> = self.load::<Vec<Uuid>>("all_subnets_index").await?.unwrap_or_default();
+        let updated: Vec<Uuid> = all.into_iter().filter(|subnet_id| *subnet_id != id).collect();
+        self.store("all_subnets_index".to_string(), &updated).await?;
+
+        let db = self.write_db().await?;
+        remove_object(&db, &self.cache, &format!("subnet:{}", id))?;
+        drop(db);
+
+        self.log_action("delete_subnet", &format!("subnet:{}", subnet.cidr()), Some(id)).await?;
         Ok(())
     }
 
-    pub async fn get_ou(&self, id: Uuid) -> Result<Option<OrganizationalUnit>, DirectoryError> {
-        self.load(&format!("ou:{}", id)).await
+    /// Сайт клиента по его адресу: из подсетей, в которые входит адрес, выбирается самая узкая
+    /// (наибольший префикс), как в DC locator AD; `None` — адрес не входит ни в одну подсеть
+    pub async fn find_site_for_ip(&self, addr: std::net::IpAddr) -> Result<Option<Site>, DirectoryError> {
+        let subnet = self.get_all_subnets().await?.into_iter()
+            .filter(|subnet| subnet.contains(addr))
+            .max_by_key(|subnet| subnet.prefix_len);
+        match subnet {
+            Some(subnet) => self.get_site(subnet.site_id).await,
+            None => Ok(None),
+        }
     }
 
-    pub async fn find_ou_by_dn(&self, dn: &str) -> Result<Option<OrganizationalUnit>, DirectoryError> {
-        if let Some(ou_id) = self.load::<Uuid>(&format!("dn_index:{}", dn)).await? {
-            self.get_ou(ou_id).await
+    // ================= DNS =================
+
+    /// Динамические записи по полному имени
+    pub async fn get_dns_records(&self, name: &str) -> Result<Vec<DnsRecord>, DirectoryError> {
+        let key = format!("dns_record:{}", name.trim_end_matches('.').to_lowercase());
+        Ok(self.load::<Vec<DnsRecord>>(&key).await?.unwrap_or_default())
+    }
+
+    /// Заменить динамические записи имени; пустой список удаляет имя
+    #[tracing::instrument(skip(self, records))]
+    pub async fn set_dns_records(&self, zone: &str, name: &str, records: &[DnsRecord]) -> Result<(), DirectoryError> {
+        let key = format!("dns_record:{}", name.trim_end_matches('.').to_lowercase());
+        if records.is_empty() {
+            let db = self.write_db().await?;
+            db.remove(&key);
+            drop(db);
         } else {
-            Ok(None)
+            self.store(key, &records.to_vec()).await?;
         }
+
+        self.bump_dns_serial(zone).await?;
+        self.log_action("dns_update", &format!("name:{} records:{}", name, records.len()), None).await?;
+        Ok(())
     }
 
-    pub async fn get_all_ous(&self) -> Result<Vec<OrganizationalUnit>, DirectoryError> {
-        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_ous_index").await?.unwrap_or_default();
-        let mut ous = Vec::new();
+    /// Серийный номер зоны (растёт при каждом изменении)
+    pub async fn get_dns_serial(&self, zone: &str) -> Result<u32, DirectoryError> {
+        let key = format!("dns_serial:{}", zone.trim_end_matches('.').to_lowercase());
+        Ok(self.load::<u32>(&key).await?.unwrap_or(1))
+    }
+
+    async fn bump_dns_serial(&self, zone: &str) -> Result<(), DirectoryError> {
+        let serial = self.get_dns_serial(zone).await?.wrapping_add(1);
+        self.store(format!("dns_serial:{}", zone.trim_end_matches('.').to_lowercase()), &serial).await
+    }
+
+    // ================= OAUTH CLIENTS =================
+
+    #[tracing::instrument(skip_all, fields(client_id = %client.client_id))]
+    pub async fn create_oauth_client(&self, client: &OAuthClient) -> Result<(), DirectoryError> {
+        if client.redirect_uris.is_empty() {
+            return Err(DirectoryError::InvalidInput("Client must have at least one redirect URI".to_string()));
+        }
+        if let Some(existing) = self.get_oauth_client(&client.client_id).await?
+            && existing.id != client.id
+        {
+            return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyExists, format!("OAuth client {} already exists", client.client_id)));
+        }
+
+        self.store(format!("oauth_client:{}", client.client_id), client).await?;
+
+        let mut all_clients: Vec<String> = self.load::<Vec<String>>("all_oauth_clients_index").await?.unwrap_or_default();
+        if !all_clients.contains(&client.client_id) {
+            all_clients.push(client.client_id.clone());
+            self.store("all_oauth_clients_index".to_string(), &all_clients).await?;
+        }
+
+        self.log_action("create_oauth_client", &format!("client_id:{}", client.client_id), None).await?;
+        Ok(())
+    }
+
+    pub async fn get_oauth_client(&self, client_id: &str) -> Result<Option<OAuthClient>, DirectoryError> {
+        self.load(&format!("oauth_client:{}", client_id)).await
+    }
+
+    pub async fn get_all_oauth_clients(&self) -> Result<Vec<OAuthClient>, DirectoryError> {
+        let ids: Vec<String> = self.load::<Vec<String>>("all_oauth_clients_index").await?.unwrap_or_default();
+        let mut clients = Vec::new();
         for id in ids {
-            if let Some(ou) = self.get_ou(id).await? {
-                ous.push(ou);
+            if let Some(client) = self.get_oauth_client(&id).await? {
+                clients.push(client);
             }
         }
-        Ok(ous)
+        Ok(clients)
     }
 
-    pub async fn delete_ou(&self, ou_id: Uuid) -> Result<(), DirectoryError> {
-        let ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_oauth_client(&self, client_id: &str) -> Result<(), DirectoryError> {
+        if self.get_oauth_client(client_id).await?.is_none() {
+            return Err(DirectoryError::NotFound(format!("OAuth client not found: {}", client_id)));
+        }
 
-        let all_ous: Vec<Uuid> = self.load::<Vec<Uuid>>("all_ous_index").await?.unwrap_or_default();
-        let updated_ous: Vec<Uuid> = all_ous.into_iter().filter(|id| *id != ou_id).collect();
-        self.store("all_ous_index".to_string(), &updated_ous).await?;
+        let db = self.write_db().await?;
+        db.remove(&format!("oauth_client:{}", client_id));
+        drop(db);
 
-        let db = self.db.write().await;
-        db.remove(&format!("ou:{}", ou_id));
-        db.remove(&format!("dn_index:{}", ou.dn));
+        let mut all_clients: Vec<String> = self.load::<Vec<String>>("all_oauth_clients_index").await?.unwrap_or_default();
+        all_clients.retain(|id| id != client_id);
+        self.store("all_oauth_clients_index".to_string(), &all_clients).await?;
+
+        self.log_action("delete_oauth_client", &format!("client_id:{}", client_id), None).await?;
+        Ok(())
+    }
+
+    // ================= API KEYS =================
+
+    #[tracing::instrument(skip_all, fields(name = %key.name))]
+    pub async fn create_api_key(&self, key: &ApiKey) -> Result<(), DirectoryError> {
+        if key.name.trim().is_empty() {
+            return Err(DirectoryError::InvalidInput("API key name is required".to_string()));
+        }
+        if key.scopes.is_empty() {
+            return Err(DirectoryError::InvalidInput("API key must have at least one scope".to_string()));
+        }
+        if let Some(unknown) = key.scopes.iter().find(|s| !apikey::scope::ALL.contains(&s.as_str())) {
+            return Err(DirectoryError::InvalidInput(format!(
+                "Unknown scope '{}': expected one of {}", unknown, apikey::scope::ALL.join(", ")
+            )));
+        }
+        if key.is_expired() {
+            return Err(DirectoryError::InvalidInput("API key expiry must be in the future".to_string()));
+        }
+        if self.get_user(key.owner_id).await?.is_none() {
+            return Err(DirectoryError::NotFound("Owner not found".to_string()));
+        }
+
+        self.store(format!("apikey:{}", key.id), key).await?;
+
+        let mut all_keys: Vec<Uuid> = self.load::<Vec<Uuid>>("all_apikeys_index").await?.unwrap_or_default();
+        if !all_keys.contains(&key.id) {
+            all_keys.push(key.id);
+            self.store("all_apikeys_index".to_string(), &all_keys).await?;
+        }
+
+        self.log_action("create_api_key", &format!("name:{} scopes:{}", key.name, key.scopes.join(",")), Some(key.owner_id)).await?;
+        Ok(())
+    }
+
+    pub async fn get_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, DirectoryError> {
+        self.load(&format!("apikey:{}", id)).await
+    }
+
+    pub async fn get_all_api_keys(&self) -> Result<Vec<ApiKey>, DirectoryError> {
+        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_apikeys_index").await?.unwrap_or_default();
+        let mut keys = Vec::new();
+        for id in ids {
+            if let Some(key) = self.get_api_key(id).await? {
+                keys.push(key);
+            }
+        }
+        Ok(keys)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_api_key(&self, id: Uuid) -> Result<(), DirectoryError> {
+        let key = self.get_api_key(id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("API key not found: {}", id)))?;
+
+        let db = self.write_db().await?;
+        db.remove(&format!("apikey:{}", id));
         drop(db);
 
-        self.log_action("delete_ou", &format!("ou:{}", ou.dn), None).await?;
+        let mut all_keys: Vec<Uuid> = self.load::<Vec<Uuid>>("all_apikeys_index").await?.unwrap_or_default();
+        all_keys.retain(|k| *k != id);
+        self.store("all_apikeys_index".to_string(), &all_keys).await?;
+
+        self.log_action("delete_api_key", &format!("name:{}", key.name), Some(key.owner_id)).await?;
         Ok(())
     }
 
-    // ================= GPO =================
+    /// Найти ключ по значению из `X-Api-Key`; просроченный или неверный — `AuthenticationFailed`
+    pub async fn authenticate_api_key(&self, value: &str) -> Result<ApiKey, DirectoryError> {
+        let invalid = || DirectoryError::AuthenticationFailed("Invalid API key".to_string());
+        let (id, secret) = ApiKey::parse(value).ok_or_else(invalid)?;
+        let key = self.get_api_key(id).await?
+            .filter(|key| key.verify_secret(secret))
+            .ok_or_else(invalid)?;
+        if key.is_expired() {
+            return Err(DirectoryError::AuthenticationFailed("API key has expired".to_string()));
+        }
+        Ok(key)
+    }
 
-    pub async fn create_gpo(&self, gpo: &GroupPolicy) -> Result<(), DirectoryError> {
-        gpo.validate().map_err(|e| DirectoryError::InvalidInput(e))?;
+    // ================= LDAP SYNC =================
 
-        self.store(format!("gpo:{}", gpo.id), gpo).await?;
-        for target_id in &gpo.linked_to {
-            let key = format!("gpo_link:{}", target_id);
-            let mut gpo_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&key).await?.unwrap_or_else(|| HashSet::new());
-            gpo_ids.insert(gpo.id);
-            self.store(key, &gpo_ids).await?;
+    pub async fn get_sync_state(&self, source: &str) -> Result<Option<LdapSyncState>, DirectoryError> {
+        self.load(&format!("sync_state:{}", source)).await
+    }
+
+    pub async fn save_sync_state(&self, state: &LdapSyncState) -> Result<(), DirectoryError> {
+        self.store(format!("sync_state:{}", state.source), state).await
+    }
+
+    pub async fn get_sync_objects(&self, source: &str) -> Result<LdapSyncObjects, DirectoryError> {
+        Ok(self.load(&format!("sync_objects:{}", source)).await?.unwrap_or_default())
+    }
+
+    pub async fn save_sync_objects(&self, source: &str, objects: &LdapSyncObjects) -> Result<(), DirectoryError> {
+        self.store(format!("sync_objects:{}", source), objects).await
+    }
+
+    // ================= REPLICATION =================
+
+    /// Снимок каталога для реплики только для чтения: все записи, кроме данных самого контроллера
+    /// (журнал аудита, отметки об уведомлениях). Пароли, ключи Kerberos и история паролей передаются
+    /// только для пользователей из `cache_credentials_for` — по имени или через группу, в том числе
+    /// вложенную; у остальных они стёрты
+    #[tracing::instrument(skip(self))]
+    pub async fn replication_snapshot(&self, cache_credentials_for: &[String], requested_by: Option<Uuid>) -> Result<Vec<(String, Vec<u8>)>, DirectoryError> {
+        let mut cached_users = HashSet::new();
+        let mut cached_groups = Vec::new();
+        for name in cache_credentials_for {
+            if let Some(group) = self.find_group_by_sam_account_name(name).await? {
+                cached_groups.push(group.sid);
+            } else if let Some(user) = self.find_user_by_username(name).await? {
+                cached_users.insert(user.id);
+            } else {
+                return Err(DirectoryError::NotFound(format!("User or group not found: {}", name)));
+            }
+        }
+        if !cached_groups.is_empty() {
+            for user in self.get_all_users().await? {
+                if self.get_token_groups(user.id).await?.iter().any(|sid| cached_groups.contains(sid)) {
+                    cached_users.insert(user.id);
+                }
+            }
         }
 
-        let all_gpos: Vec<Uuid> = self.load::<Vec<Uuid>>("all_gpos_index").await?.unwrap_or_default();
-        if !all_gpos.contains(&gpo.id) {
-            let mut updated = all_gpos;
-            updated.push(gpo.id);
-            self.store("all_gpos_index".to_string(), &updated).await?;
+        let entries = self.db.read().await.entries();
+        let mut snapshot = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            if is_local_key(&key) {
+                continue;
+            }
+            let owner = |prefix: &str| key.strip_prefix(prefix).and_then(|id| Uuid::parse_str(id).ok());
+            if let Some(id) = owner("user:") && !cached_users.contains(&id) {
+                let mut user: User = bincode::deserialize(&value).map_err(|e| DirectoryError::Serialization(e.to_string()))?;
+                user.password_hash.hash.clear();
+                user.password_hash.salt.clear();
+                user.kerberos_keys.clear();
+                user.totp_secret = None;
+                user.totp_pending_secret = None;
+                let value = bincode::serialize(&user).map_err(|e| DirectoryError::Serialization(e.to_string()))?;
+                snapshot.push((key, value));
+            } else if let Some(id) = owner("service_account:") && !cached_users.contains(&id) {
+                let mut account: ServiceAccount = bincode::deserialize(&value).map_err(|e| DirectoryError::Serialization(e.to_string()))?;
+                account.current_password.clear();
+                account.previous_password = None;
+                let value = bincode::serialize(&account).map_err(|e| DirectoryError::Serialization(e.to_string()))?;
+                snapshot.push((key, value));
+            } else if owner("password_history:").is_some_and(|id| !cached_users.contains(&id)) {
+                continue;
+            } else {
+                snapshot.push((key, value));
+            }
+        }
+
+        let mut event = AuditEvent::new("replication_snapshot", AuditResult::Success);
+        event.actor_id = requested_by;
+        event.metadata.insert("entries".to_string(), snapshot.len().to_string());
+        event.metadata.insert("cached_credentials".to_string(), cached_users.len().to_string());
+        self.record(event).await?;
+        Ok(snapshot)
+    }
+
+    /// Заменить каталог реплики снимком записываемого контроллера; журнал аудита реплики остаётся.
+    /// Возвращает число записей
+    #[tracing::instrument(skip(self, snapshot))]
+    pub async fn apply_replication_snapshot(&self, snapshot: Vec<(String, Vec<u8>)>, upstream: &str) -> Result<usize, DirectoryError> {
+        if !self.is_replica() {
+            return Err(DirectoryError::InvalidInput("Only a read-only replica accepts a replication snapshot".to_string()));
+        }
+        let entries = snapshot.len();
+        let db = self.db.write().await;
+        self.cache.clear();
+        db.replace(snapshot, is_local_key)?;
+        drop(db);
+
+        let mut event = AuditEvent::new("replication_pull", AuditResult::Success);
+        event.metadata.insert("upstream".to_string(), upstream.to_string());
+        event.metadata.insert("entries".to_string(), entries.to_string());
+        self.record(event).await?;
+        Ok(entries)
+    }
+
+    // ================= CHANGE LOG =================
+
+    /// Последний выданный USN; 0 — изменений ещё не было
+    pub async fn highest_usn(&self) -> Result<u64, DirectoryError> {
+        Ok(self.load::<u64>(USN_KEY).await?.unwrap_or(0))
+    }
+
+    /// Изменения с USN больше `since` по возрастанию, не больше `limit`
+    pub async fn get_changes(&self, since: u64, limit: usize) -> Result<Vec<ChangeEntry>, DirectoryError> {
+        let highest = self.highest_usn().await?;
+        let mut changes = Vec::new();
+        for usn in (since + 1..=highest).take(limit) {
+            if let Some(change) = self.load::<ChangeEntry>(&format!("change:{}", usn)).await? {
+                changes.push(change);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// DirSync (LDAP_SERVER_DIRSYNC_OID) в режиме OBJECT_SECURITY: последнее изменение каждого
+    /// пользователя, группы, OU и контакта после `cookie`, если `user` может его читать.
+    /// Удаления видны тем, кто читает корень домена
+    pub async fn dirsync(&self, user: &User, cookie: DirSyncCookie, max_changes: usize) -> Result<DirSyncPage, DirectoryError> {
+        let sids = self.principal_sids(user).await?;
+        let highest = self.highest_usn().await?;
+        let changes = self.get_changes(cookie.usn, max_changes).await?;
+        let last_usn = changes.last().map_or(cookie.usn, |change| change.usn);
+
+        let mut latest: Vec<ChangeEntry> = Vec::new();
+        for change in changes.into_iter().rev() {
+            if dirsync_object(&change).is_some() && !latest.iter().any(|seen| seen.object_id == change.object_id) {
+                latest.push(change);
+            }
+        }
+        latest.reverse();
+
+        let reads_root = self.root_security_descriptor().await?.check_access(&sids, AccessMask::READ_PROPERTY);
+        let mut visible = Vec::with_capacity(latest.len());
+        for change in latest {
+            let readable = match dirsync_object(&change) {
+                _ if change.change_type == ChangeType::Delete => reads_root,
+                Some(object) => match self.check_access(&sids, object, AccessMask::READ_PROPERTY).await {
+                    Ok(readable) => readable,
+                    // Удалён после этой страницы: удаление придёт со следующей
+                    Err(DirectoryError::NotFound(_)) => false,
+                    Err(e) => return Err(e),
+                },
+                None => false,
+            };
+            if readable {
+                visible.push(change);
+            }
+        }
+
+        Ok(DirSyncPage {
+            changes: visible,
+            cookie: DirSyncCookie { usn: last_usn },
+            more: last_usn < highest,
+        })
+    }
+
+    // ================= STATISTICS =================
+
+    /// Сводка каталога: число объектов по типу (по ключам, без чтения объектов), отключённые
+    /// и заблокированные пользователи, пользователи по OU, GPO, размер базы и время последней записи
+    pub async fn stats(&self) -> Result<DirectoryStats, DirectoryError> {
+        let (objects, db_size_bytes, last_flush) = {
+            let db = self.db.read().await;
+            let objects = CHANGE_TRACKED.iter()
+                .map(|(prefix, object_type)| (object_type.to_string(), db.count_prefix(prefix)))
+                .collect();
+            (objects, db.file_size(), db.last_flush().map(chrono::DateTime::<Utc>::from))
+        };
+
+        let now = Utc::now();
+        let users = self.get_all_users().await?;
+        let mut per_ou: HashMap<Option<Uuid>, usize> = HashMap::new();
+        for user in &users {
+            *per_ou.entry(user.organizational_unit).or_default() += 1;
+        }
+        let ous: HashMap<Uuid, String> = self.get_all_ous().await?.into_iter().map(|ou| (ou.id, ou.dn)).collect();
+        let mut users_per_ou: Vec<OuUserCount> = per_ou.into_iter()
+            .map(|(ou_id, users)| OuUserCount { ou_id, dn: ou_id.and_then(|id| ous.get(&id).cloned()), users })
+            .collect();
+        users_per_ou.sort_by(|a, b| a.dn.cmp(&b.dn));
+        let gpos = self.get_all_gpos().await?;
+
+        Ok(DirectoryStats {
+            objects,
+            computers: users.iter().filter(|user| is_computer_account(user)).count(),
+            disabled_users: users.iter().filter(|user| !user.enabled).count(),
+            locked_users: users.iter().filter(|user| user.is_locked_out()).count(),
+            enabled_gpos: gpos.iter().filter(|gpo| gpo.enabled).count(),
+            linked_gpos: gpos.iter().filter(|gpo| !gpo.linked_to.is_empty()).count(),
+            users_per_ou,
+            db_size_bytes,
+            last_flush,
+            generated_at: now,
+        })
+    }
+
+    /// Ссылки на несуществующие объекты: участники групп (пользователи и вложенные группы) и владельцы, привязки GPO с обеих
+    /// сторон, родители OU, OU пользователей и контактов. Каталог не меняется
+    pub async fn find_dangling_references(&self) -> Result<Vec<DanglingReference>, DirectoryError> {
+        let users: HashSet<Uuid> = self.get_all_users().await?.into_iter().map(|user| user.id).collect();
+        let ous = self.get_all_ous().await?;
+        let ou_ids: HashSet<Uuid> = ous.iter().map(|ou| ou.id).collect();
+        let domain_ids: HashSet<Uuid> = self.get_all_domains().await?.into_iter().map(|domain| domain.id).collect();
+        let gpos = self.get_all_gpos().await?;
+        let gpo_ids: HashSet<Uuid> = gpos.iter().map(|gpo| gpo.id).collect();
+
+        let mut dangling = Vec::new();
+        let mut check = |object_type: &str, object_id: Uuid, object: &str, attribute: &str, target_id: Uuid, exists: bool| {
+            if !exists {
+                dangling.push(DanglingReference {
+                    object_type: object_type.to_string(),
+                    object_id,
+                    object: object.to_string(),
+                    attribute: attribute.to_string(),
+                    target_id,
+                });
+            }
+        };
+
+        let groups = self.get_all_groups().await?;
+        let group_ids: HashSet<Uuid> = groups.iter().map(|group| group.id).collect();
+        for group in &groups {
+            for member in &group.members {
+                check("group", group.id, &group.sam_account_name, "members", *member, users.contains(member) || group_ids.contains(member));
+            }
+            if let Some(owner) = group.managed_by {
+                check("group", group.id, &group.sam_account_name, "managed_by", owner, users.contains(&owner));
+            }
+        }
+        for gpo in &gpos {
+            for target in &gpo.linked_to {
+                check("gpo", gpo.id, &gpo.name, "linked_to", *target, ou_ids.contains(target) || domain_ids.contains(target));
+            }
+        }
+        for ou in &ous {
+            for gpo_id in &ou.linked_gpos {
+                check("ou", ou.id, &ou.dn, "linked_gpos", *gpo_id, gpo_ids.contains(gpo_id));
+            }
+            if let Some(parent) = ou.parent {
+                check("ou", ou.id, &ou.dn, "parent", parent, ou_ids.contains(&parent) || domain_ids.contains(&parent));
+            }
+        }
+        for user in self.get_all_users().await? {
+            if let Some(ou_id) = user.organizational_unit {
+                check("user", user.id, &user.username, "organizational_unit", ou_id, ou_ids.contains(&ou_id));
+            }
+        }
+        for contact in self.get_all_contacts().await? {
+            if let Some(ou_id) = contact.organizational_unit {
+                check("contact", contact.id, &contact.name, "organizational_unit", ou_id, ou_ids.contains(&ou_id));
+            }
+        }
+        Ok(dangling)
+    }
+
+    /// Найти висячие ссылки и сообщить о них: предупреждение в журнал на каждую и событие
+    /// `dangling_references` в аудит, если они есть. Возвращает их число
+    pub async fn report_dangling_references(&self) -> Result<usize, DirectoryError> {
+        let dangling = self.find_dangling_references().await?;
+        if dangling.is_empty() {
+            return Ok(0);
+        }
+        for reference in &dangling {
+            tracing::warn!(
+                object_type = %reference.object_type,
+                object = %reference.object,
+                attribute = %reference.attribute,
+                target_id = %reference.target_id,
+                "Ссылка на несуществующий объект"
+            );
+        }
+
+        let mut event = AuditEvent::new("dangling_references", AuditResult::Failure);
+        event.metadata.insert("count".to_string(), dangling.len().to_string());
+        let sample: Vec<String> = dangling.iter()
+            .take(20)
+            .map(|reference| format!("{}:{}.{} -> {}", reference.object_type, reference.object, reference.attribute, reference.target_id))
+            .collect();
+        event.metadata.insert("references".to_string(), sample.join("; "));
+        self.record(event).await?;
+        Ok(dangling.len())
+    }
+
+    // ================= AUDIT =================
+
+    /// Записать событие и дописать его в индексы по дню, автору, объекту и действию — одной записью на диск.
+    /// При включённой цепочке событие получает звено; возвращается новый якорь, если он подписан
+    async fn store_audit_event(&self, event: &mut AuditEvent) -> Result<Option<AuditAnchor>, DirectoryError> {
+        fn encode<T: serde::Serialize>(value: &T) -> Result<Vec<u8>, DirectoryError> {
+            bincode::serialize(value).map_err(|e| DirectoryError::Serialization(e.to_string()))
+        }
+        fn decode<T: serde::de::DeserializeOwned + Default>(db: &RadDB, key: &str) -> Result<T, DirectoryError> {
+            match db.get(key) {
+                Some(data) => bincode::deserialize(&data).map_err(|e| DirectoryError::Serialization(e.to_string())),
+                None => Ok(T::default()),
+            }
+        }
+
+        let day = event.timestamp.format("%Y-%m-%d").to_string();
+        let mut index_keys = vec![
+            format!("audit_day_index:{}", day),
+            format!("audit_action_index:{}", event.action),
+        ];
+        if let Some(actor) = event.actor_id {
+            index_keys.push(format!("audit_actor_index:{}", actor));
+        }
+        if let Some(target) = event.target_id {
+            index_keys.push(format!("audit_target_index:{}", target));
+        }
+
+        // Чтение индексов и запись под одной блокировкой: параллельные события не теряются, звенья идут по порядку
+        let db = self.db.write().await;
+        let mut entries = Vec::new();
+        let mut anchor = None;
+
+        if let Some(chain) = &self.audit_chain {
+            let head: AuditChainHead = decode(&db, "audit_chain_head")?;
+            let seq = head.seq + 1;
+            let hash = event.chain_hash(seq, &head.hash);
+            event.chain = Some(AuditChainLink { seq, prev_hash: head.hash, hash: hash.clone() });
+
+            let head = AuditChainHead { seq, hash };
+            entries.push((format!("audit_chain:{}", seq), encode(&event.id)?));
+            entries.push(("audit_chain_head".to_string(), encode(&head)?));
+
+            if chain.anchor_every > 0 && seq.is_multiple_of(chain.anchor_every) {
+                // Без ключа сервера цепочка продолжается, но без подписанных якорей
+                match AuditAnchor::sign(&head) {
+                    Ok(signed) => {
+                        let mut anchors: Vec<u64> = decode(&db, "audit_anchors_index")?;
+                        anchors.push(seq);
+                        entries.push((format!("audit_anchor:{}", seq), encode(&signed)?));
+                        entries.push(("audit_anchors_index".to_string(), encode(&anchors)?));
+                        anchor = Some(signed);
+                    }
+                    Err(e) => tracing::error!(seq, error = %e, "Не удалось подписать якорь аудита"),
+                }
+            }
+        }
+
+        entries.push((format!("audit:{}", event.id), encode(&*event)?));
+        for key in index_keys {
+            let mut ids: Vec<Uuid> = decode(&db, &key)?;
+            ids.push(event.id);
+            entries.push((key, encode(&ids)?));
+        }
+
+        let mut days: Vec<String> = decode(&db, "audit_days_index")?;
+        if !days.contains(&day) {
+            days.push(day);
+            days.sort();
+            entries.push(("audit_days_index".to_string(), encode(&days)?));
+        }
+
+        db.set_many(entries)?;
+        Ok(anchor)
+    }
+
+    /// Проверить цепочку: каждое звено на месте и не изменено, якоря подписаны и совпадают с цепочкой.
+    /// `external_anchor` — якорь из внешнего журнала: выявляет откат истории вместе с локальными якорями
+    #[tracing::instrument(skip_all)]
+    pub async fn verify_audit_chain(&self, external_anchor: Option<&str>) -> Result<AuditChainReport, DirectoryError> {
+        let head: AuditChainHead = self.load("audit_chain_head").await?.unwrap_or_default();
+        let mut report = AuditChainReport { head_seq: head.seq, ..Default::default() };
+
+        // Хэши звеньев по порядку; для проверки якорей
+        let mut hashes = Vec::with_capacity(head.seq as usize);
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for seq in 1..=head.seq {
+            let Some(id) = self.load::<Uuid>(&format!("audit_chain:{}", seq)).await? else {
+                report.problems.push(format!("#{}: chain entry is missing", seq));
+                hashes.push(None);
+                continue;
+            };
+            let Some(event) = self.load::<AuditEvent>(&format!("audit:{}", id)).await? else {
+                report.problems.push(format!("#{}: event {} is missing", seq, id));
+                hashes.push(None);
+                continue;
+            };
+            let Some(link) = event.chain.clone().filter(|link| link.seq == seq) else {
+                report.problems.push(format!("#{}: event {} has no chain link for this position", seq, id));
+                hashes.push(None);
+                continue;
+            };
+            if link.prev_hash != prev_hash {
+                report.problems.push(format!("#{}: previous hash mismatch (event {})", seq, id));
+            }
+            if event.chain_hash(seq, &link.prev_hash) != link.hash {
+                report.problems.push(format!("#{}: event {} was modified", seq, id));
+            }
+            report.verified += 1;
+            prev_hash = link.hash.clone();
+            hashes.push(Some(link.hash));
+        }
+        if head.seq > 0 && prev_hash != head.hash {
+            report.problems.push("Chain head does not match the last event".to_string());
+        }
+        if self.load::<Uuid>(&format!("audit_chain:{}", head.seq + 1)).await?.is_some() {
+            report.problems.push(format!("Events after #{} exist but the head was rolled back", head.seq));
+        }
+
+        let hash_at = |seq: u64| hashes.get((seq as usize).wrapping_sub(1)).cloned().flatten();
+        let check_anchor = |source: &str, token: &str, report: &mut AuditChainReport| {
+            let signed = match AuditAnchor::verify_token(token) {
+                Ok(signed) => signed,
+                Err(e) => {
+                    report.problems.push(format!("{}: invalid signature: {}", source, e));
+                    return;
+                }
+            };
+            if signed.seq > head.seq {
+                report.problems.push(format!("{}: history truncated, anchor is at #{} but the chain ends at #{}", source, signed.seq, head.seq));
+            } else if hash_at(signed.seq).as_deref() != Some(signed.hash.as_str()) {
+                report.problems.push(format!("{}: chain at #{} does not match the signed hash", source, signed.seq));
+            } else {
+                report.anchors_verified += 1;
+            }
+        };
+
+        let anchors: Vec<u64> = self.load("audit_anchors_index").await?.unwrap_or_default();
+        for seq in anchors {
+            match self.load::<AuditAnchor>(&format!("audit_anchor:{}", seq)).await? {
+                Some(anchor) => {
+                    check_anchor(&format!("Anchor #{}", seq), &anchor.token, &mut report);
+                    report.last_anchor = Some(anchor.created_at);
+                }
+                None => report.problems.push(format!("Anchor #{} is missing", seq)),
+            }
+        }
+        if let Some(token) = external_anchor {
+            check_anchor("External anchor", token, &mut report);
         }
 
-        self.log_action("create_gpo", &format!("gpo:{}", gpo.id), None).await?;
-        Ok(())
+        Ok(report)
     }
 
-    pub async fn get_gpo(&self, id: Uuid) -> Result<Option<GroupPolicy>, DirectoryError> {
-        self.load(&format!("gpo:{}", id)).await
-    }
+    /// Поиск событий аудита; кандидаты берутся из самого узкого подходящего индекса
+    #[tracing::instrument(skip_all)]
+    pub async fn search_audit(&self, query: &AuditQuery) -> Result<AuditPage, DirectoryError> {
+        let candidates: Vec<Uuid> = if let Some(actor) = query.actor {
+            self.load(&format!("audit_actor_index:{}", actor)).await?.unwrap_or_default()
+        } else if let Some(target) = query.target {
+            self.load(&format!("audit_target_index:{}", target)).await?.unwrap_or_default()
+        } else if let Some(action) = query.exact_action() {
+            self.load(&format!("audit_action_index:{}", action)).await?.unwrap_or_default()
+        } else {
+            let from = query.from.map(|t| t.format("%Y-%m-%d").to_string());
+            let to = query.to.map(|t| t.format("%Y-%m-%d").to_string());
+            let days: Vec<String> = self.load("audit_days_index").await?.unwrap_or_default();
+            let mut ids = Vec::new();
+            for day in days {
+                if from.as_ref().is_some_and(|from| &day < from) || to.as_ref().is_some_and(|to| &day > to) {
+                    continue;
+                }
+                let day_ids: Vec<Uuid> = self.load(&format!("audit_day_index:{}", day)).await?.unwrap_or_default();
+                ids.extend(day_ids);
+            }
+            ids
+        };
 
-    pub async fn get_all_gpos(&self) -> Result<Vec<GroupPolicy>, DirectoryError> {
-        let ids: Vec<Uuid> = self.load::<Vec<Uuid>>("all_gpos_index").await?.unwrap_or_default();
-        let mut gpos = Vec::new();
-        for id in ids {
-            if let Some(gpo) = self.get_gpo(id).await? {
-                gpos.push(gpo);
+        let mut events = Vec::new();
+        for id in candidates {
+            if let Some(event) = self.load::<AuditEvent>(&format!("audit:{}", id)).await?
+                && query.matches(&event)
+            {
+                events.push(event);
             }
         }
-        Ok(gpos)
+        events.sort_by_key(|event| std::cmp::Reverse(event.timestamp));
+
+        let (page, per_page) = query.page_bounds();
+        let total = events.len();
+        let events = events.into_iter().skip((page - 1) * per_page).take(per_page).collect();
+        Ok(AuditPage { total, page, per_page, events })
     }
 
-    pub async fn find_gpos_for_ou(&self, ou_id: Uuid) -> Result<Vec<GroupPolicy>, DirectoryError> {
-        let ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&format!("gpo_link:{}", ou_id)).await?.unwrap_or_else(|| HashSet::new());
-        let mut gpos = Vec::new();
-        for id in ids {
-            if let Some(gpo) = self.get_gpo(id).await? {
-                gpos.push(gpo);
-            }
+    /// Пользователь по UUID или имени — для фильтров поиска по аудиту
+    pub async fn resolve_user_ref(&self, value: &str) -> Result<Uuid, DirectoryError> {
+        if let Ok(id) = Uuid::parse_str(value) {
+            return Ok(id);
         }
-        Ok(gpos)
+        self.find_user_by_username(value)
+            .await?
+            .map(|user| user.id)
+            .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", value)))
     }
 
-    pub async fn find_gpos_for_domain(&self, domain_id: Uuid) -> Result<Vec<GroupPolicy>, DirectoryError> {
-        let ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&format!("gpo_link:{}", domain_id)).await?.unwrap_or_else(|| HashSet::new());
-        let mut gpos = Vec::new();
-        for id in ids {
-            if let Some(gpo) = self.get_gpo(id).await? {
-                gpos.push(gpo);
-            }
-        }
-        Ok(gpos)
+    // ================= LDIF =================
+
+    /// Импортировать LDIF: сначала OU (от корня вглубь), затем пользователи, затем группы с участниками
+    pub async fn import_ldif(&self, input: &str, on_duplicate: DuplicatePolicy) -> Result<ImportReport, DirectoryError> {
+        self.import_ldif_entries(ldif::parse(input)?, on_duplicate).await
     }
 
-    pub async fn link_gpo_to_ou(&self, gpo_id: Uuid, ou_id: Uuid) -> Result<(), DirectoryError> {
-        let _gpo = self.get_gpo(gpo_id).await?.ok_or_else(|| DirectoryError::NotFound("GPO not found".to_string()))?;
-        let mut ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+    /// Импортировать уже разобранные записи (из LDIF-файла или поиска во внешнем LDAP)
+    pub async fn import_ldif_entries(&self, entries: Vec<LdifEntry>, on_duplicate: DuplicatePolicy) -> Result<ImportReport, DirectoryError> {
+        self.import_ldif_resolving(entries, on_duplicate, &mut HashMap::new()).await
+    }
 
-        if !ou.linked_gpos.contains(&gpo_id) {
-            ou.linked_gpos.push(gpo_id);
-            ou.enforced = true;
-            ou.update_gplink();
-            ou.updated_at = Utc::now();
+    /// Импорт с известными DN: `dns` (канонический DN → id объекта) дополняется каждой записью
+    /// импорта, включая пропущенные. По нему, а затем по DN объектов каталога, как их строит
+    /// экспорт, разрешаются участники групп; синхронизация передаёт сюда DN прошлых циклов.
+    /// Список участников группы из записи заменяет прежний
+    #[tracing::instrument(skip_all, fields(entries = entries.len()))]
+    pub async fn import_ldif_resolving(
+        &self,
+        mut entries: Vec<LdifEntry>,
+        on_duplicate: DuplicatePolicy,
+        dns: &mut HashMap<String, Uuid>,
+    ) -> Result<ImportReport, DirectoryError> {
+        entries.sort_by_key(|e| Dn::parse(&e.dn).map(|dn| dn.len()).unwrap_or_default());
 
-            self.store(format!("ou:{}", ou.id), &ou).await?;
+        let mut report = ImportReport::default();
+        let mut objects = Vec::new();
+        for entry in entries {
+            if entry.get("changetype").is_some_and(|c| !c.eq_ignore_ascii_case("add")) {
+                report.errors.push(format!("{}: only add records are supported", entry.dn));
+                continue;
+            }
+            match entry.kind() {
+                Some(kind) => objects.push((kind, entry)),
+                // Домены, контейнеры и прочие классы не отображаются в модель каталога
+                None => report.skipped += 1,
+            }
+        }
 
-            let index_key = format!("gpo_link:{}", ou_id);
-            let mut gpo_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&index_key).await?.unwrap_or_else(|| HashSet::new());
-            gpo_ids.insert(gpo_id);
-            self.store(index_key, &gpo_ids).await?;
+        if on_duplicate == DuplicatePolicy::Fail {
+            for (kind, entry) in &objects {
+                if self.find_ldif_duplicate(*kind, entry).await? {
+                    return Err(DirectoryError::AlreadyExists(ErrorCode::AlreadyExists, format!("Object already exists: {}", entry.dn)));
+                }
+            }
         }
 
-        self.log_action("link_gpo_to_ou", &format!("gpo:{} ou:{}", gpo_id, ou_id), None).await?;
-        Ok(())
+        // Участники назначаются, когда известны все группы импорта: группа может входить в группу
+        let mut groups = Vec::new();
+        for kind in [LdifObjectKind::OrganizationalUnit, LdifObjectKind::User, LdifObjectKind::Group, LdifObjectKind::Contact] {
+            for (_, entry) in objects.iter().filter(|(k, _)| *k == kind) {
+                let result = match kind {
+                    LdifObjectKind::OrganizationalUnit => self.import_ldif_ou(entry, on_duplicate, &mut report).await,
+                    LdifObjectKind::User => self.import_ldif_user(entry, on_duplicate, &mut report).await,
+                    LdifObjectKind::Group => self.import_ldif_group(entry, on_duplicate, &mut report).await.map(|(id, group)| {
+                        groups.extend(group.map(|group| (group, entry)));
+                        id
+                    }),
+                    LdifObjectKind::Contact => self.import_ldif_contact(entry, on_duplicate, &mut report).await,
+                };
+                match result {
+                    Ok(id) => { dns.insert(dn::key(&entry.dn), id); }
+                    Err(e) => report.errors.push(format!("{}: {}", entry.dn, e)),
+                }
+            }
+        }
+        let mut local_dns = None;
+        for (group, entry) in groups {
+            if let Err(e) = self.set_imported_members(entry, &group, dns, &mut local_dns, &mut report).await {
+                report.errors.push(format!("{}: {}", entry.dn, e));
+            }
+        }
+
+        self.log_action(
+            "import_ldif",
+            &format!("created:{} updated:{} skipped:{} errors:{}", report.created, report.updated, report.skipped, report.errors.len()),
+            None,
+        ).await?;
+        Ok(report)
     }
 
-    pub async fn unlink_gpo_from_ou(&self, gpo_id: Uuid, ou_id: Uuid) -> Result<(), DirectoryError> {
-        let mut ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+    /// Выгрузить OU, пользователей, группы и контакты в LDIF
+    #[tracing::instrument(skip_all)]
+    pub async fn export_ldif(&self) -> Result<String, DirectoryError> {
+        let ctx = LdapContext::new(self).await?;
+        let mut entries = Vec::new();
 
-        if ou.linked_gpos.contains(&gpo_id) {
-            ou.linked_gpos.retain(|id| id != &gpo_id);
-            ou.update_gplink();
-            ou.updated_at = Utc::now();
+        let mut ous = self.get_all_ous().await?;
+        ous.sort_by_key(|ou| Dn::parse(&ou.dn).map(|dn| dn.len()).unwrap_or_default());
+        for ou in &ous {
+            let (dn, attributes) = ou.ldap_entry(&ctx).await?;
+            entries.push(ldap_entry_to_ldif(&dn, attributes));
+        }
 
-            self.store(format!("ou:{}", ou.id), &ou).await?;
+        let mut user_dns: HashMap<Uuid, String> = HashMap::new();
+        for user in self.get_all_users().await? {
+            let (dn, mut attributes) = user.ldap_entry(&ctx).await?;
+            for computed in ["memberOf", "lastLogon", "lastLogonTimestamp"] {
+                attributes.remove(computed);
+            }
+            // Значения LDIF здесь строковые: двоичные фотографии не переносятся
+            attributes.remove("jpegPhoto");
+            attributes.remove("thumbnailPhoto");
+            entries.push(ldap_entry_to_ldif(&dn, attributes));
+            user_dns.insert(user.id, dn);
+        }
 
-            let index_key = format!("gpo_link:{}", ou_id);
-            let mut gpo_ids: HashSet<Uuid> = self.load::<HashSet<Uuid>>(&index_key).await?.unwrap_or_else(|| HashSet::new());
-            gpo_ids.remove(&gpo_id);
-            self.store(index_key, &gpo_ids).await?;
+        for group in self.get_all_groups().await? {
+            let (dn, mut attributes) = group.ldap_entry(&ctx).await?;
+            let members: Vec<String> = group.members.iter().filter_map(|id| user_dns.get(id).cloned()).collect();
+            if !members.is_empty() {
+                attributes.insert("member", members);
+            }
+            entries.push(ldap_entry_to_ldif(&dn, attributes));
         }
 
-        self.log_action("unlink_gpo_from_ou", &format!("gpo:{} ou:{}", gpo_id, ou_id), None).await?;
-        Ok(())
+        for contact in self.get_all_contacts().await? {
+            let (dn, attributes) = contact.ldap_entry(&ctx).await?;
+            entries.push(ldap_entry_to_ldif(&dn, attributes));
+        }
+
+        self.log_action("export_ldif", &format!("entries:{}", entries.len()), None).await?;
+        Ok(ldif::write(&entries))
     }
 
-    pub async fn set_block_inheritance(&self, ou_id: Uuid, block: bool) -> Result<(), DirectoryError> {
-        let mut ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+    async fn find_ldif_duplicate(&self, kind: LdifObjectKind, entry: &LdifEntry) -> Result<bool, DirectoryError> {
+        Ok(match kind {
+            LdifObjectKind::OrganizationalUnit => self.find_ou_by_dn(&entry.dn).await?.is_some(),
+            LdifObjectKind::User => self.find_user_by_username(&ldif_username(entry)).await?.is_some(),
+            LdifObjectKind::Group => self.find_group_by_sam_account_name(&ldif_group_sam(entry)).await?.is_some(),
+            LdifObjectKind::Contact => match entry.get("mail") {
+                Some(mail) => self.find_contact_by_email(mail).await?.is_some(),
+                None => false,
+            },
+        })
+    }
 
-        ou.block_inheritance = block;
-        ou.update_gpoptions();
-        ou.updated_at = Utc::now();
+    async fn import_ldif_ou(&self, entry: &LdifEntry, on_duplicate: DuplicatePolicy, report: &mut ImportReport) -> Result<Uuid, DirectoryError> {
+        let existing = self.find_ou_by_dn(&entry.dn).await?;
+        if let Some(ou) = &existing && on_duplicate == DuplicatePolicy::Skip {
+            report.skipped += 1;
+            return Ok(ou.id);
+        }
+        let is_new = existing.is_none();
 
-        self.store(format!("ou:{}", ou.id), &ou).await?;
+        let mut ou = match existing {
+            Some(ou) => ou,
+            None => {
+                let name = entry.get("ou").map(str::to_string).unwrap_or_else(|| rdn_value(&entry.dn));
+                let parent = match Dn::parse(&entry.dn)?.parent() {
+                    Some(parent_dn) => self.find_ou_by_dn(&parent_dn.to_string()).await?.map(|p| p.id),
+                    None => None,
+                };
+                OrganizationalUnit::new(name, entry.dn.clone(), parent)
+            }
+        };
 
-        self.log_action("set_block_inheritance", &format!("ou:{} block:{}", ou_id, block), None).await?;
-        Ok(())
+        ou.display_name = entry.get("displayName").map(str::to_string).or(ou.display_name);
+        ou.description = entry.get("description").map(str::to_string).or(ou.description);
+        self.ldif_attributes(entry, SchemaClass::OrganizationalUnit, &mut ou.meta).await?;
+        ou.updated_at = Utc::now();
+        self.create_ou(&ou).await?;
+
+        if is_new { report.created += 1 } else { report.updated += 1 }
+        Ok(ou.id)
     }
 
-    pub async fn set_gpo_enforced(&self, ou_id: Uuid, enforced: bool) -> Result<(), DirectoryError> {
-        let mut ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
+    async fn import_ldif_user(&self, entry: &LdifEntry, on_duplicate: DuplicatePolicy, report: &mut ImportReport) -> Result<Uuid, DirectoryError> {
+        let username = ldif_username(entry);
+        let existing = self.find_user_by_username(&username).await?;
+        if let Some(user) = &existing && on_duplicate == DuplicatePolicy::Skip {
+            report.skipped += 1;
+            return Ok(user.id);
+        }
 
-        ou.enforced = enforced;
-        ou.update_gplink();
-        ou.updated_at = Utc::now();
+        let dn = Dn::parse(&entry.dn)?;
+        let organizational_unit = match dn.parent() {
+            Some(parent_dn) => self.find_ou_by_dn(&parent_dn.to_string()).await?.map(|ou| ou.id),
+            None => None,
+        };
+        let realm = dn.dns_name().unwrap_or_default();
+        // userAccountControl переносится целиком, ACCOUNTDISABLE отключает учётную запись
+        let account_control = entry.get("userAccountControl")
+            .and_then(|v| v.parse::<u32>().ok())
+            .map(UserAccountControl::from_bits_truncate);
+        // Переносим только bcrypt-хеши OpenLDAP ({CRYPT}$2b$...); остальным паролям нужен сброс
+        let imported_hash = entry.get("userPassword")
+            .and_then(|p| p.strip_prefix("{CRYPT}").or_else(|| p.strip_prefix("{crypt}")))
+            .filter(|h| h.starts_with("$2"))
+            .map(|h| PasswordHash { hash: h.to_string(), algorithm: PasswordAlgorithm::Bcrypt, salt: vec![] });
 
-        self.store(format!("ou:{}", ou.id), &ou).await?;
+        let mut user = match existing {
+            Some(user) => user,
+            None => User {
+                id: Uuid::new_v4(),
+                // objectSid в строковой форме (экспорт nextDomen, синхронизация с AD) сохраняется
+                sid: entry.get("objectSid")
+                    .and_then(|sid| sid.parse().ok())
+                    .unwrap_or_else(|| SecurityIdentifier::new_nt_authority(1001)),
+                username: username.clone(),
+                user_principal_name: String::new(),
+                email: None,
+                display_name: None,
+                given_name: None,
+                surname: None,
+                password_hash: PasswordHash {
+                    hash: "!".to_string(),
+                    algorithm: PasswordAlgorithm::Bcrypt,
+                    salt: vec![],
+                },
+                password_expires: None,
+                last_password_change: Utc::now(),
+                lockout_until: None,
+                failed_logins: 0,
+                enabled: true,
+                mfa_enabled: false,
+                mfa_methods: vec![],
+                domains: vec![],
+                groups: vec![],
+                organizational_unit,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                last_login: None,
+                profile_path: None,
+                script_path: None,
+                meta: HashMap::new(),
+                primary_group_id: Some(513),
+                service_principal_names: vec![],
+                kerberos_keys: vec![],
+                user_account_control: UserAccountControl::NORMAL_ACCOUNT,
+                totp_secret: None,
+                totp_pending_secret: None,
+            },
+        };
+        let is_new = user.user_principal_name.is_empty();
 
-        self.log_action("set_gpo_enforced", &format!("ou:{} enforced:{}", ou_id, enforced), None).await?;
-        Ok(())
+        user.user_principal_name = entry.get("userPrincipalName").map(str::to_string)
+            .unwrap_or_else(|| if realm.is_empty() { username.clone() } else { format!("{}@{}", username, realm) });
+        user.email = entry.get("mail").map(str::to_string).or(user.email);
+        user.display_name = entry.get("displayName").or(entry.get("cn")).map(str::to_string).or(user.display_name);
+        user.given_name = entry.get("givenName").map(str::to_string).or(user.given_name);
+        user.surname = entry.get("sn").map(str::to_string).or(user.surname);
+        user.profile_path = entry.get("profilePath").map(str::to_string).or(user.profile_path);
+        user.script_path = entry.get("scriptPath").map(str::to_string).or(user.script_path);
+        user.organizational_unit = organizational_unit.or(user.organizational_unit);
+        if let Some(hash) = imported_hash {
+            user.password_hash = hash;
+            user.last_password_change = Utc::now();
+        }
+        if let Some(flags) = account_control {
+            user.set_account_control(flags);
+        }
+        self.ldif_attributes(entry, SchemaClass::User, &mut user.meta).await?;
+        user.updated_at = Utc::now();
+
+        self.create_user(&user).await?;
+        if is_new { report.created += 1 } else { report.updated += 1 }
+        Ok(user.id)
     }
 
-    pub async fn is_gpo_applicable_to(
+    /// Группа без изменения участников; вторым элементом — сохранённая группа, участников
+    /// которой назначит `set_imported_members` (`None`, если запись пропущена)
+    async fn import_ldif_group(
         &self,
-        gpo: &GroupPolicy,
-        principal_sid: &SecurityIdentifier,
-    ) -> Result<bool, DirectoryError> {
-        if gpo.security_filtering.is_empty() {
-            return Ok(true);
+        entry: &LdifEntry,
+        on_duplicate: DuplicatePolicy,
+        report: &mut ImportReport,
+    ) -> Result<(Uuid, Option<Group>), DirectoryError> {
+        let sam = ldif_group_sam(entry);
+        let existing = self.find_group_by_sam_account_name(&sam).await?;
+        if let Some(group) = &existing && on_duplicate == DuplicatePolicy::Skip {
+            report.skipped += 1;
+            return Ok((group.id, None));
         }
+        let is_new = existing.is_none();
 
-        for filter in &gpo.security_filtering {
-            if let SidOrId::Sid(sid) = filter {
-                if sid == principal_sid {
-                    return Ok(true);
-                }
-            }
+        // groupType из AD: 0x2 global, 0x4 domain local, 0x8 universal, 0x80000000 security
+        let group_type = entry.get("groupType").and_then(|v| v.parse::<i64>().ok()).map(|v| v as u32);
+        let scope = match group_type {
+            Some(t) if t & 0x4 != 0 => GroupScope::DomainLocal,
+            Some(t) if t & 0x8 != 0 => GroupScope::Universal,
+            _ => GroupScope::Global,
+        };
+        let type_flags = match group_type {
+            Some(t) if t & 0x8000_0000 == 0 => GroupTypeFlags::DISTRIBUTION,
+            _ => GroupTypeFlags::SECURITY,
+        };
+
+        let mut group = existing.unwrap_or_else(|| {
+            let name = entry.get("cn").map(str::to_string).unwrap_or_else(|| rdn_value(&entry.dn));
+            Group::new(name, sam.clone(), Uuid::nil(), type_flags, scope)
+        });
+        group.description = entry.get("description").map(str::to_string).or(group.description);
+        if group_type.is_some() {
+            group.scope = scope;
+            group.type_flags = type_flags;
+        }
+        self.ldif_attributes(entry, SchemaClass::Group, &mut group.meta).await?;
+        // memberURL (groupOfURLs): `ldap:///<base>?<attrs>?<scope>?<filter>` — правило членства
+        if let Some(rule) = entry.get("memberURL").and_then(|url| url.splitn(4, '?').nth(3)) {
+            group.membership_rule = Some(LdapFilter::parse(rule)?.to_string());
         }
 
-        Ok(false)
+        // Группа сохраняется с прежними участниками: новые добавляются так же, как через API
+        self.create_group(&group).await?;
+        if is_new { report.created += 1 } else { report.updated += 1 }
+        Ok((group.id, Some(group)))
     }
 
-    pub async fn get_effective_gpos_for_ou(
+    /// Заменить участников группы на перечисленных в member, uniqueMember и memberUid записи.
+    /// DN ищется в `dns`, затем среди DN пользователей и групп каталога (`local_dns`, строится
+    /// при первой надобности); не найденные попадают в ошибки отчёта. Участники, для которых
+    /// группа основная, не удаляются: AD не перечисляет их в member
+    async fn set_imported_members(
         &self,
-        ou_id: Uuid,
-    ) -> Result<Vec<GroupPolicy>, DirectoryError> {
-        let mut all_gpos = Vec::new();
-        let mut visited_ou_ids = HashSet::new();
-        let mut current_ou_id = Some(ou_id);
-
-        while let Some(ou_id) = current_ou_id {
-            if visited_ou_ids.contains(&ou_id) {
-                return Err(DirectoryError::InvalidInput("Circular OU hierarchy detected".to_string()));
+        entry: &LdifEntry,
+        group: &Group,
+        dns: &HashMap<String, Uuid>,
+        local_dns: &mut Option<HashMap<String, Uuid>>,
+        report: &mut ImportReport,
+    ) -> Result<(), DirectoryError> {
+        let mut members = Vec::new();
+        let member_dns = entry.get_all("member").into_iter().chain(entry.get_all("uniqueMember"));
+        for member_dn in member_dns {
+            let key = dn::key(member_dn);
+            let id = match dns.get(&key) {
+                Some(id) => Some(*id),
+                None => {
+                    if local_dns.is_none() {
+                        *local_dns = Some(self.local_dns().await?);
+                    }
+                    local_dns.as_ref().and_then(|local| local.get(&key).copied())
+                }
+            };
+            match id {
+                Some(id) => members.push(id),
+                None => report.errors.push(format!("{}: member not found: {}", entry.dn, member_dn)),
             }
-            visited_ou_ids.insert(ou_id);
-
-            let ou = self.get_ou(ou_id).await?.ok_or_else(|| DirectoryError::NotFound("OU not found".to_string()))?;
-
-            if !all_gpos.is_empty() && ou.block_inheritance {
-                let gpos = self.find_gpos_for_ou(ou_id).await?;
-                let enforced: Vec<_> = gpos.into_iter().filter(|g| g.enforced).collect();
-                all_gpos.extend(enforced);
-                break;
+        }
+        for uid in entry.get_all("memberUid") {
+            match self.find_user_by_username(uid).await? {
+                Some(user) => members.push(user.id),
+                None => report.errors.push(format!("{}: member not found: {}", entry.dn, uid)),
             }
-
-            let mut gpos = self.find_gpos_for_ou(ou_id).await?;
-            gpos.sort_by(|a, b| b.enforced.cmp(&a.enforced).then_with(|| a.order.cmp(&b.order)));
-            all_gpos.extend(gpos);
-
-            current_ou_id = ou.parent;
+        }
+        if group.is_dynamic() {
+            return Ok(());
         }
 
-        let mut seen = HashSet::new();
-        let mut unique = Vec::new();
-        for gpo in all_gpos {
-            if seen.insert(gpo.id) {
-                unique.push(gpo);
+        for member_id in members.iter().filter(|id| !group.members.contains(id)) {
+            if let Err(e) = self.add_imported_member(group, *member_id, report).await {
+                report.errors.push(format!("{}: {}", entry.dn, e));
             }
         }
-
-        Ok(unique)
+        for member_id in group.members.iter().filter(|id| !members.contains(id)) {
+            if let Some(user) = self.get_user(*member_id).await?
+                && user.primary_group_id == Some(group.get_rid())
+            {
+                continue;
+            }
+            if let Err(e) = self.remove_member_from_group(group.id, *member_id).await {
+                report.errors.push(format!("{}: {}", entry.dn, e));
+            }
+        }
+        Ok(())
     }
 
-    pub async fn get_effective_gpos_for_user(
-        &self,
-        user_id: Uuid,
-    ) -> Result<Vec<GroupPolicy>, DirectoryError> {
-        let user = self.get_user(user_id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))?;
-
-        let mut all_gpos = Vec::new();
-
-        if let Some(ou_id) = user.organizational_unit {
-            let gpos = self.get_effective_gpos_for_ou(ou_id).await?;
-            all_gpos.extend(gpos);
+    /// DN пользователей и групп каталога, как их строит экспорт LDIF, → id
+    async fn local_dns(&self) -> Result<HashMap<String, Uuid>, DirectoryError> {
+        let mut dns = HashMap::new();
+        for user in self.get_all_users().await? {
+            dns.insert(dn::key(&self.user_dn(&user).await?), user.id);
         }
-
-        let groups = self.find_groups_by_member(user_id).await?;
-        for _group in groups {}
-
-        if let Some(domain_id) = user.domains.get(0) {
-            let gpos = self.find_gpos_for_domain(*domain_id).await?;
-            all_gpos.extend(gpos);
+        for group in self.get_all_groups().await? {
+            dns.insert(dn::key(&self.group_dn(&group).await?), group.id);
         }
+        Ok(dns)
+    }
 
-        let mut seen = HashSet::new();
-        let mut unique = Vec::new();
-        for gpo in all_gpos {
-            if seen.insert(gpo.id) {
-                unique.push(gpo);
+    /// Участник из импорта проходит проверки `add_member_to_group` (существование, вложение групп);
+    /// если импорт запустил вошедший администратор, добавление в административную группу
+    /// требует одобрения, как в `submit_operation`
+    async fn add_imported_member(&self, group: &Group, member_id: Uuid, report: &mut ImportReport) -> Result<(), DirectoryError> {
+        match ActorContext::current().and_then(|actor| actor.user_id) {
+            Some(requested_by) => {
+                let operation = SensitiveOperation::AddGroupMember { group_id: group.id, member_id, ttl_secs: None };
+                if self.submit_operation(operation, requested_by).await?.is_some() {
+                    report.pending_approval += 1;
+                }
+                Ok(())
             }
+            None => self.add_member_to_group(group.id, member_id, None).await,
         }
+    }
 
-        unique.sort_by(|a, b| b.enforced.cmp(&a.enforced).then_with(|| a.order.cmp(&b.order)));
+    async fn import_ldif_contact(&self, entry: &LdifEntry, on_duplicate: DuplicatePolicy, report: &mut ImportReport) -> Result<Uuid, DirectoryError> {
+        let mail = entry.get("mail")
+            .or_else(|| entry.get("targetAddress").map(|address| address.trim_start_matches("SMTP:").trim_start_matches("smtp:")))
+            .ok_or_else(|| DirectoryError::InvalidInput("Contact has no mail".to_string()))?;
+        let existing = self.find_contact_by_email(mail).await?;
+        if let Some(contact) = &existing && on_duplicate == DuplicatePolicy::Skip {
+            report.skipped += 1;
+            return Ok(contact.id);
+        }
+        let is_new = existing.is_none();
 
-        Ok(unique)
+        let mut contact = existing.unwrap_or_else(|| {
+            let name = entry.get("cn").map(str::to_string).unwrap_or_else(|| rdn_value(&entry.dn));
+            Contact::new(name, mail.to_string())
+        });
+        contact.organizational_unit = match Dn::parse(&entry.dn)?.parent() {
+            Some(parent_dn) => self.find_ou_by_dn(&parent_dn.to_string()).await?.map(|ou| ou.id),
+            None => None,
+        }.or(contact.organizational_unit);
+        contact.display_name = entry.get("displayName").map(str::to_string).or(contact.display_name);
+        contact.given_name = entry.get("givenName").map(str::to_string).or(contact.given_name);
+        contact.surname = entry.get("sn").map(str::to_string).or(contact.surname);
+        contact.telephone_number = entry.get("telephoneNumber").map(str::to_string).or(contact.telephone_number);
+        contact.company = entry.get("company").map(str::to_string).or(contact.company);
+        contact.description = entry.get("description").map(str::to_string).or(contact.description);
+        // proxyAddresses: `smtp:` в нижнем регистре — дополнительные адреса
+        let other_emails: Vec<String> = entry.get_all("proxyAddresses").into_iter()
+            .filter_map(|address| address.strip_prefix("smtp:"))
+            .map(str::to_string)
+            .collect();
+        if !other_emails.is_empty() {
+            contact.other_emails = other_emails;
+        }
+        if entry.get("msExchRecipientTypeDetails").and_then(|v| v.parse::<u64>().ok()) == Some(ContactKind::SharedMailbox.recipient_type_details()) {
+            contact.kind = ContactKind::SharedMailbox;
+        }
+        self.ldif_attributes(entry, SchemaClass::Contact, &mut contact.meta).await?;
+        contact.updated_at = Utc::now();
+
+        if is_new {
+            self.create_contact(&contact).await?;
+            report.created += 1;
+        } else {
+            self.update_contact(&contact).await?;
+            report.updated += 1;
+        }
+        Ok(contact.id)
     }
 
     pub async fn find_group_by_rid(&self, rid: u32) -> Result<Option<Group>, DirectoryError> {
@@ -676,16 +5982,23 @@ impl DirectoryService {
     ) -> Result<Vec<SecurityIdentifier>, DirectoryError> {
         let mut tokens = Vec::new();
 
-        let direct_groups = self.find_groups_by_member(user_id).await?;
-        for group in &direct_groups {
-            tokens.push(group.sid.clone());
+        // Как в AD, tokenGroups транзитивен: группы, в которые вложены группы пользователя, тоже входят
+        let mut seen = HashSet::new();
+        let mut queue: std::collections::VecDeque<Group> = self.find_groups_by_member(user_id).await?.into();
+        while let Some(group) = queue.pop_front() {
+            if seen.insert(group.id) {
+                queue.extend(self.find_static_groups_by_member(group.id).await?);
+                tokens.push(group.sid);
+            }
         }
 
         if let Some(user) = self.get_user(user_id).await? {
             if let Some(primary_rid) = user.primary_group_id {
                 if let Some(group) = self.find_group_by_rid(primary_rid).await? {
                     let token_sid = group.get_primary_group_token();
-                    tokens.push(token_sid);
+                    if !tokens.contains(&token_sid) {
+                        tokens.push(token_sid);
+                    }
                 }
             }
         }
@@ -694,25 +6007,231 @@ impl DirectoryService {
     }
 
     pub fn generate_user_dn(user: &User, domain: &Domain) -> String {
-        format!("CN={},{}", user.username, Self::domain_dn(domain))
+        Dn::from_dns_name(&domain.name).child(Rdn::cn(user.username.as_str())).to_string()
     }
 
-    pub fn generate_ou_dn(name: &str, parent: Option<&str>) -> String {
-        let mut dn = format!("OU={}", name);
-        if let Some(parent_dn) = parent {
-            dn.push_str(",");
-            dn.push_str(parent_dn);
+    /// DN `OU=<name>` под `parent` или без родителя; имя экранируется, родитель должен быть корректным DN
+    pub fn generate_ou_dn(name: &str, parent: Option<&str>) -> Result<String, DirectoryError> {
+        if name.trim().is_empty() {
+            return Err(DirectoryError::InvalidInput(format!("Invalid OU name '{}'", name)));
         }
-        dn
+        let parent = Dn::parse(parent.unwrap_or_default())?;
+        Ok(parent.child(Rdn::ou(name)).to_string())
     }
 
     pub fn domain_dn(domain: &Domain) -> String {
-        domain.name
-            .split('.')
-            .map(|part| format!("DC={}", part))
-            .collect::<Vec<_>>()
-            .join(",")
+        Dn::from_dns_name(&domain.name).to_string()
+    }
+}
+
+/// Ключ индекса поиска по имени или адресу: значение после `casefold::fold`
+fn lookup_key(index: &str, value: &str) -> String {
+    format!("{}:{}", index, casefold::fold(value))
+}
+
+/// Ключ индекса OU по DN: каноническая форма, чтобы `ou=sales, dc=x,dc=com` находил `OU=Sales,DC=x,DC=com`
+fn dn_index_key(dn: &str) -> String {
+    format!("dn_index:{}", dn::key(dn))
+}
+
+/// Значение первого RDN без экранирования: `CN=Smith\, John,OU=...` → `Smith, John`
+fn rdn_value(dn: &str) -> String {
+    Dn::parse(dn).ok()
+        .and_then(|dn| dn.rdn().map(|rdn| rdn.value().to_string()))
+        .unwrap_or_default()
+}
+
+/// Участников динамической группы задаёт только правило
+fn reject_dynamic(group: &Group) -> Result<(), DirectoryError> {
+    if group.is_dynamic() {
+        return Err(DirectoryError::InvalidInput(format!(
+            "Membership of dynamic group {} is computed from its rule",
+            group.sam_account_name
+        )));
+    }
+    Ok(())
+}
+
+/// sAMAccountName, затем uid (OpenLDAP), затем значение RDN
+fn ldif_username(entry: &LdifEntry) -> String {
+    entry.get("sAMAccountName").or(entry.get("uid")).map(str::to_string).unwrap_or_else(|| rdn_value(&entry.dn))
+}
+
+fn ldif_group_sam(entry: &LdifEntry) -> String {
+    entry.get("sAMAccountName").or(entry.get("cn")).map(str::to_string).unwrap_or_else(|| rdn_value(&entry.dn))
+}
+
+/// Новый хеш пароля и ключи Kerberos со следующим kvno; возвращает kvno
+fn apply_password(user: &mut User, password: &str) -> Result<u32, DirectoryError> {
+    user.password_hash = PasswordHash::new_bcrypt(password)
+        .map_err(|e| DirectoryError::InvalidInput(e.to_string()))?;
+
+    let realm = crate::kerberos::realm_from_upn(&user.user_principal_name)
+        .ok_or_else(|| DirectoryError::InvalidInput(format!("Cannot derive realm from UPN {}", user.user_principal_name)))?;
+    let kvno = user.kerberos_keys.iter().map(|k| k.kvno).max().unwrap_or(0) + 1;
+    user.kerberos_keys = KerberosKey::derive_all(password, &realm, &user.username, kvno);
+
+    user.last_password_change = Utc::now();
+    user.updated_at = Utc::now();
+    Ok(kvno)
+}
+
+/// Subject и SAN сертификата учётной записи: компьютер — DNS-имена из SPN `HOST/...`
+/// (по ним же mTLS находит учётную запись), пользователь — имя и UPN с адресом почты
+fn certificate_subject(user: &User) -> Result<crate::ca::CertificateSubject, DirectoryError> {
+    if is_computer_account(user) {
+        let mut hosts: Vec<String> = Vec::new();
+        for spn in &user.service_principal_names {
+            if let Some((service, host)) = spn.split_once('/')
+                && service.eq_ignore_ascii_case("HOST")
+                && !hosts.iter().any(|known| known.eq_ignore_ascii_case(host))
+            {
+                hosts.push(host.to_lowercase());
+            }
+        }
+        let common_name = hosts.first().cloned().ok_or_else(|| DirectoryError::InvalidInput(format!(
+            "Computer {} has no HOST/ SPN to put into a certificate",
+            user.username
+        )))?;
+        return Ok(crate::ca::CertificateSubject { common_name, kind: CertificateKind::Computer, sans: hosts });
+    }
+
+    let mut sans = vec![user.user_principal_name.clone()];
+    if let Some(email) = &user.email
+        && !email.eq_ignore_ascii_case(&user.user_principal_name)
+    {
+        sans.push(email.clone());
+    }
+    Ok(crate::ca::CertificateSubject { common_name: user.username.clone(), kind: CertificateKind::User, sans })
+}
+
+/// Учётная запись компьютера: `имя$` или флаг WORKSTATION_TRUST_ACCOUNT
+fn is_computer_account(user: &User) -> bool {
+    user.username.ends_with('$') || user.user_account_control.contains(UserAccountControl::WORKSTATION_TRUST_ACCOUNT)
+}
+
+/// Записи журнала изменений для записи или удаления объекта по ключу `key`; пусто, если объект
+/// не отслеживается. Записываются вместе с объектом под той же блокировкой базы
+fn change_entries(db: &RadDB, key: &str, change_type: ChangeType) -> Result<Vec<(String, Vec<u8>)>, DirectoryError> {
+    let tracked = CHANGE_TRACKED.iter().find_map(|(prefix, object_type)| {
+        let id = Uuid::parse_str(key.strip_prefix(prefix)?).ok()?;
+        Some((id, *object_type))
+    });
+    let Some((object_id, object_type)) = tracked else {
+        return Ok(Vec::new());
+    };
+    let usn = match db.get(USN_KEY) {
+        Some(data) => bincode::deserialize::<u64>(&data).map_err(|e| DirectoryError::Serialization(e.to_string()))? + 1,
+        None => 1,
+    };
+    let change = ChangeEntry { usn, object_id, object_type: object_type.to_string(), change_type, timestamp: Utc::now() };
+    let serialization = |e: bincode::Error| DirectoryError::Serialization(e.to_string());
+    Ok(vec![
+        (format!("change:{}", usn), bincode::serialize(&change).map_err(serialization)?),
+        (USN_KEY.to_string(), bincode::serialize(&usn).map_err(serialization)?),
+    ])
+}
+
+/// Объект изменения, который отдаёт DirSync: только то, что видно в LDAP
+fn dirsync_object(change: &ChangeEntry) -> Option<SecuredObject> {
+    match change.object_type.as_str() {
+        "user" => Some(SecuredObject::User(change.object_id)),
+        "group" => Some(SecuredObject::Group(change.object_id)),
+        "ou" => Some(SecuredObject::Ou(change.object_id)),
+        "contact" => Some(SecuredObject::Contact(change.object_id)),
+        _ => None,
+    }
+}
+
+/// Смена области и типа группы по правилам AD: у встроенных групп они не меняются,
+/// а между DomainLocal и Global группа переводится только через Universal
+fn check_group_conversion(previous: &Group, group: &Group) -> Result<(), DirectoryError> {
+    let type_changed = previous.type_flags != group.type_flags;
+    if (previous.scope != group.scope || type_changed) && (previous.is_builtin() || previous.is_protected()) {
+        return Err(DirectoryError::Protected(format!("Scope and type of built-in group {} cannot be changed", previous.sam_account_name)));
+    }
+    if type_changed && group.type_flags.contains(GroupTypeFlags::SECURITY) == group.type_flags.contains(GroupTypeFlags::DISTRIBUTION) {
+        return Err(DirectoryError::InvalidInput("Group must be either a security or a distribution group".to_string()));
+    }
+    match (previous.scope, group.scope) {
+        (GroupScope::DomainLocal, GroupScope::Global) | (GroupScope::Global, GroupScope::DomainLocal) => Err(DirectoryError::InvalidInput(format!(
+            "Group {} cannot be converted from {:?} to {:?} directly; convert it to Universal first",
+            previous.sam_account_name, previous.scope, group.scope
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Ключ `sid_index` для SID домена или BUILTIN; SID-заглушка с одним sub-authority (`S-1-5-1001`)
+/// общая у объектов, созданных до домена, и не индексируется
+fn sid_index_key(sid: &SecurityIdentifier) -> Option<String> {
+    (sid.sub_authorities.len() > 1).then(|| format!("sid_index:{}", sid))
+}
+
+/// Удалить объект, сбросить его в кэше и записать удаление в журнал изменений
+fn remove_object(db: &RadDB, cache: &ObjectCache, key: &str) -> Result<(), DirectoryError> {
+    cache.invalidate(key);
+    if db.remove(key) {
+        db.set_many(change_entries(db, key, ChangeType::Delete)?)?;
+    }
+    Ok(())
+}
+
+/// Данные самого контроллера, которые не реплицируются: журнал аудита, отметки об уведомлениях,
+/// токены сброса пароля и внутренний CA — в нём закрытый ключ
+fn is_local_key(key: &str) -> bool {
+    key == "ca" || key.starts_with("audit") || key.starts_with("password_expiry_notice:") || key.starts_with("password_reset")
+}
+
+/// Когда истечёт только что установленный пароль; `None` — бессрочный (`max_age_days` = 0
+/// или флаг DONT_EXPIRE_PASSWORD)
+fn password_expiry(user: &User, policy: &PasswordPolicy) -> Option<chrono::DateTime<Utc>> {
+    if policy.max_age_days == 0 || user.user_account_control.contains(UserAccountControl::DONT_EXPIRE_PASSWORD) {
+        return None;
+    }
+    Some(user.last_password_change + chrono::Duration::days(policy.max_age_days.into()))
+}
+
+/// Подтверждение подозрительного входа (`step_up`) вторым фактором пользователя. Проверить сервер
+/// может только TOTP: без него подтвердить вход нечем, и он отклоняется, как при `block`
+fn confirm_second_factor(user: &User, mfa_code: Option<&str>, signals: &[&str]) -> Result<(), DirectoryError> {
+    let Some(secret) = user.totp_secret.as_deref().filter(|_| user.mfa_enabled && user.mfa_methods.contains(&MfaMethod::Totp)) else {
+        return Err(DirectoryError::AccessDenied(format!("Login blocked by risk policy, no second factor to confirm it: {}", signals.join(", "))));
+    };
+    match mfa_code {
+        None => Err(DirectoryError::MfaRequired(vec![MfaMethod::Totp])),
+        Some(code) if crate::models::mfa::verify_totp(secret, code, Utc::now()) => Ok(()),
+        Some(_) => Err(DirectoryError::AuthenticationFailed("invalid_mfa_code".to_string())),
+    }
+}
+
+/// LDAP-запись модели в запись LDIF: objectClass первым, остальные по алфавиту, пустые значения опускаются
+fn ldap_entry_to_ldif(dn: &str, mut attributes: LdapAttributes) -> LdifEntry {
+    attributes.remove("distinguishedName");
+    // objectGUID импорт не переносит: объект получает новый
+    attributes.remove(OBJECT_GUID);
+    let mut ldif_entry = LdifEntry::new(dn);
+    for class in attributes.text("objectClass") {
+        ldif_entry.add("objectClass", class.to_string());
+    }
+    attributes.remove("objectClass");
+    let mut names: Vec<&String> = attributes.names().collect();
+    names.sort_by_key(|n| n.to_lowercase());
+    for name in names {
+        // objectSid — в строковой форме, как его читает импорт; прочие двоичные значения отбрасываются
+        if name.eq_ignore_ascii_case("objectSid") {
+            for value in attributes.get(name).unwrap_or_default() {
+                if let Ok(sid) = SecurityIdentifier::from_bytes(value.as_bytes()) {
+                    ldif_entry.add(name.clone(), sid.to_string());
+                }
+            }
+            continue;
+        }
+        for value in attributes.text(name).into_iter().filter(|v| !v.is_empty()) {
+            ldif_entry.add(name.clone(), value.to_string());
+        }
     }
+    ldif_entry
 }
 
 impl Drop for DirectoryService {
@@ -0,0 +1,335 @@
+// src/dn.rs
+
+//! Distinguished Name (RFC 4514): RDN и DN как значения, экранирование, разбор, сравнение без
+//! учёта регистра (как в AD) и каноническая форма для индексов. DN каталога собираются через
+//! `Dn` и `Rdn`, а не `format!`: имя с запятой или `+` не ломает DN
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// Символы, которые экранируются в значении RDN где угодно
+const SPECIAL: &[char] = &[',', '+', '"', '\\', '<', '>', ';', '='];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnError {
+    pub dn: String,
+    pub reason: String,
+}
+
+impl DnError {
+    fn new(dn: &str, reason: impl Into<String>) -> Self {
+        Self { dn: dn.to_string(), reason: reason.into() }
+    }
+}
+
+impl fmt::Display for DnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid DN '{}': {}", self.dn, self.reason)
+    }
+}
+
+impl std::error::Error for DnError {}
+
+/// Один RDN: тип атрибута и значение без экранирования. Многозначные RDN (`CN=a+SN=b`) не поддерживаются
+#[derive(Debug, Clone)]
+pub struct Rdn {
+    attribute: String,
+    value: String,
+}
+
+impl Rdn {
+    pub fn new(attribute: impl Into<String>, value: impl Into<String>) -> Self {
+        Self { attribute: attribute.into(), value: value.into() }
+    }
+
+    pub fn cn(value: impl Into<String>) -> Self {
+        Self::new("CN", value)
+    }
+
+    pub fn ou(value: impl Into<String>) -> Self {
+        Self::new("OU", value)
+    }
+
+    pub fn dc(value: impl Into<String>) -> Self {
+        Self::new("DC", value)
+    }
+
+    pub fn attribute(&self) -> &str {
+        &self.attribute
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Тип атрибута без учёта регистра: `rdn.is("OU")`
+    pub fn is(&self, attribute: &str) -> bool {
+        self.attribute.eq_ignore_ascii_case(attribute)
+    }
+
+    fn canonical(&self) -> String {
+        format!("{}={}", self.attribute.to_ascii_lowercase(), escape_value(&self.value.to_lowercase()))
+    }
+}
+
+impl fmt::Display for Rdn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.attribute, escape_value(&self.value))
+    }
+}
+
+impl PartialEq for Rdn {
+    fn eq(&self, other: &Self) -> bool {
+        self.attribute.eq_ignore_ascii_case(&other.attribute) && self.value.to_lowercase() == other.value.to_lowercase()
+    }
+}
+
+impl Eq for Rdn {}
+
+impl Hash for Rdn {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state);
+    }
+}
+
+/// DN от листа к корню: `CN=bob,OU=Sales,DC=corp,DC=com`. Пустой DN — корень
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Dn(Vec<Rdn>);
+
+impl Dn {
+    /// Разобрать DN: значения могут быть экранированы (`\,`, `\2C`) или взяты в кавычки;
+    /// пробелы вокруг `,` и `=` допускаются
+    pub fn parse(dn: &str) -> Result<Self, DnError> {
+        if dn.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let rdns = split_unescaped(dn, ',')
+            .into_iter()
+            .map(|rdn| parse_rdn(rdn).map_err(|reason| DnError::new(dn, reason)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self(rdns))
+    }
+
+    /// `corp.acme.com` → `DC=corp,DC=acme,DC=com`
+    pub fn from_dns_name(dns_name: &str) -> Self {
+        Self(dns_name.trim_end_matches('.')
+            .split('.')
+            .filter(|label| !label.is_empty())
+            .map(Rdn::dc)
+            .collect())
+    }
+
+    /// Дочерний DN: `rdn` под этим DN
+    pub fn child(&self, rdn: Rdn) -> Self {
+        let mut rdns = Vec::with_capacity(self.0.len() + 1);
+        rdns.push(rdn);
+        rdns.extend(self.0.iter().cloned());
+        Self(rdns)
+    }
+
+    /// Первый RDN
+    pub fn rdn(&self) -> Option<&Rdn> {
+        self.0.first()
+    }
+
+    pub fn rdns(&self) -> &[Rdn] {
+        &self.0
+    }
+
+    /// DN родителя; у DN из одного RDN родителя нет
+    pub fn parent(&self) -> Option<Self> {
+        (self.0.len() > 1).then(|| Self(self.0[1..].to_vec()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Совпадает с `suffix` или лежит под ним
+    pub fn ends_with(&self, suffix: &Dn) -> bool {
+        self.0.len() >= suffix.0.len() && self.0[self.0.len() - suffix.0.len()..] == suffix.0[..]
+    }
+
+    /// Лежит под `ancestor` (сам `ancestor` — нет)
+    pub fn is_descendant_of(&self, ancestor: &Dn) -> bool {
+        self.0.len() > ancestor.0.len() && self.ends_with(ancestor)
+    }
+
+    /// Тот же путь под новым предком: `OU=East,OU=Sales,...` с `OU=Sales,...` → `OU=Retail,...`
+    /// даёт `OU=East,OU=Retail,...`. `None`, если DN не под `old`
+    pub fn rebase(&self, old: &Dn, new: &Dn) -> Option<Self> {
+        if !self.ends_with(old) {
+            return None;
+        }
+        let mut rdns = self.0[..self.0.len() - old.0.len()].to_vec();
+        rdns.extend(new.0.iter().cloned());
+        Some(Self(rdns))
+    }
+
+    /// DNS-имя из компонентов `DC=` подряд в конце DN: `OU=x,DC=corp,DC=com` → `corp.com`
+    pub fn dns_name(&self) -> Option<String> {
+        let labels: Vec<&str> = self.0.iter().rev()
+            .take_while(|rdn| rdn.is("DC"))
+            .map(Rdn::value)
+            .collect();
+        (!labels.is_empty()).then(|| labels.into_iter().rev().collect::<Vec<_>>().join("."))
+    }
+
+    /// Каноническая форма: типы и значения в нижнем регистре, без лишних пробелов, с единым
+    /// экранированием. Равные DN дают одну строку — ключ индексов
+    pub fn canonical(&self) -> String {
+        self.0.iter().map(Rdn::canonical).collect::<Vec<_>>().join(",")
+    }
+}
+
+impl fmt::Display for Dn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, rdn) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", rdn)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Dn {
+    type Err = DnError;
+
+    fn from_str(dn: &str) -> Result<Self, Self::Err> {
+        Self::parse(dn)
+    }
+}
+
+/// RDN под DN `parent`, уже записанным строкой (DN из каталога); пустой `parent` — только RDN
+pub fn join(rdn: &Rdn, parent: &str) -> String {
+    match parent.is_empty() {
+        true => rdn.to_string(),
+        false => format!("{},{}", rdn, parent),
+    }
+}
+
+/// Каноническая форма DN из строки; `None`, если DN не разбирается
+pub fn canonical(dn: &str) -> Option<String> {
+    Dn::parse(dn).ok().map(|dn| dn.canonical())
+}
+
+/// Ключ для сравнения DN: каноническая форма или, если DN не разбирается, `casefold::fold`
+pub fn key(dn: &str) -> String {
+    canonical(dn).unwrap_or_else(|| crate::casefold::fold(dn))
+}
+
+/// Экранировать значение RDN (RFC 4514, раздел 2.4)
+pub fn escape_value(value: &str) -> String {
+    let last = value.chars().count().saturating_sub(1);
+    let mut out = String::with_capacity(value.len());
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            '\0' => out.push_str("\\00"),
+            _ if SPECIAL.contains(&c) || (i == 0 && matches!(c, '#' | ' ')) || (i == last && c == ' ') => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Разделить по `separator` вне экранирования и кавычек
+fn split_unescaped(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    let mut quoted = false;
+    for (i, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            _ if c == separator && !quoted => {
+                parts.push(&input[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+fn parse_rdn(rdn: &str) -> Result<Rdn, String> {
+    if split_unescaped(rdn, '+').len() > 1 {
+        return Err(format!("multi-valued RDN '{}' is not supported", rdn.trim()));
+    }
+    let (attribute, value) = rdn.split_once('=').ok_or_else(|| format!("RDN '{}' has no '='", rdn.trim()))?;
+    let attribute = attribute.trim();
+    let descriptor = attribute.starts_with(|c: char| c.is_ascii_alphabetic())
+        && attribute.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    let oid = !attribute.is_empty() && attribute.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    if !descriptor && !oid {
+        return Err(format!("invalid attribute type '{}'", attribute));
+    }
+    Ok(Rdn::new(attribute, unescape_value(value)?))
+}
+
+/// Значение RDN без экранирования: `\,` и `\2C` → `,`; значение в кавычках берётся как есть.
+/// Неэкранированные пробелы по краям отбрасываются
+fn unescape_value(value: &str) -> Result<String, String> {
+    let value = value.trim_start();
+    if let Some(quoted) = value.strip_prefix('"') {
+        let end = quoted.rfind('"').ok_or("unterminated quoted value")?;
+        if !quoted[end + 1..].trim().is_empty() {
+            return Err("text after quoted value".to_string());
+        }
+        return Ok(unescape_quoted(&quoted[..end]));
+    }
+
+    let mut bytes = Vec::with_capacity(value.len());
+    // Длина без хвоста из неэкранированных пробелов
+    let mut keep = 0;
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            if c != ' ' {
+                keep = bytes.len();
+            }
+            continue;
+        }
+        let next = chars.next().ok_or("value ends with '\\'")?;
+        match (next.to_digit(16), chars.peek().and_then(|c| c.to_digit(16))) {
+            (Some(high), Some(low)) => {
+                chars.next();
+                bytes.push((high * 16 + low) as u8);
+            }
+            _ if SPECIAL.contains(&next) || matches!(next, ' ' | '#') => {
+                bytes.push(next as u8);
+            }
+            _ => return Err(format!("invalid escape '\\{}'", next)),
+        }
+        keep = bytes.len();
+    }
+    bytes.truncate(keep);
+    String::from_utf8(bytes).map_err(|_| "escaped value is not valid UTF-8".to_string())
+}
+
+/// Внутри кавычек экранируются только `"` и `\`
+fn unescape_quoted(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            _ => out.push(c),
+        }
+    }
+    out
+}
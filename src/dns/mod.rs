@@ -0,0 +1,165 @@
+// src/dns/mod.rs
+
+//! Авторитетный DNS для доменов каталога: SOA/NS/A/AAAA/SRV генерируются из
+//! доменов и зарегистрированных контроллеров, компьютеры регистрируют свои
+//! адреса динамическими обновлениями (RFC 2136).
+
+pub mod wire;
+pub mod zone;
+
+use std::sync::Arc;
+use futures_util::future::{try_join_all, BoxFuture};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+use wire::Message;
+pub use zone::DnsAuthority;
+
+/// Размер ответа UDP без EDNS (RFC 1035)
+const UDP_PAYLOAD_LIMIT: usize = 512;
+
+/// Максимальный размер, который мы объявляем через EDNS
+const MAX_UDP_PAYLOAD: usize = 4096;
+
+/// Тип псевдозаписи OPT (EDNS0, RFC 6891)
+const TYPE_OPT: u16 = 41;
+
+#[derive(Debug)]
+pub enum DnsError {
+    Io(std::io::Error),
+    Decode(String),
+    Directory(DirectoryError),
+}
+
+impl From<std::io::Error> for DnsError {
+    fn from(e: std::io::Error) -> Self {
+        DnsError::Io(e)
+    }
+}
+
+impl From<DirectoryError> for DnsError {
+    fn from(e: DirectoryError) -> Self {
+        DnsError::Directory(e)
+    }
+}
+
+impl std::fmt::Display for DnsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DnsError::Io(e) => write!(f, "IO error: {}", e),
+            DnsError::Decode(e) => write!(f, "Decode error: {}", e),
+            DnsError::Directory(e) => write!(f, "Directory error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DnsError {}
+
+/// Сетевой фронтенд DNS: UDP и TCP на каждом адресе (`0.0.0.0:53`, `[::]:53`)
+pub struct DnsServer {
+    authority: Arc<DnsAuthority>,
+    udp: Vec<UdpSocket>,
+    tcp: Vec<TcpListener>,
+}
+
+impl DnsServer {
+    pub async fn bind(service: Arc<DirectoryService>, addrs: &[String], dynamic_updates: bool) -> Result<Self, DnsError> {
+        let (mut udp, mut tcp) = (Vec::with_capacity(addrs.len()), Vec::with_capacity(addrs.len()));
+        for addr in addrs {
+            udp.push(crate::net::bind_udp(addr).await?);
+            tcp.push(crate::net::bind_tcp(addr).await?);
+        }
+        if dynamic_updates {
+            tracing::warn!("Динамические обновления DNS включены без TSIG: записи может менять любой клиент сети");
+        }
+        Ok(Self {
+            authority: Arc::new(DnsAuthority::new(service, dynamic_updates)),
+            udp,
+            tcp,
+        })
+    }
+
+    pub async fn run(self) -> Result<(), DnsError> {
+        let mut tasks: Vec<BoxFuture<'static, Result<(), DnsError>>> = Vec::new();
+        for udp in self.udp {
+            tracing::info!(addr = %udp.local_addr()?, "DNS слушает");
+            tasks.push(Box::pin(serve_udp(udp, Arc::clone(&self.authority))));
+        }
+        for tcp in self.tcp {
+            tasks.push(Box::pin(serve_tcp(tcp, Arc::clone(&self.authority))));
+        }
+        // Сокеты работают, пока не откажет любой из них
+        try_join_all(tasks).await.map(|_| ())
+    }
+}
+
+async fn serve_udp(udp: UdpSocket, authority: Arc<DnsAuthority>) -> Result<(), DnsError> {
+    let mut buf = vec![0u8; MAX_UDP_PAYLOAD];
+    loop {
+        let (n, peer) = udp.recv_from(&mut buf).await?;
+        // Неразборчивые пакеты и ответы молча отбрасываем
+        let Ok(request) = Message::parse(&buf[..n]) else { continue };
+        if request.is_response() {
+            continue;
+        }
+        let reply = answer(&authority, &request).await;
+        udp.send_to(&reply.encode_truncated(udp_payload_limit(&request)), peer).await?;
+    }
+}
+
+async fn serve_tcp(tcp: TcpListener, authority: Arc<DnsAuthority>) -> Result<(), DnsError> {
+    loop {
+        let (socket, _) = tcp.accept().await?;
+        let authority = Arc::clone(&authority);
+        tokio::spawn(async move {
+            if let Err(e) = handle_tcp_client(socket, authority).await {
+                tracing::warn!(error = %e, "Ошибка TCP-клиента DNS");
+            }
+        });
+    }
+}
+
+/// Ответ на запрос; клиенту с EDNS возвращаем собственную OPT-запись
+async fn answer(authority: &DnsAuthority, request: &Message) -> Message {
+    let mut reply = authority.handle(request).await;
+    if request.additional.iter().any(|rr| rr.rtype == TYPE_OPT) {
+        reply.additional.push(wire::ResourceRecord {
+            name: String::new(),
+            rtype: TYPE_OPT,
+            class: MAX_UDP_PAYLOAD as u16,
+            ttl: 0,
+            rdata: vec![],
+        });
+    }
+    reply
+}
+
+/// Размер UDP-ответа: 512 байт или значение из OPT-записи клиента
+fn udp_payload_limit(request: &Message) -> usize {
+    request
+        .additional
+        .iter()
+        .find(|rr| rr.rtype == TYPE_OPT)
+        .map(|opt| (opt.class as usize).clamp(UDP_PAYLOAD_LIMIT, MAX_UDP_PAYLOAD))
+        .unwrap_or(UDP_PAYLOAD_LIMIT)
+}
+
+/// По TCP каждое сообщение предваряется 2-байтной длиной (RFC 1035, 4.2.2)
+async fn handle_tcp_client(mut socket: TcpStream, authority: Arc<DnsAuthority>) -> Result<(), DnsError> {
+    loop {
+        let len = match socket.read_u16().await {
+            Ok(len) => len as usize,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut data = vec![0u8; len];
+        socket.read_exact(&mut data).await?;
+
+        let request = Message::parse(&data)?;
+        let reply = answer(&authority, &request).await.encode();
+        socket.write_u16(reply.len() as u16).await?;
+        socket.write_all(&reply).await?;
+    }
+}
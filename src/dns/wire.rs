@@ -0,0 +1,317 @@
+// src/dns/wire.rs
+
+//! Формат DNS-сообщений (RFC 1035) в объёме, нужном авторитетному серверу.
+
+use super::DnsError;
+use crate::models::DnsRecordData;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_NS: u16 = 2;
+pub const TYPE_SOA: u16 = 6;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_SRV: u16 = 33;
+pub const TYPE_ANY: u16 = 255;
+
+pub const CLASS_IN: u16 = 1;
+pub const CLASS_NONE: u16 = 254;
+pub const CLASS_ANY: u16 = 255;
+
+pub const OPCODE_QUERY: u8 = 0;
+pub const OPCODE_UPDATE: u8 = 5;
+
+pub const RCODE_NOERROR: u8 = 0;
+pub const RCODE_FORMERR: u8 = 1;
+pub const RCODE_SERVFAIL: u8 = 2;
+pub const RCODE_NXDOMAIN: u8 = 3;
+pub const RCODE_NOTIMP: u8 = 4;
+pub const RCODE_REFUSED: u8 = 5;
+pub const RCODE_YXDOMAIN: u8 = 6;
+pub const RCODE_YXRRSET: u8 = 7;
+pub const RCODE_NXRRSET: u8 = 8;
+pub const RCODE_NOTAUTH: u8 = 9;
+pub const RCODE_NOTZONE: u8 = 10;
+
+const FLAG_QR: u16 = 0x8000;
+const FLAG_AA: u16 = 0x0400;
+const FLAG_TC: u16 = 0x0200;
+const FLAG_RD: u16 = 0x0100;
+
+/// Максимальная глубина указателей сжатия имён
+const MAX_POINTERS: usize = 32;
+
+/// Вопрос (в UPDATE — описание зоны)
+#[derive(Debug, Clone)]
+pub struct Question {
+    pub name: String,
+    pub qtype: u16,
+    pub qclass: u16,
+}
+
+/// Ресурсная запись с необработанными RDATA
+#[derive(Debug, Clone)]
+pub struct ResourceRecord {
+    pub name: String,
+    pub rtype: u16,
+    pub class: u16,
+    pub ttl: u32,
+    pub rdata: Vec<u8>,
+}
+
+impl ResourceRecord {
+    pub fn from_data(name: &str, ttl: u32, data: &DnsRecordData) -> Self {
+        Self {
+            name: name.to_string(),
+            rtype: record_type(data),
+            class: CLASS_IN,
+            ttl,
+            rdata: encode_rdata(data),
+        }
+    }
+}
+
+/// DNS-сообщение. В UPDATE секции называются zone/prerequisite/update/additional
+#[derive(Debug, Clone, Default)]
+pub struct Message {
+    pub id: u16,
+    pub flags: u16,
+    pub questions: Vec<Question>,
+    pub answers: Vec<ResourceRecord>,
+    pub authority: Vec<ResourceRecord>,
+    pub additional: Vec<ResourceRecord>,
+}
+
+impl Message {
+    pub fn opcode(&self) -> u8 {
+        ((self.flags >> 11) & 0x0F) as u8
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.flags & FLAG_QR != 0
+    }
+
+    /// Заготовка ответа: тот же id, opcode, RD и секция вопросов
+    pub fn reply(&self) -> Message {
+        Message {
+            id: self.id,
+            flags: FLAG_QR | (self.flags & (0x7800 | FLAG_RD)),
+            questions: self.questions.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_rcode(&mut self, rcode: u8) {
+        self.flags = (self.flags & !0x000F) | (rcode as u16 & 0x0F);
+    }
+
+    pub fn set_authoritative(&mut self) {
+        self.flags |= FLAG_AA;
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, DnsError> {
+        let mut reader = Reader { data, pos: 0 };
+        let id = reader.u16()?;
+        let flags = reader.u16()?;
+        let qdcount = reader.u16()?;
+        let ancount = reader.u16()?;
+        let nscount = reader.u16()?;
+        let arcount = reader.u16()?;
+
+        let mut questions = Vec::new();
+        for _ in 0..qdcount {
+            questions.push(Question {
+                name: reader.name()?,
+                qtype: reader.u16()?,
+                qclass: reader.u16()?,
+            });
+        }
+
+        Ok(Self {
+            id,
+            flags,
+            questions,
+            answers: reader.records(ancount)?,
+            authority: reader.records(nscount)?,
+            additional: reader.records(arcount)?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(512);
+        out.extend_from_slice(&self.id.to_be_bytes());
+        out.extend_from_slice(&self.flags.to_be_bytes());
+        for count in [self.questions.len(), self.answers.len(), self.authority.len(), self.additional.len()] {
+            out.extend_from_slice(&(count as u16).to_be_bytes());
+        }
+
+        for q in &self.questions {
+            write_name(&mut out, &q.name);
+            out.extend_from_slice(&q.qtype.to_be_bytes());
+            out.extend_from_slice(&q.qclass.to_be_bytes());
+        }
+        for rr in self.answers.iter().chain(&self.authority).chain(&self.additional) {
+            write_name(&mut out, &rr.name);
+            out.extend_from_slice(&rr.rtype.to_be_bytes());
+            out.extend_from_slice(&rr.class.to_be_bytes());
+            out.extend_from_slice(&rr.ttl.to_be_bytes());
+            out.extend_from_slice(&(rr.rdata.len() as u16).to_be_bytes());
+            out.extend_from_slice(&rr.rdata);
+        }
+        out
+    }
+
+    /// Закодировать с учётом лимита UDP: при превышении — только заголовок и вопросы с флагом TC
+    pub fn encode_truncated(&self, limit: usize) -> Vec<u8> {
+        let full = self.encode();
+        if full.len() <= limit {
+            return full;
+        }
+        let truncated = Message {
+            id: self.id,
+            flags: self.flags | FLAG_TC,
+            questions: self.questions.clone(),
+            ..Default::default()
+        };
+        truncated.encode()
+    }
+}
+
+/// Тип записи для данных модели
+pub fn record_type(data: &DnsRecordData) -> u16 {
+    match data {
+        DnsRecordData::A(_) => TYPE_A,
+        DnsRecordData::Aaaa(_) => TYPE_AAAA,
+        DnsRecordData::Ns(_) => TYPE_NS,
+        DnsRecordData::Srv { .. } => TYPE_SRV,
+        DnsRecordData::Soa { .. } => TYPE_SOA,
+    }
+}
+
+pub fn encode_rdata(data: &DnsRecordData) -> Vec<u8> {
+    let mut out = Vec::new();
+    match data {
+        DnsRecordData::A(addr) => out.extend_from_slice(&addr.octets()),
+        DnsRecordData::Aaaa(addr) => out.extend_from_slice(&addr.octets()),
+        DnsRecordData::Ns(host) => write_name(&mut out, host),
+        DnsRecordData::Srv { priority, weight, port, target } => {
+            out.extend_from_slice(&priority.to_be_bytes());
+            out.extend_from_slice(&weight.to_be_bytes());
+            out.extend_from_slice(&port.to_be_bytes());
+            write_name(&mut out, target);
+        }
+        DnsRecordData::Soa { mname, rname, serial, refresh, retry, expire, minimum } => {
+            write_name(&mut out, mname);
+            write_name(&mut out, rname);
+            for value in [serial, refresh, retry, expire, minimum] {
+                out.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+    }
+    out
+}
+
+/// Разобрать RDATA адресных записей (только они принимаются в динамических обновлениях)
+pub fn decode_address(rtype: u16, rdata: &[u8]) -> Result<DnsRecordData, DnsError> {
+    match (rtype, rdata.len()) {
+        (TYPE_A, 4) => {
+            let octets: [u8; 4] = rdata.try_into().expect("length checked");
+            Ok(DnsRecordData::A(octets.into()))
+        }
+        (TYPE_AAAA, 16) => {
+            let octets: [u8; 16] = rdata.try_into().expect("length checked");
+            Ok(DnsRecordData::Aaaa(octets.into()))
+        }
+        _ => Err(DnsError::Decode(format!("Unsupported address record type {}", rtype))),
+    }
+}
+
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    for label in name.trim_end_matches('.').split('.').filter(|l| !l.is_empty()) {
+        let bytes = &label.as_bytes()[..label.len().min(63)];
+        out.push(bytes.len() as u8);
+        out.extend_from_slice(bytes);
+    }
+    out.push(0);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, n: usize) -> Result<&[u8], DnsError> {
+        let end = self.pos.checked_add(n).filter(|end| *end <= self.data.len())
+            .ok_or_else(|| DnsError::Decode("Unexpected end of message".to_string()))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16, DnsError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, DnsError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Имя в нижнем регистре, без завершающей точки; поддерживает сжатие
+    fn name(&mut self) -> Result<String, DnsError> {
+        let mut labels: Vec<String> = Vec::new();
+        let mut pos = self.pos;
+        let mut jumped = false;
+        let mut jumps = 0;
+
+        loop {
+            let len = *self.data.get(pos)
+                .ok_or_else(|| DnsError::Decode("Unexpected end of name".to_string()))? as usize;
+            match len {
+                0 => {
+                    pos += 1;
+                    break;
+                }
+                l if l & 0xC0 == 0xC0 => {
+                    let low = *self.data.get(pos + 1)
+                        .ok_or_else(|| DnsError::Decode("Truncated name pointer".to_string()))? as usize;
+                    if !jumped {
+                        self.pos = pos + 2;
+                    }
+                    jumped = true;
+                    jumps += 1;
+                    if jumps > MAX_POINTERS {
+                        return Err(DnsError::Decode("Name pointer loop".to_string()));
+                    }
+                    pos = ((l & 0x3F) << 8) | low;
+                }
+                l if l <= 63 => {
+                    let label = self.data.get(pos + 1..pos + 1 + l)
+                        .ok_or_else(|| DnsError::Decode("Truncated label".to_string()))?;
+                    labels.push(String::from_utf8_lossy(label).to_lowercase());
+                    pos += 1 + l;
+                }
+                _ => return Err(DnsError::Decode("Invalid label length".to_string())),
+            }
+        }
+
+        if !jumped {
+            self.pos = pos;
+        }
+        Ok(labels.join("."))
+    }
+
+    fn records(&mut self, count: u16) -> Result<Vec<ResourceRecord>, DnsError> {
+        let mut records = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let name = self.name()?;
+            let rtype = self.u16()?;
+            let class = self.u16()?;
+            let ttl = self.u32()?;
+            let len = self.u16()? as usize;
+            let rdata = self.bytes(len)?.to_vec();
+            records.push(ResourceRecord { name, rtype, class, ttl, rdata });
+        }
+        Ok(records)
+    }
+}
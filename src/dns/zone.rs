@@ -0,0 +1,271 @@
+// src/dns/zone.rs
+
+use std::sync::Arc;
+
+use super::wire::*;
+use super::DnsError;
+use crate::directory_service::DirectoryService;
+use crate::models::{DnsRecord, DnsRecordData, Domain, DomainControllerInfo};
+
+/// TTL генерируемых записей
+const DEFAULT_TTL: u32 = 600;
+
+/// SRV-записи, по которым клиенты AD ищут контроллеры: (префикс, порт)
+const DC_SERVICES: &[(&str, u16)] = &[
+    ("_ldap._tcp", 389),
+    ("_ldap._tcp.dc._msdcs", 389),
+    ("_kerberos._tcp", 88),
+    ("_kerberos._udp", 88),
+    ("_kerberos._tcp.dc._msdcs", 88),
+    ("_kpasswd._tcp", 464),
+    ("_kpasswd._udp", 464),
+    ("_gc._tcp", 3268),
+];
+
+/// Авторитетный сервер для зон доменов каталога
+pub struct DnsAuthority {
+    service: Arc<DirectoryService>,
+    dynamic_updates: bool,
+}
+
+impl DnsAuthority {
+    pub fn new(service: Arc<DirectoryService>, dynamic_updates: bool) -> Self {
+        Self { service, dynamic_updates }
+    }
+
+    /// Обработать разобранный запрос и сформировать ответ
+    pub async fn handle(&self, request: &Message) -> Message {
+        let mut reply = request.reply();
+        let result = match request.opcode() {
+            OPCODE_QUERY => self.query(request, &mut reply).await,
+            OPCODE_UPDATE => self.update(request).await,
+            _ => Ok(RCODE_NOTIMP),
+        };
+
+        match result {
+            Ok(rcode) => reply.set_rcode(rcode),
+            Err(e) => {
+                tracing::warn!(error = %e, "Ошибка обработки DNS-запроса");
+                reply = request.reply();
+                reply.set_rcode(RCODE_SERVFAIL);
+            }
+        }
+        reply
+    }
+
+    // ================= QUERY =================
+
+    async fn query(&self, request: &Message, reply: &mut Message) -> Result<u8, DnsError> {
+        let [question] = request.questions.as_slice() else {
+            return Ok(RCODE_FORMERR);
+        };
+        if question.qclass != CLASS_IN && question.qclass != CLASS_ANY {
+            return Ok(RCODE_REFUSED);
+        }
+        let Some(domain) = self.find_zone(&question.name).await? else {
+            return Ok(RCODE_REFUSED);
+        };
+        reply.set_authoritative();
+
+        let zone = self.zone_records(&domain).await?;
+        let records = self.records_at(&zone, &question.name).await?;
+        if records.is_empty() {
+            reply.authority.push(soa_record(&zone));
+            return Ok(RCODE_NXDOMAIN);
+        }
+
+        for record in records.iter().filter(|r| question.qtype == TYPE_ANY || record_type(&r.data) == question.qtype) {
+            reply.answers.push(ResourceRecord::from_data(&question.name, record.ttl, &record.data));
+
+            // Адреса целей SRV/NS, чтобы клиенту не нужен был второй запрос
+            if let DnsRecordData::Srv { target, .. } | DnsRecordData::Ns(target) = &record.data {
+                for glue in zone.iter().filter(|r| &r.name == target) {
+                    if matches!(glue.data, DnsRecordData::A(_) | DnsRecordData::Aaaa(_)) {
+                        reply.additional.push(ResourceRecord::from_data(target, glue.ttl, &glue.data));
+                    }
+                }
+            }
+        }
+
+        // Имя есть, но записей нужного типа нет (NODATA)
+        if reply.answers.is_empty() {
+            reply.authority.push(soa_record(&zone));
+        }
+        Ok(RCODE_NOERROR)
+    }
+
+    /// Самая длинная зона, которой принадлежит имя
+    async fn find_zone(&self, name: &str) -> Result<Option<Domain>, DnsError> {
+        let domains = self.service.get_all_domains().await?;
+        Ok(domains
+            .into_iter()
+            .filter(|d| d.enabled && in_zone(name, &zone_name(d)))
+            .max_by_key(|d| d.dns_name.len()))
+    }
+
+    /// Записи, генерируемые из домена и его зарегистрированных контроллеров
+    async fn zone_records(&self, domain: &Domain) -> Result<Vec<DnsRecord>, DnsError> {
+        let zone = zone_name(domain);
+        let dcs = self.service.get_domain_controllers(domain.id).await?;
+        let serial = self.service.get_dns_serial(&zone).await?;
+
+        let primary = dcs.first().map(|dc| dc.hostname.to_lowercase()).unwrap_or_else(|| zone.clone());
+        let mut records = vec![DnsRecord::new(&zone, DEFAULT_TTL, DnsRecordData::Soa {
+            mname: primary,
+            rname: format!("hostmaster.{}", zone),
+            serial,
+            refresh: 900,
+            retry: 600,
+            expire: 86400,
+            minimum: 3600,
+        })];
+
+        for dc in &dcs {
+            let host = dc.hostname.trim_end_matches('.').to_lowercase();
+            records.push(DnsRecord::new(&zone, DEFAULT_TTL, DnsRecordData::Ns(host.clone())));
+
+            // Адреса контроллера публикуются и на его имени, и на вершине зоны
+            for name in [&host, &zone] {
+                records.extend(dc_addresses(dc).map(|data| DnsRecord::new(name, DEFAULT_TTL, data)));
+            }
+
+            for (prefix, port) in DC_SERVICES {
+                records.push(DnsRecord::new(format!("{}.{}", prefix, zone), DEFAULT_TTL, DnsRecordData::Srv {
+                    priority: 0,
+                    weight: 100,
+                    port: *port,
+                    target: host.clone(),
+                }));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Все записи (генерируемые и динамические) на имени
+    async fn records_at(&self, zone: &[DnsRecord], name: &str) -> Result<Vec<DnsRecord>, DnsError> {
+        let mut records: Vec<DnsRecord> = zone.iter().filter(|r| r.name == name).cloned().collect();
+        records.extend(self.service.get_dns_records(name).await?);
+        Ok(records)
+    }
+
+    // ================= UPDATE (RFC 2136) =================
+
+    async fn update(&self, request: &Message) -> Result<u8, DnsError> {
+        if !self.dynamic_updates {
+            return Ok(RCODE_REFUSED);
+        }
+        let [zone_question] = request.questions.as_slice() else {
+            return Ok(RCODE_FORMERR);
+        };
+        if zone_question.qtype != TYPE_SOA {
+            return Ok(RCODE_FORMERR);
+        }
+        let Some(domain) = self.find_zone(&zone_question.name).await? else {
+            return Ok(RCODE_NOTAUTH);
+        };
+        let zone = zone_name(&domain);
+        if zone != zone_question.name {
+            return Ok(RCODE_NOTAUTH);
+        }
+        let zone_records = self.zone_records(&domain).await?;
+
+        // Секция prerequisite передаётся в answers
+        for prereq in &request.answers {
+            if !in_zone(&prereq.name, &zone) {
+                return Ok(RCODE_NOTZONE);
+            }
+            let existing = self.records_at(&zone_records, &prereq.name).await?;
+            let has_type = existing.iter().any(|r| record_type(&r.data) == prereq.rtype);
+            let rcode = match (prereq.class, prereq.rtype) {
+                (CLASS_ANY, TYPE_ANY) if existing.is_empty() => RCODE_NXDOMAIN,
+                (CLASS_NONE, TYPE_ANY) if !existing.is_empty() => RCODE_YXDOMAIN,
+                (CLASS_ANY, _) if prereq.rtype != TYPE_ANY && !has_type => RCODE_NXRRSET,
+                (CLASS_NONE, _) if prereq.rtype != TYPE_ANY && has_type => RCODE_YXRRSET,
+                (CLASS_ANY | CLASS_NONE, _) => RCODE_NOERROR,
+                (CLASS_IN, _) => {
+                    let matched = existing.iter()
+                        .any(|r| record_type(&r.data) == prereq.rtype && encode_rdata(&r.data) == prereq.rdata);
+                    if matched { RCODE_NOERROR } else { RCODE_NXRRSET }
+                }
+                _ => return Ok(RCODE_FORMERR),
+            };
+            if rcode != RCODE_NOERROR {
+                return Ok(rcode);
+            }
+        }
+
+        // Секция update передаётся в authority; сначала проверяем всё, потом применяем
+        for rr in &request.authority {
+            if !in_zone(&rr.name, &zone) {
+                return Ok(RCODE_NOTZONE);
+            }
+            let valid_type = matches!(rr.rtype, TYPE_A | TYPE_AAAA) || (rr.class == CLASS_ANY && rr.rtype == TYPE_ANY);
+            if !valid_type || !matches!(rr.class, CLASS_IN | CLASS_ANY | CLASS_NONE) {
+                return Ok(RCODE_REFUSED);
+            }
+            if zone_records.iter().any(|r| r.name == rr.name) {
+                // Записи контроллеров и вершины зоны управляются только каталогом
+                return Ok(RCODE_REFUSED);
+            }
+            if self.computer_account(&rr.name, &zone).await?.is_none() {
+                return Ok(RCODE_REFUSED);
+            }
+        }
+
+        for rr in &request.authority {
+            let owner = self.computer_account(&rr.name, &zone).await?;
+            let mut records = self.service.get_dns_records(&rr.name).await?;
+            match rr.class {
+                CLASS_IN => {
+                    let data = decode_address(rr.rtype, &rr.rdata)?;
+                    records.retain(|r| r.data != data);
+                    let mut record = DnsRecord::new(&rr.name, rr.ttl, data);
+                    record.owner = owner;
+                    records.push(record);
+                }
+                CLASS_ANY if rr.rtype == TYPE_ANY => records.clear(),
+                CLASS_ANY => records.retain(|r| record_type(&r.data) != rr.rtype),
+                _ => {
+                    let data = decode_address(rr.rtype, &rr.rdata)?;
+                    records.retain(|r| r.data != data);
+                }
+            }
+            self.service.set_dns_records(&zone, &rr.name, &records).await?;
+        }
+        Ok(RCODE_NOERROR)
+    }
+
+    /// Динамически обновлять можно только `<host>.<zone>`, где `<host>$` — учётная запись компьютера
+    async fn computer_account(&self, name: &str, zone: &str) -> Result<Option<uuid::Uuid>, DnsError> {
+        let Some(host) = name.strip_suffix(zone).and_then(|h| h.strip_suffix('.')) else {
+            return Ok(None);
+        };
+        if host.is_empty() || host.contains('.') {
+            return Ok(None);
+        }
+        for candidate in [format!("{}$", host), format!("{}$", host.to_uppercase())] {
+            if let Some(user) = self.service.find_user_by_username(&candidate).await? {
+                return Ok(Some(user.id));
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn zone_name(domain: &Domain) -> String {
+    domain.dns_name.trim_end_matches('.').to_lowercase()
+}
+
+fn in_zone(name: &str, zone: &str) -> bool {
+    name == zone || name.strip_suffix(zone).is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+fn soa_record(zone: &[DnsRecord]) -> ResourceRecord {
+    let soa = &zone[0];
+    ResourceRecord::from_data(&soa.name, soa.ttl, &soa.data)
+}
+
+fn dc_addresses(dc: &DomainControllerInfo) -> impl Iterator<Item = DnsRecordData> + '_ {
+    dc.ipv4_addresses.iter().map(|a| DnsRecordData::A(*a))
+        .chain(dc.ipv6_addresses.iter().map(|a| DnsRecordData::Aaaa(*a)))
+}
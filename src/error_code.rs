@@ -0,0 +1,163 @@
+// src/error_code.rs
+
+//! Стабильные коды ошибок: одинаковы в REST (`code` ответа problem+json) и в gRPC (`reason`
+//! `google.rpc.ErrorInfo`). Клиенты ветвятся по коду, а не по тексту сообщения: коды не меняются
+//! между версиями, новые только добавляются
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    // Ошибки каталога: код по умолчанию для каждого варианта `DirectoryError`
+    NotFound,
+    AlreadyExists,
+    InvalidInput,
+    AuthenticationFailed,
+    /// Пароль верен, но срок его действия истёк: клиент предлагает сменить пароль
+    PasswordExpired,
+    /// Новый пароль не отвечает парольной политике: длина, состав или история
+    PasswordPolicyViolation,
+    RateLimited,
+    ProtectedObject,
+    AccessDenied,
+    ReadOnlyReplica,
+    /// База открыта только для чтения (`--read-only`)
+    DatabaseReadOnly,
+    SizeLimitExceeded,
+    TimeLimitExceeded,
+    /// Операция не выполнена: создана заявка, её одобряет другой администратор (`security.approvals`)
+    ApprovalPending,
+    /// Вход отклонён: достигнут предел одновременных сессий (`security.sessions`)
+    SessionLimitExceeded,
+    /// Вход отклонён политикой риска до подтверждения вторым фактором (`security.risk`)
+    MfaRequired,
+    InternalError,
+
+    // Конфликты уникальности: что именно занято
+    UserAlreadyExists,
+    EmailAlreadyExists,
+    GroupAlreadyExists,
+    OuAlreadyExists,
+    ContactAlreadyExists,
+    SpnAlreadyRegistered,
+    SidAlreadyAssigned,
+    AlreadyMember,
+    RequestAlreadyPending,
+
+    // Ошибки транспорта: аутентификация запроса и отказы самого HTTP-сервера
+    MissingToken,
+    InvalidToken,
+    InvalidApiKey,
+    MissingScope,
+    InvalidRequest,
+    Unauthorized,
+    MethodNotAllowed,
+    Timeout,
+    Conflict,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    Unavailable,
+}
+
+impl ErrorCode {
+    /// Код в виде строки: `USER_ALREADY_EXISTS`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::AlreadyExists => "ALREADY_EXISTS",
+            ErrorCode::InvalidInput => "INVALID_INPUT",
+            ErrorCode::AuthenticationFailed => "AUTHENTICATION_FAILED",
+            ErrorCode::PasswordExpired => "PASSWORD_EXPIRED",
+            ErrorCode::PasswordPolicyViolation => "PASSWORD_POLICY_VIOLATION",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::ProtectedObject => "PROTECTED_OBJECT",
+            ErrorCode::AccessDenied => "ACCESS_DENIED",
+            ErrorCode::ReadOnlyReplica => "READ_ONLY_REPLICA",
+            ErrorCode::DatabaseReadOnly => "DATABASE_READ_ONLY",
+            ErrorCode::SizeLimitExceeded => "SIZE_LIMIT_EXCEEDED",
+            ErrorCode::TimeLimitExceeded => "TIME_LIMIT_EXCEEDED",
+            ErrorCode::ApprovalPending => "APPROVAL_PENDING",
+            ErrorCode::SessionLimitExceeded => "SESSION_LIMIT_EXCEEDED",
+            ErrorCode::MfaRequired => "MFA_REQUIRED",
+            ErrorCode::InternalError => "INTERNAL_ERROR",
+            ErrorCode::UserAlreadyExists => "USER_ALREADY_EXISTS",
+            ErrorCode::EmailAlreadyExists => "EMAIL_ALREADY_EXISTS",
+            ErrorCode::GroupAlreadyExists => "GROUP_ALREADY_EXISTS",
+            ErrorCode::OuAlreadyExists => "OU_ALREADY_EXISTS",
+            ErrorCode::ContactAlreadyExists => "CONTACT_ALREADY_EXISTS",
+            ErrorCode::SpnAlreadyRegistered => "SPN_ALREADY_REGISTERED",
+            ErrorCode::SidAlreadyAssigned => "SID_ALREADY_ASSIGNED",
+            ErrorCode::AlreadyMember => "ALREADY_MEMBER",
+            ErrorCode::RequestAlreadyPending => "REQUEST_ALREADY_PENDING",
+            ErrorCode::MissingToken => "MISSING_TOKEN",
+            ErrorCode::InvalidToken => "INVALID_TOKEN",
+            ErrorCode::InvalidApiKey => "INVALID_API_KEY",
+            ErrorCode::MissingScope => "MISSING_SCOPE",
+            ErrorCode::InvalidRequest => "INVALID_REQUEST",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::MethodNotAllowed => "METHOD_NOT_ALLOWED",
+            ErrorCode::Timeout => "TIMEOUT",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ErrorCode::UnsupportedMediaType => "UNSUPPORTED_MEDIA_TYPE",
+            ErrorCode::Unavailable => "UNAVAILABLE",
+        }
+    }
+
+    /// Краткое описание вида ошибки — `title` ответа problem+json
+    pub fn title(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "Object not found",
+            ErrorCode::AlreadyExists => "Object already exists",
+            ErrorCode::InvalidInput => "Invalid input",
+            ErrorCode::AuthenticationFailed => "Authentication failed",
+            ErrorCode::PasswordExpired => "Password expired",
+            ErrorCode::PasswordPolicyViolation => "Password does not meet the policy",
+            ErrorCode::RateLimited => "Too many attempts",
+            ErrorCode::ProtectedObject => "Protected object",
+            ErrorCode::AccessDenied => "Access denied",
+            ErrorCode::ReadOnlyReplica => "Read-only replica",
+            ErrorCode::DatabaseReadOnly => "Database opened read-only",
+            ErrorCode::SizeLimitExceeded => "Size limit exceeded",
+            ErrorCode::TimeLimitExceeded => "Time limit exceeded",
+            ErrorCode::ApprovalPending => "Approval pending",
+            ErrorCode::SessionLimitExceeded => "Session limit exceeded",
+            ErrorCode::MfaRequired => "MFA required",
+            ErrorCode::InternalError => "Internal error",
+            ErrorCode::UserAlreadyExists => "Username already taken",
+            ErrorCode::EmailAlreadyExists => "Email already in use",
+            ErrorCode::GroupAlreadyExists => "Group already exists",
+            ErrorCode::OuAlreadyExists => "Organizational unit already exists",
+            ErrorCode::ContactAlreadyExists => "Contact already exists",
+            ErrorCode::SpnAlreadyRegistered => "SPN already registered",
+            ErrorCode::SidAlreadyAssigned => "SID already assigned",
+            ErrorCode::AlreadyMember => "Already a member",
+            ErrorCode::RequestAlreadyPending => "Request already pending",
+            ErrorCode::MissingToken => "Missing token",
+            ErrorCode::InvalidToken => "Invalid token",
+            ErrorCode::InvalidApiKey => "Invalid API key",
+            ErrorCode::MissingScope => "Missing API key scope",
+            ErrorCode::InvalidRequest => "Invalid request",
+            ErrorCode::Unauthorized => "Unauthorized",
+            ErrorCode::MethodNotAllowed => "Method not allowed",
+            ErrorCode::Timeout => "Request timeout",
+            ErrorCode::Conflict => "Conflict",
+            ErrorCode::PayloadTooLarge => "Payload too large",
+            ErrorCode::UnsupportedMediaType => "Unsupported media type",
+            ErrorCode::Unavailable => "Service unavailable",
+        }
+    }
+
+    /// `type` ответа problem+json: URI вида ошибки, по одному на код
+    pub fn type_uri(self) -> String {
+        format!("urn:nextdomen:error:{}", self.as_str().to_lowercase().replace('_', "-"))
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -5,15 +5,80 @@ use uuid::Uuid;
 use chrono::Utc;
 use tokio::sync::broadcast;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Итог действия: изменение выполнено / вход удался или нет
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditResult {
+    Success,
+    Failure,
+}
+
+impl AuditResult {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditResult::Success => "success",
+            AuditResult::Failure => "failure",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, utoipa::ToSchema)]
 pub struct AuditEvent {
     pub id: Uuid,
     pub action: String,
     pub actor_id: Option<Uuid>,
     pub target_id: Option<Uuid>,
     pub ip_addr: Option<String>,
+    pub result: AuditResult,
     pub metadata: std::collections::HashMap<String, String>,
     pub timestamp: chrono::DateTime<Utc>,
+    /// Звено цепочки хэшей, если `security.audit.chain` включена
+    pub chain: Option<AuditChainLink>,
+}
+
+/// Место события в цепочке: хэш покрывает событие и хэш предыдущего звена
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, utoipa::ToSchema)]
+pub struct AuditChainLink {
+    pub seq: u64,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEvent {
+    pub fn new(action: impl Into<String>, result: AuditResult) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            action: action.into(),
+            actor_id: None,
+            target_id: None,
+            ip_addr: None,
+            result,
+            metadata: std::collections::HashMap::new(),
+            timestamp: Utc::now(),
+            chain: None,
+        }
+    }
+
+    /// SHA-256 звена `seq` после `prev_hash`; метаданные упорядочены, чтобы хэш не зависел от HashMap
+    pub fn chain_hash(&self, seq: u64, prev_hash: &str) -> String {
+        use sha2::{Digest, Sha256};
+
+        let metadata: std::collections::BTreeMap<_, _> = self.metadata.iter().collect();
+        let content = (
+            seq,
+            prev_hash,
+            self.id,
+            &self.action,
+            self.actor_id,
+            self.target_id,
+            &self.ip_addr,
+            self.result,
+            metadata,
+            self.timestamp,
+        );
+        let bytes = bincode::serialize(&content).expect("audit event is serializable");
+        hex::encode(Sha256::digest(&bytes))
+    }
 }
 
 pub struct EventHub {
@@ -49,8 +114,10 @@ macro_rules! audit_log {
                 actor_id: $actor,
                 target_id: $target,
                 ip_addr: $ip,
+                result: AuditResult::Success,
                 metadata: meta,
                 timestamp: Utc::now(),
+                chain: None,
             };
             $hub.emit(event);
         }
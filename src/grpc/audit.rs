@@ -0,0 +1,110 @@
+// src/grpc/audit.rs
+
+//! `audit_api`: поиск по журналу аудита и поток новых событий — Domain Admins.
+//! Фильтры те же, что у `GET /api/audit` и `/api/events/stream`.
+
+use std::pin::Pin;
+use std::sync::Arc;
+use futures_util::stream::{self, Stream};
+use tokio::sync::broadcast::error::RecvError;
+use tonic::{Request, Response, Status};
+
+use crate::audit::query::AuditSearchParams;
+use crate::directory_service::DirectoryService;
+use crate::events::AuditEvent;
+use super::audit_api::{self, audit_api_server::AuditApi};
+use super::auth::{self, Role};
+use super::status;
+
+#[derive(Clone)]
+pub struct AuditApiService {
+    service: Arc<DirectoryService>,
+}
+
+impl AuditApiService {
+    /// Сервис аудита; в сервер добавляется под `auth::interceptor`
+    pub fn new(service: Arc<DirectoryService>) -> Self {
+        Self { service }
+    }
+}
+
+impl From<AuditEvent> for audit_api::AuditEvent {
+    fn from(event: AuditEvent) -> Self {
+        Self {
+            id: event.id.to_string(),
+            action: event.action,
+            actor_id: event.actor_id.map(|id| id.to_string()).unwrap_or_default(),
+            target_id: event.target_id.map(|id| id.to_string()).unwrap_or_default(),
+            ip_address: event.ip_addr.unwrap_or_default(),
+            metadata: event.metadata,
+            timestamp: event.timestamp.timestamp(),
+            result: event.result.as_str().to_string(),
+        }
+    }
+}
+
+/// Действие подходит под один из шаблонов (`*` в конце — префикс); пустой список пропускает всё
+fn matches_actions(actions: &[String], action: &str) -> bool {
+    actions.is_empty() || actions.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => action.starts_with(prefix),
+        None => action == pattern,
+    })
+}
+
+#[tonic::async_trait]
+impl AuditApi for AuditApiService {
+    async fn query_events(
+        &self,
+        request: Request<audit_api::QueryEventsRequest>,
+    ) -> Result<Response<audit_api::QueryEventsResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        let params = AuditSearchParams {
+            actor: Some(req.actor),
+            target: Some(req.target),
+            action: Some(req.action),
+            result: Some(req.result),
+            from: Some(req.from),
+            to: Some(req.to),
+            page: req.page as usize,
+            per_page: req.per_page as usize,
+        };
+        let query = params.into_query(&self.service).await.map_err(status)?;
+        let page = self.service.search_audit(&query).await.map_err(status)?;
+
+        Ok(Response::new(audit_api::QueryEventsResponse {
+            events: page.events.into_iter().map(Into::into).collect(),
+            total: page.total as u64,
+            page: page.page as u32,
+            per_page: page.per_page as u32,
+        }))
+    }
+
+    type StreamEventsStream = Pin<Box<dyn Stream<Item = Result<audit_api::StreamEventsResponse, Status>> + Send>>;
+
+    async fn stream_events(
+        &self,
+        request: Request<audit_api::StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let actions = request.into_inner().actions;
+        let receiver = self.service.events().subscribe();
+
+        // Медленный подписчик не тормозит каталог: получает сообщение с числом пропущенных событий
+        let events = stream::unfold((receiver, actions), |(mut receiver, actions)| async move {
+            loop {
+                let message = match receiver.recv().await {
+                    Ok(event) if matches_actions(&actions, &event.action) => {
+                        audit_api::StreamEventsResponse { event: Some(event.into()), missed: 0 }
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => audit_api::StreamEventsResponse { event: None, missed },
+                    Err(RecvError::Closed) => return None,
+                };
+                return Some((Ok::<_, Status>(message), (receiver, actions)));
+            }
+        });
+
+        Ok(Response::new(Box::pin(events)))
+    }
+}
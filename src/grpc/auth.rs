@@ -0,0 +1,129 @@
+// src/grpc/auth.rs
+
+//! Аутентификация RPC: перехватчик проверяет JWT из метаданных `authorization`
+//! (тот же RS256, что и REST) и кладёт `Caller` в расширения запроса.
+//! Права проверяет сам метод через `authorize` — перехватчик не видит имени метода
+//! и не может обращаться к каталогу. Клиент mTLS должен предъявить сертификат той же учётной записи,
+//! что и токен (`tls::client_account`). Автора событий аудита задаёт `ActorLayer` вместе с `authorize`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tonic::codegen::http;
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+use tonic::{Request, Status};
+use uuid::Uuid;
+
+use crate::audit::actor::ActorContext;
+use crate::auth;
+use crate::directory_service::DirectoryService;
+use crate::models::{LoginProtocol, User};
+
+/// Вызывающий по проверенному токену
+#[derive(Debug, Clone, Copy)]
+pub struct Caller {
+    pub user_id: Uuid,
+    /// Сессия токена (`jti`); отозванную отклоняет `authorize`
+    pub session_id: Option<Uuid>,
+}
+
+/// Что нужно для вызова метода
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Любая включённая учётная запись
+    User,
+    /// Член Domain Admins
+    DomainAdmin,
+}
+
+/// Перехватчик для сервисов, закрытых токеном: `UserApiServer::with_interceptor(api, auth::interceptor)`
+#[allow(clippy::result_large_err)] // сигнатуру задаёт tonic
+pub fn interceptor(mut request: Request<()>) -> Result<Request<()>, Status> {
+    let token = request.metadata()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+
+    let claims = auth::validate_token(token).map_err(|_| Status::unauthenticated("Invalid or expired token"))?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| Status::unauthenticated("Invalid token subject"))?;
+
+    request.extensions_mut().insert(Caller { user_id, session_id: claims.session_id() });
+    Ok(request)
+}
+
+/// Проверить права вызывающего на метод; возвращает его учётную запись
+pub async fn authorize<T>(service: &DirectoryService, request: &Request<T>, role: Role) -> Result<User, Status> {
+    let caller = request.extensions().get::<Caller>()
+        .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+
+    if let Some(session_id) = caller.session_id
+        && service.is_session_revoked(session_id).await.map_err(|_| Status::internal("DB error"))?
+    {
+        return Err(Status::unauthenticated("Session revoked"));
+    }
+
+    let user = service.get_user(caller.user_id).await
+        .map_err(|_| Status::internal("DB error"))?
+        .filter(|user| user.enabled)
+        .ok_or_else(|| Status::unauthenticated("Account not found or disabled"))?;
+
+    // Клиент mTLS: сертификат должен принадлежать той же учётной записи, что и токен
+    if let Some(account) = super::tls::client_account(service, request).await?
+        && account.id != user.id
+    {
+        return Err(Status::permission_denied(format!("Client certificate belongs to {}, not to the token's account", account.username)));
+    }
+
+    if role == Role::DomainAdmin
+        && !service.is_domain_admin(user.id).await.map_err(|_| Status::internal("DB error"))?
+    {
+        return Err(Status::permission_denied("Administrator rights required"));
+    }
+    ActorContext::set_current_user(Some(&user));
+    Ok(user)
+}
+
+/// Слой сервера: вызов выполняется в `ActorContext` с адресом клиента, `authorize` дописывает вошедшего
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActorLayer;
+
+impl<S> tower::Layer<S> for ActorLayer {
+    type Service = ActorService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ActorService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActorService<S> {
+    inner: S,
+}
+
+impl<S, B> tower::Service<http::Request<B>> for ActorService<S>
+where
+    S: tower::Service<http::Request<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let extensions = request.extensions();
+        let ip_addr = extensions.get::<TcpConnectInfo>()
+            .or_else(|| extensions.get::<TlsConnectInfo<TcpConnectInfo>>().map(|info| info.get_ref()))
+            .and_then(|info| info.remote_addr())
+            .map(|addr| crate::net::client_ip(addr).to_string());
+        let device = request.headers()
+            .get(http::header::USER_AGENT)
+            .and_then(|agent| agent.to_str().ok())
+            .map(str::to_string);
+        Box::pin(ActorContext::new(LoginProtocol::Grpc, ip_addr).with_device(device).scope(self.inner.call(request)))
+    }
+}
@@ -0,0 +1,93 @@
+// src/grpc/change.rs
+
+//! `change_api`: журнал изменений по USN для внешних систем синхронизации — Domain Admins.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+use futures_util::stream::{self, Stream};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tonic::{Request, Response, Status};
+
+use crate::directory_service::DirectoryService;
+use crate::events::AuditEvent;
+use crate::models::ChangeEntry;
+use super::auth::{self, Role};
+use super::change_api::{self, change_api_server::ChangeApi};
+use super::status;
+
+/// Сколько изменений читается из журнала за раз
+const BATCH_SIZE: usize = 500;
+
+#[derive(Clone)]
+pub struct ChangeApiService {
+    pub(super) service: Arc<DirectoryService>,
+}
+
+impl From<ChangeEntry> for change_api::Change {
+    fn from(change: ChangeEntry) -> Self {
+        Self {
+            usn: change.usn,
+            object_id: change.object_id.to_string(),
+            object_type: change.object_type,
+            change_type: change.change_type.as_str().to_string(),
+            timestamp: change.timestamp.timestamp(),
+        }
+    }
+}
+
+/// Состояние потока: последний отданный USN и прочитанные, но ещё не отданные изменения
+struct Cursor {
+    service: Arc<DirectoryService>,
+    since: u64,
+    pending: VecDeque<ChangeEntry>,
+    /// Для `follow`: любое событие каталога — повод перечитать журнал
+    wakeup: Option<Receiver<AuditEvent>>,
+    failed: bool,
+}
+
+#[tonic::async_trait]
+impl ChangeApi for ChangeApiService {
+    type StreamChangesStream = Pin<Box<dyn Stream<Item = Result<change_api::Change, Status>> + Send>>;
+
+    async fn stream_changes(
+        &self,
+        request: Request<change_api::StreamChangesRequest>,
+    ) -> Result<Response<Self::StreamChangesStream>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        // Подписка до первого чтения: изменение между чтением и подпиской не потеряется
+        let wakeup = req.follow.then(|| self.service.events().subscribe());
+        let cursor = Cursor { service: self.service.clone(), since: req.since_usn, pending: VecDeque::new(), wakeup, failed: false };
+
+        let changes = stream::unfold(cursor, |mut cursor| async move {
+            loop {
+                if cursor.failed {
+                    return None;
+                }
+                if let Some(change) = cursor.pending.pop_front() {
+                    cursor.since = change.usn;
+                    return Some((Ok(change.into()), cursor));
+                }
+                match cursor.service.get_changes(cursor.since, BATCH_SIZE).await {
+                    Ok(changes) if !changes.is_empty() => {
+                        cursor.pending.extend(changes);
+                        continue;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        cursor.failed = true;
+                        return Some((Err(status(e)), cursor));
+                    }
+                }
+                let receiver = cursor.wakeup.as_mut()?;
+                match receiver.recv().await {
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(changes)))
+    }
+}
@@ -0,0 +1,150 @@
+// src/grpc/gpo.rs
+
+//! `gpo_api`: групповые политики, их привязка к OU и результирующий набор (RSoP).
+//! Чтение — любой вошедший пользователь, изменения — Domain Admins.
+
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::directory_service::DirectoryService;
+use crate::models::SensitiveOperation;
+use crate::models::policy::{GroupPolicy, PolicyTarget, PolicyType};
+use super::auth::{self, Role};
+use super::gpo_api::{self, gpo_api_server::GpoApi};
+use super::{parse_id, status, submit};
+
+#[derive(Clone)]
+pub struct GpoApiService {
+    pub(super) service: Arc<DirectoryService>,
+}
+
+impl From<GroupPolicy> for gpo_api::Gpo {
+    fn from(gpo: GroupPolicy) -> Self {
+        Self {
+            id: gpo.id.to_string(),
+            name: gpo.name,
+            display_name: gpo.display_name.unwrap_or_default(),
+            description: gpo.description.unwrap_or_default(),
+            version: gpo.version,
+            enabled: gpo.enabled,
+            enforced: gpo.enforced,
+            order: gpo.order,
+            linked_to: gpo.linked_to.iter().map(|id| id.to_string()).collect(),
+            created_at: gpo.created_at.timestamp(),
+            updated_at: gpo.updated_at.timestamp(),
+        }
+    }
+}
+
+fn gpo_list(gpos: Vec<GroupPolicy>) -> Response<gpo_api::ListGposResponse> {
+    Response::new(gpo_api::ListGposResponse {
+        gpos: gpos.into_iter().map(Into::into).collect(),
+    })
+}
+
+#[tonic::async_trait]
+impl GpoApi for GpoApiService {
+    async fn create_gpo(
+        &self,
+        request: Request<gpo_api::CreateGpoRequest>,
+    ) -> Result<Response<gpo_api::Gpo>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+
+        // Те же значения по умолчанию, что и у POST /api/gpos; привязка — отдельным LinkGpo
+        let gpo = GroupPolicy {
+            id: uuid::Uuid::new_v4(),
+            name: req.name,
+            display_name: req.display_name,
+            description: req.description,
+            linked_to: vec![],
+            enforced: req.enforced,
+            security_filtering: vec![],
+            order: 0,
+            version: 1,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            enabled: req.enabled,
+            policy_type: PolicyType::Custom("Custom".to_string()),
+            target: PolicyTarget::All,
+            settings: std::collections::HashMap::new(),
+            wmi_filter: None,
+        };
+
+        self.service.create_gpo(&gpo).await.map_err(status)?;
+        Ok(Response::new(gpo.into()))
+    }
+
+    async fn get_gpo(
+        &self,
+        request: Request<gpo_api::GetGpoRequest>,
+    ) -> Result<Response<gpo_api::Gpo>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
+        let id = request.into_inner().id;
+        let gpo = self.service.get_gpo(parse_id("GPO id", &id)?).await
+            .map_err(status)?
+            .ok_or_else(|| Status::not_found(format!("GPO not found: {}", id)))?;
+        Ok(Response::new(gpo.into()))
+    }
+
+    async fn list_gpos(
+        &self,
+        request: Request<gpo_api::ListGposRequest>,
+    ) -> Result<Response<gpo_api::ListGposResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
+        Ok(gpo_list(self.service.get_all_gpos().await.map_err(status)?))
+    }
+
+    async fn delete_gpo(
+        &self,
+        request: Request<gpo_api::DeleteGpoRequest>,
+    ) -> Result<Response<gpo_api::DeleteGpoResponse>, Status> {
+        let caller = auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let gpo_id = parse_id("GPO id", &request.into_inner().id)?;
+        submit(&self.service, &caller, SensitiveOperation::DeleteGpo { gpo_id }).await?;
+        Ok(Response::new(gpo_api::DeleteGpoResponse {}))
+    }
+
+    async fn link_gpo(
+        &self,
+        request: Request<gpo_api::LinkGpoRequest>,
+    ) -> Result<Response<gpo_api::LinkGpoResponse>, Status> {
+        let caller = auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        let operation = SensitiveOperation::LinkGpo { gpo_id: parse_id("GPO id", &req.gpo_id)?, ou_id: parse_id("OU id", &req.ou_id)? };
+        submit(&self.service, &caller, operation).await?;
+        Ok(Response::new(gpo_api::LinkGpoResponse {}))
+    }
+
+    async fn unlink_gpo(
+        &self,
+        request: Request<gpo_api::LinkGpoRequest>,
+    ) -> Result<Response<gpo_api::LinkGpoResponse>, Status> {
+        let caller = auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        let operation = SensitiveOperation::UnlinkGpo { gpo_id: parse_id("GPO id", &req.gpo_id)?, ou_id: parse_id("OU id", &req.ou_id)? };
+        submit(&self.service, &caller, operation).await?;
+        Ok(Response::new(gpo_api::LinkGpoResponse {}))
+    }
+
+    async fn get_effective_gpos_for_ou(
+        &self,
+        request: Request<gpo_api::EffectiveGposForOuRequest>,
+    ) -> Result<Response<gpo_api::ListGposResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
+        let ou_id = parse_id("OU id", &request.into_inner().ou_id)?;
+        Ok(gpo_list(self.service.get_effective_gpos_for_ou(ou_id).await.map_err(status)?))
+    }
+
+    async fn get_effective_gpos_for_user(
+        &self,
+        request: Request<gpo_api::EffectiveGposForUserRequest>,
+    ) -> Result<Response<gpo_api::ListGposResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
+        let username = request.into_inner().username;
+        let user = self.service.find_user_by_username(&username).await
+            .map_err(status)?
+            .ok_or_else(|| Status::not_found(format!("User not found: {}", username)))?;
+        Ok(gpo_list(self.service.get_effective_gpos_for_user(user.id).await.map_err(status)?))
+    }
+}
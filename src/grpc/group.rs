@@ -0,0 +1,164 @@
+// src/grpc/group.rs
+
+//! `group_api`: группы и членство. Чтение — любой вошедший пользователь, изменения — Domain Admins.
+
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::directory_service::DirectoryService;
+use crate::models::{Group, GroupScope, GroupTypeFlags, SensitiveOperation};
+use super::auth::{self, Role};
+use super::group_api::{self, group_api_server::GroupApi};
+use super::{status, submit};
+
+#[derive(Clone)]
+pub struct GroupApiService {
+    pub(super) service: Arc<DirectoryService>,
+}
+
+impl From<Group> for group_api::Group {
+    fn from(group: Group) -> Self {
+        let disabled = group.is_disabled();
+        let now = chrono::Utc::now();
+        let member_ttls = group.member_expirations.keys()
+            .filter_map(|id| group.member_ttl(*id, now).map(|ttl| (id.to_string(), ttl.num_seconds())))
+            .collect();
+        Self {
+            id: group.id.to_string(),
+            sid: group.sid.to_string(),
+            name: group.name,
+            sam_account_name: group.sam_account_name,
+            description: group.description.unwrap_or_default(),
+            scope: format!("{:?}", group.scope),
+            member_ids: group.members.iter().map(|id| id.to_string()).collect(),
+            created_at: group.created_at.timestamp(),
+            membership_rule: group.membership_rule.unwrap_or_default(),
+            managed_by: group.managed_by.map(|id| id.to_string()).unwrap_or_default(),
+            expires_at: group.expires_at.map(|at| at.timestamp()).unwrap_or_default(),
+            disabled,
+            member_ttls,
+        }
+    }
+}
+
+impl GroupApiService {
+    async fn group_by_sam(&self, sam: &str) -> Result<Group, Status> {
+        self.service.find_group_by_sam_account_name(sam).await
+            .map_err(status)?
+            .ok_or_else(|| Status::not_found(format!("Group not found: {}", sam)))
+    }
+
+    async fn user_id_by_name(&self, username: &str) -> Result<uuid::Uuid, Status> {
+        self.service.find_user_by_username(username).await
+            .map_err(status)?
+            .map(|user| user.id)
+            .ok_or_else(|| Status::not_found(format!("User not found: {}", username)))
+    }
+
+    /// Участник запроса: вложенная группа по `member_group`, иначе пользователь по `username`
+    async fn member_id(&self, req: &group_api::MemberRequest) -> Result<uuid::Uuid, Status> {
+        if req.member_group.is_empty() {
+            self.user_id_by_name(&req.username).await
+        } else {
+            Ok(self.group_by_sam(&req.member_group).await?.id)
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl GroupApi for GroupApiService {
+    async fn create_group(
+        &self,
+        request: Request<group_api::CreateGroupRequest>,
+    ) -> Result<Response<group_api::Group>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        if req.name.trim().is_empty() {
+            return Err(Status::invalid_argument("Group name cannot be empty"));
+        }
+
+        // Как и в REST: глобальная группа безопасности, sAMAccountName по умолчанию — имя в верхнем регистре
+        let sam = req.sam_account_name.unwrap_or_else(|| req.name.to_uppercase());
+        let mut group = Group::new(req.name, sam, uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+        group.description = req.description.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        group.membership_rule = req.membership_rule.filter(|rule| !rule.trim().is_empty());
+
+        self.service.create_group(&group).await.map_err(status)?;
+        let group = self.service.get_group(group.id).await.map_err(status)?.unwrap_or(group);
+        Ok(Response::new(group.into()))
+    }
+
+    async fn get_group(
+        &self,
+        request: Request<group_api::GetGroupRequest>,
+    ) -> Result<Response<group_api::Group>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
+        let group = self.group_by_sam(&request.into_inner().sam_account_name).await?;
+        Ok(Response::new(group.into()))
+    }
+
+    async fn list_groups(
+        &self,
+        request: Request<group_api::ListGroupsRequest>,
+    ) -> Result<Response<group_api::ListGroupsResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
+        let groups = self.service.get_all_groups().await.map_err(status)?;
+        Ok(Response::new(group_api::ListGroupsResponse {
+            groups: groups.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn delete_group(
+        &self,
+        request: Request<group_api::DeleteGroupRequest>,
+    ) -> Result<Response<group_api::DeleteGroupResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let group = self.group_by_sam(&request.into_inner().sam_account_name).await?;
+        self.service.delete_group(group.id).await.map_err(status)?;
+        Ok(Response::new(group_api::DeleteGroupResponse {}))
+    }
+
+    async fn add_member(
+        &self,
+        request: Request<group_api::MemberRequest>,
+    ) -> Result<Response<group_api::Group>, Status> {
+        let caller = auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        let group = self.group_by_sam(&req.sam_account_name).await?;
+        let member_id = self.member_id(&req).await?;
+
+        let ttl_secs = match req.ttl_seconds {
+            0 => None,
+            secs => Some(u64::try_from(secs).map_err(|_| Status::invalid_argument("ttl_seconds must not be negative"))?),
+        };
+        submit(&self.service, &caller, SensitiveOperation::AddGroupMember { group_id: group.id, member_id, ttl_secs }).await?;
+        let group = self.group_by_sam(&req.sam_account_name).await?;
+        Ok(Response::new(group.into()))
+    }
+
+    async fn remove_member(
+        &self,
+        request: Request<group_api::MemberRequest>,
+    ) -> Result<Response<group_api::Group>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        let group = self.group_by_sam(&req.sam_account_name).await?;
+        let member_id = self.member_id(&req).await?;
+
+        self.service.remove_member_from_group(group.id, member_id).await.map_err(status)?;
+        let group = self.group_by_sam(&req.sam_account_name).await?;
+        Ok(Response::new(group.into()))
+    }
+
+    async fn list_user_groups(
+        &self,
+        request: Request<group_api::ListUserGroupsRequest>,
+    ) -> Result<Response<group_api::ListGroupsResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
+        let user_id = self.user_id_by_name(&request.into_inner().username).await?;
+        let groups = self.service.find_groups_by_member(user_id).await.map_err(status)?;
+        Ok(Response::new(group_api::ListGroupsResponse {
+            groups: groups.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
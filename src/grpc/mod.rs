@@ -1,24 +1,27 @@
 // src/grpc/mod.rs
 
-pub mod gen; // Сгенерированный код
+pub mod auth;
+pub mod tls;
+pub mod group;
+pub mod ou;
+pub mod gpo;
+pub mod service_account;
+pub mod change;
+pub mod audit;
+pub mod organization;
 
 use tonic::{transport::Server, Request, Response, Status};
+use prost::Message;
+use std::pin::Pin;
 use std::sync::Arc;
-use jsonwebtoken::{encode, decode, EncodingKey, DecodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
+use futures_util::stream::{self, Stream};
+use tokio::sync::broadcast::error::RecvError;
 
-use crate::directory_service::DirectoryService;
-use crate::models::User;
-
-// === JWT Config ===
-
-static SECRET: &[u8] = b"your-super-secret-jwt-key-for-nextdomen"; // Замени на env var!
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String, // user_id
-    exp: usize,
-}
+use crate::config::ServerConfig;
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::error_code::ErrorCode;
+use crate::models::{LoginProtocol, SensitiveOperation, User, UserAccountControl};
+use auth::Role;
 
 // === Сервисы ===
 
@@ -30,57 +33,284 @@ pub mod auth_api {
     tonic::include_proto!("auth_api");
 }
 
+pub mod group_api {
+    tonic::include_proto!("group_api");
+}
+
+pub mod ou_api {
+    tonic::include_proto!("ou_api");
+}
+
+pub mod gpo_api {
+    tonic::include_proto!("gpo_api");
+}
+
+pub mod service_account_api {
+    tonic::include_proto!("service_account_api");
+}
+
+pub mod change_api {
+    tonic::include_proto!("change_api");
+}
+
+pub mod audit_api {
+    tonic::include_proto!("audit_api");
+}
+
+pub mod organization_api {
+    tonic::include_proto!("organization_api");
+}
+
+/// Домен `google.rpc.ErrorInfo` в сведениях об ошибке
+pub const ERROR_DOMAIN: &str = "nextdomen";
+
+/// `google.rpc.Status` и `google.rpc.ErrorInfo` — стандартная упаковка сведений об ошибке в
+/// `grpc-status-details-bin`, которую разбирают клиентские библиотеки gRPC
+pub mod rpc {
+    /// `type_url` упакованного в `Any` сообщения `ErrorInfo`
+    pub const ERROR_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.ErrorInfo";
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Status {
+        #[prost(int32, tag = "1")]
+        pub code: i32,
+        #[prost(string, tag = "2")]
+        pub message: String,
+        #[prost(message, repeated, tag = "3")]
+        pub details: Vec<prost_types::Any>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ErrorInfo {
+        /// Стабильный код ошибки (`ErrorCode`)
+        #[prost(string, tag = "1")]
+        pub reason: String,
+        #[prost(string, tag = "2")]
+        pub domain: String,
+        #[prost(map = "string, string", tag = "3")]
+        pub metadata: std::collections::HashMap<String, String>,
+    }
+}
+
+/// Статус gRPC со стабильным кодом ошибки в `ErrorInfo.reason`
+pub fn coded_status(code: tonic::Code, message: impl Into<String>, error_code: ErrorCode) -> Status {
+    let message = message.into();
+    let info = rpc::ErrorInfo {
+        reason: error_code.as_str().to_string(),
+        domain: ERROR_DOMAIN.to_string(),
+        metadata: Default::default(),
+    };
+    let details = rpc::Status {
+        code: code as i32,
+        message: message.clone(),
+        details: vec![prost_types::Any { type_url: rpc::ERROR_INFO_TYPE_URL.to_string(), value: info.encode_to_vec() }],
+    };
+    Status::with_details(code, message, details.encode_to_vec().into())
+}
+
+/// Стабильный код ошибки из сведений статуса; `None` — статус без `ErrorInfo`
+pub fn error_code(status: &Status) -> Option<String> {
+    let details = rpc::Status::decode(status.details()).ok()?;
+    details.details.iter()
+        .filter(|any| any.type_url == rpc::ERROR_INFO_TYPE_URL)
+        .find_map(|any| rpc::ErrorInfo::decode(any.value.as_slice()).ok())
+        .map(|info| info.reason)
+}
+
+/// Ошибка каталога → статус gRPC: тот же смысл, что и у HTTP-кодов REST, и тот же код ошибки
+pub fn status(e: DirectoryError) -> Status {
+    let code = match &e {
+        DirectoryError::NotFound(_) => tonic::Code::NotFound,
+        DirectoryError::AlreadyExists(..) => tonic::Code::AlreadyExists,
+        DirectoryError::InvalidInput(_) | DirectoryError::PasswordPolicy(_) => tonic::Code::InvalidArgument,
+        DirectoryError::AuthenticationFailed(_) | DirectoryError::MfaRequired(_) => tonic::Code::Unauthenticated,
+        DirectoryError::PasswordExpired(_) | DirectoryError::ReadOnly(_) | DirectoryError::DatabaseReadOnly => {
+            tonic::Code::FailedPrecondition
+        }
+        DirectoryError::RateLimited(_) | DirectoryError::SizeLimitExceeded(_) | DirectoryError::SessionLimitExceeded(_) => {
+            tonic::Code::ResourceExhausted
+        }
+        DirectoryError::Protected(_) | DirectoryError::AccessDenied(_) => tonic::Code::PermissionDenied,
+        DirectoryError::TimeLimitExceeded(_) => tonic::Code::DeadlineExceeded,
+        DirectoryError::DbError(_) | DirectoryError::Serialization(_) => tonic::Code::Internal,
+    };
+    let message = match &e {
+        DirectoryError::NotFound(msg)
+        | DirectoryError::AlreadyExists(_, msg)
+        | DirectoryError::InvalidInput(msg)
+        | DirectoryError::PasswordPolicy(msg)
+        | DirectoryError::AuthenticationFailed(msg)
+        | DirectoryError::Protected(msg)
+        | DirectoryError::AccessDenied(msg) => msg.clone(),
+        _ => e.to_string(),
+    };
+    coded_status(code, message, e.code())
+}
+
+/// Выполнить чувствительную операцию от имени `caller`; если она ждёт одобрения
+/// (`security.approvals`) — `FAILED_PRECONDITION` с кодом `APPROVAL_PENDING` и номером заявки
+pub(crate) async fn submit(service: &DirectoryService, caller: &User, operation: SensitiveOperation) -> Result<(), Status> {
+    match service.submit_operation(operation, caller.id).await.map_err(status)? {
+        None => Ok(()),
+        Some(request) => Err(coded_status(
+            tonic::Code::FailedPrecondition,
+            format!("Operation '{}' is waiting for approval: request {}", request.summary, request.id),
+            ErrorCode::ApprovalPending,
+        )),
+    }
+}
+
+/// Идентификатор объекта из строки запроса
+#[allow(clippy::result_large_err)] // Status — тип ошибки tonic
+pub(crate) fn parse_id(field: &str, value: &str) -> Result<uuid::Uuid, Status> {
+    uuid::Uuid::parse_str(value).map_err(|_| Status::invalid_argument(format!("Invalid {}: {}", field, value)))
+}
+
 // === User API ===
 
+/// Поля, которые UpdateUser меняет по update_mask
+const UPDATABLE_FIELDS: &[&str] = &["username", "email", "display_name", "given_name", "surname"];
+
+/// Размер страницы ListUsers, если клиент его не указал, и его предел
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// События аудита об изменении пользователей, которые отдаёт WatchUsers
+const USER_ACTIONS: &[&str] = &[
+    "create_user",
+    "update_user",
+    "rename_user",
+    "delete_user",
+    "enable_user",
+    "disable_user",
+    "update_user_account_control",
+    "unlock_user",
+    "move_user",
+    "set_password",
+    "register_spn",
+    "remove_spn",
+];
+
 #[derive(Clone)]
 pub struct UserApiService {
     service: Arc<DirectoryService>,
 }
 
+impl From<User> for user_api::GetUserResponse {
+    fn from(user: User) -> Self {
+        let locked_out = user.is_locked_out();
+        let account_control = user.account_control();
+        Self {
+            id: user.id.to_string(),
+            username: user.username,
+            email: user.email.unwrap_or_default(),
+            display_name: user.display_name.unwrap_or_default(),
+            created_at: user.created_at.timestamp(),
+            enabled: user.enabled,
+            locked_out,
+            user_account_control: account_control.bits(),
+            account_flags: account_control.flag_names(),
+        }
+    }
+}
+
+impl UserApiService {
+    /// Сервис пользователей; в сервер добавляется под `auth::interceptor`
+    pub fn new(service: Arc<DirectoryService>) -> Self {
+        Self { service }
+    }
+
+    async fn user_by_name(&self, username: &str) -> Result<User, Status> {
+        self.service.find_user_by_username(username).await
+            .map_err(status)?
+            .ok_or_else(|| Status::not_found(format!("User not found: {}", username)))
+    }
+}
+
 #[tonic::async_trait]
 impl user_api::user_api_server::UserApi for UserApiService {
     async fn get_user(
         &self,
         request: Request<user_api::GetUserRequest>,
     ) -> Result<Response<user_api::GetUserResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
         let username = &request.into_inner().username;
         let user = self.service.find_user_by_username(username)
             .await
             .map_err(|_| Status::internal("DB error"))?
             .ok_or(Status::not_found("User not found"))?;
 
-        Ok(Response::new(user_api::GetUserResponse {
-            id: user.id.to_string(),
-            username: user.username,
-            email: user.email.unwrap_or_default(),
-            display_name: user.display_name.unwrap_or_default(),
-            created_at: user.created_at.timestamp(),
-        }))
+        Ok(Response::new(user.into()))
     }
 
+    type ListUsersStream = Pin<Box<dyn Stream<Item = Result<user_api::ListUsersResponse, Status>> + Send>>;
+
     async fn list_users(
         &self,
-        _request: Request<user_api::ListUsersRequest>,
-    ) -> Result<Response<user_api::ListUsersResponse>, Status> {
+        request: Request<user_api::ListUsersRequest>,
+    ) -> Result<Response<Self::ListUsersStream>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
+        let page_size = match request.into_inner().page_size {
+            0 => DEFAULT_PAGE_SIZE,
+            size => (size as usize).min(MAX_PAGE_SIZE),
+        };
         let users = self.service.get_all_users().await
             .map_err(|_| Status::internal("DB error"))?;
 
-        let responses: Vec<_> = users.into_iter().map(|u| user_api::GetUserResponse {
-            id: u.id.to_string(),
-            username: u.username,
-            email: u.email.unwrap_or_default(),
-            display_name: u.display_name.unwrap_or_default(),
-            created_at: u.created_at.timestamp(),
+        // Каждая страница — отдельное сообщение: ответ не упирается в лимит размера сообщения
+        let pages: Vec<_> = users.chunks(page_size).map(|chunk| user_api::ListUsersResponse {
+            users: chunk.iter().cloned().map(Into::into).collect(),
         }).collect();
 
-        Ok(Response::new(user_api::ListUsersResponse { users: responses }))
+        Ok(Response::new(Box::pin(stream::iter(pages.into_iter().map(Ok)))))
+    }
+
+    type WatchUsersStream = Pin<Box<dyn Stream<Item = Result<user_api::UserEvent, Status>> + Send>>;
+
+    async fn watch_users(
+        &self,
+        request: Request<user_api::WatchUsersRequest>,
+    ) -> Result<Response<Self::WatchUsersStream>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let actions = request.into_inner().actions;
+        if let Some(action) = actions.iter().find(|a| !USER_ACTIONS.contains(&a.as_str())) {
+            return Err(Status::invalid_argument(format!("Unknown user action: {}", action)));
+        }
+        let receiver = self.service.events().subscribe();
+
+        // Медленный подписчик не тормозит каталог: получает сообщение с числом пропущенных событий
+        let events = stream::unfold((receiver, actions), |(mut receiver, actions)| async move {
+            loop {
+                let message = match receiver.recv().await {
+                    Ok(event) if USER_ACTIONS.contains(&event.action.as_str())
+                        && (actions.is_empty() || actions.contains(&event.action)) =>
+                    {
+                        user_api::UserEvent {
+                            action: event.action,
+                            user_id: event.target_id.map(|id| id.to_string()).unwrap_or_default(),
+                            details: event.metadata.get("details").cloned().unwrap_or_default(),
+                            timestamp: event.timestamp.timestamp(),
+                            missed: 0,
+                        }
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => user_api::UserEvent { missed, ..Default::default() },
+                    Err(RecvError::Closed) => return None,
+                };
+                return Some((Ok::<_, Status>(message), (receiver, actions)));
+            }
+        });
+
+        Ok(Response::new(Box::pin(events)))
     }
 
     async fn create_user(
         &self,
         request: Request<user_api::CreateUserRequest>,
     ) -> Result<Response<user_api::CreateUserResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
         let req = request.into_inner();
+        crate::models::validate_username(&req.username).map_err(Status::invalid_argument)?;
         use crate::models::{SecurityIdentifier, PasswordHash, PasswordAlgorithm};
 
         let user = User {
@@ -88,8 +318,8 @@ impl user_api::user_api_server::UserApi for UserApiService {
             sid: SecurityIdentifier::new_nt_authority(1001),
             username: req.username.clone(),
             user_principal_name: format!("{}@corp.acme.com", req.username),
-            email: req.email.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
-            display_name: req.display_name.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
+            email: Some(req.email.trim().to_string()).filter(|s| !s.is_empty()),
+            display_name: Some(req.display_name.trim().to_string()).filter(|s| !s.is_empty()),
             given_name: None,
             surname: None,
             password_hash: PasswordHash {
@@ -114,15 +344,121 @@ impl user_api::user_api_server::UserApi for UserApiService {
             script_path: None,
             meta: std::collections::HashMap::new(),
             primary_group_id: Some(513),
+            service_principal_names: vec![],
+            kerberos_keys: vec![],
+            user_account_control: UserAccountControl::NORMAL_ACCOUNT,
+            totp_secret: None,
+            totp_pending_secret: None,
         };
 
-        self.service.create_user(&user).await
-            .map_err(|_| Status::internal("Failed to create user"))?;
+        self.service.create_user(&user).await.map_err(status)?;
 
         Ok(Response::new(user_api::CreateUserResponse {
             id: user.id.to_string(),
         }))
     }
+
+    async fn update_user(
+        &self,
+        request: Request<user_api::UpdateUserRequest>,
+    ) -> Result<Response<user_api::GetUserResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        let fields = req.user.unwrap_or_default();
+        let paths = req.update_mask.map(|mask| mask.paths).unwrap_or_default();
+        if paths.is_empty() {
+            return Err(Status::invalid_argument("update_mask is required"));
+        }
+        if let Some(path) = paths.iter().find(|p| !UPDATABLE_FIELDS.contains(&p.as_str())) {
+            return Err(Status::invalid_argument(format!("Field cannot be updated: {}", path)));
+        }
+
+        let mut user = self.user_by_name(&req.username).await?;
+        let masked = |field: &str| paths.iter().any(|p| p == field);
+        let optional = |value: String| Some(value.trim().to_string()).filter(|s| !s.is_empty());
+
+        // Переименование переносит индекс имени, поэтому идёт отдельной операцией
+        if masked("username") && fields.username != user.username {
+            if fields.username.trim().is_empty() {
+                return Err(Status::invalid_argument("Username cannot be empty"));
+            }
+            self.service.rename_user(user.id, Some(fields.username.clone()), None).await.map_err(status)?;
+            user = self.user_by_name(&fields.username).await?;
+        }
+        if masked("email") {
+            user.email = optional(fields.email);
+        }
+        if masked("display_name") {
+            user.display_name = optional(fields.display_name);
+        }
+        if masked("given_name") {
+            user.given_name = optional(fields.given_name);
+        }
+        if masked("surname") {
+            user.surname = optional(fields.surname);
+        }
+
+        user.updated_at = chrono::Utc::now();
+        self.service.update_user(&user).await.map_err(status)?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn delete_user(
+        &self,
+        request: Request<user_api::DeleteUserRequest>,
+    ) -> Result<Response<user_api::DeleteUserResponse>, Status> {
+        let caller = auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let user = self.user_by_name(&request.into_inner().username).await?;
+        submit(&self.service, &caller, SensitiveOperation::DeleteUser { user_id: user.id }).await?;
+        Ok(Response::new(user_api::DeleteUserResponse {}))
+    }
+
+    async fn enable_user(
+        &self,
+        request: Request<user_api::EnableUserRequest>,
+    ) -> Result<Response<user_api::GetUserResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let user = self.user_by_name(&request.into_inner().username).await?;
+        let user = self.service.set_user_enabled(user.id, true).await.map_err(status)?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn disable_user(
+        &self,
+        request: Request<user_api::EnableUserRequest>,
+    ) -> Result<Response<user_api::GetUserResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let user = self.user_by_name(&request.into_inner().username).await?;
+        let user = self.service.set_user_enabled(user.id, false).await.map_err(status)?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn set_user_account_control(
+        &self,
+        request: Request<user_api::SetUserAccountControlRequest>,
+    ) -> Result<Response<user_api::GetUserResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        let set = UserAccountControl::from_flag_names(&req.set).map_err(Status::invalid_argument)?;
+        let clear = UserAccountControl::from_flag_names(&req.clear).map_err(Status::invalid_argument)?;
+        let user = self.user_by_name(&req.username).await?;
+        let user = self.service.update_user_account_control(user.id, set, clear).await.map_err(status)?;
+        Ok(Response::new(user.into()))
+    }
+
+    async fn set_password(
+        &self,
+        request: Request<user_api::SetPasswordRequest>,
+    ) -> Result<Response<user_api::SetPasswordResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        if req.password.is_empty() {
+            return Err(Status::invalid_argument("Password cannot be empty"));
+        }
+        let user = self.user_by_name(&req.username).await?;
+        self.service.set_password(user.id, &req.password).await.map_err(status)?;
+        Ok(Response::new(user_api::SetPasswordResponse {}))
+    }
 }
 
 // === Auth API ===
@@ -133,42 +469,37 @@ pub struct AuthService {
 }
 
 #[tonic::async_trait]
-impl auth_api::auth_api_server::AuthService for AuthService {
+impl auth_api::auth_service_server::AuthService for AuthService {
     async fn login(
         &self,
         request: Request<auth_api::LoginRequest>,
     ) -> Result<Response<auth_api::LoginResponse>, Status> {
+        let peer = request.remote_addr().map(|addr| crate::net::client_ip(addr).to_string());
+        let device = request.metadata().get("user-agent").and_then(|agent| agent.to_str().ok()).map(str::to_string);
         let req = request.into_inner();
-        let user = self.service.find_user_by_username(&req.username)
+        // Проверка пароля, блокировка, лимит попыток и второй фактор — в authenticate_with_mfa
+        let mfa_code = Some(req.mfa_code.as_str()).filter(|code| !code.is_empty());
+        let user = self.service.authenticate_with_mfa(&req.username, &req.password, mfa_code, peer.clone(), LoginProtocol::Grpc)
             .await
-            .map_err(|_| Status::internal("DB error"))?
-            .ok_or(Status::unauthenticated("Invalid credentials"))?;
-
-        // В реальности: проверь пароль
-        // Здесь: заглушка
-        if req.password != "password" {
-            return Err(Status::unauthenticated("Invalid credentials"));
-        }
-
-        let expiration = chrono::Utc::now()
-            .checked_add_signed(chrono::Duration::hours(24))
-            .expect("Valid timestamp")
-            .timestamp() as usize;
-
-        let claims = Claims {
-            sub: user.id.to_string(),
-            exp: expiration,
-        };
-
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(SECRET),
-        ).map_err(|_| Status::internal("JWT encode error"))?;
+            .map_err(|e| match e {
+                DirectoryError::AuthenticationFailed(_) => {
+                    coded_status(tonic::Code::Unauthenticated, "Invalid credentials", ErrorCode::AuthenticationFailed)
+                }
+                DirectoryError::PasswordExpired(_)
+                | DirectoryError::RateLimited(_)
+                | DirectoryError::MfaRequired(_)
+                | DirectoryError::AccessDenied(_) => status(e),
+                _ => Status::internal("DB error"),
+            })?;
+
+        let session = self.service.open_session(user.id, LoginProtocol::Grpc, peer, device).await.map_err(status)?;
+        // Тот же RS256-токен, что и у REST: его принимает auth::interceptor
+        let token = crate::auth::generate_token(&session)
+            .map_err(|_| Status::internal("JWT encode error"))?;
 
         Ok(Response::new(auth_api::LoginResponse {
             token,
-            expires_at: expiration as i64,
+            expires_at: session.expires_at.timestamp(),
             user_id: user.id.to_string(),
         }))
     }
@@ -179,33 +510,125 @@ impl auth_api::auth_api_server::AuthService for AuthService {
     ) -> Result<Response<auth_api::ValidateTokenResponse>, Status> {
         let token = &request.into_inner().token;
 
-        match decode::<Claims>(token, &DecodingKey::from_secret(SECRET), &Validation::default()) {
-            Ok(_) => {
-                // В реальности: получи user_id из токена
-                Ok(Response::new(auth_api::ValidateTokenResponse {
-                    valid: true,
-                    user_id: "unknown".to_string(), // Добавь из claims.sub
-                }))
-            }
-            Err(_) => Ok(Response::new(auth_api::ValidateTokenResponse {
-                valid: false,
-                user_id: "".to_string(),
-            })),
+        let response = match self.token_info(token).await? {
+            Some(info) => auth_api::ValidateTokenResponse {
+                valid: true,
+                user_id: info.user.id.to_string(),
+                username: info.user.username,
+                expires_at: info.claims.exp as i64,
+                roles: info.roles,
+                group_sids: info.group_sids,
+            },
+            None => auth_api::ValidateTokenResponse::default(),
+        };
+        Ok(Response::new(response))
+    }
+
+    async fn introspect(
+        &self,
+        request: Request<auth_api::IntrospectRequest>,
+    ) -> Result<Response<auth_api::IntrospectResponse>, Status> {
+        let token = &request.into_inner().token;
+
+        // RFC 7662: о недействительном токене сообщается только active = false
+        let response = match self.token_info(token).await? {
+            Some(info) => auth_api::IntrospectResponse {
+                active: true,
+                sub: info.claims.sub,
+                username: info.user.username,
+                token_type: "Bearer".to_string(),
+                exp: info.claims.exp as i64,
+                iat: info.claims.iat as i64,
+                scope: info.roles.join(" "),
+                group_sids: info.group_sids,
+            },
+            None => auth_api::IntrospectResponse::default(),
+        };
+        Ok(Response::new(response))
+    }
+}
+
+/// Проверенный токен и то, что по нему известно о пользователе
+struct TokenInfo {
+    claims: crate::auth::Claims,
+    user: User,
+    roles: Vec<String>,
+    group_sids: Vec<String>,
+}
+
+impl AuthService {
+    /// Сервис входа и проверки токенов; в сервер добавляется без перехватчика
+    pub fn new(service: Arc<DirectoryService>) -> Self {
+        Self { service }
+    }
+
+    /// `None` — подпись или срок не прошли проверку, сессия отозвана, либо учётная запись удалена или отключена
+    async fn token_info(&self, token: &str) -> Result<Option<TokenInfo>, Status> {
+        let Ok(claims) = crate::auth::validate_token(token) else {
+            return Ok(None);
+        };
+        if let Some(session_id) = claims.session_id()
+            && self.service.is_session_revoked(session_id).await.map_err(status)?
+        {
+            return Ok(None);
         }
+        let Ok(user_id) = uuid::Uuid::parse_str(&claims.sub) else {
+            return Ok(None);
+        };
+        let Some(user) = self.service.get_user(user_id).await.map_err(status)?.filter(|user| user.enabled) else {
+            return Ok(None);
+        };
+
+        let mut roles = vec!["user".to_string()];
+        if self.service.is_domain_admin(user.id).await.map_err(status)? {
+            roles.push("domain_admin".to_string());
+        }
+        let group_sids = self.service.find_groups_by_member(user.id).await.map_err(status)?
+            .into_iter()
+            .map(|group| group.sid.to_string())
+            .collect();
+
+        Ok(Some(TokenInfo { claims, user, roles, group_sids }))
     }
 }
 
 // === Запуск сервера ===
 
-pub async fn run_grpc_server(service: Arc<DirectoryService>, addr: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let addr = addr.parse()?;
-    let user_api = user_api::user_api_server::UserApiServer::new(UserApiService { service: service.clone() });
-    let auth_api = auth_api::auth_api_server::AuthServiceServer::new(AuthService { service });
-
-    Server::builder()
+/// gRPC по `grpc_server`: при `enable_tls` — TLS, а с `tls.ca_cert_file` — и проверка клиентов (mTLS)
+pub async fn run_grpc_server(service: Arc<DirectoryService>, addrs: &[String], config: &ServerConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // По сокету на адрес (`0.0.0.0:50051` и `[::]:50051`), соединения всех сокетов — одному серверу
+    let mut listeners = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        let listener = crate::net::bind_tcp(addr).await?;
+        tracing::info!(addr = %listener.local_addr()?, tls = config.enable_tls, mtls = config.tls.ca_cert_file.is_some(), "gRPC запущен");
+        listeners.push(tokio_stream::wrappers::TcpListenerStream::new(listener));
+    }
+    // Login, ValidateToken и Introspect открыты, остальные сервисы — только с токеном
+    let user_api = user_api::user_api_server::UserApiServer::with_interceptor(UserApiService::new(service.clone()), auth::interceptor);
+    let group_api = group_api::group_api_server::GroupApiServer::with_interceptor(group::GroupApiService { service: service.clone() }, auth::interceptor);
+    let ou_api = ou_api::ou_api_server::OuApiServer::with_interceptor(ou::OuApiService { service: service.clone() }, auth::interceptor);
+    let gpo_api = gpo_api::gpo_api_server::GpoApiServer::with_interceptor(gpo::GpoApiService { service: service.clone() }, auth::interceptor);
+    let service_account_api = service_account_api::service_account_api_server::ServiceAccountApiServer::with_interceptor(service_account::ServiceAccountApiService { service: service.clone() }, auth::interceptor);
+    let change_api = change_api::change_api_server::ChangeApiServer::with_interceptor(change::ChangeApiService { service: service.clone() }, auth::interceptor);
+    let audit_api = audit_api::audit_api_server::AuditApiServer::with_interceptor(audit::AuditApiService::new(service.clone()), auth::interceptor);
+    let organization_api = organization_api::organization_api_server::OrganizationApiServer::with_interceptor(organization::OrganizationApiService { service: service.clone() }, auth::interceptor);
+    let auth_api = auth_api::auth_service_server::AuthServiceServer::new(AuthService::new(service));
+
+    let mut builder = Server::builder().layer(auth::ActorLayer).layer(tls::SanAccountsLayer::new(&config.tls));
+    if config.enable_tls {
+        builder = builder.tls_config(tls::server_tls_config(&config.tls)?)?;
+    }
+    builder
         .add_service(user_api)
+        .add_service(group_api)
+        .add_service(ou_api)
+        .add_service(gpo_api)
+        .add_service(service_account_api)
+        .add_service(change_api)
+        .add_service(audit_api)
+        .add_service(organization_api)
         .add_service(auth_api)
-        .serve(addr)
+        .serve_with_incoming(stream::select_all(listeners))
         .await?;
 
     Ok(())
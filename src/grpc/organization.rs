@@ -0,0 +1,224 @@
+// src/grpc/organization.rs
+
+//! `organization_api`: организации (арендаторы) и их домены. Создание, удаление и домены —
+//! Domain Admins, чтение и изменение реквизитов — ещё и администраторы самой организации.
+
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::directory_service::DirectoryService;
+use crate::models::{Organization, OrganizationAddress, User};
+use super::auth::{self, Role};
+use super::organization_api::{self, organization_api_server::OrganizationApi};
+use super::{parse_id, status};
+
+/// Поля, которые UpdateOrganization меняет по update_mask
+const UPDATABLE_FIELDS: &[&str] = &["name", "legal_name", "tax_id", "phone", "email", "website", "address", "admins"];
+
+#[derive(Clone)]
+pub struct OrganizationApiService {
+    pub(super) service: Arc<DirectoryService>,
+}
+
+impl From<OrganizationAddress> for organization_api::Address {
+    fn from(address: OrganizationAddress) -> Self {
+        Self {
+            street: address.street,
+            city: address.city,
+            state: address.state,
+            postal_code: address.postal_code,
+            country: address.country,
+        }
+    }
+}
+
+impl From<organization_api::Address> for OrganizationAddress {
+    fn from(address: organization_api::Address) -> Self {
+        Self {
+            street: address.street.trim().to_string(),
+            city: address.city.trim().to_string(),
+            state: address.state.trim().to_string(),
+            postal_code: address.postal_code.trim().to_string(),
+            country: address.country.trim().to_uppercase(),
+        }
+    }
+}
+
+impl From<Organization> for organization_api::Organization {
+    fn from(organization: Organization) -> Self {
+        Self {
+            id: organization.id.to_string(),
+            name: organization.name,
+            legal_name: organization.legal_name,
+            tax_id: organization.tax_id,
+            phone: organization.phone,
+            email: organization.email,
+            website: organization.website,
+            address: Some(organization.address.into()),
+            domain_ids: organization.domains.iter().map(|id| id.to_string()).collect(),
+            default_domain_id: organization.default_domain_id.map(|id| id.to_string()).unwrap_or_default(),
+            admin_ids: organization.admins.iter().map(|id| id.to_string()).collect(),
+            created_at: organization.created_at.timestamp(),
+            updated_at: organization.updated_at.timestamp(),
+        }
+    }
+}
+
+impl OrganizationApiService {
+    async fn organization_by_id(&self, id: &str) -> Result<Organization, Status> {
+        self.service.get_organization(parse_id("organization id", id)?).await
+            .map_err(status)?
+            .ok_or_else(|| Status::not_found(format!("Organization not found: {}", id)))
+    }
+
+    /// Организация, если вызывающий — её администратор или Domain Admin
+    async fn administered(&self, caller: &User, id: &str) -> Result<Organization, Status> {
+        let organization = self.organization_by_id(id).await?;
+        if !self.service.is_organization_admin(&organization, caller.id).await.map_err(status)? {
+            return Err(Status::permission_denied("Organization administrator rights required"));
+        }
+        Ok(organization)
+    }
+
+    /// UUID администраторов по UUID или именам; учётные записи должны существовать
+    async fn resolve_admins(&self, admins: &[String]) -> Result<Vec<Uuid>, Status> {
+        let mut ids = Vec::new();
+        for admin in admins.iter().map(|admin| admin.trim()).filter(|admin| !admin.is_empty()) {
+            let id = self.service.resolve_user_ref(admin).await.map_err(status)?;
+            if self.service.get_user(id).await.map_err(status)?.is_none() {
+                return Err(Status::not_found(format!("User not found: {}", admin)));
+            }
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    async fn domain_id(&self, dns_name: &str) -> Result<Uuid, Status> {
+        Ok(self.service.find_domain_by_dns_name(dns_name).await
+            .map_err(status)?
+            .ok_or_else(|| Status::not_found(format!("Domain not found: {}", dns_name)))?
+            .id)
+    }
+}
+
+#[tonic::async_trait]
+impl OrganizationApi for OrganizationApiService {
+    async fn create_organization(
+        &self,
+        request: Request<organization_api::CreateOrganizationRequest>,
+    ) -> Result<Response<organization_api::Organization>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let fields = request.into_inner().organization.unwrap_or_default();
+
+        let mut organization = Organization::new(fields.name.trim().to_string());
+        organization.legal_name = fields.legal_name.trim().to_string();
+        organization.tax_id = fields.tax_id.trim().to_string();
+        organization.phone = fields.phone.trim().to_string();
+        organization.email = fields.email.trim().to_string();
+        organization.website = fields.website.trim().to_string();
+        organization.address = fields.address.map(Into::into).unwrap_or_default();
+        organization.admins = self.resolve_admins(&fields.admins).await?;
+        self.service.create_organization(&organization).await.map_err(status)?;
+        Ok(Response::new(organization.into()))
+    }
+
+    async fn get_organization(
+        &self,
+        request: Request<organization_api::GetOrganizationRequest>,
+    ) -> Result<Response<organization_api::Organization>, Status> {
+        let caller = auth::authorize(&self.service, &request, Role::User).await?;
+        let organization = self.administered(&caller, &request.into_inner().id).await?;
+        Ok(Response::new(organization.into()))
+    }
+
+    async fn list_organizations(
+        &self,
+        request: Request<organization_api::ListOrganizationsRequest>,
+    ) -> Result<Response<organization_api::ListOrganizationsResponse>, Status> {
+        let caller = auth::authorize(&self.service, &request, Role::User).await?;
+        let mut organizations = self.service.list_organizations().await.map_err(status)?;
+        if !self.service.is_domain_admin(caller.id).await.map_err(status)? {
+            organizations.retain(|organization| organization.admins.contains(&caller.id));
+        }
+        Ok(Response::new(organization_api::ListOrganizationsResponse {
+            organizations: organizations.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn update_organization(
+        &self,
+        request: Request<organization_api::UpdateOrganizationRequest>,
+    ) -> Result<Response<organization_api::Organization>, Status> {
+        let caller = auth::authorize(&self.service, &request, Role::User).await?;
+        let req = request.into_inner();
+        let fields = req.organization.unwrap_or_default();
+        let paths = req.update_mask.map(|mask| mask.paths).unwrap_or_default();
+        if paths.is_empty() {
+            return Err(Status::invalid_argument("update_mask is required"));
+        }
+        if let Some(path) = paths.iter().find(|p| !UPDATABLE_FIELDS.contains(&p.as_str())) {
+            return Err(Status::invalid_argument(format!("Field cannot be updated: {}", path)));
+        }
+
+        let mut organization = self.administered(&caller, &req.id).await?;
+        let masked = |field: &str| paths.iter().any(|p| p == field);
+        for (field, value, target) in [
+            ("name", fields.name, &mut organization.name),
+            ("legal_name", fields.legal_name, &mut organization.legal_name),
+            ("tax_id", fields.tax_id, &mut organization.tax_id),
+            ("phone", fields.phone, &mut organization.phone),
+            ("email", fields.email, &mut organization.email),
+            ("website", fields.website, &mut organization.website),
+        ] {
+            if masked(field) {
+                *target = value.trim().to_string();
+            }
+        }
+        if masked("address") {
+            organization.address = fields.address.map(Into::into).unwrap_or_default();
+        }
+        if masked("admins") {
+            organization.admins = self.resolve_admins(&fields.admins).await?;
+        }
+
+        let organization = self.service.update_organization(&organization).await.map_err(status)?;
+        Ok(Response::new(organization.into()))
+    }
+
+    async fn delete_organization(
+        &self,
+        request: Request<organization_api::DeleteOrganizationRequest>,
+    ) -> Result<Response<organization_api::DeleteOrganizationResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let id = parse_id("organization id", &request.into_inner().id)?;
+        self.service.delete_organization(id).await.map_err(status)?;
+        Ok(Response::new(organization_api::DeleteOrganizationResponse {}))
+    }
+
+    async fn attach_domain(
+        &self,
+        request: Request<organization_api::AttachDomainRequest>,
+    ) -> Result<Response<organization_api::Organization>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        let domain_id = self.domain_id(&req.domain).await?;
+        let organization = self.service.attach_domain(parse_id("organization id", &req.id)?, domain_id, req.make_default).await
+            .map_err(status)?;
+        Ok(Response::new(organization.into()))
+    }
+
+    async fn detach_domain(
+        &self,
+        request: Request<organization_api::DetachDomainRequest>,
+    ) -> Result<Response<organization_api::Organization>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        let domain_id = self.domain_id(&req.domain).await?;
+        let organization = self.service.detach_domain(parse_id("organization id", &req.id)?, domain_id).await
+            .map_err(status)?;
+        Ok(Response::new(organization.into()))
+    }
+}
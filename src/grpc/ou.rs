@@ -0,0 +1,140 @@
+// src/grpc/ou.rs
+
+//! `ou_api`: организационные подразделения и флаги наследования политик.
+//! Чтение — любой вошедший пользователь, изменения — Domain Admins.
+
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::directory_service::DirectoryService;
+use crate::models::{OrganizationalUnit, OuContents};
+use super::auth::{self, Role};
+use super::ou_api::{self, ou_api_server::OuApi};
+use super::{parse_id, status};
+
+#[derive(Clone)]
+pub struct OuApiService {
+    pub(super) service: Arc<DirectoryService>,
+}
+
+impl From<OrganizationalUnit> for ou_api::Ou {
+    fn from(ou: OrganizationalUnit) -> Self {
+        Self {
+            id: ou.id.to_string(),
+            name: ou.name,
+            dn: ou.dn,
+            parent_id: ou.parent.map(|id| id.to_string()).unwrap_or_default(),
+            linked_gpo_ids: ou.linked_gpos.iter().map(|id| id.to_string()).collect(),
+            block_inheritance: ou.block_inheritance,
+            enforced: ou.enforced,
+            created_at: ou.created_at.timestamp(),
+            updated_at: ou.updated_at.timestamp(),
+        }
+    }
+}
+
+impl OuApiService {
+    async fn ou_by_id(&self, id: &str) -> Result<OrganizationalUnit, Status> {
+        self.service.get_ou(parse_id("OU id", id)?).await
+            .map_err(status)?
+            .ok_or_else(|| Status::not_found(format!("OU not found: {}", id)))
+    }
+}
+
+#[tonic::async_trait]
+impl OuApi for OuApiService {
+    async fn create_ou(
+        &self,
+        request: Request<ou_api::CreateOuRequest>,
+    ) -> Result<Response<ou_api::Ou>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        if req.name.trim().is_empty() {
+            return Err(Status::invalid_argument("OU name cannot be empty"));
+        }
+
+        // Родитель должен существовать: от него наследуются политики
+        let parent = match req.parent_dn.as_deref().filter(|dn| !dn.is_empty()) {
+            Some(parent_dn) => Some(
+                self.service.find_ou_by_dn(parent_dn).await
+                    .map_err(status)?
+                    .ok_or_else(|| Status::not_found(format!("Parent OU not found: {}", parent_dn)))?
+                    .id,
+            ),
+            None => None,
+        };
+        let dn = DirectoryService::generate_ou_dn(&req.name, req.parent_dn.as_deref().filter(|dn| !dn.is_empty()))
+            .map_err(status)?;
+        if self.service.find_ou_by_dn(&dn).await.map_err(status)?.is_some() {
+            return Err(Status::already_exists(format!("OU already exists: {}", dn)));
+        }
+
+        let ou = OrganizationalUnit::new(req.name, dn, parent);
+        self.service.create_ou(&ou).await.map_err(status)?;
+        Ok(Response::new(ou.into()))
+    }
+
+    async fn get_ou(
+        &self,
+        request: Request<ou_api::GetOuRequest>,
+    ) -> Result<Response<ou_api::Ou>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
+        let req = request.into_inner();
+        let ou = if !req.id.is_empty() {
+            self.ou_by_id(&req.id).await?
+        } else if !req.dn.is_empty() {
+            self.service.find_ou_by_dn(&req.dn).await
+                .map_err(status)?
+                .ok_or_else(|| Status::not_found(format!("OU not found: {}", req.dn)))?
+        } else {
+            return Err(Status::invalid_argument("Either id or dn is required"));
+        };
+        Ok(Response::new(ou.into()))
+    }
+
+    async fn list_ous(
+        &self,
+        request: Request<ou_api::ListOusRequest>,
+    ) -> Result<Response<ou_api::ListOusResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::User).await?;
+        let ous = self.service.get_all_ous().await.map_err(status)?;
+        Ok(Response::new(ou_api::ListOusResponse {
+            ous: ous.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn delete_ou(
+        &self,
+        request: Request<ou_api::DeleteOuRequest>,
+    ) -> Result<Response<ou_api::DeleteOuResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        let contents = match req.contents() {
+            ou_api::delete_ou_request::Contents::Refuse => OuContents::Refuse,
+            ou_api::delete_ou_request::Contents::MoveToParent => OuContents::MoveToParent,
+            ou_api::delete_ou_request::Contents::Delete => OuContents::Delete,
+        };
+        self.service.delete_ou_tree(parse_id("OU id", &req.id)?, req.recursive, contents).await.map_err(status)?;
+        Ok(Response::new(ou_api::DeleteOuResponse {}))
+    }
+
+    async fn set_block_inheritance(
+        &self,
+        request: Request<ou_api::SetBlockInheritanceRequest>,
+    ) -> Result<Response<ou_api::Ou>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        self.service.set_block_inheritance(parse_id("OU id", &req.id)?, req.block).await.map_err(status)?;
+        Ok(Response::new(self.ou_by_id(&req.id).await?.into()))
+    }
+
+    async fn set_enforced(
+        &self,
+        request: Request<ou_api::SetEnforcedRequest>,
+    ) -> Result<Response<ou_api::Ou>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let req = request.into_inner();
+        self.service.set_gpo_enforced(parse_id("OU id", &req.id)?, req.enforced).await.map_err(status)?;
+        Ok(Response::new(self.ou_by_id(&req.id).await?.into()))
+    }
+}
@@ -0,0 +1,63 @@
+// src/grpc/service_account.rs
+
+//! `service_account_api`: выдача пароля управляемой учётной записи службы её участникам
+//! (`allowed_principals`) и внеплановая смена пароля — Domain Admins.
+
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::directory_service::DirectoryService;
+use crate::models::ServiceAccount;
+use super::auth::{self, Role};
+use super::service_account_api::{self, service_account_api_server::ServiceAccountApi};
+use super::status;
+
+#[derive(Clone)]
+pub struct ServiceAccountApiService {
+    pub(super) service: Arc<DirectoryService>,
+}
+
+impl ServiceAccountApiService {
+    async fn account_by_username(&self, username: &str) -> Result<ServiceAccount, Status> {
+        let not_found = || Status::not_found(format!("Service account not found: {}", username));
+        let user = self.service.find_user_by_username(username).await
+            .map_err(status)?
+            .ok_or_else(not_found)?;
+        self.service.get_service_account(user.id).await
+            .map_err(status)?
+            .ok_or_else(not_found)
+    }
+}
+
+#[tonic::async_trait]
+impl ServiceAccountApi for ServiceAccountApiService {
+    async fn get_managed_password(
+        &self,
+        request: Request<service_account_api::GetManagedPasswordRequest>,
+    ) -> Result<Response<service_account_api::ManagedPassword>, Status> {
+        let caller = auth::authorize(&self.service, &request, Role::User).await?;
+        let account = self.account_by_username(&request.get_ref().username).await?;
+
+        let password = self.service.retrieve_managed_password(&caller, account.id).await.map_err(status)?;
+        Ok(Response::new(service_account_api::ManagedPassword {
+            current_password: password.current_password,
+            previous_password: password.previous_password,
+            password_changed_at: password.password_changed_at.timestamp(),
+            next_rotation: password.next_rotation.timestamp(),
+        }))
+    }
+
+    async fn rotate_password(
+        &self,
+        request: Request<service_account_api::RotatePasswordRequest>,
+    ) -> Result<Response<service_account_api::RotatePasswordResponse>, Status> {
+        auth::authorize(&self.service, &request, Role::DomainAdmin).await?;
+        let account = self.account_by_username(&request.get_ref().username).await?;
+
+        let account = self.service.rotate_service_account_password(account.id).await.map_err(status)?;
+        Ok(Response::new(service_account_api::RotatePasswordResponse {
+            password_changed_at: account.password_changed_at.timestamp(),
+            next_rotation: account.next_rotation().timestamp(),
+        }))
+    }
+}
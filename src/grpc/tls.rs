@@ -0,0 +1,147 @@
+// src/grpc/tls.rs
+
+//! TLS для gRPC по `grpc_server.tls`: сертификат сервера и, если задан `ca_cert_file`,
+//! проверка клиентских сертификатов (mTLS). Клиент сопоставляется с учётной записью каталога
+//! по SAN своего сертификата.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tonic::codegen::http;
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+use tonic::{Request, Status};
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::config::TlsConfig;
+use crate::directory_service::DirectoryService;
+use crate::models::User;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Настройки TLS сервера; без `ca_cert_file` клиентские сертификаты не запрашиваются
+pub fn server_tls_config(tls: &TlsConfig) -> Result<ServerTlsConfig, BoxError> {
+    let cert_file = tls.cert_file.as_deref().ok_or("grpc_server.tls.cert_file is required when enable_tls is set")?;
+    let key_file = tls.key_file.as_deref().ok_or("grpc_server.tls.key_file is required when enable_tls is set")?;
+    let cert = std::fs::read(cert_file).map_err(|e| format!("Cannot read {}: {}", cert_file, e))?;
+    let key = std::fs::read(key_file).map_err(|e| format!("Cannot read {}: {}", key_file, e))?;
+
+    let mut config = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+    match tls.ca_cert_file.as_deref() {
+        Some(ca_file) => {
+            let ca = std::fs::read(ca_file).map_err(|e| format!("Cannot read {}: {}", ca_file, e))?;
+            config = config
+                .client_ca_root(Certificate::from_pem(ca))
+                .client_auth_optional(!tls.client_auth_required);
+        }
+        None if tls.client_auth_required => {
+            return Err("grpc_server.tls.client_auth_required needs ca_cert_file".into());
+        }
+        None => {}
+    }
+    Ok(config)
+}
+
+/// SAN клиентского сертификата: DNS-имена, URI (SPIFFE) и адреса почты
+pub fn peer_sans<T>(request: &Request<T>) -> Vec<String> {
+    let Some(certs) = request.peer_certs() else {
+        return Vec::new();
+    };
+    // Первый сертификат цепочки — сертификат самого клиента; tonic хранит его в DER
+    let Some(leaf) = certs.first() else {
+        return Vec::new();
+    };
+    let Ok((_, cert)) = X509Certificate::from_der(leaf.get_ref()) else {
+        return Vec::new();
+    };
+    let Ok(Some(san)) = cert.subject_alternative_name() else {
+        return Vec::new();
+    };
+
+    san.value.general_names.iter()
+        .filter_map(|name| match name {
+            GeneralName::DNSName(dns) => Some(dns.to_string()),
+            GeneralName::URI(uri) => Some(uri.to_string()),
+            GeneralName::RFC822Name(email) => Some(email.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `san_accounts` в расширениях запроса: по ним `auth::authorize` сверяет клиента mTLS
+#[derive(Debug, Clone, Default)]
+pub struct SanAccounts(pub Arc<HashMap<String, String>>);
+
+/// Слой сервера: кладёт `SanAccounts` в расширения каждого запроса
+#[derive(Debug, Clone)]
+pub struct SanAccountsLayer {
+    accounts: SanAccounts,
+}
+
+impl SanAccountsLayer {
+    pub fn new(tls: &TlsConfig) -> Self {
+        Self { accounts: SanAccounts(Arc::new(tls.san_accounts.clone())) }
+    }
+}
+
+impl<S> tower::Layer<S> for SanAccountsLayer {
+    type Service = SanAccountsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SanAccountsService { inner, accounts: self.accounts.clone() }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SanAccountsService<S> {
+    inner: S,
+    accounts: SanAccounts,
+}
+
+impl<S, B> tower::Service<http::Request<B>> for SanAccountsService<S>
+where
+    S: tower::Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        request.extensions_mut().insert(self.accounts.clone());
+        self.inner.call(request)
+    }
+}
+
+/// Учётная запись клиента mTLS: сначала `san_accounts` (SAN → имя пользователя),
+/// затем учётная запись с SPN `HOST/<DNS-имя из SAN>`. `None` — клиент без сертификата;
+/// сертификат, ни один SAN которого не сопоставлен, отклоняется
+pub async fn client_account<T>(service: &DirectoryService, request: &Request<T>) -> Result<Option<User>, Status> {
+    let internal = |e: crate::directory_service::DirectoryError| Status::internal(e.to_string());
+    if request.peer_certs().is_none_or(|certs| certs.is_empty()) {
+        return Ok(None);
+    }
+    let sans = peer_sans(request);
+    let accounts = request.extensions().get::<SanAccounts>().cloned().unwrap_or_default();
+
+    for san in &sans {
+        let user = match accounts.0.get(san) {
+            Some(username) => service.find_user_by_username(username).await.map_err(internal)?,
+            None if !san.contains(':') && !san.contains('@') => {
+                service.find_user_by_spn(&format!("HOST/{}", san)).await.map_err(internal)?
+            }
+            None => None,
+        };
+        if let Some(user) = user {
+            if !user.enabled {
+                return Err(Status::permission_denied(format!("Account for {} is disabled", san)));
+            }
+            return Ok(Some(user));
+        }
+    }
+    Err(Status::unauthenticated(format!("Client certificate is not mapped to an account (SAN: {})", sans.join(", "))))
+}
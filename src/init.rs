@@ -0,0 +1,155 @@
+// src/init.rs
+
+//! Первый запуск (`init`) и генерация ключей (`keys generate`): мастер-ключ базы,
+//! пара RS256 для JWT, стартовый `config.yaml`, домен и первый администратор.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+
+use crate::directory_service::DirectoryService;
+use crate::models::DomainController;
+use crate::raddb::RadDB;
+
+type InitResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+pub const JWT_PRIVATE_KEY_FILE: &str = "jwt-private.pem";
+pub const JWT_PUBLIC_KEY_FILE: &str = "jwt-public.pem";
+const JWT_KEY_BITS: usize = 2048;
+
+/// Параметры `init`
+pub struct InitOptions {
+    /// Куда записать конфигурацию; ключи JWT кладутся в `keys/` рядом с ней
+    pub config_path: PathBuf,
+    pub db_path: String,
+    /// DNS-имя домена (`corp.acme.com`)
+    pub domain: String,
+    pub admin: String,
+    /// Перезаписать существующие конфигурацию и ключи JWT
+    pub force: bool,
+}
+
+/// Новый мастер-ключ базы в hex — значение `master_key_hex`
+pub fn generate_master_key_hex() -> String {
+    hex::encode(RadDB::generate_key())
+}
+
+/// Создать пару ключей RS256 для JWT в `dir`; возвращает пути к закрытому и открытому ключу
+pub fn write_jwt_keys(dir: &Path, force: bool) -> InitResult<(PathBuf, PathBuf)> {
+    let private_path = dir.join(JWT_PRIVATE_KEY_FILE);
+    let public_path = dir.join(JWT_PUBLIC_KEY_FILE);
+    refuse_overwrite(&private_path, force)?;
+    refuse_overwrite(&public_path, force)?;
+
+    let private_key = RsaPrivateKey::new(&mut rand::rngs::OsRng, JWT_KEY_BITS)?;
+    let public_key = RsaPublicKey::from(&private_key);
+
+    fs::create_dir_all(dir)?;
+    write_secret(&private_path, private_key.to_pkcs8_pem(LineEnding::LF)?.as_bytes())?;
+    fs::write(&public_path, public_key.to_public_key_pem(LineEnding::LF)?)?;
+    Ok((private_path, public_path))
+}
+
+/// Инициализировать новую установку и вывести дальнейшие шаги
+pub async fn run_init(options: InitOptions) -> InitResult<()> {
+    refuse_overwrite(&options.config_path, options.force)?;
+    if Path::new(&options.db_path).exists() {
+        return Err(format!("Database {} already exists; remove it or choose another --db-path", options.db_path).into());
+    }
+    let dns_name = options.domain.trim_end_matches('.').to_lowercase();
+    if !dns_name.contains('.') {
+        return Err(format!("Invalid domain '{}': expected a DNS name like corp.acme.com", options.domain).into());
+    }
+
+    // Пароль спрашиваем до записи файлов, чтобы отказ не оставил половину установки
+    let password = prompt_new_password(&options.admin)?;
+
+    let base_dir = match options.config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let (private_key_path, public_key_path) = write_jwt_keys(&base_dir.join("keys"), options.force)?;
+    println!("🔑 Ключи JWT: {}, {}", private_key_path.display(), public_key_path.display());
+
+    let master_key = RadDB::generate_key();
+    if let Some(dir) = Path::new(&options.db_path).parent() && !dir.as_os_str().is_empty() {
+        fs::create_dir_all(dir)?;
+    }
+    let service = Arc::new(DirectoryService::open(&options.db_path, &master_key)?);
+
+    let controller = DomainController::new(Arc::clone(&service));
+    let domain = controller.bootstrap_domain(dns_name.clone(), dns_name.clone()).await?;
+    println!("✅ Домен создан: {} ({})", domain.dns_name, domain.dn());
+
+    let admin = controller.create_administrator(&domain, &options.admin).await?;
+    service.set_password(admin.id, &password).await?;
+    println!("✅ Администратор создан: {} (Domain Admins)", admin.user_principal_name);
+
+    write_config(&options.config_path, &options.db_path, &hex::encode(master_key))?;
+    println!("✅ Конфигурация записана: {}", options.config_path.display());
+
+    println!();
+    println!("Дальнейшие шаги:");
+    println!("  1. Сохраните master_key_hex из {} в надёжном месте: без него базу не открыть", options.config_path.display());
+    println!("  2. export JWT_PRIVATE_KEY_PATH={}", private_key_path.display());
+    println!("     export JWT_PUBLIC_KEY_PATH={}", public_key_path.display());
+    println!("  3. nextDomen --config {} web --addr 0.0.0.0:8080", options.config_path.display());
+    println!("  4. nextDomen --config {} cli — управление каталогом", options.config_path.display());
+    Ok(())
+}
+
+fn write_config(path: &Path, db_path: &str, master_key_hex: &str) -> InitResult<()> {
+    let mut config = serde_yaml::Mapping::new();
+    config.insert("db_path".into(), db_path.into());
+    config.insert("master_key_hex".into(), master_key_hex.into());
+
+    let content = format!("# {} — создан `nextDomen init`\n{}", path.display(), serde_yaml::to_string(&config)?);
+    Ok(write_secret(path, content.as_bytes())?)
+}
+
+fn refuse_overwrite(path: &Path, force: bool) -> InitResult<()> {
+    if path.exists() && !force {
+        return Err(format!("{} already exists; use --force to overwrite", path.display()).into());
+    }
+    Ok(())
+}
+
+/// Записать файл с ключом, доступный только владельцу
+pub fn write_secret(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content)
+}
+
+/// Запросить новый пароль дважды, не показывая ввод
+pub fn prompt_new_password(username: &str) -> InitResult<String> {
+    let password = rpassword::prompt_password(format!("Пароль для {}: ", username))?;
+    if password.is_empty() {
+        return Err("Password must not be empty".into());
+    }
+    if rpassword::prompt_password("Повторите пароль: ")? != password {
+        return Err("Passwords do not match".into());
+    }
+    Ok(password)
+}
+
+/// Пароль из первой строки stdin, без перевода строки: `printf '%s\n' "$PASS" | ... --password-stdin`
+pub fn read_password_stdin() -> InitResult<String> {
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let password = line.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        return Err("Password must not be empty".into());
+    }
+    Ok(password.to_string())
+}
@@ -0,0 +1,405 @@
+// src/jobs.rs
+
+//! Фоновые задачи процесса `web`: реестр задач с расписанием (cron по UTC или `@every`),
+//! история выполнений в каталоге и событие `job_run` на каждое выполнение. Расписание
+//! встроенной задачи переопределяется в секции `jobs` конфигурации; `nextDomen jobs list`
+//! показывает задачи, `nextDomen jobs run <имя>` выполняет задачу сразу.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, DurationRound, Timelike, Utc};
+use futures_util::future::{join_all, BoxFuture};
+use serde::Serialize;
+
+use crate::config::{AppConfig, JobConfig};
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::models::{JobRun, JobTrigger};
+
+#[derive(Debug)]
+pub enum JobError {
+    UnknownJob(String),
+    InvalidSchedule(String),
+    /// Задача меняет каталог, а он — реплика или база только для чтения
+    ReadOnly(String),
+    Directory(DirectoryError),
+}
+
+impl From<DirectoryError> for JobError {
+    fn from(e: DirectoryError) -> Self {
+        JobError::Directory(e)
+    }
+}
+
+impl std::fmt::Display for JobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobError::UnknownJob(name) => write!(f, "Unknown job: {}", name),
+            JobError::InvalidSchedule(e) => write!(f, "Invalid schedule: {}", e),
+            JobError::ReadOnly(name) => write!(f, "Job {} changes the directory and cannot run on a read-only replica or database", name),
+            JobError::Directory(e) => write!(f, "Directory error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for JobError {}
+
+/// Расписание задачи
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// `@every 10m`: при запуске процесса и затем через равные промежутки
+    Every(Duration),
+    /// Пять полей cron (минута, час, день месяца, месяц, день недели) по UTC
+    Cron(CronSchedule),
+}
+
+/// Поля cron как битовые маски допустимых значений
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// День месяца или день недели ограничен не `*`: тогда подходит любой из двух, как в cron
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl Schedule {
+    /// `*/15 * * * *`, `0 3 * * 1-5`, `@hourly`, `@daily`, `@weekly`, `@monthly`, `@yearly`
+    /// или `@every 30s|10m|2h|1d`
+    pub fn parse(expression: &str) -> Result<Self, JobError> {
+        let expression = expression.trim();
+        let cron = match expression {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            _ => match expression.strip_prefix("@every") {
+                Some(every) => return parse_every(every.trim()).map(Schedule::Every),
+                None => expression,
+            },
+        };
+        let fields: Vec<&str> = cron.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(JobError::InvalidSchedule(format!("'{}': expected 5 cron fields or @every <duration>", expression)));
+        };
+        // Воскресенье — и 0, и 7
+        let weekdays = parse_field(weekdays, 0, 7)?;
+        Ok(Schedule::Cron(CronSchedule {
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days: parse_field(days, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            weekdays: (weekdays | weekdays >> 7) & 0x7F,
+            days_restricted: days != "*",
+            weekdays_restricted: fields[4] != "*",
+        }))
+    }
+
+    /// Первое выполнение после `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Every(every) => Some(after + ChronoDuration::from_std(*every).ok()?),
+            Schedule::Cron(cron) => cron.next_after(after),
+        }
+    }
+}
+
+impl CronSchedule {
+    fn matches_day(&self, time: DateTime<Utc>) -> bool {
+        let day = bit(self.days, time.day());
+        let weekday = bit(self.weekdays, time.weekday().num_days_from_sunday());
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.duration_trunc(ChronoDuration::minutes(1)).ok()? + ChronoDuration::minutes(1);
+        // Невыполнимое расписание (`0 0 30 2 *`) ищется не дальше нескольких лет
+        let limit = after + ChronoDuration::days(366 * 5);
+        while time <= limit {
+            if !bit(self.months, time.month()) || !self.matches_day(time) {
+                time = (time + ChronoDuration::days(1)).duration_trunc(ChronoDuration::days(1)).ok()?;
+            } else if !bit(self.hours, time.hour()) {
+                time = (time + ChronoDuration::hours(1)).duration_trunc(ChronoDuration::hours(1)).ok()?;
+            } else if !bit(self.minutes, time.minute()) {
+                time += ChronoDuration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Поле cron: `*`, `5`, `1-5`, `*/15`, `0-30/10` и их списки через запятую
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, JobError> {
+    let invalid = || JobError::InvalidSchedule(format!("'{}': expected values {}-{}", field, min, max));
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0).ok_or_else(invalid)?),
+            None => (part, 1),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((from, to)) => (from.parse().map_err(|_| invalid())?, to.parse().map_err(|_| invalid())?),
+                None => {
+                    let value = range.parse().map_err(|_| invalid())?;
+                    (value, if step > 1 { max } else { value })
+                }
+            },
+        };
+        if from < min || to > max || from > to {
+            return Err(invalid());
+        }
+        for value in (from..=to).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// `30s`, `10m`, `2h`, `1d`, `1h30m`; число без единицы — секунды
+fn parse_every(every: &str) -> Result<Duration, JobError> {
+    match crate::config::duration::parse(every) {
+        Ok(duration) if !duration.is_zero() => Ok(duration),
+        _ => Err(JobError::InvalidSchedule(format!("'@every {}': expected a duration like 30s, 10m, 2h or 1d", every))),
+    }
+}
+
+/// Выполнение задачи: число обработанных объектов
+type JobFn = Arc<dyn Fn(Arc<DirectoryService>) -> BoxFuture<'static, Result<usize, DirectoryError>> + Send + Sync>;
+
+pub struct Job {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Исходная запись расписания
+    pub schedule_expression: String,
+    pub schedule: Schedule,
+    pub enabled: bool,
+    /// Задача меняет каталог: на реплике только для чтения её выполняет upstream
+    pub writes: bool,
+    run: JobFn,
+}
+
+impl Job {
+    pub fn new<F, Fut>(name: &'static str, description: &'static str, schedule: &str, run: F) -> Result<Self, JobError>
+    where
+        F: Fn(Arc<DirectoryService>) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<usize, DirectoryError>> + Send + 'static,
+    {
+        Ok(Self {
+            name,
+            description,
+            schedule_expression: schedule.to_string(),
+            schedule: Schedule::parse(schedule)?,
+            enabled: true,
+            writes: true,
+            run: Arc::new(move |service| Box::pin(run(service))),
+        })
+    }
+
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+
+    /// Задача только читает каталог и выполняется и на реплике
+    pub fn read_only(mut self) -> Self {
+        self.writes = false;
+        self
+    }
+
+    /// Расписание и включение из секции `jobs` конфигурации
+    fn configure(&mut self, config: &JobConfig) -> Result<(), JobError> {
+        if let Some(schedule) = &config.schedule {
+            self.schedule = Schedule::parse(schedule)?;
+            self.schedule_expression = schedule.clone();
+            self.enabled = true;
+        }
+        if let Some(enabled) = config.enabled {
+            self.enabled = enabled;
+        }
+        Ok(())
+    }
+}
+
+/// Задача, её расписание и последнее выполнение — для `nextDomen jobs list`
+#[derive(Serialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub description: String,
+    pub schedule: String,
+    pub enabled: bool,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_run: Option<JobRun>,
+}
+
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Vec<Job>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, job: Job) {
+        self.jobs.retain(|existing| existing.name != job.name);
+        self.jobs.push(job);
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.name == name)
+    }
+
+    /// Встроенные задачи с периодами из прежних настроек (`groups.*_secs`, `security.*`) и
+    /// переопределениями из секции `jobs`; неизвестное имя в `jobs` — ошибка
+    pub fn builtin(config: &AppConfig) -> Result<Self, JobError> {
+        let every = |secs: u64| format!("@every {}s", secs.max(1));
+        let warning_days = config.security.password_expiry.warning_days;
+        let mut registry = Self::new();
+
+        registry.register(Job::new(
+            "service_account_rotation",
+            "Смена паролей учётных записей служб по их расписанию",
+            &every(config.security.service_accounts.rotation_check_secs),
+            |service| async move { service.rotate_due_service_accounts().await },
+        )?);
+        let dynamic_groups = Job::new(
+            "dynamic_group_refresh",
+            "Пересчёт снимков участников динамических групп",
+            &every(config.groups.dynamic_refresh_secs),
+            |service| async move { service.refresh_dynamic_groups().await },
+        )?;
+        registry.register(if config.groups.dynamic_refresh_secs == 0 { dynamic_groups.disabled() } else { dynamic_groups });
+        registry.register(Job::new(
+            "group_expiration",
+            "Отключение групп с истёкшим сроком",
+            &every(config.groups.expiration_check_secs),
+            |service| async move { service.expire_groups().await },
+        )?);
+        registry.register(Job::new(
+            "membership_expiration",
+            "Удаление участников групп с истёкшим сроком временного членства",
+            &every(config.groups.expiration_check_secs),
+            |service| async move { service.expire_memberships().await },
+        )?);
+        registry.register(Job::new(
+            "password_expiry_notices",
+            "События о скором и наступившем истечении паролей",
+            &every(config.security.password_expiry.check_secs),
+            move |service| async move { service.notify_password_expiry(warning_days).await },
+        )?);
+        registry.register(Job::new(
+            "reference_check",
+            "Поиск ссылок на несуществующие объекты: участники групп, привязки GPO, OU пользователей",
+            "@daily",
+            |service| async move { service.report_dangling_references().await },
+        )?.read_only());
+
+        let mut names: Vec<&String> = config.jobs.keys().collect();
+        names.sort();
+        for name in names {
+            let job = registry.jobs.iter_mut().find(|job| job.name == name.as_str())
+                .ok_or_else(|| JobError::UnknownJob(name.clone()))?;
+            job.configure(&config.jobs[name])?;
+        }
+        Ok(registry)
+    }
+
+    /// Выполнить задачу сейчас и сохранить результат в историю
+    pub async fn run_job(&self, service: &Arc<DirectoryService>, name: &str, trigger: JobTrigger) -> Result<JobRun, JobError> {
+        let job = self.get(name).ok_or_else(|| JobError::UnknownJob(name.to_string()))?;
+        if job.writes && service.is_read_only() {
+            return Err(JobError::ReadOnly(name.to_string()));
+        }
+
+        let started_at = Utc::now();
+        let result = (job.run)(Arc::clone(service)).await;
+        let run = JobRun {
+            job: name.to_string(),
+            trigger,
+            started_at,
+            finished_at: Utc::now(),
+            processed: result.as_ref().ok().copied(),
+            error: result.err().map(|e| e.to_string()),
+        };
+        service.record_job_run(&run).await?;
+        Ok(run)
+    }
+
+    /// Задачи с ближайшим выполнением и последним результатом
+    pub async fn status(&self, service: &DirectoryService) -> Result<Vec<JobStatus>, JobError> {
+        let now = Utc::now();
+        let mut statuses = Vec::with_capacity(self.jobs.len());
+        for job in &self.jobs {
+            let last_run = service.get_job_runs(job.name).await?.into_iter().next();
+            let next_run = match (&job.schedule, &last_run) {
+                _ if !job.enabled => None,
+                (Schedule::Every(_), Some(last_run)) => job.schedule.next_after(last_run.started_at).map(|next| next.max(now)),
+                (Schedule::Every(_), None) => Some(now),
+                (Schedule::Cron(_), _) => job.schedule.next_after(now),
+            };
+            statuses.push(JobStatus {
+                name: job.name.to_string(),
+                description: job.description.to_string(),
+                schedule: job.schedule_expression.clone(),
+                enabled: job.enabled,
+                next_run,
+                last_run,
+            });
+        }
+        Ok(statuses)
+    }
+
+    /// Выполнять включённые задачи по расписанию; ошибка выполнения не останавливает следующие.
+    /// На реплике только для чтения задачи, меняющие каталог, пропускаются
+    pub async fn run(self, service: Arc<DirectoryService>) {
+        let jobs = self.jobs.iter()
+            .filter(|job| job.enabled && !(job.writes && service.is_read_only()))
+            .map(|job| self.run_scheduled(job, &service));
+        join_all(jobs).await;
+    }
+
+    async fn run_scheduled(&self, job: &Job, service: &Arc<DirectoryService>) {
+        tracing::info!(job = job.name, schedule = %job.schedule_expression, "Фоновая задача по расписанию");
+        let mut next = match job.schedule {
+            Schedule::Every(_) => Some(Utc::now()),
+            Schedule::Cron(_) => job.schedule.next_after(Utc::now()),
+        };
+        while let Some(at) = next {
+            if let Ok(wait) = (at - Utc::now()).to_std() {
+                tokio::time::sleep(wait).await;
+            }
+            let started_at = Utc::now();
+            match self.run_job(service, job.name, JobTrigger::Schedule).await {
+                Ok(JobRun { processed: Some(0), .. }) => {}
+                Ok(JobRun { processed: Some(processed), .. }) => tracing::info!(job = job.name, processed, "Фоновая задача выполнена"),
+                Ok(JobRun { error, .. }) => tracing::error!(job = job.name, error = error.as_deref().unwrap_or_default(), "Ошибка фоновой задачи"),
+                Err(e) => tracing::error!(job = job.name, error = %e, "Ошибка фоновой задачи"),
+            }
+            next = job.schedule.next_after(match job.schedule {
+                Schedule::Every(_) => started_at,
+                Schedule::Cron(_) => Utc::now(),
+            });
+        }
+        tracing::warn!(job = job.name, schedule = %job.schedule_expression, "У фоновой задачи нет следующего выполнения");
+    }
+}
@@ -0,0 +1,227 @@
+// src/kerberos/crypto.rs
+
+//! Профиль шифрования aes256-cts-hmac-sha1-96 (etype 18, RFC 3961/3962).
+
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::Aes256;
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha1::Sha1;
+
+use super::KerberosError;
+
+/// Номер типа шифрования aes256-cts-hmac-sha1-96
+pub const ETYPE_AES256_CTS_HMAC_SHA1_96: i32 = 18;
+
+/// Число итераций PBKDF2 по умолчанию (RFC 3962)
+const DEFAULT_ITERATIONS: u32 = 4096;
+
+const BLOCK: usize = 16;
+const KEY_LEN: usize = 32;
+const MAC_LEN: usize = 12;
+
+/// Вывести долгосрочный ключ из пароля и соли (string-to-key)
+pub fn string_to_key(password: &str, salt: &str) -> Vec<u8> {
+    string_to_key_iter(password, salt, DEFAULT_ITERATIONS)
+}
+
+/// string-to-key с явным числом итераций PBKDF2 (s2kparams, RFC 3962, раздел 4)
+pub fn string_to_key_iter(password: &str, salt: &str, iterations: u32) -> Vec<u8> {
+    let mut tkey = [0u8; KEY_LEN];
+    pbkdf2::pbkdf2_hmac::<Sha1>(password.as_bytes(), salt.as_bytes(), iterations, &mut tkey);
+    derive_key(&tkey, b"kerberos")
+}
+
+/// Сгенерировать случайный сеансовый ключ
+pub fn random_key() -> Vec<u8> {
+    let mut key = vec![0u8; KEY_LEN];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+/// Зашифровать данные базовым ключом для указанного key usage
+pub fn encrypt(key: &[u8], usage: u32, plaintext: &[u8]) -> Result<Vec<u8>, KerberosError> {
+    let ke = derive_key(key, &usage_constant(usage, 0xAA));
+    let ki = derive_key(key, &usage_constant(usage, 0x55));
+
+    let mut data = vec![0u8; BLOCK];
+    OsRng.fill_bytes(&mut data);
+    data.extend_from_slice(plaintext);
+
+    let mut out = cts_encrypt(&ke, &data)?;
+    out.extend_from_slice(&hmac_sha1(&ki, &data)[..MAC_LEN]);
+    Ok(out)
+}
+
+/// Расшифровать данные и проверить целостность
+pub fn decrypt(key: &[u8], usage: u32, ciphertext: &[u8]) -> Result<Vec<u8>, KerberosError> {
+    if ciphertext.len() < BLOCK + MAC_LEN {
+        return Err(KerberosError::Integrity);
+    }
+    let ke = derive_key(key, &usage_constant(usage, 0xAA));
+    let ki = derive_key(key, &usage_constant(usage, 0x55));
+
+    let (body, mac) = ciphertext.split_at(ciphertext.len() - MAC_LEN);
+    let data = cts_decrypt(&ke, body)?;
+    let expected = hmac_sha1(&ki, &data);
+    if !constant_time_eq(&expected[..MAC_LEN], mac) {
+        return Err(KerberosError::Integrity);
+    }
+    Ok(data[BLOCK..].to_vec())
+}
+
+fn usage_constant(usage: u32, kind: u8) -> [u8; 5] {
+    let u = usage.to_be_bytes();
+    [u[0], u[1], u[2], u[3], kind]
+}
+
+fn hmac_sha1(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha1> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn cipher(key: &[u8]) -> Result<Aes256, KerberosError> {
+    Aes256::new_from_slice(key).map_err(|_| KerberosError::Crypto("Invalid AES-256 key".to_string()))
+}
+
+/// DK(key, constant) из RFC 3961
+fn derive_key(key: &[u8], constant: &[u8]) -> Vec<u8> {
+    let aes = Aes256::new_from_slice(key).expect("Kerberos base keys are 32 bytes");
+    let mut block = GenericArray::clone_from_slice(&nfold(constant, BLOCK));
+    let mut out = Vec::with_capacity(KEY_LEN);
+    while out.len() < KEY_LEN {
+        aes.encrypt_block(&mut block);
+        out.extend_from_slice(&block);
+    }
+    out.truncate(KEY_LEN);
+    out
+}
+
+/// n-fold из RFC 3961 (реализация по образцу MIT krb5)
+fn nfold(input: &[u8], out_len: usize) -> Vec<u8> {
+    let in_len = input.len();
+    let lcm = in_len / gcd(in_len, out_len) * out_len;
+    let in_bits = in_len << 3;
+    let mut out = vec![0u8; out_len];
+    let mut carry: u32 = 0;
+
+    for i in (0..lcm).rev() {
+        let msbit = ((in_bits - 1) + ((in_bits + 13) * (i / in_len)) + ((in_len - (i % in_len)) << 3))
+            % in_bits;
+        let hi = input[((in_len - 1) - (msbit >> 3)) % in_len] as u32;
+        let lo = input[(in_len - (msbit >> 3)) % in_len] as u32;
+        carry += (((hi << 8) | lo) >> ((msbit & 7) + 1)) & 0xFF;
+        carry += out[i % out_len] as u32;
+        out[i % out_len] = (carry & 0xFF) as u8;
+        carry >>= 8;
+    }
+
+    if carry != 0 {
+        for byte in out.iter_mut().rev() {
+            carry += *byte as u32;
+            *byte = (carry & 0xFF) as u8;
+            carry >>= 8;
+        }
+    }
+    out
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+/// AES-CBC с перестановкой последних блоков (ciphertext stealing), IV = 0
+fn cts_encrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>, KerberosError> {
+    let aes = cipher(key)?;
+    if data.len() < BLOCK {
+        return Err(KerberosError::Crypto("Plaintext shorter than one block".to_string()));
+    }
+    let n = data.len().div_ceil(BLOCK);
+    let last_len = data.len() - BLOCK * (n - 1);
+
+    let mut prev = [0u8; BLOCK];
+    let mut blocks: Vec<[u8; BLOCK]> = Vec::with_capacity(n);
+    for chunk in data.chunks(BLOCK) {
+        let mut block = [0u8; BLOCK];
+        block[..chunk.len()].copy_from_slice(chunk);
+        for (b, p) in block.iter_mut().zip(prev.iter()) {
+            *b ^= p;
+        }
+        let mut ga = GenericArray::clone_from_slice(&block);
+        aes.encrypt_block(&mut ga);
+        prev.copy_from_slice(&ga);
+        blocks.push(prev);
+    }
+
+    if n == 1 {
+        return Ok(blocks[0].to_vec());
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    for block in &blocks[..n - 2] {
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&blocks[n - 1]);
+    out.extend_from_slice(&blocks[n - 2][..last_len]);
+    Ok(out)
+}
+
+fn cts_decrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>, KerberosError> {
+    let aes = cipher(key)?;
+    if data.len() < BLOCK {
+        return Err(KerberosError::Integrity);
+    }
+    let decrypt_block = |block: &[u8]| -> [u8; BLOCK] {
+        let mut ga = GenericArray::clone_from_slice(block);
+        aes.decrypt_block(&mut ga);
+        let mut out = [0u8; BLOCK];
+        out.copy_from_slice(&ga);
+        out
+    };
+
+    let n = data.len().div_ceil(BLOCK);
+    if n == 1 {
+        return Ok(decrypt_block(data).to_vec());
+    }
+    let last_len = data.len() - BLOCK * (n - 1);
+
+    let mut out = Vec::with_capacity(data.len());
+    let mut prev = [0u8; BLOCK];
+    for block in data[..BLOCK * (n - 2)].chunks(BLOCK) {
+        let mut plain = decrypt_block(block);
+        for (p, c) in plain.iter_mut().zip(prev.iter()) {
+            *p ^= c;
+        }
+        out.extend_from_slice(&plain);
+        prev.copy_from_slice(block);
+    }
+
+    // Последний полный блок шифротекста соответствует P(n), усечённый — P(n-1)
+    let cn = &data[BLOCK * (n - 2)..BLOCK * (n - 1)];
+    let tail = &data[BLOCK * (n - 1)..];
+    let d = decrypt_block(cn);
+
+    let mut c_prev = [0u8; BLOCK];
+    c_prev[..last_len].copy_from_slice(tail);
+    c_prev[last_len..].copy_from_slice(&d[last_len..]);
+
+    let mut p_prev = decrypt_block(&c_prev);
+    for (p, c) in p_prev.iter_mut().zip(prev.iter()) {
+        *p ^= c;
+    }
+    out.extend_from_slice(&p_prev);
+
+    let p_last: Vec<u8> = d.iter().zip(c_prev.iter()).map(|(a, b)| a ^ b).take(last_len).collect();
+    out.extend_from_slice(&p_last);
+    Ok(out)
+}
@@ -0,0 +1,210 @@
+// src/kerberos/der.rs
+
+//! Минимальный DER-кодек для сообщений Kerberos (RFC 4120, раздел 5).
+//!
+//! Поддерживаются только те типы ASN.1, которые реально встречаются
+//! в обмене AS/TGS: INTEGER, OCTET STRING, GeneralString, GeneralizedTime,
+//! BIT STRING, SEQUENCE, а также контекстные и APPLICATION-теги.
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+
+use super::KerberosError;
+
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_GENERAL_STRING: u8 = 0x1B;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+
+/// Узел DER: тег и необработанное содержимое
+#[derive(Debug, Clone)]
+pub struct Tlv {
+    pub tag: u8,
+    pub value: Vec<u8>,
+}
+
+impl Tlv {
+    /// Разобрать ровно один TLV из буфера
+    pub fn parse(data: &[u8]) -> Result<Self, KerberosError> {
+        let (tlv, used) = Self::parse_prefix(data)?;
+        if used != data.len() {
+            return Err(KerberosError::Decode("Trailing bytes after DER value".to_string()));
+        }
+        Ok(tlv)
+    }
+
+    /// Разобрать TLV с начала буфера, вернуть его и число прочитанных байт
+    fn parse_prefix(data: &[u8]) -> Result<(Self, usize), KerberosError> {
+        if data.len() < 2 {
+            return Err(KerberosError::Decode("Truncated DER value".to_string()));
+        }
+        let tag = data[0];
+        let (len, header) = if data[1] & 0x80 == 0 {
+            (data[1] as usize, 2)
+        } else {
+            let num_bytes = (data[1] & 0x7F) as usize;
+            if num_bytes == 0 || num_bytes > 4 || data.len() < 2 + num_bytes {
+                return Err(KerberosError::Decode("Invalid DER length".to_string()));
+            }
+            let len = data[2..2 + num_bytes]
+                .iter()
+                .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+            (len, 2 + num_bytes)
+        };
+        if data.len() < header + len {
+            return Err(KerberosError::Decode("Truncated DER value".to_string()));
+        }
+        Ok((
+            Self { tag, value: data[header..header + len].to_vec() },
+            header + len,
+        ))
+    }
+
+    /// Дочерние элементы конструктивного значения
+    pub fn children(&self) -> Result<Vec<Tlv>, KerberosError> {
+        let mut items = Vec::new();
+        let mut rest = &self.value[..];
+        while !rest.is_empty() {
+            let (item, used) = Self::parse_prefix(rest)?;
+            items.push(item);
+            rest = &rest[used..];
+        }
+        Ok(items)
+    }
+
+    /// Содержимое APPLICATION-обёртки с ожидаемым номером
+    pub fn unwrap_application(&self, number: u8) -> Result<Tlv, KerberosError> {
+        if self.tag != 0x60 | number {
+            return Err(KerberosError::Decode(format!("Expected [APPLICATION {}]", number)));
+        }
+        Tlv::parse(&self.value)
+    }
+
+    /// Необязательное поле SEQUENCE с контекстным тегом `[n]`
+    pub fn field(&self, n: u8) -> Result<Option<Tlv>, KerberosError> {
+        for child in self.children()? {
+            if child.tag == 0xA0 | n {
+                return Ok(Some(Tlv::parse(&child.value)?));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Обязательное поле SEQUENCE с контекстным тегом `[n]`
+    pub fn required(&self, n: u8) -> Result<Tlv, KerberosError> {
+        self.field(n)?
+            .ok_or_else(|| KerberosError::Decode(format!("Missing field [{}]", n)))
+    }
+
+    pub fn as_integer(&self) -> Result<i64, KerberosError> {
+        if self.tag != TAG_INTEGER || self.value.is_empty() || self.value.len() > 8 {
+            return Err(KerberosError::Decode("Expected INTEGER".to_string()));
+        }
+        let mut val: i64 = if self.value[0] & 0x80 != 0 { -1 } else { 0 };
+        for &b in &self.value {
+            val = (val << 8) | b as i64;
+        }
+        Ok(val)
+    }
+
+    pub fn as_bytes(&self) -> Result<&[u8], KerberosError> {
+        if self.tag != TAG_OCTET_STRING {
+            return Err(KerberosError::Decode("Expected OCTET STRING".to_string()));
+        }
+        Ok(&self.value)
+    }
+
+    pub fn as_string(&self) -> Result<String, KerberosError> {
+        if self.tag != TAG_GENERAL_STRING {
+            return Err(KerberosError::Decode("Expected GeneralString".to_string()));
+        }
+        String::from_utf8(self.value.clone())
+            .map_err(|_| KerberosError::Decode("Invalid UTF-8 in KerberosString".to_string()))
+    }
+
+    pub fn as_time(&self) -> Result<DateTime<Utc>, KerberosError> {
+        if self.tag != TAG_GENERALIZED_TIME {
+            return Err(KerberosError::Decode("Expected GeneralizedTime".to_string()));
+        }
+        let text = std::str::from_utf8(&self.value)
+            .map_err(|_| KerberosError::Decode("Invalid KerberosTime".to_string()))?;
+        let naive = NaiveDateTime::parse_from_str(text, "%Y%m%d%H%M%SZ")
+            .map_err(|_| KerberosError::Decode(format!("Invalid KerberosTime: {}", text)))?;
+        Ok(Utc.from_utc_datetime(&naive))
+    }
+
+    /// Первые 32 бита BIT STRING (флаги KDCOptions/TicketFlags)
+    pub fn as_flags(&self) -> Result<u32, KerberosError> {
+        if self.tag != TAG_BIT_STRING || self.value.is_empty() {
+            return Err(KerberosError::Decode("Expected BIT STRING".to_string()));
+        }
+        let mut bytes = [0u8; 4];
+        for (i, b) in self.value[1..].iter().take(4).enumerate() {
+            bytes[i] = *b;
+        }
+        Ok(u32::from_be_bytes(bytes))
+    }
+}
+
+// === Запись ===
+
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut w = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        w.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len.to_be_bytes().iter().copied().skip_while(|b| *b == 0).collect();
+        w.push(0x80 | bytes.len() as u8);
+        w.extend(bytes);
+    }
+    w.extend_from_slice(content);
+    w
+}
+
+pub fn sequence(items: &[Vec<u8>]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &items.concat())
+}
+
+/// Контекстный тег `[n] EXPLICIT`
+pub fn context(n: u8, inner: Vec<u8>) -> Vec<u8> {
+    tlv(0xA0 | n, &inner)
+}
+
+/// `[APPLICATION n]`
+pub fn application(n: u8, inner: Vec<u8>) -> Vec<u8> {
+    tlv(0x60 | n, &inner)
+}
+
+pub fn integer(n: i64) -> Vec<u8> {
+    let bytes = n.to_be_bytes();
+    let mut start = 0;
+    while start < 7 {
+        let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    tlv(TAG_INTEGER, &bytes[start..])
+}
+
+pub fn octet_string(data: &[u8]) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, data)
+}
+
+pub fn general_string(s: &str) -> Vec<u8> {
+    tlv(TAG_GENERAL_STRING, s.as_bytes())
+}
+
+pub fn time(dt: &DateTime<Utc>) -> Vec<u8> {
+    tlv(TAG_GENERALIZED_TIME, dt.format("%Y%m%d%H%M%SZ").to_string().as_bytes())
+}
+
+pub fn flags(bits: u32) -> Vec<u8> {
+    let mut content = vec![0u8];
+    content.extend_from_slice(&bits.to_be_bytes());
+    tlv(TAG_BIT_STRING, &content)
+}
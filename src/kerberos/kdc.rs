@@ -0,0 +1,451 @@
+// src/kerberos/kdc.rs
+
+use chrono::{DateTime, Duration, Utc};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use super::crypto::{self, ETYPE_AES256_CTS_HMAC_SHA1_96};
+use super::der::Tlv;
+use super::messages::*;
+use super::*;
+use crate::directory_service::DirectoryService;
+use crate::events::{AuditEvent, AuditResult};
+use crate::models::{KerberosKey, User};
+
+/// Имя учётной записи, ключом которой шифруются TGT
+pub const KRBTGT: &str = "krbtgt";
+
+/// Максимальное время жизни билета
+const MAX_TICKET_LIFETIME_HOURS: i64 = 10;
+
+/// Допустимое расхождение часов клиента и KDC
+const MAX_CLOCK_SKEW_MINUTES: i64 = 5;
+
+// Key usage (RFC 4120, 7.5.1)
+const USAGE_PA_ENC_TIMESTAMP: u32 = 1;
+const USAGE_TICKET: u32 = 2;
+const USAGE_AS_REP_ENC_PART: u32 = 3;
+const USAGE_TGS_REQ_AUTHENTICATOR: u32 = 7;
+const USAGE_TGS_REP_SESSION_KEY: u32 = 8;
+const USAGE_TGS_REP_SUBKEY: u32 = 9;
+
+/// Центр распределения ключей поверх каталога
+pub struct Kdc {
+    service: Arc<DirectoryService>,
+    realm: String,
+}
+
+/// Принципал службы, для которого выдаётся билет
+struct ServicePrincipal {
+    name: PrincipalName,
+    key: KerberosKey,
+}
+
+impl Kdc {
+    pub fn new(service: Arc<DirectoryService>, realm: &str) -> Self {
+        Self { service, realm: realm.to_uppercase() }
+    }
+
+    pub fn realm(&self) -> &str {
+        &self.realm
+    }
+
+    /// Создать учётную запись krbtgt со случайным паролем, если её ещё нет
+    pub async fn ensure_krbtgt(&self) -> Result<(), KerberosError> {
+        if self.service.find_user_by_username(KRBTGT).await?.is_some() {
+            return Ok(());
+        }
+
+        use crate::models::{PasswordAlgorithm, PasswordHash, SecurityIdentifier, UserAccountControl};
+        use crate::models::well_known::{rid, CRITICAL_SYSTEM_OBJECT};
+
+        // RID 502 в SID домена realm; без домена — прежний SID без домена
+        let domain = self.service.find_domain_by_dns_name(&self.realm.to_lowercase()).await?;
+        let user = User {
+            id: uuid::Uuid::new_v4(),
+            sid: domain.as_ref()
+                .map(|domain| domain.sid.with_rid(rid::KRBTGT))
+                .unwrap_or_else(|| SecurityIdentifier::new_nt_authority(rid::KRBTGT)),
+            username: KRBTGT.to_string(),
+            user_principal_name: format!("{}@{}", KRBTGT, self.realm.to_lowercase()),
+            email: None,
+            display_name: Some("Key Distribution Center Service Account".to_string()),
+            given_name: None,
+            surname: None,
+            password_hash: PasswordHash {
+                hash: String::new(),
+                algorithm: PasswordAlgorithm::Bcrypt,
+                salt: vec![],
+            },
+            password_expires: None,
+            last_password_change: Utc::now(),
+            lockout_until: None,
+            failed_logins: 0,
+            // Как и в AD, krbtgt отключена для входа — её ключ используется только KDC
+            enabled: false,
+            mfa_enabled: false,
+            mfa_methods: vec![],
+            domains: domain.iter().map(|domain| domain.id).collect(),
+            groups: vec![],
+            organizational_unit: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_login: None,
+            profile_path: None,
+            script_path: None,
+            meta: [(CRITICAL_SYSTEM_OBJECT.to_string(), "TRUE".to_string())].into(),
+            primary_group_id: Some(513),
+            service_principal_names: vec![],
+            kerberos_keys: vec![],
+            user_account_control: UserAccountControl::NORMAL_ACCOUNT,
+            totp_secret: None,
+            totp_pending_secret: None,
+        };
+        self.service.create_user(&user).await?;
+
+        let password = hex::encode(crypto::random_key());
+        self.service.set_generated_password(user.id, &password).await?;
+        Ok(())
+    }
+
+    /// Обработать один запрос; ошибки возвращаются клиенту в виде KRB-ERROR
+    #[tracing::instrument(name = "kdc_request", skip(self, data), fields(otel.kind = "server"))]
+    pub async fn handle(&self, data: &[u8], peer: Option<IpAddr>) -> Vec<u8> {
+        let (result, sname) = match Tlv::parse(data) {
+            Ok(tlv) if tlv.tag == 0x60 | MSG_AS_REQ as u8 || tlv.tag == 0x60 | MSG_TGS_REQ as u8 => {
+                match KdcReq::decode(&tlv) {
+                    Ok(req) => {
+                        let sname = req.body.sname.clone();
+                        let result = if req.msg_type == MSG_AS_REQ {
+                            let result = self.handle_as_req(&req).await;
+                            self.audit_as_req(&req, &result, peer).await;
+                            result
+                        } else {
+                            self.handle_tgs_req(&req).await
+                        };
+                        (result, sname)
+                    }
+                    Err(e) => (Err(e), None),
+                }
+            }
+            Ok(_) => (Err(KerberosError::Decode("Unsupported message type".to_string())), None),
+            Err(e) => (Err(e), None),
+        };
+
+        match result {
+            Ok(reply) => reply,
+            Err(e) => {
+                let (text, e_data) = match &e {
+                    KerberosError::PreauthRequired { etype, salt } => (None, Some(preauth_method_data(*etype, salt))),
+                    other => (Some(other.to_string()), None),
+                };
+                KrbError {
+                    error_code: e.error_code(),
+                    realm: self.realm.clone(),
+                    sname: sname.unwrap_or_else(|| PrincipalName::service(KRBTGT, &self.realm)),
+                    cname: None,
+                    text,
+                    e_data,
+                }
+                .encode()
+            }
+        }
+    }
+
+    // ================= AS =================
+
+    /// AS-REQ — попытка входа; запрос без предварительной аутентификации — ещё не попытка
+    async fn audit_as_req(&self, req: &KdcReq, result: &Result<Vec<u8>, KerberosError>, peer: Option<IpAddr>) {
+        let (audit_result, reason) = match result {
+            Ok(_) => (AuditResult::Success, None),
+            Err(KerberosError::PreauthRequired { .. }) => return,
+            Err(e) => (AuditResult::Failure, Some(e.to_string())),
+        };
+        let mut event = AuditEvent::new("kerberos_as_req", audit_result);
+        event.ip_addr = peer.map(|ip| ip.to_string());
+        if let Some(cname) = &req.body.cname {
+            event.metadata.insert("principal".to_string(), format!("{}@{}", cname.to_text(), self.realm));
+        }
+        if let Some(reason) = reason {
+            event.metadata.insert("reason".to_string(), reason);
+        }
+        if let Err(e) = self.service.record(event).await {
+            tracing::error!(error = %e, "Не удалось записать событие аудита");
+        }
+    }
+
+    async fn handle_as_req(&self, req: &KdcReq) -> Result<Vec<u8>, KerberosError> {
+        self.check_realm(&req.body.realm)?;
+        let cname = req.body.cname.clone()
+            .ok_or_else(|| KerberosError::Decode("AS-REQ without cname".to_string()))?;
+        let sname = req.body.sname.clone()
+            .ok_or_else(|| KerberosError::Decode("AS-REQ without sname".to_string()))?;
+
+        let client = self.find_client(&cname).await?;
+        let client_key = select_key(&client, &req.body.etypes)?;
+        let server = self.find_service(&sname).await?;
+
+        // Предварительная аутентификация обязательна
+        let pa = req.find_padata(PA_ENC_TIMESTAMP)
+            .ok_or_else(|| KerberosError::PreauthRequired { etype: client_key.etype, salt: client_key.salt.clone() })?;
+        let enc = EncryptedData::decode(&Tlv::parse(&pa.value)?)?;
+        // Неверный ключ — неудачная попытка входа: как неверный пароль в REST, ведёт к блокировке
+        let Ok(plain) = crypto::decrypt(&client_key.key, USAGE_PA_ENC_TIMESTAMP, &enc.cipher) else {
+            self.service.record_failed_login(client.id).await?;
+            return Err(KerberosError::Kdc(KDC_ERR_PREAUTH_FAILED, "Pre-authentication failed".to_string()));
+        };
+        check_skew(&decode_pa_enc_timestamp(&plain)?)?;
+        // Как и в AD, об истёкшем пароле сообщается только после верной предаутентификации
+        if client.is_password_expired(Utc::now()) {
+            return Err(KerberosError::Kdc(KDC_ERR_KEY_EXPIRED, "Password has expired, change it first".to_string()));
+        }
+        self.service.record_successful_login(client.id).await?;
+
+        let now = Utc::now();
+        let session_key = EncryptionKey {
+            keytype: ETYPE_AES256_CTS_HMAC_SHA1_96,
+            value: crypto::random_key(),
+        };
+        let endtime = ticket_endtime(now, req.body.till, None);
+        let flags = FLAG_INITIAL | FLAG_PRE_AUTHENT;
+
+        let ticket = self.seal_ticket(&server, &EncTicketPart {
+            flags,
+            key: session_key.clone(),
+            crealm: self.realm.clone(),
+            cname: cname.clone(),
+            authtime: now,
+            starttime: Some(now),
+            endtime,
+            renew_till: None,
+        })?;
+
+        let enc_part = EncKdcRepPart {
+            key: session_key,
+            nonce: req.body.nonce,
+            flags,
+            authtime: now,
+            starttime: Some(now),
+            endtime,
+            renew_till: None,
+            srealm: self.realm.clone(),
+            sname: server.name.clone(),
+        };
+
+        let reply = KdcRep {
+            msg_type: MSG_AS_REP,
+            padata: vec![PaData {
+                padata_type: PA_ETYPE_INFO2,
+                value: encode_etype_info2(client_key.etype, &client_key.salt),
+            }],
+            crealm: self.realm.clone(),
+            cname,
+            ticket,
+            enc_part: EncryptedData {
+                etype: client_key.etype,
+                kvno: Some(client_key.kvno),
+                cipher: crypto::encrypt(&client_key.key, USAGE_AS_REP_ENC_PART, &enc_part.encode(25))?,
+            },
+        };
+        Ok(reply.encode())
+    }
+
+    // ================= TGS =================
+
+    async fn handle_tgs_req(&self, req: &KdcReq) -> Result<Vec<u8>, KerberosError> {
+        self.check_realm(&req.body.realm)?;
+        let pa = req.find_padata(PA_TGS_REQ)
+            .ok_or_else(|| KerberosError::Kdc(KDC_ERR_PADATA_TYPE_NOSUPP, "PA-TGS-REQ required".to_string()))?;
+        let ap_req = ApReq::decode(&pa.value)?;
+
+        // Расшифровываем TGT ключом krbtgt
+        let krbtgt = self.find_service(&PrincipalName::service(KRBTGT, &self.realm)).await?;
+        if ap_req.ticket.sname.components.first().map(String::as_str) != Some(KRBTGT) {
+            return Err(KerberosError::Kdc(KRB_AP_ERR_BADMATCH, "Ticket is not a TGT".to_string()));
+        }
+        let tgt = EncTicketPart::decode(&crypto::decrypt(
+            &krbtgt.key.key,
+            USAGE_TICKET,
+            &ap_req.ticket.enc_part.cipher,
+        )?)?;
+
+        let now = Utc::now();
+        if tgt.endtime < now {
+            return Err(KerberosError::Kdc(KRB_AP_ERR_TKT_EXPIRED, "TGT expired".to_string()));
+        }
+
+        let authenticator = Authenticator::decode(&crypto::decrypt(
+            &tgt.key.value,
+            USAGE_TGS_REQ_AUTHENTICATOR,
+            &ap_req.authenticator.cipher,
+        )?)?;
+        if authenticator.cname != tgt.cname || !authenticator.crealm.eq_ignore_ascii_case(&tgt.crealm) {
+            return Err(KerberosError::Kdc(KRB_AP_ERR_BADMATCH, "Authenticator does not match ticket".to_string()));
+        }
+        check_skew(&authenticator.ctime)?;
+
+        // Учётная запись могла быть отключена после выдачи TGT
+        self.find_client(&tgt.cname).await?;
+
+        let sname = req.body.sname.clone()
+            .ok_or_else(|| KerberosError::Decode("TGS-REQ without sname".to_string()))?;
+        let server = self.find_service(&sname).await?;
+
+        let session_key = EncryptionKey {
+            keytype: ETYPE_AES256_CTS_HMAC_SHA1_96,
+            value: crypto::random_key(),
+        };
+        let endtime = ticket_endtime(now, req.body.till, Some(tgt.endtime));
+        let flags = tgt.flags & !FLAG_INITIAL;
+
+        let ticket = self.seal_ticket(&server, &EncTicketPart {
+            flags,
+            key: session_key.clone(),
+            crealm: tgt.crealm.clone(),
+            cname: tgt.cname.clone(),
+            authtime: tgt.authtime,
+            starttime: Some(now),
+            endtime,
+            renew_till: None,
+        })?;
+
+        let enc_part = EncKdcRepPart {
+            key: session_key,
+            nonce: req.body.nonce,
+            flags,
+            authtime: tgt.authtime,
+            starttime: Some(now),
+            endtime,
+            renew_till: None,
+            srealm: self.realm.clone(),
+            sname: server.name.clone(),
+        };
+
+        let cipher = match &authenticator.subkey {
+            Some(subkey) => crypto::encrypt(&subkey.value, USAGE_TGS_REP_SUBKEY, &enc_part.encode(26))?,
+            None => crypto::encrypt(&tgt.key.value, USAGE_TGS_REP_SESSION_KEY, &enc_part.encode(26))?,
+        };
+
+        let reply = KdcRep {
+            msg_type: MSG_TGS_REP,
+            padata: vec![],
+            crealm: tgt.crealm,
+            cname: tgt.cname,
+            ticket,
+            enc_part: EncryptedData {
+                etype: ETYPE_AES256_CTS_HMAC_SHA1_96,
+                kvno: None,
+                cipher,
+            },
+        };
+        Ok(reply.encode())
+    }
+
+    // ================= Принципалы =================
+
+    fn check_realm(&self, realm: &str) -> Result<(), KerberosError> {
+        if !realm.eq_ignore_ascii_case(&self.realm) {
+            return Err(KerberosError::Kdc(KDC_ERR_WRONG_REALM, format!("Unknown realm {}", realm)));
+        }
+        Ok(())
+    }
+
+    /// Клиент — учётная запись пользователя: имя из одного компонента (`alice`, не `alice/admin`)
+    async fn find_client(&self, cname: &PrincipalName) -> Result<User, KerberosError> {
+        let name = match cname.components.as_slice() {
+            [name] => name,
+            [] => return Err(KerberosError::Decode("Empty principal name".to_string())),
+            _ => return Err(KerberosError::Kdc(KDC_ERR_C_PRINCIPAL_UNKNOWN, format!("Client not found: {}", cname.to_text()))),
+        };
+        let user = self.service.find_user_by_username(name).await?
+            .ok_or_else(|| KerberosError::Kdc(KDC_ERR_C_PRINCIPAL_UNKNOWN, format!("Client not found: {}", name)))?;
+
+        if !user.enabled || user.is_locked_out() {
+            return Err(KerberosError::Kdc(KDC_ERR_CLIENT_REVOKED, "Client account is disabled or locked".to_string()));
+        }
+        Ok(user)
+    }
+
+    /// krbtgt/REALM → учётная запись krbtgt, иначе только зарегистрированный servicePrincipalName:
+    /// по имени учётной записи билет не выдаётся, иначе билет на ключ любого пользователя можно
+    /// было бы получить и подбирать его пароль офлайн (Kerberoasting)
+    async fn find_service(&self, sname: &PrincipalName) -> Result<ServicePrincipal, KerberosError> {
+        let is_krbtgt = sname.components.len() == 2
+            && sname.components[0] == KRBTGT
+            && sname.components[1].eq_ignore_ascii_case(&self.realm);
+
+        let user = if is_krbtgt {
+            self.service.find_user_by_username(KRBTGT).await?
+        } else {
+            self.service.find_user_by_spn(&sname.to_text()).await?
+        };
+
+        let user = user.ok_or_else(|| {
+            KerberosError::Kdc(KDC_ERR_S_PRINCIPAL_UNKNOWN, format!("Service not found: {}", sname.to_text()))
+        })?;
+        // krbtgt отключена для входа, но её ключ нужен KDC; остальным службам билеты не выдаются
+        if !is_krbtgt && (!user.enabled || user.is_locked_out()) {
+            return Err(KerberosError::Kdc(KDC_ERR_SERVICE_REVOKED, "Service account is disabled or locked".to_string()));
+        }
+        let key = select_key(&user, &[ETYPE_AES256_CTS_HMAC_SHA1_96])?;
+        Ok(ServicePrincipal { name: sname.clone(), key })
+    }
+
+    fn seal_ticket(&self, server: &ServicePrincipal, part: &EncTicketPart) -> Result<Ticket, KerberosError> {
+        Ok(Ticket {
+            realm: self.realm.clone(),
+            sname: server.name.clone(),
+            enc_part: EncryptedData {
+                etype: server.key.etype,
+                kvno: Some(server.key.kvno),
+                cipher: crypto::encrypt(&server.key.key, USAGE_TICKET, &part.encode())?,
+            },
+        })
+    }
+
+}
+
+/// METHOD-DATA для KDC_ERR_PREAUTH_REQUIRED: PA-ENC-TIMESTAMP и PA-ETYPE-INFO2 с ключом клиента
+fn preauth_method_data(etype: i32, salt: &str) -> Vec<u8> {
+    super::der::sequence(&[
+        PaData { padata_type: PA_ENC_TIMESTAMP, value: vec![] }.encode(),
+        PaData {
+            padata_type: PA_ETYPE_INFO2,
+            value: encode_etype_info2(etype, salt),
+        }
+        .encode(),
+    ])
+}
+
+/// Выбрать актуальный ключ принципала для одного из запрошенных etype
+fn select_key(user: &User, etypes: &[i32]) -> Result<KerberosKey, KerberosError> {
+    user.kerberos_keys
+        .iter()
+        .filter(|k| etypes.contains(&k.etype))
+        .max_by_key(|k| k.kvno)
+        .cloned()
+        .ok_or_else(|| KerberosError::Kdc(
+            KDC_ERR_ETYPE_NOSUPP,
+            format!("No usable key for {}", user.username),
+        ))
+}
+
+fn check_skew(client_time: &DateTime<Utc>) -> Result<(), KerberosError> {
+    let skew = (Utc::now() - *client_time).num_minutes().abs();
+    if skew > MAX_CLOCK_SKEW_MINUTES {
+        return Err(KerberosError::Kdc(KRB_AP_ERR_SKEW, "Clock skew too great".to_string()));
+    }
+    Ok(())
+}
+
+/// Время окончания: запрошенное `till`, но не дальше лимита и срока TGT
+fn ticket_endtime(now: DateTime<Utc>, till: Option<DateTime<Utc>>, limit: Option<DateTime<Utc>>) -> DateTime<Utc> {
+    let mut end = now + Duration::hours(MAX_TICKET_LIFETIME_HOURS);
+    if let Some(till) = till.filter(|t| *t > now && *t < end) {
+        end = till;
+    }
+    if let Some(limit) = limit {
+        end = end.min(limit);
+    }
+    end
+}
@@ -0,0 +1,68 @@
+// src/kerberos/keytab.rs
+
+//! Keytab в формате MIT (версия 0x0502): ключи принципала службы для проверки
+//! её билетов без обращения к KDC. Числа — big-endian, строки — u16 длины и байты.
+
+use super::messages::PrincipalName;
+use crate::models::KerberosKey;
+
+/// Сигнатура и версия файла
+const KEYTAB_VERSION: u16 = 0x0502;
+
+/// Запись keytab: один ключ одного принципала
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeytabEntry {
+    pub principal: PrincipalName,
+    pub realm: String,
+    /// Время записи ключа, секунды Unix
+    pub timestamp: u32,
+    pub kvno: u32,
+    pub etype: i32,
+    pub key: Vec<u8>,
+}
+
+impl KeytabEntry {
+    pub fn new(principal: &PrincipalName, realm: &str, key: &KerberosKey, timestamp: u32) -> Self {
+        Self {
+            principal: principal.clone(),
+            realm: realm.to_string(),
+            timestamp,
+            kvno: key.kvno,
+            etype: key.etype,
+            key: key.key.clone(),
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(self.principal.components.len() as u16).to_be_bytes());
+        put_data(&mut out, self.realm.as_bytes());
+        for component in &self.principal.components {
+            put_data(&mut out, component.as_bytes());
+        }
+        out.extend_from_slice(&(self.principal.name_type as u32).to_be_bytes());
+        out.extend_from_slice(&self.timestamp.to_be_bytes());
+        // 8-битный kvno устарел, полный номер — в хвосте записи
+        out.push(self.kvno as u8);
+        out.extend_from_slice(&(self.etype as u16).to_be_bytes());
+        put_data(&mut out, &self.key);
+        out.extend_from_slice(&self.kvno.to_be_bytes());
+        out
+    }
+}
+
+/// Файл keytab целиком
+pub fn encode(entries: &[KeytabEntry]) -> Vec<u8> {
+    let mut out = KEYTAB_VERSION.to_be_bytes().to_vec();
+    for entry in entries {
+        let record = entry.encode();
+        out.extend_from_slice(&(record.len() as i32).to_be_bytes());
+        out.extend_from_slice(&record);
+    }
+    out
+}
+
+fn put_data(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    out.extend_from_slice(data);
+}
@@ -0,0 +1,449 @@
+// src/kerberos/messages.rs
+
+//! Структуры сообщений Kerberos V5, необходимые для обменов AS и TGS.
+
+use chrono::{DateTime, Utc};
+
+use super::der::{self, Tlv};
+use super::KerberosError;
+
+pub const PVNO: i64 = 5;
+
+pub const MSG_AS_REQ: i64 = 10;
+pub const MSG_AS_REP: i64 = 11;
+pub const MSG_TGS_REQ: i64 = 12;
+pub const MSG_TGS_REP: i64 = 13;
+pub const MSG_AP_REQ: i64 = 14;
+pub const MSG_KRB_ERROR: i64 = 30;
+
+pub const PA_TGS_REQ: i32 = 1;
+pub const PA_ENC_TIMESTAMP: i32 = 2;
+pub const PA_ETYPE_INFO2: i32 = 19;
+
+pub const NT_PRINCIPAL: i32 = 1;
+pub const NT_SRV_INST: i32 = 2;
+
+// Флаги билета (бит 0 — старший)
+pub const FLAG_FORWARDABLE: u32 = 1 << 30;
+pub const FLAG_RENEWABLE: u32 = 1 << 23;
+pub const FLAG_INITIAL: u32 = 1 << 22;
+pub const FLAG_PRE_AUTHENT: u32 = 1 << 21;
+
+/// Имя принципала: тип и компоненты (`HTTP/web01` → ["HTTP", "web01"])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrincipalName {
+    pub name_type: i32,
+    pub components: Vec<String>,
+}
+
+impl PrincipalName {
+    pub fn principal(name: &str) -> Self {
+        Self { name_type: NT_PRINCIPAL, components: vec![name.to_string()] }
+    }
+
+    pub fn service(service: &str, instance: &str) -> Self {
+        Self {
+            name_type: NT_SRV_INST,
+            components: vec![service.to_string(), instance.to_string()],
+        }
+    }
+
+    pub fn decode(tlv: &Tlv) -> Result<Self, KerberosError> {
+        let name_type = tlv.required(0)?.as_integer()? as i32;
+        let components = tlv
+            .required(1)?
+            .children()?
+            .iter()
+            .map(|c| c.as_string())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { name_type, components })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let names: Vec<Vec<u8>> = self.components.iter().map(|c| der::general_string(c)).collect();
+        der::sequence(&[
+            der::context(0, der::integer(self.name_type as i64)),
+            der::context(1, der::sequence(&names)),
+        ])
+    }
+
+    /// Компоненты через `/`, без realm
+    pub fn to_text(&self) -> String {
+        self.components.join("/")
+    }
+}
+
+/// Зашифрованный блок данных
+#[derive(Debug, Clone)]
+pub struct EncryptedData {
+    pub etype: i32,
+    pub kvno: Option<u32>,
+    pub cipher: Vec<u8>,
+}
+
+impl EncryptedData {
+    pub fn decode(tlv: &Tlv) -> Result<Self, KerberosError> {
+        Ok(Self {
+            etype: tlv.required(0)?.as_integer()? as i32,
+            kvno: tlv.field(1)?.map(|t| t.as_integer()).transpose()?.map(|v| v as u32),
+            cipher: tlv.required(2)?.as_bytes()?.to_vec(),
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut items = vec![der::context(0, der::integer(self.etype as i64))];
+        if let Some(kvno) = self.kvno {
+            items.push(der::context(1, der::integer(kvno as i64)));
+        }
+        items.push(der::context(2, der::octet_string(&self.cipher)));
+        der::sequence(&items)
+    }
+}
+
+/// Элемент PA-DATA
+#[derive(Debug, Clone)]
+pub struct PaData {
+    pub padata_type: i32,
+    pub value: Vec<u8>,
+}
+
+impl PaData {
+    pub fn decode(tlv: &Tlv) -> Result<Self, KerberosError> {
+        Ok(Self {
+            padata_type: tlv.required(1)?.as_integer()? as i32,
+            value: tlv.required(2)?.as_bytes()?.to_vec(),
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        der::sequence(&[
+            der::context(1, der::integer(self.padata_type as i64)),
+            der::context(2, der::octet_string(&self.value)),
+        ])
+    }
+}
+
+/// Билет (Ticket ::= [APPLICATION 1])
+#[derive(Debug, Clone)]
+pub struct Ticket {
+    pub realm: String,
+    pub sname: PrincipalName,
+    pub enc_part: EncryptedData,
+}
+
+impl Ticket {
+    pub fn decode(tlv: &Tlv) -> Result<Self, KerberosError> {
+        let seq = tlv.unwrap_application(1)?;
+        Ok(Self {
+            realm: seq.required(1)?.as_string()?,
+            sname: PrincipalName::decode(&seq.required(2)?)?,
+            enc_part: EncryptedData::decode(&seq.required(3)?)?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        der::application(1, der::sequence(&[
+            der::context(0, der::integer(PVNO)),
+            der::context(1, der::general_string(&self.realm)),
+            der::context(2, self.sname.encode()),
+            der::context(3, self.enc_part.encode()),
+        ]))
+    }
+}
+
+/// Сеансовый ключ (EncryptionKey)
+#[derive(Debug, Clone)]
+pub struct EncryptionKey {
+    pub keytype: i32,
+    pub value: Vec<u8>,
+}
+
+impl EncryptionKey {
+    pub fn decode(tlv: &Tlv) -> Result<Self, KerberosError> {
+        Ok(Self {
+            keytype: tlv.required(0)?.as_integer()? as i32,
+            value: tlv.required(1)?.as_bytes()?.to_vec(),
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        der::sequence(&[
+            der::context(0, der::integer(self.keytype as i64)),
+            der::context(1, der::octet_string(&self.value)),
+        ])
+    }
+}
+
+/// Зашифрованная часть билета (EncTicketPart ::= [APPLICATION 3])
+#[derive(Debug, Clone)]
+pub struct EncTicketPart {
+    pub flags: u32,
+    pub key: EncryptionKey,
+    pub crealm: String,
+    pub cname: PrincipalName,
+    pub authtime: DateTime<Utc>,
+    pub starttime: Option<DateTime<Utc>>,
+    pub endtime: DateTime<Utc>,
+    pub renew_till: Option<DateTime<Utc>>,
+}
+
+impl EncTicketPart {
+    pub fn decode(data: &[u8]) -> Result<Self, KerberosError> {
+        let seq = Tlv::parse(data)?.unwrap_application(3)?;
+        Ok(Self {
+            flags: seq.required(0)?.as_flags()?,
+            key: EncryptionKey::decode(&seq.required(1)?)?,
+            crealm: seq.required(2)?.as_string()?,
+            cname: PrincipalName::decode(&seq.required(3)?)?,
+            authtime: seq.required(5)?.as_time()?,
+            starttime: seq.field(6)?.map(|t| t.as_time()).transpose()?,
+            endtime: seq.required(7)?.as_time()?,
+            renew_till: seq.field(8)?.map(|t| t.as_time()).transpose()?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut items = vec![
+            der::context(0, der::flags(self.flags)),
+            der::context(1, self.key.encode()),
+            der::context(2, der::general_string(&self.crealm)),
+            der::context(3, self.cname.encode()),
+            // transited: пустая кодировка DOMAIN-X500-COMPRESS
+            der::context(4, der::sequence(&[
+                der::context(0, der::integer(1)),
+                der::context(1, der::octet_string(&[])),
+            ])),
+            der::context(5, der::time(&self.authtime)),
+        ];
+        if let Some(start) = &self.starttime {
+            items.push(der::context(6, der::time(start)));
+        }
+        items.push(der::context(7, der::time(&self.endtime)));
+        if let Some(renew) = &self.renew_till {
+            items.push(der::context(8, der::time(renew)));
+        }
+        der::application(3, der::sequence(&items))
+    }
+}
+
+/// Тело запроса к KDC (KDC-REQ-BODY)
+#[derive(Debug, Clone)]
+pub struct KdcReqBody {
+    pub kdc_options: u32,
+    pub cname: Option<PrincipalName>,
+    pub realm: String,
+    pub sname: Option<PrincipalName>,
+    pub till: Option<DateTime<Utc>>,
+    pub rtime: Option<DateTime<Utc>>,
+    pub nonce: u32,
+    pub etypes: Vec<i32>,
+}
+
+/// Запрос AS-REQ или TGS-REQ
+#[derive(Debug, Clone)]
+pub struct KdcReq {
+    pub msg_type: i64,
+    pub padata: Vec<PaData>,
+    pub body: KdcReqBody,
+}
+
+impl KdcReq {
+    pub fn decode(tlv: &Tlv) -> Result<Self, KerberosError> {
+        let seq = Tlv::parse(&tlv.value)?;
+        let msg_type = seq.required(2)?.as_integer()?;
+
+        let padata = match seq.field(3)? {
+            Some(list) => list.children()?.iter().map(PaData::decode).collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+
+        let body = seq.required(4)?;
+        let etypes = body
+            .required(8)?
+            .children()?
+            .iter()
+            .map(|e| e.as_integer().map(|v| v as i32))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            msg_type,
+            padata,
+            body: KdcReqBody {
+                kdc_options: body.required(0)?.as_flags()?,
+                cname: body.field(1)?.map(|t| PrincipalName::decode(&t)).transpose()?,
+                realm: body.required(2)?.as_string()?,
+                sname: body.field(3)?.map(|t| PrincipalName::decode(&t)).transpose()?,
+                till: body.field(5)?.map(|t| t.as_time()).transpose()?,
+                rtime: body.field(6)?.map(|t| t.as_time()).transpose()?,
+                nonce: body.required(7)?.as_integer()? as u32,
+                etypes,
+            },
+        })
+    }
+
+    pub fn find_padata(&self, padata_type: i32) -> Option<&PaData> {
+        self.padata.iter().find(|p| p.padata_type == padata_type)
+    }
+}
+
+/// Зашифрованная часть ответа KDC (EncKDCRepPart)
+#[derive(Debug, Clone)]
+pub struct EncKdcRepPart {
+    pub key: EncryptionKey,
+    pub nonce: u32,
+    pub flags: u32,
+    pub authtime: DateTime<Utc>,
+    pub starttime: Option<DateTime<Utc>>,
+    pub endtime: DateTime<Utc>,
+    pub renew_till: Option<DateTime<Utc>>,
+    pub srealm: String,
+    pub sname: PrincipalName,
+}
+
+impl EncKdcRepPart {
+    /// `application` — 25 для EncASRepPart, 26 для EncTGSRepPart
+    pub fn encode(&self, application: u8) -> Vec<u8> {
+        let last_req = der::sequence(&[der::sequence(&[
+            der::context(0, der::integer(0)),
+            der::context(1, der::time(&self.authtime)),
+        ])]);
+        let mut items = vec![
+            der::context(0, self.key.encode()),
+            der::context(1, last_req),
+            der::context(2, der::integer(self.nonce as i64)),
+            der::context(4, der::flags(self.flags)),
+            der::context(5, der::time(&self.authtime)),
+        ];
+        if let Some(start) = &self.starttime {
+            items.push(der::context(6, der::time(start)));
+        }
+        items.push(der::context(7, der::time(&self.endtime)));
+        if let Some(renew) = &self.renew_till {
+            items.push(der::context(8, der::time(renew)));
+        }
+        items.push(der::context(9, der::general_string(&self.srealm)));
+        items.push(der::context(10, self.sname.encode()));
+        der::application(application, der::sequence(&items))
+    }
+}
+
+/// Ответ AS-REP или TGS-REP
+#[derive(Debug, Clone)]
+pub struct KdcRep {
+    pub msg_type: i64,
+    pub padata: Vec<PaData>,
+    pub crealm: String,
+    pub cname: PrincipalName,
+    pub ticket: Ticket,
+    pub enc_part: EncryptedData,
+}
+
+impl KdcRep {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut items = vec![
+            der::context(0, der::integer(PVNO)),
+            der::context(1, der::integer(self.msg_type)),
+        ];
+        if !self.padata.is_empty() {
+            let list: Vec<Vec<u8>> = self.padata.iter().map(PaData::encode).collect();
+            items.push(der::context(2, der::sequence(&list)));
+        }
+        items.push(der::context(3, der::general_string(&self.crealm)));
+        items.push(der::context(4, self.cname.encode()));
+        items.push(der::context(5, self.ticket.encode()));
+        items.push(der::context(6, self.enc_part.encode()));
+        der::application(self.msg_type as u8, der::sequence(&items))
+    }
+}
+
+/// Запрос аутентификации приложения (AP-REQ ::= [APPLICATION 14])
+#[derive(Debug, Clone)]
+pub struct ApReq {
+    pub ticket: Ticket,
+    pub authenticator: EncryptedData,
+}
+
+impl ApReq {
+    pub fn decode(data: &[u8]) -> Result<Self, KerberosError> {
+        let seq = Tlv::parse(data)?.unwrap_application(14)?;
+        if seq.required(1)?.as_integer()? != MSG_AP_REQ {
+            return Err(KerberosError::Decode("Expected AP-REQ".to_string()));
+        }
+        Ok(Self {
+            ticket: Ticket::decode(&seq.required(3)?)?,
+            authenticator: EncryptedData::decode(&seq.required(4)?)?,
+        })
+    }
+}
+
+/// Аутентификатор клиента (Authenticator ::= [APPLICATION 2])
+#[derive(Debug, Clone)]
+pub struct Authenticator {
+    pub crealm: String,
+    pub cname: PrincipalName,
+    pub ctime: DateTime<Utc>,
+    /// Подключ клиента: если задан, ответ TGS шифруется им (key usage 9)
+    pub subkey: Option<EncryptionKey>,
+}
+
+impl Authenticator {
+    pub fn decode(data: &[u8]) -> Result<Self, KerberosError> {
+        let seq = Tlv::parse(data)?.unwrap_application(2)?;
+        Ok(Self {
+            crealm: seq.required(1)?.as_string()?,
+            cname: PrincipalName::decode(&seq.required(2)?)?,
+            ctime: seq.required(5)?.as_time()?,
+            subkey: seq.field(6)?.map(|t| EncryptionKey::decode(&t)).transpose()?,
+        })
+    }
+}
+
+/// Метка времени предварительной аутентификации (PA-ENC-TS-ENC)
+pub fn decode_pa_enc_timestamp(data: &[u8]) -> Result<DateTime<Utc>, KerberosError> {
+    Tlv::parse(data)?.required(0)?.as_time()
+}
+
+/// PA-ETYPE-INFO2 с одной записью: подсказка клиенту о типе ключа и соли
+pub fn encode_etype_info2(etype: i32, salt: &str) -> Vec<u8> {
+    der::sequence(&[der::sequence(&[
+        der::context(0, der::integer(etype as i64)),
+        der::context(1, der::general_string(salt)),
+    ])])
+}
+
+/// Сообщение об ошибке (KRB-ERROR ::= [APPLICATION 30])
+#[derive(Debug, Clone)]
+pub struct KrbError {
+    pub error_code: i32,
+    pub realm: String,
+    pub sname: PrincipalName,
+    pub cname: Option<PrincipalName>,
+    pub text: Option<String>,
+    pub e_data: Option<Vec<u8>>,
+}
+
+impl KrbError {
+    pub fn encode(&self) -> Vec<u8> {
+        let now = Utc::now();
+        let mut items = vec![
+            der::context(0, der::integer(PVNO)),
+            der::context(1, der::integer(MSG_KRB_ERROR)),
+            der::context(4, der::time(&now)),
+            der::context(5, der::integer(0)),
+            der::context(6, der::integer(self.error_code as i64)),
+        ];
+        if let Some(cname) = &self.cname {
+            items.push(der::context(7, der::general_string(&self.realm)));
+            items.push(der::context(8, cname.encode()));
+        }
+        items.push(der::context(9, der::general_string(&self.realm)));
+        items.push(der::context(10, self.sname.encode()));
+        if let Some(text) = &self.text {
+            items.push(der::context(11, der::general_string(text)));
+        }
+        if let Some(e_data) = &self.e_data {
+            items.push(der::context(12, der::octet_string(e_data)));
+        }
+        der::application(30, der::sequence(&items))
+    }
+}
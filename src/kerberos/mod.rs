@@ -0,0 +1,181 @@
+// src/kerberos/mod.rs
+
+//! Минимальный KDC (RFC 4120): обмен AS выдаёт TGT, обмен TGS — билеты служб.
+//! Ключи принципалов хранятся в каталоге и выводятся из пароля при его установке.
+//!
+//! Известные ограничения обмена TGS:
+//! - контрольная сумма аутентификатора над KDC-REQ-BODY не проверяется: тело запроса (служба,
+//!   срок билета) не защищено от подмены по пути к KDC;
+//! - кэша повторов нет: тот же аутентификатор принимается повторно, пока не вышло допустимое
+//!   расхождение часов.
+
+pub mod crypto;
+pub mod der;
+pub mod kdc;
+pub mod keytab;
+pub mod messages;
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use futures_util::future::{try_join_all, BoxFuture};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+pub use kdc::Kdc;
+
+// === Коды ошибок KDC (RFC 4120, 7.5.9) ===
+
+pub const KDC_ERR_C_PRINCIPAL_UNKNOWN: i32 = 6;
+pub const KDC_ERR_S_PRINCIPAL_UNKNOWN: i32 = 7;
+pub const KDC_ERR_ETYPE_NOSUPP: i32 = 14;
+pub const KDC_ERR_PADATA_TYPE_NOSUPP: i32 = 16;
+pub const KDC_ERR_CLIENT_REVOKED: i32 = 18;
+pub const KDC_ERR_SERVICE_REVOKED: i32 = 20;
+pub const KDC_ERR_KEY_EXPIRED: i32 = 23;
+pub const KDC_ERR_PREAUTH_FAILED: i32 = 24;
+pub const KDC_ERR_PREAUTH_REQUIRED: i32 = 25;
+pub const KRB_AP_ERR_BAD_INTEGRITY: i32 = 31;
+pub const KRB_AP_ERR_TKT_EXPIRED: i32 = 32;
+pub const KRB_AP_ERR_BADMATCH: i32 = 36;
+pub const KRB_AP_ERR_SKEW: i32 = 37;
+pub const KRB_ERR_GENERIC: i32 = 60;
+pub const KDC_ERR_WRONG_REALM: i32 = 68;
+
+/// Максимальный размер сообщения по TCP
+const MAX_TCP_MESSAGE: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub enum KerberosError {
+    Io(std::io::Error),
+    Decode(String),
+    Crypto(String),
+    Integrity,
+    Directory(DirectoryError),
+    /// Ошибка протокола с кодом KRB-ERROR
+    Kdc(i32, String),
+    /// AS-REQ без PA-ENC-TIMESTAMP: клиент получает в e-data METHOD-DATA с PA-ETYPE-INFO2 —
+    /// etype и соль, из которых он выведет ключ
+    PreauthRequired { etype: i32, salt: String },
+}
+
+impl KerberosError {
+    /// Код, передаваемый клиенту в KRB-ERROR
+    pub fn error_code(&self) -> i32 {
+        match self {
+            KerberosError::Kdc(code, _) => *code,
+            KerberosError::PreauthRequired { .. } => KDC_ERR_PREAUTH_REQUIRED,
+            KerberosError::Integrity => KRB_AP_ERR_BAD_INTEGRITY,
+            _ => KRB_ERR_GENERIC,
+        }
+    }
+}
+
+impl From<std::io::Error> for KerberosError {
+    fn from(e: std::io::Error) -> Self {
+        KerberosError::Io(e)
+    }
+}
+
+impl From<DirectoryError> for KerberosError {
+    fn from(e: DirectoryError) -> Self {
+        KerberosError::Directory(e)
+    }
+}
+
+impl std::fmt::Display for KerberosError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KerberosError::Io(e) => write!(f, "IO error: {}", e),
+            KerberosError::Decode(e) => write!(f, "Decode error: {}", e),
+            KerberosError::Crypto(e) => write!(f, "Crypto error: {}", e),
+            KerberosError::Integrity => write!(f, "Integrity check failed"),
+            KerberosError::Directory(e) => write!(f, "Directory error: {}", e),
+            KerberosError::Kdc(code, e) => write!(f, "KDC error {}: {}", code, e),
+            KerberosError::PreauthRequired { .. } => write!(f, "KDC error {}: Pre-authentication required", KDC_ERR_PREAUTH_REQUIRED),
+        }
+    }
+}
+
+impl std::error::Error for KerberosError {}
+
+/// Realm по суффиксу UPN (`alice@corp.acme.com` → `CORP.ACME.COM`)
+pub fn realm_from_upn(upn: &str) -> Option<String> {
+    upn.rsplit_once('@').map(|(_, domain)| domain.to_uppercase())
+}
+
+/// Сетевой фронтенд KDC: UDP и TCP на каждом адресе (`0.0.0.0:88`, `[::]:88`)
+pub struct KdcServer {
+    kdc: Arc<Kdc>,
+    udp: Vec<UdpSocket>,
+    tcp: Vec<TcpListener>,
+}
+
+impl KdcServer {
+    pub async fn bind(service: Arc<DirectoryService>, addrs: &[String], realm: &str) -> Result<Self, KerberosError> {
+        let kdc = Kdc::new(service, realm);
+        kdc.ensure_krbtgt().await?;
+
+        let (mut udp, mut tcp) = (Vec::with_capacity(addrs.len()), Vec::with_capacity(addrs.len()));
+        for addr in addrs {
+            udp.push(crate::net::bind_udp(addr).await?);
+            tcp.push(crate::net::bind_tcp(addr).await?);
+        }
+        Ok(Self { kdc: Arc::new(kdc), udp, tcp })
+    }
+
+    pub async fn run(self) -> Result<(), KerberosError> {
+        let mut tasks: Vec<BoxFuture<'static, Result<(), KerberosError>>> = Vec::new();
+        for udp in self.udp {
+            tracing::info!(realm = %self.kdc.realm(), addr = %udp.local_addr()?, "KDC слушает");
+            tasks.push(Box::pin(serve_udp(udp, Arc::clone(&self.kdc))));
+        }
+        for tcp in self.tcp {
+            tasks.push(Box::pin(serve_tcp(tcp, Arc::clone(&self.kdc))));
+        }
+        // Сокеты работают, пока не откажет любой из них
+        try_join_all(tasks).await.map(|_| ())
+    }
+}
+
+async fn serve_udp(udp: UdpSocket, kdc: Arc<Kdc>) -> Result<(), KerberosError> {
+    let mut buf = vec![0u8; MAX_TCP_MESSAGE];
+    loop {
+        let (n, peer) = udp.recv_from(&mut buf).await?;
+        let reply = kdc.handle(&buf[..n], Some(crate::net::client_ip(peer))).await;
+        udp.send_to(&reply, peer).await?;
+    }
+}
+
+async fn serve_tcp(tcp: TcpListener, kdc: Arc<Kdc>) -> Result<(), KerberosError> {
+    loop {
+        let (socket, peer) = tcp.accept().await?;
+        let kdc = Arc::clone(&kdc);
+        tokio::spawn(async move {
+            if let Err(e) = handle_tcp_client(socket, crate::net::client_ip(peer), kdc).await {
+                tracing::warn!(error = %e, "Ошибка TCP-клиента KDC");
+            }
+        });
+    }
+}
+
+/// По TCP каждое сообщение предваряется 4-байтной длиной (RFC 4120, 7.2.2)
+async fn handle_tcp_client(mut socket: TcpStream, peer: IpAddr, kdc: Arc<Kdc>) -> Result<(), KerberosError> {
+    loop {
+        let len = match socket.read_u32().await {
+            Ok(len) => len as usize,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if len > MAX_TCP_MESSAGE {
+            return Err(KerberosError::Decode("TCP message too large".to_string()));
+        }
+
+        let mut request = vec![0u8; len];
+        socket.read_exact(&mut request).await?;
+
+        let reply = kdc.handle(&request, Some(peer)).await;
+        socket.write_u32(reply.len() as u32).await?;
+        socket.write_all(&reply).await?;
+    }
+}
@@ -11,6 +11,14 @@ pub enum Asn1 {
     Set(Vec<Asn1>),
     Boolean(bool),
     Null,
+    /// Контекстный тег `[n]` составного типа, например Controls сообщения (`[0]`)
+    Context(u8, Vec<Asn1>),
+    /// Контекстный тег `[n]` простого типа, например пароль simple bind (`[0]`)
+    ContextPrimitive(u8, Vec<u8>),
+    /// Тег `[APPLICATION n]` составного типа — операция LDAP, например BindRequest (`[APPLICATION 0]`)
+    Application(u8, Vec<Asn1>),
+    /// Тег `[APPLICATION n]` простого типа, например UnbindRequest (`[APPLICATION 2]`)
+    ApplicationPrimitive(u8, Vec<u8>),
 }
 
 #[derive(Debug)]
@@ -68,8 +76,26 @@ impl Asn1Parser {
                 }
                 Asn1::Set(items)
             }
-            0x01 => Asn1::Boolean(content[0] != 0),
+            0x01 => Asn1::Boolean(content.first().is_some_and(|b| *b != 0)),
             0x05 => Asn1::Null,
+            0xA0..=0xBF => {
+                let mut parser = Asn1Parser::new(content);
+                let mut items = Vec::new();
+                while let Some(item) = parser.parse()? {
+                    items.push(item);
+                }
+                Asn1::Context(tag & 0x1F, items)
+            }
+            0x80..=0x9F => Asn1::ContextPrimitive(tag & 0x1F, content),
+            0x60..=0x7F => {
+                let mut parser = Asn1Parser::new(content);
+                let mut items = Vec::new();
+                while let Some(item) = parser.parse()? {
+                    items.push(item);
+                }
+                Asn1::Application(tag & 0x1F, items)
+            }
+            0x40..=0x5F => Asn1::ApplicationPrimitive(tag & 0x1F, content),
             _ => return Err(Asn1Error::UnsupportedType(tag)),
         };
 
@@ -100,6 +126,19 @@ impl Asn1Parser {
     }
 }
 
+/// Длина первого элемента в буфере вместе с тегом и длиной; None — заголовок или содержимое
+/// ещё не пришли целиком
+pub fn element_length(data: &[u8]) -> Option<usize> {
+    let len_byte = *data.get(1)?;
+    if len_byte & 0x80 == 0 {
+        return Some(2 + len_byte as usize).filter(|total| *total <= data.len());
+    }
+    let num_bytes = (len_byte & 0x7F) as usize;
+    let header = 2 + num_bytes;
+    let length = data.get(2..header)?.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+    Some(header + length).filter(|total| *total <= data.len())
+}
+
 fn decode_integer(bytes: &[u8]) -> Result<i64, Asn1Error> {
     if bytes.is_empty() {
         return Ok(0);
@@ -108,9 +147,9 @@ fn decode_integer(bytes: &[u8]) -> Result<i64, Asn1Error> {
     for &b in bytes {
         val = (val << 8) | (b as i64);
     }
-    // Если старший бит установлен, это отрицательное число
-    if bytes[0] & 0x80 != 0 {
-        val -= (1i64 << (bytes.len() * 8)) as i64;
+    // Если старший бит установлен, это отрицательное число; восемь байт уже дают знак сами
+    if bytes[0] & 0x80 != 0 && bytes.len() < 8 {
+        val -= 1i64 << (bytes.len() * 8);
     }
     Ok(val)
 }
@@ -1,7 +1,9 @@
 // src/ldap/filter.rs
 
 use crate::models::*;
-use crate::directory_service::DirectoryService;
+use crate::models::ldap_entry::{parse_object_guid, OBJECT_GUID};
+use crate::directory_service::{DirectoryError, DirectoryService};
+use super::asn1::Asn1;
 
 #[derive(Debug, Clone)]
 pub enum Filter {
@@ -31,6 +33,58 @@ impl Filter {
         Self::parse_inner(&s[1..s.len() - 1])
     }
 
+    /// Фильтр SearchRequest в кодировке BER (RFC 4511, 4.5.1)
+    pub fn from_asn1(filter: &Asn1) -> Result<Self, LdapFilterError> {
+        let assertion = |items: &[Asn1]| match items {
+            [Asn1::OctetString(attr), Asn1::OctetString(value)] => Ok((text(attr), assertion_value(value))),
+            _ => Err(LdapFilterError::InvalidSyntax),
+        };
+        let list = |items: &[Asn1]| items.iter().map(Self::from_asn1).collect::<Result<Vec<_>, _>>();
+        match filter {
+            Asn1::Context(0, items) => Ok(Filter::And(list(items)?)),
+            Asn1::Context(1, items) => Ok(Filter::Or(list(items)?)),
+            Asn1::Context(2, items) => match items.as_slice() {
+                [inner] => Ok(Filter::Not(Box::new(Self::from_asn1(inner)?))),
+                _ => Err(LdapFilterError::InvalidSyntax),
+            },
+            Asn1::Context(3, items) => assertion(items).map(|(attr, value)| Filter::Equality(attr, value)),
+            Asn1::Context(4, items) => {
+                let [Asn1::OctetString(attr), Asn1::Sequence(parts)] = items.as_slice() else {
+                    return Err(LdapFilterError::InvalidSyntax);
+                };
+                let (mut initial, mut any, mut final_) = (None, Vec::new(), None);
+                for part in parts {
+                    match part {
+                        Asn1::ContextPrimitive(0, value) => initial = Some(text(value)),
+                        Asn1::ContextPrimitive(1, value) => any.push(text(value)),
+                        Asn1::ContextPrimitive(2, value) => final_ = Some(text(value)),
+                        _ => return Err(LdapFilterError::InvalidSyntax),
+                    }
+                }
+                Ok(Filter::Substring { attr: text(attr), initial, any, final_ })
+            }
+            Asn1::Context(5, items) => assertion(items).map(|(attr, value)| Filter::GreaterOrEqual(attr, value)),
+            Asn1::Context(6, items) => assertion(items).map(|(attr, value)| Filter::LessOrEqual(attr, value)),
+            Asn1::ContextPrimitive(7, attr) => Ok(Filter::Present(text(attr))),
+            Asn1::Context(8, items) => assertion(items).map(|(attr, value)| Filter::ApproxMatch(attr, value)),
+            Asn1::Context(9, items) => {
+                let (mut attr, mut rule, mut dn_attrs, mut value) = (String::new(), None, false, None);
+                for item in items {
+                    match item {
+                        Asn1::ContextPrimitive(1, data) => rule = Some(text(data)),
+                        Asn1::ContextPrimitive(2, data) => attr = text(data),
+                        Asn1::ContextPrimitive(3, data) => value = Some(assertion_value(data)),
+                        Asn1::ContextPrimitive(4, data) => dn_attrs = data.first().is_some_and(|b| *b != 0),
+                        _ => return Err(LdapFilterError::InvalidSyntax),
+                    }
+                }
+                let value = value.ok_or(LdapFilterError::InvalidSyntax)?;
+                Ok(Filter::Extensible { attr, rule, dn_attrs, value })
+            }
+            _ => Err(LdapFilterError::InvalidSyntax),
+        }
+    }
+
     fn parse_inner(s: &str) -> Result<Self, LdapFilterError> {
         match s.chars().next() {
             Some('&') => Self::parse_list(&s[1..], Filter::And),
@@ -101,10 +155,10 @@ impl Filter {
                 any.push(part.to_string());
             }
         }
-        if let Some(last) = parts.last() {
-            if !last.is_empty() {
-                final_ = Some(last.to_string());
-            }
+        if let Some(last) = parts.last()
+            && !last.is_empty()
+        {
+            final_ = Some(last.to_string());
         }
 
         Ok(Filter::Substring {
@@ -123,7 +177,7 @@ impl Filter {
         let mut depth = 0;
         let mut start = 0;
 
-        for (i, ch) in s.chars().enumerate() {
+        for (i, ch) in s.char_indices() {
             match ch {
                 '(' => {
                     if depth == 0 {
@@ -151,26 +205,21 @@ impl Filter {
         Ok(constructor(filters))
     }
 
+    /// Фильтр по записи пользователя; tokenGroups не входит в запись и читается из каталога
     pub async fn matches_user_with_service(
         &self,
         user: &User,
+        entry: &LdapAttributes,
         service: &DirectoryService,
     ) -> Result<bool, LdapFilterError> {
         match self {
-            Filter::Present(attr) if attr == "tokenGroups" => {
+            Filter::Present(attr) if attr.eq_ignore_ascii_case(constructed::TOKEN_GROUPS) => {
                 let tokens = service.get_token_groups(user.id).await?;
                 Ok(!tokens.is_empty())
             }
-            Filter::Equality(attr, value) if attr == "memberOf" => {
-                let groups = service.find_groups_by_member(user.id).await?;
-                let target_dn = value.to_uppercase();
-                Ok(groups.iter().any(|g| {
-                    DirectoryService::generate_group_dn(&g.sam_account_name, &Domain::default()).to_uppercase() == target_dn
-                }))
-            }
             Filter::And(filters) => {
                 for f in filters {
-                    if !f.matches_user_with_service(user, service).await? {
+                    if !Box::pin(f.matches_user_with_service(user, entry, service)).await? {
                         return Ok(false);
                     }
                 }
@@ -178,14 +227,14 @@ impl Filter {
             }
             Filter::Or(filters) => {
                 for f in filters {
-                    if f.matches_user_with_service(user, service).await? {
+                    if Box::pin(f.matches_user_with_service(user, entry, service)).await? {
                         return Ok(true);
                     }
                 }
                 Ok(false)
             }
-            Filter::Not(filter) => Ok(!filter.matches_user_with_service(user, service).await?),
-            _ => Ok(self.matches_user(user)),
+            Filter::Not(filter) => Ok(!Box::pin(filter.matches_user_with_service(user, entry, service)).await?),
+            _ => Ok(self.matches_entry(entry)),
         }
     }
 
@@ -193,9 +242,10 @@ impl Filter {
         match self {
             Filter::Equality(attr, value) => match attr.as_str() {
                 "sAMAccountName" => user.username.eq_ignore_ascii_case(value),
-                "cn" | "name" => user.display_name.as_ref().map_or(false, |n| n.eq_ignore_ascii_case(value)),
-                "mail" | "email" => user.email.as_ref().map_or(false, |e| e.eq_ignore_ascii_case(value)),
+                "cn" | "name" => user.display_name.as_ref().is_some_and(|n| n.eq_ignore_ascii_case(value)),
+                "mail" | "email" => user.email.as_ref().is_some_and(|e| e.eq_ignore_ascii_case(value)),
                 "userPrincipalName" => user.user_principal_name.eq_ignore_ascii_case(value),
+                "objectGUID" => parse_object_guid(value) == Some(user.id),
                 "objectClass" => matches_object_class(value, &["user", "person"]),
                 _ => false,
             },
@@ -241,12 +291,58 @@ impl Filter {
     }
 }
 
+impl Filter {
+    /// Фильтр по готовой LDAP-записи (`LdapEntry`): атрибуты и значения без учёта регистра
+    pub fn matches_entry(&self, entry: &LdapAttributes) -> bool {
+        let values = |attr: &str| entry.text(attr);
+        match self {
+            Filter::And(filters) => filters.iter().all(|f| f.matches_entry(entry)),
+            Filter::Or(filters) => filters.iter().any(|f| f.matches_entry(entry)),
+            Filter::Not(filter) => !filter.matches_entry(entry),
+            Filter::Equality(attr, value) if attr.eq_ignore_ascii_case("anr") => LdapFilter::anr(value).matches(entry),
+            Filter::Equality(attr, value) if attr.eq_ignore_ascii_case(OBJECT_GUID) => {
+                parse_object_guid(value).is_some_and(|guid| entry.object_guid() == Some(guid))
+            }
+            Filter::Equality(attr, value) => values(attr).iter().any(|v| v.eq_ignore_ascii_case(value)),
+            Filter::Present(attr) => !values(attr).is_empty(),
+            Filter::Substring { attr, initial, any, final_ } => values(attr).iter().any(|text| {
+                let text = text.to_lowercase();
+                initial.as_ref().is_none_or(|init| text.starts_with(&init.to_lowercase()))
+                    && any.iter().all(|part| text.contains(&part.to_lowercase()))
+                    && final_.as_ref().is_none_or(|fin| text.ends_with(&fin.to_lowercase()))
+            }),
+            _ => false,
+        }
+    }
+}
+
 fn matches_object_class(value: &str, valid: &[&str]) -> bool {
     valid.iter().any(|&cls| cls.eq_ignore_ascii_case(value))
 }
 
+/// Строка из значения фильтра
+fn text(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).to_string()
+}
+
+/// Значение утверждения фильтра: строка как есть, двоичное (objectGUID) — в экранированной
+/// форме `\xx\xx…` (RFC 4515), как его понимают сравнения фильтра
+fn assertion_value(data: &[u8]) -> String {
+    match std::str::from_utf8(data) {
+        Ok(value) if !value.chars().any(char::is_control) => value.to_string(),
+        _ => data.iter().map(|b| format!("\\{:02x}", b)).collect(),
+    }
+}
+
 #[derive(Debug)]
 pub enum LdapFilterError {
     InvalidSyntax,
     NotImplemented,
-}
\ No newline at end of file
+    /// Каталог не ответил при проверке фильтра
+    Directory(DirectoryError),
+}
+impl From<DirectoryError> for LdapFilterError {
+    fn from(e: DirectoryError) -> Self {
+        LdapFilterError::Directory(e)
+    }
+}
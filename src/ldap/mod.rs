@@ -3,20 +3,64 @@
 pub mod asn1;
 pub mod filter;
 
-use crate::directory_service::DirectoryService;
-use crate::models::{User, Domain, OrganizationalUnit};
+use crate::audit::actor::ActorContext;
+use crate::config::LdapServerConfig;
+use crate::directory_service::{DirectoryError, DirectoryService, SearchLimits};
+use crate::models::{
+    well_known, AccessMask, ChangeEntry, ChangeType, DirSyncCookie, LdapAttributes, LdapContext, LdapEntry, LoginProtocol, SecuredObject,
+    SecurityIdentifier, User,
+};
+use crate::models::constructed;
+use crate::models::ldap_entry::{object_guid_bytes, OBJECT_GUID};
+use crate::proxy::{self, TrustedProxies};
 use asn1::Asn1;
+use filter::LdapFilterError;
+use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpListener;
-use tokio::sync::Arc;
-use std::collections::HashMap;
-use uuid::Uuid;
-use chrono::Utc;
+
+/// LDAP_SERVER_DIRSYNC_OID: изменения каталога после cookie (MS-ADTS 3.1.1.3.4.1.3)
+const DIRSYNC_OID: &str = "1.2.840.113556.1.4.841";
+
+/// Не больше изменений в одном ответе DirSync; остальные — по следующему cookie
+const DIRSYNC_MAX_CHANGES: usize = 1000;
+
+/// Сколько объектов поиск читает из каталога за раз
+const SEARCH_BATCH: usize = 200;
+
+/// Столько ждём заголовок PROXY от доверенного прокси (`ldap_server.proxy`)
+const PROXY_HEADER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Сообщение больше этого — ошибка протокола, соединение закрывается
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+/// Теги операций LDAP `[APPLICATION n]` (RFC 4511, 4.2)
+const OP_BIND_REQUEST: u8 = 0;
+const OP_BIND_RESPONSE: u8 = 1;
+const OP_UNBIND_REQUEST: u8 = 2;
+const OP_SEARCH_REQUEST: u8 = 3;
+const OP_SEARCH_RESULT_ENTRY: u8 = 4;
+const OP_SEARCH_RESULT_DONE: u8 = 5;
+
+/// Коды результата поиска (RFC 4511, 4.1.9)
+const RESULT_SUCCESS: u8 = 0;
+const RESULT_TIME_LIMIT_EXCEEDED: u8 = 3;
+const RESULT_SIZE_LIMIT_EXCEEDED: u8 = 4;
+const RESULT_ADMIN_LIMIT_EXCEEDED: u8 = 11;
+const RESULT_INSUFFICIENT_ACCESS_RIGHTS: u8 = 50;
+
+/// Элемент управления LDAP (RFC 4511, 4.1.11)
+struct LdapControl {
+    oid: String,
+    critical: bool,
+    value: Vec<u8>,
+}
 
 #[derive(Debug)]
 pub enum LdapError {
     Io(std::io::Error),
     ParseError,
+    Directory(DirectoryError),
     AuthenticationFailed,
     NotFound,
     NotImplemented,
@@ -28,11 +72,27 @@ impl From<std::io::Error> for LdapError {
     }
 }
 
+impl From<DirectoryError> for LdapError {
+    fn from(e: DirectoryError) -> Self {
+        LdapError::Directory(e)
+    }
+}
+
+impl From<LdapFilterError> for LdapError {
+    fn from(e: LdapFilterError) -> Self {
+        match e {
+            LdapFilterError::Directory(e) => LdapError::Directory(e),
+            _ => LdapError::ParseError,
+        }
+    }
+}
+
 impl std::fmt::Display for LdapError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             LdapError::Io(e) => write!(f, "IO error: {}", e),
             LdapError::ParseError => write!(f, "ASN.1 parse error"),
+            LdapError::Directory(e) => write!(f, "Directory error: {}", e),
             LdapError::AuthenticationFailed => write!(f, "Authentication failed"),
             LdapError::NotFound => write!(f, "Not found"),
             LdapError::NotImplemented => write!(f, "Not implemented"),
@@ -45,78 +105,251 @@ impl std::error::Error for LdapError {}
 pub struct LdapServer {
     service: Arc<DirectoryService>,
     listener: TcpListener,
+    /// Административные пределы поиска (`ldap_server.size_limit`, `ldap_server.time_limit_secs`)
+    limits: SearchLimits,
+    /// Операций на соединение (`ldap_server.max_operations_per_connection`); 0 — без предела
+    max_operations: u64,
+    /// Прокси, от которых соединение начинается заголовком PROXY с адресом клиента
+    proxies: Arc<TrustedProxies>,
+    /// Поиск без bind (`ldap_server.allow_anonymous_bind`)
+    allow_anonymous: bool,
 }
 
 impl LdapServer {
     pub async fn bind(service: Arc<DirectoryService>, addr: &str) -> Result<Self, LdapError> {
-        let listener = TcpListener::bind(addr).await?;
-        Ok(Self { service, listener })
+        let listener = crate::net::bind_tcp(addr).await?;
+        Ok(Self { service, listener, limits: SearchLimits::default(), max_operations: 0, proxies: Arc::default(), allow_anonymous: false })
+    }
+
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr, LdapError> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Пределы из `ldap_server`: клиент может запросить меньшие sizeLimit и timeLimit, но не большие
+    pub fn with_limits(mut self, config: &LdapServerConfig) -> Self {
+        self.limits = SearchLimits::new(config.size_limit, config.time_limit_secs);
+        self.max_operations = config.max_operations_per_connection;
+        self
+    }
+
+    /// Анонимный поиск из `ldap_server.allow_anonymous_bind`; без него поиск без bind получает
+    /// insufficientAccessRights
+    pub fn with_anonymous(mut self, config: &LdapServerConfig) -> Self {
+        self.allow_anonymous = config.allow_anonymous_bind;
+        self
+    }
+
+    /// Доверенные прокси из `ldap_server.proxy`
+    pub fn with_proxies(mut self, config: &LdapServerConfig) -> Result<Self, String> {
+        self.proxies = Arc::new(TrustedProxies::new(&config.proxy)?);
+        Ok(self)
     }
 
     pub async fn run(&self) -> Result<(), LdapError> {
-        println!("🔐 LDAP server listening on {}", self.listener.local_addr()?);
+        tracing::info!(addr = %self.listener.local_addr()?, "LDAP слушает");
 
         loop {
-            let (socket, _) = self.listener.accept().await?;
+            let (mut socket, peer) = self.listener.accept().await?;
             let service = Arc::clone(&self.service);
+            let (limits, max_operations, allow_anonymous) = (self.limits, self.max_operations, self.allow_anonymous);
+            let proxies = Arc::clone(&self.proxies);
 
             tokio::spawn(async move {
-                if let Err(e) = handle_client(socket, service).await {
-                    eprintln!("LDAP client error: {}", e);
+                // За доверенным прокси адрес клиента — из заголовка PROXY; от остальных его не ждём
+                let mut client = peer;
+                if proxies.expects_proxy_header(crate::net::client_ip(peer)) {
+                    match tokio::time::timeout(PROXY_HEADER_TIMEOUT, proxy::read_header(&mut socket)).await {
+                        Ok(Ok(source)) => client = source.unwrap_or(peer),
+                        Ok(Err(e)) => return tracing::warn!(%peer, error = %e, "Неверный заголовок PROXY от LDAP-прокси"),
+                        Err(_) => return tracing::warn!(%peer, "Заголовок PROXY от LDAP-прокси не пришёл вовремя"),
+                    }
                 }
+                // Изменения в соединении записываются в аудит от имени последнего bind
+                let client_ip = crate::net::client_ip(client).to_string();
+                let actor = ActorContext::new(LoginProtocol::Ldap, Some(client_ip.clone()));
+                actor.scope(async move {
+                    if let Err(e) = handle_client(socket, client_ip, service, limits, max_operations, allow_anonymous).await {
+                        tracing::warn!(%client, error = %e, "Ошибка LDAP-клиента");
+                    }
+                }).await
             });
         }
     }
 }
 
+/// `client_ip` — адрес клиента для журнала входов: от доверенного прокси — из заголовка PROXY
 async fn handle_client(
     mut socket: tokio::net::TcpStream,
+    client_ip: String,
     service: Arc<DirectoryService>,
+    limits: SearchLimits,
+    max_operations: u64,
+    allow_anonymous: bool,
 ) -> Result<(), LdapError> {
-    let mut buf = vec![0u8; 4096];
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    // Учётная запись последнего успешного bind; None — анонимный доступ
+    let mut bound: Option<User> = None;
+    let mut operations = 0u64;
 
     loop {
-        let n = socket.read(&mut buf).await?;
-        if n == 0 { break; }
-
-        let mut parser = asn1::Asn1Parser::new(buf[..n].to_vec());
+        // Сообщения LDAP приходят подряд и могут делиться между чтениями: разбираем каждое целиком
+        let Some(length) = asn1::element_length(&buf) else {
+            if buf.len() > MAX_MESSAGE_SIZE {
+                break;
+            }
+            let n = socket.read(&mut chunk).await?;
+            if n == 0 { break; }
+            buf.extend_from_slice(&chunk[..n]);
+            continue;
+        };
+        let data: Vec<u8> = buf.drain(..length).collect();
 
-        if let Some(Asn1::Sequence(mut message)) = parser.parse() {
-            if message.len() < 3 { continue; }
+        let Ok(Some(Asn1::Sequence(message))) = asn1::Asn1Parser::new(data).parse() else {
+            send_error(&mut socket, 0, 2).await?; // protocolError
+            break;
+        };
+        let msg_id = match message.first() {
+            Some(Asn1::Integer(id)) => *id as u32,
+            _ => {
+                send_error(&mut socket, 0, 2).await?; // protocolError
+                break;
+            }
+        };
 
-            let msg_id = match &message[0] {
-                Asn1::Integer(id) => *id as u32,
-                _ => continue,
-            };
+        // Квота соединения исчерпана: клиент получает adminLimitExceeded, соединение закрывается
+        operations += 1;
+        if max_operations > 0 && operations > max_operations {
+            send_error(&mut socket, msg_id, RESULT_ADMIN_LIMIT_EXCEEDED).await?;
+            break;
+        }
 
-            if let Some(Asn1::Sequence(ref op)) = message.get(2) {
-                match op.get(0) {
-                    Some(Asn1::OctetString(_)) if op.len() >= 3 => {
-                        // BIND request
-                        handle_bind(&mut socket, msg_id).await?;
-                    }
-                    Some(Asn1::Enumerated(3)) => {
-                        // SEARCH request
-                        handle_search(&mut socket, msg_id, &service, op).await?;
-                    }
-                    _ => {
-                        send_error(&mut socket, msg_id, 12).await?; // unavailable
-                    }
+        let controls = parse_controls(&message);
+        match message.get(1) {
+            Some(Asn1::Application(OP_BIND_REQUEST, op)) => {
+                bound = handle_bind(&mut socket, msg_id, &service, op, &client_ip).await?;
+                ActorContext::set_current_user(bound.as_ref());
+            }
+            Some(Asn1::Application(OP_SEARCH_REQUEST, op)) => {
+                if bound.is_none() && !allow_anonymous {
+                    send_error(&mut socket, msg_id, RESULT_INSUFFICIENT_ACCESS_RIGHTS).await?;
+                    continue;
                 }
+                handle_search(&mut socket, msg_id, &service, op, bound.as_ref(), &controls, limits).await?;
+            }
+            Some(Asn1::ApplicationPrimitive(OP_UNBIND_REQUEST, _)) => break,
+            _ => {
+                send_error(&mut socket, msg_id, 53).await?; // unwillingToPerform
             }
-        } else {
-            send_error(&mut socket, 1, 2).await?; // protocolError
         }
     }
 
     Ok(())
 }
 
-async fn handle_bind(socket: &mut tokio::net::TcpStream, msg_id: u32) -> Result<(), LdapError> {
-    // Простой успех (в реальности — проверка DN + пароля)
-    let response = build_bind_response(msg_id, 0); // success
-    socket.write_all(&response).await?;
-    Ok(())
+/// Simple bind: имя — DN (`CN=bob,...`), UPN или sAMAccountName; пустое имя — анонимный доступ.
+/// Возвращает вошедшего пользователя: от его имени проверяются права в DirSync
+async fn handle_bind(
+    socket: &mut tokio::net::TcpStream,
+    msg_id: u32,
+    service: &DirectoryService,
+    op: &[Asn1],
+    client_ip: &str,
+) -> Result<Option<User>, LdapError> {
+    let name = extract_string_from_sequence(op, 1);
+    let password = match op.get(2) {
+        Some(Asn1::ContextPrimitive(0, password)) => String::from_utf8_lossy(password).to_string(),
+        _ => String::new(),
+    };
+    if name.is_empty() {
+        socket.write_all(&build_bind_response(msg_id, 0)).await?; // success
+        return Ok(None);
+    }
+
+    match service.authenticate_from(bind_username(&name), &password, Some(client_ip.to_string()), LoginProtocol::Ldap).await {
+        Ok(user) => {
+            socket.write_all(&build_bind_response(msg_id, 0)).await?; // success
+            Ok(Some(user))
+        }
+        Err(_) => {
+            socket.write_all(&build_bind_response(msg_id, 49)).await?; // invalidCredentials
+            Ok(None)
+        }
+    }
+}
+
+/// Имя учётной записи из имени bind: значение первого RDN у DN, часть до `@` у UPN
+fn bind_username(name: &str) -> &str {
+    if let Some((_, value)) = name.split(',').next().and_then(|rdn| rdn.split_once('=')) {
+        return value.trim();
+    }
+    name.split('@').next().unwrap_or(name)
+}
+
+/// Пределы одного поиска: sizeLimit и timeLimit из SearchRequest, не больше административных
+struct SearchBudget {
+    size_limit: usize,
+    deadline: Option<std::time::Instant>,
+    sent: usize,
+}
+
+impl SearchBudget {
+    fn new(op: &[Asn1], admin: SearchLimits) -> Self {
+        let requested = |index: usize| match op.get(index) {
+            Some(Asn1::Integer(n)) if *n > 0 => *n as usize,
+            _ => 0,
+        };
+        // 0 — без предела и у клиента, и у администратора
+        let stricter = |client: usize, admin: usize| match (client, admin) {
+            (0, admin) => admin,
+            (client, 0) => client,
+            (client, admin) => client.min(admin),
+        };
+        let size_limit = stricter(requested(3), admin.size_limit);
+        let admin_secs = admin.time_limit.map_or(0, |limit| limit.as_secs() as usize);
+        let deadline = match stricter(requested(4), admin_secs) {
+            0 => None,
+            secs => Some(std::time::Instant::now() + std::time::Duration::from_secs(secs as u64)),
+        };
+        Self { size_limit, deadline, sent: 0 }
+    }
+
+    /// Код завершения, если следующую запись отправлять уже нельзя
+    fn exhausted(&self) -> Option<u8> {
+        if self.deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline) {
+            return Some(RESULT_TIME_LIMIT_EXCEEDED);
+        }
+        (self.size_limit > 0 && self.sent >= self.size_limit).then_some(RESULT_SIZE_LIMIT_EXCEEDED)
+    }
+}
+
+/// Право чтения записей поиска, как в REST (`READ_PROPERTY` по DACL): от имени вошедшего,
+/// а при анонимном поиске — только Everyone
+struct SearchAccess {
+    sids: Vec<SecurityIdentifier>,
+    user_id: Option<uuid::Uuid>,
+}
+
+impl SearchAccess {
+    async fn new(service: &DirectoryService, bound: Option<&User>) -> Result<Self, DirectoryError> {
+        Ok(match bound {
+            Some(user) => Self { sids: service.principal_sids(user).await?, user_id: Some(user.id) },
+            None => Self { sids: vec![well_known::sids::everyone()], user_id: None },
+        })
+    }
+
+    /// `None` — объект домена
+    async fn can_read(&self, service: &DirectoryService, object: Option<SecuredObject>) -> Result<bool, DirectoryError> {
+        let Some(object) = object else {
+            return service.check_root_access(&self.sids, AccessMask::READ_PROPERTY).await;
+        };
+        if self.user_id.is_some_and(|id| object == SecuredObject::User(id)) {
+            let mut own = self.sids.clone();
+            own.push(well_known::sids::principal_self());
+            return service.check_access(&own, object, AccessMask::READ_PROPERTY).await;
+        }
+        service.check_access(&self.sids, object, AccessMask::READ_PROPERTY).await
+    }
 }
 
 async fn handle_search(
@@ -124,76 +357,273 @@ async fn handle_search(
     msg_id: u32,
     service: &DirectoryService,
     op: &[Asn1],
+    bound: Option<&User>,
+    controls: &[LdapControl],
+    limits: SearchLimits,
 ) -> Result<(), LdapError> {
     let base = extract_string_from_sequence(op, 0);
     let scope = extract_enumerated_from_sequence(op, 1); // 0=base, 1=one, 2=subtree
-    let filter_bytes = if let Some(Asn1::OctetString(data)) = op.get(4) {
-        data
-    } else {
+    let attributes = requested_attributes(op);
+    let Some(filter) = op.get(6) else {
         return send_error(socket, msg_id, 2).await; // protocolError
     };
-
-    let filter_str = String::from_utf8_lossy(filter_bytes);
-    eprintln!("🔍 LDAP filter: {}", filter_str);
-
-    let filter = match filter::Filter::parse(&filter_str) {
+    let filter = match filter::Filter::from_asn1(filter) {
         Ok(f) => f,
-        Err(_) => return send_error(socket, msg_id, 21).await, // invalidAttributeSyntax
+        Err(_) => return send_error(socket, msg_id, 2).await, // protocolError
     };
+    tracing::debug!(%base, scope, ?filter, "Поиск LDAP");
 
-    // 🔽 Используем заглушку для домена (в реальности — получи из базы)
-    let domain = Domain::new_with_defaults(
-        "Acme Corp".to_string(),
-        "corp.acme.com".to_string(),
-        SecurityIdentifier::new_nt_authority(512),
-    );
-    let domain_dn = domain.dn();
-
-    // 🔽 Получим всех пользователей (в реальности — через индекс)
-    // Пока заглушка: получи список user_id из базы
-    let all_user_ids = vec![]; // service.get_all_user_ids().await?;
-
-    for user_id in all_user_ids {
-        let user = match service.get_user(user_id).await? {
-            Some(u) => u,
-            None => continue,
-        };
+    if let Some(control) = controls.iter().find(|control| control.oid == DIRSYNC_OID) {
+        return handle_dirsync(socket, msg_id, service, bound, control, &filter).await;
+    }
+    let link_ttl = controls.iter().any(|control| control.oid == constructed::LINK_TTL_OID);
+    if controls.iter().any(|control| control.critical && control.oid != constructed::LINK_TTL_OID) {
+        return send_error(socket, msg_id, 12).await; // unavailableCriticalExtension
+    }
+
+    // DN домена и OU читаются один раз на запрос
+    let ctx = LdapContext::new(service).await?;
+    // Недоступный на чтение объект не сопоставляется фильтру: по ответу не узнать его атрибуты
+    let access = SearchAccess::new(service, bound).await?;
+
+    // Пользователи читаются пачками по индексу: ответ уходит клиенту по мере чтения,
+    // а поиск останавливается на sizeLimit / timeLimit, не дочитывая каталог
+    let mut budget = SearchBudget::new(op, limits);
+    let mut cursor = Some(0);
+    while let Some(position) = cursor {
+        let page = service.scan_users(position, SEARCH_BATCH).await?;
+        cursor = page.next;
+
+        for user in page.items {
+            if let Some(code) = budget.exhausted().filter(|code| *code == RESULT_TIME_LIMIT_EXCEEDED) {
+                return socket.write_all(&build_search_done(msg_id, code)).await.map_err(Into::into);
+            }
+
+            if !in_scope(scope, &base, &user.dn(&ctx)) || !access.can_read(service, Some(SecuredObject::User(user.id))).await? {
+                continue;
+            }
+            let Ok((dn, mut entry)) = user.ldap_entry(&ctx).await else {
+                continue;
+            };
+            // Проверяем фильтр с сервисом (для tokenGroups)
+            if !filter.matches_user_with_service(&user, &entry, service).await? {
+                continue;
+            }
+            if let Some(code) = budget.exhausted() {
+                return socket.write_all(&build_search_done(msg_id, code)).await.map_err(Into::into);
+            }
+
+            constructed::add_user_attributes(service, &user, scope, &attributes, &mut entry).await?;
+            if link_ttl {
+                constructed::add_link_ttl(&ctx, &user, &mut entry).await?;
+            }
+            socket.write_all(&build_search_result_entry(msg_id, &dn, &entry)).await?;
+            budget.sent += 1;
+        }
+    }
 
-        // Проверяем фильтр с сервисом (для tokenGroups)
-        if !filter.matches_user_with_service(&user, service).await? {
+    // Остальные объекты сопоставляются фильтру по готовой записи; primaryGroupToken у групп
+    // вычисляется, только если его запросили
+    let mut entries = Vec::new();
+    if access.can_read(service, None).await? {
+        for domain in service.get_all_domains().await? {
+            entries.push(domain.ldap_entry(&ctx).await?);
+        }
+    }
+    for ou in service.get_all_ous().await? {
+        if access.can_read(service, Some(SecuredObject::Ou(ou.id))).await? {
+            entries.push(ou.ldap_entry(&ctx).await?);
+        }
+    }
+    for group in service.get_all_groups().await? {
+        if !access.can_read(service, Some(SecuredObject::Group(group.id))).await? {
             continue;
         }
+        let (dn, mut entry) = group.ldap_entry(&ctx).await?;
+        constructed::add_group_attributes(&group, &attributes, &mut entry);
+        entries.push((dn, entry));
+    }
+    for (dn, entry) in entries {
+        if !in_scope(scope, &base, &dn) || !filter.matches_entry(&entry) {
+            continue;
+        }
+        if let Some(code) = budget.exhausted() {
+            return socket.write_all(&build_search_done(msg_id, code)).await.map_err(Into::into);
+        }
+        socket.write_all(&build_search_result_entry(msg_id, &dn, &entry)).await?;
+        budget.sent += 1;
+    }
 
-        let dn = DirectoryService::generate_user_dn(&user, &domain);
-        let entry = match user.to_ldap_entry(&dn, service).await {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
+    // Контакты: адресные книги видят их рядом с пользователями
+    let mut cursor = Some(0);
+    while let Some(position) = cursor {
+        let page = service.scan_contacts(position, SEARCH_BATCH).await?;
+        cursor = page.next;
 
-        // Собираем ASN.1 ответ
-        let mut attrs = Vec::new();
-        for (attr, values) in entry {
-            let mut vals = Vec::new();
-            for v in values {
-                vals.push(Asn1::OctetString(v.into_bytes()));
+        for contact in page.items {
+            if !access.can_read(service, Some(SecuredObject::Contact(contact.id))).await? {
+                continue;
+            }
+            let (dn, entry) = contact.ldap_entry(&ctx).await?;
+            if !in_scope(scope, &base, &dn) || !filter.matches_entry(&entry) {
+                continue;
             }
-            attrs.push(Asn1::Sequence(vec![
-                Asn1::OctetString(attr.into_bytes()),
-                Asn1::Sequence(vals),
-            ]));
+            if let Some(code) = budget.exhausted() {
+                return socket.write_all(&build_search_done(msg_id, code)).await.map_err(Into::into);
+            }
+            socket.write_all(&build_search_result_entry(msg_id, &dn, &entry)).await?;
+            budget.sent += 1;
         }
-
-        let response = build_search_result_entry(msg_id, &dn, &attrs);
-        socket.write_all(&response).await?;
     }
 
     // SearchDone
-    let done = build_search_done(msg_id, 0);
+    let done = build_search_done(msg_id, RESULT_SUCCESS);
     socket.write_all(&done).await?;
 
     Ok(())
 }
 
+/// DirSync: объекты, изменившиеся после cookie и доступные вошедшему на чтение; в ответе —
+/// новый cookie и флаг «есть ещё изменения»
+async fn handle_dirsync(
+    socket: &mut tokio::net::TcpStream,
+    msg_id: u32,
+    service: &DirectoryService,
+    bound: Option<&User>,
+    control: &LdapControl,
+    filter: &filter::Filter,
+) -> Result<(), LdapError> {
+    let Some(user) = bound else {
+        return send_error(socket, msg_id, RESULT_INSUFFICIENT_ACCESS_RIGHTS).await;
+    };
+    let Some((max_bytes, cookie)) = parse_dirsync_request(&control.value) else {
+        return send_error(socket, msg_id, 2).await; // protocolError
+    };
+    let Ok(cookie) = DirSyncCookie::from_bytes(&cookie) else {
+        return send_error(socket, msg_id, 53).await; // unwillingToPerform
+    };
+    let page = service.dirsync(user, cookie, DIRSYNC_MAX_CHANGES).await?;
+    let ctx = LdapContext::new(service).await?;
+
+    for change in &page.changes {
+        let Some((dn, entry)) = dirsync_entry(&ctx, change).await? else {
+            continue;
+        };
+        // Удалённый объект фильтру не сопоставить: клиент узнаёт его по objectGUID
+        if change.change_type != ChangeType::Delete && !filter.matches_entry(&entry) {
+            continue;
+        }
+        socket.write_all(&build_search_result_entry(msg_id, &dn, &entry)).await?;
+    }
+
+    let response = build_dirsync_response(page.more, max_bytes, &page.cookie.to_bytes());
+    let done = build_search_done_with_controls(msg_id, 0, &[(DIRSYNC_OID, response)]);
+    socket.write_all(&done).await?;
+    Ok(())
+}
+
+/// Запись для изменения DirSync: текущее состояние объекта, а для удалённого — objectGUID
+/// и isDeleted, как у tombstone в AD
+async fn dirsync_entry(
+    ctx: &LdapContext<'_>,
+    change: &ChangeEntry,
+) -> Result<Option<(String, LdapAttributes)>, DirectoryError> {
+    let id = change.object_id;
+    if change.change_type == ChangeType::Delete {
+        let mut entry = LdapAttributes::new();
+        entry.insert_binary(OBJECT_GUID, vec![object_guid_bytes(id)]);
+        entry.insert("isDeleted", vec!["TRUE".to_string()]);
+        return Ok(Some((format!("<GUID={}>", id), entry)));
+    }
+
+    let service = ctx.service;
+    let entry = match change.object_type.as_str() {
+        "user" => match service.get_user(id).await? {
+            Some(user) => Some(user.ldap_entry(ctx).await?),
+            None => None,
+        },
+        "group" => match service.get_group(id).await? {
+            Some(group) => Some(group.ldap_entry(ctx).await?),
+            None => None,
+        },
+        "ou" => match service.get_ou(id).await? {
+            Some(ou) => Some(ou.ldap_entry(ctx).await?),
+            None => None,
+        },
+        "contact" => match service.get_contact(id).await? {
+            Some(contact) => Some(contact.ldap_entry(ctx).await?),
+            None => None,
+        },
+        _ => None,
+    };
+    Ok(entry)
+}
+
+/// Controls сообщения — `[0]` после protocolOp
+fn parse_controls(message: &[Asn1]) -> Vec<LdapControl> {
+    let Some(Asn1::Context(0, controls)) = message.get(2) else {
+        return Vec::new();
+    };
+    controls.iter()
+        .filter_map(|control| {
+            let Asn1::Sequence(fields) = control else {
+                return None;
+            };
+            let Some(Asn1::OctetString(oid)) = fields.first() else {
+                return None;
+            };
+            let critical = fields.iter().any(|field| matches!(field, Asn1::Boolean(true)));
+            let value = fields.iter().skip(1)
+                .find_map(|field| match field {
+                    Asn1::OctetString(value) => Some(value.clone()),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            Some(LdapControl { oid: String::from_utf8_lossy(oid).to_string(), critical, value })
+        })
+        .collect()
+}
+
+/// Значение запроса DirSync: `SEQUENCE { Flags, MaxBytes, Cookie }`; возвращает MaxBytes и Cookie
+fn parse_dirsync_request(value: &[u8]) -> Option<(i64, Vec<u8>)> {
+    let Ok(Some(Asn1::Sequence(fields))) = asn1::Asn1Parser::new(value.to_vec()).parse() else {
+        return None;
+    };
+    match fields.as_slice() {
+        [Asn1::Integer(_flags), Asn1::Integer(max_bytes), Asn1::OctetString(cookie)] => Some((*max_bytes, cookie.clone())),
+        _ => None,
+    }
+}
+
+/// Значение ответа DirSync: `SEQUENCE { Flags, MaxBytes, Cookie }`; ненулевой Flags — есть ещё изменения
+fn build_dirsync_response(more: bool, max_bytes: i64, cookie: &[u8]) -> Vec<u8> {
+    let mut w = Vec::new();
+    write_sequence(&mut w, |w| {
+        write_integer(w, if more { 1 } else { 0 });
+        write_integer(w, max_bytes);
+        write_octet_string(w, cookie);
+    });
+    w
+}
+
+/// Список атрибутов SearchRequest — последнее поле запроса; пустой — все обычные атрибуты
+fn requested_attributes(op: &[Asn1]) -> Vec<String> {
+    let Some(Asn1::Sequence(attributes)) = op.last() else {
+        return Vec::new();
+    };
+    attributes.iter()
+        .filter_map(|attr| match attr {
+            Asn1::OctetString(name) => Some(String::from_utf8_lossy(name).to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Попадает ли запись в область поиска: при base — только объект с DN базы
+fn in_scope(scope: u32, base: &str, dn: &str) -> bool {
+    scope != constructed::SCOPE_BASE || dn.eq_ignore_ascii_case(base)
+}
+
 fn extract_string_from_sequence(seq: &[Asn1], index: usize) -> String {
     if let Some(Asn1::OctetString(data)) = seq.get(index) {
         String::from_utf8_lossy(data).to_string()
@@ -216,9 +646,8 @@ fn build_bind_response(msg_id: u32, result_code: u8) -> Vec<u8> {
     let mut w = Vec::new();
     write_sequence(&mut w, |w| {
         write_integer(w, msg_id as i64);
-        write_enumerated(w, 1); // bindResponse
-        write_sequence(w, |w| {
-            write_enumerated(w, result_code); // success = 0
+        write_application(w, OP_BIND_RESPONSE, |w| {
+            write_enumerated(w, result_code.into()); // success = 0
             write_octet_string(w, &[]);
             write_octet_string(w, &[]);
         });
@@ -226,44 +655,54 @@ fn build_bind_response(msg_id: u32, result_code: u8) -> Vec<u8> {
     w
 }
 
-fn build_search_result_entry(msg_id: u32, dn: &str, attributes: &[Asn1]) -> Vec<u8> {
+/// SearchResultEntry: значения атрибутов уходят байтами как есть, двоичные — без перекодирования
+fn build_search_result_entry(msg_id: u32, dn: &str, entry: &LdapAttributes) -> Vec<u8> {
     let mut w = Vec::new();
     write_sequence(&mut w, |w| {
         write_integer(w, msg_id as i64);
-        write_enumerated(w, 4); // searchResEntry
-        write_octet_string(w, dn.as_bytes());
-        write_sequence(w, |w| {
-            for attr in attributes {
-                write_sequence(w, |w| {
-                    if let Asn1::Sequence(ref inner) = attr {
-                        if let Some(Asn1::OctetString(name)) = inner.get(0) {
-                            write_octet_string(w, name);
-                        }
-                        if let Some(Asn1::Sequence(vals)) = inner.get(1) {
-                            write_sequence(w, |w| {
-                                for val in vals {
-                                    if let Asn1::OctetString(data) = val {
-                                        write_octet_string(w, data);
-                                    }
-                                }
-                            });
-                        }
-                    }
-                });
-            }
+        write_application(w, OP_SEARCH_RESULT_ENTRY, |w| {
+            write_octet_string(w, dn.as_bytes());
+            write_sequence(w, |w| {
+                for (name, values) in entry.iter() {
+                    write_sequence(w, |w| {
+                        write_octet_string(w, name.as_bytes());
+                        write_set(w, |w| {
+                            for value in values {
+                                write_octet_string(w, value.as_bytes());
+                            }
+                        });
+                    });
+                }
+            });
         });
     });
     w
 }
 
 fn build_search_done(msg_id: u32, result_code: u8) -> Vec<u8> {
+    build_search_done_with_controls(msg_id, result_code, &[])
+}
+
+/// SearchResultDone с элементами управления ответа: OID и значение
+fn build_search_done_with_controls(msg_id: u32, result_code: u8, controls: &[(&str, Vec<u8>)]) -> Vec<u8> {
     let mut w = Vec::new();
     write_sequence(&mut w, |w| {
         write_integer(w, msg_id as i64);
-        write_enumerated(w, 5); // searchResDone
-        write_enumerated(w, result_code);
-        write_octet_string(w, &[]);
-        write_octet_string(w, &[]);
+        write_application(w, OP_SEARCH_RESULT_DONE, |w| {
+            write_enumerated(w, result_code.into());
+            write_octet_string(w, &[]);
+            write_octet_string(w, &[]);
+        });
+        if !controls.is_empty() {
+            write_context(w, 0, |w| {
+                for (oid, value) in controls {
+                    write_sequence(w, |w| {
+                        write_octet_string(w, oid.as_bytes());
+                        write_octet_string(w, value);
+                    });
+                }
+            });
+        }
     });
     w
 }
@@ -279,7 +718,7 @@ fn write_integer(w: &mut Vec<u8>, mut n: i64) {
             bytes.push((n & 0xFF) as u8);
             n >>= 8;
         }
-        if bytes.last().unwrap() >= 0x80 {
+        if *bytes.last().unwrap() >= 0x80 {
             bytes.push(0);
         }
     }
@@ -298,7 +737,7 @@ fn write_enumerated(w: &mut Vec<u8>, n: u32) {
     if bytes.is_empty() {
         bytes.push(0);
     }
-    if bytes.last().unwrap() >= 0x80 {
+    if *bytes.last().unwrap() >= 0x80 {
         bytes.push(0);
     }
     bytes.reverse();
@@ -318,6 +757,30 @@ fn write_sequence<F>(w: &mut Vec<u8>, f: F) where F: FnOnce(&mut Vec<u8>) {
     w.extend(body);
 }
 
+/// SET OF (значения атрибута, RFC 4511 4.1.7)
+fn write_set<F>(w: &mut Vec<u8>, f: F) where F: FnOnce(&mut Vec<u8>) {
+    let mut body = Vec::new();
+    f(&mut body);
+    write_type_and_length(w, 0x31, body.len());
+    w.extend(body);
+}
+
+/// Операция LDAP `[APPLICATION tag]`
+fn write_application<F>(w: &mut Vec<u8>, tag: u8, f: F) where F: FnOnce(&mut Vec<u8>) {
+    let mut body = Vec::new();
+    f(&mut body);
+    write_type_and_length(w, 0x60 | tag, body.len());
+    w.extend(body);
+}
+
+/// Составной контекстный тег `[tag]`
+fn write_context<F>(w: &mut Vec<u8>, tag: u8, f: F) where F: FnOnce(&mut Vec<u8>) {
+    let mut body = Vec::new();
+    f(&mut body);
+    write_type_and_length(w, 0xA0 | tag, body.len());
+    w.extend(body);
+}
+
 fn write_type_and_length(w: &mut Vec<u8>, tag: u8, len: usize) {
     w.push(tag);
     if len < 0x80 {
@@ -336,8 +799,8 @@ fn write_type_and_length(w: &mut Vec<u8>, tag: u8, len: usize) {
 
 // === Вспомогательные функции ===
 
-fn send_error(socket: &mut tokio::net::TcpStream, msg_id: u32, code: u8) -> Result<(), LdapError> {
+async fn send_error(socket: &mut tokio::net::TcpStream, msg_id: u32, code: u8) -> Result<(), LdapError> {
     let response = build_search_done(msg_id, code);
-    socket.write_all(&response)?;
+    socket.write_all(&response).await?;
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,220 @@
+// src/ldif.rs
+
+//! Формат LDIF (RFC 2849): разбор и запись записей для миграции из OpenLDAP / AD.
+
+use base64::engine::{general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+/// Максимальная длина строки при записи; длинные строки переносятся
+const LINE_WIDTH: usize = 76;
+
+#[derive(Debug)]
+pub struct LdifError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for LdifError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LDIF line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for LdifError {}
+
+/// Что делать, если объект уже есть в каталоге
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, clap::ValueEnum, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// Оставить существующий объект
+    #[default]
+    Skip,
+    /// Обновить атрибуты существующего объекта
+    Update,
+    /// Прервать импорт до внесения изменений
+    Fail,
+}
+
+/// Итог импорта
+#[derive(Debug, Default, Serialize, utoipa::ToSchema)]
+pub struct ImportReport {
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    /// Участники административных групп, ждущие одобрения (`security.approvals`)
+    pub pending_approval: usize,
+    pub errors: Vec<String>,
+}
+
+/// Тип объекта каталога, в который отображается запись
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LdifObjectKind {
+    OrganizationalUnit,
+    User,
+    Group,
+    Contact,
+}
+
+/// Одна запись LDIF; значения, не являющиеся UTF-8 (objectSid, jpegPhoto), отбрасываются
+#[derive(Debug, Clone)]
+pub struct LdifEntry {
+    pub dn: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl LdifEntry {
+    pub fn new(dn: impl Into<String>) -> Self {
+        Self { dn: dn.into(), attributes: Vec::new() }
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.attributes.push((name.into(), value.into()));
+    }
+
+    /// Первое значение атрибута; имя без учёта регистра и опций (`;binary`)
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.get_all(name).into_iter().next()
+    }
+
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.attributes
+            .iter()
+            .filter(|(n, _)| n.split(';').next().is_some_and(|base| base.eq_ignore_ascii_case(name)))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    pub fn has_object_class(&self, class: &str) -> bool {
+        self.get_all("objectClass").iter().any(|c| c.eq_ignore_ascii_case(class))
+    }
+
+    pub fn kind(&self) -> Option<LdifObjectKind> {
+        if self.has_object_class("organizationalUnit") {
+            Some(LdifObjectKind::OrganizationalUnit)
+        } else if ["group", "groupOfNames", "groupOfUniqueNames", "posixGroup"].iter().any(|c| self.has_object_class(c)) {
+            Some(LdifObjectKind::Group)
+        } else if self.has_object_class("contact") {
+            // contact тоже наследует person — проверяется раньше пользователя
+            Some(LdifObjectKind::Contact)
+        } else if ["user", "inetOrgPerson", "person"].iter().any(|c| self.has_object_class(c)) {
+            Some(LdifObjectKind::User)
+        } else {
+            None
+        }
+    }
+}
+
+/// Разобрать содержимое LDIF-файла
+pub fn parse(input: &str) -> Result<Vec<LdifEntry>, LdifError> {
+    let mut entries = Vec::new();
+    let mut current: Option<LdifEntry> = None;
+
+    for (line_no, line) in unfold(input) {
+        let error = |message: &str| LdifError { line: line_no, message: message.to_string() };
+
+        if line.is_empty() {
+            entries.extend(current.take());
+            continue;
+        }
+        if line.starts_with('#') || line == "-" {
+            continue;
+        }
+
+        let (name, value) = parse_line(&line).map_err(|m| error(&m))?;
+        match current.as_mut() {
+            None if name.eq_ignore_ascii_case("version") => {
+                if value.as_deref() != Some("1") {
+                    return Err(error("Unsupported LDIF version"));
+                }
+            }
+            None if name.eq_ignore_ascii_case("dn") => {
+                let dn = value.ok_or_else(|| error("DN is not valid UTF-8"))?;
+                crate::dn::Dn::parse(&dn).map_err(|e| error(&e.reason))?;
+                current = Some(LdifEntry::new(dn));
+            }
+            None => return Err(error("Record must start with dn:")),
+            Some(entry) => {
+                if let Some(value) = value {
+                    entry.add(name, value);
+                }
+            }
+        }
+    }
+
+    entries.extend(current);
+    Ok(entries)
+}
+
+/// Записать записи в LDIF (с заголовком `version: 1`)
+pub fn write(entries: &[LdifEntry]) -> String {
+    let mut out = String::from("version: 1\n");
+    for entry in entries {
+        out.push('\n');
+        write_line(&mut out, "dn", &entry.dn);
+        for (name, value) in &entry.attributes {
+            write_line(&mut out, name, value);
+        }
+    }
+    out
+}
+
+/// Склеить перенесённые строки; возвращает номер первой физической строки
+fn unfold(input: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (i, raw) in input.lines().enumerate() {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        if let Some(continuation) = raw.strip_prefix(' ')
+            && let Some((_, last)) = lines.last_mut()
+            && !last.is_empty()
+        {
+            last.push_str(continuation);
+            continue;
+        }
+        lines.push((i + 1, raw.to_string()));
+    }
+    lines
+}
+
+/// `name: value`, `name:: base64` или `name:< url`
+fn parse_line(line: &str) -> Result<(String, Option<String>), String> {
+    let (name, rest) = line.split_once(':').ok_or_else(|| "Expected attribute: value".to_string())?;
+    if name.is_empty() {
+        return Err("Empty attribute name".to_string());
+    }
+
+    let value = if let Some(encoded) = rest.strip_prefix(':') {
+        let bytes = STANDARD
+            .decode(encoded.trim())
+            .map_err(|_| format!("Invalid base64 value for {}", name))?;
+        String::from_utf8(bytes).ok()
+    } else if rest.starts_with('<') {
+        return Err(format!("URL values are not supported ({})", name));
+    } else {
+        Some(rest.trim_start_matches(' ').to_string())
+    };
+    Ok((name.to_string(), value))
+}
+
+fn write_line(out: &mut String, name: &str, value: &str) {
+    let line = if is_safe_string(value) {
+        format!("{}: {}", name, value)
+    } else {
+        format!("{}:: {}", name, STANDARD.encode(value))
+    };
+
+    let chars: Vec<char> = line.chars().collect();
+    let (first, rest) = chars.split_at(chars.len().min(LINE_WIDTH));
+    out.extend(first);
+    for chunk in rest.chunks(LINE_WIDTH - 1) {
+        out.push_str("\n ");
+        out.extend(chunk);
+    }
+    out.push('\n');
+}
+
+/// SAFE-STRING из RFC 2849: ASCII без управляющих символов, не начинается с пробела, `:` и `<`
+fn is_safe_string(value: &str) -> bool {
+    !value.starts_with([' ', ':', '<'])
+        && !value.ends_with(' ')
+        && value.bytes().all(|b| (0x20..0x7f).contains(&b))
+}
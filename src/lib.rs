@@ -1,8 +1,35 @@
 pub mod raddb;
 pub mod models;
 pub mod directory_service;
+pub mod error_code;
 pub mod web;
+pub mod grpc;
 pub mod auth;
+pub mod auth_provider;
+pub mod ca;
+pub mod dn;
+pub mod tls_bootstrap;
 pub mod config;
 pub mod events;
-pub mod cli;
\ No newline at end of file
+pub mod cli;
+pub mod kerberos;
+pub mod ldap;
+pub mod dns;
+pub mod radius;
+pub mod ldif;
+pub mod sysvol;
+pub mod csv_io;
+pub mod sync;
+pub mod replication;
+pub mod jobs;
+pub mod middleware;
+pub mod net;
+pub mod proxy;
+pub mod audit;
+pub mod telemetry;
+pub mod ratelimit;
+pub mod cache;
+pub mod casefold;
+pub mod init;
+pub mod reload;
+pub mod mail;
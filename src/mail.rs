@@ -0,0 +1,106 @@
+// src/mail.rs
+
+//! Доставка одноразовых ссылок пользователям (сброс пароля, приглашения) через `OtpSender`.
+//! Отправитель задаётся секцией `mail`; сейчас это SMTP (`mail.smtp`)
+
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::{MailConfig, SmtpConfig, SmtpSecurity};
+
+/// Метка в шаблоне ссылки, вместо которой подставляется токен
+pub const TOKEN_PLACEHOLDER: &str = "{token}";
+
+#[derive(Debug)]
+pub enum MailError {
+    Config(String),
+    Send(String),
+}
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MailError::Config(e) => write!(f, "Mail config error: {}", e),
+            MailError::Send(e) => write!(f, "Mail delivery error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MailError {}
+
+/// Письмо пользователю с одноразовой ссылкой или кодом
+#[derive(Debug, Clone)]
+pub struct OtpMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Доставка одноразовых ссылок и кодов; в тестах подменяется записью в память
+pub trait OtpSender: Send + Sync {
+    fn send<'a>(&'a self, message: &'a OtpMessage) -> BoxFuture<'a, Result<(), MailError>>;
+}
+
+/// Отправитель из секции `mail`; `None` — почта не настроена
+pub fn build_sender(config: &MailConfig) -> Result<Option<Arc<dyn OtpSender>>, MailError> {
+    match &config.smtp {
+        Some(smtp) => Ok(Some(Arc::new(SmtpSender::new(smtp)?))),
+        None => Ok(None),
+    }
+}
+
+/// Подставить токен в шаблон ссылки
+pub fn token_url(template: &str, token: &str) -> String {
+    template.replace(TOKEN_PLACEHOLDER, token)
+}
+
+/// Отправка через SMTP-сервер; соединение открывается на каждое письмо
+pub struct SmtpSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpSender {
+    /// Собрать транспорт без подключения к серверу
+    pub fn new(config: &SmtpConfig) -> Result<Self, MailError> {
+        let from = config.from.parse::<Mailbox>()
+            .map_err(|e| MailError::Config(format!("invalid from address '{}': {}", config.from, e)))?;
+        let builder = match config.security {
+            SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host),
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)),
+        };
+        let mut builder = builder.map_err(|e| MailError::Config(e.to_string()))?;
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        match (&config.username, &config.password) {
+            (Some(username), Some(password)) => builder = builder.credentials(Credentials::new(username.clone(), password.clone())),
+            (None, None) => {}
+            _ => return Err(MailError::Config("username and password must be set together".to_string())),
+        }
+        Ok(Self { transport: builder.build(), from })
+    }
+}
+
+impl OtpSender for SmtpSender {
+    fn send<'a>(&'a self, message: &'a OtpMessage) -> BoxFuture<'a, Result<(), MailError>> {
+        Box::pin(async move {
+            let to = message.to.parse::<Mailbox>()
+                .map_err(|e| MailError::Send(format!("invalid recipient '{}': {}", message.to, e)))?;
+            let email = Message::builder()
+                .from(self.from.clone())
+                .to(to)
+                .subject(message.subject.clone())
+                .header(ContentType::TEXT_PLAIN)
+                .body(message.body.clone())
+                .map_err(|e| MailError::Send(e.to_string()))?;
+            self.transport.send(email).await.map_err(|e| MailError::Send(e.to_string()))?;
+            Ok(())
+        })
+    }
+}
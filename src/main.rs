@@ -1,72 +1,556 @@
 // src/main.rs
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use std::sync::Arc;
 
-mod cli;
-mod web;
-mod directory_service;
-mod models;
-mod raddb;
+use nextDomen::{audit, cli, config, directory_service, dns, grpc, init, jobs, kerberos, ldap, radius, reload, replication, sync, telemetry, tls_bootstrap, web};
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::raddb::RadDbError;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct CliArgs {
+    /// Файл конфигурации; без него — `config.yaml`, если он есть. Поля переопределяются переменными `NEXTDOMEN_*`
+    #[arg(long, global = true, env = config::CONFIG_ENV)]
+    config: Option<std::path::PathBuf>,
+    /// Открыть базу только для чтения, без блокировки: рядом с процессом, который держит её на запись
+    #[arg(long, global = true)]
+    read_only: bool,
     #[command(subcommand)]
     command: AppCommand,
 }
 
 #[derive(clap::Subcommand)]
 enum AppCommand {
-    /// Запустить REST API сервер
+    /// Первый запуск: ключи, `config.yaml` (или `--config`), домен и администратор
+    Init {
+        /// DNS-имя домена
+        #[arg(long)]
+        domain: String,
+        /// Имя первого администратора; пароль запрашивается
+        #[arg(long, default_value = "administrator")]
+        admin: String,
+        #[arg(long, default_value = "data/raddb.bin")]
+        db_path: String,
+        /// Перезаписать существующие конфигурацию и ключи JWT
+        #[arg(long)]
+        force: bool,
+    },
+    /// Ключи установки
+    Keys {
+        #[command(subcommand)]
+        cmd: KeysCommand,
+    },
+    /// Вывести скрипт автодополнения для оболочки
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Страницы man (roff): основная — в stdout, с `--out-dir` — по странице на каждую команду
+    Man {
+        #[arg(long)]
+        out_dir: Option<std::path::PathBuf>,
+    },
+    /// Запустить REST API сервер; с `grpc_server.address` — и gRPC API в том же процессе
     Web {
-        #[arg(short, long, default_value = "127.0.0.1:8080")]
-        addr: String,
+        /// `host:port`, `[::]:8080` или `unix:/run/nextdomen.sock`, можно несколько раз; по умолчанию
+        /// `web_server.address`, без него 127.0.0.1:8080. Сокеты от systemd (`LISTEN_FDS`) главнее
+        #[arg(short, long)]
+        addr: Vec<String>,
+    },
+    /// Интерактивная оболочка администрирования
+    Cli {
+        /// Работать через REST API запущенного сервера (`http://dc01:8080`), не открывая базу
+        #[arg(long)]
+        server: Option<String>,
+        /// Токен администратора для `--server`; без него имя и пароль запрашиваются при входе
+        #[arg(long, env = config::TOKEN_ENV, requires = "server")]
+        token: Option<String>,
+        /// Формат вывода по умолчанию для команд сессии
+        #[command(flatten)]
+        output: cli::OutputArgs,
+    },
+    /// Запустить Kerberos KDC
+    Kdc {
+        /// `host:port`, можно несколько раз: `--addr 0.0.0.0:88 --addr [::]:88`
+        #[arg(short, long, default_value = "0.0.0.0:88")]
+        addr: Vec<String>,
+        #[arg(short, long, default_value = "CORP.ACME.COM")]
+        realm: String,
+    },
+    /// Keytab и servicePrincipalName учётных записей служб
+    Kerberos {
+        #[command(subcommand)]
+        cmd: KerberosCommand,
+    },
+    /// Внутренний CA (секция `ca`): сертификаты пользователей и компьютеров, CRL
+    Ca {
+        #[command(subcommand)]
+        cmd: CaCommand,
+    },
+    /// Запустить DNS-сервер для зон доменов
+    Dns {
+        /// `host:port`, можно несколько раз: `--addr 0.0.0.0:53 --addr [::]:53`
+        #[arg(short, long, default_value = "0.0.0.0:53")]
+        addr: Vec<String>,
+        /// Принимать динамические обновления (RFC 2136) от компьютеров. Обновления не
+        /// подписываются (TSIG/GSS-TSIG пока нет): включайте только в доверенной сети
+        #[arg(long)]
+        dynamic_updates: bool,
+    },
+    /// Запустить RADIUS-сервер (секция `radius_server` конфигурации)
+    Radius,
+    /// Синхронизация из внешнего LDAP / AD (секция `ldap_sync` конфигурации)
+    Sync {
+        /// Выполнить один цикл и выйти
+        #[arg(long)]
+        once: bool,
+        /// Полная синхронизация вместо инкрементальной
+        #[arg(long)]
+        full: bool,
+    },
+    /// Файл конфигурации
+    Config {
+        #[command(subcommand)]
+        cmd: ConfigCommand,
+    },
+    /// Сводка каталога: число объектов по типам, пользователи по OU, размер базы
+    Stats {
+        #[command(flatten)]
+        output: cli::OutputArgs,
+    },
+    /// Ссылки на несуществующие объекты: участники групп, привязки GPO, OU пользователей и контактов
+    CheckReferences {
+        #[command(flatten)]
+        output: cli::OutputArgs,
+    },
+    /// Фоновые задачи процесса `web` (секция `jobs` конфигурации)
+    Jobs {
+        #[command(subcommand)]
+        cmd: JobsCommand,
+        #[command(flatten)]
+        output: cli::OutputArgs,
+    },
+}
+
+#[derive(clap::Subcommand)]
+enum JobsCommand {
+    /// Задачи, их расписание, следующее и последнее выполнение
+    List,
+    /// Выполнить задачу сейчас
+    Run { name: String },
+    /// Последние выполнения задачи
+    History { name: String },
+}
+
+#[derive(clap::Subcommand)]
+enum KerberosCommand {
+    /// Записать keytab (формат MIT) с ключами учётной записи службы
+    Keytab {
+        /// SPN (`HTTP/web01`) или имя учётной записи; `@REALM` — по желанию
+        #[arg(long)]
+        principal: String,
+        /// Файл keytab; создаётся с правами 0600
+        #[arg(long)]
+        out: std::path::PathBuf,
+    },
+    /// servicePrincipalName учётных записей
+    Spn {
+        #[command(subcommand)]
+        cmd: SpnCommand,
+    },
+}
+
+#[derive(clap::Subcommand)]
+enum SpnCommand {
+    /// Зарегистрировать SPN за учётной записью
+    Add { username: String, spn: String },
+    /// Снять SPN с учётной записи
+    Remove { username: String, spn: String },
+    /// SPN учётной записи
+    List { username: String },
+}
+
+#[derive(clap::Subcommand)]
+enum CaCommand {
+    /// Создать ключ и сертификат CA
+    Init {
+        /// Заменить существующий CA: выданные им сертификаты перестанут проверяться
+        #[arg(long)]
+        force: bool,
+    },
+    /// Вывести сертификат CA (PEM) — для `tls.ca_cert_file` и доверенных корней клиентов
+    Cert {
+        /// Файл вместо stdout
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Выпустить сертификат учётной записи пользователя или компьютера (`dc01$`)
+    Issue {
+        subject: String,
+        /// CSR (PEM или DER); без него создаются новый ключ и CSR, ключ пишется в `--key-out`
+        #[arg(long, required_unless_present = "key_out")]
+        csr: Option<std::path::PathBuf>,
+        /// Файл сертификата (PEM)
+        #[arg(long)]
+        out: std::path::PathBuf,
+        /// Файл нового закрытого ключа (PEM, права 0600)
+        #[arg(long, conflicts_with = "csr")]
+        key_out: Option<std::path::PathBuf>,
+    },
+    /// Выданные сертификаты
+    List {
+        /// Только сертификаты этой учётной записи
+        #[arg(long)]
+        subject: Option<String>,
+        #[command(flatten)]
+        output: cli::OutputArgs,
+    },
+    /// Отозвать сертификат
+    Revoke {
+        serial: String,
+        #[arg(long, value_enum, default_value_t)]
+        reason: nextDomen::models::RevocationReason,
+    },
+    /// Выпустить CRL (DER)
+    Crl {
+        #[arg(long)]
+        out: std::path::PathBuf,
+    },
+}
+
+#[derive(clap::Subcommand)]
+enum ConfigCommand {
+    /// Проверить конфигурацию с переменными `NEXTDOMEN_*`: неизвестные поля, ключи, файлы TLS, адреса
+    Validate {
+        /// Файл вместо `--config`
+        #[arg(long)]
+        file: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(clap::Subcommand)]
+enum KeysCommand {
+    /// Вывести новый мастер-ключ базы; с `--jwt-dir` — ещё и создать ключи JWT
+    Generate {
+        /// Каталог для `jwt-private.pem` и `jwt-public.pem`
+        #[arg(long)]
+        jwt_dir: Option<std::path::PathBuf>,
+        /// Перезаписать существующие ключи JWT
+        #[arg(long)]
+        force: bool,
     },
-    /// Запустить CLI режим
-    Cli,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
-
     let args = CliArgs::parse();
-    let config = load_config()?;
-    let key = decode_key(&config.master_key_hex)?;
+    // Удалённому CLI не нужны ни конфигурация, ни база: базу держит сервер
+    if let AppCommand::Cli { server: Some(server), token, output } = &args.command {
+        return cli::run_remote_cli(server, token.clone(), *output).await;
+    }
+    // Первый запуск и генерация ключей создают конфигурацию, а не читают её
+    match args.command {
+        AppCommand::Init { domain, admin, db_path, force } => {
+            let config_path = args.config.unwrap_or_else(|| config::DEFAULT_CONFIG_PATH.into());
+            return init::run_init(init::InitOptions { config_path, db_path, domain, admin, force }).await;
+        }
+        AppCommand::Keys { cmd: KeysCommand::Generate { jwt_dir, force } } => {
+            if let Some(dir) = jwt_dir {
+                let (private_key_path, public_key_path) = init::write_jwt_keys(&dir, force)?;
+                eprintln!("🔑 Ключи JWT: {}, {}", private_key_path.display(), public_key_path.display());
+            }
+            println!("master_key_hex: \"{}\"", init::generate_master_key_hex());
+            return Ok(());
+        }
+        AppCommand::Completions { shell } => {
+            let mut command = CliArgs::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+            return Ok(());
+        }
+        AppCommand::Man { out_dir } => {
+            let Some(dir) = out_dir else {
+                clap_mangen::Man::new(CliArgs::command()).render(&mut std::io::stdout())?;
+                return Ok(());
+            };
+            std::fs::create_dir_all(&dir)?;
+            clap_mangen::generate_to(CliArgs::command(), &dir)?;
+            // Команды оболочки `cli` — отдельное дерево, не подкоманды бинарника
+            clap_mangen::generate_to(cli::shell_command(), &dir)?;
+            eprintln!("✅ Страницы man записаны в {}", dir.display());
+            return Ok(());
+        }
+        AppCommand::Config { cmd: ConfigCommand::Validate { file } } => {
+            let path = file.or(args.config);
+            let issues = match config::AppConfig::load_with_env(path.as_deref()) {
+                Ok(config) => config.validate(&config.web_listeners(&[])),
+                Err(e) => vec![config::validate::ConfigIssue { field: "config".into(), message: e.to_string() }],
+            };
+            if issues.is_empty() {
+                println!("✅ Конфигурация корректна");
+                return Ok(());
+            }
+            for issue in &issues {
+                eprintln!("❌ {}", issue);
+            }
+            std::process::exit(1);
+        }
+        _ => {}
+    }
+    let mut config = config::AppConfig::load_with_env(args.config.as_deref())?;
+    // Серверы проверяют конфигурацию до открытия базы и сокетов
+    let listeners = match &args.command {
+        AppCommand::Web { addr } => Some(config.web_listeners(addr)),
+        AppCommand::Kdc { addr, .. } => Some(addr.iter().flat_map(|addr| [config::validate::Listener::tcp("kdc --addr", addr), config::validate::Listener::udp("kdc --addr", addr)]).collect()),
+        AppCommand::Dns { addr, .. } => Some(addr.iter().flat_map(|addr| [config::validate::Listener::tcp("dns --addr", addr), config::validate::Listener::udp("dns --addr", addr)]).collect()),
+        AppCommand::Radius => Some(Vec::new()),
+        _ => None,
+    };
+    if let Some(listeners) = listeners {
+        check_config(&config, &listeners)?;
+    }
+    let _telemetry = telemetry::init(&config.logging)?;
+    nextDomen::auth::set_token_expiry(config.security.jwt.token_expiry.as_duration());
+    let key = config.master_key().await?;
 
-    // Открываем сервис
-    let service = Arc::new(directory_service::DirectoryService::open(&config.db_path, &key)?);
+    // Открываем сервис; база на запись открывается одним процессом (файл блокировки `<db_path>.lock`)
+    // Команды, которые только читают, блокировку не берут
+    let read_only = args.read_only
+        || matches!(args.command, AppCommand::Stats { .. } | AppCommand::CheckReferences { .. } | AppCommand::Jobs { cmd: JobsCommand::List | JobsCommand::History { .. }, .. }
+            | AppCommand::Kerberos { cmd: KerberosCommand::Spn { cmd: SpnCommand::List { .. } } }
+            | AppCommand::Ca { cmd: CaCommand::Cert { .. } | CaCommand::List { .. } });
+    let service = if read_only {
+        directory_service::DirectoryService::open_read_only(&config.db_path, &key)?
+    } else {
+        match directory_service::DirectoryService::open(&config.db_path, &key) {
+            // Оболочка рядом с работающим сервером: просмотр и выгрузки, изменения — через `cli --server`
+            Err(DirectoryError::DbError(RadDbError::Locked(_))) if matches!(args.command, AppCommand::Cli { .. }) => {
+                eprintln!("⚠️ База открыта другим процессом: оболочка только для чтения (list, get, export); изменения — через cli --server");
+                directory_service::DirectoryService::open_read_only(&config.db_path, &key)?
+            }
+            service => service?,
+        }
+    };
+    let service = Arc::new(service
+        .with_audit_chain(&config.security.audit.chain)
+        .with_rate_limit(&config.security.rate_limit)
+        .with_password_policy(&config.security.password_policy)
+        .with_approvals(&config.security.approvals)
+        .with_sessions(&config.security.sessions)
+        .with_risk(&config.security.risk)
+        .with_auth_providers(&config.security.auth_providers)
+        .with_ca(&config.ca)
+        .with_replication(&config.replication)
+        .with_cache(&config.cache));
+    // Индексы поиска прежних версий различали регистр: перестраиваем один раз
+    let migrated = service.migrate_lookup_indexes().await?;
+    if migrated > 0 {
+        tracing::info!(keys = migrated, "Индексы поиска перестроены без учёта регистра");
+    }
+    let audit_writer = audit::spawn_writer(service.events().subscribe(), audit::build_sink(&config.security.audit)?);
 
     match args.command {
+        AppCommand::Init { .. } | AppCommand::Keys { .. } | AppCommand::Completions { .. } | AppCommand::Man { .. } | AppCommand::Config { .. } => {
+            unreachable!("handled before loading the configuration")
+        }
         AppCommand::Web { addr } => {
-            println!("🌐 Запуск REST API на http://{}", addr);
-            web::run_web_server(service, &addr).await?;
+            let addrs = config.web_addresses(&addr);
+            // Сертификатов ещё нет — выпускаем их внутренним CA или самоподписанными
+            let certs_dir = config.paths.certs_dir();
+            if config.web_server.enable_tls && addrs.iter().any(|addr| !addr.starts_with(web::listener::UNIX_PREFIX)) {
+                tls_bootstrap::ensure_certificate(&service, "web_server", &mut config.web_server.tls, &certs_dir).await?;
+            }
+            if config.grpc_server.enable_tls && !config.grpc_server.address.is_empty() {
+                tls_bootstrap::ensure_certificate(&service, "grpc_server", &mut config.grpc_server.tls, &certs_dir).await?;
+            }
+            tracing::info!(addr = %addrs.join(","), "Запуск REST API");
+            // gRPC работает с той же открытой базой: второй процесс не открыл бы её на запись
+            let grpc = async {
+                if config.grpc_server.address.is_empty() {
+                    return Ok(());
+                }
+                tracing::info!(addr = %config.grpc_server.address, "Запуск gRPC API");
+                grpc::run_grpc_server(Arc::clone(&service), &config.grpc_server.address.to_vec(), &config.grpc_server).await
+                    .map_err(|e| -> Box<dyn std::error::Error> { e })
+            };
+            // LDAP — рядом с REST, если задан `ldap_server.address`
+            let ldap = async {
+                let mut servers = Vec::new();
+                for addr in config.ldap_server.address.iter() {
+                    let server = ldap::LdapServer::bind(Arc::clone(&service), addr).await?
+                        .with_limits(&config.ldap_server)
+                        .with_anonymous(&config.ldap_server)
+                        .with_proxies(&config.ldap_server)?;
+                    servers.push(server);
+                }
+                futures_util::future::try_join_all(servers.iter().map(ldap::LdapServer::run)).await?;
+                Ok::<(), Box<dyn std::error::Error>>(())
+            };
+            let replica = async {
+                if config.replication.read_only {
+                    replication::ReplicaPuller::new(Arc::clone(&service), config.replication.clone())?.run().await;
+                }
+                Ok::<(), Box<dyn std::error::Error>>(())
+            };
+            // Задачи, меняющие каталог, на реплике пропускаются
+            let registry = jobs::JobRegistry::builtin(&config)?;
+            let jobs = async {
+                registry.run(Arc::clone(&service)).await;
+                Ok::<(), Box<dyn std::error::Error>>(())
+            };
+            // Перезагружаемые секции конфигурации — по SIGHUP и POST /api/admin/reload
+            let reloader = Arc::new(reload::ConfigReloader::new(args.config.clone(), &config, Arc::clone(&service))?);
+            reloader.spawn_sighup()?;
+            tokio::try_join!(web::run_web_server(Arc::clone(&service), &addrs, &config, reloader), grpc, ldap, jobs, replica)?;
+        }
+        AppCommand::Cli { output, .. } => {
+            tracing::debug!("Запуск CLI режима");
+            cli::run_cli(Arc::clone(&service), output).await?;
+        }
+        AppCommand::Kdc { addr, realm } => {
+            tracing::info!(addr = %addr.join(","), %realm, "Запуск Kerberos KDC");
+            kerberos::KdcServer::bind(Arc::clone(&service), &addr, &realm).await?.run().await?;
+        }
+        AppCommand::Kerberos { cmd } => match cmd {
+            KerberosCommand::Keytab { principal, out } => {
+                let entries = service.export_keytab(&principal).await?;
+                init::write_secret(&out, &kerberos::keytab::encode(&entries))?;
+                println!("🔑 Keytab {} записан в {}: ключей {}", principal, out.display(), entries.len());
+            }
+            KerberosCommand::Spn { cmd } => {
+                let (SpnCommand::Add { username, .. } | SpnCommand::Remove { username, .. } | SpnCommand::List { username }) = &cmd;
+                let user = service.find_user_by_username(username).await?
+                    .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+                match cmd {
+                    SpnCommand::Add { username, spn } => {
+                        service.register_spn(user.id, &spn).await?;
+                        println!("✅ SPN {} зарегистрирован за {}", spn, username);
+                    }
+                    SpnCommand::Remove { username, spn } => {
+                        service.remove_spn(user.id, &spn).await?;
+                        println!("🗑️ SPN {} снят с {}", spn, username);
+                    }
+                    SpnCommand::List { .. } => {
+                        for spn in &user.service_principal_names {
+                            println!("{}", spn);
+                        }
+                    }
+                }
+            }
+        },
+        AppCommand::Ca { cmd } => match cmd {
+            CaCommand::Init { force } => {
+                service.init_ca(force).await?;
+                println!("🔐 CA «{}» создан, сертификат: `nextDomen ca cert`", config.ca.common_name);
+            }
+            CaCommand::Cert { out: Some(out) } => std::fs::write(&out, service.ca_certificate().await?)?,
+            CaCommand::Cert { out: None } => print!("{}", service.ca_certificate().await?),
+            CaCommand::Issue { subject, csr, out, key_out } => {
+                let user = service.find_user_by_username(&subject).await?
+                    .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", subject)))?;
+                let (csr, key) = match csr {
+                    Some(path) => (std::fs::read(path)?, None),
+                    None => {
+                        let (csr, key) = nextDomen::ca::generate_csr(&user.username)?;
+                        (csr, Some(key))
+                    }
+                };
+                let certificate = service.issue_certificate(&user, &csr).await?;
+                std::fs::write(&out, &certificate.pem)?;
+                if let (Some(key), Some(key_out)) = (key, key_out) {
+                    init::write_secret(&key_out, key.as_bytes())?;
+                }
+                println!("✅ Сертификат {} для {} записан в {}, действует до {}", certificate.serial, subject, out.display(), certificate.not_after.format("%Y-%m-%d"));
+            }
+            CaCommand::List { subject, output } => {
+                let output = output.resolve(Default::default());
+                let subject_id = match subject {
+                    Some(username) => Some(service.find_user_by_username(&username).await?
+                        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?
+                        .id),
+                    None => None,
+                };
+                output.list(&service.list_certificates(subject_id).await?)?;
+            }
+            CaCommand::Revoke { serial, reason } => {
+                service.revoke_certificate(&serial, reason).await?;
+                println!("🚫 Сертификат {} отозван, он попадёт в следующий CRL", serial);
+            }
+            CaCommand::Crl { out } => {
+                std::fs::write(&out, service.certificate_revocation_list().await?)?;
+                println!("📜 CRL записан в {}", out.display());
+            }
+        },
+        AppCommand::Dns { addr, dynamic_updates } => {
+            tracing::info!(addr = %addr.join(","), "Запуск DNS");
+            dns::DnsServer::bind(Arc::clone(&service), &addr, dynamic_updates).await?.run().await?;
+        }
+        AppCommand::Radius => {
+            tracing::info!("Запуск RADIUS");
+            radius::RadiusServer::bind(Arc::clone(&service), &config.radius_server).await?.run().await?;
+        }
+        AppCommand::Stats { output } => {
+            let output = output.resolve(Default::default());
+            let stats = service.stats().await?;
+            output.one(&stats)?;
+            // В таблице пользователи по OU — отдельным списком, в JSON и YAML они в `users_per_ou`
+            if output.format == cli::OutputFormat::Table && !output.quiet {
+                println!();
+                output.list(&stats.users_per_ou)?;
+            }
         }
-        AppCommand::Cli => {
-            println!("💻 Запуск CLI режима");
-            cli::run_cli().await?;
+        AppCommand::CheckReferences { output } => {
+            let output = output.resolve(Default::default());
+            let dangling = service.find_dangling_references().await?;
+            if dangling.is_empty() && output.format == cli::OutputFormat::Table && !output.quiet {
+                println!("✅ Висячих ссылок нет");
+            } else {
+                output.list(&dangling)?;
+            }
+        }
+        AppCommand::Jobs { cmd, output } => {
+            let registry = jobs::JobRegistry::builtin(&config)?;
+            let output = output.resolve(Default::default());
+            match cmd {
+                JobsCommand::List => output.list(&registry.status(&service).await?)?,
+                JobsCommand::Run { name } => {
+                    let run = registry.run_job(&service, &name, nextDomen::models::JobTrigger::Manual).await?;
+                    match (&run.processed, &run.error) {
+                        (Some(processed), _) => println!("✅ Задача {} выполнена за {} мс, обработано: {}", name, run.duration_ms(), processed),
+                        (None, error) => return Err(format!("Job {} failed: {}", name, error.as_deref().unwrap_or_default()).into()),
+                    }
+                }
+                JobsCommand::History { name } => {
+                    registry.get(&name).ok_or_else(|| jobs::JobError::UnknownJob(name.clone()))?;
+                    output.list(&service.get_job_runs(&name).await?)?;
+                }
+            }
+        }
+        AppCommand::Sync { once, full } => {
+            let sync_config = config.ldap_sync.ok_or("Section ldap_sync is missing in the configuration")?;
+            let connector = sync::LdapSyncConnector::new(Arc::clone(&service), sync_config);
+            if once {
+                sync::print_report(&connector.run_once(full).await?);
+            } else {
+                connector.run(full).await?;
+            }
         }
     }
 
+    // Канал аудита закрывается вместе с сервисом — дожидаемся записи последних событий
+    drop(service);
+    let _ = audit_writer.join();
     Ok(())
 }
 
-/// Конфигурация из `config.yaml`
-#[derive(serde::Deserialize)]
-struct Config {
-    db_path: String,
-    master_key_hex: String,
-}
-
-fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
-    let file = std::fs::File::open("config.yaml")?;
-    let config: Config = serde_yaml::from_reader(file)?;
-    Ok(config)
+fn check_config(config: &config::AppConfig, listeners: &[config::validate::Listener]) -> Result<(), Box<dyn std::error::Error>> {
+    let issues = config.validate(listeners);
+    for issue in &issues {
+        eprintln!("❌ {}", issue);
+    }
+    if !issues.is_empty() {
+        return Err(format!("Configuration has {} problem(s), see `nextDomen config validate`", issues.len()).into());
+    }
+    Ok(())
 }
-
-fn decode_key(hex: &str) -> Result<[u8; 32], hex::FromHexError> {
-    let mut key = [0u8; 32];
-    hex::decode_to_slice(hex, &mut key)?;
-    Ok(key)
-}
\ No newline at end of file
@@ -2,16 +2,28 @@
 
 use axum::{
     async_trait,
-    extract::{FromRequestParts, State},
-    http::{request::Parts, StatusCode},
+    extract::{ConnectInfo, FromRequestParts, Request},
+    http::{header, request::Parts, Extensions, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
+use std::marker::PhantomData;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
-use crate::directory_service::DirectoryService;
+use crate::audit::actor::ActorContext;
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::error_code::ErrorCode;
 use crate::auth::{self, Claims};
+use crate::models::apikey::scope;
+use crate::models::{ApiKey, LoginProtocol, User};
+use crate::proxy::TrustedProxies;
+use crate::web::access_log::RequestId;
+
+/// Заголовок с API-ключом
+pub const API_KEY_HEADER: &str = "X-Api-Key";
 
 /// Состояние приложения
 pub type AppState = Arc<DirectoryService>;
@@ -22,17 +34,27 @@ pub enum AuthError {
     NoToken,
     InvalidToken,
     DecodeError,
+    Forbidden,
+    InvalidApiKey,
+    /// У API-ключа нет нужной области
+    MissingScope(&'static str),
+    /// Обработчик принимает только вход по JWT
+    ApiKeyNotAllowed,
 }
 
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            AuthError::NoToken => (StatusCode::UNAUTHORIZED, "Missing token"),
-            AuthError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid or expired token"),
-            AuthError::DecodeError => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to decode token"),
+        let (status, code, message) = match self {
+            AuthError::NoToken => (StatusCode::UNAUTHORIZED, ErrorCode::MissingToken, "Missing token".to_string()),
+            AuthError::InvalidToken => (StatusCode::UNAUTHORIZED, ErrorCode::InvalidToken, "Invalid or expired token".to_string()),
+            AuthError::DecodeError => (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::InternalError, "Failed to decode token".to_string()),
+            AuthError::Forbidden => (StatusCode::FORBIDDEN, ErrorCode::AccessDenied, "Administrator rights required".to_string()),
+            AuthError::InvalidApiKey => (StatusCode::UNAUTHORIZED, ErrorCode::InvalidApiKey, "Invalid or expired API key".to_string()),
+            AuthError::MissingScope(scope) => (StatusCode::FORBIDDEN, ErrorCode::MissingScope, format!("API key lacks scope {}", scope)),
+            AuthError::ApiKeyNotAllowed => (StatusCode::FORBIDDEN, ErrorCode::AccessDenied, "API keys are not accepted here; sign in as an administrator".to_string()),
         };
 
-        (status, Json(json!({ "error": message }))).into_response()
+        crate::web::errors::problem(status, code, &message)
     }
 }
 
@@ -49,18 +71,52 @@ where
         let auth_header = parts.headers
             .get("Authorization")
             .and_then(|h| h.to_str().ok())
-            .and_then(|h| h.strip_prefix("Bearer "));
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(str::to_string);
 
         let token = auth_header.ok_or(AuthError::NoToken)?;
 
         // 2. Валидировать токен
-        match auth::validate_token(token) {
+        match auth::validate_token(&token) {
             Ok(claims) => Ok(claims),
             Err(_) => Err(AuthError::InvalidToken),
         }
     }
 }
 
+/// Токен из `?access_token=` для страниц, которые браузер открывает без своих заголовков
+/// (EventSource, WebSocket, Swagger UI): если нет ни `Authorization`, ни `X-Api-Key`, параметр
+/// становится заголовком `Authorization: Bearer`, дальше проверяет извлекатель `T`. Остальные
+/// обработчики токен в адресе не принимают — он оседает в журналах прокси и истории браузера
+pub struct QueryToken<T>(pub T);
+
+#[async_trait]
+impl<T> FromRequestParts<AppState> for QueryToken<T>
+where
+    T: FromRequestParts<AppState>,
+{
+    type Rejection = T::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, service: &AppState) -> Result<Self, Self::Rejection> {
+        let has_credentials = parts.headers.contains_key(header::AUTHORIZATION) || parts.headers.contains_key(API_KEY_HEADER);
+        if !has_credentials
+            && let Some(value) = query_token(parts).and_then(|token| HeaderValue::from_str(&format!("Bearer {}", token)).ok())
+        {
+            parts.headers.insert(header::AUTHORIZATION, value);
+        }
+        T::from_request_parts(parts, service).await.map(QueryToken)
+    }
+}
+
+fn query_token(parts: &Parts) -> Option<String> {
+    let query = parts.uri.query()?;
+    serde_urlencoded::from_str::<Vec<(String, String)>>(query)
+        .ok()?
+        .into_iter()
+        .find(|(name, _)| name == "access_token")
+        .map(|(_, value)| value)
+}
+
 /// Middleware: проверяет JWT и извлекает `Claims`
 pub async fn auth_middleware(
     claims: Result<Claims, AuthError>,
@@ -77,18 +133,289 @@ pub async fn auth_middleware(
     }
 }
 
-/// Утилита: проверка, является ли пользователь админом (пример)
-pub async fn require_admin(
-    claims: Claims,
-    State(service): State<AppState>,
-) -> Result<Claims, AuthError> {
-    let user = service.get_user(uuid::Uuid::parse_str(&claims.sub).map_err(|_| AuthError::DecodeError)?).await
+/// Аутентифицированный администратор: включённая учётная запись из группы Domain Admins.
+/// Сам по себе извлекатель принимает только JWT: у API-ключа нет области «администратор вообще»,
+/// поэтому обработчики, доступные ключам, берут `Authorized<S>` с конкретной областью
+pub struct AdminUser {
+    pub user: User,
+    /// `None` — вход по JWT, доступны все области
+    pub api_key: Option<ApiKey>,
+}
+
+impl AdminUser {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.api_key.as_ref().is_none_or(|key| key.has_scope(scope))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, service: &AppState) -> Result<Self, Self::Rejection> {
+        let admin = authenticate_admin(parts, service).await?;
+        if admin.api_key.is_some() {
+            return Err(AuthError::ApiKeyNotAllowed);
+        }
+        Ok(admin)
+    }
+}
+
+/// Участник Domain Admins по JWT или по API-ключу; области ключа проверяет вызывающий
+async fn authenticate_admin(parts: &mut Parts, service: &AppState) -> Result<AdminUser, AuthError> {
+    let (user, api_key) = authenticate(parts, service).await?;
+    if !service.is_domain_admin(user.id).await.map_err(|_| AuthError::InvalidToken)? {
+        return Err(AuthError::Forbidden);
+    }
+
+    Ok(AdminUser { user, api_key })
+}
+
+/// Аутентифицированный пользователь без требования прав администратора: доступ к объекту
+/// каталога решает его DACL (`DirectoryService::authorize`). По JWT или по API-ключу с областью `directory:write`
+pub struct Caller {
+    pub user: User,
+    pub api_key: Option<ApiKey>,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Caller {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, service: &AppState) -> Result<Self, Self::Rejection> {
+        let (user, api_key) = authenticate(parts, service).await?;
+        if api_key.as_ref().is_some_and(|key| !key.has_scope(scope::DIRECTORY_WRITE)) {
+            return Err(AuthError::MissingScope(scope::DIRECTORY_WRITE));
+        }
+
+        Ok(Caller { user, api_key })
+    }
+}
+
+/// Аутентифицированный пользователь для чтения каталога: какие объекты ему видны, решает DACL
+/// (`READ_PROPERTY`). По JWT или по API-ключу с областью `directory:read` или `directory:write`
+pub struct Reader {
+    pub user: User,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Reader {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, service: &AppState) -> Result<Self, Self::Rejection> {
+        let (user, api_key) = authenticate(parts, service).await?;
+        if api_key.as_ref().is_some_and(|key| !key.has_scope(scope::DIRECTORY_READ) && !key.has_scope(scope::DIRECTORY_WRITE)) {
+            return Err(AuthError::MissingScope(scope::DIRECTORY_READ));
+        }
+
+        Ok(Reader { user })
+    }
+}
+
+/// Вошедший пользователь для операций над собой (`/api/me`): DACL не проверяется, что можно
+/// менять — решает `security.self_service`. По JWT или по API-ключу с областью `self:service`
+pub struct SelfServiceUser {
+    pub user: User,
+    pub api_key: Option<ApiKey>,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for SelfServiceUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, service: &AppState) -> Result<Self, Self::Rejection> {
+        let (user, api_key) = authenticate(parts, service).await?;
+        if api_key.as_ref().is_some_and(|key| !key.has_scope(scope::SELF_SERVICE)) {
+            return Err(AuthError::MissingScope(scope::SELF_SERVICE));
+        }
+
+        Ok(SelfServiceUser { user, api_key })
+    }
+}
+
+/// Вошедший пользователь без требования прав администратора для операций области `S` (например,
+/// выпуск сертификата себе): по JWT или по API-ключу с этой областью
+pub struct Scoped<S> {
+    pub user: User,
+    pub api_key: Option<ApiKey>,
+    _scope: PhantomData<S>,
+}
+
+#[async_trait]
+impl<S: ApiScope> FromRequestParts<AppState> for Scoped<S> {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, service: &AppState) -> Result<Self, Self::Rejection> {
+        let (user, api_key) = authenticate(parts, service).await?;
+        if api_key.as_ref().is_some_and(|key| !key.has_scope(S::SCOPE)) {
+            return Err(AuthError::MissingScope(S::SCOPE));
+        }
+
+        Ok(Scoped { user, api_key, _scope: PhantomData })
+    }
+}
+
+/// Включённая учётная запись по `X-Api-Key` (владелец ключа) или по JWT
+async fn authenticate(parts: &mut Parts, service: &AppState) -> Result<(User, Option<ApiKey>), AuthError> {
+    let api_key = match parts.headers.get(API_KEY_HEADER) {
+        Some(value) => {
+            let value = value.to_str().map_err(|_| AuthError::InvalidApiKey)?;
+            Some(service.authenticate_api_key(value).await.map_err(|e| match e {
+                DirectoryError::AuthenticationFailed(_) => AuthError::InvalidApiKey,
+                _ => AuthError::DecodeError,
+            })?)
+        }
+        None => None,
+    };
+    let user_id = match &api_key {
+        Some(key) => key.owner_id,
+        None => {
+            let claims = Claims::from_request_parts(parts, service).await?;
+            // Отозванная сессия (`DELETE /api/sessions/:id`) отклоняется до истечения токена
+            if let Some(session_id) = claims.session_id()
+                && service.is_session_revoked(session_id).await.map_err(|_| AuthError::InvalidToken)?
+            {
+                return Err(AuthError::InvalidToken);
+            }
+            uuid::Uuid::parse_str(&claims.sub).map_err(|_| AuthError::DecodeError)?
+        }
+    };
+
+    let user = service.get_user(user_id).await
         .map_err(|_| AuthError::InvalidToken)?
+        .filter(|user| user.enabled)
         .ok_or(AuthError::InvalidToken)?;
+    ActorContext::set_current_user(Some(&user));
+    Ok((user, api_key))
+}
+
+/// Адрес клиента для аудита и лимитов входа, IPv4 или IPv6 (`crate::net::client_ip`); за
+/// доверенным прокси (`web_server.proxy.trusted`) — из X-Forwarded-For
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = <ConnectInfo<SocketAddr> as FromRequestParts<S>>::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ConnectInfo(peer) = ConnectInfo::<SocketAddr>::from_request_parts(parts, state).await?;
+        Ok(ClientIp(client_ip(peer, &parts.extensions, &parts.headers)))
+    }
+}
 
-    if !user.is_admin() {
-        return Err(AuthError::InvalidToken); // или создать свой `Forbidden`
+fn client_ip(peer: SocketAddr, extensions: &Extensions, headers: &HeaderMap) -> IpAddr {
+    let peer = crate::net::client_ip(peer);
+    match extensions.get::<Arc<TrustedProxies>>() {
+        Some(proxies) => proxies.forwarded_client(peer, headers),
+        None => peer,
     }
+}
+
+/// Слой REST: запрос обрабатывается в `ActorContext` с адресом клиента и идентификатором запроса,
+/// а извлекатели `Caller`, `AdminUser` и `Authorized` дописывают в него вошедшего — автора событий
+/// аудита. Итоговый контекст уходит в расширения ответа для журнала доступа
+pub async fn actor_context(request: Request, next: Next) -> Response {
+    let ip_addr = request.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| client_ip(*addr, request.extensions(), request.headers()).to_string());
+    let device = request.headers()
+        .get(header::USER_AGENT)
+        .and_then(|agent| agent.to_str().ok())
+        .map(str::to_string);
+    let request_id = request.extensions().get::<RequestId>().map(|RequestId(id)| id.clone());
+    let context = ActorContext::new(LoginProtocol::Rest, ip_addr).with_device(device).with_request_id(request_id);
+    let (mut response, actor) = context.scope(async move {
+        let response = next.run(request).await;
+        (response, ActorContext::current())
+    }).await;
+    // Для журнала доступа (`web::access_log`)
+    if let Some(actor) = actor {
+        response.extensions_mut().insert(actor);
+    }
+    response
+}
+
+/// Область, которую требует обработчик
+pub trait ApiScope {
+    const SCOPE: &'static str;
+}
+
+pub struct AuditRead;
+pub struct EventsRead;
+pub struct ApiKeysManage;
+pub struct ReplicationPull;
+pub struct ChangesRead;
+pub struct ConfigReload;
+pub struct DirectoryWrite;
+pub struct CaIssue;
+pub struct ApprovalsRead;
+pub struct SessionsManage;
+pub struct ServiceAccountsManage;
+pub struct ServiceAccountsPassword;
+
+impl ApiScope for AuditRead {
+    const SCOPE: &'static str = scope::AUDIT_READ;
+}
 
-    Ok(claims)
-}
\ No newline at end of file
+impl ApiScope for EventsRead {
+    const SCOPE: &'static str = scope::EVENTS_READ;
+}
+
+impl ApiScope for ReplicationPull {
+    const SCOPE: &'static str = scope::REPLICATION_PULL;
+}
+
+impl ApiScope for ChangesRead {
+    const SCOPE: &'static str = scope::CHANGES_READ;
+}
+
+impl ApiScope for ConfigReload {
+    const SCOPE: &'static str = scope::CONFIG_RELOAD;
+}
+
+impl ApiScope for DirectoryWrite {
+    const SCOPE: &'static str = scope::DIRECTORY_WRITE;
+}
+
+impl ApiScope for CaIssue {
+    const SCOPE: &'static str = scope::CA_ISSUE;
+}
+
+impl ApiScope for ApprovalsRead {
+    const SCOPE: &'static str = scope::APPROVALS_READ;
+}
+
+impl ApiScope for SessionsManage {
+    const SCOPE: &'static str = scope::SESSIONS_MANAGE;
+}
+
+impl ApiScope for ServiceAccountsManage {
+    const SCOPE: &'static str = scope::SERVICE_ACCOUNTS_MANAGE;
+}
+
+impl ApiScope for ServiceAccountsPassword {
+    const SCOPE: &'static str = scope::SERVICE_ACCOUNTS_PASSWORD;
+}
+
+impl ApiScope for ApiKeysManage {
+    const SCOPE: &'static str = scope::APIKEYS_MANAGE;
+}
+
+/// Администратор с доступом к области `S`: по JWT или по API-ключу с этой областью
+pub struct Authorized<S>(pub AdminUser, pub PhantomData<S>);
+
+#[async_trait]
+impl<S: ApiScope> FromRequestParts<AppState> for Authorized<S> {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, service: &AppState) -> Result<Self, Self::Rejection> {
+        let admin = authenticate_admin(parts, service).await?;
+        if !admin.has_scope(S::SCOPE) {
+            return Err(AuthError::MissingScope(S::SCOPE));
+        }
+        Ok(Authorized(admin, PhantomData))
+    }
+}
@@ -0,0 +1,168 @@
+// src/models/agent.rs
+
+//! Агент групповых политик на клиенте: при check-in он получает итоговый набор политик
+//! (RSoP) и сообщает, как применил полученные в прошлый раз. По отчётам считается соответствие
+//! компьютера политикам.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+
+use super::policy::{GroupPolicy, PolicyType, PolicyValue};
+
+/// Итог применения политики на клиенте
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyApplyStatus {
+    Applied,
+    Failed,
+    /// Агент не применял политику, например не поддерживает её тип
+    Skipped,
+}
+
+/// Отчёт агента о применении одной политики
+#[derive(Serialize, Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct PolicyReport {
+    pub gpo_id: Uuid,
+    /// Версия политики, которую агент применял
+    pub version: u32,
+    pub status: PolicyApplyStatus,
+    /// Текст ошибки или причина пропуска
+    pub message: Option<String>,
+}
+
+/// Политика, полученная агентом при check-in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedPolicy {
+    pub gpo_id: Uuid,
+    pub version: u32,
+}
+
+/// Соответствие компьютера одной политике
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ComplianceState {
+    /// Применена текущая версия
+    Compliant,
+    /// Агент ещё не сообщил о применении
+    Pending,
+    /// Применена прежняя версия
+    Outdated,
+    Failed,
+    Skipped,
+}
+
+/// Последний check-in агента: компьютер и учётная запись, от имени которой работает агент
+/// (сам компьютер или вошедший на нём пользователь)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentCheckin {
+    pub computer_id: Uuid,
+    /// Имя компьютера без `$`
+    pub computer: String,
+    pub principal_id: Uuid,
+    pub principal: String,
+    pub checked_in_at: DateTime<Utc>,
+    /// Политики, выданные агенту при этом check-in, по убыванию приоритета
+    pub expected: Vec<ExpectedPolicy>,
+    pub reports: Vec<PolicyReport>,
+    /// Все выданные политики применены в выданной версии
+    pub compliant: bool,
+}
+
+impl AgentCheckin {
+    pub fn new(computer: &super::User, principal: &super::User, expected: Vec<ExpectedPolicy>, reports: Vec<PolicyReport>) -> Self {
+        let mut checkin = Self {
+            computer_id: computer.id,
+            computer: computer.username.trim_end_matches('$').to_string(),
+            principal_id: principal.id,
+            principal: principal.username.clone(),
+            checked_in_at: Utc::now(),
+            expected,
+            reports,
+            compliant: false,
+        };
+        checkin.compliant = checkin.expected.iter()
+            .all(|policy| checkin.state(policy.gpo_id, policy.version) == Some(ComplianceState::Compliant));
+        checkin
+    }
+
+    pub fn report(&self, gpo_id: Uuid) -> Option<&PolicyReport> {
+        self.reports.iter().find(|report| report.gpo_id == gpo_id)
+    }
+
+    /// Соответствие политике текущей версии `version`; `None` — политика агенту не выдавалась
+    pub fn state(&self, gpo_id: Uuid, version: u32) -> Option<ComplianceState> {
+        if !self.expected.iter().any(|policy| policy.gpo_id == gpo_id) {
+            return None;
+        }
+        Some(match self.report(gpo_id) {
+            None => ComplianceState::Pending,
+            Some(report) => match report.status {
+                PolicyApplyStatus::Failed => ComplianceState::Failed,
+                PolicyApplyStatus::Skipped => ComplianceState::Skipped,
+                PolicyApplyStatus::Applied if report.version < version => ComplianceState::Outdated,
+                PolicyApplyStatus::Applied => ComplianceState::Compliant,
+            },
+        })
+    }
+}
+
+/// Настройка итогового набора политик: значение из политики с наибольшим приоритетом
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, utoipa::ToSchema)]
+pub struct ResolvedSetting {
+    /// Тип политики (`Security`, `Registry`, ...)
+    pub policy_type: String,
+    /// Ключ настройки; для Registry — `куст\раздел\параметр`
+    pub key: String,
+    /// Для Registry — `data`
+    #[schema(value_type = Object)]
+    pub value: PolicyValue,
+    /// Тип параметра реестра
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<String>,
+    /// Политика, из которой взято значение
+    pub gpo_id: Uuid,
+}
+
+/// Слияние настроек политик, упорядоченных по убыванию приоритета: ключ берётся из первой
+/// политики, где он задан. Политика Registry задаёт один параметр реестра
+pub fn merge_settings(gpos: &[GroupPolicy]) -> Vec<ResolvedSetting> {
+    let string = |gpo: &GroupPolicy, key: &str| match gpo.get_setting(key) {
+        Some(PolicyValue::String(value)) => Some(value.clone()),
+        _ => None,
+    };
+
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    for gpo in gpos {
+        let policy_type = gpo.policy_type.name().to_string();
+        if gpo.policy_type == PolicyType::Registry {
+            let (Some(hive), Some(key), Some(value_name), Some(data)) =
+                (string(gpo, "hive"), string(gpo, "key"), string(gpo, "value_name"), gpo.get_setting("data"))
+            else {
+                continue;
+            };
+            let key = format!("{}\\{}\\{}", hive.to_uppercase(), key, value_name);
+            if seen.insert((policy_type.clone(), key.to_lowercase())) {
+                merged.push(ResolvedSetting { policy_type, key, value: data.clone(), value_type: string(gpo, "value_type"), gpo_id: gpo.id });
+            }
+            continue;
+        }
+
+        let mut keys: Vec<&String> = gpo.settings.keys().collect();
+        keys.sort();
+        for key in keys {
+            if seen.insert((policy_type.clone(), key.to_lowercase())) {
+                merged.push(ResolvedSetting {
+                    policy_type: policy_type.clone(),
+                    key: key.clone(),
+                    value: gpo.settings[key].clone(),
+                    value_type: None,
+                    gpo_id: gpo.id,
+                });
+            }
+        }
+    }
+    merged
+}
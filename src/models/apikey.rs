@@ -0,0 +1,100 @@
+// src/models/apikey.rs
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Префикс ключа: по нему ключ легко найти в конфигурации и логах
+const KEY_PREFIX: &str = "ndk_";
+
+/// Области доступа API-ключа
+pub mod scope {
+    pub const AUDIT_READ: &str = "audit:read";
+    pub const EVENTS_READ: &str = "events:read";
+    pub const APIKEYS_MANAGE: &str = "apikeys:manage";
+    /// Чтение пользователей, групп, OU и контактов; какие объекты видны, решает DACL
+    pub const DIRECTORY_READ: &str = "directory:read";
+    /// Изменение пользователей, групп и OU и их ACL; права на объект проверяет DACL
+    pub const DIRECTORY_WRITE: &str = "directory:write";
+    /// Снимок каталога для реплики только для чтения
+    pub const REPLICATION_PULL: &str = "replication:pull";
+    /// Журнал изменений для внешних систем синхронизации
+    pub const CHANGES_READ: &str = "changes:read";
+    /// Перезагрузка конфигурации сервера
+    pub const CONFIG_RELOAD: &str = "config:reload";
+    /// Профиль, пароль и MFA самого владельца ключа (`/api/me`)
+    pub const SELF_SERVICE: &str = "self:service";
+    /// Выпуск сертификатов внутреннего CA, их список и отзыв
+    pub const CA_ISSUE: &str = "ca:issue";
+    /// Список заявок на чувствительные операции; решает заявки только вошедший администратор, не ключ
+    pub const APPROVALS_READ: &str = "approvals:read";
+    /// Список сессий входа пользователей и их отзыв
+    pub const SESSIONS_MANAGE: &str = "sessions:manage";
+    /// Создание, изменение и ротация паролей учётных записей служб
+    pub const SERVICE_ACCOUNTS_MANAGE: &str = "service-accounts:manage";
+    /// Получение пароля учётной записи службы; кому он выдаётся, решает `allowed_principals`
+    pub const SERVICE_ACCOUNTS_PASSWORD: &str = "service-accounts:password";
+
+    pub const ALL: &[&str] = &[AUDIT_READ, EVENTS_READ, APIKEYS_MANAGE, DIRECTORY_READ, DIRECTORY_WRITE, REPLICATION_PULL, CHANGES_READ, CONFIG_RELOAD, SELF_SERVICE, CA_ISSUE, APPROVALS_READ, SESSIONS_MANAGE,
+        SERVICE_ACCOUNTS_MANAGE, SERVICE_ACCOUNTS_PASSWORD];
+}
+
+/// Ключ для межсервисного доступа к REST API (`X-Api-Key`) от имени владельца
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    /// Пользователь, от имени которого действует ключ; права владельца проверяются при каждом вызове
+    pub owner_id: Uuid,
+    pub scopes: Vec<String>,
+    /// SHA-256 секрета (hex): секрет случайный, 256 бит, поэтому медленный хеш не нужен,
+    /// а ключ проверяется на каждом запросе
+    pub secret_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Новый ключ и его значение `ndk_<id>_<секрет>`; значение показывается один раз и не хранится
+    pub fn generate(name: impl Into<String>, owner_id: Uuid, scopes: Vec<String>, expires_at: Option<DateTime<Utc>>) -> (Self, String) {
+        let id = Uuid::new_v4();
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let secret = hex::encode(bytes);
+
+        let key = Self {
+            id,
+            name: name.into(),
+            owner_id,
+            scopes,
+            secret_hash: hash_secret(&secret),
+            created_at: Utc::now(),
+            expires_at,
+        };
+        (key, format!("{}{}_{}", KEY_PREFIX, id.simple(), secret))
+    }
+
+    /// Разобрать значение ключа на идентификатор и секрет
+    pub fn parse(value: &str) -> Option<(Uuid, &str)> {
+        let (id, secret) = value.strip_prefix(KEY_PREFIX)?.split_once('_')?;
+        Some((Uuid::parse_str(id).ok()?, secret))
+    }
+
+    pub fn verify_secret(&self, secret: &str) -> bool {
+        hash_secret(secret) == self.secret_hash
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|at| at <= Utc::now())
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
@@ -0,0 +1,92 @@
+// src/models/approval.rs
+
+//! Заявка на чувствительную операцию (принцип четырёх глаз): операция из
+//! `security.approvals.operations` не выполняется сразу, а ждёт решения другого администратора
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// Вид чувствительной операции в `security.approvals.operations`
+#[derive(Serialize, Deserialize, utoipa::ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalOperation {
+    /// Удаление пользователя
+    DeleteUser,
+    /// Добавление участника в административную группу (Domain Admins, Enterprise Admins, ...)
+    AddPrivilegedMember,
+    /// Удаление GPO, привязка и отвязка от OU
+    ChangeGpo,
+}
+
+/// Отложенная операция со всем, что нужно для её выполнения после одобрения
+#[derive(Serialize, Deserialize, utoipa::ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveOperation {
+    DeleteUser { user_id: Uuid },
+    /// `ttl_secs` — временное членство; срок отсчитывается от выполнения
+    AddGroupMember { group_id: Uuid, member_id: Uuid, ttl_secs: Option<u64> },
+    DeleteGpo { gpo_id: Uuid },
+    LinkGpo { gpo_id: Uuid, ou_id: Uuid },
+    UnlinkGpo { gpo_id: Uuid, ou_id: Uuid },
+}
+
+impl SensitiveOperation {
+    /// Объект, который меняет операция, — `target_id` событий аудита
+    pub fn target_id(&self) -> Uuid {
+        match self {
+            Self::DeleteUser { user_id } => *user_id,
+            Self::AddGroupMember { group_id, .. } => *group_id,
+            Self::DeleteGpo { gpo_id } | Self::LinkGpo { gpo_id, .. } | Self::UnlinkGpo { gpo_id, .. } => *gpo_id,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalStatus {
+    Pending,
+    /// Одобрена и выполнена
+    Approved,
+    Rejected,
+    /// Одобрена, но выполнить не удалось (объект удалён, группа отключена, ...)
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApprovalRequest {
+    pub id: Uuid,
+    pub operation: SensitiveOperation,
+    /// Операция словами на момент заявки: `delete user bob`
+    pub summary: String,
+    /// Кто запросил; решать заявку он не может
+    pub requested_by: Uuid,
+    pub requested_at: DateTime<Utc>,
+    pub status: ApprovalStatus,
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub comment: Option<String>,
+    /// Почему одобренная операция не выполнилась
+    pub error: Option<String>,
+}
+
+impl ApprovalRequest {
+    pub fn new(operation: SensitiveOperation, summary: String, requested_by: Uuid) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            operation,
+            summary,
+            requested_by,
+            requested_at: Utc::now(),
+            status: ApprovalStatus::Pending,
+            decided_by: None,
+            decided_at: None,
+            comment: None,
+            error: None,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.status == ApprovalStatus::Pending
+    }
+}
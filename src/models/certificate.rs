@@ -0,0 +1,88 @@
+// src/models/certificate.rs
+
+//! Внутренний CA: его ключ и сертификат хранятся в базе (зашифрованы мастер-ключом вместе с ней),
+//! выданные сертификаты привязаны к учётным записям пользователей и компьютеров
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// Ключ и сертификат CA (`nextDomen ca init`)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CertificateAuthority {
+    pub cert_pem: String,
+    /// Закрытый ключ ECDSA P-256, PKCS#8 PEM
+    pub key_pem: String,
+    pub created_at: DateTime<Utc>,
+    /// Номер последнего выпущенного CRL
+    pub crl_number: u64,
+}
+
+/// Для кого выпущен сертификат; от этого зависят SAN и назначение ключа
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateKind {
+    /// CN — имя пользователя, SAN — адрес почты (UPN); clientAuth и emailProtection
+    User,
+    /// CN и SAN — DNS-имена из SPN `HOST/...`; serverAuth и clientAuth
+    Computer,
+}
+
+impl CertificateKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CertificateKind::User => "user",
+            CertificateKind::Computer => "computer",
+        }
+    }
+}
+
+/// Причина отзыва (RFC 5280, 5.3.1)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, utoipa::ToSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationReason {
+    #[default]
+    Unspecified,
+    KeyCompromise,
+    AffiliationChanged,
+    Superseded,
+    CessationOfOperation,
+}
+
+impl RevocationReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RevocationReason::Unspecified => "unspecified",
+            RevocationReason::KeyCompromise => "key_compromise",
+            RevocationReason::AffiliationChanged => "affiliation_changed",
+            RevocationReason::Superseded => "superseded",
+            RevocationReason::CessationOfOperation => "cessation_of_operation",
+        }
+    }
+}
+
+/// Выданный сертификат
+#[derive(Serialize, Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct IssuedCertificate {
+    /// Серийный номер, hex
+    pub serial: String,
+    /// Учётная запись, для которой выпущен сертификат
+    pub subject_id: Uuid,
+    pub subject: String,
+    pub kind: CertificateKind,
+    pub sans: Vec<String>,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    /// Кто запросил выпуск; `None` — локальный CLI
+    pub issued_by: Option<Uuid>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revocation_reason: Option<RevocationReason>,
+    pub pem: String,
+}
+
+impl IssuedCertificate {
+    /// Не отозван и не истёк
+    pub fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.not_before <= now && now < self.not_after
+    }
+}
@@ -0,0 +1,76 @@
+// src/models/change.rs
+
+//! Журнал изменений каталога: каждая запись объекта получает следующий USN (как uSNChanged в AD).
+//! Внешние системы синхронизации забирают изменения после последнего виденного USN.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeType {
+    Add,
+    Modify,
+    Delete,
+}
+
+impl ChangeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeType::Add => "add",
+            ChangeType::Modify => "modify",
+            ChangeType::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChangeEntry {
+    /// Номер изменения, возрастает без пропусков с 1
+    pub usn: u64,
+    pub object_id: Uuid,
+    /// Тип объекта: `user`, `group`, `ou`, `contact`, `gpo`, `service_account`, `site`, `subnet`, `pso`
+    pub object_type: String,
+    pub change_type: ChangeType,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Cookie элемента управления DirSync: для клиента непрозрачен, внутри — последний
+/// обработанный USN
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DirSyncCookie {
+    pub usn: u64,
+}
+
+/// Начало cookie: чужой или повреждённый cookie отклоняется, а не читается как USN
+const DIRSYNC_COOKIE_MAGIC: &[u8; 4] = b"NDDS";
+
+impl DirSyncCookie {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = DIRSYNC_COOKIE_MAGIC.to_vec();
+        bytes.extend_from_slice(&self.usn.to_be_bytes());
+        bytes
+    }
+
+    /// Пустой cookie — первая, полная синхронизация
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.is_empty() {
+            return Ok(Self::default());
+        }
+        let usn = bytes.strip_prefix(DIRSYNC_COOKIE_MAGIC.as_slice())
+            .and_then(|usn| <[u8; 8]>::try_from(usn).ok())
+            .ok_or_else(|| "Invalid DirSync cookie".to_string())?;
+        Ok(Self { usn: u64::from_be_bytes(usn) })
+    }
+}
+
+/// Ответ DirSync: изменившиеся объекты и cookie для следующего запроса
+#[derive(Debug, Clone)]
+pub struct DirSyncPage {
+    /// Последнее изменение каждого объекта по возрастанию USN
+    pub changes: Vec<ChangeEntry>,
+    pub cookie: DirSyncCookie,
+    /// После cookie есть ещё изменения
+    pub more: bool,
+}
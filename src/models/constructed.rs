@@ -0,0 +1,64 @@
+// src/models/constructed.rs
+
+//! Конструируемые атрибуты (MS-ADTS 3.1.1.4.5): не хранятся, вычисляются при каждом поиске
+//! и попадают в ответ, только если клиент назвал их в списке атрибутов — `*` их не включает
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::models::{Group, LdapAttributes, LdapContext, LdapEntry, User};
+
+/// tokenGroups: двоичные SID всех групп пользователя с учётом вложенности и основной группы;
+/// как в AD, вычисляется только при поиске с областью base
+pub const TOKEN_GROUPS: &str = "tokenGroups";
+
+/// primaryGroupToken: RID группы — значение, которое пользователи хранят в primaryGroupID
+pub const PRIMARY_GROUP_TOKEN: &str = "primaryGroupToken";
+
+/// LDAP_SERVER_LINK_TTL_OID: с этим элементом управления значения memberOf временного
+/// членства приходят как `<TTL=секунды>,DN`, как в AD с Privileged Access Management
+pub const LINK_TTL_OID: &str = "1.2.840.113556.1.4.2309";
+
+/// Область поиска base (RFC 4511, 4.5.1.2)
+pub const SCOPE_BASE: u32 = 0;
+
+/// Назван ли атрибут в списке явно
+fn requested(attributes: &[String], name: &str) -> bool {
+    attributes.iter().any(|attr| attr.eq_ignore_ascii_case(name))
+}
+
+/// Добавить к записи пользователя запрошенные конструируемые атрибуты
+pub async fn add_user_attributes(
+    service: &DirectoryService,
+    user: &User,
+    scope: u32,
+    attributes: &[String],
+    entry: &mut LdapAttributes,
+) -> Result<(), DirectoryError> {
+    if scope == SCOPE_BASE && requested(attributes, TOKEN_GROUPS) {
+        let sids = service.get_token_groups(user.id).await?;
+        entry.insert_binary(TOKEN_GROUPS, sids.iter().map(|sid| sid.to_bytes()).collect());
+    }
+    Ok(())
+}
+
+/// memberOf пользователя с оставшимся сроком временного членства (`LINK_TTL_OID`)
+pub async fn add_link_ttl(ctx: &LdapContext<'_>, user: &User, entry: &mut LdapAttributes) -> Result<(), DirectoryError> {
+    let now = chrono::Utc::now();
+    let member_of: Vec<String> = ctx.service.find_groups_by_member(user.id).await?
+        .iter()
+        .map(|group| match group.member_ttl(user.id, now) {
+            Some(ttl) => format!("<TTL={}>,{}", ttl.num_seconds(), group.dn(ctx)),
+            None => group.dn(ctx),
+        })
+        .collect();
+    if !member_of.is_empty() {
+        entry.insert("memberOf", member_of);
+    }
+    Ok(())
+}
+
+/// Добавить к записи группы запрошенные конструируемые атрибуты
+pub fn add_group_attributes(group: &Group, attributes: &[String], entry: &mut LdapAttributes) {
+    if requested(attributes, PRIMARY_GROUP_TOKEN) {
+        entry.insert(PRIMARY_GROUP_TOKEN, vec![group.get_rid().to_string()]);
+    }
+}
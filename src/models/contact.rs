@@ -0,0 +1,153 @@
+// src/models/contact.rs
+
+//! Контакт (objectClass=contact): внешний адресат или общий почтовый ящик без учётных данных.
+//! В SID, пароле и входе не участвует — нужен адресным книгам, которые читают каталог по LDAP
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::Utc;
+use crate::directory_service::DirectoryError;
+use crate::models::ldap_entry::{LdapAttributes, LdapContext, LdapEntry};
+use std::collections::HashMap;
+
+/// Вид контакта; в LDAP — `msExchRecipientTypeDetails`, как у Exchange
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactKind {
+    /// Внешний адресат (MailContact)
+    #[default]
+    External,
+    /// Общий почтовый ящик отдела (SharedMailbox)
+    SharedMailbox,
+}
+
+impl ContactKind {
+    /// RecipientTypeDetails Exchange
+    pub fn recipient_type_details(&self) -> u64 {
+        match self {
+            ContactKind::External => 0x40,
+            ContactKind::SharedMailbox => 0x4,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Contact {
+    pub id: Uuid,
+    /// cn: имя в DN и адресной книге
+    pub name: String,
+    pub display_name: Option<String>,
+    pub given_name: Option<String>,
+    pub surname: Option<String>,
+    /// Основной адрес; у контакта он обязателен
+    pub email: String,
+    /// Дополнительные адреса (`proxyAddresses` с префиксом `smtp:`)
+    #[serde(default)]
+    pub other_emails: Vec<String>,
+    pub telephone_number: Option<String>,
+    pub company: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub kind: ContactKind,
+    pub organizational_unit: Option<Uuid>,
+    pub meta: HashMap<String, String>,
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+impl Contact {
+    pub fn new(name: String, email: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            display_name: None,
+            given_name: None,
+            surname: None,
+            email,
+            other_emails: Vec::new(),
+            telephone_number: None,
+            company: None,
+            description: None,
+            kind: ContactKind::External,
+            organizational_unit: None,
+            meta: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Проверка имени и адресов
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Contact name cannot be empty".to_string());
+        }
+        for email in std::iter::once(&self.email).chain(&self.other_emails) {
+            if !email.contains('@') {
+                return Err(format!("Invalid email '{}'", email));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LdapEntry for Contact {
+    fn dn(&self, ctx: &LdapContext<'_>) -> String {
+        ctx.child_dn(&self.name, self.organizational_unit)
+    }
+
+    fn object_classes(&self) -> &'static [&'static str] {
+        &["top", "person", "organizationalPerson", "contact"]
+    }
+
+    fn object_guid(&self) -> Uuid {
+        self.id
+    }
+
+    async fn attributes(&self, _ctx: &LdapContext<'_>) -> Result<LdapAttributes, DirectoryError> {
+        let mut entry = LdapAttributes::new();
+        entry.insert("cn".to_string(), vec![self.name.clone()]);
+        entry.insert("name".to_string(), vec![self.name.clone()]);
+        entry.insert("displayName".to_string(), vec![
+            self.display_name.as_deref().unwrap_or(&self.name).to_string()
+        ]);
+        entry.insert("mail".to_string(), vec![self.email.clone()]);
+        entry.insert("targetAddress".to_string(), vec![format!("SMTP:{}", self.email)]);
+
+        // proxyAddresses: основной адрес — `SMTP:`, остальные — `smtp:`
+        let mut proxy_addresses = vec![format!("SMTP:{}", self.email)];
+        proxy_addresses.extend(self.other_emails.iter().map(|email| format!("smtp:{}", email)));
+        entry.insert("proxyAddresses".to_string(), proxy_addresses);
+        entry.insert("msExchRecipientTypeDetails".to_string(), vec![self.kind.recipient_type_details().to_string()]);
+
+        if let Some(given_name) = &self.given_name {
+            entry.insert("givenName".to_string(), vec![given_name.clone()]);
+        }
+        if let Some(surname) = &self.surname {
+            entry.insert("sn".to_string(), vec![surname.clone()]);
+        }
+        if let Some(telephone_number) = &self.telephone_number {
+            entry.insert("telephoneNumber".to_string(), vec![telephone_number.clone()]);
+        }
+        if let Some(company) = &self.company {
+            entry.insert("company".to_string(), vec![company.clone()]);
+        }
+        if let Some(description) = &self.description {
+            entry.insert("description".to_string(), vec![description.clone()]);
+        }
+
+        entry.insert("whenCreated".to_string(), vec![format_ldap_time(&self.created_at)]);
+        entry.insert("whenChanged".to_string(), vec![format_ldap_time(&self.updated_at)]);
+
+        // meta — кастомные атрибуты; многозначные — по строке на значение
+        for (k, v) in &self.meta {
+            entry.insert(k.clone(), crate::models::schema::values(v));
+        }
+
+        Ok(entry)
+    }
+}
+
+/// Форматирует время в LDAP Generalized Time (YYYYMMDDHHMMSS.0Z)
+fn format_ldap_time(dt: &chrono::DateTime<Utc>) -> String {
+    dt.format("%Y%m%d%H%M%S.0Z").to_string()
+}
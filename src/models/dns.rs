@@ -0,0 +1,54 @@
+// src/models/dns.rs
+
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// Данные DNS-записи
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DnsRecordData {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    Ns(String),
+    Srv {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: String,
+    },
+    Soa {
+        mname: String,
+        rname: String,
+        serial: u32,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: u32,
+    },
+}
+
+/// DNS-запись зоны домена
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DnsRecord {
+    /// Полное имя в нижнем регистре, без завершающей точки
+    pub name: String,
+    pub ttl: u32,
+    pub data: DnsRecordData,
+
+    /// Учётная запись компьютера, зарегистрировавшая запись динамически
+    pub owner: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DnsRecord {
+    pub fn new(name: impl Into<String>, ttl: u32, data: DnsRecordData) -> Self {
+        Self {
+            name: name.into().trim_end_matches('.').to_lowercase(),
+            ttl,
+            data,
+            owner: None,
+            updated_at: Utc::now(),
+        }
+    }
+}
@@ -5,6 +5,10 @@ use uuid::Uuid;
 use crate::models::sid::SecurityIdentifier;
 use crate::models::policy::PolicyId;
 use chrono::Utc;
+use crate::directory_service::DirectoryError;
+use crate::dn::{self, Dn, Rdn};
+use crate::models::ldap_entry::{LdapAttributes, LdapContext, LdapEntry};
+use crate::models::UserAccountControl;
 use std::collections::HashMap;
 
 /// Уровень функциональности домена
@@ -70,10 +74,75 @@ impl Domain {
 
     /// Получить DN домена (например, DC=corp,DC=acme,DC=com)
     pub fn dn(&self) -> String {
-        self.dns_name
-            .split('.')
-            .map(|part| format!("DC={}", part))
-            .collect::<Vec<_>>()
-            .join(",")
+        Dn::from_dns_name(&self.dns_name).to_string()
     }
-}
\ No newline at end of file
+}
+/// Зарегистрированный контроллер домена (публикуется в DNS)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DomainControllerInfo {
+    pub id: Uuid,
+    pub domain_id: Uuid,
+    /// FQDN контроллера, например `dc01.corp.acme.com`
+    pub hostname: String,
+    pub ipv4_addresses: Vec<std::net::Ipv4Addr>,
+    pub ipv6_addresses: Vec<std::net::Ipv6Addr>,
+    pub registered_at: chrono::DateTime<Utc>,
+}
+
+impl LdapEntry for Domain {
+    fn dn(&self, _ctx: &LdapContext<'_>) -> String {
+        Domain::dn(self)
+    }
+
+    fn object_classes(&self) -> &'static [&'static str] {
+        &["top", "domain", "domainDNS"]
+    }
+
+    fn object_guid(&self) -> Uuid {
+        self.id
+    }
+
+    async fn attributes(&self, _ctx: &LdapContext<'_>) -> Result<LdapAttributes, DirectoryError> {
+        let mut entry = LdapAttributes::new();
+        entry.insert("dc", vec![self.dns_name.split('.').next().unwrap_or_default().to_string()]);
+        entry.insert("name", vec![self.name.clone()]);
+        entry.insert_binary("objectSid", vec![self.sid.to_bytes()]);
+        entry.insert("whenCreated", vec![self.created_at.format("%Y%m%d%H%M%S.0Z").to_string()]);
+        Ok(entry)
+    }
+}
+
+/// Контроллер домена в LDAP — учётная запись компьютера в `OU=Domain Controllers`, как в AD
+impl LdapEntry for DomainControllerInfo {
+    fn dn(&self, ctx: &LdapContext<'_>) -> String {
+        dn::join(&Rdn::cn(self.computer_name()), &format!("OU=Domain Controllers,{}", ctx.domain_dn))
+    }
+
+    fn object_classes(&self) -> &'static [&'static str] {
+        &["top", "person", "organizationalPerson", "user", "computer"]
+    }
+
+    fn object_guid(&self) -> Uuid {
+        self.id
+    }
+
+    async fn attributes(&self, _ctx: &LdapContext<'_>) -> Result<LdapAttributes, DirectoryError> {
+        let name = self.computer_name();
+        let account_control = UserAccountControl::SERVER_TRUST_ACCOUNT | UserAccountControl::TRUSTED_FOR_DELEGATION;
+        let mut entry = LdapAttributes::new();
+        entry.insert("cn", vec![name.clone()]);
+        entry.insert("name", vec![name.clone()]);
+        entry.insert("sAMAccountName", vec![format!("{}$", name)]);
+        entry.insert("dNSHostName", vec![self.hostname.clone()]);
+        entry.insert("userAccountControl", vec![account_control.bits().to_string()]);
+        entry.insert("whenCreated", vec![self.registered_at.format("%Y%m%d%H%M%S.0Z").to_string()]);
+        Ok(entry)
+    }
+}
+
+impl DomainControllerInfo {
+    /// NetBIOS-имя компьютера: первая метка FQDN заглавными
+    pub fn computer_name(&self) -> String {
+        self.hostname.split('.').next().unwrap_or_default().to_uppercase()
+    }
+}
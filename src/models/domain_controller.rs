@@ -1,10 +1,9 @@
-// src/domain_controller.rs
+// src/models/domain_controller.rs
 
 use crate::directory_service::{DirectoryService, DirectoryError};
-use crate::models::{Domain, User, Group, OrganizationalUnit};
-use crate::models::well_known::WellKnownContainers;
-use uuid::Uuid;
-use chrono::Utc;
+use crate::models::{Domain, Group, GroupTypeFlags, OrganizationalUnit, PasswordAlgorithm, PasswordHash, SecurityIdentifier, User, UserAccountControl};
+use crate::models::well_known::{self, rid, WellKnownContainers, CRITICAL_SYSTEM_OBJECT};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Контроллер домена — управляет жизненным циклом домена и системными объектами
@@ -17,40 +16,22 @@ impl DomainController {
         Self { service }
     }
 
-    /// Инициализировать новый домен с системными контейнерами
+    /// Инициализировать новый домен: системные контейнеры, well-known группы и гость
     pub async fn bootstrap_domain(
         &self,
         name: String,
         dns_name: String,
     ) -> Result<Domain, DirectoryError> {
-        use crate::models::sid::SecurityIdentifier;
-
-        let sid = SecurityIdentifier::new_nt_authority(500); // S-1-5-21-...-500
-        let domain = Domain {
-            id: Uuid::new_v4(),
-            sid,
-            name,
-            dns_name,
-            netbios_name: "CORP".to_string(),
-            parent_domain: None,
-            child_domains: vec![],
-            functional_level: crate::models::domain::FunctionalLevel::Native,
-            users: vec![],
-            groups: vec![],
-            organizational_units: vec![],
-            policies: vec![],
-            enabled: true,
-            created_at: Utc::now(),
-            meta: std::collections::HashMap::new(),
-        };
-
-        // Сохраняем домен
-        self.service.store(format!("domain:{}", domain.id), &domain).await?;
+        let mut domain = Domain::new(name, dns_name.trim_end_matches('.').to_lowercase(), SecurityIdentifier::new_domain());
+        domain.netbios_name = domain.dns_name.split('.').next().unwrap_or_default().to_uppercase();
+
+        // Сохраняем домен через сервис, чтобы он попал в индексы DNS-имён
+        self.service.create_domain(&domain).await?;
 
         // Создаём well-known контейнеры
         let wk = WellKnownContainers::new(&domain.dn());
 
-        for (guid, dn) in wk.list() {
+        for dn in wk.list().values() {
             let ou = OrganizationalUnit::new(
                 extract_cn(dn).unwrap_or("Unknown").to_string(),
                 dn.clone(),
@@ -59,25 +40,37 @@ impl DomainController {
             self.service.create_ou(&ou).await?;
         }
 
-        // Создаём группу "Domain Users"
-        let domain_users = Group::new(
-            "Domain Users".to_string(),
-            "DOMAIN USERS".to_string(),
-            domain.id,
-            crate::models::group::GroupTypeFlags::SECURITY,
-            crate::models::group::GroupScope::Global,
-        );
-        self.service.create_group(&domain_users).await?;
-
-        // Создаём группу "Domain Admins"
-        let domain_admins = Group::new(
-            "Domain Admins".to_string(),
-            "DOMAIN ADMINS".to_string(),
-            domain.id,
-            crate::models::group::GroupTypeFlags::SECURITY,
-            crate::models::group::GroupScope::Global,
-        );
-        self.service.create_group(&domain_admins).await?;
+        // Группы домена и BUILTIN с каноническими RID
+        for well_known in well_known::GROUPS {
+            let type_flags = if well_known.builtin {
+                GroupTypeFlags::SECURITY | GroupTypeFlags::BUILTIN
+            } else {
+                GroupTypeFlags::SECURITY
+            };
+            let mut group = Group::new(
+                well_known.name.to_string(),
+                well_known.name.to_string(),
+                domain.id,
+                type_flags,
+                well_known.scope,
+            );
+            group.sid = if well_known.builtin {
+                SecurityIdentifier::new_builtin(well_known.rid)
+            } else {
+                domain.sid.with_rid(well_known.rid)
+            };
+            group.description = Some(well_known.description.to_string());
+            mark_critical(&mut group.meta);
+            self.service.create_group(&group).await?;
+        }
+
+        // Гость: отключён, основная группа — Domain Guests
+        let mut guest = builtin_user(&domain, "Guest", rid::GUEST);
+        guest.enabled = false;
+        guest.user_account_control |= UserAccountControl::PASSWD_NOTREQD | UserAccountControl::DONT_EXPIRE_PASSWORD;
+        guest.primary_group_id = Some(rid::DOMAIN_GUESTS);
+        self.service.create_user(&guest).await?;
+        self.add_to_groups(&guest, well_known::GUEST_GROUPS).await?;
 
         // Логируем инициализацию
         self.service.log_action(
@@ -89,18 +82,27 @@ impl DomainController {
         Ok(domain)
     }
 
+    /// Создать встроенного администратора домена (RID 500) и включить его в группы администраторов
+    pub async fn create_administrator(&self, domain: &Domain, username: &str) -> Result<User, DirectoryError> {
+        let mut admin = builtin_user(domain, username, rid::ADMINISTRATOR);
+        admin.display_name = Some("Administrator".to_string());
+        self.service.create_user(&admin).await?;
+        self.add_to_groups(&admin, well_known::ADMINISTRATOR_GROUPS).await?;
+        Ok(admin)
+    }
+
+    async fn add_to_groups(&self, user: &User, groups: &[&str]) -> Result<(), DirectoryError> {
+        for name in groups {
+            let group = self.service.find_group_by_sam_account_name(name).await?
+                .ok_or_else(|| DirectoryError::NotFound(format!("Group {} not found", name)))?;
+            self.service.add_member_to_group(group.id, user.id, None).await?;
+        }
+        Ok(())
+    }
+
     /// Найти домен по DNS-имени
     pub async fn find_domain_by_dns(&self, dns_name: &str) -> Result<Option<Domain>, DirectoryError> {
-        let domains: Vec<Uuid> = self.service.load("all_domains_index").await?.unwrap_or_default();
-        for id in domains {
-            let key = format!("domain:{}", id);
-            if let Some(domain) = self.service.load::<Domain>(&key).await? {
-                if domain.dns_name == dns_name {
-                    return Ok(Some(domain));
-                }
-            }
-        }
-        Ok(None)
+        self.service.find_domain_by_dns_name(dns_name).await
     }
 }
 
@@ -108,4 +110,52 @@ impl DomainController {
 fn extract_cn(dn: &str) -> Option<&str> {
     dn.strip_prefix("CN=")
         .and_then(|s| s.split(',').next())
-}
\ No newline at end of file
+}
+
+/// Пометить объект как системный: удалить его нельзя
+fn mark_critical(meta: &mut HashMap<String, String>) {
+    meta.insert(CRITICAL_SYSTEM_OBJECT.to_string(), "TRUE".to_string());
+}
+
+/// Встроенная учётная запись домена без пароля
+fn builtin_user(domain: &Domain, username: &str, rid: u32) -> User {
+    let mut meta = HashMap::new();
+    mark_critical(&mut meta);
+    User {
+        id: uuid::Uuid::new_v4(),
+        sid: domain.sid.with_rid(rid),
+        username: username.to_string(),
+        user_principal_name: format!("{}@{}", username, domain.dns_name),
+        email: None,
+        display_name: None,
+        given_name: None,
+        surname: None,
+        password_hash: PasswordHash {
+            hash: String::new(),
+            algorithm: PasswordAlgorithm::Bcrypt,
+            salt: vec![],
+        },
+        password_expires: None,
+        last_password_change: chrono::Utc::now(),
+        lockout_until: None,
+        failed_logins: 0,
+        enabled: true,
+        mfa_enabled: false,
+        mfa_methods: vec![],
+        domains: vec![domain.id],
+        groups: vec![],
+        organizational_unit: None,
+        created_at: chrono::Utc::now(),
+        updated_at: chrono::Utc::now(),
+        last_login: None,
+        profile_path: None,
+        script_path: None,
+        meta,
+        primary_group_id: Some(rid::DOMAIN_USERS),
+        service_principal_names: vec![],
+        kerberos_keys: vec![],
+        user_account_control: UserAccountControl::NORMAL_ACCOUNT,
+        totp_secret: None,
+        totp_pending_secret: None,
+    }
+}
@@ -0,0 +1,307 @@
+// src/models/filter.rs
+
+//! Фильтр поиска LDAP в строковой форме (RFC 4515) и его проверка на LDAP-записи объекта.
+//! Атрибуты и значения сравниваются без учёта регистра, как у строковых атрибутов AD.
+
+use crate::models::ldap_entry::{parse_object_guid, LdapAttributes, OBJECT_GUID};
+use std::fmt;
+
+/// Правило сопоставления LDAP_MATCHING_RULE_BIT_AND
+const BIT_AND_RULE: &str = "1.2.840.113556.1.4.803";
+/// Правило сопоставления LDAP_MATCHING_RULE_BIT_OR
+const BIT_OR_RULE: &str = "1.2.840.113556.1.4.804";
+
+/// Атрибуты, по которым ищет ANR (Ambiguous Name Resolution) — `(anr=jo)` из адресной книги Outlook
+pub const ANR_ATTRIBUTES: &[&str] = &["displayName", "givenName", "sn", "name", "sAMAccountName", "mail"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LdapFilter {
+    And(Vec<LdapFilter>),
+    Or(Vec<LdapFilter>),
+    Not(Box<LdapFilter>),
+    Equality(String, String),
+    Approx(String, String),
+    GreaterOrEqual(String, String),
+    LessOrEqual(String, String),
+    Present(String),
+    Substring {
+        attr: String,
+        initial: Option<String>,
+        any: Vec<String>,
+        last: Option<String>,
+    },
+    /// `attr[:dn][:rule]:=value`; с `:dn` совпадают и RDN из distinguishedName — `(ou:dn:=Sales)`
+    Extensible {
+        attr: String,
+        rule: Option<String>,
+        dn_attributes: bool,
+        value: String,
+    },
+}
+
+impl LdapFilter {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        let (filter, rest) = parse_filter(s).map_err(|e| format!("Invalid LDAP filter '{}': {}", s, e))?;
+        if !rest.is_empty() {
+            return Err(format!("Invalid LDAP filter '{}': unexpected trailing '{}'", s, rest));
+        }
+        Ok(filter)
+    }
+
+    /// Во что раскрывается `(anr=value)`, как в AD: значение — начало любого из `ANR_ATTRIBUTES`;
+    /// «имя фамилия» — ещё и givenName и sn по началу в обоих порядках; `=value` — точное совпадение
+    pub fn anr(value: &str) -> LdapFilter {
+        let value = value.trim();
+        if let Some(exact) = value.strip_prefix('=') {
+            return LdapFilter::Or(ANR_ATTRIBUTES.iter()
+                .map(|attr| LdapFilter::Equality(attr.to_string(), exact.trim().to_string()))
+                .collect());
+        }
+        let prefix = |attr: &str, value: &str| LdapFilter::Substring {
+            attr: attr.to_string(),
+            initial: Some(value.to_string()),
+            any: Vec::new(),
+            last: None,
+        };
+        let mut filters: Vec<LdapFilter> = ANR_ATTRIBUTES.iter().map(|attr| prefix(attr, value)).collect();
+        if let Some((first, last)) = value.split_once(char::is_whitespace) {
+            let last = last.trim();
+            filters.push(LdapFilter::And(vec![prefix("givenName", first), prefix("sn", last)]));
+            filters.push(LdapFilter::And(vec![prefix("givenName", last), prefix("sn", first)]));
+        }
+        LdapFilter::Or(filters)
+    }
+
+    /// Подходит ли LDAP-запись (`LdapEntry`) под фильтр; двоичные значения не сравниваются
+    pub fn matches(&self, entry: &LdapAttributes) -> bool {
+        let values = |attr: &str| entry.text(attr);
+        match self {
+            LdapFilter::And(filters) => filters.iter().all(|f| f.matches(entry)),
+            LdapFilter::Or(filters) => filters.iter().any(|f| f.matches(entry)),
+            LdapFilter::Not(filter) => !filter.matches(entry),
+            LdapFilter::Equality(attr, value) if attr.eq_ignore_ascii_case("anr") => LdapFilter::anr(value).matches(entry),
+            LdapFilter::Equality(attr, value) if attr.eq_ignore_ascii_case(OBJECT_GUID) => {
+                parse_object_guid(value).is_some_and(|guid| entry.object_guid() == Some(guid))
+            }
+            LdapFilter::Equality(attr, value) | LdapFilter::Approx(attr, value) => {
+                values(attr).iter().any(|v| v.eq_ignore_ascii_case(value))
+            }
+            LdapFilter::GreaterOrEqual(attr, value) => {
+                values(attr).iter().any(|v| compare(v, value).is_ge())
+            }
+            LdapFilter::LessOrEqual(attr, value) => {
+                values(attr).iter().any(|v| compare(v, value).is_le())
+            }
+            LdapFilter::Present(attr) => !values(attr).is_empty(),
+            LdapFilter::Substring { attr, initial, any, last } => values(attr).iter().any(|text| {
+                let text = text.to_lowercase();
+                let mut pos = 0;
+                if let Some(initial) = initial.as_deref().map(str::to_lowercase) {
+                    if !text.starts_with(&initial) {
+                        return false;
+                    }
+                    pos = initial.len();
+                }
+                for part in any.iter().map(|part| part.to_lowercase()) {
+                    match text[pos..].find(&part) {
+                        Some(found) => pos += found + part.len(),
+                        None => return false,
+                    }
+                }
+                last.as_ref().is_none_or(|last| text[pos..].ends_with(&last.to_lowercase()))
+            }),
+            LdapFilter::Extensible { attr, rule, dn_attributes, value } => {
+                let matches_value = |v: &str| match rule.as_deref() {
+                    Some(BIT_AND_RULE) => bits(v).zip(bits(value)).is_some_and(|(v, mask)| v & mask == mask),
+                    Some(BIT_OR_RULE) => bits(v).zip(bits(value)).is_some_and(|(v, mask)| v & mask != 0),
+                    _ => v.eq_ignore_ascii_case(value),
+                };
+                let in_entry = !attr.is_empty() && values(attr).iter().any(|v| matches_value(v));
+                let in_dn = *dn_attributes && values("distinguishedName").iter().any(|dn| {
+                    crate::dn::Dn::parse(dn).is_ok_and(|dn| dn.rdns().iter()
+                        .any(|rdn| (attr.is_empty() || rdn.is(attr)) && matches_value(rdn.value())))
+                });
+                in_entry || in_dn
+            }
+        }
+    }
+}
+
+impl fmt::Display for LdapFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LdapFilter::And(filters) | LdapFilter::Or(filters) => {
+                write!(f, "({}", if matches!(self, LdapFilter::And(_)) { '&' } else { '|' })?;
+                for filter in filters {
+                    write!(f, "{}", filter)?;
+                }
+                write!(f, ")")
+            }
+            LdapFilter::Not(filter) => write!(f, "(!{})", filter),
+            LdapFilter::Equality(attr, value) => write!(f, "({}={})", attr, escape(value)),
+            LdapFilter::Approx(attr, value) => write!(f, "({}~={})", attr, escape(value)),
+            LdapFilter::GreaterOrEqual(attr, value) => write!(f, "({}>={})", attr, escape(value)),
+            LdapFilter::LessOrEqual(attr, value) => write!(f, "({}<={})", attr, escape(value)),
+            LdapFilter::Present(attr) => write!(f, "({}=*)", attr),
+            LdapFilter::Substring { attr, initial, any, last } => {
+                write!(f, "({}={}*", attr, initial.as_deref().map(escape).unwrap_or_default())?;
+                for part in any {
+                    write!(f, "{}*", escape(part))?;
+                }
+                write!(f, "{})", last.as_deref().map(escape).unwrap_or_default())
+            }
+            LdapFilter::Extensible { attr, rule, dn_attributes, value } => {
+                write!(f, "({}", attr)?;
+                if *dn_attributes {
+                    write!(f, ":dn")?;
+                }
+                if let Some(rule) = rule {
+                    write!(f, ":{}", rule)?;
+                }
+                write!(f, ":={})", escape(value))
+            }
+        }
+    }
+}
+
+/// `(filtercomp)` в начале строки; возвращает фильтр и остаток
+fn parse_filter(s: &str) -> Result<(LdapFilter, &str), String> {
+    let s = s.strip_prefix('(').ok_or("expected '('")?;
+    let (filter, rest) = match s.chars().next() {
+        Some('&') => parse_list(&s[1..], LdapFilter::And)?,
+        Some('|') => parse_list(&s[1..], LdapFilter::Or)?,
+        Some('!') => {
+            let (inner, rest) = parse_filter(&s[1..])?;
+            (LdapFilter::Not(Box::new(inner)), rest)
+        }
+        _ => {
+            let end = s.find(')').ok_or("missing ')'")?;
+            (parse_item(&s[..end])?, &s[end..])
+        }
+    };
+    let rest = rest.strip_prefix(')').ok_or("missing ')'")?;
+    Ok((filter, rest))
+}
+
+fn parse_list(mut s: &str, constructor: fn(Vec<LdapFilter>) -> LdapFilter) -> Result<(LdapFilter, &str), String> {
+    let mut filters = Vec::new();
+    while s.starts_with('(') {
+        let (filter, rest) = parse_filter(s)?;
+        filters.push(filter);
+        s = rest;
+    }
+    if filters.is_empty() {
+        return Err("empty filter list".to_string());
+    }
+    Ok((constructor(filters), s))
+}
+
+/// Простое условие без скобок: `attr=value`, `attr>=value`, `attr=*`, `attr=a*b*c`, `attr:dn:rule:=value`
+fn parse_item(s: &str) -> Result<LdapFilter, String> {
+    let eq = s.find('=').ok_or_else(|| format!("expected '=' in '{}'", s))?;
+    let (name, raw_value) = (&s[..eq], &s[eq + 1..]);
+
+    if let Some(name) = name.strip_suffix(':') {
+        let mut parts = name.split(':');
+        let attr = parts.next().unwrap_or_default().to_string();
+        let mut dn_attributes = false;
+        let mut rule = None;
+        for part in parts {
+            if part.eq_ignore_ascii_case("dn") {
+                dn_attributes = true;
+            } else if matches!(part, BIT_AND_RULE | BIT_OR_RULE) {
+                rule = Some(part.to_string());
+            } else {
+                return Err(format!("unsupported matching rule '{}'", part));
+            }
+        }
+        if attr.is_empty() && rule.is_none() {
+            return Err("extensible match needs an attribute or a matching rule".to_string());
+        }
+        return Ok(LdapFilter::Extensible { attr, rule, dn_attributes, value: unescape(raw_value)? });
+    }
+
+    let (attr, kind) = match name.as_bytes().last() {
+        Some(b'>') => (&name[..name.len() - 1], Some('>')),
+        Some(b'<') => (&name[..name.len() - 1], Some('<')),
+        Some(b'~') => (&name[..name.len() - 1], Some('~')),
+        _ => (name, None),
+    };
+    let attr = attr.trim();
+    if attr.is_empty() || !attr.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ';')) {
+        return Err(format!("invalid attribute '{}'", attr));
+    }
+    let attr = attr.to_string();
+
+    Ok(match kind {
+        Some('>') => LdapFilter::GreaterOrEqual(attr, unescape(raw_value)?),
+        Some('<') => LdapFilter::LessOrEqual(attr, unescape(raw_value)?),
+        Some(_) => LdapFilter::Approx(attr, unescape(raw_value)?),
+        None if raw_value == "*" => LdapFilter::Present(attr),
+        None if raw_value.contains('*') => {
+            let parts: Vec<&str> = raw_value.split('*').collect();
+            let optional = |part: &str| -> Result<Option<String>, String> {
+                Ok(if part.is_empty() { None } else { Some(unescape(part)?) })
+            };
+            LdapFilter::Substring {
+                attr,
+                initial: optional(parts[0])?,
+                any: parts[1..parts.len() - 1].iter()
+                    .filter(|part| !part.is_empty())
+                    .map(|part| unescape(part))
+                    .collect::<Result<_, _>>()?,
+                last: optional(parts[parts.len() - 1])?,
+            }
+        }
+        // objectGUID приходит и в двоичной экранированной форме, которая не UTF-8
+        None if attr.eq_ignore_ascii_case(OBJECT_GUID) => {
+            let guid = parse_object_guid(raw_value).ok_or_else(|| format!("invalid objectGUID '{}'", raw_value))?;
+            LdapFilter::Equality(attr, guid.to_string())
+        }
+        None => LdapFilter::Equality(attr, unescape(raw_value)?),
+    })
+}
+
+/// Значение с экранированием `\XX` (RFC 4515)
+fn unescape(value: &str) -> Result<String, String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'\\' {
+            let hex = tail.get(..2).and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("invalid escape in '{}'", value))?;
+            bytes.push(hex);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| format!("value '{}' is not UTF-8", value))
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '*' | '(' | ')' | '\\' | '\0' => out.push_str(&format!("\\{:02x}", c as u32)),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Числа сравниваются как числа, остальное (в том числе Generalized Time) — как строки
+fn compare(value: &str, bound: &str) -> std::cmp::Ordering {
+    match (value.parse::<i64>(), bound.parse::<i64>()) {
+        (Ok(value), Ok(bound)) => value.cmp(&bound),
+        _ => value.to_lowercase().cmp(&bound.to_lowercase()),
+    }
+}
+
+/// userAccountControl и другие битовые маски; отрицательные — как в AD (int32)
+fn bits(value: &str) -> Option<u64> {
+    value.parse::<u64>().ok().or_else(|| value.parse::<i32>().ok().map(|v| v as u32 as u64))
+}
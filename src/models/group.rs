@@ -3,7 +3,10 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::models::sid::SecurityIdentifier;
+use crate::models::filter::LdapFilter;
 use chrono::Utc;
+use crate::directory_service::DirectoryError;
+use crate::models::ldap_entry::{LdapAttributes, LdapContext, LdapEntry};
 use bitflags::bitflags;
 use std::collections::HashMap;
 
@@ -60,13 +63,26 @@ impl std::fmt::Debug for GroupTypeFlags {
 // 🌐 GroupScope — область действия
 // ========================================
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, utoipa::ToSchema, clap::ValueEnum)]
+#[clap(rename_all = "PascalCase")]
 pub enum GroupScope {
     DomainLocal,
     Global,
     Universal,
 }
 
+impl GroupScope {
+    /// Может ли группа этой области содержать группу области `member`, как в AD:
+    /// Global — только Global, Universal — Global и Universal, DomainLocal — любые
+    pub fn can_contain(self, member: GroupScope) -> bool {
+        match self {
+            GroupScope::Global => member == GroupScope::Global,
+            GroupScope::Universal => member != GroupScope::DomainLocal,
+            GroupScope::DomainLocal => true,
+        }
+    }
+}
+
 // ========================================
 // 👥 Group — основная структура
 // ========================================
@@ -84,6 +100,22 @@ pub struct Group {
     pub type_flags: GroupTypeFlags,
     pub created_at: chrono::DateTime<Utc>,
     pub meta: HashMap<String, String>,
+    /// Правило членства (фильтр LDAP): участники вычисляются по нему, `members` — последний снимок
+    #[serde(default)]
+    pub membership_rule: Option<String>,
+    /// Владелец (managedBy): одобряет заявки на вступление
+    #[serde(default)]
+    pub managed_by: Option<Uuid>,
+    /// После этого момента группа отключается фоновой задачей
+    #[serde(default)]
+    pub expires_at: Option<chrono::DateTime<Utc>>,
+    /// Когда группа отключена по сроку; отключённая группа не даёт членства (memberOf, tokenGroups)
+    #[serde(default)]
+    pub disabled_at: Option<chrono::DateTime<Utc>>,
+    /// Срок временного членства (как в AD с Privileged Access Management): после него
+    /// участник удаляется фоновой задачей
+    #[serde(default)]
+    pub member_expirations: HashMap<Uuid, chrono::DateTime<Utc>>,
 }
 
 // ========================================
@@ -110,6 +142,11 @@ impl Group {
             type_flags,
             created_at: Utc::now(),
             meta: HashMap::new(),
+            membership_rule: None,
+            managed_by: None,
+            expires_at: None,
+            disabled_at: None,
+            member_expirations: HashMap::new(),
         }
     }
 
@@ -123,6 +160,43 @@ impl Group {
         self.type_flags.contains(GroupTypeFlags::BUILTIN)
     }
 
+    /// Системная группа (`isCriticalSystemObject`), её нельзя удалить
+    pub fn is_protected(&self) -> bool {
+        crate::models::well_known::is_critical(&self.meta)
+    }
+
+    /// Динамическая группа: членство задаётся правилом, а не списком
+    pub fn is_dynamic(&self) -> bool {
+        self.membership_rule.is_some()
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
+
+    /// Срок истёк, а группа ещё не отключена
+    pub fn is_expired(&self, now: chrono::DateTime<Utc>) -> bool {
+        !self.is_disabled() && self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Сколько осталось временному членству; `None` — членство постоянное
+    pub fn member_ttl(&self, member_id: Uuid, now: chrono::DateTime<Utc>) -> Option<chrono::Duration> {
+        self.member_expirations.get(&member_id).map(|expires_at| (*expires_at - now).max(chrono::Duration::zero()))
+    }
+
+    /// Участники, срок членства которых истёк
+    pub fn expired_members(&self, now: chrono::DateTime<Utc>) -> Vec<Uuid> {
+        self.member_expirations.iter()
+            .filter(|(_, expires_at)| **expires_at <= now)
+            .map(|(member_id, _)| *member_id)
+            .collect()
+    }
+
+    /// Разобранное правило членства
+    pub fn membership_filter(&self) -> Result<Option<LdapFilter>, String> {
+        self.membership_rule.as_deref().map(LdapFilter::parse).transpose()
+    }
+
     #[allow(dead_code)]
     pub fn add_member(&mut self, user_id: Uuid) {
         if !self.members.contains(&user_id) {
@@ -135,60 +209,83 @@ impl Group {
         self.members.retain(|id| id != user_id);
     }
 
-    #[allow(dead_code)]
-    pub fn to_ldap_entry(&self, dn: &str) -> HashMap<String, Vec<String>> {
-        let mut entry = HashMap::new();
+    pub fn get_primary_group_token(&self) -> SecurityIdentifier {
+        // primaryGroupToken = domain SID + group RID
+        // Например: S-1-5-21-...-513
+        let mut sid = self.sid.clone();
+        // Удаляем последний RID (если это встроенный SID)
+        if let Some(_rid) = sid.sub_authorities.pop() {
+            // Оставляем домен SID
+        }
+        sid.sub_authorities.push(self.get_rid());
+        sid
+    }
 
-        entry.insert("objectClass".to_string(), vec![
-            "top".to_string(),
-            "group".to_string(),
-        ]);
-        entry.insert("distinguishedName".to_string(), vec![dn.to_string()]);
+    /// Получить RID группы (например, 513 для Domain Users): последний sub-authority
+    /// SID домена или BUILTIN; у групп без такого SID — производный от id
+    pub fn get_rid(&self) -> u32 {
+        match self.sid.sub_authorities.as_slice() {
+            [21, _, _, _, rid] | [32, rid] => *rid,
+            _ => match self.type_flags {
+                f if f.contains(GroupTypeFlags::BUILTIN) => 512 + self.id.as_bytes()[0] as u32 % 100,
+                _ => 1000 + self.id.as_u128() as u32 % 1_000_000,
+            },
+        }
+    }
+}
+
+impl LdapEntry for Group {
+    fn dn(&self, ctx: &LdapContext<'_>) -> String {
+        ctx.child_dn(&self.name, None)
+    }
+
+    fn object_classes(&self) -> &'static [&'static str] {
+        &["top", "group"]
+    }
+
+    fn object_guid(&self) -> Uuid {
+        self.id
+    }
+
+    async fn attributes(&self, _ctx: &LdapContext<'_>) -> Result<LdapAttributes, DirectoryError> {
+        let mut entry = LdapAttributes::new();
         entry.insert("cn".to_string(), vec![self.name.clone()]);
         entry.insert("sAMAccountName".to_string(), vec![self.sam_account_name.clone()]);
         entry.insert("name".to_string(), vec![self.name.clone()]);
-        entry.insert("objectSid".to_string(), vec![self.sid.to_string()]);
+        entry.insert_binary("objectSid", vec![self.sid.to_bytes()]);
 
         if let Some(desc) = &self.description {
             entry.insert("description".to_string(), vec![desc.clone()]);
         }
+        if let Some(rule) = &self.membership_rule {
+            // memberURL, как у groupOfURLs в OpenLDAP
+            entry.insert("memberURL".to_string(), vec![format!("ldap:///??sub?{}", rule)]);
+        }
+        if self.is_protected() {
+            entry.insert("isCriticalSystemObject".to_string(), vec!["TRUE".to_string()]);
+        }
 
-        // groupType: зависит от флагов
-        let mut group_type = 0u32;
+        // groupType (как в AD): бит области действия + 0x80000000 для групп безопасности
+        let mut group_type: u32 = match self.scope {
+            GroupScope::Global => 0x0000_0002,
+            GroupScope::DomainLocal => 0x0000_0004,
+            GroupScope::Universal => 0x0000_0008,
+        };
         if self.type_flags.contains(GroupTypeFlags::SECURITY) {
-            group_type |= 0x0000_0008; // SECURITY_ENABLED
-        }
-        if self.type_flags.contains(GroupTypeFlags::BUILTIN) {
-            // Не влияет на groupType напрямую
+            group_type |= 0x8000_0000;
         }
-        entry.insert("groupType".to_string(), vec![group_type.to_string()]);
+        entry.insert("groupType".to_string(), vec![(group_type as i32).to_string()]);
 
         entry.insert("whenCreated".to_string(), vec![
             format_ldap_time(&self.created_at)
         ]);
 
-        entry
-    }
-
-    pub fn get_primary_group_token(&self) -> SecurityIdentifier {
-        // primaryGroupToken = domain SID + group RID
-        // Например: S-1-5-21-...-513
-        let mut sid = self.sid.clone();
-        // Удаляем последний RID (если это встроенный SID)
-        if let Some(_rid) = sid.sub_authorities.pop() {
-            // Оставляем домен SID
+        // meta — кастомные атрибуты; многозначные — по строке на значение
+        for (k, v) in &self.meta {
+            entry.insert(k.clone(), crate::models::schema::values(v));
         }
-        sid.sub_authorities.push(self.get_rid());
-        sid
-    }
 
-    /// Получить RID группы (например, 513 для Domain Users)
-    pub fn get_rid(&self) -> u32 {
-        // В реальности — зависит от типа
-        match self.type_flags {
-            f if f.contains(GroupTypeFlags::BUILTIN) => 512 + self.id.as_bytes()[0] as u32 % 100,
-            _ => 1000 + self.id.as_u128() as u32 % 1_000_000,
-        }
+        Ok(entry)
     }
 }
 
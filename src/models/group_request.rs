@@ -0,0 +1,51 @@
+// src/models/group_request.rs
+
+//! Заявка на вступление в группу: пользователь просит, владелец группы (`managed_by`)
+//! или администратор одобряет либо отклоняет.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinRequestStatus {
+    Pending,
+    Approved,
+    Denied,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GroupJoinRequest {
+    pub id: Uuid,
+    pub group_id: Uuid,
+    /// Кто просит членства
+    pub user_id: Uuid,
+    pub justification: Option<String>,
+    pub status: JoinRequestStatus,
+    pub requested_at: DateTime<Utc>,
+    /// Кто принял решение; `None` — локальный CLI
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub comment: Option<String>,
+}
+
+impl GroupJoinRequest {
+    pub fn new(group_id: Uuid, user_id: Uuid, justification: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            group_id,
+            user_id,
+            justification,
+            status: JoinRequestStatus::Pending,
+            requested_at: Utc::now(),
+            decided_by: None,
+            decided_at: None,
+            comment: None,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.status == JoinRequestStatus::Pending
+    }
+}
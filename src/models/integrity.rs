@@ -0,0 +1,20 @@
+// src/models/integrity.rs
+
+//! Висячие ссылки: объект ссылается на UUID, которого в каталоге нет. Находит их задача
+//! `reference_check` и команда `nextDomen check-references`
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct DanglingReference {
+    /// Тип ссылающегося объекта: `group`, `gpo`, `ou`, `user`, `contact`
+    pub object_type: String,
+    pub object_id: Uuid,
+    /// Имя ссылающегося объекта: sAMAccountName, имя пользователя, DN OU, имя GPO или контакта
+    pub object: String,
+    /// Поле со ссылкой: `members`, `managed_by`, `linked_to`, `linked_gpos`, `parent`, `organizational_unit`
+    pub attribute: String,
+    /// UUID, которого нет в каталоге
+    pub target_id: Uuid,
+}
@@ -0,0 +1,44 @@
+// src/models/invitation.rs
+
+//! Приглашение нового пользователя: учётная запись создаётся выключенной, приглашённый
+//! по ссылке из письма задаёт пароль и MFA, после чего она включается
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Duration, Utc};
+
+use super::password_reset::new_token;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Invitation {
+    pub user_id: Uuid,
+    /// SHA-256 токена из ссылки; сам токен не хранится
+    pub token_hash: String,
+    /// Кто пригласил
+    pub invited_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    /// Срок ссылки; повторная отправка выдаёт новую ссылку с новым сроком
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Invitation {
+    /// Новое приглашение и токен для ссылки
+    pub fn generate(user_id: Uuid, invited_by: Option<Uuid>, ttl: Duration) -> (Self, String) {
+        let (token, token_hash) = new_token();
+        let now = Utc::now();
+        let invitation = Self { user_id, token_hash, invited_by, created_at: now, expires_at: now + ttl };
+        (invitation, token)
+    }
+
+    /// Заменить ссылку: прежний токен перестаёт действовать
+    pub fn renew(&mut self, ttl: Duration) -> String {
+        let (token, token_hash) = new_token();
+        self.token_hash = token_hash;
+        self.expires_at = Utc::now() + ttl;
+        token
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
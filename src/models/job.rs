@@ -0,0 +1,47 @@
+// src/models/job.rs
+
+//! История выполнения фоновых задач (`crate::jobs`)
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// Почему задача запущена
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobTrigger {
+    /// По расписанию процесса `web`
+    Schedule,
+    /// Вручную: `nextDomen jobs run`
+    Manual,
+}
+
+impl JobTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobTrigger::Schedule => "schedule",
+            JobTrigger::Manual => "manual",
+        }
+    }
+}
+
+/// Одно выполнение задачи
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JobRun {
+    pub job: String,
+    pub trigger: JobTrigger,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Сколько объектов обработано; `None` — задача завершилась ошибкой
+    pub processed: Option<usize>,
+    pub error: Option<String>,
+}
+
+impl JobRun {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn duration_ms(&self) -> i64 {
+        (self.finished_at - self.started_at).num_milliseconds()
+    }
+}
@@ -0,0 +1,30 @@
+// src/models/kerberos.rs
+
+use serde::{Deserialize, Serialize};
+
+/// Долгосрочный ключ Kerberos принципала
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KerberosKey {
+    /// Тип шифрования (18 = aes256-cts-hmac-sha1-96)
+    pub etype: i32,
+    /// Номер версии ключа, растёт при каждой смене пароля
+    pub kvno: u32,
+    /// Соль, использованная при выводе ключа (REALM + имя)
+    pub salt: String,
+    pub key: Vec<u8>,
+}
+
+impl KerberosKey {
+    /// Вывести ключи принципала из пароля
+    pub fn derive_all(password: &str, realm: &str, principal: &str, kvno: u32) -> Vec<Self> {
+        use crate::kerberos::crypto;
+
+        let salt = format!("{}{}", realm.to_uppercase(), principal);
+        vec![Self {
+            etype: crypto::ETYPE_AES256_CTS_HMAC_SHA1_96,
+            kvno,
+            key: crypto::string_to_key(password, &salt),
+            salt,
+        }]
+    }
+}
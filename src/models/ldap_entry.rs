@@ -0,0 +1,218 @@
+// src/models/ldap_entry.rs
+
+//! Объект каталога в виде записи LDAP: DN, objectClass и атрибуты. Записи строятся одинаково
+//! для поиска и DirSync сервера LDAP, экспорта LDIF и правил членства динамических групп
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::dn::{self, Rdn};
+use crate::models::Domain;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Неизменяемый идентификатор объекта, по которому его узнают инструменты синхронизации
+pub const OBJECT_GUID: &str = "objectGUID";
+
+/// objectGUID — UUID объекта в порядке байтов GUID Windows: первые три поля little-endian
+pub fn object_guid_bytes(id: Uuid) -> Vec<u8> {
+    id.to_bytes_le().to_vec()
+}
+
+/// objectGUID из значения фильтра: строка GUID (в том числе в `{}`) или 16 байт в
+/// экранированной форме `\xx\xx…`, как его передают клиенты AD
+pub fn parse_object_guid(value: &str) -> Option<Uuid> {
+    let text = value.trim().trim_start_matches('{').trim_end_matches('}');
+    if let Ok(id) = Uuid::parse_str(text) {
+        return Some(id);
+    }
+    let bytes = value.strip_prefix('\\')?
+        .split('\\')
+        .map(|hex| if hex.len() == 2 { u8::from_str_radix(hex, 16).ok() } else { None })
+        .collect::<Option<Vec<u8>>>()?;
+    Uuid::from_slice_le(&bytes).ok()
+}
+
+/// Значение атрибута — байты, как в протоколе LDAP: строки в UTF-8, двоичные значения
+/// (objectSid, фотографии) — как есть
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LdapValue(Vec<u8>);
+
+impl LdapValue {
+    pub fn text(value: impl Into<String>) -> Self {
+        Self(value.into().into_bytes())
+    }
+
+    pub fn binary(data: impl Into<Vec<u8>>) -> Self {
+        Self(data.into())
+    }
+
+    /// Значение как строка, если это UTF-8
+    pub fn as_text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<String> for LdapValue {
+    fn from(text: String) -> Self {
+        Self::text(text)
+    }
+}
+
+impl From<&str> for LdapValue {
+    fn from(text: &str) -> Self {
+        Self::text(text)
+    }
+}
+
+impl From<Vec<u8>> for LdapValue {
+    fn from(data: Vec<u8>) -> Self {
+        Self::binary(data)
+    }
+}
+
+/// Атрибуты записи LDAP; имена при поиске сравниваются без учёта регистра, как в AD
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LdapAttributes(HashMap<String, Vec<LdapValue>>);
+
+impl LdapAttributes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Задать строковые значения атрибута, заменив прежние
+    pub fn insert(&mut self, name: impl Into<String>, values: Vec<String>) {
+        self.0.insert(name.into(), values.into_iter().map(LdapValue::text).collect());
+    }
+
+    /// Задать двоичные значения атрибута, заменив прежние
+    pub fn insert_binary(&mut self, name: impl Into<String>, values: Vec<Vec<u8>>) {
+        self.0.insert(name.into(), values.into_iter().map(LdapValue::binary).collect());
+    }
+
+    /// objectClass, distinguishedName и objectGUID записи
+    pub fn insert_identity(&mut self, dn: &str, object_classes: &[&str], guid: Uuid) {
+        self.insert("objectClass", object_classes.iter().map(|class| class.to_string()).collect());
+        self.insert("distinguishedName", vec![dn.to_string()]);
+        self.insert_binary(OBJECT_GUID, vec![object_guid_bytes(guid)]);
+    }
+
+    /// objectGUID записи
+    pub fn object_guid(&self) -> Option<Uuid> {
+        let value = self.get(OBJECT_GUID)?.first()?;
+        Uuid::from_slice_le(value.as_bytes()).ok()
+    }
+
+    /// Значения атрибута по имени без учёта регистра
+    pub fn get(&self, name: &str) -> Option<&[LdapValue]> {
+        self.0.iter()
+            .find(|(attr, _)| attr.eq_ignore_ascii_case(name))
+            .map(|(_, values)| values.as_slice())
+    }
+
+    /// Значения атрибута, которые являются строками UTF-8
+    pub fn text(&self, name: &str) -> Vec<&str> {
+        self.get(name).unwrap_or_default().iter().filter_map(LdapValue::as_text).collect()
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Vec<LdapValue>> {
+        let key = self.0.keys().find(|attr| attr.eq_ignore_ascii_case(name))?.clone();
+        self.0.remove(&key)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.0.keys()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<LdapValue>)> {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for LdapAttributes {
+    type Item = (String, Vec<LdapValue>);
+    type IntoIter = std::collections::hash_map::IntoIter<String, Vec<LdapValue>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl FromIterator<(String, Vec<String>)> for LdapAttributes {
+    fn from_iter<I: IntoIterator<Item = (String, Vec<String>)>>(iter: I) -> Self {
+        let mut attributes = Self::new();
+        for (name, values) in iter {
+            attributes.insert(name, values);
+        }
+        attributes
+    }
+}
+
+/// Общее для записей одного запроса: каталог и DN домена и OU, прочитанные один раз
+pub struct LdapContext<'a> {
+    pub service: &'a DirectoryService,
+    /// DN домена, например `DC=corp,DC=acme,DC=com`
+    pub domain_dn: String,
+    ou_dns: HashMap<Uuid, String>,
+}
+
+impl<'a> LdapContext<'a> {
+    pub async fn new(service: &'a DirectoryService) -> Result<Self, DirectoryError> {
+        let domain_dn = service.get_all_domains().await?
+            .first()
+            .map(Domain::dn)
+            .unwrap_or_else(|| "DC=corp,DC=acme,DC=com".to_string());
+        let ou_dns = service.get_all_ous().await?
+            .into_iter()
+            .map(|ou| (ou.id, ou.dn))
+            .collect();
+        Ok(Self { service, domain_dn, ou_dns })
+    }
+
+    /// DN OU или, без неё, `CN=Users` домена
+    pub fn container_dn(&self, ou: Option<Uuid>) -> String {
+        match ou.and_then(|id| self.ou_dns.get(&id)) {
+            Some(dn) => dn.clone(),
+            None => dn::join(&Rdn::cn("Users"), &self.domain_dn),
+        }
+    }
+
+    /// DN `CN=<name>` в контейнере
+    pub fn child_dn(&self, name: &str, ou: Option<Uuid>) -> String {
+        dn::join(&Rdn::cn(name), &self.container_dn(ou))
+    }
+}
+
+/// Объект каталога, который отдаётся клиентам LDAP и выгружается в LDIF
+#[allow(async_fn_in_trait)]
+pub trait LdapEntry {
+    /// DN объекта
+    fn dn(&self, ctx: &LdapContext<'_>) -> String;
+
+    /// Значения objectClass от `top` к самому частному классу
+    fn object_classes(&self) -> &'static [&'static str];
+
+    /// objectGUID: UUID объекта в каталоге
+    fn object_guid(&self) -> Uuid;
+
+    /// Атрибуты, кроме objectClass, distinguishedName и objectGUID
+    async fn attributes(&self, ctx: &LdapContext<'_>) -> Result<LdapAttributes, DirectoryError>;
+
+    /// DN и полная запись с objectClass, distinguishedName и objectGUID
+    async fn ldap_entry(&self, ctx: &LdapContext<'_>) -> Result<(String, LdapAttributes), DirectoryError> {
+        let dn = self.dn(ctx);
+        let mut attributes = self.attributes(ctx).await?;
+        attributes.insert_identity(&dn, self.object_classes(), self.object_guid());
+        Ok((dn, attributes))
+    }
+}
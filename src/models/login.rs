@@ -0,0 +1,47 @@
+// src/models/login.rs
+
+//! История входов пользователя: последние попытки входа по паролю с адресом и протоколом
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// Через что пользователь входил
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LoginProtocol {
+    Rest,
+    Grpc,
+    Ldap,
+    Radius,
+    Oidc,
+    /// Прямой вызов сервиса, например из CLI
+    Other,
+}
+
+impl LoginProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoginProtocol::Rest => "rest",
+            LoginProtocol::Grpc => "grpc",
+            LoginProtocol::Ldap => "ldap",
+            LoginProtocol::Radius => "radius",
+            LoginProtocol::Oidc => "oidc",
+            LoginProtocol::Other => "other",
+        }
+    }
+}
+
+/// Попытка входа существующего пользователя
+#[derive(Serialize, Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct LoginRecord {
+    pub timestamp: DateTime<Utc>,
+    pub protocol: LoginProtocol,
+    /// Адрес клиента, для RADIUS — адрес NAS
+    pub ip_addr: Option<String>,
+    /// Устройство клиента: User-Agent REST и gRPC
+    #[serde(default)]
+    pub device: Option<String>,
+    pub success: bool,
+    /// Причина отказа
+    pub reason: Option<String>,
+}
@@ -5,19 +5,82 @@ pub mod organization;
 pub mod domain;
 pub mod user;
 pub mod group;
+pub mod group_request;
 pub mod ou;
+pub mod contact;
 pub mod policy;
+pub mod policy_schema;
+pub mod agent;
 pub mod password;
+pub mod password_policy;
 pub mod mfa; // ✅ Добавлен
+pub mod kerberos;
+pub mod dns;
+pub mod oauth;
+pub mod sync;
+pub mod apikey;
+pub mod service_account;
+pub mod schema;
+pub mod filter;
+pub mod security;
+pub mod well_known;
+pub mod domain_controller;
+pub mod site;
+pub mod change;
+pub mod job;
+pub mod login;
+pub mod photo;
+pub mod password_reset;
+pub mod invitation;
+pub mod stats;
+pub mod integrity;
+pub mod ldap_entry;
+pub mod constructed;
+pub mod object;
+pub mod modify;
+pub mod approval;
+pub mod session;
+pub mod risk;
+pub mod certificate;
 
 // Re-exports
 
-pub use sid::SecurityIdentifier;
-pub use organization::Organization;
-pub use domain::{Domain};
-pub use user::User;
+pub use sid::{SecurityIdentifier, SidError};
+pub use organization::{Organization, OrganizationAddress};
+pub use domain::{Domain, DomainControllerInfo};
+pub use user::{normalize_username, validate_username, User, UserAccountControl, MAX_USERNAME_LENGTH};
 pub use group::{Group, GroupScope, GroupTypeFlags};
-pub use ou::OrganizationalUnit;
+pub use group_request::{GroupJoinRequest, JoinRequestStatus};
+pub use ou::{OrganizationalUnit, OuContents};
+pub use contact::{Contact, ContactKind};
 pub use policy::{GroupPolicy, SidOrId};
 pub use password::{PasswordHash, PasswordAlgorithm};
-pub use mfa::MfaMethod; // ✅ Экспорт из mfa.rs
\ No newline at end of file
+pub use password_policy::{PasswordPolicy, PasswordSettingsObject};
+pub use mfa::MfaMethod; // ✅ Экспорт из mfa.rs
+pub use kerberos::KerberosKey;
+pub use dns::{DnsRecord, DnsRecordData};
+pub use oauth::OAuthClient;
+pub use sync::{LdapSyncObjects, LdapSyncState};
+pub use apikey::ApiKey;
+pub use service_account::{ManagedPassword, ServiceAccount, ServiceAccountFlags};
+pub use filter::LdapFilter;
+pub use schema::{AttributeDefinition, AttributeSyntax, SchemaClass};
+pub use security::{AccessMask, Ace, AceFlags, AceType, SecuredObject, SecurityDescriptor};
+pub use domain_controller::DomainController;
+pub use site::{Site, Subnet};
+pub use change::{ChangeEntry, ChangeType, DirSyncCookie, DirSyncPage};
+pub use job::{JobRun, JobTrigger};
+pub use login::{LoginProtocol, LoginRecord};
+pub use photo::UserPhoto;
+pub use password_reset::PasswordResetToken;
+pub use invitation::Invitation;
+pub use stats::{DirectoryStats, OuUserCount};
+pub use integrity::DanglingReference;
+pub use ldap_entry::{LdapAttributes, LdapContext, LdapEntry, LdapValue};
+pub use object::DirectoryObject;
+pub use modify::{AttributeChange, ModifiableObject};
+pub use approval::{ApprovalOperation, ApprovalRequest, ApprovalStatus, SensitiveOperation};
+pub use session::Session;
+pub use risk::{LoginRisk, RiskAction, RiskSignal};
+pub use certificate::{CertificateAuthority, CertificateKind, IssuedCertificate, RevocationReason};
+pub use agent::{AgentCheckin, ComplianceState, ExpectedPolicy, PolicyApplyStatus, PolicyReport, ResolvedSetting};
\ No newline at end of file
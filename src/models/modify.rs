@@ -0,0 +1,241 @@
+// src/models/modify.rs
+
+//! Изменение отдельных атрибутов объекта, как в операции LDAP Modify (RFC 4511, 4.6):
+//! добавить или удалить значения, заменить атрибут целиком — без перезаписи всего объекта
+
+use crate::models::{Contact, Group, OrganizationalUnit, User};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "operation", rename_all = "lowercase")]
+pub enum AttributeChange {
+    /// Добавить значения; уже заданное значение — ошибка
+    Add { attribute: String, values: Vec<String> },
+    /// Удалить значения, без значений — атрибут целиком; отсутствующее значение — ошибка
+    Delete {
+        attribute: String,
+        #[serde(default)]
+        values: Vec<String>,
+    },
+    /// Заменить все значения; пустой список удаляет атрибут
+    Replace {
+        attribute: String,
+        #[serde(default)]
+        values: Vec<String>,
+    },
+}
+
+impl AttributeChange {
+    pub fn attribute(&self) -> &str {
+        match self {
+            Self::Add { attribute, .. } | Self::Delete { attribute, .. } | Self::Replace { attribute, .. } => attribute,
+        }
+    }
+
+    /// Название операции для аудита: `add`, `delete`, `replace`
+    pub fn operation(&self) -> &'static str {
+        match self {
+            Self::Add { .. } => "add",
+            Self::Delete { .. } => "delete",
+            Self::Replace { .. } => "replace",
+        }
+    }
+
+    /// Значения атрибута после изменения. Значения сравниваются без учёта регистра,
+    /// как строки каталога в AD
+    pub fn apply(&self, mut current: Vec<String>) -> Result<Vec<String>, String> {
+        let position = |values: &[String], value: &str| values.iter().position(|other| other.eq_ignore_ascii_case(value));
+        match self {
+            Self::Add { attribute, values } => {
+                if values.is_empty() {
+                    return Err(format!("No values to add to {}", attribute));
+                }
+                for value in values {
+                    if position(&current, value).is_some() {
+                        return Err(format!("Attribute {} already has value '{}'", attribute, value));
+                    }
+                    current.push(value.clone());
+                }
+                Ok(current)
+            }
+            Self::Delete { attribute, values } if values.is_empty() => {
+                if current.is_empty() {
+                    return Err(format!("Attribute {} has no values", attribute));
+                }
+                Ok(Vec::new())
+            }
+            Self::Delete { attribute, values } => {
+                for value in values {
+                    let index = position(&current, value)
+                        .ok_or_else(|| format!("Attribute {} has no value '{}'", attribute, value))?;
+                    current.remove(index);
+                }
+                Ok(current)
+            }
+            Self::Replace { values, .. } => Ok(values.clone()),
+        }
+    }
+}
+
+/// Объект, атрибуты которого меняются по отдельности (`DirectoryService::modify_object`).
+/// Встроенные атрибуты хранятся в полях модели, остальные — атрибуты схемы в `meta`
+pub trait ModifiableObject {
+    /// Имя встроенного атрибута как в LDAP и его текущие значения; `None` — такого встроенного
+    /// атрибута нет или он меняется своей операцией (пароль, членство, перемещение)
+    fn modifiable_attribute(&self, name: &str) -> Option<(&'static str, Vec<String>)>;
+
+    /// Задать значения встроенного атрибута; ошибка — значения ему не подходят
+    fn set_attribute(&mut self, name: &str, values: Vec<String>) -> Result<(), String>;
+
+    /// Атрибуты схемы
+    fn meta_mut(&mut self) -> &mut HashMap<String, String>;
+}
+
+fn optional(value: &Option<String>) -> Vec<String> {
+    value.iter().cloned().collect()
+}
+
+/// Значение однозначного атрибута; пустой список — атрибут удалён
+fn single(name: &str, values: Vec<String>) -> Result<Option<String>, String> {
+    let mut values = values.into_iter();
+    let value = values.next();
+    if values.next().is_some() {
+        return Err(format!("Attribute {} is single-valued", name));
+    }
+    Ok(value)
+}
+
+/// Значение обязательного однозначного атрибута
+fn required(name: &str, values: Vec<String>) -> Result<String, String> {
+    single(name, values)?
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| format!("Attribute {} is required", name))
+}
+
+fn email(name: &str, value: Option<String>) -> Result<Option<String>, String> {
+    match value {
+        Some(value) if !value.contains('@') => Err(format!("Invalid email '{}' in {}", value, name)),
+        value => Ok(value),
+    }
+}
+
+fn unsupported(name: &str) -> String {
+    format!("Attribute {} cannot be modified", name)
+}
+
+impl ModifiableObject for User {
+    fn modifiable_attribute(&self, name: &str) -> Option<(&'static str, Vec<String>)> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "samaccountname" => ("sAMAccountName", vec![self.username.clone()]),
+            "userprincipalname" => ("userPrincipalName", vec![self.user_principal_name.clone()]),
+            "mail" => ("mail", optional(&self.email)),
+            "displayname" => ("displayName", optional(&self.display_name)),
+            "givenname" => ("givenName", optional(&self.given_name)),
+            "sn" => ("sn", optional(&self.surname)),
+            "profilepath" => ("profilePath", optional(&self.profile_path)),
+            "scriptpath" => ("scriptPath", optional(&self.script_path)),
+            _ => return None,
+        })
+    }
+
+    fn set_attribute(&mut self, name: &str, values: Vec<String>) -> Result<(), String> {
+        match name {
+            "sAMAccountName" => {
+                let username = required(name, values)?;
+                crate::models::validate_username(&username)?;
+                self.username = username;
+            }
+            "userPrincipalName" => self.user_principal_name = required(name, values)?,
+            "mail" => self.email = email(name, single(name, values)?)?,
+            "displayName" => self.display_name = single(name, values)?,
+            "givenName" => self.given_name = single(name, values)?,
+            "sn" => self.surname = single(name, values)?,
+            "profilePath" => self.profile_path = single(name, values)?,
+            "scriptPath" => self.script_path = single(name, values)?,
+            _ => return Err(unsupported(name)),
+        }
+        Ok(())
+    }
+
+    fn meta_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.meta
+    }
+}
+
+impl ModifiableObject for Group {
+    fn modifiable_attribute(&self, name: &str) -> Option<(&'static str, Vec<String>)> {
+        match name.to_ascii_lowercase().as_str() {
+            "description" => Some(("description", optional(&self.description))),
+            _ => None,
+        }
+    }
+
+    fn set_attribute(&mut self, name: &str, values: Vec<String>) -> Result<(), String> {
+        match name {
+            "description" => self.description = single(name, values)?,
+            _ => return Err(unsupported(name)),
+        }
+        Ok(())
+    }
+
+    fn meta_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.meta
+    }
+}
+
+impl ModifiableObject for OrganizationalUnit {
+    fn modifiable_attribute(&self, name: &str) -> Option<(&'static str, Vec<String>)> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "displayname" => ("displayName", optional(&self.display_name)),
+            "description" => ("description", optional(&self.description)),
+            _ => return None,
+        })
+    }
+
+    fn set_attribute(&mut self, name: &str, values: Vec<String>) -> Result<(), String> {
+        match name {
+            "displayName" => self.display_name = single(name, values)?,
+            "description" => self.description = single(name, values)?,
+            _ => return Err(unsupported(name)),
+        }
+        Ok(())
+    }
+
+    fn meta_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.meta
+    }
+}
+
+impl ModifiableObject for Contact {
+    fn modifiable_attribute(&self, name: &str) -> Option<(&'static str, Vec<String>)> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "mail" => ("mail", vec![self.email.clone()]),
+            "displayname" => ("displayName", optional(&self.display_name)),
+            "givenname" => ("givenName", optional(&self.given_name)),
+            "sn" => ("sn", optional(&self.surname)),
+            "telephonenumber" => ("telephoneNumber", optional(&self.telephone_number)),
+            "company" => ("company", optional(&self.company)),
+            "description" => ("description", optional(&self.description)),
+            _ => return None,
+        })
+    }
+
+    fn set_attribute(&mut self, name: &str, values: Vec<String>) -> Result<(), String> {
+        match name {
+            "mail" => self.email = required(name, values)?,
+            "displayName" => self.display_name = single(name, values)?,
+            "givenName" => self.given_name = single(name, values)?,
+            "sn" => self.surname = single(name, values)?,
+            "telephoneNumber" => self.telephone_number = single(name, values)?,
+            "company" => self.company = single(name, values)?,
+            "description" => self.description = single(name, values)?,
+            _ => return Err(unsupported(name)),
+        }
+        Ok(())
+    }
+
+    fn meta_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.meta
+    }
+}
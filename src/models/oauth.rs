@@ -0,0 +1,37 @@
+// src/models/oauth.rs
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use crate::models::password::PasswordHash;
+
+/// Приложение, зарегистрированное для входа через OAuth2 / OpenID Connect
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OAuthClient {
+    pub id: Uuid,
+    /// Публичный идентификатор (`client_id`)
+    pub client_id: String,
+    pub name: String,
+    /// Разрешённые адреса возврата; сравниваются целиком
+    pub redirect_uris: Vec<String>,
+    /// Хеш секрета; у публичных клиентов (SPA, мобильные приложения) секрета нет
+    pub client_secret: Option<PasswordHash>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl OAuthClient {
+    pub fn new(client_id: impl Into<String>, name: impl Into<String>, redirect_uris: Vec<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            client_id: client_id.into(),
+            name: name.into(),
+            redirect_uris,
+            client_secret: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn is_confidential(&self) -> bool {
+        self.client_secret.is_some()
+    }
+}
@@ -0,0 +1,18 @@
+// src/models/object.rs
+
+//! Объект каталога любого типа, найденный по objectGUID — неизменяемому идентификатору,
+//! которым инструменты синхронизации связывают свои записи с каталогом
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryObject {
+    /// objectGUID — UUID объекта
+    pub object_guid: Uuid,
+    /// Тип объекта: `user`, `group`, `ou`, `contact`, `domain`, `gpo`
+    pub object_type: String,
+    pub dn: String,
+    /// Имя пользователя, sAMAccountName группы, имя OU, контакта, GPO или DNS-имя домена
+    pub name: String,
+}
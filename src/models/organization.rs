@@ -1,5 +1,8 @@
 // src/models/organization.rs
 
+//! Организация (арендатор): реквизиты, домены каталога и свои администраторы.
+//! Домен принадлежит не более чем одной организации
+
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::models::policy::PolicyId;
@@ -8,12 +11,78 @@ use chrono::Utc;
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Organization {
     pub id: Uuid,
+    /// Короткое имя, уникально без учёта регистра
     pub name: String,
-    pub display_name: String,
+    #[serde(default)]
+    pub legal_name: String,
+    /// ИНН / VAT ID
+    #[serde(default)]
+    pub tax_id: String,
+    #[serde(default)]
+    pub phone: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub website: String,
+    #[serde(default)]
+    pub address: OrganizationAddress,
     pub domains: Vec<Uuid>,
-    pub default_domain_id: Uuid,
+    /// Домен новых учётных записей организации; первый присоединённый, если не выбран другой
+    pub default_domain_id: Option<Uuid>,
+    /// Администраторы организации: читают и меняют её реквизиты наравне с Domain Admins
+    #[serde(default)]
+    pub admins: Vec<Uuid>,
     pub policies: Vec<PolicyId>,
     pub created_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
     pub meta: std::collections::HashMap<String, String>,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrganizationAddress {
+    pub street: String,
+    pub city: String,
+    pub state: String,
+    pub postal_code: String,
+    /// ISO 3166-1 alpha-2
+    pub country: String,
+}
+
+impl Organization {
+    pub fn new(name: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            legal_name: String::new(),
+            tax_id: String::new(),
+            phone: String::new(),
+            email: String::new(),
+            website: String::new(),
+            address: OrganizationAddress::default(),
+            domains: Vec::new(),
+            default_domain_id: None,
+            admins: Vec::new(),
+            policies: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            meta: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Имя непустое, адрес почты с `@`, страна — две латинские буквы
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Organization name cannot be empty".to_string());
+        }
+        if !self.email.is_empty() && !self.email.contains('@') {
+            return Err(format!("Invalid organization email: {}", self.email));
+        }
+        let country = &self.address.country;
+        let alpha2 = country.len() == 2 && country.chars().all(|c| c.is_ascii_uppercase());
+        if !country.is_empty() && !alpha2 {
+            return Err(format!("Invalid country code '{}': expected ISO 3166-1 alpha-2", country));
+        }
+        Ok(())
+    }
+}
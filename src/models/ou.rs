@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::models::policy::PolicyId;
 use chrono::Utc;
+use crate::directory_service::DirectoryError;
+use crate::models::ldap_entry::{LdapAttributes, LdapContext, LdapEntry};
 use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -89,17 +91,36 @@ impl OrganizationalUnit {
         ou.update_gpoptions();
         ou
     }
+}
 
-    /// Преобразовать OU в LDAP-запись
-        #[allow(dead_code)]
-    pub fn to_ldap_entry(&self) -> HashMap<String, Vec<String>> {
-        let mut entry = HashMap::new();
+/// Что делать с пользователями и контактами удаляемой OU и её вложенных OU
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, clap::ValueEnum, utoipa::ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum OuContents {
+    /// Не удалять OU, пока в ней есть объекты
+    #[default]
+    Refuse,
+    /// Перенести объекты в родительскую OU удаляемой (или в `CN=Users` домена)
+    MoveToParent,
+    /// Удалить объекты вместе с OU
+    Delete,
+}
 
-        entry.insert("objectClass".to_string(), vec![
-            "top".to_string(),
-            "organizationalUnit".to_string(),
-        ]);
-        entry.insert("distinguishedName".to_string(), vec![self.dn.clone()]);
+impl LdapEntry for OrganizationalUnit {
+    fn dn(&self, _ctx: &LdapContext<'_>) -> String {
+        self.dn.clone()
+    }
+
+    fn object_classes(&self) -> &'static [&'static str] {
+        &["top", "organizationalUnit"]
+    }
+
+    fn object_guid(&self) -> Uuid {
+        self.id
+    }
+
+    async fn attributes(&self, _ctx: &LdapContext<'_>) -> Result<LdapAttributes, DirectoryError> {
+        let mut entry = LdapAttributes::new();
         entry.insert("ou".to_string(), vec![self.name.clone()]);
         entry.insert("name".to_string(), vec![self.name.clone()]);
 
@@ -122,12 +143,12 @@ impl OrganizationalUnit {
             format_ldap_time(&self.updated_at)
         ]);
 
-        // meta — кастомные атрибуты
+        // meta — кастомные атрибуты; многозначные — по строке на значение
         for (k, v) in &self.meta {
-            entry.insert(k.clone(), vec![v.clone()]);
+            entry.insert(k.clone(), crate::models::schema::values(v));
         }
 
-        entry
+        Ok(entry)
     }
 }
 
@@ -0,0 +1,132 @@
+// src/models/password_policy.rs
+
+//! Парольная политика: политика домена (`security.password_policy`) и детальные политики
+//! (PSO, как msDS-PasswordSettings в AD), привязанные к группам и OU. Для пользователя действует
+//! PSO с наименьшим `precedence` среди подходящих, без них — политика домена.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::config::duration;
+
+/// Спецсимволы для `require_special_chars` — всё, кроме букв, цифр и пробелов
+fn is_special(c: char) -> bool {
+    !c.is_alphanumeric() && !c.is_whitespace()
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct PasswordPolicy {
+    #[serde(default = "default_min_length")]
+    pub min_length: u8,
+    #[serde(default = "default_require_uppercase")]
+    pub require_uppercase: bool,
+    #[serde(default = "default_require_lowercase")]
+    pub require_lowercase: bool,
+    #[serde(default = "default_require_digits")]
+    pub require_digits: bool,
+    #[serde(default = "default_require_special_chars")]
+    pub require_special_chars: bool,
+    #[serde(default = "default_max_age_days", deserialize_with = "duration::days")]
+    pub max_age_days: u32,
+    /// Сколько последних паролей нельзя использовать снова; 0 — без истории
+    #[serde(default = "default_history_count")]
+    pub history_count: u8,
+    /// Неудачных попыток входа до блокировки; 0 — не блокировать
+    #[serde(default = "default_lockout_threshold")]
+    pub lockout_threshold: u32,
+    #[serde(default = "default_lockout_duration_minutes", deserialize_with = "duration::minutes")]
+    pub lockout_duration_minutes: u32,
+}
+
+fn default_min_length() -> u8 { 8 }
+fn default_require_uppercase() -> bool { true }
+fn default_require_lowercase() -> bool { true }
+fn default_require_digits() -> bool { true }
+fn default_require_special_chars() -> bool { false }
+fn default_max_age_days() -> u32 { 90 }
+fn default_history_count() -> u8 { 5 }
+fn default_lockout_threshold() -> u32 { 5 }
+fn default_lockout_duration_minutes() -> u32 { 15 }
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: default_min_length(),
+            require_uppercase: default_require_uppercase(),
+            require_lowercase: default_require_lowercase(),
+            require_digits: default_require_digits(),
+            require_special_chars: default_require_special_chars(),
+            max_age_days: default_max_age_days(),
+            history_count: default_history_count(),
+            lockout_threshold: default_lockout_threshold(),
+            lockout_duration_minutes: default_lockout_duration_minutes(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Проверить длину и состав пароля
+    pub fn check(&self, password: &str) -> Result<(), String> {
+        let mut missing = Vec::new();
+        if password.chars().count() < usize::from(self.min_length) {
+            missing.push(format!("at least {} characters", self.min_length));
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            missing.push("an uppercase letter".to_string());
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            missing.push("a lowercase letter".to_string());
+        }
+        if self.require_digits && !password.chars().any(|c| c.is_ascii_digit()) {
+            missing.push("a digit".to_string());
+        }
+        if self.require_special_chars && !password.chars().any(is_special) {
+            missing.push("a special character".to_string());
+        }
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Password does not meet the policy: needs {}", missing.join(", ")))
+        }
+    }
+
+    /// Блокировка после неудачных попыток входа; `None` — не блокировать
+    pub fn lockout_duration(&self) -> Option<chrono::Duration> {
+        (self.lockout_threshold > 0).then(|| chrono::Duration::minutes(self.lockout_duration_minutes.into()))
+    }
+}
+
+/// Детальная парольная политика (PSO)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PasswordSettingsObject {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    /// msDS-PasswordSettingsPrecedence: при нескольких подходящих PSO действует меньший
+    pub precedence: u32,
+    pub policy: PasswordPolicy,
+    /// Группы, участникам которых (в том числе через основную группу) назначена политика
+    pub applies_to_groups: Vec<Uuid>,
+    /// OU, пользователям которых и вложенных OU назначена политика
+    pub applies_to_ous: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PasswordSettingsObject {
+    pub fn new(name: String, precedence: u32, policy: PasswordPolicy) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            description: None,
+            precedence,
+            policy,
+            applies_to_groups: Vec::new(),
+            applies_to_ous: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
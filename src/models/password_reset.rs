@@ -0,0 +1,47 @@
+// src/models/password_reset.rs
+
+//! Одноразовый токен сброса пароля («забыли пароль»): уходит пользователю письмом,
+//! в базе хранится только его SHA-256
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Duration, Utc};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PasswordResetToken {
+    pub user_id: Uuid,
+    /// SHA-256 токена (hex): токен случайный, 256 бит, как секрет API-ключа
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PasswordResetToken {
+    /// Новый токен и его значение; значение отправляется пользователю и не хранится
+    pub fn generate(user_id: Uuid, ttl: Duration) -> (Self, String) {
+        let (token, token_hash) = new_token();
+        let now = Utc::now();
+        let reset = Self { user_id, token_hash, created_at: now, expires_at: now + ttl };
+        (reset, token)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// Случайный токен для ссылки в письме и его SHA-256; так же устроены приглашения
+pub fn new_token() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+    let hash = hash_token(&token);
+    (token, hash)
+}
+
+/// Ключ поиска токена в базе
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.trim().as_bytes()))
+}
@@ -0,0 +1,47 @@
+// src/models/photo.rs
+
+//! Фотография пользователя: в LDAP — thumbnailPhoto (Outlook, адресные книги) и jpegPhoto.
+//! Хранится в базе отдельно от пользователя, чтобы не читать её при каждой загрузке учётной записи
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Предел размера, как у thumbnailPhoto в AD
+pub const MAX_PHOTO_SIZE: usize = 100 * 1024;
+
+pub const JPEG: &str = "image/jpeg";
+pub const PNG: &str = "image/png";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UserPhoto {
+    /// `image/jpeg` или `image/png`
+    pub content_type: String,
+    pub data: Vec<u8>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UserPhoto {
+    /// Фотография с проверкой размера и соответствия данных заявленному типу
+    pub fn new(content_type: &str, data: Vec<u8>) -> Result<Self, String> {
+        let content_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        let signature: &[u8] = match content_type.as_str() {
+            JPEG => &[0xFF, 0xD8, 0xFF],
+            PNG => b"\x89PNG\r\n\x1a\n",
+            _ => return Err(format!("Unsupported photo content type '{}', expected {} or {}", content_type, JPEG, PNG)),
+        };
+        if data.is_empty() {
+            return Err("Photo is empty".to_string());
+        }
+        if data.len() > MAX_PHOTO_SIZE {
+            return Err(format!("Photo is {} bytes, the limit is {}", data.len(), MAX_PHOTO_SIZE));
+        }
+        if !data.starts_with(signature) {
+            return Err(format!("Photo data is not {}", content_type));
+        }
+        Ok(Self { content_type, data, updated_at: Utc::now() })
+    }
+
+    pub fn is_jpeg(&self) -> bool {
+        self.content_type == JPEG
+    }
+}
@@ -21,6 +21,36 @@ pub enum PolicyType {
     Custom(String),
 }
 
+impl PolicyType {
+    /// Имя типа, как в `policy_type` запросов; у `Custom` — заданное имя
+    pub fn name(&self) -> &str {
+        match self {
+            PolicyType::Security => "Security",
+            PolicyType::Registry => "Registry",
+            PolicyType::Script => "Script",
+            PolicyType::Network => "Network",
+            PolicyType::Software => "Software",
+            PolicyType::FolderRedirection => "FolderRedirection",
+            PolicyType::Custom(name) => name,
+        }
+    }
+
+    /// Встроенный тип по имени без учёта регистра, иначе `Custom`
+    pub fn from_name(name: &str) -> Self {
+        [
+            PolicyType::Security,
+            PolicyType::Registry,
+            PolicyType::Script,
+            PolicyType::Network,
+            PolicyType::Software,
+            PolicyType::FolderRedirection,
+        ]
+        .into_iter()
+        .find(|builtin| builtin.name().eq_ignore_ascii_case(name))
+        .unwrap_or_else(|| PolicyType::Custom(name.to_string()))
+    }
+}
+
 impl Default for PolicyType {
     fn default() -> Self {
         Self::Custom("Custom".to_string())
@@ -274,7 +304,10 @@ impl GroupPolicy {
         if self.linked_to.is_empty() && !self.target.is_all() {
             return Err("Policy must be linked to an object or target 'All'".to_string());
         }
-        Ok(())
+
+        // Настройки встроенных типов — по схеме, у Custom — произвольные
+        crate::models::policy_schema::validate_settings(&self.policy_type, &self.settings)
+
     }
 
     /// Обновить временную метку
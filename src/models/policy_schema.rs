@@ -0,0 +1,241 @@
+// src/models/policy_schema.rs
+
+//! Схемы настроек встроенных типов GPO: допустимые ключи `GroupPolicy::settings`, их типы
+//! и пределы. У `PolicyType::Custom` схемы нет — настройки произвольные.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::policy::{PolicyType, PolicyValue};
+
+/// Тип значения настройки
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, utoipa::ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SettingKind {
+    Integer { min: i64, max: i64 },
+    Boolean,
+    String,
+    /// Путь к файлу или UNC, например `\\corp\netlogon\logon.cmd`
+    Path,
+    /// Список путей
+    PathList,
+    /// Одно из перечисленных значений, без учёта регистра
+    Choice { values: Vec<String> },
+    /// Данные параметра реестра: тип задаёт настройка `value_type`
+    RegistryData,
+}
+
+impl std::fmt::Display for SettingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingKind::Integer { min, max } => write!(f, "integer {}..{}", min, max),
+            SettingKind::Boolean => write!(f, "boolean"),
+            SettingKind::String => write!(f, "string"),
+            SettingKind::Path => write!(f, "path"),
+            SettingKind::PathList => write!(f, "path list"),
+            SettingKind::Choice { values } => write!(f, "{}", values.join("|")),
+            SettingKind::RegistryData => write!(f, "registry data"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct SettingDefinition {
+    pub key: String,
+    pub description: String,
+    #[serde(flatten)]
+    pub kind: SettingKind,
+    pub required: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct PolicySchema {
+    /// Имя типа, как в `policy_type` запроса
+    pub policy_type: String,
+    pub description: String,
+    pub settings: Vec<SettingDefinition>,
+}
+
+/// Типы параметров реестра (`value_type`)
+const REGISTRY_VALUE_TYPES: &[&str] = &["REG_SZ", "REG_EXPAND_SZ", "REG_MULTI_SZ", "REG_DWORD", "REG_QWORD", "REG_BINARY"];
+
+fn setting(key: &str, description: &str, kind: SettingKind, required: bool) -> SettingDefinition {
+    SettingDefinition { key: key.to_string(), description: description.to_string(), kind, required }
+}
+
+fn integer(min: i64, max: i64) -> SettingKind {
+    SettingKind::Integer { min, max }
+}
+
+fn choice(values: &[&str]) -> SettingKind {
+    SettingKind::Choice { values: values.iter().map(|v| v.to_string()).collect() }
+}
+
+/// Схема встроенного типа; `None` — у `Custom`
+pub fn schema_for(policy_type: &PolicyType) -> Option<PolicySchema> {
+    let (description, settings) = match policy_type {
+        PolicyType::Security => ("Политика учётных записей: пароли и блокировка", vec![
+            setting("min_password_length", "Минимальная длина пароля", integer(0, 128), false),
+            setting("password_history_size", "Сколько прежних паролей нельзя повторять", integer(0, 24), false),
+            setting("max_password_age_days", "Срок действия пароля; 0 — бессрочный", integer(0, 999), false),
+            setting("min_password_age_days", "Через сколько дней пароль можно сменить снова", integer(0, 998), false),
+            setting("password_complexity", "Пароль должен отвечать требованиям сложности", SettingKind::Boolean, false),
+            setting("lockout_threshold", "Неудачных попыток до блокировки; 0 — не блокировать", integer(0, 999), false),
+            setting("lockout_duration_minutes", "Продолжительность блокировки", integer(0, 99_999), false),
+            setting("reset_lockout_count_minutes", "Через сколько минут сбрасывается счётчик неудачных попыток", integer(1, 99_999), false),
+        ]),
+        PolicyType::Registry => ("Параметр реестра", vec![
+            setting("hive", "Куст реестра", choice(&["HKLM", "HKCU"]), true),
+            setting("key", "Путь раздела, например `Software\\Policies\\Microsoft\\Windows`", SettingKind::String, true),
+            setting("value_name", "Имя параметра", SettingKind::String, true),
+            setting("value_type", "Тип параметра", choice(REGISTRY_VALUE_TYPES), true),
+            setting("data", "Значение: строка, список строк (REG_MULTI_SZ), число (REG_DWORD, REG_QWORD) или байты (REG_BINARY)", SettingKind::RegistryData, true),
+        ]),
+        PolicyType::Script => ("Сценарии входа, выхода, запуска и завершения работы", vec![
+            setting("logon_scripts", "Сценарии входа пользователя", SettingKind::PathList, false),
+            setting("logoff_scripts", "Сценарии выхода пользователя", SettingKind::PathList, false),
+            setting("startup_scripts", "Сценарии запуска компьютера", SettingKind::PathList, false),
+            setting("shutdown_scripts", "Сценарии завершения работы компьютера", SettingKind::PathList, false),
+        ]),
+        PolicyType::Network => ("Сетевые параметры", vec![
+            setting("firewall_enabled", "Брандмауэр включён", SettingKind::Boolean, false),
+            setting("proxy_server", "Прокси-сервер `host:port`", SettingKind::String, false),
+            setting("proxy_bypass", "Адреса без прокси", SettingKind::PathList, false),
+            setting("dns_suffix_search_list", "Суффиксы DNS для поиска", SettingKind::PathList, false),
+        ]),
+        PolicyType::Software => ("Установка программ", vec![
+            setting("packages", "Пакеты MSI по путям UNC", SettingKind::PathList, true),
+            setting("deployment", "Назначение (assigned) или публикация (published)", choice(&["assigned", "published"]), false),
+            setting("uninstall_when_out_of_scope", "Удалять, когда политика перестаёт применяться", SettingKind::Boolean, false),
+        ]),
+        PolicyType::FolderRedirection => ("Перенаправление папок пользователя", vec![
+            setting("documents_path", "Документы", SettingKind::Path, false),
+            setting("desktop_path", "Рабочий стол", SettingKind::Path, false),
+            setting("app_data_path", "AppData (Roaming)", SettingKind::Path, false),
+            setting("move_contents", "Переносить содержимое в новое место", SettingKind::Boolean, false),
+            setting("grant_exclusive_rights", "Монопольные права пользователя на папки", SettingKind::Boolean, false),
+        ]),
+        PolicyType::Custom(_) => return None,
+    };
+    Some(PolicySchema { policy_type: policy_type.name().to_string(), description: description.to_string(), settings })
+}
+
+/// Схемы всех встроенных типов, для `GET /api/gpos/schemas`
+pub fn builtin_schemas() -> Vec<PolicySchema> {
+    [
+        PolicyType::Security,
+        PolicyType::Registry,
+        PolicyType::Script,
+        PolicyType::Network,
+        PolicyType::Software,
+        PolicyType::FolderRedirection,
+    ]
+    .iter()
+    .filter_map(schema_for)
+    .collect()
+}
+
+/// Проверить настройки по схеме типа: неизвестные ключи, обязательные, типы и пределы
+pub fn validate_settings(policy_type: &PolicyType, settings: &HashMap<String, PolicyValue>) -> Result<(), String> {
+    let Some(schema) = schema_for(policy_type) else {
+        return Ok(());
+    };
+    if let Some(key) = settings.keys().find(|key| !schema.settings.iter().any(|s| &s.key == *key)) {
+        return Err(format!("Unknown setting '{}' for {} policy", key, schema.policy_type));
+    }
+    for definition in &schema.settings {
+        match settings.get(&definition.key) {
+            Some(value) => check_value(definition, value, settings)?,
+            None if definition.required => {
+                return Err(format!("Setting '{}' is required for {} policy", definition.key, schema.policy_type));
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+fn check_value(definition: &SettingDefinition, value: &PolicyValue, settings: &HashMap<String, PolicyValue>) -> Result<(), String> {
+    let invalid = |expected: &str| format!("Setting '{}' must be {}", definition.key, expected);
+    match (&definition.kind, value) {
+        (SettingKind::Integer { min, max }, PolicyValue::Integer(n)) => {
+            if n < min || n > max {
+                return Err(invalid(&format!("between {} and {}", min, max)));
+            }
+        }
+        (SettingKind::Integer { .. }, _) => return Err(invalid("an integer")),
+        (SettingKind::Boolean, PolicyValue::Boolean(_)) => {}
+        (SettingKind::Boolean, _) => return Err(invalid("a boolean")),
+        (SettingKind::String | SettingKind::Path, PolicyValue::String(s)) if !s.trim().is_empty() => {}
+        (SettingKind::String | SettingKind::Path, _) => return Err(invalid("a non-empty string")),
+        (SettingKind::PathList, PolicyValue::List(items))
+            if items.iter().all(|item| matches!(item, PolicyValue::String(s) if !s.trim().is_empty())) => {}
+        (SettingKind::PathList, _) => return Err(invalid("a list of non-empty strings")),
+        (SettingKind::Choice { values }, PolicyValue::String(s)) if values.iter().any(|v| v.eq_ignore_ascii_case(s)) => {}
+        (SettingKind::Choice { values }, _) => return Err(invalid(&format!("one of {}", values.join(", ")))),
+        (SettingKind::RegistryData, data) => check_registry_data(settings.get("value_type"), data)?,
+    }
+    Ok(())
+}
+
+/// Значение `data` должно соответствовать `value_type`
+fn check_registry_data(value_type: Option<&PolicyValue>, data: &PolicyValue) -> Result<(), String> {
+    let Some(PolicyValue::String(value_type)) = value_type else {
+        return Ok(()); // ошибку даст сама настройка value_type
+    };
+    let valid = match value_type.to_ascii_uppercase().as_str() {
+        "REG_SZ" | "REG_EXPAND_SZ" => matches!(data, PolicyValue::String(_)),
+        "REG_MULTI_SZ" => matches!(data, PolicyValue::List(items) if items.iter().all(|item| matches!(item, PolicyValue::String(_)))),
+        "REG_DWORD" => matches!(data, PolicyValue::Integer(n) if (0..=i64::from(u32::MAX)).contains(n)),
+        "REG_QWORD" => matches!(data, PolicyValue::Integer(n) if *n >= 0),
+        "REG_BINARY" => match data {
+            PolicyValue::Binary(_) => true,
+            PolicyValue::List(items) => items.iter().all(|item| matches!(item, PolicyValue::Integer(b) if (0..=255).contains(b))),
+            _ => false,
+        },
+        _ => return Ok(()),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Setting 'data' does not match value_type {}", value_type))
+    }
+}
+
+/// Настройки из командной строки (`key=value`) по схеме: числа, true/false, списки через `;`;
+/// `data` параметра реестра — по его `value_type`. Для `Custom` и неизвестных ключей — строки
+pub fn parse_settings(policy_type: &PolicyType, pairs: &[String]) -> Result<HashMap<String, PolicyValue>, String> {
+    let mut raw = Vec::with_capacity(pairs.len());
+    for pair in pairs {
+        let (key, value) = pair.split_once('=').ok_or_else(|| format!("Invalid setting '{}': expected key=value", pair))?;
+        raw.push((key.trim().to_string(), value.to_string()));
+    }
+    let value_type = raw.iter()
+        .find(|(key, _)| key == "value_type")
+        .map(|(_, value)| value.trim().to_ascii_uppercase());
+    let kinds = schema_for(policy_type).map(|schema| schema.settings).unwrap_or_default();
+    let list = |raw: &str| PolicyValue::List(raw.split(';').map(|item| PolicyValue::String(item.trim().to_string())).collect());
+    let integer = |key: &str, raw: &str| raw.trim().parse().map(PolicyValue::Integer).map_err(|_| format!("Setting '{}' must be an integer", key));
+
+    let mut settings = HashMap::new();
+    for (key, value) in raw {
+        let kind = kinds.iter().find(|definition| definition.key == key).map(|definition| &definition.kind);
+        let parsed = match kind {
+            Some(SettingKind::Integer { .. }) => integer(&key, &value)?,
+            Some(SettingKind::Boolean) => PolicyValue::Boolean(value.trim().parse().map_err(|_| format!("Setting '{}' must be true or false", key))?),
+            Some(SettingKind::PathList) => list(&value),
+            Some(SettingKind::RegistryData) => match value_type.as_deref() {
+                Some("REG_DWORD" | "REG_QWORD") => integer(&key, &value)?,
+                Some("REG_MULTI_SZ") => list(&value),
+                Some("REG_BINARY") => PolicyValue::List(
+                    value.split(';').map(|byte| integer(&key, byte)).collect::<Result<_, _>>()?,
+                ),
+                _ => PolicyValue::String(value),
+            },
+            _ => PolicyValue::String(value),
+        };
+        settings.insert(key, parsed);
+    }
+    Ok(settings)
+}
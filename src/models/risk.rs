@@ -0,0 +1,66 @@
+// src/models/risk.rs
+
+//! Оценка риска входа (`security.risk`): вход сравнивается с недавней историей удачных входов
+//! пользователя — устройство (User-Agent) и сайт адреса клиента (по подсетям сайтов)
+
+use serde::{Deserialize, Serialize};
+
+/// Чем вход отличается от обычных для пользователя
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskSignal {
+    /// Такого устройства в истории удачных входов нет
+    NewDevice,
+    /// С этого сайта пользователь ещё не входил
+    NewLocation,
+    /// Предыдущий вход — с другого сайта и раньше `travel_window_secs` назад
+    ImpossibleTravel,
+}
+
+impl RiskSignal {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskSignal::NewDevice => "new_device",
+            RiskSignal::NewLocation => "new_location",
+            RiskSignal::ImpossibleTravel => "impossible_travel",
+        }
+    }
+}
+
+/// Реакция на признак; из сработавших признаков действует самая строгая
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskAction {
+    /// Не учитывать признак
+    Ignore,
+    /// Событие `login_risk` в аудите, вход разрешён
+    #[default]
+    Log,
+    /// Вход только с кодом TOTP пользователя: без кода отказ с `MFA_REQUIRED`, без TOTP — как `Block`
+    StepUp,
+    /// Отказать во входе
+    Block,
+}
+
+impl RiskAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskAction::Ignore => "ignore",
+            RiskAction::Log => "log",
+            RiskAction::StepUp => "step_up",
+            RiskAction::Block => "block",
+        }
+    }
+}
+
+/// Итог оценки входа с хотя бы одним признаком
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginRisk {
+    pub signals: Vec<RiskSignal>,
+    pub action: RiskAction,
+    /// Сайт адреса клиента
+    pub site: Option<String>,
+    /// Сайт и адрес предыдущего удачного входа
+    pub previous_site: Option<String>,
+    pub previous_ip: Option<String>,
+}
@@ -0,0 +1,164 @@
+// src/models/schema.rs
+
+//! Расширяемая схема: определения дополнительных атрибутов (как attributeSchema в AD).
+//! Значения хранятся в `meta` объекта; у многозначных атрибутов — через перевод строки
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Разделитель значений многозначного атрибута в `meta`
+pub const VALUE_SEPARATOR: char = '\n';
+
+/// Атрибуты, которые объекты выдают сами; переопределять их в схеме нельзя
+const BUILTIN_ATTRIBUTES: &[&str] = &[
+    "objectClass", "distinguishedName", "cn", "name", "ou", "uid", "sAMAccountName", "userPrincipalName",
+    "mail", "displayName", "givenName", "sn", "description", "objectSid", "objectGUID", "memberOf", "member",
+    "accountExpires", "userAccountControl", "whenCreated", "whenChanged", "lastLogon", "lastLogonTimestamp",
+    "profilePath", "scriptPath", "servicePrincipalName", "primaryGroupToken", "primaryGroupID", "tokenGroups",
+    "groupType", "gPLink", "gPOptions", "userPassword", "unicodePwd", "isCriticalSystemObject", "nTSecurityDescriptor",
+    "proxyAddresses", "targetAddress", "telephoneNumber", "company", "msExchRecipientTypeDetails", "memberURL",
+];
+
+/// Синтаксис значений атрибута
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeSyntax {
+    /// Произвольная строка
+    String,
+    /// Целое со знаком (64 бит)
+    Integer,
+    /// `TRUE` или `FALSE`, как в LDAP
+    Boolean,
+    /// LDAP Generalized Time: `20240131120000Z`
+    GeneralizedTime,
+    /// DN: `CN=...,DC=...`
+    Dn,
+}
+
+impl AttributeSyntax {
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        let valid = match self {
+            AttributeSyntax::String => !value.is_empty(),
+            AttributeSyntax::Integer => value.parse::<i64>().is_ok(),
+            AttributeSyntax::Boolean => value == "TRUE" || value == "FALSE",
+            AttributeSyntax::GeneralizedTime => parse_generalized_time(value).is_some(),
+            AttributeSyntax::Dn => value.split(',').all(|rdn| rdn.split_once('=').is_some_and(|(t, v)| !t.trim().is_empty() && !v.is_empty())),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("'{}' is not a valid {:?} value", value, self))
+        }
+    }
+}
+
+/// Класс объектов, к которым применяется атрибут
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaClass {
+    User,
+    Group,
+    OrganizationalUnit,
+    Contact,
+}
+
+impl From<crate::models::SecuredObject> for SchemaClass {
+    fn from(object: crate::models::SecuredObject) -> Self {
+        match object {
+            crate::models::SecuredObject::User(_) => SchemaClass::User,
+            crate::models::SecuredObject::Group(_) => SchemaClass::Group,
+            crate::models::SecuredObject::Ou(_) => SchemaClass::OrganizationalUnit,
+            crate::models::SecuredObject::Contact(_) => SchemaClass::Contact,
+        }
+    }
+}
+
+/// Определение дополнительного атрибута
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttributeDefinition {
+    /// lDAPDisplayName: имя в `meta`, LDAP и REST
+    pub name: String,
+    pub description: Option<String>,
+    pub syntax: AttributeSyntax,
+    pub multi_valued: bool,
+    /// Обязателен при создании и изменении объектов из `classes`
+    pub required: bool,
+    /// Индекс по значению для поиска на равенство
+    pub indexed: bool,
+    pub classes: Vec<SchemaClass>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AttributeDefinition {
+    /// Проверка имени: буква, затем буквы, цифры и `-`; встроенные атрибуты заняты
+    pub fn validate(&self) -> Result<(), String> {
+        let mut chars = self.name.chars();
+        let well_formed = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !well_formed {
+            return Err(format!("Invalid attribute name '{}'", self.name));
+        }
+        if BUILTIN_ATTRIBUTES.iter().any(|builtin| builtin.eq_ignore_ascii_case(&self.name)) {
+            return Err(format!("Attribute {} is built in", self.name));
+        }
+        if self.classes.is_empty() {
+            return Err(format!("Attribute {} applies to no object class", self.name));
+        }
+        Ok(())
+    }
+
+    pub fn applies_to(&self, class: SchemaClass) -> bool {
+        self.classes.contains(&class)
+    }
+
+    /// Проверить значения атрибута объекта
+    pub fn validate_values(&self, values: &[String]) -> Result<(), String> {
+        if values.len() > 1 && !self.multi_valued {
+            return Err(format!("Attribute {} is single-valued", self.name));
+        }
+        for value in values {
+            if value.contains(VALUE_SEPARATOR) {
+                return Err(format!("Attribute {}: values cannot contain line breaks", self.name));
+            }
+            self.syntax.validate(value).map_err(|e| format!("Attribute {}: {}", self.name, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Значения атрибута из `meta`
+pub fn values(raw: &str) -> Vec<String> {
+    raw.split(VALUE_SEPARATOR).map(str::to_string).collect()
+}
+
+/// Изменить атрибуты в `meta`: пустой список значений удаляет атрибут
+pub fn apply_changes(meta: &mut HashMap<String, String>, changes: HashMap<String, Vec<String>>) {
+    for (name, values) in changes {
+        if values.is_empty() {
+            meta.remove(&name);
+        } else {
+            meta.insert(name, values.join(&VALUE_SEPARATOR.to_string()));
+        }
+    }
+}
+
+/// Проверить атрибуты объекта класса `class` по схеме; атрибуты вне схемы не проверяются
+pub fn validate_meta(schema: &[AttributeDefinition], class: SchemaClass, meta: &HashMap<String, String>) -> Result<(), String> {
+    for definition in schema.iter().filter(|definition| definition.applies_to(class)) {
+        match meta.get(&definition.name) {
+            Some(raw) => definition.validate_values(&values(raw))?,
+            None if definition.required => return Err(format!("Attribute {} is required", definition.name)),
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+fn parse_generalized_time(value: &str) -> Option<DateTime<Utc>> {
+    let digits = value.strip_suffix('Z')?;
+    let digits = digits.split_once('.').map_or(digits, |(whole, _)| whole);
+    chrono::NaiveDateTime::parse_from_str(digits, "%Y%m%d%H%M%S")
+        .ok()
+        .map(|time| time.and_utc())
+}
@@ -0,0 +1,244 @@
+// src/models/security.rs
+
+//! Дескриптор безопасности объекта каталога (аналог nTSecurityDescriptor): владелец и DACL
+//! из ACE с масками доступа и флагами наследования; проверка доступа — по MS-DTYP 2.5.3.2
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use bitflags::bitflags;
+use crate::models::sid::SecurityIdentifier;
+
+bitflags! {
+    /// Права на объект каталога (ACCESS_MASK, MS-ADTS 5.1.3.2)
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    pub struct AccessMask: u32 {
+        const CREATE_CHILD   = 0x0000_0001;
+        const DELETE_CHILD   = 0x0000_0002;
+        const LIST_CHILDREN  = 0x0000_0004;
+        const SELF_WRITE     = 0x0000_0008;
+        const READ_PROPERTY  = 0x0000_0010;
+        const WRITE_PROPERTY = 0x0000_0020;
+        const DELETE_TREE    = 0x0000_0040;
+        const LIST_OBJECT    = 0x0000_0080;
+        const CONTROL_ACCESS = 0x0000_0100;
+        const DELETE         = 0x0001_0000;
+        const READ_CONTROL   = 0x0002_0000;
+        const WRITE_DAC      = 0x0004_0000;
+        const WRITE_OWNER    = 0x0008_0000;
+
+        const GENERIC_READ  = Self::READ_CONTROL.bits() | Self::LIST_CHILDREN.bits() | Self::READ_PROPERTY.bits() | Self::LIST_OBJECT.bits();
+        const GENERIC_WRITE = Self::READ_CONTROL.bits() | Self::SELF_WRITE.bits() | Self::WRITE_PROPERTY.bits();
+        const GENERIC_ALL   = 0x000F_01FF;
+    }
+}
+
+bitflags! {
+    /// Флаги наследования ACE (ACE_HEADER.AceFlags)
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    pub struct AceFlags: u8 {
+        /// Наследуется объектами (пользователи, группы)
+        const OBJECT_INHERIT       = 0x01;
+        /// Наследуется контейнерами (OU)
+        const CONTAINER_INHERIT    = 0x02;
+        /// Наследуется только непосредственными потомками
+        const NO_PROPAGATE_INHERIT = 0x04;
+        /// Действует только на потомков, не на сам объект
+        const INHERIT_ONLY         = 0x08;
+        /// Унаследован от родителя; выставляется при вычислении, не хранится
+        const INHERITED            = 0x10;
+    }
+}
+
+// Маски и флаги в базе — числами
+impl Serialize for AccessMask {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(self.bits())
+    }
+}
+
+impl<'de> Deserialize<'de> for AccessMask {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let bits = u32::deserialize(deserializer)?;
+        Self::from_bits(bits).ok_or_else(|| {
+            serde::de::Error::custom(format!("Invalid AccessMask: 0x{:08X}", bits))
+        })
+    }
+}
+
+impl Serialize for AceFlags {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u8(self.bits())
+    }
+}
+
+impl<'de> Deserialize<'de> for AceFlags {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let bits = u8::deserialize(deserializer)?;
+        Self::from_bits(bits).ok_or_else(|| {
+            serde::de::Error::custom(format!("Invalid AceFlags: 0x{:02X}", bits))
+        })
+    }
+}
+
+impl AccessMask {
+    /// Объединение прав по именам (`GENERIC_READ`, `WRITE_PROPERTY`, ...; без учёта регистра)
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Self, String> {
+        names.iter().try_fold(Self::empty(), |mask, name| {
+            Self::from_name(&name.as_ref().trim().to_uppercase())
+                .map(|right| mask | right)
+                .ok_or_else(|| format!("Unknown access right: {}", name.as_ref()))
+        })
+    }
+
+    /// Имена прав; набор, целиком покрывающий `GENERIC_*`, называется им
+    pub fn names(&self) -> Vec<String> {
+        if self.contains(Self::GENERIC_ALL) {
+            return vec!["GENERIC_ALL".to_string()];
+        }
+        let generic = [("GENERIC_READ", Self::GENERIC_READ), ("GENERIC_WRITE", Self::GENERIC_WRITE)];
+        let mut covered = Self::empty();
+        let mut names = Vec::new();
+        for (name, rights) in generic.into_iter().filter(|(_, rights)| self.contains(*rights)) {
+            covered |= rights;
+            names.push(name.to_string());
+        }
+        names.extend((*self - covered).iter_names().map(|(name, _)| name.to_string()));
+        names
+    }
+}
+
+impl AceFlags {
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Self, String> {
+        names.iter().try_fold(Self::empty(), |flags, name| {
+            Self::from_name(&name.as_ref().trim().to_uppercase())
+                .map(|flag| flags | flag)
+                .ok_or_else(|| format!("Unknown ACE flag: {}", name.as_ref()))
+        })
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.iter_names().map(|(name, _)| name.to_string()).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AceType {
+    Allow,
+    Deny,
+}
+
+/// Запись DACL: разрешение или запрет прав для участника
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Ace {
+    pub ace_type: AceType,
+    pub flags: AceFlags,
+    pub mask: AccessMask,
+    pub trustee: SecurityIdentifier,
+}
+
+impl Ace {
+    pub fn allow(trustee: SecurityIdentifier, mask: AccessMask, flags: AceFlags) -> Self {
+        Self { ace_type: AceType::Allow, flags, mask, trustee }
+    }
+
+    pub fn deny(trustee: SecurityIdentifier, mask: AccessMask, flags: AceFlags) -> Self {
+        Self { ace_type: AceType::Deny, flags, mask, trustee }
+    }
+}
+
+/// Дескриптор безопасности: владелец и явные ACE объекта
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SecurityDescriptor {
+    pub owner: SecurityIdentifier,
+    pub dacl: Vec<Ace>,
+    /// SE_DACL_PROTECTED: ACE родителей не наследуются
+    pub protected: bool,
+}
+
+impl SecurityDescriptor {
+    pub fn new(owner: SecurityIdentifier) -> Self {
+        Self { owner, dacl: Vec::new(), protected: false }
+    }
+
+    /// ACE, которые получает потомок: `container` — потомок OU, `direct` — непосредственный.
+    /// У копий выставлен INHERITED и снят INHERIT_ONLY
+    pub fn inheritable_aces(&self, container: bool, direct: bool) -> impl Iterator<Item = Ace> + '_ {
+        let applies = if container { AceFlags::CONTAINER_INHERIT } else { AceFlags::OBJECT_INHERIT };
+        self.dacl.iter()
+            .filter(move |ace| ace.flags.contains(applies) && (direct || !ace.flags.contains(AceFlags::NO_PROPAGATE_INHERIT)))
+            .map(|ace| {
+                let mut inherited = ace.clone();
+                inherited.flags = (ace.flags | AceFlags::INHERITED) - AceFlags::INHERIT_ONLY;
+                inherited
+            })
+    }
+
+    /// Проверка доступа: ACE по порядку, запрет любой из запрошенных прав — отказ,
+    /// разрешения накапливаются. Владелец всегда может читать и менять DACL
+    pub fn check_access(&self, principal_sids: &[SecurityIdentifier], desired: AccessMask) -> bool {
+        let mut remaining = desired;
+        if principal_sids.contains(&self.owner) {
+            remaining -= AccessMask::READ_CONTROL | AccessMask::WRITE_DAC;
+        }
+
+        for ace in self.dacl.iter().filter(|ace| !ace.flags.contains(AceFlags::INHERIT_ONLY)) {
+            if remaining.is_empty() {
+                break;
+            }
+            if !principal_sids.contains(&ace.trustee) {
+                continue;
+            }
+            match ace.ace_type {
+                AceType::Deny if ace.mask.intersects(remaining) => return false,
+                AceType::Deny => {}
+                AceType::Allow => remaining -= ace.mask,
+            }
+        }
+        remaining.is_empty()
+    }
+}
+
+/// Объект каталога, на который назначается дескриптор
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecuredObject {
+    User(Uuid),
+    Group(Uuid),
+    Ou(Uuid),
+    Contact(Uuid),
+}
+
+impl SecuredObject {
+    pub fn id(&self) -> Uuid {
+        match self {
+            SecuredObject::User(id) | SecuredObject::Group(id) | SecuredObject::Ou(id) | SecuredObject::Contact(id) => *id,
+        }
+    }
+
+    /// OU — контейнер, пользователи, группы и контакты — листья
+    pub fn is_container(&self) -> bool {
+        matches!(self, SecuredObject::Ou(_))
+    }
+}
+
+impl std::fmt::Display for SecuredObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecuredObject::User(id) => write!(f, "user {}", id),
+            SecuredObject::Group(id) => write!(f, "group {}", id),
+            SecuredObject::Ou(id) => write!(f, "OU {}", id),
+            SecuredObject::Contact(id) => write!(f, "contact {}", id),
+        }
+    }
+}
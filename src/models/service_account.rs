@@ -0,0 +1,152 @@
+// src/models/service_account.rs
+
+//! Управляемая учётная запись службы (как gMSA в AD): пароль генерирует каталог и сам меняет
+//! по расписанию, а получить его могут только участники из `allowed_principals`.
+//! Учётная запись — обычный `User` с тем же id: SPN, ключи Kerberos, членство в группах.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Duration, Utc};
+use bitflags::bitflags;
+use rand::{rngs::OsRng, RngCore};
+use crate::models::sid::SecurityIdentifier;
+
+/// Случайных байт в пароле: bcrypt учитывает только первые 72 байта, 48 в base64 — 64 символа
+const PASSWORD_BYTES: usize = 48;
+
+/// msDS-ManagedPasswordInterval по умолчанию, дней
+pub const DEFAULT_ROTATION_DAYS: u32 = 30;
+
+/// Сколько после смены принимается прежний пароль, часов
+pub const DEFAULT_OVERLAP_HOURS: u32 = 24;
+
+bitflags! {
+    /// Ограничения использования учётной записи службы
+    #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+    #[serde(transparent)]
+    pub struct ServiceAccountFlags: u32 {
+        /// Вход по паролю (REST, gRPC, LDAP bind, RADIUS) запрещён — только Kerberos
+        const DENY_PASSWORD_LOGON = 0x1;
+        /// Учётную запись нельзя делегировать (userAccountControl NOT_DELEGATED)
+        const NOT_DELEGATED       = 0x2;
+    }
+}
+
+impl ServiceAccountFlags {
+    /// Флаг по имени без учёта регистра
+    pub fn from_flag_name(name: &str) -> Result<Self, String> {
+        Self::all().iter_names()
+            .find(|(flag, _)| flag.eq_ignore_ascii_case(name))
+            .map(|(_, flag)| flag)
+            .ok_or_else(|| format!("Unknown service account flag: {}", name))
+    }
+
+    /// Флаги по списку имён
+    pub fn from_flag_names<S: AsRef<str>>(names: &[S]) -> Result<Self, String> {
+        names.iter().try_fold(Self::empty(), |flags, name| Ok(flags | Self::from_flag_name(name.as_ref())?))
+    }
+
+    /// Имена установленных флагов
+    pub fn flag_names(&self) -> Vec<String> {
+        self.iter_names().map(|(name, _)| name.to_string()).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServiceAccount {
+    /// id учётной записи `User`
+    pub id: Uuid,
+    pub flags: ServiceAccountFlags,
+    /// msDS-GroupMSAMembership: SID пользователей и групп, которым выдаётся пароль
+    pub allowed_principals: Vec<SecurityIdentifier>,
+    pub rotation_interval_days: u32,
+    pub overlap_hours: u32,
+    pub current_password: String,
+    /// Прежний пароль; принимается и выдаётся до конца окна перекрытия
+    pub previous_password: Option<String>,
+    pub password_changed_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Пароль для участника из `allowed_principals` (аналог msDS-ManagedPassword)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ManagedPassword {
+    pub current_password: String,
+    /// Прежний пароль, пока он ещё принимается
+    pub previous_password: Option<String>,
+    pub password_changed_at: DateTime<Utc>,
+    pub next_rotation: DateTime<Utc>,
+}
+
+impl ServiceAccount {
+    pub fn new(id: Uuid, allowed_principals: Vec<SecurityIdentifier>) -> Self {
+        Self {
+            id,
+            flags: ServiceAccountFlags::DENY_PASSWORD_LOGON,
+            allowed_principals,
+            rotation_interval_days: DEFAULT_ROTATION_DAYS,
+            overlap_hours: DEFAULT_OVERLAP_HOURS,
+            current_password: generate_password(),
+            previous_password: None,
+            password_changed_at: Utc::now(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rotation_interval_days == 0 {
+            return Err("Rotation interval must be at least one day".to_string());
+        }
+        if i64::from(self.overlap_hours) >= i64::from(self.rotation_interval_days) * 24 {
+            return Err("Overlap window must be shorter than the rotation interval".to_string());
+        }
+        Ok(())
+    }
+
+    /// Когда пароль сменится по расписанию
+    pub fn next_rotation(&self) -> DateTime<Utc> {
+        self.password_changed_at + Duration::days(self.rotation_interval_days.into())
+    }
+
+    pub fn rotation_due(&self, now: DateTime<Utc>) -> bool {
+        now >= self.next_rotation()
+    }
+
+    /// Конец окна, в котором ещё принимается прежний пароль
+    pub fn overlap_ends(&self) -> DateTime<Utc> {
+        self.password_changed_at + Duration::hours(self.overlap_hours.into())
+    }
+
+    /// Прежний пароль, если окно перекрытия ещё не закончилось
+    pub fn previous_password_valid(&self, now: DateTime<Utc>) -> Option<&str> {
+        self.previous_password.as_deref().filter(|_| now < self.overlap_ends())
+    }
+
+    /// Что выдаётся участнику из `allowed_principals`
+    pub fn managed_password(&self, now: DateTime<Utc>) -> ManagedPassword {
+        ManagedPassword {
+            current_password: self.current_password.clone(),
+            previous_password: self.previous_password_valid(now).map(str::to_string),
+            password_changed_at: self.password_changed_at,
+            next_rotation: self.next_rotation(),
+        }
+    }
+
+    /// Новый пароль; текущий становится прежним
+    pub fn rotate(&mut self) {
+        self.previous_password = Some(std::mem::replace(&mut self.current_password, generate_password()));
+        self.password_changed_at = Utc::now();
+        self.updated_at = Utc::now();
+    }
+}
+
+/// Случайный пароль из `PASSWORD_BYTES` байт ОС в base64
+fn generate_password() -> String {
+    use base64::Engine;
+
+    let mut bytes = [0u8; PASSWORD_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
@@ -0,0 +1,45 @@
+// src/models/session.rs
+
+//! Сессия — выданный при входе токен (REST, gRPC): id сессии — `jti` токена. Отозванная
+//! сессия попадает в список отзыва, и токен перестаёт приниматься до истечения срока
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::models::LoginProtocol;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub protocol: LoginProtocol,
+    /// Адрес клиента при входе
+    pub ip_addr: Option<String>,
+    /// Клиент: заголовок User-Agent (REST) или метаданные `user-agent` (gRPC)
+    pub device: Option<String>,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    pub fn new(user_id: Uuid, protocol: LoginProtocol, ip_addr: Option<String>, device: Option<String>, ttl: chrono::Duration) -> Self {
+        let issued_at = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            protocol,
+            ip_addr,
+            device,
+            issued_at,
+            expires_at: issued_at + ttl,
+            revoked_at: None,
+        }
+    }
+
+    /// Токен сессии ещё принимается
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SecurityIdentifier {
@@ -19,6 +20,38 @@ impl SecurityIdentifier {
         }
     }
 
+    /// SID нового домена: S-1-5-21-x-y-z со случайными x, y, z
+    pub fn new_domain() -> Self {
+        Self::new_from_parts(
+            [0, 0, 0, 0, 0, 5],
+            vec![21, rand::random(), rand::random(), rand::random()],
+        )
+    }
+
+    /// Встроенная группа BUILTIN: S-1-5-32-rid
+    pub fn new_builtin(rid: u32) -> Self {
+        Self::new_from_parts([0, 0, 0, 0, 0, 5], vec![32, rid])
+    }
+
+    /// SID участника: этот SID (домена) с добавленным RID
+    pub fn with_rid(&self, rid: u32) -> Self {
+        let mut sid = self.clone();
+        sid.sub_authorities.push(rid);
+        sid
+    }
+
+    /// SID учётной записи домена: S-1-5-21-x-y-z-rid
+    pub fn is_domain_account(&self) -> bool {
+        self.authority == [0, 0, 0, 0, 0, 5]
+            && self.sub_authorities.len() == 5
+            && self.sub_authorities[0] == 21
+    }
+
+    /// RID — последний sub-authority
+    pub fn rid(&self) -> Option<u32> {
+        self.sub_authorities.last().copied()
+    }
+
     pub fn new_from_parts(authority: [u8; 6], subs: Vec<u32>) -> Self {
         Self {
             revision: 1,
@@ -27,19 +60,107 @@ impl SecurityIdentifier {
         }
     }
 
-    pub fn to_string(&self) -> String {
-        let auth = u64::from_be_bytes([0, 0, 0, 0, 0, 0, self.authority[0], self.authority[1]])
-            + (u64::from(self.authority[2]) << 40)
-            + (u64::from(self.authority[3]) << 32)
-            + (u64::from(self.authority[4]) << 24)
-            + (u64::from(self.authority[5]) << 16);
-        let subs: Vec<String> = self.sub_authorities.iter().map(|a| a.to_string()).collect();
-        format!("S-{}-{}-{}", self.revision, auth, subs.join("-"))
+    /// Числовое значение IdentifierAuthority (48 бит, big-endian)
+    pub fn authority_value(&self) -> u64 {
+        self.authority.iter().fold(0, |value, byte| (value << 8) | u64::from(*byte))
+    }
+
+    /// Двоичная форма (MS-DTYP 2.4.2.2), как в атрибуте `objectSid`: ревизия, число
+    /// sub-authority, IdentifierAuthority big-endian, затем sub-authority little-endian
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + 4 * self.sub_authorities.len());
+        bytes.push(self.revision);
+        bytes.push(self.sub_authorities.len() as u8);
+        bytes.extend_from_slice(&self.authority);
+        for sub in &self.sub_authorities {
+            bytes.extend_from_slice(&sub.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Разобрать двоичную форму `objectSid`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SidError> {
+        if bytes.len() < 8 {
+            return Err(SidError(format!("{} bytes is too short for a SID", bytes.len())));
+        }
+        let count = bytes[1] as usize;
+        if count > MAX_SUB_AUTHORITIES {
+            return Err(SidError(format!("{} sub-authorities, at most {} allowed", count, MAX_SUB_AUTHORITIES)));
+        }
+        if bytes.len() != 8 + 4 * count {
+            return Err(SidError(format!("expected {} bytes for {} sub-authorities, got {}", 8 + 4 * count, count, bytes.len())));
+        }
+
+        let mut authority = [0u8; 6];
+        authority.copy_from_slice(&bytes[2..8]);
+        let sub_authorities = bytes[8..]
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        Ok(Self { revision: bytes[0], authority, sub_authorities })
     }
 }
 
+/// Не более 15 sub-authority (SID_MAX_SUB_AUTHORITIES)
+const MAX_SUB_AUTHORITIES: usize = 15;
+
+/// Ошибка разбора SID из строки или двоичной формы
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SidError(String);
+
+impl fmt::Display for SidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid SID: {}", self.0)
+    }
+}
+
+impl std::error::Error for SidError {}
+
+/// Строковая форма `S-1-5-21-…`; IdentifierAuthority от 2^32 — в hex, как у ConvertSidToStringSid
 impl fmt::Display for SecurityIdentifier {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        let authority = self.authority_value();
+        if authority >> 32 == 0 {
+            write!(f, "S-{}-{}", self.revision, authority)?;
+        } else {
+            write!(f, "S-{}-0x{:012X}", self.revision, authority)?;
+        }
+        for sub in &self.sub_authorities {
+            write!(f, "-{}", sub)?;
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+impl FromStr for SecurityIdentifier {
+    type Err = SidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SidError(format!("'{}'", s));
+
+        let mut parts = s.split('-');
+        if !parts.next().is_some_and(|prefix| prefix.eq_ignore_ascii_case("S")) {
+            return Err(invalid());
+        }
+        let revision: u8 = parts.next().and_then(|r| r.parse().ok()).ok_or_else(invalid)?;
+        let authority_part = parts.next().ok_or_else(invalid)?;
+        let authority_value = match authority_part.strip_prefix("0x").or(authority_part.strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => authority_part.parse(),
+        }.map_err(|_| invalid())?;
+        if authority_value >> 48 != 0 {
+            return Err(invalid());
+        }
+
+        let sub_authorities = parts
+            .map(|sub| sub.parse::<u32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if sub_authorities.len() > MAX_SUB_AUTHORITIES {
+            return Err(invalid());
+        }
+
+        let mut authority = [0u8; 6];
+        authority.copy_from_slice(&authority_value.to_be_bytes()[2..]);
+        Ok(Self { revision, authority, sub_authorities })
+    }
+}
@@ -0,0 +1,112 @@
+// src/models/site.rs
+
+//! Сайты и подсети (как CN=Sites в AD): подсеть в нотации CIDR привязана к сайту, по адресу
+//! клиента определяется его сайт, а по сайту — ближайшие контроллеры домена.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use std::net::IpAddr;
+
+/// Сайт — группа хорошо связанных сетей, например офис или ЦОД
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Site {
+    pub id: Uuid,
+    /// Имя сайта, например `Default-First-Site-Name`
+    pub name: String,
+    pub description: Option<String>,
+    /// Адрес или описание размещения (атрибут location)
+    pub location: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Site {
+    pub fn new(name: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            description: None,
+            location: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// Подсеть IPv4 или IPv6, привязанная к сайту
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Subnet {
+    pub id: Uuid,
+    /// Адрес сети: биты узла нулевые
+    pub network: IpAddr,
+    pub prefix_len: u8,
+    pub site_id: Uuid,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Subnet {
+    /// Подсеть из записи CIDR (`10.1.0.0/16`, `2001:db8::/32`)
+    pub fn new(cidr: &str, site_id: Uuid) -> Result<Self, String> {
+        let (network, prefix_len) = parse_cidr(cidr)?;
+        Ok(Self {
+            id: Uuid::new_v4(),
+            network,
+            prefix_len,
+            site_id,
+            description: None,
+            location: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+    }
+
+    /// Имя подсети в нотации CIDR, как cn объекта subnet в AD
+    pub fn cidr(&self) -> String {
+        format!("{}/{}", self.network, self.prefix_len)
+    }
+
+    /// Входит ли адрес в подсеть; IPv4, отображённый в IPv6 (`::ffff:10.1.2.3`), сравнивается как IPv4
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        cidr_contains(self.network, self.prefix_len, addr)
+    }
+}
+
+/// Входит ли адрес в сеть `network/prefix_len` (биты узла `network` нулевые)
+pub fn cidr_contains(network: IpAddr, prefix_len: u8, addr: IpAddr) -> bool {
+    match (network, addr.to_canonical()) {
+        (IpAddr::V4(network), IpAddr::V4(addr)) => mask_v4(u32::from(addr), prefix_len) == u32::from(network),
+        (IpAddr::V6(network), IpAddr::V6(addr)) => mask_v6(u128::from(addr), prefix_len) == u128::from(network),
+        _ => false,
+    }
+}
+
+/// Разобрать запись CIDR; биты узла должны быть нулевыми, как требует AD
+pub fn parse_cidr(cidr: &str) -> Result<(IpAddr, u8), String> {
+    let invalid = || format!("Invalid subnet '{}': expected address/prefix, e.g. 10.1.0.0/16", cidr);
+    let (address, prefix) = cidr.trim().split_once('/').ok_or_else(invalid)?;
+    let address: IpAddr = address.parse().map_err(|_| invalid())?;
+    let prefix_len: u8 = prefix.parse().map_err(|_| invalid())?;
+
+    let host_bits_set = match address {
+        IpAddr::V4(v4) if prefix_len <= 32 => mask_v4(u32::from(v4), prefix_len) != u32::from(v4),
+        IpAddr::V6(v6) if prefix_len <= 128 => mask_v6(u128::from(v6), prefix_len) != u128::from(v6),
+        _ => return Err(format!("Invalid subnet '{}': prefix length is out of range", cidr)),
+    };
+    if host_bits_set {
+        return Err(format!("Invalid subnet '{}': host bits are set", cidr));
+    }
+    Ok((address, prefix_len))
+}
+
+/// Обнулить биты узла
+fn mask_v4(addr: u32, prefix_len: u8) -> u32 {
+    if prefix_len == 0 { 0 } else { addr & (u32::MAX << (32 - u32::from(prefix_len))) }
+}
+
+fn mask_v6(addr: u128, prefix_len: u8) -> u128 {
+    if prefix_len == 0 { 0 } else { addr & (u128::MAX << (128 - u32::from(prefix_len))) }
+}
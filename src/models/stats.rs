@@ -0,0 +1,37 @@
+// src/models/stats.rs
+
+//! Сводка каталога для панелей мониторинга: `GET /api/stats` и `nextDomen stats` без выборки
+//! всех объектов клиентом
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema, Debug, Clone)]
+pub struct DirectoryStats {
+    /// Число объектов по типу: `user`, `group`, `ou`, `contact`, `gpo`, `service_account`, `site`, `subnet`, `pso`
+    pub objects: BTreeMap<String, usize>,
+    /// Учётные записи компьютеров среди пользователей
+    pub computers: usize,
+    pub disabled_users: usize,
+    /// Заблокированные после неудачных попыток входа на момент запроса
+    pub locked_users: usize,
+    pub enabled_gpos: usize,
+    /// GPO, привязанные хотя бы к одному OU или домену
+    pub linked_gpos: usize,
+    /// Пользователи по OU, в порядке DN; пользователи вне OU — строка без `ou_id`
+    pub users_per_ou: Vec<OuUserCount>,
+    /// Размер файла базы, байт
+    pub db_size_bytes: u64,
+    /// Последняя запись базы на диск; до первой записи процесса — время изменения файла
+    pub last_flush: Option<DateTime<Utc>>,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema, Debug, Clone)]
+pub struct OuUserCount {
+    pub ou_id: Option<Uuid>,
+    pub dn: Option<String>,
+    pub users: usize,
+}
@@ -0,0 +1,41 @@
+// src/models/sync.rs
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Состояние односторонней синхронизации с внешним LDAP / AD (cookie для инкрементальных циклов)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LdapSyncState {
+    /// URL источника; состояние хранится отдельно для каждого источника
+    pub source: String,
+    /// highestCommittedUSN источника на момент последнего успешного цикла (AD)
+    pub highest_usn: Option<u64>,
+    /// Наибольший modifyTimestamp среди полученных записей (OpenLDAP и др.)
+    pub last_modify_timestamp: Option<String>,
+    pub last_full_sync: Option<DateTime<Utc>>,
+    pub last_sync: Option<DateTime<Utc>>,
+}
+
+impl LdapSyncState {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self { source: source.into(), ..Default::default() }
+    }
+
+    /// Полный цикл нужен, если ещё не было ни одного успешного
+    pub fn needs_full_sync(&self) -> bool {
+        self.highest_usn.is_none() && self.last_modify_timestamp.is_none()
+    }
+}
+
+/// Объекты каталога, сопоставленные записям источника синхронизации: по ним разрешаются
+/// участники групп, не попавшие в инкрементальный цикл, и находятся удалённые в источнике
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LdapSyncObjects {
+    /// Ключ DN записи источника (`dn::key`) → id объекта
+    pub by_dn: HashMap<String, Uuid>,
+    /// objectGUID (AD) или entryUUID записи → id объекта; надгробия AD узнаются по нему
+    pub by_guid: HashMap<String, Uuid>,
+}
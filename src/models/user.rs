@@ -5,9 +5,88 @@ use uuid::Uuid;
 use crate::models::sid::SecurityIdentifier;
 use crate::models::password::PasswordHash;
 use crate::models::MfaMethod;
+use crate::models::kerberos::KerberosKey;
+use crate::models::ldap_entry::{LdapAttributes, LdapContext, LdapEntry};
+use crate::directory_service::DirectoryError;
 use chrono::Utc;
+use bitflags::bitflags;
 use std::collections::HashMap;
 
+bitflags! {
+    /// userAccountControl (MS-ADTS 2.2.16): флаги учётной записи
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    pub struct UserAccountControl: u32 {
+        const SCRIPT                         = 0x0000_0001;
+        const ACCOUNTDISABLE                 = 0x0000_0002;
+        const HOMEDIR_REQUIRED               = 0x0000_0008;
+        const LOCKOUT                        = 0x0000_0010;
+        const PASSWD_NOTREQD                 = 0x0000_0020;
+        const PASSWD_CANT_CHANGE             = 0x0000_0040;
+        const ENCRYPTED_TEXT_PWD_ALLOWED     = 0x0000_0080;
+        const TEMP_DUPLICATE_ACCOUNT         = 0x0000_0100;
+        const NORMAL_ACCOUNT                 = 0x0000_0200;
+        const INTERDOMAIN_TRUST_ACCOUNT      = 0x0000_0800;
+        const WORKSTATION_TRUST_ACCOUNT      = 0x0000_1000;
+        const SERVER_TRUST_ACCOUNT           = 0x0000_2000;
+        const DONT_EXPIRE_PASSWORD           = 0x0001_0000;
+        const MNS_LOGON_ACCOUNT              = 0x0002_0000;
+        const SMARTCARD_REQUIRED             = 0x0004_0000;
+        const TRUSTED_FOR_DELEGATION         = 0x0008_0000;
+        const NOT_DELEGATED                  = 0x0010_0000;
+        const USE_DES_KEY_ONLY               = 0x0020_0000;
+        const DONT_REQ_PREAUTH               = 0x0040_0000;
+        const PASSWORD_EXPIRED               = 0x0080_0000;
+        const TRUSTED_TO_AUTH_FOR_DELEGATION = 0x0100_0000;
+        const PARTIAL_SECRETS_ACCOUNT        = 0x0400_0000;
+    }
+}
+
+impl Default for UserAccountControl {
+    fn default() -> Self {
+        Self::NORMAL_ACCOUNT
+    }
+}
+
+// В базе — числом, как в AD
+impl Serialize for UserAccountControl {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(self.bits())
+    }
+}
+
+impl<'de> Deserialize<'de> for UserAccountControl {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let bits = u32::deserialize(deserializer)?;
+        Self::from_bits(bits).ok_or_else(|| {
+            serde::de::Error::custom(format!("Invalid userAccountControl: 0x{:08X}", bits))
+        })
+    }
+}
+
+impl UserAccountControl {
+    /// Флаг по имени (`DONT_EXPIRE_PASSWORD`, без учёта регистра)
+    pub fn from_flag_name(name: &str) -> Result<Self, String> {
+        Self::from_name(&name.trim().to_uppercase())
+            .ok_or_else(|| format!("Unknown userAccountControl flag: {}", name))
+    }
+
+    /// Объединение флагов по списку имён
+    pub fn from_flag_names<S: AsRef<str>>(names: &[S]) -> Result<Self, String> {
+        names.iter().try_fold(Self::empty(), |flags, name| Ok(flags | Self::from_flag_name(name.as_ref())?))
+    }
+
+    /// Имена установленных флагов
+    pub fn flag_names(&self) -> Vec<String> {
+        self.iter_names().map(|(name, _)| name.to_string()).collect()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct User {
     pub id: Uuid,
@@ -38,24 +117,122 @@ pub struct User {
 
     /// ID основной группы (например, 513 = Domain Users)
     pub primary_group_id: Option<u32>,
+
+    /// servicePrincipalName — имена служб, для которых KDC выдаёт билеты
+    #[serde(default)]
+    pub service_principal_names: Vec<String>,
+
+    /// Ключи Kerberos, выведенные из пароля при его установке
+    #[serde(default)]
+    pub kerberos_keys: Vec<KerberosKey>,
+
+    /// Флаги userAccountControl, кроме ACCOUNTDISABLE, LOCKOUT и PASSWORD_EXPIRED: их задают
+    /// `enabled`, `lockout_until` и `password_expires`, итоговое значение — `account_control()`
+    #[serde(default)]
+    pub user_account_control: UserAccountControl,
+
+    /// Общий секрет TOTP (RFC 6238): появляется, когда пользователь подтверждает метод `Totp`
+    /// кодом из приложения, и нужен для подтверждения входа вторым фактором (`security.risk`, `step_up`)
+    #[serde(default)]
+    pub totp_secret: Option<Vec<u8>>,
+
+    /// Секрет TOTP, выданный при подключении приложения и ещё не подтверждённый кодом из него
+    #[serde(default)]
+    pub totp_pending_secret: Option<Vec<u8>>,
+}
+
+/// Предел длины sAMAccountName в AD (MS-ADTS 3.1.1.5.2.2)
+pub const MAX_USERNAME_LENGTH: usize = 20;
+
+/// Символы, запрещённые в sAMAccountName
+const USERNAME_FORBIDDEN: &[char] = &['"', '/', '\\', '[', ']', ':', ';', '|', '=', ',', '+', '*', '?', '<', '>', '@'];
+
+/// Имена устройств Windows и встроенных субъектов безопасности: их нельзя занять учётной записью
+const RESERVED_USERNAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+    "SYSTEM", "EVERYONE", "SELF", "NETWORK", "INTERACTIVE", "BATCH", "SERVICE", "ANONYMOUS LOGON",
+    "AUTHENTICATED USERS", "LOCAL SERVICE", "NETWORK SERVICE", "CREATOR OWNER", "CREATOR GROUP",
+];
+
+/// Проверить имя учётной записи по правилам AD для sAMAccountName: до 20 символов, без
+/// `"/\[]:;|=,+*?<>@` и управляющих символов, не только точки и пробелы, без точки в конце,
+/// не зарезервированное имя
+pub fn validate_username(username: &str) -> Result<(), String> {
+    if username.trim().is_empty() {
+        return Err("Username cannot be empty".to_string());
+    }
+    if username != username.trim() {
+        return Err(format!("Invalid username '{}': leading or trailing spaces are not allowed", username));
+    }
+    if username.chars().count() > MAX_USERNAME_LENGTH {
+        return Err(format!("Invalid username '{}': must be at most {} characters", username, MAX_USERNAME_LENGTH));
+    }
+    if let Some(c) = username.chars().find(|c| USERNAME_FORBIDDEN.contains(c) || c.is_control()) {
+        return Err(format!("Invalid username '{}': character {:?} is not allowed", username, c));
+    }
+    if username.chars().all(|c| c == '.' || c == ' ') {
+        return Err(format!("Invalid username '{}': cannot consist only of dots and spaces", username));
+    }
+    if username.ends_with('.') {
+        return Err(format!("Invalid username '{}': cannot end with a dot", username));
+    }
+    if RESERVED_USERNAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(username)) {
+        return Err(format!("Invalid username '{}': the name is reserved", username));
+    }
+    Ok(())
+}
+
+/// Ключ уникальности имени: `Alice` и `alice` — одна учётная запись, как в AD
+pub fn normalize_username(username: &str) -> String {
+    crate::casefold::fold(username)
 }
+
     #[allow(dead_code)]
 impl User {
-    /// Преобразовать пользователя в LDAP-запись
-    pub async fn to_ldap_entry(
-        &self,
-        dn: &str,
-        service: &crate::directory_service::DirectoryService,
-    ) -> Result<HashMap<String, Vec<String>>, crate::directory_service::DirectoryError> {
-        let mut entry = HashMap::new();
-
-        entry.insert("objectClass".to_string(), vec![
-            "top".to_string(),
-            "person".to_string(),
-            "organizationalPerson".to_string(),
-            "user".to_string(),
-        ]);
-        entry.insert("distinguishedName".to_string(), vec![dn.to_string()]);
+    /// Встроенная учётная запись (`isCriticalSystemObject`), её нельзя удалить
+    pub fn is_protected(&self) -> bool {
+        crate::models::well_known::is_critical(&self.meta)
+    }
+
+    /// Заблокирована ли учётная запись после неудачных попыток входа
+    pub fn is_locked_out(&self) -> bool {
+        self.lockout_until.is_some_and(|until| until > Utc::now())
+    }
+
+    /// Истёк ли срок пароля: вход по нему отклоняется, пока пароль не сменят
+    pub fn is_password_expired(&self, now: chrono::DateTime<Utc>) -> bool {
+        !self.user_account_control.contains(UserAccountControl::DONT_EXPIRE_PASSWORD)
+            && self.password_expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// Итоговый userAccountControl: сохранённые флаги + ACCOUNTDISABLE, LOCKOUT и PASSWORD_EXPIRED
+    /// из состояния
+    pub fn account_control(&self) -> UserAccountControl {
+        let mut flags = self.user_account_control;
+        flags.set(UserAccountControl::ACCOUNTDISABLE, !self.enabled);
+        flags.set(UserAccountControl::LOCKOUT, self.is_locked_out());
+        flags.set(UserAccountControl::PASSWORD_EXPIRED, self.is_password_expired(Utc::now()));
+        flags
+    }
+
+    /// Применить userAccountControl: ACCOUNTDISABLE включает/отключает учётную запись,
+    /// снятый LOCKOUT снимает блокировку; установить LOCKOUT вручную нельзя (как в AD)
+    pub fn set_account_control(&mut self, flags: UserAccountControl) {
+        self.enabled = !flags.contains(UserAccountControl::ACCOUNTDISABLE);
+        if !flags.contains(UserAccountControl::LOCKOUT) && self.is_locked_out() {
+            self.lockout_until = None;
+            self.failed_logins = 0;
+        }
+        self.user_account_control = flags - UserAccountControl::ACCOUNTDISABLE - UserAccountControl::LOCKOUT
+            - UserAccountControl::PASSWORD_EXPIRED;
+    }
+
+    /// Атрибуты из хранимых полей, без вычисляемых memberOf и фотографий
+    pub fn stored_attributes(&self) -> LdapAttributes {
+        let mut entry = LdapAttributes::new();
+
         entry.insert("cn".to_string(), vec![
             self.display_name.as_deref().unwrap_or(&self.username).to_string()
         ]);
@@ -76,16 +253,14 @@ impl User {
             entry.insert("sn".to_string(), vec![surname.clone()]);
         }
 
-        entry.insert("objectSid".to_string(), vec![self.sid.to_string()]);
+        entry.insert_binary("objectSid", vec![self.sid.to_bytes()]);
 
         // accountExpires: 0 = never, 9223372036854775807 = disabled
         entry.insert("accountExpires".to_string(), vec![
             if self.enabled { "0" } else { "9223372036854775807" }.to_string()
         ]);
 
-        // userAccountControl: 512 = enabled, 514 = disabled
-        let uac = if self.enabled { 512 } else { 514 };
-        entry.insert("userAccountControl".to_string(), vec![uac.to_string()]);
+        entry.insert("userAccountControl".to_string(), vec![self.account_control().bits().to_string()]);
 
         entry.insert("whenCreated".to_string(), vec![
             format_ldap_time(&self.created_at)
@@ -94,8 +269,10 @@ impl User {
             format_ldap_time(&self.updated_at)
         ]);
 
+        // Integer8, как в AD; lastLogonTimestamp на одном контроллере совпадает с lastLogon
         if let Some(last_login) = &self.last_login {
-            entry.insert("lastLogon".to_string(), vec![format_ldap_time(last_login)]);
+            entry.insert("lastLogon".to_string(), vec![format_filetime(last_login)]);
+            entry.insert("lastLogonTimestamp".to_string(), vec![format_filetime(last_login)]);
         }
 
         if let Some(profile_path) = &self.profile_path {
@@ -104,41 +281,50 @@ impl User {
         if let Some(script_path) = &self.script_path {
             entry.insert("scriptPath".to_string(), vec![script_path.clone()]);
         }
-
-        // 🔽 memberOf
-        let groups = service.find_groups_by_member(self.id).await?;
-        let mut member_of = Vec::new();
-        for group in &groups {
-            let domain_dn = "DC=corp,DC=acme,DC=com"; // можно улучшить
-            let group_dn = format!("CN={},{}", group.name, domain_dn);
-            member_of.push(group_dn);
-        }
-        if !member_of.is_empty() {
-            entry.insert("memberOf".to_string(), member_of);
+        if !self.service_principal_names.is_empty() {
+            entry.insert("servicePrincipalName".to_string(), self.service_principal_names.clone());
         }
 
-        // 🔽 primaryGroupToken
-        if let Some(primary_id) = self.primary_group_id {
-            if let Some(group) = service.find_group_by_rid(primary_id).await? {
-                let token_sid = group.get_primary_group_token();
-                entry.insert("primaryGroupToken".to_string(), vec![token_sid.to_string()]);
-            }
+        // meta — кастомные атрибуты; многозначные — по строке на значение
+        for (k, v) in &self.meta {
+            entry.insert(k.clone(), crate::models::schema::values(v));
         }
 
-        // 🔽 tokenGroups — все группы, в которых состоит пользователь
-        match service.get_token_groups(self.id).await {
-            Ok(sids) => {
-                let tokens: Vec<String> = sids.into_iter().map(|sid| sid.to_string()).collect();
-                if !tokens.is_empty() {
-                    entry.insert("tokenGroups".to_string(), tokens);
-                }
-            }
-            Err(_) => {}
+        entry
+    }
+}
+
+impl LdapEntry for User {
+    fn dn(&self, ctx: &LdapContext<'_>) -> String {
+        ctx.child_dn(self.display_name.as_deref().unwrap_or(&self.username), self.organizational_unit)
+    }
+
+    fn object_classes(&self) -> &'static [&'static str] {
+        &["top", "person", "organizationalPerson", "user"]
+    }
+
+    fn object_guid(&self) -> Uuid {
+        self.id
+    }
+
+    /// Хранимые атрибуты, memberOf и фотографии; конструируемые tokenGroups и primaryGroupToken
+    /// добавляет сервер LDAP по явному запросу (`models::constructed`)
+    async fn attributes(&self, ctx: &LdapContext<'_>) -> Result<LdapAttributes, DirectoryError> {
+        let mut entry = self.stored_attributes();
+
+        // 🔽 memberOf
+        let groups = ctx.service.find_groups_by_member(self.id).await?;
+        let member_of: Vec<String> = groups.iter().map(|group| group.dn(ctx)).collect();
+        if !member_of.is_empty() {
+            entry.insert("memberOf", member_of);
         }
 
-        // meta — кастомные атрибуты
-        for (k, v) in &self.meta {
-            entry.insert(k.clone(), vec![v.clone()]);
+        // 🔽 thumbnailPhoto / jpegPhoto — двоичные
+        if let Some(photo) = ctx.service.get_user_photo(self.id).await? {
+            if photo.is_jpeg() {
+                entry.insert_binary("jpegPhoto", vec![photo.data.clone()]);
+            }
+            entry.insert_binary("thumbnailPhoto", vec![photo.data]);
         }
 
         Ok(entry)
@@ -148,4 +334,10 @@ impl User {
 /// Форматирует время в LDAP Generalized Time (YYYYMMDDHHMMSS.0Z)
 fn format_ldap_time(dt: &chrono::DateTime<Utc>) -> String {
     dt.format("%Y%m%d%H%M%S.0Z").to_string()
-}
\ No newline at end of file
+}
+
+/// Время как FILETIME — интервалы по 100 нс с 1601-01-01 UTC
+fn format_filetime(dt: &chrono::DateTime<Utc>) -> String {
+    const UNIX_EPOCH_AS_FILETIME: i64 = 116_444_736_000_000_000;
+    (UNIX_EPOCH_AS_FILETIME + dt.timestamp() * 10_000_000 + i64::from(dt.timestamp_subsec_nanos() / 100)).to_string()
+}
@@ -3,6 +3,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::models::GroupScope;
+
 /// GUID well-known объектов из Active Directory
 pub mod guid {
     pub const USERS_CONTAINER: &str = "AA312825768811D1ADED00C04FD8D5CD";
@@ -12,6 +14,116 @@ pub mod guid {
     pub const FOREIGN_SECURITY_PRINCIPALS_CONTAINER: &str = "E48D0154BCC811D19D7A00C04FD8D5CD";
 }
 
+/// Канонические RID well-known участников (MS-DTYP 2.4.2.4)
+pub mod rid {
+    pub const ADMINISTRATOR: u32 = 500;
+    pub const GUEST: u32 = 501;
+    pub const KRBTGT: u32 = 502;
+    pub const DOMAIN_ADMINS: u32 = 512;
+    pub const DOMAIN_USERS: u32 = 513;
+    pub const DOMAIN_GUESTS: u32 = 514;
+    pub const DOMAIN_COMPUTERS: u32 = 515;
+    pub const DOMAIN_CONTROLLERS: u32 = 516;
+    pub const CERT_PUBLISHERS: u32 = 517;
+    pub const SCHEMA_ADMINS: u32 = 518;
+    pub const ENTERPRISE_ADMINS: u32 = 519;
+    pub const GROUP_POLICY_CREATOR_OWNERS: u32 = 520;
+    pub const READ_ONLY_DOMAIN_CONTROLLERS: u32 = 521;
+
+    // BUILTIN: S-1-5-32-rid
+    pub const BUILTIN_ADMINISTRATORS: u32 = 544;
+    pub const BUILTIN_USERS: u32 = 545;
+    pub const BUILTIN_GUESTS: u32 = 546;
+    pub const ACCOUNT_OPERATORS: u32 = 548;
+    pub const SERVER_OPERATORS: u32 = 549;
+    pub const PRINT_OPERATORS: u32 = 550;
+    pub const BACKUP_OPERATORS: u32 = 551;
+    pub const REPLICATOR: u32 = 552;
+    pub const REMOTE_DESKTOP_USERS: u32 = 555;
+}
+
+/// Well-known SID вне домена
+pub mod sids {
+    use crate::models::SecurityIdentifier;
+
+    /// Everyone: S-1-1-0
+    pub fn everyone() -> SecurityIdentifier {
+        SecurityIdentifier::new_from_parts([0, 0, 0, 0, 0, 1], vec![0])
+    }
+
+    /// Principal Self: S-1-5-10 — объект, к которому проверяется доступ, если это сам пользователь
+    pub fn principal_self() -> SecurityIdentifier {
+        SecurityIdentifier::new_nt_authority(10)
+    }
+
+    /// Authenticated Users: S-1-5-11
+    pub fn authenticated_users() -> SecurityIdentifier {
+        SecurityIdentifier::new_nt_authority(11)
+    }
+}
+
+/// Атрибут AD системных объектов (в `meta` пользователя или группы); такие объекты нельзя удалить
+pub const CRITICAL_SYSTEM_OBJECT: &str = "isCriticalSystemObject";
+
+/// Помечен ли объект как системный
+pub fn is_critical(meta: &HashMap<String, String>) -> bool {
+    meta.get(CRITICAL_SYSTEM_OBJECT).is_some_and(|v| v.eq_ignore_ascii_case("TRUE"))
+}
+
+/// Well-known группа, создаваемая при инициализации домена
+pub struct WellKnownGroup {
+    pub rid: u32,
+    /// Имя и sAMAccountName
+    pub name: &'static str,
+    /// `true` — SID вида S-1-5-32-rid, иначе SID домена + rid
+    pub builtin: bool,
+    pub scope: GroupScope,
+    pub description: &'static str,
+}
+
+const fn domain_group(rid: u32, name: &'static str, scope: GroupScope, description: &'static str) -> WellKnownGroup {
+    WellKnownGroup { rid, name, builtin: false, scope, description }
+}
+
+const fn builtin_group(rid: u32, name: &'static str, description: &'static str) -> WellKnownGroup {
+    WellKnownGroup { rid, name, builtin: true, scope: GroupScope::DomainLocal, description }
+}
+
+/// Группы домена и BUILTIN, как их создаёт dcpromo
+pub const GROUPS: &[WellKnownGroup] = &[
+    domain_group(rid::DOMAIN_ADMINS, "Domain Admins", GroupScope::Global, "Designated administrators of the domain"),
+    domain_group(rid::DOMAIN_USERS, "Domain Users", GroupScope::Global, "All domain users"),
+    domain_group(rid::DOMAIN_GUESTS, "Domain Guests", GroupScope::Global, "All domain guests"),
+    domain_group(rid::DOMAIN_COMPUTERS, "Domain Computers", GroupScope::Global, "All workstations and servers joined to the domain"),
+    domain_group(rid::DOMAIN_CONTROLLERS, "Domain Controllers", GroupScope::Global, "All domain controllers in the domain"),
+    domain_group(rid::CERT_PUBLISHERS, "Cert Publishers", GroupScope::DomainLocal, "Members of this group are permitted to publish certificates to the directory"),
+    domain_group(rid::SCHEMA_ADMINS, "Schema Admins", GroupScope::Universal, "Designated administrators of the schema"),
+    domain_group(rid::ENTERPRISE_ADMINS, "Enterprise Admins", GroupScope::Universal, "Designated administrators of the enterprise"),
+    domain_group(rid::GROUP_POLICY_CREATOR_OWNERS, "Group Policy Creator Owners", GroupScope::Global, "Members in this group can modify group policy for the domain"),
+    domain_group(rid::READ_ONLY_DOMAIN_CONTROLLERS, "Read-only Domain Controllers", GroupScope::Global, "Members of this group are Read-Only Domain Controllers in the domain"),
+    builtin_group(rid::BUILTIN_ADMINISTRATORS, "Administrators", "Administrators have complete and unrestricted access to the domain"),
+    builtin_group(rid::BUILTIN_USERS, "Users", "Users are prevented from making accidental or intentional system-wide changes"),
+    builtin_group(rid::BUILTIN_GUESTS, "Guests", "Guests have the same access as members of the Users group by default"),
+    builtin_group(rid::ACCOUNT_OPERATORS, "Account Operators", "Members can administer domain user and group accounts"),
+    builtin_group(rid::SERVER_OPERATORS, "Server Operators", "Members can administer domain servers"),
+    builtin_group(rid::PRINT_OPERATORS, "Print Operators", "Members can administer printers installed on domain controllers"),
+    builtin_group(rid::BACKUP_OPERATORS, "Backup Operators", "Backup Operators can override security restrictions for the sole purpose of backing up or restoring files"),
+    builtin_group(rid::REPLICATOR, "Replicator", "Supports file replication in a domain"),
+    builtin_group(rid::REMOTE_DESKTOP_USERS, "Remote Desktop Users", "Members in this group are granted the right to logon remotely"),
+];
+
+/// Группы, в которые входит встроенный администратор
+pub const ADMINISTRATOR_GROUPS: &[&str] = &[
+    "Domain Admins",
+    "Enterprise Admins",
+    "Schema Admins",
+    "Group Policy Creator Owners",
+    "Administrators",
+];
+
+/// Группы, в которые входит встроенный гость
+pub const GUEST_GROUPS: &[&str] = &["Domain Guests", "Guests"];
+
 /// Well-Known объекты домена
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct WellKnownContainers {
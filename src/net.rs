@@ -0,0 +1,59 @@
+// src/net.rs
+
+//! Сокеты серверов. Каждый адрес из `address` — отдельный сокет; IPv6-сокет принимает только
+//! IPv6 (`IPV6_V6ONLY`), поэтому `0.0.0.0:8080` и `[::]:8080` слушают рядом, не мешая друг другу.
+//! Сокет от systemd может быть двухстековым: его IPv4-клиенты приходят как `::ffff:10.0.0.5`,
+//! и `client_ip` возвращает их как IPv4
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::{TcpListener, UdpSocket};
+
+/// Очередь соединений, ещё не принятых сервером
+const BACKLOG: i32 = 1024;
+
+/// TCP-сокет на `addr` (`host:port`; имя узла разрешается, берётся первый адрес)
+pub async fn bind_tcp(addr: &str) -> io::Result<TcpListener> {
+    let socket_addr = resolve(addr).await?;
+    let bind = || -> io::Result<TcpListener> {
+        let socket = Socket::new(Domain::for_address(socket_addr), Type::STREAM, Some(Protocol::TCP))?;
+        if socket_addr.is_ipv6() {
+            socket.set_only_v6(true)?;
+        }
+        socket.set_reuse_address(true)?;
+        socket.set_nonblocking(true)?;
+        socket.bind(&socket_addr.into())?;
+        socket.listen(BACKLOG)?;
+        TcpListener::from_std(socket.into())
+    };
+    bind().map_err(|e| io::Error::new(e.kind(), format!("{}: {}", addr, e)))
+}
+
+/// UDP-сокет на `addr`, как `bind_tcp`
+pub async fn bind_udp(addr: &str) -> io::Result<UdpSocket> {
+    let socket_addr = resolve(addr).await?;
+    let bind = || -> io::Result<UdpSocket> {
+        let socket = Socket::new(Domain::for_address(socket_addr), Type::DGRAM, Some(Protocol::UDP))?;
+        if socket_addr.is_ipv6() {
+            socket.set_only_v6(true)?;
+        }
+        socket.set_nonblocking(true)?;
+        socket.bind(&socket_addr.into())?;
+        UdpSocket::from_std(socket.into())
+    };
+    bind().map_err(|e| io::Error::new(e.kind(), format!("{}: {}", addr, e)))
+}
+
+/// Адрес клиента для аудита, лимитов и списков разрешённых адресов: IPv4 через
+/// двухстековый сокет (`::ffff:10.0.0.5`) — как `10.0.0.5`
+pub fn client_ip(peer: SocketAddr) -> IpAddr {
+    peer.ip().to_canonical()
+}
+
+async fn resolve(addr: &str) -> io::Result<SocketAddr> {
+    tokio::net::lookup_host(addr).await?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{} resolves to no address", addr)))
+}
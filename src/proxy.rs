@@ -0,0 +1,161 @@
+// src/proxy.rs
+
+//! Адрес клиента за обратным прокси. Прокси из `trusted` (адреса и сети CIDR) передают адрес
+//! клиента заголовком X-Forwarded-For или PROXY protocol v1/v2 в начале соединения. Тем, кто не
+//! в списке, не верим: их X-Forwarded-For игнорируется, а заголовок PROXY не разбирается, иначе
+//! любой клиент подставил бы чужой адрес в аудит и обошёл лимиты входа
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use axum::http::HeaderMap;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::config::ProxyConfig;
+use crate::models::site::{cidr_contains, parse_cidr};
+
+/// Заголовок с цепочкой адресов: клиент, затем прокси, через которые прошёл запрос
+pub const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Начало заголовка PROXY protocol v2
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// Длина строки PROXY protocol v1 вместе с CRLF не больше
+const V1_MAX_LEN: usize = 107;
+
+/// Доверенные прокси из `web_server.proxy` или `ldap_server.proxy`
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    networks: Vec<(IpAddr, u8)>,
+    proxy_protocol: bool,
+}
+
+impl TrustedProxies {
+    /// `10.0.0.10` — один адрес, `10.0.0.0/24` и `fd00::/8` — сети
+    pub fn new(config: &ProxyConfig) -> Result<Self, String> {
+        let networks = config.trusted.iter()
+            .map(|entry| match entry.parse::<IpAddr>().map(|ip| ip.to_canonical()) {
+                Ok(ip) => Ok((ip, if ip.is_ipv4() { 32 } else { 128 })),
+                Err(_) => parse_cidr(entry).map_err(|e| format!("invalid trusted proxy '{}': {}", entry, e)),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { networks, proxy_protocol: config.proxy_protocol })
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|(network, prefix_len)| cidr_contains(*network, *prefix_len, ip))
+    }
+
+    pub fn proxy_protocol(&self) -> bool {
+        self.proxy_protocol
+    }
+
+    /// Соединение от `peer` начинается заголовком PROXY
+    pub fn expects_proxy_header(&self, peer: IpAddr) -> bool {
+        self.proxy_protocol && self.is_trusted(peer)
+    }
+
+    /// Клиент по X-Forwarded-For: цепочка читается справа, пока адреса — доверенные прокси.
+    /// От недоверенного `peer` заголовок не принимается
+    pub fn forwarded_client(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let hops: Vec<&str> = headers.get_all(FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+        let mut client = peer.to_canonical();
+        for hop in hops.into_iter().rev() {
+            if !self.is_trusted(client) {
+                break;
+            }
+            match parse_hop(hop) {
+                Some(ip) => client = ip,
+                None => break,
+            }
+        }
+        client
+    }
+}
+
+/// Адрес в X-Forwarded-For: `10.0.0.5`, `2001:db8::1`, иногда с портом (`10.0.0.5:4711`, `[2001:db8::1]:4711`)
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    hop.parse::<IpAddr>()
+        .or_else(|_| hop.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .ok()
+        .map(|ip| ip.to_canonical())
+}
+
+/// Прочитать заголовок PROXY protocol v1 или v2 и ничего сверх него. `None` — прокси не передал
+/// адрес клиента (`PROXY UNKNOWN`, команда LOCAL проверок состояния): клиент — сам прокси
+pub async fn read_header<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Option<SocketAddr>> {
+    let mut prefix = [0u8; 12];
+    stream.read_exact(&mut prefix[..6]).await?;
+    if &prefix[..6] == b"PROXY " {
+        let mut line = prefix[..6].to_vec();
+        while !line.ends_with(b"\r\n") {
+            if line.len() >= V1_MAX_LEN {
+                return Err(invalid("PROXY v1 header is too long"));
+            }
+            line.push(stream.read_u8().await?);
+        }
+        return parse_v1(&line[..line.len() - 2]);
+    }
+
+    stream.read_exact(&mut prefix[6..]).await?;
+    if prefix != V2_SIGNATURE {
+        return Err(invalid("connection from a trusted proxy does not start with a PROXY header"));
+    }
+    let mut head = [0u8; 4];
+    stream.read_exact(&mut head).await?;
+    let mut addresses = vec![0u8; usize::from(u16::from_be_bytes([head[2], head[3]]))];
+    stream.read_exact(&mut addresses).await?;
+    parse_v2(head[0], head[1], &addresses)
+}
+
+/// `PROXY TCP4 <клиент> <сервер> <порт клиента> <порт сервера>`
+fn parse_v1(line: &[u8]) -> io::Result<Option<SocketAddr>> {
+    let line = std::str::from_utf8(line).map_err(|_| invalid("PROXY v1 header is not ASCII"))?;
+    let fields: Vec<&str> = line.split(' ').collect();
+    match fields.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => Ok(None),
+        ["PROXY", protocol @ ("TCP4" | "TCP6"), source, _, port, _] => {
+            let ip: IpAddr = source.parse().map_err(|_| invalid("invalid source address in PROXY v1 header"))?;
+            let port: u16 = port.parse().map_err(|_| invalid("invalid source port in PROXY v1 header"))?;
+            if ip.is_ipv4() != (*protocol == "TCP4") {
+                return Err(invalid("PROXY v1 address does not match the protocol"));
+            }
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        _ => Err(invalid("malformed PROXY v1 header")),
+    }
+}
+
+/// Версия и команда, семейство адресов и блок адресов заголовка v2; TLV после адресов пропускаются
+fn parse_v2(version_command: u8, family: u8, addresses: &[u8]) -> io::Result<Option<SocketAddr>> {
+    if version_command >> 4 != 2 {
+        return Err(invalid("unsupported PROXY protocol version"));
+    }
+    match version_command & 0x0f {
+        0x0 => return Ok(None),
+        0x1 => {}
+        _ => return Err(invalid("unsupported PROXY v2 command")),
+    }
+    let source = match family >> 4 {
+        0x1 if addresses.len() >= 12 => {
+            let ip = Ipv4Addr::new(addresses[0], addresses[1], addresses[2], addresses[3]);
+            SocketAddr::new(ip.into(), u16::from_be_bytes([addresses[8], addresses[9]]))
+        }
+        0x2 if addresses.len() >= 36 => {
+            let octets: [u8; 16] = addresses[..16].try_into().expect("length checked");
+            SocketAddr::new(Ipv6Addr::from(octets).into(), u16::from_be_bytes([addresses[32], addresses[33]]))
+        }
+        0x1 | 0x2 => return Err(invalid("truncated PROXY v2 address block")),
+        // AF_UNSPEC и AF_UNIX: адреса клиента нет
+        _ => return Ok(None),
+    };
+    Ok(Some(source))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
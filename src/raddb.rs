@@ -6,10 +6,12 @@ use aes_gcm::{
 };
 use bincode;
 use std::collections::HashMap;
-use std::fs::OpenOptions;
+use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
 
 // 🔁 Добавлено: RngCore для fill_bytes
 use rand::{rngs::OsRng, RngCore};
@@ -22,6 +24,10 @@ pub enum RadDbError {
     Decryption(String),
     Encryption(String),
     KeyInvalid,
+    /// База уже открыта на запись другим процессом (файл блокировки занят)
+    Locked(PathBuf),
+    /// База открыта только для чтения
+    ReadOnly,
 }
 
 impl From<std::io::Error> for RadDbError {
@@ -38,6 +44,12 @@ impl std::fmt::Display for RadDbError {
             RadDbError::Decryption(e) => write!(f, "Decryption error: {}", e),
             RadDbError::Encryption(e) => write!(f, "Encryption error: {}", e),
             RadDbError::KeyInvalid => write!(f, "Invalid key length"),
+            RadDbError::Locked(path) => write!(
+                f,
+                "Database {} is already open by another process; stop it, use --read-only or the remote CLI (cli --server)",
+                path.display()
+            ),
+            RadDbError::ReadOnly => write!(f, "Database is opened read-only"),
         }
     }
 }
@@ -52,21 +64,61 @@ pub struct RadDB {
     path: PathBuf,
     cipher: Aes256Gcm,
     cache: RwLock<HashMap<String, Vec<u8>>>,
+    /// Открытые пакеты (`begin_batch`): пока они есть, изменения не записываются на диск
+    batch_depth: Arc<AtomicUsize>,
+    /// Файл блокировки `<база>.lock` с исключительной блокировкой; `None` — база открыта только для чтения
+    lock: Option<File>,
+    /// Последняя запись на диск; при открытии — время изменения файла
+    last_flush: Mutex<Option<SystemTime>>,
 }
 
 impl RadDB {
-    /// Открыть базу по пути с мастер-ключом
+    /// Открыть базу на запись по пути с мастер-ключом. Каждый процесс держит всю базу в памяти
+    /// и записывает её целиком, поэтому второй процесс на запись её не откроет: `RadDbError::Locked`
     pub fn open<P: AsRef<Path>>(path: P, key: &MasterKey) -> Result<Self, RadDbError> {
+        let path = path.as_ref();
+        let lock = OpenOptions::new().write(true).create(true).truncate(false).open(lock_path(path))?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(std::fs::TryLockError::WouldBlock) => return Err(RadDbError::Locked(path.to_path_buf())),
+            Err(std::fs::TryLockError::Error(e)) => return Err(RadDbError::Io(e)),
+        }
+        Self::open_with_lock(path, key, Some(lock))
+    }
+
+    /// Открыть базу только для чтения, без блокировки: снимок на момент открытия, даже если
+    /// базу держит на запись другой процесс. Изменения отклоняются с `RadDbError::ReadOnly`
+    pub fn open_read_only<P: AsRef<Path>>(path: P, key: &MasterKey) -> Result<Self, RadDbError> {
+        Self::open_with_lock(path.as_ref(), key, None)
+    }
+
+    fn open_with_lock(path: &Path, key: &MasterKey, lock: Option<File>) -> Result<Self, RadDbError> {
         let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
         let db = Self {
-            path: path.as_ref().to_path_buf(),
+            path: path.to_path_buf(),
             cipher,
             cache: RwLock::new(HashMap::new()),
+            batch_depth: Arc::new(AtomicUsize::new(0)),
+            lock,
+            last_flush: Mutex::new(None),
         };
         db.load()?;
         Ok(db)
     }
 
+    /// Открыта ли база только для чтения
+    pub fn is_read_only(&self) -> bool {
+        self.lock.is_none()
+    }
+
+    /// Изменения базы, открытой только для чтения, отклоняются до изменения кэша
+    fn check_writable(&self) -> Result<(), RadDbError> {
+        if self.is_read_only() {
+            return Err(RadDbError::ReadOnly);
+        }
+        Ok(())
+    }
+
     #[allow(dead_code)]
     /// Создать новый мастер-ключ (надо сохранить!)
     pub fn generate_key() -> MasterKey {
@@ -83,6 +135,7 @@ impl RadDB {
         }
 
         let mut file = OpenOptions::new().read(true).open(&self.path)?;
+        *self.last_flush.lock().unwrap() = file.metadata().and_then(|metadata| metadata.modified()).ok();
         let mut encrypted = Vec::new();
         file.read_to_end(&mut encrypted)?;
 
@@ -119,6 +172,8 @@ impl RadDB {
 
     /// Сохранить данные на диск
     pub fn flush(&self) -> Result<(), RadDbError> {
+        self.check_writable()?;
+        let _span = tracing::info_span!("raddb.flush").entered();
         let cache = self.cache.read().map_err(|_| RadDbError::Io(std::io::Error::new(std::io::ErrorKind::Other, "RwLock poisoned")))?;
         let plaintext = bincode::serialize(&*cache)
             .map_err(|e| RadDbError::Serialization(e.to_string()))?;
@@ -146,6 +201,7 @@ impl RadDB {
         file.write_all(&nonce_bytes)?;
         file.write_all(&ciphertext)?;
         file.sync_all()?;
+        *self.last_flush.lock().unwrap() = Some(SystemTime::now());
 
         Ok(())
     }
@@ -157,7 +213,6 @@ impl RadDB {
     }
 
     /// Проверить наличие ключа
-    #[allow(dead_code)]
     pub fn contains_key(&self, key: &str) -> bool {
         match self.cache.read() {
             Ok(cache) => cache.contains_key(key),
@@ -167,14 +222,88 @@ impl RadDB {
 
     /// Установить значение
     pub fn set(&self, key: String, value: Vec<u8>) -> Result<(), RadDbError> {
+        self.check_writable()?;
         let mut cache = self.cache.write().map_err(|_| RadDbError::Io(std::io::Error::new(std::io::ErrorKind::Other, "RwLock poisoned")))?;
         cache.insert(key, value);
-        self.flush()?;
-        Ok(())
+        drop(cache); // flush() берёт блокировку на чтение
+        self.persist()
     }
 
-    /// Удалить ключ
+    /// Установить несколько значений с одной записью на диск
+    pub fn set_many(&self, entries: Vec<(String, Vec<u8>)>) -> Result<(), RadDbError> {
+        self.check_writable()?;
+        let mut cache = self.cache.write().map_err(|_| RadDbError::Io(std::io::Error::other("RwLock poisoned")))?;
+        cache.extend(entries);
+        drop(cache);
+        self.persist()
+    }
+
+    /// Число ключей с префиксом, без копирования значений
+    pub fn count_prefix(&self, prefix: &str) -> usize {
+        match self.cache.read() {
+            Ok(cache) => cache.keys().filter(|key| key.starts_with(prefix)).count(),
+            Err(_) => 0,
+        }
+    }
+
+    /// Размер файла базы, байт; 0 — файла ещё нет
+    pub fn file_size(&self) -> u64 {
+        std::fs::metadata(&self.path).map(|metadata| metadata.len()).unwrap_or(0)
+    }
+
+    /// Последняя запись базы на диск
+    pub fn last_flush(&self) -> Option<SystemTime> {
+        *self.last_flush.lock().unwrap()
+    }
+
+    /// Все записи базы
+    pub fn entries(&self) -> Vec<(String, Vec<u8>)> {
+        match self.cache.read() {
+            Ok(cache) => cache.iter().map(|(key, value)| (key.clone(), value.clone())).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Заменить содержимое базы записями `entries` с одной записью на диск;
+    /// ключи, для которых `keep` возвращает true, остаются как есть
+    pub fn replace(&self, entries: Vec<(String, Vec<u8>)>, keep: impl Fn(&str) -> bool) -> Result<(), RadDbError> {
+        self.check_writable()?;
+        let mut cache = self.cache.write().map_err(|_| RadDbError::Io(std::io::Error::other("RwLock poisoned")))?;
+        cache.retain(|key, _| keep(key));
+        cache.extend(entries.into_iter().filter(|(key, _)| !keep(key)));
+        drop(cache);
+        self.persist()
+    }
+
+    /// Начать пакет: изменения копятся в памяти и записываются на диск одной записью в `end_batch`.
+    /// Пакеты вкладываются; пока открыт хотя бы один, на диск не пишут и другие изменения.
+    /// Брошенный пакет (например, запрос прервался на `await`) закрывается при удалении `Batch`
+    pub fn begin_batch(&self) -> Batch {
+        self.batch_depth.fetch_add(1, Ordering::SeqCst);
+        Batch { depth: Some(Arc::clone(&self.batch_depth)) }
+    }
+
+    /// Закрыть пакет; последний закрытый записывает базу на диск
+    pub fn end_batch(&self, mut batch: Batch) -> Result<(), RadDbError> {
+        match batch.close() {
+            Some(1) => self.flush(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Записать изменение на диск, если не открыт пакет
+    fn persist(&self) -> Result<(), RadDbError> {
+        if self.batch_depth.load(Ordering::SeqCst) > 0 {
+            return Ok(());
+        }
+        self.flush()
+    }
+
+    /// Удалить ключ; в базе только для чтения ключ остаётся
     pub fn remove(&self, key: &str) -> bool {
+        if self.is_read_only() {
+            return false;
+        }
         let mut cache = self.cache.write().unwrap();
         cache.remove(key).is_some()
     }
@@ -187,9 +316,38 @@ impl RadDB {
     }
 }
 
-// Автоматическое сохранение при выходе
+/// Открытый пакет изменений (`RadDB::begin_batch`). Закрывается в `end_batch`, а если до него
+/// не дошло — при удалении: тогда на диск накопленное запишет следующее изменение
+#[must_use = "пакет закрывается в `end_batch`"]
+pub struct Batch {
+    depth: Option<Arc<AtomicUsize>>,
+}
+
+impl Batch {
+    /// Закрыть пакет; число открытых пакетов до закрытия, `None` — уже закрыт
+    fn close(&mut self) -> Option<usize> {
+        self.depth.take().map(|depth| depth.fetch_sub(1, Ordering::SeqCst))
+    }
+}
+
+impl Drop for Batch {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Файл блокировки рядом с базой: `raddb.bin` → `raddb.bin.lock`
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+// Автоматическое сохранение при выходе; блокировка снимается после записи, когда закрывается её файл
 impl Drop for RadDB {
     fn drop(&mut self) {
-        let _ = self.flush();
+        if !self.is_read_only() {
+            let _ = self.flush();
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,262 @@
+// src/radius/eap.rs
+
+//! EAP-TTLS (RFC 5281) с внутренней аутентификацией PAP.
+//! TLS-сессия живёт в памяти: записи TLS переносятся внутри EAP-Message.
+
+use std::io::Read;
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::RadiusError;
+
+pub const EAP_REQUEST: u8 = 1;
+pub const EAP_RESPONSE: u8 = 2;
+pub const EAP_SUCCESS: u8 = 3;
+pub const EAP_FAILURE: u8 = 4;
+
+pub const EAP_TYPE_IDENTITY: u8 = 1;
+pub const EAP_TYPE_NAK: u8 = 3;
+pub const EAP_TYPE_TTLS: u8 = 21;
+
+const TTLS_FLAG_LENGTH: u8 = 0x80;
+const TTLS_FLAG_MORE: u8 = 0x40;
+const TTLS_FLAG_START: u8 = 0x20;
+
+/// Размер фрагмента TLS внутри одного EAP-Request
+const FRAGMENT_SIZE: usize = 1000;
+
+/// Максимальный размер собранного сообщения TLS от клиента
+const MAX_TLS_MESSAGE: usize = 64 * 1024;
+
+/// Коды Diameter AVP внутри туннеля
+const AVP_USER_NAME: u32 = 1;
+const AVP_USER_PASSWORD: u32 = 2;
+const AVP_FLAG_VENDOR: u8 = 0x80;
+
+#[derive(Debug, Clone)]
+pub struct EapPacket {
+    pub code: u8,
+    pub identifier: u8,
+    pub eap_type: Option<u8>,
+    pub data: Vec<u8>,
+}
+
+impl EapPacket {
+    pub fn parse(data: &[u8]) -> Result<Self, RadiusError> {
+        if data.len() < 4 {
+            return Err(RadiusError::Decode("EAP packet too short".to_string()));
+        }
+        let length = u16::from_be_bytes([data[2], data[3]]) as usize;
+        if length < 4 || length > data.len() {
+            return Err(RadiusError::Decode("Invalid EAP length".to_string()));
+        }
+        Ok(Self {
+            code: data[0],
+            identifier: data[1],
+            eap_type: data.get(4).copied().filter(|_| length > 4),
+            data: data.get(5..length).map(<[u8]>::to_vec).unwrap_or_default(),
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![self.code, self.identifier, 0, 0];
+        if let Some(eap_type) = self.eap_type {
+            out.push(eap_type);
+            out.extend_from_slice(&self.data);
+        }
+        let len = out.len() as u16;
+        out[2..4].copy_from_slice(&len.to_be_bytes());
+        out
+    }
+
+    pub fn success(identifier: u8) -> Self {
+        Self { code: EAP_SUCCESS, identifier, eap_type: None, data: vec![] }
+    }
+
+    pub fn failure(identifier: u8) -> Self {
+        Self { code: EAP_FAILURE, identifier, eap_type: None, data: vec![] }
+    }
+}
+
+/// Результат очередного шага EAP-TTLS
+pub enum TtlsStep {
+    /// Отправить клиенту EAP-Request в Access-Challenge
+    Challenge(EapPacket),
+    /// Из туннеля получены учётные данные
+    Credentials { username: String, password: String },
+}
+
+/// Состояние одного диалога EAP-TTLS
+pub struct TtlsSession {
+    tls: rustls::ServerConnection,
+    identifier: u8,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    /// Длина TLS-сообщения, которое сейчас отправляется фрагментами
+    outgoing_total: usize,
+    pub created: Instant,
+}
+
+impl TtlsSession {
+    /// Начать сессию в ответ на EAP-Response/Identity; возвращает EAP-Request/TTLS Start
+    pub fn start(config: Arc<rustls::ServerConfig>, identity: &EapPacket) -> Result<(Self, EapPacket), RadiusError> {
+        let tls = rustls::ServerConnection::new(config).map_err(|e| RadiusError::Tls(e.to_string()))?;
+        let mut session = Self {
+            tls,
+            identifier: identity.identifier,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+            outgoing_total: 0,
+            created: Instant::now(),
+        };
+        let start = session.request(TTLS_FLAG_START, &[]);
+        Ok((session, start))
+    }
+
+    pub fn identifier(&self) -> u8 {
+        self.identifier
+    }
+
+    pub fn step(&mut self, response: &EapPacket) -> Result<TtlsStep, RadiusError> {
+        if response.code != EAP_RESPONSE || response.eap_type != Some(EAP_TYPE_TTLS) {
+            return Err(RadiusError::Eap("Client declined EAP-TTLS".to_string()));
+        }
+        let (&flags, mut payload) = response.data.split_first()
+            .ok_or_else(|| RadiusError::Decode("Empty EAP-TTLS response".to_string()))?;
+        if flags & TTLS_FLAG_LENGTH != 0 {
+            payload = payload.get(4..).ok_or_else(|| RadiusError::Decode("Truncated TTLS length".to_string()))?;
+        }
+
+        if self.incoming.len() + payload.len() > MAX_TLS_MESSAGE {
+            return Err(RadiusError::Decode("TLS message too large".to_string()));
+        }
+        self.incoming.extend_from_slice(payload);
+
+        // Клиент присылает сообщение фрагментами — подтверждаем каждый
+        if flags & TTLS_FLAG_MORE != 0 {
+            return Ok(TtlsStep::Challenge(self.request(0, &[])));
+        }
+
+        if !self.incoming.is_empty() {
+            let input = std::mem::take(&mut self.incoming);
+            let mut cursor = input.as_slice();
+            while !cursor.is_empty() {
+                if self.tls.read_tls(&mut cursor)? == 0 {
+                    break;
+                }
+                self.tls.process_new_packets().map_err(|e| RadiusError::Tls(e.to_string()))?;
+            }
+
+            if let Some(credentials) = self.read_credentials()? {
+                return Ok(credentials);
+            }
+        }
+
+        while self.tls.wants_write() {
+            self.tls.write_tls(&mut self.outgoing)?;
+        }
+        Ok(TtlsStep::Challenge(self.next_fragment()))
+    }
+
+    /// Ключевой материал MSK для MS-MPPE-ключей (RFC 5281, 8)
+    pub fn msk(&self) -> Result<[u8; 64], RadiusError> {
+        let mut msk = [0u8; 64];
+        self.tls
+            .export_keying_material(&mut msk, b"ttls keying material", None)
+            .map_err(|e| RadiusError::Tls(e.to_string()))?;
+        Ok(msk)
+    }
+
+    fn read_credentials(&mut self) -> Result<Option<TtlsStep>, RadiusError> {
+        let mut plaintext = Vec::new();
+        let mut buf = [0u8; 1024];
+        loop {
+            match self.tls.reader().read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => plaintext.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if plaintext.is_empty() {
+            return Ok(None);
+        }
+
+        let avps = parse_avps(&plaintext)?;
+        let find = |code: u32| {
+            avps.iter().find(|(c, _)| *c == code).map(|(_, v)| {
+                let trimmed: &[u8] = match v.iter().rposition(|b| *b != 0) {
+                    Some(end) => &v[..=end],
+                    None => &[],
+                };
+                String::from_utf8_lossy(trimmed).into_owned()
+            })
+        };
+        match (find(AVP_USER_NAME), find(AVP_USER_PASSWORD)) {
+            (Some(username), Some(password)) => Ok(Some(TtlsStep::Credentials { username, password })),
+            _ => Err(RadiusError::Eap("Tunnel did not carry PAP credentials".to_string())),
+        }
+    }
+
+    fn next_fragment(&mut self) -> EapPacket {
+        if self.outgoing.is_empty() {
+            self.outgoing_total = 0;
+            return self.request(0, &[]);
+        }
+        let first = self.outgoing_total == 0;
+        if first {
+            self.outgoing_total = self.outgoing.len();
+        }
+
+        let take = self.outgoing.len().min(FRAGMENT_SIZE);
+        let chunk: Vec<u8> = self.outgoing.drain(..take).collect();
+        let more = !self.outgoing.is_empty();
+
+        let mut flags = 0;
+        let mut data = Vec::new();
+        if first && more {
+            flags |= TTLS_FLAG_LENGTH;
+            data.extend_from_slice(&(self.outgoing_total as u32).to_be_bytes());
+        }
+        if more {
+            flags |= TTLS_FLAG_MORE;
+        } else {
+            self.outgoing_total = 0;
+        }
+        data.extend_from_slice(&chunk);
+        self.request(flags, &data)
+    }
+
+    fn request(&mut self, flags: u8, data: &[u8]) -> EapPacket {
+        self.identifier = self.identifier.wrapping_add(1);
+        let mut payload = vec![flags];
+        payload.extend_from_slice(data);
+        EapPacket {
+            code: EAP_REQUEST,
+            identifier: self.identifier,
+            eap_type: Some(EAP_TYPE_TTLS),
+            data: payload,
+        }
+    }
+}
+
+/// Разобрать Diameter AVP (RFC 5281, 10.1)
+fn parse_avps(data: &[u8]) -> Result<Vec<(u32, Vec<u8>)>, RadiusError> {
+    let mut avps = Vec::new();
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let code = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        let flags = data[pos + 4];
+        let length = u32::from_be_bytes([0, data[pos + 5], data[pos + 6], data[pos + 7]]) as usize;
+        let header = if flags & AVP_FLAG_VENDOR != 0 { 12 } else { 8 };
+        if length < header || pos + length > data.len() {
+            return Err(RadiusError::Decode("Invalid AVP length".to_string()));
+        }
+        // Vendor-specific AVP нам не нужны
+        if flags & AVP_FLAG_VENDOR == 0 {
+            avps.push((code, data[pos + header..pos + length].to_vec()));
+        }
+        pos += length.div_ceil(4) * 4;
+    }
+    Ok(avps)
+}
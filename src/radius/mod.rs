@@ -0,0 +1,326 @@
+// src/radius/mod.rs
+
+//! RADIUS-сервер (RFC 2865) для Wi-Fi и VPN: PAP и EAP-TTLS/PAP,
+//! учётные данные проверяются через `DirectoryService::authenticate`.
+
+pub mod eap;
+pub mod packet;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use rand::{rngs::OsRng, RngCore};
+use tokio::net::UdpSocket;
+
+use crate::config::{RadiusClientConfig, RadiusGroupAttribute, RadiusServerConfig};
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::models::User;
+use eap::{EapPacket, TtlsSession, TtlsStep, EAP_RESPONSE, EAP_TYPE_IDENTITY};
+use packet::*;
+
+/// Адрес по умолчанию (RFC 2865)
+const DEFAULT_ADDRESS: &str = "0.0.0.0:1812";
+
+/// Незавершённые EAP-диалоги старше этого срока удаляются
+const SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub enum RadiusError {
+    Io(std::io::Error),
+    Decode(String),
+    Tls(String),
+    Eap(String),
+    Config(String),
+    InvalidAuthenticator,
+    Directory(DirectoryError),
+}
+
+impl From<std::io::Error> for RadiusError {
+    fn from(e: std::io::Error) -> Self {
+        RadiusError::Io(e)
+    }
+}
+
+impl From<DirectoryError> for RadiusError {
+    fn from(e: DirectoryError) -> Self {
+        RadiusError::Directory(e)
+    }
+}
+
+impl std::fmt::Display for RadiusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RadiusError::Io(e) => write!(f, "IO error: {}", e),
+            RadiusError::Decode(e) => write!(f, "Decode error: {}", e),
+            RadiusError::Tls(e) => write!(f, "TLS error: {}", e),
+            RadiusError::Eap(e) => write!(f, "EAP error: {}", e),
+            RadiusError::Config(e) => write!(f, "Config error: {}", e),
+            RadiusError::InvalidAuthenticator => write!(f, "Invalid Message-Authenticator"),
+            RadiusError::Directory(e) => write!(f, "Directory error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RadiusError {}
+
+pub struct RadiusServer {
+    service: Arc<DirectoryService>,
+    /// По сокету на адрес `radius_server.address`
+    sockets: Vec<Arc<UdpSocket>>,
+    clients: HashMap<IpAddr, RadiusClientConfig>,
+    group_attributes: Vec<RadiusGroupAttribute>,
+    /// Без сертификата доступен только PAP
+    tls_config: Option<Arc<rustls::ServerConfig>>,
+    /// EAP-диалоги по значению атрибута State
+    sessions: Mutex<HashMap<Vec<u8>, TtlsSession>>,
+}
+
+impl RadiusServer {
+    pub async fn bind(service: Arc<DirectoryService>, config: &RadiusServerConfig) -> Result<Self, RadiusError> {
+        if !config.enabled {
+            return Err(RadiusError::Config("radius_server.enabled is false".to_string()));
+        }
+
+        let mut clients = HashMap::new();
+        for client in &config.clients {
+            let ip: IpAddr = client.address.parse()
+                .map_err(|_| RadiusError::Config(format!("Invalid client address {}", client.address)))?;
+            clients.insert(ip.to_canonical(), client.clone());
+        }
+
+        let tls_config = match (&config.tls.cert_file, &config.tls.key_file) {
+            (Some(cert), Some(key)) => Some(load_tls_config(cert, key)?),
+            _ => None,
+        };
+
+        let mut sockets = Vec::new();
+        for addr in config.address.iter().chain(config.address.is_empty().then_some(DEFAULT_ADDRESS)) {
+            sockets.push(Arc::new(crate::net::bind_udp(addr).await?));
+        }
+        Ok(Self {
+            service,
+            sockets,
+            clients,
+            group_attributes: config.group_attributes.clone(),
+            tls_config,
+            sessions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn run(self) -> Result<(), RadiusError> {
+        for socket in &self.sockets {
+            tracing::info!(
+                addr = %socket.local_addr()?,
+                clients = self.clients.len(),
+                eap_ttls = self.tls_config.is_some(),
+                "RADIUS слушает",
+            );
+        }
+
+        let server = Arc::new(self);
+        let receivers = server.sockets.iter().map(|socket| Arc::clone(&server).receive(Arc::clone(socket)));
+        // Сокеты работают, пока не откажет любой из них
+        futures_util::future::try_join_all(receivers).await.map(|_| ())
+    }
+
+    async fn receive(self: Arc<Self>, socket: Arc<UdpSocket>) -> Result<(), RadiusError> {
+        let mut buf = vec![0u8; 4096];
+        loop {
+            let (n, peer) = socket.recv_from(&mut buf).await?;
+            let data = buf[..n].to_vec();
+            let (server, socket) = (Arc::clone(&self), Arc::clone(&socket));
+            // Проверка пароля (bcrypt) небыстрая — обрабатываем запросы параллельно
+            tokio::spawn(async move {
+                if let Err(e) = server.handle_datagram(&socket, &data, peer).await {
+                    tracing::warn!(%peer, error = %e, "Ошибка RADIUS-запроса");
+                }
+            });
+        }
+    }
+
+    #[tracing::instrument(name = "radius_request", skip(self, socket, data), fields(otel.kind = "server"))]
+    async fn handle_datagram(&self, socket: &UdpSocket, data: &[u8], peer: SocketAddr) -> Result<(), RadiusError> {
+        // Пакеты от незарегистрированных NAS молча отбрасываются (RFC 2865, 3)
+        let Some(client) = self.clients.get(&crate::net::client_ip(peer)) else {
+            return Err(RadiusError::Config("Unknown client".to_string()));
+        };
+        if let Some(reply) = self.handle(data, client.secret.as_bytes()).await? {
+            socket.send_to(&reply, peer).await?;
+        }
+        Ok(())
+    }
+
+    /// Обработать Access-Request; `None` — ответа не будет
+    pub async fn handle(&self, data: &[u8], secret: &[u8]) -> Result<Option<Vec<u8>>, RadiusError> {
+        let request = Packet::parse(data)?;
+        if request.code != CODE_ACCESS_REQUEST {
+            return Ok(None);
+        }
+
+        let has_message_authenticator = request.verify_message_authenticator(secret)?;
+        let reply = match request.concat_attribute(ATTR_EAP_MESSAGE) {
+            Some(eap) if has_message_authenticator => self.handle_eap(&request, &eap, secret).await?,
+            // EAP без Message-Authenticator запрещён (RFC 3579, 3.2)
+            Some(_) => return Err(RadiusError::InvalidAuthenticator),
+            None => self.handle_pap(&request, secret).await?,
+        };
+        Ok(Some(reply.encode_response(secret)))
+    }
+
+    // ================= PAP =================
+
+    async fn handle_pap(&self, request: &Packet, secret: &[u8]) -> Result<Packet, RadiusError> {
+        let (Some(username), Some(password)) = (request.attribute(ATTR_USER_NAME), request.attribute(ATTR_USER_PASSWORD)) else {
+            return Ok(reject(request));
+        };
+        let username = String::from_utf8_lossy(username).into_owned();
+        let password = decrypt_user_password(secret, &request.authenticator, password)?;
+
+        match self.authenticate(request, &username, &password).await? {
+            Some(user) => {
+                let mut reply = request.response(CODE_ACCESS_ACCEPT);
+                self.add_group_attributes(&mut reply, &user).await?;
+                Ok(reply)
+            }
+            None => Ok(reject(request)),
+        }
+    }
+
+    // ================= EAP-TTLS =================
+
+    async fn handle_eap(&self, request: &Packet, eap_data: &[u8], secret: &[u8]) -> Result<Packet, RadiusError> {
+        let eap = EapPacket::parse(eap_data)?;
+
+        if eap.code == EAP_RESPONSE && eap.eap_type == Some(EAP_TYPE_IDENTITY) {
+            let Some(tls_config) = &self.tls_config else {
+                return Ok(eap_reject(request, EapPacket::failure(eap.identifier)));
+            };
+            let (session, start) = TtlsSession::start(Arc::clone(tls_config), &eap)?;
+            let mut state = vec![0u8; 16];
+            OsRng.fill_bytes(&mut state);
+            self.store_session(state.clone(), session);
+            return Ok(challenge(request, &start, &state));
+        }
+
+        let Some(state) = request.attribute(ATTR_STATE).map(<[u8]>::to_vec) else {
+            return Ok(eap_reject(request, EapPacket::failure(eap.identifier)));
+        };
+        let Some(mut session) = self.take_session(&state) else {
+            return Ok(eap_reject(request, EapPacket::failure(eap.identifier)));
+        };
+
+        match session.step(&eap) {
+            Ok(TtlsStep::Challenge(next)) => {
+                self.store_session(state.clone(), session);
+                Ok(challenge(request, &next, &state))
+            }
+            Ok(TtlsStep::Credentials { username, password }) => {
+                let Some(user) = self.authenticate(request, &username, &password).await? else {
+                    return Ok(eap_reject(request, EapPacket::failure(session.identifier())));
+                };
+
+                let msk = session.msk()?;
+                let mut reply = request.response(CODE_ACCESS_ACCEPT);
+                reply.add_split(ATTR_EAP_MESSAGE, &EapPacket::success(session.identifier()).encode());
+                reply.add(ATTR_USER_NAME, user.username.as_bytes());
+                reply.add_vendor(VENDOR_MICROSOFT, MS_MPPE_RECV_KEY, &encrypt_mppe_key(secret, &request.authenticator, &msk[..32]));
+                reply.add_vendor(VENDOR_MICROSOFT, MS_MPPE_SEND_KEY, &encrypt_mppe_key(secret, &request.authenticator, &msk[32..]));
+                self.add_group_attributes(&mut reply, &user).await?;
+                Ok(reply)
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "EAP-TTLS не удался");
+                Ok(eap_reject(request, EapPacket::failure(session.identifier())))
+            }
+        }
+    }
+
+    fn store_session(&self, state: Vec<u8>, session: TtlsSession) {
+        let mut sessions = self.sessions.lock().expect("RADIUS session lock poisoned");
+        sessions.retain(|_, s| s.created.elapsed() < SESSION_TIMEOUT);
+        sessions.insert(state, session);
+    }
+
+    fn take_session(&self, state: &[u8]) -> Option<TtlsSession> {
+        let mut sessions = self.sessions.lock().expect("RADIUS session lock poisoned");
+        sessions.remove(state).filter(|s| s.created.elapsed() < SESSION_TIMEOUT)
+    }
+
+    // ================= Общее =================
+
+    /// `None` — неверные учётные данные (ошибки хранилища пробрасываются); в аудит попадает NAS-IP-Address
+    async fn authenticate(&self, request: &Packet, username: &str, password: &str) -> Result<Option<User>, RadiusError> {
+        let nas_ip = request.attribute(ATTR_NAS_IP_ADDRESS)
+            .and_then(|ip| <[u8; 4]>::try_from(ip).ok())
+            .map(|ip| std::net::Ipv4Addr::from(ip).to_string());
+        match self.service.authenticate_for_nas(username, password, nas_ip).await {
+            Ok(user) => Ok(Some(user)),
+            // Превышение лимита попыток и истёкший пароль — тоже Access-Reject
+            Err(DirectoryError::AuthenticationFailed(_) | DirectoryError::PasswordExpired(_) | DirectoryError::RateLimited(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn add_group_attributes(&self, reply: &mut Packet, user: &User) -> Result<(), RadiusError> {
+        if self.group_attributes.is_empty() {
+            return Ok(());
+        }
+        let groups = self.service.find_groups_by_member(user.id).await?;
+        for mapping in &self.group_attributes {
+            if groups.iter().any(|g| g.sam_account_name.eq_ignore_ascii_case(&mapping.group)) {
+                reply.add_vendor(mapping.vendor_id, mapping.vendor_type, mapping.value.as_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn reject(request: &Packet) -> Packet {
+    let mut reply = request.response(CODE_ACCESS_REJECT);
+    reply.add(ATTR_REPLY_MESSAGE, b"Authentication failed");
+    reply
+}
+
+fn eap_reject(request: &Packet, eap: EapPacket) -> Packet {
+    let mut reply = request.response(CODE_ACCESS_REJECT);
+    reply.add_split(ATTR_EAP_MESSAGE, &eap.encode());
+    reply
+}
+
+fn challenge(request: &Packet, eap: &EapPacket, state: &[u8]) -> Packet {
+    let mut reply = request.response(CODE_ACCESS_CHALLENGE);
+    reply.add_split(ATTR_EAP_MESSAGE, &eap.encode());
+    reply.add(ATTR_STATE, state);
+    reply
+}
+
+/// TLS для EAP-TTLS: только TLS 1.2, т.к. вывод ключей TTLS определён для него
+fn load_tls_config(cert_path: &str, key_path: &str) -> Result<Arc<rustls::ServerConfig>, RadiusError> {
+    let cert_file = &mut BufReader::new(File::open(cert_path)?);
+    let key_file = &mut BufReader::new(File::open(key_path)?);
+
+    let cert_chain: Vec<rustls::Certificate> = rustls_pemfile::certs(cert_file)?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+
+    let key = rustls_pemfile::pkcs8_private_keys(key_file)?
+        .into_iter()
+        .next()
+        .map(rustls::PrivateKey)
+        .ok_or_else(|| RadiusError::Config(format!("No PKCS#8 private key in {}", key_path)))?;
+
+    let config = rustls::ServerConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(&[&rustls::version::TLS12])
+        .map_err(|e| RadiusError::Tls(e.to_string()))?
+        .with_no_client_auth()
+        .with_single_cert(cert_chain, key)
+        .map_err(|e| RadiusError::Tls(e.to_string()))?;
+
+    Ok(Arc::new(config))
+}
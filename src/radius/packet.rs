@@ -0,0 +1,219 @@
+// src/radius/packet.rs
+
+//! Пакеты RADIUS (RFC 2865) и связанные с ними вычисления на общем секрете.
+
+use hmac::{Hmac, Mac};
+use md5::{Digest, Md5};
+use rand::{rngs::OsRng, RngCore};
+
+use super::RadiusError;
+
+pub const CODE_ACCESS_REQUEST: u8 = 1;
+pub const CODE_ACCESS_ACCEPT: u8 = 2;
+pub const CODE_ACCESS_REJECT: u8 = 3;
+pub const CODE_ACCESS_CHALLENGE: u8 = 11;
+
+pub const ATTR_USER_NAME: u8 = 1;
+pub const ATTR_USER_PASSWORD: u8 = 2;
+pub const ATTR_NAS_IP_ADDRESS: u8 = 4;
+pub const ATTR_REPLY_MESSAGE: u8 = 18;
+pub const ATTR_STATE: u8 = 24;
+pub const ATTR_VENDOR_SPECIFIC: u8 = 26;
+pub const ATTR_EAP_MESSAGE: u8 = 79;
+pub const ATTR_MESSAGE_AUTHENTICATOR: u8 = 80;
+
+/// Microsoft (RFC 2548)
+pub const VENDOR_MICROSOFT: u32 = 311;
+pub const MS_MPPE_SEND_KEY: u8 = 16;
+pub const MS_MPPE_RECV_KEY: u8 = 17;
+
+const HEADER_LEN: usize = 20;
+const MAX_PACKET_LEN: usize = 4096;
+const MAX_ATTR_VALUE: usize = 253;
+
+#[derive(Debug, Clone)]
+pub struct Packet {
+    pub code: u8,
+    pub identifier: u8,
+    pub authenticator: [u8; 16],
+    pub attributes: Vec<(u8, Vec<u8>)>,
+}
+
+impl Packet {
+    pub fn parse(data: &[u8]) -> Result<Self, RadiusError> {
+        if data.len() < HEADER_LEN {
+            return Err(RadiusError::Decode("Packet too short".to_string()));
+        }
+        let length = u16::from_be_bytes([data[2], data[3]]) as usize;
+        if length < HEADER_LEN || length > data.len() || length > MAX_PACKET_LEN {
+            return Err(RadiusError::Decode("Invalid packet length".to_string()));
+        }
+
+        let mut attributes = Vec::new();
+        let mut pos = HEADER_LEN;
+        while pos < length {
+            if pos + 2 > length {
+                return Err(RadiusError::Decode("Truncated attribute".to_string()));
+            }
+            let (attr_type, attr_len) = (data[pos], data[pos + 1] as usize);
+            if attr_len < 2 || pos + attr_len > length {
+                return Err(RadiusError::Decode("Invalid attribute length".to_string()));
+            }
+            attributes.push((attr_type, data[pos + 2..pos + attr_len].to_vec()));
+            pos += attr_len;
+        }
+
+        let mut authenticator = [0u8; 16];
+        authenticator.copy_from_slice(&data[4..20]);
+        Ok(Self { code: data[0], identifier: data[1], authenticator, attributes })
+    }
+
+    /// Заготовка ответа на запрос
+    pub fn response(&self, code: u8) -> Packet {
+        Packet {
+            code,
+            identifier: self.identifier,
+            authenticator: self.authenticator,
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn attribute(&self, attr_type: u8) -> Option<&[u8]> {
+        self.attributes.iter().find(|(t, _)| *t == attr_type).map(|(_, v)| v.as_slice())
+    }
+
+    /// Значения атрибута, разбитого на части (EAP-Message), склеенные вместе
+    pub fn concat_attribute(&self, attr_type: u8) -> Option<Vec<u8>> {
+        let parts: Vec<&Vec<u8>> = self.attributes.iter().filter(|(t, _)| *t == attr_type).map(|(_, v)| v).collect();
+        if parts.is_empty() {
+            return None;
+        }
+        Some(parts.into_iter().flatten().copied().collect())
+    }
+
+    pub fn add(&mut self, attr_type: u8, value: &[u8]) {
+        self.attributes.push((attr_type, value.to_vec()));
+    }
+
+    /// Добавить длинное значение, разбив его на атрибуты по 253 байта
+    pub fn add_split(&mut self, attr_type: u8, value: &[u8]) {
+        for chunk in value.chunks(MAX_ATTR_VALUE) {
+            self.add(attr_type, chunk);
+        }
+    }
+
+    pub fn add_vendor(&mut self, vendor_id: u32, vendor_type: u8, value: &[u8]) {
+        let mut data = vendor_id.to_be_bytes().to_vec();
+        data.push(vendor_type);
+        data.push((value.len() + 2) as u8);
+        data.extend_from_slice(value);
+        self.add(ATTR_VENDOR_SPECIFIC, &data);
+    }
+
+    fn encode_raw(&self) -> Vec<u8> {
+        let mut out = vec![self.code, self.identifier, 0, 0];
+        out.extend_from_slice(&self.authenticator);
+        for (attr_type, value) in &self.attributes {
+            out.push(*attr_type);
+            out.push((value.len() + 2) as u8);
+            out.extend_from_slice(value);
+        }
+        let len = out.len() as u16;
+        out[2..4].copy_from_slice(&len.to_be_bytes());
+        out
+    }
+
+    /// Проверить Message-Authenticator запроса; `Ok(false)` — атрибута нет
+    pub fn verify_message_authenticator(&self, secret: &[u8]) -> Result<bool, RadiusError> {
+        let Some(received) = self.attribute(ATTR_MESSAGE_AUTHENTICATOR) else {
+            return Ok(false);
+        };
+        let mut zeroed = self.clone();
+        for (t, v) in zeroed.attributes.iter_mut() {
+            if *t == ATTR_MESSAGE_AUTHENTICATOR {
+                *v = vec![0u8; 16];
+            }
+        }
+        let expected = hmac_md5(secret, &zeroed.encode_raw());
+        if !constant_time_eq(&expected, received) {
+            return Err(RadiusError::InvalidAuthenticator);
+        }
+        Ok(true)
+    }
+
+    /// Закодировать ответ: Message-Authenticator и Response Authenticator.
+    /// `self.authenticator` должен содержать Request Authenticator запроса
+    pub fn encode_response(mut self, secret: &[u8]) -> Vec<u8> {
+        self.attributes.retain(|(t, _)| *t != ATTR_MESSAGE_AUTHENTICATOR);
+        self.attributes.insert(0, (ATTR_MESSAGE_AUTHENTICATOR, vec![0u8; 16]));
+        let mac = hmac_md5(secret, &self.encode_raw());
+        self.attributes[0].1 = mac;
+
+        let mut out = self.encode_raw();
+        let mut hasher = Md5::new();
+        hasher.update(&out);
+        hasher.update(secret);
+        out[4..20].copy_from_slice(&hasher.finalize());
+        out
+    }
+}
+
+/// Расшифровать User-Password (RFC 2865, 5.2)
+pub fn decrypt_user_password(secret: &[u8], authenticator: &[u8; 16], encrypted: &[u8]) -> Result<String, RadiusError> {
+    if encrypted.is_empty() || !encrypted.len().is_multiple_of(16) || encrypted.len() > 128 {
+        return Err(RadiusError::Decode("Invalid User-Password length".to_string()));
+    }
+
+    let mut plain = Vec::with_capacity(encrypted.len());
+    let mut prev: &[u8] = authenticator;
+    for chunk in encrypted.chunks(16) {
+        let b = md5_concat(secret, prev);
+        plain.extend(chunk.iter().zip(b.iter()).map(|(c, k)| c ^ k));
+        prev = chunk;
+    }
+
+    while plain.last() == Some(&0) {
+        plain.pop();
+    }
+    String::from_utf8(plain).map_err(|_| RadiusError::Decode("User-Password is not UTF-8".to_string()))
+}
+
+/// Зашифровать ключ MS-MPPE-Send/Recv-Key (RFC 2548, 2.4.2)
+pub fn encrypt_mppe_key(secret: &[u8], request_authenticator: &[u8; 16], key: &[u8]) -> Vec<u8> {
+    let mut salt = [0u8; 2];
+    OsRng.fill_bytes(&mut salt);
+    salt[0] |= 0x80;
+
+    let mut plain = vec![key.len() as u8];
+    plain.extend_from_slice(key);
+    while !plain.len().is_multiple_of(16) {
+        plain.push(0);
+    }
+
+    let mut out = salt.to_vec();
+    let mut prev = [request_authenticator.as_slice(), &salt].concat();
+    for chunk in plain.chunks(16) {
+        let b = md5_concat(secret, &prev);
+        let cipher: Vec<u8> = chunk.iter().zip(b.iter()).map(|(p, k)| p ^ k).collect();
+        out.extend_from_slice(&cipher);
+        prev = cipher;
+    }
+    out
+}
+
+fn md5_concat(secret: &[u8], data: &[u8]) -> [u8; 16] {
+    let mut hasher = Md5::new();
+    hasher.update(secret);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+fn hmac_md5(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Md5> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,142 @@
+// src/ratelimit.rs
+
+//! Ограничение частоты попыток входа: корзины токенов в памяти по адресу клиента и по учётной записи.
+//! Превышение лимита — `DirectoryError::RateLimited` (REST отвечает 429 с `Retry-After`).
+//! IPv6-клиенту обычно выдают целую сеть /64, поэтому лимит по адресу для IPv6 — общий на /64.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::{RateLimit, RateLimitConfig};
+
+/// Сверх этого числа корзин полные (давно не использованные) удаляются
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Какой лимит исчерпан
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitScope {
+    Ip,
+    Account,
+}
+
+impl LimitScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitScope::Ip => "ip",
+            LimitScope::Account => "account",
+        }
+    }
+}
+
+/// Отказ в попытке
+#[derive(Debug, Clone, Copy)]
+pub struct Limited {
+    pub scope: LimitScope,
+    pub retry_after: Duration,
+    /// Первый отказ с тех пор, как в корзине были токены: о нём пишется аудит
+    pub breach: bool,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    exhausted: bool,
+}
+
+/// Корзины токенов по ключу: `burst` попыток сразу, далее `per_minute` в минуту
+pub struct RateLimiter {
+    burst: f64,
+    per_second: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            burst: f64::from(limit.burst.max(1)),
+            per_second: f64::from(limit.per_minute.max(1)) / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Взять токен; `Err` — сколько ждать до следующего и первый ли это отказ
+    pub fn acquire(&self, key: &str) -> Result<(), (Duration, bool)> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_KEYS {
+            buckets.retain(|_, bucket| self.refilled(bucket, now) < self.burst);
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket { tokens: self.burst, updated: now, exhausted: false });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            bucket.exhausted = false;
+            return Ok(());
+        }
+
+        let retry_after = Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second);
+        let breach = !bucket.exhausted;
+        bucket.exhausted = true;
+        Err((retry_after, breach))
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.per_second).min(self.burst)
+    }
+}
+
+/// Ключ корзины по адресу клиента: IPv4 как есть, IPv6 — сеть /64 (`2001:db8:1:2::/64`)
+pub fn ip_key(ip: &str) -> String {
+    match ip.parse::<IpAddr>().map(|ip| ip.to_canonical()) {
+        Ok(IpAddr::V6(ip)) => {
+            let mut segments = ip.segments();
+            segments[4..].fill(0);
+            format!("{}/64", std::net::Ipv6Addr::from(segments))
+        }
+        Ok(canonical) => canonical.to_string(),
+        Err(_) => ip.to_string(),
+    }
+}
+
+/// Один и тот же адрес в любой записи: `::1` и `0:0:0:0:0:0:0:1`, `10.0.0.5` и `::ffff:10.0.0.5`
+fn same_ip(a: &str, b: &str) -> bool {
+    match (a.parse::<IpAddr>(), b.parse::<IpAddr>()) {
+        (Ok(a), Ok(b)) => a.to_canonical() == b.to_canonical(),
+        _ => a == b,
+    }
+}
+
+/// Лимиты попыток входа (`security.rate_limit`)
+pub struct AuthRateLimiter {
+    per_ip: RateLimiter,
+    per_account: RateLimiter,
+    exempt_ips: Vec<String>,
+    /// Блокировать учётную запись при исчерпании её лимита
+    pub lock_account: bool,
+}
+
+impl AuthRateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            per_ip: RateLimiter::new(config.per_ip),
+            per_account: RateLimiter::new(config.per_account),
+            exempt_ips: config.exempt_ips.clone(),
+            lock_account: config.lock_account,
+        }
+    }
+
+    /// Проверить попытку входа: сначала адрес, затем учётная запись
+    pub fn check(&self, username: &str, source_ip: Option<&str>) -> Result<(), Limited> {
+        if let Some(ip) = source_ip.filter(|ip| !self.exempt_ips.iter().any(|exempt| same_ip(exempt, ip))) {
+            self.per_ip.acquire(&ip_key(ip))
+                .map_err(|(retry_after, breach)| Limited { scope: LimitScope::Ip, retry_after, breach })?;
+        }
+        self.per_account.acquire(username)
+            .map_err(|(retry_after, breach)| Limited { scope: LimitScope::Account, retry_after, breach })
+    }
+}
@@ -0,0 +1,221 @@
+// src/reload.rs
+
+//! Перезагрузка конфигурации процесса `web` без перезапуска: по SIGHUP или
+//! `POST /api/admin/reload`. Файл читается заново (с `${ENV_VAR}` и `NEXTDOMEN_*`) и проверяется;
+//! секции `logging.level`, `logging.modules`, `security.rate_limit`, `security.password_policy`,
+//! `security.approvals`, `security.sessions`, `security.risk`, `security.auth_providers`, `ca.validity_days`,
+//! `ca.crl_validity_secs`, `ca.crl_url` и `web_server.cors`
+//! применяются все вместе или ни одна. Изменение базы или мастер-ключа отклоняет перезагрузку целиком; прочие изменения вступят в силу после перезапуска.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use serde_yaml::Value;
+
+use crate::config::AppConfig;
+use crate::directory_service::DirectoryService;
+use crate::telemetry;
+use crate::web::cors::SharedCors;
+
+/// Применяются без перезапуска
+const RELOADABLE: &[&str] = &["logging.level", "logging.modules", "security.rate_limit", "security.password_policy", "security.approvals", "security.sessions", "security.risk", "security.auth_providers", "ca.validity_days", "ca.crl_validity_secs", "ca.crl_url", "web_server.cors"];
+
+/// Не меняются у работающего процесса: база открыта с этим ключом
+const IMMUTABLE: &[&str] = &["db_path", "master_key_hex", "master_key_file", "master_key_secret", "secrets"];
+
+/// Итог перезагрузки: изменённые поля по тому, что с ними стало
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+pub struct ReloadReport {
+    /// Применены
+    pub applied: Vec<String>,
+    /// Изменены, но действуют после перезапуска
+    pub restart_required: Vec<String>,
+    /// Изменены, но не применены: уровни логирования задаёт `RUST_LOG`
+    pub ignored: Vec<String>,
+    /// Неизменяемые поля, из-за которых перезагрузка отклонена
+    pub rejected: Vec<String>,
+    /// Файл не читается или не проходит проверку; ничего не применено
+    pub errors: Vec<String>,
+}
+
+impl ReloadReport {
+    /// Новые настройки применены
+    pub fn is_applied(&self) -> bool {
+        self.rejected.is_empty() && self.errors.is_empty()
+    }
+}
+
+pub struct ConfigReloader {
+    /// `--config`; без него — как при запуске (`NEXTDOMEN_CONFIG` или `config.yaml`)
+    path: Option<PathBuf>,
+    service: Arc<DirectoryService>,
+    cors: SharedCors,
+    /// Действующая конфигурация для сравнения; перезагрузки идут по одной
+    current: Mutex<Value>,
+}
+
+impl ConfigReloader {
+    pub fn new(path: Option<PathBuf>, config: &AppConfig, service: Arc<DirectoryService>) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            path,
+            service,
+            cors: SharedCors::new(&config.web_server.cors)?,
+            current: Mutex::new(serde_yaml::to_value(config)?),
+        })
+    }
+
+    /// CORS, который меняется при перезагрузке
+    pub fn cors(&self) -> SharedCors {
+        self.cors.clone()
+    }
+
+    /// Перечитать конфигурацию и применить перезагружаемые секции
+    pub fn reload(&self) -> ReloadReport {
+        let mut current = self.current.lock().unwrap();
+        let mut report = ReloadReport::default();
+
+        let config = match AppConfig::load_with_env(self.path.as_deref()) {
+            Ok(config) => config,
+            Err(e) => {
+                report.errors.push(e.to_string());
+                return report;
+            }
+        };
+        report.errors.extend(config.validate(&[]).into_iter().map(|issue| issue.to_string()));
+        let next = match serde_yaml::to_value(&config) {
+            Ok(next) => next,
+            Err(e) => {
+                report.errors.push(e.to_string());
+                return report;
+            }
+        };
+
+        for field in changed_fields(&current, &next) {
+            if IMMUTABLE.iter().any(|immutable| field == *immutable) {
+                report.rejected.push(field);
+            } else if RELOADABLE.iter().any(|reloadable| field == *reloadable) {
+                report.applied.push(field);
+            } else {
+                report.restart_required.push(field);
+            }
+        }
+        // Проверить всё до применения, чтобы не применить половину
+        if let Err(e) = telemetry::check_level(&config.logging) {
+            report.errors.push(e.to_string());
+        }
+        if let Err(e) = crate::web::cors::cors_layer(&config.web_server.cors) {
+            report.errors.push(e);
+        }
+        if !report.is_applied() {
+            report.applied.clear();
+            return report;
+        }
+
+        let applied = std::mem::take(&mut report.applied);
+        for field in applied {
+            match field.as_str() {
+                "logging.level" | "logging.modules" => match telemetry::reload_level(&config.logging) {
+                    Ok(true) => report.applied.push(field),
+                    Ok(false) => report.ignored.push(field),
+                    Err(e) => report.errors.push(e.to_string()),
+                },
+                "security.rate_limit" => {
+                    self.service.set_rate_limit(&config.security.rate_limit);
+                    report.applied.push(field);
+                }
+                "security.password_policy" => {
+                    self.service.set_password_policy(&config.security.password_policy);
+                    report.applied.push(field);
+                }
+                "security.approvals" => {
+                    self.service.set_approvals(&config.security.approvals);
+                    report.applied.push(field);
+                }
+                "security.sessions" => {
+                    self.service.set_sessions(&config.security.sessions);
+                    report.applied.push(field);
+                }
+                "security.risk" => {
+                    self.service.set_risk(&config.security.risk);
+                    report.applied.push(field);
+                }
+                "security.auth_providers" => {
+                    self.service.set_auth_providers(&config.security.auth_providers);
+                    report.applied.push(field);
+                }
+                "ca.validity_days" | "ca.crl_validity_secs" | "ca.crl_url" => {
+                    self.service.set_ca(&config.ca);
+                    report.applied.push(field);
+                }
+                "web_server.cors" => match self.cors.set(&config.web_server.cors) {
+                    Ok(()) => report.applied.push(field),
+                    Err(e) => report.errors.push(e),
+                },
+                _ => {}
+            }
+        }
+        *current = next;
+        report
+    }
+
+    /// Перезагружать конфигурацию по SIGHUP, пока жив процесс
+    #[cfg(unix)]
+    pub fn spawn_sighup(self: &Arc<Self>) -> Result<(), std::io::Error> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangups = signal(SignalKind::hangup())?;
+        let reloader = Arc::clone(self);
+        tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                let reloader = Arc::clone(&reloader);
+                let Ok(report) = tokio::task::spawn_blocking(move || reloader.reload()).await else {
+                    continue;
+                };
+                log_report(&report);
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn spawn_sighup(self: &Arc<Self>) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+pub fn log_report(report: &ReloadReport) {
+    if report.is_applied() {
+        tracing::info!(applied = ?report.applied, restart_required = ?report.restart_required, ignored = ?report.ignored, "Конфигурация перезагружена");
+    } else {
+        tracing::error!(rejected = ?report.rejected, errors = ?report.errors, "Перезагрузка конфигурации отклонена");
+    }
+}
+
+/// Изменённые поля до второго уровня: `db_path`, `security.rate_limit`, `logging.level`
+fn changed_fields(old: &Value, new: &Value) -> Vec<String> {
+    let mut fields = Vec::new();
+    let (Value::Mapping(old), Value::Mapping(new)) = (old, new) else {
+        return fields;
+    };
+    let keys = old.keys().chain(new.keys().filter(|key| !old.contains_key(*key)));
+    for key in keys {
+        let Some(name) = key.as_str() else { continue };
+        let (before, after) = (old.get(key), new.get(key));
+        if before == after {
+            continue;
+        }
+        match (before, after) {
+            (Some(Value::Mapping(before)), Some(Value::Mapping(after))) => {
+                let subkeys = before.keys().chain(after.keys().filter(|subkey| !before.contains_key(*subkey)));
+                for subkey in subkeys {
+                    if before.get(subkey) != after.get(subkey) {
+                        fields.push(format!("{}.{}", name, subkey.as_str().unwrap_or_default()));
+                    }
+                }
+            }
+            _ => fields.push(name.to_string()),
+        }
+    }
+    fields
+}
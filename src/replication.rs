@@ -0,0 +1,94 @@
+// src/replication.rs
+
+//! Реплика только для чтения (`replication.read_only`): процесс `web` периодически забирает
+//! снимок каталога с записываемого контроллера (`POST /api/replication/snapshot`) и заменяет им
+//! свою базу. Локальные изменения отклоняет `DirectoryService` со ссылкой на `upstream`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::ReplicationConfig;
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::web::replication::{SnapshotRequest, SnapshotResponse};
+
+#[derive(Debug)]
+pub enum ReplicationError {
+    Http(reqwest::Error),
+    Directory(DirectoryError),
+    /// Ошибка конфигурации или ответа upstream
+    Invalid(String),
+}
+
+impl From<reqwest::Error> for ReplicationError {
+    fn from(e: reqwest::Error) -> Self {
+        ReplicationError::Http(e)
+    }
+}
+
+impl From<DirectoryError> for ReplicationError {
+    fn from(e: DirectoryError) -> Self {
+        ReplicationError::Directory(e)
+    }
+}
+
+impl std::fmt::Display for ReplicationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplicationError::Http(e) => write!(f, "HTTP error: {}", e),
+            ReplicationError::Directory(e) => write!(f, "Directory error: {}", e),
+            ReplicationError::Invalid(e) => write!(f, "Replication error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReplicationError {}
+
+pub struct ReplicaPuller {
+    service: Arc<DirectoryService>,
+    config: ReplicationConfig,
+    upstream: String,
+    api_key: String,
+    http: reqwest::Client,
+}
+
+impl ReplicaPuller {
+    /// Для реплики нужны `replication.upstream` и `replication.api_key`
+    pub fn new(service: Arc<DirectoryService>, config: ReplicationConfig) -> Result<Self, ReplicationError> {
+        let upstream = config.upstream.clone()
+            .ok_or_else(|| ReplicationError::Invalid("replication.upstream is required for a read-only replica".to_string()))?;
+        let api_key = config.api_key.clone()
+            .ok_or_else(|| ReplicationError::Invalid("replication.api_key is required for a read-only replica".to_string()))?;
+        Ok(Self {
+            service,
+            config,
+            upstream: upstream.trim_end_matches('/').to_string(),
+            api_key,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// Забирать каталог по расписанию; ошибка цикла не останавливает следующие
+    pub async fn run(self) {
+        tracing::info!(upstream = %self.upstream, interval_secs = self.config.pull_interval_secs, "Репликация с записываемого контроллера");
+        loop {
+            match self.pull_once().await {
+                Ok(entries) => tracing::info!(entries, "Каталог реплицирован"),
+                Err(e) => tracing::error!(error = %e, "Ошибка репликации"),
+            }
+            tokio::time::sleep(Duration::from_secs(self.config.pull_interval_secs.max(1))).await;
+        }
+    }
+
+    /// Один цикл: снимок каталога целиком; возвращает число записей
+    pub async fn pull_once(&self) -> Result<usize, ReplicationError> {
+        let response = self.http.post(format!("{}/api/replication/snapshot", self.upstream))
+            .header(crate::middleware::API_KEY_HEADER, &self.api_key)
+            .json(&SnapshotRequest { cache_credentials_for: self.config.cache_credentials_for.clone() })
+            .send()
+            .await?
+            .error_for_status()?;
+        let snapshot: SnapshotResponse = response.json().await?;
+        let entries = snapshot.into_entries().map_err(ReplicationError::Invalid)?;
+        Ok(self.service.apply_replication_snapshot(entries, &self.upstream).await?)
+    }
+}
@@ -0,0 +1,357 @@
+// src/sync.rs
+
+//! Односторонняя синхронизация из внешнего LDAP / Active Directory:
+//! первый цикл — полный, далее инкрементальные по uSNChanged (AD) или modifyTimestamp.
+//! Записи источника проходят тот же путь, что и импорт LDIF. Объекты, удалённые в источнике,
+//! удаляются и локально: в инкрементальном цикле — по надгробиям AD (`isDeleted`), в полном —
+//! все сопоставленные источнику объекты, которых больше нет в выборке.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use ldap3::adapters::PagedResults;
+use ldap3::controls::RawControl;
+use ldap3::{Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+use uuid::Uuid;
+
+use crate::config::LdapSyncConfig;
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::dn::{self, Dn};
+use crate::ldif::{ImportReport, LdifEntry};
+use crate::models::{LdapSyncState, OuContents, SecurityIdentifier};
+
+/// Show Deleted (`LDAP_SERVER_SHOW_DELETED_OID`): поиск AD возвращает и надгробия
+const SHOW_DELETED_OID: &str = "1.2.840.113556.1.4.417";
+
+#[derive(Debug)]
+pub enum SyncError {
+    Ldap(ldap3::LdapError),
+    Directory(DirectoryError),
+}
+
+impl From<ldap3::LdapError> for SyncError {
+    fn from(e: ldap3::LdapError) -> Self {
+        SyncError::Ldap(e)
+    }
+}
+
+impl From<DirectoryError> for SyncError {
+    fn from(e: DirectoryError) -> Self {
+        SyncError::Directory(e)
+    }
+}
+
+impl std::fmt::Display for SyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncError::Ldap(e) => write!(f, "LDAP error: {}", e),
+            SyncError::Directory(e) => write!(f, "Directory error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+/// Итог одного цикла синхронизации
+#[derive(Debug)]
+pub struct SyncReport {
+    pub full: bool,
+    /// Сколько записей получено из источника
+    pub fetched: usize,
+    pub import: ImportReport,
+    /// Сколько объектов удалено вслед за источником
+    pub deleted: usize,
+}
+
+/// Запись источника с её неизменяемым идентификатором: objectGUID (AD) или entryUUID
+#[derive(Debug, Clone)]
+pub struct SourceEntry {
+    pub entry: LdifEntry,
+    pub guid: Option<String>,
+}
+
+pub struct LdapSyncConnector {
+    service: Arc<DirectoryService>,
+    config: LdapSyncConfig,
+    /// Переименования атрибутов с ключами в нижнем регистре
+    attribute_mapping: HashMap<String, String>,
+}
+
+impl LdapSyncConnector {
+    pub fn new(service: Arc<DirectoryService>, config: LdapSyncConfig) -> Self {
+        let attribute_mapping = config.attribute_mapping
+            .iter()
+            .map(|(from, to)| (from.to_lowercase(), to.clone()))
+            .collect();
+        Self { service, config, attribute_mapping }
+    }
+
+    /// Синхронизировать по расписанию; ошибка цикла не останавливает следующие
+    pub async fn run(self, force_full: bool) -> Result<(), SyncError> {
+        tracing::info!(url = %self.config.url, interval_secs = self.config.interval_secs, "Синхронизация по расписанию");
+        let mut full = force_full;
+        loop {
+            match self.run_once(full).await {
+                Ok(report) => log_report(&report),
+                Err(e) => tracing::error!(error = %e, "Ошибка синхронизации"),
+            }
+            full = false;
+            tokio::time::sleep(Duration::from_secs(self.config.interval_secs)).await;
+        }
+    }
+
+    /// Один цикл: полный, если cookie ещё нет или `force_full`, иначе только изменения
+    pub async fn run_once(&self, force_full: bool) -> Result<SyncReport, SyncError> {
+        let mut state = self.service.get_sync_state(&self.config.url).await?
+            .unwrap_or_else(|| LdapSyncState::new(&self.config.url));
+        let full = force_full || state.needs_full_sync();
+
+        let settings = LdapConnSettings::new()
+            .set_starttls(self.config.starttls)
+            .set_no_tls_verify(self.config.tls_skip_verify);
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &self.config.url).await?;
+        ldap3::drive!(conn);
+        ldap.simple_bind(&self.config.bind_dn, &self.config.bind_password).await?.success()?;
+
+        // highestCommittedUSN фиксируем до поиска: изменения во время цикла попадут в следующий
+        let highest_usn = read_highest_usn(&mut ldap).await?;
+        let change_filter = if full {
+            None
+        } else if let (Some(last_usn), Some(_)) = (state.highest_usn, highest_usn) {
+            Some(format!("(uSNChanged>={})", last_usn + 1))
+        } else {
+            state.last_modify_timestamp.as_ref().map(|ts| format!("(modifyTimestamp>={})", ts))
+        };
+        let full = change_filter.is_none();
+
+        let mut entries = Vec::new();
+        let mut max_timestamp = state.last_modify_timestamp.clone();
+        for filter in [&self.config.ou_filter, &self.config.user_filter, &self.config.group_filter] {
+            let filter = match &change_filter {
+                Some(change) => format!("(&{}{})", filter, change),
+                None => filter.clone(),
+            };
+            for entry in self.search(&mut ldap, &filter).await? {
+                if let Some(ts) = entry.attrs.get("modifyTimestamp").and_then(|v| v.first())
+                    && max_timestamp.as_deref().is_none_or(|max| timestamp_key(ts) > timestamp_key(max))
+                {
+                    max_timestamp = Some(ts.clone());
+                }
+                entries.push(self.to_ldif_entry(entry));
+            }
+        }
+        // Надгробия есть только у AD; без uSNChanged удаления находит полный цикл
+        let tombstones = match (&change_filter, state.highest_usn, highest_usn) {
+            (Some(_), Some(last_usn), Some(_)) => self.search_tombstones(&mut ldap, last_usn).await?,
+            _ => Vec::new(),
+        };
+        let _ = ldap.unbind().await;
+
+        let fetched = entries.len();
+        let (import, deleted) = self.apply(entries, &tombstones, full).await?;
+
+        state.highest_usn = highest_usn;
+        state.last_modify_timestamp = max_timestamp;
+        state.last_sync = Some(Utc::now());
+        if full {
+            state.last_full_sync = state.last_sync;
+        }
+        self.service.save_sync_state(&state).await?;
+
+        Ok(SyncReport { full, fetched, import, deleted })
+    }
+
+    /// Применить выборку источника: импорт записей с участниками групп, разрешёнными и по прошлым
+    /// циклам, затем удаление объектов с надгробиями `tombstones` (objectGUID) и, после полного
+    /// цикла (`full`), сопоставленных источнику объектов, которых в выборке нет. Возвращает отчёт
+    /// импорта (ошибки удаления — в нём же) и число удалённых объектов
+    pub async fn apply(&self, entries: Vec<SourceEntry>, tombstones: &[String], full: bool) -> Result<(ImportReport, usize), SyncError> {
+        let source = &self.config.url;
+        let mut objects = self.service.get_sync_objects(source).await?;
+        let fetched: Vec<(String, Option<String>)> = entries.iter().map(|e| (dn::key(&e.entry.dn), e.guid.clone())).collect();
+
+        let mut dns = objects.by_dn.clone();
+        let entries = entries.into_iter().map(|e| e.entry).collect();
+        let mut import = self.service.import_ldif_resolving(entries, self.config.on_conflict, &mut dns).await?;
+
+        // Переименованный в источнике объект остаётся тем же: его прежний DN забывается
+        let mut seen = HashSet::new();
+        for (key, guid) in &fetched {
+            let Some(id) = dns.get(key).copied() else { continue };
+            objects.by_dn.retain(|other, other_id| *other_id != id || other == key);
+            objects.by_dn.insert(key.clone(), id);
+            if let Some(guid) = guid {
+                objects.by_guid.insert(guid.clone(), id);
+            }
+            seen.insert(id);
+        }
+
+        let mut gone: HashSet<Uuid> = tombstones.iter().filter_map(|guid| objects.by_guid.get(guid).copied()).collect();
+        if full {
+            let fetched_keys: HashSet<&String> = fetched.iter().map(|(key, _)| key).collect();
+            // Запись, которая пришла, но не импортировалась из-за ошибки, не удаляется
+            gone.extend(objects.by_dn.iter()
+                .filter(|(key, id)| !fetched_keys.contains(key) && !seen.contains(id))
+                .map(|(_, id)| *id));
+        }
+
+        // Глубже по DN — раньше: OU удаляется после своих объектов
+        let mut gone: Vec<(usize, Uuid)> = gone.into_iter()
+            .map(|id| {
+                let depth = objects.by_dn.iter()
+                    .filter(|(_, other)| **other == id)
+                    .filter_map(|(key, _)| Dn::parse(key).ok().map(|dn| dn.len()))
+                    .max()
+                    .unwrap_or_default();
+                (depth, id)
+            })
+            .collect();
+        gone.sort_by_key(|(depth, _)| std::cmp::Reverse(*depth));
+        let mut deleted = 0;
+        for (_, id) in gone {
+            match delete_object(&self.service, id).await {
+                Ok(found) => {
+                    deleted += usize::from(found);
+                    objects.by_dn.retain(|_, other| *other != id);
+                    objects.by_guid.retain(|_, other| *other != id);
+                }
+                Err(e) => import.errors.push(format!("{}: {}", id, e)),
+            }
+        }
+
+        self.service.save_sync_objects(source, &objects).await?;
+        Ok((import, deleted))
+    }
+
+    /// objectGUID надгробий, изменившихся после `last_usn`. Удалённые объекты AD лежат в
+    /// `CN=Deleted Objects` домена, поэтому поиск идёт от корня домена base_dn
+    async fn search_tombstones(&self, ldap: &mut Ldap, last_usn: u64) -> Result<Vec<String>, SyncError> {
+        let base = Dn::parse(&self.config.base_dn)
+            .ok()
+            .and_then(|dn| dn.dns_name())
+            .map(|name| name.split('.').map(|dc| format!("DC={}", dc)).collect::<Vec<_>>().join(","))
+            .unwrap_or_else(|| self.config.base_dn.clone());
+        let filter = format!("(&(isDeleted=TRUE)(uSNChanged>={}))", last_usn + 1);
+        let (entries, _) = ldap
+            .with_controls(RawControl { ctype: SHOW_DELETED_OID.to_string(), crit: false, val: None })
+            .search(&base, Scope::Subtree, &filter, vec!["objectGUID", "isDeleted"])
+            .await?
+            .success()?;
+        Ok(entries.into_iter().filter_map(|entry| source_guid(&SearchEntry::construct(entry))).collect())
+    }
+
+    /// Постраничный поиск по поддереву base_dn
+    async fn search(&self, ldap: &mut Ldap, filter: &str) -> Result<Vec<SearchEntry>, SyncError> {
+        let mut stream = ldap.streaming_search_with(
+            PagedResults::new(self.config.page_size),
+            &self.config.base_dn,
+            Scope::Subtree,
+            filter,
+            vec!["*", "modifyTimestamp", "uSNChanged", "entryUUID"],
+        ).await?;
+
+        let mut entries = Vec::new();
+        while let Some(entry) = stream.next().await? {
+            entries.push(SearchEntry::construct(entry));
+        }
+        stream.finish().await.success()?;
+        Ok(entries)
+    }
+
+    /// Запись источника → запись LDIF с учётом `attribute_mapping`; из двоичных атрибутов
+    /// переносится только objectSid (в строковой форме), остальные отбрасываются
+    fn to_ldif_entry(&self, entry: SearchEntry) -> SourceEntry {
+        let guid = source_guid(&entry);
+        let mut ldif_entry = LdifEntry::new(entry.dn);
+        for (name, values) in entry.attrs {
+            let name = self.attribute_mapping.get(&name.to_lowercase()).cloned().unwrap_or(name);
+            for value in values {
+                ldif_entry.add(name.clone(), value);
+            }
+        }
+        for (name, values) in entry.bin_attrs {
+            if name.eq_ignore_ascii_case("objectSid") {
+                for sid in values.iter().filter_map(|value| SecurityIdentifier::from_bytes(value).ok()) {
+                    ldif_entry.add("objectSid", sid.to_string());
+                }
+            }
+        }
+        SourceEntry { entry: ldif_entry, guid }
+    }
+}
+
+/// objectGUID (двоичный, порядок байтов GUID Windows) или entryUUID записи в виде UUID
+fn source_guid(entry: &SearchEntry) -> Option<String> {
+    // ldap3 кладёт значение в attrs, если байты GUID случайно оказались допустимым UTF-8
+    let binary = entry.bin_attrs.iter().map(|(name, values)| (name, values.first().map(Vec::as_slice)));
+    let text = entry.attrs.iter().map(|(name, values)| (name, values.first().map(String::as_bytes)));
+    let object_guid = binary.chain(text)
+        .find(|(name, _)| name.eq_ignore_ascii_case("objectGUID"))
+        .and_then(|(_, value)| <[u8; 16]>::try_from(value?).ok())
+        .map(|bytes| Uuid::from_bytes_le(bytes).to_string());
+    object_guid.or_else(|| entry.attrs.iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("entryUUID"))
+        .and_then(|(_, values)| values.first())
+        .map(|value| value.to_lowercase()))
+}
+
+/// Удалить объект каталога по id, какого бы типа он ни был; `false` — его уже нет.
+/// OU удаляется, только если в ней ничего не осталось
+async fn delete_object(service: &DirectoryService, id: Uuid) -> Result<bool, DirectoryError> {
+    if service.get_user(id).await?.is_some() {
+        service.delete_user(id).await?;
+    } else if service.get_group(id).await?.is_some() {
+        service.delete_group(id).await?;
+    } else if service.get_contact(id).await?.is_some() {
+        service.delete_contact(id).await?;
+    } else if service.get_ou(id).await?.is_some() {
+        service.delete_ou_tree(id, false, OuContents::Refuse).await?;
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// highestCommittedUSN из RootDSE; есть только у Active Directory
+async fn read_highest_usn(ldap: &mut Ldap) -> Result<Option<u64>, SyncError> {
+    let (entries, _) = ldap.search("", Scope::Base, "(objectClass=*)", vec!["highestCommittedUSN"]).await?.success()?;
+    Ok(entries
+        .into_iter()
+        .next()
+        .map(SearchEntry::construct)
+        .and_then(|root| root.attrs.get("highestCommittedUSN")?.first()?.parse().ok()))
+}
+
+/// GeneralizedTime без дробной части и зоны: `20240101120000.0Z` → `20240101120000`
+fn timestamp_key(value: &str) -> &str {
+    value.split(['.', 'Z', '+', '-']).next().unwrap_or(value)
+}
+
+/// Итог цикла в журнал — для синхронизации по расписанию
+fn log_report(report: &SyncReport) {
+    tracing::info!(
+        full = report.full,
+        fetched = report.fetched,
+        created = report.import.created,
+        updated = report.import.updated,
+        skipped = report.import.skipped,
+        deleted = report.deleted,
+        "Синхронизация завершена",
+    );
+    for error in &report.import.errors {
+        tracing::warn!(%error, "Запись не синхронизирована");
+    }
+}
+
+/// Итог `sync --once` в консоль
+pub fn print_report(report: &SyncReport) {
+    println!(
+        "✅ {} синхронизация: получено {}, создано {}, обновлено {}, пропущено {}, удалено {}",
+        if report.full { "Полная" } else { "Инкрементальная" },
+        report.fetched, report.import.created, report.import.updated, report.import.skipped, report.deleted
+    );
+    for error in &report.import.errors {
+        eprintln!("⚠️  {}", error);
+    }
+}
@@ -0,0 +1,205 @@
+// src/sysvol.rs
+
+//! Содержимое папки GPO в SYSVOL (`Policies/{GUID}/...`) в тех форматах, которые ожидает
+//! Windows: `GPT.INI` с версией, `Registry.pol` из настроек типа Registry, `scripts.ini` из
+//! настроек типа Script и `GptTmpl.inf` из настроек типа Security. Файлы строятся из
+//! `GroupPolicy` при каждом запросе и нигде не хранятся.
+
+use crate::models::policy::{GroupPolicy, PolicyType, PolicyValue};
+
+pub const GPT_INI: &str = "GPT.INI";
+pub const MACHINE_REGISTRY_POL: &str = "Machine/Registry.pol";
+pub const USER_REGISTRY_POL: &str = "User/Registry.pol";
+pub const MACHINE_SCRIPTS_INI: &str = "Machine/Scripts/scripts.ini";
+pub const USER_SCRIPTS_INI: &str = "User/Scripts/scripts.ini";
+pub const SECURITY_TEMPLATE: &str = "Machine/Microsoft/Windows NT/SecEdit/GptTmpl.inf";
+
+/// Имя папки GPO в `Policies`: GUID в фигурных скобках, прописными
+pub fn folder_name(gpo: &GroupPolicy) -> String {
+    format!("{{{}}}", gpo.id.as_hyphenated().to_string().to_uppercase())
+}
+
+/// GUID из имени папки; скобки необязательны
+pub fn parse_folder_name(name: &str) -> Option<uuid::Uuid> {
+    let guid = name.strip_prefix('{').and_then(|guid| guid.strip_suffix('}')).unwrap_or(name);
+    uuid::Uuid::parse_str(guid).ok()
+}
+
+/// Пути файлов политики относительно её папки
+pub fn files(gpo: &GroupPolicy) -> Vec<&'static str> {
+    [GPT_INI, MACHINE_REGISTRY_POL, USER_REGISTRY_POL, MACHINE_SCRIPTS_INI, USER_SCRIPTS_INI, SECURITY_TEMPLATE]
+        .into_iter()
+        .filter(|path| render(gpo, path).is_some())
+        .collect()
+}
+
+/// Файл политики по пути относительно её папки; регистр и вид разделителя не важны, как в SMB
+pub fn render(gpo: &GroupPolicy, path: &str) -> Option<Vec<u8>> {
+    let path = path.trim_matches(|c| c == '/' || c == '\\').replace('\\', "/");
+    let is = |file: &str| path.eq_ignore_ascii_case(file);
+
+    if is(GPT_INI) {
+        Some(gpt_ini(gpo).into_bytes())
+    } else if is(MACHINE_REGISTRY_POL) {
+        registry_pol(gpo, "HKLM")
+    } else if is(USER_REGISTRY_POL) {
+        registry_pol(gpo, "HKCU")
+    } else if is(MACHINE_SCRIPTS_INI) {
+        scripts_ini(gpo, &[("Startup", "startup_scripts"), ("Shutdown", "shutdown_scripts")])
+    } else if is(USER_SCRIPTS_INI) {
+        scripts_ini(gpo, &[("Logon", "logon_scripts"), ("Logoff", "logoff_scripts")])
+    } else if is(SECURITY_TEMPLATE) {
+        security_template(gpo)
+    } else {
+        None
+    }
+}
+
+/// Версия в `GPT.INI`: в старших 16 битах — версия пользовательской части, в младших —
+/// компьютерной. Клиент перечитывает только ту часть, чья версия изменилась
+fn gpt_version(gpo: &GroupPolicy) -> u32 {
+    let version = gpo.version & 0xFFFF;
+    let has = |path| render(gpo, path).is_some();
+    let user = has(USER_REGISTRY_POL) || has(USER_SCRIPTS_INI);
+    let machine = has(MACHINE_REGISTRY_POL) || has(MACHINE_SCRIPTS_INI) || has(SECURITY_TEMPLATE);
+    match (machine, user) {
+        (false, true) => version << 16,
+        (true, true) => version << 16 | version,
+        _ => version,
+    }
+}
+
+fn gpt_ini(gpo: &GroupPolicy) -> String {
+    let display_name = gpo.display_name.as_deref().unwrap_or(&gpo.name);
+    format!("[General]\r\nVersion={}\r\ndisplayName={}\r\n", gpt_version(gpo), display_name)
+}
+
+fn string_setting<'a>(gpo: &'a GroupPolicy, key: &str) -> Option<&'a str> {
+    match gpo.get_setting(key) {
+        Some(PolicyValue::String(value)) => Some(value),
+        _ => None,
+    }
+}
+
+fn integer_setting(gpo: &GroupPolicy, key: &str) -> Option<i64> {
+    match gpo.get_setting(key) {
+        Some(PolicyValue::Integer(value)) => Some(*value),
+        Some(PolicyValue::Boolean(value)) => Some(i64::from(*value)),
+        _ => None,
+    }
+}
+
+fn utf16(text: &str) -> impl Iterator<Item = u8> + '_ {
+    text.encode_utf16().flat_map(u16::to_le_bytes)
+}
+
+/// Строка UTF-16LE с нулём в конце
+fn utf16z(text: &str) -> Vec<u8> {
+    utf16(text).chain([0, 0]).collect()
+}
+
+/// Текст UTF-16LE с BOM — так Windows пишет `scripts.ini` и `GptTmpl.inf`
+fn utf16_file(text: &str) -> Vec<u8> {
+    [0xFF, 0xFE].into_iter().chain(utf16(text)).collect()
+}
+
+/// Тип и данные параметра реестра в формате `Registry.pol`
+fn registry_data(value_type: &str, data: &PolicyValue) -> Option<(u32, Vec<u8>)> {
+    match (value_type.to_ascii_uppercase().as_str(), data) {
+        ("REG_SZ", PolicyValue::String(text)) => Some((1, utf16z(text))),
+        ("REG_EXPAND_SZ", PolicyValue::String(text)) => Some((2, utf16z(text))),
+        ("REG_BINARY", PolicyValue::Binary(bytes)) => Some((3, bytes.clone())),
+        ("REG_BINARY", PolicyValue::List(items)) => items.iter()
+            .map(|item| match item {
+                PolicyValue::Integer(byte) => u8::try_from(*byte).ok(),
+                _ => None,
+            })
+            .collect::<Option<Vec<u8>>>()
+            .map(|bytes| (3, bytes)),
+        ("REG_DWORD", PolicyValue::Integer(n)) => u32::try_from(*n).ok().map(|n| (4, n.to_le_bytes().to_vec())),
+        ("REG_MULTI_SZ", PolicyValue::List(items)) => {
+            let mut bytes = Vec::new();
+            for item in items {
+                let PolicyValue::String(text) = item else { return None };
+                bytes.extend(utf16z(text));
+            }
+            bytes.extend([0, 0]);
+            Some((7, bytes))
+        }
+        ("REG_QWORD", PolicyValue::Integer(n)) => u64::try_from(*n).ok().map(|n| (11, n.to_le_bytes().to_vec())),
+        _ => None,
+    }
+}
+
+/// `Registry.pol` (сигнатура `PReg`, версия 1, записи `[key;value;type;size;data]`) для
+/// параметра политики типа Registry в кусте `hive`
+fn registry_pol(gpo: &GroupPolicy, hive: &str) -> Option<Vec<u8>> {
+    if gpo.policy_type != PolicyType::Registry || !string_setting(gpo, "hive")?.eq_ignore_ascii_case(hive) {
+        return None;
+    }
+    let (value_type, data) = registry_data(string_setting(gpo, "value_type")?, gpo.get_setting("data")?)?;
+    let separator = || utf16(";");
+
+    let mut pol = b"PReg".to_vec();
+    pol.extend(1u32.to_le_bytes());
+    pol.extend(utf16("["));
+    pol.extend(utf16z(string_setting(gpo, "key")?));
+    pol.extend(separator());
+    pol.extend(utf16z(string_setting(gpo, "value_name")?));
+    pol.extend(separator());
+    pol.extend(value_type.to_le_bytes());
+    pol.extend(separator());
+    pol.extend(u32::try_from(data.len()).ok()?.to_le_bytes());
+    pol.extend(separator());
+    pol.extend(data);
+    pol.extend(utf16("]"));
+    Some(pol)
+}
+
+/// `scripts.ini`: секция на событие, сценарии нумеруются с 0. Сами сценарии клиент берёт по
+/// указанным путям
+fn scripts_ini(gpo: &GroupPolicy, sections: &[(&str, &str)]) -> Option<Vec<u8>> {
+    if gpo.policy_type != PolicyType::Script {
+        return None;
+    }
+    let mut ini = String::new();
+    for (section, key) in sections {
+        let Some(PolicyValue::List(scripts)) = gpo.get_setting(key) else { continue };
+        ini.push_str(&format!("\r\n[{}]\r\n", section));
+        let paths = scripts.iter().filter_map(|script| match script {
+            PolicyValue::String(path) => Some(path),
+            _ => None,
+        });
+        for (index, path) in paths.enumerate() {
+            ini.push_str(&format!("{index}CmdLine={path}\r\n{index}Parameters=\r\n"));
+        }
+    }
+    (!ini.is_empty()).then(|| utf16_file(&ini))
+}
+
+/// Настройки Security и соответствующие им ключи секции `[System Access]`
+const SYSTEM_ACCESS: &[(&str, &str)] = &[
+    ("min_password_age_days", "MinimumPasswordAge"),
+    ("max_password_age_days", "MaximumPasswordAge"),
+    ("min_password_length", "MinimumPasswordLength"),
+    ("password_complexity", "PasswordComplexity"),
+    ("password_history_size", "PasswordHistorySize"),
+    ("lockout_threshold", "LockoutBadCount"),
+    ("reset_lockout_count_minutes", "ResetLockoutCount"),
+    ("lockout_duration_minutes", "LockoutDuration"),
+];
+
+/// Шаблон безопасности `GptTmpl.inf` с политикой учётных записей
+fn security_template(gpo: &GroupPolicy) -> Option<Vec<u8>> {
+    if gpo.policy_type != PolicyType::Security {
+        return None;
+    }
+    let mut inf = String::from("[Unicode]\r\nUnicode=yes\r\n[System Access]\r\n");
+    for (setting, key) in SYSTEM_ACCESS {
+        if let Some(value) = integer_setting(gpo, setting) {
+            inf.push_str(&format!("{} = {}\r\n", key, value));
+        }
+    }
+    inf.push_str("[Version]\r\nsignature=\"$CHICAGO$\"\r\nRevision=1\r\n");
+    Some(utf16_file(&inf))
+}
@@ -0,0 +1,169 @@
+// src/telemetry.rs
+
+//! Логирование и трассировка по секции `logging`: текст или JSON в stdout либо `log_file`,
+//! уровень и переопределения для модулей, экспорт спанов по OTLP (gRPC) при `enable_tracing`.
+
+use std::fs::OpenOptions;
+use std::io::IsTerminal;
+use std::sync::{Mutex, OnceLock};
+
+use opentelemetry::propagation::Extractor;
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{self, Sampler};
+use opentelemetry_sdk::Resource;
+use opentelemetry_otlp::WithExportConfig;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+
+use crate::config::LoggingConfig;
+
+#[derive(Debug)]
+pub enum TelemetryError {
+    Config(String),
+    Exporter(String),
+}
+
+impl std::fmt::Display for TelemetryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TelemetryError::Config(e) => write!(f, "Config error: {}", e),
+            TelemetryError::Exporter(e) => write!(f, "OTLP exporter error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TelemetryError {}
+
+/// Замена фильтра уровней работающего подписчика (`reload_level`)
+type FilterReload = Box<dyn Fn(EnvFilter) -> Result<(), String> + Send + Sync>;
+
+static FILTER_RELOAD: OnceLock<FilterReload> = OnceLock::new();
+
+/// Держать до выхода из программы: при удалении отправляет оставшиеся спаны
+pub struct TelemetryGuard {
+    tracing: bool,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if self.tracing {
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
+/// Настроить логирование и, если включено, экспорт трассировки. Вызывать внутри runtime tokio.
+/// Переменная `RUST_LOG`, если задана, заменяет `level` и `modules`
+pub fn init(config: &LoggingConfig) -> Result<TelemetryGuard, TelemetryError> {
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(directives) => EnvFilter::try_new(directives).map_err(|e| TelemetryError::Config(format!("Invalid RUST_LOG: {}", e)))?,
+        Err(_) => level_filter(config)?,
+    };
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let output = output_layer(config)?;
+    let otel = match config.enable_tracing {
+        true => Some(tracing_opentelemetry::layer().with_tracer(otlp_tracer(config)?)),
+        false => None,
+    };
+
+    // Вместе с подписчиком ставится мост для крейтов на `log` (ldap3, rustls)
+    tracing_subscriber::registry()
+        .with(output)
+        .with(otel)
+        .with(filter)
+        .try_init()
+        .map_err(|e| TelemetryError::Config(e.to_string()))?;
+    let _ = FILTER_RELOAD.set(Box::new(move |filter| handle.reload(filter).map_err(|e| e.to_string())));
+
+    Ok(TelemetryGuard { tracing: config.enable_tracing })
+}
+
+/// Проверить `level` и `modules` без применения
+pub fn check_level(config: &LoggingConfig) -> Result<(), TelemetryError> {
+    level_filter(config).map(|_| ())
+}
+
+/// Применить новые `level` и `modules` без перезапуска. `Ok(false)` — не применено: уровни задаёт
+/// `RUST_LOG` или логирование не настроено через `init`
+pub fn reload_level(config: &LoggingConfig) -> Result<bool, TelemetryError> {
+    let filter = level_filter(config)?;
+    let Some(reload) = FILTER_RELOAD.get() else {
+        return Ok(false);
+    };
+    if std::env::var_os("RUST_LOG").is_some() {
+        return Ok(false);
+    }
+    reload(filter).map_err(TelemetryError::Config)?;
+    Ok(true)
+}
+
+/// `level` для всего и `modules` — уровни отдельных модулей (`nextDomen::kerberos: DEBUG`)
+fn level_filter(config: &LoggingConfig) -> Result<EnvFilter, TelemetryError> {
+    let mut directives = vec![config.level.to_lowercase()];
+    for (module, level) in &config.modules {
+        directives.push(format!("{}={}", module, level.to_lowercase()));
+    }
+    EnvFilter::try_new(directives.join(","))
+        .map_err(|e| TelemetryError::Config(format!("Invalid logging level: {}", e)))
+}
+
+/// Текст или JSON; в `log_file` (дописывается) или в stdout
+fn output_layer(config: &LoggingConfig) -> Result<Box<dyn Layer<Registry> + Send + Sync>, TelemetryError> {
+    let layer = tracing_subscriber::fmt::layer();
+    let layer = match &config.log_file {
+        Some(path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)
+                .map_err(|e| TelemetryError::Config(format!("Cannot open log file {}: {}", path, e)))?;
+            let layer = layer.with_writer(Mutex::new(file)).with_ansi(false);
+            match config.enable_json_output {
+                true => layer.json().boxed(),
+                false => layer.boxed(),
+            }
+        }
+        None => match config.enable_json_output {
+            true => layer.json().boxed(),
+            false => layer.with_ansi(std::io::stdout().is_terminal()).boxed(),
+        },
+    };
+    Ok(layer)
+}
+
+fn otlp_tracer(config: &LoggingConfig) -> Result<opentelemetry_sdk::trace::Tracer, TelemetryError> {
+    if !(0.0..=1.0).contains(&config.trace_sampling_ratio) {
+        return Err(TelemetryError::Config("logging.trace_sampling_ratio must be between 0 and 1".to_string()));
+    }
+
+    // Решение о выборке принимает вызывающая сторона, если передала traceparent
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.trace_sampling_ratio)));
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(config.otlp_endpoint.clone()))
+        .with_trace_config(trace::config()
+            .with_sampler(sampler)
+            .with_resource(Resource::new(vec![KeyValue::new("service.name", config.service_name.clone())])))
+        .install_batch(opentelemetry_sdk::runtime::Tokio)
+        .map_err(|e| TelemetryError::Exporter(e.to_string()))?;
+
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    Ok(tracer)
+}
+
+/// Контекст вызывающей стороны из заголовков `traceparent` / `tracestate`
+pub fn extract_context(headers: &axum::http::HeaderMap) -> opentelemetry::Context {
+    opentelemetry::global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+struct HeaderExtractor<'a>(&'a axum::http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
@@ -0,0 +1,94 @@
+// src/tls_bootstrap.rs
+
+//! Сертификаты TLS при первом запуске: если у сервера включён `enable_tls`, а ни сертификата,
+//! ни ключа ещё нет, они выпускаются внутренним CA (если он создан, `nextDomen ca init`)
+//! или самоподписанными и сохраняются в `paths.certs_dir`. Отпечаток SHA-256 пишется в журнал,
+//! чтобы клиенты могли сверить его при первом подключении.
+
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use crate::config::TlsConfig;
+use crate::directory_service::DirectoryService;
+
+type BoxError = Box<dyn std::error::Error>;
+
+/// Срок самоподписанного сертификата, дней
+const SELF_SIGNED_VALIDITY_DAYS: i64 = 365;
+
+/// Новый сертификат сервера
+#[derive(Debug, Clone)]
+pub struct BootstrappedCertificate {
+    /// SHA-256 от DER, байты через двоеточие (`AB:CD:...`)
+    pub fingerprint: String,
+    /// Подписан внутренним CA, а не самим собой
+    pub ca_signed: bool,
+}
+
+/// Подставить в `tls` пути сертификата и ключа сервера `server` и создать их, если обоих нет.
+/// `None` — файлы уже были; один файл без другого — ошибка: перезаписывать его нельзя
+pub async fn ensure_certificate(
+    service: &DirectoryService,
+    server: &str,
+    tls: &mut TlsConfig,
+    certs_dir: &Path,
+) -> Result<Option<BootstrappedCertificate>, BoxError> {
+    let (cert_path, key_path) = tls.files(server, certs_dir);
+    tls.cert_file = Some(cert_path.display().to_string());
+    tls.key_file = Some(key_path.display().to_string());
+    match (cert_path.exists(), key_path.exists()) {
+        (true, true) => return Ok(None),
+        (false, false) => {}
+        (true, false) => return Err(format!("{} exists but {} is missing", cert_path.display(), key_path.display()).into()),
+        (false, true) => return Err(format!("{} exists but {} is missing", key_path.display(), cert_path.display()).into()),
+    }
+
+    let names = server_names(service).await?;
+    let (cert_pem, key_pem, ca_signed) = match service.issue_server_certificate(&names).await? {
+        Some((cert_pem, key_pem)) => (cert_pem, key_pem, true),
+        None => {
+            let (cert_pem, key_pem) = crate::ca::self_signed(&names, chrono::Duration::days(SELF_SIGNED_VALIDITY_DAYS))?;
+            (cert_pem, key_pem, false)
+        }
+    };
+    for path in [&cert_path, &key_path] {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+    }
+    std::fs::write(&cert_path, &cert_pem)?;
+    crate::init::write_secret(&key_path, key_pem.as_bytes())?;
+
+    let fingerprint = fingerprint(&crate::ca::pem_to_der(&cert_pem)?);
+    tracing::info!(
+        server, cert = %cert_path.display(), key = %key_path.display(), sans = %names.join(","), %fingerprint,
+        issuer = if ca_signed { "internal CA" } else { "self-signed" },
+        "Создан сертификат TLS: сверьте отпечаток при первом подключении клиентов"
+    );
+    Ok(Some(BootstrappedCertificate { fingerprint, ca_signed }))
+}
+
+/// SHA-256 от DER сертификата, как его показывают браузеры и `openssl x509 -fingerprint -sha256`
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der).iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(":")
+}
+
+/// Имя узла, оно же в каждом домене каталога, и `localhost`
+async fn server_names(service: &DirectoryService) -> Result<Vec<String>, BoxError> {
+    let mut names = Vec::new();
+    let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|hostname| hostname.trim().to_lowercase())
+        .unwrap_or_default();
+    if !hostname.is_empty() && hostname != "localhost" {
+        names.push(hostname.clone());
+        for domain in service.get_all_domains().await? {
+            let fqdn = format!("{}.{}", hostname, domain.dns_name.to_lowercase());
+            if !hostname.contains('.') && !names.contains(&fqdn) {
+                names.push(fqdn);
+            }
+        }
+    }
+    names.push("localhost".to_string());
+    Ok(names)
+}
@@ -1,25 +1,66 @@
 // src/web.rs
 
 use axum::{
-    routing::{get, post, delete},
+    routing::{get, post, put, delete},
     Router,
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::IntoResponse,
-    http::StatusCode,
+    http::{header, StatusCode},
+    Extension,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
-use crate::directory_service::{DirectoryService, DirectoryError};
+use crate::config::{AppConfig, DEFAULT_WEB_ADDRESS};
+use crate::directory_service::{DirectoryService, DirectoryError, SearchLimits};
+use crate::error_code::ErrorCode;
+use crate::middleware::{Authorized, Caller, ConfigReload, DirectoryWrite, Reader};
+use crate::proxy::TrustedProxies;
+use crate::reload::ConfigReloader;
+use crate::models::{AccessMask, SchemaClass, SecuredObject, SecurityIdentifier, SidError};
+
+pub mod access_log;
+pub mod acl;
+pub mod agent;
+pub mod apikeys;
+pub mod approvals;
+pub mod audit;
+pub mod bulk;
+pub mod ca;
+pub mod changes;
+pub mod contacts;
+pub mod cors;
+pub mod errors;
+pub mod events;
+pub mod group_requests;
+pub mod invitations;
+pub mod listener;
+pub mod login;
+pub mod me;
+pub mod metrics;
+pub mod objects;
+pub mod oidc;
+pub mod openapi;
+pub mod patch;
+pub mod password_policies;
+pub mod password_reset;
+pub mod photos;
+pub mod replication;
+pub mod schema;
+pub mod service_accounts;
+pub mod sessions;
+pub mod sites;
+pub mod sysvol;
 
 // === Тип состояния ===
 pub type SharedService = Arc<DirectoryService>;
 
 // === Запросы ===
 
-#[derive(Deserialize)]
+#[derive(Deserialize, utoipa::ToSchema)]
 pub struct CreateUserRequest {
     pub username: String,
     #[serde(default)]
@@ -30,13 +71,14 @@ pub struct CreateUserRequest {
     pub given_name: Option<String>,
     #[serde(default)]
     pub surname: Option<String>,
+    /// Дополнительные атрибуты из схемы (`/api/schema/attributes`)
+    #[serde(default)]
+    pub attributes: Option<HashMap<String, Vec<String>>>,
 }
 
 impl CreateUserRequest {
     fn validate(&self) -> Result<(), DirectoryError> {
-        if self.username.is_empty() || self.username.len() > 64 {
-            return Err(DirectoryError::InvalidInput("Username must be 1-64 characters".to_string()));
-        }
+        crate::models::validate_username(&self.username).map_err(DirectoryError::InvalidInput)?;
         if let Some(email) = &self.email {
             if !email.contains('@') {
                 return Err(DirectoryError::InvalidInput("Invalid email format".to_string()));
@@ -46,7 +88,7 @@ impl CreateUserRequest {
     }
 }
 
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, utoipa::ToSchema, Default)]
 pub struct UpdateUserRequest {
     #[serde(default)]
     pub email: Option<String>,
@@ -58,13 +100,69 @@ pub struct UpdateUserRequest {
     pub surname: Option<String>,
     #[serde(default)]
     pub enabled: Option<bool>,
+    /// sAMAccountName новой основной группы (primaryGroupID): статическая группа безопасности
+    /// Global или Universal, в которой пользователь уже состоит постоянно
+    #[serde(default)]
+    pub primary_group: Option<String>,
+    /// Дополнительные атрибуты из схемы; пустой список значений удаляет атрибут
+    #[serde(default)]
+    pub attributes: Option<HashMap<String, Vec<String>>>,
+}
+
+/// Флаги userAccountControl по именам (`DONT_EXPIRE_PASSWORD`, `SMARTCARD_REQUIRED`, ...)
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UserAccountControlRequest {
+    /// Установить
+    #[serde(default)]
+    pub set: Vec<String>,
+    /// Снять; снятый `LOCKOUT` разблокирует учётную запись
+    #[serde(default)]
+    pub clear: Vec<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, utoipa::ToSchema)]
 pub struct CreateGroupRequest {
     pub name: String,
     #[serde(default)]
     pub sam_account_name: Option<String>,
+    /// Правило членства (фильтр LDAP), например `(&(ou:dn:=Sales)(mail=*))`; без него группа статическая
+    #[serde(default)]
+    pub membership_rule: Option<String>,
+    /// Дополнительные атрибуты из схемы (`/api/schema/attributes`)
+    #[serde(default)]
+    pub attributes: Option<HashMap<String, Vec<String>>>,
+}
+
+/// Изменение группы; не указанные поля не меняются
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UpdateGroupRequest {
+    pub name: Option<String>,
+    /// Пустая строка удаляет описание
+    pub description: Option<String>,
+    /// Между DomainLocal и Global — только через Universal; новая область должна допускать
+    /// вложенные группы и группы, в которые вложена эта
+    pub scope: Option<crate::models::GroupScope>,
+    /// `true` — группа безопасности, `false` — группа рассылки
+    pub security: Option<bool>,
+    /// Дополнительные атрибуты из схемы; пустой список значений удаляет атрибут
+    #[serde(default)]
+    pub attributes: Option<HashMap<String, Vec<String>>>,
+}
+
+/// Страница участников группы
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct GroupMembersQuery {
+    /// `next_cursor` предыдущей страницы; по умолчанию с начала
+    #[serde(default)]
+    pub cursor: usize,
+    /// Участников на странице (по умолчанию 100, не больше 1000)
+    pub limit: Option<usize>,
+}
+
+/// Правило членства группы; `null` делает группу статической
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct MembershipRuleRequest {
+    pub membership_rule: Option<String>,
 }
 
 impl CreateGroupRequest {
@@ -76,11 +174,43 @@ impl CreateGroupRequest {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, utoipa::ToSchema)]
 pub struct CreateOuRequest {
     pub name: String,
     #[serde(default)]
     pub parent: Option<String>,
+    /// Дополнительные атрибуты из схемы (`/api/schema/attributes`)
+    #[serde(default)]
+    pub attributes: Option<HashMap<String, Vec<String>>>,
+}
+
+/// Переименование и перенос OU; не указанные поля не меняются
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UpdateOuRequest {
+    pub name: Option<String>,
+    /// DN нового родителя: другая OU или корень домена
+    pub parent: Option<String>,
+    /// Дополнительные атрибуты из схемы; пустой список значений удаляет атрибут
+    #[serde(default)]
+    pub attributes: Option<HashMap<String, Vec<String>>>,
+}
+
+/// Поиск по индексируемому атрибуту схемы
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct ListUsersQuery {
+    pub attribute: Option<String>,
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct DeleteOuQuery {
+    /// Удалить вместе с вложенными OU
+    #[serde(default)]
+    pub recursive: bool,
+    /// Что делать с пользователями и контактами: `refuse` (по умолчанию), `move-to-parent`, `delete`
+    #[serde(default)]
+    #[param(inline)]
+    pub contents: crate::models::OuContents,
 }
 
 impl CreateOuRequest {
@@ -92,7 +222,7 @@ impl CreateOuRequest {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, utoipa::ToSchema)]
 pub struct CreateGpoRequest {
     pub name: String,
     #[serde(default)]
@@ -105,6 +235,14 @@ pub struct CreateGpoRequest {
     pub enforced: bool,
     #[serde(default)]
     pub enabled: bool,
+    /// `Security`, `Registry`, `Script`, `Network`, `Software`, `FolderRedirection` или своё имя
+    /// (настройки без схемы); по умолчанию `Custom`
+    #[serde(default)]
+    pub policy_type: Option<String>,
+    /// Настройки по схеме типа из `GET /api/gpos/schemas`
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub settings: std::collections::HashMap<String, crate::models::policy::PolicyValue>,
 }
 
 impl CreateGpoRequest {
@@ -121,15 +259,33 @@ impl CreateGpoRequest {
 
 // === Ответы ===
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
 pub struct UserResponse {
     pub id: uuid::Uuid,
+    /// objectSid в строковой форме
+    #[serde(default)]
+    pub sid: String,
     pub username: String,
     pub email: Option<String>,
     pub display_name: Option<String>,
     pub given_name: Option<String>,
     pub surname: Option<String>,
     pub enabled: bool,
+    /// Заблокирована после неудачных попыток входа
+    pub locked_out: bool,
+    /// Когда истекает пароль; после этого вход только после смены пароля
+    #[serde(default)]
+    pub password_expires: Option<chrono::DateTime<chrono::Utc>>,
+    /// userAccountControl числом, как в LDAP
+    pub user_account_control: u32,
+    /// Имена установленных флагов userAccountControl
+    pub account_flags: Vec<String>,
+    /// RID основной группы (primaryGroupID), по умолчанию 513 — Domain Users
+    #[serde(default)]
+    pub primary_group_id: Option<u32>,
+    /// Дополнительные атрибуты (`meta`); многозначные — списком
+    #[serde(default)]
+    pub attributes: BTreeMap<String, Vec<String>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub last_login: Option<chrono::DateTime<chrono::Utc>>,
@@ -137,14 +293,23 @@ pub struct UserResponse {
 
 impl From<crate::models::User> for UserResponse {
     fn from(user: crate::models::User) -> Self {
+        let account_control = user.account_control();
+        let locked_out = user.is_locked_out();
         Self {
             id: user.id,
+            sid: user.sid.to_string(),
             username: user.username,
             email: user.email,
             display_name: user.display_name,
             given_name: user.given_name,
             surname: user.surname,
             enabled: user.enabled,
+            locked_out,
+            password_expires: user.password_expires,
+            user_account_control: account_control.bits(),
+            account_flags: account_control.flag_names(),
+            primary_group_id: user.primary_group_id,
+            attributes: attribute_values(user.meta),
             created_at: user.created_at,
             updated_at: user.updated_at,
             last_login: user.last_login,
@@ -152,32 +317,94 @@ impl From<crate::models::User> for UserResponse {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
 pub struct GroupResponse {
     pub id: uuid::Uuid,
+    /// objectSid в строковой форме
+    #[serde(default)]
+    pub sid: String,
     pub name: String,
     pub sam_account_name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub scope: crate::models::GroupScope,
+    /// Группа безопасности; `false` — группа рассылки
+    pub security: bool,
     pub members_count: usize,
+    /// Правило членства динамической группы; `members_count` — по последнему снимку
+    #[serde(default)]
+    pub membership_rule: Option<String>,
+    /// id пользователя-владельца (managedBy)
+    #[serde(default)]
+    pub managed_by: Option<uuid::Uuid>,
+    #[serde(default)]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Отключена по истечении срока: членство не действует
+    #[serde(default)]
+    pub disabled: bool,
+    /// Дополнительные атрибуты (`meta`); многозначные — списком
+    #[serde(default)]
+    pub attributes: BTreeMap<String, Vec<String>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
 impl From<crate::models::Group> for GroupResponse {
     fn from(group: crate::models::Group) -> Self {
+        let disabled = group.is_disabled();
+        let security = group.is_security_group();
         Self {
             id: group.id,
+            sid: group.sid.to_string(),
             name: group.name,
             sam_account_name: group.sam_account_name,
+            description: group.description,
+            scope: group.scope,
+            security,
             members_count: group.members.len(),
+            membership_rule: group.membership_rule,
+            managed_by: group.managed_by,
+            expires_at: group.expires_at,
+            disabled,
+            attributes: attribute_values(group.meta),
             created_at: group.created_at,
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct GroupMemberResponse {
+    pub id: uuid::Uuid,
+    /// Имя пользователя; у вложенной группы — её sAMAccountName
+    pub username: String,
+    pub display_name: Option<String>,
+    /// `user` или `group` — вложенная группа
+    pub object_class: String,
+    /// Окончание временного членства; `null` — постоянное
+    #[serde(default)]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Сколько секунд осталось временному членству
+    #[serde(default)]
+    pub ttl_secs: Option<i64>,
+}
+
+/// Группа и страница её участников; у динамической группы — по последнему снимку
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct GroupDetailsResponse {
+    #[serde(flatten)]
+    pub group: GroupResponse,
+    pub members: Vec<GroupMemberResponse>,
+    /// Курсор следующей страницы участников; `null` — страница последняя
+    pub next_cursor: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
 pub struct OuResponse {
     pub id: uuid::Uuid,
     pub name: String,
     pub dn: String,
+    /// Дополнительные атрибуты (`meta`); многозначные — списком
+    #[serde(default)]
+    pub attributes: BTreeMap<String, Vec<String>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -188,13 +415,54 @@ impl From<crate::models::OrganizationalUnit> for OuResponse {
             id: ou.id,
             name: ou.name,
             dn: ou.dn,
+            attributes: attribute_values(ou.meta),
             created_at: ou.created_at,
             updated_at: ou.updated_at,
         }
     }
 }
 
-#[derive(Serialize)]
+fn attribute_values(meta: HashMap<String, String>) -> BTreeMap<String, Vec<String>> {
+    meta.into_iter().map(|(name, raw)| (name, crate::models::schema::values(&raw))).collect()
+}
+
+/// OU с вложенными OU — `GET /api/ous/tree`
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct OuTreeNode {
+    #[serde(flatten)]
+    pub ou: OuResponse,
+    #[schema(no_recursion)]
+    pub children: Vec<OuTreeNode>,
+}
+
+impl OuTreeNode {
+    /// Дерево по DN; корни — OU, родитель которых не OU (обычно корень домена). Соседи — по имени
+    pub fn build(ous: Vec<crate::models::OrganizationalUnit>) -> Vec<OuTreeNode> {
+        let dns: std::collections::HashSet<String> = ous.iter().filter_map(|ou| crate::dn::canonical(&ou.dn)).collect();
+        let mut levels: std::collections::HashMap<Option<String>, Vec<OuResponse>> = std::collections::HashMap::new();
+        for ou in ous {
+            let parent = crate::dn::Dn::parse(&ou.dn).ok()
+                .and_then(|dn| dn.parent())
+                .map(|parent| parent.canonical())
+                .filter(|parent| dns.contains(parent));
+            levels.entry(parent).or_default().push(ou.into());
+        }
+        Self::attach(None, &mut levels)
+    }
+
+    fn attach(parent: Option<String>, levels: &mut std::collections::HashMap<Option<String>, Vec<OuResponse>>) -> Vec<OuTreeNode> {
+        let mut level = levels.remove(&parent).unwrap_or_default();
+        level.sort_by(|a, b| a.name.cmp(&b.name));
+        level.into_iter()
+            .map(|ou| {
+                let children = Self::attach(crate::dn::canonical(&ou.dn), levels);
+                OuTreeNode { ou, children }
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
 pub struct GpoResponse {
     pub id: uuid::Uuid,
     pub name: String,
@@ -203,6 +471,11 @@ pub struct GpoResponse {
     pub linked_to: Vec<uuid::Uuid>,
     pub enforced: bool,
     pub enabled: bool,
+    #[serde(default)]
+    pub policy_type: String,
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub settings: std::collections::HashMap<String, crate::models::policy::PolicyValue>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -217,6 +490,8 @@ impl From<crate::models::policy::GroupPolicy> for GpoResponse {
             linked_to: gpo.linked_to,
             enforced: gpo.enforced,
             enabled: gpo.enabled,
+            policy_type: gpo.policy_type.name().to_string(),
+            settings: gpo.settings,
             created_at: gpo.created_at,
             updated_at: gpo.updated_at,
         }
@@ -227,56 +502,217 @@ impl From<crate::models::policy::GroupPolicy> for GpoResponse {
 
 impl IntoResponse for DirectoryError {
     fn into_response(self) -> axum::response::Response {
-        let (status, body) = match &self {
-            DirectoryError::NotFound(msg) => (
-                StatusCode::NOT_FOUND,
-                json!({ "error": msg }),
-            ),
-            DirectoryError::AlreadyExists(msg) => (
-                StatusCode::CONFLICT,
-                json!({ "error": msg }),
-            ),
-            DirectoryError::InvalidInput(msg) | DirectoryError::Serialization(msg) => (
-                StatusCode::BAD_REQUEST,
-                json!({ "error": msg }),
-            ),
-            DirectoryError::DbError(_) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                json!({ "error": "Database error" }),
-            ),
+        let status = match &self {
+            DirectoryError::NotFound(_) => StatusCode::NOT_FOUND,
+            DirectoryError::AlreadyExists(..) => StatusCode::CONFLICT,
+            DirectoryError::InvalidInput(_) | DirectoryError::Serialization(_) | DirectoryError::PasswordPolicy(_) => StatusCode::BAD_REQUEST,
+            // Код ошибки отличает истёкший пароль от неверного: клиент предлагает сменить пароль
+            DirectoryError::AuthenticationFailed(_) | DirectoryError::PasswordExpired(_) | DirectoryError::MfaRequired(_) => {
+                StatusCode::UNAUTHORIZED
+            }
+            DirectoryError::DbError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            DirectoryError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            DirectoryError::Protected(_) | DirectoryError::AccessDenied(_) => StatusCode::FORBIDDEN,
+            // Реплика только для чтения отсылает к записываемому контроллеру
+            DirectoryError::ReadOnly(_) => StatusCode::MISDIRECTED_REQUEST,
+            DirectoryError::DatabaseReadOnly => StatusCode::SERVICE_UNAVAILABLE,
+            // Пределы выдачи списка (`web_server.size_limit`, `web_server.time_limit_secs`)
+            DirectoryError::SizeLimitExceeded(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            DirectoryError::TimeLimitExceeded(_) => StatusCode::REQUEST_TIMEOUT,
+            DirectoryError::SessionLimitExceeded(_) => StatusCode::CONFLICT,
+        };
+        let detail = match &self {
+            DirectoryError::NotFound(msg)
+            | DirectoryError::AlreadyExists(_, msg)
+            | DirectoryError::InvalidInput(msg)
+            | DirectoryError::Serialization(msg)
+            | DirectoryError::PasswordPolicy(msg)
+            | DirectoryError::AuthenticationFailed(msg)
+            | DirectoryError::Protected(msg)
+            | DirectoryError::AccessDenied(msg) => msg.clone(),
+            DirectoryError::DbError(_) => "Database error".to_string(),
+            _ => self.to_string(),
         };
-        (status, Json(body)).into_response()
+
+        let mut body = errors::problem_body(status, self.code(), &detail);
+        match &self {
+            DirectoryError::ReadOnly(upstream) => body["referral"] = json!(upstream),
+            DirectoryError::SizeLimitExceeded(limit) => body["size_limit"] = json!(limit),
+            DirectoryError::TimeLimitExceeded(secs) => body["time_limit_secs"] = json!(secs),
+            DirectoryError::SessionLimitExceeded(limit) => body["max_concurrent"] = json!(limit),
+            DirectoryError::MfaRequired(methods) => body["methods"] = json!(methods),
+            _ => {}
+        }
+        let mut response = errors::problem_response(status, body);
+        if let DirectoryError::RateLimited(secs) = self {
+            response.headers_mut().insert(header::RETRY_AFTER, secs.into());
+        }
+        response
     }
 }
 
 // === Обработчики: Users ===
 
+#[utoipa::path(get, path = "/api/users", tag = "users",
+    params(ListUsersQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Пользователи с правом `READ_PROPERTY`: все или с заданным значением атрибута", body = Vec<UserResponse>),
+        (status = 400, description = "Атрибут не определён или не индексируется", body = openapi::ErrorBody),
+        (status = 408, description = "Превышен предел времени `web_server.time_limit_secs`", body = openapi::ErrorBody),
+        (status = 413, description = "Превышен предел `web_server.size_limit`", body = openapi::ErrorBody),
+    ))]
 async fn list_users(
+    reader: Reader,
     State(service): State<SharedService>,
+    Extension(limits): Extension<SearchLimits>,
+    Query(query): Query<ListUsersQuery>,
 ) -> Result<Json<Vec<UserResponse>>, DirectoryError> {
-    let users = service.get_all_users().await?;
+    let users = match &query.attribute {
+        Some(attribute) => {
+            let ids = service.find_by_attribute(attribute, query.value.as_deref().unwrap_or_default()).await?;
+            if limits.size_limit > 0 && ids.len() > limits.size_limit {
+                return Err(DirectoryError::SizeLimitExceeded(limits.size_limit));
+            }
+            let mut users = Vec::new();
+            for id in ids {
+                users.extend(service.get_user(id).await?);
+            }
+            users
+        }
+        None => service.list_users_within(limits).await?,
+    };
+    let users = service.filter_authorized(&reader.user, users, |user| SecuredObject::User(user.id), AccessMask::READ_PROPERTY).await?;
     Ok(Json(users.into_iter().map(UserResponse::from).collect()))
 }
 
+#[utoipa::path(get, path = "/api/users/{username}", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `READ_PROPERTY`", body = openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = openapi::ErrorBody),
+    ))]
 async fn get_user(
+    reader: Reader,
     Path(username): Path<String>,
     State(service): State<SharedService>,
 ) -> Result<Json<UserResponse>, DirectoryError> {
     let user = service.find_user_by_username(&username)
         .await?
         .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+    service.authorize(&reader.user, SecuredObject::User(user.id), AccessMask::READ_PROPERTY).await?;
+    Ok(Json(UserResponse::from(user)))
+}
+
+#[utoipa::path(get, path = "/api/users/by-id/{id}", tag = "users",
+    params(("id" = uuid::Uuid, Path, description = "id пользователя")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `READ_PROPERTY`", body = openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = openapi::ErrorBody),
+    ))]
+async fn get_user_by_id(
+    reader: Reader,
+    Path(id): Path<uuid::Uuid>,
+    State(service): State<SharedService>,
+) -> Result<Json<UserResponse>, DirectoryError> {
+    let user = service.get_user(id)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", id)))?;
+    service.authorize(&reader.user, SecuredObject::User(user.id), AccessMask::READ_PROPERTY).await?;
     Ok(Json(UserResponse::from(user)))
 }
 
+#[utoipa::path(get, path = "/api/users/by-sid/{sid}", tag = "users",
+    params(("sid" = String, Path, description = "objectSid, например S-1-5-21-1-2-3-1105")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 400, description = "Неверный SID", body = openapi::ErrorBody),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `READ_PROPERTY`", body = openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = openapi::ErrorBody),
+    ))]
+async fn get_user_by_sid(
+    reader: Reader,
+    Path(sid): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<UserResponse>, DirectoryError> {
+    let user = service.find_user_by_sid(&parse_sid(&sid)?)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", sid)))?;
+    service.authorize(&reader.user, SecuredObject::User(user.id), AccessMask::READ_PROPERTY).await?;
+    Ok(Json(UserResponse::from(user)))
+}
+
+fn parse_sid(sid: &str) -> Result<SecurityIdentifier, DirectoryError> {
+    sid.parse().map_err(|e: SidError| DirectoryError::InvalidInput(e.to_string()))
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct LoginHistoryQuery {
+    /// Не больше записей (по умолчанию все хранимые, последние 100)
+    pub limit: Option<usize>,
+}
+
+#[utoipa::path(get, path = "/api/users/{username}/logins", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя"), LoginHistoryQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Попытки входа по паролю, последние первыми", body = [crate::models::LoginRecord]),
+        (status = 403, description = "Нет права `READ_PROPERTY` на пользователя", body = openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = openapi::ErrorBody),
+    ))]
+async fn list_user_logins(
+    reader: Reader,
+    Path(username): Path<String>,
+    Query(query): Query<LoginHistoryQuery>,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<crate::models::LoginRecord>>, DirectoryError> {
+    let user = service.find_user_by_username(&username).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+    service.authorize(&reader.user, SecuredObject::User(user.id), AccessMask::READ_PROPERTY).await?;
+
+    let mut logins = service.get_login_history(user.id).await?;
+    if let Some(limit) = query.limit {
+        logins.truncate(limit);
+    }
+    Ok(Json(logins))
+}
+
+#[utoipa::path(post, path = "/api/users", tag = "users",
+    request_body = CreateUserRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "Пользователь создан", body = UserResponse),
+        (status = 400, description = "Неверные данные", body = openapi::ErrorBody),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `CREATE_CHILD` на корень домена", body = openapi::ErrorBody),
+        (status = 409, description = "Имя уже занято", body = openapi::ErrorBody),
+    ))]
 async fn create_user(
+    caller: Caller,
     State(service): State<SharedService>,
     Json(payload): Json<CreateUserRequest>,
 ) -> Result<impl IntoResponse, DirectoryError> {
+    let user = new_user(&service, payload).await?;
+    service.authorize_create(&caller.user, user.organizational_unit).await?;
+    service.create_user(&user).await?;
+    Ok((StatusCode::CREATED, Json(UserResponse::from(user))))
+}
+
+/// Новый включённый пользователь из запроса; REST-создание и приглашения
+async fn new_user(service: &SharedService, payload: CreateUserRequest) -> Result<crate::models::User, DirectoryError> {
     payload.validate()?;
-    
-    use crate::models::{SecurityIdentifier, PasswordHash, PasswordAlgorithm};
 
-    let user = crate::models::User {
+    use crate::models::{PasswordHash, PasswordAlgorithm, UserAccountControl};
+
+    let mut user = crate::models::User {
         id: uuid::Uuid::new_v4(),
         sid: SecurityIdentifier::new_nt_authority(1001),
         username: payload.username.clone(),
@@ -307,13 +743,31 @@ async fn create_user(
         script_path: None,
         meta: std::collections::HashMap::new(),
         primary_group_id: Some(513),
+        service_principal_names: vec![],
+        kerberos_keys: vec![],
+        user_account_control: UserAccountControl::NORMAL_ACCOUNT,
+        totp_secret: None,
+        totp_pending_secret: None,
     };
-
-    service.create_user(&user).await?;
-    Ok((StatusCode::CREATED, Json(UserResponse::from(user))))
+    if let Some(attributes) = payload.attributes {
+        crate::models::schema::apply_changes(&mut user.meta, service.schema_attribute_changes(SchemaClass::User, attributes).await?);
+    }
+    Ok(user)
 }
 
+#[utoipa::path(put, path = "/api/users/{username}", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    request_body = UpdateUserRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 400, description = "Группа не может быть основной или пользователь не её постоянный участник", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` на пользователя", body = openapi::ErrorBody),
+        (status = 404, description = "Пользователь или основная группа не найдены", body = openapi::ErrorBody),
+        (status = 409, description = "Email уже используется", body = openapi::ErrorBody),
+    ))]
 async fn update_user(
+    caller: Caller,
     Path(username): Path<String>,
     State(service): State<SharedService>,
     Json(payload): Json<UpdateUserRequest>,
@@ -321,11 +775,12 @@ async fn update_user(
     let mut user = service.find_user_by_username(&username)
         .await?
         .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+    service.authorize(&caller.user, SecuredObject::User(user.id), AccessMask::WRITE_PROPERTY).await?;
 
     if let Some(email) = &payload.email {
         if let Some(existing) = service.find_user_by_email(email).await? {
             if existing.id != user.id {
-                return Err(DirectoryError::AlreadyExists("Email already in use".to_string()));
+                return Err(DirectoryError::AlreadyExists(ErrorCode::EmailAlreadyExists, "Email already in use".to_string()));
             }
         }
         user.email = Some(email.clone());
@@ -347,62 +802,362 @@ async fn update_user(
         user.enabled = enabled;
     }
 
+    if let Some(primary_group) = &payload.primary_group {
+        let group = service.find_group_by_sam_account_name(primary_group)
+            .await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", primary_group)))?;
+        user.primary_group_id = Some(group.get_rid());
+    }
+
+    if let Some(attributes) = &payload.attributes {
+        crate::models::schema::apply_changes(&mut user.meta, service.schema_attribute_changes(SchemaClass::User, attributes.clone()).await?);
+    }
+
     user.updated_at = chrono::Utc::now();
     service.update_user(&user).await?;
 
     Ok(Json(UserResponse::from(user)))
 }
 
+/// Поля `UserResponse`, которые меняет PATCH, и их атрибуты LDAP
+const USER_PATCH_FIELDS: &[patch::PatchField] = &[
+    ("username", "sAMAccountName"),
+    ("email", "mail"),
+    ("display_name", "displayName"),
+    ("given_name", "givenName"),
+    ("surname", "sn"),
+];
+
+#[utoipa::path(patch, path = "/api/users/{username}", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    request_body(
+        description = "Merge patch: `null` очищает поле; JSON Patch: `add`, `remove`, `replace`, `test` по путям `/email`, `/attributes/<имя>`, `/attributes/<имя>/-`",
+        content((Object = "application/merge-patch+json"), (Vec<patch::PatchOperation> = "application/json-patch+json")),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 400, description = "Поле только для чтения, неверное значение или операция", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` на пользователя", body = openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = openapi::ErrorBody),
+        (status = 409, description = "Имя или email заняты, не прошла операция `test`", body = openapi::ErrorBody),
+        (status = 415, description = "Тело не merge patch и не JSON Patch", body = openapi::ErrorBody),
+    ))]
+async fn patch_user(
+    caller: Caller,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+    document: patch::PatchDocument,
+) -> Result<Json<UserResponse>, DirectoryError> {
+    let user = service.find_user_by_username(&username)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+    service.authorize(&caller.user, SecuredObject::User(user.id), AccessMask::WRITE_PROPERTY).await?;
+
+    let current = serde_json::to_value(UserResponse::from(user.clone()))
+        .map_err(|e| DirectoryError::Serialization(e.to_string()))?;
+    let changes = document.attribute_changes(USER_PATCH_FIELDS, &current)?;
+    if !changes.is_empty() {
+        service.modify_object(user.id, changes).await?;
+    }
+    let user = service.get_user(user.id).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+    Ok(Json(UserResponse::from(user)))
+}
+
+#[utoipa::path(put, path = "/api/users/{username}/account-control", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    request_body = UserAccountControlRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 400, description = "Неизвестный флаг или установка LOCKOUT", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` на пользователя", body = openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = openapi::ErrorBody),
+    ))]
+async fn update_user_account_control(
+    caller: Caller,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<UserAccountControlRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let user = service.find_user_by_username(&username)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+    service.authorize(&caller.user, SecuredObject::User(user.id), AccessMask::WRITE_PROPERTY).await?;
+
+    let set = crate::models::UserAccountControl::from_flag_names(&payload.set)?;
+    let clear = crate::models::UserAccountControl::from_flag_names(&payload.clear)?;
+    let user = service.update_user_account_control(user.id, set, clear).await?;
+    Ok(Json(UserResponse::from(user)))
+}
+
+#[utoipa::path(delete, path = "/api/users/{username}", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Пользователь удалён"),
+        (status = 202, description = "Удаление ждёт одобрения другим администратором (`security.approvals`)", body = approvals::ApprovalResponse),
+        (status = 403, description = "Нет права `DELETE` или встроенная учётная запись", body = openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = openapi::ErrorBody),
+        (status = 409, description = "Удаление этого пользователя уже ждёт одобрения", body = openapi::ErrorBody),
+    ))]
 async fn delete_user(
+    caller: Caller,
     Path(username): Path<String>,
     State(service): State<SharedService>,
 ) -> Result<impl IntoResponse, DirectoryError> {
     let user = service.find_user_by_username(&username)
         .await?
         .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+    service.authorize(&caller.user, SecuredObject::User(user.id), AccessMask::DELETE).await?;
 
-    service.delete_user(user.id).await?;
-    Ok(StatusCode::NO_CONTENT)
+    let operation = crate::models::SensitiveOperation::DeleteUser { user_id: user.id };
+    Ok(match service.submit_operation(operation, caller.user.id).await? {
+        None => StatusCode::NO_CONTENT.into_response(),
+        Some(request) => (StatusCode::ACCEPTED, Json(approvals::ApprovalResponse::from(request))).into_response(),
+    })
 }
 
 // === Обработчики: Groups ===
 
+#[utoipa::path(get, path = "/api/groups", tag = "groups",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Группы с правом `READ_PROPERTY`", body = Vec<GroupResponse>),
+        (status = 408, description = "Превышен предел времени `web_server.time_limit_secs`", body = openapi::ErrorBody),
+        (status = 413, description = "Превышен предел `web_server.size_limit`", body = openapi::ErrorBody),
+    ))]
 async fn list_groups(
+    reader: Reader,
     State(service): State<SharedService>,
+    Extension(limits): Extension<SearchLimits>,
 ) -> Result<Json<Vec<GroupResponse>>, DirectoryError> {
-    let groups = service.get_all_groups().await?;
+    let groups = service.list_groups_within(limits).await?;
+    let groups = service.filter_authorized(&reader.user, groups, |group| SecuredObject::Group(group.id), AccessMask::READ_PROPERTY).await?;
     Ok(Json(groups.into_iter().map(GroupResponse::from).collect()))
 }
 
+/// Участников на странице `GET /api/groups/:sam`, если клиент не указал, и предел
+const DEFAULT_MEMBERS_LIMIT: usize = 100;
+const MAX_MEMBERS_LIMIT: usize = 1000;
+
+#[utoipa::path(get, path = "/api/groups/{sam}", tag = "groups",
+    params(("sam" = String, Path, description = "sAMAccountName группы"), GroupMembersQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Группа и страница участников", body = GroupDetailsResponse),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `READ_PROPERTY` на группу", body = openapi::ErrorBody),
+        (status = 404, description = "Группа не найдена", body = openapi::ErrorBody),
+    ))]
+async fn get_group(
+    reader: Reader,
+    Path(sam): Path<String>,
+    State(service): State<SharedService>,
+    Query(query): Query<GroupMembersQuery>,
+) -> Result<Json<GroupDetailsResponse>, DirectoryError> {
+    let group = service.find_group_by_sam_account_name(&sam)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", sam)))?;
+    service.authorize(&reader.user, SecuredObject::Group(group.id), AccessMask::READ_PROPERTY).await?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_MEMBERS_LIMIT).clamp(1, MAX_MEMBERS_LIMIT);
+    let start = query.cursor.min(group.members.len());
+    let end = start.saturating_add(limit).min(group.members.len());
+    let now = chrono::Utc::now();
+    let mut members = Vec::with_capacity(end - start);
+    for id in &group.members[start..end] {
+        let (username, display_name, object_class) = if let Some(user) = service.get_user(*id).await? {
+            (user.username, user.display_name, "user")
+        } else if let Some(nested) = service.get_group(*id).await? {
+            (nested.sam_account_name, Some(nested.name), "group")
+        } else {
+            continue;
+        };
+        members.push(GroupMemberResponse {
+            id: *id,
+            username,
+            display_name,
+            object_class: object_class.to_string(),
+            expires_at: group.member_expirations.get(id).copied(),
+            ttl_secs: group.member_ttl(*id, now).map(|ttl| ttl.num_seconds()),
+        });
+    }
+    let next_cursor = (end < group.members.len()).then_some(end);
+    Ok(Json(GroupDetailsResponse { group: GroupResponse::from(group), members, next_cursor }))
+}
+
+#[utoipa::path(put, path = "/api/groups/{sam}", tag = "groups",
+    params(("sam" = String, Path, description = "sAMAccountName группы")),
+    request_body = UpdateGroupRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = GroupResponse),
+        (status = 400, description = "Недопустимая смена области или неверные атрибуты", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` или смена области встроенной группы", body = openapi::ErrorBody),
+        (status = 404, description = "Группа не найдена", body = openapi::ErrorBody),
+    ))]
+async fn update_group(
+    caller: Caller,
+    Path(sam): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<UpdateGroupRequest>,
+) -> Result<Json<GroupResponse>, DirectoryError> {
+    use crate::models::GroupTypeFlags;
+
+    let mut group = service.find_group_by_sam_account_name(&sam)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", sam)))?;
+    service.authorize(&caller.user, SecuredObject::Group(group.id), AccessMask::WRITE_PROPERTY).await?;
+
+    if let Some(name) = payload.name {
+        group.name = name;
+    }
+    if let Some(description) = payload.description {
+        group.description = (!description.is_empty()).then_some(description);
+    }
+    if let Some(scope) = payload.scope {
+        group.scope = scope;
+    }
+    if let Some(security) = payload.security {
+        group.type_flags.set(GroupTypeFlags::SECURITY, security);
+        group.type_flags.set(GroupTypeFlags::DISTRIBUTION, !security);
+    }
+    if let Some(attributes) = payload.attributes {
+        crate::models::schema::apply_changes(&mut group.meta, service.schema_attribute_changes(SchemaClass::Group, attributes).await?);
+    }
+
+    let group = service.update_group(&group).await?;
+    Ok(Json(GroupResponse::from(group)))
+}
+
+#[utoipa::path(get, path = "/api/groups/by-id/{id}", tag = "groups",
+    params(("id" = uuid::Uuid, Path, description = "id группы")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = GroupResponse),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `READ_PROPERTY`", body = openapi::ErrorBody),
+        (status = 404, description = "Группа не найдена", body = openapi::ErrorBody),
+    ))]
+async fn get_group_by_id(
+    reader: Reader,
+    Path(id): Path<uuid::Uuid>,
+    State(service): State<SharedService>,
+) -> Result<Json<GroupResponse>, DirectoryError> {
+    let group = service.get_group(id)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", id)))?;
+    service.authorize(&reader.user, SecuredObject::Group(group.id), AccessMask::READ_PROPERTY).await?;
+    Ok(Json(GroupResponse::from(group)))
+}
+
+#[utoipa::path(get, path = "/api/groups/by-sid/{sid}", tag = "groups",
+    params(("sid" = String, Path, description = "objectSid, например S-1-5-32-544")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = GroupResponse),
+        (status = 400, description = "Неверный SID", body = openapi::ErrorBody),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `READ_PROPERTY`", body = openapi::ErrorBody),
+        (status = 404, description = "Группа не найдена", body = openapi::ErrorBody),
+    ))]
+async fn get_group_by_sid(
+    reader: Reader,
+    Path(sid): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<GroupResponse>, DirectoryError> {
+    let group = service.find_group_by_sid(&parse_sid(&sid)?)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", sid)))?;
+    service.authorize(&reader.user, SecuredObject::Group(group.id), AccessMask::READ_PROPERTY).await?;
+    Ok(Json(GroupResponse::from(group)))
+}
+
+#[utoipa::path(post, path = "/api/groups", tag = "groups",
+    request_body = CreateGroupRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "Группа создана", body = GroupResponse),
+        (status = 400, description = "Неверные данные или правило членства", body = openapi::ErrorBody),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `CREATE_CHILD` на корень домена", body = openapi::ErrorBody),
+        (status = 409, description = "Группа уже существует", body = openapi::ErrorBody),
+    ))]
 async fn create_group(
+    caller: Caller,
     State(service): State<SharedService>,
     Json(payload): Json<CreateGroupRequest>,
 ) -> Result<impl IntoResponse, DirectoryError> {
     payload.validate()?;
+    // Группы не лежат в OU — создаются в корне домена
+    service.authorize_create(&caller.user, None).await?;
 
     use crate::models::{GroupTypeFlags, GroupScope};
 
     let sam = payload.sam_account_name.unwrap_or_else(|| payload.name.to_uppercase());
 
-    let group = crate::models::Group::new(
+    let mut group = crate::models::Group::new(
         payload.name,
         sam,
         uuid::Uuid::nil(),
         GroupTypeFlags::SECURITY,
         GroupScope::Global,
     );
+    group.membership_rule = payload.membership_rule;
+    if let Some(attributes) = payload.attributes {
+        crate::models::schema::apply_changes(&mut group.meta, service.schema_attribute_changes(SchemaClass::Group, attributes).await?);
+    }
 
     service.create_group(&group).await?;
+    // Участники динамической группы вычислены при сохранении
+    let group = service.get_group(group.id).await?.unwrap_or(group);
     Ok((StatusCode::CREATED, Json(GroupResponse::from(group))))
 }
 
+#[utoipa::path(put, path = "/api/groups/{sam}/membership-rule", tag = "groups",
+    params(("sam" = String, Path, description = "sAMAccountName группы")),
+    request_body = MembershipRuleRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Правило сохранено, участники пересчитаны", body = GroupResponse),
+        (status = 400, description = "Неверный фильтр LDAP", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` на группу", body = openapi::ErrorBody),
+        (status = 404, description = "Группа не найдена", body = openapi::ErrorBody),
+    ))]
+async fn set_group_membership_rule(
+    caller: Caller,
+    Path(sam): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<MembershipRuleRequest>,
+) -> Result<Json<GroupResponse>, DirectoryError> {
+    let group = service.find_group_by_sam_account_name(&sam)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", sam)))?;
+    service.authorize(&caller.user, SecuredObject::Group(group.id), AccessMask::WRITE_PROPERTY).await?;
+
+    let group = service.set_group_membership_rule(group.id, payload.membership_rule).await?;
+    Ok(Json(GroupResponse::from(group)))
+}
+
+#[utoipa::path(delete, path = "/api/groups/{sam}", tag = "groups",
+    params(("sam" = String, Path, description = "sAMAccountName группы")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Группа удалена"),
+        (status = 403, description = "Нет права `DELETE` или встроенная группа", body = openapi::ErrorBody),
+        (status = 404, description = "Группа не найдена", body = openapi::ErrorBody),
+    ))]
 async fn delete_group(
+    caller: Caller,
     Path(sam): Path<String>,
     State(service): State<SharedService>,
 ) -> Result<impl IntoResponse, DirectoryError> {
     let group = service.find_group_by_sam_account_name(&sam)
         .await?
         .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", sam)))?;
+    service.authorize(&caller.user, SecuredObject::Group(group.id), AccessMask::DELETE).await?;
 
     service.delete_group(group.id).await?;
     Ok(StatusCode::NO_CONTENT)
@@ -410,35 +1165,150 @@ async fn delete_group(
 
 // === Обработчики: OUs ===
 
+#[utoipa::path(get, path = "/api/ous", tag = "ous",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "OU с правом `READ_PROPERTY`", body = Vec<OuResponse>),
+        (status = 408, description = "Превышен предел времени `web_server.time_limit_secs`", body = openapi::ErrorBody),
+        (status = 413, description = "Превышен предел `web_server.size_limit`", body = openapi::ErrorBody),
+    ))]
 async fn list_ous(
+    reader: Reader,
     State(service): State<SharedService>,
+    Extension(limits): Extension<SearchLimits>,
 ) -> Result<Json<Vec<OuResponse>>, DirectoryError> {
-    let ous = service.get_all_ous().await?;
+    let ous = service.list_ous_within(limits).await?;
+    let ous = service.filter_authorized(&reader.user, ous, |ou| SecuredObject::Ou(ou.id), AccessMask::READ_PROPERTY).await?;
     Ok(Json(ous.into_iter().map(OuResponse::from).collect()))
 }
 
+#[utoipa::path(post, path = "/api/ous", tag = "ous",
+    request_body = CreateOuRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "OU создано", body = OuResponse),
+        (status = 400, description = "Неверные данные", body = openapi::ErrorBody),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `CREATE_CHILD` на родительское OU или корень домена", body = openapi::ErrorBody),
+        (status = 404, description = "Родительское OU не найдено", body = openapi::ErrorBody),
+    ))]
 async fn create_ou(
+    caller: Caller,
     State(service): State<SharedService>,
     Json(payload): Json<CreateOuRequest>,
 ) -> Result<impl IntoResponse, DirectoryError> {
     payload.validate()?;
 
     let parent_dn = payload.parent.as_deref();
-    let dn = crate::directory_service::DirectoryService::generate_ou_dn(&payload.name, parent_dn);
+    let dn = crate::directory_service::DirectoryService::generate_ou_dn(&payload.name, parent_dn)?;
 
-    let ou = crate::models::OrganizationalUnit::new(payload.name, dn, None);
+    let parent_id = match parent_dn {
+        Some(parent) => Some(service.find_ou_by_dn(parent).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("OU not found: {}", parent)))?
+            .id),
+        None => None,
+    };
+    service.authorize_create(&caller.user, parent_id).await?;
+    let mut ou = crate::models::OrganizationalUnit::new(payload.name, dn, parent_id);
+    if let Some(attributes) = payload.attributes {
+        crate::models::schema::apply_changes(&mut ou.meta, service.schema_attribute_changes(SchemaClass::OrganizationalUnit, attributes).await?);
+    }
     service.create_ou(&ou).await?;
 
     Ok((StatusCode::CREATED, Json(OuResponse::from(ou))))
 }
 
+#[utoipa::path(get, path = "/api/ous/tree", tag = "ous",
+    security(("bearer" = []), ("api_key" = [])),
+    responses((status = 200, description = "Иерархия OU с правом `READ_PROPERTY`; OU под скрытой — на уровень выше", body = Vec<OuTreeNode>)))]
+async fn ou_tree(
+    reader: Reader,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<OuTreeNode>>, DirectoryError> {
+    let ous = service.filter_authorized(&reader.user, service.get_all_ous().await?, |ou| SecuredObject::Ou(ou.id), AccessMask::READ_PROPERTY).await?;
+    Ok(Json(OuTreeNode::build(ous)))
+}
+
+#[utoipa::path(put, path = "/api/ous/{id}", tag = "ous",
+    params(("id" = uuid::Uuid, Path, description = "ID OU")),
+    request_body = UpdateOuRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "OU переименовано или перенесено; DN вложенных OU пересчитаны", body = OuResponse),
+        (status = 400, description = "Неверное имя или перенос в собственное поддерево", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` на OU", body = openapi::ErrorBody),
+        (status = 404, description = "OU или родитель не найдены", body = openapi::ErrorBody),
+        (status = 409, description = "OU с таким DN уже есть", body = openapi::ErrorBody),
+    ))]
+async fn update_ou(
+    caller: Caller,
+    Path(id): Path<uuid::Uuid>,
+    State(service): State<SharedService>,
+    Json(payload): Json<UpdateOuRequest>,
+) -> Result<Json<OuResponse>, DirectoryError> {
+    let mut ou = service.get_ou(id).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("OU not found: {}", id)))?;
+    service.authorize(&caller.user, SecuredObject::Ou(id), AccessMask::WRITE_PROPERTY).await?;
+    if let Some(name) = &payload.name {
+        ou = service.rename_ou(id, name).await?;
+    }
+    if let Some(parent) = &payload.parent {
+        ou = service.move_ou(id, parent).await?;
+    }
+    if let Some(attributes) = &payload.attributes {
+        service.set_attributes(SecuredObject::Ou(id), attributes.clone()).await?;
+        ou = service.get_ou(id).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("OU not found: {}", id)))?;
+    }
+    Ok(Json(OuResponse::from(ou)))
+}
+
+#[utoipa::path(delete, path = "/api/ous/{id}", tag = "ous",
+    params(("id" = uuid::Uuid, Path, description = "ID OU"), DeleteOuQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "OU удалено"),
+        (status = 400, description = "В OU есть вложенные OU (без `recursive`) или пользователи и контакты (при `contents=refuse`)", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `DELETE` (`DELETE_TREE` для `recursive`) на OU или в OU встроенная учётная запись (при `contents=delete`)", body = openapi::ErrorBody),
+        (status = 404, description = "OU не найдено", body = openapi::ErrorBody),
+    ))]
+async fn delete_ou(
+    caller: Caller,
+    Path(id): Path<uuid::Uuid>,
+    State(service): State<SharedService>,
+    Query(query): Query<DeleteOuQuery>,
+) -> Result<StatusCode, DirectoryError> {
+    // Как в AD: поддерево удаляется по одному праву DELETE_TREE на его корень
+    let right = if query.recursive { AccessMask::DELETE_TREE } else { AccessMask::DELETE };
+    service.authorize(&caller.user, SecuredObject::Ou(id), right).await?;
+    service.delete_ou_tree(id, query.recursive, query.contents).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 // === Обработчики: GPO ===
 
+#[utoipa::path(post, path = "/api/gpos", tag = "gpos",
+    request_body = CreateGpoRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "GPO создана", body = GpoResponse),
+        (status = 400, description = "Неверные данные или настройки не по схеме типа", body = openapi::ErrorBody),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет права `CREATE_CHILD` на корень домена или `WRITE_PROPERTY` на связываемое OU", body = openapi::ErrorBody),
+    ))]
 async fn create_gpo(
+    caller: Caller,
     State(service): State<SharedService>,
     Json(payload): Json<CreateGpoRequest>,
 ) -> Result<impl IntoResponse, DirectoryError> {
     payload.validate()?;
+    // GPO создаётся в корне домена; связь с OU меняет её gPLink
+    service.authorize_create(&caller.user, None).await?;
+    for target in &payload.linked_to {
+        if service.get_ou(*target).await?.is_some() {
+            service.authorize(&caller.user, SecuredObject::Ou(*target), AccessMask::WRITE_PROPERTY).await?;
+        }
+    }
 
     use crate::models::policy::{PolicyType, PolicyTarget};
 
@@ -455,9 +1325,9 @@ async fn create_gpo(
         created_at: chrono::Utc::now(),
         updated_at: chrono::Utc::now(),
         enabled: payload.enabled,
-        policy_type: PolicyType::Custom("Custom".to_string()),
+        policy_type: payload.policy_type.as_deref().map(PolicyType::from_name).unwrap_or_default(),
         target: PolicyTarget::All,
-        settings: std::collections::HashMap::new(),
+        settings: payload.settings,
         wmi_filter: None,
     };
 
@@ -465,35 +1335,227 @@ async fn create_gpo(
     Ok((StatusCode::CREATED, Json(GpoResponse::from(gpo))))
 }
 
+#[utoipa::path(get, path = "/api/gpos/schemas", tag = "gpos",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Схемы настроек встроенных типов GPO", body = [crate::models::policy_schema::PolicySchema]),
+    ))]
+async fn list_gpo_schemas(_caller: Caller) -> Json<Vec<crate::models::policy_schema::PolicySchema>> {
+    Json(crate::models::policy_schema::builtin_schemas())
+}
+
+// === Обработчики: Admin ===
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct ImportLdifQuery {
+    #[serde(default)]
+    pub on_duplicate: crate::ldif::DuplicatePolicy,
+}
+
+#[utoipa::path(post, path = "/api/admin/import/ldif", tag = "admin",
+    params(ImportLdifQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    request_body(content = String, content_type = "text/plain", description = "LDIF"),
+    responses(
+        (status = 200, description = "Итог импорта", body = crate::ldif::ImportReport),
+        (status = 400, description = "Ошибка разбора LDIF", body = openapi::ErrorBody),
+        (status = 401, description = "Нет токена или API-ключа", body = openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = openapi::ErrorBody),
+    ))]
+async fn import_ldif(
+    _admin: Authorized<DirectoryWrite>,
+    State(service): State<SharedService>,
+    Query(query): Query<ImportLdifQuery>,
+    body: String,
+) -> Result<Json<crate::ldif::ImportReport>, DirectoryError> {
+    let report = service.import_ldif(&body, query.on_duplicate).await?;
+    Ok(Json(report))
+}
+
+#[utoipa::path(post, path = "/api/admin/reload", tag = "admin",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Перезагружаемые секции применены", body = crate::reload::ReloadReport),
+        (status = 409, description = "Изменены неизменяемые поля (`rejected`), ничего не применено", body = crate::reload::ReloadReport),
+        (status = 422, description = "Конфигурация не читается или не проходит проверку (`errors`)", body = crate::reload::ReloadReport),
+        (status = 403, description = "Нет прав администратора или области `config:reload`", body = openapi::ErrorBody),
+    ))]
+async fn reload_config(
+    _admin: Authorized<ConfigReload>,
+    Extension(reloader): Extension<Arc<ConfigReloader>>,
+) -> impl IntoResponse {
+    let report = tokio::task::spawn_blocking(move || reloader.reload()).await
+        .unwrap_or_else(|e| crate::reload::ReloadReport { errors: vec![e.to_string()], ..Default::default() });
+    crate::reload::log_report(&report);
+    let status = if !report.rejected.is_empty() {
+        StatusCode::CONFLICT
+    } else if !report.errors.is_empty() {
+        StatusCode::UNPROCESSABLE_ENTITY
+    } else {
+        StatusCode::OK
+    };
+    (status, Json(report))
+}
+
+#[utoipa::path(get, path = "/api/stats", tag = "admin",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Сводка каталога для панелей мониторинга", body = crate::models::DirectoryStats),
+        (status = 403, description = "Не Domain Admin или у ключа нет области `directory:read`", body = openapi::ErrorBody),
+    ))]
+async fn get_stats(
+    reader: Reader,
+    State(service): State<SharedService>,
+) -> Result<Json<crate::models::DirectoryStats>, DirectoryError> {
+    if !service.is_domain_admin(reader.user.id).await? {
+        return Err(DirectoryError::AccessDenied("Only Domain Admins can view directory statistics".to_string()));
+    }
+    Ok(Json(service.stats().await?))
+}
+
 // === Health Check ===
 
+#[utoipa::path(get, path = "/health", tag = "admin",
+    responses((status = 200, description = "Сервер работает", body = Object, example = json!({ "status": "OK", "timestamp": "2024-01-01T00:00:00Z" }))))]
 async fn health() -> impl IntoResponse {
     Json(json!({ "status": "OK", "timestamp": chrono::Utc::now() }))
 }
 
 // === Запуск сервера ===
 
-pub async fn run_web_server(service: Arc<DirectoryService>, addr: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let cors = tower_http::cors::CorsLayer::new()
-        .allow_origin(tower_http::cors::Any)
-        .allow_methods(tower_http::cors::Any)
-        .allow_headers(tower_http::cors::Any);
+/// Предел тела запроса для извлекателей (`Json`, `Bytes`, `String`); 0 — без предела
+fn body_limit(max_request_size: u64) -> axum::extract::DefaultBodyLimit {
+    match usize::try_from(max_request_size) {
+        Ok(limit) if limit > 0 => axum::extract::DefaultBodyLimit::max(limit),
+        _ => axum::extract::DefaultBodyLimit::disable(),
+    }
+}
 
-    let app = Router::new()
+/// Маршруты и слои REST API; `default_issuer` — издатель OIDC, если `oidc.issuer` не задан
+pub fn router(service: Arc<DirectoryService>, config: &AppConfig, reloader: Arc<ConfigReloader>, default_issuer: String) -> Result<Router, Box<dyn std::error::Error>> {
+    let cors = reloader.cors();
+    let mail = crate::mail::build_sender(&config.mail)?;
+    let proxies = Arc::new(TrustedProxies::new(&config.web_server.proxy)?);
+
+    Ok(Router::new()
         .route("/health", get(health))
+        .route("/api/me", get(me::get_me).patch(me::update_me))
+        .route("/api/me/password", put(me::change_my_password))
+        .route("/api/me/sessions", get(me::list_my_sessions))
+        .route("/api/me/mfa", get(me::get_my_mfa).put(me::set_my_mfa))
+        .route("/api/me/mfa/totp", post(me::enroll_my_totp))
+        .route("/api/me/mfa/totp/confirm", post(me::confirm_my_totp))
         .route("/api/users", get(list_users).post(create_user))
-        .route("/api/users/:username", get(get_user).put(update_user).delete(delete_user))
+        .route("/api/users/invite", post(invitations::invite_user))
+        .route("/api/users/by-id/:id", get(get_user_by_id))
+        .route("/api/users/by-sid/:sid", get(get_user_by_sid))
+        .route("/api/users/:username", get(get_user).put(update_user).patch(patch_user).delete(delete_user))
+        .route("/api/users/:username/account-control", put(update_user_account_control))
+        .route("/api/users/:username/password-policy", get(password_policies::get_resultant_password_policy))
+        .route("/api/users/:username/logins", get(list_user_logins))
+        .route("/api/users/:username/sessions", get(sessions::list_user_sessions))
+        .route("/api/users/:username/photo", get(photos::get_user_photo).put(photos::set_user_photo).delete(photos::delete_user_photo))
+        .route("/api/users/:username/invitation", post(invitations::resend_invitation).delete(invitations::revoke_invitation))
         .route("/api/groups", get(list_groups).post(create_group))
-        .route("/api/groups/:sam", delete(delete_group))
+        .route("/api/users/:username/acl", get(acl::get_user_acl).put(acl::set_user_acl))
+        .route("/api/groups/by-id/:id", get(get_group_by_id))
+        .route("/api/groups/by-sid/:sid", get(get_group_by_sid))
+        .route("/api/groups/:sam", get(get_group).put(update_group).delete(delete_group))
+        .route("/api/groups/:sam/membership-rule", put(set_group_membership_rule))
+        .route("/api/groups/:sam/lifecycle", put(group_requests::set_group_lifecycle))
+        .route("/api/groups/:sam/requests", get(group_requests::list_group_requests).post(group_requests::request_group_membership))
+        .route("/api/groups/:sam/requests/:id/approve", post(group_requests::approve_group_request))
+        .route("/api/groups/:sam/requests/:id/deny", post(group_requests::deny_group_request))
+        .route("/api/groups/:sam/acl", get(acl::get_group_acl).put(acl::set_group_acl))
         .route("/api/ous", get(list_ous).post(create_ou))
+        .route("/api/ous/tree", get(ou_tree))
+        .route("/api/ous/:id", put(update_ou).delete(delete_ou))
+        .route("/api/ous/:id/acl", get(acl::get_ou_acl).put(acl::set_ou_acl))
+        .route("/api/contacts", get(contacts::list_contacts).post(contacts::create_contact))
+        .route("/api/contacts/:id", get(contacts::get_contact).put(contacts::update_contact).patch(contacts::patch_contact).delete(contacts::delete_contact))
+        .route("/api/service-accounts", get(service_accounts::list_service_accounts).post(service_accounts::create_service_account))
+        .route("/api/service-accounts/:username", get(service_accounts::get_service_account).put(service_accounts::update_service_account))
+        .route("/api/service-accounts/:username/rotate", post(service_accounts::rotate_service_account_password))
+        .route("/api/service-accounts/:username/password", get(service_accounts::retrieve_managed_password))
+        .route("/api/password-policies", get(password_policies::list_password_policies).post(password_policies::create_password_policy))
+        .route("/api/password-policies/:name", get(password_policies::get_password_policy).put(password_policies::update_password_policy).delete(password_policies::delete_password_policy))
+        .route("/api/sites", get(sites::list_sites).post(sites::create_site))
+        .route("/api/sites/lookup", get(sites::lookup_site))
+        .route("/api/sites/:name", get(sites::get_site).put(sites::update_site).delete(sites::delete_site))
+        .route("/api/subnets", get(sites::list_subnets).post(sites::create_subnet))
+        .route("/api/subnets/:network/:prefix_len", put(sites::update_subnet).delete(sites::delete_subnet))
+        .route("/api/replication/snapshot", post(replication::replication_snapshot))
+        .route("/api/changes", get(changes::list_changes))
+        .route("/api/objects/:guid", get(objects::get_object))
         .route("/api/gpos", post(create_gpo))
-        .with_state(service)
-        .layer(cors)
-        .layer(tower_http::trace::TraceLayer::new_for_http());
+        .route("/api/gpos/schemas", get(list_gpo_schemas))
+        .route("/api/gpos/:id/compliance", get(agent::gpo_compliance))
+        .route("/api/agent/checkin", post(agent::agent_checkin))
+        .route("/sysvol/:domain/Policies/:gpo", get(sysvol::list_gpo_files))
+        .route("/sysvol/:domain/Policies/:gpo/*path", get(sysvol::get_gpo_file))
+        .route("/api/schema/attributes", get(schema::list_attributes).post(schema::define_attribute))
+        .route("/api/schema/attributes/:name", delete(schema::delete_attribute))
+        .route("/api/admin/import/ldif", post(import_ldif))
+        .route("/api/bulk", post(bulk::bulk))
+        .route("/api/admin/reload", post(reload_config))
+        .route("/api/stats", get(get_stats))
+        .route("/api/auth/login", post(login::login_handler))
+        .route("/api/password-reset/request", post(password_reset::request_password_reset))
+        .route("/api/password-reset/confirm", post(password_reset::confirm_password_reset))
+        .route("/api/invitations", get(invitations::list_invitations))
+        .route("/api/invitations/:token/accept", post(invitations::accept_invitation))
+        .route("/api/events/stream", get(events::stream_events))
+        .route("/api/events/ws", get(events::websocket_events))
+        .route("/api/audit", get(audit::search_audit))
+        .route("/api/apikeys", get(apikeys::list_api_keys).post(apikeys::create_api_key))
+        .route("/api/apikeys/:id", delete(apikeys::delete_api_key))
+        .route("/api/approvals", get(approvals::list_approvals))
+        .route("/api/approvals/:id/approve", post(approvals::approve_operation))
+        .route("/api/approvals/:id/reject", post(approvals::reject_operation))
+        .route("/api/sessions/:id", delete(sessions::revoke_session))
+        .route("/api/ca/certificate", get(ca::get_ca_certificate))
+        .route("/api/ca/crl", get(ca::get_crl))
+        .route("/api/ca/sign", post(ca::sign_certificate))
+        .route("/api/ca/enroll", post(ca::enroll_computer))
+        .route("/api/ca/certificates", get(ca::list_certificates))
+        .route("/api/ca/certificates/:serial/revoke", post(ca::revoke_certificate))
+        .with_state(Arc::clone(&service))
+        .merge(openapi::router(Arc::clone(&service), config.web_server.swagger_ui))
+        .merge(metrics::router(Arc::clone(&service), &config.metrics))
+        .layer(Extension(SearchLimits::new(config.web_server.size_limit, config.web_server.time_limit_secs)))
+        .layer(Extension(reloader))
+        .layer(Extension(config.security.self_service.clone()))
+        .layer(Extension(Arc::new(password_reset::PasswordReset::new(&config.security.password_reset, mail.clone()))))
+        .layer(Extension(Arc::new(invitations::Invitations::new(&config.security.invitations, mail))))
+        .merge(oidc::router(service, &config.oidc, default_issuer))
+        .layer(body_limit(config.web_server.max_request_size))
+        .layer(axum::middleware::from_fn(errors::normalize_errors))
+        .layer(axum::middleware::from_fn(crate::middleware::actor_context))
+        .layer(Extension(Arc::clone(&proxies)))
+        .layer(axum::middleware::from_fn_with_state(cors, cors::apply_cors))
+        .layer(axum::middleware::from_fn(access_log::access_log)))
+}
+
+pub async fn run_web_server(service: Arc<DirectoryService>, addrs: &[String], config: &AppConfig, reloader: Arc<ConfigReloader>) -> Result<(), Box<dyn std::error::Error>> {
+    // За прокси на Unix-сокете внешний адрес знает только прокси — его задают в `oidc.issuer`
+    let addr = addrs.first().map(String::as_str).unwrap_or(DEFAULT_WEB_ADDRESS);
+    let default_issuer = match addr.strip_prefix(listener::UNIX_PREFIX) {
+        Some(_) => "http://localhost".to_string(),
+        None if config.web_server.enable_tls => format!("https://{}", addr),
+        None => format!("http://{}", addr),
+    };
+    let app = router(service, config, reloader, default_issuer)?;
+    let proxies = Arc::new(TrustedProxies::new(&config.web_server.proxy)?);
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    println!("🌐 REST API запущен на http://{}", addr);
+    let listeners = listener::WebListener::bind_all(addrs, &config.web_server.unix_socket).await?;
+    let tls = match config.web_server.enable_tls {
+        true => Some(listener::tls_acceptor(&config.web_server.tls)?),
+        false => None,
+    };
+    for listener in &listeners {
+        tracing::info!(addr = %listener.describe(), tls = tls.is_some(), "REST API запущен");
+    }
 
-    axum::serve(listener, app).await?;
+    futures_util::future::try_join_all(listeners.into_iter().map(|listener| listener.serve(app.clone(), tls.clone(), Arc::clone(&proxies)))).await?;
     Ok(())
 }
\ No newline at end of file
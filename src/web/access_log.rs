@@ -0,0 +1,97 @@
+// src/web/access_log.rs
+
+//! Журнал доступа REST: запись на каждый запрос — метод, путь, статус, время обработки, вошедший
+//! (`actor`), адрес клиента и идентификатор запроса. Идентификатор берётся из `X-Request-Id`
+//! клиента или прокси, иначе создаётся; он возвращается в ответе, попадает в спан запроса и в
+//! события аудита (`metadata.http_request_id`). Уровень записей задаёт `logging.modules`
+//! (`nextDomen::web::access_log`)
+
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::audit::actor::ActorContext;
+
+/// Заголовок с идентификатором запроса
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Идентификатор клиента длиннее — заменяется новым
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Идентификатор запроса в расширениях запроса
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Идентификатор из `X-Request-Id`, если он пригоден для журнала (печатные ASCII, не длиннее
+    /// 128 символов), иначе новый UUID
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers.get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic()))
+            .map(|id| Self(id.to_string()))
+            .unwrap_or_else(|| Self(Uuid::new_v4().to_string()))
+    }
+}
+
+/// Спан входящего запроса; родитель — контекст из `traceparent`, если клиент его передал
+fn request_span(request: &Request, request_id: &RequestId) -> tracing::Span {
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let span = tracing::info_span!(
+        "http_request",
+        otel.kind = "server",
+        otel.name = %format!("{} {}", request.method(), request.uri().path()),
+        http.method = %request.method(),
+        http.target = %request.uri().path(),
+        http.request_id = %request_id.0,
+        http.status_code = tracing::field::Empty,
+    );
+    span.set_parent(crate::telemetry::extract_context(request.headers()));
+    span
+}
+
+/// Внешний слой REST: идентификатор запроса, спан и запись журнала доступа после ответа.
+/// Вошедшего и адрес клиента сообщает `middleware::actor_context` в расширениях ответа
+pub async fn access_log(mut request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let request_id = RequestId::from_headers(request.headers());
+    let header_value = HeaderValue::from_str(&request_id.0).expect("request id is visible ASCII");
+    request.headers_mut().insert(REQUEST_ID_HEADER, header_value.clone());
+    request.extensions_mut().insert(request_id.clone());
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let peer = request.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| crate::net::client_ip(*addr).to_string());
+    let span = request_span(&request, &request_id);
+
+    let mut response = next.run(request).instrument(span.clone()).await;
+    let status = response.status().as_u16();
+    span.record("http.status_code", status);
+    // Ответы до `actor_context` (предварительные запросы CORS) — без вошедшего, адрес — собеседника
+    let actor = response.extensions_mut().remove::<ActorContext>();
+    let ip = actor.as_ref().and_then(|actor| actor.ip_addr.clone()).or(peer);
+    let actor = actor.and_then(|actor| actor.user_id);
+    span.in_scope(|| tracing::info!(
+        method = %method,
+        path = %path,
+        status,
+        latency_ms = started.elapsed().as_millis() as u64,
+        actor = %actor.map(|id| id.to_string()).unwrap_or_else(|| "-".to_string()),
+        ip = %ip.as_deref().unwrap_or("-"),
+        request_id = %request_id.0,
+        "HTTP-запрос",
+    ));
+    response.headers_mut().insert(REQUEST_ID_HEADER, header_value);
+    response
+}
@@ -0,0 +1,284 @@
+// src/web/acl.rs
+
+//! ACL объектов каталога: `GET/PUT /api/users/{username}/acl`, `/api/groups/{sam}/acl`, `/api/ous/{id}/acl`.
+//! GET показывает действующий DACL вместе с унаследованными ACE, PUT заменяет явные ACE и владельца.
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::middleware::Caller;
+use crate::models::{well_known, AccessMask, Ace, AceFlags, AceType, SecuredObject, SecurityDescriptor, SecurityIdentifier};
+use super::SharedService;
+
+#[derive(Serialize, Deserialize, Clone, Copy, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AceKind {
+    Allow,
+    Deny,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AceEntry {
+    #[serde(rename = "type")]
+    pub kind: AceKind,
+    /// SID (`S-1-5-21-…-512`), `Everyone`, `Authenticated Users`, `Self`, имя пользователя или sAMAccountName группы
+    pub trustee: String,
+    /// Имя участника; только в ответе
+    #[serde(default, skip_deserializing)]
+    pub trustee_name: Option<String>,
+    /// `GENERIC_ALL`, `GENERIC_READ`, `GENERIC_WRITE`, `READ_PROPERTY`, `WRITE_PROPERTY`, `DELETE`, `DELETE_TREE`,
+    /// `CREATE_CHILD`, `DELETE_CHILD`, `READ_CONTROL`, `WRITE_DAC`, `WRITE_OWNER`, ...
+    pub rights: Vec<String>,
+    /// `CONTAINER_INHERIT`, `OBJECT_INHERIT`, `NO_PROPAGATE_INHERIT`, `INHERIT_ONLY`
+    #[serde(default)]
+    pub flags: Vec<String>,
+    /// Унаследован от родительской OU или корня домена; в PUT такие ACE пропускаются
+    #[serde(default)]
+    pub inherited: bool,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct AclResponse {
+    /// SID владельца
+    pub owner: String,
+    pub owner_name: Option<String>,
+    /// Наследование от родителей отключено
+    pub protected: bool,
+    /// Действующий DACL: сначала явные ACE, затем унаследованные
+    pub aces: Vec<AceEntry>,
+}
+
+/// Новый DACL объекта; унаследованные ACE задаются на родительской OU
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SetAclRequest {
+    /// Новый владелец (нужно право `WRITE_OWNER`); без поля — прежний
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Отключить наследование ACE от родителей
+    #[serde(default)]
+    pub protected: bool,
+    pub aces: Vec<AceEntry>,
+}
+
+#[utoipa::path(get, path = "/api/users/{username}/acl", tag = "acl",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = AclResponse),
+        (status = 403, description = "Нет права `READ_CONTROL`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_user_acl(
+    caller: Caller,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<AclResponse>, DirectoryError> {
+    let object = user_object(&service, &username).await?;
+    read_acl(&service, &caller, object).await
+}
+
+#[utoipa::path(put, path = "/api/users/{username}/acl", tag = "acl",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    request_body = SetAclRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Действующий DACL после изменения", body = AclResponse),
+        (status = 400, description = "Неизвестное право, флаг или участник", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_DAC` (`WRITE_OWNER` при смене владельца)", body = super::openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn set_user_acl(
+    caller: Caller,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<SetAclRequest>,
+) -> Result<Json<AclResponse>, DirectoryError> {
+    let object = user_object(&service, &username).await?;
+    write_acl(&service, &caller, object, payload).await
+}
+
+#[utoipa::path(get, path = "/api/groups/{sam}/acl", tag = "acl",
+    params(("sam" = String, Path, description = "sAMAccountName группы")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = AclResponse),
+        (status = 403, description = "Нет права `READ_CONTROL`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Группа не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_group_acl(
+    caller: Caller,
+    Path(sam): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<AclResponse>, DirectoryError> {
+    let object = group_object(&service, &sam).await?;
+    read_acl(&service, &caller, object).await
+}
+
+#[utoipa::path(put, path = "/api/groups/{sam}/acl", tag = "acl",
+    params(("sam" = String, Path, description = "sAMAccountName группы")),
+    request_body = SetAclRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Действующий DACL после изменения", body = AclResponse),
+        (status = 400, description = "Неизвестное право, флаг или участник", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_DAC` (`WRITE_OWNER` при смене владельца)", body = super::openapi::ErrorBody),
+        (status = 404, description = "Группа не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn set_group_acl(
+    caller: Caller,
+    Path(sam): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<SetAclRequest>,
+) -> Result<Json<AclResponse>, DirectoryError> {
+    let object = group_object(&service, &sam).await?;
+    write_acl(&service, &caller, object, payload).await
+}
+
+#[utoipa::path(get, path = "/api/ous/{id}/acl", tag = "acl",
+    params(("id" = Uuid, Path, description = "ID OU")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = AclResponse),
+        (status = 403, description = "Нет права `READ_CONTROL`", body = super::openapi::ErrorBody),
+        (status = 404, description = "OU не найдено", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_ou_acl(
+    caller: Caller,
+    Path(id): Path<Uuid>,
+    State(service): State<SharedService>,
+) -> Result<Json<AclResponse>, DirectoryError> {
+    read_acl(&service, &caller, SecuredObject::Ou(id)).await
+}
+
+#[utoipa::path(put, path = "/api/ous/{id}/acl", tag = "acl",
+    params(("id" = Uuid, Path, description = "ID OU")),
+    request_body = SetAclRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Действующий DACL после изменения; наследуемые ACE сразу действуют на вложенные объекты", body = AclResponse),
+        (status = 400, description = "Неизвестное право, флаг или участник", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_DAC` (`WRITE_OWNER` при смене владельца)", body = super::openapi::ErrorBody),
+        (status = 404, description = "OU не найдено", body = super::openapi::ErrorBody),
+    ))]
+pub async fn set_ou_acl(
+    caller: Caller,
+    Path(id): Path<Uuid>,
+    State(service): State<SharedService>,
+    Json(payload): Json<SetAclRequest>,
+) -> Result<Json<AclResponse>, DirectoryError> {
+    write_acl(&service, &caller, SecuredObject::Ou(id), payload).await
+}
+
+async fn user_object(service: &DirectoryService, username: &str) -> Result<SecuredObject, DirectoryError> {
+    let user = service.find_user_by_username(username).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+    Ok(SecuredObject::User(user.id))
+}
+
+async fn group_object(service: &DirectoryService, sam: &str) -> Result<SecuredObject, DirectoryError> {
+    let group = service.find_group_by_sam_account_name(sam).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", sam)))?;
+    Ok(SecuredObject::Group(group.id))
+}
+
+async fn read_acl(service: &DirectoryService, caller: &Caller, object: SecuredObject) -> Result<Json<AclResponse>, DirectoryError> {
+    let effective = service.effective_security_descriptor(object).await?;
+    service.authorize(&caller.user, object, AccessMask::READ_CONTROL).await?;
+    Ok(Json(acl_response(service, effective).await?))
+}
+
+async fn write_acl(service: &DirectoryService, caller: &Caller, object: SecuredObject, payload: SetAclRequest) -> Result<Json<AclResponse>, DirectoryError> {
+    let current = service.effective_security_descriptor(object).await?;
+    let owner = match &payload.owner {
+        Some(owner) => resolve_trustee(service, owner).await?,
+        None => current.owner.clone(),
+    };
+    let mut desired = AccessMask::WRITE_DAC;
+    if owner != current.owner {
+        desired |= AccessMask::WRITE_OWNER;
+    }
+    service.authorize(&caller.user, object, desired).await?;
+
+    let mut descriptor = SecurityDescriptor::new(owner);
+    descriptor.protected = payload.protected;
+    for entry in payload.aces.iter().filter(|entry| !entry.inherited) {
+        let trustee = resolve_trustee(service, &entry.trustee).await?;
+        let mask = AccessMask::from_names(&entry.rights)?;
+        if mask.is_empty() {
+            return Err(DirectoryError::InvalidInput(format!("ACE for {} grants no rights", entry.trustee)));
+        }
+        let flags = AceFlags::from_names(&entry.flags)? - AceFlags::INHERITED;
+        descriptor.dacl.push(match entry.kind {
+            AceKind::Allow => Ace::allow(trustee, mask, flags),
+            AceKind::Deny => Ace::deny(trustee, mask, flags),
+        });
+    }
+    service.set_security_descriptor(object, &descriptor).await?;
+
+    let effective = service.effective_security_descriptor(object).await?;
+    Ok(Json(acl_response(service, effective).await?))
+}
+
+/// Участники вне каталога, которых можно указать по имени
+fn well_known_trustees() -> [(SecurityIdentifier, &'static str); 3] {
+    [
+        (well_known::sids::everyone(), "Everyone"),
+        (well_known::sids::principal_self(), "Self"),
+        (well_known::sids::authenticated_users(), "Authenticated Users"),
+    ]
+}
+
+/// Участник ACE: SID строкой, имя well-known участника, иначе имя пользователя или sAMAccountName группы
+pub(super) async fn resolve_trustee(service: &DirectoryService, value: &str) -> Result<SecurityIdentifier, DirectoryError> {
+    if let Ok(sid) = value.parse::<SecurityIdentifier>() {
+        return Ok(sid);
+    }
+    if let Some((sid, _)) = well_known_trustees().into_iter().find(|(_, name)| name.eq_ignore_ascii_case(value)) {
+        return Ok(sid);
+    }
+    if let Some(user) = service.find_user_by_username(value).await? {
+        return Ok(user.sid);
+    }
+    if let Some(group) = service.find_group_by_sam_account_name(value).await? {
+        return Ok(group.sid);
+    }
+    Err(DirectoryError::InvalidInput(format!("Unknown trustee: {}", value)))
+}
+
+async fn acl_response(service: &DirectoryService, descriptor: SecurityDescriptor) -> Result<AclResponse, DirectoryError> {
+    let mut names: HashMap<SecurityIdentifier, String> = well_known_trustees().into_iter()
+        .map(|(sid, name)| (sid, name.to_string()))
+        .collect();
+    for group in service.get_all_groups().await? {
+        names.insert(group.sid, group.sam_account_name);
+    }
+    for user in service.get_all_users().await? {
+        names.insert(user.sid, user.username);
+    }
+
+    let aces = descriptor.dacl.into_iter()
+        .map(|ace| AceEntry {
+            kind: match ace.ace_type {
+                AceType::Allow => AceKind::Allow,
+                AceType::Deny => AceKind::Deny,
+            },
+            trustee: ace.trustee.to_string(),
+            trustee_name: names.get(&ace.trustee).cloned(),
+            rights: ace.mask.names(),
+            flags: (ace.flags - AceFlags::INHERITED).names(),
+            inherited: ace.flags.contains(AceFlags::INHERITED),
+        })
+        .collect();
+    Ok(AclResponse {
+        owner: descriptor.owner.to_string(),
+        owner_name: names.get(&descriptor.owner).cloned(),
+        protected: descriptor.protected,
+        aces,
+    })
+}
@@ -0,0 +1,160 @@
+// src/web/agent.rs
+
+//! Протокол агента политик: `POST /api/agent/checkin` — агент компьютера (по учётной записи
+//! компьютера) или пользователя сообщает, как применил политики, и получает итоговый набор
+//! (RSoP). `GET /api/gpos/{id}/compliance` — соответствие компьютеров политике, для Domain Admins
+//! (API-ключ — с областью `directory:read`).
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::{Caller, Reader};
+use crate::models::{ComplianceState, PolicyReport, ResolvedSetting};
+use super::SharedService;
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CheckinRequest {
+    /// Компьютер, на котором работает агент пользователя; агенту компьютера не нужен
+    pub computer: Option<String>,
+    /// Итоги применения политик, полученных при прошлом check-in
+    #[serde(default)]
+    pub reports: Vec<PolicyReport>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RsopPolicy {
+    pub gpo_id: Uuid,
+    pub name: String,
+    pub version: u32,
+    pub policy_type: String,
+    pub enforced: bool,
+    /// Папка политики в SYSVOL: `/sysvol/{домен}/Policies/{GUID}`
+    pub sysvol_path: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CheckinResponse {
+    pub computer: String,
+    pub checked_in_at: DateTime<Utc>,
+    /// Политики по убыванию приоритета
+    pub policies: Vec<RsopPolicy>,
+    /// Настройки после слияния политик
+    pub settings: Vec<ResolvedSetting>,
+}
+
+#[utoipa::path(post, path = "/api/agent/checkin", tag = "gpos",
+    request_body = CheckinRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Итоговый набор политик", body = CheckinResponse),
+        (status = 400, description = "Агенту пользователя не указан компьютер", body = super::openapi::ErrorBody),
+        (status = 404, description = "Нет учётной записи компьютера", body = super::openapi::ErrorBody),
+    ))]
+pub async fn agent_checkin(
+    caller: Caller,
+    State(service): State<SharedService>,
+    Json(payload): Json<CheckinRequest>,
+) -> Result<Json<CheckinResponse>, DirectoryError> {
+    let (checkin, gpos) = service.agent_checkin(&caller.user, payload.computer.as_deref(), payload.reports).await?;
+    let domain = match caller.user.domains.first() {
+        Some(id) => service.get_domain(*id).await?.map(|domain| domain.dns_name.trim_end_matches('.').to_lowercase()),
+        None => None,
+    };
+
+    let policies = gpos.iter()
+        .map(|gpo| RsopPolicy {
+            gpo_id: gpo.id,
+            name: gpo.name.clone(),
+            version: gpo.version,
+            policy_type: gpo.policy_type.name().to_string(),
+            enforced: gpo.enforced,
+            sysvol_path: domain.as_ref().map(|domain| format!("/sysvol/{}/Policies/{}", domain, crate::sysvol::folder_name(gpo))),
+        })
+        .collect();
+    Ok(Json(CheckinResponse {
+        computer: checkin.computer,
+        checked_in_at: checkin.checked_in_at,
+        policies,
+        settings: crate::models::agent::merge_settings(&gpos),
+    }))
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ComputerCompliance {
+    pub computer: String,
+    /// Учётная запись агента: компьютер или пользователь
+    pub principal: String,
+    pub checked_in_at: DateTime<Utc>,
+    pub state: ComplianceState,
+    /// Версия из последнего отчёта агента
+    pub reported_version: Option<u32>,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct GpoComplianceResponse {
+    pub gpo_id: Uuid,
+    pub name: String,
+    pub version: u32,
+    pub compliant: usize,
+    pub pending: usize,
+    pub outdated: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// Агенты, получившие политику при последнем check-in
+    pub computers: Vec<ComputerCompliance>,
+}
+
+#[utoipa::path(get, path = "/api/gpos/{id}/compliance", tag = "gpos",
+    params(("id" = Uuid, Path, description = "ID политики")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Соответствие компьютеров политике", body = GpoComplianceResponse),
+        (status = 403, description = "Не Domain Admin или у ключа нет области `directory:read`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Политика не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn gpo_compliance(
+    reader: Reader,
+    State(service): State<SharedService>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GpoComplianceResponse>, DirectoryError> {
+    if !service.is_domain_admin(reader.user.id).await? {
+        return Err(DirectoryError::AccessDenied("Only Domain Admins can view GPO compliance".to_string()));
+    }
+    let gpo = service.get_gpo(id).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("GPO not found: {}", id)))?;
+
+    let computers: Vec<ComputerCompliance> = service.get_agent_checkins().await?.into_iter()
+        .filter_map(|checkin| {
+            let state = checkin.state(gpo.id, gpo.version)?;
+            let report = checkin.report(gpo.id);
+            Some(ComputerCompliance {
+                reported_version: report.map(|report| report.version),
+                message: report.and_then(|report| report.message.clone()),
+                computer: checkin.computer,
+                principal: checkin.principal,
+                checked_in_at: checkin.checked_in_at,
+                state,
+            })
+        })
+        .collect();
+    let count = |state: ComplianceState| computers.iter().filter(|computer| computer.state == state).count();
+
+    Ok(Json(GpoComplianceResponse {
+        gpo_id: gpo.id,
+        name: gpo.name.clone(),
+        version: gpo.version,
+        compliant: count(ComplianceState::Compliant),
+        pending: count(ComplianceState::Pending),
+        outdated: count(ComplianceState::Outdated),
+        failed: count(ComplianceState::Failed),
+        skipped: count(ComplianceState::Skipped),
+        computers,
+    }))
+}
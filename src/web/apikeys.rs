@@ -0,0 +1,120 @@
+// src/web/apikeys.rs
+
+//! API-ключи для межсервисного доступа: `POST/GET /api/apikeys`, `DELETE /api/apikeys/{id}`.
+//! Ключ действует от имени создавшего его администратора и только в своих областях.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::{ApiKeysManage, Authorized};
+use crate::models::apikey::scope;
+use crate::models::ApiKey;
+use super::SharedService;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// Области из `models::apikey::scope::ALL`: `audit:read`, `events:read`, `apikeys:manage`,
+    /// `directory:read`, `directory:write`, `replication:pull`, `changes:read`, `config:reload`,
+    /// `self:service`, `ca:issue`, `approvals:read`, `sessions:manage`, `service-accounts:manage`,
+    /// `service-accounts:password`
+    pub scopes: Vec<String>,
+    /// Без срока — бессрочный
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    pub owner_id: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id.to_string(),
+            name: key.name,
+            owner_id: key.owner_id.to_string(),
+            scopes: key.scopes,
+            created_at: key.created_at,
+            expires_at: key.expires_at,
+        }
+    }
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub key: ApiKeyResponse,
+    /// Значение для `X-Api-Key`; показывается один раз
+    pub api_key: String,
+}
+
+#[utoipa::path(post, path = "/api/apikeys", tag = "apikeys",
+    request_body = CreateApiKeyRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "Ключ создан", body = CreatedApiKeyResponse),
+        (status = 400, description = "Неверные области или срок", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `apikeys:manage`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn create_api_key(
+    Authorized(admin, _): Authorized<ApiKeysManage>,
+    State(service): State<SharedService>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    if let Some(unknown) = payload.scopes.iter().find(|name| !scope::ALL.contains(&name.as_str())) {
+        return Err(DirectoryError::InvalidInput(format!("Unknown scope '{}': expected one of {}", unknown, scope::ALL.join(", "))));
+    }
+    // Ключ не может выдать больше, чем есть у него самого
+    if let Some(scope) = payload.scopes.iter().find(|scope| !admin.has_scope(scope)) {
+        return Err(DirectoryError::InvalidInput(format!("Cannot grant scope {} not held by the calling API key", scope)));
+    }
+
+    let (key, value) = ApiKey::generate(payload.name, admin.user.id, payload.scopes, payload.expires_at);
+    service.create_api_key(&key).await?;
+    Ok((StatusCode::CREATED, Json(CreatedApiKeyResponse { key: key.into(), api_key: value })))
+}
+
+#[utoipa::path(get, path = "/api/apikeys", tag = "apikeys",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Все ключи, без секретов", body = Vec<ApiKeyResponse>),
+        (status = 403, description = "Нет прав администратора или области `apikeys:manage`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_api_keys(
+    _admin: Authorized<ApiKeysManage>,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<ApiKeyResponse>>, DirectoryError> {
+    let keys = service.get_all_api_keys().await?;
+    Ok(Json(keys.into_iter().map(ApiKeyResponse::from).collect()))
+}
+
+#[utoipa::path(delete, path = "/api/apikeys/{id}", tag = "apikeys",
+    params(("id" = String, Path, description = "Идентификатор ключа")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Ключ отозван"),
+        (status = 404, description = "Ключ не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn delete_api_key(
+    _admin: Authorized<ApiKeysManage>,
+    State(service): State<SharedService>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, DirectoryError> {
+    let id = Uuid::parse_str(&id).map_err(|_| DirectoryError::InvalidInput(format!("Invalid API key id: {}", id)))?;
+    service.delete_api_key(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
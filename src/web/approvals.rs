@@ -0,0 +1,122 @@
+// src/web/approvals.rs
+
+//! Заявки на чувствительные операции (`security.approvals`): `GET /api/approvals`,
+//! `POST /api/approvals/{id}/approve|reject`. Только для Domain Admins; решает заявку
+//! не тот администратор, который её создал, и только по JWT — API-ключу доступен лишь список
+//! (область `approvals:read`).
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::{AdminUser, ApprovalsRead, Authorized};
+use crate::models::{ApprovalRequest, ApprovalStatus, SensitiveOperation};
+use super::SharedService;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct ApprovalsQuery {
+    /// Только заявки в этом состоянии: `pending`, `approved`, `rejected`, `failed`
+    #[param(value_type = Option<String>)]
+    pub status: Option<ApprovalStatus>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct DecideApprovalRequest {
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ApprovalResponse {
+    pub id: Uuid,
+    pub operation: SensitiveOperation,
+    /// Операция словами: `delete user bob`
+    pub summary: String,
+    pub requested_by: Uuid,
+    pub requested_at: DateTime<Utc>,
+    /// `pending`, `approved`, `rejected` или `failed`
+    #[schema(value_type = String)]
+    pub status: ApprovalStatus,
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub comment: Option<String>,
+    /// Почему одобренная операция не выполнилась
+    pub error: Option<String>,
+}
+
+impl From<ApprovalRequest> for ApprovalResponse {
+    fn from(request: ApprovalRequest) -> Self {
+        Self {
+            id: request.id,
+            operation: request.operation,
+            summary: request.summary,
+            requested_by: request.requested_by,
+            requested_at: request.requested_at,
+            status: request.status,
+            decided_by: request.decided_by,
+            decided_at: request.decided_at,
+            comment: request.comment,
+            error: request.error,
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/api/approvals", tag = "approvals",
+    params(ApprovalsQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Заявки от старых к новым", body = Vec<ApprovalResponse>),
+        (status = 403, description = "Нет прав администратора или области `approvals:read`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_approvals(
+    _admin: Authorized<ApprovalsRead>,
+    State(service): State<SharedService>,
+    Query(query): Query<ApprovalsQuery>,
+) -> Result<Json<Vec<ApprovalResponse>>, DirectoryError> {
+    let requests = service.list_approvals(query.status).await?;
+    Ok(Json(requests.into_iter().map(ApprovalResponse::from).collect()))
+}
+
+#[utoipa::path(post, path = "/api/approvals/{id}/approve", tag = "approvals",
+    params(("id" = Uuid, Path, description = "id заявки")),
+    request_body = DecideApprovalRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Заявка одобрена: операция выполнена (`approved`) или не удалась (`failed`, причина в `error`)", body = ApprovalResponse),
+        (status = 400, description = "Заявка уже решена", body = super::openapi::ErrorBody),
+        (status = 403, description = "Не Domain Admin, вход по API-ключу или заявку создал сам вызывающий", body = super::openapi::ErrorBody),
+        (status = 404, description = "Заявка не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn approve_operation(
+    admin: AdminUser,
+    Path(id): Path<Uuid>,
+    State(service): State<SharedService>,
+    Json(payload): Json<DecideApprovalRequest>,
+) -> Result<Json<ApprovalResponse>, DirectoryError> {
+    let request = service.decide_approval(id, true, admin.user.id, payload.comment).await?;
+    Ok(Json(ApprovalResponse::from(request)))
+}
+
+#[utoipa::path(post, path = "/api/approvals/{id}/reject", tag = "approvals",
+    params(("id" = Uuid, Path, description = "id заявки")),
+    request_body = DecideApprovalRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Заявка отклонена, операция не выполнялась", body = ApprovalResponse),
+        (status = 400, description = "Заявка уже решена", body = super::openapi::ErrorBody),
+        (status = 403, description = "Не Domain Admin, вход по API-ключу или заявку создал сам вызывающий", body = super::openapi::ErrorBody),
+        (status = 404, description = "Заявка не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn reject_operation(
+    admin: AdminUser,
+    Path(id): Path<Uuid>,
+    State(service): State<SharedService>,
+    Json(payload): Json<DecideApprovalRequest>,
+) -> Result<Json<ApprovalResponse>, DirectoryError> {
+    let request = service.decide_approval(id, false, admin.user.id, payload.comment).await?;
+    Ok(Json(ApprovalResponse::from(request)))
+}
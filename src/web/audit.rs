@@ -0,0 +1,32 @@
+// src/web/audit.rs
+
+//! Поиск по журналу аудита: `GET /api/audit?actor=&target=&action=&from=&to=&result=&page=&per_page=`.
+//! `actor` и `target` — UUID или имя пользователя, `from` / `to` — RFC 3339 или `YYYY-MM-DD`.
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+use crate::audit::query::{AuditPage, AuditSearchParams};
+use crate::directory_service::DirectoryError;
+use crate::middleware::{AuditRead, Authorized};
+use super::SharedService;
+
+#[utoipa::path(get, path = "/api/audit", tag = "audit",
+    params(AuditSearchParams),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "События от новых к старым", body = AuditPage),
+        (status = 400, description = "Неверный фильтр", body = super::openapi::ErrorBody),
+        (status = 401, description = "Нет токена или ключа", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `audit:read`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn search_audit(
+    _admin: Authorized<AuditRead>,
+    State(service): State<SharedService>,
+    Query(params): Query<AuditSearchParams>,
+) -> Result<Json<AuditPage>, DirectoryError> {
+    let query = params.into_query(&service).await?;
+    Ok(Json(service.search_audit(&query).await?))
+}
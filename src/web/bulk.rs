@@ -0,0 +1,197 @@
+// src/web/bulk.rs
+
+//! Массовые операции: `POST /api/bulk` выполняет по порядку список создания, изменения и удаления
+//! пользователей, групп, OU и контактов. Каждая операция проходит через тот же обработчик, что и
+//! одиночный запрос (проверки, права, аудит), ошибка одной не останавливает остальные.
+//! База пишется на диск один раз на `BULK_CHUNK_SIZE` операций
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::directory_service::DirectoryError;
+use crate::events::{AuditEvent, AuditResult};
+use crate::middleware::Caller;
+use super::{contacts, SharedService};
+
+/// Больше операций в одном запросе не принимается
+pub const MAX_BULK_OPERATIONS: usize = 1000;
+/// Операций на одну запись базы на диск
+const BULK_CHUNK_SIZE: usize = 100;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkAction {
+    Create,
+    Update,
+    Delete,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkObjectType {
+    User,
+    Group,
+    Ou,
+    Contact,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct BulkOperation {
+    pub op: BulkAction,
+    #[serde(rename = "type")]
+    pub object_type: BulkObjectType,
+    /// Объект для update и delete: имя пользователя, sAMAccountName группы, ID OU или контакта
+    pub id: Option<String>,
+    /// Тело, как у одиночного запроса (`POST /api/users`, `PUT /api/groups/{sam}`, ...);
+    /// для удаления OU — `{"recursive": true}`
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub data: serde_json::Value,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct BulkRequest {
+    pub operations: Vec<BulkOperation>,
+}
+
+/// Итог одной операции
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct BulkItemResult {
+    pub index: usize,
+    /// HTTP-статус, который вернул бы одиночный запрос
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Стабильный код ошибки, как в ответе problem+json одиночного запроса
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Тело ответа одиночного запроса, например созданный объект
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct BulkResponse {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+}
+
+fn body<T: DeserializeOwned>(data: serde_json::Value) -> Result<T, DirectoryError> {
+    let data = if data.is_null() { serde_json::Value::Object(Default::default()) } else { data };
+    serde_json::from_value(data).map_err(|e| DirectoryError::InvalidInput(format!("Invalid data: {}", e)))
+}
+
+fn target(id: Option<String>) -> Result<String, DirectoryError> {
+    id.ok_or_else(|| DirectoryError::InvalidInput("id is required for update and delete".to_string()))
+}
+
+fn target_uuid(id: Option<String>) -> Result<Uuid, DirectoryError> {
+    let id = target(id)?;
+    Uuid::parse_str(&id).map_err(|_| DirectoryError::InvalidInput(format!("Invalid id: {}", id)))
+}
+
+/// Выполнить операцию обработчиком одиночного запроса
+async fn dispatch(service: &SharedService, caller: &Caller, operation: BulkOperation) -> Result<Response, DirectoryError> {
+    let state = || State(service.clone());
+    let caller = || Caller { user: caller.user.clone(), api_key: caller.api_key.clone() };
+    let BulkOperation { op, object_type, id, data } = operation;
+    let response = match (op, object_type) {
+        (BulkAction::Create, BulkObjectType::User) => super::create_user(caller(), state(), Json(body(data)?)).await.into_response(),
+        (BulkAction::Update, BulkObjectType::User) => super::update_user(caller(), Path(target(id)?), state(), Json(body(data)?)).await.into_response(),
+        (BulkAction::Delete, BulkObjectType::User) => super::delete_user(caller(), Path(target(id)?), state()).await.into_response(),
+        (BulkAction::Create, BulkObjectType::Group) => super::create_group(caller(), state(), Json(body(data)?)).await.into_response(),
+        (BulkAction::Update, BulkObjectType::Group) => super::update_group(caller(), Path(target(id)?), state(), Json(body(data)?)).await.into_response(),
+        (BulkAction::Delete, BulkObjectType::Group) => super::delete_group(caller(), Path(target(id)?), state()).await.into_response(),
+        (BulkAction::Create, BulkObjectType::Ou) => super::create_ou(caller(), state(), Json(body(data)?)).await.into_response(),
+        (BulkAction::Update, BulkObjectType::Ou) => super::update_ou(caller(), Path(target_uuid(id)?), state(), Json(body(data)?)).await.into_response(),
+        (BulkAction::Delete, BulkObjectType::Ou) => super::delete_ou(caller(), Path(target_uuid(id)?), state(), Query(body(data)?)).await.into_response(),
+        (BulkAction::Create, BulkObjectType::Contact) => contacts::create_contact(caller(), state(), Json(body(data)?)).await.into_response(),
+        (BulkAction::Update, BulkObjectType::Contact) => contacts::update_contact(caller(), Path(target_uuid(id)?), state(), Json(body(data)?)).await.into_response(),
+        (BulkAction::Delete, BulkObjectType::Contact) => contacts::delete_contact(caller(), Path(target_uuid(id)?), state()).await.into_response(),
+    };
+    Ok(response)
+}
+
+/// Выполнить операцию и записать её итог в аудит
+async fn execute(service: &SharedService, caller: &Caller, index: usize, operation: BulkOperation) -> Result<BulkItemResult, DirectoryError> {
+    let mut event = AuditEvent::new("bulk_operation", AuditResult::Success);
+    event.metadata.insert("index".to_string(), index.to_string());
+    event.metadata.insert("op".to_string(), format!("{:?}", operation.op).to_lowercase());
+    event.metadata.insert("type".to_string(), format!("{:?}", operation.object_type).to_lowercase());
+    if let Some(id) = &operation.id {
+        event.metadata.insert("id".to_string(), id.clone());
+    }
+
+    let response = match dispatch(service, caller, operation).await {
+        Ok(response) => response,
+        Err(e) => e.into_response(),
+    };
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap_or_default();
+    let value: Option<serde_json::Value> = serde_json::from_slice(&bytes).ok();
+
+    let mut item = BulkItemResult { index, status: status.as_u16(), error: None, code: None, result: None };
+    if status.is_success() {
+        item.result = value;
+    } else {
+        let field = |name: &str| value.as_ref()
+            .and_then(|value| value.get(name))
+            .and_then(|field| field.as_str())
+            .map(str::to_string);
+        let error = field("detail").unwrap_or_else(|| status.to_string());
+        event.result = AuditResult::Failure;
+        event.metadata.insert("error".to_string(), error.clone());
+        item.error = Some(error);
+        item.code = field("code");
+    }
+    event.metadata.insert("status".to_string(), item.status.to_string());
+    service.record(event).await?;
+    Ok(item)
+}
+
+#[utoipa::path(post, path = "/api/bulk", tag = "admin",
+    request_body = BulkRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Итог каждой операции по порядку; ошибки операций не меняют статус ответа", body = BulkResponse),
+        (status = 400, description = "Пустой список или больше 1000 операций", body = super::openapi::ErrorBody),
+    ))]
+pub async fn bulk(
+    caller: Caller,
+    State(service): State<SharedService>,
+    Json(payload): Json<BulkRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    if payload.operations.is_empty() || payload.operations.len() > MAX_BULK_OPERATIONS {
+        return Err(DirectoryError::InvalidInput(format!("Expected 1-{} operations, got {}", MAX_BULK_OPERATIONS, payload.operations.len())));
+    }
+
+    let mut results = Vec::with_capacity(payload.operations.len());
+    let mut operations = payload.operations.into_iter().enumerate().peekable();
+    while operations.peek().is_some() {
+        let batch = service.begin_batch().await;
+        let mut outcome = Ok(());
+        for (index, operation) in operations.by_ref().take(BULK_CHUNK_SIZE) {
+            match execute(&service, &caller, index, operation).await {
+                Ok(item) => results.push(item),
+                Err(e) => {
+                    outcome = Err(e);
+                    break;
+                }
+            }
+        }
+        service.end_batch(batch).await?;
+        outcome?;
+    }
+
+    let failed = results.iter().filter(|item| item.error.is_some()).count();
+    Ok((StatusCode::OK, Json(BulkResponse { succeeded: results.len() - failed, failed, results })))
+}
@@ -0,0 +1,146 @@
+// src/web/ca.rs
+
+//! Внутренний CA (секция `ca`): `GET /api/ca/certificate` и `GET /api/ca/crl` — без входа,
+//! `POST /api/ca/sign` — сертификат по CSR себе (Domain Admins — любой учётной записи),
+//! `POST /api/ca/enroll` — автоматическая выдача компьютеру по его учётной записи (как SCEP),
+//! список и отзыв — для Domain Admins. Выпуску, списку и отзыву по API-ключу нужна область `ca:issue`.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::{Authorized, CaIssue, Scoped};
+use crate::models::{IssuedCertificate, RevocationReason};
+use super::SharedService;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SignRequest {
+    /// CSR в PEM (PKCS#10); из него берётся только открытый ключ
+    pub csr: String,
+    /// Учётная запись, для которой выпускается сертификат; без поля — вызывающий
+    pub subject: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct CertificateQuery {
+    /// Только сертификаты этой учётной записи
+    pub subject: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct RevokeRequest {
+    #[serde(default)]
+    pub reason: RevocationReason,
+}
+
+#[utoipa::path(get, path = "/api/ca/certificate", tag = "ca",
+    responses(
+        (status = 200, description = "Сертификат CA", content((String = "application/x-pem-file"))),
+        (status = 404, description = "CA не создан", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_ca_certificate(State(service): State<SharedService>) -> Result<impl IntoResponse, DirectoryError> {
+    let pem = service.ca_certificate().await?;
+    Ok(([(header::CONTENT_TYPE, "application/x-pem-file")], pem))
+}
+
+#[utoipa::path(get, path = "/api/ca/crl", tag = "ca",
+    responses(
+        (status = 200, description = "Свежий CRL (DER)", content((Vec<u8> = "application/pkix-crl"))),
+        (status = 404, description = "CA не создан", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_crl(State(service): State<SharedService>) -> Result<impl IntoResponse, DirectoryError> {
+    let crl = service.certificate_revocation_list().await?;
+    Ok(([(header::CONTENT_TYPE, "application/pkix-crl")], crl))
+}
+
+#[utoipa::path(post, path = "/api/ca/sign", tag = "ca",
+    request_body = SignRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Выпущенный сертификат", body = IssuedCertificate),
+        (status = 400, description = "CSR не разбирается или у компьютера нет SPN `HOST/...`", body = super::openapi::ErrorBody),
+        (status = 403, description = "Чужая учётная запись без прав Domain Admins, учётная запись отключена или у ключа нет области `ca:issue`", body = super::openapi::ErrorBody),
+        (status = 404, description = "CA не создан или учётная запись не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn sign_certificate(
+    caller: Scoped<CaIssue>,
+    State(service): State<SharedService>,
+    Json(payload): Json<SignRequest>,
+) -> Result<Json<IssuedCertificate>, DirectoryError> {
+    let subject = match payload.subject {
+        Some(username) if !username.eq_ignore_ascii_case(&caller.user.username) => {
+            if !service.is_domain_admin(caller.user.id).await? {
+                return Err(DirectoryError::AccessDenied("Only Domain Admins can request certificates for other accounts".to_string()));
+            }
+            service.find_user_by_username(&username).await?
+                .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?
+        }
+        _ => caller.user,
+    };
+    Ok(Json(service.issue_certificate(&subject, payload.csr.as_bytes()).await?))
+}
+
+#[utoipa::path(post, path = "/api/ca/enroll", tag = "ca",
+    request_body(description = "CSR в PEM или DER", content((Vec<u8> = "application/pkcs10"))),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Сертификат компьютера", content((String = "application/x-pem-file"))),
+        (status = 400, description = "CSR не разбирается или у компьютера нет SPN `HOST/...`", body = super::openapi::ErrorBody),
+        (status = 403, description = "Вызывающий — не учётная запись компьютера или у ключа нет области `ca:issue`", body = super::openapi::ErrorBody),
+        (status = 404, description = "CA не создан", body = super::openapi::ErrorBody),
+    ))]
+pub async fn enroll_computer(
+    caller: Scoped<CaIssue>,
+    State(service): State<SharedService>,
+    body: Bytes,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let certificate = service.enroll_computer_certificate(&caller.user, &body).await?;
+    Ok(([(header::CONTENT_TYPE, "application/x-pem-file")], certificate.pem))
+}
+
+#[utoipa::path(get, path = "/api/ca/certificates", tag = "ca",
+    params(CertificateQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Выданные сертификаты, новые первыми", body = Vec<IssuedCertificate>),
+        (status = 403, description = "Нет прав администратора или области `ca:issue`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Учётная запись не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_certificates(
+    _admin: Authorized<CaIssue>,
+    Query(query): Query<CertificateQuery>,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<IssuedCertificate>>, DirectoryError> {
+    let subject_id = match query.subject {
+        Some(username) => Some(service.find_user_by_username(&username).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?
+            .id),
+        None => None,
+    };
+    Ok(Json(service.list_certificates(subject_id).await?))
+}
+
+#[utoipa::path(post, path = "/api/ca/certificates/{serial}/revoke", tag = "ca",
+    params(("serial" = String, Path, description = "Серийный номер, hex")),
+    request_body = RevokeRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Сертификат отозван и попадёт в следующий CRL", body = IssuedCertificate),
+        (status = 400, description = "Сертификат уже отозван", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `ca:issue`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Сертификат не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn revoke_certificate(
+    _admin: Authorized<CaIssue>,
+    Path(serial): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<RevokeRequest>,
+) -> Result<Json<IssuedCertificate>, DirectoryError> {
+    Ok(Json(service.revoke_certificate(&serial, payload.reason).await?))
+}
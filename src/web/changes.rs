@@ -0,0 +1,88 @@
+// src/web/changes.rs
+
+//! Журнал изменений: `GET /api/changes?since=<usn>&limit=` — изменения объектов после USN,
+//! который клиент видел последним. Внешняя система синхронизации хранит `last_usn` и передаёт
+//! его в следующем запросе.
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::{Authorized, ChangesRead};
+use crate::models::ChangeEntry;
+use super::SharedService;
+
+/// Размер страницы, если клиент его не указал, и его предел
+const DEFAULT_LIMIT: usize = 500;
+const MAX_LIMIT: usize = 5000;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct ChangesQuery {
+    /// Последний обработанный USN; 0 — с начала журнала
+    #[serde(default)]
+    pub since: u64,
+    /// Не больше изменений за запрос (по умолчанию 500, не больше 5000)
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ChangeResponse {
+    pub usn: u64,
+    pub object_id: String,
+    /// `user`, `group`, `ou`, `contact`, `gpo`, `service_account`, `site`, `subnet`, `pso`
+    pub object_type: String,
+    /// `add`, `modify` или `delete`
+    pub change_type: String,
+    pub timestamp: String,
+}
+
+impl From<ChangeEntry> for ChangeResponse {
+    fn from(change: ChangeEntry) -> Self {
+        Self {
+            usn: change.usn,
+            object_id: change.object_id.to_string(),
+            object_type: change.object_type,
+            change_type: change.change_type.as_str().to_string(),
+            timestamp: change.timestamp.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ChangesPage {
+    pub changes: Vec<ChangeResponse>,
+    /// `since` для следующего запроса
+    pub last_usn: u64,
+    /// Последний выданный USN каталога
+    pub highest_usn: u64,
+    /// Есть изменения после `last_usn`
+    pub has_more: bool,
+}
+
+#[utoipa::path(get, path = "/api/changes", tag = "changes",
+    params(ChangesQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Изменения по возрастанию USN", body = ChangesPage),
+        (status = 401, description = "Нет токена или ключа", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `changes:read`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_changes(
+    _admin: Authorized<ChangesRead>,
+    State(service): State<SharedService>,
+    Query(query): Query<ChangesQuery>,
+) -> Result<Json<ChangesPage>, DirectoryError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let highest_usn = service.highest_usn().await?;
+    let changes = service.get_changes(query.since, limit).await?;
+    let last_usn = changes.last().map_or(query.since, |change| change.usn);
+    Ok(Json(ChangesPage {
+        changes: changes.into_iter().map(Into::into).collect(),
+        last_usn,
+        highest_usn,
+        has_more: last_usn < highest_usn,
+    }))
+}
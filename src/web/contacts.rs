@@ -0,0 +1,299 @@
+// src/web/contacts.rs
+
+//! Контакты и общие почтовые ящики: `GET/POST /api/contacts`, `GET/PUT/PATCH/DELETE /api/contacts/{id}`.
+//! Учётных данных у контакта нет — он виден только в адресной книге (LDAP, LDIF).
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::directory_service::{DirectoryError, DirectoryService, SearchLimits};
+use crate::middleware::{Caller, Reader};
+use crate::models::{AccessMask, Contact, ContactKind, SchemaClass, SecuredObject};
+use super::patch::{PatchDocument, PatchField, PatchOperation};
+use super::{attribute_values, SharedService};
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateContactRequest {
+    pub name: String,
+    pub email: String,
+    #[serde(default)]
+    pub other_emails: Vec<String>,
+    pub display_name: Option<String>,
+    pub given_name: Option<String>,
+    pub surname: Option<String>,
+    pub telephone_number: Option<String>,
+    pub company: Option<String>,
+    pub description: Option<String>,
+    /// `external` (по умолчанию) или `shared_mailbox`
+    #[serde(default)]
+    #[schema(value_type = String)]
+    pub kind: ContactKind,
+    /// DN OU; без него контакт попадает в `CN=Users`
+    pub ou: Option<String>,
+    /// Дополнительные атрибуты из схемы (`/api/schema/attributes`)
+    #[serde(default)]
+    pub attributes: Option<HashMap<String, Vec<String>>>,
+}
+
+/// Изменение контакта; не указанные поля не меняются
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UpdateContactRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub other_emails: Option<Vec<String>>,
+    pub display_name: Option<String>,
+    pub given_name: Option<String>,
+    pub surname: Option<String>,
+    pub telephone_number: Option<String>,
+    pub company: Option<String>,
+    pub description: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub kind: Option<ContactKind>,
+    /// DN новой OU
+    pub ou: Option<String>,
+    /// Дополнительные атрибуты из схемы; пустой список значений удаляет атрибут
+    #[serde(default)]
+    pub attributes: Option<HashMap<String, Vec<String>>>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ContactResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    #[serde(default)]
+    pub other_emails: Vec<String>,
+    pub display_name: Option<String>,
+    pub given_name: Option<String>,
+    pub surname: Option<String>,
+    pub telephone_number: Option<String>,
+    pub company: Option<String>,
+    pub description: Option<String>,
+    #[schema(value_type = String)]
+    pub kind: ContactKind,
+    pub organizational_unit: Option<Uuid>,
+    /// Дополнительные атрибуты (`meta`); многозначные — списком
+    #[serde(default)]
+    pub attributes: BTreeMap<String, Vec<String>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<Contact> for ContactResponse {
+    fn from(contact: Contact) -> Self {
+        Self {
+            id: contact.id,
+            name: contact.name,
+            email: contact.email,
+            other_emails: contact.other_emails,
+            display_name: contact.display_name,
+            given_name: contact.given_name,
+            surname: contact.surname,
+            telephone_number: contact.telephone_number,
+            company: contact.company,
+            description: contact.description,
+            kind: contact.kind,
+            organizational_unit: contact.organizational_unit,
+            attributes: attribute_values(contact.meta),
+            created_at: contact.created_at,
+            updated_at: contact.updated_at,
+        }
+    }
+}
+
+/// OU по DN; для создания объекта в ней нужно право `CREATE_CHILD`
+async fn target_ou(service: &DirectoryService, caller: &Caller, dn: &str) -> Result<Uuid, DirectoryError> {
+    let ou = service.find_ou_by_dn(dn).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("OU not found: {}", dn)))?;
+    service.authorize(&caller.user, SecuredObject::Ou(ou.id), AccessMask::CREATE_CHILD).await?;
+    Ok(ou.id)
+}
+
+async fn find_contact(service: &DirectoryService, id: Uuid) -> Result<Contact, DirectoryError> {
+    service.get_contact(id).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Contact not found: {}", id)))
+}
+
+#[utoipa::path(get, path = "/api/contacts", tag = "contacts",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Контакты с правом `READ_PROPERTY`", body = Vec<ContactResponse>),
+        (status = 408, description = "Превышен предел времени `web_server.time_limit_secs`", body = super::openapi::ErrorBody),
+        (status = 413, description = "Превышен предел `web_server.size_limit`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_contacts(
+    reader: Reader,
+    State(service): State<SharedService>,
+    Extension(limits): Extension<SearchLimits>,
+) -> Result<Json<Vec<ContactResponse>>, DirectoryError> {
+    let contacts = service.list_contacts_within(limits).await?;
+    let contacts = service.filter_authorized(&reader.user, contacts, |contact| SecuredObject::Contact(contact.id), AccessMask::READ_PROPERTY).await?;
+    Ok(Json(contacts.into_iter().map(ContactResponse::from).collect()))
+}
+
+#[utoipa::path(get, path = "/api/contacts/{id}", tag = "contacts",
+    params(("id" = Uuid, Path, description = "ID контакта")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = ContactResponse),
+        (status = 403, description = "Нет права `READ_PROPERTY` на контакт", body = super::openapi::ErrorBody),
+        (status = 404, description = "Контакт не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_contact(
+    reader: Reader,
+    Path(id): Path<Uuid>,
+    State(service): State<SharedService>,
+) -> Result<Json<ContactResponse>, DirectoryError> {
+    let contact = find_contact(&service, id).await?;
+    service.authorize(&reader.user, SecuredObject::Contact(id), AccessMask::READ_PROPERTY).await?;
+    Ok(Json(ContactResponse::from(contact)))
+}
+
+#[utoipa::path(post, path = "/api/contacts", tag = "contacts",
+    request_body = CreateContactRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "Контакт создан", body = ContactResponse),
+        (status = 400, description = "Неверное имя, адрес или атрибуты", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет права `CREATE_CHILD` на OU или корень домена", body = super::openapi::ErrorBody),
+        (status = 404, description = "OU не найдено", body = super::openapi::ErrorBody),
+        (status = 409, description = "Адрес уже занят контактом или пользователем", body = super::openapi::ErrorBody),
+    ))]
+pub async fn create_contact(
+    caller: Caller,
+    State(service): State<SharedService>,
+    Json(payload): Json<CreateContactRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let mut contact = Contact::new(payload.name, payload.email);
+    contact.other_emails = payload.other_emails;
+    contact.display_name = payload.display_name;
+    contact.given_name = payload.given_name;
+    contact.surname = payload.surname;
+    contact.telephone_number = payload.telephone_number;
+    contact.company = payload.company;
+    contact.description = payload.description;
+    contact.kind = payload.kind;
+    match &payload.ou {
+        Some(dn) => contact.organizational_unit = Some(target_ou(&service, &caller, dn).await?),
+        None => service.authorize_create(&caller.user, None).await?,
+    }
+    if let Some(attributes) = payload.attributes {
+        crate::models::schema::apply_changes(&mut contact.meta, service.schema_attribute_changes(SchemaClass::Contact, attributes).await?);
+    }
+
+    service.create_contact(&contact).await?;
+    Ok((StatusCode::CREATED, Json(ContactResponse::from(contact))))
+}
+
+#[utoipa::path(put, path = "/api/contacts/{id}", tag = "contacts",
+    params(("id" = Uuid, Path, description = "ID контакта")),
+    request_body = UpdateContactRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = ContactResponse),
+        (status = 400, description = "Неверное имя, адрес или атрибуты", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` на контакт или `CREATE_CHILD` на новую OU", body = super::openapi::ErrorBody),
+        (status = 404, description = "Контакт или OU не найдены", body = super::openapi::ErrorBody),
+        (status = 409, description = "Адрес уже занят контактом или пользователем", body = super::openapi::ErrorBody),
+    ))]
+pub async fn update_contact(
+    caller: Caller,
+    Path(id): Path<Uuid>,
+    State(service): State<SharedService>,
+    Json(payload): Json<UpdateContactRequest>,
+) -> Result<Json<ContactResponse>, DirectoryError> {
+    let mut contact = find_contact(&service, id).await?;
+    service.authorize(&caller.user, SecuredObject::Contact(id), AccessMask::WRITE_PROPERTY).await?;
+
+    if let Some(dn) = &payload.ou {
+        contact.organizational_unit = Some(target_ou(&service, &caller, dn).await?);
+    }
+    contact.name = payload.name.unwrap_or(contact.name);
+    contact.email = payload.email.unwrap_or(contact.email);
+    contact.other_emails = payload.other_emails.unwrap_or(contact.other_emails);
+    contact.display_name = payload.display_name.or(contact.display_name);
+    contact.given_name = payload.given_name.or(contact.given_name);
+    contact.surname = payload.surname.or(contact.surname);
+    contact.telephone_number = payload.telephone_number.or(contact.telephone_number);
+    contact.company = payload.company.or(contact.company);
+    contact.description = payload.description.or(contact.description);
+    contact.kind = payload.kind.unwrap_or(contact.kind);
+    if let Some(attributes) = payload.attributes {
+        crate::models::schema::apply_changes(&mut contact.meta, service.schema_attribute_changes(SchemaClass::Contact, attributes).await?);
+    }
+    contact.updated_at = chrono::Utc::now();
+
+    service.update_contact(&contact).await?;
+    Ok(Json(ContactResponse::from(contact)))
+}
+
+/// Поля `ContactResponse`, которые меняет PATCH, и их атрибуты LDAP
+const CONTACT_PATCH_FIELDS: &[PatchField] = &[
+    ("email", "mail"),
+    ("display_name", "displayName"),
+    ("given_name", "givenName"),
+    ("surname", "sn"),
+    ("telephone_number", "telephoneNumber"),
+    ("company", "company"),
+    ("description", "description"),
+];
+
+#[utoipa::path(patch, path = "/api/contacts/{id}", tag = "contacts",
+    params(("id" = Uuid, Path, description = "ID контакта")),
+    request_body(
+        description = "Merge patch: `null` очищает поле; JSON Patch: `add`, `remove`, `replace`, `test`",
+        content((Object = "application/merge-patch+json"), (Vec<PatchOperation> = "application/json-patch+json")),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = ContactResponse),
+        (status = 400, description = "Поле только для чтения, неверное значение или операция", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` на контакт", body = super::openapi::ErrorBody),
+        (status = 404, description = "Контакт не найден", body = super::openapi::ErrorBody),
+        (status = 409, description = "Адрес уже занят, не прошла операция `test`", body = super::openapi::ErrorBody),
+        (status = 415, description = "Тело не merge patch и не JSON Patch", body = super::openapi::ErrorBody),
+    ))]
+pub async fn patch_contact(
+    caller: Caller,
+    Path(id): Path<Uuid>,
+    State(service): State<SharedService>,
+    document: PatchDocument,
+) -> Result<Json<ContactResponse>, DirectoryError> {
+    let contact = find_contact(&service, id).await?;
+    service.authorize(&caller.user, SecuredObject::Contact(id), AccessMask::WRITE_PROPERTY).await?;
+
+    let current = serde_json::to_value(ContactResponse::from(contact))
+        .map_err(|e| DirectoryError::Serialization(e.to_string()))?;
+    let changes = document.attribute_changes(CONTACT_PATCH_FIELDS, &current)?;
+    if !changes.is_empty() {
+        service.modify_object(id, changes).await?;
+    }
+    Ok(Json(ContactResponse::from(find_contact(&service, id).await?)))
+}
+
+#[utoipa::path(delete, path = "/api/contacts/{id}", tag = "contacts",
+    params(("id" = Uuid, Path, description = "ID контакта")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Контакт удалён"),
+        (status = 403, description = "Нет права `DELETE` на контакт", body = super::openapi::ErrorBody),
+        (status = 404, description = "Контакт не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn delete_contact(
+    caller: Caller,
+    Path(id): Path<Uuid>,
+    State(service): State<SharedService>,
+) -> Result<StatusCode, DirectoryError> {
+    find_contact(&service, id).await?;
+    service.authorize(&caller.user, SecuredObject::Contact(id), AccessMask::DELETE).await?;
+    service.delete_contact(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,68 @@
+// src/web/cors.rs
+
+//! CORS по секции `web_server.cors`. Слой хранится в `SharedCors` и применяется к каждому запросу,
+//! поэтому перезагрузка конфигурации меняет источники без перезапуска сервера.
+
+use std::sync::{Arc, RwLock};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use tower::{Layer, ServiceExt};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::config::CorsConfig;
+use crate::web::access_log::REQUEST_ID_HEADER;
+
+/// CORS по секции `web_server.cors`; без источников заголовки CORS не выдаются
+pub fn cors_layer(config: &CorsConfig) -> Result<CorsLayer, String> {
+    let origin = if config.allowed_origins.iter().any(|origin| origin == "*") {
+        if config.allow_credentials {
+            return Err("web_server.cors: allowed_origins '*' cannot be combined with allow_credentials".into());
+        }
+        AllowOrigin::any()
+    } else {
+        let origins = config.allowed_origins.iter()
+            .map(|origin| origin.trim_end_matches('/').parse::<HeaderValue>()
+                .map_err(|_| format!("web_server.cors: invalid origin '{}'", origin)))
+            .collect::<Result<Vec<_>, _>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(origin)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, HeaderName::from_static("x-api-key"), HeaderName::from_static(REQUEST_ID_HEADER)])
+        .expose_headers([header::RETRY_AFTER, HeaderName::from_static(REQUEST_ID_HEADER)])
+        .allow_credentials(config.allow_credentials)
+        .max_age(std::time::Duration::from_secs(config.max_age_secs)))
+}
+
+/// Текущий слой CORS, общий для сервера и перезагрузки конфигурации
+#[derive(Clone)]
+pub struct SharedCors(Arc<RwLock<CorsLayer>>);
+
+impl SharedCors {
+    pub fn new(config: &CorsConfig) -> Result<Self, String> {
+        Ok(Self(Arc::new(RwLock::new(cors_layer(config)?))))
+    }
+
+    /// Заменить настройки; при ошибке действуют прежние
+    pub fn set(&self, config: &CorsConfig) -> Result<(), String> {
+        let layer = cors_layer(config)?;
+        *self.0.write().unwrap() = layer;
+        Ok(())
+    }
+}
+
+/// Промежуточный слой: запрос проходит через слой CORS, действующий в момент запроса
+pub async fn apply_cors(State(cors): State<SharedCors>, request: Request, next: Next) -> Response {
+    let layer = cors.0.read().unwrap().clone();
+    match layer.layer(next).oneshot(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    }
+}
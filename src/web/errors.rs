@@ -0,0 +1,92 @@
+// src/web/errors.rs
+
+//! Единый вид ошибок REST — problem+json (RFC 7807): `type`, `title`, `status`, `detail` и стабильный
+//! код `code` (`ErrorCode`). Ошибки каталога и входа получают код при формировании ответа, а
+//! `normalize_errors` переписывает в тот же вид ответы, которые собирает сам axum: отказы
+//! извлекателей (неверный JSON, превышен `web_server.max_request_size`), 404 и 405
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+
+use crate::error_code::ErrorCode;
+
+/// Тип содержимого ответов с ошибкой
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Сколько текста ошибки axum переносится в `detail`
+const MAX_ERROR_TEXT: usize = 4096;
+
+/// Тело problem+json; поля расширения (`referral`, `size_limit`, ...) добавляет вызывающий
+pub fn problem_body(status: StatusCode, code: ErrorCode, detail: &str) -> Value {
+    json!({
+        "type": code.type_uri(),
+        "title": code.title(),
+        "status": status.as_u16(),
+        "detail": detail,
+        "code": code,
+    })
+}
+
+/// Ответ с телом problem+json
+pub fn problem_response(status: StatusCode, body: Value) -> Response {
+    let mut response = (status, Json(body)).into_response();
+    response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+    response
+}
+
+/// Ответ problem+json без полей расширения
+pub fn problem(status: StatusCode, code: ErrorCode, detail: &str) -> Response {
+    problem_response(status, problem_body(status, code, detail))
+}
+
+/// Код по статусу — для ошибок, о которых известен только статус
+pub fn status_error_code(status: StatusCode) -> ErrorCode {
+    match status {
+        StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+        StatusCode::FORBIDDEN => ErrorCode::AccessDenied,
+        StatusCode::NOT_FOUND => ErrorCode::NotFound,
+        StatusCode::METHOD_NOT_ALLOWED => ErrorCode::MethodNotAllowed,
+        StatusCode::REQUEST_TIMEOUT => ErrorCode::Timeout,
+        StatusCode::CONFLICT => ErrorCode::Conflict,
+        StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => ErrorCode::UnsupportedMediaType,
+        StatusCode::TOO_MANY_REQUESTS => ErrorCode::RateLimited,
+        StatusCode::SERVICE_UNAVAILABLE => ErrorCode::Unavailable,
+        status if status.is_server_error() => ErrorCode::InternalError,
+        _ => ErrorCode::InvalidRequest,
+    }
+}
+
+/// Слой REST: ответ с ошибкой не в JSON становится problem+json с тем же статусом. HTML не
+/// трогается: это страницы для браузера (форма входа OIDC с сообщением об ошибке)
+pub async fn normalize_errors(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let status = response.status();
+    let content_type = response.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let keep = content_type.starts_with("application/json") || content_type.contains("+json") || content_type.starts_with("text/html");
+    if !(status.is_client_error() || status.is_server_error()) || keep {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let text = match axum::body::to_bytes(body, MAX_ERROR_TEXT).await {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).trim().to_string(),
+        Err(_) => String::new(),
+    };
+    let detail = if text.is_empty() { status.canonical_reason().unwrap_or("Error").to_string() } else { text };
+    let body = problem_body(status, status_error_code(status), &detail).to_string();
+
+    parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
@@ -0,0 +1,139 @@
+// src/web/events.rs
+
+//! Поток событий аудита для администраторов: SSE (`/api/events/stream`) и WebSocket (`/api/events/ws`).
+//! Медленный подписчик не тормозит каталог: broadcast-канал отбрасывает для него старые события,
+//! а клиент получает событие `lagged` с числом пропущенных. Браузер не задаёт заголовки
+//! EventSource и WebSocket, поэтому здесь токен принимается и в `?access_token=` (`QueryToken`).
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+};
+use futures_util::stream::{self, Stream};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::events::AuditEvent;
+use crate::middleware::{Authorized, EventsRead, QueryToken};
+use super::SharedService;
+
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventStreamQuery {
+    /// Типы событий через запятую; `*` в конце — префикс: `create_user,delete_*`
+    pub types: Option<String>,
+}
+
+/// Фильтр по типу (action) события; пустой пропускает всё
+#[derive(Clone, Default)]
+struct EventFilter(Vec<String>);
+
+impl EventFilter {
+    fn parse(types: Option<&str>) -> Self {
+        Self(types
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn matches(&self, action: &str) -> bool {
+        self.0.is_empty() || self.0.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => action.starts_with(prefix),
+            None => action == pattern,
+        })
+    }
+}
+
+/// Следующее подходящее событие или сообщение о пропуске; `None` — канал закрыт
+enum Next {
+    Event(Box<AuditEvent>),
+    Lagged(u64),
+}
+
+async fn next_event(receiver: &mut Receiver<AuditEvent>, filter: &EventFilter) -> Option<Next> {
+    loop {
+        match receiver.recv().await {
+            Ok(event) if filter.matches(&event.action) => return Some(Next::Event(Box::new(event))),
+            Ok(_) => continue,
+            Err(RecvError::Lagged(missed)) => return Some(Next::Lagged(missed)),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/api/events/stream", tag = "events",
+    params(EventStreamQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Server-Sent Events: `audit` с событием в JSON, `lagged` с числом пропущенных", content_type = "text/event-stream", body = AuditEvent),
+        (status = 401, description = "Нет токена или ключа", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `events:read`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn stream_events(
+    _admin: QueryToken<Authorized<EventsRead>>,
+    State(service): State<SharedService>,
+    Query(query): Query<EventStreamQuery>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let filter = EventFilter::parse(query.types.as_deref());
+    let receiver = service.events().subscribe();
+
+    let events = stream::unfold((receiver, filter), |(mut receiver, filter)| async move {
+        let event = match next_event(&mut receiver, &filter).await? {
+            Next::Event(event) => Event::default().event("audit").id(event.id.to_string()).json_data(&event),
+            Next::Lagged(missed) => Ok(Event::default().event("lagged").data(json!({ "missed": missed }).to_string())),
+        };
+        Some((event, (receiver, filter)))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[utoipa::path(get, path = "/api/events/ws", tag = "events",
+    params(EventStreamQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 101, description = "WebSocket: сообщения `audit` с событием и `lagged` с числом пропущенных"),
+        (status = 401, description = "Нет токена или ключа", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `events:read`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn websocket_events(
+    _admin: QueryToken<Authorized<EventsRead>>,
+    State(service): State<SharedService>,
+    Query(query): Query<EventStreamQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let filter = EventFilter::parse(query.types.as_deref());
+    let receiver = service.events().subscribe();
+    ws.on_upgrade(move |socket| forward_events(socket, receiver, filter))
+}
+
+async fn forward_events(mut socket: WebSocket, mut receiver: Receiver<AuditEvent>, filter: EventFilter) {
+    loop {
+        tokio::select! {
+            next = next_event(&mut receiver, &filter) => {
+                let message = match next {
+                    Some(Next::Event(event)) => json!({ "type": "audit", "event": event }),
+                    Some(Next::Lagged(missed)) => json!({ "type": "lagged", "missed": missed }),
+                    None => break,
+                };
+                if socket.send(Message::Text(message.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+        }
+    }
+}
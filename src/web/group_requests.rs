@@ -0,0 +1,222 @@
+// src/web/group_requests.rs
+
+//! Владельцы, срок действия и заявки на вступление в группы:
+//! `PUT /api/groups/{sam}/lifecycle` — владелец и срок (право `WRITE_PROPERTY`);
+//! `POST /api/groups/{sam}/requests` — заявка от вызывающего;
+//! `GET /api/groups/{sam}/requests`, `POST /api/groups/{sam}/requests/{id}/approve|deny` —
+//! для владельца группы и держателей `WRITE_PROPERTY`.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::middleware::Caller;
+use crate::models::{AccessMask, Group, GroupJoinRequest, JoinRequestStatus, SecuredObject};
+use super::{GroupResponse, SharedService};
+
+/// Владелец и срок действия группы; оба поля задаются целиком, `null` снимает значение
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct GroupLifecycleRequest {
+    /// Имя пользователя-владельца (managedBy)
+    pub managed_by: Option<String>,
+    /// После этого момента группа отключается: её членство перестаёт действовать
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct JoinGroupRequest {
+    /// Зачем нужно членство — для владельца группы
+    pub justification: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct DecideJoinRequest {
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct JoinRequestResponse {
+    pub id: Uuid,
+    pub group: String,
+    pub username: String,
+    pub justification: Option<String>,
+    /// `pending`, `approved` или `denied`
+    #[schema(value_type = String)]
+    pub status: JoinRequestStatus,
+    pub requested_at: DateTime<Utc>,
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub comment: Option<String>,
+}
+
+impl JoinRequestResponse {
+    pub async fn new(service: &DirectoryService, group: &Group, request: GroupJoinRequest) -> Result<Self, DirectoryError> {
+        let username = service.get_user(request.user_id).await?
+            .map(|user| user.username)
+            .unwrap_or_else(|| request.user_id.to_string());
+        Ok(Self {
+            id: request.id,
+            group: group.sam_account_name.clone(),
+            username,
+            justification: request.justification,
+            status: request.status,
+            requested_at: request.requested_at,
+            decided_by: request.decided_by,
+            decided_at: request.decided_at,
+            comment: request.comment,
+        })
+    }
+}
+
+async fn find_group(service: &DirectoryService, sam: &str) -> Result<Group, DirectoryError> {
+    service.find_group_by_sam_account_name(sam)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", sam)))
+}
+
+/// Группа, заявки которой вызывающий может просматривать и решать
+async fn find_managed_group(service: &DirectoryService, caller: &Caller, sam: &str) -> Result<Group, DirectoryError> {
+    let group = find_group(service, sam).await?;
+    if !service.can_manage_group_membership(&caller.user, &group).await? {
+        return Err(DirectoryError::AccessDenied(format!("{} is neither the owner of {} nor allowed to manage it", caller.user.username, sam)));
+    }
+    Ok(group)
+}
+
+#[utoipa::path(put, path = "/api/groups/{sam}/lifecycle", tag = "groups",
+    params(("sam" = String, Path, description = "sAMAccountName группы")),
+    request_body = GroupLifecycleRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Владелец и срок сохранены; срок в будущем снова включает группу", body = GroupResponse),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` или срок для встроенной группы", body = super::openapi::ErrorBody),
+        (status = 404, description = "Группа или владелец не найдены", body = super::openapi::ErrorBody),
+    ))]
+pub async fn set_group_lifecycle(
+    caller: Caller,
+    Path(sam): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<GroupLifecycleRequest>,
+) -> Result<Json<GroupResponse>, DirectoryError> {
+    let group = find_group(&service, &sam).await?;
+    service.authorize(&caller.user, SecuredObject::Group(group.id), AccessMask::WRITE_PROPERTY).await?;
+
+    let managed_by = match &payload.managed_by {
+        Some(username) => Some(service.find_user_by_username(username).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?.id),
+        None => None,
+    };
+    let group = service.set_group_lifecycle(group.id, managed_by, payload.expires_at).await?;
+    Ok(Json(GroupResponse::from(group)))
+}
+
+#[utoipa::path(post, path = "/api/groups/{sam}/requests", tag = "groups",
+    params(("sam" = String, Path, description = "sAMAccountName группы")),
+    request_body = JoinGroupRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "Заявка создана и ждёт решения владельца", body = JoinRequestResponse),
+        (status = 400, description = "Динамическая или отключённая группа", body = super::openapi::ErrorBody),
+        (status = 404, description = "Группа не найдена", body = super::openapi::ErrorBody),
+        (status = 409, description = "Вызывающий уже участник или его заявка уже ждёт решения", body = super::openapi::ErrorBody),
+    ))]
+pub async fn request_group_membership(
+    caller: Caller,
+    Path(sam): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<JoinGroupRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let group = find_group(&service, &sam).await?;
+    let request = service.request_group_membership(group.id, caller.user.id, payload.justification).await?;
+    Ok((StatusCode::CREATED, Json(JoinRequestResponse::new(&service, &group, request).await?)))
+}
+
+#[utoipa::path(get, path = "/api/groups/{sam}/requests", tag = "groups",
+    params(("sam" = String, Path, description = "sAMAccountName группы")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Заявки группы от старых к новым", body = Vec<JoinRequestResponse>),
+        (status = 403, description = "Вызывающий не владелец и не имеет `WRITE_PROPERTY`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Группа не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_group_requests(
+    caller: Caller,
+    Path(sam): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<JoinRequestResponse>>, DirectoryError> {
+    let group = find_managed_group(&service, &caller, &sam).await?;
+    let mut requests = Vec::new();
+    for request in service.get_group_join_requests(group.id).await? {
+        requests.push(JoinRequestResponse::new(&service, &group, request).await?);
+    }
+    Ok(Json(requests))
+}
+
+#[utoipa::path(post, path = "/api/groups/{sam}/requests/{id}/approve", tag = "groups",
+    params(
+        ("sam" = String, Path, description = "sAMAccountName группы"),
+        ("id" = Uuid, Path, description = "id заявки"),
+    ),
+    request_body = DecideJoinRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Заявка одобрена, пользователь добавлен в группу", body = JoinRequestResponse),
+        (status = 400, description = "Заявка уже решена", body = super::openapi::ErrorBody),
+        (status = 403, description = "Вызывающий не владелец и не имеет `WRITE_PROPERTY`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Группа или заявка не найдены", body = super::openapi::ErrorBody),
+    ))]
+pub async fn approve_group_request(
+    caller: Caller,
+    Path((sam, id)): Path<(String, Uuid)>,
+    State(service): State<SharedService>,
+    Json(payload): Json<DecideJoinRequest>,
+) -> Result<Json<JoinRequestResponse>, DirectoryError> {
+    decide(&service, &caller, &sam, id, true, payload.comment).await
+}
+
+#[utoipa::path(post, path = "/api/groups/{sam}/requests/{id}/deny", tag = "groups",
+    params(
+        ("sam" = String, Path, description = "sAMAccountName группы"),
+        ("id" = Uuid, Path, description = "id заявки"),
+    ),
+    request_body = DecideJoinRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Заявка отклонена", body = JoinRequestResponse),
+        (status = 400, description = "Заявка уже решена", body = super::openapi::ErrorBody),
+        (status = 403, description = "Вызывающий не владелец и не имеет `WRITE_PROPERTY`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Группа или заявка не найдены", body = super::openapi::ErrorBody),
+    ))]
+pub async fn deny_group_request(
+    caller: Caller,
+    Path((sam, id)): Path<(String, Uuid)>,
+    State(service): State<SharedService>,
+    Json(payload): Json<DecideJoinRequest>,
+) -> Result<Json<JoinRequestResponse>, DirectoryError> {
+    decide(&service, &caller, &sam, id, false, payload.comment).await
+}
+
+async fn decide(
+    service: &DirectoryService,
+    caller: &Caller,
+    sam: &str,
+    id: Uuid,
+    approve: bool,
+    comment: Option<String>,
+) -> Result<Json<JoinRequestResponse>, DirectoryError> {
+    let group = find_managed_group(service, caller, sam).await?;
+    // Заявка другой группы для этого пути не существует
+    let not_found = || DirectoryError::NotFound(format!("Membership request not found: {}", id));
+    if service.get_group_join_request(id).await?.ok_or_else(not_found)?.group_id != group.id {
+        return Err(not_found());
+    }
+    let request = service.decide_group_join_request(id, approve, Some(caller.user.id), comment).await?;
+    Ok(Json(JoinRequestResponse::new(service, &group, request).await?))
+}
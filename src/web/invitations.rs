@@ -0,0 +1,237 @@
+// src/web/invitations.rs
+
+//! Приглашения: `POST /api/users/invite` создаёт выключенного пользователя и отправляет ему
+//! ссылку, по которой `POST /api/invitations/{token}/accept` задаёт пароль и MFA и включает
+//! учётную запись. Повторная отправка и отзыв — `POST` / `DELETE /api/users/{username}/invitation`.
+//! Включается `security.invitations`, письма отправляет `mail.smtp`
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::InvitationConfig;
+use crate::directory_service::DirectoryError;
+use crate::mail::{token_url, OtpMessage, OtpSender};
+use crate::middleware::{Authorized, Caller, ClientIp, DirectoryWrite};
+use crate::models::{AccessMask, Invitation, MfaMethod, SecuredObject, User};
+use super::{CreateUserRequest, SharedService, UserResponse};
+
+/// Настройки приглашений и отправитель писем
+pub struct Invitations {
+    config: InvitationConfig,
+    sender: Option<Arc<dyn OtpSender>>,
+}
+
+impl Invitations {
+    pub fn new(config: &InvitationConfig, sender: Option<Arc<dyn OtpSender>>) -> Self {
+        Self { config: config.clone(), sender }
+    }
+
+    fn sender(&self) -> Result<&Arc<dyn OtpSender>, DirectoryError> {
+        match &self.sender {
+            Some(sender) if self.config.enabled => Ok(sender),
+            _ => Err(DirectoryError::AccessDenied("invitations are disabled".to_string())),
+        }
+    }
+
+    fn ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.config.token_ttl_secs.try_into().unwrap_or(i64::MAX))
+    }
+
+    /// Отправить ссылку; сбой доставки не отменяет приглашение — его можно отправить повторно
+    async fn send(&self, user: &User, invitation: &Invitation, token: &str) -> Result<bool, DirectoryError> {
+        let sender = self.sender()?;
+        let Some(email) = user.email.clone() else {
+            return Ok(false);
+        };
+        let message = OtpMessage {
+            to: email,
+            subject: self.config.subject.clone(),
+            body: format!(
+                "An account {} has been created for you.\n\nTo set your password and activate it, open this link:\n\n{}\n\nThe link is valid until {}.\n",
+                user.username,
+                token_url(self.config.accept_url.as_deref().unwrap_or_default(), token),
+                invitation.expires_at.format("%Y-%m-%d %H:%M UTC"),
+            ),
+        };
+        match sender.send(&message).await {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                tracing::error!(username = %user.username, error = %e, "Не удалось отправить приглашение");
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct InviteUserRequest {
+    /// Как при создании пользователя; `email` обязателен
+    #[serde(flatten)]
+    pub user: CreateUserRequest,
+    /// DN OU для нового пользователя; нужно право `CREATE_CHILD` на неё
+    pub ou: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct AcceptInvitationRequest {
+    pub password: String,
+    /// Методы MFA; пустой список — без MFA
+    #[serde(default)]
+    pub mfa_methods: Vec<MfaMethod>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct InvitationResponse {
+    pub user_id: Uuid,
+    pub username: String,
+    pub email: Option<String>,
+    pub invited_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub expired: bool,
+    /// Ушло ли письмо; только в ответ на создание и повторную отправку
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_sent: Option<bool>,
+}
+
+impl InvitationResponse {
+    fn new(invitation: Invitation, user: &User, email_sent: Option<bool>) -> Self {
+        Self {
+            user_id: invitation.user_id,
+            username: user.username.clone(),
+            email: user.email.clone(),
+            invited_by: invitation.invited_by,
+            created_at: invitation.created_at,
+            expires_at: invitation.expires_at,
+            expired: invitation.is_expired(),
+            email_sent,
+        }
+    }
+}
+
+async fn find_user(service: &SharedService, username: &str) -> Result<User, DirectoryError> {
+    service.find_user_by_username(username)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))
+}
+
+#[utoipa::path(post, path = "/api/users/invite", tag = "users",
+    request_body = InviteUserRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "Пользователь создан выключенным, ссылка отправлена", body = InvitationResponse),
+        (status = 400, description = "Неверные данные или нет email", body = super::openapi::ErrorBody),
+        (status = 403, description = "Приглашения выключены или нет права `CREATE_CHILD` на OU или корень домена", body = super::openapi::ErrorBody),
+        (status = 404, description = "OU не найдено", body = super::openapi::ErrorBody),
+        (status = 409, description = "Имя или email уже заняты", body = super::openapi::ErrorBody),
+    ))]
+pub async fn invite_user(
+    caller: Caller,
+    State(service): State<SharedService>,
+    Extension(invitations): Extension<Arc<Invitations>>,
+    Json(payload): Json<InviteUserRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    invitations.sender()?;
+    if payload.user.email.is_none() {
+        return Err(DirectoryError::InvalidInput("An invited user needs an email".to_string()));
+    }
+    let mut user = super::new_user(&service, payload.user).await?;
+    if let Some(dn) = &payload.ou {
+        let ou = service.find_ou_by_dn(dn).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("OU not found: {}", dn)))?;
+        user.organizational_unit = Some(ou.id);
+    }
+    service.authorize_create(&caller.user, user.organizational_unit).await?;
+
+    let (invitation, token) = service.invite_user(&user, Some(caller.user.id), invitations.ttl()).await?;
+    let email_sent = invitations.send(&user, &invitation, &token).await?;
+    Ok((StatusCode::CREATED, Json(InvitationResponse::new(invitation, &user, Some(email_sent)))))
+}
+
+#[utoipa::path(get, path = "/api/invitations", tag = "users",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Непринятые приглашения, в том числе просроченные", body = Vec<InvitationResponse>),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_invitations(
+    _admin: Authorized<DirectoryWrite>,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<InvitationResponse>>, DirectoryError> {
+    let mut response = Vec::new();
+    for invitation in service.list_invitations().await? {
+        if let Some(user) = service.get_user(invitation.user_id).await? {
+            response.push(InvitationResponse::new(invitation, &user, None));
+        }
+    }
+    Ok(Json(response))
+}
+
+#[utoipa::path(post, path = "/api/users/{username}/invitation", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Отправлена новая ссылка, прежняя больше не действует", body = InvitationResponse),
+        (status = 403, description = "Приглашения выключены или нет права `WRITE_PROPERTY` на пользователя", body = super::openapi::ErrorBody),
+        (status = 404, description = "Нет пользователя или непринятого приглашения", body = super::openapi::ErrorBody),
+    ))]
+pub async fn resend_invitation(
+    caller: Caller,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+    Extension(invitations): Extension<Arc<Invitations>>,
+) -> Result<Json<InvitationResponse>, DirectoryError> {
+    invitations.sender()?;
+    let user = find_user(&service, &username).await?;
+    service.authorize(&caller.user, SecuredObject::User(user.id), AccessMask::WRITE_PROPERTY).await?;
+
+    let (invitation, token) = service.resend_invitation(user.id, invitations.ttl()).await?;
+    let email_sent = invitations.send(&user, &invitation, &token).await?;
+    Ok(Json(InvitationResponse::new(invitation, &user, Some(email_sent))))
+}
+
+#[utoipa::path(delete, path = "/api/users/{username}/invitation", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Приглашение отозвано, приглашённый пользователь удалён"),
+        (status = 403, description = "Нет права `DELETE` на пользователя", body = super::openapi::ErrorBody),
+        (status = 404, description = "Нет пользователя или непринятого приглашения", body = super::openapi::ErrorBody),
+    ))]
+pub async fn revoke_invitation(
+    caller: Caller,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let user = find_user(&service, &username).await?;
+    service.authorize(&caller.user, SecuredObject::User(user.id), AccessMask::DELETE).await?;
+
+    service.revoke_invitation(user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(post, path = "/api/invitations/{token}/accept", tag = "auth",
+    params(("token" = String, Path, description = "Токен из ссылки в письме")),
+    request_body = AcceptInvitationRequest,
+    responses(
+        (status = 200, description = "Пароль задан, учётная запись включена", body = UserResponse),
+        (status = 400, description = "Приглашение неверное или истекло, либо пароль не проходит парольную политику", body = super::openapi::ErrorBody),
+    ))]
+pub async fn accept_invitation(
+    Path(token): Path<String>,
+    State(service): State<SharedService>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<AcceptInvitationRequest>,
+) -> Result<Json<UserResponse>, DirectoryError> {
+    let user = service.accept_invitation(&token, &payload.password, payload.mfa_methods, Some(ip.to_string())).await?;
+    Ok(Json(UserResponse::from(user)))
+}
@@ -0,0 +1,238 @@
+// src/web/listener.rs
+
+//! Сокеты REST API: TCP (`127.0.0.1:8080`, `[::]:8080`), Unix-сокет (`unix:/run/nextdomen.sock`)
+//! для обратного прокси на том же узле или сокеты, переданные systemd (`LISTEN_FDS`, socket activation).
+//! Клиенты Unix-сокета видны как `127.0.0.1`: настоящий адрес знает только прокси, он передаёт
+//! его в X-Forwarded-For или заголовком PROXY (`web_server.proxy`).
+//! При `web_server.enable_tls` TCP-сокет принимает только HTTPS; Unix-сокет — всегда HTTP.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::extract::ConnectInfo;
+use axum::{Extension, Router};
+use hyper_util::rt::TokioIo;
+use tokio::net::{TcpListener, UnixListener};
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+
+use crate::config::{TlsConfig, UnixSocketConfig};
+use crate::proxy::{self, TrustedProxies};
+
+/// Префикс адреса Unix-сокета
+pub const UNIX_PREFIX: &str = "unix:";
+
+/// Первый сокет, который передаёт systemd
+const SD_LISTEN_FDS_START: i32 = 3;
+
+/// Столько ждём заголовок PROXY от доверенного прокси
+const PROXY_HEADER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+pub enum WebListener {
+    Tcp(TcpListener),
+    /// Путь удаляется при остановке, если сокет создан процессом, а не systemd
+    Unix(UnixListener, Option<PathBuf>),
+}
+
+impl WebListener {
+    /// Сокеты от systemd, если процесс запущен через socket activation, иначе по сокету на адрес
+    pub async fn bind_all(addrs: &[String], unix_socket: &UnixSocketConfig) -> std::io::Result<Vec<Self>> {
+        let systemd = Self::from_systemd()?;
+        if !systemd.is_empty() {
+            return Ok(systemd);
+        }
+        let mut listeners = Vec::with_capacity(addrs.len());
+        for addr in addrs {
+            listeners.push(Self::bind(addr, unix_socket).await?);
+        }
+        Ok(listeners)
+    }
+
+    /// Сокет на один адрес, без сокетов systemd
+    pub async fn bind(addr: &str, unix_socket: &UnixSocketConfig) -> std::io::Result<Self> {
+        match addr.strip_prefix(UNIX_PREFIX) {
+            Some(path) => Self::bind_unix(Path::new(path), unix_socket),
+            None => Ok(WebListener::Tcp(crate::net::bind_tcp(addr).await?)),
+        }
+    }
+
+    fn bind_unix(path: &Path, config: &UnixSocketConfig) -> std::io::Result<Self> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+        // Сокет от прошлого запуска; обычный файл по этому пути не трогаем
+        if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        let mode = u32::from_str_radix(&config.mode, 8)
+            .map_err(|_| invalid(format!("web_server.unix_socket.mode '{}' is not an octal mode", config.mode)))?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+        if let Some(group) = &config.group {
+            std::os::unix::fs::chown(path, None, Some(resolve_group(group)?))?;
+        }
+        Ok(WebListener::Unix(listener, Some(path.to_path_buf())))
+    }
+
+    /// Сокеты из `LISTEN_FDS`, если `LISTEN_PID` — этот процесс
+    fn from_systemd() -> std::io::Result<Vec<Self>> {
+        let for_us = std::env::var("LISTEN_PID").ok().and_then(|pid| pid.parse::<u32>().ok()) == Some(std::process::id());
+        let count = std::env::var("LISTEN_FDS").ok().and_then(|count| count.parse::<i32>().ok()).unwrap_or(0);
+        if !for_us {
+            return Ok(Vec::new());
+        }
+        (SD_LISTEN_FDS_START..SD_LISTEN_FDS_START + count).map(Self::from_fd).collect()
+    }
+
+    fn from_fd(fd: i32) -> std::io::Result<Self> {
+        use std::os::unix::io::{FromRawFd, IntoRawFd};
+
+        // SAFETY: по протоколу socket activation дескрипторы с 3 по 3 + LISTEN_FDS открыты
+        // systemd для этого процесса и больше никем не используются
+        let tcp = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+        if tcp.local_addr().is_ok() {
+            tcp.set_nonblocking(true)?;
+            return Ok(WebListener::Tcp(TcpListener::from_std(tcp)?));
+        }
+        // Не TCP: getsockname вернул адрес Unix-сокета
+        // SAFETY: тот же дескриптор, владение передаётся из `tcp`
+        let unix = unsafe { std::os::unix::net::UnixListener::from_raw_fd(tcp.into_raw_fd()) };
+        unix.set_nonblocking(true)?;
+        Ok(WebListener::Unix(UnixListener::from_std(unix)?, None))
+    }
+
+    /// Адрес для журнала
+    pub fn describe(&self) -> String {
+        match self {
+            WebListener::Tcp(listener) => listener.local_addr().map(|addr| addr.to_string()).unwrap_or_default(),
+            WebListener::Unix(listener, _) => listener.local_addr().ok()
+                .and_then(|addr| addr.as_pathname().map(|path| format!("{}{}", UNIX_PREFIX, path.display())))
+                .unwrap_or_else(|| format!("{}(systemd)", UNIX_PREFIX)),
+        }
+    }
+
+    /// С `tls` соединения TCP-сокета принимаются только по TLS. Соединения от прокси из
+    /// `proxies` с `proxy_protocol` начинаются заголовком PROXY — адрес клиента берётся из него
+    pub async fn serve(self, app: Router, tls: Option<TlsAcceptor>, proxies: Arc<TrustedProxies>) -> std::io::Result<()> {
+        match (self, tls) {
+            (WebListener::Tcp(listener), None) if !proxies.proxy_protocol() => {
+                axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await
+            }
+            (WebListener::Tcp(listener), tls) => loop {
+                let (stream, peer) = listener.accept().await?;
+                tokio::spawn(accept_connection(stream, peer, app.clone(), tls.clone(), Arc::clone(&proxies)));
+            },
+            (WebListener::Unix(listener, path), tls) => {
+                if tls.is_some() {
+                    tracing::warn!("web_server.enable_tls не действует на Unix-сокете: TLS завершает обратный прокси");
+                }
+                let _cleanup = path.map(SocketCleanup);
+                let peer = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+                loop {
+                    let (stream, _) = listener.accept().await?;
+                    tokio::spawn(accept_connection(stream, peer, app.clone(), None, Arc::clone(&proxies)));
+                }
+            }
+        }
+    }
+}
+
+/// Заголовок PROXY от доверенного прокси, затем TLS, если он включён, и HTTP
+async fn accept_connection<S>(mut stream: S, peer: SocketAddr, app: Router, tls: Option<TlsAcceptor>, proxies: Arc<TrustedProxies>)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    let mut client = peer;
+    if proxies.expects_proxy_header(crate::net::client_ip(peer)) {
+        match tokio::time::timeout(PROXY_HEADER_TIMEOUT, proxy::read_header(&mut stream)).await {
+            Ok(Ok(source)) => client = source.unwrap_or(peer),
+            Ok(Err(e)) => {
+                tracing::debug!(%peer, error = %e, "Заголовок PROXY не разобран");
+                return;
+            }
+            Err(_) => {
+                tracing::debug!(%peer, "Прокси не прислал заголовок PROXY вовремя");
+                return;
+            }
+        }
+    }
+    let app = app.layer(Extension(ConnectInfo(client)));
+    match tls {
+        Some(acceptor) => match acceptor.accept(stream).await {
+            Ok(stream) => serve_connection(stream, app).await,
+            Err(e) => tracing::debug!(%peer, error = %e, "TLS-рукопожатие не удалось"),
+        },
+        None => serve_connection(stream, app).await,
+    }
+}
+
+/// HTTP/1.1 поверх принятого соединения, с WebSocket
+async fn serve_connection<S>(stream: S, app: Router)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    let service = hyper::service::service_fn(move |request| app.clone().oneshot(request));
+    let connection = hyper::server::conn::http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .with_upgrades();
+    if let Err(e) = connection.await {
+        tracing::debug!(error = %e, "Соединение закрыто с ошибкой");
+    }
+}
+
+/// TLS REST API по `web_server.tls`: сертификат и ключ сервера, клиентские сертификаты не запрашиваются
+pub fn tls_acceptor(tls: &TlsConfig) -> std::io::Result<TlsAcceptor> {
+    let cert_file = tls.cert_file.as_deref().ok_or_else(|| invalid("web_server.tls.cert_file is required when enable_tls is set".to_string()))?;
+    let key_file = tls.key_file.as_deref().ok_or_else(|| invalid("web_server.tls.key_file is required when enable_tls is set".to_string()))?;
+    let certs: Vec<rustls::Certificate> = rustls_pemfile::certs(&mut BufReader::new(File::open(cert_file)?))?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+    let key = rustls_pemfile::read_all(&mut BufReader::new(File::open(key_file)?))?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key) | rustls_pemfile::Item::RSAKey(key) | rustls_pemfile::Item::ECKey(key) => Some(rustls::PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| invalid(format!("No private key in {}", key_file)))?;
+
+    let mut config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| invalid(format!("web_server.tls: {}", e)))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Удаляет файл сокета при остановке сервера
+struct SocketCleanup(PathBuf);
+
+impl Drop for SocketCleanup {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// gid по числу или имени из /etc/group
+fn resolve_group(group: &str) -> std::io::Result<u32> {
+    if let Ok(gid) = group.parse() {
+        return Ok(gid);
+    }
+    std::fs::read_to_string("/etc/group")?
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let gid = fields.nth(1)?.parse().ok()?;
+            (name == group).then_some(gid)
+        })
+        .next()
+        .ok_or_else(|| invalid(format!("web_server.unix_socket.group '{}' not found", group)))
+}
+
+fn invalid(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
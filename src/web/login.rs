@@ -3,41 +3,67 @@
 use axum::{
     extract::{State, Json},
     response::IntoResponse,
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use crate::directory_service::DirectoryService;
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::error_code::ErrorCode;
+use crate::middleware::ClientIp;
+use crate::web::errors::{problem, problem_body, problem_response};
 use crate::auth;
+use crate::models::{LoginProtocol, MfaMethod};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, utoipa::ToSchema)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    /// Код второго фактора (TOTP): нужен, когда прошлая попытка вернула `MFA_REQUIRED`
+    #[serde(default)]
+    pub mfa_code: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, utoipa::ToSchema)]
 pub struct LoginResponse {
     pub token: String,
     pub user_id: String,
-    pub expires_in: usize,
+    pub expires_in: u64,
 }
 
+#[utoipa::path(post, path = "/api/auth/login", tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Токен доступа", body = LoginResponse),
+        (status = 401, description = "Неверные учётные данные, истёкший пароль (`code`: `password_expired`) или вход требует второго фактора (`MFA_REQUIRED`, `security.risk`): повторить с `mfa_code` одного из методов `methods`"),
+        (status = 403, description = "Вход отклонён политикой риска (`security.risk`)"),
+        (status = 409, description = "Достигнут предел одновременных сессий (`security.sessions`, `on_limit: reject`)"),
+        (status = 429, description = "Слишком много попыток; повторить через `Retry-After` секунд"),
+    ))]
 pub async fn login_handler(
     State(service): State<Arc<DirectoryService>>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
 ) -> Result<impl IntoResponse, LoginError> {
-    let user = service.find_user_by_username(&payload.username).await
-        .map_err(|_| LoginError::Internal)?
-        .ok_or(LoginError::InvalidCredentials)?;
+    // authenticate учитывает отключённые и заблокированные учётные записи
+    let user = service.authenticate_with_mfa(&payload.username, &payload.password, payload.mfa_code.as_deref(), Some(ip.to_string()), LoginProtocol::Rest).await
+        .map_err(|e| match e {
+            DirectoryError::AuthenticationFailed(_) => LoginError::InvalidCredentials,
+            DirectoryError::RateLimited(secs) => LoginError::RateLimited(secs),
+            DirectoryError::PasswordExpired(_) => LoginError::PasswordExpired,
+            DirectoryError::MfaRequired(methods) => LoginError::MfaRequired(methods),
+            DirectoryError::AccessDenied(reason) => LoginError::Blocked(reason),
+            _ => LoginError::Internal,
+        })?;
 
-    if !user.password_hash.verify(&payload.password)
-        .map_err(|_| LoginError::Internal)? {
-        return Err(LoginError::InvalidCredentials);
-    }
-
-    let token = auth::generate_token(&user.id.to_string())
+    let device = headers.get(header::USER_AGENT).and_then(|agent| agent.to_str().ok()).map(str::to_string);
+    let session = service.open_session(user.id, LoginProtocol::Rest, Some(ip.to_string()), device).await
+        .map_err(|e| match e {
+            DirectoryError::SessionLimitExceeded(limit) => LoginError::SessionLimit(limit),
+            _ => LoginError::Internal,
+        })?;
+    let token = auth::generate_token(&session)
         .map_err(|_| LoginError::TokenGeneration)?;
 
     Ok((
@@ -45,7 +71,7 @@ pub async fn login_handler(
         Json(LoginResponse {
             token,
             user_id: user.id.to_string(),
-            expires_in: 86400,
+            expires_in: auth::token_expiry_secs(),
         }),
     ).into_response())
 }
@@ -53,18 +79,45 @@ pub async fn login_handler(
 #[derive(Debug)]
 pub enum LoginError {
     InvalidCredentials,
+    /// Пароль верен, но истёк: сначала его нужно сменить
+    PasswordExpired,
+    /// Через сколько секунд повторить
+    RateLimited(u64),
+    /// Политика риска (`security.risk`) требует второго фактора одним из методов
+    MfaRequired(Vec<MfaMethod>),
+    /// Вход отклонён политикой риска
+    Blocked(String),
+    /// Действующих сессий уже `security.sessions.max_concurrent`
+    SessionLimit(usize),
     Internal,
     TokenGeneration,
 }
 
 impl IntoResponse for LoginError {
     fn into_response(self) -> axum::response::Response {
-        let (status, message) = match self {
-            LoginError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "Invalid username or password"),
-            LoginError::Internal => (StatusCode::INTERNAL_SERVER_ERROR, "Internal error"),
-            LoginError::TokenGeneration => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to generate token"),
+        let (status, code, message) = match self {
+            LoginError::InvalidCredentials => (StatusCode::UNAUTHORIZED, ErrorCode::AuthenticationFailed, "Invalid username or password".to_string()),
+            LoginError::PasswordExpired => (StatusCode::UNAUTHORIZED, ErrorCode::PasswordExpired, "Password expired, must change".to_string()),
+            LoginError::RateLimited(secs) => {
+                let message = format!("Too many login attempts, retry in {}s", secs);
+                let mut response = problem(StatusCode::TOO_MANY_REQUESTS, ErrorCode::RateLimited, &message);
+                response.headers_mut().insert(header::RETRY_AFTER, secs.into());
+                return response;
+            }
+            LoginError::MfaRequired(methods) => {
+                let message = DirectoryError::MfaRequired(methods.clone()).to_string();
+                let mut body = problem_body(StatusCode::UNAUTHORIZED, ErrorCode::MfaRequired, &message);
+                body["methods"] = serde_json::json!(methods);
+                return problem_response(StatusCode::UNAUTHORIZED, body);
+            }
+            LoginError::Blocked(reason) => (StatusCode::FORBIDDEN, ErrorCode::AccessDenied, reason),
+            LoginError::SessionLimit(limit) => {
+                (StatusCode::CONFLICT, ErrorCode::SessionLimitExceeded, DirectoryError::SessionLimitExceeded(limit).to_string())
+            }
+            LoginError::Internal => (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::InternalError, "Internal error".to_string()),
+            LoginError::TokenGeneration => (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::InternalError, "Failed to generate token".to_string()),
         };
 
-        (status, format!("{{\"error\":\"{}\"}}", message)).into_response()
+        problem(status, code, &message)
     }
 }
\ No newline at end of file
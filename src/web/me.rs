@@ -0,0 +1,290 @@
+// src/web/me.rs
+
+//! Самообслуживание: `/api/me` — профиль, пароль, сессии и MFA вошедшего пользователя.
+//! Субъект берётся из JWT или владельца API-ключа (область `self:service`); права на свой
+//! объект в DACL не нужны, а менять можно только то, что разрешает `security.self_service`.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ProfileField, SelfServiceConfig};
+use crate::directory_service::DirectoryError;
+use crate::middleware::{ClientIp, SelfServiceUser};
+use crate::models::{mfa, MfaMethod, SchemaClass, User};
+use super::sessions::SessionResponse;
+use super::{SharedService, UserResponse};
+
+/// Изменение своего профиля; не указанные поля не меняются, пустая строка очищает поле
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UpdateProfileRequest {
+    pub display_name: Option<String>,
+    pub given_name: Option<String>,
+    pub surname: Option<String>,
+    pub email: Option<String>,
+    /// Атрибуты схемы из `security.self_service.attributes`; пустой список значений удаляет атрибут
+    #[serde(default)]
+    pub attributes: Option<HashMap<String, Vec<String>>>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Настройки MFA пользователя
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct MfaSettings {
+    pub enabled: bool,
+    pub methods: Vec<MfaMethod>,
+}
+
+/// Новые настройки MFA; менять их можно только с текущим паролем — украденного токена мало,
+/// чтобы выключить второй фактор. `Totp` в `methods` — только после подключения приложения
+/// (`POST /api/me/mfa/totp` и `/api/me/mfa/totp/confirm`)
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct SetMfaRequest {
+    pub enabled: bool,
+    pub methods: Vec<MfaMethod>,
+    pub current_password: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct EnrollTotpRequest {
+    pub current_password: String,
+}
+
+/// Новый, ещё не подтверждённый секрет TOTP
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct TotpEnrollment {
+    /// `otpauth://` для приложения-аутентификатора; показывается один раз
+    pub totp_uri: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ConfirmTotpRequest {
+    /// Текущий код из приложения-аутентификатора
+    pub code: String,
+}
+
+fn check_enabled(config: &SelfServiceConfig) -> Result<(), DirectoryError> {
+    if !config.enabled {
+        return Err(DirectoryError::AccessDenied("self-service is disabled".to_string()));
+    }
+    Ok(())
+}
+
+/// Свежая копия пользователя из базы: субъект запроса загружен до проверок, и запись его копии
+/// затёрла бы сделанные за это время изменения администратора
+async fn reload(service: &SharedService, me: &SelfServiceUser) -> Result<User, DirectoryError> {
+    service.get_user(me.user.id).await?.ok_or_else(|| DirectoryError::NotFound("User not found".to_string()))
+}
+
+/// Новое значение поля профиля, если политика разрешает его менять
+fn profile_value(config: &SelfServiceConfig, field: ProfileField, name: &str, value: Option<String>) -> Result<Option<Option<String>>, DirectoryError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    if !config.profile_fields.contains(&field) {
+        return Err(DirectoryError::AccessDenied(format!("{} cannot be changed by the user", name)));
+    }
+    Ok(Some((!value.is_empty()).then_some(value)))
+}
+
+#[utoipa::path(get, path = "/api/me", tag = "me",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Вошедший пользователь", body = UserResponse),
+    ))]
+pub async fn get_me(me: SelfServiceUser) -> Json<UserResponse> {
+    Json(UserResponse::from(me.user))
+}
+
+#[utoipa::path(patch, path = "/api/me", tag = "me",
+    request_body = UpdateProfileRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = UserResponse),
+        (status = 400, description = "Неверное значение атрибута", body = super::openapi::ErrorBody),
+        (status = 403, description = "Поле не разрешено `security.self_service` или самообслуживание выключено", body = super::openapi::ErrorBody),
+        (status = 409, description = "Email уже используется", body = super::openapi::ErrorBody),
+    ))]
+pub async fn update_me(
+    me: SelfServiceUser,
+    State(service): State<SharedService>,
+    Extension(config): Extension<SelfServiceConfig>,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<UserResponse>, DirectoryError> {
+    check_enabled(&config)?;
+    let mut user = reload(&service, &me).await?;
+
+    if let Some(value) = profile_value(&config, ProfileField::DisplayName, "display_name", payload.display_name)? {
+        user.display_name = value;
+    }
+    if let Some(value) = profile_value(&config, ProfileField::GivenName, "given_name", payload.given_name)? {
+        user.given_name = value;
+    }
+    if let Some(value) = profile_value(&config, ProfileField::Surname, "surname", payload.surname)? {
+        user.surname = value;
+    }
+    if let Some(value) = profile_value(&config, ProfileField::Email, "email", payload.email)? {
+        user.email = value;
+    }
+    if let Some(attributes) = payload.attributes {
+        if let Some(name) = attributes.keys().find(|name| !config.attributes.iter().any(|allowed| allowed.eq_ignore_ascii_case(name))) {
+            return Err(DirectoryError::AccessDenied(format!("{} cannot be changed by the user", name)));
+        }
+        crate::models::schema::apply_changes(&mut user.meta, service.schema_attribute_changes(SchemaClass::User, attributes).await?);
+    }
+
+    user.updated_at = chrono::Utc::now();
+    service.update_user(&user).await?;
+    Ok(Json(UserResponse::from(user)))
+}
+
+#[utoipa::path(put, path = "/api/me/password", tag = "me",
+    request_body = ChangePasswordRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Пароль сменён"),
+        (status = 400, description = "Новый пароль не проходит парольную политику", body = super::openapi::ErrorBody),
+        (status = 403, description = "Неверный текущий пароль или самообслуживание выключено", body = super::openapi::ErrorBody),
+        (status = 429, description = "Слишком много попыток; повторить через `Retry-After` секунд", body = super::openapi::ErrorBody),
+    ))]
+pub async fn change_my_password(
+    me: SelfServiceUser,
+    State(service): State<SharedService>,
+    Extension(config): Extension<SelfServiceConfig>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    check_enabled(&config)?;
+    service.change_password(me.user.id, &payload.current_password, &payload.new_password, Some(ip.to_string())).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(get, path = "/api/me/sessions", tag = "me",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Свои неистёкшие сессии, включая отозванные, новые первыми; id — те же, что у `DELETE /api/sessions/{id}`", body = Vec<SessionResponse>),
+    ))]
+pub async fn list_my_sessions(
+    me: SelfServiceUser,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<SessionResponse>>, DirectoryError> {
+    let sessions = service.list_user_sessions(me.user.id).await?;
+    Ok(Json(sessions.into_iter().map(SessionResponse::from).collect()))
+}
+
+#[utoipa::path(get, path = "/api/me/mfa", tag = "me",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = MfaSettings),
+    ))]
+pub async fn get_my_mfa(me: SelfServiceUser) -> Json<MfaSettings> {
+    Json(MfaSettings { enabled: me.user.mfa_enabled, methods: me.user.mfa_methods })
+}
+
+#[utoipa::path(put, path = "/api/me/mfa", tag = "me",
+    request_body = SetMfaRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Новые настройки", body = MfaSettings),
+        (status = 400, description = "Нет ни одного метода или `Totp` без подключённого приложения", body = super::openapi::ErrorBody),
+        (status = 403, description = "Неверный текущий пароль или самообслуживание выключено", body = super::openapi::ErrorBody),
+        (status = 429, description = "Слишком много попыток; повторить через `Retry-After` секунд", body = super::openapi::ErrorBody),
+    ))]
+pub async fn set_my_mfa(
+    me: SelfServiceUser,
+    State(service): State<SharedService>,
+    Extension(config): Extension<SelfServiceConfig>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<SetMfaRequest>,
+) -> Result<Json<MfaSettings>, DirectoryError> {
+    check_enabled(&config)?;
+    if payload.enabled && payload.methods.is_empty() {
+        return Err(DirectoryError::InvalidInput("MFA cannot be enabled without a method".to_string()));
+    }
+    service.confirm_password(me.user.id, &payload.current_password, Some(ip.to_string())).await?;
+
+    let mut user = reload(&service, &me).await?;
+    if !payload.methods.contains(&MfaMethod::Totp) {
+        user.totp_secret = None;
+        user.totp_pending_secret = None;
+    } else if user.totp_secret.is_none() {
+        return Err(DirectoryError::InvalidInput("TOTP is not enrolled; add it with POST /api/me/mfa/totp and confirm it with a code".to_string()));
+    }
+    user.mfa_enabled = payload.enabled;
+    user.mfa_methods = payload.methods;
+    user.updated_at = chrono::Utc::now();
+    service.update_user(&user).await?;
+    Ok(Json(MfaSettings { enabled: user.mfa_enabled, methods: user.mfa_methods }))
+}
+
+#[utoipa::path(post, path = "/api/me/mfa/totp", tag = "me",
+    request_body = EnrollTotpRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Новый секрет; действует после подтверждения кодом, прежний до тех пор остаётся в силе", body = TotpEnrollment),
+        (status = 403, description = "Неверный текущий пароль или самообслуживание выключено", body = super::openapi::ErrorBody),
+        (status = 429, description = "Слишком много попыток; повторить через `Retry-After` секунд", body = super::openapi::ErrorBody),
+    ))]
+pub async fn enroll_my_totp(
+    me: SelfServiceUser,
+    State(service): State<SharedService>,
+    Extension(config): Extension<SelfServiceConfig>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<EnrollTotpRequest>,
+) -> Result<Json<TotpEnrollment>, DirectoryError> {
+    check_enabled(&config)?;
+    service.confirm_password(me.user.id, &payload.current_password, Some(ip.to_string())).await?;
+
+    let mut user = reload(&service, &me).await?;
+    let secret = mfa::generate_totp_secret();
+    let issuer = user.user_principal_name.split_once('@').map_or("nextDomen", |(_, suffix)| suffix);
+    let totp_uri = mfa::totp_uri(&secret, issuer, &user.user_principal_name);
+    user.totp_pending_secret = Some(secret);
+    user.updated_at = chrono::Utc::now();
+    service.update_user(&user).await?;
+    Ok(Json(TotpEnrollment { totp_uri }))
+}
+
+#[utoipa::path(post, path = "/api/me/mfa/totp/confirm", tag = "me",
+    request_body = ConfirmTotpRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Секрет подтверждён, `Totp` включён", body = MfaSettings),
+        (status = 400, description = "Нет нового секрета или код не подходит", body = super::openapi::ErrorBody),
+        (status = 403, description = "Самообслуживание выключено", body = super::openapi::ErrorBody),
+    ))]
+pub async fn confirm_my_totp(
+    me: SelfServiceUser,
+    State(service): State<SharedService>,
+    Extension(config): Extension<SelfServiceConfig>,
+    Json(payload): Json<ConfirmTotpRequest>,
+) -> Result<Json<MfaSettings>, DirectoryError> {
+    check_enabled(&config)?;
+    let mut user = reload(&service, &me).await?;
+    let Some(secret) = user.totp_pending_secret.take() else {
+        return Err(DirectoryError::InvalidInput("No TOTP enrollment to confirm; start one with POST /api/me/mfa/totp".to_string()));
+    };
+    if !mfa::verify_totp(&secret, &payload.code, chrono::Utc::now()) {
+        return Err(DirectoryError::InvalidInput("Invalid TOTP code".to_string()));
+    }
+
+    user.totp_secret = Some(secret);
+    if !user.mfa_methods.contains(&MfaMethod::Totp) {
+        user.mfa_methods.push(MfaMethod::Totp);
+    }
+    user.mfa_enabled = true;
+    user.updated_at = chrono::Utc::now();
+    service.update_user(&user).await?;
+    Ok(Json(MfaSettings { enabled: user.mfa_enabled, methods: user.mfa_methods }))
+}
@@ -0,0 +1,36 @@
+// src/web/metrics.rs
+
+//! Счётчики процесса в текстовом формате Prometheus: при `metrics.enabled` — по пути
+//! `metrics.prometheus_endpoint` (по умолчанию `/metrics`), без аутентификации, как принято для скрейпинга
+
+use axum::{extract::State, http::header, response::IntoResponse, Router};
+use std::fmt::Write;
+
+use crate::config::MetricsConfig;
+use super::SharedService;
+
+const DEFAULT_ENDPOINT: &str = "/metrics";
+
+pub fn router(service: SharedService, config: &MetricsConfig) -> Router {
+    if !config.enabled {
+        return Router::new();
+    }
+    let path = config.prometheus_endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT);
+    Router::new()
+        .route(path, axum::routing::get(metrics))
+        .with_state(service)
+}
+
+async fn metrics(State(service): State<SharedService>) -> impl IntoResponse {
+    let stats = service.cache_stats();
+    let mut body = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+        let _ = writeln!(body, "# HELP {} {}\n# TYPE {} {}\n{} {}", name, help, name, kind, name, value);
+    };
+    metric("nextdomen_object_cache_hits_total", "counter", "Object cache lookups served from memory", stats.hits);
+    metric("nextdomen_object_cache_misses_total", "counter", "Object cache lookups that read the database", stats.misses);
+    metric("nextdomen_object_cache_evictions_total", "counter", "Objects evicted from the object cache", stats.evictions);
+    metric("nextdomen_object_cache_entries", "gauge", "Objects in the object cache", stats.entries as u64);
+    metric("nextdomen_object_cache_capacity", "gauge", "Object cache capacity", stats.capacity as u64);
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
@@ -0,0 +1,38 @@
+// src/web/objects.rs
+
+//! `GET /api/objects/:guid` — объект любого типа по objectGUID. Инструмент синхронизации хранит
+//! objectGUID как постоянную привязку и по нему узнаёт текущие DN и имя объекта, даже если
+//! тот переименован или перенесён в другую OU.
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::{Authorized, ChangesRead};
+use crate::models::DirectoryObject;
+use crate::models::ldap_entry::parse_object_guid;
+use super::SharedService;
+
+#[utoipa::path(get, path = "/api/objects/{guid}", tag = "objects",
+    params(("guid" = String, Path, description = "objectGUID, например 2b6e3f1c-8a4d-4c2e-9f10-5d7a2c4b8e01")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = DirectoryObject),
+        (status = 400, description = "Неверный GUID", body = super::openapi::ErrorBody),
+        (status = 401, description = "Нет токена или ключа", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `changes:read`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Объект не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_object(
+    _admin: Authorized<ChangesRead>,
+    Path(guid): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<DirectoryObject>, DirectoryError> {
+    let id = parse_object_guid(&guid)
+        .ok_or_else(|| DirectoryError::InvalidInput(format!("Invalid objectGUID: {}", guid)))?;
+    let object = service.find_object_by_guid(id).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Object not found: {}", guid)))?;
+    Ok(Json(object))
+}
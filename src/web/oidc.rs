@@ -0,0 +1,658 @@
+// src/web/oidc.rs
+
+//! Провайдер OAuth2 / OpenID Connect: поток authorization code с обязательным
+//! PKCE (RFC 7636), токены подписываются тем же ключом RS256, что и токены REST API.
+//! Форма входа защищена от CSRF (токен в скрытом поле и в cookie) и от встраивания во фреймы.
+
+use axum::{
+    routing::get,
+    Router,
+    Json,
+    Form,
+    extract::{Query, State},
+    response::{Html, IntoResponse, Redirect, Response},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+};
+use base64::engine::{general_purpose::{STANDARD, URL_SAFE_NO_PAD}, Engine};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::auth;
+use crate::config::OidcConfig;
+use crate::directory_service::{DirectoryError, DirectoryService, INVALID_CREDENTIALS};
+use crate::middleware::ClientIp;
+use crate::models::{LoginProtocol, OAuthClient, User};
+
+/// Сколько живёт код авторизации до обмена на токены
+const CODE_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Значение `typ` в заголовке access-токена (RFC 9068)
+const ACCESS_TOKEN_TYPE: &str = "at+jwt";
+
+const SUPPORTED_SCOPES: &[&str] = &["openid", "profile", "email", "groups"];
+
+/// Cookie с токеном CSRF формы входа; его копия приходит в поле `csrf_token`
+const CSRF_COOKIE: &str = "oidc_csrf";
+
+/// Состояние провайдера: выданные, но ещё не обменянные коды живут в памяти
+pub struct OidcProvider {
+    service: Arc<DirectoryService>,
+    issuer: String,
+    token_lifetime: u64,
+    codes: Mutex<HashMap<String, AuthorizationCode>>,
+}
+
+struct AuthorizationCode {
+    client_id: String,
+    redirect_uri: String,
+    /// redirect_uri был в запросе авторизации: тогда при обмене он обязателен (RFC 6749, 4.1.3)
+    redirect_uri_sent: bool,
+    user_id: Uuid,
+    scope: String,
+    nonce: Option<String>,
+    code_challenge: String,
+    auth_time: i64,
+    created: Instant,
+}
+
+type SharedProvider = Arc<OidcProvider>;
+
+/// Маршруты провайдера; `default_issuer` используется, если `issuer` не задан в конфигурации
+pub fn router(service: Arc<DirectoryService>, config: &OidcConfig, default_issuer: String) -> Router {
+    let provider = Arc::new(OidcProvider {
+        service,
+        issuer: config.issuer.clone().unwrap_or(default_issuer).trim_end_matches('/').to_string(),
+        token_lifetime: config.token_lifetime_secs,
+        codes: Mutex::new(HashMap::new()),
+    });
+
+    Router::new()
+        .route("/.well-known/openid-configuration", get(discovery))
+        .route("/oauth2/jwks", get(jwks))
+        .route("/oauth2/authorize", get(authorize_form).post(authorize_submit))
+        .route("/oauth2/token", axum::routing::post(token))
+        .route("/oauth2/userinfo", get(userinfo).post(userinfo))
+        .with_state(provider)
+}
+
+// === Запросы ===
+
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuthorizeParams {
+    pub response_type: String,
+    pub client_id: String,
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
+    #[serde(default)]
+    pub scope: Option<String>,
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub nonce: Option<String>,
+    #[serde(default)]
+    pub code_challenge: Option<String>,
+    #[serde(default)]
+    pub code_challenge_method: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LoginForm {
+    #[serde(flatten)]
+    pub params: AuthorizeParams,
+    pub username: String,
+    pub password: String,
+    /// Код TOTP, если вход требует второго фактора (`security.risk`); пустое поле — кода нет
+    #[serde(default)]
+    pub mfa_code: Option<String>,
+    /// Должен совпасть с cookie `oidc_csrf`, выданной вместе с формой
+    #[serde(default)]
+    pub csrf_token: Option<String>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    #[serde(default)]
+    pub code_verifier: Option<String>,
+}
+
+// === Токены ===
+
+#[derive(Serialize, Deserialize)]
+pub struct AccessTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub aud: String,
+    pub client_id: String,
+    pub scope: String,
+    pub exp: u64,
+    pub iat: u64,
+    pub jti: String,
+}
+
+#[derive(Serialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub aud: String,
+    pub exp: u64,
+    pub iat: u64,
+    pub auth_time: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(flatten)]
+    pub profile: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: u64,
+    pub scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+}
+
+// === Ошибки ===
+
+/// Ошибка запроса авторизации (RFC 6749, 4.1.2.1)
+pub enum AuthorizeError {
+    /// Клиент или redirect_uri неизвестны — перенаправлять нельзя, показываем страницу
+    Page(String),
+    Redirect {
+        redirect_uri: String,
+        error: &'static str,
+        description: String,
+        state: Option<String>,
+    },
+    Directory(DirectoryError),
+}
+
+impl From<DirectoryError> for AuthorizeError {
+    fn from(e: DirectoryError) -> Self {
+        AuthorizeError::Directory(e)
+    }
+}
+
+impl IntoResponse for AuthorizeError {
+    fn into_response(self) -> Response {
+        match self {
+            AuthorizeError::Page(message) => page(
+                StatusCode::BAD_REQUEST,
+                format!("<!DOCTYPE html><html><body><h1>Invalid request</h1><p>{}</p></body></html>", escape_html(&message)),
+            ),
+            AuthorizeError::Redirect { redirect_uri, error, description, state } => {
+                let mut params = vec![("error", error.to_string()), ("error_description", description)];
+                params.extend(state.map(|s| ("state", s)));
+                Redirect::to(&with_query(&redirect_uri, &params)).into_response()
+            }
+            AuthorizeError::Directory(e) => e.into_response(),
+        }
+    }
+}
+
+/// Ошибка token/userinfo endpoint (RFC 6749, 5.2)
+pub struct TokenError {
+    status: StatusCode,
+    error: &'static str,
+    description: String,
+}
+
+impl TokenError {
+    fn new(status: StatusCode, error: &'static str, description: impl Into<String>) -> Self {
+        Self { status, error, description: description.into() }
+    }
+
+    fn invalid_client() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "invalid_client", "Client authentication failed")
+    }
+
+    fn invalid_grant(description: &str) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_grant", description)
+    }
+
+    fn invalid_token() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "invalid_token", "Access token is invalid or expired")
+    }
+}
+
+impl From<DirectoryError> for TokenError {
+    fn from(_: DirectoryError) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "Directory error")
+    }
+}
+
+impl From<auth::AuthError> for TokenError {
+    fn from(_: auth::AuthError) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "Failed to sign token")
+    }
+}
+
+impl IntoResponse for TokenError {
+    fn into_response(self) -> Response {
+        let body = Json(json!({ "error": self.error, "error_description": self.description }));
+        let mut response = (self.status, [(header::CACHE_CONTROL, "no-store")], body).into_response();
+        if self.status == StatusCode::UNAUTHORIZED {
+            // Клиент аутентифицируется через Basic, владелец токена — через Bearer
+            let scheme = if self.error == "invalid_client" { "Basic realm=\"oauth2\", " } else { "Bearer " };
+            let challenge = format!("{}error=\"{}\"", scheme, self.error);
+            if let Ok(value) = challenge.parse() {
+                response.headers_mut().insert(header::WWW_AUTHENTICATE, value);
+            }
+        }
+        response
+    }
+}
+
+// === Discovery ===
+
+#[utoipa::path(get, path = "/.well-known/openid-configuration", tag = "oidc",
+    responses((status = 200, description = "Метаданные провайдера OpenID Connect", body = Object)))]
+pub(super) async fn discovery(State(provider): State<SharedProvider>) -> impl IntoResponse {
+    let issuer = &provider.issuer;
+    Json(json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{}/oauth2/authorize", issuer),
+        "token_endpoint": format!("{}/oauth2/token", issuer),
+        "userinfo_endpoint": format!("{}/oauth2/userinfo", issuer),
+        "jwks_uri": format!("{}/oauth2/jwks", issuer),
+        "response_types_supported": ["code"],
+        "grant_types_supported": ["authorization_code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": ["RS256"],
+        "scopes_supported": SUPPORTED_SCOPES,
+        "token_endpoint_auth_methods_supported": ["client_secret_basic", "client_secret_post", "none"],
+        "code_challenge_methods_supported": ["S256"],
+        "claims_supported": [
+            "iss", "sub", "aud", "exp", "iat", "auth_time", "nonce",
+            "preferred_username", "name", "given_name", "family_name", "email", "groups",
+        ],
+    }))
+}
+
+#[utoipa::path(get, path = "/oauth2/jwks", tag = "oidc",
+    responses((status = 200, description = "Открытый ключ подписи токенов (JWK Set)", body = Object)))]
+pub(super) async fn jwks() -> Result<impl IntoResponse, TokenError> {
+    Ok(Json(json!({ "keys": [auth::public_jwk()?] })))
+}
+
+// === Authorization endpoint ===
+
+#[utoipa::path(get, path = "/oauth2/authorize", tag = "oidc",
+    params(AuthorizeParams),
+    responses(
+        (status = 200, description = "Форма входа", content_type = "text/html", body = String),
+        (status = 400, description = "Неверный запрос авторизации"),
+    ))]
+pub(super) async fn authorize_form(
+    State(provider): State<SharedProvider>,
+    Query(params): Query<AuthorizeParams>,
+) -> Result<Response, AuthorizeError> {
+    let (client, _) = provider.check_authorize(&params).await?;
+    let csrf = random_token();
+    let mut response = page(StatusCode::OK, login_page(&client, &params, &csrf, None));
+    // Strict: с чужого сайта форму отправят без cookie, и вход не состоится
+    let secure = if provider.issuer.starts_with("https://") { "; Secure" } else { "" };
+    let cookie = format!("{}={}; Path=/oauth2/authorize; HttpOnly; SameSite=Strict{}", CSRF_COOKIE, csrf, secure);
+    if let Ok(value) = HeaderValue::from_str(&cookie) {
+        response.headers_mut().insert(header::SET_COOKIE, value);
+    }
+    Ok(response)
+}
+
+async fn authorize_submit(
+    State(provider): State<SharedProvider>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    Form(form): Form<LoginForm>,
+) -> Result<Response, AuthorizeError> {
+    let params = form.params;
+    let (client, redirect_uri) = provider.check_authorize(&params).await?;
+    let csrf = form.csrf_token
+        .filter(|token| !token.is_empty() && csrf_cookie(&headers).as_deref() == Some(token.as_str()))
+        .ok_or_else(|| AuthorizeError::Page("The sign-in form has expired or was not sent from this site, reload it".to_string()))?;
+
+    let mfa_code = form.mfa_code.as_deref().filter(|code| !code.trim().is_empty());
+    let user = match provider.service.authenticate_with_mfa(&form.username, &form.password, mfa_code, Some(ip.to_string()), LoginProtocol::Oidc).await {
+        Ok(user) => user,
+        // Причина отказа — в аудите; на странице всегда одно и то же
+        Err(DirectoryError::AuthenticationFailed(_)) => {
+            return Ok(page(StatusCode::UNAUTHORIZED, login_page(&client, &params, &csrf, Some(INVALID_CREDENTIALS))));
+        }
+        Err(e @ (DirectoryError::PasswordExpired(_) | DirectoryError::MfaRequired(_))) => {
+            return Ok(page(StatusCode::UNAUTHORIZED, login_page(&client, &params, &csrf, Some(&e.to_string()))));
+        }
+        Err(DirectoryError::AccessDenied(message)) => {
+            return Ok(page(StatusCode::FORBIDDEN, login_page(&client, &params, &csrf, Some(&message))));
+        }
+        Err(e @ DirectoryError::RateLimited(secs)) => {
+            let mut response = page(StatusCode::TOO_MANY_REQUESTS, login_page(&client, &params, &csrf, Some(&e.to_string())));
+            response.headers_mut().insert(header::RETRY_AFTER, secs.into());
+            return Ok(response);
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let code = random_token();
+    let grant = AuthorizationCode {
+        client_id: client.client_id.clone(),
+        redirect_uri: redirect_uri.clone(),
+        redirect_uri_sent: params.redirect_uri.is_some(),
+        user_id: user.id,
+        scope: normalize_scope(params.scope.as_deref()),
+        nonce: params.nonce,
+        code_challenge: params.code_challenge.unwrap_or_default(),
+        auth_time: chrono::Utc::now().timestamp(),
+        created: Instant::now(),
+    };
+    {
+        let mut codes = provider.codes.lock().unwrap();
+        codes.retain(|_, c| c.created.elapsed() < CODE_LIFETIME);
+        codes.insert(code.clone(), grant);
+    }
+
+    let mut query = vec![("code", code)];
+    query.extend(params.state.map(|s| ("state", s)));
+    Ok(Redirect::to(&with_query(&redirect_uri, &query)).into_response())
+}
+
+impl OidcProvider {
+    /// Проверить параметры запроса авторизации; возвращает клиента и итоговый redirect_uri
+    async fn check_authorize(&self, params: &AuthorizeParams) -> Result<(OAuthClient, String), AuthorizeError> {
+        let client = self.service.get_oauth_client(&params.client_id).await?
+            .ok_or_else(|| AuthorizeError::Page(format!("Unknown client: {}", params.client_id)))?;
+
+        let redirect_uri = match &params.redirect_uri {
+            Some(uri) if client.redirect_uris.contains(uri) => uri.clone(),
+            None if client.redirect_uris.len() == 1 => client.redirect_uris[0].clone(),
+            _ => return Err(AuthorizeError::Page("redirect_uri is not registered for this client".to_string())),
+        };
+
+        let fail = |error: &'static str, description: &str| AuthorizeError::Redirect {
+            redirect_uri: redirect_uri.clone(),
+            error,
+            description: description.to_string(),
+            state: params.state.clone(),
+        };
+        if params.response_type != "code" {
+            return Err(fail("unsupported_response_type", "Only response_type=code is supported"));
+        }
+        if params.code_challenge.as_deref().is_none_or(str::is_empty) {
+            return Err(fail("invalid_request", "PKCE code_challenge is required"));
+        }
+        if params.code_challenge_method.as_deref() != Some("S256") {
+            return Err(fail("invalid_request", "Only S256 code_challenge_method is supported"));
+        }
+
+        Ok((client, redirect_uri))
+    }
+
+    /// Claims профиля пользователя в соответствии с запрошенными scope
+    async fn user_claims(&self, user: &User, scope: &str) -> Result<serde_json::Map<String, serde_json::Value>, DirectoryError> {
+        let scopes: Vec<&str> = scope.split_whitespace().collect();
+        let mut claims = serde_json::Map::new();
+
+        if scopes.contains(&"profile") {
+            claims.insert("preferred_username".into(), json!(user.username));
+            claims.insert("updated_at".into(), json!(user.updated_at.timestamp()));
+            if let Some(name) = &user.display_name {
+                claims.insert("name".into(), json!(name));
+            }
+            if let Some(given_name) = &user.given_name {
+                claims.insert("given_name".into(), json!(given_name));
+            }
+            if let Some(surname) = &user.surname {
+                claims.insert("family_name".into(), json!(surname));
+            }
+        }
+        if scopes.contains(&"email") && let Some(email) = &user.email {
+            claims.insert("email".into(), json!(email));
+        }
+        if scopes.contains(&"groups") {
+            let groups = self.service.find_groups_by_member(user.id).await?;
+            let names: Vec<String> = groups.into_iter().map(|g| g.sam_account_name).collect();
+            claims.insert("groups".into(), json!(names));
+        }
+        Ok(claims)
+    }
+}
+
+// === Token endpoint ===
+
+#[utoipa::path(post, path = "/oauth2/token", tag = "oidc",
+    request_body(content = TokenRequest, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Токены", body = TokenResponse),
+        (status = 400, description = "Ошибка OAuth2 (`invalid_grant`, `unsupported_grant_type`)", body = Object),
+        (status = 401, description = "Клиент не аутентифицирован", body = Object),
+    ))]
+pub(super) async fn token(
+    State(provider): State<SharedProvider>,
+    headers: HeaderMap,
+    Form(request): Form<TokenRequest>,
+) -> Result<Response, TokenError> {
+    if request.grant_type != "authorization_code" {
+        return Err(TokenError::new(StatusCode::BAD_REQUEST, "unsupported_grant_type", "Only authorization_code is supported"));
+    }
+
+    // Аутентификация клиента: HTTP Basic или параметры формы
+    let (client_id, client_secret) = match basic_credentials(&headers) {
+        Some((id, secret)) => (id, Some(secret)),
+        None => (request.client_id.clone().ok_or_else(TokenError::invalid_client)?, request.client_secret.clone()),
+    };
+    let client = provider.service.get_oauth_client(&client_id).await?
+        .ok_or_else(TokenError::invalid_client)?;
+    if let Some(hash) = &client.client_secret {
+        let secret = client_secret.ok_or_else(TokenError::invalid_client)?;
+        if !hash.verify(&secret).unwrap_or(false) {
+            return Err(TokenError::invalid_client());
+        }
+    }
+
+    // Код одноразовый: удаляем его до любых проверок
+    let code = request.code.as_deref().ok_or_else(|| TokenError::invalid_grant("Missing code"))?;
+    let grant = provider.codes.lock().unwrap().remove(code)
+        .ok_or_else(|| TokenError::invalid_grant("Invalid or expired code"))?;
+    if grant.created.elapsed() >= CODE_LIFETIME || grant.client_id != client.client_id {
+        return Err(TokenError::invalid_grant("Invalid or expired code"));
+    }
+    match request.redirect_uri.as_deref() {
+        Some(uri) if uri != grant.redirect_uri => return Err(TokenError::invalid_grant("redirect_uri does not match")),
+        None if grant.redirect_uri_sent => return Err(TokenError::invalid_grant("redirect_uri is required, it was sent to /authorize")),
+        _ => {}
+    }
+
+    let verifier = request.code_verifier.as_deref().ok_or_else(|| TokenError::invalid_grant("Missing code_verifier"))?;
+    if URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) != grant.code_challenge {
+        return Err(TokenError::invalid_grant("PKCE verification failed"));
+    }
+
+    // Учётную запись могли отключить или заблокировать после выдачи кода — как при входе
+    let user = provider.service.get_user(grant.user_id).await?
+        .filter(|u| u.enabled && !u.is_locked_out())
+        .ok_or_else(|| TokenError::invalid_grant("User is no longer available"))?;
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    let access_token = auth::sign_claims(&AccessTokenClaims {
+        iss: provider.issuer.clone(),
+        sub: user.id.to_string(),
+        aud: client.client_id.clone(),
+        client_id: client.client_id.clone(),
+        scope: grant.scope.clone(),
+        exp: now + provider.token_lifetime,
+        iat: now,
+        jti: Uuid::new_v4().to_string(),
+    }, Some(ACCESS_TOKEN_TYPE))?;
+
+    let id_token = if grant.scope.split_whitespace().any(|s| s == "openid") {
+        let claims = IdTokenClaims {
+            iss: provider.issuer.clone(),
+            sub: user.id.to_string(),
+            aud: client.client_id.clone(),
+            exp: now + provider.token_lifetime,
+            iat: now,
+            auth_time: grant.auth_time,
+            nonce: grant.nonce.clone(),
+            profile: provider.user_claims(&user, &grant.scope).await?,
+        };
+        Some(auth::sign_claims(&claims, None)?)
+    } else {
+        None
+    };
+
+    let body = TokenResponse {
+        access_token,
+        token_type: "Bearer",
+        expires_in: provider.token_lifetime,
+        scope: grant.scope,
+        id_token,
+    };
+    Ok(([(header::CACHE_CONTROL, "no-store")], Json(body)).into_response())
+}
+
+// === UserInfo endpoint ===
+
+#[utoipa::path(get, path = "/oauth2/userinfo", tag = "oidc",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Утверждения о пользователе по scope токена", body = Object),
+        (status = 401, description = "Недействительный токен"),
+    ))]
+pub(super) async fn userinfo(
+    State(provider): State<SharedProvider>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, TokenError> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or_else(TokenError::invalid_token)?;
+
+    let mut validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::RS256);
+    validation.set_issuer(&[&provider.issuer]);
+    validation.validate_aud = false;
+    let data = auth::verify_claims::<AccessTokenClaims>(token, &validation)
+        .map_err(|_| TokenError::invalid_token())?;
+    if data.header.typ.as_deref() != Some(ACCESS_TOKEN_TYPE) {
+        return Err(TokenError::invalid_token());
+    }
+
+    let user_id = Uuid::parse_str(&data.claims.sub).map_err(|_| TokenError::invalid_token())?;
+    let user = provider.service.get_user(user_id).await?
+        .filter(|u| u.enabled && !u.is_locked_out())
+        .ok_or_else(TokenError::invalid_token)?;
+
+    let mut claims = provider.user_claims(&user, &data.claims.scope).await?;
+    claims.insert("sub".into(), json!(user.id.to_string()));
+    Ok(Json(claims))
+}
+
+// === Вспомогательные функции ===
+
+/// Оставить только поддерживаемые scope, без повторов
+fn normalize_scope(scope: Option<&str>) -> String {
+    let mut scopes: Vec<&str> = Vec::new();
+    for s in scope.unwrap_or_default().split_whitespace() {
+        if SUPPORTED_SCOPES.contains(&s) && !scopes.contains(&s) {
+            scopes.push(s);
+        }
+    }
+    scopes.join(" ")
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let encoded = headers.get(header::AUTHORIZATION)?.to_str().ok()?.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(STANDARD.decode(encoded).ok()?).ok()?;
+    let (id, secret) = decoded.split_once(':')?;
+    Some((id.to_string(), secret.to_string()))
+}
+
+fn with_query(uri: &str, params: &[(&str, String)]) -> String {
+    let query = serde_urlencoded::to_string(params).unwrap_or_default();
+    let separator = if uri.contains('?') { '&' } else { '?' };
+    format!("{}{}{}", uri, separator, query)
+}
+
+/// Значение cookie `oidc_csrf` запроса
+fn csrf_cookie(headers: &HeaderMap) -> Option<String> {
+    headers.get_all(header::COOKIE).iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(CSRF_COOKIE)?.strip_prefix('=').map(str::to_string))
+}
+
+/// HTML-страница провайдера; встраивать её во фреймы нельзя — иначе чужой сайт может
+/// подсунуть форму входа под свои элементы (clickjacking)
+fn page(status: StatusCode, html: String) -> Response {
+    let mut response = (status, Html(html)).into_response();
+    let headers = response.headers_mut();
+    headers.insert(header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY"));
+    headers.insert(header::CONTENT_SECURITY_POLICY, HeaderValue::from_static("frame-ancestors 'none'"));
+    response
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Форма входа; параметры запроса авторизации и токен CSRF передаются скрытыми полями
+fn login_page(client: &OAuthClient, params: &AuthorizeParams, csrf: &str, error: Option<&str>) -> String {
+    let hidden = [
+        ("csrf_token", Some(csrf)),
+        ("response_type", Some(params.response_type.as_str())),
+        ("client_id", Some(params.client_id.as_str())),
+        ("redirect_uri", params.redirect_uri.as_deref()),
+        ("scope", params.scope.as_deref()),
+        ("state", params.state.as_deref()),
+        ("nonce", params.nonce.as_deref()),
+        ("code_challenge", params.code_challenge.as_deref()),
+        ("code_challenge_method", params.code_challenge_method.as_deref()),
+    ];
+    let fields: String = hidden
+        .iter()
+        .filter_map(|(name, value)| value.map(|v| format!("<input type=\"hidden\" name=\"{}\" value=\"{}\">", name, escape_html(v))))
+        .collect();
+    let error = error
+        .map(|e| format!("<p style=\"color:#b00\">{}</p>", escape_html(e)))
+        .unwrap_or_default();
+
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Sign in</title></head><body>\
+         <h1>Sign in to {}</h1>{}\
+         <form method=\"post\" action=\"/oauth2/authorize\">{}\
+         <p><input name=\"username\" placeholder=\"Username\" autocomplete=\"username\" required></p>\
+         <p><input name=\"password\" type=\"password\" placeholder=\"Password\" autocomplete=\"current-password\" required></p>\
+         <p><input name=\"mfa_code\" placeholder=\"One-time code, if asked\" autocomplete=\"one-time-code\" inputmode=\"numeric\"></p>\
+         <p><button type=\"submit\">Sign in</button></p>\
+         </form></body></html>",
+        escape_html(&client.name),
+        error,
+        fields,
+    )
+}
@@ -0,0 +1,243 @@
+// src/web/openapi.rs
+
+//! Описание REST API в формате OpenAPI 3: строится из аннотаций обработчиков и структур запросов/ответов.
+//! Спецификация — `/api/openapi.json`, Swagger UI — `/api/docs/` (если `web_server.swagger_ui`, только для Domain Admins).
+
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use serde::Serialize;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+use utoipa_swagger_ui::{Config, SwaggerUi};
+
+use crate::error_code::ErrorCode;
+use crate::middleware::{AdminUser, QueryToken, API_KEY_HEADER};
+use super::SharedService;
+
+const SPEC_PATH: &str = "/api/openapi.json";
+const DOCS_PATH: &str = "/api/docs";
+
+/// Тело ответа с ошибкой — problem+json (RFC 7807), тип содержимого `application/problem+json`
+#[derive(Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// URI вида ошибки, по одному на код: `urn:nextdomen:error:user-already-exists`
+    #[serde(rename = "type")]
+    pub type_uri: String,
+    pub title: String,
+    /// HTTP-статус ответа
+    pub status: u16,
+    /// Описание конкретного случая
+    pub detail: String,
+    /// Стабильный код ошибки: по нему клиент отличает, например, занятое имя (`USER_ALREADY_EXISTS`)
+    /// от занятого адреса (`EMAIL_ALREADY_EXISTS`) и истёкший пароль (`PASSWORD_EXPIRED`) от неверного
+    pub code: ErrorCode,
+    /// Ответ 421 реплики только для чтения: записываемый контроллер, которому отправить изменение
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referral: Option<String>,
+    /// Ответ 413: предел `web_server.size_limit`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_limit: Option<usize>,
+    /// Ответ 408: предел `web_server.time_limit_secs`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_limit_secs: Option<u64>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "NextDomen API", description = "Управление каталогом: пользователи, группы, OU, контакты, GPO, аудит"),
+    paths(
+        super::health,
+        super::list_users,
+        super::get_user,
+        super::get_user_by_id,
+        super::get_user_by_sid,
+        super::list_user_logins,
+        super::me::get_me,
+        super::me::update_me,
+        super::me::change_my_password,
+        super::me::list_my_sessions,
+        super::me::get_my_mfa,
+        super::me::set_my_mfa,
+        super::me::enroll_my_totp,
+        super::me::confirm_my_totp,
+        super::photos::get_user_photo,
+        super::photos::set_user_photo,
+        super::photos::delete_user_photo,
+        super::create_user,
+        super::update_user,
+        super::patch_user,
+        super::update_user_account_control,
+        super::delete_user,
+        super::list_groups,
+        super::get_group,
+        super::update_group,
+        super::get_group_by_id,
+        super::get_group_by_sid,
+        super::create_group,
+        super::delete_group,
+        super::set_group_membership_rule,
+        super::group_requests::set_group_lifecycle,
+        super::group_requests::request_group_membership,
+        super::group_requests::list_group_requests,
+        super::group_requests::approve_group_request,
+        super::group_requests::deny_group_request,
+        super::list_ous,
+        super::create_ou,
+        super::ou_tree,
+        super::update_ou,
+        super::delete_ou,
+        super::contacts::list_contacts,
+        super::contacts::get_contact,
+        super::contacts::create_contact,
+        super::contacts::update_contact,
+        super::contacts::patch_contact,
+        super::contacts::delete_contact,
+        super::service_accounts::list_service_accounts,
+        super::service_accounts::get_service_account,
+        super::service_accounts::create_service_account,
+        super::service_accounts::update_service_account,
+        super::service_accounts::rotate_service_account_password,
+        super::service_accounts::retrieve_managed_password,
+        super::password_policies::list_password_policies,
+        super::password_policies::get_password_policy,
+        super::password_policies::create_password_policy,
+        super::password_policies::update_password_policy,
+        super::password_policies::delete_password_policy,
+        super::password_policies::get_resultant_password_policy,
+        super::sites::list_sites,
+        super::sites::get_site,
+        super::sites::lookup_site,
+        super::sites::create_site,
+        super::sites::update_site,
+        super::sites::delete_site,
+        super::sites::list_subnets,
+        super::sites::create_subnet,
+        super::sites::update_subnet,
+        super::sites::delete_subnet,
+        super::replication::replication_snapshot,
+        super::changes::list_changes,
+        super::objects::get_object,
+        super::acl::get_user_acl,
+        super::acl::set_user_acl,
+        super::acl::get_group_acl,
+        super::acl::set_group_acl,
+        super::acl::get_ou_acl,
+        super::acl::set_ou_acl,
+        super::create_gpo,
+        super::list_gpo_schemas,
+        super::agent::agent_checkin,
+        super::agent::gpo_compliance,
+        super::sysvol::list_gpo_files,
+        super::sysvol::get_gpo_file,
+        super::schema::list_attributes,
+        super::schema::define_attribute,
+        super::schema::delete_attribute,
+        super::import_ldif,
+        super::bulk::bulk,
+        super::reload_config,
+        super::get_stats,
+        super::login::login_handler,
+        super::password_reset::request_password_reset,
+        super::password_reset::confirm_password_reset,
+        super::invitations::invite_user,
+        super::invitations::list_invitations,
+        super::invitations::resend_invitation,
+        super::invitations::revoke_invitation,
+        super::invitations::accept_invitation,
+        super::events::stream_events,
+        super::events::websocket_events,
+        super::audit::search_audit,
+        super::apikeys::create_api_key,
+        super::apikeys::list_api_keys,
+        super::apikeys::delete_api_key,
+        super::approvals::list_approvals,
+        super::approvals::approve_operation,
+        super::approvals::reject_operation,
+        super::sessions::list_user_sessions,
+        super::sessions::revoke_session,
+        super::ca::get_ca_certificate,
+        super::ca::get_crl,
+        super::ca::sign_certificate,
+        super::ca::enroll_computer,
+        super::ca::list_certificates,
+        super::ca::revoke_certificate,
+        super::oidc::discovery,
+        super::oidc::jwks,
+        super::oidc::authorize_form,
+        super::oidc::token,
+        super::oidc::userinfo,
+    ),
+    components(schemas(ErrorBody, ErrorCode)),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "users", description = "Пользователи"),
+        (name = "me", description = "Самообслуживание вошедшего пользователя"),
+        (name = "groups", description = "Группы"),
+        (name = "ous", description = "Организационные подразделения"),
+        (name = "contacts", description = "Контакты и общие почтовые ящики"),
+        (name = "service-accounts", description = "Управляемые учётные записи служб"),
+        (name = "password-policies", description = "Детальные парольные политики (PSO)"),
+        (name = "sites", description = "Сайты и подсети"),
+        (name = "replication", description = "Репликация на контроллеры только для чтения"),
+        (name = "changes", description = "Журнал изменений для внешней синхронизации"),
+        (name = "objects", description = "Объекты любого типа по objectGUID"),
+        (name = "gpos", description = "Групповые политики"),
+        (name = "acl", description = "Дескрипторы безопасности объектов каталога"),
+        (name = "schema", description = "Дополнительные атрибуты объектов"),
+        (name = "admin", description = "Импорт и обслуживание"),
+        (name = "auth", description = "Вход и выдача токенов"),
+        (name = "events", description = "Поток событий аудита"),
+        (name = "audit", description = "Журнал аудита"),
+        (name = "apikeys", description = "API-ключи для межсервисного доступа"),
+        (name = "approvals", description = "Заявки на чувствительные операции (принцип четырёх глаз)"),
+        (name = "sessions", description = "Сессии входа и их отзыв"),
+        (name = "ca", description = "Внутренний центр сертификации"),
+        (name = "oidc", description = "OAuth2 / OpenID Connect"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Схемы `bearer` (JWT из `/api/auth/login`) и `api_key` (`X-Api-Key`)
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("bearer", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)));
+        components.add_security_scheme("api_key", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))));
+    }
+}
+
+async fn spec() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Спецификация и, если включено, Swagger UI
+pub fn router(service: SharedService, swagger_ui: bool) -> Router {
+    let router = Router::new().route(SPEC_PATH, axum::routing::get(spec));
+    if !swagger_ui {
+        return router;
+    }
+
+    let ui: Router = SwaggerUi::new(DOCS_PATH).config(Config::from(SPEC_PATH)).into();
+    router.merge(ui.route_layer(middleware::from_fn_with_state(service, require_admin_for_page)))
+}
+
+/// Страница UI — только для администраторов; статика Swagger UI (JS, CSS) общедоступна.
+/// Браузер не передаёт заголовок при переходе, поэтому токен — в `?access_token=`
+async fn require_admin_for_page(State(service): State<SharedService>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if path != format!("{}/", DOCS_PATH) && path != format!("{}/index.html", DOCS_PATH) {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    if let Err(rejection) = QueryToken::<AdminUser>::from_request_parts(&mut parts, &service).await {
+        return rejection.into_response();
+    }
+    next.run(Request::from_parts(parts, body)).await
+}
@@ -0,0 +1,351 @@
+// src/web/password_policies.rs
+
+//! Детальные парольные политики (PSO): чтение `GET /api/password-policies[/{name}]` — для вошедших
+//! (API-ключ — с областью `directory:read`), `POST /api/password-policies` и
+//! `PUT/DELETE /api/password-policies/{name}` — для Domain Admins с областью `directory:write`;
+//! `GET /api/users/{username}/password-policy` — действующая политика пользователя.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::middleware::{Authorized, Caller, DirectoryWrite, Reader};
+use crate::models::{AccessMask, PasswordPolicy, PasswordSettingsObject, SecuredObject};
+use super::SharedService;
+
+/// Параметры парольной политики
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PasswordPolicyBody {
+    pub min_length: u8,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digits: bool,
+    pub require_special_chars: bool,
+    pub max_age_days: u32,
+    /// Сколько последних паролей нельзя использовать снова
+    pub history_count: u8,
+    /// Неудачных попыток входа до блокировки; 0 — не блокировать
+    pub lockout_threshold: u32,
+    pub lockout_duration_minutes: u32,
+}
+
+impl From<PasswordPolicy> for PasswordPolicyBody {
+    fn from(policy: PasswordPolicy) -> Self {
+        Self {
+            min_length: policy.min_length,
+            require_uppercase: policy.require_uppercase,
+            require_lowercase: policy.require_lowercase,
+            require_digits: policy.require_digits,
+            require_special_chars: policy.require_special_chars,
+            max_age_days: policy.max_age_days,
+            history_count: policy.history_count,
+            lockout_threshold: policy.lockout_threshold,
+            lockout_duration_minutes: policy.lockout_duration_minutes,
+        }
+    }
+}
+
+/// Изменение параметров политики; не указанные не меняются
+#[derive(Serialize, Deserialize, Default, utoipa::ToSchema, clap::Args)]
+pub struct PasswordPolicyChanges {
+    #[arg(long)]
+    pub min_length: Option<u8>,
+    #[arg(long)]
+    pub require_uppercase: Option<bool>,
+    #[arg(long)]
+    pub require_lowercase: Option<bool>,
+    #[arg(long)]
+    pub require_digits: Option<bool>,
+    #[arg(long)]
+    pub require_special_chars: Option<bool>,
+    #[arg(long)]
+    pub max_age_days: Option<u32>,
+    #[arg(long)]
+    pub history_count: Option<u8>,
+    #[arg(long)]
+    pub lockout_threshold: Option<u32>,
+    #[arg(long)]
+    pub lockout_duration_minutes: Option<u32>,
+}
+
+impl PasswordPolicyChanges {
+    pub fn apply(&self, policy: &mut PasswordPolicy) {
+        if let Some(value) = self.min_length {
+            policy.min_length = value;
+        }
+        if let Some(value) = self.require_uppercase {
+            policy.require_uppercase = value;
+        }
+        if let Some(value) = self.require_lowercase {
+            policy.require_lowercase = value;
+        }
+        if let Some(value) = self.require_digits {
+            policy.require_digits = value;
+        }
+        if let Some(value) = self.require_special_chars {
+            policy.require_special_chars = value;
+        }
+        if let Some(value) = self.max_age_days {
+            policy.max_age_days = value;
+        }
+        if let Some(value) = self.history_count {
+            policy.history_count = value;
+        }
+        if let Some(value) = self.lockout_threshold {
+            policy.lockout_threshold = value;
+        }
+        if let Some(value) = self.lockout_duration_minutes {
+            policy.lockout_duration_minutes = value;
+        }
+    }
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreatePasswordSettingsRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// При нескольких подходящих политиках действует меньший
+    pub precedence: u32,
+    /// sAMAccountName групп
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// DN OU; политика действует и во вложенных OU
+    #[serde(default)]
+    pub ous: Vec<String>,
+    /// Не указанные параметры — как у политики домена по умолчанию
+    #[serde(default)]
+    pub settings: PasswordPolicyChanges,
+}
+
+/// Изменение PSO; не указанные поля не меняются, списки групп и OU заменяются целиком
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UpdatePasswordSettingsRequest {
+    pub description: Option<String>,
+    pub precedence: Option<u32>,
+    pub groups: Option<Vec<String>>,
+    pub ous: Option<Vec<String>>,
+    #[serde(default)]
+    pub settings: PasswordPolicyChanges,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PasswordSettingsResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub precedence: u32,
+    pub groups: Vec<String>,
+    pub ous: Vec<String>,
+    pub settings: PasswordPolicyBody,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PasswordSettingsResponse {
+    pub async fn new(service: &DirectoryService, pso: PasswordSettingsObject) -> Result<Self, DirectoryError> {
+        let mut groups = Vec::new();
+        for id in &pso.applies_to_groups {
+            if let Some(group) = service.get_group(*id).await? {
+                groups.push(group.sam_account_name);
+            }
+        }
+        let mut ous = Vec::new();
+        for id in &pso.applies_to_ous {
+            if let Some(ou) = service.get_ou(*id).await? {
+                ous.push(ou.dn);
+            }
+        }
+        Ok(Self {
+            id: pso.id,
+            name: pso.name,
+            description: pso.description,
+            precedence: pso.precedence,
+            groups,
+            ous,
+            settings: pso.policy.into(),
+            updated_at: pso.updated_at,
+        })
+    }
+}
+
+/// Действующая политика пользователя
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ResultantPasswordPolicyResponse {
+    /// Имя PSO; `null` — политика домена
+    pub pso: Option<String>,
+    pub settings: PasswordPolicyBody,
+}
+
+pub(crate) async fn find_pso(service: &DirectoryService, name: &str) -> Result<PasswordSettingsObject, DirectoryError> {
+    service.find_pso_by_name(name).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Password settings not found: {}", name)))
+}
+
+/// id групп по sAMAccountName
+pub(crate) async fn resolve_groups(service: &DirectoryService, names: &[String]) -> Result<Vec<Uuid>, DirectoryError> {
+    let mut ids = Vec::new();
+    for name in names {
+        let group = service.find_group_by_sam_account_name(name).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("Group not found: {}", name)))?;
+        if !ids.contains(&group.id) {
+            ids.push(group.id);
+        }
+    }
+    Ok(ids)
+}
+
+/// id OU по DN
+pub(crate) async fn resolve_ous(service: &DirectoryService, dns: &[String]) -> Result<Vec<Uuid>, DirectoryError> {
+    let mut ids = Vec::new();
+    for dn in dns {
+        let ou = service.find_ou_by_dn(dn).await?
+            .ok_or_else(|| DirectoryError::NotFound(format!("OU not found: {}", dn)))?;
+        if !ids.contains(&ou.id) {
+            ids.push(ou.id);
+        }
+    }
+    Ok(ids)
+}
+
+#[utoipa::path(get, path = "/api/password-policies", tag = "password-policies",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "PSO по возрастанию `precedence`", body = Vec<PasswordSettingsResponse>),
+        (status = 403, description = "У API-ключа нет области `directory:read`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_password_policies(
+    _reader: Reader,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<PasswordSettingsResponse>>, DirectoryError> {
+    let mut psos = Vec::new();
+    for pso in service.get_all_psos().await? {
+        psos.push(PasswordSettingsResponse::new(&service, pso).await?);
+    }
+    Ok(Json(psos))
+}
+
+#[utoipa::path(get, path = "/api/password-policies/{name}", tag = "password-policies",
+    params(("name" = String, Path, description = "Имя PSO")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = PasswordSettingsResponse),
+        (status = 403, description = "У API-ключа нет области `directory:read`", body = super::openapi::ErrorBody),
+        (status = 404, description = "PSO не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_password_policy(
+    _reader: Reader,
+    Path(name): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<PasswordSettingsResponse>, DirectoryError> {
+    let pso = find_pso(&service, &name).await?;
+    Ok(Json(PasswordSettingsResponse::new(&service, pso).await?))
+}
+
+#[utoipa::path(post, path = "/api/password-policies", tag = "password-policies",
+    request_body = CreatePasswordSettingsRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "PSO создан", body = PasswordSettingsResponse),
+        (status = 400, description = "Пустое имя или неверные параметры блокировки", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Группа или OU не найдены", body = super::openapi::ErrorBody),
+        (status = 409, description = "PSO с таким именем уже есть", body = super::openapi::ErrorBody),
+    ))]
+pub async fn create_password_policy(
+    _admin: Authorized<DirectoryWrite>,
+    State(service): State<SharedService>,
+    Json(payload): Json<CreatePasswordSettingsRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let mut policy = PasswordPolicy::default();
+    payload.settings.apply(&mut policy);
+    let mut pso = PasswordSettingsObject::new(payload.name, payload.precedence, policy);
+    pso.description = payload.description;
+    pso.applies_to_groups = resolve_groups(&service, &payload.groups).await?;
+    pso.applies_to_ous = resolve_ous(&service, &payload.ous).await?;
+    service.save_pso(&pso).await?;
+
+    let pso = service.get_pso(pso.id).await?.unwrap_or(pso);
+    Ok((StatusCode::CREATED, Json(PasswordSettingsResponse::new(&service, pso).await?)))
+}
+
+#[utoipa::path(put, path = "/api/password-policies/{name}", tag = "password-policies",
+    params(("name" = String, Path, description = "Имя PSO")),
+    request_body = UpdatePasswordSettingsRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = PasswordSettingsResponse),
+        (status = 400, description = "Неверные параметры блокировки", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 404, description = "PSO, группа или OU не найдены", body = super::openapi::ErrorBody),
+    ))]
+pub async fn update_password_policy(
+    _admin: Authorized<DirectoryWrite>,
+    Path(name): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<UpdatePasswordSettingsRequest>,
+) -> Result<Json<PasswordSettingsResponse>, DirectoryError> {
+    let mut pso = find_pso(&service, &name).await?;
+    if let Some(description) = payload.description {
+        pso.description = Some(description);
+    }
+    if let Some(precedence) = payload.precedence {
+        pso.precedence = precedence;
+    }
+    if let Some(groups) = &payload.groups {
+        pso.applies_to_groups = resolve_groups(&service, groups).await?;
+    }
+    if let Some(ous) = &payload.ous {
+        pso.applies_to_ous = resolve_ous(&service, ous).await?;
+    }
+    payload.settings.apply(&mut pso.policy);
+    service.save_pso(&pso).await?;
+
+    let pso = find_pso(&service, &name).await?;
+    Ok(Json(PasswordSettingsResponse::new(&service, pso).await?))
+}
+
+#[utoipa::path(delete, path = "/api/password-policies/{name}", tag = "password-policies",
+    params(("name" = String, Path, description = "Имя PSO")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "PSO удалён; его пользователям снова действует следующий PSO или политика домена"),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 404, description = "PSO не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn delete_password_policy(
+    _admin: Authorized<DirectoryWrite>,
+    Path(name): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let pso = find_pso(&service, &name).await?;
+    service.delete_pso(pso.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(get, path = "/api/users/{username}/password-policy", tag = "password-policies",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "PSO пользователя или политика домена", body = ResultantPasswordPolicyResponse),
+        (status = 403, description = "Нет права `READ_PROPERTY` на пользователя", body = super::openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_resultant_password_policy(
+    caller: Caller,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<ResultantPasswordPolicyResponse>, DirectoryError> {
+    let user = service.find_user_by_username(&username).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+    service.authorize(&caller.user, SecuredObject::User(user.id), AccessMask::READ_PROPERTY).await?;
+
+    let pso = service.resultant_pso(&user).await?;
+    let settings = service.resultant_password_policy(&user).await?;
+    Ok(Json(ResultantPasswordPolicyResponse { pso: pso.map(|pso| pso.name), settings: settings.into() }))
+}
@@ -0,0 +1,153 @@
+// src/web/password_reset.rs
+
+//! Сброс забытого пароля: `POST /api/password-reset/request` отправляет на email пользователя
+//! ссылку с одноразовым токеном, `POST /api/password-reset/confirm` меняет пароль по токену.
+//! Без входа, поэтому запрос всегда отвечает 202 — существует ли учётная запись, снаружи не видно.
+//! Включается `security.password_reset`, письма отправляет `mail.smtp`
+
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde::Deserialize;
+
+use crate::config::PasswordResetConfig;
+use crate::directory_service::DirectoryError;
+use crate::events::{AuditEvent, AuditResult};
+use crate::mail::{token_url, OtpMessage, OtpSender};
+use crate::middleware::ClientIp;
+use crate::ratelimit::{ip_key, LimitScope, RateLimiter};
+use super::SharedService;
+
+/// Настройки сброса, отправитель писем и лимиты запросов
+pub struct PasswordReset {
+    config: PasswordResetConfig,
+    sender: Option<Arc<dyn OtpSender>>,
+    per_ip: RateLimiter,
+    per_account: RateLimiter,
+}
+
+impl PasswordReset {
+    pub fn new(config: &PasswordResetConfig, sender: Option<Arc<dyn OtpSender>>) -> Self {
+        Self {
+            config: config.clone(),
+            sender,
+            per_ip: RateLimiter::new(config.per_ip),
+            per_account: RateLimiter::new(config.per_account),
+        }
+    }
+
+    fn sender(&self) -> Result<&Arc<dyn OtpSender>, DirectoryError> {
+        match &self.sender {
+            Some(sender) if self.config.enabled => Ok(sender),
+            _ => Err(DirectoryError::AccessDenied("password reset is disabled".to_string())),
+        }
+    }
+
+    /// Взять попытку из корзины; первый отказ попадает в аудит
+    async fn check_limit(&self, service: &SharedService, scope: LimitScope, key: &str, ip: &str) -> Result<(), DirectoryError> {
+        let (limiter, bucket) = match scope {
+            LimitScope::Ip => (&self.per_ip, ip_key(key)),
+            LimitScope::Account => (&self.per_account, key.to_string()),
+        };
+        let Err((retry_after, breach)) = limiter.acquire(&bucket) else {
+            return Ok(());
+        };
+        if breach {
+            let mut event = AuditEvent::new("password_reset_rate_limited", AuditResult::Failure);
+            event.ip_addr = Some(ip.to_string());
+            event.metadata.insert("scope".to_string(), scope.as_str().to_string());
+            if scope == LimitScope::Account {
+                event.metadata.insert("login".to_string(), key.to_string());
+            }
+            service.record(event).await?;
+        }
+        Err(DirectoryError::RateLimited(retry_after.as_secs().max(1)))
+    }
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct PasswordResetRequest {
+    /// Имя пользователя или email
+    pub login: String,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct PasswordResetConfirm {
+    /// Токен из ссылки в письме
+    pub token: String,
+    pub new_password: String,
+}
+
+#[utoipa::path(post, path = "/api/password-reset/request", tag = "auth",
+    request_body = PasswordResetRequest,
+    responses(
+        (status = 202, description = "Если учётная запись есть и у неё есть email, на него отправлена ссылка"),
+        (status = 403, description = "Сброс пароля выключен", body = super::openapi::ErrorBody),
+        (status = 429, description = "Слишком много запросов; повторить через `Retry-After` секунд", body = super::openapi::ErrorBody),
+    ))]
+pub async fn request_password_reset(
+    State(service): State<SharedService>,
+    Extension(reset): Extension<Arc<PasswordReset>>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<PasswordResetRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let sender = Arc::clone(reset.sender()?);
+    let ip = ip.to_string();
+    let login = payload.login.trim().to_lowercase();
+    reset.check_limit(&service, LimitScope::Ip, &ip, &ip).await?;
+    reset.check_limit(&service, LimitScope::Account, &login, &ip).await?;
+
+    let ttl = chrono::Duration::seconds(reset.config.token_ttl_secs.try_into().unwrap_or(i64::MAX));
+    let Some((user, token)) = service.request_password_reset(payload.login.trim(), ttl, Some(ip)).await? else {
+        return Ok(StatusCode::ACCEPTED);
+    };
+    let Some(email) = user.email.clone() else {
+        return Ok(StatusCode::ACCEPTED);
+    };
+
+    let url = token_url(reset.config.reset_url.as_deref().unwrap_or_default(), &token);
+    let message = OtpMessage {
+        to: email,
+        subject: reset.config.subject.clone(),
+        body: format!(
+            "A password reset was requested for your account {}.\n\nTo set a new password, open this link:\n\n{}\n\nThe link can be used once and is valid until {}. If you did not request a reset, ignore this message.\n",
+            user.username,
+            url,
+            (chrono::Utc::now() + ttl).format("%Y-%m-%d %H:%M UTC"),
+        ),
+    };
+    // Письмо уходит в фоне: время ответа не выдаёт, нашлась ли учётная запись
+    tokio::spawn(async move {
+        if let Err(e) = sender.send(&message).await {
+            tracing::error!(username = %user.username, error = %e, "Не удалось отправить письмо для сброса пароля");
+        }
+    });
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(post, path = "/api/password-reset/confirm", tag = "auth",
+    request_body = PasswordResetConfirm,
+    responses(
+        (status = 204, description = "Пароль сменён, токен погашен"),
+        (status = 400, description = "Токен неверный или истёк, либо пароль не проходит парольную политику", body = super::openapi::ErrorBody),
+        (status = 403, description = "Сброс пароля выключен", body = super::openapi::ErrorBody),
+        (status = 429, description = "Слишком много запросов; повторить через `Retry-After` секунд", body = super::openapi::ErrorBody),
+    ))]
+pub async fn confirm_password_reset(
+    State(service): State<SharedService>,
+    Extension(reset): Extension<Arc<PasswordReset>>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<PasswordResetConfirm>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    reset.sender()?;
+    let ip = ip.to_string();
+    reset.check_limit(&service, LimitScope::Ip, &ip, &ip).await?;
+
+    service.confirm_password_reset(&payload.token, &payload.new_password, Some(ip)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
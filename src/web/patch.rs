@@ -0,0 +1,250 @@
+// src/web/patch.rs
+
+//! PATCH ресурсов REST: JSON Merge Patch (RFC 7386, `application/merge-patch+json`) и
+//! JSON Patch (RFC 6902, `application/json-patch+json`) переводятся в изменения атрибутов
+//! `DirectoryService::modify_object`. Поля ресурса называются как в ответе GET, атрибуты
+//! схемы — в объекте `attributes`
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::directory_service::DirectoryError;
+use crate::error_code::ErrorCode;
+use crate::models::AttributeChange;
+use super::errors;
+
+pub const MERGE_PATCH: &str = "application/merge-patch+json";
+pub const JSON_PATCH: &str = "application/json-patch+json";
+
+/// Объект с атрибутами схемы в ресурсе
+const ATTRIBUTES: &str = "attributes";
+
+/// Поле ресурса REST и атрибут, в который оно записывается
+pub type PatchField = (&'static str, &'static str);
+
+/// Тело PATCH; обычный `application/json` читается как merge patch
+pub enum PatchDocument {
+    Merge(Map<String, Value>),
+    Operations(Vec<PatchOperation>),
+}
+
+/// Операция JSON Patch; `move` и `copy` не поддерживаются
+#[derive(Deserialize, utoipa::ToSchema, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add {
+        path: String,
+        #[schema(value_type = Object)]
+        value: Value,
+    },
+    Remove { path: String },
+    Replace {
+        path: String,
+        #[schema(value_type = Object)]
+        value: Value,
+    },
+    /// Сравнение с ресурсом до изменений; не совпало — весь patch отклоняется с 409
+    Test {
+        path: String,
+        #[schema(value_type = Object)]
+        value: Value,
+    },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequest<S> for PatchDocument {
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = request.headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        let body = Bytes::from_request(request, state).await.map_err(IntoResponse::into_response)?;
+        let invalid = |error: serde_json::Error| {
+            DirectoryError::InvalidInput(format!("Invalid patch document: {}", error)).into_response()
+        };
+        match content_type.as_str() {
+            MERGE_PATCH | "application/json" => match serde_json::from_slice(&body).map_err(invalid)? {
+                Value::Object(patch) => Ok(Self::Merge(patch)),
+                _ => Err(DirectoryError::InvalidInput("Merge patch must be a JSON object".to_string()).into_response()),
+            },
+            JSON_PATCH => Ok(Self::Operations(serde_json::from_slice(&body).map_err(invalid)?)),
+            other => Err(errors::problem(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ErrorCode::UnsupportedMediaType,
+                &format!("Unsupported patch type '{}': use {} or {}", other, MERGE_PATCH, JSON_PATCH),
+            )),
+        }
+    }
+}
+
+impl PatchDocument {
+    /// Изменения атрибутов ресурса: `fields` — поля, которые можно менять, `current` — ресурс
+    /// в JSON, как его отдаёт GET. Остальные поля ресурса только для чтения
+    pub fn attribute_changes(self, fields: &[PatchField], current: &Value) -> Result<Vec<AttributeChange>, DirectoryError> {
+        let mut changes = Vec::new();
+        match self {
+            Self::Merge(patch) => {
+                for (name, value) in patch {
+                    // Ресурс из GET можно прислать целиком: неизменённые поля, в том числе
+                    // только для чтения, пропускаются
+                    if current.get(&name) == Some(&value) {
+                        continue;
+                    }
+                    if name == ATTRIBUTES {
+                        changes.extend(replace_attributes(value, current)?);
+                    } else {
+                        changes.push(replace(field_attribute(fields, &name)?, values(&name, value)?));
+                    }
+                }
+            }
+            Self::Operations(operations) => {
+                for operation in operations {
+                    changes.extend(operation_changes(operation, fields, current)?);
+                }
+            }
+        }
+        Ok(changes)
+    }
+}
+
+fn replace(attribute: &str, values: Vec<String>) -> AttributeChange {
+    AttributeChange::Replace { attribute: attribute.to_string(), values }
+}
+
+fn field_attribute(fields: &[PatchField], name: &str) -> Result<&'static str, DirectoryError> {
+    fields.iter()
+        .find(|(field, _)| *field == name)
+        .map(|(_, attribute)| *attribute)
+        .ok_or_else(|| DirectoryError::InvalidInput(format!("Field {} cannot be patched", name)))
+}
+
+/// Одно значение: строка, число или `TRUE`/`FALSE`, как булевы значения LDAP
+fn scalar(name: &str, value: Value) -> Result<String, DirectoryError> {
+    match value {
+        Value::String(text) => Ok(text),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(flag) => Ok(if flag { "TRUE" } else { "FALSE" }.to_string()),
+        _ => Err(DirectoryError::InvalidInput(format!("Invalid value for {}", name))),
+    }
+}
+
+/// Значения атрибута: `null` — ни одного, массив — по значению на элемент
+fn values(name: &str, value: Value) -> Result<Vec<String>, DirectoryError> {
+    match value {
+        Value::Null => Ok(Vec::new()),
+        Value::Array(items) => items.into_iter().map(|item| scalar(name, item)).collect(),
+        value => Ok(vec![scalar(name, value)?]),
+    }
+}
+
+/// Атрибуты схемы, заданные у ресурса сейчас
+fn current_attributes(current: &Value) -> Vec<String> {
+    current.get(ATTRIBUTES)
+        .and_then(Value::as_object)
+        .map(|attributes| attributes.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Объект `attributes` целиком: перечисленные атрибуты заменяются, `null` удаляет все
+fn replace_attributes(value: Value, current: &Value) -> Result<Vec<AttributeChange>, DirectoryError> {
+    match value {
+        Value::Object(attributes) => attributes.into_iter()
+            .map(|(name, value)| Ok(replace(&name, values(&name, value)?)))
+            .collect(),
+        Value::Null => Ok(current_attributes(current).iter().map(|name| replace(name, Vec::new())).collect()),
+        _ => Err(DirectoryError::InvalidInput("attributes must be an object".to_string())),
+    }
+}
+
+/// Сегменты JSON Pointer (RFC 6901)
+fn pointer(path: &str) -> Result<Vec<String>, DirectoryError> {
+    let rest = path.strip_prefix('/')
+        .ok_or_else(|| DirectoryError::InvalidInput(format!("Invalid JSON pointer '{}'", path)))?;
+    Ok(rest.split('/').map(|segment| segment.replace("~1", "/").replace("~0", "~")).collect())
+}
+
+/// Значение атрибута схемы по индексу, как его отдаёт GET
+fn indexed_value(current: &Value, path: &str) -> Result<String, DirectoryError> {
+    current.pointer(path)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| DirectoryError::InvalidInput(format!("No value at {}", path)))
+}
+
+fn operation_changes(operation: PatchOperation, fields: &[PatchField], current: &Value) -> Result<Vec<AttributeChange>, DirectoryError> {
+    match operation {
+        PatchOperation::Add { path, value } => match target(&path)? {
+            Target::Field(name) => Ok(vec![replace(field_attribute(fields, &name)?, values(&name, value)?)]),
+            Target::Attributes => replace_all_attributes(value, current),
+            Target::Attribute(name) => Ok(vec![replace(&name, values(&name, value)?)]),
+            // Порядок значений в каталоге не хранится: вставка по индексу или в конец — добавление значения
+            Target::Value(name) => Ok(vec![AttributeChange::Add { values: vec![scalar(&name, value)?], attribute: name }]),
+        },
+        PatchOperation::Replace { path, value } => match target(&path)? {
+            Target::Field(name) => Ok(vec![replace(field_attribute(fields, &name)?, values(&name, value)?)]),
+            Target::Attributes => replace_all_attributes(value, current),
+            Target::Attribute(name) => Ok(vec![replace(&name, values(&name, value)?)]),
+            Target::Value(name) => Ok(vec![
+                AttributeChange::Delete { attribute: name.clone(), values: vec![indexed_value(current, &path)?] },
+                AttributeChange::Add { values: vec![scalar(&name, value)?], attribute: name },
+            ]),
+        },
+        PatchOperation::Remove { path } => match target(&path)? {
+            Target::Field(name) => Ok(vec![AttributeChange::Delete { attribute: field_attribute(fields, &name)?.to_string(), values: Vec::new() }]),
+            Target::Attributes => replace_attributes(Value::Null, current),
+            Target::Attribute(name) => Ok(vec![AttributeChange::Delete { attribute: name, values: Vec::new() }]),
+            Target::Value(name) => Ok(vec![AttributeChange::Delete { attribute: name, values: vec![indexed_value(current, &path)?] }]),
+        },
+        PatchOperation::Test { path, value } => {
+            if current.pointer(&path) != Some(&value) {
+                return Err(DirectoryError::AlreadyExists(ErrorCode::Conflict, format!("Test failed at {}", path)));
+            }
+            Ok(Vec::new())
+        }
+        PatchOperation::Move { .. } | PatchOperation::Copy { .. } => {
+            Err(DirectoryError::InvalidInput("JSON Patch move and copy are not supported".to_string()))
+        }
+    }
+}
+
+/// Новый объект `attributes`: прежние атрибуты удаляются, перечисленные задаются
+fn replace_all_attributes(value: Value, current: &Value) -> Result<Vec<AttributeChange>, DirectoryError> {
+    let mut changes = replace_attributes(Value::Null, current)?;
+    changes.extend(replace_attributes(value, current)?);
+    Ok(changes)
+}
+
+/// Что меняет операция JSON Patch
+enum Target {
+    /// `/<field>` — поле ресурса
+    Field(String),
+    /// `/attributes`
+    Attributes,
+    /// `/attributes/<name>`
+    Attribute(String),
+    /// `/attributes/<name>/<index>` или `/attributes/<name>/-` — одно значение
+    Value(String),
+}
+
+fn target(path: &str) -> Result<Target, DirectoryError> {
+    let segments = pointer(path)?;
+    match segments.as_slice() {
+        [field] if field != ATTRIBUTES => Ok(Target::Field(field.clone())),
+        [_] => Ok(Target::Attributes),
+        [root, name] if root == ATTRIBUTES => Ok(Target::Attribute(name.clone())),
+        [root, name, _] if root == ATTRIBUTES => Ok(Target::Value(name.clone())),
+        _ => Err(DirectoryError::InvalidInput(format!("Path {} cannot be patched", path))),
+    }
+}
@@ -0,0 +1,86 @@
+// src/web/photos.rs
+
+//! Фотография пользователя: `PUT /api/users/{username}/photo` с телом-изображением
+//! (`Content-Type: image/jpeg` или `image/png`, до 100 КБ), `GET` отдаёт его обратно,
+//! `DELETE` удаляет. В LDAP фотография видна как thumbnailPhoto, JPEG — ещё и как jpegPhoto.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::Caller;
+use crate::models::{AccessMask, SecuredObject, User, UserPhoto};
+use super::SharedService;
+
+async fn find_user(service: &SharedService, username: &str) -> Result<User, DirectoryError> {
+    service.find_user_by_username(username)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))
+}
+
+#[utoipa::path(get, path = "/api/users/{username}/photo", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    responses(
+        (status = 200, description = "Фотография", content((Vec<u8> = "image/jpeg"), (Vec<u8> = "image/png"))),
+        (status = 404, description = "Нет пользователя или фотографии", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_user_photo(
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let user = find_user(&service, &username).await?;
+    let photo = service.get_user_photo(user.id)
+        .await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User {} has no photo", username)))?;
+    Ok(([(header::CONTENT_TYPE, photo.content_type)], photo.data))
+}
+
+#[utoipa::path(put, path = "/api/users/{username}/photo", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    request_body(description = "Изображение до 100 КБ", content((Vec<u8> = "image/jpeg"), (Vec<u8> = "image/png"))),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Фотография сохранена"),
+        (status = 400, description = "Не JPEG/PNG, данные не совпадают с `Content-Type` или больше 100 КБ", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` на пользователя", body = super::openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn set_user_photo(
+    caller: Caller,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let user = find_user(&service, &username).await?;
+    service.authorize(&caller.user, SecuredObject::User(user.id), AccessMask::WRITE_PROPERTY).await?;
+
+    let content_type = headers.get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    let photo = UserPhoto::new(content_type, body.to_vec()).map_err(DirectoryError::InvalidInput)?;
+    service.set_user_photo(user.id, Some(&photo)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(delete, path = "/api/users/{username}/photo", tag = "users",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Фотография удалена"),
+        (status = 403, description = "Нет права `WRITE_PROPERTY` на пользователя", body = super::openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn delete_user_photo(
+    caller: Caller,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let user = find_user(&service, &username).await?;
+    service.authorize(&caller.user, SecuredObject::User(user.id), AccessMask::WRITE_PROPERTY).await?;
+
+    service.set_user_photo(user.id, None).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
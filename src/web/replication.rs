@@ -0,0 +1,70 @@
+// src/web/replication.rs
+
+//! Протокол репликации: `POST /api/replication/snapshot` — снимок каталога для реплики только
+//! для чтения (`replication.read_only`). Нужны права администратора и область `replication:pull`.
+
+use axum::{extract::State, Json};
+use base64::engine::{general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::{Authorized, ReplicationPull};
+use super::SharedService;
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SnapshotRequest {
+    /// Пользователи и группы, чьи пароли и ключи Kerberos передать реплике; у остальных они стёрты
+    #[serde(default)]
+    pub cache_credentials_for: Vec<String>,
+}
+
+/// Запись базы каталога
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SnapshotEntry {
+    pub key: String,
+    /// Значение в формате базы (bincode), base64; реплика должна быть той же версии
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SnapshotResponse {
+    pub entries: Vec<SnapshotEntry>,
+}
+
+impl SnapshotResponse {
+    pub fn new(entries: Vec<(String, Vec<u8>)>) -> Self {
+        Self {
+            entries: entries.into_iter()
+                .map(|(key, value)| SnapshotEntry { key, value: STANDARD.encode(value) })
+                .collect(),
+        }
+    }
+
+    /// Записи для `DirectoryService::apply_replication_snapshot`
+    pub fn into_entries(self) -> Result<Vec<(String, Vec<u8>)>, String> {
+        self.entries.into_iter()
+            .map(|entry| {
+                let value = STANDARD.decode(&entry.value)
+                    .map_err(|e| format!("Invalid value of {}: {}", entry.key, e))?;
+                Ok((entry.key, value))
+            })
+            .collect()
+    }
+}
+
+#[utoipa::path(post, path = "/api/replication/snapshot", tag = "replication",
+    request_body = SnapshotRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Все записи каталога, кроме журнала аудита", body = SnapshotResponse),
+        (status = 403, description = "Нет прав администратора или области `replication:pull`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Пользователь или группа из `cache_credentials_for` не найдены", body = super::openapi::ErrorBody),
+    ))]
+pub async fn replication_snapshot(
+    Authorized(admin, _): Authorized<ReplicationPull>,
+    State(service): State<SharedService>,
+    Json(payload): Json<SnapshotRequest>,
+) -> Result<Json<SnapshotResponse>, DirectoryError> {
+    let entries = service.replication_snapshot(&payload.cache_credentials_for, Some(admin.user.id)).await?;
+    Ok(Json(SnapshotResponse::new(entries)))
+}
@@ -0,0 +1,123 @@
+// src/web/schema.rs
+
+//! Схема дополнительных атрибутов: `GET/POST /api/schema/attributes`, `DELETE /api/schema/attributes/{name}`.
+//! Значения задаются в поле `attributes` пользователей, групп и OU и проверяются по схеме.
+//! Определяют и удаляют атрибуты Domain Admins; API-ключу нужна область `directory:write`.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::{Authorized, DirectoryWrite};
+use crate::models::{AttributeDefinition, AttributeSyntax, SchemaClass};
+use super::SharedService;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct DefineAttributeRequest {
+    /// lDAPDisplayName: буква, затем буквы, цифры и `-`
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// `string`, `integer`, `boolean` (`TRUE`/`FALSE`), `generalized_time`, `dn`
+    #[schema(value_type = String)]
+    pub syntax: AttributeSyntax,
+    #[serde(default)]
+    pub multi_valued: bool,
+    #[serde(default)]
+    pub required: bool,
+    /// Поиск на равенство: `GET /api/users?attribute=&value=`
+    #[serde(default)]
+    pub indexed: bool,
+    /// `user`, `group`, `organizational_unit`, `contact`
+    #[schema(value_type = Vec<String>)]
+    pub classes: Vec<SchemaClass>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct AttributeResponse {
+    pub name: String,
+    pub description: Option<String>,
+    #[schema(value_type = String)]
+    pub syntax: AttributeSyntax,
+    pub multi_valued: bool,
+    pub required: bool,
+    pub indexed: bool,
+    #[schema(value_type = Vec<String>)]
+    pub classes: Vec<SchemaClass>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AttributeDefinition> for AttributeResponse {
+    fn from(definition: AttributeDefinition) -> Self {
+        Self {
+            name: definition.name,
+            description: definition.description,
+            syntax: definition.syntax,
+            multi_valued: definition.multi_valued,
+            required: definition.required,
+            indexed: definition.indexed,
+            classes: definition.classes,
+            created_at: definition.created_at,
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/api/schema/attributes", tag = "schema",
+    responses((status = 200, description = "Определённые атрибуты", body = Vec<AttributeResponse>)))]
+pub async fn list_attributes(
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<AttributeResponse>>, DirectoryError> {
+    let schema = service.get_schema().await?;
+    Ok(Json(schema.into_iter().map(AttributeResponse::from).collect()))
+}
+
+#[utoipa::path(post, path = "/api/schema/attributes", tag = "schema",
+    request_body = DefineAttributeRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "Атрибут определён", body = AttributeResponse),
+        (status = 400, description = "Неверное имя, встроенный атрибут или нет классов", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 409, description = "Атрибут уже определён", body = super::openapi::ErrorBody),
+    ))]
+pub async fn define_attribute(
+    _admin: Authorized<DirectoryWrite>,
+    State(service): State<SharedService>,
+    Json(payload): Json<DefineAttributeRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let definition = AttributeDefinition {
+        name: payload.name,
+        description: payload.description,
+        syntax: payload.syntax,
+        multi_valued: payload.multi_valued,
+        required: payload.required,
+        indexed: payload.indexed,
+        classes: payload.classes,
+        created_at: Utc::now(),
+    };
+    service.define_attribute(&definition).await?;
+    Ok((StatusCode::CREATED, Json(AttributeResponse::from(definition))))
+}
+
+#[utoipa::path(delete, path = "/api/schema/attributes/{name}", tag = "schema",
+    params(("name" = String, Path, description = "Имя атрибута")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Определение удалено; значения в объектах остаются без проверки"),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Атрибут не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn delete_attribute(
+    _admin: Authorized<DirectoryWrite>,
+    State(service): State<SharedService>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, DirectoryError> {
+    service.delete_attribute_definition(&name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,258 @@
+// src/web/service_accounts.rs
+
+//! Управляемые учётные записи служб: `GET/POST /api/service-accounts`,
+//! `GET/PUT /api/service-accounts/{username}`, `POST /api/service-accounts/{username}/rotate` — для Domain Admins;
+//! `GET /api/service-accounts/{username}/password` — для участников из `allowed_principals`.
+//! API-ключу нужна область `service-accounts:manage` или, для пароля, `service-accounts:password`.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::middleware::{Authorized, Scoped, ServiceAccountsManage, ServiceAccountsPassword};
+use crate::models::{ManagedPassword, SecurityIdentifier, ServiceAccount, ServiceAccountFlags, User};
+use super::acl::resolve_trustee;
+use super::SharedService;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateServiceAccountRequest {
+    pub username: String,
+    /// servicePrincipalName, например `HTTP/web.corp.acme.com`
+    #[serde(default)]
+    pub service_principal_names: Vec<String>,
+    /// Кто может получить пароль: SID, имя пользователя или sAMAccountName группы
+    pub allowed_principals: Vec<String>,
+    /// По умолчанию 30
+    pub rotation_interval_days: Option<u32>,
+    /// Сколько часов после смены принимается прежний пароль; по умолчанию 24
+    pub overlap_hours: Option<u32>,
+    /// `DENY_PASSWORD_LOGON`, `NOT_DELEGATED`; по умолчанию — только `DENY_PASSWORD_LOGON`
+    pub flags: Option<Vec<String>>,
+}
+
+/// Изменение учётной записи службы; не указанные поля не меняются, пароль — только ротацией
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UpdateServiceAccountRequest {
+    pub allowed_principals: Option<Vec<String>>,
+    pub rotation_interval_days: Option<u32>,
+    pub overlap_hours: Option<u32>,
+    pub flags: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ServiceAccountResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub service_principal_names: Vec<String>,
+    pub flags: Vec<String>,
+    /// SID участников, которым выдаётся пароль
+    pub allowed_principals: Vec<String>,
+    pub rotation_interval_days: u32,
+    pub overlap_hours: u32,
+    pub password_changed_at: DateTime<Utc>,
+    pub next_rotation: DateTime<Utc>,
+}
+
+impl ServiceAccountResponse {
+    pub fn new(account: ServiceAccount, user: User) -> Self {
+        Self {
+            id: account.id,
+            username: user.username,
+            service_principal_names: user.service_principal_names,
+            flags: account.flags.flag_names(),
+            allowed_principals: account.allowed_principals.iter().map(ToString::to_string).collect(),
+            rotation_interval_days: account.rotation_interval_days,
+            overlap_hours: account.overlap_hours,
+            password_changed_at: account.password_changed_at,
+            next_rotation: account.next_rotation(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ManagedPasswordResponse {
+    pub current_password: String,
+    /// Прежний пароль, пока он ещё принимается
+    pub previous_password: Option<String>,
+    pub password_changed_at: DateTime<Utc>,
+    pub next_rotation: DateTime<Utc>,
+}
+
+impl From<ManagedPassword> for ManagedPasswordResponse {
+    fn from(password: ManagedPassword) -> Self {
+        Self {
+            current_password: password.current_password,
+            previous_password: password.previous_password,
+            password_changed_at: password.password_changed_at,
+            next_rotation: password.next_rotation,
+        }
+    }
+}
+
+/// Учётная запись службы по имени пользователя
+pub(crate) async fn find_service_account(service: &DirectoryService, username: &str) -> Result<(ServiceAccount, User), DirectoryError> {
+    let not_found = || DirectoryError::NotFound(format!("Service account not found: {}", username));
+    let user = service.find_user_by_username(username).await?.ok_or_else(not_found)?;
+    let account = service.get_service_account(user.id).await?.ok_or_else(not_found)?;
+    Ok((account, user))
+}
+
+/// SID участников по именам (как у ACE), без повторов
+pub(crate) async fn resolve_principals(service: &DirectoryService, names: &[String]) -> Result<Vec<SecurityIdentifier>, DirectoryError> {
+    let mut sids = Vec::new();
+    for name in names {
+        let sid = resolve_trustee(service, name).await?;
+        if !sids.contains(&sid) {
+            sids.push(sid);
+        }
+    }
+    Ok(sids)
+}
+
+#[utoipa::path(get, path = "/api/service-accounts", tag = "service-accounts",
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Все учётные записи служб", body = Vec<ServiceAccountResponse>),
+        (status = 403, description = "Нет прав администратора или области `service-accounts:manage`", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_service_accounts(
+    _admin: Authorized<ServiceAccountsManage>,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<ServiceAccountResponse>>, DirectoryError> {
+    let mut accounts = Vec::new();
+    for account in service.get_all_service_accounts().await? {
+        if let Some(user) = service.get_user(account.id).await? {
+            accounts.push(ServiceAccountResponse::new(account, user));
+        }
+    }
+    Ok(Json(accounts))
+}
+
+#[utoipa::path(get, path = "/api/service-accounts/{username}", tag = "service-accounts",
+    params(("username" = String, Path, description = "Имя учётной записи службы")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = ServiceAccountResponse),
+        (status = 403, description = "Нет прав администратора или области `service-accounts:manage`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Учётная запись службы не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_service_account(
+    _admin: Authorized<ServiceAccountsManage>,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<ServiceAccountResponse>, DirectoryError> {
+    let (account, user) = find_service_account(&service, &username).await?;
+    Ok(Json(ServiceAccountResponse::new(account, user)))
+}
+
+#[utoipa::path(post, path = "/api/service-accounts", tag = "service-accounts",
+    request_body = CreateServiceAccountRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "Учётная запись службы создана; пароль выдаётся только через `/password`", body = ServiceAccountResponse),
+        (status = 400, description = "Нет участников, неизвестный участник или флаг, неверное расписание или SPN", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `service-accounts:manage`", body = super::openapi::ErrorBody),
+        (status = 409, description = "Имя или SPN уже заняты", body = super::openapi::ErrorBody),
+    ))]
+pub async fn create_service_account(
+    _admin: Authorized<ServiceAccountsManage>,
+    State(service): State<SharedService>,
+    Json(payload): Json<CreateServiceAccountRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    if payload.username.trim().is_empty() {
+        return Err(DirectoryError::InvalidInput("Username cannot be empty".to_string()));
+    }
+    let mut account = ServiceAccount::new(Uuid::new_v4(), resolve_principals(&service, &payload.allowed_principals).await?);
+    if let Some(days) = payload.rotation_interval_days {
+        account.rotation_interval_days = days;
+    }
+    if let Some(hours) = payload.overlap_hours {
+        account.overlap_hours = hours;
+    }
+    if let Some(flags) = &payload.flags {
+        account.flags = ServiceAccountFlags::from_flag_names(flags)?;
+    }
+
+    let user = service.create_service_account(&payload.username, &payload.service_principal_names, &account).await?;
+    Ok((StatusCode::CREATED, Json(ServiceAccountResponse::new(account, user))))
+}
+
+#[utoipa::path(put, path = "/api/service-accounts/{username}", tag = "service-accounts",
+    params(("username" = String, Path, description = "Имя учётной записи службы")),
+    request_body = UpdateServiceAccountRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = ServiceAccountResponse),
+        (status = 400, description = "Нет участников, неизвестный участник или флаг, неверное расписание", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `service-accounts:manage`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Учётная запись службы не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn update_service_account(
+    _admin: Authorized<ServiceAccountsManage>,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<UpdateServiceAccountRequest>,
+) -> Result<Json<ServiceAccountResponse>, DirectoryError> {
+    let (mut account, _) = find_service_account(&service, &username).await?;
+    if let Some(principals) = &payload.allowed_principals {
+        account.allowed_principals = resolve_principals(&service, principals).await?;
+    }
+    if let Some(days) = payload.rotation_interval_days {
+        account.rotation_interval_days = days;
+    }
+    if let Some(hours) = payload.overlap_hours {
+        account.overlap_hours = hours;
+    }
+    if let Some(flags) = &payload.flags {
+        account.flags = ServiceAccountFlags::from_flag_names(flags)?;
+    }
+    service.update_service_account(&account).await?;
+
+    let (account, user) = find_service_account(&service, &username).await?;
+    Ok(Json(ServiceAccountResponse::new(account, user)))
+}
+
+#[utoipa::path(post, path = "/api/service-accounts/{username}/rotate", tag = "service-accounts",
+    params(("username" = String, Path, description = "Имя учётной записи службы")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Пароль сменён; прежний принимается до конца окна перекрытия", body = ServiceAccountResponse),
+        (status = 403, description = "Нет прав администратора или области `service-accounts:manage`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Учётная запись службы не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn rotate_service_account_password(
+    _admin: Authorized<ServiceAccountsManage>,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<ServiceAccountResponse>, DirectoryError> {
+    let (account, _) = find_service_account(&service, &username).await?;
+    service.rotate_service_account_password(account.id).await?;
+
+    let (account, user) = find_service_account(&service, &username).await?;
+    Ok(Json(ServiceAccountResponse::new(account, user)))
+}
+
+#[utoipa::path(get, path = "/api/service-accounts/{username}/password", tag = "service-accounts",
+    params(("username" = String, Path, description = "Имя учётной записи службы")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Текущий и, в окне перекрытия, прежний пароль", body = ManagedPasswordResponse),
+        (status = 403, description = "Вызывающего и его групп нет в `allowed_principals` или у ключа нет области `service-accounts:password`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Учётная запись службы не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn retrieve_managed_password(
+    caller: Scoped<ServiceAccountsPassword>,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<ManagedPasswordResponse>, DirectoryError> {
+    let (account, _) = find_service_account(&service, &username).await?;
+    let password = service.retrieve_managed_password(&caller.user, account.id).await?;
+    Ok(Json(password.into()))
+}
@@ -0,0 +1,88 @@
+// src/web/sessions.rs
+
+//! Сессии входа (`security.sessions`): `GET /api/users/{username}/sessions`,
+//! `DELETE /api/sessions/{id}`. Только для Domain Admins (API-ключу нужна область
+//! `sessions:manage`); токен отозванной сессии
+//! отклоняется сразу, не дожидаясь истечения срока.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::{Authorized, SessionsManage};
+use crate::models::{LoginProtocol, Session};
+use super::SharedService;
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct SessionResponse {
+    /// id сессии — claim `jti` её токена
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub protocol: LoginProtocol,
+    pub ip_addr: Option<String>,
+    /// User-Agent клиента
+    pub device: Option<String>,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Токен сессии ещё принимается
+    pub active: bool,
+}
+
+impl From<Session> for SessionResponse {
+    fn from(session: Session) -> Self {
+        Self {
+            active: session.is_active(Utc::now()),
+            id: session.id,
+            user_id: session.user_id,
+            protocol: session.protocol,
+            ip_addr: session.ip_addr,
+            device: session.device,
+            issued_at: session.issued_at,
+            expires_at: session.expires_at,
+            revoked_at: session.revoked_at,
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/api/users/{username}/sessions", tag = "sessions",
+    params(("username" = String, Path, description = "Имя пользователя")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Неистёкшие сессии, включая отозванные, новые первыми", body = Vec<SessionResponse>),
+        (status = 403, description = "Нет прав администратора или области `sessions:manage`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Пользователь не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_user_sessions(
+    _admin: Authorized<SessionsManage>,
+    Path(username): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<SessionResponse>>, DirectoryError> {
+    let user = service.find_user_by_username(&username).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("User not found: {}", username)))?;
+    let sessions = service.list_user_sessions(user.id).await?;
+    Ok(Json(sessions.into_iter().map(SessionResponse::from).collect()))
+}
+
+#[utoipa::path(delete, path = "/api/sessions/{id}", tag = "sessions",
+    params(("id" = Uuid, Path, description = "id сессии")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Сессия отозвана, её токен больше не принимается"),
+        (status = 403, description = "Нет прав администратора или области `sessions:manage`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Сессия не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn revoke_session(
+    _admin: Authorized<SessionsManage>,
+    Path(id): Path<Uuid>,
+    State(service): State<SharedService>,
+) -> Result<StatusCode, DirectoryError> {
+    service.revoke_session(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,343 @@
+// src/web/sites.rs
+
+//! Сайты и подсети: `GET /api/sites`, `GET /api/sites/{name}`, `GET /api/subnets` и
+//! `GET /api/sites/lookup?ip=` (сайт клиента по адресу) — для любого вошедшего пользователя
+//! (API-ключ — с областью `directory:read`); `POST/PUT/DELETE` — для Domain Admins с областью
+//! `directory:write`. Подсеть в пути — адрес сети и длина префикса:
+//! `/api/subnets/10.1.0.0/16`.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use uuid::Uuid;
+
+use crate::directory_service::{DirectoryError, DirectoryService};
+use crate::middleware::{Authorized, DirectoryWrite, Reader};
+use crate::models::{Site, Subnet};
+use super::SharedService;
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateSiteRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+}
+
+/// Изменение сайта; не указанные поля не меняются
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UpdateSiteRequest {
+    /// Новое имя сайта
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SiteResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    /// Подсети сайта в нотации CIDR
+    pub subnets: Vec<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SiteResponse {
+    pub async fn new(service: &DirectoryService, site: Site) -> Result<Self, DirectoryError> {
+        let subnets = service.get_site_subnets(site.id).await?.iter().map(Subnet::cidr).collect();
+        Ok(Self {
+            id: site.id,
+            name: site.name,
+            description: site.description,
+            location: site.location,
+            subnets,
+            updated_at: site.updated_at,
+        })
+    }
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateSubnetRequest {
+    /// Подсеть в нотации CIDR: `10.1.0.0/16`, `2001:db8::/32`
+    pub subnet: String,
+    /// Имя сайта
+    pub site: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+}
+
+/// Изменение подсети; не указанные поля не меняются
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UpdateSubnetRequest {
+    /// Перенести подсеть в другой сайт
+    pub site: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SubnetResponse {
+    pub id: Uuid,
+    pub subnet: String,
+    pub site: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SubnetResponse {
+    pub async fn new(service: &DirectoryService, subnet: Subnet) -> Result<Self, DirectoryError> {
+        let site = service.get_site(subnet.site_id).await?
+            .map(|site| site.name)
+            .unwrap_or_else(|| subnet.site_id.to_string());
+        Ok(Self {
+            id: subnet.id,
+            subnet: subnet.cidr(),
+            site,
+            description: subnet.description,
+            location: subnet.location,
+            updated_at: subnet.updated_at,
+        })
+    }
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct SiteLookupQuery {
+    /// Адрес клиента IPv4 или IPv6
+    pub ip: String,
+}
+
+pub(crate) async fn find_site(service: &DirectoryService, name: &str) -> Result<Site, DirectoryError> {
+    service.find_site_by_name(name).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Site not found: {}", name)))
+}
+
+pub(crate) async fn find_subnet(service: &DirectoryService, cidr: &str) -> Result<Subnet, DirectoryError> {
+    service.find_subnet(cidr).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Subnet not found: {}", cidr)))
+}
+
+#[utoipa::path(get, path = "/api/sites", tag = "sites",
+    security(("bearer" = []), ("api_key" = [])),
+    responses((status = 200, description = "Сайты по имени", body = Vec<SiteResponse>)))]
+pub async fn list_sites(
+    _reader: Reader,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<SiteResponse>>, DirectoryError> {
+    let mut sites = Vec::new();
+    for site in service.get_all_sites().await? {
+        sites.push(SiteResponse::new(&service, site).await?);
+    }
+    Ok(Json(sites))
+}
+
+#[utoipa::path(get, path = "/api/sites/{name}", tag = "sites",
+    params(("name" = String, Path, description = "Имя сайта")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = SiteResponse),
+        (status = 404, description = "Сайт не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_site(
+    _reader: Reader,
+    Path(name): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<Json<SiteResponse>, DirectoryError> {
+    let site = find_site(&service, &name).await?;
+    Ok(Json(SiteResponse::new(&service, site).await?))
+}
+
+#[utoipa::path(get, path = "/api/sites/lookup", tag = "sites",
+    params(SiteLookupQuery),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Сайт самой узкой подсети, в которую входит адрес", body = SiteResponse),
+        (status = 400, description = "Неверный адрес", body = super::openapi::ErrorBody),
+        (status = 404, description = "Адрес не входит ни в одну подсеть", body = super::openapi::ErrorBody),
+    ))]
+pub async fn lookup_site(
+    _reader: Reader,
+    Query(query): Query<SiteLookupQuery>,
+    State(service): State<SharedService>,
+) -> Result<Json<SiteResponse>, DirectoryError> {
+    let ip: IpAddr = query.ip.parse()
+        .map_err(|_| DirectoryError::InvalidInput(format!("Invalid IP address: {}", query.ip)))?;
+    let site = service.find_site_for_ip(ip).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("No site for address {}", ip)))?;
+    Ok(Json(SiteResponse::new(&service, site).await?))
+}
+
+#[utoipa::path(post, path = "/api/sites", tag = "sites",
+    request_body = CreateSiteRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "Сайт создан", body = SiteResponse),
+        (status = 400, description = "Пустое имя", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 409, description = "Сайт с таким именем уже есть", body = super::openapi::ErrorBody),
+    ))]
+pub async fn create_site(
+    _admin: Authorized<DirectoryWrite>,
+    State(service): State<SharedService>,
+    Json(payload): Json<CreateSiteRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let mut site = Site::new(payload.name);
+    site.description = payload.description;
+    site.location = payload.location;
+    service.save_site(&site).await?;
+
+    let site = service.get_site(site.id).await?.unwrap_or(site);
+    Ok((StatusCode::CREATED, Json(SiteResponse::new(&service, site).await?)))
+}
+
+#[utoipa::path(put, path = "/api/sites/{name}", tag = "sites",
+    params(("name" = String, Path, description = "Имя сайта")),
+    request_body = UpdateSiteRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = SiteResponse),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Сайт не найден", body = super::openapi::ErrorBody),
+        (status = 409, description = "Сайт с новым именем уже есть", body = super::openapi::ErrorBody),
+    ))]
+pub async fn update_site(
+    _admin: Authorized<DirectoryWrite>,
+    Path(name): Path<String>,
+    State(service): State<SharedService>,
+    Json(payload): Json<UpdateSiteRequest>,
+) -> Result<Json<SiteResponse>, DirectoryError> {
+    let mut site = find_site(&service, &name).await?;
+    if let Some(name) = payload.name {
+        site.name = name;
+    }
+    if let Some(description) = payload.description {
+        site.description = Some(description);
+    }
+    if let Some(location) = payload.location {
+        site.location = Some(location);
+    }
+    service.save_site(&site).await?;
+
+    let site = service.get_site(site.id).await?.unwrap_or(site);
+    Ok(Json(SiteResponse::new(&service, site).await?))
+}
+
+#[utoipa::path(delete, path = "/api/sites/{name}", tag = "sites",
+    params(("name" = String, Path, description = "Имя сайта")),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Сайт удалён"),
+        (status = 400, description = "У сайта остались подсети", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Сайт не найден", body = super::openapi::ErrorBody),
+    ))]
+pub async fn delete_site(
+    _admin: Authorized<DirectoryWrite>,
+    Path(name): Path<String>,
+    State(service): State<SharedService>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let site = find_site(&service, &name).await?;
+    service.delete_site(site.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(get, path = "/api/subnets", tag = "sites",
+    security(("bearer" = []), ("api_key" = [])),
+    responses((status = 200, description = "Подсети: IPv4, затем IPv6, по адресу сети", body = Vec<SubnetResponse>)))]
+pub async fn list_subnets(
+    _reader: Reader,
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<SubnetResponse>>, DirectoryError> {
+    let mut subnets = Vec::new();
+    for subnet in service.get_all_subnets().await? {
+        subnets.push(SubnetResponse::new(&service, subnet).await?);
+    }
+    Ok(Json(subnets))
+}
+
+#[utoipa::path(post, path = "/api/subnets", tag = "sites",
+    request_body = CreateSubnetRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 201, description = "Подсеть создана", body = SubnetResponse),
+        (status = 400, description = "Неверная запись CIDR", body = super::openapi::ErrorBody),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Сайт не найден", body = super::openapi::ErrorBody),
+        (status = 409, description = "Подсеть уже есть", body = super::openapi::ErrorBody),
+    ))]
+pub async fn create_subnet(
+    _admin: Authorized<DirectoryWrite>,
+    State(service): State<SharedService>,
+    Json(payload): Json<CreateSubnetRequest>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let site = find_site(&service, &payload.site).await?;
+    let mut subnet = Subnet::new(&payload.subnet, site.id)?;
+    subnet.description = payload.description;
+    subnet.location = payload.location;
+    service.save_subnet(&subnet).await?;
+
+    let subnet = service.get_subnet(subnet.id).await?.unwrap_or(subnet);
+    Ok((StatusCode::CREATED, Json(SubnetResponse::new(&service, subnet).await?)))
+}
+
+#[utoipa::path(put, path = "/api/subnets/{network}/{prefix_len}", tag = "sites",
+    params(
+        ("network" = String, Path, description = "Адрес сети"),
+        ("prefix_len" = u8, Path, description = "Длина префикса"),
+    ),
+    request_body = UpdateSubnetRequest,
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, body = SubnetResponse),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Подсеть или сайт не найдены", body = super::openapi::ErrorBody),
+    ))]
+pub async fn update_subnet(
+    _admin: Authorized<DirectoryWrite>,
+    Path((network, prefix_len)): Path<(String, u8)>,
+    State(service): State<SharedService>,
+    Json(payload): Json<UpdateSubnetRequest>,
+) -> Result<Json<SubnetResponse>, DirectoryError> {
+    let mut subnet = find_subnet(&service, &format!("{}/{}", network, prefix_len)).await?;
+    if let Some(site) = &payload.site {
+        subnet.site_id = find_site(&service, site).await?.id;
+    }
+    if let Some(description) = payload.description {
+        subnet.description = Some(description);
+    }
+    if let Some(location) = payload.location {
+        subnet.location = Some(location);
+    }
+    service.save_subnet(&subnet).await?;
+
+    let subnet = service.get_subnet(subnet.id).await?.unwrap_or(subnet);
+    Ok(Json(SubnetResponse::new(&service, subnet).await?))
+}
+
+#[utoipa::path(delete, path = "/api/subnets/{network}/{prefix_len}", tag = "sites",
+    params(
+        ("network" = String, Path, description = "Адрес сети"),
+        ("prefix_len" = u8, Path, description = "Длина префикса"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 204, description = "Подсеть удалена"),
+        (status = 403, description = "Нет прав администратора или области `directory:write`", body = super::openapi::ErrorBody),
+        (status = 404, description = "Подсеть не найдена", body = super::openapi::ErrorBody),
+    ))]
+pub async fn delete_subnet(
+    _admin: Authorized<DirectoryWrite>,
+    Path((network, prefix_len)): Path<(String, u8)>,
+    State(service): State<SharedService>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let subnet = find_subnet(&service, &format!("{}/{}", network, prefix_len)).await?;
+    service.delete_subnet(subnet.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,74 @@
+// src/web/sysvol.rs
+
+//! Раздача политик по образцу SYSVOL, только чтение: `GET /sysvol/{domain}/Policies/{GUID}/{path}` —
+//! файл GPO (`GPT.INI`, `Machine/Registry.pol`, `User/Scripts/scripts.ini`, ...), а
+//! `GET /sysvol/{domain}/Policies/{GUID}` — список её файлов. Доступно любому вошедшему
+//! пользователю, как SYSVOL группе Authenticated Users.
+
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::directory_service::DirectoryError;
+use crate::middleware::Caller;
+use crate::models::policy::GroupPolicy;
+use super::SharedService;
+
+/// GPO из папки `Policies` домена `domain`
+async fn find_gpo(service: &SharedService, domain: &str, folder: &str) -> Result<GroupPolicy, DirectoryError> {
+    service.find_domain_by_dns_name(domain).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("Domain {} not found", domain)))?;
+    let id = crate::sysvol::parse_folder_name(folder)
+        .ok_or_else(|| DirectoryError::NotFound(format!("GPO {} not found", folder)))?;
+    service.get_gpo(id).await?
+        .ok_or_else(|| DirectoryError::NotFound(format!("GPO {} not found", folder)))
+}
+
+#[utoipa::path(get, path = "/sysvol/{domain}/Policies/{gpo_guid}", tag = "gpos",
+    params(
+        ("domain" = String, Path, description = "DNS-имя домена"),
+        ("gpo_guid" = String, Path, description = "GUID политики, можно в фигурных скобках"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Пути файлов политики относительно её папки", body = [String]),
+        (status = 404, description = "Нет домена или политики", body = super::openapi::ErrorBody),
+    ))]
+pub async fn list_gpo_files(
+    _caller: Caller,
+    State(service): State<SharedService>,
+    Path((domain, folder)): Path<(String, String)>,
+) -> Result<Json<Vec<&'static str>>, DirectoryError> {
+    let gpo = find_gpo(&service, &domain, &folder).await?;
+    Ok(Json(crate::sysvol::files(&gpo)))
+}
+
+#[utoipa::path(get, path = "/sysvol/{domain}/Policies/{gpo_guid}/{path}", tag = "gpos",
+    params(
+        ("domain" = String, Path, description = "DNS-имя домена"),
+        ("gpo_guid" = String, Path, description = "GUID политики, можно в фигурных скобках"),
+        ("path" = String, Path, description = "Путь файла в папке политики, регистр не важен: `GPT.INI`, `Machine/Registry.pol`"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Содержимое файла в формате Windows", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 404, description = "Нет домена, политики или такого файла у неё", body = super::openapi::ErrorBody),
+    ))]
+pub async fn get_gpo_file(
+    _caller: Caller,
+    State(service): State<SharedService>,
+    Path((domain, folder, path)): Path<(String, String, String)>,
+) -> Result<impl IntoResponse, DirectoryError> {
+    let gpo = find_gpo(&service, &domain, &folder).await?;
+    let content = crate::sysvol::render(&gpo, &path)
+        .ok_or_else(|| DirectoryError::NotFound(format!("{} not found in GPO {}", path, folder)))?;
+    let content_type = if path.eq_ignore_ascii_case(crate::sysvol::GPT_INI) {
+        "text/plain; charset=utf-8"
+    } else {
+        "application/octet-stream"
+    };
+    Ok(([(header::CONTENT_TYPE, content_type)], content))
+}
@@ -0,0 +1,92 @@
+// tests/integration/acl.rs
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use nextDomen::models::apikey::scope;
+use nextDomen::models::{AccessMask, Ace, AceFlags, SecuredObject, SecurityDescriptor};
+
+use super::{call, request, TestDirectory};
+
+#[tokio::test]
+async fn test_create_and_modify_by_dacl() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let (_, admin) = directory.api_key("alice", true, &[scope::DIRECTORY_WRITE]).await;
+    let (bob, user) = directory.api_key("bob", false, &[scope::DIRECTORY_WRITE]).await;
+    let app = directory.router("");
+
+    // Без входа ничего не создаётся и не читается
+    let (status, _) = call(&app, request("POST", "/api/users", None, Some(json!({ "username": "carol" })))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(call(&app, request("GET", "/api/users", None, None)).await.0, StatusCode::UNAUTHORIZED);
+
+    let (status, sales) = call(&app, request("POST", "/api/ous", Some(&admin), Some(json!({ "name": "Sales" })))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", sales);
+    let sales_id: uuid::Uuid = sales["id"].as_str().unwrap().parse().unwrap();
+    let sales_dn = sales["dn"].as_str().unwrap().to_string();
+    assert_eq!(call(&app, request("POST", "/api/users", Some(&admin), Some(json!({ "username": "carol" })))).await.0, StatusCode::CREATED);
+
+    // Обычному пользователю корень домена и чужие OU доступны только для чтения
+    let denied = [
+        request("POST", "/api/users", Some(&user), Some(json!({ "username": "dave" }))),
+        request("POST", "/api/groups", Some(&user), Some(json!({ "name": "Bobs" }))),
+        request("POST", "/api/ous", Some(&user), Some(json!({ "name": "Team", "parent": sales_dn }))),
+        request("POST", "/api/contacts", Some(&user), Some(json!({ "name": "Partner", "email": "p@partner.com", "ou": sales_dn }))),
+        request("PUT", "/api/users/carol", Some(&user), Some(json!({ "display_name": "Carol" }))),
+        request("PUT", &format!("/api/ous/{}", sales_id), Some(&user), Some(json!({ "name": "Marketing" }))),
+    ];
+    for request in denied {
+        let uri = request.uri().to_string();
+        let (status, body) = call(&app, request).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} {}", uri, body);
+    }
+    let (status, _) = call(&app, request("POST", "/api/ous", Some(&user), Some(json!({ "name": "Team", "parent": "OU=Missing,DC=x,DC=com" })))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Делегирование: bob создаёт объекты в Sales и меняет то, что в ней лежит, но не саму Sales
+    let mut descriptor = SecurityDescriptor::new(bob.sid.clone());
+    descriptor.dacl.push(Ace::allow(bob.sid.clone(), AccessMask::CREATE_CHILD, AceFlags::empty()));
+    descriptor.dacl.push(Ace::allow(bob.sid.clone(), AccessMask::GENERIC_WRITE, AceFlags::CONTAINER_INHERIT | AceFlags::OBJECT_INHERIT | AceFlags::INHERIT_ONLY));
+    service.set_security_descriptor(SecuredObject::Ou(sales_id), &descriptor).await.unwrap();
+
+    let (status, team) = call(&app, request("POST", "/api/ous", Some(&user), Some(json!({ "name": "Team", "parent": sales_dn })))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", team);
+    let (status, contact) = call(&app, request("POST", "/api/contacts", Some(&user), Some(json!({ "name": "Partner", "email": "p@partner.com", "ou": sales_dn })))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", contact);
+    let (status, renamed) = call(&app, request("PUT", &format!("/api/ous/{}", team["id"].as_str().unwrap()), Some(&user), Some(json!({ "name": "Squad" })))).await;
+    assert_eq!(status, StatusCode::OK, "{}", renamed);
+    assert_eq!(call(&app, request("PUT", &format!("/api/ous/{}", sales_id), Some(&user), Some(json!({ "name": "Marketing" })))).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, request("POST", "/api/users", Some(&user), Some(json!({ "username": "dave" })))).await.0, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_read_by_dacl() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let (bob, user) = directory.api_key("bob", false, &[scope::DIRECTORY_READ]).await;
+    let (erin, _) = directory.api_key("erin", false, &[scope::DIRECTORY_READ]).await;
+    let (_, audit_only) = directory.api_key("frank", false, &[scope::AUDIT_READ]).await;
+    let app = directory.router("");
+
+    let usernames = |users: &serde_json::Value| -> Vec<String> {
+        users.as_array().unwrap().iter().map(|user| user["username"].as_str().unwrap().to_string()).collect()
+    };
+    let (status, users) = call(&app, request("GET", "/api/users", Some(&user), None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(usernames(&users).contains(&"erin".to_string()), "{}", users);
+    assert_eq!(call(&app, request("GET", "/api/users", Some(&audit_only), None)).await.0, StatusCode::FORBIDDEN);
+    // Ключ только для чтения не даёт писать
+    assert_eq!(call(&app, request("POST", "/api/users", Some(&user), Some(json!({ "username": "dave" })))).await.0, StatusCode::FORBIDDEN);
+
+    let mut descriptor = SecurityDescriptor::new(erin.sid.clone());
+    descriptor.dacl.push(Ace::deny(bob.sid.clone(), AccessMask::READ_PROPERTY, AceFlags::empty()));
+    service.set_security_descriptor(SecuredObject::User(erin.id), &descriptor).await.unwrap();
+
+    let (_, users) = call(&app, request("GET", "/api/users", Some(&user), None)).await;
+    assert!(!usernames(&users).contains(&"erin".to_string()), "{}", users);
+    assert!(usernames(&users).contains(&"bob".to_string()), "{}", users);
+    assert_eq!(call(&app, request("GET", "/api/users/erin", Some(&user), None)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, request("GET", &format!("/api/users/by-id/{}", erin.id), Some(&user), None)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, request("GET", "/api/users/bob", Some(&user), None)).await.0, StatusCode::OK);
+}
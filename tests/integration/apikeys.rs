@@ -0,0 +1,103 @@
+// tests/integration/apikeys.rs
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use nextDomen::models::apikey::scope;
+
+use super::{call, jwt, request, TestDirectory};
+
+#[tokio::test]
+async fn test_write_routes_require_write_scope() {
+    let directory = TestDirectory::new().await;
+    let (_, reader) = directory.api_key("alice", true, &[scope::DIRECTORY_READ]).await;
+    let (_, writer) = directory.api_key("bob", true, &[scope::DIRECTORY_WRITE]).await;
+    let app = directory.router("");
+
+    let (status, created) = call(&app, request("POST", "/api/users", Some(&writer), Some(json!({ "username": "carol" })))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    assert_eq!(call(&app, request("GET", "/api/users/carol", Some(&reader), None)).await.0, StatusCode::OK);
+
+    // Владелец — администратор, но ключ только для чтения
+    let writes = [
+        request("POST", "/api/users", Some(&reader), Some(json!({ "username": "dave" }))),
+        request("PUT", "/api/users/carol", Some(&reader), Some(json!({ "display_name": "Carol" }))),
+        request("DELETE", "/api/users/carol", Some(&reader), None),
+        request("POST", "/api/groups", Some(&reader), Some(json!({ "name": "Readers" }))),
+        request("POST", "/api/ous", Some(&reader), Some(json!({ "name": "Sales" }))),
+    ];
+    for request in writes {
+        let target = format!("{} {}", request.method(), request.uri());
+        let (status, body) = call(&app, request).await;
+        assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")), "{}", target);
+    }
+    assert!(directory.service.find_user_by_username("carol").await.unwrap().is_some());
+    assert!(directory.service.find_user_by_username("dave").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_expired_and_deleted_keys() {
+    let directory = TestDirectory::new().await;
+    let (_, manager) = directory.api_key("alice", true, &[scope::APIKEYS_MANAGE, scope::DIRECTORY_READ]).await;
+    let app = directory.router("");
+    let create = |body| request("POST", "/api/apikeys", Some(&manager), Some(body));
+
+    // Ключ не выдаёт областей, которых нет у него самого, и не создаётся просроченным
+    let (status, body) = call(&app, create(json!({ "name": "audit", "scopes": [scope::AUDIT_READ] }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let past = chrono::Utc::now() - chrono::Duration::minutes(1);
+    assert_eq!(call(&app, create(json!({ "name": "old", "scopes": [scope::DIRECTORY_READ], "expires_at": past }))).await.0, StatusCode::BAD_REQUEST);
+
+    let soon = chrono::Utc::now() + chrono::Duration::milliseconds(500);
+    let (status, expiring) = call(&app, create(json!({ "name": "expiring", "scopes": [scope::DIRECTORY_READ], "expires_at": soon }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", expiring);
+    let (status, lasting) = call(&app, create(json!({ "name": "lasting", "scopes": [scope::DIRECTORY_READ] }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", lasting);
+    let expiring_key = expiring["api_key"].as_str().unwrap();
+    let lasting_key = lasting["api_key"].as_str().unwrap();
+    assert_eq!(call(&app, request("GET", "/api/users", Some(expiring_key), None)).await.0, StatusCode::OK);
+    assert_eq!(call(&app, request("GET", "/api/users", Some(lasting_key), None)).await.0, StatusCode::OK);
+
+    // Секрет в ответе на список не возвращается, подобранный секрет не подходит
+    let (_, keys) = call(&app, request("GET", "/api/apikeys", Some(&manager), None)).await;
+    assert_eq!(keys.as_array().unwrap().len(), 3);
+    assert!(keys.as_array().unwrap().iter().all(|key| key.get("api_key").is_none() && key.get("secret_hash").is_none()));
+    let last = if lasting_key.ends_with('0') { '1' } else { '0' };
+    let forged = format!("{}{}", &lasting_key[..lasting_key.len() - 1], last);
+    let (status, body) = call(&app, request("GET", "/api/users", Some(&forged), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::UNAUTHORIZED, Some("INVALID_API_KEY")));
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    let (status, body) = call(&app, request("GET", "/api/users", Some(expiring_key), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::UNAUTHORIZED, Some("INVALID_API_KEY")));
+
+    let uri = format!("/api/apikeys/{}", lasting["id"].as_str().unwrap());
+    assert_eq!(call(&app, request("DELETE", &uri, Some(&manager), None)).await.0, StatusCode::NO_CONTENT);
+    let (status, body) = call(&app, request("GET", "/api/users", Some(lasting_key), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::UNAUTHORIZED, Some("INVALID_API_KEY")));
+    assert_eq!(call(&app, request("DELETE", &uri, Some(&manager), None)).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_unknown_scopes_and_jwt_only_routes() {
+    let directory = TestDirectory::new().await;
+    let (admin, everything) = directory.api_key("alice", true, scope::ALL).await;
+    let app = directory.router("");
+
+    // Даже администратор по JWT не создаёт ключ с неизвестной областью
+    for scopes in [json!(["directory:everything"]), json!([scope::DIRECTORY_READ, "admin"])] {
+        let mut create = request("POST", "/api/apikeys", None, Some(json!({ "name": "bad", "scopes": scopes })));
+        create.headers_mut().insert("Authorization", format!("Bearer {}", jwt(&admin)).parse().unwrap());
+        let (status, body) = call(&app, create).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert!(body["detail"].as_str().unwrap().starts_with("Unknown scope"), "{}", body);
+    }
+    assert_eq!(directory.service.get_all_api_keys().await.unwrap().len(), 1);
+
+    // Ключ со всеми областями не проходит туда, где нужен вход администратора
+    let uri = format!("/api/approvals/{}/approve", uuid::Uuid::new_v4());
+    let (status, body) = call(&app, request("POST", &uri, Some(&everything), Some(json!({})))).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("ACCESS_DENIED")));
+}
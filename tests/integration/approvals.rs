@@ -0,0 +1,173 @@
+// tests/integration/approvals.rs
+
+use axum::http::StatusCode;
+use serde_json::json;
+use nextDomen::config::ApprovalConfig;
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::error_code::ErrorCode;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::{ApprovalOperation, ApprovalStatus, Group, GroupScope, GroupTypeFlags, OuContents, SensitiveOperation};
+
+use super::{call, jwt, request, TestDirectory};
+
+const USERS_LDIF: &str = "\
+dn: CN=alice,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: alice
+
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+
+dn: CN=carol,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: carol
+
+dn: CN=dave,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: dave
+";
+
+#[tokio::test]
+async fn test_four_eyes_approvals() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(USERS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let user = |name: &'static str| async move { service.find_user_by_username(name).await.unwrap().unwrap() };
+    let (alice, bob, carol, dave) = (user("alice").await, user("bob").await, user("carol").await, user("dave").await);
+
+    let admins = Group::new("Domain Admins".into(), "Domain Admins".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&admins).await.unwrap();
+    let staff = Group::new("Staff".into(), "Staff".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&staff).await.unwrap();
+
+    // Без настройки операция выполняется сразу
+    let add_staff = SensitiveOperation::AddGroupMember { group_id: staff.id, member_id: carol.id, ttl_secs: None };
+    assert!(service.submit_operation(add_staff, alice.id).await.unwrap().is_none());
+    assert!(service.get_group(staff.id).await.unwrap().unwrap().members.contains(&carol.id));
+
+    service.set_approvals(&ApprovalConfig {
+        enabled: true,
+        operations: vec![ApprovalOperation::DeleteUser, ApprovalOperation::AddPrivilegedMember],
+    });
+
+    // Удаление ждёт второго администратора; создавший заявку решить её не может
+    let delete_carol = SensitiveOperation::DeleteUser { user_id: carol.id };
+    let request = service.submit_operation(delete_carol.clone(), alice.id).await.unwrap().unwrap();
+    assert_eq!(request.summary, "delete user carol");
+    assert!(service.get_user(carol.id).await.unwrap().is_some());
+    assert!(matches!(
+        service.submit_operation(delete_carol, bob.id).await,
+        Err(DirectoryError::AlreadyExists(ErrorCode::RequestAlreadyPending, _))
+    ));
+    assert!(matches!(
+        service.decide_approval(request.id, true, alice.id, None).await,
+        Err(DirectoryError::AccessDenied(_))
+    ));
+    let request = service.decide_approval(request.id, true, bob.id, Some("offboarding".into())).await.unwrap();
+    assert_eq!(request.status, ApprovalStatus::Approved);
+    assert_eq!(request.decided_by, Some(bob.id));
+    assert!(service.get_user(carol.id).await.unwrap().is_none());
+    assert!(matches!(
+        service.decide_approval(request.id, false, bob.id, None).await,
+        Err(DirectoryError::InvalidInput(_))
+    ));
+
+    // Членство в обычной группе не требует одобрения, в административной — требует
+    let add_staff = SensitiveOperation::AddGroupMember { group_id: staff.id, member_id: dave.id, ttl_secs: None };
+    assert!(service.submit_operation(add_staff, alice.id).await.unwrap().is_none());
+    let add_admin = SensitiveOperation::AddGroupMember { group_id: admins.id, member_id: dave.id, ttl_secs: Some(3600) };
+    let request = service.submit_operation(add_admin, alice.id).await.unwrap().unwrap();
+    assert_eq!(request.summary, "add dave to Domain Admins for 1h");
+    let request = service.decide_approval(request.id, false, bob.id, None).await.unwrap();
+    assert_eq!(request.status, ApprovalStatus::Rejected);
+    assert!(!service.get_group(admins.id).await.unwrap().unwrap().members.contains(&dave.id));
+
+    // Объект исчез до одобрения: заявка закрывается как failed с причиной
+    let request = service.submit_operation(SensitiveOperation::DeleteUser { user_id: dave.id }, alice.id).await.unwrap().unwrap();
+    service.delete_user(dave.id).await.unwrap();
+    let request = service.decide_approval(request.id, true, bob.id, None).await.unwrap();
+    assert_eq!(request.status, ApprovalStatus::Failed);
+    assert!(request.error.is_some());
+
+    assert_eq!(service.list_approvals(None).await.unwrap().len(), 3);
+    assert!(service.list_approvals(Some(ApprovalStatus::Pending)).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_approvals_not_bypassed() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let ldif = "dn: OU=Leavers,DC=x,DC=com\nobjectClass: organizationalUnit\nou: Leavers\n\n\
+                dn: CN=erin,OU=Leavers,DC=x,DC=com\nobjectClass: user\nsAMAccountName: erin\n";
+    let report = service.import_ldif(&format!("{}\n{}", USERS_LDIF, ldif), DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let alice = service.find_user_by_username("alice").await.unwrap().unwrap();
+    let erin = service.find_user_by_username("erin").await.unwrap().unwrap();
+    let leavers = service.find_ou_by_dn("OU=Leavers,DC=x,DC=com").await.unwrap().unwrap();
+    let admins = Group::new("Domain Admins".into(), "Domain Admins".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&admins).await.unwrap();
+
+    service.set_approvals(&ApprovalConfig {
+        enabled: true,
+        operations: vec![ApprovalOperation::DeleteUser, ApprovalOperation::AddPrivilegedMember],
+    });
+
+    // Удаление OU вместе с пользователями не обходит заявку на удаление пользователя
+    assert!(matches!(
+        service.delete_ou_tree(leavers.id, false, OuContents::Delete).await,
+        Err(DirectoryError::AccessDenied(_))
+    ));
+    assert!(service.get_user(erin.id).await.unwrap().is_some());
+    assert!(service.get_ou(leavers.id).await.unwrap().is_some());
+
+    // Одобрение вступления в административную группу не обходит заявку на добавление участника
+    let request = service.request_group_membership(admins.id, erin.id, None).await.unwrap();
+    assert!(matches!(
+        service.decide_group_join_request(request.id, true, Some(alice.id), None).await,
+        Err(DirectoryError::AccessDenied(_))
+    ));
+    assert!(!service.get_group(admins.id).await.unwrap().unwrap().members.contains(&erin.id));
+    assert!(service.get_group_join_request(request.id).await.unwrap().unwrap().is_pending());
+    service.decide_group_join_request(request.id, false, Some(alice.id), None).await.unwrap();
+
+    // Без настройки оба пути работают как прежде
+    service.set_approvals(&ApprovalConfig::default());
+    let request = service.request_group_membership(admins.id, erin.id, None).await.unwrap();
+    service.decide_group_join_request(request.id, true, Some(alice.id), None).await.unwrap();
+    assert!(service.get_group(admins.id).await.unwrap().unwrap().members.contains(&erin.id));
+    assert_eq!(service.delete_ou_tree(leavers.id, false, OuContents::Delete).await.unwrap(), 1);
+    assert!(service.get_user(erin.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_approvals_over_rest() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let (alice, _) = directory.api_key("alice", true, &[scope::DIRECTORY_READ]).await;
+    let (bob, reader) = directory.api_key("bob", true, &[scope::APPROVALS_READ]).await;
+    let (_, writer) = directory.api_key("carol", true, &[scope::DIRECTORY_WRITE]).await;
+    let (dave, _) = directory.api_key("dave", false, &[scope::SELF_SERVICE]).await;
+    service.set_approvals(&ApprovalConfig { enabled: true, operations: vec![ApprovalOperation::DeleteUser] });
+    let pending = service.submit_operation(SensitiveOperation::DeleteUser { user_id: dave.id }, alice.id).await.unwrap().unwrap();
+    let app = directory.router("");
+
+    let (status, body) = call(&app, request("GET", "/api/approvals", Some(&writer), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    let (status, listed) = call(&app, request("GET", "/api/approvals?status=pending", Some(&reader), None)).await;
+    assert_eq!((status, listed.as_array().map(Vec::len)), (StatusCode::OK, Some(1)));
+
+    // Ключ с областью заявку не решает, даже если владелец — другой администратор
+    let approve = format!("/api/approvals/{}/approve", pending.id);
+    let (status, body) = call(&app, request("POST", &approve, Some(&reader), Some(json!({})))).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("ACCESS_DENIED")), "{}", body);
+    assert!(service.get_user(dave.id).await.unwrap().is_some());
+
+    let mut by_jwt = request("POST", &approve, None, Some(json!({})));
+    by_jwt.headers_mut().insert("Authorization", format!("Bearer {}", jwt(&bob)).parse().unwrap());
+    let (status, decided) = call(&app, by_jwt).await;
+    assert_eq!((status, decided["status"].as_str()), (StatusCode::OK, Some("approved")), "{}", decided);
+    assert!(service.get_user(dave.id).await.unwrap().is_none());
+}
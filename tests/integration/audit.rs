@@ -0,0 +1,359 @@
+// tests/integration/audit.rs
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use tower::ServiceExt;
+
+use nextDomen::audit::actor::ActorContext;
+use nextDomen::audit::chain::{AuditAnchor, AuditChainHead, GENESIS_HASH};
+use nextDomen::audit::query::AuditQuery;
+use nextDomen::audit::{self, AuditSink, SyslogSink};
+use nextDomen::config::{AuditChainConfig, AuditConfig, SyslogConfig};
+use nextDomen::directory_service::DirectoryService;
+use nextDomen::events::{AuditEvent, AuditResult};
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::LoginProtocol;
+use nextDomen::raddb::RadDB;
+use nextDomen::web::access_log::{access_log, REQUEST_ID_HEADER};
+
+use super::{call, request, TestDirectory};
+
+const USERS_LDIF: &str = "\
+dn: CN=alice,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: alice
+
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+";
+
+#[tokio::test]
+async fn test_audit_actor() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(USERS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let alice = service.find_user_by_username("alice").await.unwrap().unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+
+    // Автор задаётся на время запроса, вошедший дописывается после проверки токена
+    let actor = ActorContext::new(LoginProtocol::Grpc, Some("10.0.0.9".to_string()));
+    actor.scope(async {
+        service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+        ActorContext::set_current_user(Some(&alice));
+        service.set_password(bob.id, "Correct-Horse-Battery-10").await.unwrap();
+    }).await;
+    assert!(ActorContext::current().is_none());
+    service.set_password(bob.id, "Correct-Horse-Battery-11").await.unwrap();
+
+    let query = AuditQuery { target: Some(bob.id), action: Some("set_password".to_string()), ..Default::default() };
+    let mut events = service.search_audit(&query).await.unwrap().events;
+    events.sort_by_key(|event| event.timestamp);
+    assert_eq!(events.len(), 3);
+
+    let anonymous = &events[0];
+    assert_eq!(anonymous.actor_id, None);
+    assert_eq!(anonymous.ip_addr.as_deref(), Some("10.0.0.9"));
+    assert_eq!(anonymous.metadata["protocol"], "grpc");
+
+    let by_alice = &events[1];
+    assert_eq!(by_alice.actor_id, Some(alice.id));
+    assert_eq!(by_alice.target_id, Some(bob.id));
+    assert_eq!(by_alice.metadata["actor"], "alice");
+
+    // Вне запроса, например из CLI, автора нет
+    assert_eq!(events[2].actor_id, None);
+    assert!(!events[2].metadata.contains_key("protocol"));
+
+    let query = AuditQuery { actor: Some(alice.id), ..Default::default() };
+    let by_alice = service.search_audit(&query).await.unwrap().events;
+    assert!(by_alice.iter().all(|event| event.target_id == Some(bob.id)), "{:?}", by_alice);
+}
+
+#[tokio::test]
+async fn test_request_id() {
+    let directory = TestDirectory::new().await;
+    let service = Arc::clone(&directory.service);
+    service.import_ldif(USERS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let app = Router::new()
+        .route("/password", post(move |password: String| async move {
+            service.set_password(bob.id, &password).await.unwrap();
+        }))
+        .layer(axum::middleware::from_fn(nextDomen::middleware::actor_context))
+        .layer(axum::middleware::from_fn(access_log));
+
+    // Идентификатор клиента возвращается в ответе и попадает в аудит; непригодный заменяется новым
+    let mut returned = Vec::new();
+    for (n, sent) in [Some("req-42"), Some("two words"), None].into_iter().enumerate() {
+        let mut request = Request::post("/password");
+        if let Some(id) = sent {
+            request = request.header(REQUEST_ID_HEADER, id);
+        }
+        let response = app.clone().oneshot(request.body(Body::from(format!("Correct-Horse-Battery-{}", n))).unwrap()).await.unwrap();
+        returned.push(response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string());
+    }
+    assert_eq!(returned[0], "req-42");
+    assert!(uuid::Uuid::parse_str(&returned[1]).is_ok(), "{}", returned[1]);
+    assert_ne!(returned[1], returned[2]);
+
+    let query = AuditQuery { target: Some(bob.id), action: Some("set_password".to_string()), ..Default::default() };
+    let mut logged: Vec<String> = directory.service.search_audit(&query).await.unwrap().events.into_iter()
+        .map(|event| event.metadata["http_request_id"].clone())
+        .collect();
+    logged.sort();
+    returned.sort();
+    assert_eq!(logged, returned);
+}
+
+fn audit_config(yaml: &str) -> AuditConfig {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+#[tokio::test]
+async fn test_file_sink_receives_mutations_and_logins() {
+    let directory = TestDirectory::new().await;
+    let service = Arc::clone(&directory.service);
+    let path = std::env::temp_dir().join(format!("nextdomen-audit-{}.jsonl", uuid::Uuid::new_v4()));
+    let sink = audit::build_sink(&audit_config(&format!("backend: file\nfile_path: {}\n", path.display()))).unwrap();
+    let writer = audit::spawn_writer(service.events().subscribe(), sink);
+
+    service.import_ldif(USERS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let alice = service.find_user_by_username("alice").await.unwrap().unwrap();
+    ActorContext::new(LoginProtocol::Rest, Some("10.0.0.9".to_string())).scope(async {
+        ActorContext::set_current_user(Some(&alice));
+        service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+    }).await;
+    assert!(service.authenticate_from("bob", "wrong", Some("198.51.100.4".to_string()), LoginProtocol::Rest).await.is_err());
+    service.authenticate_from("bob", "Correct-Horse-Battery-9", Some("198.51.100.4".to_string()), LoginProtocol::Ldap).await.unwrap();
+
+    // Поток записи завершается, когда сервис каталога удалён и канал закрыт
+    drop(service);
+    drop(directory);
+    writer.join().unwrap();
+    let events: Vec<AuditEvent> = std::fs::read_to_string(&path).unwrap().lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(events.iter().any(|event| event.action == "create_user" && event.target_id == Some(bob.id)), "{:?}", events);
+    let set_password = events.iter().find(|event| event.action == "set_password").unwrap();
+    assert_eq!(set_password.actor_id, Some(alice.id));
+    assert_eq!(set_password.target_id, Some(bob.id));
+    assert_eq!(set_password.ip_addr.as_deref(), Some("10.0.0.9"));
+    assert_eq!(set_password.result, AuditResult::Success);
+
+    let logins: Vec<_> = events.iter().filter(|event| event.action == "authenticate").collect();
+    assert_eq!(logins.len(), 2);
+    assert_eq!(logins[0].result, AuditResult::Failure);
+    assert_eq!(logins[0].actor_id, None);
+    assert_eq!(logins[0].ip_addr.as_deref(), Some("198.51.100.4"));
+    assert_eq!(logins[0].metadata["username"], "bob");
+    assert!(logins[0].metadata.contains_key("reason"));
+    assert_eq!(logins[1].result, AuditResult::Success);
+    assert_eq!((logins[1].actor_id, logins[1].target_id), (Some(bob.id), Some(bob.id)));
+    assert_eq!(logins[1].metadata["protocol"], "ldap");
+}
+
+#[test]
+fn test_syslog_sink_and_backend_selection() {
+    let collector = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    collector.set_read_timeout(Some(std::time::Duration::from_secs(5))).unwrap();
+    let config = SyslogConfig { address: Some(collector.local_addr().unwrap().to_string()), facility: None };
+    let mut sink = SyslogSink::connect(&config).unwrap();
+
+    // RFC 5424, facility 13 (log audit): неудача — warning (4), успех — informational (6)
+    let mut failed = AuditEvent::new("authenticate", AuditResult::Failure);
+    failed.ip_addr = Some("198.51.100.4".to_string());
+    sink.write(&failed).unwrap();
+    sink.write(&AuditEvent::new("create_user", AuditResult::Success)).unwrap();
+    let mut buffer = [0u8; 4096];
+    let mut receive = || {
+        let n = collector.recv(&mut buffer).unwrap();
+        String::from_utf8(buffer[..n].to_vec()).unwrap()
+    };
+    let message = receive();
+    assert!(message.starts_with("<108>1 "), "{}", message);
+    let (header, json) = message.split_once(" - ").unwrap();
+    assert!(header.contains(" nextdomen "), "{}", header);
+    assert!(header.ends_with(" authenticate"), "{}", header);
+    let event: AuditEvent = serde_json::from_str(json).unwrap();
+    assert_eq!((event.id, event.ip_addr.as_deref()), (failed.id, Some("198.51.100.4")));
+    assert!(receive().starts_with("<110>1 "));
+
+    assert!(audit::build_sink(&audit_config("backend: none\n")).is_ok());
+    let syslog = format!("backend: SYSLOG\nsyslog:\n  address: {}\n  facility: 4\n", collector.local_addr().unwrap());
+    let mut sink = audit::build_sink(&audit_config(&syslog)).unwrap();
+    sink.write(&AuditEvent::new("create_user", AuditResult::Success)).unwrap();
+    assert!(receive().starts_with("<38>1 "), "facility 4 (auth)");
+    assert!(audit::build_sink(&audit_config("backend: kafka\n")).is_err(), "KAFKA без security.audit.kafka");
+    assert!(audit::build_sink(&audit_config("backend: postgres\n")).is_err());
+}
+
+#[tokio::test]
+async fn test_audit_search_api() {
+    let directory = TestDirectory::new().await;
+    let (admin, auditor) = directory.api_key("admin", true, &[scope::AUDIT_READ, scope::DIRECTORY_WRITE]).await;
+    let (_, no_scope) = directory.api_key("operator", true, &[scope::DIRECTORY_WRITE]).await;
+    let (_, user) = directory.api_key("mallory", false, &[scope::AUDIT_READ]).await;
+    let app = directory.router("");
+
+    // Кто удалил пользователя: создание и удаление через REST от имени admin
+    for username in ["carol", "dave", "erin"] {
+        assert_eq!(call(&app, request("POST", "/api/users", Some(&auditor), Some(serde_json::json!({ "username": username })))).await.0, StatusCode::CREATED);
+    }
+    let carol = directory.service.find_user_by_username("carol").await.unwrap().unwrap();
+    assert_eq!(call(&app, request("DELETE", "/api/users/carol", Some(&auditor), None)).await.0, StatusCode::NO_CONTENT);
+
+    let search = |query: &str| request("GET", &format!("/api/audit?{}", query), Some(&auditor), None);
+    let (status, page) = call(&app, search(&format!("target={}&action=delete_user", carol.id))).await;
+    assert_eq!(status, StatusCode::OK, "{}", page);
+    assert_eq!(page["total"], 1);
+    assert_eq!(page["events"][0]["actor_id"], admin.id.to_string());
+    assert_eq!(page["events"][0]["ip_addr"], "127.0.0.1");
+
+    // Имя пользователя вместо UUID, префикс действия, события от новых к старым
+    let (_, page) = call(&app, search("actor=admin&action=create_*")).await;
+    assert_eq!(page["total"], 3);
+    let times: Vec<&str> = page["events"].as_array().unwrap().iter().map(|event| event["timestamp"].as_str().unwrap()).collect();
+    assert!(times.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", times);
+    let (_, second) = call(&app, search("actor=admin&action=create_*&per_page=2&page=2")).await;
+    assert_eq!((second["total"].as_u64(), second["page"].as_u64(), second["per_page"].as_u64()), (Some(3), Some(2), Some(2)));
+    assert_eq!(second["events"].as_array().unwrap().len(), 1);
+    assert_eq!(second["events"][0]["id"], page["events"][2]["id"]);
+
+    // Интервал: дата как верхняя граница включает весь день
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let tomorrow = (chrono::Utc::now() + chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+    assert_eq!(call(&app, search(&format!("action=delete_user&from={0}&to={0}", today))).await.1["total"], 1);
+    assert_eq!(call(&app, search(&format!("action=delete_user&from={}", tomorrow))).await.1["total"], 0);
+    assert_eq!(call(&app, search("action=delete_user&to=2000-01-01T00:00:00Z")).await.1["total"], 0);
+    assert_eq!(call(&app, search("action=create_user&result=failure")).await.1["total"], 0);
+
+    for (bad, expected) in [("from=yesterday", StatusCode::BAD_REQUEST), ("result=maybe", StatusCode::BAD_REQUEST), ("actor=nobody", StatusCode::NOT_FOUND)] {
+        let (status, body) = call(&app, search(bad)).await;
+        assert_eq!(status, expected, "{} {}", bad, body);
+    }
+    assert_eq!(call(&app, request("GET", "/api/audit", None, None)).await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(call(&app, request("GET", "/api/audit", Some(&no_scope), None)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, request("GET", "/api/audit", Some(&user), None)).await.0, StatusCode::FORBIDDEN);
+}
+
+/// Копия базы `source` с изменениями `tamper`: каждая проверка портит свою копию
+fn tampered(source: &std::path::Path, key: &[u8; 32], name: &str, tamper: impl FnOnce(&RadDB)) -> String {
+    let path = source.with_file_name(name);
+    std::fs::copy(source, &path).unwrap();
+    let db = RadDB::open(&path, key).unwrap();
+    tamper(&db);
+    db.flush().unwrap();
+    path.display().to_string()
+}
+
+/// Нарушения цепочки в копии базы с внешним якорем `anchor`
+async fn verify_copy(path: &str, key: &[u8; 32], anchor: &str) -> Vec<String> {
+    DirectoryService::open(path, key).unwrap().verify_audit_chain(Some(anchor)).await.unwrap().problems
+}
+
+fn load<T: serde::de::DeserializeOwned>(db: &RadDB, key: &str) -> T {
+    bincode::deserialize(&db.get(key).unwrap()).unwrap()
+}
+
+fn save<T: serde::Serialize>(db: &RadDB, key: &str, value: &T) {
+    db.set(key.to_string(), bincode::serialize(value).unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_audit_chain_verify() {
+    super::jwt_keys();
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let db_path = dir.join("directory.db");
+    let key = RadDB::generate_key();
+    let chain = AuditChainConfig { enabled: true, anchor_every: 3 };
+
+    let service = DirectoryService::open(db_path.to_str().unwrap(), &key).unwrap().with_audit_chain(&chain);
+    let mut hub = service.events().subscribe();
+    for n in 0..7 {
+        let mut event = AuditEvent::new("test_action", AuditResult::Success);
+        event.metadata.insert("n".to_string(), n.to_string());
+        service.record(event).await.unwrap();
+    }
+    // Каждое событие — звено после предыдущего; копии якорей уходят подписчикам
+    let mut anchors = Vec::new();
+    let mut prev_hash = GENESIS_HASH.to_string();
+    while let Ok(event) = hub.try_recv() {
+        match event.action.as_str() {
+            "audit_anchor" => anchors.push(event.metadata["token"].clone()),
+            _ => {
+                let link = event.chain.clone().unwrap();
+                assert_eq!(link.prev_hash, prev_hash);
+                assert_eq!(link.hash, event.chain_hash(link.seq, &prev_hash));
+                prev_hash = link.hash;
+            }
+        }
+    }
+    assert_eq!(anchors.len(), 2);
+    assert_eq!(AuditAnchor::verify_token(&anchors[1]).unwrap().seq, 6);
+
+    let report = service.verify_audit_chain(Some(&anchors[1])).await.unwrap();
+    assert!(report.is_intact(), "{:?}", report.problems);
+    assert_eq!((report.head_seq, report.verified, report.anchors_verified), (7, 7, 3));
+    assert!(report.last_anchor.is_some());
+    let report = service.verify_audit_chain(Some("not-a-jwt")).await.unwrap();
+    assert!(report.problems.iter().any(|problem| problem.contains("invalid signature")), "{:?}", report.problems);
+    drop(hub);
+    drop(service);
+
+    let external = anchors[1].as_str();
+    let event_id = |db: &RadDB, seq: u64| -> uuid::Uuid { load(db, &format!("audit_chain:{}", seq)) };
+
+    // Изменённое событие
+    let path = tampered(&db_path, &key, "modified.db", |db| {
+        let id = event_id(db, 2);
+        let mut event: AuditEvent = load(db, &format!("audit:{}", id));
+        event.metadata.insert("n".to_string(), "42".to_string());
+        save(db, &format!("audit:{}", id), &event);
+    });
+    let problems = verify_copy(&path, &key, external).await;
+    assert!(problems.iter().any(|problem| problem.starts_with("#2:") && problem.contains("was modified")), "{:?}", problems);
+
+    // Удалённое событие
+    let path = tampered(&db_path, &key, "deleted.db", |db| {
+        let id = event_id(db, 4);
+        db.remove(&format!("audit:{}", id));
+    });
+    let problems = verify_copy(&path, &key, external).await;
+    assert!(problems.iter().any(|problem| problem.starts_with("#4:") && problem.contains("missing")), "{:?}", problems);
+
+    // Усечённая история: последние звенья и якорь убраны, голова переставлена — выдаёт внешний якорь
+    let path = tampered(&db_path, &key, "truncated.db", |db| {
+        for seq in 6..=7 {
+            let id = event_id(db, seq);
+            db.remove(&format!("audit:{}", id));
+            db.remove(&format!("audit_chain:{}", seq));
+        }
+        let last: AuditEvent = load(db, &format!("audit:{}", event_id(db, 5)));
+        save(db, "audit_chain_head", &AuditChainHead { seq: 5, hash: last.chain.unwrap().hash });
+        db.remove("audit_anchor:6");
+        save(db, "audit_anchors_index", &vec![3u64]);
+    });
+    let problems = verify_copy(&path, &key, external).await;
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert!(problems[0].contains("history truncated"), "{:?}", problems);
+
+    // Откат только головы: звенья после неё остались
+    let path = tampered(&db_path, &key, "rolled_back.db", |db| {
+        let last: AuditEvent = load(db, &format!("audit:{}", event_id(db, 5)));
+        save(db, "audit_chain_head", &AuditChainHead { seq: 5, hash: last.chain.unwrap().hash });
+    });
+    let problems = verify_copy(&path, &key, external).await;
+    assert!(problems.iter().any(|problem| problem.contains("rolled back")), "{:?}", problems);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,30 @@
+// tests/integration/bulk.rs
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use nextDomen::models::apikey::scope;
+use tower::ServiceExt;
+
+use super::{call, request, TestDirectory};
+
+#[tokio::test]
+async fn test_bulk_dropped_mid_chunk_keeps_flushing() {
+    let directory = TestDirectory::new().await;
+    let app = directory.router("");
+    let (_, key) = directory.api_key("admin", true, &[scope::DIRECTORY_WRITE]).await;
+
+    let operations: Vec<_> = (0..nextDomen::web::bulk::MAX_BULK_OPERATIONS)
+        .map(|n| serde_json::json!({"op": "create", "type": "contact", "data": {"name": format!("c{}", n), "email": format!("c{}@partner.com", n)}}))
+        .collect();
+    let mut pending = Box::pin(app.clone().oneshot(request("POST", "/api/bulk", Some(&key), Some(serde_json::json!({"operations": operations})))));
+
+    // Клиент отключился посреди пакета: обработчик уступил планировщику и брошен
+    assert!(tokio::time::timeout(Duration::ZERO, &mut pending).await.is_err());
+    drop(pending);
+
+    // Обычная запись после этого сразу попадает на диск
+    let (status, body) = call(&app, request("POST", "/api/contacts", Some(&key), Some(serde_json::json!({"name": "after", "email": "after@partner.com"})))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(directory.on_disk().contains_key(&format!("contact:{}", body["id"].as_str().unwrap())));
+}
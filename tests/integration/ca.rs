@@ -0,0 +1,115 @@
+// tests/integration/ca.rs
+
+use axum::http::StatusCode;
+use base64::Engine;
+use serde_json::json;
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::{CertificateKind, RevocationReason};
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::{FromDer, X509Certificate};
+use x509_parser::revocation_list::CertificateRevocationList;
+
+use super::{call, request, TestDirectory};
+
+const CA_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+
+dn: CN=WS01,CN=Computers,DC=x,DC=com
+objectClass: user
+sAMAccountName: WS01$
+";
+
+fn sans(certificate: &X509Certificate) -> Vec<String> {
+    certificate.subject_alternative_name().unwrap().unwrap().value.general_names.iter()
+        .map(|name| match name {
+            GeneralName::DNSName(dns) => dns.to_string(),
+            GeneralName::RFC822Name(email) => email.to_string(),
+            other => format!("{:?}", other),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_certificate_authority() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(CA_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let ws = service.find_user_by_username("WS01$").await.unwrap().unwrap();
+    let (csr, _key) = nextDomen::ca::generate_csr("ignored").unwrap();
+
+    assert!(matches!(service.issue_certificate(&bob, &csr).await, Err(DirectoryError::NotFound(_))));
+    service.init_ca(false).await.unwrap();
+    assert!(matches!(service.init_ca(false).await, Err(DirectoryError::AlreadyExists(..))));
+    let ca_der = nextDomen::ca::pem_to_der(&service.ca_certificate().await.unwrap()).unwrap();
+    let (_, ca) = X509Certificate::from_der(&ca_der).unwrap();
+
+    // Subject и SAN — из каталога, а не из CSR; подпись проверяется ключом CA
+    let issued = service.issue_certificate(&bob, &csr).await.unwrap();
+    assert_eq!(issued.kind, CertificateKind::User);
+    let der = nextDomen::ca::pem_to_der(&issued.pem).unwrap();
+    let (_, certificate) = X509Certificate::from_der(&der).unwrap();
+    assert_eq!(certificate.subject().to_string(), "CN=bob");
+    assert_eq!(certificate.issuer(), ca.subject());
+    assert_eq!(sans(&certificate), vec![bob.user_principal_name.clone()]);
+    certificate.verify_signature(Some(ca.public_key())).unwrap();
+    assert_eq!(hex::encode(certificate.raw_serial()), issued.serial);
+
+    // Компьютер — только сам за себя и только с SPN HOST/...
+    assert!(matches!(service.enroll_computer_certificate(&bob, &csr).await, Err(DirectoryError::AccessDenied(_))));
+    assert!(matches!(service.enroll_computer_certificate(&ws, &csr).await, Err(DirectoryError::InvalidInput(_))));
+    service.register_spn(ws.id, "HOST/ws01.x.com").await.unwrap();
+    let ws = service.get_user(ws.id).await.unwrap().unwrap();
+    let enrolled = service.enroll_computer_certificate(&ws, &csr).await.unwrap();
+    assert_eq!(enrolled.sans, vec!["ws01.x.com".to_string()]);
+    let der = nextDomen::ca::pem_to_der(&enrolled.pem).unwrap();
+    let (_, certificate) = X509Certificate::from_der(&der).unwrap();
+    assert_eq!(sans(&certificate), vec!["ws01.x.com".to_string()]);
+
+    assert!(matches!(service.issue_certificate(&bob, b"not a csr").await, Err(DirectoryError::InvalidInput(_))));
+    assert_eq!(service.list_certificates(Some(bob.id)).await.unwrap().len(), 1);
+    assert_eq!(service.list_certificates(None).await.unwrap().len(), 2);
+
+    // Отозванный сертификат — в CRL, номер CRL растёт
+    service.revoke_certificate(&issued.serial, RevocationReason::KeyCompromise).await.unwrap();
+    assert!(matches!(service.revoke_certificate(&issued.serial, RevocationReason::Unspecified).await, Err(DirectoryError::InvalidInput(_))));
+    service.certificate_revocation_list().await.unwrap();
+    let crl_der = service.certificate_revocation_list().await.unwrap();
+    let (_, crl) = CertificateRevocationList::from_der(&crl_der).unwrap();
+    crl.verify_signature(ca.public_key()).unwrap();
+    assert_eq!(crl.crl_number().map(|n| n.to_string()), Some("2".to_string()));
+    let revoked: Vec<String> = crl.iter_revoked_certificates().map(|cert| hex::encode(cert.raw_serial())).collect();
+    assert_eq!(revoked, vec![issued.serial.clone()]);
+    assert!(!service.get_certificate(&issued.serial).await.unwrap().unwrap().is_valid(chrono::Utc::now()));
+}
+
+#[tokio::test]
+async fn test_certificate_routes_require_issue_scope() {
+    let directory = TestDirectory::new().await;
+    directory.service.init_ca(false).await.unwrap();
+    let (_, writer) = directory.api_key("alice", true, &[scope::DIRECTORY_WRITE]).await;
+    let (_, issuer) = directory.api_key("bob", true, &[scope::CA_ISSUE]).await;
+    let app = directory.router("");
+    let (csr, _key) = nextDomen::ca::generate_csr("ignored").unwrap();
+    let csr = format!("-----BEGIN CERTIFICATE REQUEST-----\n{}\n-----END CERTIFICATE REQUEST-----\n", base64::engine::general_purpose::STANDARD.encode(csr));
+    let sign = |key| request("POST", "/api/ca/sign", Some(key), Some(json!({ "csr": csr })));
+
+    // Ключ администратора без `ca:issue` не выпускает, не перечисляет и не отзывает сертификаты
+    let (status, body) = call(&app, sign(&writer)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    let (status, body) = call(&app, request("GET", "/api/ca/certificates", Some(&writer), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+
+    let (status, issued) = call(&app, sign(&issuer)).await;
+    assert_eq!(status, StatusCode::OK, "{}", issued);
+    let revoke = format!("/api/ca/certificates/{}/revoke", issued["serial"].as_str().unwrap());
+    let (status, body) = call(&app, request("POST", &revoke, Some(&writer), Some(json!({})))).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    let (status, listed) = call(&app, request("GET", "/api/ca/certificates", Some(&issuer), None)).await;
+    assert_eq!((status, listed.as_array().map(Vec::len)), (StatusCode::OK, Some(1)));
+    assert_eq!(call(&app, request("POST", &revoke, Some(&issuer), Some(json!({})))).await.0, StatusCode::OK);
+}
@@ -0,0 +1,56 @@
+// tests/integration/cache.rs
+
+use nextDomen::cache::ObjectCache;
+use nextDomen::ldif::DuplicatePolicy;
+
+use super::TestDirectory;
+
+const BOB_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+";
+
+#[test]
+fn test_object_cache_eviction() {
+    let cache = ObjectCache::new(2);
+    cache.insert("user:a", &1u32);
+    cache.insert("user:b", &2u32);
+    // Обращение к `a` делает давно не использованным `b`
+    assert_eq!(cache.get::<u32>("user:a"), Some(1));
+    cache.insert("group:c", &3u32);
+    assert_eq!(cache.get::<u32>("user:b"), None);
+    assert_eq!(cache.get::<u32>("user:a"), Some(1));
+    assert_eq!(cache.get::<u32>("group:c"), Some(3));
+
+    // Ключи прочих объектов не кэшируются
+    cache.insert("gpo:d", &4u32);
+    assert_eq!(cache.get::<u32>("gpo:d"), None);
+
+    cache.invalidate("user:a");
+    let stats = cache.stats();
+    assert_eq!((stats.entries, stats.hits, stats.misses, stats.evictions), (1, 3, 2, 1));
+}
+
+#[tokio::test]
+async fn test_directory_cache_invalidation() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+
+    let before = service.cache_stats();
+    service.get_user(bob.id).await.unwrap().unwrap();
+    assert_eq!(service.cache_stats().hits, before.hits + 1);
+
+    // Запись сбрасывает объект: следующее чтение видит новые данные
+    service.rename_user(bob.id, None, Some("Bob Builder".to_string())).await.unwrap();
+    let cached = service.get_user(bob.id).await.unwrap().unwrap();
+    assert_eq!(cached.display_name.as_deref(), Some("Bob Builder"));
+
+    service.set_user_enabled(bob.id, false).await.unwrap();
+    assert!(!service.get_user(bob.id).await.unwrap().unwrap().enabled);
+
+    service.delete_user(bob.id).await.unwrap();
+    assert!(service.get_user(bob.id).await.unwrap().is_none());
+}
@@ -0,0 +1,97 @@
+// tests/integration/changes.rs
+
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::{AccessMask, Ace, AceFlags, ChangeType, DirSyncCookie, SecuredObject, SecurityDescriptor};
+
+use super::TestDirectory;
+
+const BOB_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+";
+
+#[tokio::test]
+async fn test_change_log() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let start = service.highest_usn().await.unwrap();
+
+    let report = service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+
+    let changes = service.get_changes(start, 1000).await.unwrap();
+    let usns: Vec<u64> = changes.iter().map(|change| change.usn).collect();
+    assert_eq!(usns, (start + 1..=service.highest_usn().await.unwrap()).collect::<Vec<_>>());
+    let bob_changes: Vec<ChangeType> = changes.iter()
+        .filter(|change| change.object_id == bob.id)
+        .map(|change| change.change_type)
+        .collect();
+    assert_eq!(bob_changes.first(), Some(&ChangeType::Add));
+    assert!(bob_changes[1..].iter().all(|change_type| *change_type == ChangeType::Modify));
+    assert!(changes.iter().all(|change| change.object_id != bob.id || change.object_type == "user"));
+
+    // Вход, как lastLogon в AD, USN не меняет
+    let before_login = service.highest_usn().await.unwrap();
+    service.authenticate("bob", "Correct-Horse-Battery-9").await.unwrap();
+    assert!(service.authenticate("bob", "wrong").await.is_err());
+    assert_eq!(service.highest_usn().await.unwrap(), before_login);
+
+    service.delete_user(bob.id).await.unwrap();
+    let changes = service.get_changes(before_login, 1000).await.unwrap();
+    let deleted = changes.iter().find(|change| change.object_id == bob.id).unwrap();
+    assert_eq!(deleted.change_type, ChangeType::Delete);
+
+    // Курсор: страница не больше limit, следующая начинается после последнего USN
+    let page = service.get_changes(start, 2).await.unwrap();
+    assert_eq!(page.len(), 2);
+    assert_eq!(service.get_changes(page[1].usn, 1).await.unwrap()[0].usn, page[1].usn + 1);
+}
+
+const DIRSYNC_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+
+dn: CN=erin,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: erin
+";
+
+#[tokio::test]
+async fn test_dirsync() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(DIRSYNC_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let erin = service.find_user_by_username("erin").await.unwrap().unwrap();
+
+    // bob не может читать erin
+    let mut descriptor = service.get_security_descriptor(SecuredObject::User(erin.id)).await.unwrap()
+        .unwrap_or_else(|| SecurityDescriptor::new(bob.sid.clone()));
+    descriptor.dacl.insert(0, Ace::deny(bob.sid.clone(), AccessMask::READ_PROPERTY, AceFlags::empty()));
+    service.set_security_descriptor(SecuredObject::User(erin.id), &descriptor).await.unwrap();
+
+    assert!(DirSyncCookie::from_bytes(b"garbage").is_err());
+    let page = service.dirsync(&bob, DirSyncCookie::default(), 10_000).await.unwrap();
+    assert!(!page.more);
+    assert!(page.changes.iter().any(|change| change.object_id == bob.id));
+    assert!(page.changes.iter().all(|change| change.object_id != erin.id));
+    let ids: Vec<_> = page.changes.iter().map(|change| change.object_id).collect();
+    assert_eq!(ids.len(), ids.iter().collect::<std::collections::HashSet<_>>().len(), "one entry per object");
+
+    // Следующий cookie — только новые изменения; удаление видно без доступа к объекту
+    let cookie = DirSyncCookie::from_bytes(&page.cookie.to_bytes()).unwrap();
+    assert!(service.dirsync(&bob, cookie, 10_000).await.unwrap().changes.is_empty());
+    service.delete_user(erin.id).await.unwrap();
+    let page = service.dirsync(&bob, cookie, 10_000).await.unwrap();
+    assert!(page.changes.iter().any(|change| change.object_id == erin.id && change.change_type == ChangeType::Delete));
+
+    // Страница ограничена, остальное — по следующему cookie
+    let first = service.dirsync(&bob, DirSyncCookie::default(), 1).await.unwrap();
+    assert!(first.more);
+    assert_eq!(first.cookie.usn, 1);
+}
@@ -0,0 +1,165 @@
+// tests/integration/config.rs
+
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+
+use nextDomen::config::duration;
+use nextDomen::config::secrets::{SecretError, SecretProvider};
+use nextDomen::config::validate::Listener;
+use nextDomen::config::AppConfig;
+use nextDomen::init;
+
+fn load(yaml: &str, vars: &[(&str, &str)]) -> Result<AppConfig, String> {
+    let base = serde_yaml::from_str(&format!("db_path: x\nmaster_key_hex: {}\n{}", "ab".repeat(32), yaml)).unwrap();
+    let vars = vars.iter().map(|(name, value)| (name.to_string(), value.to_string()));
+    AppConfig::from_yaml_with_env(base, vars).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_config_durations() {
+    assert_eq!(duration::parse("30m"), Ok(Duration::from_secs(1800)));
+    assert_eq!(duration::parse("1h30m"), Ok(Duration::from_secs(5400)));
+    assert_eq!(duration::parse("7d"), Ok(Duration::from_secs(7 * 86_400)));
+    assert_eq!(duration::parse("45"), Ok(Duration::from_secs(45)));
+    for invalid in ["", "h", "5w", "1.5h", "-1h", "10 minutes"] {
+        assert!(duration::parse(invalid).is_err(), "{}", invalid);
+    }
+    assert_eq!(duration::format(Duration::from_secs(5400)), "1h30m");
+
+    let defaults = load("", &[]).unwrap();
+    assert_eq!(defaults.security.jwt.token_expiry.as_secs(), 24 * 3600);
+    assert_eq!(defaults.oidc.token_lifetime_secs, 3600);
+
+    let config = load(
+        "security:\n  jwt:\n    token_expiry: 8h\n  password_policy:\n    lockout_duration_minutes: 1h\n    max_age_days: 6w\n",
+        &[],
+    );
+    assert!(config.unwrap_err().contains("invalid duration '6w'"));
+
+    let config = load(
+        "oidc:\n  token_lifetime_secs: 15m\nsecurity:\n  jwt:\n    token_expiry: 8h\n  password_policy:\n    lockout_duration_minutes: 1h\n    max_age_days: 2160h\n",
+        &[("NEXTDOMEN_REPLICATION__PULL_INTERVAL_SECS", "2m")],
+    ).unwrap();
+    assert_eq!(config.oidc.token_lifetime_secs, 900);
+    assert_eq!(config.security.jwt.token_expiry.as_secs(), 8 * 3600);
+    assert_eq!(config.security.password_policy.lockout_duration_minutes, 60);
+    assert_eq!(config.security.password_policy.max_age_days, 90);
+    assert_eq!(config.replication.pull_interval_secs, 120);
+
+    // Числа — в единицах поля; дробная часть единицы — ошибка
+    let config = load("security:\n  password_policy:\n    lockout_duration_minutes: 30\n", &[]).unwrap();
+    assert_eq!(config.security.password_policy.lockout_duration_minutes, 30);
+    let error = load("security:\n  password_policy:\n    lockout_duration_minutes: 90s\n", &[]).unwrap_err();
+    assert!(error.contains("whole number of 1m"), "{}", error);
+}
+
+#[test]
+fn test_config_validation() {
+    let fields = |config: &AppConfig, listeners: &[Listener]| -> Vec<String> {
+        config.validate(listeners).into_iter().map(|issue| issue.field).collect()
+    };
+
+    let error = load("security:\n  jwt:\n    token_expiry: 8h\n    tokn_expiry: 1h\n", &[]).unwrap_err();
+    assert!(error.contains("unknown field `tokn_expiry`"), "{}", error);
+    // Токен удалённого CLI — не поле конфигурации
+    assert!(load("", &[("NEXTDOMEN_TOKEN", "abc")]).is_ok());
+    assert!(load("", &[("NEXTDOMEN_WEB_SERVER__ADDRES", "0.0.0.0:8080")]).is_err());
+
+    let config = load("", &[]).unwrap();
+    assert!(fields(&config, &[Listener::tcp("web --addr", "127.0.0.1:8080")]).is_empty());
+
+    let config = load("", &[("NEXTDOMEN_MASTER_KEY", "abcd")]).unwrap();
+    assert_eq!(fields(&config, &[]), ["master_key_hex"]);
+
+    let config = load("security:\n  auth_providers:\n    corp.acme.com:\n      type: upstream_ldap\n      url: dc01.corp.acme.com\n      bind_name: cn=bob\n", &[]).unwrap();
+    assert_eq!(fields(&config, &[]), ["security.auth_providers.corp.acme.com.url", "security.auth_providers.corp.acme.com.bind_name"]);
+
+    let config = load(
+        "grpc_server:\n  address: 0.0.0.0:8080\n  enable_tls: true\n  tls:\n    cert_file: /nonexistent/cert.pem\n    client_auth_required: true\n",
+        &[],
+    ).unwrap();
+    assert_eq!(
+        fields(&config, &[Listener::tcp("web --addr", "127.0.0.1:8080"), Listener::udp("dns --addr", "127.0.0.1:8080")]),
+        ["grpc_server.tls.client_auth_required", "grpc_server.address"],
+    );
+    // Несколько адресов на сервер: IPv4 и IPv6 на одном порту не конфликтуют, повтор — конфликт
+    let config = load("web_server:\n  address: [\"0.0.0.0:8080\", \"[::]:8080\"]\ngrpc_server:\n  address: 127.0.0.1:50051, [::1]:50051\n", &[]).unwrap();
+    assert_eq!(config.web_addresses(&[]), ["0.0.0.0:8080", "[::]:8080"]);
+    assert_eq!(config.web_addresses(&["[::1]:9000".to_string()]), ["[::1]:9000"]);
+    assert_eq!(config.grpc_server.address.to_vec(), ["127.0.0.1:50051", "[::1]:50051"]);
+    assert!(fields(&config, &config.web_listeners(&[])).is_empty());
+    let config = load("", &[("NEXTDOMEN_WEB_SERVER__ADDRESS", "[::]:8080,[::1]:8080")]).unwrap();
+    assert_eq!(fields(&config, &config.web_listeners(&[])), ["web_server.address"]);
+    assert_eq!(load("", &[]).unwrap().web_addresses(&[]), ["127.0.0.1:8080"]);
+
+    // Ни сертификата, ни ключа — их создаст первый запуск; один без другого — ошибка
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (cert_pem, _) = nextDomen::ca::self_signed(&["localhost".to_string()], chrono::Duration::days(1)).unwrap();
+    std::fs::write(dir.join("web_server.pem"), cert_pem).unwrap();
+    let tls = |certs_dir: &std::path::Path| load(&format!("paths:\n  certs_dir: {}\nweb_server:\n  enable_tls: true\n", certs_dir.display()), &[]).unwrap();
+    assert!(fields(&tls(&dir.join("empty")), &[]).is_empty());
+    assert_eq!(fields(&tls(&dir), &[]), ["web_server.tls.key_file"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Открытый ключ от другой пары
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+    let (private_key, public_key) = init::write_jwt_keys(&dir.join("a"), false).unwrap();
+    let (_, other_public_key) = init::write_jwt_keys(&dir.join("b"), false).unwrap();
+    let jwt = |public_key: &std::path::Path| load(
+        &format!("security:\n  jwt:\n    private_key_path: {}\n    public_key_path: {}\n", private_key.display(), public_key.display()),
+        &[],
+    ).unwrap();
+    assert!(fields(&jwt(&public_key), &[]).is_empty());
+    assert_eq!(fields(&jwt(&other_public_key), &[]), ["security.jwt.public_key_path"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Хранилище секретов в памяти вместо Vault / KMS
+struct StaticSecrets(&'static str, String);
+
+impl SecretProvider for StaticSecrets {
+    fn fetch<'a>(&'a self, reference: &'a str) -> BoxFuture<'a, Result<String, SecretError>> {
+        Box::pin(async move {
+            if reference == self.0 { Ok(self.1.clone()) } else { Err(SecretError::NotFound(reference.to_string())) }
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_config_secrets() {
+    let key = "cd".repeat(32);
+    let config = |yaml: &str, vars: &[(&str, &str)]| {
+        let base = serde_yaml::from_str(&format!("db_path: x\n{}", yaml)).unwrap();
+        let vars = vars.iter().map(|(name, value)| (name.to_string(), value.to_string()));
+        AppConfig::from_yaml_with_env(base, vars).map_err(|e| e.to_string())
+    };
+
+    let loaded = config(
+        "master_key_hex: ${MASTER}\nsecurity:\n  jwt:\n    secret_key: \"pre-${JWT:-fallback}-$${LITERAL}\"\n",
+        &[("MASTER", &key)],
+    ).unwrap();
+    assert_eq!(loaded.master_key().await.unwrap(), [0xcd; 32]);
+    assert_eq!(loaded.security.jwt.secret_key.as_deref(), Some("pre-fallback-${LITERAL}"));
+    let error = config("master_key_hex: ${MASTER}\n", &[]).unwrap_err();
+    assert!(error.contains("Environment variable MASTER is not set"), "{}", error);
+
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let key_file = dir.join("master.key");
+    std::fs::write(&key_file, format!("{}\n", key)).unwrap();
+    let loaded = config(&format!("master_key_file: {}\n", key_file.display()), &[]).unwrap();
+    assert!(loaded.validate(&[]).is_empty());
+    assert_eq!(loaded.master_key().await.unwrap(), [0xcd; 32]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let loaded = config("master_key_secret: nextdomen/prod#master_key\n", &[]).unwrap();
+    assert_eq!(loaded.validate(&[]).into_iter().map(|issue| issue.field).collect::<Vec<_>>(), ["master_key_secret"]);
+    assert!(matches!(loaded.master_key().await, Err(SecretError::NotConfigured(_))));
+    let store = StaticSecrets("nextdomen/prod#master_key", key.clone());
+    assert_eq!(loaded.master_key_with(Some(&store)).await.unwrap(), [0xcd; 32]);
+
+    let loaded = config(&format!("master_key_hex: {}\nmaster_key_secret: nextdomen/prod#master_key\n", key), &[]).unwrap();
+    assert!(matches!(loaded.master_key().await, Err(SecretError::NotConfigured(_))));
+}
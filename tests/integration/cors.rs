@@ -0,0 +1,69 @@
+// tests/integration/cors.rs
+
+use axum::http::{header, HeaderMap, HeaderValue};
+use nextDomen::config::CorsConfig;
+use nextDomen::web::cors::cors_layer;
+use tower::ServiceExt;
+
+use super::{request, TestDirectory};
+
+/// Заголовки ответа на `GET /health` (или preflight, если `preflight`) с источника `origin`
+async fn cors_headers(app: &axum::Router, origin: &str, preflight: bool) -> HeaderMap {
+    let mut request = request(if preflight { "OPTIONS" } else { "GET" }, "/health", None, None);
+    request.headers_mut().insert(header::ORIGIN, HeaderValue::from_str(origin).unwrap());
+    if preflight {
+        request.headers_mut().insert(header::ACCESS_CONTROL_REQUEST_METHOD, HeaderValue::from_static("POST"));
+        request.headers_mut().insert(header::ACCESS_CONTROL_REQUEST_HEADERS, HeaderValue::from_static("authorization"));
+    }
+    app.clone().oneshot(request).await.unwrap().headers().clone()
+}
+
+#[tokio::test]
+async fn test_same_origin_by_default() {
+    let directory = TestDirectory::new().await;
+    let app = directory.router("");
+
+    for preflight in [false, true] {
+        let headers = cors_headers(&app, "https://evil.example.com", preflight).await;
+        assert!(headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+        assert!(headers.get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS).is_none());
+    }
+}
+
+#[tokio::test]
+async fn test_allowed_origins() {
+    let directory = TestDirectory::new().await;
+    let app = directory.router("web_server:\n  cors:\n    allowed_origins: [https://admin.x.com/]\n    max_age_secs: 1m\n");
+
+    // Источник из списка (завершающий `/` в конфигурации не мешает) получает себя в ACAO
+    let headers = cors_headers(&app, "https://admin.x.com", false).await;
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://admin.x.com");
+    assert!(headers.get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS).is_none());
+
+    let headers = cors_headers(&app, "https://admin.x.com", true).await;
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://admin.x.com");
+    assert!(headers[header::ACCESS_CONTROL_ALLOW_METHODS].to_str().unwrap().contains("POST"));
+    assert!(headers[header::ACCESS_CONTROL_ALLOW_HEADERS].to_str().unwrap().contains("authorization"));
+    assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "60");
+
+    // Похожий, но другой источник — без заголовков CORS
+    for origin in ["https://admin.x.com.evil.com", "http://admin.x.com"] {
+        assert!(cors_headers(&app, origin, false).await.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    }
+}
+
+#[tokio::test]
+async fn test_credentials_need_explicit_origin() {
+    let directory = TestDirectory::new().await;
+    let app = directory.router("web_server:\n  cors:\n    allowed_origins: [https://admin.x.com]\n    allow_credentials: true\n");
+
+    // С учётными данными ACAO — конкретный источник, не `*`
+    let headers = cors_headers(&app, "https://admin.x.com", false).await;
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://admin.x.com");
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+
+    let wildcard = CorsConfig { allowed_origins: vec!["*".to_string()], allow_credentials: true, ..CorsConfig::default() };
+    assert!(cors_layer(&wildcard).unwrap_err().contains("cannot be combined with allow_credentials"));
+    let invalid = CorsConfig { allowed_origins: vec!["https://bad\norigin".to_string()], ..CorsConfig::default() };
+    assert!(cors_layer(&invalid).unwrap_err().contains("invalid origin"));
+}
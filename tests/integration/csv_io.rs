@@ -0,0 +1,158 @@
+// tests/integration/csv_io.rs
+
+use nextDomen::csv_io::{self, CsvColumnMapping, CsvImportOptions, CsvRowStatus};
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::LoginProtocol;
+
+use super::TestDirectory;
+
+const DIRECTORY_LDIF: &str = "\
+dn: OU=Sales,DC=x,DC=com
+objectClass: organizationalUnit
+ou: Sales
+
+dn: CN=Sales Team,OU=Sales,DC=x,DC=com
+objectClass: group
+sAMAccountName: Sales
+
+dn: CN=Managers,OU=Sales,DC=x,DC=com
+objectClass: group
+sAMAccountName: Managers
+";
+
+/// Выгрузка отдела кадров: свои названия столбцов, `;` между столбцами, `,` между группами
+const MAPPING_YAML: &str = "\
+username: Логин
+email: Почта
+display_name: ФИО
+password: Пароль
+enabled: Активен
+ou: Подразделение
+groups: Группы
+delimiter: ';'
+list_separator: ','
+";
+
+const HR_CSV: &str = "\
+Логин;Почта;ФИО;Пароль;Активен;Подразделение;Группы
+alice;alice@x.com;Алиса Петрова;Correct-Horse-Battery-1;да;OU=Sales,DC=x,DC=com;Sales, Managers
+bob;bob@x.com;Борис Иванов;;;;
+carol;not-an-email;Карина;;;;
+ALICE;alice2@x.com;Дубликат;;;;
+dave;dave@x.com;Денис;;;;Marketing
+erin;erin@x.com;Елена;;;OU=Missing,DC=x,DC=com;
+frank;frank@x.com;Фёдор;;может быть;;
+;nobody@x.com;Без логина;;;;
+";
+
+fn mapping() -> CsvColumnMapping {
+    let path = std::env::temp_dir().join(format!("nextdomen-mapping-{}.yaml", uuid::Uuid::new_v4()));
+    std::fs::write(&path, MAPPING_YAML).unwrap();
+    let mapping = CsvColumnMapping::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    mapping
+}
+
+fn failed(status: &CsvRowStatus) -> bool {
+    matches!(status, CsvRowStatus::Failed(_))
+}
+
+#[tokio::test]
+async fn test_csv_import() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(DIRECTORY_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let mapping = mapping();
+
+    // Пробный прогон проверяет строки, но ничего не пишет
+    let options = CsvImportOptions { dry_run: true, generate_passwords: true, update_existing: false };
+    let report = csv_io::import_users(service, HR_CSV, &mapping, options).await.unwrap();
+    assert!(report.dry_run);
+    assert_eq!(report.count(|status| *status == CsvRowStatus::Created), 2);
+    assert!(service.find_user_by_username("alice").await.unwrap().is_none());
+
+    let options = CsvImportOptions { dry_run: false, ..options };
+    let report = csv_io::import_users(service, HR_CSV, &mapping, options).await.unwrap();
+    let statuses: Vec<(u64, &str, &CsvRowStatus)> = report.rows.iter().map(|row| (row.line, row.username.as_str(), &row.status)).collect();
+    assert_eq!(statuses.len(), 8);
+    assert_eq!(statuses[..2], [(2, "alice", &CsvRowStatus::Created), (3, "bob", &CsvRowStatus::Created)]);
+    // Ошибка строки называет причину и не останавливает импорт
+    for (row, reason) in report.rows[2..].iter().zip(["email", "Duplicate", "Marketing", "OU=Missing", "enabled", "Username"]) {
+        match &row.status {
+            CsvRowStatus::Failed(message) => assert!(message.contains(reason), "line {}: {}", row.line, message),
+            status => panic!("line {}: {:?}", row.line, status),
+        }
+    }
+    assert_eq!(report.count(failed), 6);
+
+    let alice = service.find_user_by_username("alice").await.unwrap().unwrap();
+    assert_eq!(alice.email.as_deref(), Some("alice@x.com"));
+    assert_eq!(alice.display_name.as_deref(), Some("Алиса Петрова"));
+    assert_eq!(alice.user_principal_name, "alice@x.com");
+    assert!(alice.enabled);
+    let sales = service.find_ou_by_dn("OU=Sales,DC=x,DC=com").await.unwrap().unwrap();
+    assert_eq!(alice.organizational_unit, Some(sales.id));
+    let mut groups: Vec<String> = service.find_groups_by_member(alice.id).await.unwrap().into_iter().map(|group| group.sam_account_name).collect();
+    groups.sort();
+    assert_eq!(groups, ["Managers", "Sales"]);
+    service.authenticate_from("alice", "Correct-Horse-Battery-1", None, LoginProtocol::Other).await.unwrap();
+
+    // Пароль сгенерирован только тому, у кого его не было, и показан в отчёте
+    assert!(report.rows[0].generated_password.is_none());
+    let generated = report.rows[1].generated_password.clone().unwrap();
+    assert_eq!(generated.len(), 16);
+    assert!(generated.chars().any(|c| c.is_ascii_digit()) && generated.chars().any(|c| !c.is_ascii_alphanumeric()), "{}", generated);
+    service.authenticate_from("bob", &generated, None, LoginProtocol::Other).await.unwrap();
+    assert!(report.rows[2..].iter().all(|row| row.generated_password.is_none()));
+    assert!(service.find_user_by_username("carol").await.unwrap().is_none());
+
+    // Повторный импорт: без update_existing — ошибка, с ним — обновление
+    let update = "Логин;ФИО;Активен\nalice;Алиса Смирнова;нет\n";
+    let report = csv_io::import_users(service, update, &mapping, CsvImportOptions::default()).await.unwrap();
+    assert!(failed(&report.rows[0].status), "{:?}", report.rows[0]);
+    let options = CsvImportOptions { update_existing: true, ..Default::default() };
+    let report = csv_io::import_users(service, update, &mapping, options).await.unwrap();
+    assert_eq!(report.rows[0].status, CsvRowStatus::Updated);
+    let updated = service.find_user_by_username("alice").await.unwrap().unwrap();
+    assert_eq!((updated.id, updated.display_name.as_deref(), updated.enabled), (alice.id, Some("Алиса Смирнова"), false));
+    assert_eq!(updated.email.as_deref(), Some("alice@x.com"), "пустые столбцы не стирают значения");
+
+    // Без столбца имени файл не принимается целиком
+    assert!(csv_io::import_users(service, "Почта\nx@x.com\n", &mapping, CsvImportOptions::default()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_csv_export_round_trip() {
+    let source = TestDirectory::new().await;
+    source.service.import_ldif(DIRECTORY_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let mapping = mapping();
+    let options = CsvImportOptions { generate_passwords: true, ..Default::default() };
+    let report = csv_io::import_users(&source.service, &HR_CSV.lines().take(3).collect::<Vec<_>>().join("\n"), &mapping, options).await.unwrap();
+    assert_eq!(report.count(failed), 0, "{:?}", report.rows);
+
+    // Экспорт читается импортом с тем же соответствием столбцов
+    let users = csv_io::export_users(&source.service, &mapping).await.unwrap();
+    let mut lines = users.lines();
+    assert_eq!(lines.next(), Some("Логин;user_principal_name;Почта;ФИО;given_name;surname;Активен;Подразделение;Группы"));
+    let mut rows: Vec<&str> = lines.collect();
+    rows.sort();
+    assert_eq!(rows, [
+        "alice;alice@x.com;alice@x.com;Алиса Петрова;;;true;OU=Sales,DC=x,DC=com;Managers,Sales",
+        "bob;bob@x.com;bob@x.com;Борис Иванов;;;true;;",
+    ]);
+
+    let memberships = csv_io::export_group_memberships(&source.service, &mapping).await.unwrap();
+    let mut memberships: Vec<&str> = memberships.lines().collect();
+    assert_eq!(memberships.remove(0), "group;group_name;username");
+    memberships.sort();
+    assert_eq!(memberships, ["Managers;Managers;alice", "Sales;Sales Team;alice"]);
+
+    let target = TestDirectory::new().await;
+    target.service.import_ldif(DIRECTORY_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let report = csv_io::import_users(&target.service, &users, &mapping, CsvImportOptions::default()).await.unwrap();
+    assert_eq!(report.count(|status| *status == CsvRowStatus::Created), 2, "{:?}", report.rows);
+    assert_eq!(csv_io::export_users(&target.service, &mapping).await.unwrap().lines().count(), 3);
+    let mut exported: Vec<String> = csv_io::export_users(&target.service, &mapping).await.unwrap().lines().skip(1).map(str::to_string).collect();
+    exported.sort();
+    assert_eq!(exported, rows);
+}
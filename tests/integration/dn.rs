@@ -0,0 +1,71 @@
+// tests/integration/dn.rs
+
+use nextDomen::directory_service::DirectoryService;
+use nextDomen::dn::{self, Dn, Rdn};
+use nextDomen::models::OrganizationalUnit;
+
+use super::TestDirectory;
+
+#[test]
+fn test_parse_and_escape() {
+    let dn = Dn::parse(r#"CN=Smith\, John,OU=R\26D\2C Lab, OU="Sales, East" ,DC=x,DC=com"#).unwrap();
+    let values: Vec<&str> = dn.rdns().iter().map(Rdn::value).collect();
+    assert_eq!(values, ["Smith, John", "R&D, Lab", "Sales, East", "x", "com"]);
+    assert_eq!(dn.to_string(), r"CN=Smith\, John,OU=R&D\, Lab,OU=Sales\, East,DC=x,DC=com");
+    assert_eq!(dn.dns_name().as_deref(), Some("x.com"));
+
+    // Разбор обратим для любых значений
+    for value in ["#tag", " padded ", "a\\ ", "a+b=c;d<e>\"f\"\\g", "Отдел продаж"] {
+        let dn = Dn::from_dns_name("x.com").child(Rdn::ou(value));
+        assert_eq!(Dn::parse(&dn.to_string()).unwrap().rdn().unwrap().value(), value);
+    }
+    assert_eq!(dn::escape_value(" #a "), r"\ #a\ ");
+
+    for invalid in ["CN=a,", "noequals", "CN=a+SN=b,DC=x", r"CN=a\", "1a=b", r#"CN="open"#] {
+        assert!(Dn::parse(invalid).is_err(), "{}", invalid);
+    }
+    assert!(Dn::parse("").unwrap().is_empty());
+}
+
+#[test]
+fn test_compare_and_rebase() {
+    let a = Dn::parse("OU=Sales,DC=X,DC=Com").unwrap();
+    let b = Dn::parse("ou = sales , dc=x,dc=com").unwrap();
+    assert_eq!(a, b);
+    assert_eq!(a.canonical(), "ou=sales,dc=x,dc=com");
+    assert_eq!(dn::canonical(r"OU=Sales\2C East,DC=x"), dn::canonical(r#"ou="sales, east",dc=X"#));
+
+    let east = Dn::parse("OU=East,OU=Sales,DC=x,DC=com").unwrap();
+    assert!(east.is_descendant_of(&b));
+    assert!(!a.is_descendant_of(&b));
+    // Суффикс строки без границы RDN — не предок
+    assert!(!Dn::parse("OU=Pre,OU=XSales,DC=x,DC=com").unwrap().ends_with(&a));
+
+    let retail = Dn::parse("OU=Retail,DC=x,DC=com").unwrap();
+    assert_eq!(east.rebase(&a, &retail).unwrap().to_string(), "OU=East,OU=Retail,DC=x,DC=com");
+    assert_eq!(east.parent(), Some(a.clone()));
+    assert!(Dn::parse("DC=com").unwrap().parent().is_none());
+}
+
+#[tokio::test]
+async fn test_ou_with_special_characters() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+
+    let dn = DirectoryService::generate_ou_dn("R&D, Lab", Some("DC=x,DC=com")).unwrap();
+    assert_eq!(dn, r"OU=R&D\, Lab,DC=x,DC=com");
+    service.create_ou(&OrganizationalUnit::new("R&D, Lab".to_string(), dn, None)).await.unwrap();
+    let lab = service.find_ou_by_dn(r"ou=r&d\2c lab, dc=X,dc=COM").await.unwrap().unwrap();
+    assert_eq!(lab.name, "R&D, Lab");
+    assert!(DirectoryService::generate_ou_dn(" ", None).is_err());
+    assert!(DirectoryService::generate_ou_dn("Sales", Some("not a dn")).is_err());
+
+    // Переименование и перенос пересчитывают DN вложенных OU по RDN, а не по подстрокам
+    let team = DirectoryService::generate_ou_dn("Team", Some(&lab.dn)).unwrap();
+    service.create_ou(&OrganizationalUnit::new("Team".to_string(), team, Some(lab.id))).await.unwrap();
+    let lab = service.rename_ou(lab.id, "Lab; Main").await.unwrap();
+    assert_eq!(lab.dn, r"OU=Lab\; Main,DC=x,DC=com");
+    let team = service.find_ou_by_dn(r"OU=Team,OU=Lab\; Main,DC=x,DC=com").await.unwrap().unwrap();
+    assert!(service.move_ou(lab.id, &team.dn).await.is_err());
+    assert!(service.find_ou_by_dn(r"OU=Team,OU=R&D\, Lab,DC=x,DC=com").await.unwrap().is_none());
+}
@@ -0,0 +1,159 @@
+// tests/integration/dns.rs
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+
+use nextDomen::dns::wire::{self, Message, Question, ResourceRecord};
+use nextDomen::dns::DnsAuthority;
+use nextDomen::models::DnsRecordData;
+
+use super::TestDirectory;
+
+/// Запрос `dig _ldap._tcp.X.com SRV`: id 0x1234, RD
+const SRV_QUERY: &[u8] = &[
+    0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x05, b'_', b'l', b'd', b'a', b'p', 0x04, b'_', b't', b'c', b'p', 0x01, b'X', 0x03, b'c', b'o', b'm', 0x00,
+    0x00, 0x21, 0x00, 0x01,
+];
+
+#[test]
+fn test_parse_query() {
+    let query = Message::parse(SRV_QUERY).unwrap();
+    assert_eq!(query.id, 0x1234);
+    assert_eq!(query.opcode(), wire::OPCODE_QUERY);
+    assert!(!query.is_response());
+    assert_eq!(query.questions.len(), 1);
+    // Имена приводятся к нижнему регистру
+    assert_eq!(query.questions[0].name, "_ldap._tcp.x.com");
+    assert_eq!(query.questions[0].qtype, wire::TYPE_SRV);
+    assert_eq!(query.questions[0].qclass, wire::CLASS_IN);
+    assert!(query.answers.is_empty());
+}
+
+#[test]
+fn test_reply_encoding() {
+    let query = Message::parse(SRV_QUERY).unwrap();
+    let mut reply = query.reply();
+    reply.set_authoritative();
+    reply.set_rcode(wire::RCODE_NXDOMAIN);
+
+    let encoded = reply.encode();
+    // QR, AA, RD и NXDOMAIN; один вопрос, остальные секции пусты
+    assert_eq!(&encoded[..12], &[0x12, 0x34, 0x85, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(&encoded[12..], &SRV_QUERY[12..].to_ascii_lowercase()[..]);
+}
+
+#[test]
+fn test_rdata_encoding() {
+    assert_eq!(wire::encode_rdata(&DnsRecordData::A(Ipv4Addr::new(192, 0, 2, 10))), [192, 0, 2, 10]);
+    assert_eq!(
+        wire::encode_rdata(&DnsRecordData::Srv { priority: 0, weight: 100, port: 389, target: "dc1.x.com".into() }),
+        [&[0x00, 0x00, 0x00, 0x64, 0x01, 0x85][..], b"\x03dc1\x01x\x03com\x00"].concat(),
+    );
+    assert_eq!(wire::encode_rdata(&DnsRecordData::Ns("dc1.x.com.".into())), b"\x03dc1\x01x\x03com\x00");
+
+    let soa = wire::encode_rdata(&DnsRecordData::Soa {
+        mname: "dc1.x.com".into(),
+        rname: "hostmaster.x.com".into(),
+        serial: 2026101801,
+        refresh: 900,
+        retry: 600,
+        expire: 86400,
+        minimum: 3600,
+    });
+    assert_eq!(soa.len(), 11 + 18 + 20);
+    assert_eq!(&soa[29..33], &2026101801u32.to_be_bytes());
+    assert_eq!(&soa[45..], &3600u32.to_be_bytes());
+}
+
+#[test]
+fn test_message_round_trip() {
+    let aaaa = DnsRecordData::Aaaa("2001:db8::10".parse::<Ipv6Addr>().unwrap());
+    let message = Message {
+        id: 7,
+        flags: 0x8400,
+        questions: vec![Question { name: "dc1.x.com".into(), qtype: wire::TYPE_ANY, qclass: wire::CLASS_IN }],
+        answers: vec![
+            ResourceRecord::from_data("dc1.x.com", 600, &DnsRecordData::A(Ipv4Addr::new(10, 0, 0, 1))),
+            ResourceRecord::from_data("dc1.x.com", 600, &aaaa),
+        ],
+        authority: vec![ResourceRecord::from_data("x.com", 3600, &DnsRecordData::Ns("dc1.x.com".into()))],
+        additional: Vec::new(),
+    };
+
+    let parsed = Message::parse(&message.encode()).unwrap();
+    assert_eq!(parsed.id, 7);
+    assert_eq!(parsed.flags, 0x8400);
+    assert_eq!(parsed.answers.len(), 2);
+    assert_eq!(parsed.authority.len(), 1);
+    assert_eq!(parsed.answers[1].rtype, wire::TYPE_AAAA);
+    assert_eq!(parsed.answers[1].ttl, 600);
+    assert_eq!(wire::decode_address(parsed.answers[0].rtype, &parsed.answers[0].rdata).unwrap(), DnsRecordData::A(Ipv4Addr::new(10, 0, 0, 1)));
+    assert_eq!(wire::decode_address(parsed.answers[1].rtype, &parsed.answers[1].rdata).unwrap(), aaaa);
+    assert_eq!(parsed.authority[0].name, "x.com");
+    assert_eq!(wire::record_type(&aaaa), wire::TYPE_AAAA);
+}
+
+#[test]
+fn test_parse_compressed_names() {
+    // Ответ на A dc1.x.com, имя записи — указатель на вопрос (смещение 12)
+    let mut response = vec![0x00, 0x01, 0x84, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00];
+    response.extend_from_slice(b"\x03DC1\x01x\x03com\x00\x00\x01\x00\x01");
+    response.extend_from_slice(&[0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x0E, 0x10, 0x00, 0x04, 10, 0, 0, 1]);
+
+    let parsed = Message::parse(&response).unwrap();
+    assert_eq!(parsed.answers[0].name, "dc1.x.com");
+    assert_eq!(parsed.answers[0].ttl, 3600);
+    assert_eq!(parsed.answers[0].rdata, [10, 0, 0, 1]);
+}
+
+#[test]
+fn test_parse_rejects_malformed_messages() {
+    assert!(Message::parse(&SRV_QUERY[..8]).is_err());
+    assert!(Message::parse(&SRV_QUERY[..20]).is_err());
+
+    // Указатель сам на себя
+    let mut looped = SRV_QUERY[..12].to_vec();
+    looped.extend_from_slice(&[0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01]);
+    assert!(Message::parse(&looped).is_err());
+
+    assert!(wire::decode_address(wire::TYPE_A, &[1, 2, 3]).is_err());
+    assert!(wire::decode_address(wire::TYPE_NS, &[1, 2, 3, 4]).is_err());
+}
+
+#[test]
+fn test_encode_truncated() {
+    let mut reply = Message::parse(SRV_QUERY).unwrap().reply();
+    for i in 0..40 {
+        reply.answers.push(ResourceRecord::from_data(
+            "_ldap._tcp.x.com",
+            600,
+            &DnsRecordData::Srv { priority: 0, weight: 100, port: 389, target: format!("dc{}.x.com", i) },
+        ));
+    }
+    assert!(reply.encode().len() > 512);
+    assert_eq!(reply.encode_truncated(65535), reply.encode());
+
+    let truncated = Message::parse(&reply.encode_truncated(512)).unwrap();
+    assert_ne!(truncated.flags & 0x0200, 0);
+    assert_eq!(truncated.questions.len(), 1);
+    assert!(truncated.answers.is_empty());
+}
+
+#[tokio::test]
+async fn test_dynamic_updates_opt_in() {
+    let directory = TestDirectory::new().await;
+    // Пустое обновление зоны x.com: без prerequisite и записей
+    let update = Message {
+        id: 7,
+        flags: (wire::OPCODE_UPDATE as u16) << 11,
+        questions: vec![Question { name: "x.com".into(), qtype: wire::TYPE_SOA, qclass: wire::CLASS_IN }],
+        ..Default::default()
+    };
+    let rcode = |reply: &Message| (reply.flags & 0x000F) as u8;
+
+    let disabled = DnsAuthority::new(Arc::clone(&directory.service), false);
+    assert_eq!(rcode(&disabled.handle(&update).await), wire::RCODE_REFUSED);
+    let enabled = DnsAuthority::new(Arc::clone(&directory.service), true);
+    assert_eq!(rcode(&enabled.handle(&update).await), wire::RCODE_NOERROR);
+}
@@ -0,0 +1,116 @@
+// tests/integration/errors.rs
+
+use axum::body::{to_bytes, Body};
+use axum::extract::DefaultBodyLimit;
+use axum::http::{header, Request, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::error_code::ErrorCode;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::web::errors::{normalize_errors, PROBLEM_JSON};
+
+use super::TestDirectory;
+
+const USERS_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+
+dn: CN=carol,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: carol
+";
+
+#[derive(serde::Deserialize)]
+struct Payload {
+    #[allow(dead_code)]
+    name: String,
+}
+
+async fn echo(Json(_): Json<Payload>) -> Result<StatusCode, DirectoryError> {
+    Err(DirectoryError::NotFound("User not found: bob".to_string()))
+}
+
+async fn call(app: &Router, body: &'static str, content_type: Option<&str>) -> (StatusCode, Value) {
+    let mut request = Request::post("/echo");
+    if let Some(content_type) = content_type {
+        request = request.header(header::CONTENT_TYPE, content_type);
+    }
+    let response = app.clone().oneshot(request.body(Body::from(body)).unwrap()).await.unwrap();
+    let status = response.status();
+    assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_error_envelope() {
+    let app = Router::new()
+        .route("/echo", post(echo))
+        .layer(DefaultBodyLimit::max(32))
+        .layer(axum::middleware::from_fn(normalize_errors));
+    let json = Some("application/json");
+
+    // Ошибка каталога — код уже в ответе
+    let (status, body) = call(&app, r#"{"name":"bob"}"#, json).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::NOT_FOUND, Some("NOT_FOUND")));
+    assert_eq!(body["type"], "urn:nextdomen:error:not-found");
+    assert_eq!(body["status"], 404);
+    assert_eq!(body["detail"], "User not found: bob");
+
+    // Отказы извлекателя и маршрутизатора переписываются в тот же вид
+    let (status, body) = call(&app, "{not json", json).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::BAD_REQUEST, Some("INVALID_REQUEST")));
+    assert!(body["detail"].as_str().unwrap().contains("JSON"));
+    let (status, body) = call(&app, r#"{"name":"bob"}"#, None).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::UNSUPPORTED_MEDIA_TYPE, Some("UNSUPPORTED_MEDIA_TYPE")));
+    let (status, body) = call(&app, r#"{"name":"a very long name that does not fit"}"#, json).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::PAYLOAD_TOO_LARGE, Some("PAYLOAD_TOO_LARGE")));
+
+    let response = app.clone().oneshot(Request::get("/missing").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(body["code"], "NOT_FOUND");
+
+    // Страница для браузера остаётся HTML
+    let app = Router::new()
+        .route("/page", axum::routing::get(|| async { (StatusCode::UNAUTHORIZED, axum::response::Html("<p>Sign in</p>")) }))
+        .layer(axum::middleware::from_fn(normalize_errors));
+    let response = app.oneshot(Request::get("/page").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers()[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/html"));
+    assert_eq!(&to_bytes(response.into_body(), usize::MAX).await.unwrap()[..], b"<p>Sign in</p>");
+}
+
+#[tokio::test]
+async fn test_conflict_codes() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(USERS_LDIF, DuplicatePolicy::Fail).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let mut bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    bob.email = Some("bob@x.com".to_string());
+    service.update_user(&bob).await.unwrap();
+
+    // Занятое имя и занятый адрес различаются кодом, а не текстом
+    let carol = service.find_user_by_username("carol").await.unwrap().unwrap();
+    let mut renamed = carol.clone();
+    renamed.username = "bob".to_string();
+    let error = service.update_user(&renamed).await.unwrap_err();
+    assert_eq!(error.code(), ErrorCode::UserAlreadyExists);
+    let mut readdressed = carol.clone();
+    readdressed.email = bob.email.clone();
+    let error = service.update_user(&readdressed).await.unwrap_err();
+    assert_eq!(error.code(), ErrorCode::EmailAlreadyExists);
+
+    // gRPC несёт тот же код в google.rpc.ErrorInfo
+    let status = nextDomen::grpc::status(error);
+    assert_eq!(status.code(), tonic::Code::AlreadyExists);
+    assert_eq!(nextDomen::grpc::error_code(&status).as_deref(), Some("EMAIL_ALREADY_EXISTS"));
+    let error = service.set_password(carol.id, "short").await.unwrap_err();
+    assert_eq!(nextDomen::grpc::error_code(&nextDomen::grpc::status(error)).as_deref(), Some("PASSWORD_POLICY_VIOLATION"));
+}
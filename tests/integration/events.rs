@@ -0,0 +1,89 @@
+// tests/integration/events.rs
+
+use std::time::Duration;
+
+use axum::body::BodyDataStream;
+use axum::http::StatusCode;
+use futures_util::StreamExt;
+use tower::ServiceExt;
+
+use nextDomen::events::{AuditEvent, AuditResult};
+use nextDomen::models::apikey::scope;
+
+use super::{call, jwt, request, TestDirectory};
+
+/// Следующее событие SSE: (`event`, `data`); комментарии keep-alive пропускаются
+async fn next_sse(body: &mut BodyDataStream, buffer: &mut String) -> (String, String) {
+    loop {
+        if let Some(end) = buffer.find("\n\n") {
+            let frame: String = buffer.drain(..end + 2).collect();
+            let field = |name: &str| frame.lines().find_map(|line| line.strip_prefix(name)).unwrap_or_default().trim().to_string();
+            if frame.starts_with(':') {
+                continue;
+            }
+            return (field("event:"), field("data:"));
+        }
+        let chunk = tokio::time::timeout(Duration::from_secs(5), body.next()).await.unwrap().unwrap().unwrap();
+        buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+}
+
+#[tokio::test]
+async fn test_event_stream() {
+    let directory = TestDirectory::new().await;
+    let (_, admin) = directory.api_key("alice", true, &[scope::EVENTS_READ]).await;
+    let (_, other_scope) = directory.api_key("bob", true, &[scope::AUDIT_READ]).await;
+    let (_, user) = directory.api_key("carol", false, &[scope::EVENTS_READ]).await;
+    let app = directory.router("");
+
+    assert_eq!(call(&app, request("GET", "/api/events/stream", None, None)).await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(call(&app, request("GET", "/api/events/stream", Some(&user), None)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, request("GET", "/api/events/stream", Some(&other_scope), None)).await.0, StatusCode::FORBIDDEN);
+
+    let response = app.clone().oneshot(request("GET", "/api/events/stream?types=create_*,delete_group", Some(&admin), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let mut body = response.into_body().into_data_stream();
+    let mut buffer = String::new();
+
+    // Фильтр по типам: delete_user не подходит ни под префикс, ни под точное имя
+    let events = directory.service.events();
+    for action in ["delete_user", "create_user", "delete_group"] {
+        events.emit(AuditEvent::new(action, AuditResult::Success));
+    }
+    for expected in ["create_user", "delete_group"] {
+        let (event, data) = next_sse(&mut body, &mut buffer).await;
+        assert_eq!(event, "audit");
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&data).unwrap()["action"], expected);
+    }
+
+    // Подписчик, не успевающий читать, получает число пропущенных, затем события идут дальше
+    for _ in 0..2000 {
+        events.emit(AuditEvent::new("create_user", AuditResult::Success));
+    }
+    let (event, data) = next_sse(&mut body, &mut buffer).await;
+    assert_eq!(event, "lagged");
+    let missed = serde_json::from_str::<serde_json::Value>(&data).unwrap()["missed"].as_u64().unwrap();
+    assert!(missed > 0 && missed <= 1000, "{}", missed);
+    let (event, _) = next_sse(&mut body, &mut buffer).await;
+    assert_eq!(event, "audit");
+}
+
+#[tokio::test]
+async fn test_query_token_only_for_event_streams() {
+    let directory = TestDirectory::new().await;
+    let (alice, _) = directory.api_key("alice", true, &[scope::EVENTS_READ]).await;
+    let app = directory.router("");
+    let token = jwt(&alice);
+
+    let response = app.clone().oneshot(request("GET", &format!("/api/events/stream?access_token={}", token), None, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let (status, _) = call(&app, request("GET", &format!("/api/events/stream?access_token={}x", token), None, None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // На остальных путях токен в адресе не читается
+    let (status, _) = call(&app, request("GET", &format!("/api/users?access_token={}", token), None, None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let mut with_header = request("GET", "/api/users", None, None);
+    with_header.headers_mut().insert("authorization", format!("Bearer {}", token).parse().unwrap());
+    assert_eq!(call(&app, with_header).await.0, StatusCode::OK);
+}
@@ -0,0 +1,166 @@
+// tests/integration/gpos.rs
+
+use std::collections::HashMap;
+
+use axum::http::StatusCode;
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::agent::merge_settings;
+use nextDomen::models::policy::{GroupPolicy, PolicyType, PolicyValue};
+use nextDomen::models::{ComplianceState, PolicyApplyStatus, PolicyReport};
+use nextDomen::models::policy_schema::{builtin_schemas, parse_settings};
+
+use nextDomen::sysvol;
+
+use super::{call, request, TestDirectory};
+
+fn settings(pairs: &[&str], policy_type: &PolicyType) -> HashMap<String, PolicyValue> {
+    parse_settings(policy_type, &pairs.iter().map(|pair| pair.to_string()).collect::<Vec<_>>()).unwrap()
+}
+
+#[tokio::test]
+async fn test_gpo_settings_schema() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    assert_eq!(builtin_schemas().len(), 6);
+
+    // Настройки по схеме сохраняются вместе с типом
+    let mut security = GroupPolicy::new("Baseline").with_type(PolicyType::from_name("security"));
+    security.settings = settings(&["min_password_length=12", "password_complexity=true"], &PolicyType::Security);
+    service.create_gpo(&security).await.unwrap();
+    let stored = service.get_gpo(security.id).await.unwrap().unwrap();
+    assert_eq!(stored.policy_type, PolicyType::Security);
+    assert_eq!(stored.get_setting("min_password_length"), Some(&PolicyValue::Integer(12)));
+
+    let rejected = |settings: HashMap<String, PolicyValue>, policy_type: PolicyType| {
+        let mut gpo = GroupPolicy::new("Rejected").with_type(policy_type);
+        gpo.settings = settings;
+        async move { matches!(service.create_gpo(&gpo).await, Err(DirectoryError::InvalidInput(_))) }
+    };
+    assert!(rejected(settings(&["min_password_length=500"], &PolicyType::Security), PolicyType::Security).await);
+    assert!(rejected(settings(&["wallpaper=x.png"], &PolicyType::Security), PolicyType::Security).await);
+    assert!(rejected(HashMap::from([("password_complexity".to_string(), PolicyValue::from("yes"))]), PolicyType::Security).await);
+
+    // Данные параметра реестра — по value_type
+    let registry = ["hive=HKLM", "key=Software\\Policies\\Corp", "value_name=Level", "value_type=REG_DWORD"];
+    let mut dword = settings(&registry, &PolicyType::Registry);
+    assert!(rejected(dword.clone(), PolicyType::Registry).await, "data is required");
+    dword.insert("data".to_string(), PolicyValue::from("high"));
+    assert!(rejected(dword, PolicyType::Registry).await);
+    let mut gpo = GroupPolicy::new("Registry").with_type(PolicyType::Registry);
+    gpo.settings = settings(&[&registry[..], &["data=3"]].concat(), &PolicyType::Registry);
+    service.create_gpo(&gpo).await.unwrap();
+
+    // У своего типа схемы нет
+    let mut custom = GroupPolicy::new("Custom").with_type(PolicyType::from_name("Kiosk"));
+    custom.settings = settings(&["anything=goes"], &custom.policy_type);
+    service.create_gpo(&custom).await.unwrap();
+    assert_eq!(service.get_gpo(custom.id).await.unwrap().unwrap().policy_type, PolicyType::Custom("Kiosk".into()));
+}
+
+#[test]
+fn test_sysvol_rendering() {
+    let registry = ["hive=HKCU", "key=Software\\Corp", "value_name=Level", "value_type=REG_DWORD", "data=3"];
+    let mut gpo = GroupPolicy::new("Corp").with_type(PolicyType::Registry);
+    gpo.settings = settings(&registry, &PolicyType::Registry);
+    gpo.version = 3;
+
+    assert_eq!(sysvol::parse_folder_name(&sysvol::folder_name(&gpo)), Some(gpo.id));
+    assert_eq!(sysvol::files(&gpo), vec![sysvol::GPT_INI, sysvol::USER_REGISTRY_POL]);
+    // Только пользовательская часть: версия в старших 16 битах
+    let gpt = String::from_utf8(sysvol::render(&gpo, "gpt.ini").unwrap()).unwrap();
+    assert!(gpt.contains("Version=196608\r\n"), "{}", gpt);
+
+    let pol = sysvol::render(&gpo, "user\\registry.pol").unwrap();
+    assert_eq!(&pol[..8], b"PReg\x01\x00\x00\x00");
+    // ...;REG_DWORD;4;3]
+    assert_eq!(&pol[pol.len() - 18..], &[4, 0, 0, 0, b';', 0, 4, 0, 0, 0, b';', 0, 3, 0, 0, 0, b']', 0]);
+    assert!(sysvol::render(&gpo, sysvol::MACHINE_REGISTRY_POL).is_none());
+
+    let mut scripts = GroupPolicy::new("Scripts").with_type(PolicyType::Script);
+    scripts.settings = settings(&["logon_scripts=\\\\corp\\netlogon\\map.bat;\\\\corp\\netlogon\\printers.bat"], &PolicyType::Script);
+    let ini = sysvol::render(&scripts, sysvol::USER_SCRIPTS_INI).unwrap();
+    let ini = String::from_utf16(&ini[2..].chunks(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect::<Vec<_>>()).unwrap();
+    assert!(ini.contains("[Logon]\r\n0CmdLine=\\\\corp\\netlogon\\map.bat\r\n0Parameters=\r\n1CmdLine="), "{}", ini);
+    assert!(sysvol::render(&scripts, sysvol::MACHINE_SCRIPTS_INI).is_none());
+}
+
+const WORKSTATIONS_LDIF: &str = "\
+dn: OU=Workstations,DC=x,DC=com
+objectClass: organizationalUnit
+ou: Workstations
+
+dn: CN=WS01,OU=Workstations,DC=x,DC=com
+objectClass: user
+sAMAccountName: WS01$
+
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+";
+
+#[tokio::test]
+async fn test_agent_checkin() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(WORKSTATIONS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let ws = service.find_user_by_username("WS01$").await.unwrap().unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let ou = service.find_ou_by_dn("OU=Workstations,DC=x,DC=com").await.unwrap().unwrap();
+
+    let mut baseline = GroupPolicy::new("Baseline").with_type(PolicyType::Security);
+    baseline.settings = settings(&["min_password_length=12"], &PolicyType::Security);
+    service.create_gpo(&baseline).await.unwrap();
+    service.link_gpo_to_ou(baseline.id, ou.id).await.unwrap();
+
+    // Первый check-in: политика выдана, отчёта ещё нет
+    let (checkin, gpos) = service.agent_checkin(&ws, None, vec![]).await.unwrap();
+    assert_eq!(checkin.computer, "WS01");
+    assert_eq!(gpos.iter().map(|gpo| gpo.id).collect::<Vec<_>>(), vec![baseline.id]);
+    assert!(!checkin.compliant);
+    assert_eq!(checkin.state(baseline.id, baseline.version), Some(ComplianceState::Pending));
+
+    let applied = |version| PolicyReport { gpo_id: baseline.id, version, status: PolicyApplyStatus::Applied, message: None };
+    let (checkin, _) = service.agent_checkin(&ws, Some("ws01"), vec![applied(1)]).await.unwrap();
+    assert!(checkin.compliant);
+    assert_eq!(checkin.state(baseline.id, 2), Some(ComplianceState::Outdated));
+    assert!(matches!(service.agent_checkin(&ws, Some("WS02"), vec![]).await, Err(DirectoryError::InvalidInput(_))));
+
+    // Агент пользователя указывает компьютер
+    assert!(matches!(service.agent_checkin(&bob, None, vec![]).await, Err(DirectoryError::InvalidInput(_))));
+    assert!(matches!(service.agent_checkin(&bob, Some("WS02"), vec![]).await, Err(DirectoryError::NotFound(_))));
+    let (checkin, gpos) = service.agent_checkin(&bob, Some("WS01"), vec![]).await.unwrap();
+    assert_eq!((checkin.computer_id, gpos.len()), (ws.id, 0));
+
+    let checkins = service.get_agent_checkins().await.unwrap();
+    assert_eq!(checkins.len(), 2);
+    assert_eq!(checkins[0].state(baseline.id, baseline.version), Some(ComplianceState::Compliant));
+
+    // Слияние: ключ берётся из политики с наибольшим приоритетом
+    let mut strict = GroupPolicy::new("Strict").with_type(PolicyType::Security);
+    strict.settings = settings(&["min_password_length=16", "lockout_threshold=5"], &PolicyType::Security);
+    let merged = merge_settings(&[strict.clone(), baseline.clone()]);
+    assert_eq!(merged.len(), 2);
+    assert!(merged.iter().all(|setting| setting.gpo_id == strict.id));
+}
+
+#[tokio::test]
+async fn test_gpo_compliance_route() {
+    let directory = TestDirectory::new().await;
+    let gpo = GroupPolicy::new("Baseline").with_type(PolicyType::Security);
+    directory.service.create_gpo(&gpo).await.unwrap();
+    let (_, reader) = directory.api_key("alice", true, &[scope::DIRECTORY_READ]).await;
+    let (_, auditor) = directory.api_key("carol", true, &[scope::AUDIT_READ]).await;
+    let (_, user_reader) = directory.api_key("bob", false, &[scope::DIRECTORY_READ]).await;
+    let app = directory.router("");
+    let uri = format!("/api/gpos/{}/compliance", gpo.id);
+
+    let (status, body) = call(&app, request("GET", &uri, Some(&auditor), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    let (status, body) = call(&app, request("GET", &uri, Some(&user_reader), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("ACCESS_DENIED")));
+    let (status, body) = call(&app, request("GET", &uri, Some(&reader), None)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
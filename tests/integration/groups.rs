@@ -0,0 +1,403 @@
+// tests/integration/groups.rs
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::error_code::ErrorCode;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::policy::GroupPolicy;
+use nextDomen::models::{AccessMask, Ace, AceFlags, Group, GroupScope, GroupTypeFlags, LdapFilter, OrganizationalUnit, OuContents, SecuredObject, SecurityDescriptor};
+
+use super::{call, request, TestDirectory};
+
+const SALES_LDIF: &str = "\
+dn: OU=Sales,DC=x,DC=com
+objectClass: organizationalUnit
+ou: Sales
+
+dn: CN=bob,OU=Sales,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+mail: bob@x.com
+
+dn: CN=carol,OU=Sales,DC=x,DC=com
+objectClass: user
+sAMAccountName: carol
+
+dn: CN=erin,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: erin
+mail: erin@x.com
+";
+
+#[test]
+fn test_ldap_filter_parse_and_match() {
+    let filter = LdapFilter::parse("(&(objectClass=user)(|(mail=*@X.com)(sn=Smith))(!(userAccountControl:1.2.840.113556.1.4.803:=2)))").unwrap();
+    assert_eq!(LdapFilter::parse(&filter.to_string()).unwrap(), filter);
+
+    let entry = [
+        ("objectClass", vec!["top", "user"]),
+        ("mail", vec!["bob@x.com"]),
+        ("userAccountControl", vec!["512"]),
+        ("distinguishedName", vec!["CN=bob,OU=Sales,DC=x,DC=com"]),
+    ].into_iter().map(|(k, v)| (k.to_string(), v.into_iter().map(str::to_string).collect())).collect();
+    assert!(filter.matches(&entry));
+    assert!(LdapFilter::parse("(ou:dn:=sales)").unwrap().matches(&entry));
+    assert!(!LdapFilter::parse("(userAccountControl:1.2.840.113556.1.4.803:=514)").unwrap().matches(&entry));
+    assert!(LdapFilter::parse("(userAccountControl>=500)").unwrap().matches(&entry));
+    assert!(LdapFilter::parse("(cn=a\\2ab)").is_ok());
+
+    for invalid in ["", "mail=*", "(&)", "(mail=*", "(mail=*))", "(cn=\\zz)", "(x:1.2.3:=1)"] {
+        assert!(LdapFilter::parse(invalid).is_err(), "{}", invalid);
+    }
+}
+
+#[tokio::test]
+async fn test_dynamic_group_membership() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(SALES_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let carol = service.find_user_by_username("carol").await.unwrap().unwrap();
+    let erin = service.find_user_by_username("erin").await.unwrap().unwrap();
+
+    let mut group = Group::new("Sales Mail".into(), "SalesMail".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    group.membership_rule = Some("(&(ou:dn:=Sales)(mail=*))".into());
+    service.create_group(&group).await.unwrap();
+
+    // Снимок вычислен при сохранении, memberOf и tokenGroups — по правилу
+    let group = service.get_group(group.id).await.unwrap().unwrap();
+    assert_eq!(group.members, vec![bob.id]);
+    assert!(service.get_token_groups(bob.id).await.unwrap().contains(&group.sid));
+    assert!(!service.get_token_groups(carol.id).await.unwrap().contains(&group.sid));
+    assert!(!service.get_token_groups(erin.id).await.unwrap().contains(&group.sid));
+    assert!(matches!(service.add_member_to_group(group.id, erin.id, None).await, Err(DirectoryError::InvalidInput(_))));
+
+    // Пользователь начал подходить под правило: членство видно сразу, снимок — после пересчёта
+    let mut carol = carol;
+    carol.email = Some("carol@x.com".into());
+    service.update_user(&carol).await.unwrap();
+    assert!(service.find_groups_by_member(carol.id).await.unwrap().iter().any(|g| g.id == group.id));
+    assert!(service.refresh_dynamic_group(group.id).await.unwrap());
+    let mut members = service.get_group(group.id).await.unwrap().unwrap().members;
+    members.sort();
+    let mut expected = vec![bob.id, carol.id];
+    expected.sort();
+    assert_eq!(members, expected);
+
+    // Без правила группа статическая с последним составом
+    let group = service.set_group_membership_rule(group.id, None).await.unwrap();
+    assert!(!group.is_dynamic());
+    service.add_member_to_group(group.id, erin.id, None).await.unwrap();
+    assert!(service.get_token_groups(erin.id).await.unwrap().contains(&group.sid));
+
+    assert!(matches!(
+        service.set_group_membership_rule(group.id, Some("(mail=*".into())).await,
+        Err(DirectoryError::InvalidInput(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_group_join_requests_and_expiration() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(SALES_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let erin = service.find_user_by_username("erin").await.unwrap().unwrap();
+
+    let group = Group::new("Project X".into(), "ProjectX".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&group).await.unwrap();
+    let group = service.set_group_lifecycle(group.id, Some(bob.id), None).await.unwrap();
+    assert!(service.can_manage_group_membership(&bob, &group).await.unwrap());
+    assert!(!service.can_manage_group_membership(&erin, &group).await.unwrap());
+
+    // Владелец одобряет заявку — пользователь становится участником
+    let request = service.request_group_membership(group.id, erin.id, Some("project work".into())).await.unwrap();
+    assert!(matches!(
+        service.request_group_membership(group.id, erin.id, None).await,
+        Err(DirectoryError::AlreadyExists(ErrorCode::RequestAlreadyPending, _))
+    ));
+    assert!(matches!(
+        service.decide_group_join_request(request.id, true, Some(erin.id), None).await,
+        Err(DirectoryError::AccessDenied(_))
+    ));
+    let request = service.decide_group_join_request(request.id, true, Some(bob.id), None).await.unwrap();
+    assert!(!request.is_pending());
+    assert!(service.get_token_groups(erin.id).await.unwrap().contains(&group.sid));
+    assert!(matches!(
+        service.decide_group_join_request(request.id, false, Some(bob.id), None).await,
+        Err(DirectoryError::InvalidInput(_))
+    ));
+
+    // Истёкшая группа отключается, членство перестаёт действовать; новый срок включает её снова
+    let past = chrono::Utc::now() - chrono::Duration::hours(1);
+    service.set_group_lifecycle(group.id, Some(bob.id), Some(past)).await.unwrap();
+    let mut events = service.events().subscribe();
+    assert_eq!(service.expire_groups().await.unwrap(), 1);
+    assert_eq!(service.expire_groups().await.unwrap(), 0);
+    let event = events.recv().await.unwrap();
+    assert_eq!(event.action, "group_expired");
+    assert_eq!(event.metadata.get("owner").map(String::as_str), Some("bob"));
+    assert!(!service.get_token_groups(erin.id).await.unwrap().contains(&group.sid));
+    assert!(matches!(
+        service.request_group_membership(group.id, bob.id, None).await,
+        Err(DirectoryError::InvalidInput(_))
+    ));
+
+    let group = service.set_group_lifecycle(group.id, Some(bob.id), Some(chrono::Utc::now() + chrono::Duration::days(30))).await.unwrap();
+    assert!(!group.is_disabled());
+    assert!(service.get_token_groups(erin.id).await.unwrap().contains(&group.sid));
+}
+
+#[tokio::test]
+async fn test_temporary_membership() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(SALES_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let erin = service.find_user_by_username("erin").await.unwrap().unwrap();
+    let group = Group::new("Admins X".into(), "AdminsX".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&group).await.unwrap();
+
+    assert!(matches!(
+        service.add_member_to_group(group.id, bob.id, Some(chrono::Duration::zero())).await,
+        Err(DirectoryError::InvalidInput(_))
+    ));
+    service.add_member_to_group(group.id, bob.id, Some(chrono::Duration::hours(8))).await.unwrap();
+    service.add_member_to_group(group.id, erin.id, Some(chrono::Duration::milliseconds(50))).await.unwrap();
+    let group = service.get_group(group.id).await.unwrap().unwrap();
+    let ttl = group.member_ttl(bob.id, chrono::Utc::now()).unwrap();
+    assert!(ttl > chrono::Duration::hours(7) && ttl <= chrono::Duration::hours(8));
+
+    // Повторное добавление без срока делает членство постоянным
+    service.add_member_to_group(group.id, bob.id, None).await.unwrap();
+    assert!(service.get_group(group.id).await.unwrap().unwrap().member_ttl(bob.id, chrono::Utc::now()).is_none());
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let mut events = service.events().subscribe();
+    assert_eq!(service.expire_memberships().await.unwrap(), 1);
+    assert_eq!(service.expire_memberships().await.unwrap(), 0);
+    let event = events.recv().await.unwrap();
+    assert_eq!((event.action.as_str(), event.target_id), ("remove_member_from_group", Some(erin.id)));
+    let event = events.recv().await.unwrap();
+    assert_eq!((event.action.as_str(), event.target_id), ("group_membership_expired", Some(erin.id)));
+    let group = service.get_group(group.id).await.unwrap().unwrap();
+    assert_eq!(group.members, vec![bob.id]);
+    assert!(group.member_expirations.is_empty());
+}
+
+#[tokio::test]
+async fn test_find_by_sid() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(SALES_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let group = Group::new("Sales".into(), "Sales".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&group).await.unwrap();
+    let group = service.get_group(group.id).await.unwrap().unwrap();
+
+    assert_eq!(service.find_user_by_sid(&bob.sid).await.unwrap().map(|user| user.id), Some(bob.id));
+    assert_eq!(service.find_group_by_sid(&group.sid).await.unwrap().map(|group| group.id), Some(group.id));
+    // SID группы не находит пользователя и наоборот
+    assert!(service.find_user_by_sid(&group.sid).await.unwrap().is_none());
+    assert!(service.find_group_by_sid(&bob.sid).await.unwrap().is_none());
+
+    // SID занят: второй объект с ним не сохраняется
+    let mut carol = service.find_user_by_username("carol").await.unwrap().unwrap();
+    carol.sid = group.sid.clone();
+    assert!(matches!(service.update_user(&carol).await, Err(DirectoryError::AlreadyExists(ErrorCode::SidAlreadyAssigned, _))));
+
+    service.delete_user(bob.id).await.unwrap();
+    assert!(service.find_user_by_sid(&bob.sid).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_update_group_scope() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let group = Group::new("Sales".into(), "Sales".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&group).await.unwrap();
+    let mut group = service.get_group(group.id).await.unwrap().unwrap();
+
+    // Между Global и DomainLocal — только через Universal
+    group.scope = GroupScope::DomainLocal;
+    assert!(matches!(service.update_group(&group).await, Err(DirectoryError::InvalidInput(_))));
+    group.scope = GroupScope::Universal;
+    group.description = Some("Отдел продаж".into());
+    group.type_flags = GroupTypeFlags::DISTRIBUTION;
+    let group = service.update_group(&group).await.unwrap();
+    assert_eq!(group.scope, GroupScope::Universal);
+    assert!(!group.is_security_group());
+    let mut group = service.find_group_by_sam_account_name("sales").await.unwrap().unwrap();
+    assert_eq!(group.description.as_deref(), Some("Отдел продаж"));
+    group.scope = GroupScope::DomainLocal;
+    service.update_group(&group).await.unwrap();
+
+    let mut renamed = group.clone();
+    renamed.sam_account_name = "Marketing".into();
+    assert!(matches!(service.update_group(&renamed).await, Err(DirectoryError::InvalidInput(_))));
+}
+
+#[tokio::test]
+async fn test_referential_integrity() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(SALES_LDIF, DuplicatePolicy::Fail).await.unwrap();
+    let group = Group::new("Sales".into(), "Sales".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&group).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+
+    // Участник и цель привязки GPO должны существовать
+    let missing = uuid::Uuid::new_v4();
+    assert!(matches!(service.add_member_to_group(group.id, missing, None).await, Err(DirectoryError::NotFound(_))));
+    service.add_member_to_group(group.id, bob.id, None).await.unwrap();
+    let mut with_missing = service.get_group(group.id).await.unwrap().unwrap();
+    with_missing.members.push(missing);
+    assert!(matches!(service.create_group(&with_missing).await, Err(DirectoryError::NotFound(_))));
+    let mut gpo = GroupPolicy::new("Baseline");
+    gpo.link_to(missing);
+    assert!(matches!(service.create_gpo(&gpo).await, Err(DirectoryError::NotFound(_))));
+    assert!(service.find_dangling_references().await.unwrap().is_empty());
+
+    // OU удалена, а GPO всё ещё ссылается на неё — это находит проверка ссылок
+    let archive = OrganizationalUnit::new("Archive".into(), "OU=Archive,DC=x,DC=com".into(), None);
+    service.create_ou(&archive).await.unwrap();
+    let mut gpo = GroupPolicy::new("Archive policy");
+    gpo.link_to(archive.id);
+    service.create_gpo(&gpo).await.unwrap();
+    service.delete_ou_tree(archive.id, false, OuContents::Refuse).await.unwrap();
+    let dangling = service.find_dangling_references().await.unwrap();
+    assert_eq!(dangling.len(), 1);
+    assert_eq!((dangling[0].object_id, dangling[0].attribute.as_str(), dangling[0].target_id), (gpo.id, "linked_to", archive.id));
+    assert_eq!(service.report_dangling_references().await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_group_nesting_and_scope_conversion() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(SALES_LDIF, DuplicatePolicy::Fail).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let create = |sam: &str, scope: GroupScope| {
+        let group = Group::new(sam.into(), sam.into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, scope);
+        async move {
+            service.create_group(&group).await.unwrap();
+            service.get_group(group.id).await.unwrap().unwrap()
+        }
+    };
+    let sales = create("Sales", GroupScope::Global).await;
+    let east = create("East", GroupScope::Global).await;
+    let staff = create("Staff", GroupScope::Universal).await;
+    let partners = create("Partners", GroupScope::Universal).await;
+    let printers = create("Printers", GroupScope::DomainLocal).await;
+
+    // Global — только Global, Universal — без DomainLocal, и без циклов
+    service.add_member_to_group(sales.id, east.id, None).await.unwrap();
+    service.add_member_to_group(staff.id, sales.id, None).await.unwrap();
+    service.add_member_to_group(east.id, bob.id, None).await.unwrap();
+    for (group, member) in [(&sales, &staff), (&staff, &printers), (&east, &sales), (&sales, &sales)] {
+        let result = service.add_member_to_group(group.id, member.id, None).await;
+        assert!(matches!(result, Err(DirectoryError::InvalidInput(_))), "{} <- {}", group.sam_account_name, member.sam_account_name);
+    }
+    service.add_member_to_group(printers.id, staff.id, None).await.unwrap();
+
+    // tokenGroups транзитивен: East -> Sales -> Staff -> Printers
+    let tokens = service.get_token_groups(bob.id).await.unwrap();
+    for group in [&east, &sales, &staff, &printers] {
+        assert!(tokens.contains(&group.sid), "{}", group.sam_account_name);
+    }
+
+    // East вложена в Global-группу Sales и не может стать Universal; Sales в Universal — может
+    let result = service.convert_group_scope(east.id, GroupScope::Universal).await;
+    assert!(matches!(result, Err(DirectoryError::InvalidInput(message)) if message.contains("Sales")));
+    assert_eq!(service.convert_group_scope(sales.id, GroupScope::Universal).await.unwrap().scope, GroupScope::Universal);
+    service.convert_group_scope(sales.id, GroupScope::Global).await.unwrap();
+    // Universal с Universal-участником не становится Global, вложенная в Universal — DomainLocal
+    service.add_member_to_group(staff.id, partners.id, None).await.unwrap();
+    assert!(matches!(service.convert_group_scope(staff.id, GroupScope::Global).await, Err(DirectoryError::InvalidInput(_))));
+    assert!(matches!(service.convert_group_scope(partners.id, GroupScope::DomainLocal).await, Err(DirectoryError::InvalidInput(_))));
+
+    service.delete_group(east.id).await.unwrap();
+    assert!(!service.get_group(sales.id).await.unwrap().unwrap().members.contains(&east.id));
+    assert!(service.find_dangling_references().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_primary_group() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(SALES_LDIF, DuplicatePolicy::Fail).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let create = |sam: &str, scope: GroupScope| {
+        let group = Group::new(sam.into(), sam.into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, scope);
+        async move {
+            service.create_group(&group).await.unwrap();
+            service.get_group(group.id).await.unwrap().unwrap()
+        }
+    };
+    let sales = create("Sales", GroupScope::Global).await;
+    let staff = create("Staff", GroupScope::Universal).await;
+    let printers = create("Printers", GroupScope::DomainLocal).await;
+
+    // Основной может стать только группа, где пользователь уже состоит постоянно: членство она не добавляет
+    assert!(matches!(service.set_primary_group(bob.id, sales.id).await, Err(DirectoryError::InvalidInput(_))));
+    assert!(!service.get_group(sales.id).await.unwrap().unwrap().members.contains(&bob.id));
+    service.add_member_to_group(sales.id, bob.id, Some(chrono::Duration::hours(1))).await.unwrap();
+    assert!(matches!(service.set_primary_group(bob.id, sales.id).await, Err(DirectoryError::InvalidInput(_))));
+    service.add_member_to_group(sales.id, bob.id, None).await.unwrap();
+
+    // Основная группа содержит пользователя, в tokenGroups она один раз
+    let updated = service.set_primary_group(bob.id, sales.id).await.unwrap();
+    assert_eq!(updated.primary_group_id, Some(sales.get_rid()));
+    assert!(service.get_group(sales.id).await.unwrap().unwrap().members.contains(&bob.id));
+    let tokens = service.get_token_groups(bob.id).await.unwrap();
+    assert_eq!(tokens.iter().filter(|sid| **sid == sales.sid).count(), 1);
+
+    // Из основной группы не удалить, основную группу не удалить, DomainLocal основной не бывает
+    assert!(matches!(service.remove_member_from_group(sales.id, bob.id).await, Err(DirectoryError::InvalidInput(_))));
+    assert!(matches!(service.delete_group(sales.id).await, Err(DirectoryError::InvalidInput(_))));
+    assert!(matches!(service.set_primary_group(bob.id, printers.id).await, Err(DirectoryError::InvalidInput(_))));
+
+    // После смены основной группы прежняя — обычное членство, его можно снять
+    service.add_member_to_group(staff.id, bob.id, None).await.unwrap();
+    service.set_primary_group(bob.id, staff.id).await.unwrap();
+    assert!(service.get_group(staff.id).await.unwrap().unwrap().members.contains(&bob.id));
+    service.remove_member_from_group(sales.id, bob.id).await.unwrap();
+    service.delete_group(sales.id).await.unwrap();
+
+    service.delete_user(bob.id).await.unwrap();
+    assert!(service.get_group(staff.id).await.unwrap().unwrap().members.is_empty());
+}
+
+#[tokio::test]
+async fn test_primary_group_does_not_grant_membership() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let (_, admin) = directory.api_key("alice", true, &[scope::DIRECTORY_WRITE]).await;
+    let (bob, key) = directory.api_key("bob", false, &[scope::DIRECTORY_WRITE]).await;
+    let (carol, _) = directory.api_key("carol", false, &[scope::DIRECTORY_READ]).await;
+    let app = directory.router("");
+    let domain_admins = service.find_group_by_sam_account_name("Domain Admins").await.unwrap().unwrap();
+
+    // bob может менять carol, но прав на Domain Admins у него нет
+    let mut descriptor = SecurityDescriptor::new(bob.sid.clone());
+    descriptor.dacl.push(Ace::allow(bob.sid.clone(), AccessMask::GENERIC_WRITE, AceFlags::empty()));
+    service.set_security_descriptor(SecuredObject::User(carol.id), &descriptor).await.unwrap();
+    let (status, body) = call(&app, request("PUT", "/api/users/carol", Some(&key), Some(json!({ "display_name": "Carol" })))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Смена основной группы не делает carol участником Domain Admins ни для bob, ни для администратора
+    for key in [&key, &admin] {
+        let (status, body) = call(&app, request("PUT", "/api/users/carol", Some(key), Some(json!({ "primary_group": "Domain Admins" })))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    }
+    assert!(!service.get_group(domain_admins.id).await.unwrap().unwrap().members.contains(&carol.id));
+    assert!(!service.get_token_groups(carol.id).await.unwrap().contains(&domain_admins.sid));
+    assert_eq!(service.get_user(carol.id).await.unwrap().unwrap().primary_group_id, carol.primary_group_id);
+}
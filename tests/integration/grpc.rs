@@ -0,0 +1,362 @@
+// tests/integration/grpc.rs
+
+use std::path::Path;
+use std::time::Duration;
+
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+use tonic::{Code, Request, Status};
+
+use nextDomen::ca::{self, CertificateSubject};
+use nextDomen::config::ServerConfig;
+use nextDomen::events::{AuditEvent, AuditResult};
+use nextDomen::grpc::audit::AuditApiService;
+use nextDomen::grpc::audit_api::audit_api_server::AuditApi;
+use nextDomen::grpc::audit_api::QueryEventsRequest;
+use nextDomen::grpc::auth;
+use nextDomen::grpc::auth_api::auth_service_server::AuthService as _;
+use nextDomen::grpc::auth_api::{IntrospectRequest, IntrospectResponse, ValidateTokenRequest};
+use nextDomen::grpc::user_api::user_api_server::UserApi;
+use nextDomen::grpc::user_api::{
+    DeleteUserRequest, EnableUserRequest, GetUserRequest, GetUserResponse, SetPasswordRequest, UpdateUserRequest, UserFields,
+};
+use nextDomen::grpc::{AuthService, UserApiService};
+use nextDomen::models::apikey::scope;
+use nextDomen::models::{CertificateAuthority, CertificateKind, Group, GroupScope, GroupTypeFlags, LoginProtocol};
+
+use super::{jwt, TestDirectory};
+
+/// Запрос метода после перехватчика, как его собирает `UserApiServer::with_interceptor`;
+/// `Status` в коробке — он слишком велик для `Err`
+fn intercepted<T>(token: Option<&str>, message: T) -> Result<Request<T>, Box<Status>> {
+    let mut request = Request::new(());
+    if let Some(token) = token {
+        request.metadata_mut().insert("authorization", format!("Bearer {}", token).parse().unwrap());
+    }
+    let (metadata, extensions, ()) = auth::interceptor(request).map_err(Box::new)?.into_parts();
+    Ok(Request::from_parts(metadata, extensions, message))
+}
+
+fn get(username: &str) -> GetUserRequest {
+    GetUserRequest { username: username.into() }
+}
+
+#[tokio::test]
+async fn test_user_rpcs_require_token_and_role() {
+    let directory = TestDirectory::new().await;
+    let api = UserApiService::new(directory.service.clone());
+    let (alice, _) = directory.api_key("alice", false, &[scope::DIRECTORY_READ]).await;
+    let (admin, _) = directory.api_key("admin", true, &[scope::DIRECTORY_READ]).await;
+
+    // Без токена и с негодным токеном отказывает перехватчик
+    assert_eq!(intercepted(None, get("alice")).unwrap_err().code(), Code::Unauthenticated);
+    assert_eq!(intercepted(Some("not-a-jwt"), get("alice")).unwrap_err().code(), Code::Unauthenticated);
+    let mut request = Request::new(());
+    request.metadata_mut().insert("authorization", jwt(&alice).parse().unwrap());
+    assert_eq!(auth::interceptor(request).unwrap_err().code(), Code::Unauthenticated, "без схемы Bearer");
+    // Метод, вызванный мимо перехватчика, тоже не пускает
+    assert_eq!(api.get_user(Request::new(get("alice"))).await.unwrap_err().code(), Code::Unauthenticated);
+
+    // Токен с подменённой подписью не принимается
+    let mut token = jwt(&alice);
+    token.truncate(token.rfind('.').unwrap() + 1);
+    token.push_str("AAAA");
+    assert_eq!(intercepted(Some(&token), get("alice")).unwrap_err().code(), Code::Unauthenticated);
+
+    // Любой пользователь читает, менять — только Domain Admins
+    let user_token = jwt(&alice);
+    let found = api.get_user(intercepted(Some(&user_token), get("admin")).unwrap()).await.unwrap().into_inner();
+    assert_eq!(found.username, "admin");
+    let error = api.delete_user(intercepted(Some(&user_token), DeleteUserRequest { username: "admin".into() }).unwrap()).await.unwrap_err();
+    assert_eq!(error.code(), Code::PermissionDenied);
+    assert!(directory.service.find_user_by_username("admin").await.unwrap().is_some());
+
+    let admin_token = jwt(&admin);
+    api.delete_user(intercepted(Some(&admin_token), DeleteUserRequest { username: "alice".into() }).unwrap()).await.unwrap();
+    assert!(directory.service.find_user_by_username("alice").await.unwrap().is_none());
+
+    // Токен удалённой учётной записи больше не действует
+    let error = api.get_user(intercepted(Some(&user_token), get("admin")).unwrap()).await.unwrap_err();
+    assert_eq!(error.code(), Code::Unauthenticated);
+}
+
+/// UpdateUserRequest для `username`: меняются только поля из `paths`
+fn update(username: &str, user: UserFields, paths: &[&str]) -> UpdateUserRequest {
+    let update_mask = prost_types::FieldMask { paths: paths.iter().map(|path| path.to_string()).collect() };
+    UpdateUserRequest { username: username.into(), user: Some(user), update_mask: Some(update_mask) }
+}
+
+#[tokio::test]
+async fn test_user_mutation_rpcs() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let api = UserApiService::new(service.clone());
+    let (alice, _) = directory.api_key("alice", false, &[scope::DIRECTORY_READ]).await;
+    let (admin, _) = directory.api_key("admin", true, &[scope::DIRECTORY_READ]).await;
+    let (user_token, admin_token) = (jwt(&alice), jwt(&admin));
+    let enable = |username: &str| EnableUserRequest { username: username.into() };
+    let password = |username: &str, password: &str| SetPasswordRequest { username: username.into(), password: password.into() };
+
+    // Без Domain Admins ни одно изменение не проходит
+    let fields = UserFields { email: "a@x.com".into(), ..Default::default() };
+    let denied = [
+        api.update_user(intercepted(Some(&user_token), update("alice", fields, &["email"])).unwrap()).await.unwrap_err(),
+        api.disable_user(intercepted(Some(&user_token), enable("alice")).unwrap()).await.unwrap_err(),
+        api.set_password(intercepted(Some(&user_token), password("alice", "Str0ng-Passw0rd!")).unwrap()).await.unwrap_err(),
+    ];
+    assert!(denied.iter().all(|error| error.code() == Code::PermissionDenied));
+    assert!(service.get_user(alice.id).await.unwrap().unwrap().email.is_none());
+
+    // Меняются только поля из маски
+    let fields = UserFields { email: " alice@x.com ".into(), display_name: "Alice".into(), given_name: "Alice".into(), ..Default::default() };
+    let updated = api.update_user(intercepted(Some(&admin_token), update("alice", fields, &["email", "display_name"])).unwrap()).await.unwrap().into_inner();
+    assert_eq!((updated.email.as_str(), updated.display_name.as_str()), ("alice@x.com", "Alice"));
+    assert!(service.get_user(alice.id).await.unwrap().unwrap().given_name.is_none());
+
+    // Пустая строка в поле из маски очищает его, поле вне маски остаётся
+    let updated = api.update_user(intercepted(Some(&admin_token), update("alice", UserFields::default(), &["email"])).unwrap()).await.unwrap().into_inner();
+    assert_eq!((updated.email.as_str(), updated.display_name.as_str()), ("", "Alice"));
+
+    // Переименование через маску; пустое имя, пустая маска и неизвестное поле — INVALID_ARGUMENT
+    let fields = UserFields { username: "alice2".into(), ..Default::default() };
+    let renamed = api.update_user(intercepted(Some(&admin_token), update("alice", fields, &["username"])).unwrap()).await.unwrap().into_inner();
+    assert_eq!(renamed.username, "alice2");
+    assert!(service.find_user_by_username("alice").await.unwrap().is_none());
+    for (fields, paths) in [(UserFields::default(), &["username"][..]), (UserFields::default(), &[]), (UserFields::default(), &["enabled"])] {
+        let error = api.update_user(intercepted(Some(&admin_token), update("alice2", fields, paths)).unwrap()).await.unwrap_err();
+        assert_eq!(error.code(), Code::InvalidArgument);
+    }
+    let error = api.update_user(intercepted(Some(&admin_token), update("nobody", UserFields::default(), &["email"])).unwrap()).await.unwrap_err();
+    assert_eq!(error.code(), Code::NotFound);
+
+    // Отключение и включение
+    let disabled = api.disable_user(intercepted(Some(&admin_token), enable("alice2")).unwrap()).await.unwrap().into_inner();
+    assert!(!disabled.enabled);
+    assert!(!service.get_user(alice.id).await.unwrap().unwrap().enabled);
+    let enabled = api.enable_user(intercepted(Some(&admin_token), enable("alice2")).unwrap()).await.unwrap().into_inner();
+    assert!(enabled.enabled);
+
+    // Пароль проверяется политикой; принятый пароль действует для входа
+    for weak in ["", "short"] {
+        let error = api.set_password(intercepted(Some(&admin_token), password("alice2", weak)).unwrap()).await.unwrap_err();
+        assert_eq!(error.code(), Code::InvalidArgument);
+    }
+    assert!(service.authenticate("alice2", "short").await.is_err());
+    api.set_password(intercepted(Some(&admin_token), password("alice2", "Str0ng-Passw0rd!")).unwrap()).await.unwrap();
+    assert_eq!(service.authenticate("alice2", "Str0ng-Passw0rd!").await.unwrap().id, alice.id);
+
+    // Удаление: неизвестный пользователь — NOT_FOUND
+    let error = api.delete_user(intercepted(Some(&admin_token), DeleteUserRequest { username: "nobody".into() }).unwrap()).await.unwrap_err();
+    assert_eq!(error.code(), Code::NotFound);
+    api.delete_user(intercepted(Some(&admin_token), DeleteUserRequest { username: "alice2".into() }).unwrap()).await.unwrap();
+    assert!(service.get_user(alice.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_validate_token_and_introspect() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let api = AuthService::new(service.clone());
+    let (alice, _) = directory.api_key("alice", false, &[scope::DIRECTORY_READ]).await;
+    let (admin, _) = directory.api_key("admin", true, &[scope::DIRECTORY_READ]).await;
+    let sales = Group::new("Sales".into(), "Sales".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&sales).await.unwrap();
+    service.add_member_to_group(sales.id, alice.id, None).await.unwrap();
+    let sales_sid = service.find_group_by_sam_account_name("Sales").await.unwrap().unwrap().sid.to_string();
+    let admins_sid = service.find_group_by_sam_account_name("Domain Admins").await.unwrap().unwrap().sid.to_string();
+
+    let validate = |token: String| api.validate_token(Request::new(ValidateTokenRequest { token }));
+    let introspect = |token: String| api.introspect(Request::new(IntrospectRequest { token, token_type_hint: "access_token".into() }));
+
+    // Токен сессии: настоящие сроки, роли и SID групп пользователя
+    let session = service.open_session(alice.id, LoginProtocol::Grpc, None, None).await.unwrap();
+    let token = nextDomen::auth::generate_token(&session).unwrap();
+    let valid = validate(token.clone()).await.unwrap().into_inner();
+    assert!(valid.valid);
+    assert_eq!((valid.user_id, valid.username.as_str()), (alice.id.to_string(), "alice"));
+    assert_eq!(valid.expires_at, session.expires_at.timestamp());
+    assert_eq!(valid.roles, ["user"]);
+    assert_eq!(valid.group_sids, std::slice::from_ref(&sales_sid));
+
+    let active = introspect(token.clone()).await.unwrap().into_inner();
+    assert!(active.active);
+    assert_eq!((active.sub, active.username.as_str(), active.token_type.as_str()), (alice.id.to_string(), "alice", "Bearer"));
+    assert_eq!((active.iat, active.exp), (session.issued_at.timestamp(), session.expires_at.timestamp()));
+    assert_eq!((active.scope.as_str(), active.group_sids), ("user", vec![sales_sid]));
+
+    // Domain Admins получают роль domain_admin и SID группы
+    let admin_info = introspect(jwt(&admin)).await.unwrap().into_inner();
+    assert_eq!((admin_info.scope.as_str(), admin_info.group_sids), ("user domain_admin", vec![admins_sid]));
+
+    // Истёкший, испорченный и отозванный токены — только active = false
+    let now = chrono::Utc::now().timestamp() as usize;
+    let claims = nextDomen::auth::Claims { sub: alice.id.to_string(), exp: now - 3600, iat: now - 7200, jti: None };
+    let expired = nextDomen::auth::sign_claims(&claims, None).unwrap();
+    let mut tampered = token.clone();
+    tampered.truncate(tampered.rfind('.').unwrap() + 1);
+    tampered.push_str("AAAA");
+    for token in [expired, tampered, "not-a-jwt".to_string()] {
+        assert!(!validate(token.clone()).await.unwrap().into_inner().valid);
+        assert_eq!(introspect(token).await.unwrap().into_inner(), IntrospectResponse::default());
+    }
+
+    service.revoke_session(session.id).await.unwrap();
+    assert_eq!(validate(token.clone()).await.unwrap().into_inner(), Default::default());
+    assert_eq!(introspect(token).await.unwrap().into_inner(), IntrospectResponse::default());
+
+    // Отключённая учётная запись — тоже недействительный токен
+    let token = jwt(&admin);
+    service.set_user_enabled(admin.id, false).await.unwrap();
+    assert!(!validate(token.clone()).await.unwrap().into_inner().valid);
+    assert!(!introspect(token).await.unwrap().into_inner().active);
+}
+
+#[tokio::test]
+async fn test_audit_query_events() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let api = AuditApiService::new(service.clone());
+    let (alice, _) = directory.api_key("alice", false, &[scope::AUDIT_READ]).await;
+    let (admin, _) = directory.api_key("admin", true, &[scope::AUDIT_READ]).await;
+    let (user_token, admin_token) = (jwt(&alice), jwt(&admin));
+
+    // Три сброса пароля от admin (от старых к новым) и неудачное удаление alice
+    let now = chrono::Utc::now();
+    for (minutes, action, result) in [(40, "reset_password", AuditResult::Success), (30, "reset_password", AuditResult::Success), (20, "reset_password", AuditResult::Success), (10, "delete_user", AuditResult::Failure)] {
+        let mut event = AuditEvent::new(action, result);
+        event.actor_id = Some(admin.id);
+        event.target_id = Some(alice.id);
+        event.ip_addr = Some("10.0.0.7".to_string());
+        event.metadata.insert("minutes_ago".to_string(), minutes.to_string());
+        event.timestamp = now - chrono::Duration::minutes(minutes);
+        service.record(event).await.unwrap();
+    }
+    let query = |token: &str, request: QueryEventsRequest| api.query_events(intercepted(Some(token), request).unwrap());
+
+    // Только Domain Admins; без токена метод не пускает
+    assert_eq!(query(&user_token, QueryEventsRequest::default()).await.unwrap_err().code(), Code::PermissionDenied);
+    assert_eq!(api.query_events(Request::new(QueryEventsRequest::default())).await.unwrap_err().code(), Code::Unauthenticated);
+
+    // Префикс действия и имя вместо UUID; события от новых к старым
+    let request = QueryEventsRequest { action: "reset_*".into(), actor: "admin".into(), ..Default::default() };
+    let page = query(&admin_token, request).await.unwrap().into_inner();
+    assert_eq!((page.total, page.page, page.per_page), (3, 1, 50));
+    let ages: Vec<&str> = page.events.iter().map(|event| event.metadata["minutes_ago"].as_str()).collect();
+    assert_eq!(ages, ["20", "30", "40"]);
+
+    // Вторая страница по два события
+    let request = QueryEventsRequest { action: "reset_*".into(), actor: admin.id.to_string(), page: 2, per_page: 2, ..Default::default() };
+    let second = query(&admin_token, request).await.unwrap().into_inner();
+    assert_eq!((second.total, second.page, second.per_page), (3, 2, 2));
+    assert_eq!(second.events.len(), 1);
+    assert_eq!(second.events[0].id, page.events[2].id);
+    let request = QueryEventsRequest { action: "reset_*".into(), actor: "admin".into(), per_page: 10_000, ..Default::default() };
+    assert_eq!(query(&admin_token, request).await.unwrap().into_inner().per_page, 500);
+
+    // Цель и результат; поля события переданы как есть
+    let request = QueryEventsRequest { target: "alice".into(), result: "failure".into(), ..Default::default() };
+    let failed = query(&admin_token, request).await.unwrap().into_inner();
+    assert_eq!(failed.total, 1);
+    let event = &failed.events[0];
+    assert_eq!((event.action.as_str(), event.result.as_str(), event.ip_address.as_str()), ("delete_user", "failure", "10.0.0.7"));
+    assert_eq!((event.actor_id.clone(), event.target_id.clone()), (admin.id.to_string(), alice.id.to_string()));
+    assert_eq!(event.timestamp, (now - chrono::Duration::minutes(10)).timestamp());
+
+    // Интервал времени
+    let from = (now - chrono::Duration::minutes(25)).to_rfc3339();
+    let request = QueryEventsRequest { actor: "admin".into(), from, ..Default::default() };
+    assert_eq!(query(&admin_token, request).await.unwrap().into_inner().total, 2);
+
+    // Негодные фильтры
+    for (request, code) in [
+        (QueryEventsRequest { result: "maybe".into(), ..Default::default() }, Code::InvalidArgument),
+        (QueryEventsRequest { from: "yesterday".into(), ..Default::default() }, Code::InvalidArgument),
+        (QueryEventsRequest { actor: "nobody".into(), ..Default::default() }, Code::NotFound),
+    ] {
+        assert_eq!(query(&admin_token, request).await.unwrap_err().code(), code);
+    }
+}
+
+/// Сертификат и ключ (PEM), выпущенные `ca` для SAN `sans`
+fn issue(ca: &CertificateAuthority, kind: CertificateKind, sans: &[&str]) -> (String, String) {
+    let (csr, key) = ca::generate_csr(sans[0]).unwrap();
+    let subject = CertificateSubject { common_name: sans[0].into(), kind, sans: sans.iter().map(|san| san.to_string()).collect() };
+    (ca::sign(ca, &csr, &subject, chrono::Duration::days(1), None).unwrap().pem, key)
+}
+
+/// `GetUser` по TLS с клиентским сертификатом `client` и токеном `token`
+async fn get_over_tls(port: u16, ca_pem: &str, client: &(String, String), token: &str) -> Result<GetUserResponse, Status> {
+    let tls = ClientTlsConfig::new()
+        .ca_certificate(Certificate::from_pem(ca_pem))
+        .identity(Identity::from_pem(&client.0, &client.1))
+        .domain_name("localhost");
+    let endpoint = Channel::from_shared(format!("https://localhost:{}", port)).unwrap().tls_config(tls).unwrap();
+    // Сервер мог ещё не начать слушать
+    let mut channel = None;
+    for _ in 0..50 {
+        match endpoint.connect().await {
+            Ok(connected) => {
+                channel = Some(connected);
+                break;
+            }
+            Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    }
+    let mut grpc = tonic::client::Grpc::new(channel.expect("gRPC server is listening"));
+    grpc.ready().await.unwrap();
+
+    let mut request = Request::new(get("alice"));
+    request.metadata_mut().insert("authorization", format!("Bearer {}", token).parse().unwrap());
+    let path = PathAndQuery::from_static("/user_api.UserApi/GetUser");
+    grpc.unary(request, path, tonic::codec::ProstCodec::default()).await.map(|response| response.into_inner())
+}
+
+#[tokio::test]
+async fn test_mtls_client_certificates() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let (alice, _) = directory.api_key("alice", false, &[scope::DIRECTORY_READ]).await;
+    directory.api_key("bob", false, &[scope::DIRECTORY_READ]).await;
+    service.register_spn(alice.id, "HOST/app01.x.com").await.unwrap();
+
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let write = |name: &str, pem: &str| -> String {
+        let path = Path::new(&dir).join(name);
+        std::fs::write(&path, pem).unwrap();
+        path.display().to_string()
+    };
+    let authority = ca::generate("Test CA", chrono::Duration::days(1)).unwrap();
+    let (server_cert, server_key) = issue(&authority, CertificateKind::Computer, &["localhost"]);
+
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let config: ServerConfig = serde_yaml::from_str(&format!(
+        "enable_tls: true\ntls:\n  cert_file: {}\n  key_file: {}\n  ca_cert_file: {}\n  client_auth_required: true\n  san_accounts:\n    alice@x.com: alice\n    bob@x.com: bob\n",
+        write("server.pem", &server_cert), write("server-key.pem", &server_key), write("ca.pem", &authority.cert_pem),
+    )).unwrap();
+    let server = tokio::spawn({
+        let service = service.clone();
+        async move { nextDomen::grpc::run_grpc_server(service, &[format!("127.0.0.1:{}", port)], &config).await.unwrap() }
+    });
+
+    let token = jwt(&alice);
+    // SAN из san_accounts и DNS-имя по SPN `HOST/...` — учётная запись токена
+    for sans in [&["alice@x.com"][..], &["app01.x.com"]] {
+        let kind = if sans[0].contains('@') { CertificateKind::User } else { CertificateKind::Computer };
+        let found = get_over_tls(port, &authority.cert_pem, &issue(&authority, kind, sans), &token).await.unwrap();
+        assert_eq!(found.username, "alice", "{:?}", sans);
+    }
+
+    // SAN без сопоставления и SAN чужой учётной записи отклоняются
+    let unmapped = issue(&authority, CertificateKind::Computer, &["web01.x.com"]);
+    let error = get_over_tls(port, &authority.cert_pem, &unmapped, &token).await.unwrap_err();
+    assert_eq!(error.code(), Code::Unauthenticated, "{}", error.message());
+    let unmapped_email = issue(&authority, CertificateKind::User, &["carol@x.com"]);
+    assert_eq!(get_over_tls(port, &authority.cert_pem, &unmapped_email, &token).await.unwrap_err().code(), Code::Unauthenticated);
+    let mismatched = issue(&authority, CertificateKind::User, &["bob@x.com"]);
+    let error = get_over_tls(port, &authority.cert_pem, &mismatched, &token).await.unwrap_err();
+    assert_eq!(error.code(), Code::PermissionDenied, "{}", error.message());
+
+    server.abort();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
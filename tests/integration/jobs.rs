@@ -0,0 +1,65 @@
+// tests/integration/jobs.rs
+
+use chrono::{TimeZone, Utc};
+
+use nextDomen::config::AppConfig;
+use nextDomen::jobs::{JobError, JobRegistry, Schedule};
+use nextDomen::models::JobTrigger;
+
+use super::TestDirectory;
+
+fn config(yaml: &str) -> AppConfig {
+    let base = serde_yaml::from_str(&format!("db_path: x\nmaster_key_hex: y\n{}", yaml)).unwrap();
+    AppConfig::from_yaml_with_env(base, Vec::new()).unwrap()
+}
+
+#[test]
+fn test_job_schedules() {
+    let at = |d, h, m| Utc.with_ymd_and_hms(2026, 3, d, h, m, 0).unwrap();
+    let next = |expression: &str, after| Schedule::parse(expression).unwrap().next_after(after);
+
+    // 2026-03-06 — пятница
+    assert_eq!(next("*/15 * * * *", at(6, 10, 7)), Some(at(6, 10, 15)));
+    assert_eq!(next("0 3 * * 1-5", at(6, 4, 0)), Some(at(9, 3, 0)));
+    assert_eq!(next("@daily", at(6, 0, 0)), Some(at(7, 0, 0)));
+    assert_eq!(next("30 12 * * 7", at(6, 0, 0)), Some(at(8, 12, 30)));
+    // День месяца или день недели
+    assert_eq!(next("0 0 10 * 1", at(6, 0, 0)), Some(at(9, 0, 0)));
+    assert_eq!(next("@every 10m", at(6, 0, 0)), Some(at(6, 0, 10)));
+    assert_eq!(next("0 0 30 2 *", at(6, 0, 0)), None);
+
+    for invalid in ["* * * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "@every", "@every 0s", "@every 5w"] {
+        assert!(matches!(Schedule::parse(invalid), Err(JobError::InvalidSchedule(_))), "{}", invalid);
+    }
+}
+
+#[tokio::test]
+async fn test_job_registry() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+
+    let registry = JobRegistry::builtin(&config("groups:\n  expiration_check_secs: 120\n")).unwrap();
+    assert_eq!(registry.get("group_expiration").unwrap().schedule_expression, "@every 120s");
+    assert!(!registry.get("dynamic_group_refresh").unwrap().enabled);
+
+    let registry = JobRegistry::builtin(&config("jobs:\n  dynamic_group_refresh:\n    schedule: \"*/5 * * * *\"\n  group_expiration:\n    enabled: false\n")).unwrap();
+    assert!(registry.get("dynamic_group_refresh").unwrap().enabled);
+    assert!(!registry.get("group_expiration").unwrap().enabled);
+    assert!(matches!(JobRegistry::builtin(&config("jobs:\n  tombstone_purge: {}\n")), Err(JobError::UnknownJob(_))));
+
+    // Ручной запуск сохраняется в историю и передаётся подписчикам событий
+    let mut events = service.events().subscribe();
+    let run = registry.run_job(service, "group_expiration", JobTrigger::Manual).await.unwrap();
+    assert_eq!(run.processed, Some(0));
+    let event = events.recv().await.unwrap();
+    assert_eq!((event.action.as_str(), event.metadata["job"].as_str()), ("job_run", "group_expiration"));
+    registry.run_job(service, "group_expiration", JobTrigger::Manual).await.unwrap();
+    assert_eq!(service.get_job_runs("group_expiration").await.unwrap().len(), 2);
+    assert!(matches!(registry.run_job(service, "nope", JobTrigger::Manual).await, Err(JobError::UnknownJob(_))));
+
+    let status = registry.status(service).await.unwrap();
+    let expiration = status.iter().find(|job| job.name == "group_expiration").unwrap();
+    assert!(expiration.last_run.is_some() && expiration.next_run.is_none());
+    let refresh = status.iter().find(|job| job.name == "dynamic_group_refresh").unwrap();
+    assert!(refresh.last_run.is_none() && refresh.next_run.is_some());
+}
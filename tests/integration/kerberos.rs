@@ -0,0 +1,245 @@
+// tests/integration/kerberos.rs
+
+use std::sync::Arc;
+
+use chrono::{TimeZone, Utc};
+use nextDomen::kerberos::messages::{EncryptedData, PrincipalName};
+use nextDomen::kerberos::{
+    crypto, der, Kdc, KDC_ERR_CLIENT_REVOKED, KDC_ERR_C_PRINCIPAL_UNKNOWN, KDC_ERR_PREAUTH_FAILED, KDC_ERR_PREAUTH_REQUIRED,
+    KDC_ERR_SERVICE_REVOKED, KDC_ERR_S_PRINCIPAL_UNKNOWN,
+};
+use nextDomen::ldif::DuplicatePolicy;
+
+use super::TestDirectory;
+
+#[test]
+fn test_string_to_key_rfc3962_vectors() {
+    let vectors: &[(&str, &str, u32, &str)] = &[
+        ("password", "ATHENA.MIT.EDUraeburn", 1, "fe697b52bc0d3ce14432ba036a92e65bbb52280990a2fa27883998d72af30161"),
+        ("password", "ATHENA.MIT.EDUraeburn", 2, "a2e16d16b36069c135d5e9d2e25f896102685618b95914b467c67622225824ff"),
+        ("password", "ATHENA.MIT.EDUraeburn", 1200, "55a6ac740ad17b4846941051e1e8b0a7548d93b0ab30a8bc3ff16280382b8c2a"),
+        ("password", "\x12\x34\x56\x78\x78\x56\x34\x12", 5, "97a4e786be20d81a382d5ebc96d5909cabcdadc87ca48f574504159f16c36e31"),
+        (
+            "XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX",
+            "pass phrase equals block size",
+            1200,
+            "89adee3608db8bc71f1bfbfe459486b05618b70cbae22092534e56c553ba4b34",
+        ),
+        (
+            "XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX",
+            "pass phrase exceeds block size",
+            1200,
+            "d78c5c9cb872a8c9dad4697f0bb5b2d21496c82beb2caeda2112fceea057401b",
+        ),
+        ("\u{1D11E}", "EXAMPLE.COMpianist", 50, "4b6d9839f84406df1f09cc166db4b83c571848b784a3d6bdc346589a3e393f9e"),
+    ];
+    for (password, salt, iterations, expected) in vectors {
+        assert_eq!(hex::encode(crypto::string_to_key_iter(password, salt, *iterations)), *expected, "{} iterations", iterations);
+    }
+}
+
+#[test]
+fn test_string_to_key_uses_default_iterations() {
+    assert_eq!(
+        crypto::string_to_key("password", "ATHENA.MIT.EDUraeburn"),
+        crypto::string_to_key_iter("password", "ATHENA.MIT.EDUraeburn", 4096),
+    );
+}
+
+#[test]
+fn test_encrypt_decrypt_round_trip() {
+    let key = crypto::random_key();
+    assert_eq!(key.len(), 32);
+    // Пустой текст, неполный блок и несколько блоков (ciphertext stealing)
+    for len in [0, 5, 16, 17, 31, 32, 100] {
+        let plaintext: Vec<u8> = (0..len as u8).collect();
+        let ciphertext = crypto::encrypt(&key, 3, &plaintext).unwrap();
+        assert_eq!(ciphertext.len(), 16 + len + 12);
+        assert_eq!(crypto::decrypt(&key, 3, &ciphertext).unwrap(), plaintext);
+    }
+}
+
+#[test]
+fn test_decrypt_rejects_tampering_and_wrong_usage() {
+    let key = crypto::string_to_key("Passw0rd!", "X.COMadmin");
+    let mut ciphertext = crypto::encrypt(&key, 1, b"encrypted timestamp").unwrap();
+
+    assert!(crypto::decrypt(&key, 2, &ciphertext).is_err());
+    assert!(crypto::decrypt(&crypto::random_key(), 1, &ciphertext).is_err());
+    assert!(crypto::decrypt(&key, 1, &ciphertext[..20]).is_err());
+
+    ciphertext[20] ^= 0x01;
+    assert!(crypto::decrypt(&key, 1, &ciphertext).is_err());
+}
+
+#[test]
+fn test_der_integer_encoding() {
+    assert_eq!(der::integer(0), [0x02, 0x01, 0x00]);
+    assert_eq!(der::integer(127), [0x02, 0x01, 0x7F]);
+    assert_eq!(der::integer(128), [0x02, 0x02, 0x00, 0x80]);
+    assert_eq!(der::integer(256), [0x02, 0x02, 0x01, 0x00]);
+    assert_eq!(der::integer(-1), [0x02, 0x01, 0xFF]);
+    assert_eq!(der::integer(-129), [0x02, 0x02, 0xFF, 0x7F]);
+
+    for n in [0, 1, -1, 127, 128, -128, -129, 65535, i32::MAX as i64, i64::MIN, i64::MAX] {
+        assert_eq!(der::Tlv::parse(&der::integer(n)).unwrap().as_integer().unwrap(), n);
+    }
+}
+
+#[test]
+fn test_der_long_length() {
+    let content = vec![0xAB; 300];
+    let encoded = der::octet_string(&content);
+    assert_eq!(&encoded[..4], &[0x04, 0x82, 0x01, 0x2C]);
+    assert_eq!(der::Tlv::parse(&encoded).unwrap().as_bytes().unwrap(), &content[..]);
+}
+
+#[test]
+fn test_der_message_round_trip() {
+    let till = Utc.with_ymd_and_hms(2037, 9, 13, 2, 48, 5).unwrap();
+    // Упрощённый KDC-REQ-BODY: [APPLICATION 10] SEQUENCE { [0] flags, [1] realm, [2] till, [3] nonce }
+    let encoded = der::application(10, der::sequence(&[
+        der::context(0, der::flags(0x4081_0010)),
+        der::context(1, der::general_string("X.COM")),
+        der::context(2, der::time(&till)),
+        der::context(3, der::integer(123_456_789)),
+    ]));
+
+    let body = der::Tlv::parse(&encoded).unwrap().unwrap_application(10).unwrap();
+    assert_eq!(body.tag, der::TAG_SEQUENCE);
+    assert_eq!(body.children().unwrap().len(), 4);
+    assert_eq!(body.required(0).unwrap().as_flags().unwrap(), 0x4081_0010);
+    assert_eq!(body.required(1).unwrap().as_string().unwrap(), "X.COM");
+    assert_eq!(body.required(2).unwrap().as_time().unwrap(), till);
+    assert_eq!(body.required(3).unwrap().as_integer().unwrap(), 123_456_789);
+    assert!(body.field(4).unwrap().is_none());
+    assert!(body.required(4).is_err());
+
+    assert_eq!(der::time(&till), [&[0x18, 0x0F][..], b"20370913024805Z"].concat());
+    assert!(der::Tlv::parse(&encoded).unwrap().unwrap_application(11).is_err());
+}
+
+#[test]
+fn test_der_rejects_malformed_input() {
+    assert!(der::Tlv::parse(&[0x04]).is_err());
+    assert!(der::Tlv::parse(&[0x04, 0x05, 0x01]).is_err());
+    assert!(der::Tlv::parse(&[0x04, 0x80]).is_err());
+    assert!(der::Tlv::parse(&[0x04, 0x01, 0x00, 0x00]).is_err());
+    assert!(der::Tlv::parse(&der::octet_string(b"x")).unwrap().as_integer().is_err());
+}
+
+/// AS-REQ для alice@X.COM на krbtgt; `key` — ключ для PA-ENC-TIMESTAMP, без него — без предаутентификации
+fn as_req(key: Option<&[u8]>) -> Vec<u8> {
+    as_req_for(&PrincipalName::principal("alice"), &PrincipalName::service("krbtgt", "X.COM"), key)
+}
+
+fn as_req_for(cname: &PrincipalName, sname: &PrincipalName, key: Option<&[u8]>) -> Vec<u8> {
+    let padata = key.map(|key| {
+        let timestamp = der::sequence(&[der::context(0, der::time(&Utc::now()))]);
+        let enc = EncryptedData { etype: 18, kvno: None, cipher: crypto::encrypt(key, 1, &timestamp).unwrap() };
+        der::context(3, der::sequence(&[der::sequence(&[der::context(1, der::integer(2)), der::context(2, der::octet_string(&enc.encode()))])]))
+    });
+    let body = der::sequence(&[
+        der::context(0, der::flags(0)),
+        der::context(1, cname.encode()),
+        der::context(2, der::general_string("X.COM")),
+        der::context(3, sname.encode()),
+        der::context(7, der::integer(42)),
+        der::context(8, der::sequence(&[der::integer(18)])),
+    ]);
+    let mut fields = vec![der::context(1, der::integer(5)), der::context(2, der::integer(10))];
+    fields.extend(padata);
+    fields.push(der::context(4, body));
+    der::application(10, der::sequence(&fields))
+}
+
+/// Код KRB-ERROR и его e-data; `None` — ответ не ошибка
+fn krb_error(reply: &[u8]) -> Option<(i64, Option<Vec<u8>>)> {
+    let error = der::Tlv::parse(reply).unwrap().unwrap_application(30).ok()?;
+    let e_data = error.field(12).unwrap().map(|e_data| e_data.as_bytes().unwrap().to_vec());
+    Some((error.required(6).unwrap().as_integer().unwrap(), e_data))
+}
+
+#[tokio::test]
+async fn test_as_req_preauth_and_lockout() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif("dn: CN=alice,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: alice\n", DuplicatePolicy::Skip).await.unwrap();
+    let alice = service.find_user_by_username("alice").await.unwrap().unwrap();
+    service.set_password(alice.id, "Passw0rd!").await.unwrap();
+    let kdc = Kdc::new(Arc::clone(service), "X.COM");
+    kdc.ensure_krbtgt().await.unwrap();
+
+    // Без предаутентификации: etype и соль — в PA-ETYPE-INFO2 внутри METHOD-DATA, не в тексте ошибки
+    let (code, e_data) = krb_error(&kdc.handle(&as_req(None), None).await).unwrap();
+    assert_eq!(code, KDC_ERR_PREAUTH_REQUIRED as i64);
+    let method_data = der::Tlv::parse(&e_data.unwrap()).unwrap().children().unwrap();
+    let etype_info2 = method_data.iter()
+        .find(|pa| pa.required(1).unwrap().as_integer().unwrap() == 19)
+        .map(|pa| der::Tlv::parse(pa.required(2).unwrap().as_bytes().unwrap()).unwrap().children().unwrap().remove(0))
+        .unwrap();
+    assert_eq!(etype_info2.required(0).unwrap().as_integer().unwrap(), 18);
+    let salt = etype_info2.required(1).unwrap().as_string().unwrap();
+    let key = service.get_user(alice.id).await.unwrap().unwrap().kerberos_keys[0].clone();
+    assert_eq!(salt, key.salt);
+
+    // Неверный ключ — неудачная попытка, верный сбрасывает счётчик
+    let wrong = crypto::string_to_key("wrong", &salt);
+    let right = crypto::string_to_key("Passw0rd!", &salt);
+    for _ in 0..4 {
+        assert_eq!(krb_error(&kdc.handle(&as_req(Some(&wrong)), None).await).unwrap().0, KDC_ERR_PREAUTH_FAILED as i64);
+    }
+    assert_eq!(service.get_user(alice.id).await.unwrap().unwrap().failed_logins, 4);
+    assert!(krb_error(&kdc.handle(&as_req(Some(&right)), None).await).is_none());
+    assert_eq!(service.get_user(alice.id).await.unwrap().unwrap().failed_logins, 0);
+
+    // После lockout_threshold (5) неудач учётная запись заблокирована и для Kerberos, и для пароля
+    for _ in 0..5 {
+        kdc.handle(&as_req(Some(&wrong)), None).await;
+    }
+    assert!(service.get_user(alice.id).await.unwrap().unwrap().is_locked_out());
+    assert_eq!(krb_error(&kdc.handle(&as_req(Some(&right)), None).await).unwrap().0, KDC_ERR_CLIENT_REVOKED as i64);
+    assert!(service.authenticate("alice", "Passw0rd!").await.is_err());
+}
+
+#[tokio::test]
+async fn test_principal_checks() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(
+        "dn: CN=alice,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: alice\n\n\
+         dn: CN=web,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: web\n",
+        DuplicatePolicy::Skip,
+    ).await.unwrap();
+    let alice = service.find_user_by_username("alice").await.unwrap().unwrap();
+    let web = service.find_user_by_username("web").await.unwrap().unwrap();
+    service.set_password(alice.id, "Passw0rd!").await.unwrap();
+    service.set_password(web.id, "Web-Passw0rd!").await.unwrap();
+    let kdc = Kdc::new(Arc::clone(service), "X.COM");
+    kdc.ensure_krbtgt().await.unwrap();
+    let salt = service.get_user(alice.id).await.unwrap().unwrap().kerberos_keys[0].salt.clone();
+    let key = crypto::string_to_key("Passw0rd!", &salt);
+    let alice_name = PrincipalName::principal("alice");
+
+    // Лишние компоненты имени клиента не отбрасываются: alice/admin — не alice
+    let admin = PrincipalName { name_type: 1, components: vec!["alice".to_string(), "admin".to_string()] };
+    let reply = kdc.handle(&as_req_for(&admin, &PrincipalName::service("krbtgt", "X.COM"), Some(&key)), None).await;
+    assert_eq!(krb_error(&reply).unwrap().0, KDC_ERR_C_PRINCIPAL_UNKNOWN as i64);
+
+    // Билет выдаётся только по зарегистрированному SPN, не по имени учётной записи
+    let reply = kdc.handle(&as_req_for(&alice_name, &PrincipalName::principal("web"), Some(&key)), None).await;
+    assert_eq!(krb_error(&reply).unwrap().0, KDC_ERR_S_PRINCIPAL_UNKNOWN as i64);
+    let reply = kdc.handle(&as_req_for(&alice_name, &PrincipalName::principal("alice"), Some(&key)), None).await;
+    assert_eq!(krb_error(&reply).unwrap().0, KDC_ERR_S_PRINCIPAL_UNKNOWN as i64);
+    service.register_spn(web.id, "HTTP/web.x.com").await.unwrap();
+
+    // Билет для включённой службы выдаётся, для отключённой — нет
+    let web_name = PrincipalName::service("HTTP", "web.x.com");
+    assert!(krb_error(&kdc.handle(&as_req_for(&alice_name, &web_name, Some(&key)), None).await).is_none());
+    service.set_user_enabled(web.id, false).await.unwrap();
+    let reply = kdc.handle(&as_req_for(&alice_name, &web_name, Some(&key)), None).await;
+    assert_eq!(krb_error(&reply).unwrap().0, KDC_ERR_SERVICE_REVOKED as i64);
+
+    // Отключённая krbtgt по-прежнему выдаёт TGT
+    assert!(krb_error(&kdc.handle(&as_req(Some(&key)), None).await).is_none());
+}
@@ -0,0 +1,292 @@
+// tests/integration/ldap.rs
+
+use std::net::SocketAddr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use ldap3::controls::RawControl;
+use ldap3::{LdapConnAsync, Scope, SearchEntry, SearchOptions, SearchResult};
+use nextDomen::config::LdapServerConfig;
+use nextDomen::ldap::asn1::{Asn1, Asn1Parser};
+use nextDomen::ldap::LdapServer;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::{well_known, AccessMask, Ace, AceFlags, Group, GroupScope, GroupTypeFlags, SecuredObject, SecurityDescriptor};
+
+use super::TestDirectory;
+
+/// LDAP_SERVER_DIRSYNC_OID
+const DIRSYNC_OID: &str = "1.2.840.113556.1.4.841";
+
+const PASSWORD: &str = "Correct-Horse-Battery-9";
+
+const USERS_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+mail: bob@x.com
+
+dn: CN=alice,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: alice
+";
+
+/// Каталог с bob (пароль `PASSWORD`, участник Sales) и alice и сервер LDAP на свободном порту
+async fn serve() -> (TestDirectory, SocketAddr) {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(USERS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    service.set_password(bob.id, PASSWORD).await.unwrap();
+    let sales = Group::new("Sales".into(), "Sales".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&sales).await.unwrap();
+    service.add_member_to_group(sales.id, bob.id, None).await.unwrap();
+
+    let server = LdapServer::bind(std::sync::Arc::clone(service), "127.0.0.1:0").await.unwrap();
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(async move { server.run().await });
+    (directory, addr)
+}
+
+/// Значения атрибута байтами: ldap3 кладёт двоичные значения, которые оказались UTF-8, в `attrs`
+fn binary(entry: &SearchEntry, name: &str) -> Vec<Vec<u8>> {
+    let text = entry.attrs.get(name).into_iter().flatten().map(|value| value.as_bytes().to_vec());
+    text.chain(entry.bin_attrs.get(name).into_iter().flatten().cloned()).collect()
+}
+
+async fn connect(addr: SocketAddr) -> ldap3::Ldap {
+    let (conn, ldap) = LdapConnAsync::new(&format!("ldap://{}", addr)).await.unwrap();
+    ldap3::drive!(conn);
+    ldap
+}
+
+#[tokio::test]
+async fn test_ldap_bind() {
+    let (directory, addr) = serve().await;
+    let mut ldap = connect(addr).await;
+
+    assert_eq!(ldap.simple_bind("CN=bob,CN=Users,DC=x,DC=com", "wrong").await.unwrap().rc, 49);
+    assert_eq!(ldap.simple_bind("nobody", PASSWORD).await.unwrap().rc, 49);
+    // Имя bind — DN, UPN или sAMAccountName
+    ldap.simple_bind("CN=bob,CN=Users,DC=x,DC=com", PASSWORD).await.unwrap().success().unwrap();
+    ldap.simple_bind("bob@x.com", PASSWORD).await.unwrap().success().unwrap();
+    ldap.simple_bind("bob", PASSWORD).await.unwrap().success().unwrap();
+    // Пустое имя — анонимный доступ
+    ldap.simple_bind("", "").await.unwrap().success().unwrap();
+    ldap.unbind().await.unwrap();
+
+    let bob = directory.service.find_user_by_username("bob").await.unwrap().unwrap();
+    let history = directory.service.get_login_history(bob.id).await.unwrap();
+    assert_eq!(history.iter().filter(|login| login.success).count(), 3);
+    assert_eq!(history.iter().filter(|login| !login.success).count(), 1);
+    assert_eq!(history[0].ip_addr.as_deref(), Some("127.0.0.1"));
+}
+
+#[tokio::test]
+async fn test_ldap_search() {
+    let (directory, addr) = serve().await;
+    let mut ldap = connect(addr).await;
+    ldap.simple_bind("bob", PASSWORD).await.unwrap().success().unwrap();
+    let bob = directory.service.find_user_by_username("bob").await.unwrap().unwrap();
+
+    let (entries, _) = ldap.search("DC=x,DC=com", Scope::Subtree, "(&(objectClass=user)(sAMAccountName=bob))", vec!["*"])
+        .await.unwrap().success().unwrap();
+    assert_eq!(entries.len(), 1);
+    let entry = SearchEntry::construct(entries.into_iter().next().unwrap());
+    assert_eq!(entry.dn, "CN=bob,CN=Users,DC=x,DC=com");
+    assert_eq!(entry.attrs["mail"], ["bob@x.com"]);
+    assert_eq!(entry.attrs["memberOf"], ["CN=Sales,CN=Users,DC=x,DC=com"]);
+    // Двоичные значения приходят байтами
+    assert_eq!(binary(&entry, "objectGUID"), [bob.id.to_bytes_le().to_vec()]);
+
+    let names = |entries: Vec<ldap3::ResultEntry>| {
+        let mut names: Vec<String> = entries.into_iter().map(|entry| SearchEntry::construct(entry).dn).collect();
+        names.sort();
+        names
+    };
+    let (entries, _) = ldap.search("DC=x,DC=com", Scope::Subtree, "(objectClass=group)", vec!["cn"]).await.unwrap().success().unwrap();
+    assert_eq!(names(entries), ["CN=Sales,CN=Users,DC=x,DC=com"]);
+    let (entries, _) = ldap.search("DC=x,DC=com", Scope::Subtree, "(|(sAMAccountName=al*)(mail=bob@*))", vec!["cn"]).await.unwrap().success().unwrap();
+    assert_eq!(names(entries), ["CN=alice,CN=Users,DC=x,DC=com", "CN=bob,CN=Users,DC=x,DC=com"]);
+    let (entries, _) = ldap.search("DC=x,DC=com", Scope::Subtree, "(&(objectClass=user)(!(memberOf=CN=Sales,CN=Users,DC=x,DC=com)))", vec!["cn"])
+        .await.unwrap().success().unwrap();
+    assert_eq!(names(entries), ["CN=alice,CN=Users,DC=x,DC=com"]);
+    let (entries, _) = ldap.search("DC=x,DC=com", Scope::Subtree, "(anr=ali)", vec!["cn"]).await.unwrap().success().unwrap();
+    assert_eq!(names(entries), ["CN=alice,CN=Users,DC=x,DC=com"]);
+    // Область base — только объект с DN базы
+    let (entries, _) = ldap.search("CN=alice,CN=Users,DC=x,DC=com", Scope::Base, "(objectClass=*)", vec!["cn"]).await.unwrap().success().unwrap();
+    assert_eq!(names(entries), ["CN=alice,CN=Users,DC=x,DC=com"]);
+
+    // sizeLimit клиента: записи до предела и sizeLimitExceeded
+    let SearchResult(entries, result) = ldap.with_search_options(SearchOptions::new().sizelimit(1))
+        .search("DC=x,DC=com", Scope::Subtree, "(objectClass=user)", vec!["cn"]).await.unwrap();
+    assert_eq!((entries.len(), result.rc), (1, 4));
+    ldap.unbind().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_ldap_search_access() {
+    let (directory, addr) = serve().await;
+    let service = &directory.service;
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let alice = service.find_user_by_username("alice").await.unwrap().unwrap();
+    let search = |ldap: &mut ldap3::Ldap, filter: &'static str| {
+        let mut ldap = ldap.clone();
+        async move {
+            let SearchResult(entries, result) = ldap.search("DC=x,DC=com", Scope::Subtree, filter, vec!["cn"]).await.unwrap();
+            let mut names: Vec<String> = entries.into_iter().map(|entry| SearchEntry::construct(entry).dn).collect();
+            names.sort();
+            (result.rc, names)
+        }
+    };
+
+    // Без bind поиск закрыт, пока анонимный доступ не разрешён явно
+    let mut ldap = connect(addr).await;
+    assert_eq!(search(&mut ldap, "(objectClass=user)").await.0, 50);
+    ldap.simple_bind("", "").await.unwrap().success().unwrap();
+    assert_eq!(search(&mut ldap, "(objectClass=user)").await.0, 50);
+
+    // Объект, который DACL закрывает на чтение, не попадает в ответ и не сопоставляется фильтру
+    let mut descriptor = SecurityDescriptor::new(alice.sid.clone());
+    descriptor.dacl.push(Ace::deny(bob.sid.clone(), AccessMask::READ_PROPERTY, AceFlags::empty()));
+    service.set_security_descriptor(SecuredObject::User(alice.id), &descriptor).await.unwrap();
+    ldap.simple_bind("bob", PASSWORD).await.unwrap().success().unwrap();
+    assert_eq!(search(&mut ldap, "(objectClass=user)").await, (0, vec!["CN=bob,CN=Users,DC=x,DC=com".to_string()]));
+    assert_eq!(search(&mut ldap, "(sAMAccountName=alice)").await, (0, Vec::new()));
+
+    // Разрешённый анонимный поиск видит только то, что открыто Everyone
+    let config: LdapServerConfig = serde_yaml::from_str("allow_anonymous_bind: true").unwrap();
+    let server = LdapServer::bind(std::sync::Arc::clone(service), "127.0.0.1:0").await.unwrap().with_anonymous(&config);
+    let anonymous_addr = server.local_addr().unwrap();
+    tokio::spawn(async move { server.run().await });
+    let mut anonymous = connect(anonymous_addr).await;
+    assert_eq!(search(&mut anonymous, "(objectClass=*)").await, (0, Vec::new()));
+    let mut descriptor = SecurityDescriptor::new(bob.sid.clone());
+    descriptor.dacl.push(Ace::allow(well_known::sids::everyone(), AccessMask::READ_PROPERTY, AceFlags::empty()));
+    service.set_security_descriptor(SecuredObject::User(bob.id), &descriptor).await.unwrap();
+    assert_eq!(search(&mut anonymous, "(objectClass=*)").await, (0, vec!["CN=bob,CN=Users,DC=x,DC=com".to_string()]));
+}
+
+/// Значение запроса DirSync: `SEQUENCE { Flags 0, MaxBytes 1 МБ, Cookie }`
+fn dirsync_request(cookie: &[u8]) -> Vec<u8> {
+    let mut body = vec![0x02, 0x01, 0x00, 0x02, 0x03, 0x10, 0x00, 0x00, 0x04, cookie.len() as u8];
+    body.extend_from_slice(cookie);
+    let mut value = vec![0x30, body.len() as u8];
+    value.extend(body);
+    value
+}
+
+/// DN записей ответа DirSync и cookie для следующего запроса
+async fn dirsync(ldap: &mut ldap3::Ldap, cookie: &[u8]) -> (Vec<String>, Vec<u8>) {
+    let control = RawControl { ctype: DIRSYNC_OID.to_string(), crit: true, val: Some(dirsync_request(cookie)) };
+    let (entries, result) = ldap.with_controls(control)
+        .search("DC=x,DC=com", Scope::Subtree, "(objectClass=user)", vec!["cn"])
+        .await.unwrap().success().unwrap();
+    let response = result.ctrls.iter().find(|control| control.1.ctype == DIRSYNC_OID).unwrap();
+    let Ok(Some(Asn1::Sequence(fields))) = Asn1Parser::new(response.1.val.clone().unwrap()).parse() else {
+        panic!("DirSync response value is not a sequence");
+    };
+    let [Asn1::Integer(_), Asn1::Integer(_), Asn1::OctetString(cookie)] = fields.as_slice() else {
+        panic!("unexpected DirSync response {:?}", fields);
+    };
+    let mut names: Vec<String> = entries.into_iter().map(|entry| SearchEntry::construct(entry).dn).collect();
+    names.sort();
+    (names, cookie.clone())
+}
+
+#[tokio::test]
+async fn test_ldap_constructed_attributes_and_dirsync() {
+    let (directory, addr) = serve().await;
+    let service = &directory.service;
+    let mut ldap = connect(addr).await;
+
+    // Без bind DirSync недоступен
+    let control = RawControl { ctype: DIRSYNC_OID.to_string(), crit: true, val: Some(dirsync_request(&[])) };
+    let result = ldap.with_controls(control).search("DC=x,DC=com", Scope::Subtree, "(objectClass=*)", vec!["cn"]).await.unwrap();
+    assert_eq!(result.1.rc, 50);
+
+    ldap.simple_bind("bob", PASSWORD).await.unwrap().success().unwrap();
+    let sales = service.find_group_by_sam_account_name("Sales").await.unwrap().unwrap();
+
+    // tokenGroups — только при области base и только по запросу
+    let (entries, _) = ldap.search("CN=bob,CN=Users,DC=x,DC=com", Scope::Base, "(objectClass=*)", vec!["tokenGroups"])
+        .await.unwrap().success().unwrap();
+    let entry = SearchEntry::construct(entries.into_iter().next().unwrap());
+    assert!(binary(&entry, "tokenGroups").contains(&sales.sid.to_bytes()));
+    let (entries, _) = ldap.search("DC=x,DC=com", Scope::Subtree, "(sAMAccountName=bob)", vec!["tokenGroups"])
+        .await.unwrap().success().unwrap();
+    assert!(binary(&SearchEntry::construct(entries.into_iter().next().unwrap()), "tokenGroups").is_empty());
+    let (entries, _) = ldap.search("DC=x,DC=com", Scope::Subtree, "(cn=Sales)", vec!["primaryGroupToken"])
+        .await.unwrap().success().unwrap();
+    let entry = SearchEntry::construct(entries.into_iter().next().unwrap());
+    assert_eq!(entry.attrs["primaryGroupToken"], [sales.get_rid().to_string()]);
+
+    // Первый DirSync — все пользователи, следующий — только изменившиеся после cookie
+    let (names, cookie) = dirsync(&mut ldap, &[]).await;
+    assert_eq!(names, ["CN=alice,CN=Users,DC=x,DC=com", "CN=bob,CN=Users,DC=x,DC=com"]);
+    let (names, cookie) = dirsync(&mut ldap, &cookie).await;
+    assert!(names.is_empty(), "{:?}", names);
+    let mut alice = service.find_user_by_username("alice").await.unwrap().unwrap();
+    alice.email = Some("alice@x.com".into());
+    service.update_user(&alice).await.unwrap();
+    let (names, _) = dirsync(&mut ldap, &cookie).await;
+    assert_eq!(names, ["CN=alice,CN=Users,DC=x,DC=com"]);
+}
+
+#[tokio::test]
+async fn test_ldap_operation_quota() {
+    let directory = TestDirectory::new().await;
+    let config: LdapServerConfig = serde_yaml::from_str("max_operations_per_connection: 2\nallow_anonymous_bind: true").unwrap();
+    let server = LdapServer::bind(std::sync::Arc::clone(&directory.service), "127.0.0.1:0").await.unwrap()
+        .with_limits(&config)
+        .with_anonymous(&config);
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(async move { server.run().await });
+
+    let mut ldap = connect(addr).await;
+    ldap.simple_bind("", "").await.unwrap().success().unwrap();
+    ldap.search("DC=x,DC=com", Scope::Subtree, "(objectClass=*)", vec!["cn"]).await.unwrap().success().unwrap();
+    // Третья операция — adminLimitExceeded, затем соединение закрывается
+    let result = ldap.search("DC=x,DC=com", Scope::Subtree, "(objectClass=*)", vec!["cn"]).await.unwrap();
+    assert_eq!(result.1.rc, 11);
+    assert!(ldap.search("DC=x,DC=com", Scope::Subtree, "(objectClass=*)", vec!["cn"]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_ldap_behind_proxy_protocol() {
+    let (directory, _) = serve().await;
+    let config: LdapServerConfig = serde_yaml::from_str("proxy: { trusted: [127.0.0.1], proxy_protocol: true }").unwrap();
+    let server = LdapServer::bind(std::sync::Arc::clone(&directory.service), "127.0.0.1:0").await.unwrap()
+        .with_proxies(&config).unwrap();
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(async move { server.run().await });
+
+    // Прокси: каждое соединение начинает заголовком PROXY с адресом клиента 203.0.113.7
+    let relay = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let relay_addr = relay.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut client, _)) = relay.accept().await {
+            let mut upstream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            upstream.write_all(b"PROXY TCP4 203.0.113.7 10.0.0.1 4711 389\r\n").await.unwrap();
+            tokio::spawn(async move { tokio::io::copy_bidirectional(&mut client, &mut upstream).await });
+        }
+    });
+
+    let mut ldap = connect(relay_addr).await;
+    assert_eq!(ldap.simple_bind("bob", "wrong").await.unwrap().rc, 49);
+    ldap.simple_bind("bob", PASSWORD).await.unwrap().success().unwrap();
+    let bob = directory.service.find_user_by_username("bob").await.unwrap().unwrap();
+    let history = directory.service.get_login_history(bob.id).await.unwrap();
+    let addresses: Vec<_> = history.iter().map(|login| login.ip_addr.as_deref()).collect();
+    assert_eq!(addresses, [Some("203.0.113.7"), Some("203.0.113.7")]);
+
+    // Доверенный адрес без заголовка PROXY: соединение закрывается, запрос не обрабатывается
+    let mut direct = tokio::net::TcpStream::connect(addr).await.unwrap();
+    direct.write_all(&[0x30, 0x0c, 0x02, 0x01, 0x01, 0x60, 0x07, 0x02, 0x01, 0x03, 0x04, 0x00, 0x80, 0x00]).await.unwrap();
+    // Непрочитанный запрос на закрытом сокете оборачивается сбросом соединения
+    let mut response = Vec::new();
+    match direct.read_to_end(&mut response).await {
+        Ok(_) => assert!(response.is_empty(), "{:?}", response),
+        Err(e) => assert_eq!(e.kind(), std::io::ErrorKind::ConnectionReset),
+    }
+}
@@ -0,0 +1,152 @@
+// tests/integration/ldif.rs
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use nextDomen::config::ApprovalConfig;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::middleware::API_KEY_HEADER;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::ApprovalOperation;
+
+use super::TestDirectory;
+
+const USERS_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+mail: bob@x.com
+displayName: Bob
+
+dn: CN=erin,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: erin
+
+dn: CN=Engineers,CN=Users,DC=x,DC=com
+objectClass: group
+sAMAccountName: Engineers
+member: CN=bob,CN=Users,DC=x,DC=com
+member: CN=erin,CN=Users,DC=x,DC=com
+";
+
+#[tokio::test]
+async fn test_import_users_and_group() {
+    let directory = TestDirectory::new().await;
+
+    let report = directory.service.import_ldif(USERS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_eq!(report.created, 3);
+
+    let bob = directory.service.find_user_by_username("bob").await.unwrap().unwrap();
+    assert_eq!(bob.email.as_deref(), Some("bob@x.com"));
+    assert_eq!(bob.display_name.as_deref(), Some("Bob"));
+    let erin = directory.service.find_user_by_username("erin").await.unwrap().unwrap();
+
+    let group = directory.service.find_group_by_sam_account_name("Engineers").await.unwrap().unwrap();
+    assert!(group.members.contains(&bob.id));
+    assert!(group.members.contains(&erin.id));
+
+    // Импортированный пароль не переносится: входа нет до сброса
+    assert!(directory.service.authenticate("bob", "Passw0rd!").await.is_err());
+    directory.service.set_password(bob.id, "Passw0rd!").await.unwrap();
+    assert_eq!(directory.service.authenticate("bob", "Passw0rd!").await.unwrap().id, bob.id);
+
+    // Повторный импорт ничего не создаёт
+    let report = directory.service.import_ldif(USERS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert_eq!(report.created, 0);
+    assert_eq!(report.skipped, 3);
+}
+
+#[tokio::test]
+async fn test_contact_round_trip() {
+    use nextDomen::models::{Contact, ContactKind};
+
+    let source = TestDirectory::new().await;
+    let mut contact = Contact::new("Helpdesk".to_string(), "helpdesk@partner.com".to_string());
+    contact.other_emails = vec!["support@partner.com".to_string()];
+    contact.kind = ContactKind::SharedMailbox;
+    source.service.create_contact(&contact).await.unwrap();
+
+    let exported = source.service.export_ldif().await.unwrap();
+    assert!(exported.contains("objectClass: contact"));
+
+    // Контакт наследует person, но не должен превратиться в пользователя
+    let target = TestDirectory::new().await;
+    let report = target.service.import_ldif(&exported, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert!(target.service.find_user_by_email("helpdesk@partner.com").await.unwrap().is_none());
+
+    let imported = target.service.find_contact_by_email("support@partner.com").await.unwrap().unwrap();
+    assert_eq!(imported.email, "helpdesk@partner.com");
+    assert_eq!(imported.kind, ContactKind::SharedMailbox);
+}
+
+#[tokio::test]
+async fn test_import_endpoint_requires_admin() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let (alice, admin_key) = directory.api_key("alice", true, &[scope::DIRECTORY_WRITE]).await;
+    let (_, audit_key) = directory.api_key("auditor", true, &[scope::AUDIT_READ]).await;
+    let (_, user_key) = directory.api_key("mallory", false, &[scope::DIRECTORY_WRITE]).await;
+    let app = directory.router("");
+
+    // Пользователь с выбранным хэшем пароля и группа, которая сделала бы его администратором
+    let ldif = "\
+dn: CN=eve,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: eve
+userPassword: {CRYPT}$2b$12$C6UzMDM.H6dfI/f/IKxGhuYVH4IjXBdZ/2KEZMMsCRDhBUxgGxJ3e
+
+dn: CN=Domain Admins,CN=Users,DC=x,DC=com
+objectClass: group
+sAMAccountName: Domain Admins
+memberUid: eve
+";
+    let import = |key: Option<&str>| {
+        let mut request = Request::post("/api/admin/import/ldif?on_duplicate=update");
+        if let Some(key) = key {
+            request = request.header(API_KEY_HEADER, key);
+        }
+        app.clone().oneshot(request.body(Body::from(ldif)).unwrap())
+    };
+    assert_eq!(import(None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(import(Some(&user_key)).await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert_eq!(import(Some(&audit_key)).await.unwrap().status(), StatusCode::FORBIDDEN);
+    assert!(service.find_user_by_username("eve").await.unwrap().is_none());
+
+    // Участник административной группы из импорта ждёт одобрения, как при добавлении через API
+    service.set_approvals(&ApprovalConfig { enabled: true, operations: vec![ApprovalOperation::AddPrivilegedMember] });
+    let response = import(Some(&admin_key)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(report["pending_approval"], 1, "{}", report);
+    let eve = service.find_user_by_username("eve").await.unwrap().unwrap();
+    assert!(!service.is_domain_admin(eve.id).await.unwrap());
+    let pending = service.list_approvals(None).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].requested_by, alice.id);
+}
+
+#[tokio::test]
+async fn test_update_replaces_members() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(USERS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+
+    // Участники — по DN экспорта (erin, bob с displayName Bob), а не по CN как имени
+    let ldif = "\
+dn: CN=Engineers,CN=Users,DC=x,DC=com
+objectClass: group
+sAMAccountName: Engineers
+member: CN=Bob,CN=Users,DC=x,DC=com
+member: CN=erin,OU=Elsewhere,DC=x,DC=com
+";
+    let report = service.import_ldif(ldif, DuplicatePolicy::Update).await.unwrap();
+    assert_eq!(report.updated, 1);
+    assert_eq!(report.errors, vec!["CN=Engineers,CN=Users,DC=x,DC=com: member not found: CN=erin,OU=Elsewhere,DC=x,DC=com".to_string()]);
+    let group = service.find_group_by_sam_account_name("Engineers").await.unwrap().unwrap();
+    assert_eq!(group.members, vec![bob.id]);
+}
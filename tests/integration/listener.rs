@@ -0,0 +1,153 @@
+// tests/integration/listener.rs
+
+use std::net::SocketAddr;
+use std::os::unix::fs::PermissionsExt;
+
+use axum::extract::ConnectInfo;
+use axum::routing::get;
+use axum::Router;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use nextDomen::config::{RateLimit, RateLimitConfig, TlsConfig, UnixSocketConfig};
+use nextDomen::ratelimit::{ip_key, AuthRateLimiter, LimitScope};
+use nextDomen::tls_bootstrap;
+use nextDomen::web::listener::WebListener;
+
+use super::TestDirectory;
+
+#[tokio::test]
+async fn test_unix_socket_listener() {
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("web.sock");
+    // Сокет, оставшийся от прошлого запуска, заменяется
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+    let config = UnixSocketConfig { mode: "600".into(), group: None };
+    let listener = WebListener::bind(&format!("unix:{}", path.display()), &config).await.unwrap();
+    assert_eq!(listener.describe(), format!("unix:{}", path.display()));
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+    let app = Router::new().route("/peer", get(|ConnectInfo(peer): ConnectInfo<SocketAddr>| async move { peer.ip().to_string() }));
+    let server = tokio::spawn(listener.serve(app, None, Default::default()));
+
+    let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+    stream.write_all(b"GET /peer HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.ends_with("127.0.0.1"), "{}", response);
+
+    server.abort();
+    let _ = server.await;
+    // Сокет удаляется вместе с сервером
+    assert!(!path.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let error = WebListener::bind("unix:/nonexistent-dir/web.sock", &UnixSocketConfig::default()).await.err().unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn test_tls_bootstrap() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+
+    // Самоподписанный, пока нет CA; второй запуск берёт готовые файлы
+    let mut tls = TlsConfig::default();
+    let created = tls_bootstrap::ensure_certificate(service, "web_server", &mut tls, &dir).await.unwrap().unwrap();
+    assert!(!created.ca_signed);
+    assert_eq!(tls.cert_file, Some(dir.join("web_server.pem").display().to_string()));
+    assert_eq!(std::fs::metadata(dir.join("web_server-key.pem")).unwrap().permissions().mode() & 0o777, 0o600);
+    let cert_pem = std::fs::read_to_string(dir.join("web_server.pem")).unwrap();
+    let der = nextDomen::ca::pem_to_der(&cert_pem).unwrap();
+    assert_eq!(tls_bootstrap::fingerprint(&der), created.fingerprint);
+    assert!(tls_bootstrap::ensure_certificate(service, "web_server", &mut TlsConfig::default(), &dir).await.unwrap().is_none());
+
+    // HTTPS с этим сертификатом; адрес клиента виден как обычно
+    let listener = WebListener::bind("127.0.0.1:0", &UnixSocketConfig::default()).await.unwrap();
+    let addr: SocketAddr = listener.describe().parse().unwrap();
+    let acceptor = nextDomen::web::listener::tls_acceptor(&tls).unwrap();
+    let app = Router::new().route("/peer", get(|ConnectInfo(peer): ConnectInfo<SocketAddr>| async move { peer.ip().to_string() }));
+    let server = tokio::spawn(listener.serve(app, Some(acceptor), Default::default()));
+
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(&rustls::Certificate(der)).unwrap();
+    let client = rustls::ClientConfig::builder().with_safe_defaults().with_root_certificates(roots).with_no_client_auth();
+    let connector = tokio_rustls::TlsConnector::from(std::sync::Arc::new(client));
+    let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let mut stream = connector.connect("localhost".try_into().unwrap(), tcp).await.unwrap();
+    stream.write_all(b"GET /peer HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response).await;
+    let response = String::from_utf8(response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.ends_with("127.0.0.1"), "{}", response);
+    server.abort();
+
+    // С CA сертификат подписан им
+    service.init_ca(false).await.unwrap();
+    let created = tls_bootstrap::ensure_certificate(service, "grpc_server", &mut TlsConfig::default(), &dir).await.unwrap().unwrap();
+    assert!(created.ca_signed);
+    let ca_der = nextDomen::ca::pem_to_der(&service.ca_certificate().await.unwrap()).unwrap();
+    let (_, ca) = X509Certificate::from_der(&ca_der).unwrap();
+    let der = nextDomen::ca::pem_to_der(&std::fs::read_to_string(dir.join("grpc_server.pem")).unwrap()).unwrap();
+    let (_, certificate) = X509Certificate::from_der(&der).unwrap();
+    certificate.verify_signature(Some(ca.public_key())).unwrap();
+
+    // Ключ без сертификата не перезаписывается
+    std::fs::remove_file(dir.join("grpc_server.pem")).unwrap();
+    assert!(tls_bootstrap::ensure_certificate(service, "grpc_server", &mut TlsConfig::default(), &dir).await.is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_dual_stack_listeners() {
+    // IPv4 и IPv6 на одном порту — отдельные сокеты
+    let v4 = nextDomen::net::bind_tcp("127.0.0.1:0").await.unwrap();
+    let port = v4.local_addr().unwrap().port();
+    let v6 = nextDomen::net::bind_tcp(&format!("[::1]:{}", port)).await.unwrap();
+    assert!(nextDomen::net::bind_tcp(&format!("127.0.0.1:{}", port)).await.is_err());
+
+    let app = Router::new().route("/peer", get(|ConnectInfo(peer): ConnectInfo<SocketAddr>| async move { peer.ip().to_string() }));
+    let servers: Vec<_> = [v4, v6].into_iter()
+        .map(|listener| tokio::spawn(WebListener::Tcp(listener).serve(app.clone(), None, Default::default())))
+        .collect();
+    for (host, expected) in [("127.0.0.1", "127.0.0.1"), ("[::1]", "::1")] {
+        let mut stream = tokio::net::TcpStream::connect(format!("{}:{}", host, port)).await.unwrap();
+        stream.write_all(b"GET /peer HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.ends_with(expected), "{}", response);
+    }
+    for server in servers {
+        server.abort();
+    }
+}
+
+#[test]
+fn test_ipv6_client_addresses() {
+    // IPv4-клиент двухстекового сокета — это IPv4-адрес
+    let mapped: SocketAddr = "[::ffff:10.0.0.5]:50000".parse().unwrap();
+    assert_eq!(nextDomen::net::client_ip(mapped).to_string(), "10.0.0.5");
+    assert_eq!(ip_key("::ffff:10.0.0.5"), "10.0.0.5");
+    assert_eq!(ip_key("2001:db8:1:2:aaaa::1"), "2001:db8:1:2::/64");
+
+    // Лимит по адресу для IPv6 — общий на /64; исключение совпадает в любой записи адреса
+    let limiter = AuthRateLimiter::new(&RateLimitConfig {
+        per_ip: RateLimit { burst: 1, per_minute: 1 },
+        per_account: RateLimit { burst: 100, per_minute: 100 },
+        exempt_ips: vec!["0:0:0:0:0:0:0:1".to_string()],
+        ..RateLimitConfig::default()
+    });
+    assert!(limiter.check("alice", Some("2001:db8:1:2::10")).is_ok());
+    let limited = limiter.check("bob", Some("2001:db8:1:2::20")).unwrap_err();
+    assert_eq!(limited.scope, LimitScope::Ip);
+    assert!(limiter.check("bob", Some("2001:db8:1:3::20")).is_ok());
+    for _ in 0..3 {
+        assert!(limiter.check("carol", Some("::1")).is_ok());
+    }
+}
@@ -0,0 +1,578 @@
+// tests/integration/logins.rs
+
+use axum::http::StatusCode;
+use nextDomen::audit::actor::ActorContext;
+use nextDomen::config::{AuthProviderConfig, OidcProviderConfig, RiskConfig, SessionConfig, SessionLimitAction};
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::mfa;
+use nextDomen::models::{LdapContext, LdapEntry, LoginProtocol, MfaMethod, RiskAction, SecurityIdentifier, Site, Subnet, UserAccountControl};
+
+use super::{call, request, TestDirectory};
+
+const BOB_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+";
+
+#[tokio::test]
+async fn test_login_history() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+    assert!(bob.last_login.is_none());
+
+    let ip = Some("10.0.0.7".to_string());
+    service.authenticate_from("bob", "Correct-Horse-Battery-9", ip.clone(), LoginProtocol::Rest).await.unwrap();
+    assert!(matches!(
+        service.authenticate_from("bob", "wrong", ip.clone(), LoginProtocol::Ldap).await,
+        Err(DirectoryError::AuthenticationFailed(_))
+    ));
+    service.authenticate_for_nas("bob", "Correct-Horse-Battery-9", Some("10.0.0.1".to_string())).await.unwrap();
+    // Несуществующее имя в историю не попадает
+    assert!(service.authenticate("nobody", "x").await.is_err());
+
+    let history = service.get_login_history(bob.id).await.unwrap();
+    let summary: Vec<(LoginProtocol, bool)> = history.iter().map(|login| (login.protocol, login.success)).collect();
+    assert_eq!(summary, vec![(LoginProtocol::Radius, true), (LoginProtocol::Ldap, false), (LoginProtocol::Rest, true)]);
+    assert_eq!(history[1].ip_addr, ip);
+    assert_eq!(history[1].reason.as_deref(), Some("Invalid username or password"));
+
+    // lastLogon и lastLogonTimestamp — FILETIME последнего удачного входа
+    let bob = service.get_user(bob.id).await.unwrap().unwrap();
+    let last_login = bob.last_login.unwrap();
+    assert!(history[0].timestamp >= last_login);
+    let entry = bob.attributes(&LdapContext::new(service).await.unwrap()).await.unwrap();
+    let filetime: i64 = entry.text("lastLogon")[0].parse().unwrap();
+    assert_eq!((filetime - 116_444_736_000_000_000) / 10_000_000, last_login.timestamp());
+    assert_eq!(entry.get("lastLogonTimestamp"), entry.get("lastLogon"));
+
+    // История — чтение: хватает ключа с `directory:read`
+    let (_, reader) = directory.api_key("auditor", true, &[scope::DIRECTORY_READ]).await;
+    let (status, body) = call(&directory.router(""), request("GET", "/api/users/bob/logins?limit=2", Some(&reader), None)).await;
+    assert_eq!((status, body.as_array().map(Vec::len)), (StatusCode::OK, Some(2)));
+}
+
+#[tokio::test]
+async fn test_login_failures_do_not_reveal_account_state() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+    let failure = |result: Result<_, DirectoryError>| match result {
+        Err(DirectoryError::AuthenticationFailed(message)) => message,
+        other => panic!("unexpected result: {:?}", other.map(|user: nextDomen::models::User| user.username)),
+    };
+
+    // Отключённая учётная запись отвечает так же, как неверный пароль или неизвестное имя
+    service.set_user_enabled(bob.id, false).await.unwrap();
+    assert_eq!(failure(service.authenticate("bob", "Correct-Horse-Battery-9").await), "Invalid username or password");
+    assert_eq!(failure(service.authenticate("bob", "wrong").await), "Invalid username or password");
+    assert_eq!(failure(service.authenticate("nobody", "wrong").await), "Invalid username or password");
+
+    // Настоящая причина — в истории входов и аудите
+    let history = service.get_login_history(bob.id).await.unwrap();
+    assert_eq!(history[0].reason.as_deref(), Some("Account is disabled"));
+}
+
+#[tokio::test]
+async fn test_account_control_matches_account_state() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+    let (_, key) = directory.api_key("admin", true, &[scope::DIRECTORY_WRITE]).await;
+    let app = directory.router("");
+    let flags = |user: &nextDomen::models::User| user.account_control();
+    let uac = |set: &[&str], clear: &[&str]| {
+        request("PUT", "/api/users/bob/account-control", Some(&key), Some(serde_json::json!({ "set": set, "clear": clear })))
+    };
+
+    // Отключение учётной записи видно как ACCOUNTDISABLE, включение его снимает
+    let disabled = service.set_user_enabled(bob.id, false).await.unwrap();
+    assert!(flags(&disabled).contains(UserAccountControl::ACCOUNTDISABLE));
+    let entry = disabled.attributes(&LdapContext::new(service).await.unwrap()).await.unwrap();
+    assert_eq!(entry.get("userAccountControl").unwrap()[0].as_text(), Some("514"));
+    let enabled = service.set_user_enabled(bob.id, true).await.unwrap();
+    assert_eq!(flags(&enabled), UserAccountControl::NORMAL_ACCOUNT);
+
+    // И наоборот: ACCOUNTDISABLE через userAccountControl отключает вход
+    let (status, body) = call(&app, uac(&["ACCOUNTDISABLE", "DONT_EXPIRE_PASSWORD"], &[])).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["enabled"], false);
+    assert!(!service.get_user(bob.id).await.unwrap().unwrap().enabled);
+    assert!(service.authenticate("bob", "Correct-Horse-Battery-9").await.is_err());
+    let (_, body) = call(&app, uac(&[], &["ACCOUNTDISABLE"])).await;
+    assert_eq!(body["enabled"], true);
+    assert_eq!(body["account_flags"], serde_json::json!(["NORMAL_ACCOUNT", "DONT_EXPIRE_PASSWORD"]));
+    service.authenticate("bob", "Correct-Horse-Battery-9").await.unwrap();
+
+    // Блокировка после неудачных попыток видна как LOCKOUT; вручную её не установить
+    for _ in 0..5 {
+        assert!(service.authenticate("bob", "wrong").await.is_err());
+    }
+    let locked = service.get_user(bob.id).await.unwrap().unwrap();
+    assert!(locked.is_locked_out());
+    assert!(flags(&locked).contains(UserAccountControl::LOCKOUT));
+    assert!(locked.enabled, "блокировка не отключает учётную запись");
+    assert_eq!(call(&app, uac(&["LOCKOUT"], &[])).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(call(&app, uac(&["ACCOUNTDISABLE"], &["ACCOUNTDISABLE"])).await.0, StatusCode::BAD_REQUEST);
+
+    // Снятый LOCKOUT снимает блокировку и обнуляет счётчик, остальные флаги не трогает
+    let (_, body) = call(&app, uac(&[], &["LOCKOUT"])).await;
+    assert_eq!(body["locked_out"], false);
+    let unlocked = service.get_user(bob.id).await.unwrap().unwrap();
+    assert!(!unlocked.is_locked_out());
+    assert_eq!(unlocked.failed_logins, 0);
+    assert!(flags(&unlocked).contains(UserAccountControl::DONT_EXPIRE_PASSWORD));
+    service.authenticate("bob", "Correct-Horse-Battery-9").await.unwrap();
+
+    // Вычисляемые флаги не сохраняются: после разблокировки сервисом LOCKOUT тоже нет
+    for _ in 0..5 {
+        let _ = service.authenticate("bob", "wrong").await;
+    }
+    let unlocked = service.unlock_user(bob.id).await.unwrap();
+    assert!(!flags(&unlocked).contains(UserAccountControl::LOCKOUT));
+    assert!(!unlocked.user_account_control.intersects(UserAccountControl::ACCOUNTDISABLE | UserAccountControl::LOCKOUT));
+}
+
+#[tokio::test]
+async fn test_change_own_password() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+
+    // Неверный текущий пароль — отказ в смене и неудачная попытка в истории входов
+    assert!(matches!(
+        service.change_password(bob.id, "wrong", "Staple-Battery-Horse-7", None).await,
+        Err(DirectoryError::AccessDenied(_))
+    ));
+    assert!(!service.get_login_history(bob.id).await.unwrap()[0].success);
+    assert!(matches!(
+        service.change_password(bob.id, "Correct-Horse-Battery-9", "short", None).await,
+        Err(DirectoryError::PasswordPolicy(_))
+    ));
+
+    service.change_password(bob.id, "Correct-Horse-Battery-9", "Staple-Battery-Horse-7", None).await.unwrap();
+    assert!(service.authenticate("bob", "Staple-Battery-Horse-7").await.is_ok());
+    assert!(service.authenticate("bob", "Correct-Horse-Battery-9").await.is_err());
+}
+
+#[tokio::test]
+async fn test_self_service_mfa_requires_password() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let (bob, key) = directory.api_key("bob", false, &[scope::SELF_SERVICE]).await;
+    service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+    let mut user = service.get_user(bob.id).await.unwrap().unwrap();
+    user.mfa_enabled = true;
+    user.mfa_methods = vec![MfaMethod::Totp];
+    user.totp_secret = Some(mfa::generate_totp_secret());
+    service.update_user(&user).await.unwrap();
+    let app = directory.router("");
+
+    // Одного токена мало, чтобы выключить MFA: без пароля и с неверным паролем — отказ
+    let disable = |password: Option<&str>| {
+        let mut body = serde_json::json!({ "enabled": false, "methods": [] });
+        if let Some(password) = password {
+            body["current_password"] = serde_json::json!(password);
+        }
+        call(&app, request("PUT", "/api/me/mfa", Some(&key), Some(body)))
+    };
+    assert!(disable(None).await.0.is_client_error());
+    let (status, body) = disable(Some("wrong")).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("ACCESS_DENIED")));
+    let user = service.get_user(bob.id).await.unwrap().unwrap();
+    assert!(user.mfa_enabled && user.totp_secret.is_some());
+
+    let (status, body) = disable(Some("Correct-Horse-Battery-9")).await;
+    assert_eq!((status, body["enabled"].as_bool()), (StatusCode::OK, Some(false)));
+    let user = service.get_user(bob.id).await.unwrap().unwrap();
+    assert!(!user.mfa_enabled && user.totp_secret.is_none());
+}
+
+#[tokio::test]
+async fn test_self_service_profile() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let (bob, key) = directory.api_key("bob", false, &[scope::SELF_SERVICE]).await;
+    let app = directory.router("");
+
+    // display_name по умолчанию не меняется: из него строится DN
+    let (status, _) = call(&app, request("PATCH", "/api/me", Some(&key), Some(serde_json::json!({ "display_name": "Administrator" })))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = call(&app, request("PATCH", "/api/me", Some(&key), Some(serde_json::json!({ "given_name": "Bob", "surname": "Smith" })))).await;
+    assert_eq!((status, body["given_name"].as_str()), (StatusCode::OK, Some("Bob")));
+    let user = service.get_user(bob.id).await.unwrap().unwrap();
+    assert_eq!((user.given_name.as_deref(), user.surname.as_deref(), user.display_name), (Some("Bob"), Some("Smith"), None));
+}
+
+#[tokio::test]
+async fn test_self_service_sessions() {
+    let directory = TestDirectory::new().await;
+    let (bob, key) = directory.api_key("bob", false, &[scope::SELF_SERVICE]).await;
+    let session = directory.service.open_session(bob.id, LoginProtocol::Rest, Some("10.0.0.7".to_string()), Some("firefox".to_string())).await.unwrap();
+    let app = directory.router("");
+
+    // Свои сессии — с теми же id, по которым администратор их отзывает
+    let (status, body) = call(&app, request("GET", "/api/me/sessions", Some(&key), None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+    assert_eq!(body[0]["id"], serde_json::json!(session.id));
+    assert_eq!((body[0]["device"].as_str(), body[0]["active"].as_bool()), (Some("firefox"), Some(true)));
+}
+
+#[tokio::test]
+async fn test_password_reset_token() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let mut bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+    let ttl = chrono::Duration::hours(1);
+
+    // Без email сбрасывать некуда
+    assert!(service.request_password_reset("bob", ttl, None).await.unwrap().is_none());
+    assert!(service.request_password_reset("nobody", ttl, None).await.unwrap().is_none());
+
+    bob.email = Some("bob@x.com".to_string());
+    service.update_user(&bob).await.unwrap();
+    let (_, first) = service.request_password_reset("bob", ttl, None).await.unwrap().unwrap();
+    let (user, token) = service.request_password_reset("bob@x.com", ttl, None).await.unwrap().unwrap();
+    assert_eq!(user.id, bob.id);
+
+    // Новый запрос гасит прежний токен; пароль не по политике токен не гасит
+    assert!(matches!(
+        service.confirm_password_reset(&first, "Staple-Battery-Horse-7", None).await,
+        Err(DirectoryError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        service.confirm_password_reset(&token, "short", None).await,
+        Err(DirectoryError::PasswordPolicy(_))
+    ));
+    let session = service.open_session(bob.id, LoginProtocol::Rest, None, None).await.unwrap();
+    service.confirm_password_reset(&token, "Staple-Battery-Horse-7", None).await.unwrap();
+    assert!(service.authenticate("bob", "Staple-Battery-Horse-7").await.is_ok());
+    // Сессии, открытые прежним паролем, отозваны
+    assert!(service.is_session_revoked(session.id).await.unwrap());
+    assert!(matches!(
+        service.confirm_password_reset(&token, "Battery-Staple-Horse-5", None).await,
+        Err(DirectoryError::InvalidInput(_))
+    ));
+
+    let expired = service.request_password_reset("bob", chrono::Duration::zero(), None).await.unwrap().unwrap().1;
+    assert!(service.confirm_password_reset(&expired, "Battery-Staple-Horse-5", None).await.is_err());
+
+    // Заблокированной учётной записи токен не подходит до конца блокировки
+    let (_, token) = service.request_password_reset("bob", ttl, None).await.unwrap().unwrap();
+    let mut locked = service.get_user(bob.id).await.unwrap().unwrap();
+    locked.lockout_until = Some(chrono::Utc::now() + chrono::Duration::minutes(30));
+    service.update_user(&locked).await.unwrap();
+    assert!(matches!(service.confirm_password_reset(&token, "Battery-Staple-Horse-5", None).await, Err(DirectoryError::InvalidInput(_))));
+    service.unlock_user(bob.id).await.unwrap();
+    service.confirm_password_reset(&token, "Battery-Staple-Horse-5", None).await.unwrap();
+
+    // Отключённой — не подходит вовсе, даже после включения
+    let (_, token) = service.request_password_reset("bob", ttl, None).await.unwrap().unwrap();
+    service.set_user_enabled(bob.id, false).await.unwrap();
+    assert!(matches!(service.confirm_password_reset(&token, "Horse-Staple-Battery-3", None).await, Err(DirectoryError::InvalidInput(_))));
+    service.set_user_enabled(bob.id, true).await.unwrap();
+    assert!(matches!(service.confirm_password_reset(&token, "Horse-Staple-Battery-3", None).await, Err(DirectoryError::InvalidInput(_))));
+    assert!(service.authenticate("bob", "Battery-Staple-Horse-5").await.is_ok());
+}
+
+#[tokio::test]
+async fn test_invitation_lifecycle() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let mut invitee = service.find_user_by_username("bob").await.unwrap().unwrap();
+    invitee.id = uuid::Uuid::new_v4();
+    invitee.sid = SecurityIdentifier::new_nt_authority(1001);
+    invitee.username = "dave".to_string();
+    invitee.user_principal_name = "dave@x.com".to_string();
+    invitee.email = None;
+    let ttl = chrono::Duration::days(7);
+
+    assert!(matches!(service.invite_user(&invitee, None, ttl).await, Err(DirectoryError::InvalidInput(_))));
+    invitee.email = Some("dave@x.com".to_string());
+    let (invitation, first) = service.invite_user(&invitee, None, ttl).await.unwrap();
+    let dave = service.get_user(invitation.user_id).await.unwrap().unwrap();
+    assert!(!dave.enabled);
+    assert_eq!(service.list_invitations().await.unwrap().len(), 1);
+
+    // Повторная отправка гасит прежнюю ссылку
+    let (_, token) = service.resend_invitation(dave.id, ttl).await.unwrap();
+    assert!(matches!(
+        service.accept_invitation(&first, "Correct-Horse-Battery-9", vec![], None).await,
+        Err(DirectoryError::InvalidInput(_))
+    ));
+    assert!(service.accept_invitation(&token, "short", vec![], None).await.is_err());
+
+    let dave = service.accept_invitation(&token, "Correct-Horse-Battery-9", vec![MfaMethod::Totp], None).await.unwrap();
+    assert!(dave.enabled && dave.mfa_enabled);
+    assert!(service.authenticate("dave", "Correct-Horse-Battery-9").await.is_ok());
+    assert!(service.list_invitations().await.unwrap().is_empty());
+    assert!(matches!(service.revoke_invitation(dave.id).await, Err(DirectoryError::NotFound(_))));
+
+    // Отзыв удаляет приглашённого пользователя
+    invitee.id = uuid::Uuid::new_v4();
+    invitee.username = "erin".to_string();
+    invitee.user_principal_name = "erin@x.com".to_string();
+    invitee.email = Some("erin@x.com".to_string());
+    let (invitation, _) = service.invite_user(&invitee, None, ttl).await.unwrap();
+    service.revoke_invitation(invitation.user_id).await.unwrap();
+    assert!(service.find_user_by_username("erin").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_invitation_list_requires_write_scope() {
+    let directory = TestDirectory::new().await;
+    let (_, reader) = directory.api_key("alice", true, &[scope::DIRECTORY_READ]).await;
+    let (_, writer) = directory.api_key("carol", true, &[scope::DIRECTORY_WRITE]).await;
+    let app = directory.router("");
+
+    let (status, body) = call(&app, request("GET", "/api/invitations", Some(&reader), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    let (status, listed) = call(&app, request("GET", "/api/invitations", Some(&writer), None)).await;
+    assert_eq!((status, listed.as_array().map(Vec::len)), (StatusCode::OK, Some(0)));
+}
+
+#[tokio::test]
+async fn test_session_limits() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let open = |agent: &'static str| service.open_session(bob.id, LoginProtocol::Rest, Some("10.0.0.7".to_string()), Some(agent.to_string()));
+
+    // Без предела сессий сколько угодно
+    let first = open("curl").await.unwrap();
+    let second = open("firefox").await.unwrap();
+    assert!(first.expires_at > first.issued_at);
+
+    // revoke_oldest: третья сессия при пределе 2 отзывает самую старую
+    service.set_sessions(&SessionConfig { max_concurrent: 2, on_limit: SessionLimitAction::RevokeOldest });
+    let third = open("chrome").await.unwrap();
+    assert!(service.is_session_revoked(first.id).await.unwrap());
+    assert!(!service.is_session_revoked(second.id).await.unwrap());
+    let sessions = service.list_user_sessions(bob.id).await.unwrap();
+    let ids: Vec<_> = sessions.iter().map(|session| session.id).collect();
+    assert_eq!(ids, vec![third.id, second.id, first.id]);
+    assert_eq!(sessions[0].device.as_deref(), Some("chrome"));
+
+    // reject: вход отклоняется, пока администратор не отзовёт сессию
+    service.set_sessions(&SessionConfig { max_concurrent: 2, on_limit: SessionLimitAction::Reject });
+    assert!(matches!(open("safari").await, Err(DirectoryError::SessionLimitExceeded(2))));
+    let revoked = service.revoke_session(second.id).await.unwrap();
+    assert!(revoked.revoked_at.is_some());
+    assert!(service.is_session_revoked(second.id).await.unwrap());
+    open("safari").await.unwrap();
+
+    assert!(matches!(service.revoke_session(uuid::Uuid::new_v4()).await, Err(DirectoryError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_session_routes_require_sessions_scope() {
+    let directory = TestDirectory::new().await;
+    let (bob, _) = directory.api_key("bob", false, &[scope::SELF_SERVICE]).await;
+    let (_, writer) = directory.api_key("alice", true, &[scope::DIRECTORY_WRITE]).await;
+    let (_, manager) = directory.api_key("carol", true, &[scope::SESSIONS_MANAGE]).await;
+    let session = directory.service.open_session(bob.id, LoginProtocol::Rest, None, None).await.unwrap();
+    let app = directory.router("");
+    let revoke = format!("/api/sessions/{}", session.id);
+
+    // Ключ администратора без `sessions:manage` сессии не видит и не отзывает
+    let (status, body) = call(&app, request("GET", "/api/users/bob/sessions", Some(&writer), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    let (status, body) = call(&app, request("DELETE", &revoke, Some(&writer), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    assert!(!directory.service.is_session_revoked(session.id).await.unwrap());
+
+    let (status, listed) = call(&app, request("GET", "/api/users/bob/sessions", Some(&manager), None)).await;
+    assert_eq!((status, listed.as_array().map(Vec::len)), (StatusCode::OK, Some(1)));
+    assert_eq!(call(&app, request("DELETE", &revoke, Some(&manager), None)).await.0, StatusCode::NO_CONTENT);
+    assert!(directory.service.is_session_revoked(session.id).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_login_risk() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+    for (name, cidr) in [("Moscow", "10.1.0.0/16"), ("Tokyo", "10.2.0.0/16")] {
+        let site = Site::new(name.to_string());
+        service.save_site(&site).await.unwrap();
+        service.save_subnet(&Subnet::new(cidr, site.id).unwrap()).await.unwrap();
+    }
+    service.set_risk(&RiskConfig {
+        enabled: true,
+        travel_window_secs: 3600,
+        new_device: RiskAction::Log,
+        new_location: RiskAction::Ignore,
+        impossible_travel: RiskAction::StepUp,
+    });
+    let login = |ip: &'static str, agent: &'static str| {
+        let actor = ActorContext::new(LoginProtocol::Rest, Some(ip.to_string())).with_device(Some(agent.to_string()));
+        actor.scope(service.authenticate_from("bob", "Correct-Horse-Battery-9", Some(ip.to_string()), LoginProtocol::Rest))
+    };
+
+    // Первый вход сравнивать не с чем, новое устройство только отмечается
+    login("10.1.0.5", "firefox").await.unwrap();
+    login("10.1.0.6", "curl").await.unwrap();
+
+    // Сразу после входа из Москвы — из Токио: нужен второй фактор, а у bob его нет — отказ
+    assert!(matches!(login("10.2.0.9", "firefox").await, Err(DirectoryError::AccessDenied(_))));
+    let history = service.get_login_history(bob.id).await.unwrap();
+    assert!(!history[0].success);
+    assert_eq!(history[0].reason.as_deref(), Some("risk_blocked"));
+    assert_eq!(history[1].device.as_deref(), Some("curl"));
+
+    // С TOTP вход без кода требует его, попытка в истории неудачная
+    let secret = mfa::generate_totp_secret();
+    let mut user = service.get_user(bob.id).await.unwrap().unwrap();
+    user.mfa_enabled = true;
+    user.mfa_methods = vec![MfaMethod::Totp];
+    user.totp_secret = Some(secret.clone());
+    service.update_user(&user).await.unwrap();
+    match login("10.2.0.9", "firefox").await {
+        Err(DirectoryError::MfaRequired(methods)) => assert_eq!(methods, [MfaMethod::Totp]),
+        other => panic!("expected MfaRequired, got {:?}", other.map(|user| user.username)),
+    }
+    let history = service.get_login_history(bob.id).await.unwrap();
+    assert_eq!(history[0].reason.as_deref(), Some("mfa_required"));
+
+    // Неверный код — неудачный вход, верный подтверждает его
+    let with_code = |code: String| {
+        let actor = ActorContext::new(LoginProtocol::Rest, Some("10.2.0.9".to_string())).with_device(Some("firefox".to_string()));
+        actor.scope(async move {
+            service.authenticate_with_mfa("bob", "Correct-Horse-Battery-9", Some(&code), Some("10.2.0.9".to_string()), LoginProtocol::Rest).await
+        })
+    };
+    let wrong = (0..4).map(|n| format!("{:06}", n)).find(|code| !mfa::verify_totp(&secret, code, chrono::Utc::now())).unwrap();
+    assert!(matches!(with_code(wrong).await, Err(DirectoryError::AuthenticationFailed(_))));
+    let history = service.get_login_history(bob.id).await.unwrap();
+    assert_eq!(history[0].reason.as_deref(), Some("invalid_mfa_code"));
+    with_code(mfa::totp_code(&secret, chrono::Utc::now())).await.unwrap();
+
+    // Теперь обратно в Москву — снова «невозможное перемещение»
+    service.set_risk(&RiskConfig { enabled: true, impossible_travel: RiskAction::Block, ..RiskConfig::default() });
+    assert!(matches!(login("10.1.0.5", "firefox").await, Err(DirectoryError::AccessDenied(_))));
+    service.set_risk(&RiskConfig::default());
+    login("10.1.0.5", "firefox").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_login_step_up_over_rest() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let (bob, key) = directory.api_key("bob", false, &[scope::SELF_SERVICE]).await;
+    service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+    let app = directory.router("");
+
+    // TOTP не включается без подключённого приложения
+    let (status, _) = call(&app, request("PUT", "/api/me/mfa", Some(&key), Some(serde_json::json!({ "enabled": true, "methods": ["Totp"], "current_password": "Correct-Horse-Battery-9" })))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Подключение отдаёт секрет один раз, но включает метод только код из приложения
+    let enroll = |password: &str| call(&app, request("POST", "/api/me/mfa/totp", Some(&key), Some(serde_json::json!({ "current_password": password }))));
+    assert_eq!(enroll("wrong").await.0, StatusCode::FORBIDDEN);
+    let (status, body) = enroll("Correct-Horse-Battery-9").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["totp_uri"].as_str().unwrap().starts_with("otpauth://totp/"));
+    let user = service.get_user(bob.id).await.unwrap().unwrap();
+    assert!(!user.mfa_enabled && user.totp_secret.is_none());
+    let secret = user.totp_pending_secret.unwrap();
+
+    let confirm = |code: String| call(&app, request("POST", "/api/me/mfa/totp/confirm", Some(&key), Some(serde_json::json!({ "code": code }))));
+    let wrong = (0..4).map(|n| format!("{:06}", n)).find(|code| !mfa::verify_totp(&secret, code, chrono::Utc::now())).unwrap();
+    assert_eq!(confirm(wrong).await.0, StatusCode::BAD_REQUEST);
+    assert!(!service.get_user(bob.id).await.unwrap().unwrap().mfa_enabled);
+    let (status, body) = confirm(mfa::totp_code(&secret, chrono::Utc::now())).await;
+    assert_eq!((status, body["enabled"].as_bool()), (StatusCode::OK, Some(true)));
+    assert_eq!(body["methods"], serde_json::json!(["Totp"]));
+    let user = service.get_user(bob.id).await.unwrap().unwrap();
+    assert_eq!(user.totp_secret.as_ref(), Some(&secret));
+    assert!(user.totp_pending_secret.is_none());
+    assert_eq!(confirm(mfa::totp_code(&secret, chrono::Utc::now())).await.0, StatusCode::BAD_REQUEST);
+
+    service.set_risk(&RiskConfig { enabled: true, new_device: RiskAction::StepUp, ..RiskConfig::default() });
+    let login = |agent: &str, code: Option<String>| {
+        let mut request = request("POST", "/api/auth/login", None, Some(serde_json::json!({
+            "username": "bob", "password": "Correct-Horse-Battery-9", "mfa_code": code,
+        })));
+        request.headers_mut().insert(axum::http::header::USER_AGENT, agent.parse().unwrap());
+        call(&app, request)
+    };
+    assert_eq!(login("firefox", None).await.0, StatusCode::OK);
+
+    // С нового устройства — 401 MFA_REQUIRED и методы, которыми подтвердить вход
+    let (status, body) = login("curl", None).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::UNAUTHORIZED, Some("MFA_REQUIRED")));
+    assert_eq!(body["methods"], serde_json::json!(["Totp"]));
+    let (status, body) = login("curl", Some(mfa::totp_code(&secret, chrono::Utc::now()))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["token"].is_string());
+}
+
+#[tokio::test]
+async fn test_external_auth_provider() {
+    use axum::{routing::post, Form, Json, Router};
+    use std::collections::HashMap;
+
+    // Token endpoint провайдера OIDC: верный пароль у bob@x.com — upstream-secret
+    async fn token(Form(form): Form<HashMap<String, String>>) -> (axum::http::StatusCode, Json<serde_json::Value>) {
+        match (form.get("grant_type"), form.get("username"), form.get("password")) {
+            (Some(grant), Some(login), Some(password)) if grant == "password" && login == "bob@x.com" && password == "upstream-secret" => {
+                (axum::http::StatusCode::OK, Json(serde_json::json!({ "access_token": "x", "token_type": "Bearer" })))
+            }
+            _ => (axum::http::StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": "invalid_grant" }))),
+        }
+    }
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let token_url = format!("http://{}/token", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, Router::new().route("/token", post(token))).await.unwrap() });
+
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    assert_eq!(bob.user_principal_name, "bob@x.com");
+    service.set_password(bob.id, "Correct-Horse-Battery-9").await.unwrap();
+
+    let oidc = |token_url: String| AuthProviderConfig::Oidc(OidcProviderConfig {
+        token_url,
+        client_id: "nextdomen".to_string(),
+        client_secret: None,
+        scope: "openid".to_string(),
+        login: "{upn}".to_string(),
+        timeout_secs: 5,
+    });
+    service.set_auth_providers(&HashMap::from([("X.com".to_string(), oidc(token_url))]));
+
+    // Пароль проверяет провайдер, локальный хеш больше не подходит; неверный пароль — неудачная попытка
+    let user = service.authenticate_from("bob", "upstream-secret", None, LoginProtocol::Grpc).await.unwrap();
+    assert_eq!(user.id, bob.id);
+    assert!(matches!(service.authenticate("bob", "Correct-Horse-Battery-9").await, Err(DirectoryError::AuthenticationFailed(_))));
+    assert_eq!(service.get_user(bob.id).await.unwrap().unwrap().failed_logins, 1);
+
+    // Недоступный провайдер — отказ без счёта неудачных попыток
+    service.set_auth_providers(&HashMap::from([("x.com".to_string(), oidc("http://127.0.0.1:1/token".to_string()))]));
+    assert!(matches!(service.authenticate("bob", "upstream-secret").await, Err(DirectoryError::AuthenticationFailed(_))));
+    assert_eq!(service.get_user(bob.id).await.unwrap().unwrap().failed_logins, 1);
+
+    service.set_auth_providers(&HashMap::from([("x.com".to_string(), AuthProviderConfig::Local)]));
+    service.authenticate("bob", "Correct-Horse-Battery-9").await.unwrap();
+}
@@ -0,0 +1,41 @@
+// tests/integration/lookup.rs
+
+use nextDomen::casefold;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::{Contact, Group, GroupScope, GroupTypeFlags};
+
+use super::TestDirectory;
+
+#[test]
+fn test_fold() {
+    assert_eq!(casefold::fold("  Alice "), "alice");
+    assert_eq!(casefold::fold("STRASSE"), casefold::fold("Straße"));
+    assert_eq!(casefold::fold("ΟΔΟΣ"), casefold::fold("οδος"));
+}
+
+#[tokio::test]
+async fn test_lookup_indexes_ignore_case() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let ldif = "dn: CN=Straße,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: Straße\nmail: Max@X.com\n";
+    service.import_ldif(ldif, DuplicatePolicy::Fail).await.unwrap();
+    let user = service.find_user_by_username("STRASSE").await.unwrap().unwrap();
+    service.register_spn(user.id, "HTTP/Web.X.com").await.unwrap();
+    let group = Group::new("Sales Mail".into(), "SalesMail".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+    service.create_group(&group).await.unwrap();
+    service.create_contact(&Contact::new("Partner".to_string(), "Ext@Partner.com".to_string())).await.unwrap();
+
+    // Первый запуск перестраивает индексы, следующий ничего не делает; поиск работает до и после
+    for expected in [true, false] {
+        assert_eq!(service.migrate_lookup_indexes().await.unwrap() > 0, expected);
+
+        let found = service.find_user_by_username("strasse").await.unwrap().unwrap();
+        assert_eq!(found.username, "Straße");
+        assert_eq!(service.find_user_by_email(" max@x.COM ").await.unwrap().unwrap().id, user.id);
+        assert_eq!(service.find_user_by_spn("http/web.x.com").await.unwrap().unwrap().id, user.id);
+        let found = service.find_group_by_sam_account_name("salesmail").await.unwrap().unwrap();
+        assert_eq!(found.sam_account_name, "SalesMail");
+        assert!(service.find_contact_by_email("EXT@partner.com").await.unwrap().is_some());
+        assert!(service.find_domain_by_dns_name("X.COM.").await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,181 @@
+// tests/integration/mod.rs
+
+//! Интеграционные тесты: сервис каталога на временной базе и известные ответы протоколов
+//!
+//! `auth.rs` и `users.rs` написаны под прежний HTTP-роутер (`web::create_router`, axum_test)
+//! и не подключены, пока роутер не собирается в тестах.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::ConnectInfo;
+use axum::http::{header, Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use nextDomen::config::AppConfig;
+use nextDomen::directory_service::DirectoryService;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::middleware::API_KEY_HEADER;
+use nextDomen::models::{ApiKey, Domain, Group, GroupScope, GroupTypeFlags, SecurityIdentifier, User};
+use nextDomen::raddb::{MasterKey, RadDB};
+use nextDomen::reload::ConfigReloader;
+use nextDomen::web;
+
+mod acl;
+mod apikeys;
+mod approvals;
+mod audit;
+mod bulk;
+mod ca;
+mod cache;
+mod changes;
+mod config;
+mod cors;
+mod csv_io;
+mod dn;
+mod dns;
+mod errors;
+mod events;
+mod gpos;
+mod groups;
+mod grpc;
+mod jobs;
+mod kerberos;
+mod ldap;
+mod ldif;
+mod listener;
+mod logins;
+mod lookup;
+mod modify;
+mod oidc;
+mod organizations;
+mod ous;
+mod password_policies;
+mod photos;
+mod proxy;
+mod raddb;
+mod radius;
+mod ratelimit;
+mod reload;
+mod replication;
+mod schema;
+mod search;
+mod service_accounts;
+mod sids;
+mod sites;
+mod stats;
+mod sync;
+mod usernames;
+
+/// Домен `x.com` во временном каталоге; каталог удаляется вместе с ним
+pub struct TestDirectory {
+    pub service: Arc<DirectoryService>,
+    dir: PathBuf,
+    key: MasterKey,
+}
+
+impl TestDirectory {
+    pub async fn new() -> Self {
+        jwt_keys();
+        let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("directory.db");
+        let key = RadDB::generate_key();
+        let service = Arc::new(DirectoryService::open(db_path.to_str().unwrap(), &key).unwrap());
+
+        let domain = Domain::new("x.com", "x.com", SecurityIdentifier::new_nt_authority(21));
+        service.create_domain(&domain).await.unwrap();
+
+        Self { service, dir, key }
+    }
+
+    /// Содержимое базы на диске — то, что переживёт перезапуск
+    pub fn on_disk(&self) -> RadDB {
+        RadDB::open_read_only(self.dir.join("directory.db"), &self.key).unwrap()
+    }
+
+    /// REST API, как его собирает `web`, с конфигурацией `yaml` поверх обязательных полей
+    pub fn router(&self, yaml: &str) -> axum::Router {
+        let base = serde_yaml::from_str(&format!("db_path: x\nmaster_key_hex: y\n{}", yaml)).unwrap();
+        let config = AppConfig::from_yaml_with_env(base, Vec::new()).unwrap();
+        let reloader = Arc::new(ConfigReloader::new(None, &config, Arc::clone(&self.service)).unwrap());
+        web::router(Arc::clone(&self.service), &config, reloader, "http://localhost".to_string()).unwrap()
+    }
+
+    /// Пользователь `username` и значение его API-ключа с областями `scopes`; `admin` — участник Domain Admins
+    pub async fn api_key(&self, username: &str, admin: bool, scopes: &[&str]) -> (User, String) {
+        let ldif = format!("dn: CN={0},CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: {0}\n", username);
+        self.service.import_ldif(&ldif, DuplicatePolicy::Skip).await.unwrap();
+        let user = self.service.find_user_by_username(username).await.unwrap().unwrap();
+        if admin {
+            let admins = match self.service.find_group_by_sam_account_name("Domain Admins").await.unwrap() {
+                Some(admins) => admins,
+                None => {
+                    let admins = Group::new("Domain Admins".into(), "Domain Admins".into(), uuid::Uuid::nil(), GroupTypeFlags::SECURITY, GroupScope::Global);
+                    self.service.create_group(&admins).await.unwrap();
+                    admins
+                }
+            };
+            self.service.add_member_to_group(admins.id, user.id, None).await.unwrap();
+        }
+        let (key, value) = ApiKey::generate(format!("{} key", username), user.id, scopes.iter().map(|scope| scope.to_string()).collect(), None);
+        self.service.create_api_key(&key).await.unwrap();
+        (user, value)
+    }
+}
+
+impl Drop for TestDirectory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Запрос к REST API с клиента 127.0.0.1: `key` — значение `X-Api-Key`, `body` — тело JSON
+pub fn request(method: &str, uri: &str, key: Option<&str>, body: Option<Value>) -> Request<Body> {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(key) = key {
+        request = request.header(API_KEY_HEADER, key);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let mut request = request.body(body).unwrap();
+    request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+    request
+}
+
+/// Выполнить запрос; тело ответа — JSON или `Null`
+pub async fn call(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+/// Ключи JWT из `keys/`; задаются до первой проверки токена — `auth` читает их один раз
+fn jwt_keys() {
+    static KEYS: std::sync::Once = std::sync::Once::new();
+    KEYS.call_once(|| {
+        let keys = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("keys");
+        // SAFETY: один раз до первого обращения к ключам JWT; другие потоки переменные не меняют
+        unsafe {
+            std::env::set_var("JWT_PRIVATE_KEY_PATH", keys.join("jwt-private.pem"));
+            std::env::set_var("JWT_PUBLIC_KEY_PATH", keys.join("jwt-public.pem"));
+        }
+    });
+}
+
+/// JWT пользователя без сессии, подписанный ключом из `keys/`
+pub fn jwt(user: &User) -> String {
+    jwt_keys();
+    let now = chrono::Utc::now().timestamp() as usize;
+    let claims = nextDomen::auth::Claims { sub: user.id.to_string(), exp: now + 3600, iat: now, jti: None };
+    nextDomen::auth::sign_claims(&claims, None).unwrap()
+}
@@ -0,0 +1,156 @@
+// tests/integration/modify.rs
+
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use axum::routing::patch;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use nextDomen::audit::query::AuditQuery;
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::{AttributeChange, AttributeDefinition, AttributeSyntax, SchemaClass};
+use nextDomen::web::errors::normalize_errors;
+use nextDomen::web::patch::{PatchDocument, PatchField};
+use nextDomen::web::UserResponse;
+
+use super::TestDirectory;
+
+fn replace(attribute: &str, values: &[&str]) -> AttributeChange {
+    AttributeChange::Replace { attribute: attribute.to_string(), values: values.iter().map(|v| v.to_string()).collect() }
+}
+
+#[tokio::test]
+async fn test_modify_object() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let ldif = "dn: CN=bob,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: bob\nmail: bob@x.com\nsn: Old\n";
+    service.import_ldif(ldif, DuplicatePolicy::Fail).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    service.define_attribute(&AttributeDefinition {
+        name: "favoriteColor".to_string(),
+        description: None,
+        syntax: AttributeSyntax::String,
+        multi_valued: true,
+        required: false,
+        indexed: true,
+        classes: vec![SchemaClass::User],
+        created_at: chrono::Utc::now(),
+    }).await.unwrap();
+
+    // Смена имени и адреса переносит индексы
+    let object = service.modify_object(bob.id, vec![
+        replace("samaccountname", &["robert"]),
+        replace("mail", &["robert@x.com"]),
+        AttributeChange::Delete { attribute: "sn".to_string(), values: vec!["old".to_string()] },
+        AttributeChange::Add { attribute: "favoriteColor".to_string(), values: vec!["red".to_string(), "blue".to_string()] },
+        AttributeChange::Delete { attribute: "favoriteColor".to_string(), values: vec!["RED".to_string()] },
+    ]).await.unwrap();
+    assert_eq!((object.object_type.as_str(), object.name.as_str()), ("user", "robert"));
+    assert!(service.find_user_by_username("bob").await.unwrap().is_none());
+    assert!(service.find_user_by_email("bob@x.com").await.unwrap().is_none());
+    let robert = service.find_user_by_email("robert@x.com").await.unwrap().unwrap();
+    assert_eq!((robert.id, robert.surname), (bob.id, None));
+    assert_eq!(robert.meta["favoriteColor"], "blue");
+    assert_eq!(service.find_by_attribute("favoriteColor", "blue").await.unwrap(), vec![bob.id]);
+    assert!(service.find_by_attribute("favoriteColor", "red").await.unwrap().is_empty());
+
+    // Ошибка в любом изменении отменяет все
+    let rejected = [
+        AttributeChange::Add { attribute: "favoriteColor".to_string(), values: vec!["Blue".to_string()] },
+        AttributeChange::Delete { attribute: "givenName".to_string(), values: Vec::new() },
+        replace("givenName", &["A", "B"]),
+        replace("sAMAccountName", &[]),
+        replace("objectSid", &["S-1-5-32-544"]),
+    ];
+    for change in rejected {
+        let result = service.modify_object(bob.id, vec![replace("displayName", &["Robert"]), change.clone()]).await;
+        assert!(matches!(result, Err(DirectoryError::InvalidInput(_))), "{:?}: {:?}", change, result);
+    }
+    assert_eq!(service.get_user(bob.id).await.unwrap().unwrap().display_name, None);
+
+    let query = AuditQuery { target: Some(bob.id), action: Some("modify_object".to_string()), ..Default::default() };
+    let events = service.search_audit(&query).await.unwrap().events;
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].metadata["attributes"],
+        "replace:sAMAccountName,replace:mail,delete:sn,add:favoriteColor,delete:favoriteColor"
+    );
+}
+
+/// Изменения, в которые превращается тело PATCH для пользователя `current`
+async fn patch_changes(current: &Value, content_type: &str, body: &str) -> (StatusCode, Value) {
+    const FIELDS: &[PatchField] = &[("username", "sAMAccountName"), ("email", "mail"), ("surname", "sn")];
+    let current = current.clone();
+    let app = Router::new()
+        .route("/patch", patch(move |document: PatchDocument| async move {
+            document.attribute_changes(FIELDS, &current).map(Json)
+        }))
+        .layer(axum::middleware::from_fn(normalize_errors));
+    let request = Request::patch("/patch").header(header::CONTENT_TYPE, content_type).body(Body::from(body.to_string())).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_patch_documents() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let ldif = "dn: CN=bob,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: bob\nmail: bob@x.com\nsn: Smith\n";
+    service.import_ldif(ldif, DuplicatePolicy::Fail).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let mut current = serde_json::to_value(UserResponse::from(bob.clone())).unwrap();
+    current["attributes"] = json!({"favoriteColor": ["red", "blue"]});
+
+    // Merge patch: null очищает поле, неизменённые поля из GET пропускаются
+    let body = json!({"id": bob.id, "username": "bob", "email": "robert@x.com", "surname": null, "attributes": {"favoriteColor": "green"}});
+    let (status, changes) = patch_changes(&current, "application/merge-patch+json", &body.to_string()).await;
+    assert_eq!(status, StatusCode::OK, "{}", changes);
+    let changes: Vec<AttributeChange> = serde_json::from_value(changes).unwrap();
+    assert_eq!(changes, vec![
+        replace("favoriteColor", &["green"]),
+        replace("mail", &["robert@x.com"]),
+        replace("sn", &[]),
+    ]);
+    let (_, unchanged) = patch_changes(&current, "application/json", &current.to_string()).await;
+    assert_eq!(unchanged, json!([]));
+
+    // Изменения из merge patch применяются к пользователю
+    service.modify_object(bob.id, changes[1..].to_vec()).await.unwrap();
+    let robert = service.get_user(bob.id).await.unwrap().unwrap();
+    assert_eq!((robert.email.as_deref(), robert.surname), (Some("robert@x.com"), None));
+
+    // JSON Patch: значения атрибутов по индексу и в конец списка
+    let operations = json!([
+        {"op": "test", "path": "/email", "value": "bob@x.com"},
+        {"op": "add", "path": "/attributes/favoriteColor/-", "value": "green"},
+        {"op": "replace", "path": "/attributes/favoriteColor/0", "value": "black"},
+        {"op": "remove", "path": "/surname"},
+    ]);
+    let (status, changes) = patch_changes(&current, "application/json-patch+json", &operations.to_string()).await;
+    assert_eq!(status, StatusCode::OK, "{}", changes);
+    let changes: Vec<AttributeChange> = serde_json::from_value(changes).unwrap();
+    let values = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    assert_eq!(changes, vec![
+        AttributeChange::Add { attribute: "favoriteColor".to_string(), values: values(&["green"]) },
+        AttributeChange::Delete { attribute: "favoriteColor".to_string(), values: values(&["red"]) },
+        AttributeChange::Add { attribute: "favoriteColor".to_string(), values: values(&["black"]) },
+        AttributeChange::Delete { attribute: "sn".to_string(), values: Vec::new() },
+    ]);
+
+    let failed_test = json!([{"op": "test", "path": "/email", "value": "other@x.com"}]).to_string();
+    let rejected = [
+        ("application/json-patch+json", failed_test.as_str(), StatusCode::CONFLICT),
+        ("application/json-patch+json", r#"[{"op": "move", "from": "/email", "path": "/surname"}]"#, StatusCode::BAD_REQUEST),
+        ("application/merge-patch+json", r#"{"enabled": false}"#, StatusCode::BAD_REQUEST),
+        ("application/merge-patch+json", "[]", StatusCode::BAD_REQUEST),
+        ("text/plain", "{}", StatusCode::UNSUPPORTED_MEDIA_TYPE),
+    ];
+    for (content_type, body, expected) in rejected {
+        let (status, problem) = patch_changes(&current, content_type, body).await;
+        assert_eq!(status, expected, "{}: {}", body, problem);
+    }
+}
@@ -0,0 +1,233 @@
+// tests/integration/oidc.rs
+
+use std::net::SocketAddr;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::ConnectInfo;
+use axum::http::{header, Request, Response, StatusCode};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tower::ServiceExt;
+
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::{OAuthClient, PasswordHash};
+
+use super::TestDirectory;
+
+const REDIRECT_URI: &str = "https://app.x.com/callback";
+const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+const PASSWORD: &str = "Correct-Horse-Battery-9";
+
+/// Каталог с пользователем bob и конфиденциальным клиентом `app` (секрет `app-secret`)
+async fn directory() -> TestDirectory {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif("dn: CN=bob,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: bob\n", DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    service.set_password(bob.id, PASSWORD).await.unwrap();
+    let mut client = OAuthClient::new("app", "App", vec![REDIRECT_URI.to_string()]);
+    client.client_secret = Some(PasswordHash::new_bcrypt("app-secret").unwrap());
+    service.create_oauth_client(&client).await.unwrap();
+    directory
+}
+
+fn form(uri: &str, fields: &[(&str, &str)]) -> Request<Body> {
+    let mut request = Request::builder().method("POST").uri(uri)
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from(serde_urlencoded::to_string(fields).unwrap()))
+        .unwrap();
+    request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+    request
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (Response<Body>, String) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let (parts, body) = response.into_parts();
+    let body = String::from_utf8(to_bytes(body, usize::MAX).await.unwrap().to_vec()).unwrap();
+    (Response::from_parts(parts, Body::empty()), body)
+}
+
+/// Значение скрытого поля `csrf_token` формы входа
+fn csrf_field(page: &str) -> &str {
+    let start = page.find("name=\"csrf_token\" value=\"").unwrap() + "name=\"csrf_token\" value=\"".len();
+    let end = start + page[start..].find('"').unwrap();
+    &page[start..end]
+}
+
+/// Форма входа: cookie `oidc_csrf` и страница
+async fn login_form(app: &axum::Router, query: &[(&str, &str)]) -> (Response<Body>, String) {
+    let uri = format!("/oauth2/authorize?{}", serde_urlencoded::to_string(query).unwrap());
+    send(app, Request::builder().uri(uri).body(Body::empty()).unwrap()).await
+}
+
+/// Вход на странице авторизации с PKCE S256; `Ok(code)` — код из перенаправления, `Err` — страница с ошибкой
+async fn sign_in(app: &axum::Router, password: &str, redirect_uri: &str) -> Result<String, (StatusCode, String)> {
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(VERIFIER.as_bytes()));
+    let mut fields = vec![
+        ("response_type", "code"), ("client_id", "app"), ("redirect_uri", redirect_uri),
+        ("scope", "openid profile"), ("state", "xyz"), ("nonce", "n-1"),
+        ("code_challenge", challenge.as_str()), ("code_challenge_method", "S256"),
+    ];
+    let (response, page) = login_form(app, &fields).await;
+    if response.status() != StatusCode::OK {
+        return Err((response.status(), page));
+    }
+    let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap().split(';').next().unwrap().to_string();
+    fields.extend([("username", "bob"), ("password", password), ("csrf_token", csrf_field(&page))]);
+    let mut request = form("/oauth2/authorize", &fields);
+    request.headers_mut().insert(header::COOKIE, cookie.parse().unwrap());
+    let (response, body) = send(app, request).await;
+    if !response.status().is_redirection() {
+        return Err((response.status(), body));
+    }
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    let query = location.strip_prefix(&format!("{}?", REDIRECT_URI)).unwrap();
+    let params: Vec<(String, String)> = serde_urlencoded::from_str(query).unwrap();
+    assert!(params.contains(&("state".to_string(), "xyz".to_string())), "{}", location);
+    Ok(params.into_iter().find(|(name, _)| name == "code").unwrap().1)
+}
+
+/// Обмен кода на токены; `redirect_uri` — пустая строка, чтобы не передавать его
+async fn exchange(app: &axum::Router, code: &str, verifier: &str, redirect_uri: &str, secret: &str) -> (StatusCode, Value) {
+    let mut fields = vec![
+        ("grant_type", "authorization_code"), ("code", code),
+        ("code_verifier", verifier), ("client_id", "app"), ("client_secret", secret),
+    ];
+    if !redirect_uri.is_empty() {
+        fields.push(("redirect_uri", redirect_uri));
+    }
+    let (response, body) = send(app, form("/oauth2/token", &fields)).await;
+    (response.status(), serde_json::from_str(&body).unwrap())
+}
+
+async fn userinfo(app: &axum::Router, token: &str) -> StatusCode {
+    let request = Request::builder().uri("/oauth2/userinfo")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await.0.status()
+}
+
+#[tokio::test]
+async fn test_authorization_code_with_pkce() {
+    let directory = directory().await;
+    let app = directory.router("");
+
+    let code = sign_in(&app, PASSWORD, REDIRECT_URI).await.unwrap();
+    let (status, tokens) = exchange(&app, &code, VERIFIER, REDIRECT_URI, "app-secret").await;
+    assert_eq!(status, StatusCode::OK, "{}", tokens);
+    assert_eq!(tokens["token_type"], "Bearer");
+    assert_eq!(tokens["scope"], "openid profile");
+    let access_token = tokens["access_token"].as_str().unwrap();
+    let id_token = tokens["id_token"].as_str().unwrap();
+
+    // UserInfo принимает только access-токен, не ID-токен
+    assert_eq!(userinfo(&app, access_token).await, StatusCode::OK);
+    assert_eq!(userinfo(&app, id_token).await, StatusCode::UNAUTHORIZED);
+
+    // REST не принимает ни тот, ни другой: у токена входа свой typ
+    assert!(nextDomen::auth::validate_token(access_token).is_err());
+    assert!(nextDomen::auth::validate_token(id_token).is_err());
+    let request = Request::builder().uri("/api/me")
+        .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(send(&app, request).await.0.status(), StatusCode::UNAUTHORIZED);
+
+    // Код одноразовый
+    let (status, error) = exchange(&app, &code, VERIFIER, REDIRECT_URI, "app-secret").await;
+    assert_eq!((status, error["error"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_grant")));
+}
+
+#[tokio::test]
+async fn test_token_request_checks() {
+    let directory = directory().await;
+    let app = directory.router("");
+
+    // Неверный verifier PKCE; код при этом сгорает
+    let code = sign_in(&app, PASSWORD, REDIRECT_URI).await.unwrap();
+    let (status, error) = exchange(&app, &code, "wrong-verifier-wrong-verifier-wrong-verifier", REDIRECT_URI, "app-secret").await;
+    assert_eq!((status, error["error"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_grant")));
+    assert_eq!(exchange(&app, &code, VERIFIER, REDIRECT_URI, "app-secret").await.0, StatusCode::BAD_REQUEST);
+
+    // redirect_uri при обмене должен совпасть с запросом авторизации
+    let code = sign_in(&app, PASSWORD, REDIRECT_URI).await.unwrap();
+    let (status, error) = exchange(&app, &code, VERIFIER, "https://evil.x.com/callback", "app-secret").await;
+    assert_eq!((status, error["error"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_grant")));
+    // и обязателен, если был в нём (RFC 6749, 4.1.3)
+    let code = sign_in(&app, PASSWORD, REDIRECT_URI).await.unwrap();
+    let (status, error) = exchange(&app, &code, VERIFIER, "", "app-secret").await;
+    assert_eq!((status, error["error"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_grant")));
+
+    // Секрет конфиденциального клиента проверяется до кода
+    let code = sign_in(&app, PASSWORD, REDIRECT_URI).await.unwrap();
+    let (status, error) = exchange(&app, &code, VERIFIER, REDIRECT_URI, "not-the-secret").await;
+    assert_eq!((status, error["error"].as_str()), (StatusCode::UNAUTHORIZED, Some("invalid_client")));
+    assert_eq!(exchange(&app, &code, VERIFIER, REDIRECT_URI, "app-secret").await.0, StatusCode::OK);
+
+    // Заблокированная после выдачи кода учётная запись токенов не получает
+    let code = sign_in(&app, PASSWORD, REDIRECT_URI).await.unwrap();
+    let mut bob = directory.service.find_user_by_username("bob").await.unwrap().unwrap();
+    bob.lockout_until = Some(chrono::Utc::now() + chrono::Duration::minutes(30));
+    directory.service.update_user(&bob).await.unwrap();
+    assert!(directory.service.get_user(bob.id).await.unwrap().unwrap().is_locked_out());
+    let (status, error) = exchange(&app, &code, VERIFIER, REDIRECT_URI, "app-secret").await;
+    assert_eq!((status, error["error"].as_str()), (StatusCode::BAD_REQUEST, Some("invalid_grant")));
+    directory.service.unlock_user(bob.id).await.unwrap();
+
+    // Незарегистрированный redirect_uri — страница ошибки без перенаправления
+    let (status, _) = sign_in(&app, PASSWORD, "https://evil.x.com/callback").await.unwrap_err();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_login_page_hides_failure_reason() {
+    let directory = directory().await;
+    let app = directory.router("");
+
+    let (status, wrong_password) = sign_in(&app, "wrong", REDIRECT_URI).await.unwrap_err();
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(wrong_password.contains("Invalid username or password"), "{}", wrong_password);
+
+    // Отключённая учётная запись с верным паролем — та же страница
+    let bob = directory.service.find_user_by_username("bob").await.unwrap().unwrap();
+    directory.service.set_user_enabled(bob.id, false).await.unwrap();
+    let (status, disabled) = sign_in(&app, PASSWORD, REDIRECT_URI).await.unwrap_err();
+    let without_csrf = |page: &str| page.replace(csrf_field(page), "");
+    assert_eq!((status, without_csrf(&disabled)), (StatusCode::UNAUTHORIZED, without_csrf(&wrong_password)));
+}
+
+#[tokio::test]
+async fn test_login_form_rejects_csrf_and_framing() {
+    let directory = directory().await;
+    let app = directory.router("");
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(VERIFIER.as_bytes()));
+    let mut fields = vec![
+        ("response_type", "code"), ("client_id", "app"), ("redirect_uri", REDIRECT_URI),
+        ("code_challenge", challenge.as_str()), ("code_challenge_method", "S256"),
+    ];
+
+    // Форму нельзя встроить во фрейм, cookie с токеном не уходит на чужие сайты
+    let (response, page) = login_form(&app, &fields).await;
+    assert_eq!(response.headers()[header::X_FRAME_OPTIONS], "DENY");
+    assert_eq!(response.headers()[header::CONTENT_SECURITY_POLICY], "frame-ancestors 'none'");
+    let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(cookie.contains("HttpOnly") && cookie.contains("SameSite=Strict"), "{}", cookie);
+    let csrf = csrf_field(&page).to_string();
+
+    // Отправка с другого сайта: поле есть, а cookie нет или она другая — входа нет
+    fields.extend([("username", "bob"), ("password", PASSWORD), ("csrf_token", csrf.as_str())]);
+    for cookie in [None, Some("oidc_csrf=forged")] {
+        let mut request = form("/oauth2/authorize", &fields);
+        if let Some(cookie) = cookie {
+            request.headers_mut().insert(header::COOKIE, cookie.parse().unwrap());
+        }
+        let (response, _) = send(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response.headers().get(header::LOCATION).is_none());
+    }
+    let bob = directory.service.find_user_by_username("bob").await.unwrap().unwrap();
+    assert!(directory.service.get_login_history(bob.id).await.unwrap().is_empty());
+}
@@ -0,0 +1,56 @@
+// tests/integration/organizations.rs
+
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::models::{Domain, Organization, SecurityIdentifier};
+
+use super::TestDirectory;
+
+#[tokio::test]
+async fn test_organizations() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let x = service.find_domain_by_dns_name("x.com").await.unwrap().unwrap();
+    let y = Domain::new("y.com", "y.com", SecurityIdentifier::new_nt_authority(22));
+    service.create_domain(&y).await.unwrap();
+
+    let mut acme = Organization::new("acme".to_string());
+    acme.address.country = "DE".to_string();
+    service.create_organization(&acme).await.unwrap();
+    assert!(matches!(service.create_organization(&Organization::new("ACME".to_string())).await, Err(DirectoryError::AlreadyExists(..))));
+    let mut invalid = Organization::new("globex".to_string());
+    invalid.address.country = "Germany".to_string();
+    assert!(matches!(service.create_organization(&invalid).await, Err(DirectoryError::InvalidInput(_))));
+    let globex = Organization::new("globex".to_string());
+    service.create_organization(&globex).await.unwrap();
+
+    // Первый домен — домен по умолчанию; чужой домен не присоединяется
+    let acme = service.attach_domain(acme.id, x.id, false).await.unwrap();
+    assert_eq!(acme.default_domain_id, Some(x.id));
+    let acme = service.attach_domain(acme.id, y.id, false).await.unwrap();
+    assert_eq!(acme.domains, vec![x.id, y.id]);
+    assert_eq!(acme.default_domain_id, Some(x.id));
+    assert!(matches!(service.attach_domain(globex.id, x.id, false).await, Err(DirectoryError::AlreadyExists(..))));
+    let acme = service.detach_domain(acme.id, x.id).await.unwrap();
+    assert_eq!(acme.default_domain_id, Some(y.id));
+    assert!(matches!(service.detach_domain(acme.id, x.id).await, Err(DirectoryError::InvalidInput(_))));
+
+    // Переименование переносит индекс имени, домены изменение реквизитов не трогает
+    let renamed = service.update_organization(&Organization { name: "Acme Corp".to_string(), domains: Vec::new(), ..acme.clone() }).await.unwrap();
+    assert_eq!(renamed.domains, vec![y.id]);
+    assert!(matches!(
+        service.update_organization(&Organization { name: "globex".to_string(), ..renamed.clone() }).await,
+        Err(DirectoryError::AlreadyExists(..)),
+    ));
+    service.create_organization(&Organization::new("acme".to_string())).await.unwrap();
+
+    // Администратор организации — только своей
+    let admin = uuid::Uuid::new_v4();
+    let renamed = service.update_organization(&Organization { admins: vec![admin], ..renamed }).await.unwrap();
+    assert!(service.is_organization_admin(&renamed, admin).await.unwrap());
+    assert!(!service.is_organization_admin(&globex, admin).await.unwrap());
+
+    service.delete_organization(globex.id).await.unwrap();
+    assert!(service.get_organization(globex.id).await.unwrap().is_none());
+    assert_eq!(service.list_organizations().await.unwrap().len(), 2);
+    service.create_organization(&Organization::new("globex".to_string())).await.unwrap();
+}
@@ -0,0 +1,59 @@
+// tests/integration/ous.rs
+
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::OuContents;
+
+use super::TestDirectory;
+
+const SALES_LDIF: &str = "\
+dn: OU=Sales,DC=x,DC=com
+objectClass: organizationalUnit
+ou: Sales
+
+dn: OU=East,OU=Sales,DC=x,DC=com
+objectClass: organizationalUnit
+ou: East
+
+dn: OU=Retail,OU=East,OU=Sales,DC=x,DC=com
+objectClass: organizationalUnit
+ou: Retail
+
+dn: CN=bob,OU=Retail,OU=East,OU=Sales,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+
+dn: CN=carol,OU=East,OU=Sales,DC=x,DC=com
+objectClass: user
+sAMAccountName: carol
+";
+
+#[tokio::test]
+async fn test_delete_ou_contents() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(SALES_LDIF, DuplicatePolicy::Fail).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let sales = service.find_ou_by_dn("OU=Sales,DC=x,DC=com").await.unwrap().unwrap();
+    let east = service.find_ou_by_dn("OU=East,OU=Sales,DC=x,DC=com").await.unwrap().unwrap();
+
+    // Вложенные OU без recursive и пользователи без выбора политики — отказ, ничего не удалено
+    let result = service.delete_ou_tree(east.id, false, OuContents::MoveToParent).await;
+    assert!(matches!(result, Err(DirectoryError::InvalidInput(_))));
+    let result = service.delete_ou_tree(east.id, true, OuContents::Refuse).await;
+    assert!(matches!(result, Err(DirectoryError::InvalidInput(_))));
+    assert_eq!(service.get_all_ous().await.unwrap().len(), 3);
+
+    // Пользователи всего поддерева переезжают в родителя удалённой OU
+    assert_eq!(service.delete_ou_tree(east.id, true, OuContents::MoveToParent).await.unwrap(), 2);
+    for username in ["bob", "carol"] {
+        let user = service.find_user_by_username(username).await.unwrap().unwrap();
+        assert_eq!(user.organizational_unit, Some(sales.id), "{}", username);
+    }
+    assert_eq!(service.get_all_ous().await.unwrap().len(), 1);
+
+    assert_eq!(service.delete_ou_tree(sales.id, false, OuContents::Delete).await.unwrap(), 1);
+    assert!(service.find_user_by_username("bob").await.unwrap().is_none());
+    assert!(service.find_user_by_username("carol").await.unwrap().is_none());
+    assert!(service.get_ou(sales.id).await.unwrap().is_none());
+}
@@ -0,0 +1,148 @@
+// tests/integration/password_policies.rs
+
+use axum::http::StatusCode;
+use serde_json::json;
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::{PasswordPolicy, PasswordSettingsObject};
+
+use super::{call, request, TestDirectory};
+
+const ADMINS_LDIF: &str = "\
+dn: OU=Ops,DC=x,DC=com
+objectClass: organizationalUnit
+ou: Ops
+
+dn: CN=bob,OU=Ops,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+
+dn: CN=erin,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: erin
+
+dn: CN=Admins,CN=Users,DC=x,DC=com
+objectClass: group
+sAMAccountName: Admins
+member: CN=erin,CN=Users,DC=x,DC=com
+";
+
+#[tokio::test]
+async fn test_resultant_password_policy() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(ADMINS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let erin = service.find_user_by_username("erin").await.unwrap().unwrap();
+    let admins = service.find_group_by_sam_account_name("Admins").await.unwrap().unwrap();
+    let ops = service.find_ou_by_dn("OU=Ops,DC=x,DC=com").await.unwrap().unwrap();
+
+    let mut strict = PasswordSettingsObject::new("strict".into(), 10, PasswordPolicy {
+        min_length: 15,
+        require_special_chars: true,
+        lockout_threshold: 2,
+        ..PasswordPolicy::default()
+    });
+    strict.applies_to_groups = vec![admins.id];
+    service.save_pso(&strict).await.unwrap();
+    let mut relaxed = PasswordSettingsObject::new("ops".into(), 20, PasswordPolicy { history_count: 1, ..PasswordPolicy::default() });
+    relaxed.applies_to_ous = vec![ops.id, uuid::Uuid::nil()];
+    assert!(matches!(service.save_pso(&relaxed).await, Err(DirectoryError::NotFound(_))));
+    relaxed.applies_to_ous = vec![ops.id];
+    service.save_pso(&relaxed).await.unwrap();
+
+    // Группа — strict, OU — ops; в обеих — меньший precedence
+    assert_eq!(service.resultant_pso(&erin).await.unwrap().map(|pso| pso.name).as_deref(), Some("strict"));
+    assert_eq!(service.resultant_pso(&bob).await.unwrap().map(|pso| pso.name).as_deref(), Some("ops"));
+    service.add_member_to_group(admins.id, bob.id, None).await.unwrap();
+    assert_eq!(service.resultant_pso(&bob).await.unwrap().map(|pso| pso.name).as_deref(), Some("strict"));
+    service.remove_member_from_group(admins.id, bob.id).await.unwrap();
+
+    assert!(matches!(service.set_password(erin.id, "Passw0rd!").await, Err(DirectoryError::PasswordPolicy(_))));
+    service.set_password(erin.id, "Correct-Horse-Battery-9").await.unwrap();
+
+    // История ops — последний пароль
+    service.set_password(bob.id, "Passw0rd1").await.unwrap();
+    assert!(matches!(service.set_password(bob.id, "Passw0rd1").await, Err(DirectoryError::PasswordPolicy(_))));
+    service.set_password(bob.id, "Passw0rd2").await.unwrap();
+
+    // strict блокирует после двух неудачных попыток
+    assert!(service.authenticate("erin", "wrong").await.is_err());
+    assert!(!service.find_user_by_username("erin").await.unwrap().unwrap().is_locked_out());
+    assert!(service.authenticate("erin", "wrong").await.is_err());
+    assert!(service.find_user_by_username("erin").await.unwrap().unwrap().is_locked_out());
+
+    service.delete_pso(strict.id).await.unwrap();
+    assert!(service.resultant_pso(&erin).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_password_expiry() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(ADMINS_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+
+    // Срок отсчитывается от смены пароля по max_age_days
+    service.set_password(bob.id, "Passw0rd1").await.unwrap();
+    let mut user = service.get_user(bob.id).await.unwrap().unwrap();
+    let expires = user.password_expires.expect("password expiry");
+    assert_eq!((expires - user.last_password_change).num_days(), 90);
+    assert_eq!(service.notify_password_expiry(14).await.unwrap(), 0);
+
+    // Предупреждение — один раз на срок
+    user.password_expires = Some(chrono::Utc::now() + chrono::Duration::days(3));
+    service.update_user(&user).await.unwrap();
+    assert_eq!(service.notify_password_expiry(14).await.unwrap(), 1);
+    assert_eq!(service.notify_password_expiry(14).await.unwrap(), 0);
+
+    // Истёкший пароль: верный пароль отклоняется отдельной ошибкой, неверный — как обычно
+    user.password_expires = Some(chrono::Utc::now() - chrono::Duration::hours(1));
+    service.update_user(&user).await.unwrap();
+    let err = service.authenticate("bob", "Passw0rd1").await.unwrap_err();
+    assert!(matches!(err, DirectoryError::PasswordExpired(_)), "{:?}", err);
+    assert!(err.ldap_diagnostic().unwrap().contains("data 532"));
+    assert!(matches!(service.authenticate("bob", "wrong").await, Err(DirectoryError::AuthenticationFailed(_))));
+    assert_eq!(service.notify_password_expiry(14).await.unwrap(), 1);
+    assert_eq!(service.notify_password_expiry(14).await.unwrap(), 0);
+
+    // После смены пароля вход снова разрешён
+    service.set_password(bob.id, "Passw0rd2").await.unwrap();
+    service.authenticate("bob", "Passw0rd2").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_password_policy_routes() {
+    let directory = TestDirectory::new().await;
+    let (_, admin_reader) = directory.api_key("alice", true, &[scope::DIRECTORY_READ]).await;
+    let (_, admin_writer) = directory.api_key("carol", true, &[scope::DIRECTORY_WRITE]).await;
+    let (_, user_writer) = directory.api_key("bob", false, &[scope::DIRECTORY_WRITE]).await;
+    let (_, self_service) = directory.api_key("bob", false, &[scope::SELF_SERVICE]).await;
+    let app = directory.router("");
+    let create = |key| request("POST", "/api/password-policies", Some(key), Some(json!({ "name": "Strict", "precedence": 10, "settings": { "min_length": 16 } })));
+
+    // Изменения — только администратору с `directory:write`
+    let (status, body) = call(&app, create(&admin_reader)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    let (status, body) = call(&app, create(&user_writer)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("ACCESS_DENIED")));
+    let (status, created) = call(&app, create(&admin_writer)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    let update = |key| request("PUT", "/api/password-policies/Strict", Some(key), Some(json!({ "precedence": 5 })));
+    assert_eq!(call(&app, update(&admin_reader)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, update(&admin_writer)).await.0, StatusCode::OK);
+
+    // Чтение — любому вошедшему с областью чтения каталога
+    let (status, listed) = call(&app, request("GET", "/api/password-policies", Some(&user_writer), None)).await;
+    assert_eq!((status, listed.as_array().map(Vec::len)), (StatusCode::OK, Some(1)));
+    let (status, pso) = call(&app, request("GET", "/api/password-policies/Strict", Some(&admin_reader), None)).await;
+    assert_eq!((status, pso["precedence"].as_u64()), (StatusCode::OK, Some(5)));
+    let (status, body) = call(&app, request("GET", "/api/password-policies", Some(&self_service), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+
+    assert_eq!(call(&app, request("DELETE", "/api/password-policies/Strict", Some(&admin_reader), None)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, request("DELETE", "/api/password-policies/Strict", Some(&admin_writer), None)).await.0, StatusCode::NO_CONTENT);
+}
@@ -0,0 +1,60 @@
+// tests/integration/photos.rs
+
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::{LdapContext, LdapEntry, LdapValue, SecurityIdentifier, UserPhoto};
+use nextDomen::models::photo::MAX_PHOTO_SIZE;
+
+use super::TestDirectory;
+
+const BOB_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+";
+
+const JPEG: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F'];
+
+#[test]
+fn test_user_photo_validation() {
+    assert_eq!(UserPhoto::new("image/JPEG; charset=binary", JPEG.to_vec()).unwrap().content_type, "image/jpeg");
+    assert!(UserPhoto::new("image/png", b"\x89PNG\r\n\x1a\n....".to_vec()).is_ok());
+    // Тип не поддерживается, данные не того формата, пусто, слишком большое
+    assert!(UserPhoto::new("image/gif", b"GIF89a".to_vec()).is_err());
+    assert!(UserPhoto::new("image/png", JPEG.to_vec()).is_err());
+    assert!(UserPhoto::new("image/jpeg", Vec::new()).is_err());
+    let mut large = JPEG.to_vec();
+    large.resize(MAX_PHOTO_SIZE + 1, 0);
+    assert!(UserPhoto::new("image/jpeg", large).is_err());
+}
+
+#[tokio::test]
+async fn test_user_photo_in_ldap_entry() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(BOB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let ctx = LdapContext::new(service).await.unwrap();
+    let (dn, entry) = bob.ldap_entry(&ctx).await.unwrap();
+    assert!(dn.starts_with("CN=bob,CN=Users,"), "{}", dn);
+    assert_eq!(entry.text("distinguishedName"), vec![dn.as_str()]);
+    assert!(!entry.contains_key("thumbnailPhoto"));
+    // objectSid — двоичный SID
+    let sid = &entry.get("objectSid").unwrap()[0];
+    assert_eq!(SecurityIdentifier::from_bytes(sid.as_bytes()).unwrap(), bob.sid);
+
+    let photo = UserPhoto::new("image/jpeg", JPEG.to_vec()).unwrap();
+    service.set_user_photo(bob.id, Some(&photo)).await.unwrap();
+    assert_eq!(service.get_user_photo(bob.id).await.unwrap(), Some(photo));
+    let entry = bob.attributes(&ctx).await.unwrap();
+    assert_eq!(entry.get("thumbnailPhoto"), Some(&[LdapValue::binary(JPEG)][..]));
+    assert_eq!(entry.get("jpegPhoto"), entry.get("thumbnailPhoto"));
+
+    // PNG не JPEG: только thumbnailPhoto
+    let png = UserPhoto::new("image/png", b"\x89PNG\r\n\x1a\n".to_vec()).unwrap();
+    service.set_user_photo(bob.id, Some(&png)).await.unwrap();
+    let entry = bob.attributes(&ctx).await.unwrap();
+    assert!(entry.contains_key("thumbnailPhoto") && !entry.contains_key("jpegPhoto"));
+
+    service.set_user_photo(bob.id, None).await.unwrap();
+    assert!(service.get_user_photo(bob.id).await.unwrap().is_none());
+}
@@ -0,0 +1,99 @@
+// tests/integration/proxy.rs
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::http::HeaderMap;
+use axum::routing::get;
+use axum::{Extension, Router};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use nextDomen::config::ProxyConfig;
+use nextDomen::middleware::ClientIp;
+use nextDomen::proxy::{self, TrustedProxies};
+use nextDomen::web::listener::WebListener;
+
+fn proxies(trusted: &[&str], proxy_protocol: bool) -> TrustedProxies {
+    TrustedProxies::new(&ProxyConfig { trusted: trusted.iter().map(|entry| entry.to_string()).collect(), proxy_protocol }).unwrap()
+}
+
+fn ip(text: &str) -> IpAddr {
+    text.parse().unwrap()
+}
+
+#[test]
+fn test_forwarded_for() {
+    let proxies = proxies(&["10.0.0.10", "192.168.0.0/24", "fd00::/8"], false);
+    let mut headers = HeaderMap::new();
+    headers.append("X-Forwarded-For", "198.51.100.1, 203.0.113.7".parse().unwrap());
+    headers.append("X-Forwarded-For", "192.168.0.5".parse().unwrap());
+
+    // Справа налево до первого недоверенного адреса: подставленное клиентом левее не читается
+    assert_eq!(proxies.forwarded_client(ip("10.0.0.10"), &headers), ip("203.0.113.7"));
+    assert_eq!(proxies.forwarded_client(ip("::ffff:10.0.0.10"), &headers), ip("203.0.113.7"));
+    // Клиент не за прокси: заголовок — подделка
+    assert_eq!(proxies.forwarded_client(ip("203.0.113.99"), &headers), ip("203.0.113.99"));
+
+    let headers: HeaderMap = [("x-forwarded-for".parse().unwrap(), "[2001:db8::7]:4711, fd00::1".parse().unwrap())].into_iter().collect();
+    assert_eq!(proxies.forwarded_client(ip("fd00::2"), &headers), ip("2001:db8::7"));
+    let headers: HeaderMap = [("x-forwarded-for".parse().unwrap(), "unknown".parse().unwrap())].into_iter().collect();
+    assert_eq!(proxies.forwarded_client(ip("10.0.0.10"), &headers), ip("10.0.0.10"));
+
+    let error = TrustedProxies::new(&ProxyConfig { trusted: vec!["10.0.0.1/24".into()], proxy_protocol: false }).unwrap_err();
+    assert!(error.contains("host bits"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_proxy_protocol_headers() {
+    let mut v1: &[u8] = b"PROXY TCP6 2001:db8::7 2001:db8::1 4711 389\r\nrest";
+    assert_eq!(proxy::read_header(&mut v1).await.unwrap(), Some("[2001:db8::7]:4711".parse().unwrap()));
+    assert_eq!(v1, b"rest");
+    let mut unknown: &[u8] = b"PROXY UNKNOWN\r\n";
+    assert_eq!(proxy::read_header(&mut unknown).await.unwrap(), None);
+
+    let mut v2 = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+    v2.extend([0x21, 0x11, 0, 12, 203, 0, 113, 7, 10, 0, 0, 1, 0x12, 0x67, 0x01, 0x85]);
+    v2.extend(b"rest");
+    let mut stream = v2.as_slice();
+    assert_eq!(proxy::read_header(&mut stream).await.unwrap(), Some("203.0.113.7:4711".parse().unwrap()));
+    assert_eq!(stream, b"rest");
+
+    for invalid in [&b"GET / HTTP/1.1\r\n\r\n"[..], b"PROXY TCP4 2001:db8::7 10.0.0.1 1 2\r\n", b"PROXY TCP4 1.2.3.4\r\n"] {
+        let mut stream = invalid;
+        assert!(proxy::read_header(&mut stream).await.is_err(), "{:?}", String::from_utf8_lossy(invalid));
+    }
+}
+
+#[tokio::test]
+async fn test_web_listener_behind_proxy() {
+    let proxies = Arc::new(proxies(&["127.0.0.1"], true));
+    let listener = WebListener::bind("127.0.0.1:0", &Default::default()).await.unwrap();
+    let addr: SocketAddr = listener.describe().parse().unwrap();
+    let app = Router::new()
+        .route("/peer", get(|ClientIp(ip): ClientIp| async move { ip.to_string() }))
+        .layer(Extension(Arc::clone(&proxies)));
+    let server = tokio::spawn(listener.serve(app, None, proxies));
+
+    // Адрес из заголовка PROXY, затем X-Forwarded-For не от доверенного адреса не принимается
+    let request = |prefix: &str, forwarded: &str| format!(
+        "{}GET /peer HTTP/1.1\r\nHost: localhost\r\nX-Forwarded-For: {}\r\nConnection: close\r\n\r\n", prefix, forwarded,
+    );
+    for (request, expected) in [
+        (request("PROXY TCP4 203.0.113.7 127.0.0.1 4711 8080\r\n", "198.51.100.1"), "203.0.113.7"),
+        (request("PROXY UNKNOWN\r\n", "198.51.100.1"), "198.51.100.1"),
+    ] {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.ends_with(expected), "{}", response);
+    }
+
+    // Без заголовка PROXY соединение от доверенного прокси закрывается (непрочитанный запрос — RST)
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream.write_all(request("", "198.51.100.1").as_bytes()).await.unwrap();
+    let mut response = String::new();
+    let read = stream.read_to_string(&mut response).await;
+    assert!(read.is_err() || response.is_empty(), "{}", response);
+    server.abort();
+}
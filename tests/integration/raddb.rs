@@ -0,0 +1,62 @@
+// tests/integration/raddb.rs
+
+use nextDomen::raddb::{RadDB, RadDbError};
+
+#[test]
+fn test_batch_defers_flush() {
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("directory.db");
+    let key = RadDB::generate_key();
+    let db = RadDB::open(&path, &key).unwrap();
+    db.set("a".to_string(), vec![1]).unwrap();
+
+    // Пока пакет открыт (в том числе вложенный), на диске прежнее содержимое
+    let outer = db.begin_batch();
+    let inner = db.begin_batch();
+    db.set("b".to_string(), vec![2]).unwrap();
+    db.set_many(vec![("c".to_string(), vec![3])]).unwrap();
+    db.end_batch(inner).unwrap();
+    assert!(!RadDB::open_read_only(&path, &key).unwrap().contains_key("b"));
+
+    db.end_batch(outer).unwrap();
+    let reopened = RadDB::open_read_only(&path, &key).unwrap();
+    assert_eq!(reopened.get("b"), Some(vec![2]));
+    assert_eq!(reopened.get("c"), Some(vec![3]));
+
+    // Брошенный без end_batch пакет закрывается сам: следующая запись снова сразу идёт на диск
+    let abandoned = db.begin_batch();
+    db.set("d".to_string(), vec![4]).unwrap();
+    drop(abandoned);
+    db.set("e".to_string(), vec![5]).unwrap();
+    let reopened = RadDB::open_read_only(&path, &key).unwrap();
+    assert!(reopened.contains_key("d") && reopened.contains_key("e"));
+
+    drop(reopened);
+    drop(db);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_single_writer_lock() {
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("directory.db");
+    let key = RadDB::generate_key();
+    let db = RadDB::open(&path, &key).unwrap();
+    db.set("a".to_string(), vec![1]).unwrap();
+
+    // Второй открывающий на запись получает отказ, только для чтения — снимок без права записи
+    assert!(matches!(RadDB::open(&path, &key), Err(RadDbError::Locked(_))));
+    let snapshot = RadDB::open_read_only(&path, &key).unwrap();
+    assert_eq!(snapshot.get("a"), Some(vec![1]));
+    assert!(matches!(snapshot.set("b".to_string(), vec![2]), Err(RadDbError::ReadOnly)));
+    assert!(!snapshot.remove("a"));
+    drop(snapshot);
+    assert_eq!(RadDB::open_read_only(&path, &key).unwrap().get("a"), Some(vec![1]));
+
+    // Блокировка снимается вместе с базой
+    drop(db);
+    assert!(RadDB::open(&path, &key).is_ok());
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,126 @@
+// tests/integration/radius.rs
+
+use md5::{Digest, Md5};
+use nextDomen::radius::packet::{self, Packet};
+
+const SECRET: &[u8] = b"xyzzy5461";
+
+/// Access-Request из RFC 2865, 7.1: nemo / arctangent
+const ACCESS_REQUEST: &str = "010000380f403f9473978057bd83d5cb98f4227a01066e656d6f02120dbe708d93d413ce3196e43f782a0aee0406c0a80110050600000003";
+
+fn md5(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Md5::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+#[test]
+fn test_parse_rfc2865_access_request() {
+    let request = Packet::parse(&hex::decode(ACCESS_REQUEST).unwrap()).unwrap();
+    assert_eq!(request.code, packet::CODE_ACCESS_REQUEST);
+    assert_eq!(request.identifier, 0);
+    assert_eq!(request.authenticator.to_vec(), hex::decode("0f403f9473978057bd83d5cb98f4227a").unwrap());
+    assert_eq!(request.attributes.len(), 4);
+    assert_eq!(request.attribute(packet::ATTR_USER_NAME), Some(&b"nemo"[..]));
+    assert_eq!(request.attribute(4), Some(&[192, 168, 1, 16][..]));
+    assert!(!request.verify_message_authenticator(SECRET).unwrap());
+}
+
+#[test]
+fn test_decrypt_rfc2865_user_password() {
+    let request = Packet::parse(&hex::decode(ACCESS_REQUEST).unwrap()).unwrap();
+    let encrypted = request.attribute(packet::ATTR_USER_PASSWORD).unwrap();
+    assert_eq!(encrypted, &hex::decode("0dbe708d93d413ce3196e43f782a0aee").unwrap()[..]);
+    assert_eq!(packet::decrypt_user_password(SECRET, &request.authenticator, encrypted).unwrap(), "arctangent");
+
+    assert!(packet::decrypt_user_password(SECRET, &request.authenticator, &encrypted[..15]).is_err());
+    assert!(packet::decrypt_user_password(SECRET, &request.authenticator, &[]).is_err());
+}
+
+#[test]
+fn test_encode_response_authenticators() {
+    let request = Packet::parse(&hex::decode(ACCESS_REQUEST).unwrap()).unwrap();
+    let mut accept = request.response(packet::CODE_ACCESS_ACCEPT);
+    accept.add(packet::ATTR_REPLY_MESSAGE, b"Welcome");
+    let encoded = accept.encode_response(SECRET);
+
+    assert_eq!(encoded[0], packet::CODE_ACCESS_ACCEPT);
+    assert_eq!(u16::from_be_bytes([encoded[2], encoded[3]]) as usize, encoded.len());
+
+    // Response Authenticator = MD5(Code + ID + Length + Request Authenticator + атрибуты + секрет)
+    let mut with_request_auth = encoded.clone();
+    with_request_auth[4..20].copy_from_slice(&request.authenticator);
+    assert_eq!(md5(&[&with_request_auth, SECRET]), &encoded[4..20]);
+
+    // Message-Authenticator идёт первым и считается по пакету с Request Authenticator
+    let reparsed = Packet::parse(&with_request_auth).unwrap();
+    assert_eq!(reparsed.attributes[0].0, packet::ATTR_MESSAGE_AUTHENTICATOR);
+    assert_eq!(reparsed.attribute(packet::ATTR_REPLY_MESSAGE), Some(&b"Welcome"[..]));
+    assert!(reparsed.verify_message_authenticator(SECRET).unwrap());
+    assert!(reparsed.verify_message_authenticator(b"wrong").is_err());
+}
+
+#[test]
+fn test_split_and_concat_attribute() {
+    let mut challenge = Packet::parse(&hex::decode(ACCESS_REQUEST).unwrap()).unwrap().response(packet::CODE_ACCESS_CHALLENGE);
+    let eap: Vec<u8> = (0..600).map(|i| i as u8).collect();
+    challenge.add_split(packet::ATTR_EAP_MESSAGE, &eap);
+
+    let lengths: Vec<usize> = challenge.attributes.iter().map(|(_, v)| v.len()).collect();
+    assert_eq!(lengths, [253, 253, 94]);
+
+    let parsed = Packet::parse(&challenge.encode_response(SECRET)).unwrap();
+    assert_eq!(parsed.concat_attribute(packet::ATTR_EAP_MESSAGE).unwrap(), eap);
+    assert!(parsed.concat_attribute(packet::ATTR_STATE).is_none());
+}
+
+#[test]
+fn test_mppe_key_encryption() {
+    let request = Packet::parse(&hex::decode(ACCESS_REQUEST).unwrap()).unwrap();
+    let key: Vec<u8> = (1..=32).collect();
+    let encrypted = packet::encrypt_mppe_key(SECRET, &request.authenticator, &key);
+
+    // Соль со старшим битом и 48 байт: длина ключа, ключ и дополнение до блока
+    assert_eq!(encrypted.len(), 2 + 48);
+    assert_ne!(encrypted[0] & 0x80, 0);
+
+    // Обратное преобразование RFC 2548, 2.4.2
+    let mut plain = Vec::new();
+    let mut prev = [&request.authenticator[..], &encrypted[..2]].concat();
+    for chunk in encrypted[2..].chunks(16) {
+        let b = md5(&[SECRET, &prev]);
+        plain.extend(chunk.iter().zip(&b).map(|(c, k)| c ^ k));
+        prev = chunk.to_vec();
+    }
+    assert_eq!(plain[0] as usize, key.len());
+    assert_eq!(&plain[1..33], &key[..]);
+    assert!(plain[33..].iter().all(|b| *b == 0));
+}
+
+#[test]
+fn test_vendor_attribute_layout() {
+    let mut accept = Packet::parse(&hex::decode(ACCESS_REQUEST).unwrap()).unwrap().response(packet::CODE_ACCESS_ACCEPT);
+    accept.add_vendor(packet::VENDOR_MICROSOFT, packet::MS_MPPE_SEND_KEY, &[0xAA; 4]);
+    assert_eq!(
+        accept.attribute(packet::ATTR_VENDOR_SPECIFIC).unwrap(),
+        &[0x00, 0x00, 0x01, 0x37, packet::MS_MPPE_SEND_KEY, 6, 0xAA, 0xAA, 0xAA, 0xAA][..],
+    );
+}
+
+#[test]
+fn test_parse_rejects_malformed_packets() {
+    let request = hex::decode(ACCESS_REQUEST).unwrap();
+    assert!(Packet::parse(&request[..19]).is_err());
+    // Длина в заголовке больше датаграммы
+    assert!(Packet::parse(&request[..40]).is_err());
+
+    let mut bad_attribute = request.clone();
+    bad_attribute[21] = 1;
+    assert!(Packet::parse(&bad_attribute).is_err());
+
+    let mut overflowing = request;
+    overflowing[21] = 0x40;
+    assert!(Packet::parse(&overflowing).is_err());
+}
@@ -0,0 +1,74 @@
+// tests/integration/ratelimit.rs
+
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+
+use nextDomen::config::{RateLimit, RateLimitConfig};
+use nextDomen::models::apikey::scope;
+use nextDomen::ratelimit::RateLimiter;
+
+use super::{call, request, TestDirectory};
+
+/// Попытка входа через прокси на 127.0.0.1 от клиента `forwarded_for`
+fn login(username: &str, forwarded_for: &str) -> Request<Body> {
+    let mut request = request("POST", "/api/auth/login", None, Some(json!({ "username": username, "password": "wrong" })));
+    request.headers_mut().insert("x-forwarded-for", forwarded_for.parse().unwrap());
+    request
+}
+
+#[tokio::test]
+async fn test_bucket_refills() {
+    // 600 в минуту — токен каждые 100 мс
+    let limiter = RateLimiter::new(RateLimit { burst: 2, per_minute: 600 });
+    assert!(limiter.acquire("alice").is_ok());
+    assert!(limiter.acquire("alice").is_ok());
+    let (retry_after, breach) = limiter.acquire("alice").unwrap_err();
+    assert!(breach);
+    assert!(retry_after <= Duration::from_millis(100), "{:?}", retry_after);
+    // Повторный отказ — уже не новое превышение; другой ключ не затронут
+    assert!(!limiter.acquire("alice").unwrap_err().1);
+    assert!(limiter.acquire("bob").is_ok());
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(limiter.acquire("alice").is_ok());
+    assert!(limiter.acquire("alice").is_err());
+}
+
+#[tokio::test]
+async fn test_login_limits_behind_proxy() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let (alice, _) = directory.api_key("alice", false, &[scope::DIRECTORY_READ]).await;
+    service.set_rate_limit(&RateLimitConfig {
+        per_ip: RateLimit { burst: 2, per_minute: 1 },
+        per_account: RateLimit { burst: 3, per_minute: 1 },
+        ..RateLimitConfig::default()
+    });
+    let app = directory.router("web_server:\n  proxy:\n    trusted: [127.0.0.1]\n");
+
+    // Лимит по адресу считает клиента из X-Forwarded-For, а не прокси
+    for _ in 0..2 {
+        assert_eq!(call(&app, login("alice", "203.0.113.7")).await.0, StatusCode::UNAUTHORIZED);
+    }
+    let response = app.clone().oneshot(login("alice", "203.0.113.7")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers()[header::RETRY_AFTER].to_str().unwrap().parse::<u64>().unwrap() >= 1);
+    assert_eq!(call(&app, login("alice", "198.51.100.1, 203.0.113.8")).await.0, StatusCode::UNAUTHORIZED);
+    assert!(!service.get_user(alice.id).await.unwrap().unwrap().is_locked_out());
+
+    // Лимит учётной записи — с любых адресов; при исчерпании она блокируется
+    let (status, body) = call(&app, login("alice", "203.0.113.9")).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::TOO_MANY_REQUESTS, Some("RATE_LIMITED")));
+    assert!(service.get_user(alice.id).await.unwrap().unwrap().is_locked_out());
+    assert_eq!(call(&app, login("bob", "203.0.113.9")).await.0, StatusCode::UNAUTHORIZED);
+
+    // Без доверенного прокси заголовок не учитывается: все попытки — с 127.0.0.1
+    let direct = directory.router("");
+    assert_eq!(call(&direct, login("carol", "192.0.2.1")).await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(call(&direct, login("dave", "192.0.2.2")).await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(call(&direct, login("erin", "192.0.2.3")).await.0, StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,52 @@
+// tests/integration/reload.rs
+
+use std::sync::Arc;
+
+use nextDomen::config::AppConfig;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::reload::ConfigReloader;
+
+use super::TestDirectory;
+
+#[tokio::test]
+async fn test_config_reload() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif("dn: CN=bob,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: bob\n", DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+
+    let path = std::env::temp_dir().join(format!("nextdomen-test-{}.yaml", uuid::Uuid::new_v4()));
+    let write = |extra: &str| {
+        std::fs::write(&path, format!("db_path: data/raddb.bin\nmaster_key_hex: {}\n{}", "ab".repeat(32), extra)).unwrap();
+    };
+    write("");
+    let config = AppConfig::load_with_env(Some(&path)).unwrap();
+    let reloader = Arc::new(ConfigReloader::new(Some(path.clone()), &config, Arc::clone(service)).unwrap());
+
+    let report = reloader.reload();
+    assert!(report.is_applied() && report.applied.is_empty(), "{:?}", report);
+
+    write("security:\n  password_policy:\n    min_length: 14\nweb_server:\n  cors:\n    allowed_origins: [https://admin.x.com]\nldap_server:\n  base_dn: DC=x,DC=com\n");
+    let report = reloader.reload();
+    assert!(report.is_applied(), "{:?}", report);
+    assert_eq!(report.applied, ["web_server.cors", "security.password_policy"]);
+    assert_eq!(report.restart_required, ["ldap_server.base_dn"]);
+    assert_eq!(service.resultant_password_policy(&bob).await.unwrap().min_length, 14);
+
+    // Другая база — отклоняется целиком, политика остаётся прежней
+    std::fs::write(&path, format!(
+        "db_path: other.bin\nmaster_key_hex: {}\nsecurity:\n  password_policy:\n    min_length: 20\n",
+        "ab".repeat(32),
+    )).unwrap();
+    let report = reloader.reload();
+    assert!(!report.is_applied());
+    assert_eq!(report.rejected, ["db_path"]);
+    assert!(report.applied.is_empty());
+    assert_eq!(service.resultant_password_policy(&bob).await.unwrap().min_length, 14);
+
+    write("security:\n  password_policy:\n    min_length: 14\n  rate_limt: {}\n");
+    let report = reloader.reload();
+    assert!(report.errors[0].contains("unknown field `rate_limt`"), "{:?}", report);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,129 @@
+// tests/integration/replication.rs
+
+use std::sync::Arc;
+
+use nextDomen::config::ReplicationConfig;
+use nextDomen::directory_service::{DirectoryError, DirectoryService};
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::{mfa, Domain, SecurityIdentifier, Site};
+use nextDomen::raddb::RadDB;
+
+use super::TestDirectory;
+
+const BRANCH_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+
+dn: CN=erin,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: erin
+
+dn: CN=Branch-Users,CN=Users,DC=x,DC=com
+objectClass: group
+sAMAccountName: Branch-Users
+member: CN=bob,CN=Users,DC=x,DC=com
+";
+
+#[tokio::test]
+async fn test_read_only_replica() {
+    let master = TestDirectory::new().await;
+    let report = master.service.import_ldif(BRANCH_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    for name in ["bob", "erin"] {
+        let mut user = master.service.find_user_by_username(name).await.unwrap().unwrap();
+        master.service.set_password(user.id, "Correct-Horse-Battery-9").await.unwrap();
+        user = master.service.get_user(user.id).await.unwrap().unwrap();
+        user.totp_secret = Some(mfa::generate_totp_secret());
+        master.service.update_user(&user).await.unwrap();
+    }
+    assert!(matches!(
+        master.service.replication_snapshot(&["Nobody".to_string()], None).await,
+        Err(DirectoryError::NotFound(_))
+    ));
+    let snapshot = master.service.replication_snapshot(&["Branch-Users".to_string()], None).await.unwrap();
+    assert!(snapshot.iter().all(|(key, _)| !key.starts_with("audit")));
+    assert!(matches!(
+        master.service.apply_replication_snapshot(snapshot.clone(), "master").await,
+        Err(DirectoryError::InvalidInput(_))
+    ));
+
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let db_path = dir.join("directory.db");
+    let replica = Arc::new(
+        DirectoryService::open(db_path.to_str().unwrap(), &RadDB::generate_key()).unwrap()
+            .with_replication(&ReplicationConfig {
+                read_only: true,
+                upstream: Some("https://dc01.x.com:8080".into()),
+                ..ReplicationConfig::default()
+            }),
+    );
+    replica.apply_replication_snapshot(snapshot, "https://dc01.x.com:8080").await.unwrap();
+
+    // Каталог на месте, пароль кэширован только у участника Branch-Users
+    assert!(replica.find_group_by_sam_account_name("Branch-Users").await.unwrap().is_some());
+    replica.authenticate("bob", "Correct-Horse-Battery-9").await.unwrap();
+    let erin = replica.find_user_by_username("erin").await.unwrap().unwrap();
+    assert!(erin.totp_secret.is_none());
+    assert!(replica.find_user_by_username("bob").await.unwrap().unwrap().totp_secret.is_some());
+    assert!(matches!(
+        replica.authenticate("erin", "Correct-Horse-Battery-9").await,
+        Err(DirectoryError::AuthenticationFailed(_))
+    ));
+
+    // Изменения отклоняются со ссылкой на записываемый контроллер
+    let refused = |result: Result<(), DirectoryError>| {
+        matches!(result, Err(DirectoryError::ReadOnly(upstream)) if upstream == "https://dc01.x.com:8080")
+    };
+    assert!(refused(replica.set_password(erin.id, "Another-Horse-Battery-7").await));
+    assert!(refused(replica.delete_user(erin.id).await));
+    assert!(refused(replica.save_site(&Site::new("Branch".into())).await));
+
+    drop(replica);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_snapshot_keeps_ca_key_local() {
+    let master = TestDirectory::new().await;
+    let ca = master.service.init_ca(false).await.unwrap();
+
+    // Ни записи CA, ни его закрытого ключа в другом месте снимка
+    let snapshot = master.service.replication_snapshot(&[], None).await.unwrap();
+    assert!(snapshot.iter().all(|(key, _)| key != "ca"));
+    let key_pem = ca.key_pem.as_bytes();
+    assert!(snapshot.iter().all(|(_, value)| !value.windows(key_pem.len()).any(|window| window == key_pem)));
+}
+
+#[tokio::test]
+async fn test_read_only_database() {
+    let dir = std::env::temp_dir().join(format!("nextdomen-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let db_path = dir.join("directory.db");
+    let db_path = db_path.to_str().unwrap();
+    let key = RadDB::generate_key();
+    let master = DirectoryService::open(db_path, &key).unwrap();
+    let domain = Domain::new("x.com", "x.com", SecurityIdentifier::new_nt_authority(21));
+    master.create_domain(&domain).await.unwrap();
+    master.import_ldif(BRANCH_LDIF, DuplicatePolicy::Skip).await.unwrap();
+
+    // Второй процесс на запись не откроет базу, только для чтения — откроет
+    assert!(DirectoryService::open(db_path, &key).is_err());
+    let reader = DirectoryService::open_read_only(db_path, &key).unwrap();
+    assert!(reader.is_read_only() && !reader.is_replica());
+    assert_eq!(reader.get_all_users().await.unwrap().len(), 2);
+    assert!(reader.export_ldif().await.unwrap().contains("sAMAccountName: bob"));
+
+    // Изменения отклоняются до записи, в том числе в обход проверок сервиса
+    let mut bob = reader.find_user_by_username("bob").await.unwrap().unwrap();
+    bob.display_name = Some("Bob".into());
+    assert!(matches!(reader.update_user(&bob).await, Err(DirectoryError::DatabaseReadOnly)));
+    assert!(matches!(reader.delete_user(bob.id).await, Err(DirectoryError::DatabaseReadOnly)));
+    assert!(matches!(reader.save_site(&Site::new("hq".into())).await, Err(DirectoryError::DatabaseReadOnly)));
+    assert!(reader.find_site_by_name("hq").await.unwrap().is_none());
+
+    drop(reader);
+    drop(master);
+    let _ = std::fs::remove_dir_all(&dir);
+}
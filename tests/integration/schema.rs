@@ -0,0 +1,89 @@
+// tests/integration/schema.rs
+
+use axum::http::StatusCode;
+use serde_json::json;
+use nextDomen::models::apikey::scope;
+
+use super::{call, request, TestDirectory};
+
+#[tokio::test]
+async fn test_schema_admin_routes() {
+    let directory = TestDirectory::new().await;
+    let (_, reader) = directory.api_key("alice", true, &[scope::DIRECTORY_READ]).await;
+    let (_, writer) = directory.api_key("carol", true, &[scope::DIRECTORY_WRITE]).await;
+    let (_, user_writer) = directory.api_key("bob", false, &[scope::DIRECTORY_WRITE]).await;
+    let app = directory.router("");
+    let define = |key, body| request("POST", "/api/schema/attributes", Some(key), Some(body));
+    let badge = json!({ "name": "badgeId", "syntax": "string", "classes": ["user"] });
+
+    // Определяет и удаляет атрибуты только администратор с `directory:write`
+    let (status, body) = call(&app, define(&reader, badge.clone())).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    let (status, body) = call(&app, define(&user_writer, badge.clone())).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("ACCESS_DENIED")));
+    let (status, created) = call(&app, define(&writer, badge.clone())).await;
+    assert_eq!((status, created["name"].as_str()), (StatusCode::CREATED, Some("badgeId")), "{}", created);
+    assert_eq!(call(&app, define(&writer, badge)).await.0, StatusCode::CONFLICT);
+
+    // Встроенный атрибут, неверное имя и атрибут без классов не определяются
+    for body in [
+        json!({ "name": "mail", "syntax": "string", "classes": ["user"] }),
+        json!({ "name": "1bad", "syntax": "string", "classes": ["user"] }),
+        json!({ "name": "orphan", "syntax": "string", "classes": [] }),
+    ] {
+        let (status, response) = call(&app, define(&writer, body.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} -> {}", body, response);
+    }
+
+    // Список схемы доступен без входа
+    let (status, schema) = call(&app, request("GET", "/api/schema/attributes", None, None)).await;
+    assert_eq!((status, schema.as_array().map(Vec::len)), (StatusCode::OK, Some(1)));
+
+    let uri = "/api/schema/attributes/badgeId";
+    assert_eq!(call(&app, request("DELETE", uri, Some(&reader), None)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, request("DELETE", uri, Some(&writer), None)).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(call(&app, request("DELETE", uri, Some(&writer), None)).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_attributes_validated_on_create_and_update() {
+    let directory = TestDirectory::new().await;
+    let (_, writer) = directory.api_key("carol", true, &[scope::DIRECTORY_WRITE]).await;
+    let app = directory.router("");
+    let define = |body| request("POST", "/api/schema/attributes", Some(&writer), Some(body));
+    let (status, body) = call(&app, define(json!({ "name": "employeeNumber", "syntax": "integer", "indexed": true, "classes": ["user"] }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let (status, body) = call(&app, define(json!({ "name": "costCenter", "syntax": "string", "required": true, "classes": ["group"] }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    // Создание: синтаксис, однозначность, неизвестный атрибут и атрибут чужого класса
+    let create_user = |attributes| request("POST", "/api/users", Some(&writer), Some(json!({ "username": "dave", "attributes": attributes })));
+    for attributes in [
+        json!({ "employeeNumber": ["abc"] }),
+        json!({ "employeeNumber": ["1", "2"] }),
+        json!({ "nickname": ["dee"] }),
+        json!({ "costCenter": ["R&D"] }),
+    ] {
+        let (status, body) = call(&app, create_user(attributes.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} -> {}", attributes, body);
+    }
+    assert!(directory.service.find_user_by_username("dave").await.unwrap().is_none());
+    let (status, dave) = call(&app, create_user(json!({ "employeeNumber": ["42"] }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", dave);
+    assert_eq!(dave["attributes"]["employeeNumber"], json!(["42"]));
+
+    // Изменение проверяется так же; прежнее значение остаётся
+    let update = |attributes| request("PUT", "/api/users/dave", Some(&writer), Some(json!({ "attributes": attributes })));
+    assert_eq!(call(&app, update(json!({ "employeeNumber": ["forty-three"] }))).await.0, StatusCode::BAD_REQUEST);
+    let (status, found) = call(&app, request("GET", "/api/users?attribute=employeeNumber&value=42", Some(&writer), None)).await;
+    assert_eq!((status, found.as_array().map(Vec::len)), (StatusCode::OK, Some(1)));
+    assert_eq!(call(&app, update(json!({ "employeeNumber": ["43"] }))).await.0, StatusCode::OK);
+    let (_, found) = call(&app, request("GET", "/api/users?attribute=employeeNumber&value=43", Some(&writer), None)).await;
+    assert_eq!(found[0]["username"].as_str(), Some("dave"));
+
+    // Обязательный атрибут группы
+    let create_group = |body| request("POST", "/api/groups", Some(&writer), Some(body));
+    assert_eq!(call(&app, create_group(json!({ "name": "Finance" }))).await.0, StatusCode::BAD_REQUEST);
+    let (status, body) = call(&app, create_group(json!({ "name": "Finance", "attributes": { "costCenter": ["F-1"] } }))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+}
@@ -0,0 +1,170 @@
+// tests/integration/search.rs
+
+use std::time::Duration;
+
+use nextDomen::directory_service::{DirectoryError, SearchLimits};
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::constructed::{self, PRIMARY_GROUP_TOKEN, SCOPE_BASE, TOKEN_GROUPS};
+use nextDomen::models::{DomainController, Group, GroupScope, GroupTypeFlags, LdapContext, LdapEntry, LdapFilter, SecurityIdentifier};
+
+use super::TestDirectory;
+
+#[tokio::test]
+async fn test_scan_users_pages() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let ldif: String = (0..5)
+        .map(|i| format!("dn: CN=user{i},CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: user{i}\n\n"))
+        .collect();
+    service.import_ldif(&ldif, DuplicatePolicy::Skip).await.unwrap();
+    let total = service.get_all_users().await.unwrap().len();
+    assert!(total >= 5);
+
+    let mut names = Vec::new();
+    let mut cursor = Some(0);
+    while let Some(position) = cursor {
+        let page = service.scan_users(position, 2).await.unwrap();
+        assert!(page.items.len() <= 2);
+        names.extend(page.items.into_iter().map(|user| user.username));
+        cursor = page.next;
+    }
+    assert_eq!(names.len(), total);
+    assert!((0..5).all(|i| names.contains(&format!("user{i}"))));
+
+    // Удалённый между страницами пользователь пропускается
+    let first = service.scan_users(0, 2).await.unwrap();
+    let user3 = service.find_user_by_username("user3").await.unwrap().unwrap();
+    service.delete_user(user3.id).await.unwrap();
+    let rest = service.scan_users(first.next.unwrap(), usize::MAX).await.unwrap();
+    assert!(rest.next.is_none());
+    assert!(rest.items.iter().all(|user| user.username != "user3"));
+
+    assert!(service.scan_users(total + 10, 2).await.unwrap().items.is_empty());
+}
+
+#[tokio::test]
+async fn test_list_within_limits() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let ldif: String = (0..3)
+        .map(|i| format!("dn: CN=user{i},CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: user{i}\n\n"))
+        .collect();
+    service.import_ldif(&ldif, DuplicatePolicy::Skip).await.unwrap();
+    let total = service.get_all_users().await.unwrap().len();
+
+    let unlimited = service.list_users_within(SearchLimits::new(0, 0)).await.unwrap();
+    assert_eq!(unlimited.len(), total);
+    assert_eq!(service.list_users_within(SearchLimits::new(total, 0)).await.unwrap().len(), total);
+    assert!(matches!(
+        service.list_users_within(SearchLimits::new(total - 1, 0)).await,
+        Err(DirectoryError::SizeLimitExceeded(limit)) if limit == total - 1
+    ));
+
+    let expired = SearchLimits { size_limit: 0, time_limit: Some(Duration::ZERO) };
+    assert!(matches!(service.list_groups_within(expired).await, Err(DirectoryError::TimeLimitExceeded(0))));
+}
+
+#[tokio::test]
+async fn test_object_guid() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let ldif = "dn: OU=Sales,DC=x,DC=com\nobjectClass: organizationalUnit\nou: Sales\n\n\
+                dn: CN=bob,OU=Sales,DC=x,DC=com\nobjectClass: user\nsAMAccountName: bob\n";
+    service.import_ldif(ldif, DuplicatePolicy::Fail).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+
+    // objectGUID — UUID в порядке байтов GUID Windows
+    let (dn, entry) = bob.ldap_entry(&LdapContext::new(service).await.unwrap()).await.unwrap();
+    assert_eq!(entry.get("objectGUID").unwrap()[0].as_bytes(), bob.id.to_bytes_le());
+    assert_eq!(entry.object_guid(), Some(bob.id));
+
+    // В фильтре — строкой GUID или экранированными байтами
+    let escaped: String = bob.id.to_bytes_le().iter().map(|b| format!("\\{:02x}", b)).collect();
+    for value in [bob.id.to_string(), format!("{{{}}}", bob.id), escaped] {
+        assert!(LdapFilter::parse(&format!("(objectGUID={})", value)).unwrap().matches(&entry), "{}", value);
+    }
+    assert!(!LdapFilter::parse(&format!("(objectGUID={})", uuid::Uuid::new_v4())).unwrap().matches(&entry));
+
+    let object = service.find_object_by_guid(bob.id).await.unwrap().unwrap();
+    assert_eq!((object.object_type.as_str(), object.dn, object.name.as_str()), ("user", dn, "bob"));
+    let sales = service.find_ou_by_dn("OU=Sales,DC=x,DC=com").await.unwrap().unwrap();
+    assert_eq!(service.find_object_by_guid(sales.id).await.unwrap().unwrap().object_type, "ou");
+    assert!(service.find_object_by_guid(uuid::Uuid::new_v4()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_anr() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let ldif = "dn: CN=John Smith,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: jsmith\n\
+                givenName: John\nsn: Smith\nmail: john.smith@x.com\n";
+    service.import_ldif(ldif, DuplicatePolicy::Fail).await.unwrap();
+    let john = service.find_user_by_username("jsmith").await.unwrap().unwrap();
+    let (_, entry) = john.ldap_entry(&LdapContext::new(service).await.unwrap()).await.unwrap();
+    let anr = |value: &str| LdapFilter::parse(&format!("(anr={})", value)).unwrap().matches(&entry);
+
+    // Начало любого из атрибутов ANR без учёта регистра
+    for value in ["jo", "SMI", "jsm", "john.smith@", "John Smith"] {
+        assert!(anr(value), "{}", value);
+    }
+    // «Имя фамилия» в обоих порядках
+    assert!(anr("jo sm"));
+    assert!(anr("Smith J"));
+    assert!(!anr("jo x"));
+    assert!(!anr("ohn"));
+    // `=` — точное совпадение
+    assert!(anr("=jsmith"));
+    assert!(!anr("=jsm"));
+}
+
+#[tokio::test]
+async fn test_constructed_attributes() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let domain = DomainController::new(service.clone()).bootstrap_domain("corp".to_string(), "corp.example.com".to_string()).await.unwrap();
+    service.import_ldif("dn: CN=bob,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: bob\n", DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let mut sales = Group::new("Sales".into(), "Sales".into(), domain.id, GroupTypeFlags::SECURITY, GroupScope::Global);
+    sales.sid = domain.sid.with_rid(1200);
+    service.create_group(&sales).await.unwrap();
+    service.add_member_to_group(sales.id, bob.id, None).await.unwrap();
+    let domain_users = service.find_group_by_sam_account_name("Domain Users").await.unwrap().unwrap();
+
+    let ctx = LdapContext::new(service).await.unwrap();
+    let all = ["*".to_string()];
+    let named = ["cn".to_string(), "TOKENGROUPS".to_string(), "primarygrouptoken".to_string()];
+
+    // Ни хранимая запись, ни `*` конструируемых атрибутов не содержат
+    let (_, mut entry) = bob.ldap_entry(&ctx).await.unwrap();
+    assert!(entry.get(TOKEN_GROUPS).is_none());
+    constructed::add_user_attributes(service, &bob, SCOPE_BASE, &all, &mut entry).await.unwrap();
+    assert!(entry.get(TOKEN_GROUPS).is_none());
+
+    // Названный явно tokenGroups вычисляется только при области base
+    for scope in [1, 2] {
+        constructed::add_user_attributes(service, &bob, scope, &named, &mut entry).await.unwrap();
+        assert!(entry.get(TOKEN_GROUPS).is_none(), "scope {}", scope);
+    }
+    constructed::add_user_attributes(service, &bob, SCOPE_BASE, &named, &mut entry).await.unwrap();
+    // Значения — двоичные SID, а не строки `S-1-…`; основная группа тоже входит
+    let values = entry.get(TOKEN_GROUPS).unwrap();
+    assert!(values.iter().all(|value| !value.as_bytes().starts_with(b"S-")));
+    let sids: Vec<SecurityIdentifier> = values.iter()
+        .map(|value| SecurityIdentifier::from_bytes(value.as_bytes()).unwrap())
+        .collect();
+    assert!(sids.contains(&sales.sid));
+    assert!(sids.contains(&domain_users.sid));
+
+    // primaryGroupToken — RID группы целым числом, только по запросу
+    let (_, mut group_entry) = domain_users.ldap_entry(&ctx).await.unwrap();
+    constructed::add_group_attributes(&domain_users, &all, &mut group_entry);
+    assert!(group_entry.get(PRIMARY_GROUP_TOKEN).is_none());
+    constructed::add_group_attributes(&domain_users, &named, &mut group_entry);
+    let token = group_entry.get(PRIMARY_GROUP_TOKEN).unwrap()[0].as_text().unwrap();
+    assert_eq!(token.parse::<u32>().unwrap(), 513);
+    assert_eq!(Some(token.parse::<u32>().unwrap()), bob.primary_group_id);
+
+    let (_, mut sales_entry) = sales.ldap_entry(&ctx).await.unwrap();
+    constructed::add_group_attributes(&sales, &named, &mut sales_entry);
+    assert_eq!(sales_entry.get(PRIMARY_GROUP_TOKEN).unwrap()[0].as_text(), Some("1200"));
+}
@@ -0,0 +1,140 @@
+// tests/integration/service_accounts.rs
+
+use axum::http::StatusCode;
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::{ServiceAccount, ServiceAccountFlags};
+
+use super::{call, request, TestDirectory};
+
+const WEB_LDIF: &str = "\
+dn: CN=bob,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+
+dn: CN=erin,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: erin
+
+dn: CN=WebServers,CN=Users,DC=x,DC=com
+objectClass: group
+sAMAccountName: WebServers
+member: CN=bob,CN=Users,DC=x,DC=com
+";
+
+#[tokio::test]
+async fn test_managed_password_retrieval_and_rotation() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(WEB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let erin = service.find_user_by_username("erin").await.unwrap().unwrap();
+    let group = service.find_group_by_sam_account_name("WebServers").await.unwrap().unwrap();
+
+    let mut account = ServiceAccount::new(uuid::Uuid::new_v4(), vec![group.sid]);
+    account.flags = ServiceAccountFlags::empty();
+    let user = service.create_service_account("svc-web", &["HTTP/web.x.com".to_string()], &account).await.unwrap();
+    assert_eq!(user.service_principal_names, vec!["HTTP/web.x.com".to_string()]);
+
+    // Пароль выдаётся только членам WebServers
+    let first = service.retrieve_managed_password(&bob, user.id).await.unwrap();
+    assert!(first.previous_password.is_none());
+    assert!(matches!(service.retrieve_managed_password(&erin, user.id).await, Err(DirectoryError::AccessDenied(_))));
+    service.authenticate("svc-web", &first.current_password).await.unwrap();
+
+    // После смены прежний пароль принимается до конца окна перекрытия
+    service.rotate_service_account_password(user.id).await.unwrap();
+    let second = service.retrieve_managed_password(&bob, user.id).await.unwrap();
+    assert_ne!(second.current_password, first.current_password);
+    assert_eq!(second.previous_password.as_deref(), Some(first.current_password.as_str()));
+    service.authenticate("svc-web", &second.current_password).await.unwrap();
+    service.authenticate("svc-web", &first.current_password).await.unwrap();
+
+    // Пароль задаётся только ротацией
+    assert!(service.set_password(user.id, "P@ssw0rd123").await.is_err());
+}
+
+#[tokio::test]
+async fn test_service_account_routes_require_scopes() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(WEB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let group = service.find_group_by_sam_account_name("WebServers").await.unwrap().unwrap();
+    service.create_service_account("svc-web", &[], &ServiceAccount::new(uuid::Uuid::new_v4(), vec![group.sid])).await.unwrap();
+    let (_, bob_writer) = directory.api_key("bob", false, &[scope::DIRECTORY_WRITE]).await;
+    let (_, bob_password) = directory.api_key("bob", false, &[scope::SERVICE_ACCOUNTS_PASSWORD]).await;
+    let (_, admin_writer) = directory.api_key("alice", true, &[scope::DIRECTORY_WRITE]).await;
+    let (_, admin_manager) = directory.api_key("alice", true, &[scope::SERVICE_ACCOUNTS_MANAGE]).await;
+    let app = directory.router("");
+
+    // Пароль — только с `service-accounts:password` и только участнику `allowed_principals`
+    let password = "/api/service-accounts/svc-web/password";
+    let (status, body) = call(&app, request("GET", password, Some(&bob_writer), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    let (status, body) = call(&app, request("GET", password, Some(&bob_password), None)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["current_password"].is_string());
+
+    // Управление и ротация — с `service-accounts:manage` у администратора
+    let rotate = "/api/service-accounts/svc-web/rotate";
+    for request in [request("GET", "/api/service-accounts", Some(&admin_writer), None), request("POST", rotate, Some(&admin_writer), None)] {
+        let (status, body) = call(&app, request).await;
+        assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    }
+    assert_eq!(call(&app, request("POST", rotate, Some(&bob_password), None)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, request("POST", rotate, Some(&admin_manager), None)).await.0, StatusCode::OK);
+    let (status, listed) = call(&app, request("GET", "/api/service-accounts", Some(&admin_manager), None)).await;
+    assert_eq!((status, listed.as_array().map(Vec::len)), (StatusCode::OK, Some(1)));
+}
+
+#[tokio::test]
+async fn test_password_logon_denied_by_default() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(WEB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+
+    let account = ServiceAccount::new(uuid::Uuid::new_v4(), vec![bob.sid.clone()]);
+    let user = service.create_service_account("svc-sql", &[], &account).await.unwrap();
+    let password = service.retrieve_managed_password(&bob, user.id).await.unwrap();
+    assert!(matches!(
+        service.authenticate("svc-sql", &password.current_password).await,
+        Err(DirectoryError::AuthenticationFailed(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_export_keytab() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    service.import_ldif(WEB_LDIF, DuplicatePolicy::Skip).await.unwrap();
+    let group = service.find_group_by_sam_account_name("WebServers").await.unwrap().unwrap();
+    let account = ServiceAccount::new(uuid::Uuid::new_v4(), vec![group.sid]);
+    let user = service.create_service_account("svc-web", &["HTTP/web.x.com".to_string()], &account).await.unwrap();
+
+    let entries = service.export_keytab("HTTP/web.x.com").await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].principal.components, vec!["HTTP".to_string(), "web.x.com".to_string()]);
+    assert_eq!(entries[0].realm, "X.COM");
+    let user = service.get_user(user.id).await.unwrap().unwrap();
+    assert_eq!(entries[0].key, user.kerberos_keys[0].key);
+
+    // На время перекрытия ротации в keytab и прежний ключ
+    service.rotate_service_account_password(user.id).await.unwrap();
+    let entries = service.export_keytab("HTTP/web.x.com@x.com").await.unwrap();
+    let mut kvnos: Vec<u32> = entries.iter().map(|e| e.kvno).collect();
+    kvnos.sort();
+    assert_eq!(kvnos, vec![1, 2]);
+
+    let bytes = nextDomen::kerberos::keytab::encode(&entries[..1]);
+    assert_eq!(&bytes[..2], &[0x05, 0x02]);
+    let size = i32::from_be_bytes(bytes[2..6].try_into().unwrap()) as usize;
+    assert_eq!(bytes.len(), 6 + size);
+    // 2 компонента, realm "X.COM", "HTTP"
+    assert_eq!(&bytes[6..15], &[0, 2, 0, 5, b'X', b'.', b'C', b'O', b'M']);
+    assert_eq!(&bytes[15..21], b"\x00\x04HTTP");
+    assert_eq!(&bytes[bytes.len() - 4..], &entries[0].kvno.to_be_bytes());
+
+    assert!(matches!(service.export_keytab("HTTP/missing.x.com").await, Err(DirectoryError::NotFound(_))));
+}
@@ -0,0 +1,106 @@
+// tests/integration/sids.rs
+
+use axum::http::StatusCode;
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::well_known::rid;
+use nextDomen::models::{DomainController, SecurityIdentifier};
+
+use super::{call, request, TestDirectory};
+
+#[test]
+fn test_sid_round_trip() {
+    let cases = [
+        // Администратор домена: RID 500
+        (
+            "S-1-5-21-1004336348-1177238915-682003330-500",
+            "010500000000000515000000dcf4dc3b833d2b46828ba628f4010000",
+        ),
+        // BUILTIN\Administrators
+        ("S-1-5-32-544", "01020000000000052000000020020000"),
+        ("S-1-1-0", "010100000000000100000000"),
+        ("S-1-5", "0100000000000005"),
+        // IdentifierAuthority от 2^32 записывается в hex
+        ("S-1-0x123456789ABC-7", "0101123456789abc07000000"),
+    ];
+    for (text, binary) in cases {
+        let sid: SecurityIdentifier = text.parse().unwrap();
+        assert_eq!(sid.to_string(), text);
+        assert_eq!(hex::encode(sid.to_bytes()), binary, "{}", text);
+        assert_eq!(SecurityIdentifier::from_bytes(&hex::decode(binary).unwrap()).unwrap(), sid, "{}", text);
+    }
+
+    let sid: SecurityIdentifier = "S-1-5-21-1004336348-1177238915-682003330-500".parse().unwrap();
+    assert!(sid.is_domain_account());
+    assert_eq!(sid.rid(), Some(500));
+    assert_eq!(SecurityIdentifier::new_builtin(544).to_string(), "S-1-5-32-544");
+    assert_eq!("s-1-5-32-544".parse::<SecurityIdentifier>().unwrap(), SecurityIdentifier::new_builtin(544));
+    assert_eq!("S-1-0x5-18".parse::<SecurityIdentifier>().unwrap().to_string(), "S-1-5-18");
+}
+
+#[test]
+fn test_malformed_sids() {
+    let too_many = format!("S-1-5{}", "-1".repeat(16));
+    let invalid = [
+        "", "S", "S-1", "X-1-5-32-544", "1-5-32-544", "S-x-5-32", "S-256-5-32", "S-1-5-32-", "S-1-5--544",
+        "S-1-5-32-abc", "S-1-5-32-4294967296", "S-1-5-32--1", "S-1-0x1000000000000-1", "S-1-281474976710656-1", &too_many,
+    ];
+    for text in invalid {
+        assert!(text.parse::<SecurityIdentifier>().is_err(), "{:?}", text);
+    }
+    assert!(format!("S-1-5{}", "-1".repeat(15)).parse::<SecurityIdentifier>().is_ok());
+
+    let admins = hex::decode("01020000000000052000000020020000").unwrap();
+    // Короче заголовка, число sub-authority не сходится с длиной, больше 15 sub-authority
+    for bytes in [&admins[..7], &admins[..15], &[admins.as_slice(), &[0]].concat(), &[1, 16, 0, 0, 0, 0, 0, 5]] {
+        assert!(SecurityIdentifier::from_bytes(bytes).is_err(), "{}", hex::encode(bytes));
+    }
+    let mut wrong_count = admins.clone();
+    wrong_count[1] = 1;
+    assert!(SecurityIdentifier::from_bytes(&wrong_count).is_err());
+}
+
+#[tokio::test]
+async fn test_well_known_principals() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let controller = DomainController::new(service.clone());
+    let domain = controller.bootstrap_domain("corp".to_string(), "corp.example.com.".to_string()).await.unwrap();
+    let administrator = controller.create_administrator(&domain, "Administrator").await.unwrap();
+
+    // Учётные записи и группы домена — SID домена с каноническим RID
+    assert_eq!(administrator.sid, domain.sid.with_rid(500));
+    let guest = service.find_user_by_username("Guest").await.unwrap().unwrap();
+    assert_eq!(guest.sid, domain.sid.with_rid(501));
+    assert!(!guest.enabled);
+    assert_eq!(guest.primary_group_id, Some(rid::DOMAIN_GUESTS));
+    for (name, expected) in [("Domain Admins", 512), ("Domain Users", 513), ("Domain Computers", 515)] {
+        let group = service.find_group_by_sam_account_name(name).await.unwrap().unwrap();
+        assert_eq!(group.sid, domain.sid.with_rid(expected), "{}", name);
+        assert_eq!(group.get_rid(), expected);
+        assert_eq!(service.find_group_by_sid(&group.sid).await.unwrap().map(|found| found.id), Some(group.id));
+    }
+    let administrators = service.find_group_by_sam_account_name("Administrators").await.unwrap().unwrap();
+    assert_eq!(administrators.sid.to_string(), "S-1-5-32-544");
+    assert!(administrators.is_builtin());
+    assert!(service.is_domain_admin(administrator.id).await.unwrap());
+
+    // Встроенные объекты не удаляются ни через сервис, ни через REST
+    assert!(matches!(service.delete_user(administrator.id).await, Err(DirectoryError::Protected(_))));
+    assert!(matches!(service.delete_group(administrators.id).await, Err(DirectoryError::Protected(_))));
+    let (_, key) = directory.api_key("operator", true, &[scope::DIRECTORY_WRITE]).await;
+    let app = directory.router("");
+    let (status, body) = call(&app, request("DELETE", "/api/users/Guest", Some(&key), None)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+    assert!(service.find_user_by_username("Guest").await.unwrap().is_some());
+
+    // Новый пользователь — в Domain Users, RID из пула, а не канонический
+    service.import_ldif("dn: CN=bob,CN=Users,DC=corp,DC=example,DC=com\nobjectClass: user\nsAMAccountName: bob\n", DuplicatePolicy::Skip).await.unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    assert_eq!(bob.primary_group_id, Some(rid::DOMAIN_USERS));
+    let domain_users = service.find_group_by_sam_account_name("Domain Users").await.unwrap().unwrap();
+    assert!(service.find_groups_by_member(bob.id).await.unwrap().iter().any(|group| group.id == domain_users.id));
+    assert!(bob.sid.rid().unwrap() >= 1000);
+    service.delete_user(bob.id).await.unwrap();
+}
@@ -0,0 +1,81 @@
+// tests/integration/sites.rs
+
+use axum::http::StatusCode;
+use serde_json::json;
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::models::apikey::scope;
+use nextDomen::models::{Site, Subnet};
+
+use super::{call, request, TestDirectory};
+
+#[tokio::test]
+async fn test_find_site_for_ip() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+
+    let hq = Site::new("HQ".into());
+    let branch = Site::new("Branch".into());
+    service.save_site(&hq).await.unwrap();
+    service.save_site(&branch).await.unwrap();
+    assert!(matches!(service.save_site(&Site::new("hq".into())).await, Err(DirectoryError::AlreadyExists(..))));
+
+    assert!(Subnet::new("10.1.2.0/16", hq.id).is_err(), "host bits are set");
+    assert!(Subnet::new("10.0.0.0/33", hq.id).is_err());
+    service.save_subnet(&Subnet::new("10.0.0.0/8", hq.id).unwrap()).await.unwrap();
+    service.save_subnet(&Subnet::new("10.20.0.0/16", branch.id).unwrap()).await.unwrap();
+    service.save_subnet(&Subnet::new("2001:db8::/32", branch.id).unwrap()).await.unwrap();
+    assert!(matches!(
+        service.save_subnet(&Subnet::new("10.20.0.0/16", hq.id).unwrap()).await,
+        Err(DirectoryError::AlreadyExists(..))
+    ));
+
+    // Самая узкая подсеть; IPv4, отображённый в IPv6, — как IPv4
+    let site_for = |ip: &str| {
+        let ip = ip.parse().unwrap();
+        async move { service.find_site_for_ip(ip).await.unwrap().map(|site| site.name) }
+    };
+    assert_eq!(site_for("10.1.2.3").await.as_deref(), Some("HQ"));
+    assert_eq!(site_for("10.20.5.6").await.as_deref(), Some("Branch"));
+    assert_eq!(site_for("::ffff:10.20.5.6").await.as_deref(), Some("Branch"));
+    assert_eq!(site_for("2001:db8:1::1").await.as_deref(), Some("Branch"));
+    assert_eq!(site_for("192.168.1.1").await, None);
+
+    // Сайт с подсетями не удаляется
+    assert!(matches!(service.delete_site(branch.id).await, Err(DirectoryError::InvalidInput(_))));
+    for subnet in service.get_site_subnets(branch.id).await.unwrap() {
+        service.delete_subnet(subnet.id).await.unwrap();
+    }
+    service.delete_site(branch.id).await.unwrap();
+    assert_eq!(site_for("10.20.5.6").await.as_deref(), Some("HQ"));
+}
+
+#[tokio::test]
+async fn test_site_routes() {
+    let directory = TestDirectory::new().await;
+    let (_, admin_reader) = directory.api_key("alice", true, &[scope::DIRECTORY_READ]).await;
+    let (_, admin_writer) = directory.api_key("carol", true, &[scope::DIRECTORY_WRITE]).await;
+    let (_, user_reader) = directory.api_key("bob", false, &[scope::DIRECTORY_READ]).await;
+    let app = directory.router("");
+
+    // Изменения — администратору с `directory:write`; ключ только для чтения получает отказ
+    let create_site = |key| request("POST", "/api/sites", Some(key), Some(json!({ "name": "HQ" })));
+    let (status, body) = call(&app, create_site(&admin_reader)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    assert_eq!(call(&app, create_site(&admin_writer)).await.0, StatusCode::CREATED);
+    let create_subnet = |key| request("POST", "/api/subnets", Some(key), Some(json!({ "subnet": "10.1.0.0/16", "site": "HQ" })));
+    assert_eq!(call(&app, create_subnet(&admin_reader)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, create_subnet(&admin_writer)).await.0, StatusCode::CREATED);
+    let rename = |key| request("PUT", "/api/sites/HQ", Some(key), Some(json!({ "location": "Moscow" })));
+    assert_eq!(call(&app, rename(&admin_reader)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, rename(&admin_writer)).await.0, StatusCode::OK);
+
+    // Чтение — любому вошедшему с `directory:read`
+    let (status, site) = call(&app, request("GET", "/api/sites/lookup?ip=10.1.2.3", Some(&user_reader), None)).await;
+    assert_eq!((status, site["name"].as_str()), (StatusCode::OK, Some("HQ")), "{}", site);
+    let (status, subnets) = call(&app, request("GET", "/api/subnets", Some(&user_reader), None)).await;
+    assert_eq!((status, subnets.as_array().map(Vec::len)), (StatusCode::OK, Some(1)));
+
+    assert_eq!(call(&app, request("DELETE", "/api/subnets/10.1.0.0/16", Some(&admin_reader), None)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call(&app, request("DELETE", "/api/subnets/10.1.0.0/16", Some(&admin_writer), None)).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(call(&app, request("DELETE", "/api/sites/HQ", Some(&admin_writer), None)).await.0, StatusCode::NO_CONTENT);
+}
@@ -0,0 +1,70 @@
+// tests/integration/stats.rs
+
+use axum::http::StatusCode;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::apikey::scope;
+
+use super::{call, request, TestDirectory};
+
+const SALES_LDIF: &str = "\
+dn: OU=Sales,DC=x,DC=com
+objectClass: organizationalUnit
+ou: Sales
+
+dn: CN=bob,OU=Sales,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+
+dn: CN=carol,OU=Sales,DC=x,DC=com
+objectClass: user
+sAMAccountName: carol
+
+dn: CN=erin,CN=Users,DC=x,DC=com
+objectClass: user
+sAMAccountName: erin
+";
+
+#[tokio::test]
+async fn test_directory_stats() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let report = service.import_ldif(SALES_LDIF, DuplicatePolicy::Fail).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let mut carol = service.find_user_by_username("carol").await.unwrap().unwrap();
+    carol.enabled = true;
+    service.update_user(&carol).await.unwrap();
+    let before = service.stats().await.unwrap();
+
+    carol.enabled = false;
+    service.update_user(&carol).await.unwrap();
+    let mut erin = service.find_user_by_username("erin").await.unwrap().unwrap();
+    erin.lockout_until = Some(chrono::Utc::now() + chrono::Duration::hours(1));
+    service.update_user(&erin).await.unwrap();
+    let stats = service.stats().await.unwrap();
+
+    assert_eq!(stats.objects["user"], service.get_all_users().await.unwrap().len());
+    assert_eq!(stats.objects["ou"], service.get_all_ous().await.unwrap().len());
+    assert_eq!(stats.disabled_users, before.disabled_users + 1);
+    assert_eq!(stats.locked_users, 1);
+    let sales = stats.users_per_ou.iter().find(|row| row.dn.as_deref() == Some("OU=Sales,DC=x,DC=com")).unwrap();
+    assert_eq!(sales.users, 2);
+    assert_eq!(stats.users_per_ou.iter().map(|row| row.users).sum::<usize>(), stats.objects["user"]);
+    assert!(stats.db_size_bytes > 0);
+    assert!(stats.last_flush.is_some_and(|flush| flush >= before.generated_at));
+}
+
+#[tokio::test]
+async fn test_stats_route() {
+    let directory = TestDirectory::new().await;
+    let (_, reader) = directory.api_key("alice", true, &[scope::DIRECTORY_READ]).await;
+    let (_, auditor) = directory.api_key("carol", true, &[scope::AUDIT_READ]).await;
+    let (_, user_reader) = directory.api_key("bob", false, &[scope::DIRECTORY_READ]).await;
+    let app = directory.router("");
+
+    let (status, body) = call(&app, request("GET", "/api/stats", Some(&auditor), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("MISSING_SCOPE")));
+    let (status, body) = call(&app, request("GET", "/api/stats", Some(&user_reader), None)).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("ACCESS_DENIED")));
+    let (status, body) = call(&app, request("GET", "/api/stats", Some(&reader), None)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
@@ -0,0 +1,132 @@
+// tests/integration/sync.rs
+
+use std::sync::Arc;
+
+use nextDomen::config::LdapSyncConfig;
+use nextDomen::ldif::{self, DuplicatePolicy};
+use nextDomen::sync::{LdapSyncConnector, SourceEntry};
+
+use super::TestDirectory;
+
+fn connector(directory: &TestDirectory) -> LdapSyncConnector {
+    let config: LdapSyncConfig = serde_yaml::from_str("\
+url: ldap://dc01.x.com
+bind_dn: CN=svc-sync,CN=Users,DC=x,DC=com
+bind_password: secret
+base_dn: DC=x,DC=com
+").unwrap();
+    LdapSyncConnector::new(Arc::clone(&directory.service), config)
+}
+
+/// Записи источника из LDIF; objectGUID — значение `guid:` записи
+fn source(text: &str) -> Vec<SourceEntry> {
+    ldif::parse(text).unwrap().into_iter()
+        .map(|entry| SourceEntry { guid: entry.get("guid").map(str::to_string), entry })
+        .collect()
+}
+
+const FULL: &str = "\
+dn: OU=Sales,DC=x,DC=com
+objectClass: organizationalUnit
+ou: Sales
+guid: 00000000-0000-0000-0000-000000000001
+
+dn: CN=Alice,OU=Sales,DC=x,DC=com
+objectClass: user
+sAMAccountName: alice
+guid: 00000000-0000-0000-0000-000000000002
+
+dn: CN=Bob,OU=Sales,DC=x,DC=com
+objectClass: user
+sAMAccountName: bob
+guid: 00000000-0000-0000-0000-000000000003
+
+dn: CN=Team,OU=Sales,DC=x,DC=com
+objectClass: group
+sAMAccountName: Team
+member: CN=Alice,OU=Sales,DC=x,DC=com
+member: CN=Bob,OU=Sales,DC=x,DC=com
+guid: 00000000-0000-0000-0000-000000000004
+";
+
+#[tokio::test]
+async fn test_incremental_members_and_deletes() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let connector = connector(&directory);
+
+    let (report, deleted) = connector.apply(source(FULL), &[], true).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_eq!((report.created, deleted), (4, 0));
+    let alice = service.find_user_by_username("alice").await.unwrap().unwrap();
+    let bob = service.find_user_by_username("bob").await.unwrap().unwrap();
+    let team = service.find_group_by_sam_account_name("Team").await.unwrap().unwrap();
+    assert_eq!(team.members.len(), 2);
+
+    // Изменилась только группа: alice находится по DN прошлого цикла, bob из группы удаляется.
+    // Локальная carol с тем же CN, но другим DN участником не становится
+    service.import_ldif("dn: CN=carol,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: carol\n", DuplicatePolicy::Skip).await.unwrap();
+    let changed = "\
+dn: CN=Team,OU=Sales,DC=x,DC=com
+objectClass: group
+sAMAccountName: Team
+member: CN=Alice,OU=Sales,DC=x,DC=com
+member: CN=carol,OU=Elsewhere,DC=x,DC=com
+guid: 00000000-0000-0000-0000-000000000004
+";
+    let (report, _) = connector.apply(source(changed), &[], false).await.unwrap();
+    assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+    assert!(report.errors[0].contains("member not found: CN=carol,OU=Elsewhere"), "{:?}", report.errors);
+    let team = service.get_group(team.id).await.unwrap().unwrap();
+    assert_eq!(team.members, vec![alice.id]);
+
+    // Надгробие AD: объект удаляется по objectGUID, хотя его DN уже другой
+    let (report, deleted) = connector.apply(Vec::new(), &["00000000-0000-0000-0000-000000000003".to_string()], false).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_eq!(deleted, 1);
+    assert!(service.get_user(bob.id).await.unwrap().is_none());
+
+    // Надгробие чужого объекта ничего не трогает
+    let (_, deleted) = connector.apply(Vec::new(), &["00000000-0000-0000-0000-0000000000ff".to_string()], false).await.unwrap();
+    assert_eq!(deleted, 0);
+    assert!(service.find_user_by_username("carol").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_full_sync_deletes_missing() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let connector = connector(&directory);
+    connector.apply(source(FULL), &[], true).await.unwrap();
+    let alice = service.find_user_by_username("alice").await.unwrap().unwrap();
+    let team = service.find_group_by_sam_account_name("Team").await.unwrap().unwrap();
+
+    // alice переименована в источнике (тот же sAMAccountName), bob и группа удалены
+    let renamed = "\
+dn: OU=Sales,DC=x,DC=com
+objectClass: organizationalUnit
+ou: Sales
+
+dn: CN=Alice Smith,OU=Sales,DC=x,DC=com
+objectClass: user
+sAMAccountName: alice
+";
+    let (report, deleted) = connector.apply(source(renamed), &[], true).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_eq!(deleted, 2);
+    assert!(service.get_user(alice.id).await.unwrap().is_some());
+    assert!(service.find_user_by_username("bob").await.unwrap().is_none());
+    assert!(service.get_group(team.id).await.unwrap().is_none());
+
+    // Прежний DN alice забыт: следующий полный цикл без изменений её не удаляет
+    let (_, deleted) = connector.apply(source(renamed), &[], true).await.unwrap();
+    assert_eq!(deleted, 0);
+    assert!(service.get_user(alice.id).await.unwrap().is_some());
+
+    // OU с локальными объектами не удаляется
+    service.import_ldif("dn: CN=dave,OU=Sales,DC=x,DC=com\nobjectClass: user\nsAMAccountName: dave\n", DuplicatePolicy::Skip).await.unwrap();
+    let (report, deleted) = connector.apply(Vec::new(), &[], true).await.unwrap();
+    assert_eq!(deleted, 1);
+    assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+    assert!(service.find_ou_by_dn("OU=Sales,DC=x,DC=com").await.unwrap().is_some());
+}
@@ -0,0 +1,49 @@
+// tests/integration/usernames.rs
+
+use nextDomen::directory_service::DirectoryError;
+use nextDomen::ldif::DuplicatePolicy;
+use nextDomen::models::validate_username;
+
+use super::TestDirectory;
+
+#[test]
+fn test_validate_username() {
+    for valid in ["alice", "j.smith", "WS01$", "svc-backup_01", "Пётр", "a b"] {
+        assert!(validate_username(valid).is_ok(), "{}", valid);
+    }
+    for invalid in ["", " ", " alice", "alice.", "...", "a/b", "a@b", "x,y", "tab\tname", "twenty-one-characters", "NUL", "Everyone"] {
+        assert!(validate_username(invalid).is_err(), "{}", invalid);
+    }
+    let error = validate_username("a*b").unwrap_err();
+    assert!(error.contains("'*'"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_username_uniqueness_ignores_case() {
+    let directory = TestDirectory::new().await;
+    let service = &directory.service;
+    let ldif = "dn: CN=Alice,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: Alice\n";
+    service.import_ldif(ldif, DuplicatePolicy::Fail).await.unwrap();
+
+    let alice = service.find_user_by_username("ALICE").await.unwrap().unwrap();
+    assert_eq!(alice.username, "Alice");
+    let duplicate = "dn: CN=alice,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: alice\n";
+    let result = service.import_ldif(duplicate, DuplicatePolicy::Fail).await;
+    assert!(matches!(result, Err(DirectoryError::AlreadyExists(..))));
+
+    // Смена регистра — то же имя; недопустимое имя отклоняется с причиной
+    service.rename_user(alice.id, Some("aLiCe".to_string()), None).await.unwrap();
+    assert_eq!(service.find_user_by_username("alice").await.unwrap().unwrap().username, "aLiCe");
+    match service.rename_user(alice.id, Some("al|ce".to_string()), None).await {
+        Err(DirectoryError::InvalidInput(message)) => assert!(message.contains("al|ce"), "{}", message),
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+
+    service.rename_user(alice.id, Some("alicia".to_string()), None).await.unwrap();
+    assert!(service.find_user_by_username("alice").await.unwrap().is_none());
+
+    let invalid = "dn: CN=bad,CN=Users,DC=x,DC=com\nobjectClass: user\nsAMAccountName: bad/name\n";
+    let report = service.import_ldif(invalid, DuplicatePolicy::Skip).await.unwrap();
+    assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+    assert!(service.find_user_by_username("bad/name").await.unwrap().is_none());
+}